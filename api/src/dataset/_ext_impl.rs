@@ -106,6 +106,9 @@ where
             Ok(false)
         }
     }
+    fn reserve_quads(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
 }
 
 impl<Q, S: BuildHasher> Dataset for HashSet<Q, S>
@@ -119,6 +122,10 @@ where
     fn quads(&self) -> DQuadSource<Self> {
         Box::from(self.iter().map(StreamedQuad::by_ref).as_quad_source())
     }
+
+    fn is_set(&self) -> bool {
+        true
+    }
 }
 
 impl<T, S> CollectibleDataset for HashSet<([T; 3], Option<T>), S>
@@ -185,6 +192,9 @@ where
         let g = g.map(T::copy);
         Ok(HashSet::remove(self, &([s, p, o], g)))
     }
+    fn reserve_quads(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
 }
 
 impl<T, S: BuildHasher> SetDataset for HashSet<T, S> where T: Eq + Hash + Quad {}
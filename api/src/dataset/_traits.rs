@@ -301,6 +301,29 @@ pub trait Dataset {
         }
     }
 
+    /// An iterator visiting one page of the quads of this dataset:
+    /// the first `offset` quads are skipped, and at most `limit` quads are yielded.
+    ///
+    /// Successive pages of an unmodified dataset cover all its quads exactly once.
+    /// The default implementation iterates over the `offset` first quads
+    /// (so fetching every page of a dataset costs a quadratic time);
+    /// implementations with ordered indexes should override it to seek directly to the page.
+    fn quads_page(&self, offset: usize, limit: usize) -> DQuadSource<'_, Self> {
+        Box::new(self.quads().skip(offset).take(limit))
+    }
+
+    /// Return `true` if this dataset is known to contain no duplicate quad,
+    /// i.e. if it behaves as a set rather than as a bag of quads.
+    ///
+    /// The default implementation returns `false`,
+    /// which is always correct (if suboptimal).
+    /// Implementations of [`SetDataset`] should override it.
+    ///
+    /// [`SetDataset`]: trait.SetDataset.html
+    fn is_set(&self) -> bool {
+        false
+    }
+
     /// An iterator visiting add quads matching the given subject, predicate, object and graph name.
     ///
     /// See also [`quads`](#tymethod.quads).
@@ -385,6 +408,59 @@ pub trait Dataset {
         }
     }
 
+    /// Count the quads matching the given subject, predicate, object and graph name.
+    ///
+    /// See also [`quads_matching`](#method.quads_matching).
+    ///
+    /// The default implementation iterates over the matching quads,
+    /// but indexed implementations may override it to answer from their indexes.
+    fn count_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> DResult<Self, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        let mut count = 0;
+        for q in self.quads_matching(ms, mp, mo, mg) {
+            q?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Estimate the number of quads matching the given subject, predicate, object and graph name,
+    /// without iterating over them.
+    ///
+    /// Like [`Iterator::size_hint`], this returns a lower bound,
+    /// and an upper bound if one is known.
+    /// The default implementation returns `(0, None)`,
+    /// but indexed implementations may override it to provide tighter bounds.
+    ///
+    /// [`Iterator::size_hint`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.size_hint
+    fn estimate_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        let _ = (ms, mp, mo, mg);
+        (0, None)
+    }
+
     /// Build a Hashset of all the terms used as subject in this Dataset.
     fn subjects(&self) -> DResultTermSet<Self>
     where
@@ -501,6 +577,19 @@ pub trait Dataset {
         Ok(res)
     }
 
+    /// Check whether this Dataset contains any quad that is only valid
+    /// in the [generalized RDF model](../index.html#generalized-vs-strict-rdf-model).
+    ///
+    /// See [`Quad::is_generalized`](../quad/trait.Quad.html#method.is_generalized).
+    fn is_generalized(&self) -> DResult<Self, bool> {
+        for q in self.quads() {
+            if q?.is_generalized() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// Borrows one of the graphs of this dataset
     fn graph<'s, T>(
         &'s self,
@@ -601,6 +690,14 @@ pub trait MutableDataset: Dataset {
         TO: TTerm + ?Sized,
         TG: TTerm + ?Sized;
 
+    /// Reserve capacity for at least `additional` more quads in this dataset.
+    ///
+    /// This is only a hint; the default implementation does nothing.
+    #[inline]
+    fn reserve_quads(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
     /// Insert into this dataset all quads from the given source.
     ///
     /// # Blank node scope
@@ -770,7 +867,11 @@ pub trait MutableDataset: Dataset {
 /// enforcing (1) but failing to enforce (2)
 /// *must not* implement this trait.
 ///
+/// Implementors should also override [`Dataset::is_set`] to return `true`,
+/// so that generic code can check this property at run time.
+///
 /// [`Dataset`]: trait.Dataset.html
+/// [`Dataset::is_set`]: trait.Dataset.html#method.is_set
 /// [`MutableDataset`]: trait.MutableDataset.html
 
 pub trait SetDataset: Dataset {}
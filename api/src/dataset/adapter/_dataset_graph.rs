@@ -54,6 +54,10 @@ where
                 .map_ok(StreamedTriple::from_quad),
         )
     }
+    fn is_set(&self) -> bool {
+        // the union of several graphs may contain duplicates, even in a set dataset
+        self.gmatcher.constant().is_some() && self.dataset.borrow().is_set()
+    }
     fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
     where
         TS: TTerm + ?Sized,
@@ -168,6 +172,10 @@ where
     {
         self.dataset.borrow_mut().remove(s, p, o, self.gmatcher)
     }
+
+    fn reserve_triples(&mut self, additional: usize) {
+        self.dataset.borrow_mut().reserve_quads(additional)
+    }
 }
 
 impl<D, E, T> SetGraph for DatasetGraph<D, E, Option<&T>>
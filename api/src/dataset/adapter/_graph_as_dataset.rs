@@ -45,6 +45,10 @@ where
         Box::new(self.0.borrow().triples().map_ok(StreamedQuad::from_triple))
     }
     #[inline]
+    fn is_set(&self) -> bool {
+        self.0.borrow().is_set()
+    }
+    #[inline]
     fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
     where
         TS: TTerm + ?Sized,
@@ -373,6 +377,10 @@ where
         };
         Ok(self.0.borrow_mut().remove(s, p, o)?)
     }
+
+    fn reserve_quads(&mut self, additional: usize) {
+        self.0.borrow_mut().reserve_triples(additional)
+    }
 }
 
 impl<G, H> SetDataset for GraphAsDataset<G, H>
@@ -327,7 +327,7 @@ macro_rules! test_dataset_impl {
             use $crate::graph::test::*;
             use $crate::ns::*;
             use $crate::term::TTerm;
-            use $crate::term::matcher::ANY;
+            use $crate::term::matcher::{GraphNameMatcher, TermMatcher, ANY};
 
             #[allow(unused_imports)]
             use super::*;
@@ -684,6 +684,34 @@ macro_rules! test_dataset_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_quads_page() -> Result<(), Box<dyn std::error::Error>> {
+                let d: $dataset_impl = $dataset_collector(some_quads()).unwrap();
+
+                let mut all: Vec<_> = d.quads().map(as_box_q).collect();
+                let mut paged = Vec::new();
+                loop {
+                    let page: Vec<_> = d.quads_page(paged.len(), 3).map(as_box_q).collect();
+                    assert!(page.len() <= 3);
+                    if page.is_empty() {
+                        break;
+                    }
+                    paged.extend(page);
+                }
+                all.sort_by_key(|q| format!("{:?}", q));
+                paged.sort_by_key(|q| format!("{:?}", q));
+                assert_eq!(paged, all);
+                assert_eq!(d.quads_page(all.len(), 3).count(), 0);
+                Ok(())
+            }
+
+            #[test]
+            fn test_is_set() -> Result<(), Box<dyn std::error::Error>> {
+                let d: $dataset_impl = $dataset_collector(some_quads()).unwrap();
+                assert_eq!(d.is_set(), $is_set);
+                Ok(())
+            }
+
             #[test]
             fn test_quads_matching() ->  Result<(), Box<dyn std::error::Error>> {
                 let d: $dataset_impl = $dataset_collector(some_quads()).unwrap();
@@ -711,6 +739,41 @@ macro_rules! test_dataset_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_count_quads_matching() -> Result<(), Box<dyn std::error::Error>> {
+                fn check<D, S, P, O, G>(d: &D, ms: &S, mp: &P, mo: &O, mg: &G) -> Result<(), D::Error>
+                where
+                    D: Dataset,
+                    S: TermMatcher + ?Sized,
+                    P: TermMatcher + ?Sized,
+                    O: TermMatcher + ?Sized,
+                    G: GraphNameMatcher + ?Sized,
+                {
+                    let expected = d.quads_matching(ms, mp, mo, mg).count();
+                    assert_eq!(d.count_quads_matching(ms, mp, mo, mg)?, expected);
+                    let (min, max) = d.estimate_quads_matching(ms, mp, mo, mg);
+                    assert!(min <= expected);
+                    assert!(max.map_or(true, |max| expected <= max));
+                    Ok(())
+                }
+
+                let d: $dataset_impl = $dataset_collector(some_quads()).unwrap();
+                let dg = None as Option<&StaticTerm>;
+                check(&d, &ANY, &ANY, &ANY, &ANY)?;
+                check(&d, &ANY, &ANY, &ANY, &GN1.as_ref())?;
+                check(&d, &ANY, &ANY, &ANY, &dg)?;
+                check(&d, &*I1A, &ANY, &ANY, &GN2.as_ref())?;
+                check(&d, &*I1A, &rdf::type_, &ANY, &GN2.as_ref())?;
+                check(&d, &*I1A, &rdf::type_, &*C1, &GN2.as_ref())?;
+                check(&d, &*I1A, &rdf::type_, &*C1, &dg)?;
+                check(&d, &ANY, &ANY, &*C1, &ANY)?;
+                check(&d, &ANY, &ANY, &*C1, &GN1.as_ref())?;
+                check(&d, &ANY, &rdf::type_, &*C1, &GN2.as_ref())?;
+                check(&d, &ANY, &[&rdf::type_, &rdfs::domain], &ANY, &GN2.as_ref())?;
+                check(&d, &ANY, &ANY, &[&*C1, &*C2], &[|g: Option<&dyn TTerm>| g.is_some()])?;
+                Ok(())
+            }
+
             #[test]
             fn test_subjects() -> Result<(), Box<dyn std::error::Error>> {
                 let d: $dataset_impl = $dataset_collector(some_quads()).unwrap();
@@ -835,6 +898,17 @@ macro_rules! test_dataset_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_is_generalized() -> Result<(), Box<dyn std::error::Error>> {
+                let d: $dataset_impl = $dataset_collector(strict_node_types_quads()).unwrap();
+                assert!(!d.is_generalized()?);
+                if $is_gen {
+                    let d: $dataset_impl = $dataset_collector(generalized_node_types_quads()).unwrap();
+                    assert!(d.is_generalized()?);
+                }
+                Ok(())
+            }
+
             // Tests for MutableGraph only, if enabled:
             $($mt)*
         }
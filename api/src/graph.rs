@@ -9,9 +9,13 @@
 #[macro_use]
 pub mod test;
 
+mod _explain;
+pub use self::_explain::*;
 mod _ext_impl;
 pub use self::_ext_impl::*;
 mod _traits;
 pub use self::_traits::*;
+mod _entailment;
+pub use self::_entailment::*;
 mod _isomorphism;
 pub use self::_isomorphism::*;
@@ -0,0 +1,258 @@
+//! This module implements simple entailment checking between RDF graphs.
+//!
+//! Its public members are transparently re-exported by its [parent module](../index.html).
+
+use crate::graph::{GTerm, Graph};
+use crate::term::matcher::{AnyOrExactlyRef, TermMatcher};
+use crate::term::{term_eq, TTerm, TermKind};
+use crate::triple::stream::{SinkResult as _, SourceResult as _, StreamResult};
+use crate::triple::Triple;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Checks if `haystack` contains a copy of `needle`,
+/// where the blank nodes of `needle` are considered as variables.
+///
+/// In other words, this checks whether some mapping of the blank nodes of `needle`
+/// to terms of `haystack` turns every triple of `needle` into a triple of `haystack`.
+/// According to the [RDF semantics](https://www.w3.org/TR/rdf11-mt/#simpleentailment-properties),
+/// this means that `haystack` *simply entails* `needle`.
+/// It is similar to the `EXISTS` filter of SPARQL,
+/// where `needle` would be the pattern and its blank nodes the variables.
+///
+/// Note that two blank nodes of `needle` can be mapped to the same term,
+/// and that blank nodes of `haystack` are considered as regular terms.
+///
+/// # Errors
+///
+/// Both graphs may fail traversing.
+/// Accordingly, a `StreamError` returned,
+/// where `SourceError`s originate from `needle`
+/// and `SinkError`s originate from `haystack`.
+///
+/// # Performance
+///
+/// The triples of `needle` without blank nodes are simply looked up in `haystack`.
+/// The other triples are matched one at a time,
+/// always choosing the one with the most terms fixed by the current mapping,
+/// and backtracking when a mapping can not be extended.
+/// This is efficient for typical patterns,
+/// but the worst case (large, loosely connected patterns of blank nodes)
+/// is exponential in the number of blank nodes of `needle`,
+/// as simple entailment is NP-complete.
+pub fn contains_subgraph<G1, G2>(
+    needle: &G1,
+    haystack: &G2,
+) -> StreamResult<bool, G1::Error, G2::Error>
+where
+    G1: Graph,
+    G2: Graph,
+    GTerm<G1>: Clone + Eq + Hash,
+    GTerm<G2>: Clone,
+{
+    let mut pending = vec![];
+    for t in needle.triples() {
+        let t = t.source_err()?;
+        let is_bn = |t: &GTerm<G1>| t.kind() == TermKind::BlankNode;
+        if is_bn(t.s()) || is_bn(t.p()) || is_bn(t.o()) {
+            pending.push([t.s().clone(), t.p().clone(), t.o().clone()]);
+        } else if !haystack.contains(t.s(), t.p(), t.o()).sink_err()? {
+            return Ok(false);
+        }
+    }
+    let mut mapping = HashMap::new();
+    extend_mapping::<G1, G2>(&mut pending, &mut mapping, haystack).sink_err()
+}
+
+/// Try to extend `mapping` so that it maps all `pending` triples into `haystack`.
+///
+/// On failure, `pending` and `mapping` are restored to their initial state.
+fn extend_mapping<G1, G2>(
+    pending: &mut Vec<[GTerm<G1>; 3]>,
+    mapping: &mut HashMap<GTerm<G1>, GTerm<G2>>,
+    haystack: &G2,
+) -> Result<bool, G2::Error>
+where
+    G1: Graph,
+    G2: Graph,
+    GTerm<G1>: Clone + Eq + Hash,
+    GTerm<G2>: Clone,
+{
+    // pick the most constrained triple
+    let i = match (0..pending.len()).max_by_key(|i| {
+        pending[*i]
+            .iter()
+            .filter(|t| !matcher::<G1, G2>(t, mapping).is_any())
+            .count()
+    }) {
+        None => return Ok(true),
+        Some(i) => i,
+    };
+    let triple = pending.swap_remove(i);
+    let candidates = {
+        let [ms, mp, mo] = [
+            matcher::<G1, G2>(&triple[0], mapping),
+            matcher::<G1, G2>(&triple[1], mapping),
+            matcher::<G1, G2>(&triple[2], mapping),
+        ];
+        haystack
+            .triples_matching(&ms, &mp, &mo)
+            .map(|t| t.map(|t| [t.s().clone(), t.p().clone(), t.o().clone()]))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    for candidate in candidates {
+        let mut added = vec![];
+        let mut consistent = true;
+        for (n, h) in triple.iter().zip(candidate) {
+            if n.kind() != TermKind::BlankNode {
+                continue;
+            }
+            match mapping.get(n) {
+                // only possible if n occurs several times in the triple
+                Some(m) if !term_eq(m, &h) => {
+                    consistent = false;
+                    break;
+                }
+                Some(_) => {}
+                None => {
+                    mapping.insert(n.clone(), h);
+                    added.push(n.clone());
+                }
+            }
+        }
+        if consistent && extend_mapping::<G1, G2>(pending, mapping, haystack)? {
+            return Ok(true);
+        }
+        for n in added {
+            mapping.remove(&n);
+        }
+    }
+    // restore pending in its initial order
+    pending.push(triple);
+    let last = pending.len() - 1;
+    pending.swap(i, last);
+    Ok(false)
+}
+
+/// The matcher for term `t` of a pending triple, given the current mapping.
+fn matcher<'a, G1, G2>(
+    t: &'a GTerm<G1>,
+    mapping: &'a HashMap<GTerm<G1>, GTerm<G2>>,
+) -> AnyOrExactlyRef<&'a dyn TTerm>
+where
+    G1: Graph,
+    G2: Graph,
+    GTerm<G1>: Eq + Hash + Sized,
+    GTerm<G2>: Sized,
+{
+    if t.kind() != TermKind::BlankNode {
+        AnyOrExactlyRef::Exactly(t.as_dyn())
+    } else {
+        match mapping.get(t) {
+            Some(mapped) => AnyOrExactlyRef::Exactly(mapped.as_dyn()),
+            None => AnyOrExactlyRef::Any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ns::rdf;
+    use crate::term::test::TestTerm;
+    use std::error::Error;
+
+    type StaticTerm = TestTerm<&'static str>;
+
+    fn t(suffix: &'static str) -> StaticTerm {
+        StaticTerm::iri2("tag:", suffix)
+    }
+
+    fn b(id: &'static str) -> StaticTerm {
+        StaticTerm::bnode(id)
+    }
+
+    fn make_graph() -> Vec<[StaticTerm; 3]> {
+        let knows = t("knows");
+        vec![
+            [t("alice"), knows, t("bob")],
+            [t("bob"), knows, t("carol")],
+            [t("carol"), knows, b("dave")],
+            [b("dave"), knows, b("dave")],
+            [t("alice"), rdf::type_.into(), t("Person")],
+        ]
+    }
+
+    #[test]
+    fn ground() -> Result<(), Box<dyn Error>> {
+        let g = make_graph();
+        assert!(contains_subgraph(&g, &g)?);
+        assert!(contains_subgraph(&Vec::<[StaticTerm; 3]>::new(), &g)?);
+        assert!(contains_subgraph(
+            &vec![[t("bob"), t("knows"), t("carol")]],
+            &g
+        )?);
+        assert!(!contains_subgraph(
+            &vec![[t("bob"), t("knows"), t("alice")]],
+            &g
+        )?);
+        assert!(!contains_subgraph(&g, &g[..4].to_vec())?);
+        Ok(())
+    }
+
+    #[test]
+    fn bnodes_as_variables() -> Result<(), Box<dyn Error>> {
+        let g = make_graph();
+        let knows = t("knows");
+        // a path of length 3 starting from alice
+        let path = vec![
+            [t("alice"), knows, b("x")],
+            [b("x"), knows, b("y")],
+            [b("y"), knows, b("z")],
+        ];
+        assert!(contains_subgraph(&path, &g)?);
+        // bnodes of the haystack are regular terms
+        assert!(contains_subgraph(&vec![[b("x"), knows, b("dave")]], &g)?);
+        // someone who knows themselves
+        assert!(contains_subgraph(&vec![[b("x"), knows, b("x")]], &g)?);
+        // a Person known by someone
+        let known_person = vec![
+            [b("y"), knows, b("x")],
+            [b("x"), rdf::type_.into(), t("Person")],
+        ];
+        assert!(!contains_subgraph(&known_person, &g)?);
+        // distinct bnodes may map to the same term
+        let cycle = vec![[b("x"), knows, b("y")], [b("y"), knows, b("x")]];
+        assert!(contains_subgraph(&cycle, &g)?);
+        // bnodes in predicate position
+        let pred = vec![[t("alice"), b("p"), t("bob")], [t("bob"), b("p"), b("o")]];
+        assert!(contains_subgraph(&pred, &g)?);
+        let pred = vec![
+            [t("alice"), b("p"), t("Person")],
+            [t("bob"), b("p"), b("o")],
+        ];
+        assert!(!contains_subgraph(&pred, &g)?);
+        Ok(())
+    }
+
+    #[test]
+    fn backtracking() -> Result<(), Box<dyn Error>> {
+        let g = make_graph();
+        let knows = t("knows");
+        // the first candidates for x (alice, bob) do not lead to a solution
+        let needle = vec![
+            [b("x"), knows, b("y")],
+            [b("y"), knows, b("y")],
+            [b("x"), knows, b("z")],
+        ];
+        assert!(contains_subgraph(&needle, &g)?);
+        let needle = vec![
+            [b("x"), knows, b("y")],
+            [b("y"), knows, b("z")],
+            [b("z"), knows, b("x")],
+            [b("x"), rdf::type_.into(), t("Person")],
+        ];
+        assert!(!contains_subgraph(&needle, &g)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+// this module is transparently re-exported by its parent `graph`
+
+use std::fmt;
+
+/// How a call to [`Graph::triples_matching`] is evaluated,
+/// as reported by [`Graph::explain_triples_matching`].
+///
+/// [`Graph::triples_matching`]: trait.Graph.html#method.triples_matching
+/// [`Graph::explain_triples_matching`]: trait.Graph.html#method.explain_triples_matching
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    /// The positions (subject, predicate, object) whose term is looked up.
+    ///
+    /// If all are `false`, all the triples of the graph are enumerated.
+    pub lookup: [bool; 3],
+    /// If the terms of a matcher are looked up one by one
+    /// (see [`TermMatcher::candidates`](../term/matcher/trait.TermMatcher.html#method.candidates)),
+    /// the number of lookups.
+    pub candidates: Option<usize>,
+    /// The name of the index used for the lookup,
+    /// or `None` if all the triples of the graph are enumerated.
+    pub index: Option<&'static str>,
+    /// The positions actually narrowed down by the index.
+    pub covered: [bool; 3],
+    /// Whether the triples enumerated need to be filtered by the matchers.
+    pub filtered: bool,
+    /// The estimated number of matching triples
+    /// (see [`Graph::estimate_triples_matching`](trait.Graph.html#method.estimate_triples_matching)).
+    pub estimate: (usize, Option<usize>),
+}
+
+impl Explanation {
+    /// Whether all the triples of the graph are enumerated.
+    pub fn is_full_scan(&self) -> bool {
+        self.index.is_none()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(index) => {
+                let positions: Vec<_> = ["s", "p", "o"]
+                    .iter()
+                    .zip(&self.covered)
+                    .filter(|(_, covered)| **covered)
+                    .map(|(pos, _)| *pos)
+                    .collect();
+                write!(f, "index {} on {}", index, positions.join(""))?;
+            }
+            None => write!(f, "full scan")?,
+        }
+        if let Some(n) = self.candidates {
+            write!(f, " x{} lookups", n)?;
+        }
+        if self.filtered {
+            write!(f, " + filter")?;
+        }
+        match self.estimate {
+            (min, Some(max)) if min == max => write!(f, ", {} triples", min),
+            (min, Some(max)) => write!(f, ", {}..={} triples", min, max),
+            (min, None) => write!(f, ", {}.. triples", min),
+        }
+    }
+}
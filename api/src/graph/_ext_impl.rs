@@ -97,6 +97,9 @@ where
             Ok(false)
         }
     }
+    fn reserve_triples(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
 }
 
 impl<T, BH> Graph for HashSet<T, BH>
@@ -111,6 +114,10 @@ where
     fn triples(&self) -> GTripleSource<Self> {
         Box::from(self.iter().map(StreamedTriple::by_ref).as_triple_source())
     }
+
+    fn is_set(&self) -> bool {
+        true
+    }
 }
 
 impl<T, BH> CollectibleGraph for HashSet<[T; 3], BH>
@@ -158,6 +165,9 @@ where
         let o = o.copied();
         Ok(HashSet::remove(self, &[s, p, o]))
     }
+    fn reserve_triples(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
 }
 
 impl<'a, T, S: BuildHasher> SetGraph for HashSet<T, S> where T: Eq + Hash + Triple {}
@@ -6,6 +6,7 @@ use std::hash::Hash;
 use resiter::filter::*;
 use resiter::map::*;
 
+use super::Explanation;
 use crate::dataset::adapter::GraphAsDataset;
 use crate::term::matcher::TermMatcher;
 use crate::term::{term_eq, TTerm, TermKind};
@@ -177,6 +178,29 @@ pub trait Graph {
         }
     }
 
+    /// An iterator visiting one page of the triples of this graph:
+    /// the first `offset` triples are skipped, and at most `limit` triples are yielded.
+    ///
+    /// Successive pages of an unmodified graph cover all its triples exactly once.
+    /// The default implementation iterates over the `offset` first triples
+    /// (so fetching every page of a graph costs a quadratic time);
+    /// implementations with ordered indexes should override it to seek directly to the page.
+    fn triples_page(&self, offset: usize, limit: usize) -> GTripleSource<'_, Self> {
+        Box::new(self.triples().skip(offset).take(limit))
+    }
+
+    /// Return `true` if this graph is known to contain no duplicate triple,
+    /// i.e. if it behaves as a set rather than as a bag of triples.
+    ///
+    /// The default implementation returns `false`,
+    /// which is always correct (if suboptimal).
+    /// Implementations of [`SetGraph`] should override it.
+    ///
+    /// [`SetGraph`]: trait.SetGraph.html
+    fn is_set(&self) -> bool {
+        false
+    }
+
     /// An iterator visiting all triples matching the given subject, predicate and object.
     ///
     /// See also [`triples`](#tymethod.triples).
@@ -246,6 +270,30 @@ pub trait Graph {
         P: TermMatcher + ?Sized,
         O: TermMatcher + ?Sized,
     {
+        if ms.constant().is_none() && mp.constant().is_none() && mo.constant().is_none() {
+            let [s_indexed, p_indexed, o_indexed] = self.indexed_positions();
+            if let Some(candidates) = ms.candidates().filter(|_| s_indexed) {
+                return Box::new(
+                    dedup_candidates(candidates)
+                        .into_iter()
+                        .flat_map(move |s| self.triples_matching(s, mp, mo)),
+                );
+            }
+            if let Some(candidates) = mp.candidates().filter(|_| p_indexed) {
+                return Box::new(
+                    dedup_candidates(candidates)
+                        .into_iter()
+                        .flat_map(move |p| self.triples_matching(ms, p, mo)),
+                );
+            }
+            if let Some(candidates) = mo.candidates().filter(|_| o_indexed) {
+                return Box::new(
+                    dedup_candidates(candidates)
+                        .into_iter()
+                        .flat_map(move |o| self.triples_matching(ms, mp, o)),
+                );
+            }
+        }
         match (&ms.constant(), &mp.constant(), &mo.constant()) {
             (None, None, None) => {
                 Box::from(self.triples().filter_ok(move |t| {
@@ -280,6 +328,124 @@ pub trait Graph {
         }
     }
 
+    /// Which of [`triples_with_s`](#method.triples_with_s),
+    /// [`triples_with_p`](#method.triples_with_p) and [`triples_with_o`](#method.triples_with_o)
+    /// (respectively) are backed by an index.
+    ///
+    /// When none of its matchers is [constant](../term/matcher/trait.TermMatcher.html#tymethod.constant),
+    /// the default implementation of [`triples_matching`](#method.triples_matching)
+    /// uses this to look up each [candidate](../term/matcher/trait.TermMatcher.html#method.candidates)
+    /// of a matcher in an index, rather than filtering all the triples of the graph.
+    ///
+    /// The default implementation returns `[false, false, false]`.
+    fn indexed_positions(&self) -> [bool; 3] {
+        [false; 3]
+    }
+
+    /// Count the triples matching the given subject, predicate and object.
+    ///
+    /// See also [`triples_matching`](#method.triples_matching).
+    ///
+    /// The default implementation iterates over the matching triples,
+    /// but indexed implementations may override it to answer from their indexes.
+    fn count_triples_matching<S, P, O>(&self, ms: &S, mp: &P, mo: &O) -> GResult<Self, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let mut count = 0;
+        for t in self.triples_matching(ms, mp, mo) {
+            t?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Estimate the number of triples matching the given subject, predicate and object,
+    /// without iterating over them.
+    ///
+    /// Like [`Iterator::size_hint`], this returns a lower bound,
+    /// and an upper bound if one is known.
+    /// The default implementation returns `(0, None)`,
+    /// but indexed implementations may override it to provide tighter bounds.
+    ///
+    /// [`Iterator::size_hint`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.size_hint
+    fn estimate_triples_matching<S, P, O>(&self, ms: &S, mp: &P, mo: &O) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let _ = (ms, mp, mo);
+        (0, None)
+    }
+
+    /// The index used to enumerate the triples whose terms are given at the `bound` positions
+    /// (i.e. by [`triples_with_s`](#method.triples_with_s) if `bound` is `[true, false, false]`,
+    /// [`triples_with_sp`](#method.triples_with_sp) if `bound` is `[true, true, false]`, etc.),
+    /// together with the positions that this index actually narrows down;
+    /// or `None` if all the triples of the graph are enumerated.
+    ///
+    /// This is used by [`explain_triples_matching`](#method.explain_triples_matching).
+    ///
+    /// The default implementation follows the default implementations of the `triples_with_*` methods,
+    /// where two-position (resp. three-position) lookups rely on one-position (resp. two-position) lookups,
+    /// and returns `None` for one-position lookups.
+    /// Implementations overriding some `triples_with_*` methods should override this method accordingly.
+    fn index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        match bound {
+            [true, true, false] | [true, false, true] => self.index_for([true, false, false]),
+            [false, true, true] => self.index_for([false, true, false]),
+            [true, true, true] => self.index_for([true, true, false]),
+            _ => None,
+        }
+    }
+
+    /// Describe how [`triples_matching`](#method.triples_matching) would evaluate the given matchers,
+    /// without evaluating them.
+    ///
+    /// This is useful for debugging the performance of pattern-heavy code.
+    fn explain_triples_matching<S, P, O>(&self, ms: &S, mp: &P, mo: &O) -> Explanation
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let mut lookup = [
+            ms.constant().is_some(),
+            mp.constant().is_some(),
+            mo.constant().is_some(),
+        ];
+        let mut candidates = None;
+        if lookup == [false; 3] {
+            // mimic the candidate lookups of triples_matching
+            let indexed = self.indexed_positions();
+            let matchers = [ms.candidates(), mp.candidates(), mo.candidates()];
+            for (i, matcher) in matchers.iter().enumerate() {
+                if let Some(terms) = matcher.as_ref().filter(|_| indexed[i]) {
+                    lookup[i] = true;
+                    candidates = Some(dedup_candidates(terms.clone()).len());
+                    break;
+                }
+            }
+        }
+        let (index, covered) = match self.index_for(lookup) {
+            Some((index, covered)) => (Some(index), covered),
+            None => (None, [false; 3]),
+        };
+        let any = [ms.is_any(), mp.is_any(), mo.is_any()];
+        let filtered = (0..3).any(|i| !any[i] && !covered[i]);
+        Explanation {
+            lookup,
+            candidates,
+            index,
+            covered,
+            filtered,
+            estimate: self.estimate_triples_matching(ms, mp, mo),
+        }
+    }
+
     /// Build a Hashset of all the terms used as subject in this Graph.
     fn subjects(&self) -> GResultTermSet<Self>
     where
@@ -380,6 +546,19 @@ pub trait Graph {
         Ok(res)
     }
 
+    /// Check whether this Graph contains any triple that is only valid
+    /// in the [generalized RDF model](../index.html#generalized-vs-strict-rdf-model).
+    ///
+    /// See [`Triple::is_generalized`](../triple/trait.Triple.html#method.is_generalized).
+    fn is_generalized(&self) -> GResult<Self, bool> {
+        for t in self.triples() {
+            if t?.is_generalized() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// [`Dataset`](../dataset/trait.Dataset.html) adapter borrowing this graph
     fn as_dataset(&self) -> GraphAsDataset<Self, &Self> {
         GraphAsDataset::new(self)
@@ -472,6 +651,14 @@ pub trait MutableGraph: Graph {
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized;
 
+    /// Reserve capacity for at least `additional` more triples in this graph.
+    ///
+    /// This is only a hint; the default implementation does nothing.
+    #[inline]
+    fn reserve_triples(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
     /// Insert into this graph all triples from the given source.
     ///
     /// # Blank node scope
@@ -631,7 +818,11 @@ pub trait MutableGraph: Graph {
 /// enforcing (1) but failing to enforce (2)
 /// *must not* implement this trait.
 ///
+/// Implementors should also override [`Graph::is_set`] to return `true`,
+/// so that generic code can check this property at run time.
+///
 /// [`Graph`]: trait.Graph.html
+/// [`Graph::is_set`]: trait.Graph.html#method.is_set
 /// [`MutableGraph`]: trait.MutableGraph.html
 
 pub trait SetGraph: Graph {}
@@ -643,6 +834,20 @@ pub(crate) fn insert_if_absent<T: Clone + Eq + Hash>(set: &mut HashSet<T>, val:
     }
 }
 
+/// Remove duplicates from the candidates of a matcher,
+/// so that triples matching several of them are not returned several times.
+fn dedup_candidates(mut candidates: Vec<&dyn TTerm>) -> Vec<&dyn TTerm> {
+    let mut i = 1;
+    while i < candidates.len() {
+        if candidates[..i].iter().any(|t| term_eq(*t, candidates[i])) {
+            candidates.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    candidates
+}
+
 #[cfg(test)]
 mod test {
     // The code from this module is tested through its use in other modules
@@ -292,7 +292,7 @@ macro_rules! test_graph_impl {
             use $crate::graph::test::*;
             use $crate::graph::*;
             use $crate::ns::*;
-            use $crate::term::matcher::ANY;
+            use $crate::term::matcher::{TermMatcher, ANY};
 
             #[allow(unused_imports)]
             use super::*;
@@ -453,6 +453,35 @@ macro_rules! test_graph_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_triples_page() -> Result<(), Box<dyn std::error::Error>> {
+                let g: $graph_impl = $graph_collector(some_triples()).unwrap();
+
+                let mut all: Vec<_> = g.triples().map(as_box_t).collect();
+                let mut paged = Vec::new();
+                loop {
+                    let page: Vec<_> = g.triples_page(paged.len(), 3).map(as_box_t).collect();
+                    assert!(page.len() <= 3);
+                    if page.is_empty() {
+                        break;
+                    }
+                    paged.extend(page);
+                }
+                all.sort_by_key(|t| format!("{:?}", t));
+                paged.sort_by_key(|t| format!("{:?}", t));
+                assert_eq!(paged, all);
+                assert_eq!(g.triples_page(all.len(), 3).count(), 0);
+                assert_eq!(g.triples_page(0, 0).count(), 0);
+                Ok(())
+            }
+
+            #[test]
+            fn test_is_set() -> Result<(), Box<dyn std::error::Error>> {
+                let g: $graph_impl = $graph_collector(some_triples()).unwrap();
+                assert_eq!(g.is_set(), $is_set);
+                Ok(())
+            }
+
             #[test]
             fn test_triples_matching() -> Result<(), Box<dyn std::error::Error>> {
                 let g: $graph_impl = $graph_collector(some_triples()).unwrap();
@@ -472,6 +501,65 @@ macro_rules! test_graph_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_triples_matching_candidates() -> Result<(), Box<dyn std::error::Error>> {
+                use $crate::term::matcher::{And, Not, Or};
+                use $crate::term::TTerm;
+
+                let g: $graph_impl = $graph_collector(some_triples()).unwrap();
+
+                // duplicate candidates must not yield duplicate triples
+                let s_matcher = [&*C1, &*C2, &*C1];
+                let expected = g
+                    .triples_matching(&[|t: &dyn TTerm| s_matcher.matches(t)], &ANY, &ANY)
+                    .count();
+                assert_eq!(g.triples_matching(&s_matcher, &ANY, &ANY).count(), expected);
+
+                let rdf_type = rdf::type_;
+                let m = Or(rdf_type, rdfs::domain);
+                let expected = g
+                    .triples_matching(&ANY, &[|t: &dyn TTerm| m.matches(t)], &ANY)
+                    .count();
+                assert_eq!(g.triples_matching(&ANY, &m, &ANY).count(), expected);
+
+                let m = And([&*C1, &*C2], Not(C1.clone()));
+                let expected = g.triples_matching(&ANY, &ANY, &*C2).count();
+                assert_eq!(g.triples_matching(&ANY, &ANY, &m).count(), expected);
+                Ok(())
+            }
+
+            #[test]
+            fn test_count_triples_matching() -> Result<(), Box<dyn std::error::Error>> {
+                fn check<G, S, P, O>(g: &G, ms: &S, mp: &P, mo: &O) -> Result<(), G::Error>
+                where
+                    G: Graph,
+                    S: TermMatcher + ?Sized,
+                    P: TermMatcher + ?Sized,
+                    O: TermMatcher + ?Sized,
+                {
+                    let expected = g.triples_matching(ms, mp, mo).count();
+                    assert_eq!(g.count_triples_matching(ms, mp, mo)?, expected);
+                    let (min, max) = g.estimate_triples_matching(ms, mp, mo);
+                    assert!(min <= expected);
+                    assert!(max.map_or(true, |max| expected <= max));
+                    Ok(())
+                }
+
+                let g: $graph_impl = $graph_collector(some_triples()).unwrap();
+                check(&g, &ANY, &ANY, &ANY)?;
+                check(&g, &*I1A, &ANY, &ANY)?;
+                check(&g, &ANY, &rdf::type_, &ANY)?;
+                check(&g, &ANY, &ANY, &*C1)?;
+                check(&g, &*I1A, &rdf::type_, &ANY)?;
+                check(&g, &ANY, &rdf::type_, &*C1)?;
+                check(&g, &*I1A, &ANY, &*C1)?;
+                check(&g, &*I1A, &rdf::type_, &*C1)?;
+                check(&g, &*C1, &rdf::type_, &*C1)?;
+                check(&g, &*I1A, &[&rdf::type_, &rdfs::domain], &ANY)?;
+                check(&g, &ANY, &[&rdf::type_, &rdfs::domain], &[&*C1, &*C2])?;
+                Ok(())
+            }
+
             #[test]
             fn test_subjects() -> Result<(), Box<dyn std::error::Error>> {
                 let g: $graph_impl = $graph_collector(some_triples()).unwrap();
@@ -585,6 +673,17 @@ macro_rules! test_graph_impl {
                 Ok(())
             }
 
+            #[test]
+            fn test_is_generalized() -> Result<(), Box<dyn std::error::Error>> {
+                let g: $graph_impl = $graph_collector(strict_node_types_triples()).unwrap();
+                assert!(!g.is_generalized()?);
+                if $is_gen {
+                    let g: $graph_impl = $graph_collector(generalized_node_types_triples()).unwrap();
+                    assert!(g.is_generalized()?);
+                }
+                Ok(())
+            }
+
             // Tests for MutableGraph only, if enabled:
             $($mt)*
         }
@@ -31,13 +31,47 @@
 //! When the distinction matters,
 //! they will be called, respectively,
 //! the *generalized* RDF model, and the *strict* RDF model.
+//!
+//! Graphs and datasets accept generalized triples and quads,
+//! and can tell whether they contain any
+//! (see [`Graph::is_generalized`](graph/trait.Graph.html#method.is_generalized)).
+//! Parsers and serializers, on the other hand, stick to the strict model
+//! unless configured otherwise.
+//!
+//! # `no_std` support
+//!
+//! The `std` feature is enabled by default.
+//! When it is disabled, this crate is `no_std` (but requires `alloc`),
+//! and only provides the core types and traits for [term]s, [triple]s and quads,
+//! as well as the standard [namespaces](ns/index.html).
+//! Graphs, datasets, streams, parsers and serializers all require `std`,
+//! and so does IRI resolution.
+//!
+//! Building without `std` requires Rust 1.81 or later,
+//! as errors then implement `core::error::Error`.
+//! Also, as [`mownstr`](https://docs.rs/mownstr) requires `std`,
+//! [`TTerm::value`](term/trait.TTerm.html#method.value) then returns a `Cow<str>` instead of a `MownStr`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub(crate) use mownstr::MownStr;
+#[cfg(not(feature = "std"))]
+pub(crate) type MownStr<'a> = alloc::borrow::Cow<'a, str>;
 
+#[cfg(feature = "std")]
 pub mod dataset;
+#[cfg(feature = "std")]
 pub mod graph;
 pub mod ns;
+#[cfg(feature = "std")]
 pub mod parser;
+#[cfg(feature = "std")]
 pub mod prefix;
 pub mod quad;
+#[cfg(feature = "std")]
 pub mod serializer;
 pub mod term;
 pub mod triple;
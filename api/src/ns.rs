@@ -16,8 +16,11 @@
 //! ```
 
 use crate::term::SimpleIri;
+#[cfg(feature = "std")]
 use mownstr::MownStr;
-use sophia_iri::{error::*, is_valid_iri_ref, resolve::*};
+#[cfg(feature = "std")]
+use sophia_iri::resolve::*;
+use sophia_iri::{error::*, is_valid_iri_ref};
 
 /// A custom namespace.
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
@@ -34,7 +37,7 @@ where
         if is_valid_iri_ref(iri.as_ref()) {
             Ok(Self(iri))
         } else {
-            Err(InvalidIri(String::from(iri.as_ref())))
+            Err(InvalidIri::new(iri.as_ref()))
         }
     }
 
@@ -77,6 +80,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a, 'b, T> Resolve<&'a Namespace<T>, Namespace<MownStr<'a>>> for IriParsed<'b>
 where
     T: AsRef<str>,
@@ -95,7 +99,7 @@ impl<T: AsRef<str>> AsRef<str> for Namespace<T> {
     }
 }
 
-impl<T: AsRef<str>> std::ops::Deref for Namespace<T> {
+impl<T: AsRef<str>> core::ops::Deref for Namespace<T> {
     type Target = str;
 
     fn deref(&self) -> &str {
@@ -106,22 +110,70 @@ impl<T: AsRef<str>> std::ops::Deref for Namespace<T> {
 /// Create a "namespace module"
 /// defining a set of terms within a given IRI space.
 ///
+/// The macro takes the prefix IRI, then a comma-separated list of identifiers
+/// (each becoming the suffix of a term),
+/// optionally followed by a semicolon and a list of `identifier, "suffix"` pairs
+/// (for suffixes that are not valid Rust identifiers, or are reserved keywords).
+/// Each term can be preceded by attributes,
+/// typically doc comments and `#[deprecated]` markers,
+/// which are applied to the generated static.
+///
+/// Besides one static per term,
+/// the module contains the `PREFIX` of the namespace,
+/// and an `ALL_TERMS` const slice, listing all its terms in the order of declaration.
+///
+/// # Example
+/// ```
+/// mod ex {
+///     sophia_api::namespace!(
+///         "http://example.org/ns#",
+///         /// The class of persons.
+///         Person,
+///         /// The name of a person.
+///         name,
+///         /// Use `ex:name` instead.
+///         #[deprecated]
+///         fullName;
+///         /// The type of something.
+///         type_, "type"
+///     );
+/// }
+///
+/// use sophia_api::term::TTerm;
+/// assert_eq!(ex::PREFIX, "http://example.org/ns#");
+/// assert_eq!(ex::Person.value(), "http://example.org/ns#Person");
+/// assert_eq!(ex::type_.value(), "http://example.org/ns#type");
+/// let all: Vec<_> = ex::ALL_TERMS.iter().map(|t| t.value().to_string()).collect();
+/// assert_eq!(all.len(), 4);
+/// assert_eq!(all[2], "http://example.org/ns#fullName");
+/// ```
+///
 /// # Tests
 /// This macro also create a test module to check that all created IRIs are valid.
 ///
 /// This allows to skip those checks at runtime, keeping the initialization of the namespace fast.
 #[macro_export]
 macro_rules! namespace {
-    ($iri_prefix:expr, $($suffix:ident),*; $($r_id:ident, $r_sf:expr),*) => {
+    ($iri_prefix:expr, $($(#[$attr:meta])* $suffix:ident),*; $($(#[$r_attr:meta])* $r_id:ident, $r_sf:expr),*) => {
         /// Prefix used in this namespace.
         pub static PREFIX:&'static str = $iri_prefix;
         $(
-            $crate::ns_iri!($iri_prefix, $suffix);
+            $crate::ns_iri!($iri_prefix, $suffix, stringify!($suffix), $(#[$attr])*);
         )*
         $(
-            $crate::ns_iri!($iri_prefix, $r_id, $r_sf);
+            $crate::ns_iri!($iri_prefix, $r_id, $r_sf, $(#[$r_attr])*);
         )*
 
+        /// All the terms of this namespace.
+        pub const ALL_TERMS: &[$crate::term::SimpleIri<'static>] = &[
+            $(
+                $crate::term::SimpleIri::new_unchecked($iri_prefix, Some(stringify!($suffix))),
+            )*
+            $(
+                $crate::term::SimpleIri::new_unchecked($iri_prefix, Some($r_sf)),
+            )*
+        ];
+
         /// Test module for checking tha IRIs are valid
         #[cfg(test)]
         mod test_valid_iri {
@@ -142,14 +194,16 @@ macro_rules! namespace {
             )*
         }
     };
-    ($iri_prefix:expr, $($suffix:ident),*) => {
-        namespace!($iri_prefix, $($suffix),*;);
+    ($iri_prefix:expr, $($(#[$attr:meta])* $suffix:ident),*) => {
+        $crate::namespace!($iri_prefix, $($(#[$attr])* $suffix),*;);
     };
 }
 
 /// Create a term in a "namespace module".
 /// In general, you should use the [`namespace!`](macro.namespace.html) macro instead.
 ///
+/// The attributes given after the suffix (if any) are applied to the generated static.
+///
 /// # Safety
 /// This macro is conceptually unsafe,
 /// as it is never checked that the prefix IRI is a valid IRI reference.
@@ -158,12 +212,18 @@ macro_rules! ns_iri {
     ($prefix:expr, $ident:ident) => {
         $crate::ns_iri!($prefix, $ident, stringify!($ident));
     };
-    ($prefix:expr, $ident:ident, $suffix:expr) => {
+    ($prefix:expr, $ident:ident, $suffix:expr $(,)?) => {
         /// Generated term.
         #[allow(non_upper_case_globals)]
         pub static $ident: $crate::term::SimpleIri =
             $crate::term::SimpleIri::new_unchecked($prefix, Some($suffix));
     };
+    ($prefix:expr, $ident:ident, $suffix:expr, $(#[$attr:meta])+) => {
+        $(#[$attr])+
+        #[allow(non_upper_case_globals)]
+        pub static $ident: $crate::term::SimpleIri =
+            $crate::term::SimpleIri::new_unchecked($prefix, Some($suffix));
+    };
 }
 
 /// The standard `rdf:` namespace.
@@ -185,6 +245,7 @@ pub mod rdf {
         // datatypes
         HTML,
         JSON,
+        dirLangString,
         langString,
         XMLLiteral,
         // properties
@@ -224,6 +285,8 @@ pub mod xsd {
         anyType,
         anySimpleType,
             duration,
+                dayTimeDuration,
+                yearMonthDuration,
             dateTime,
             time,
             date,
@@ -324,6 +387,7 @@ pub mod owl {
         InverseFunctionalProperty,
         IrreflexiveProperty,
         ObjectProperty,
+        Ontology,
         SymmetricProperty,
         TransitiveProperty,
         // Properties
@@ -335,6 +399,7 @@ pub mod owl {
         distinctMembers,
         equivalentClass,
         equivalentProperty,
+        imports,
         intersectionOf,
         inverseOf,
         maxCardinality,
@@ -354,6 +419,29 @@ pub mod owl {
     );
 }
 
+/// The [GeoSPARQL](https://www.ogc.org/standard/geosparql/) `geo:` namespace
+pub mod geo {
+    namespace!(
+        "http://www.opengis.net/ont/geosparql#",
+        // Classes
+        Feature,
+        Geometry,
+        SpatialObject,
+        // Properties
+        asGML,
+        asWKT,
+        hasDefaultGeometry,
+        hasGeometry,
+        sfContains,
+        sfDisjoint,
+        sfIntersects,
+        sfWithin,
+        // Datatypes
+        gmlLiteral,
+        wktLiteral
+    );
+}
+
 #[cfg(test)]
 mod test {
     // Nothing really worth testing here
@@ -386,4 +474,34 @@ mod test {
         let ns1 = Namespace::new("http://schema.org/").unwrap();
         assert!(ns1.get("name ").is_err());
     }
+
+    #[test]
+    fn test_all_terms() {
+        assert!(rdf::ALL_TERMS.contains(&rdf::type_));
+        assert!(rdf::ALL_TERMS.contains(&rdf::Property));
+        assert_eq!(rdf::ALL_TERMS.len(), 34);
+    }
+
+    #[allow(dead_code)]
+    mod documented {
+        namespace!(
+            "http://example.org/",
+            /// A documented term
+            a,
+            #[deprecated]
+            b;
+            /// A documented renamed term
+            #[deprecated(note = "do not use")]
+            c_, "c"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_attributes() {
+        use crate::term::TTerm;
+        assert_eq!(documented::ALL_TERMS.len(), 3);
+        assert_eq!(documented::ALL_TERMS[1], documented::b);
+        assert_eq!(documented::c_.value(), "http://example.org/c");
+    }
 }
@@ -3,6 +3,8 @@
 use crate::quad::stream::QuadSource;
 use crate::triple::stream::TripleSource;
 
+mod _error;
+pub use _error::*;
 mod _location;
 pub use _location::*;
 
@@ -0,0 +1,318 @@
+// this module is transparently re-exported by its parent `parser`
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use super::{Location, WithLocation};
+
+/// A detailed error raised by a parser.
+///
+/// Besides a human-readable message,
+/// it carries (when known) the position of the error,
+/// the offending token, the tokens that were expected instead,
+/// a snippet of the source around the error, and a hint on how to fix it.
+///
+/// The snippet is not always available to the parser itself
+/// (e.g. when parsing a stream);
+/// it can be added afterwards with [`with_snippet_from`](#method.with_snippet_from).
+///
+/// With the `miette` feature enabled, this type implements [`miette::Diagnostic`];
+/// [`byte_range`](#method.byte_range) can be used to build [`ariadne`] reports.
+///
+/// [`miette::Diagnostic`]: https://docs.rs/miette/latest/miette/trait.Diagnostic.html
+/// [`ariadne`]: https://docs.rs/ariadne/
+#[derive(Debug)]
+pub struct ParseError {
+    message: String,
+    line_column: Option<(usize, usize)>,
+    offset: Option<usize>,
+    token: Option<String>,
+    expected: Vec<String>,
+    snippet: Option<String>,
+    hint: Option<String>,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl ParseError {
+    /// Build a parse error with the given message, and no other information.
+    pub fn new<M: Into<String>>(message: M) -> ParseError {
+        ParseError {
+            message: message.into(),
+            line_column: None,
+            offset: None,
+            token: None,
+            expected: vec![],
+            snippet: None,
+            hint: None,
+            source: None,
+        }
+    }
+
+    /// Set the line and (byte) column of this error, both starting at 1.
+    pub fn with_line_column(mut self, line: usize, column: usize) -> Self {
+        self.line_column = Some((line, column));
+        self
+    }
+
+    /// Set the byte offset of this error in the source, starting at 0.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Set the offending token.
+    pub fn with_token<T: Into<String>>(mut self, token: T) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the tokens that were expected instead of the offending token.
+    pub fn with_expected<I, T>(mut self, expected: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.expected = expected.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set a hint on how to fix this error.
+    pub fn with_hint<H: Into<String>>(mut self, hint: H) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Set the underlying cause of this error.
+    pub fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Set the line of the source containing this error.
+    pub fn with_snippet<S: Into<String>>(mut self, snippet: S) -> Self {
+        self.snippet = Some(snippet.into());
+        self
+    }
+
+    /// Extract the snippet of this error from the parsed `text`,
+    /// and complete its position (line-column or offset) with the missing information.
+    ///
+    /// This has no effect if the position of this error is unknown,
+    /// or does not match `text`.
+    pub fn with_snippet_from(mut self, text: &str) -> Self {
+        let (line, column) = match (self.line_column, self.offset) {
+            (Some(lico), _) => lico,
+            (None, Some(offset)) if offset <= text.len() => {
+                let before = &text.as_bytes()[..offset];
+                let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+                let start = before
+                    .iter()
+                    .rposition(|b| *b == b'\n')
+                    .map_or(0, |i| i + 1);
+                (line, offset - start + 1)
+            }
+            _ => return self,
+        };
+        let mut start = 0;
+        for (i, txt) in text.split('\n').enumerate() {
+            if i + 1 == line {
+                if column > txt.len() + 1 {
+                    return self;
+                }
+                self.line_column = Some((line, column));
+                self.offset = Some(start + column - 1);
+                self.snippet = Some(txt.trim_end_matches('\r').to_string());
+                return self;
+            }
+            start += txt.len() + 1;
+        }
+        self
+    }
+
+    /// The message of this error, without any position information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The line of this error, starting at 1.
+    pub fn line(&self) -> Option<usize> {
+        self.line_column.map(|lico| lico.0)
+    }
+
+    /// The (byte) column of this error, starting at 1.
+    pub fn column(&self) -> Option<usize> {
+        self.line_column.map(|lico| lico.1)
+    }
+
+    /// The byte offset of this error in the source, starting at 0.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The offending token.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The tokens that were expected instead of the offending token.
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    /// The line of the source containing this error.
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+
+    /// A hint on how to fix this error.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    /// The range of bytes of the source covered by the offending token
+    /// (or by a single byte if the token is unknown).
+    pub fn byte_range(&self) -> Option<Range<usize>> {
+        self.offset.map(|start| start..start + self.token_len())
+    }
+
+    fn token_len(&self) -> usize {
+        self.token.as_ref().map_or(1, |t| t.len().max(1))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        match (self.line_column, self.offset) {
+            (Some((line, column)), _) => write!(f, " at line {}, column {}", line, column)?,
+            (None, Some(offset)) => write!(f, " at byte {}", offset)?,
+            _ => (),
+        }
+        if !self.expected.is_empty() {
+            write!(f, "; expected {}", self.expected.join(", "))?;
+        }
+        if let (Some(snippet), Some((line, column))) = (&self.snippet, self.line_column) {
+            let margin = line.to_string().len();
+            write!(f, "\n{} | {}", line, snippet)?;
+            write!(
+                f,
+                "\n{:margin$} | {:column$}{}",
+                "",
+                "",
+                "^".repeat(self.token_len()),
+                margin = margin,
+                column = column - 1,
+            )?;
+        }
+        if let Some(hint) = &self.hint {
+            write!(f, "\nhint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+impl WithLocation for ParseError {
+    fn location(&self) -> Location {
+        match (self.line_column, self.offset) {
+            (Some((line, column)), _) => Location::from_lico(line, column),
+            (None, Some(offset)) => Location::from_offset(offset),
+            _ => Location::Unknown,
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette_crate::Diagnostic for ParseError {
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.hint
+            .as_ref()
+            .map(|h| Box::new(h) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette_crate::SourceCode> {
+        self.snippet
+            .as_ref()
+            .map(|s| s as &dyn miette_crate::SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette_crate::LabeledSpan> + '_>> {
+        // labels are relative to the snippet, which is the only source code available
+        let column = self.column()?;
+        self.snippet.as_ref()?;
+        let label = match &self.token {
+            Some(token) => format!("unexpected {}", token),
+            None => "here".to_string(),
+        };
+        Some(Box::new(std::iter::once(miette_crate::LabeledSpan::new(
+            Some(label),
+            column - 1,
+            self.token_len(),
+        ))))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snippet_from_line_column() {
+        let err = ParseError::new("unexpected character")
+            .with_line_column(2, 5)
+            .with_token("!")
+            .with_expected(vec!["'.'", "';'"])
+            .with_hint("terminate the statement with '.'")
+            .with_snippet_from("<a> <b> <c>.\n<a> ! <c>.\n");
+        assert_eq!(err.offset(), Some(17));
+        assert_eq!(err.byte_range(), Some(17..18));
+        assert_eq!(err.snippet(), Some("<a> ! <c>."));
+        assert_eq!(
+            err.to_string(),
+            "unexpected character at line 2, column 5; expected '.', ';'\n\
+             2 | <a> ! <c>.\n  |     ^\n\
+             hint: terminate the statement with '.'"
+        );
+    }
+
+    #[test]
+    fn snippet_from_offset() {
+        let err = ParseError::new("oops")
+            .with_offset(17)
+            .with_snippet_from("<a> <b> <c>.\r\n<a> ! <c>.\r\n");
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.column(), Some(4));
+        assert_eq!(err.snippet(), Some("<a> ! <c>."));
+    }
+
+    #[test]
+    fn snippet_out_of_range() {
+        let err = ParseError::new("oops")
+            .with_line_column(3, 1)
+            .with_snippet_from("<a> <b> <c>.");
+        assert_eq!(err.snippet(), None);
+        assert_eq!(err.to_string(), "oops at line 3, column 1");
+    }
+
+    #[test]
+    fn no_position() {
+        let err = ParseError::new("oops");
+        assert!(matches!(err.location(), Location::Unknown));
+        assert_eq!(err.byte_range(), None);
+        assert_eq!(err.to_string(), "oops");
+    }
+}
@@ -1,7 +1,7 @@
 //! RDF-related languages (e.g. Turtle, SPARQL) often use prefixes to shorten IRIs.
 //! This crate provides generic traits to handle prefix maps.
 
-use crate::term::{SimpleIri, TTerm, TermKind};
+use crate::term::{format_quoted, term_eq, term_format, SimpleIri, TTerm, TermKind};
 use mownstr::MownStr;
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -51,6 +51,180 @@ where
     }
 }
 
+/// A prefix map indexed by namespace,
+/// for finding quickly the prefix of many IRIs (typically when serializing).
+///
+/// Unlike the implementation of [`PrefixMap`] for `HashMap`,
+/// which compares the IRI with every namespace,
+/// [`get_prefixed_pair`] only looks up a few candidate namespaces:
+/// * the namespace recorded in the IRI itself, if it has a suffix
+///   (see [`TTerm::value_raw`]);
+///   this is the case of IRIs built from a [`Namespace`],
+///   which are therefore written with their original prefix;
+/// * the whole IRI;
+/// * the prefixes of the IRI ending with `#`, `/` or `:`, longest first.
+///
+/// [`PrefixMap`]: trait.PrefixMap.html
+/// [`get_prefixed_pair`]: trait.PrefixMap.html#tymethod.get_prefixed_pair
+/// [`TTerm::value_raw`]: ../term/trait.TTerm.html#tymethod.value_raw
+/// [`Namespace`]: ../ns/struct.Namespace.html
+#[derive(Clone, Debug, Default)]
+pub struct NamespaceIndex<'a> {
+    prefixes: HashMap<&'a str, SimpleIri<'a>>,
+    namespaces: HashMap<String, &'a str>,
+}
+
+impl<'a> NamespaceIndex<'a> {
+    /// Build an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associate `prefix` to namespace `ns`,
+    /// replacing any namespace previously associated to `prefix`.
+    pub fn insert(&mut self, prefix: &'a str, ns: SimpleIri<'a>) {
+        if let Some(old) = self.prefixes.insert(prefix, ns) {
+            self.namespaces.remove(&old.value()[..]);
+        }
+        self.namespaces.insert(ns.value().to_string(), prefix);
+    }
+
+    /// The number of prefixes in this index.
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    /// Whether this index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// Iterate over the prefixes and namespaces of this index.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, SimpleIri<'a>)> + '_ {
+        self.prefixes.iter().map(|(pf, ns)| (*pf, *ns))
+    }
+}
+
+impl<'a> std::iter::FromIterator<(&'a str, SimpleIri<'a>)> for NamespaceIndex<'a> {
+    fn from_iter<I: IntoIterator<Item = (&'a str, SimpleIri<'a>)>>(iter: I) -> Self {
+        let mut index = NamespaceIndex::new();
+        for (prefix, ns) in iter {
+            index.insert(prefix, ns);
+        }
+        index
+    }
+}
+
+impl<'a> PrefixMap<'a> for NamespaceIndex<'a> {
+    type Term = SimpleIri<'a>;
+
+    fn get_namespace(&self, prefix: &str) -> Option<&Self::Term> {
+        self.prefixes.get(prefix)
+    }
+    fn get_prefixed_pair<'s, T: TTerm>(&'s self, iri: &'s T) -> Option<(&'s str, MownStr<'s>)> {
+        if iri.kind() != TermKind::Iri {
+            return None;
+        }
+        let raw_value = iri.value_raw();
+        if let Some(suffix) = raw_value.1 {
+            if let Some(prefix) = self.namespaces.get(raw_value.0) {
+                return Some((prefix, MownStr::from(suffix)));
+            }
+        }
+        let full: MownStr = raw_value.into();
+        if let Some(prefix) = self.namespaces.get(&full[..]) {
+            return Some((prefix, MownStr::from("")));
+        }
+        full.char_indices()
+            .rev()
+            .filter(|(_, c)| *c == '#' || *c == '/' || *c == ':')
+            .find_map(|(i, _)| {
+                let prefix = self.namespaces.get(&full[..=i])?;
+                Some((*prefix, raw_value.slice(i + 1..)))
+            })
+    }
+}
+
+/// Format the given term in the Turtle format,
+/// writing IRIs as prefixed names when `prefixes` allows it.
+///
+/// IRIs (including datatypes) are abbreviated
+/// only if their local part can be written without escaping;
+/// otherwise, as well as for other kinds of terms,
+/// this writes the same as [`term_format`](../term/fn.term_format.html).
+pub fn term_format_turtle<'a, T, P, W>(term: &T, prefixes: &P, w: &mut W) -> std::fmt::Result
+where
+    T: TTerm + ?Sized,
+    P: PrefixMap<'a>,
+    W: std::fmt::Write,
+{
+    match term.kind() {
+        TermKind::Iri => {
+            let raw = term.value_raw();
+            let iri = SimpleIri::new_unchecked(raw.0, raw.1);
+            if let Some((prefix, local)) = prefixes.get_prefixed_pair(&iri) {
+                if is_simple_local(&local) {
+                    return write!(w, "{}:{}", prefix, local);
+                }
+            }
+            term_format(term, w)
+        }
+        TermKind::Literal => {
+            w.write_char('"')?;
+            format_quoted(term.value_raw().0, w)?;
+            w.write_char('"')?;
+            if let Some(tag) = term.language() {
+                write!(w, "@{}", tag)?;
+                if let Some(dir) = term.base_direction() {
+                    write!(w, "--{}", dir)?;
+                }
+                Ok(())
+            } else {
+                let dt = term.datatype().unwrap();
+                if !term_eq(&dt, &crate::ns::xsd::string) {
+                    w.write_str("^^")?;
+                    term_format_turtle(&dt, prefixes, w)?;
+                }
+                Ok(())
+            }
+        }
+        _ => term_format(term, w),
+    }
+}
+
+/// Format the given term into a Turtle string,
+/// using the given prefixes (see [`term_format_turtle`](fn.term_format_turtle.html)).
+pub fn term_to_turtle_with<'a, T, P>(term: &T, prefixes: &P) -> String
+where
+    T: TTerm + ?Sized,
+    P: PrefixMap<'a>,
+{
+    let mut s = String::new();
+    term_format_turtle(term, prefixes, &mut s).unwrap(); // writing to a String can not fail
+    s
+}
+
+/// Whether `local` can be written as the local part of a prefixed name without escaping.
+fn is_simple_local(local: &str) -> bool {
+    let mut chars = local.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if c == '-' || !is_pn_chars(c) => false,
+        Some(_) => chars.all(is_pn_chars),
+    }
+}
+
+/// See <https://www.w3.org/TR/turtle/#grammar-production-PN_CHARS>
+fn is_pn_chars(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(c, '_' | ':' | '-' | '\u{B7}')
+        || matches!(c, '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{37D}')
+        || matches!(c, '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{203F}'..='\u{2040}')
+        || matches!(c, '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}')
+        || matches!(c, '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+        || matches!(c, '\u{300}'..='\u{36F}')
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -79,4 +253,65 @@ mod test {
         let iri = SimpleIri::new_unchecked(ns, sf);
         assert_eq!(map.get_prefixed_pair(&iri), expected);
     }
+
+    #[test_case("http://something.else.com/", None, None; "something else")]
+    #[test_case("http://schema.org/Person", None, Some(("s", "Person")); "s:Person")]
+    #[test_case("http://example.org/", None, Some(("", "")); "single colon")]
+    #[test_case("http://example.org/a/c", None, Some(("a", "c")); "a:c")]
+    #[test_case("http://example.org/a/b#c", None, Some(("ab", "c")); "b:c")]
+    #[test_case("http://example.org/a#c", None, Some(("", "a#c")); ":a#c")]
+    #[test_case("http://example.org/a/", Some("b/c"), Some(("a", "b/c")); "recorded split")]
+    #[test_case("http://example.org/a/b/", Some("c"), Some(("a", "b/c")); "unknown recorded split")]
+    fn namespace_index(ns: &str, sf: Option<&str>, expected: Option<(&str, &str)>) {
+        let index: NamespaceIndex = vec![
+            ("s", SimpleIri::new_unchecked("http://schema.org/", None)),
+            (
+                "ab",
+                SimpleIri::new_unchecked("http://example.org/", Some("a/b#")),
+            ),
+            (
+                "a",
+                SimpleIri::new_unchecked("http://example.org/", Some("a/")),
+            ),
+            ("", SimpleIri::new_unchecked("http://example.org/", None)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(index.len(), 4);
+        assert_eq!(
+            index.get_namespace("s").unwrap().value(),
+            "http://schema.org/"
+        );
+
+        let expected = expected.map(|(pf, sf)| (pf, MownStr::from(sf)));
+        let iri = SimpleIri::new_unchecked(ns, sf);
+        assert_eq!(index.get_prefixed_pair(&iri), expected);
+    }
+
+    #[test]
+    fn turtle() {
+        use crate::ns::xsd;
+        use crate::term::test::TestTerm;
+        let mut map = HashMap::new();
+        map.insert("s", SimpleIri::new_unchecked("http://schema.org/", None));
+        map.insert("xsd", SimpleIri::new_unchecked(xsd::PREFIX, None));
+        let person = SimpleIri::new_unchecked("http://schema.org/Person", None);
+        assert_eq!(term_to_turtle_with(&person, &map), "s:Person");
+        let other = SimpleIri::new_unchecked("http://example.org/a", None);
+        assert_eq!(term_to_turtle_with(&other, &map), "<http://example.org/a>");
+        // local parts requiring escaping are not abbreviated
+        let path = SimpleIri::new_unchecked("http://schema.org/a/b", None);
+        assert_eq!(term_to_turtle_with(&path, &map), "<http://schema.org/a/b>");
+        assert_eq!(term_to_turtle_with(&xsd::integer, &map), "xsd:integer");
+        assert_eq!(
+            term_to_turtle_with(&xsd::integer, &HashMap::<&str, SimpleIri>::new()),
+            "<http://www.w3.org/2001/XMLSchema#integer>"
+        );
+        let lit = TestTerm::<&str>::lit_dt("4\"2\n", xsd::integer);
+        assert_eq!(term_to_turtle_with(&lit, &map), r#""4\"2\n"^^xsd:integer"#);
+        let lit = TestTerm::<&str>::lit_lang("chat", "fr");
+        assert_eq!(term_to_turtle_with(&lit, &map), r#""chat"@fr"#);
+        let bnode = TestTerm::<&str>::bnode("b");
+        assert_eq!(term_to_turtle_with(&bnode, &map), "_:b");
+    }
 }
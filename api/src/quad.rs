@@ -4,9 +4,10 @@
 //!
 //! They are the individual statements of an RDF `dataset`(../dataset/index.html).
 
-use crate::term::TTerm;
+use crate::term::{TTerm, TermKind};
 use crate::triple::*;
 
+#[cfg(feature = "std")]
 pub mod stream;
 pub mod streaming_mode;
 
@@ -35,6 +36,20 @@ pub trait Quad {
     fn components(&self) -> QuadIter<Self> {
         QuadIter(self, 0)
     }
+
+    /// Whether this quad is only valid in the [generalized RDF model],
+    /// i.e. its subject or graph name is not an IRI or a blank node,
+    /// its predicate is not an IRI,
+    /// or any of its terms is a variable.
+    ///
+    /// [generalized RDF model]: ../index.html#generalized-vs-strict-rdf-model
+    fn is_generalized(&self) -> bool {
+        let node = |t: &Self::Term| matches!(t.kind(), TermKind::Iri | TermKind::BlankNode);
+        !node(self.s())
+            || self.p().kind() != TermKind::Iri
+            || self.o().kind() == TermKind::Variable
+            || !self.g().map(node).unwrap_or(true)
+    }
 }
 
 /// Iterator over the components of a quad.
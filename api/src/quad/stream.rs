@@ -14,13 +14,19 @@
 //! [`try_for_each_quad`]: ./trait.QuadSource.html#method.try_for_each_quad
 //! [`triple::stream`]: ../../triple/stream/index.html
 
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::hash::Hash;
 
 use crate::dataset::*;
+use crate::graph::MutableGraph;
 use crate::quad::streaming_mode::*;
 use crate::quad::*;
+use crate::term::{CopyTerm, TTerm};
 pub use crate::triple::stream::{SinkError, SourceError, StreamError, StreamResult};
 
+mod _dedup;
+pub use _dedup::*;
 mod _filter;
 pub use _filter::*;
 mod _filter_map;
@@ -29,6 +35,16 @@ mod _iterator;
 pub use _iterator::*;
 mod _map;
 pub use _map::*;
+mod _map_terms;
+pub use _map_terms::*;
+mod _merge;
+pub use _merge::*;
+mod _skip;
+pub use _skip::*;
+mod _take;
+pub use _take::*;
+mod _tee;
+pub use _tee::*;
 
 /// Type alias for referencing the `TermData` used in a `QuadSource`.
 pub type QSTerm<S> =
@@ -125,6 +141,118 @@ pub trait QuadSource {
     {
         MapSource { source: self, map }
     }
+    /// Takes a closure and creates a quad source which applies that closure to each term of each quad.
+    #[inline]
+    fn map_quad_terms<F, T>(self, map: F) -> MapTermsSource<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&QSTerm<Self>) -> T,
+        T: TTerm,
+    {
+        MapTermsSource { source: self, map }
+    }
+    /// Creates a quad source which yields at most the `n` first quads of this source.
+    ///
+    /// NB: since quads are pulled from the underlying source by batches
+    /// (see [`try_for_some_quad`](#tymethod.try_for_some_quad)),
+    /// the underlying source may be consumed beyond its `n` first quads.
+    #[inline]
+    fn take_quads(self, n: usize) -> TakeSource<Self>
+    where
+        Self: Sized,
+    {
+        TakeSource {
+            source: self,
+            remaining: n,
+        }
+    }
+    /// Creates a quad source which skips the `n` first quads of this source.
+    #[inline]
+    fn skip_quads(self, n: usize) -> SkipSource<Self>
+    where
+        Self: Sized,
+    {
+        SkipSource {
+            source: self,
+            remaining: n,
+        }
+    }
+    /// Creates a quad source which yields each quad of this source only once.
+    ///
+    /// A copy of every quad (made of terms of type `T`) is kept in memory,
+    /// in order to detect duplicates.
+    #[inline]
+    fn dedup_quads<T>(self) -> DedupSource<Self, T>
+    where
+        Self: Sized,
+        T: CopyTerm + Eq + Hash,
+    {
+        DedupSource {
+            source: self,
+            seen: HashSet::new(),
+        }
+    }
+    /// Creates a quad source which feeds every quad of this source to `sink`,
+    /// before yielding it.
+    ///
+    /// This allows, for example, to populate an in-memory graph
+    /// while serializing the quads, without parsing the input twice.
+    /// Errors raised by `sink` are reported as source errors.
+    #[inline]
+    fn tee_quads<K>(self, sink: K) -> TeeSource<Self, K>
+    where
+        Self: Sized,
+        K: QuadSink,
+    {
+        TeeSource { source: self, sink }
+    }
+    /// Feed all the quads of this source to `sink`,
+    /// in batches of (at most) `batch_size` quads
+    /// (see [`QuadSink::feed_batch`](./trait.QuadSink.html#method.feed_batch)).
+    ///
+    /// As streamed quads can not outlive the call to the closure receiving them,
+    /// each quad is copied (with terms of type `T`) in the batch.
+    fn feed_quads_batched<K, T>(
+        &mut self,
+        sink: &mut K,
+        batch_size: usize,
+    ) -> StreamResult<(), Self::Error, K::Error>
+    where
+        K: QuadSink,
+        T: CopyTerm + TTerm,
+    {
+        let batch_size = batch_size.max(1);
+        let mut batch: Vec<([T; 3], Option<T>)> = Vec::with_capacity(batch_size);
+        self.try_for_each_quad(|q| {
+            batch.push((
+                [T::copy(q.s()), T::copy(q.p()), T::copy(q.o())],
+                q.g().map(T::copy),
+            ));
+            if batch.len() < batch_size {
+                return Ok(());
+            }
+            let res = sink.feed_batch(&batch);
+            batch.clear();
+            res
+        })?;
+        if !batch.is_empty() {
+            sink.feed_batch(&batch).map_err(SinkError)?;
+        }
+        Ok(())
+    }
+    /// Creates a quad source which yields all the quads of this source,
+    /// then all the quads of `other`.
+    #[inline]
+    fn merge_quads<S>(self, other: S) -> MergeSource<Self, S>
+    where
+        Self: Sized,
+        S: QuadSource<Quad = Self::Quad, Error = Self::Error>,
+    {
+        MergeSource {
+            first: Some(self),
+            second: other,
+        }
+    }
     /// Returns the bounds on the remaining length of the quad source.
     ///
     /// This method has the same contract as [`Iterator::size_hint`].
@@ -154,6 +282,32 @@ pub trait QuadSource {
     {
         dataset.insert_all(self)
     }
+    /// Split the quads from this source into one [graph](../../graph/trait.MutableGraph.html)
+    /// per graph name.
+    ///
+    /// The graph names are copied as terms of type `T`
+    /// (the default graph being identified by `None`).
+    ///
+    /// Stop on the first error (in the source or in a graph).
+    fn split_by_graph<T, G>(
+        self,
+    ) -> StreamResult<HashMap<Option<T>, G>, Self::Error, <G as MutableGraph>::MutationError>
+    where
+        Self: Sized,
+        T: CopyTerm + Eq + Hash,
+        G: MutableGraph + Default,
+    {
+        let mut source = self;
+        let mut graphs = HashMap::new();
+        source.try_for_each_quad(|q| {
+            graphs
+                .entry(q.g().map(T::copy))
+                .or_insert_with(G::default)
+                .insert(q.s(), q.p(), q.o())
+                .map(|_| ())
+        })?;
+        Ok(graphs)
+    }
 }
 
 #[cfg(test)]
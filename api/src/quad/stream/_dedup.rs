@@ -0,0 +1,42 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`QuadSource::dedup_quads`](./trait.QuadSource.html#method.dedup_quads)
+pub struct DedupSource<S, T> {
+    pub source: S,
+    pub seen: HashSet<([T; 3], Option<T>)>,
+}
+
+impl<S, T> QuadSource for DedupSource<S, T>
+where
+    S: QuadSource,
+    T: CopyTerm + Eq + Hash,
+{
+    type Error = S::Error;
+    type Quad = S::Quad;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        let seen = &mut self.seen;
+        self.source.try_for_some_quad(&mut |q| {
+            let key = (
+                [T::copy(q.s()), T::copy(q.p()), T::copy(q.o())],
+                q.g().map(T::copy),
+            );
+            if seen.insert(key) {
+                f(q)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_quads();
+        (lower.min(1), upper)
+    }
+}
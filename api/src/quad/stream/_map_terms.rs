@@ -0,0 +1,37 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`QuadSource::map_quad_terms`](./trait.QuadSource.html#method.map_quad_terms)
+pub struct MapTermsSource<S, F> {
+    pub source: S,
+    pub map: F,
+}
+
+impl<S, F, T> QuadSource for MapTermsSource<S, F>
+where
+    S: QuadSource,
+    F: FnMut(&QSTerm<S>) -> T,
+    T: TTerm,
+{
+    type Error = S::Error;
+    type Quad = ByValue<([T; 3], Option<T>)>;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        let map = &mut self.map;
+        self.source.try_for_some_quad(&mut |q| {
+            f(StreamedQuad::by_value((
+                [(map)(q.s()), (map)(q.p()), (map)(q.o())],
+                q.g().map(&mut *map),
+            )))
+        })
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_quads()
+    }
+}
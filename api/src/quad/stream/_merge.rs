@@ -0,0 +1,48 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`QuadSource::merge_quads`](./trait.QuadSource.html#method.merge_quads)
+pub struct MergeSource<S1, S2> {
+    /// The first source, until it is exhausted.
+    pub first: Option<S1>,
+    pub second: S2,
+}
+
+impl<S1, S2> QuadSource for MergeSource<S1, S2>
+where
+    S1: QuadSource,
+    S2: QuadSource<Quad = S1::Quad, Error = S1::Error>,
+{
+    type Error = S1::Error;
+    type Quad = S1::Quad;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        if let Some(first) = &mut self.first {
+            if first.try_for_some_quad(f)? {
+                return Ok(true);
+            }
+            self.first = None;
+        }
+        self.second.try_for_some_quad(f)
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        let (lower2, upper2) = self.second.size_hint_quads();
+        match &self.first {
+            None => (lower2, upper2),
+            Some(first) => {
+                let (lower1, upper1) = first.size_hint_quads();
+                let upper = match (upper1, upper2) {
+                    (Some(u1), Some(u2)) => u1.checked_add(u2),
+                    _ => None,
+                };
+                (lower1.saturating_add(lower2), upper)
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`QuadSource::skip_quads`](./trait.QuadSource.html#method.skip_quads)
+pub struct SkipSource<S> {
+    pub source: S,
+    pub remaining: usize,
+}
+
+impl<S> QuadSource for SkipSource<S>
+where
+    S: QuadSource,
+{
+    type Error = S::Error;
+    type Quad = S::Quad;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        let remaining = &mut self.remaining;
+        self.source.try_for_some_quad(&mut |q| {
+            if *remaining > 0 {
+                *remaining -= 1;
+                Ok(())
+            } else {
+                f(q)
+            }
+        })
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_quads();
+        let n = self.remaining;
+        (
+            lower.saturating_sub(n),
+            upper.map(|upper| upper.saturating_sub(n)),
+        )
+    }
+}
@@ -0,0 +1,43 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`QuadSource::take_quads`](./trait.QuadSource.html#method.take_quads)
+pub struct TakeSource<S> {
+    pub source: S,
+    pub remaining: usize,
+}
+
+impl<S> QuadSource for TakeSource<S>
+where
+    S: QuadSource,
+{
+    type Error = S::Error;
+    type Quad = S::Quad;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let remaining = &mut self.remaining;
+        let more = self.source.try_for_some_quad(&mut |q| {
+            if *remaining > 0 {
+                *remaining -= 1;
+                f(q)
+            } else {
+                Ok(())
+            }
+        })?;
+        Ok(more && self.remaining > 0)
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_quads();
+        let n = self.remaining;
+        (lower.min(n), Some(upper.map_or(n, |upper| upper.min(n))))
+    }
+}
@@ -0,0 +1,125 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+use crate::triple::stream::TeeError;
+
+/// A quad sink consumes quads one at a time,
+/// and may fail in the process.
+///
+/// Triple sinks are typically used with
+/// [`QuadSource::tee_quads`](./trait.QuadSource.html#method.tee_quads),
+/// in order to feed several consumers with the same stream of quads.
+///
+/// This trait is implemented by
+/// * mutable references to [mutable datasets](../../dataset/trait.MutableDataset.html),
+///   which insert every quad they are fed;
+/// * pairs of quad sinks, which feed both of them
+///   (nest pairs to multiplex a stream to more than two sinks);
+/// * vectors of quad sinks, which feed all of them.
+///
+/// Sinks can also be fed batches of quads, with [`feed_batch`]
+/// (see also [`QuadSource::feed_quads_batched`]).
+///
+/// [`feed_batch`]: #method.feed_batch
+/// [`QuadSource::feed_quads_batched`]: ./trait.QuadSource.html#method.feed_quads_batched
+pub trait QuadSink {
+    /// The type of errors produced by this sink.
+    type Error: 'static + Error;
+
+    /// Consume one quad.
+    fn feed<Q: Quad>(&mut self, q: &Q) -> Result<(), Self::Error>;
+
+    /// Consume a batch of quads.
+    ///
+    /// The default implementation feeds them one at a time,
+    /// but implementors can override it when they have a more efficient way
+    /// to process several quads at once.
+    fn feed_batch<Q: Quad>(&mut self, batch: &[Q]) -> Result<(), Self::Error> {
+        batch.iter().try_for_each(|q| self.feed(q))
+    }
+}
+
+impl<D> QuadSink for &mut D
+where
+    D: MutableDataset + ?Sized,
+{
+    type Error = D::MutationError;
+
+    fn feed<Q: Quad>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        self.insert(q.s(), q.p(), q.o(), q.g()).map(|_| ())
+    }
+
+    fn feed_batch<Q: Quad>(&mut self, batch: &[Q]) -> Result<(), Self::Error> {
+        self.reserve_quads(batch.len());
+        self.insert_all(batch.iter().as_quad_source())
+            .map(|_| ())
+            .map_err(StreamError::unwrap_sink_error)
+    }
+}
+
+impl<K1, K2> QuadSink for (K1, K2)
+where
+    K1: QuadSink,
+    K2: QuadSink<Error = K1::Error>,
+{
+    type Error = K1::Error;
+
+    fn feed<Q: Quad>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        self.0.feed(q)?;
+        self.1.feed(q)
+    }
+
+    fn feed_batch<Q: Quad>(&mut self, batch: &[Q]) -> Result<(), Self::Error> {
+        self.0.feed_batch(batch)?;
+        self.1.feed_batch(batch)
+    }
+}
+
+impl<K> QuadSink for Vec<K>
+where
+    K: QuadSink,
+{
+    type Error = K::Error;
+
+    fn feed<Q: Quad>(&mut self, q: &Q) -> Result<(), Self::Error> {
+        self.iter_mut().try_for_each(|sink| sink.feed(q))
+    }
+
+    fn feed_batch<Q: Quad>(&mut self, batch: &[Q]) -> Result<(), Self::Error> {
+        self.iter_mut().try_for_each(|sink| sink.feed_batch(batch))
+    }
+}
+
+/// The result of
+/// [`QuadSource::tee_quads`](./trait.QuadSource.html#method.tee_quads)
+pub struct TeeSource<S, K> {
+    pub source: S,
+    pub sink: K,
+}
+
+impl<S, K> QuadSource for TeeSource<S, K>
+where
+    S: QuadSource,
+    K: QuadSink,
+{
+    type Error = StreamError<S::Error, K::Error>;
+    type Quad = S::Quad;
+    fn try_for_some_quad<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        let sink = &mut self.sink;
+        self.source
+            .try_for_some_quad(&mut |q| {
+                sink.feed(&q).map_err(TeeError::Sink)?;
+                f(q).map_err(TeeError::Consumer)
+            })
+            .map_err(TeeError::into_stream_error)
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_quads()
+    }
+}
@@ -252,3 +252,81 @@ fn map_quads_iter() {
         ]
     );
 }
+
+#[test]
+fn map_quad_terms() {
+    let d = make_dataset();
+    let e = make_mapped_dataset();
+    let mut c = 0;
+    d.quads()
+        .map_quad_terms(map_term)
+        .for_each_quad(|q| {
+            c += 1;
+            assert!(e.contains(q.s(), q.p(), q.o(), q.g()).unwrap());
+        })
+        .unwrap();
+    assert_eq!(c, e.len());
+}
+
+#[test]
+fn take_skip_quads() {
+    let d = make_dataset();
+    let mut e = Vec::<([BoxTerm; 3], Option<BoxTerm>)>::new();
+    d.quads()
+        .skip_quads(1)
+        .take_quads(2)
+        .add_to_dataset(&mut e)
+        .unwrap();
+    assert_eq!(e.len(), 2);
+    assert_eq!(e[0].o(), &ALICE_LIT as &StaticTerm);
+    assert_eq!(e[1].s(), &BOB as &StaticTerm);
+}
+
+#[test]
+fn dedup_merge_quads() {
+    let d = make_dataset();
+    let mut e = Vec::<([BoxTerm; 3], Option<BoxTerm>)>::new();
+    d.quads()
+        .merge_quads(d.quads())
+        .dedup_quads::<BoxTerm>()
+        .add_to_dataset(&mut e)
+        .unwrap();
+    assert_eq!(e.len(), d.len());
+}
+
+#[test]
+fn split_by_graph() {
+    let d = make_dataset();
+    let graphs = d
+        .quads()
+        .split_by_graph::<BoxTerm, Vec<[BoxTerm; 3]>>()
+        .unwrap();
+    assert_eq!(graphs.len(), 2);
+    assert_eq!(graphs[&Some(ALICE.copied())].len(), 3);
+    assert_eq!(graphs[&Some(BOB.copied())].len(), 2);
+}
+
+#[test]
+fn feed_quads_batched() {
+    let d = make_dataset();
+    let mut e = Vec::<([BoxTerm; 3], Option<BoxTerm>)>::new();
+    d.quads()
+        .feed_quads_batched::<_, BoxTerm>(&mut &mut e, 3)
+        .unwrap();
+    assert_eq!(e.len(), d.len());
+    assert_eq!(e[4].g().unwrap(), &ALICE as &StaticTerm);
+}
+
+#[test]
+fn tee_quads() {
+    let d = make_dataset();
+    let mut e1 = Vec::<([BoxTerm; 3], Option<BoxTerm>)>::new();
+    let mut e2 = Vec::<([BoxTerm; 3], Option<BoxTerm>)>::new();
+    d.quads()
+        .tee_quads(&mut e1)
+        .add_to_dataset(&mut e2)
+        .unwrap();
+    assert_eq!(e1.len(), d.len());
+    assert_eq!(e2.len(), d.len());
+    assert_eq!(e1[4].g().unwrap(), &ALICE as &StaticTerm);
+}
@@ -3,8 +3,8 @@
 //! See [`triple::streaming_mode`](../../triple/streaming_mode/index.html)
 //! for more detail.
 
-use std::marker::PhantomData;
-use std::ptr::NonNull;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
 
 use crate::quad::Quad;
 use crate::term::TTerm;
@@ -137,7 +137,7 @@ where
 macro_rules! make_scoped_quad_streaming_mode {
     ($mode: ident, $qt: ident) => {
         #[derive(Debug)]
-        pub struct $mode(std::marker::PhantomData<$qt<'static>>);
+        pub struct $mode(core::marker::PhantomData<$qt<'static>>);
         impl $crate::quad::streaming_mode::QuadStreamingMode for $mode {
             type UnsafeQuad = $qt<'static>;
         }
@@ -146,7 +146,7 @@ macro_rules! make_scoped_quad_streaming_mode {
             type SourceQuad = $qt<'a>;
             fn scoped(quad: $qt<'a>) -> $crate::quad::streaming_mode::StreamedQuad<'a, $mode> {
                 unsafe {
-                    $crate::quad::streaming_mode::StreamedQuad::wrap(std::mem::transmute(quad))
+                    $crate::quad::streaming_mode::StreamedQuad::wrap(core::mem::transmute(quad))
                 }
             }
         }
@@ -165,6 +165,7 @@ pub trait ScopedQuadMode<'a>: QuadStreamingMode + Sized {
 
 // adapter
 
+#[cfg(feature = "std")]
 pub(crate) use crate::triple::streaming_mode::FromTriple;
 
 /// See [module](./index.html) documentation.
@@ -178,6 +179,7 @@ impl<'a, Q> crate::triple::streaming_mode::StreamedTriple<'a, FromQuad<Q>>
 where
     Q: QuadStreamingMode,
 {
+    #[cfg(feature = "std")]
     pub(crate) fn from_quad(quad: StreamedQuad<Q>) -> Self {
         unsafe { Self::wrap(UnsafeTripleAdapter(quad.wrapped)) }
     }
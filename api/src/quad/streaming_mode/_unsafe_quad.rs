@@ -1,6 +1,6 @@
 // this module is privatedly used by its parent `streaming_mode`
 
-use std::ptr::NonNull;
+use core::ptr::NonNull;
 
 use crate::quad::Quad;
 use crate::term::TTerm;
@@ -10,11 +10,30 @@
 //!
 //! Note however that this API does not cover the creation of serializers,
 //! and therefore does not cover how their target is specified.
+//!
+//! When the target is slow (e.g. a network connection),
+//! [`flush`](trait.TripleSerializer.html#method.flush) gives control over
+//! when the serialized data is actually sent,
+//! and [`serialize_triples_chunked`](trait.TripleSerializer.html#method.serialize_triples_chunked)
+//! flushes the target regularly while serializing a large source.
+//!
+//! Code that must handle both graphs and datasets
+//! can rely on the [`RdfSerializer`] trait,
+//! implemented by every [`QuadSerializer`],
+//! and by triple serializers wrapped in a [`DefaultGraphSerializer`]
+//! (which only serializes the default graph of datasets).
+//!
+//! [`RdfSerializer`]: trait.RdfSerializer.html
+//! [`QuadSerializer`]: trait.QuadSerializer.html
+//! [`DefaultGraphSerializer`]: struct.DefaultGraphSerializer.html
 
 use crate::dataset::*;
 use crate::graph::*;
 use crate::quad::stream::*;
+use crate::quad::streaming_mode::StreamedQuad;
 use crate::triple::stream::*;
+use crate::triple::streaming_mode::StreamedTriple;
+use std::error::Error;
 
 /// A triple serializer writes triples according to a given format.
 pub trait TripleSerializer {
@@ -46,6 +65,52 @@ pub trait TripleSerializer {
     {
         self.serialize_triples(&mut graph.triples())
     }
+
+    /// Serialize all triples from the given [`TripleSource`],
+    /// [flushing](#method.flush) the target every `chunk_size` triples.
+    ///
+    /// As sources produce their triples in batches
+    /// (see [`try_for_some_triple`]),
+    /// each chunk may actually contain a few more than `chunk_size` triples.
+    ///
+    /// [`TripleSource`]: ../triple/stream/trait.TripleSource.html
+    /// [`try_for_some_triple`]: ../triple/stream/trait.TripleSource.html#tymethod.try_for_some_triple
+    fn serialize_triples_chunked<TS>(
+        &mut self,
+        mut source: TS,
+        chunk_size: usize,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+        Self: Sized,
+    {
+        let mut exhausted = false;
+        while !exhausted {
+            self.serialize_triples(TripleChunk {
+                source: &mut source,
+                remaining: chunk_size.max(1),
+                exhausted: &mut exhausted,
+            })?;
+            self.flush().map_err(SinkError)?;
+        }
+        Ok(self)
+    }
+
+    /// Write any data buffered by this serializer to its target,
+    /// and flush the target itself.
+    ///
+    /// The default implementation does nothing,
+    /// which is appropriate for serializers without an I/O target.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// The number of bytes written to the target so far, if this serializer keeps track of it.
+    ///
+    /// The default implementation returns `None`.
+    fn bytes_written(&self) -> Option<u64> {
+        None
+    }
 }
 
 /// A quad serializer writes quads according to a given format.
@@ -81,6 +146,206 @@ pub trait QuadSerializer {
     {
         self.serialize_quads(&mut dataset.quads())
     }
+
+    /// Serialize all quads from the given [`QuadSource`],
+    /// [flushing](#method.flush) the target every `chunk_size` quads.
+    ///
+    /// As sources produce their quads in batches
+    /// (see [`try_for_some_quad`]),
+    /// each chunk may actually contain a few more than `chunk_size` quads.
+    ///
+    /// [`QuadSource`]: ../quad/stream/trait.QuadSource.html
+    /// [`try_for_some_quad`]: ../quad/stream/trait.QuadSource.html#tymethod.try_for_some_quad
+    fn serialize_quads_chunked<QS>(
+        &mut self,
+        mut source: QS,
+        chunk_size: usize,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+        Self: Sized,
+    {
+        let mut exhausted = false;
+        while !exhausted {
+            self.serialize_quads(QuadChunk {
+                source: &mut source,
+                remaining: chunk_size.max(1),
+                exhausted: &mut exhausted,
+            })?;
+            self.flush().map_err(SinkError)?;
+        }
+        Ok(self)
+    }
+
+    /// Write any data buffered by this serializer to its target,
+    /// and flush the target itself.
+    ///
+    /// The default implementation does nothing,
+    /// which is appropriate for serializers without an I/O target.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// The number of bytes written to the target so far, if this serializer keeps track of it.
+    ///
+    /// The default implementation returns `None`.
+    fn bytes_written(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A serializer accepting graphs as well as datasets,
+/// so that code handling "either a graph or a dataset" needs a single code path.
+///
+/// It is implemented by all [`QuadSerializer`]s
+/// (graphs being serialized as their default graph),
+/// and by [`DefaultGraphSerializer`], which lifts a [`TripleSerializer`].
+///
+/// [`QuadSerializer`]: trait.QuadSerializer.html
+/// [`TripleSerializer`]: trait.TripleSerializer.html
+/// [`DefaultGraphSerializer`]: struct.DefaultGraphSerializer.html
+pub trait RdfSerializer {
+    type Error: 'static + std::error::Error;
+
+    /// Serialize a whole [`Graph`](../graph/trait.Graph.html).
+    fn write_graph<G>(&mut self, graph: &G) -> StreamResult<(), G::Error, Self::Error>
+    where
+        G: Graph;
+
+    /// Serialize a whole [`Dataset`](../dataset/trait.Dataset.html).
+    fn write_dataset<D>(&mut self, dataset: &D) -> StreamResult<(), D::Error, Self::Error>
+    where
+        D: Dataset;
+
+    /// Write any data buffered by this serializer to its target,
+    /// and flush the target itself.
+    fn flush_target(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<S> RdfSerializer for S
+where
+    S: QuadSerializer,
+{
+    type Error = S::Error;
+
+    fn write_graph<G>(&mut self, graph: &G) -> StreamResult<(), G::Error, Self::Error>
+    where
+        G: Graph,
+    {
+        self.serialize_dataset(&graph.as_dataset()).map(|_| ())
+    }
+
+    fn write_dataset<D>(&mut self, dataset: &D) -> StreamResult<(), D::Error, Self::Error>
+    where
+        D: Dataset,
+    {
+        self.serialize_dataset(dataset).map(|_| ())
+    }
+
+    fn flush_target(&mut self) -> Result<(), Self::Error> {
+        self.flush()
+    }
+}
+
+/// Lifts a [`TripleSerializer`] to an [`RdfSerializer`],
+/// serializing only the default graph of datasets
+/// (named graphs are silently ignored).
+///
+/// [`TripleSerializer`]: trait.TripleSerializer.html
+/// [`RdfSerializer`]: trait.RdfSerializer.html
+#[derive(Clone, Debug, Default)]
+pub struct DefaultGraphSerializer<S>(pub S);
+
+impl<S> DefaultGraphSerializer<S> {
+    /// Unwrap the underlying triple serializer.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S> RdfSerializer for DefaultGraphSerializer<S>
+where
+    S: TripleSerializer,
+{
+    type Error = S::Error;
+
+    fn write_graph<G>(&mut self, graph: &G) -> StreamResult<(), G::Error, Self::Error>
+    where
+        G: Graph,
+    {
+        self.0.serialize_graph(graph).map(|_| ())
+    }
+
+    fn write_dataset<D>(&mut self, dataset: &D) -> StreamResult<(), D::Error, Self::Error>
+    where
+        D: Dataset,
+    {
+        self.0
+            .serialize_graph(&dataset.graph(None as Option<&DTerm<D>>))
+            .map(|_| ())
+    }
+
+    fn flush_target(&mut self) -> Result<(), Self::Error> {
+        self.0.flush()
+    }
+}
+
+/// A source yielding the next (roughly) `remaining` triples of another source,
+/// without consuming it further.
+struct TripleChunk<'a, S> {
+    source: &'a mut S,
+    remaining: usize,
+    exhausted: &'a mut bool,
+}
+
+impl<'a, S: TripleSource> TripleSource for TripleChunk<'a, S> {
+    type Error = S::Error;
+    type Triple = S::Triple;
+    fn try_for_some_triple<F, E>(&mut self, f: &mut F) -> StreamResult<bool, Self::Error, E>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let remaining = &mut self.remaining;
+        let more = self.source.try_for_some_triple(&mut |t| {
+            *remaining = remaining.saturating_sub(1);
+            f(t)
+        })?;
+        *self.exhausted = !more;
+        Ok(more && self.remaining > 0)
+    }
+}
+
+/// A source yielding the next (roughly) `remaining` quads of another source,
+/// without consuming it further.
+struct QuadChunk<'a, S> {
+    source: &'a mut S,
+    remaining: usize,
+    exhausted: &'a mut bool,
+}
+
+impl<'a, S: QuadSource> QuadSource for QuadChunk<'a, S> {
+    type Error = S::Error;
+    type Quad = S::Quad;
+    fn try_for_some_quad<F, E>(&mut self, f: &mut F) -> StreamResult<bool, Self::Error, E>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let remaining = &mut self.remaining;
+        let more = self.source.try_for_some_quad(&mut |q| {
+            *remaining = remaining.saturating_sub(1);
+            f(q)
+        })?;
+        *self.exhausted = !more;
+        Ok(more && self.remaining > 0)
+    }
 }
 
 /// A stringifier is special kind of [`TripleSerializer`] or [`QuadSerializer`]:
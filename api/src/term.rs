@@ -11,15 +11,18 @@
 //! [Linked Data]: http://linkeddata.org/
 //! [generalized RDF]: https://docs.rs/sophia/latest/sophia/#generalized-vs-strict-rdf-model
 
-use mownstr::MownStr;
-use std::cmp::Ordering;
-use std::error::Error;
-use std::hash::{Hash, Hasher};
+use crate::MownStr;
+use alloc::format;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::error::Error;
+use core::hash::{Hash, Hasher};
 
 mod _dyn_term;
 mod _graph_name_matcher; // is 'pub use'd by module 'matcher'
 mod _raw_value;
 pub use self::_raw_value::*;
+pub mod binding;
 pub mod matcher;
 pub mod simple_iri;
 pub use simple_iri::SimpleIri;
@@ -91,8 +94,9 @@ pub trait TTerm {
     /// Return the datatype IRI of this term if it is a literal.
     ///
     /// NB: *all* literals have a datatype,
-    /// even simple literals (whose implicit type is `xsd:string`)
-    /// and language tagged strings (whose implicit type is `rdf:langString`).
+    /// even simple literals (whose implicit type is `xsd:string`),
+    /// language tagged strings (whose implicit type is `rdf:langString`)
+    /// and directional language tagged strings (whose implicit type is `rdf:dirLangString`).
     fn datatype(&self) -> Option<SimpleIri> {
         None
     }
@@ -107,6 +111,19 @@ pub trait TTerm {
         None
     }
 
+    /// Return the base direction of this term
+    /// if it is a [directional language-tagged literal](https://www.w3.org/TR/rdf12-concepts/#section-text-direction)
+    /// (introduced in RDF 1.2).
+    ///
+    /// # Note to implementors
+    /// The default implementation always return `None`,
+    /// so unless your type may represent a directional language-tagged literal,
+    /// you do not need to override it.
+    /// If it returns `Some(_)`, [`language`](#method.language) must also return `Some(_)`.
+    fn base_direction(&self) -> Option<BaseDirection> {
+        None
+    }
+
     /// Return the "value" of this term, possibly split in two substrings.
     /// The second part might only be non-empty if this term is an IRI reference.
     ///
@@ -162,6 +179,40 @@ pub enum TermKind {
 }
 use TermKind::*;
 
+/// The base direction of a [directional language-tagged literal](https://www.w3.org/TR/rdf12-concepts/#section-text-direction).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum BaseDirection {
+    /// Left-to-right
+    Ltr,
+    /// Right-to-left
+    Rtl,
+}
+
+impl BaseDirection {
+    /// Parse a base direction (`"ltr"` or `"rtl"`).
+    pub fn parse(txt: &str) -> Option<BaseDirection> {
+        match txt {
+            "ltr" => Some(BaseDirection::Ltr),
+            "rtl" => Some(BaseDirection::Rtl),
+            _ => None,
+        }
+    }
+
+    /// The textual representation of this base direction (`"ltr"` or `"rtl"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BaseDirection::Ltr => "ltr",
+            BaseDirection::Rtl => "rtl",
+        }
+    }
+}
+
+impl core::fmt::Display for BaseDirection {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// A type that can copy any term.
 pub trait CopyTerm: Sized {
     /// Copy `term` into an instance of this type.
@@ -224,6 +275,7 @@ where
                     for b in tag.bytes() {
                         state.write_u8(b.to_ascii_uppercase());
                     }
+                    term.base_direction().hash(state);
                 }
             };
             v.hash(state);
@@ -250,10 +302,13 @@ where
         if matches!(k1, Iri) {
             v1 == v2
         } else {
-            v1.0 == v2.0 && {
+            str_eq(v1.0, v2.0) && {
                 if matches!(k1, Literal) {
                     match (t1.language(), t2.language()) {
-                        (Some(tag1), Some(tag2)) => tag1.eq_ignore_ascii_case(tag2),
+                        (Some(tag1), Some(tag2)) => {
+                            tag1.eq_ignore_ascii_case(tag2)
+                                && t1.base_direction() == t2.base_direction()
+                        }
                         (None, None) => {
                             let dt1 = t1.datatype().unwrap();
                             let dt2 = t2.datatype().unwrap();
@@ -273,7 +328,7 @@ where
 /// * IRIs < literals < blank nodes < variables
 /// * IRIs, blank nodes and variables are ordered by their value
 /// * Literals are ordered by their datatype, then their language (if any),
-///   then their lexical value
+///   then their base direction (if any), then their lexical value
 ///
 /// NB: literals are ordered by their *lexical* value,
 /// so for example, `"10"^^xsd:integer` come `*before* "2"^^xsd:integer`.
@@ -296,6 +351,7 @@ where
                 if let (Some(tag1), Some(tag2)) = (tag1, tag2) {
                     tag1.to_uppercase()
                         .cmp(&tag2.to_uppercase())
+                        .then_with(|| t1.base_direction().cmp(&t2.base_direction()))
                         .then_with(|| v1.0.cmp(&v2.0))
                 } else {
                     let dt1 = t1.datatype().unwrap();
@@ -311,11 +367,12 @@ where
     })
 }
 
-/// Format the given term in a Turtle-like format.
-pub fn term_format<T, W>(term: &T, w: &mut W) -> std::fmt::Result
+/// Format the given term in the N-Triples format
+/// (except for variables, which are written `_?name`).
+pub fn term_format<T, W>(term: &T, w: &mut W) -> core::fmt::Result
 where
     T: TTerm + ?Sized,
-    W: std::fmt::Write,
+    W: core::fmt::Write,
 {
     let v = term.value_raw();
     match term.kind() {
@@ -328,9 +385,15 @@ where
             w.write_char('>')
         }
         Literal => {
-            write!(w, "{:?}", v.0)?;
+            w.write_char('"')?;
+            format_quoted(v.0, w)?;
+            w.write_char('"')?;
             if let Some(tag) = term.language() {
-                write!(w, "@{}", tag)
+                write!(w, "@{}", tag)?;
+                if let Some(dir) = term.base_direction() {
+                    write!(w, "--{}", dir)?;
+                }
+                Ok(())
             } else {
                 let dt = term.datatype().unwrap();
                 if !term_eq(&dt, &crate::ns::xsd::string) {
@@ -345,6 +408,27 @@ where
     }
 }
 
+/// Write `txt` in `w`, escaping the characters that are not allowed in N-Triples strings.
+pub(crate) fn format_quoted<W>(txt: &str, w: &mut W) -> core::fmt::Result
+where
+    W: core::fmt::Write,
+{
+    let mut start = 0;
+    for (pos, chr) in txt.char_indices() {
+        let escaped = match chr {
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            _ => continue,
+        };
+        w.write_str(&txt[start..pos])?;
+        w.write_str(escaped)?;
+        start = pos + 1;
+    }
+    w.write_str(&txt[start..])
+}
+
 /// Formats the given term in to a string.
 pub fn term_to_string<T>(term: &T) -> String
 where
@@ -369,11 +453,11 @@ where
 
 struct TermFormater<'a, T: ?Sized>(&'a T);
 
-impl<'a, T> std::fmt::Display for TermFormater<'a, T>
+impl<'a, T> core::fmt::Display for TermFormater<'a, T>
 where
     T: TTerm + ?Sized,
 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         term_format(self.0, fmt)
     }
 }
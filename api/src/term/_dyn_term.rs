@@ -1,7 +1,7 @@
 //! This module is transparently re-exported by its parent `lib`.
-use std::cmp::Ordering;
-use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::hash::{Hash, Hasher};
+use core::cmp::Ordering;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::hash::{Hash, Hasher};
 
 use super::*;
 
@@ -2,6 +2,7 @@
 
 use crate::term::matcher::{AnyOrExactly, AnyOrExactlyRef, AnyTerm};
 use crate::term::*;
+use alloc::vec::Vec;
 
 /// Generic trait for matching graph names, *i.e.* optional [term]s.
 ///
@@ -24,6 +25,14 @@ pub trait GraphNameMatcher {
     fn matches<T>(&self, g: Option<&T>) -> bool
     where
         T: TTerm + ?Sized;
+
+    /// Return `true` if this matcher is known to match any graph name.
+    ///
+    /// The default implementation returns `false`,
+    /// which is always correct (if suboptimal).
+    fn is_any(&self) -> bool {
+        false
+    }
 }
 
 impl GraphNameMatcher for AnyTerm {
@@ -39,6 +48,9 @@ impl GraphNameMatcher for AnyTerm {
     {
         true
     }
+    fn is_any(&self) -> bool {
+        true
+    }
 }
 
 impl<U> GraphNameMatcher for AnyOrExactly<Option<U>>
@@ -61,6 +73,9 @@ where
             AnyOrExactly::Exactly(gself) => same_graph_name(gself.as_ref(), g),
         }
     }
+    fn is_any(&self) -> bool {
+        matches!(self, AnyOrExactly::Any)
+    }
 }
 
 impl<'a, U> GraphNameMatcher for AnyOrExactlyRef<Option<&'a U>>
@@ -83,6 +98,9 @@ where
             AnyOrExactlyRef::Exactly(gself) => same_graph_name(*gself, g),
         }
     }
+    fn is_any(&self) -> bool {
+        matches!(self, AnyOrExactlyRef::Any)
+    }
 }
 
 impl<U> GraphNameMatcher for Option<&U>
@@ -1,7 +1,8 @@
 //! Implementation of the so-called [`RawValue`](./struct.RawValue.html).
 
-use mownstr::MownStr;
-use std::hash::{Hash, Hasher};
+use crate::MownStr;
+use alloc::string::String;
+use core::hash::{Hash, Hasher};
 
 /// A raw value is a string possibly split into two parts.
 ///
@@ -56,9 +57,9 @@ impl<'a> RawValue<'a> {
     }
     pub fn slice<R>(&self, range: R) -> MownStr<'a>
     where
-        R: std::ops::RangeBounds<usize> + std::slice::SliceIndex<str, Output = str>,
+        R: core::ops::RangeBounds<usize> + core::slice::SliceIndex<str, Output = str>,
     {
-        use std::ops::Bound::*;
+        use core::ops::Bound::*;
         match self.1 {
             None => self.0[range].into(),
             Some(suffix) if self.0.is_empty() => suffix[range].into(),
@@ -123,12 +124,34 @@ impl<'a> PartialEq for RawValue<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.len() == other.len()
             && match (self, other) {
-                (RawValue(s, None), RawValue(o, None)) => s == o,
+                (RawValue(s, None), RawValue(o, None)) => str_eq(s, o),
+                (RawValue(s1, Some(s2)), RawValue(o1, Some(o2)))
+                    if same_str(s1, o1) && same_str(s2, o2) =>
+                {
+                    true
+                }
                 _ => self.bytes().zip(other.bytes()).all(|(s, o)| s == o),
             }
     }
 }
 
+/// Whether `s1` and `s2` are the very same slice of memory.
+///
+/// This is typically the case of the data of terms
+/// produced by the same [term factory](https://docs.rs/sophia_term/latest/sophia_term/factory/),
+/// or by the same graph.
+#[inline]
+fn same_str(s1: &str, s2: &str) -> bool {
+    core::ptr::eq(s1, s2)
+}
+
+/// Compare two strings, skipping the comparison of their content
+/// if they are the same slice of memory.
+#[inline]
+pub(crate) fn str_eq(s1: &str, s2: &str) -> bool {
+    same_str(s1, s2) || s1 == s2
+}
+
 impl<'a> Hash for RawValue<'a> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         state.write(self.0.as_bytes());
@@ -209,6 +232,19 @@ mod test {
         RawValue(ns1, sf1) == RawValue(ns2, sf2)
     }
 
+    #[test]
+    fn eq_same_memory() {
+        let txt = String::from("http://example.org/hello");
+        let (ns, sf) = txt.split_at(19);
+        assert_eq!(RawValue(&txt, None), RawValue(&txt, None));
+        assert_eq!(RawValue(ns, Some(sf)), RawValue(ns, Some(sf)));
+        assert_eq!(RawValue(ns, Some(sf)), RawValue(&txt, None));
+        assert_ne!(
+            RawValue(&txt[1..], None),
+            RawValue(&txt[..txt.len() - 1], None)
+        );
+    }
+
     #[test_case("hello", None, "hell" => true ; "only ns")]
     #[test_case("hello", None, "heaven" => false ; "not starts with")]
     #[test_case("hel", Some("lo"), "hell" => true ; "mixed")]
@@ -250,7 +286,7 @@ mod test {
     #[test_case("hel", Some("lo"), 1..=3 => "ell" ; "across cut inclusive")]
     fn slice<'a, R>(ns: &'a str, sf: Option<&'a str>, range: R) -> MownStr<'a>
     where
-        R: std::ops::RangeBounds<usize> + std::slice::SliceIndex<str, Output = str>,
+        R: core::ops::RangeBounds<usize> + core::slice::SliceIndex<str, Output = str>,
     {
         let raw = RawValue(ns, sf);
         raw.slice(range)
@@ -0,0 +1,326 @@
+//! Variable bindings, substitution and unification.
+//!
+//! A [`Binding`] maps [variables] to terms.
+//! It can be used to [substitute](struct.Binding.html#method.substitute_triple)
+//! the variables of a triple (or of a graph pattern),
+//! and it is produced by the [unification](fn.unify_triples.html) of two triples.
+//!
+//! ```
+//! # use sophia_api::term::binding::{unify_triples, Binding};
+//! # use sophia_api::term::{CopyTerm, TTerm};
+//! # use sophia_api::triple::Triple;
+//! /// If `triple` matches `pattern`, instantiate `template` accordingly.
+//! fn instantiate<T, TR>(pattern: &TR, triple: &TR, template: &TR) -> Option<[T; 3]>
+//! where
+//!     T: TTerm + CopyTerm + Clone,
+//!     TR: Triple,
+//! {
+//!     let binding: Binding<T> = unify_triples(pattern, triple)?;
+//!     Some(binding.substitute_triple(template))
+//! }
+//! ```
+//!
+//! [`Binding`]: struct.Binding.html
+//! [variables]: ../enum.TermKind.html#variant.Variable
+
+use super::*;
+use crate::quad::Quad;
+use crate::triple::Triple;
+use alloc::collections::btree_map::{BTreeMap, Iter};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// A mapping from variable names to terms.
+///
+/// A variable can be bound to another variable;
+/// [`get`](#method.get) and the substitution methods follow such chains of variables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Binding<T> {
+    map: BTreeMap<String, T>,
+}
+
+impl<T> Default for Binding<T> {
+    fn default() -> Self {
+        Binding {
+            map: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> Binding<T>
+where
+    T: TTerm + CopyTerm + Clone,
+{
+    /// Build an empty binding.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of bound variables.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether no variable is bound.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over the variable names and the terms they are (directly) bound to.
+    pub fn iter(&self) -> Iter<'_, String, T> {
+        self.map.iter()
+    }
+
+    /// Whether the variable with the given name is bound.
+    pub fn contains(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+
+    /// The term bound to the variable with the given name, if any.
+    ///
+    /// If the variable is bound to another bound variable,
+    /// the term bound to the latter is returned instead (and so on).
+    pub fn get(&self, name: &str) -> Option<&T> {
+        let mut found = self.map.get(name)?;
+        // bounded, in case the user inserted a cycle of variables
+        for _ in 0..self.map.len() {
+            if found.kind() != TermKind::Variable {
+                break;
+            }
+            match self.map.get(&found.value()[..]) {
+                Some(next) => found = next,
+                None => break,
+            }
+        }
+        Some(found)
+    }
+
+    /// Bind the variable with the given name to `term`,
+    /// returning the term it was previously (directly) bound to, if any.
+    pub fn insert<U>(&mut self, name: &str, term: &U) -> Option<T>
+    where
+        U: TTerm + ?Sized,
+    {
+        self.map.insert(name.to_string(), T::copy(term))
+    }
+
+    /// Unbind the variable with the given name,
+    /// returning the term it was (directly) bound to, if any.
+    pub fn remove(&mut self, name: &str) -> Option<T> {
+        self.map.remove(name)
+    }
+
+    /// Merge this binding with `other`.
+    ///
+    /// Return `None` if both bindings bind the same variable to different terms.
+    pub fn merge(&self, other: &Binding<T>) -> Option<Binding<T>> {
+        let mut merged = self.clone();
+        for (name, term) in other.iter() {
+            let var = T::copy(&Variable(name));
+            if !merged.unify_terms(&var, term) {
+                return None;
+            }
+        }
+        Some(merged)
+    }
+
+    /// Replace `term` by the term it is bound to, if `term` is a bound variable.
+    pub fn substitute<U>(&self, term: &U) -> T
+    where
+        U: TTerm + ?Sized,
+    {
+        if term.kind() == TermKind::Variable {
+            if let Some(bound) = self.get(&term.value()) {
+                return bound.clone();
+            }
+        }
+        T::copy(term)
+    }
+
+    /// Replace the bound variables of `triple` by the terms they are bound to.
+    pub fn substitute_triple<TR>(&self, triple: &TR) -> [T; 3]
+    where
+        TR: Triple + ?Sized,
+    {
+        [
+            self.substitute(triple.s()),
+            self.substitute(triple.p()),
+            self.substitute(triple.o()),
+        ]
+    }
+
+    /// Replace the bound variables of `quad` by the terms they are bound to.
+    pub fn substitute_quad<Q>(&self, quad: &Q) -> ([T; 3], Option<T>)
+    where
+        Q: Quad + ?Sized,
+    {
+        (
+            [
+                self.substitute(quad.s()),
+                self.substitute(quad.p()),
+                self.substitute(quad.o()),
+            ],
+            quad.g().map(|g| self.substitute(g)),
+        )
+    }
+
+    /// Replace the bound variables of a graph pattern (a sequence of triples)
+    /// by the terms they are bound to.
+    pub fn substitute_pattern<'a, I, TR>(&self, pattern: I) -> Vec<[T; 3]>
+    where
+        I: IntoIterator<Item = &'a TR>,
+        TR: Triple + 'a,
+    {
+        pattern
+            .into_iter()
+            .map(|t| self.substitute_triple(t))
+            .collect()
+    }
+
+    /// Extend this binding so that `a` and `b` become equal after substitution.
+    ///
+    /// Return `None` if this is not possible.
+    pub fn unify<TA, TB>(&self, a: &TA, b: &TB) -> Option<Binding<T>>
+    where
+        TA: Triple + ?Sized,
+        TB: Triple + ?Sized,
+    {
+        let mut binding = self.clone();
+        if binding.unify_terms(a.s(), b.s())
+            && binding.unify_terms(a.p(), b.p())
+            && binding.unify_terms(a.o(), b.o())
+        {
+            Some(binding)
+        } else {
+            None
+        }
+    }
+
+    /// Extend this binding so that `a` and `b` become equal after substitution.
+    ///
+    /// NB: in case of failure, this binding may have been partially modified.
+    fn unify_terms<A, B>(&mut self, a: &A, b: &B) -> bool
+    where
+        A: TTerm + ?Sized,
+        B: TTerm + ?Sized,
+    {
+        let a = self.substitute(a);
+        let b = self.substitute(b);
+        if term_eq(&a, &b) {
+            true
+        } else if a.kind() == TermKind::Variable {
+            self.map.insert(a.value().to_string(), b);
+            true
+        } else if b.kind() == TermKind::Variable {
+            self.map.insert(b.value().to_string(), a);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Compute the most general binding making `a` and `b` equal after substitution,
+/// or `None` if they can not be unified.
+///
+/// Variables may occur in both triples.
+pub fn unify_triples<T, TA, TB>(a: &TA, b: &TB) -> Option<Binding<T>>
+where
+    T: TTerm + CopyTerm + Clone,
+    TA: Triple + ?Sized,
+    TB: Triple + ?Sized,
+{
+    Binding::new().unify(a, b)
+}
+
+/// A minimal variable, used internally to merge bindings.
+struct Variable<'a>(&'a str);
+
+impl<'a> TTerm for Variable<'a> {
+    fn kind(&self) -> TermKind {
+        TermKind::Variable
+    }
+    fn value_raw(&self) -> RawValue<'_> {
+        self.0.into()
+    }
+    fn as_dyn(&self) -> &dyn TTerm {
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::term::test::TestTerm;
+
+    type T = TestTerm<Box<str>>;
+
+    #[test]
+    fn unify_ground() {
+        let a = [T::iri("tag:a"), T::iri("tag:b"), T::iri("tag:c")];
+        let b = [T::iri("tag:a"), T::iri("tag:b"), T::iri("tag:d")];
+        assert!(unify_triples::<T, _, _>(&a, &a).unwrap().is_empty());
+        assert!(unify_triples::<T, _, _>(&a, &b).is_none());
+    }
+
+    #[test]
+    fn unify_repeated_variable() {
+        let pattern = [T::var("x"), T::iri("tag:b"), T::var("x")];
+        let ok = [T::iri("tag:a"), T::iri("tag:b"), T::iri("tag:a")];
+        let ko = [T::iri("tag:a"), T::iri("tag:b"), T::iri("tag:c")];
+        let binding = unify_triples::<T, _, _>(&pattern, &ok).unwrap();
+        assert_eq!(binding.len(), 1);
+        assert!(term_eq(binding.get("x").unwrap(), &T::iri("tag:a")));
+        assert!(unify_triples::<T, _, _>(&pattern, &ko).is_none());
+    }
+
+    #[test]
+    fn unify_variables_on_both_sides() {
+        let a = [T::var("x"), T::iri("tag:b"), T::lit_lang("c", "en")];
+        let b = [T::var("y"), T::iri("tag:b"), T::var("x")];
+        let binding = unify_triples::<T, _, _>(&a, &b).unwrap();
+        let expected = [
+            T::lit_lang("c", "en"),
+            T::iri("tag:b"),
+            T::lit_lang("c", "en"),
+        ];
+        for (x, y) in binding.substitute_triple(&a).iter().zip(&expected) {
+            assert!(term_eq(x, y));
+        }
+        for (x, y) in binding.substitute_triple(&b).iter().zip(&expected) {
+            assert!(term_eq(x, y));
+        }
+    }
+
+    #[test]
+    fn substitute_unbound() {
+        let mut binding = Binding::<T>::new();
+        binding.insert("x", &T::iri("tag:a"));
+        let quad = (
+            [T::var("x"), T::iri("tag:b"), T::var("y")],
+            Some(T::var("x")),
+        );
+        let (spo, g) = binding.substitute_quad(&quad);
+        assert!(term_eq(&spo[0], &T::iri("tag:a")));
+        assert!(term_eq(&spo[2], &T::var("y")));
+        assert!(term_eq(&g.unwrap(), &T::iri("tag:a")));
+        let pattern = binding.substitute_pattern(&[quad.0]);
+        assert_eq!(pattern.len(), 1);
+    }
+
+    #[test]
+    fn merge() {
+        let mut b1 = Binding::<T>::new();
+        b1.insert("x", &T::iri("tag:a"));
+        let mut b2 = Binding::<T>::new();
+        b2.insert("y", &T::iri("tag:b"));
+        let merged = b1.merge(&b2).unwrap();
+        assert_eq!(merged.len(), 2);
+        b2.insert("x", &T::iri("tag:c"));
+        assert!(b1.merge(&b2).is_none());
+    }
+}
@@ -15,6 +15,8 @@
 //!
 
 use super::*;
+use alloc::vec;
+use alloc::vec::Vec;
 
 pub use super::_graph_name_matcher::*;
 
@@ -28,10 +30,30 @@ pub trait TermMatcher {
     /// If this matcher matches only one term, return this term, else `None`.
     fn constant(&self) -> Option<&Self::Term>;
 
+    /// If this matcher matches a known, finite set of terms, return them, else `None`.
+    ///
+    /// Indexed graphs use this to look up each candidate in their indexes,
+    /// rather than filtering all their triples,
+    /// for matchers that are not [constant](#tymethod.constant).
+    /// Implementations must therefore return *exactly* the terms matched by this matcher.
+    ///
+    /// The default implementation returns the [constant](#tymethod.constant) term, if any.
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        self.constant().map(|t| vec![t.as_dyn()])
+    }
+
     /// Check whether this matcher matches `t`.
     fn matches<T>(&self, t: &T) -> bool
     where
         T: TTerm + ?Sized;
+
+    /// Return `true` if this matcher is known to match any term.
+    ///
+    /// The default implementation returns `false`,
+    /// which is always correct (if suboptimal).
+    fn is_any(&self) -> bool {
+        false
+    }
 }
 
 /// A universal matcher: it matches any term or graph name (even the default graph).
@@ -53,6 +75,9 @@ impl TermMatcher for AnyTerm {
     {
         true
     }
+    fn is_any(&self) -> bool {
+        true
+    }
 }
 
 /// A matcher matching either any term, or only a specific owned term.
@@ -95,6 +120,9 @@ where
             AnyOrExactly::Exactly(tself) => term_eq(tself, t),
         }
     }
+    fn is_any(&self) -> bool {
+        matches!(self, AnyOrExactly::Any)
+    }
 }
 
 /// A matcher matching either any term, or only a specific borrowed term.
@@ -137,6 +165,9 @@ where
             AnyOrExactlyRef::Exactly(tself) => term_eq(*tself, t),
         }
     }
+    fn is_any(&self) -> bool {
+        matches!(self, AnyOrExactlyRef::Any)
+    }
 }
 
 impl<U> TermMatcher for U
@@ -167,6 +198,9 @@ where
             None
         }
     }
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        Some(self.iter().map(|t| t.as_dyn()).collect())
+    }
     fn matches<T>(&self, t: &T) -> bool
     where
         T: TTerm + ?Sized,
@@ -189,6 +223,9 @@ macro_rules! impl_for_array {
                     None
                 }
             }
+            fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+                Some(self.iter().map(|t| t.as_dyn()).collect())
+            }
             fn matches<T>(&self, t: &T) -> bool
             where
                 T: TTerm + ?Sized,
@@ -222,6 +259,9 @@ where
             None
         }
     }
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        Some(self.iter().map(|t| t.as_dyn()).collect())
+    }
     fn matches<T>(&self, t: &T) -> bool
     where
         T: TTerm + ?Sized,
@@ -248,6 +288,105 @@ where
     }
 }
 
+/// A matcher matching the terms matched by both of its components.
+///
+/// ```
+/// # use sophia_api::term::matcher::{And, Not, TermMatcher};
+/// # use sophia_api::ns::xsd;
+/// let m = And([&xsd::int, &xsd::long], Not(xsd::long));
+/// assert!(m.matches(&xsd::int));
+/// assert!(!m.matches(&xsd::long));
+/// ```
+pub struct And<A, B>(pub A, pub B);
+
+impl<A, B> TermMatcher for And<A, B>
+where
+    A: TermMatcher,
+    B: TermMatcher,
+{
+    type Term = A::Term;
+    fn constant(&self) -> Option<&A::Term> {
+        self.0.constant().filter(|t| self.1.matches(*t))
+    }
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        match (self.0.candidates(), self.1.candidates()) {
+            (Some(mut c), _) => {
+                c.retain(|t| self.1.matches(*t));
+                Some(c)
+            }
+            (None, Some(mut c)) => {
+                c.retain(|t| self.0.matches(*t));
+                Some(c)
+            }
+            (None, None) => None,
+        }
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        self.0.matches(t) && self.1.matches(t)
+    }
+    fn is_any(&self) -> bool {
+        self.0.is_any() && self.1.is_any()
+    }
+}
+
+/// A matcher matching the terms matched by either of its components.
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A, B> TermMatcher for Or<A, B>
+where
+    A: TermMatcher,
+    B: TermMatcher,
+{
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        let mut c = self.0.candidates()?;
+        for t in self.1.candidates()? {
+            if !c.iter().any(|t2| term_eq(*t2, t)) {
+                c.push(t);
+            }
+        }
+        Some(c)
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        self.0.matches(t) || self.1.matches(t)
+    }
+    fn is_any(&self) -> bool {
+        self.0.is_any() || self.1.is_any()
+    }
+}
+
+/// A matcher matching the terms *not* matched by its component.
+pub struct Not<A>(pub A);
+
+impl<A> TermMatcher for Not<A>
+where
+    A: TermMatcher,
+{
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        !self.0.matches(t)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -404,4 +543,34 @@ mod test {
         assert!(TermMatcher::matches(&m, &t1));
         assert!(!TermMatcher::matches(&m, &t2));
     }
+
+    #[test]
+    fn test_combinators() {
+        let t1 = SimpleIri::new("http://example.org/", Some("a")).unwrap();
+        let t2 = SimpleIri::new("http://example.org/", Some("b")).unwrap();
+        let t3 = SimpleIri::new("http://example.org/", Some("c")).unwrap();
+
+        let m = And([&t1, &t2], Not(t2));
+        assert!(m.matches(&t1));
+        assert!(!m.matches(&t2));
+        assert!(!m.matches(&t3));
+        assert!(m.constant().is_none());
+        assert!(!m.is_any());
+
+        let m = And(t1, [&t1, &t2]);
+        assert_eq!(m.constant().unwrap(), &t1);
+        assert!(And(t1, t2).constant().is_none());
+        assert!(And(ANY, ANY).is_any());
+
+        let m = Or(t1, t3);
+        assert!(m.matches(&t1));
+        assert!(!m.matches(&t2));
+        assert!(m.matches(&t3));
+        assert!(m.constant().is_none());
+        assert!(Or(t1, ANY).is_any());
+
+        let m = Not(ANY);
+        assert!(!m.matches(&t1));
+        assert!(!m.is_any());
+    }
 }
@@ -1,10 +1,11 @@
 //! Minimal implementation of [`TTerm`](https://docs.rs/sophia_api/latest/sophia_api/term/trait.TTerm.html),
 //! for representing datatype IRIs of literals.
 use super::*;
+use alloc::format;
+use core::fmt;
+use core::hash;
 use sophia_iri::error::{InvalidIri, Result};
 use sophia_iri::is_valid_suffixed_iri_ref;
-use std::fmt;
-use std::hash;
 
 /// See [module documentation](./index.html)
 #[derive(Clone, Copy, Debug, Eq, Ord)]
@@ -19,7 +20,7 @@ impl<'a> SimpleIri<'a> {
         if is_valid_suffixed_iri_ref(ns, suffix) {
             Ok(Self { ns, suffix })
         } else {
-            Err(InvalidIri(format!("{}{}", ns, suffix.unwrap_or(""))))
+            Err(InvalidIri::new(format!("{}{}", ns, suffix.unwrap_or(""))))
         }
     }
 
@@ -69,7 +70,7 @@ impl<'a, T> PartialOrd<T> for SimpleIri<'a>
 where
     T: TTerm + ?Sized,
 {
-    fn partial_cmp(&self, other: &T) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &T) -> Option<core::cmp::Ordering> {
         Some(term_cmp(self, other))
     }
 }
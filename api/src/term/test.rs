@@ -11,6 +11,7 @@ pub struct TestTerm<T> {
     value: T,
     extra1: Option<T>,
     extra2: Option<T>,
+    dir: Option<BaseDirection>,
 }
 
 impl<'a, T> TestTerm<T>
@@ -23,6 +24,7 @@ where
             value: value.into(),
             extra1: None,
             extra2: None,
+            dir: None,
         }
     }
     pub fn iri2(ns: &'a str, suffix: &'a str) -> Self {
@@ -31,6 +33,7 @@ where
             value: ns.into(),
             extra1: Some(suffix.into()),
             extra2: None,
+            dir: None,
         }
     }
     pub fn bnode(value: &'a str) -> Self {
@@ -39,6 +42,7 @@ where
             value: value.into(),
             extra1: None,
             extra2: None,
+            dir: None,
         }
     }
     pub fn var(value: &'a str) -> Self {
@@ -47,6 +51,7 @@ where
             value: value.into(),
             extra1: None,
             extra2: None,
+            dir: None,
         }
     }
     pub fn lit_dt(value: &'a str, datatype: SimpleIri<'a>) -> Self {
@@ -56,6 +61,7 @@ where
             value: value.into(),
             extra1: Some(extra1.into()),
             extra2: extra2.map(From::from),
+            dir: None,
         }
     }
     pub fn lit_lang(value: &'a str, tag: &'a str) -> Self {
@@ -64,6 +70,16 @@ where
             value: value.into(),
             extra1: None,
             extra2: Some(tag.into()),
+            dir: None,
+        }
+    }
+    pub fn lit_dir_lang(value: &'a str, tag: &'a str, dir: BaseDirection) -> Self {
+        TestTerm {
+            kind: TermKind::Literal,
+            value: value.into(),
+            extra1: None,
+            extra2: Some(tag.into()),
+            dir: Some(dir),
         }
     }
 }
@@ -88,6 +104,7 @@ where
     fn datatype(&self) -> Option<SimpleIri> {
         if self.kind == TermKind::Literal {
             Some(match self.extra1.as_ref() {
+                None if self.dir.is_some() => rdf::dirLangString,
                 None => rdf::langString,
                 Some(ns) => {
                     SimpleIri::new_unchecked(ns.as_ref(), self.extra2.as_ref().map(|s| s.as_ref()))
@@ -104,6 +121,9 @@ where
             None
         }
     }
+    fn base_direction(&self) -> Option<BaseDirection> {
+        self.dir
+    }
     fn as_dyn(&self) -> &dyn TTerm {
         self
     }
@@ -177,7 +197,10 @@ where
             TermKind::Variable => TestTerm::var(raw.0),
             TermKind::Literal => match term.language() {
                 None => TestTerm::lit_dt(raw.0, term.datatype().unwrap()),
-                Some(tag) => TestTerm::lit_lang(raw.0, tag),
+                Some(tag) => match term.base_direction() {
+                    None => TestTerm::lit_lang(raw.0, tag),
+                    Some(dir) => TestTerm::lit_dir_lang(raw.0, tag, dir),
+                },
             },
         }
     }
@@ -13,8 +13,9 @@
 //!
 
 use crate::quad::Quad;
-use crate::term::TTerm;
+use crate::term::{TTerm, TermKind};
 
+#[cfg(feature = "std")]
 pub mod stream;
 pub mod streaming_mode;
 
@@ -51,6 +52,18 @@ pub trait Triple {
     fn components(&self) -> TripleIter<Self> {
         TripleIter(self, 0)
     }
+
+    /// Whether this triple is only valid in the [generalized RDF model],
+    /// i.e. its subject is not an IRI or a blank node,
+    /// its predicate is not an IRI,
+    /// or any of its terms is a variable.
+    ///
+    /// [generalized RDF model]: ../index.html#generalized-vs-strict-rdf-model
+    fn is_generalized(&self) -> bool {
+        !matches!(self.s().kind(), TermKind::Iri | TermKind::BlankNode)
+            || self.p().kind() != TermKind::Iri
+            || self.o().kind() == TermKind::Variable
+    }
 }
 
 /// Iterator over the components of a triple.
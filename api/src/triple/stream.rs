@@ -41,14 +41,19 @@
 //! [`try_for_each_triple`]: ./trait.TripleSource.html#method.try_for_each_triple
 //! [`Iterator::collect`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.collect
 
+use std::collections::HashSet;
 use std::error::Error;
+use std::hash::Hash;
 
 use crate::graph::*;
+use crate::term::{CopyTerm, TTerm};
 use crate::triple::streaming_mode::*;
 use crate::triple::*;
 
 mod _error;
 pub use self::_error::*;
+mod _dedup;
+pub use self::_dedup::*;
 mod _filter;
 pub use self::_filter::*;
 mod _filter_map;
@@ -57,6 +62,16 @@ mod _iterator;
 pub use self::_iterator::*;
 mod _map;
 pub use self::_map::*;
+mod _map_terms;
+pub use self::_map_terms::*;
+mod _merge;
+pub use self::_merge::*;
+mod _skip;
+pub use self::_skip::*;
+mod _take;
+pub use self::_take::*;
+mod _tee;
+pub use self::_tee::*;
 
 /// Type alias for referencing the `Term` used in a `TripleSource`.
 pub type TSTerm<S> =
@@ -153,6 +168,115 @@ pub trait TripleSource {
     {
         MapSource { source: self, map }
     }
+    /// Takes a closure and creates a triple source which applies that closure to each term of each triple.
+    #[inline]
+    fn map_triple_terms<F, T>(self, map: F) -> MapTermsSource<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&TSTerm<Self>) -> T,
+        T: TTerm,
+    {
+        MapTermsSource { source: self, map }
+    }
+    /// Creates a triple source which yields at most the `n` first triples of this source.
+    ///
+    /// NB: since triples are pulled from the underlying source by batches
+    /// (see [`try_for_some_triple`](#tymethod.try_for_some_triple)),
+    /// the underlying source may be consumed beyond its `n` first triples.
+    #[inline]
+    fn take_triples(self, n: usize) -> TakeSource<Self>
+    where
+        Self: Sized,
+    {
+        TakeSource {
+            source: self,
+            remaining: n,
+        }
+    }
+    /// Creates a triple source which skips the `n` first triples of this source.
+    #[inline]
+    fn skip_triples(self, n: usize) -> SkipSource<Self>
+    where
+        Self: Sized,
+    {
+        SkipSource {
+            source: self,
+            remaining: n,
+        }
+    }
+    /// Creates a triple source which yields each triple of this source only once.
+    ///
+    /// A copy of every triple (made of terms of type `T`) is kept in memory,
+    /// in order to detect duplicates.
+    #[inline]
+    fn dedup_triples<T>(self) -> DedupSource<Self, T>
+    where
+        Self: Sized,
+        T: CopyTerm + Eq + Hash,
+    {
+        DedupSource {
+            source: self,
+            seen: HashSet::new(),
+        }
+    }
+    /// Creates a triple source which feeds every triple of this source to `sink`,
+    /// before yielding it.
+    ///
+    /// This allows, for example, to populate an in-memory graph
+    /// while serializing the triples, without parsing the input twice.
+    /// Errors raised by `sink` are reported as source errors.
+    #[inline]
+    fn tee_triples<K>(self, sink: K) -> TeeSource<Self, K>
+    where
+        Self: Sized,
+        K: TripleSink,
+    {
+        TeeSource { source: self, sink }
+    }
+    /// Feed all the triples of this source to `sink`,
+    /// in batches of (at most) `batch_size` triples
+    /// (see [`TripleSink::feed_batch`](./trait.TripleSink.html#method.feed_batch)).
+    ///
+    /// As streamed triples can not outlive the call to the closure receiving them,
+    /// each triple is copied (as a triple of `T`) in the batch.
+    fn feed_triples_batched<K, T>(
+        &mut self,
+        sink: &mut K,
+        batch_size: usize,
+    ) -> StreamResult<(), Self::Error, K::Error>
+    where
+        K: TripleSink,
+        T: CopyTerm + TTerm,
+    {
+        let batch_size = batch_size.max(1);
+        let mut batch: Vec<[T; 3]> = Vec::with_capacity(batch_size);
+        self.try_for_each_triple(|t| {
+            batch.push([T::copy(t.s()), T::copy(t.p()), T::copy(t.o())]);
+            if batch.len() < batch_size {
+                return Ok(());
+            }
+            let res = sink.feed_batch(&batch);
+            batch.clear();
+            res
+        })?;
+        if !batch.is_empty() {
+            sink.feed_batch(&batch).map_err(SinkError)?;
+        }
+        Ok(())
+    }
+    /// Creates a triple source which yields all the triples of this source,
+    /// then all the triples of `other`.
+    #[inline]
+    fn merge_triples<S>(self, other: S) -> MergeSource<Self, S>
+    where
+        Self: Sized,
+        S: TripleSource<Triple = Self::Triple, Error = Self::Error>,
+    {
+        MergeSource {
+            first: Some(self),
+            second: other,
+        }
+    }
     /// Returns the bounds on the remaining length of the triple source.
     ///
     /// This method has the same contract as [`Iterator::size_hint`].
@@ -0,0 +1,38 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`TripleSource::dedup_triples`](./trait.TripleSource.html#method.dedup_triples)
+pub struct DedupSource<S, T> {
+    pub source: S,
+    pub seen: HashSet<[T; 3]>,
+}
+
+impl<S, T> TripleSource for DedupSource<S, T>
+where
+    S: TripleSource,
+    T: CopyTerm + Eq + Hash,
+{
+    type Error = S::Error;
+    type Triple = S::Triple;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        let seen = &mut self.seen;
+        self.source.try_for_some_triple(&mut |t| {
+            if seen.insert([T::copy(t.s()), T::copy(t.p()), T::copy(t.o())]) {
+                f(t)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_triples();
+        (lower.min(1), upper)
+    }
+}
@@ -0,0 +1,38 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`TripleSource::map_triple_terms`](./trait.TripleSource.html#method.map_triple_terms)
+pub struct MapTermsSource<S, F> {
+    pub source: S,
+    pub map: F,
+}
+
+impl<S, F, T> TripleSource for MapTermsSource<S, F>
+where
+    S: TripleSource,
+    F: FnMut(&TSTerm<S>) -> T,
+    T: TTerm,
+{
+    type Error = S::Error;
+    type Triple = ByValue<[T; 3]>;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        let map = &mut self.map;
+        self.source.try_for_some_triple(&mut |t| {
+            f(StreamedTriple::by_value([
+                (map)(t.s()),
+                (map)(t.p()),
+                (map)(t.o()),
+            ]))
+        })
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_triples()
+    }
+}
@@ -0,0 +1,48 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`TripleSource::merge_triples`](./trait.TripleSource.html#method.merge_triples)
+pub struct MergeSource<S1, S2> {
+    /// The first source, until it is exhausted.
+    pub first: Option<S1>,
+    pub second: S2,
+}
+
+impl<S1, S2> TripleSource for MergeSource<S1, S2>
+where
+    S1: TripleSource,
+    S2: TripleSource<Triple = S1::Triple, Error = S1::Error>,
+{
+    type Error = S1::Error;
+    type Triple = S1::Triple;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        if let Some(first) = &mut self.first {
+            if first.try_for_some_triple(f)? {
+                return Ok(true);
+            }
+            self.first = None;
+        }
+        self.second.try_for_some_triple(f)
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        let (lower2, upper2) = self.second.size_hint_triples();
+        match &self.first {
+            None => (lower2, upper2),
+            Some(first) => {
+                let (lower1, upper1) = first.size_hint_triples();
+                let upper = match (upper1, upper2) {
+                    (Some(u1), Some(u2)) => u1.checked_add(u2),
+                    _ => None,
+                };
+                (lower1.saturating_add(lower2), upper)
+            }
+        }
+    }
+}
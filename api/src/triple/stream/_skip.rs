@@ -0,0 +1,42 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`TripleSource::skip_triples`](./trait.TripleSource.html#method.skip_triples)
+pub struct SkipSource<S> {
+    pub source: S,
+    pub remaining: usize,
+}
+
+impl<S> TripleSource for SkipSource<S>
+where
+    S: TripleSource,
+{
+    type Error = S::Error;
+    type Triple = S::Triple;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        let remaining = &mut self.remaining;
+        self.source.try_for_some_triple(&mut |t| {
+            if *remaining > 0 {
+                *remaining -= 1;
+                Ok(())
+            } else {
+                f(t)
+            }
+        })
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_triples();
+        let n = self.remaining;
+        (
+            lower.saturating_sub(n),
+            upper.map(|upper| upper.saturating_sub(n)),
+        )
+    }
+}
@@ -0,0 +1,43 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+/// The result of
+/// [`TripleSource::take_triples`](./trait.TripleSource.html#method.take_triples)
+pub struct TakeSource<S> {
+    pub source: S,
+    pub remaining: usize,
+}
+
+impl<S> TripleSource for TakeSource<S>
+where
+    S: TripleSource,
+{
+    type Error = S::Error;
+    type Triple = S::Triple;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let remaining = &mut self.remaining;
+        let more = self.source.try_for_some_triple(&mut |t| {
+            if *remaining > 0 {
+                *remaining -= 1;
+                f(t)
+            } else {
+                Ok(())
+            }
+        })?;
+        Ok(more && self.remaining > 0)
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.source.size_hint_triples();
+        let n = self.remaining;
+        (lower.min(n), Some(upper.map_or(n, |upper| upper.min(n))))
+    }
+}
@@ -0,0 +1,163 @@
+// this module is transparently re-exported by its parent `stream`
+
+use super::*;
+
+use std::fmt;
+
+/// A triple sink consumes triples one at a time,
+/// and may fail in the process.
+///
+/// Triple sinks are typically used with
+/// [`TripleSource::tee_triples`](./trait.TripleSource.html#method.tee_triples),
+/// in order to feed several consumers with the same stream of triples.
+///
+/// This trait is implemented by
+/// * mutable references to [mutable graphs](../../graph/trait.MutableGraph.html),
+///   which insert every triple they are fed;
+/// * pairs of triple sinks, which feed both of them
+///   (nest pairs to multiplex a stream to more than two sinks);
+/// * vectors of triple sinks, which feed all of them.
+///
+/// Sinks can also be fed batches of triples, with [`feed_batch`]
+/// (see also [`TripleSource::feed_triples_batched`]).
+///
+/// [`feed_batch`]: #method.feed_batch
+/// [`TripleSource::feed_triples_batched`]: ./trait.TripleSource.html#method.feed_triples_batched
+pub trait TripleSink {
+    /// The type of errors produced by this sink.
+    type Error: 'static + Error;
+
+    /// Consume one triple.
+    fn feed<T: Triple>(&mut self, t: &T) -> Result<(), Self::Error>;
+
+    /// Consume a batch of triples.
+    ///
+    /// The default implementation feeds them one at a time,
+    /// but implementors can override it when they have a more efficient way
+    /// to process several triples at once.
+    fn feed_batch<T: Triple>(&mut self, batch: &[T]) -> Result<(), Self::Error> {
+        batch.iter().try_for_each(|t| self.feed(t))
+    }
+}
+
+impl<G> TripleSink for &mut G
+where
+    G: MutableGraph + ?Sized,
+{
+    type Error = G::MutationError;
+
+    fn feed<T: Triple>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.insert(t.s(), t.p(), t.o()).map(|_| ())
+    }
+
+    fn feed_batch<T: Triple>(&mut self, batch: &[T]) -> Result<(), Self::Error> {
+        self.reserve_triples(batch.len());
+        self.insert_all(batch.iter().as_triple_source())
+            .map(|_| ())
+            .map_err(StreamError::unwrap_sink_error)
+    }
+}
+
+impl<K1, K2> TripleSink for (K1, K2)
+where
+    K1: TripleSink,
+    K2: TripleSink<Error = K1::Error>,
+{
+    type Error = K1::Error;
+
+    fn feed<T: Triple>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.0.feed(t)?;
+        self.1.feed(t)
+    }
+
+    fn feed_batch<T: Triple>(&mut self, batch: &[T]) -> Result<(), Self::Error> {
+        self.0.feed_batch(batch)?;
+        self.1.feed_batch(batch)
+    }
+}
+
+impl<K> TripleSink for Vec<K>
+where
+    K: TripleSink,
+{
+    type Error = K::Error;
+
+    fn feed<T: Triple>(&mut self, t: &T) -> Result<(), Self::Error> {
+        self.iter_mut().try_for_each(|sink| sink.feed(t))
+    }
+
+    fn feed_batch<T: Triple>(&mut self, batch: &[T]) -> Result<(), Self::Error> {
+        self.iter_mut().try_for_each(|sink| sink.feed_batch(batch))
+    }
+}
+
+/// The result of
+/// [`TripleSource::tee_triples`](./trait.TripleSource.html#method.tee_triples)
+pub struct TeeSource<S, K> {
+    pub source: S,
+    pub sink: K,
+}
+
+impl<S, K> TripleSource for TeeSource<S, K>
+where
+    S: TripleSource,
+    K: TripleSink,
+{
+    type Error = StreamError<S::Error, K::Error>;
+    type Triple = S::Triple;
+    fn try_for_some_triple<G, E>(&mut self, f: &mut G) -> StreamResult<bool, Self::Error, E>
+    where
+        G: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        let sink = &mut self.sink;
+        self.source
+            .try_for_some_triple(&mut |t| {
+                sink.feed(&t).map_err(TeeError::Sink)?;
+                f(t).map_err(TeeError::Consumer)
+            })
+            .map_err(TeeError::into_stream_error)
+    }
+
+    fn size_hint_triples(&self) -> (usize, Option<usize>) {
+        self.source.size_hint_triples()
+    }
+}
+
+/// The error raised in the closure of a tee source,
+/// either by its sink or by its consumer.
+#[derive(Debug)]
+pub(crate) enum TeeError<K, E> {
+    Sink(K),
+    Consumer(E),
+}
+
+impl<K, E> TeeError<K, E>
+where
+    K: 'static + Error,
+    E: Error,
+{
+    pub(crate) fn into_stream_error<S>(
+        err: StreamError<S, Self>,
+    ) -> StreamError<StreamError<S, K>, E>
+    where
+        S: 'static + Error,
+    {
+        match err {
+            SourceError(err) => SourceError(SourceError(err)),
+            SinkError(TeeError::Sink(err)) => SourceError(SinkError(err)),
+            SinkError(TeeError::Consumer(err)) => SinkError(err),
+        }
+    }
+}
+
+impl<K: fmt::Display, E: fmt::Display> fmt::Display for TeeError<K, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TeeError::Sink(err) => err.fmt(f),
+            TeeError::Consumer(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<K: Error, E: Error> Error for TeeError<K, E> {}
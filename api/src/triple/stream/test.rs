@@ -302,3 +302,127 @@ fn map_triples_iter() {
 //             assert!(g.contains(t.s(), t.p(), t.o()).unwrap());
 //         });
 // }
+
+#[test]
+fn map_triple_terms() {
+    let g = make_graph();
+    let h = make_mapped_graph();
+    let mut c = 0;
+    g.triples()
+        .map_triple_terms(map_term)
+        .for_each_triple(|t| {
+            c += 1;
+            assert!(h.contains(t.s(), t.p(), t.o()).unwrap());
+        })
+        .unwrap();
+    assert_eq!(c, h.len());
+}
+
+#[test]
+fn take_triples() {
+    let g = make_graph();
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    g.triples().take_triples(2).add_to_graph(&mut h).unwrap();
+    assert_eq!(h.len(), 2);
+    assert_eq!(h[1].o(), &ALICE_LIT as &StaticTerm);
+    assert_eq!(g.triples().take_triples(9).size_hint_triples().1, Some(5));
+    assert_eq!(
+        g.triples().take_triples(0).for_each_triple(|_| panic!()),
+        Ok(())
+    );
+}
+
+#[test]
+fn skip_triples() {
+    let g = make_graph();
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    g.triples().skip_triples(3).add_to_graph(&mut h).unwrap();
+    assert_eq!(h.len(), 2);
+    assert_eq!(h[0].o(), &BOB_LIT as &StaticTerm);
+}
+
+#[test]
+fn dedup_triples() {
+    let mut g = make_graph();
+    g.extend(make_graph());
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    g.triples()
+        .dedup_triples::<BoxTerm>()
+        .add_to_graph(&mut h)
+        .unwrap();
+    assert_eq!(h.len(), 5);
+}
+
+#[test]
+fn merge_triples() {
+    let g1 = make_graph();
+    let g2 = make_mapped_graph();
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    g1.triples()
+        .merge_triples(g2.triples())
+        .add_to_graph(&mut h)
+        .unwrap();
+    assert_eq!(h.len(), 10);
+    assert_eq!(h[5].s(), &CHARLIE as &StaticTerm);
+}
+
+#[test]
+fn tee_triples() {
+    let g = make_graph();
+    let mut h1 = Vec::<[BoxTerm; 3]>::new();
+    let mut h2 = Vec::<[BoxTerm; 3]>::new();
+    let mut h3 = Vec::<[BoxTerm; 3]>::new();
+    g.triples()
+        .tee_triples((&mut h1, vec![&mut h2]))
+        .add_to_graph(&mut h3)
+        .unwrap();
+    assert_eq!(h1.len(), g.len());
+    assert_eq!(h2.len(), g.len());
+    assert_eq!(h3.len(), g.len());
+}
+
+#[test]
+fn feed_triples_batched() {
+    struct Batches(Vec<usize>);
+    impl TripleSink for Batches {
+        type Error = std::convert::Infallible;
+        fn feed<T: Triple>(&mut self, _: &T) -> Result<(), Self::Error> {
+            unreachable!()
+        }
+        fn feed_batch<T: Triple>(&mut self, batch: &[T]) -> Result<(), Self::Error> {
+            self.0.push(batch.len());
+            Ok(())
+        }
+    }
+    let g = make_graph();
+    let mut batches = Batches(vec![]);
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    g.triples()
+        .feed_triples_batched::<_, BoxTerm>(&mut &mut h, 2)
+        .unwrap();
+    assert_eq!(h.len(), g.len());
+    g.triples()
+        .feed_triples_batched::<_, BoxTerm>(&mut batches, 2)
+        .unwrap();
+    assert_eq!(batches.0, vec![2, 2, 1]);
+}
+
+#[test]
+fn tee_triples_sink_error() {
+    struct Failing;
+    impl TripleSink for Failing {
+        type Error = std::fmt::Error;
+        fn feed<T: Triple>(&mut self, _: &T) -> Result<(), Self::Error> {
+            Err(std::fmt::Error)
+        }
+    }
+    let g = make_graph();
+    let mut h = Vec::<[BoxTerm; 3]>::new();
+    let err = g
+        .triples()
+        .tee_triples(Failing)
+        .add_to_graph(&mut h)
+        .unwrap_err();
+    assert!(matches!(err, SourceError(SinkError(std::fmt::Error))));
+    assert!(h.is_empty());
+}
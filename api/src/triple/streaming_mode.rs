@@ -67,8 +67,8 @@
 //! [`TripleStreamingMode`]: trait.TripleStreamingMode.html
 //! [`make_scoped_triple_streaming_mode`]: ../../macro.make_scoped_triple_streaming_mode.html
 
-use std::marker::PhantomData;
-use std::ptr::NonNull;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
 
 use crate::term::TTerm;
 use crate::triple::Triple;
@@ -197,7 +197,7 @@ where
 macro_rules! make_scoped_triple_streaming_mode {
     ($mode: ident, $tt: ident) => {
         #[derive(Debug)]
-        pub struct $mode(std::marker::PhantomData<$tt<'static>>);
+        pub struct $mode(core::marker::PhantomData<$tt<'static>>);
         impl $crate::triple::streaming_mode::TripleStreamingMode for $mode {
             type UnsafeTriple = $tt<'static>;
         }
@@ -208,7 +208,7 @@ macro_rules! make_scoped_triple_streaming_mode {
                 triple: $tt<'a>,
             ) -> $crate::triple::streaming_mode::StreamedTriple<'a, $mode> {
                 unsafe {
-                    $crate::triple::streaming_mode::StreamedTriple::wrap(std::mem::transmute(
+                    $crate::triple::streaming_mode::StreamedTriple::wrap(core::mem::transmute(
                         triple,
                     ))
                 }
@@ -229,6 +229,7 @@ pub trait ScopedTripleMode<'a>: TripleStreamingMode + Sized {
 
 // adapter
 
+#[cfg(feature = "std")]
 pub(crate) use crate::quad::streaming_mode::FromQuad;
 
 #[derive(Debug)]
@@ -241,6 +242,7 @@ impl<'a, T> crate::quad::streaming_mode::StreamedQuad<'a, FromTriple<T>>
 where
     T: TripleStreamingMode,
 {
+    #[cfg(feature = "std")]
     pub(crate) fn from_triple(triple: StreamedTriple<T>) -> Self {
         unsafe { Self::wrap(UnsafeQuadAdapter(triple.wrapped)) }
     }
@@ -0,0 +1,320 @@
+#[cfg(feature = "std")]
+use super::is_valid_iri_ref;
+use crate::error::InvalidIri;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::net::Ipv6Addr;
+
+/// Check whether `ns` and `suffix` concatenate into a valid (absolute or relative) IRI reference.
+///
+/// # Performances
+/// In the current implementation, a new String is allocated if `suffix`
+/// is not `None`.
+/// Future implementations may be smarter about this.
+#[inline]
+pub fn is_valid_suffixed_iri_ref(ns: &str, suffix: Option<&str>) -> bool {
+    match suffix {
+        None => is_valid_iri_ref(ns),
+        Some(suffix) => {
+            let mut buffer = String::with_capacity(ns.len() + suffix.len());
+            buffer.push_str(ns);
+            buffer.push_str(suffix);
+            is_valid_iri_ref(&buffer)
+        }
+    }
+}
+
+/// Check whether `txt` is a valid (absolute or relative) IRI reference.
+#[cfg(not(feature = "std"))]
+#[inline]
+pub fn is_valid_iri_ref(txt: &str) -> bool {
+    check_iri_ref(txt).is_ok()
+}
+
+/// Check whether `txt` is an absolute IRI reference.
+#[cfg(not(feature = "std"))]
+#[inline]
+pub fn is_absolute_iri_ref(txt: &str) -> bool {
+    check_iri_ref(txt).is_ok() && has_scheme(txt)
+}
+
+/// Check whether `txt` is a relative IRI reference.
+#[cfg(not(feature = "std"))]
+#[inline]
+pub fn is_relative_iri_ref(txt: &str) -> bool {
+    check_iri_ref(txt).is_ok() && !has_scheme(txt)
+}
+
+/// Percent-encode the characters preventing `txt` from being a valid IRI reference.
+///
+/// This is useful for ingesting real-world data,
+/// which often contains IRIs with spaces, unescaped brackets or stray `%` signs.
+/// `txt` is returned unchanged if it is already valid.
+///
+/// Fail if `txt` contains errors that can not be fixed by percent-encoding
+/// (e.g. an invalid scheme or port).
+pub fn fix_iri_ref(txt: &str) -> Result<Cow<'_, str>, InvalidIri> {
+    if is_valid_iri_ref(txt) {
+        return Ok(Cow::Borrowed(txt));
+    }
+    let mut fixed = txt.to_string();
+    loop {
+        match diagnose(&fixed) {
+            Some((pos, production)) if ENCODABLE.contains(&production) => {
+                let c = fixed[pos..].chars().next().unwrap();
+                let mut buf = [0; 4];
+                let encoded: String = c
+                    .encode_utf8(&mut buf)
+                    .bytes()
+                    .map(|b| format!("%{:02X}", b))
+                    .collect();
+                fixed.replace_range(pos..pos + c.len_utf8(), &encoded);
+            }
+            None if is_valid_iri_ref(&fixed) => return Ok(Cow::Owned(fixed)),
+            _ => return Err(InvalidIri::new(txt)),
+        }
+    }
+}
+
+/// The productions of RFC3987 that accept percent-encoded characters.
+const ENCODABLE: &[&str] = &[
+    "iuserinfo",
+    "ihost",
+    "ipath",
+    "iquery",
+    "ifragment",
+    "pct-encoded",
+];
+
+/// Locate the first character preventing `txt` from being a valid IRI reference,
+/// and return its byte position together with the name of the
+/// [RFC3987](https://tools.ietf.org/html/rfc3987#section-2.2) production it violates.
+///
+/// Return `None` if no such character could be found.
+pub(crate) fn diagnose(txt: &str) -> Option<(usize, &'static str)> {
+    check_iri_ref(txt).err()
+}
+
+/// The result of checking (part of) an IRI reference;
+/// the error is the byte position and the violated production.
+type Check = Result<(), (usize, &'static str)>;
+
+/// Whether `txt` starts with a (possibly invalid) scheme.
+#[cfg(any(test, not(feature = "std")))]
+fn has_scheme(txt: &str) -> bool {
+    txt.find([':', '/', '?', '#'])
+        .is_some_and(|end| end > 0 && txt[end..].starts_with(':'))
+}
+
+fn check_iri_ref(txt: &str) -> Check {
+    let mut i = 0;
+    if let Some(end) = txt.find([':', '/', '?', '#']) {
+        if txt[end..].starts_with(':') {
+            // NB: in a relative reference, the first segment can not contain ':' either
+            if end == 0 {
+                return Err((0, "scheme"));
+            }
+            for (j, c) in txt[..end].char_indices() {
+                let valid = c.is_ascii_alphabetic()
+                    || j > 0 && (c.is_ascii_digit() || matches!(c, '+' | '-' | '.'));
+                if !valid {
+                    return Err((j, "scheme"));
+                }
+            }
+            i = end + 1;
+        }
+    }
+    if txt[i..].starts_with("//") {
+        i += 2;
+        let end = find_from(txt, i, &['/', '?', '#']);
+        check_authority(txt, i, end)?;
+        i = end;
+    }
+    let end = find_from(txt, i, &['?', '#']);
+    check_chars(txt, i, end, "ipath", |c| c == '/')?;
+    i = end;
+    if txt[i..].starts_with('?') {
+        let end = find_from(txt, i + 1, &['#']);
+        check_chars(
+            txt,
+            i + 1,
+            end,
+            "iquery",
+            |c| matches!(c, '/' | '?' | '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}'),
+        )?;
+        i = end;
+    }
+    if txt[i..].starts_with('#') {
+        check_chars(txt, i + 1, txt.len(), "ifragment", |c| {
+            matches!(c, '/' | '?')
+        })?;
+    }
+    Ok(())
+}
+
+fn check_authority(txt: &str, start: usize, end: usize) -> Check {
+    let mut i = start;
+    if let Some(at) = txt[start..end].find('@') {
+        check_chars(txt, start, start + at, "iuserinfo", |c| c == ':')?;
+        i = start + at + 1;
+    }
+    if txt[i..end].starts_with('[') {
+        let close = match txt[i..end].find(']') {
+            Some(close) => i + close,
+            None => return Err((i, "IP-literal")),
+        };
+        if !is_valid_ip_literal(&txt[i + 1..close]) {
+            return Err((i, "IP-literal"));
+        }
+        i = close + 1;
+        if i < end && !txt[i..end].starts_with(':') {
+            return Err((i, "IP-literal"));
+        }
+    } else {
+        let host_end = find_from(txt, i, &[':']).min(end);
+        check_chars(txt, i, host_end, "ihost", |_| false)?;
+        i = host_end;
+    }
+    if i < end {
+        // txt[i] == ':'
+        for (j, c) in txt[i + 1..end].char_indices() {
+            if !c.is_ascii_digit() {
+                return Err((i + 1 + j, "port"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that all characters of `txt[start..end]` are either `iunreserved`, `sub-delims`,
+/// percent-encoded, or accepted by `extra`.
+/// `ipchar`'s `:` and `@` are accepted in all productions but `ihost` and `iuserinfo`.
+fn check_chars<F>(txt: &str, start: usize, end: usize, production: &'static str, extra: F) -> Check
+where
+    F: Fn(char) -> bool,
+{
+    let bytes = txt.as_bytes();
+    let pchar = !matches!(production, "ihost" | "iuserinfo");
+    let mut chars = txt[start..end].char_indices();
+    while let Some((j, c)) = chars.next() {
+        let pos = start + j;
+        if c == '%' {
+            if pos + 2 < end
+                && bytes[pos + 1].is_ascii_hexdigit()
+                && bytes[pos + 2].is_ascii_hexdigit()
+            {
+                chars.next();
+                chars.next();
+                continue;
+            }
+            return Err((pos, "pct-encoded"));
+        }
+        let valid =
+            is_iunreserved(c) || is_sub_delim(c) || pchar && matches!(c, ':' | '@') || extra(c);
+        if !valid {
+            return Err((pos, production));
+        }
+    }
+    Ok(())
+}
+
+fn find_from(txt: &str, start: usize, delims: &[char]) -> usize {
+    txt[start..]
+        .find(|c| delims.contains(&c))
+        .map_or(txt.len(), |i| start + i)
+}
+
+fn is_iunreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~') || is_ucschar(c)
+}
+
+fn is_sub_delim(c: char) -> bool {
+    matches!(
+        c,
+        '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+    )
+}
+
+fn is_ucschar(c: char) -> bool {
+    let c = c as u32;
+    matches!(c, 0xA0..=0xD7FF | 0xF900..=0xFDCF | 0xFDF0..=0xFFEF)
+        || (0x10000..=0xEFFFD).contains(&c) && (c & 0xFFFF) <= 0xFFFD && c < 0xE0000
+        || (0xE1000..=0xEFFFD).contains(&c)
+}
+
+fn is_valid_ip_literal(txt: &str) -> bool {
+    if let Some(future) = txt.strip_prefix(['v', 'V']) {
+        match future.split_once('.') {
+            Some((version, rest)) => {
+                !version.is_empty()
+                    && version.bytes().all(|b| b.is_ascii_hexdigit())
+                    && !rest.is_empty()
+                    && rest.chars().all(|c| {
+                        c.is_ascii_alphanumeric()
+                            || matches!(c, '-' | '.' | '_' | '~' | ':')
+                            || is_sub_delim(c)
+                    })
+            }
+            None => false,
+        }
+    } else {
+        txt.parse::<Ipv6Addr>().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{NEGATIVE_IRIS, POSITIVE_IRIS};
+    use test_case::test_case;
+
+    #[test]
+    fn positive() {
+        for (txt, parsed) in POSITIVE_IRIS {
+            assert_eq!(diagnose(txt), None, "{}", txt);
+            assert_eq!(has_scheme(txt), parsed.0, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn negative() {
+        for txt in NEGATIVE_IRIS {
+            assert!(diagnose(txt).is_some(), "{}", txt);
+        }
+    }
+
+    #[test_case("http://a/b c", (10, "ipath"))]
+    #[test_case("1http://a/", (0, "scheme"))]
+    #[test_case("ht tp://a/", (2, "scheme"))]
+    #[test_case("http://a b/", (8, "ihost"))]
+    #[test_case("http://a:8o/", (10, "port"))]
+    #[test_case("http://[::1/", (7, "IP-literal"))]
+    #[test_case("http://[zz]/", (7, "IP-literal"))]
+    #[test_case("http://u|v@a/", (8, "iuserinfo"))]
+    #[test_case("http://a/?x={y}", (12, "iquery"))]
+    #[test_case("http://a/#x#y", (11, "ifragment"))]
+    #[test_case("http://a/100%", (12, "pct-encoded"))]
+    #[test_case("http://a/%2x", (9, "pct-encoded"))]
+    #[test_case("a b", (1, "ipath"))]
+    fn diagnosis(txt: &str, expected: (usize, &str)) {
+        assert_eq!(diagnose(txt), Some(expected));
+    }
+
+    #[test_case("http://a/b c", "http://a/b%20c")]
+    #[test_case("http://a/{x}?q=|#f#g", "http://a/%7Bx%7D?q=%7C#f%23g")]
+    #[test_case("http://a/100%", "http://a/100%25")]
+    #[test_case("http://a/é", "http://a/é")]
+    #[test_case("a\\b", "a%5Cb")]
+    fn fix(txt: &str, expected: &str) {
+        assert_eq!(fix_iri_ref(txt).unwrap(), expected);
+    }
+
+    #[test_case("1http://a/")]
+    #[test_case("ht tp://a/")]
+    #[test_case("http://[::1/")]
+    fn unfixable(txt: &str) {
+        let err = fix_iri_ref(txt).unwrap_err();
+        assert_eq!(err.iri, txt);
+    }
+}
@@ -1,13 +1,64 @@
 //! Error and result type for IRI resolution.
 
-use thiserror::Error;
+use alloc::string::String;
+use core::fmt;
 
 /// Type alias for `Result` with default error `TermError`.
 ///
 /// Can be used like `std::result::Result` as well.
-pub type Result<T, E = InvalidIri> = std::result::Result<T, E>;
+pub type Result<T, E = InvalidIri> = core::result::Result<T, E>;
 
 /// This error is raised when trying to parse an invalid IRI.
-#[derive(Debug, Error)]
-#[error("The given IRI '{0}' is not valid according to RFC3987")]
-pub struct InvalidIri(pub String);
+///
+/// When possible, it locates the first invalid character,
+/// and the [RFC3987](https://tools.ietf.org/html/rfc3987#section-2.2) production it violates.
+#[derive(Debug)]
+pub struct InvalidIri {
+    /// The invalid IRI.
+    pub iri: String,
+    /// The byte position of the first invalid character in `iri`, if it could be determined.
+    pub position: Option<usize>,
+    /// The production of RFC3987 violated at `position`, if it could be determined.
+    pub production: Option<&'static str>,
+}
+
+impl InvalidIri {
+    /// Build an `InvalidIri` error for `iri`, locating the first invalid character.
+    pub fn new<S: Into<String>>(iri: S) -> InvalidIri {
+        let iri = iri.into();
+        let diagnosis = crate::_diagnose::diagnose(&iri);
+        InvalidIri {
+            iri,
+            position: diagnosis.map(|d| d.0),
+            production: diagnosis.map(|d| d.1),
+        }
+    }
+
+    /// The first invalid character of the IRI, if it could be determined.
+    pub fn character(&self) -> Option<char> {
+        self.position.and_then(|p| self.iri[p..].chars().next())
+    }
+}
+
+impl fmt::Display for InvalidIri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.position, self.character(), self.production) {
+            (Some(position), Some(c), Some(production)) => write!(
+                f,
+                "Invalid character {:?} at position {} of IRI '{}' (in {})",
+                c, position, self.iri, production,
+            ),
+            _ => write!(
+                f,
+                "The given IRI '{}' is not valid according to RFC3987",
+                self.iri
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidIri {}
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for InvalidIri {}
@@ -7,12 +7,29 @@
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
 //! [Linked Data]: http://linkeddata.org/
+//!
+//! # `no_std` support
+//!
+//! This crate can be used without the standard library (but requires `alloc`)
+//! by disabling its default `std` feature.
+//! IRIs are then checked with a hand-written parser instead of regular expressions,
+//! and the [`resolve`] module is not available.
+//! Note that [`InvalidIri`](error::InvalidIri) then implements `core::error::Error`,
+//! which requires Rust 1.81 or later.
 
 #![deny(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+mod _diagnose;
+pub use self::_diagnose::*;
+#[cfg(feature = "std")]
 mod _regex;
+#[cfg(feature = "std")]
 pub use self::_regex::*;
 pub mod error;
+#[cfg(feature = "std")]
 pub mod resolve;
 
 #[cfg(any(test, feature = "test_data"))]
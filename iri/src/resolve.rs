@@ -57,7 +57,7 @@ impl<'a> IriParsed<'a> {
                 .map(|m| m.as_str())
                 .filter(|s| !s.is_empty());
         } else {
-            return Err(InvalidIri(txt.to_owned()));
+            return Err(InvalidIri::new(txt));
         }
         if let Some(path) = path {
             path.split('/').for_each(|i| pi.path.push(i))
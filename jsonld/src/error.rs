@@ -14,6 +14,14 @@ pub enum JsonLdError {
     #[error("IO Error: {0}")]
     IOError(#[from] std::io::Error),
 
+    /// An invalid JSON-LD document.
+    #[error("invalid JSON-LD: {0}")]
+    InvalidJsonLd(String),
+
+    /// A context that can not be processed (e.g. a remote context).
+    #[error("unsupported context: {0}")]
+    UnsupportedContext(String),
+
     /// An unsupported JSON-LD version
     #[error("unsupported version: {0:?}")]
     UnsupportedVersion(JsonLdSpecVersion),
@@ -0,0 +1,403 @@
+//! Extraction of all the structured data embedded in an HTML document.
+//!
+//! The [`HtmlParser`] produces, in a single pass, the quads described by
+//! * the [JSON-LD script elements] (`<script type="application/ld+json">`),
+//! * the [microdata] items, converted to JSON-LD as described in [Microdata to RDF],
+//! * the [RDFa] annotations (see [`sophia::parser::rdfa`]).
+//!
+//! JSON-LD and microdata are both processed by the [JSON-LD parser](../parser/index.html),
+//! so the same limitations apply.
+//!
+//! This module requires the `html` feature.
+//!
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::quad::stream::QuadSource;
+//! # use sophia_jsonld::html::HtmlParser;
+//! # use sophia_api::parser::QuadParser;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let html = r#"<html><head>
+//!   <script type="application/ld+json">
+//!     {"@context": "https://schema.org", "@type": "Event", "name": "Concert"}
+//!   </script>
+//! </head><body>
+//!   <div itemscope itemtype="http://schema.org/Person">
+//!     <span itemprop="name">Alice</span>
+//!   </div>
+//! </body></html>"#;
+//! let parser = HtmlParser {
+//!     base: Some("http://example.org/".into()),
+//! };
+//! let d: FastDataset = parser.parse_str(html).collect_quads()?;
+//! assert_eq!(d.quads().count(), 4);
+//! # Ok(()) }
+//! ```
+//!
+//! [`HtmlParser`]: struct.HtmlParser.html
+//! [JSON-LD script elements]: https://www.w3.org/TR/json-ld11/#embedding-json-ld-in-html-documents
+//! [microdata]: https://html.spec.whatwg.org/multipage/microdata.html
+//! [Microdata to RDF]: https://www.w3.org/TR/microdata-rdf/
+//! [RDFa]: https://www.w3.org/TR/html-rdfa/
+//! [`sophia::parser::rdfa`]: https://docs.rs/sophia/latest/sophia/parser/rdfa/index.html
+
+use crate::error::*;
+use crate::parser::{JsonLdSource, ToRdf};
+use json::JsonValue;
+use scraper::node::Node;
+use scraper::{ElementRef, Html};
+use sophia::parser::rdfa::RdfaParser;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_iri::is_absolute_iri_ref;
+use sophia_iri::resolve::{IriParsed, Resolve};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// A parser extracting JSON-LD, microdata and RDFa from HTML documents.
+#[derive(Clone, Debug, Default)]
+pub struct HtmlParser {
+    /// The base IRI of the document,
+    /// used if the document does not contain a `<base>` element.
+    pub base: Option<String>,
+}
+
+impl<B: BufRead> QuadParser<B> for HtmlParser {
+    type Source = JsonLdSource;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        let res = data
+            .read_to_string(&mut txt)
+            .map_err(JsonLdError::from)
+            .and_then(|_| self.extract(&txt));
+        match res {
+            Ok(quads) => quads.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        }
+        .into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(HtmlParser, QuadParser);
+
+impl HtmlParser {
+    fn extract(&self, txt: &str) -> Result<Vec<sophia::syntax::dynamic::BoxQuad>, JsonLdError> {
+        let doc = Html::parse_document(txt);
+        let base = document_base(&doc, self.base.as_deref());
+        let mut engine = ToRdf::new();
+        for script in doc.root_element().descendent_elements() {
+            let is_json_ld = script.value().name() == "script"
+                && script
+                    .attr("type")
+                    .map(|t| t.trim().eq_ignore_ascii_case("application/ld+json"))
+                    .unwrap_or(false);
+            if is_json_ld {
+                let json: String = script.text().collect();
+                engine.process(&json::parse(&json)?, base.as_deref())?;
+            }
+        }
+        let microdata = Microdata::new(&doc, base.as_deref()).into_json_ld();
+        engine.process(&microdata, base.as_deref())?;
+        let mut quads = engine.into_quads();
+        let rdfa = RdfaParser {
+            base: self.base.clone(),
+        };
+        for t in rdfa.parse_str(txt) {
+            quads.push((t?, None));
+        }
+        Ok(quads)
+    }
+}
+
+/// The base IRI of `doc`, taking its `<base>` element into account.
+fn document_base(doc: &Html, base: Option<&str>) -> Option<String> {
+    let href = doc
+        .root_element()
+        .descendent_elements()
+        .find(|e| e.value().name() == "base")
+        .and_then(|e| e.attr("href"));
+    match (href, base) {
+        (Some(href), Some(base)) => Some(resolve(base, href)),
+        (Some(href), None) => Some(href.to_string()),
+        (None, base) => base.map(str::to_string),
+    }
+}
+
+fn resolve(base: &str, iri: &str) -> String {
+    if is_absolute_iri_ref(iri) {
+        return iri.to_string();
+    }
+    IriParsed::new(base)
+        .ok()
+        .and_then(|base| {
+            let resolved: Result<_, _> = base.resolve(iri);
+            resolved.ok().map(|iri| iri.to_string())
+        })
+        .unwrap_or_else(|| iri.to_string())
+}
+
+/// Converts the microdata items of a document to JSON-LD (in expanded form).
+struct Microdata<'a> {
+    doc: &'a Html,
+    base: Option<&'a str>,
+    /// The identifiers of the items already converted
+    ids: HashMap<ego_tree::NodeId, String>,
+}
+
+impl<'a> Microdata<'a> {
+    fn new(doc: &'a Html, base: Option<&'a str>) -> Self {
+        Microdata {
+            doc,
+            base,
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Convert all the top-level items of the document.
+    fn into_json_ld(mut self) -> JsonValue {
+        let doc = self.doc;
+        let items: Vec<_> = doc
+            .root_element()
+            .descendent_elements()
+            .filter(|e| e.attr("itemscope").is_some() && e.attr("itemprop").is_none())
+            .collect();
+        JsonValue::Array(items.into_iter().map(|e| self.item(e)).collect())
+    }
+
+    /// Convert the item `elt` to a node object.
+    fn item(&mut self, elt: ElementRef<'a>) -> JsonValue {
+        let mut node = JsonValue::new_object();
+        if let Some(id) = self.ids.get(&elt.id()) {
+            // already converted (or being converted): only refer to it
+            node["@id"] = id.clone().into();
+            return node;
+        }
+        let id = match elt.attr("itemid") {
+            Some(id) => self.resolve(id),
+            None => format!("_:md{}", self.ids.len()),
+        };
+        self.ids.insert(elt.id(), id.clone());
+        node["@id"] = id.into();
+
+        let types: Vec<_> = elt
+            .attr("itemtype")
+            .unwrap_or("")
+            .split_whitespace()
+            .filter(|t| is_absolute_iri_ref(t))
+            .collect();
+        if !types.is_empty() {
+            node["@type"] = types
+                .iter()
+                .map(|t| JsonValue::from(*t))
+                .collect::<Vec<_>>()
+                .into();
+        }
+        // the vocabulary is derived from the first type
+        let vocab = types.first().map(|t| match t.rfind('#') {
+            Some(i) => t[..=i].to_string(),
+            None => match t.rfind('/') {
+                Some(i) => t[..=i].to_string(),
+                None => t.to_string(),
+            },
+        });
+
+        for prop in self.properties(elt) {
+            let value = self.property_value(prop);
+            for name in prop.attr("itemprop").unwrap_or("").split_whitespace() {
+                let iri = if is_absolute_iri_ref(name) && name.contains(':') {
+                    name.to_string()
+                } else {
+                    match &vocab {
+                        Some(vocab) => format!("{}{}", vocab, name),
+                        None => continue,
+                    }
+                };
+                if !node.has_key(&iri) {
+                    node[&iri[..]] = JsonValue::new_array();
+                }
+                node[&iri[..]].push(value.clone()).unwrap();
+            }
+        }
+        node
+    }
+
+    /// The [properties](https://html.spec.whatwg.org/multipage/microdata.html#the-properties-of-an-item)
+    /// of the item `elt`, in tree order.
+    fn properties(&self, elt: ElementRef<'a>) -> Vec<ElementRef<'a>> {
+        let mut roots = vec![elt];
+        for id in elt.attr("itemref").unwrap_or("").split_whitespace() {
+            roots.extend(
+                self.doc
+                    .root_element()
+                    .descendent_elements()
+                    .find(|e| e.value().id() == Some(id)),
+            );
+        }
+        let mut props = vec![];
+        for (i, root) in roots.into_iter().enumerate() {
+            if i > 0 && root.attr("itemprop").is_some() {
+                props.push(root);
+            }
+            if i == 0 || root.attr("itemscope").is_none() {
+                crawl(root, &mut props);
+            }
+        }
+        props.dedup_by_key(|e| e.id());
+        props
+    }
+
+    /// The value of the property element `elt`.
+    fn property_value(&mut self, elt: ElementRef<'a>) -> JsonValue {
+        if elt.attr("itemscope").is_some() {
+            return self.item(elt);
+        }
+        let iri_attr = match elt.value().name() {
+            "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => Some("src"),
+            "a" | "area" | "link" => Some("href"),
+            "object" => Some("data"),
+            _ => None,
+        };
+        if let Some(iri) = iri_attr.and_then(|a| elt.attr(a)) {
+            let mut node = JsonValue::new_object();
+            node["@id"] = self.resolve(iri).into();
+            return node;
+        }
+        let value = match elt.value().name() {
+            "meta" => elt.attr("content").map(str::to_string),
+            "data" | "meter" => elt.attr("value").map(str::to_string),
+            "time" => elt.attr("datetime").map(str::to_string),
+            _ => None,
+        }
+        .unwrap_or_else(|| elt.text().collect());
+        let mut literal = JsonValue::new_object();
+        literal["@value"] = value.into();
+        if let Some(lang) = language(elt) {
+            literal["@language"] = lang.into();
+        }
+        literal
+    }
+
+    fn resolve(&self, iri: &str) -> String {
+        match self.base {
+            Some(base) => resolve(base, iri),
+            None => iri.to_string(),
+        }
+    }
+}
+
+/// Collect the descendants of `elt` with an `itemprop`,
+/// without entering nested items.
+fn crawl<'a>(elt: ElementRef<'a>, props: &mut Vec<ElementRef<'a>>) {
+    for child in elt.child_elements() {
+        if child.attr("itemprop").is_some() {
+            props.push(child);
+        }
+        if child.attr("itemscope").is_none() {
+            crawl(child, props);
+        }
+    }
+}
+
+/// The language of `elt`, inherited from its ancestors.
+fn language(elt: ElementRef) -> Option<String> {
+    std::iter::once(*elt)
+        .chain(elt.ancestors())
+        .filter_map(|n| match n.value() {
+            Node::Element(e) => e.attr("lang").or_else(|| e.attr("xml:lang")),
+            _ => None,
+        })
+        .next()
+        .filter(|lang| !lang.is_empty())
+        .map(str::to_lowercase)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia::dataset::{inmem::FastDataset, Dataset};
+    use sophia::ns::rdf;
+    use sophia::quad::stream::QuadSource;
+    use sophia_term::StaticTerm;
+
+    fn parse(html: &str) -> FastDataset {
+        let parser = HtmlParser {
+            base: Some("http://example.org/page".into()),
+        };
+        parser.parse_str(html).collect_quads().unwrap()
+    }
+
+    fn iri(iri: &'static str) -> StaticTerm {
+        StaticTerm::new_iri(iri).unwrap()
+    }
+
+    #[test]
+    fn microdata() -> Result<(), Box<dyn std::error::Error>> {
+        let d = parse(
+            r##"<html lang="en"><body>
+              <div itemscope itemtype="http://schema.org/Person" itemid="#alice" itemref="extra">
+                <span itemprop="name">Alice</span>
+                <a itemprop="url" href="/alice">home</a>
+                <div itemprop="address" itemscope itemtype="http://schema.org/PostalAddress">
+                  <meta itemprop="postalCode" content="69000">
+                </div>
+              </div>
+              <p id="extra" itemprop="jobTitle" lang="fr">Ingénieure</p>
+            </body></html>"##,
+        );
+        let none = None as Option<&StaticTerm>;
+        let alice = iri("http://example.org/page#alice");
+        assert!(d.contains(&alice, &rdf::type_, &iri("http://schema.org/Person"), none)?);
+        let name = StaticTerm::new_literal_lang("Alice", "en")?;
+        assert!(d.contains(&alice, &iri("http://schema.org/name"), &name, none)?);
+        assert!(d.contains(
+            &alice,
+            &iri("http://schema.org/url"),
+            &iri("http://example.org/alice"),
+            none
+        )?);
+        let job = StaticTerm::new_literal_lang("Ingénieure", "fr")?;
+        assert!(d.contains(&alice, &iri("http://schema.org/jobTitle"), &job, none)?);
+        // type, name, url, address, jobTitle + address type and postal code
+        assert_eq!(d.quads().count(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn all_formats() -> Result<(), Box<dyn std::error::Error>> {
+        let d = parse(
+            r#"<html><head>
+              <base href="http://example.com/">
+              <script type="application/ld+json">
+                {"@context": {"@vocab": "http://schema.org/"}, "@id": "event", "name": "Concert"}
+              </script>
+            </head><body>
+              <p about="event" property="http://schema.org/location">Hall</p>
+              <div itemscope itemtype="http://schema.org/Thing" itemid="thing"></div>
+            </body></html>"#,
+        );
+        let none = None as Option<&StaticTerm>;
+        let event = iri("http://example.com/event");
+        let name = StaticTerm::new_literal_dt("Concert", sophia::ns::xsd::string)?;
+        assert!(d.contains(&event, &iri("http://schema.org/name"), &name, none)?);
+        let hall = StaticTerm::new_literal_dt("Hall", sophia::ns::xsd::string)?;
+        assert!(d.contains(&event, &iri("http://schema.org/location"), &hall, none)?);
+        assert!(d.contains(
+            &iri("http://example.com/thing"),
+            &rdf::type_,
+            &iri("http://schema.org/Thing"),
+            none
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_json_ld() {
+        let mut source = parse_str(r#"<script type="application/ld+json">{</script>"#);
+        assert!(matches!(
+            source.next(),
+            Some(Err(JsonLdError::InvalidJsonLiteral(_)))
+        ));
+    }
+}
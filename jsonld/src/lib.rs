@@ -2,7 +2,8 @@
 //!
 //! NB: this implementation only supports the [expanded document form] of [JSON-LD].
 //!
-//! NB2: currently, only the serializer part is implemented.
+//! NB2: the parser expands documents on the fly,
+//! with a limited support of contexts (see the [`parser`](parser/index.html) module).
 //!
 //! [JSON-LD]: https://www.w3.org/TR/json-ld11/
 //! [expanded document form]: https://www.w3.org/TR/json-ld11/#expanded-document-form
@@ -13,6 +14,10 @@ pub mod config;
 pub use config::*;
 pub mod error;
 pub use error::*;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod parser;
+pub use parser::JsonLdParser;
 pub mod serializer;
 pub use serializer::*;
 mod util_traits;
@@ -0,0 +1,480 @@
+//! A JSON-LD parser implementing the
+//! [`Deserialize JSON-LD to RDF Algorithm`].
+//!
+//! Documents are expanded on the fly, with the following limitations:
+//! * remote contexts are not fetched:
+//!   they are read from the local files of the [`IriCatalog`] of the parser, if any
+//!   (except for the `schema.org` context, which is approximated by `"@vocab": "http://schema.org/"`),
+//!   and raise a [`JsonLdError::UnsupportedContext`] error otherwise;
+//! * scoped contexts, `@index` and `@id` containers are not supported;
+//! * [base directions] are ignored, as if `rdfDirection` was unset.
+//!
+//! [`Deserialize JSON-LD to RDF Algorithm`]: https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm
+//! [`IriCatalog`]: https://docs.rs/sophia/latest/sophia/iri_catalog/struct.IriCatalog.html
+//! [`JsonLdError::UnsupportedContext`]: ../error/enum.JsonLdError.html#variant.UnsupportedContext
+//! [base directions]: https://www.w3.org/TR/json-ld11/#base-direction
+
+use crate::error::*;
+use json::object::Object;
+use json::JsonValue;
+use sophia::iri_catalog::IriCatalog;
+use sophia::ns::{rdf, xsd};
+use sophia::syntax::dynamic::BoxQuad;
+use sophia_api::parser::QuadParser;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_iri::is_absolute_iri_ref;
+use sophia_term::iri::Iri;
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::sync::Arc;
+
+pub(crate) mod context;
+#[cfg(test)]
+mod test;
+
+use context::{is_keyword, Context, TermDefinition};
+
+/// The source returned by [`JsonLdParser`](struct.JsonLdParser.html).
+pub type JsonLdSource = std::vec::IntoIter<Result<BoxQuad, JsonLdError>>;
+
+/// A JSON-LD parser.
+#[derive(Clone, Debug, Default)]
+pub struct JsonLdParser {
+    /// The base IRI of the document.
+    pub base: Option<String>,
+    /// The catalog of local files from which remote contexts are read.
+    pub catalog: Option<Arc<IriCatalog>>,
+}
+
+impl<B: BufRead> QuadParser<B> for JsonLdParser {
+    type Source = JsonLdSource;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        let res = data
+            .read_to_string(&mut txt)
+            .map_err(JsonLdError::from)
+            .and_then(|_| Ok(json::parse(&txt)?))
+            .and_then(|doc| {
+                let mut engine = ToRdf::new();
+                engine.catalog = self.catalog.clone();
+                engine.process(&doc, self.base.as_deref())?;
+                Ok(engine.into_quads())
+            });
+        match res {
+            Ok(quads) => quads.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        }
+        .into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(JsonLdParser, QuadParser);
+
+/// Converts JSON-LD documents to RDF quads.
+///
+/// Several documents can be processed by the same engine;
+/// blank node identifiers are scoped to each document.
+pub(crate) struct ToRdf {
+    quads: Vec<BoxQuad>,
+    bnodes: HashMap<String, BoxTerm>,
+    fresh: usize,
+    /// The catalog used to load remote contexts
+    pub catalog: Option<Arc<IriCatalog>>,
+}
+
+impl ToRdf {
+    pub fn new() -> Self {
+        ToRdf {
+            quads: vec![],
+            bnodes: HashMap::new(),
+            fresh: 0,
+            catalog: None,
+        }
+    }
+
+    /// Convert the JSON-LD document `doc` to RDF.
+    pub fn process(&mut self, doc: &JsonValue, base: Option<&str>) -> Result<(), JsonLdError> {
+        self.bnodes.clear();
+        let ctx = Context {
+            catalog: self.catalog.clone(),
+            ..Context::new(base)
+        };
+        match doc {
+            JsonValue::Array(nodes) => {
+                for node in nodes {
+                    self.top_level(&ctx, node)?;
+                }
+                Ok(())
+            }
+            _ => self.top_level(&ctx, doc),
+        }
+    }
+
+    /// The quads produced so far.
+    pub fn into_quads(self) -> Vec<BoxQuad> {
+        self.quads
+    }
+
+    fn top_level(&mut self, ctx: &Context, node: &JsonValue) -> Result<(), JsonLdError> {
+        let obj = match node {
+            JsonValue::Object(obj) => obj,
+            _ => return Ok(()),
+        };
+        let ctx = match obj.get("@context") {
+            Some(local) => ctx.update(local)?,
+            None => ctx.clone(),
+        };
+        // a top-level object with only @graph (and @context) describes the default graph
+        let keys: Vec<_> = obj
+            .iter()
+            .map(|(k, _)| ctx.expand_iri(k, false, true))
+            .collect();
+        let only_graph = keys
+            .iter()
+            .all(|k| matches!(k.as_deref(), Some("@graph") | Some("@context")));
+        if only_graph {
+            for (key, value) in obj.iter() {
+                if key != "@context" {
+                    for node in as_array(value) {
+                        self.node_element(&ctx, node, &None)?;
+                    }
+                }
+            }
+            Ok(())
+        } else {
+            self.node_element(&ctx, node, &None).map(|_| ())
+        }
+    }
+
+    /// Process a node object, returning its subject.
+    fn node_element(
+        &mut self,
+        ctx: &Context,
+        node: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Option<BoxTerm>, JsonLdError> {
+        let obj = match node {
+            JsonValue::Object(obj) => obj,
+            _ => return Ok(None),
+        };
+        let ctx = match obj.get("@context") {
+            Some(local) => ctx.update(local)?,
+            None => ctx.clone(),
+        };
+        let mut subject = None;
+        for (key, value) in obj.iter() {
+            if ctx.expand_iri(key, false, true).as_deref() == Some("@id") {
+                let id = value.as_str().ok_or_else(|| invalid("@id", value))?;
+                subject = self.node_id(&ctx, id);
+                if subject.is_none() {
+                    // invalid IRI: the node and its properties are ignored
+                    return Ok(None);
+                }
+            }
+        }
+        let subject = match subject {
+            Some(subject) => subject,
+            None => self.fresh_bnode(),
+        };
+        self.node_properties(&ctx, obj, &subject, graph)?;
+        Ok(Some(subject))
+    }
+
+    /// Process the properties of the node object `obj` identified by `subject`.
+    fn node_properties(
+        &mut self,
+        ctx: &Context,
+        obj: &Object,
+        subject: &BoxTerm,
+        graph: &Option<BoxTerm>,
+    ) -> Result<(), JsonLdError> {
+        for (key, value) in obj.iter() {
+            let iri = match ctx.expand_iri(key, false, true) {
+                Some(iri) => iri,
+                None => continue,
+            };
+            match &iri[..] {
+                "@type" => {
+                    for t in as_array(value) {
+                        let t = t.as_str().ok_or_else(|| invalid("@type", t))?;
+                        if let Some(t) = ctx
+                            .expand_iri(t, true, true)
+                            .and_then(|t| self.node_id(ctx, &t))
+                        {
+                            self.emit(subject, &BoxTerm::copy(&rdf::type_), &t, graph);
+                        }
+                    }
+                }
+                "@graph" => {
+                    for node in as_array(value) {
+                        self.node_element(ctx, node, &Some(subject.clone()))?;
+                    }
+                }
+                "@included" => {
+                    for node in as_array(value) {
+                        self.node_element(ctx, node, graph)?;
+                    }
+                }
+                "@nest" => {
+                    for nested in as_array(value) {
+                        if let JsonValue::Object(nested) = nested {
+                            self.node_properties(ctx, nested, subject, graph)?;
+                        }
+                    }
+                }
+                "@reverse" => {
+                    if let JsonValue::Object(reverse) = value {
+                        for (key, value) in reverse.iter() {
+                            let predicate = match self.predicate(ctx, key) {
+                                Some(p) => p,
+                                None => continue,
+                            };
+                            for o in self.objects(ctx, ctx.terms.get(key), value, graph)? {
+                                self.emit(&o, &predicate, subject, graph);
+                            }
+                        }
+                    }
+                }
+                _ if is_keyword(&iri) => (),
+                _ => {
+                    let predicate = match self.predicate(ctx, key) {
+                        Some(p) => p,
+                        None => continue,
+                    };
+                    let def = ctx.terms.get(key);
+                    let reverse = def.map(|d| d.reverse).unwrap_or(false);
+                    for o in self.objects(ctx, def, value, graph)? {
+                        if reverse {
+                            self.emit(&o, &predicate, subject, graph);
+                        } else {
+                            self.emit(subject, &predicate, &o, graph);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the value of a property to a list of RDF terms.
+    fn objects(
+        &mut self,
+        ctx: &Context,
+        def: Option<&TermDefinition>,
+        value: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Vec<BoxTerm>, JsonLdError> {
+        let container = def.map(|d| &d.container[..]).unwrap_or(&[]);
+        if container.iter().any(|c| c == "@list") {
+            let items = self.items(ctx, def, value, graph)?;
+            return Ok(vec![self.list(items, graph)]);
+        }
+        if container.iter().any(|c| c == "@language") {
+            if let JsonValue::Object(map) = value {
+                let mut res = vec![];
+                for (lang, values) in map.iter() {
+                    for v in as_array(values) {
+                        if let Some(txt) = v.as_str() {
+                            res.extend(BoxTerm::new_literal_lang(txt, lang.to_lowercase()).ok());
+                        }
+                    }
+                }
+                return Ok(res);
+            }
+        }
+        self.items(ctx, def, value, graph)
+    }
+
+    /// Convert each item of `value` (if it is an array) or `value` itself to an RDF term.
+    fn items(
+        &mut self,
+        ctx: &Context,
+        def: Option<&TermDefinition>,
+        value: &JsonValue,
+        graph: &Option<BoxTerm>,
+    ) -> Result<Vec<BoxTerm>, JsonLdError> {
+        let mut res = vec![];
+        for item in as_array(value) {
+            if def.and_then(|d| d.type_.as_deref()) == Some("@json") {
+                res.extend(BoxTerm::new_literal_dt(item.dump(), rdf::JSON).ok());
+                continue;
+            }
+            match item {
+                JsonValue::Null => (),
+                JsonValue::Object(obj) => {
+                    let keyword = |k: &str| {
+                        obj.iter()
+                            .find(|(key, _)| ctx.expand_iri(key, false, true).as_deref() == Some(k))
+                            .map(|(_, v)| v)
+                    };
+                    if let Some(v) = keyword("@value") {
+                        res.extend(self.value_object(ctx, v, &keyword));
+                    } else if let Some(list) = keyword("@list") {
+                        let items = self.items(ctx, def, list, graph)?;
+                        res.push(self.list(items, graph));
+                    } else if let Some(set) = keyword("@set") {
+                        res.extend(self.items(ctx, def, set, graph)?);
+                    } else {
+                        res.extend(self.node_element(ctx, item, graph)?);
+                    }
+                }
+                _ => res.extend(self.scalar(ctx, def, item)),
+            }
+        }
+        Ok(res)
+    }
+
+    /// Convert a value object to a literal.
+    fn value_object<'a, F>(
+        &mut self,
+        ctx: &Context,
+        value: &JsonValue,
+        keyword: &F,
+    ) -> Option<BoxTerm>
+    where
+        F: Fn(&str) -> Option<&'a JsonValue>,
+    {
+        let type_ = keyword("@type").and_then(JsonValue::as_str);
+        if type_.map(|t| ctx.expand_iri(t, true, true)) == Some(Some("@json".to_string())) {
+            return BoxTerm::new_literal_dt(value.dump(), rdf::JSON).ok();
+        }
+        let (lexical, default_dt) = lexical_form(value)?;
+        match (type_, keyword("@language").and_then(JsonValue::as_str)) {
+            (Some(t), _) => {
+                let dt = ctx.expand_iri(t, true, true)?;
+                literal_dt(lexical, &dt)
+            }
+            (None, Some(lang)) if value.is_string() => {
+                BoxTerm::new_literal_lang(lexical, lang.to_lowercase()).ok()
+            }
+            (None, _) => literal_dt(lexical, &default_dt),
+        }
+    }
+
+    /// Convert a scalar value (string, number or boolean) according to its term definition.
+    fn scalar(
+        &mut self,
+        ctx: &Context,
+        def: Option<&TermDefinition>,
+        value: &JsonValue,
+    ) -> Option<BoxTerm> {
+        let type_ = def.and_then(|d| d.type_.as_deref());
+        if let (Some(txt), Some(t)) = (value.as_str(), type_) {
+            match t {
+                "@id" => return self.node_id(ctx, &ctx.expand_iri(txt, true, false)?),
+                "@vocab" => return self.node_id(ctx, &ctx.expand_iri(txt, true, true)?),
+                _ => (),
+            }
+        }
+        let (lexical, default_dt) = lexical_form(value)?;
+        match type_ {
+            Some(t) if !t.starts_with('@') => literal_dt(lexical, t),
+            _ if value.is_string() => {
+                let language = match def.and_then(|d| d.language.as_ref()) {
+                    Some(language) => language.as_ref(),
+                    None => ctx.language.as_ref(),
+                };
+                match language {
+                    Some(lang) => BoxTerm::new_literal_lang(lexical, &lang[..]).ok(),
+                    None => literal_dt(lexical, &default_dt),
+                }
+            }
+            _ => literal_dt(lexical, &default_dt),
+        }
+    }
+
+    /// Build an RDF list with the given items, and return its head.
+    fn list(&mut self, items: Vec<BoxTerm>, graph: &Option<BoxTerm>) -> BoxTerm {
+        let mut head = BoxTerm::copy(&rdf::nil);
+        for item in items.into_iter().rev() {
+            let node = self.fresh_bnode();
+            self.emit(&node, &BoxTerm::copy(&rdf::first), &item, graph);
+            self.emit(&node, &BoxTerm::copy(&rdf::rest), &head, graph);
+            head = node;
+        }
+        head
+    }
+
+    /// The predicate IRI for the given key (`None` if it does not expand to an absolute IRI).
+    fn predicate(&self, ctx: &Context, key: &str) -> Option<BoxTerm> {
+        let iri = ctx.expand_iri(key, false, true)?;
+        if is_absolute_iri_ref(&iri) && !iri.starts_with("_:") {
+            BoxTerm::new_iri(iri).ok()
+        } else {
+            None
+        }
+    }
+
+    /// The IRI or blank node identified by `id` (`None` if it is not a valid identifier).
+    fn node_id(&mut self, ctx: &Context, id: &str) -> Option<BoxTerm> {
+        if let Some(label) = id.strip_prefix("_:") {
+            if let Some(bnode) = self.bnodes.get(label) {
+                return Some(bnode.clone());
+            }
+            let bnode = self.fresh_bnode();
+            self.bnodes.insert(label.to_string(), bnode.clone());
+            return Some(bnode);
+        }
+        let iri = ctx.expand_iri(id, true, false)?;
+        if is_absolute_iri_ref(&iri) {
+            BoxTerm::new_iri(iri).ok()
+        } else {
+            None
+        }
+    }
+
+    fn fresh_bnode(&mut self) -> BoxTerm {
+        self.fresh += 1;
+        BoxTerm::new_bnode(format!("jsonld{}", self.fresh)).unwrap()
+    }
+
+    fn emit(&mut self, s: &BoxTerm, p: &BoxTerm, o: &BoxTerm, g: &Option<BoxTerm>) {
+        self.quads
+            .push(([s.clone(), p.clone(), o.clone()], g.clone()));
+    }
+}
+
+/// The lexical form of a scalar value, and its default datatype.
+fn lexical_form(value: &JsonValue) -> Option<(String, String)> {
+    match value {
+        JsonValue::Short(_) | JsonValue::String(_) => {
+            Some((value.as_str()?.to_string(), xsd::string.value().to_string()))
+        }
+        JsonValue::Boolean(b) => Some((b.to_string(), xsd::boolean.value().to_string())),
+        JsonValue::Number(_) => {
+            let txt = value.dump();
+            let f = value.as_f64()?;
+            if !txt.contains(['.', 'e', 'E']) {
+                Some((txt, xsd::integer.value().to_string()))
+            } else if f.fract() == 0.0 && f.abs() < 1e21 {
+                Some((format!("{}", f), xsd::integer.value().to_string()))
+            } else {
+                // canonical xsd:double, e.g. 1.5E0
+                let txt = format!("{:E}", f);
+                let txt = match txt.find('E') {
+                    Some(i) if !txt[..i].contains('.') => format!("{}.0{}", &txt[..i], &txt[i..]),
+                    _ => txt,
+                };
+                Some((txt, xsd::double.value().to_string()))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn literal_dt(lexical: String, datatype: &str) -> Option<BoxTerm> {
+    let dt = Iri::<Box<str>>::new(datatype).ok()?;
+    BoxTerm::new_literal_dt(lexical, dt).ok()
+}
+
+fn as_array(value: &JsonValue) -> Vec<&JsonValue> {
+    match value {
+        JsonValue::Array(values) => values.iter().collect(),
+        value => vec![value],
+    }
+}
+
+fn invalid(keyword: &str, value: &JsonValue) -> JsonLdError {
+    JsonLdError::InvalidJsonLd(format!("invalid {} value {}", keyword, value.dump()))
+}
@@ -0,0 +1,356 @@
+//! A subset of the [Context Processing Algorithm].
+//!
+//! [Context Processing Algorithm]: https://www.w3.org/TR/json-ld11-api/#context-processing-algorithm
+
+use crate::error::*;
+use json::object::Object;
+use json::JsonValue;
+use sophia::iri_catalog::IriCatalog;
+use sophia_iri::is_absolute_iri_ref;
+use sophia_iri::resolve::{IriParsed, Resolve};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The remote contexts that are known without being loaded,
+/// and the vocabulary mapping they are approximated with.
+const KNOWN_CONTEXTS: &[(&str, &str)] = &[
+    ("http://schema.org", "http://schema.org/"),
+    ("http://schema.org/", "http://schema.org/"),
+    ("https://schema.org", "http://schema.org/"),
+    ("https://schema.org/", "http://schema.org/"),
+];
+
+const KEYWORDS: &[&str] = &[
+    "@base",
+    "@container",
+    "@context",
+    "@direction",
+    "@graph",
+    "@id",
+    "@import",
+    "@included",
+    "@index",
+    "@json",
+    "@language",
+    "@list",
+    "@nest",
+    "@none",
+    "@prefix",
+    "@propagate",
+    "@protected",
+    "@reverse",
+    "@set",
+    "@type",
+    "@value",
+    "@version",
+    "@vocab",
+];
+
+/// Whether `txt` is a JSON-LD keyword.
+pub(crate) fn is_keyword(txt: &str) -> bool {
+    KEYWORDS.contains(&txt)
+}
+
+/// A term definition.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TermDefinition {
+    /// The IRI mapping (`None` if the term is explicitly mapped to null)
+    pub iri: Option<String>,
+    /// The type mapping (an IRI, `@id`, `@vocab` or `@json`)
+    pub type_: Option<String>,
+    /// The language mapping (`Some(None)` if the language is explicitly null)
+    pub language: Option<Option<String>>,
+    /// The container mapping
+    pub container: Vec<String>,
+    /// Whether this term is a reverse property
+    pub reverse: bool,
+}
+
+/// An active context.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Context {
+    pub base: Option<String>,
+    pub vocab: Option<String>,
+    pub language: Option<String>,
+    pub terms: HashMap<String, TermDefinition>,
+    /// The catalog used to load remote contexts
+    pub catalog: Option<Arc<IriCatalog>>,
+}
+
+impl Context {
+    /// Build an initial context with the given base IRI.
+    pub fn new(base: Option<&str>) -> Self {
+        Context {
+            base: base.map(str::to_string),
+            ..Context::default()
+        }
+    }
+
+    /// Compute the new active context resulting from applying `local` to this context.
+    pub fn update(&self, local: &JsonValue) -> Result<Context, JsonLdError> {
+        self.update_rec(local, &mut Vec::new())
+    }
+
+    /// Same as `update`, where `remote` is the stack of the remote contexts being loaded.
+    fn update_rec(
+        &self,
+        local: &JsonValue,
+        remote: &mut Vec<String>,
+    ) -> Result<Context, JsonLdError> {
+        match local {
+            JsonValue::Array(contexts) => {
+                let mut ctx = self.clone();
+                for local in contexts {
+                    ctx = ctx.update_rec(local, remote)?;
+                }
+                Ok(ctx)
+            }
+            JsonValue::Null => Ok(Context {
+                catalog: self.catalog.clone(),
+                ..Context::new(self.base.as_deref())
+            }),
+            JsonValue::Object(local) => self.update_with_object(local),
+            _ => match local.as_str() {
+                Some(iri) => match KNOWN_CONTEXTS.iter().find(|(k, _)| *k == iri) {
+                    Some((_, vocab)) => {
+                        let mut ctx = self.clone();
+                        ctx.vocab = Some(vocab.to_string());
+                        Ok(ctx)
+                    }
+                    None => self.update_with_remote(iri, remote),
+                },
+                None => Err(JsonLdError::InvalidJsonLd(format!(
+                    "invalid local context {}",
+                    local.dump()
+                ))),
+            },
+        }
+    }
+
+    /// Load the remote context `iri` from the catalog (if any), and apply it to this context.
+    fn update_with_remote(
+        &self,
+        iri: &str,
+        remote: &mut Vec<String>,
+    ) -> Result<Context, JsonLdError> {
+        // relative IRIs in a remote context are resolved against the IRI of that context
+        let iri = resolve_iri(
+            remote.last().or(self.base.as_ref()).map(String::as_str),
+            iri,
+        );
+        let path = match self.catalog.as_ref().and_then(|c| c.resolve(&iri)) {
+            Some(path) => path,
+            None => return Err(JsonLdError::UnsupportedContext(iri)),
+        };
+        if remote.contains(&iri) {
+            return Err(JsonLdError::InvalidJsonLd(format!(
+                "recursive context inclusion {}",
+                iri
+            )));
+        }
+        let doc = json::parse(&std::fs::read_to_string(path)?)?;
+        if !doc.has_key("@context") {
+            return Err(JsonLdError::InvalidJsonLd(format!(
+                "invalid remote context {}",
+                iri
+            )));
+        }
+        let mut local = doc["@context"].clone();
+        // @base is ignored in remote contexts
+        local.remove("@base");
+        remote.push(iri);
+        let ctx = self.update_rec(&local, remote);
+        remote.pop();
+        ctx
+    }
+
+    fn update_with_object(&self, local: &Object) -> Result<Context, JsonLdError> {
+        let mut ctx = self.clone();
+        if local.get("@import").is_some() {
+            return Err(JsonLdError::UnsupportedContext("@import".to_string()));
+        }
+        if let Some(base) = local.get("@base") {
+            ctx.base = match base.as_str() {
+                None if base.is_null() => None,
+                None => return Err(invalid("@base", base)),
+                Some(iri) => Some(ctx.resolve(iri)),
+            };
+        }
+        if let Some(vocab) = local.get("@vocab") {
+            ctx.vocab = match vocab.as_str() {
+                None if vocab.is_null() => None,
+                None => return Err(invalid("@vocab", vocab)),
+                Some(iri) => ctx.expand_iri(iri, true, true),
+            };
+        }
+        if let Some(language) = local.get("@language") {
+            ctx.language = match language.as_str() {
+                None if language.is_null() => None,
+                None => return Err(invalid("@language", language)),
+                Some(lang) => Some(lang.to_lowercase()),
+            };
+        }
+        let mut defined = HashMap::new();
+        for (term, _) in local.iter() {
+            if !term.starts_with('@') {
+                ctx.define(local, term, &mut defined)?;
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// The [Create Term Definition] algorithm (simplified).
+    ///
+    /// [Create Term Definition]: https://www.w3.org/TR/json-ld11-api/#create-term-definition
+    fn define(
+        &mut self,
+        local: &Object,
+        term: &str,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<(), JsonLdError> {
+        match defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return Err(JsonLdError::InvalidJsonLd(format!(
+                    "cyclic IRI mapping for {}",
+                    term
+                )))
+            }
+            None => {
+                defined.insert(term.to_string(), false);
+            }
+        }
+        let value = match local.get(term) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        let mut def = TermDefinition::default();
+        let id = match value {
+            JsonValue::Null => None,
+            JsonValue::Object(value) => {
+                if let Some(reverse) = value.get("@reverse") {
+                    def.reverse = true;
+                    Some(reverse)
+                } else {
+                    value.get("@id")
+                }
+            }
+            _ => Some(value),
+        };
+        if let Some(id) = id {
+            let id = id.as_str().ok_or_else(|| invalid("@id", id))?;
+            def.iri = self.expand_iri_for_definition(id, local, defined)?;
+        } else if !value.is_null() {
+            // no explicit @id: the term itself is (or expands to) the IRI
+            def.iri = self.expand_iri_for_definition(term, local, defined)?;
+            if def.iri.as_deref() == Some(term) && !term.contains(':') {
+                def.iri = self.vocab.as_ref().map(|v| format!("{}{}", v, term));
+            }
+        }
+        if let JsonValue::Object(value) = value {
+            if let Some(type_) = value.get("@type") {
+                let type_ = type_.as_str().ok_or_else(|| invalid("@type", type_))?;
+                def.type_ = if type_.starts_with('@') {
+                    Some(type_.to_string())
+                } else {
+                    self.expand_iri_for_definition(type_, local, defined)?
+                };
+            }
+            if let Some(language) = value.get("@language") {
+                def.language = Some(language.as_str().map(str::to_lowercase));
+            }
+            if let Some(container) = value.get("@container") {
+                def.container = match container {
+                    JsonValue::Array(c) => c
+                        .iter()
+                        .filter_map(JsonValue::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                    c => c.as_str().map(str::to_string).into_iter().collect(),
+                };
+            }
+        }
+        self.terms.insert(term.to_string(), def);
+        defined.insert(term.to_string(), true);
+        Ok(())
+    }
+
+    /// Expand an IRI in a term definition,
+    /// defining the terms it depends on if necessary.
+    fn expand_iri_for_definition(
+        &mut self,
+        value: &str,
+        local: &Object,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<Option<String>, JsonLdError> {
+        if let Some(i) = value.find(':') {
+            let prefix = &value[..i];
+            if local.get(prefix).is_some() && prefix != value {
+                self.define(local, prefix, defined)?;
+            }
+        } else if local.get(value).is_some() && defined.get(value).is_none() {
+            self.define(local, value, defined)?;
+        }
+        Ok(self.expand_iri(value, false, true))
+    }
+
+    /// The [IRI Expansion] algorithm.
+    ///
+    /// Return `None` if `value` is explicitly mapped to null.
+    ///
+    /// [IRI Expansion]: https://www.w3.org/TR/json-ld11-api/#iri-expansion
+    pub fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_string());
+        }
+        if vocab {
+            if let Some(def) = self.terms.get(value) {
+                return def.iri.clone();
+            }
+        }
+        if let Some(i) = value.find(':') {
+            let (prefix, suffix) = (&value[..i], &value[i + 1..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+            if let Some(TermDefinition { iri: Some(iri), .. }) = self.terms.get(prefix) {
+                return Some(format!("{}{}", iri, suffix));
+            }
+            if is_absolute_iri_ref(value) {
+                return Some(value.to_string());
+            }
+        }
+        if vocab {
+            if let Some(v) = &self.vocab {
+                return Some(format!("{}{}", v, value));
+            }
+        }
+        if document_relative {
+            return Some(self.resolve(value));
+        }
+        Some(value.to_string())
+    }
+
+    /// Resolve `iri` against the base IRI (if any).
+    pub fn resolve(&self, iri: &str) -> String {
+        resolve_iri(self.base.as_deref(), iri)
+    }
+}
+
+/// Resolve `iri` against `base` (if any).
+fn resolve_iri(base: Option<&str>, iri: &str) -> String {
+    match base {
+        Some(base) if !is_absolute_iri_ref(iri) => IriParsed::new(base)
+            .ok()
+            .and_then(|base| {
+                let resolved: Result<_, _> = base.resolve(iri);
+                resolved.ok().map(|iri| iri.to_string())
+            })
+            .unwrap_or_else(|| iri.to_string()),
+        _ => iri.to_string(),
+    }
+}
+
+fn invalid(keyword: &str, value: &JsonValue) -> JsonLdError {
+    JsonLdError::InvalidJsonLd(format!("invalid {} value {}", keyword, value.dump()))
+}
@@ -0,0 +1,173 @@
+use super::*;
+use sophia::dataset::{inmem::FastDataset, Dataset};
+use sophia::iri_catalog::IriCatalog;
+use sophia::quad::stream::QuadSource;
+use sophia::quad::Quad;
+use sophia_term::StaticTerm;
+
+fn parse(txt: &str) -> FastDataset {
+    let parser = JsonLdParser {
+        base: Some("http://example.org/doc".into()),
+        ..JsonLdParser::default()
+    };
+    parser.parse_str(txt).collect_quads().unwrap()
+}
+
+fn iri(iri: &'static str) -> StaticTerm {
+    StaticTerm::new_iri(iri).unwrap()
+}
+
+#[test]
+fn expanded_form() -> Result<(), Box<dyn std::error::Error>> {
+    let d = parse(
+        r##"[{
+            "@id": "#alice",
+            "@type": ["http://xmlns.com/foaf/0.1/Person"],
+            "http://xmlns.com/foaf/0.1/name": [{"@value": "Alice", "@language": "EN"}],
+            "http://xmlns.com/foaf/0.1/age": [{"@value": 42}],
+            "http://xmlns.com/foaf/0.1/knows": [{"@id": "_:b"}],
+            "http://example.org/list": [{"@list": [{"@value": true}]}]
+        }, {
+            "@id": "_:b",
+            "http://xmlns.com/foaf/0.1/name": [{"@value": "Bob"}]
+        }]"##,
+    );
+    let alice = iri("http://example.org/doc#alice");
+    let name = StaticTerm::new_literal_lang("Alice", "en")?;
+    let age = StaticTerm::new_literal_dt("42", xsd::integer)?;
+    assert!(d.contains(
+        &alice,
+        &iri("http://xmlns.com/foaf/0.1/name"),
+        &name,
+        None as Option<&StaticTerm>
+    )?);
+    assert!(d.contains(
+        &alice,
+        &iri("http://xmlns.com/foaf/0.1/age"),
+        &age,
+        None as Option<&StaticTerm>
+    )?);
+    // type, name, age, knows, list, 2 list triples, bob's name
+    assert_eq!(d.quads().count(), 8);
+    let bob = d
+        .quads_with_p(&iri("http://xmlns.com/foaf/0.1/knows"))
+        .next()
+        .unwrap()?
+        .o()
+        .value()
+        .to_string();
+    let bob_name = d
+        .quads_with_p(&iri("http://xmlns.com/foaf/0.1/name"))
+        .filter(|q| q.as_ref().unwrap().s().value()[..] == bob)
+        .count();
+    assert_eq!(bob_name, 1);
+    Ok(())
+}
+
+#[test]
+fn compacted_form() -> Result<(), Box<dyn std::error::Error>> {
+    let d = parse(
+        r#"{
+            "@context": {
+                "foaf": "http://xmlns.com/foaf/0.1/",
+                "xsd": "http://www.w3.org/2001/XMLSchema#",
+                "name": "foaf:name",
+                "homepage": {"@id": "foaf:homepage", "@type": "@id"},
+                "born": {"@id": "http://example.org/born", "@type": "xsd:date"},
+                "id": "@id"
+            },
+            "id": "http://example.org/alice",
+            "name": "Alice",
+            "homepage": "/alice/",
+            "born": "2000-01-01",
+            "unmapped": "ignored"
+        }"#,
+    );
+    let alice = iri("http://example.org/alice");
+    let none = None as Option<&StaticTerm>;
+    assert!(d.contains(
+        &alice,
+        &iri("http://xmlns.com/foaf/0.1/homepage"),
+        &iri("http://example.org/alice/"),
+        none
+    )?);
+    let born = StaticTerm::new_literal_dt("2000-01-01", xsd::date)?;
+    assert!(d.contains(&alice, &iri("http://example.org/born"), &born, none)?);
+    assert_eq!(d.quads().count(), 3);
+    Ok(())
+}
+
+#[test]
+fn named_graph_and_schema_org() -> Result<(), Box<dyn std::error::Error>> {
+    let d = parse(
+        r#"{
+            "@context": "https://schema.org",
+            "@id": "http://example.org/g",
+            "@graph": [{"@type": "Person", "name": "Alice", "height": 1.5}]
+        }"#,
+    );
+    let g = iri("http://example.org/g");
+    assert_eq!(d.quads_with_g(Some(&g)).count(), 3);
+    let height = StaticTerm::new_literal_dt("1.5E0", xsd::double)?;
+    assert!(d.quads_with_o(&height).next().is_some());
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    let mut source = parse_str(r#"{"@context": "http://example.org/context.jsonld"}"#);
+    assert!(matches!(
+        source.next(),
+        Some(Err(JsonLdError::UnsupportedContext(_)))
+    ));
+    let mut source = parse_str("{");
+    assert!(matches!(
+        source.next(),
+        Some(Err(JsonLdError::InvalidJsonLiteral(_)))
+    ));
+}
+
+#[test]
+fn remote_context_from_catalog() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("sophia-jsonld-ctx-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("ctx.jsonld"),
+        r#"{"@context": ["nested.jsonld", {"name": "http://xmlns.com/foaf/0.1/name"}]}"#,
+    )?;
+    std::fs::write(
+        dir.join("nested.jsonld"),
+        r#"{"@context": {"@base": "http://ignored.org/", "@vocab": "http://example.org/ns#"}}"#,
+    )?;
+    std::fs::write(dir.join("loop.jsonld"), r#"{"@context": "loop.jsonld"}"#)?;
+    let mut catalog = IriCatalog::new();
+    catalog.add_prefix("http://example.org/ctx/", &dir);
+    let parser = JsonLdParser {
+        base: Some("http://example.org/doc".into()),
+        catalog: Some(Arc::new(catalog)),
+    };
+    let res: Result<FastDataset, _> = parser
+        .parse_str(r#"{"@context": "ctx/ctx.jsonld", "@id": "alice", "name": "Alice", "age": 42}"#)
+        .collect_quads();
+    let looping = parser
+        .parse_str(r#"{"@context": "http://example.org/ctx/loop.jsonld"}"#)
+        .next();
+    let missing = parser
+        .parse_str(r#"{"@context": "http://example.org/other.jsonld"}"#)
+        .next();
+    std::fs::remove_dir_all(&dir)?;
+
+    let d = res?;
+    let alice = iri("http://example.org/alice");
+    assert_eq!(d.quads_with_s(&alice).count(), 2);
+    let name = iri("http://xmlns.com/foaf/0.1/name");
+    assert_eq!(d.quads_with_p(&name).count(), 1);
+    let age = iri("http://example.org/ns#age");
+    assert_eq!(d.quads_with_p(&age).count(), 1);
+    assert!(matches!(looping, Some(Err(JsonLdError::InvalidJsonLd(_)))));
+    assert!(matches!(
+        missing,
+        Some(Err(JsonLdError::UnsupportedContext(_)))
+    ));
+    Ok(())
+}
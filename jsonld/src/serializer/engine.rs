@@ -263,10 +263,14 @@ impl Engine {
                 let value = JsonValue::from(lit.txt().as_ref());
                 match lit.lang() {
                     Some(tag) => {
-                        json::object! {
+                        let mut obj = json::object! {
                             "@value": value,
                             "@language": JsonValue::from(tag.as_ref()),
+                        };
+                        if let Some(dir) = lit.dir() {
+                            obj["@direction"] = dir.as_str().into();
                         }
+                        obj
                     }
                     None => {
                         let txt = lit.txt().as_ref();
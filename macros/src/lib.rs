@@ -0,0 +1,215 @@
+//! Procedural macros for [Sophia](https://docs.rs/sophia),
+//! re-exported by the `sophia` crate when its `macros` feature is enabled.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use rio_api::model::{Literal, NamedNode, NamedOrBlankNode, Term};
+use rio_api::parser::TriplesParser;
+use rio_turtle::{NTriplesParser, TurtleError, TurtleParser};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Error, Ident, LitStr, Token};
+
+/// Embed an RDF graph in the binary, by parsing a file at compile time.
+///
+/// The macro expands to a `&'static [[StaticTerm; 3]]`,
+/// which implements the `Graph` trait,
+/// and can be used in `const` and `static` items.
+/// The terms of the graph are built at compile time, so using it requires no allocation,
+/// and its content never needs to be parsed at run time.
+///
+/// The macro accepts the following arguments:
+/// * `include` (required): the path of the file to parse,
+///   relative to the root directory of the crate (where its `Cargo.toml` is);
+///   files with the `.nt` extension are parsed as N-Triples, other files as Turtle;
+/// * `base`: the base IRI against which relative IRIs are resolved.
+///
+/// Syntax errors in the file are reported as compilation errors,
+/// and the crate is rebuilt whenever the file changes.
+///
+/// # Example
+/// ```
+/// use sophia::graph::Graph;
+/// use sophia::static_graph;
+/// use sophia::term::{StaticTerm, TTerm};
+/// use sophia::triple::Triple;
+///
+/// static DOAP: &[[StaticTerm; 3]] = static_graph!(include = "../sophia_doap.ttl");
+///
+/// let name = StaticTerm::new_iri_unchecked("http://usefulinc.com/ns/doap#name");
+/// let names: Vec<_> = DOAP
+///     .triples_with_p(&name)
+///     .map(|t| t.unwrap().o().value().to_string())
+///     .collect();
+/// assert!(names.contains(&"Sophia".to_string()));
+/// ```
+#[proc_macro]
+pub fn static_graph(input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(input as StaticGraphArgs);
+    match expand_static_graph(&args) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The arguments of [`static_graph!`](macro.static_graph.html).
+struct StaticGraphArgs {
+    include: LitStr,
+    base: Option<LitStr>,
+}
+
+impl Parse for StaticGraphArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut include = None;
+        let mut base = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value: LitStr = input.parse()?;
+            let slot = match key.to_string().as_str() {
+                "include" => &mut include,
+                "base" => &mut base,
+                _ => return Err(Error::new(key.span(), "expected `include` or `base`")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::new(key.span(), format!("duplicate `{}`", key)));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        let include = include.ok_or_else(|| input.error("missing `include = \"...\"`"))?;
+        Ok(StaticGraphArgs { include, base })
+    }
+}
+
+fn expand_static_graph(args: &StaticGraphArgs) -> syn::Result<TokenStream2> {
+    let span = args.include.span();
+    let mut path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    path.push(args.include.value());
+    let file = File::open(&path)
+        .map_err(|e| Error::new(span, format!("can not open {}: {}", path.display(), e)))?;
+    let reader = BufReader::new(file);
+    let base = args.base.as_ref().map(LitStr::value).unwrap_or_default();
+
+    let mut triples = vec![];
+    let mut on_triple = |t: rio_api::model::Triple| -> Result<(), TurtleError> {
+        let s = match t.subject {
+            NamedOrBlankNode::NamedNode(n) => iri(n),
+            NamedOrBlankNode::BlankNode(b) => bnode(b.id),
+        };
+        let p = iri(t.predicate);
+        let o = match t.object {
+            Term::NamedNode(n) => iri(n),
+            Term::BlankNode(b) => bnode(b.id),
+            Term::Literal(l) => literal(l),
+        };
+        triples.push(quote! { [#s, #p, #o] });
+        Ok(())
+    };
+    let parsed = if path.extension().is_some_and(|ext| ext == "nt") {
+        NTriplesParser::new(reader).and_then(|mut p| p.parse_all(&mut on_triple))
+    } else {
+        TurtleParser::new(reader, &base).and_then(|mut p| p.parse_all(&mut on_triple))
+    };
+    parsed.map_err(|e| Error::new(span, format!("can not parse {}: {}", path.display(), e)))?;
+
+    let path = path.to_string_lossy();
+    Ok(quote! {
+        {
+            // ensures that the crate is rebuilt when the file changes
+            const _: &str = include_str!(#path);
+            const TRIPLES: &[[::sophia::term::StaticTerm; 3]] = &[#(#triples),*];
+            TRIPLES
+        }
+    })
+}
+
+fn iri(n: NamedNode) -> TokenStream2 {
+    let iri = n.iri;
+    quote! {
+        ::sophia::term::Term::Iri(::sophia::term::iri::Iri::new_const_unchecked(#iri, None))
+    }
+}
+
+fn bnode(id: &str) -> TokenStream2 {
+    quote! {
+        ::sophia::term::Term::BNode(::sophia::term::blank_node::BlankNode::new_const_unchecked(#id))
+    }
+}
+
+fn literal(l: Literal) -> TokenStream2 {
+    let lit = match l {
+        Literal::Simple { value } => quote! {
+            ::sophia::term::literal::Literal::new_dt_const(
+                #value,
+                ::sophia::term::iri::Iri::new_const_unchecked(
+                    "http://www.w3.org/2001/XMLSchema#string",
+                    None,
+                ),
+            )
+        },
+        Literal::LanguageTaggedString { value, language } => quote! {
+            ::sophia::term::literal::Literal::new_lang_const_unchecked(#value, #language, None)
+        },
+        Literal::Typed { value, datatype } => {
+            let dt = datatype.iri;
+            quote! {
+                ::sophia::term::literal::Literal::new_dt_const(
+                    #value,
+                    ::sophia::term::iri::Iri::new_const_unchecked(#dt, None),
+                )
+            }
+        }
+    };
+    quote! { ::sophia::term::Term::Literal(#lit) }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(args: &str) -> syn::Result<String> {
+        let args: StaticGraphArgs = syn::parse_str(args)?;
+        expand_static_graph(&args).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn arguments() {
+        let args: StaticGraphArgs =
+            syn::parse_str(r#"include = "data.ttl", base = "http://example.org/""#).unwrap();
+        assert_eq!(args.include.value(), "data.ttl");
+        assert_eq!(args.base.unwrap().value(), "http://example.org/");
+        for bad in [
+            "",
+            r#"base = "http://example.org/""#,
+            r#"include = "a.ttl", include = "b.ttl""#,
+            r#"file = "a.ttl""#,
+            "include = 42",
+        ]
+        .iter()
+        {
+            assert!(syn::parse_str::<StaticGraphArgs>(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn expansion() {
+        let code = expand(r#"include = "../sophia_doap.ttl""#).unwrap();
+        assert!(code.contains("include_str !"));
+        assert!(code.contains(r#""http://usefulinc.com/ns/doap#name""#));
+        assert!(code.contains(r#"new_dt_const ("Sophia""#));
+        let err = expand(r#"include = "no_such_file.ttl""#).unwrap_err();
+        assert!(err.to_string().starts_with("can not open"), "{}", err);
+        let err = expand(r#"include = "Cargo.toml""#).unwrap_err();
+        assert!(err.to_string().starts_with("can not parse"), "{}", err);
+    }
+}
@@ -0,0 +1,327 @@
+//! Single-pass aggregations over quad sources.
+//!
+//! Counting things in a large file does not require loading it into a
+//! [`Dataset`] first: the functions of this module consume a [`QuadSource`]
+//! (typically a parser) in a single pass, and only keep the aggregated values.
+//! * [`group_by_subject`] folds the quads of each subject into an accumulator;
+//!   [`fold_subject_runs`] does the same with a single live accumulator,
+//!   for sources where the quads of a subject are contiguous
+//!   (as produced by most Turtle and TriG serializers);
+//! * [`count_distinct_predicates`] counts the quads using each predicate;
+//! * [`top_k_objects_per_predicate`] finds the most frequent objects of each predicate,
+//!   with a bounded number of counters per predicate.
+//!
+//! Graph names are ignored: quads from all graphs are aggregated together.
+//!
+//! # Example
+//! ```
+//! use sophia::aggregate::{count_distinct_predicates, top_k_objects_per_predicate};
+//! use sophia::parser::nq;
+//! use sophia::term::TTerm;
+//!
+//! let data = r#"
+//!     <tag:a> <tag:type> <tag:Person> .
+//!     <tag:b> <tag:type> <tag:Person> .
+//!     <tag:c> <tag:type> <tag:Robot> <tag:g> .
+//!     <tag:a> <tag:knows> <tag:b> .
+//! "#;
+//! let counts = count_distinct_predicates(nq::parse_str(data))?;
+//! assert_eq!(counts.len(), 2);
+//! assert_eq!(counts.values().sum::<usize>(), 4);
+//!
+//! let top = top_k_objects_per_predicate(nq::parse_str(data), 1, 100)?;
+//! let types = top.iter().find(|(p, _)| p.value() == "tag:type").unwrap().1;
+//! assert_eq!(types[0].0.value(), "tag:Person");
+//! assert_eq!(types[0].1, 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`group_by_subject`]: fn.group_by_subject.html
+//! [`fold_subject_runs`]: fn.fold_subject_runs.html
+//! [`count_distinct_predicates`]: fn.count_distinct_predicates.html
+//! [`top_k_objects_per_predicate`]: fn.top_k_objects_per_predicate.html
+//! [`QuadSource`]: ../quad/stream/trait.QuadSource.html
+//! [`Dataset`]: ../dataset/trait.Dataset.html
+
+use std::collections::HashMap;
+
+use sophia_api::quad::streaming_mode::StreamedQuad;
+use sophia_api::term::{term_cmp, term_eq, CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use crate::quad::stream::QuadSource;
+use crate::quad::Quad;
+
+/// Fold the quads of `source` by subject.
+///
+/// Each subject gets its own copy of `init`,
+/// which is then updated by `fold` for every quad having this subject.
+///
+/// Memory is proportional to the number of distinct subjects;
+/// see [`fold_subject_runs`](fn.fold_subject_runs.html)
+/// for a bounded-memory alternative.
+pub fn group_by_subject<QS, A, F>(
+    mut source: QS,
+    init: A,
+    mut fold: F,
+) -> Result<HashMap<BoxTerm, A>, QS::Error>
+where
+    QS: QuadSource,
+    A: Clone,
+    F: FnMut(&mut A, StreamedQuad<QS::Quad>),
+{
+    let mut groups: HashMap<BoxTerm, A> = HashMap::new();
+    source.for_each_quad(|q| {
+        let acc = groups
+            .entry(BoxTerm::copy(q.s()))
+            .or_insert_with(|| init.clone());
+        fold(acc, q);
+    })?;
+    Ok(groups)
+}
+
+/// Fold runs of consecutive quads sharing the same subject.
+///
+/// Each run starts from a copy of `init`, is updated by `fold` for every quad of the run,
+/// and is passed to `emit` (with its subject) as soon as the run ends.
+/// Only one accumulator is alive at any time.
+///
+/// If the quads of a subject are not contiguous in `source`,
+/// that subject is emitted once per run.
+pub fn fold_subject_runs<QS, A, F, E>(
+    mut source: QS,
+    init: A,
+    mut fold: F,
+    mut emit: E,
+) -> Result<(), QS::Error>
+where
+    QS: QuadSource,
+    A: Clone,
+    F: FnMut(&mut A, StreamedQuad<QS::Quad>),
+    E: FnMut(BoxTerm, A),
+{
+    let mut current: Option<(BoxTerm, A)> = None;
+    source.for_each_quad(|q| {
+        match &current {
+            Some((s, _)) if term_eq(s, q.s()) => (),
+            _ => {
+                let next = (BoxTerm::copy(q.s()), init.clone());
+                if let Some((s, acc)) = current.replace(next) {
+                    emit(s, acc);
+                }
+            }
+        }
+        if let Some((_, acc)) = current.as_mut() {
+            fold(acc, q);
+        }
+    })?;
+    if let Some((s, acc)) = current {
+        emit(s, acc);
+    }
+    Ok(())
+}
+
+/// Count the quads of `source` using each predicate.
+///
+/// The number of distinct predicates is the length of the returned map.
+/// Memory is proportional to the number of distinct predicates.
+pub fn count_distinct_predicates<QS>(mut source: QS) -> Result<HashMap<BoxTerm, usize>, QS::Error>
+where
+    QS: QuadSource,
+{
+    let mut counts: HashMap<BoxTerm, usize> = HashMap::new();
+    source.for_each_quad(|q| {
+        *counts.entry(BoxTerm::copy(q.p())).or_default() += 1;
+    })?;
+    Ok(counts)
+}
+
+/// The most frequent objects of each predicate,
+/// as returned by [`top_k_objects_per_predicate`](fn.top_k_objects_per_predicate.html).
+pub type TopObjects = HashMap<BoxTerm, Vec<(BoxTerm, usize)>>;
+
+/// Find the (at most) `k` most frequent objects of each predicate of `source`,
+/// with their number of occurrences, most frequent first.
+///
+/// At most `capacity` objects (or `k` if it is greater) are counted for each predicate,
+/// using the *Space-Saving* algorithm:
+/// when a new object is met and all counters are in use,
+/// it replaces the least frequent object, inheriting its count.
+/// As a consequence:
+/// * if a predicate has at most `capacity` distinct objects, its result is exact;
+/// * otherwise, any object occurring more than `n / capacity` times
+///   (where `n` is the number of quads with that predicate) is reported,
+///   and its count over-estimates its actual number of occurrences by at most `n / capacity`.
+///
+/// Ties are broken by [term order](../term/fn.term_cmp.html).
+pub fn top_k_objects_per_predicate<QS>(
+    mut source: QS,
+    k: usize,
+    capacity: usize,
+) -> Result<TopObjects, QS::Error>
+where
+    QS: QuadSource,
+{
+    let capacity = capacity.max(k).max(1);
+    let mut counters: HashMap<BoxTerm, SpaceSaving> = HashMap::new();
+    source.for_each_quad(|q| {
+        counters
+            .entry(BoxTerm::copy(q.p()))
+            .or_insert_with(|| SpaceSaving::new(capacity))
+            .insert(q.o());
+    })?;
+    Ok(counters
+        .into_iter()
+        .map(|(p, counter)| (p, counter.top(k)))
+        .collect())
+}
+
+/// Bounded set of frequency counters (Metwally et al., 2005).
+struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<BoxTerm, usize>,
+}
+
+impl SpaceSaving {
+    fn new(capacity: usize) -> Self {
+        SpaceSaving {
+            capacity,
+            counts: HashMap::new(),
+        }
+    }
+
+    fn insert<T: TTerm + ?Sized>(&mut self, term: &T) {
+        let term = BoxTerm::copy(term);
+        if let Some(count) = self.counts.get_mut(&term) {
+            *count += 1;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(term, 1);
+        } else {
+            let (evicted, min) = self
+                .counts
+                .iter()
+                .min_by(|a, b| a.1.cmp(b.1).then_with(|| term_cmp(a.0, b.0)))
+                .map(|(t, c)| (t.clone(), *c))
+                .unwrap();
+            self.counts.remove(&evicted);
+            self.counts.insert(term, min + 1);
+        }
+    }
+
+    fn top(self, k: usize) -> Vec<(BoxTerm, usize)> {
+        let mut counts: Vec<_> = self.counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| term_cmp(&a.0, &b.0)));
+        counts.truncate(k);
+        counts
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+
+    const NQ: &str = r#"
+        <tag:a> <tag:type> <tag:Person> .
+        <tag:a> <tag:name> "a" .
+        <tag:b> <tag:type> <tag:Person> <tag:g> .
+        <tag:b> <tag:name> "b" .
+        <tag:a> <tag:knows> <tag:b> .
+        <tag:c> <tag:type> <tag:Robot> .
+    "#;
+
+    fn value(t: &BoxTerm) -> String {
+        t.value().to_string()
+    }
+
+    #[test]
+    fn by_subject() -> Result<(), Box<dyn std::error::Error>> {
+        let groups = group_by_subject(nq::parse_str(NQ), 0, |n, _| *n += 1)?;
+        let mut sizes: Vec<_> = groups.iter().map(|(s, n)| (value(s), *n)).collect();
+        sizes.sort();
+        assert_eq!(
+            sizes,
+            vec![
+                ("tag:a".to_string(), 3),
+                ("tag:b".to_string(), 2),
+                ("tag:c".to_string(), 1)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn subject_runs() -> Result<(), Box<dyn std::error::Error>> {
+        let mut runs = vec![];
+        fold_subject_runs(
+            nq::parse_str(NQ),
+            vec![],
+            |preds, q| preds.push(value(&BoxTerm::copy(q.p()))),
+            |s, preds| runs.push((value(&s), preds.len())),
+        )?;
+        let expected: Vec<_> = [("tag:a", 2), ("tag:b", 2), ("tag:a", 1), ("tag:c", 1)]
+            .iter()
+            .map(|(s, n)| (s.to_string(), *n))
+            .collect();
+        assert_eq!(runs, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn predicates() -> Result<(), Box<dyn std::error::Error>> {
+        let counts = count_distinct_predicates(nq::parse_str(NQ))?;
+        let mut counts: Vec<_> = counts.iter().map(|(p, n)| (value(p), *n)).collect();
+        counts.sort();
+        assert_eq!(
+            counts,
+            vec![
+                ("tag:knows".to_string(), 1),
+                ("tag:name".to_string(), 2),
+                ("tag:type".to_string(), 3)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn top_k_exact() -> Result<(), Box<dyn std::error::Error>> {
+        let top = top_k_objects_per_predicate(nq::parse_str(NQ), 2, 10)?;
+        let types: Vec<_> = top
+            .iter()
+            .find(|(p, _)| p.value() == "tag:type")
+            .unwrap()
+            .1
+            .iter()
+            .map(|(o, n)| (value(o), *n))
+            .collect();
+        assert_eq!(
+            types,
+            vec![("tag:Person".to_string(), 2), ("tag:Robot".to_string(), 1)]
+        );
+        let names = &top.iter().find(|(p, _)| p.value() == "tag:name").unwrap().1;
+        assert_eq!(names.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn top_k_bounded() {
+        // a frequent object among many rare ones is still found with few counters
+        let mut counter = SpaceSaving::new(4);
+        for i in 0..1000 {
+            let o = if i % 3 == 0 {
+                "tag:frequent".to_string()
+            } else {
+                format!("tag:rare{}", i)
+            };
+            counter.insert(&BoxTerm::new_iri(o).unwrap());
+            assert!(counter.counts.len() <= 4);
+        }
+        let top = counter.top(1);
+        assert_eq!(top[0].0.value(), "tag:frequent");
+        assert!(top[0].1 >= 334);
+    }
+}
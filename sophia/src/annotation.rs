@@ -0,0 +1,244 @@
+//! Annotating triples with metadata (provenance, confidence scores...),
+//! in the spirit of [RDF-star] annotations.
+//!
+//! As quoted triples can not be represented as terms yet,
+//! a triple is annotated through a blank node standing for it,
+//! described with the [RDF reification] vocabulary
+//! (`rdf:type rdf:Statement`, `rdf:subject`, `rdf:predicate` and `rdf:object`).
+//! This is the same encoding as the one produced by the [`nq12`] and [`nt12`] parsers
+//! for quoted triples,
+//! so that the annotations of a parsed Turtle-star or N-Triples-star document
+//! can be retrieved with [`annotations`].
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::term::StaticTerm;
+//! use sophia::annotation::{annotations, assert_annotated};
+//!
+//! let alice = StaticTerm::new_iri("http://example.org/alice")?;
+//! let knows = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/knows")?;
+//! let bob = StaticTerm::new_iri("http://example.org/bob")?;
+//! let source = StaticTerm::new_iri("http://purl.org/dc/terms/source")?;
+//! let wiki = StaticTerm::new_iri("http://example.org/wiki")?;
+//!
+//! let mut g = FastGraph::new();
+//! assert_annotated(&mut g, &[alice, knows, bob], &source, &wiki)?;
+//! assert!(g.contains(&alice, &knows, &bob)?);
+//! let found = annotations(&g, &[alice, knows, bob])?;
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(found[0][1], wiki);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [RDF-star]: https://www.w3.org/2021/12/rdf-star.html#annotation-syntax
+//! [RDF reification]: https://www.w3.org/TR/rdf11-mt/#reification
+//! [`nq12`]: ../parser/nq12/index.html
+//! [`nt12`]: ../parser/nt12/index.html
+//! [`annotations`]: fn.annotations.html
+
+use sophia_api::graph::{GResult, Graph, MutableGraph};
+use sophia_api::ns::rdf;
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_api::triple::Triple;
+use sophia_term::RcTerm;
+
+/// Return the nodes standing for `triple` in `graph`
+/// (i.e. having `triple`'s subject, predicate and object as their
+/// `rdf:subject`, `rdf:predicate` and `rdf:object`, respectively).
+pub fn reifiers<G, T>(graph: &G, triple: &T) -> GResult<G, Vec<RcTerm>>
+where
+    G: Graph,
+    T: Triple,
+{
+    let mut nodes = vec![];
+    for t in graph.triples_with_po(&rdf::subject, triple.s()) {
+        let node = RcTerm::copy(t?.s());
+        if graph.contains(&node, &rdf::predicate, triple.p())?
+            && graph.contains(&node, &rdf::object, triple.o())?
+            && !nodes.iter().any(|n| n == &node)
+        {
+            nodes.push(node);
+        }
+    }
+    Ok(nodes)
+}
+
+/// Return the annotations of `triple` in `graph`, as (predicate, value) pairs.
+///
+/// The triples describing the reification itself
+/// (`rdf:type rdf:Statement`, `rdf:subject`, `rdf:predicate` and `rdf:object`)
+/// are not considered as annotations.
+/// Note that `triple` itself may or may not be asserted in `graph`.
+pub fn annotations<G, T>(graph: &G, triple: &T) -> GResult<G, Vec<[RcTerm; 2]>>
+where
+    G: Graph,
+    T: Triple,
+{
+    let mut found = vec![];
+    for node in reifiers(graph, triple)? {
+        for t in graph.triples_with_s(&node) {
+            let t = t?;
+            let (p, o) = (t.p(), t.o());
+            if is_reification(p, o) {
+                continue;
+            }
+            let annotation = [RcTerm::copy(p), RcTerm::copy(o)];
+            if !found.iter().any(|a| a == &annotation) {
+                found.push(annotation);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Annotate `triple` in `graph`, with the given predicate and value,
+/// without asserting `triple` itself (as with an RDF-star quoted triple).
+///
+/// Reuse the node standing for `triple` if any, or create a new blank node otherwise;
+/// return this node.
+///
+/// Errors raised while querying `graph` are wrapped in `SourceError`,
+/// errors raised while inserting into `graph` are wrapped in `SinkError`.
+pub fn annotate<G, T, P, V>(
+    graph: &mut G,
+    triple: &T,
+    predicate: &P,
+    value: &V,
+) -> StreamResult<RcTerm, G::Error, G::MutationError>
+where
+    G: MutableGraph,
+    T: Triple,
+    P: TTerm + ?Sized,
+    V: TTerm + ?Sized,
+{
+    let node = match reifiers(graph, triple).map_err(SourceError)?.pop() {
+        Some(node) => node,
+        None => {
+            let node = fresh_bnode(graph);
+            graph
+                .insert(&node, &rdf::type_, &rdf::Statement)
+                .map_err(SinkError)?;
+            graph
+                .insert(&node, &rdf::subject, triple.s())
+                .map_err(SinkError)?;
+            graph
+                .insert(&node, &rdf::predicate, triple.p())
+                .map_err(SinkError)?;
+            graph
+                .insert(&node, &rdf::object, triple.o())
+                .map_err(SinkError)?;
+            node
+        }
+    };
+    graph.insert(&node, predicate, value).map_err(SinkError)?;
+    Ok(node)
+}
+
+/// Assert `triple` in `graph`, and annotate it with the given predicate and value
+/// (as with the RDF-star annotation syntax `s p o {| predicate value |}`).
+///
+/// See [`annotate`](fn.annotate.html).
+pub fn assert_annotated<G, T, P, V>(
+    graph: &mut G,
+    triple: &T,
+    predicate: &P,
+    value: &V,
+) -> StreamResult<RcTerm, G::Error, G::MutationError>
+where
+    G: MutableGraph,
+    T: Triple,
+    P: TTerm + ?Sized,
+    V: TTerm + ?Sized,
+{
+    graph
+        .insert(triple.s(), triple.p(), triple.o())
+        .map_err(SinkError)?;
+    annotate(graph, triple, predicate, value)
+}
+
+/// Whether (`p`, `o`) belongs to the reification vocabulary.
+fn is_reification<P, O>(p: &P, o: &O) -> bool
+where
+    P: TTerm + ?Sized,
+    O: TTerm + ?Sized,
+{
+    term_eq(p, &rdf::subject)
+        || term_eq(p, &rdf::predicate)
+        || term_eq(p, &rdf::object)
+        || (term_eq(p, &rdf::type_) && term_eq(o, &rdf::Statement))
+}
+
+/// Return a blank node not used in `graph`.
+fn fresh_bnode<G: Graph>(graph: &G) -> RcTerm {
+    (0..)
+        .map(|i| RcTerm::new_bnode_unchecked(format!("annotated{}", i)))
+        .find(|node| {
+            graph.triples_with_s(node).next().is_none()
+                && graph.triples_with_o(node).next().is_none()
+        })
+        .unwrap()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::nt12::NTriples12Parser;
+    use crate::test_util::t;
+    use sophia_api::parser::TripleParser;
+    use sophia_api::triple::stream::TripleSource;
+
+    #[test]
+    fn annotate_and_query() -> Result<(), Box<dyn std::error::Error>> {
+        let triple = [t("a"), t("b"), t("c")];
+        let mut g = FastGraph::new();
+        let n1 = annotate(&mut g, &triple, &t("source"), &t("x"))?;
+        assert!(!g.contains(&t("a"), &t("b"), &t("c"))?);
+        let n2 = assert_annotated(&mut g, &triple, &t("confidence"), &t("high"))?;
+        assert_eq!(n1, n2);
+        assert!(g.contains(&t("a"), &t("b"), &t("c"))?);
+        assert_eq!(g.triples().count(), 7);
+
+        let mut found = annotations(&g, &triple)?;
+        found.sort_by_key(|[p, _]| p.value().to_string());
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0][0], t("confidence"));
+        assert_eq!(found[1][1], t("x"));
+        assert!(annotations(&g, &[t("a"), t("b"), t("d")])?.is_empty());
+
+        // another triple gets another node
+        let n3 = annotate(&mut g, &[t("a"), t("b"), t("d")], &t("source"), &t("y"))?;
+        assert_ne!(n1, n3);
+        assert_eq!(reifiers(&g, &triple)?, vec![n1]);
+        Ok(())
+    }
+
+    #[test]
+    fn parsed_annotations() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = "<< <tag:a> <tag:b> <tag:c> >> <tag:source> <tag:x>.\n\
+                  <tag:a> <tag:b> <tag:c>.\n";
+        let mut g = FastGraph::new();
+        NTriples12Parser {
+            star: true,
+            ..NTriples12Parser::default()
+        }
+        .parse_str(nt)
+        .add_to_graph(&mut g)?;
+        let found = annotations(&g, &[t("a"), t("b"), t("c")])?;
+        assert_eq!(
+            found,
+            vec![[RcTerm::copy(&t("source")), RcTerm::copy(&t("x"))]]
+        );
+        // new annotations reuse the node created by the parser
+        annotate(&mut g, &[t("a"), t("b"), t("c")], &t("source"), &t("y"))?;
+        assert_eq!(annotations(&g, &[t("a"), t("b"), t("c")])?.len(), 2);
+        assert_eq!(g.triples_with_p(&rdf::subject).count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,510 @@
+//! Random generation of terms, triples and graphs, for property-based testing.
+//!
+//! [`Gen`] is a small, seedable pseudo-random generator,
+//! and [`Arbitrary`] is implemented for [`BoxTerm`] and triples (`[BoxTerm; 3]`).
+//! Generated terms are always valid:
+//! IRIs are absolute (and include non-ASCII characters and percent-encodings),
+//! language tags are well-formed,
+//! literals of XSD datatypes have a valid lexical form,
+//! and string literals include characters that must be escaped in most syntaxes.
+//!
+//! Graphs are generated by [`arbitrary_graph`],
+//! according to a [`GraphShape`] constraining their size,
+//! the number of distinct nodes and predicates,
+//! the proportion of literals and blank nodes,
+//! and their [topology](enum.Topology.html) (random, chain or tree).
+//! [`shrink_graph`] produces smaller variants of a failing graph.
+//!
+//! The API mirrors the `Arbitrary` trait of [quickcheck],
+//! so that wrapping these generators in a quickcheck or [proptest] strategy
+//! only requires seeding a [`Gen`] with a random number from those frameworks.
+//! This module does not depend on either framework (nor on the `arbitrary` crate),
+//! and its [`Arbitrary`] trait is distinct from theirs.
+//!
+//! This module requires the `arbitrary` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::arbitrary::{arbitrary_graph, Arbitrary, Gen, GraphShape, Topology};
+//! use sophia::term::{BoxTerm, TTerm};
+//!
+//! let mut gen = Gen::new(42);
+//! let term = BoxTerm::arbitrary(&mut gen);
+//! assert!(!term.value().is_empty());
+//!
+//! let mut shape = GraphShape::default();
+//! shape.set_triples(10).set_topology(Topology::Tree);
+//! let triples = arbitrary_graph(&mut gen, &shape);
+//! assert_eq!(triples.len(), 10);
+//!
+//! // the same seed produces the same graph
+//! assert_eq!(arbitrary_graph(&mut Gen::new(7), &shape), arbitrary_graph(&mut Gen::new(7), &shape));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Gen`]: struct.Gen.html
+//! [`Arbitrary`]: trait.Arbitrary.html
+//! [`BoxTerm`]: ../term/type.BoxTerm.html
+//! [`arbitrary_graph`]: fn.arbitrary_graph.html
+//! [`GraphShape`]: struct.GraphShape.html
+//! [`shrink_graph`]: fn.shrink_graph.html
+//! [quickcheck]: https://docs.rs/quickcheck
+//! [proptest]: https://docs.rs/proptest
+
+use sophia_api::ns::xsd;
+use sophia_term::BoxTerm;
+
+/// A seedable pseudo-random generator (SplitMix64).
+///
+/// The [size](#method.size) of a generator bounds the size of generated values
+/// (length of strings, number of items...).
+#[derive(Clone, Debug)]
+pub struct Gen {
+    state: u64,
+    size: usize,
+}
+
+impl Gen {
+    /// A new generator with the given seed, and size 10.
+    pub fn new(seed: u64) -> Gen {
+        Gen::with_size(seed, 10)
+    }
+
+    /// A new generator with the given seed and size.
+    pub fn with_size(seed: u64, size: usize) -> Gen {
+        Gen { state: seed, size }
+    }
+
+    /// The size of this generator.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// A random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `0..n` (`n` must not be 0).
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True with the given probability (between 0 and 1).
+    pub fn ratio(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// A random element of `items` (which must not be empty).
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Types that can be randomly generated.
+pub trait Arbitrary: Sized {
+    /// Generate a random value.
+    fn arbitrary(gen: &mut Gen) -> Self;
+}
+
+impl Arbitrary for BoxTerm {
+    /// Generate an IRI, a blank node or a literal.
+    fn arbitrary(gen: &mut Gen) -> BoxTerm {
+        match gen.below(3) {
+            0 => arbitrary_iri(gen),
+            1 => arbitrary_bnode(gen),
+            _ => arbitrary_literal(gen),
+        }
+    }
+}
+
+impl Arbitrary for [BoxTerm; 3] {
+    /// Generate a (non-generalized) triple.
+    fn arbitrary(gen: &mut Gen) -> [BoxTerm; 3] {
+        let s = if gen.ratio(0.5) {
+            arbitrary_iri(gen)
+        } else {
+            arbitrary_bnode(gen)
+        };
+        [s, arbitrary_iri(gen), BoxTerm::arbitrary(gen)]
+    }
+}
+
+const ALPHA: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'Z',
+];
+const DIGITS: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+/// Characters allowed in IRIs, beyond letters and digits.
+const IRI_EXTRA: &[&str] = &["-", ".", "_", "~", "é", "漢", "%20", "%C3%A9", "(", "'"];
+/// Characters in strings, beyond letters and digits.
+const STRING_EXTRA: &[&str] = &[
+    " ", "\"", "'", "\\", "\n", "\r", "\t", "é", "ñ", "漢", "😀", "#", "<", ">",
+];
+
+/// A random string of up to `max` elements (at least `min`),
+/// taken from letters and digits, and from `extra`.
+fn arbitrary_string(gen: &mut Gen, min: usize, max: usize, extra: &[&str]) -> String {
+    let len = min + gen.below(max.saturating_sub(min) + 1);
+    let mut txt = String::new();
+    for _ in 0..len {
+        match gen.below(4) {
+            0 if !extra.is_empty() => txt.push_str(gen.choose::<&str>(extra)),
+            1 => txt.push(*gen.choose(DIGITS)),
+            _ => txt.push(*gen.choose(ALPHA)),
+        }
+    }
+    txt
+}
+
+/// A random (absolute) IRI.
+pub fn arbitrary_iri(gen: &mut Gen) -> BoxTerm {
+    const PREFIXES: &[&str] = &[
+        "http://example.org/",
+        "https://example.com/a/b#",
+        "tag:sophia.rs,2020:",
+        "urn:x-test:",
+        "http://[::1]:8080/",
+    ];
+    let prefix = gen.choose(PREFIXES);
+    let max = gen.size().max(1);
+    let suffix = arbitrary_string(gen, 0, max, IRI_EXTRA);
+    BoxTerm::new_iri(format!("{}{}", prefix, suffix)).expect("generated IRI should be valid")
+}
+
+/// A random blank node.
+pub fn arbitrary_bnode(gen: &mut Gen) -> BoxTerm {
+    let n = gen.below(gen.size().max(1) * 10);
+    BoxTerm::new_bnode(format!("b{}", n)).expect("generated bnode ID should be valid")
+}
+
+/// A random literal (plain, language-tagged or typed with an XSD or custom datatype).
+pub fn arbitrary_literal(gen: &mut Gen) -> BoxTerm {
+    const LANGS: &[&str] = &["en", "fr-BE", "zh-Hant-TW", "x-private", "de-1996"];
+    let max = gen.size();
+    let term = match gen.below(8) {
+        0 => BoxTerm::new_literal_lang(
+            arbitrary_string(gen, 0, max, STRING_EXTRA),
+            *gen.choose(LANGS),
+        ),
+        1 => {
+            let lex = format!(
+                "{}{}",
+                gen.choose(&["", "-", "+"]),
+                gen.next_u64() % 100_000
+            );
+            BoxTerm::new_literal_dt(lex, xsd::integer)
+        }
+        2 => {
+            let lex = format!("{}.{}", gen.next_u64() % 1000, gen.next_u64() % 1000);
+            BoxTerm::new_literal_dt(lex, xsd::decimal)
+        }
+        3 => {
+            let lex = match gen.below(6) {
+                0 => "NaN".to_string(),
+                1 => "-INF".to_string(),
+                _ => format!("{}E{}", gen.next_u64() % 100, gen.below(20) as i64 - 10),
+            };
+            BoxTerm::new_literal_dt(lex, xsd::double)
+        }
+        4 => BoxTerm::new_literal_dt(*gen.choose(&["true", "false", "1", "0"]), xsd::boolean),
+        5 => {
+            let lex = format!(
+                "{:04}-{:02}-{:02}{}",
+                gen.below(3000),
+                1 + gen.below(12),
+                1 + gen.below(28),
+                gen.choose(&["", "Z", "+02:00"])
+            );
+            BoxTerm::new_literal_dt(lex, xsd::date)
+        }
+        6 => {
+            let dt = arbitrary_iri(gen);
+            BoxTerm::new_literal_dt(arbitrary_string(gen, 0, max, STRING_EXTRA), dt)
+        }
+        _ => BoxTerm::new_literal_dt(arbitrary_string(gen, 0, max, STRING_EXTRA), xsd::string),
+    };
+    term.expect("generated literal should be valid")
+}
+
+/// How nodes are linked in a graph generated by [`arbitrary_graph`](fn.arbitrary_graph.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Subjects and objects are picked at random among the nodes.
+    Random,
+    /// Each triple links a node to the next one, forming a chain
+    /// (as long as there are enough nodes).
+    Chain,
+    /// Each node, except the root, is the object of exactly one triple
+    /// whose subject is an earlier node (as long as there are enough nodes).
+    Tree,
+}
+
+/// Constraints on the graphs generated by [`arbitrary_graph`](fn.arbitrary_graph.html).
+#[derive(Clone, Debug)]
+pub struct GraphShape {
+    triples: usize,
+    nodes: usize,
+    predicates: usize,
+    literal_ratio: f64,
+    bnode_ratio: f64,
+    topology: Topology,
+}
+
+impl Default for GraphShape {
+    fn default() -> GraphShape {
+        GraphShape {
+            triples: 20,
+            nodes: 10,
+            predicates: 3,
+            literal_ratio: 0.3,
+            bnode_ratio: 0.2,
+            topology: Topology::Random,
+        }
+    }
+}
+
+impl GraphShape {
+    /// The number of triples to generate (defaults to 20).
+    ///
+    /// Generated graphs may contain fewer triples,
+    /// if the other constraints do not allow that many distinct triples.
+    pub fn triples(&self) -> usize {
+        self.triples
+    }
+
+    /// Set the number of triples to generate.
+    pub fn set_triples(&mut self, triples: usize) -> &mut Self {
+        self.triples = triples;
+        self
+    }
+
+    /// The maximum number of distinct nodes (non-literal subjects and objects)
+    /// (defaults to 10).
+    ///
+    /// With the `Chain` and `Tree` topologies, it is extended to exceed the number of triples.
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Set the maximum number of distinct nodes.
+    pub fn set_nodes(&mut self, nodes: usize) -> &mut Self {
+        self.nodes = nodes;
+        self
+    }
+
+    /// The maximum number of distinct predicates (defaults to 3).
+    pub fn predicates(&self) -> usize {
+        self.predicates
+    }
+
+    /// Set the maximum number of distinct predicates.
+    pub fn set_predicates(&mut self, predicates: usize) -> &mut Self {
+        self.predicates = predicates;
+        self
+    }
+
+    /// The proportion of triples whose object is a literal,
+    /// with the `Random` topology (defaults to 0.3).
+    pub fn literal_ratio(&self) -> f64 {
+        self.literal_ratio
+    }
+
+    /// Set the proportion of triples whose object is a literal.
+    pub fn set_literal_ratio(&mut self, literal_ratio: f64) -> &mut Self {
+        self.literal_ratio = literal_ratio;
+        self
+    }
+
+    /// The proportion of nodes that are blank nodes (defaults to 0.2).
+    pub fn bnode_ratio(&self) -> f64 {
+        self.bnode_ratio
+    }
+
+    /// Set the proportion of nodes that are blank nodes.
+    pub fn set_bnode_ratio(&mut self, bnode_ratio: f64) -> &mut Self {
+        self.bnode_ratio = bnode_ratio;
+        self
+    }
+
+    /// The topology of generated graphs (defaults to `Random`).
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Set the topology of generated graphs.
+    pub fn set_topology(&mut self, topology: Topology) -> &mut Self {
+        self.topology = topology;
+        self
+    }
+}
+
+/// Generate the triples of a graph with the given shape.
+///
+/// Generated triples are all distinct.
+pub fn arbitrary_graph(gen: &mut Gen, shape: &GraphShape) -> Vec<[BoxTerm; 3]> {
+    let node_count = match shape.topology {
+        Topology::Random => shape.nodes.max(1),
+        _ => shape.nodes.max(shape.triples + 1),
+    };
+    let mut nodes: Vec<BoxTerm> = Vec::with_capacity(node_count);
+    for i in 0..node_count {
+        nodes.push(if gen.ratio(shape.bnode_ratio) {
+            BoxTerm::new_bnode(format!("n{}", i)).unwrap()
+        } else {
+            BoxTerm::new_iri(format!("http://example.org/node/{}", i)).unwrap()
+        });
+    }
+    let predicates: Vec<BoxTerm> = (0..shape.predicates.max(1))
+        .map(|i| BoxTerm::new_iri(format!("http://example.org/p{}", i)).unwrap())
+        .collect();
+
+    let mut triples: Vec<[BoxTerm; 3]> = Vec::with_capacity(shape.triples);
+    let mut attempts = 0;
+    while triples.len() < shape.triples && attempts < shape.triples * 10 {
+        attempts += 1;
+        let i = triples.len();
+        let p = gen.choose(&predicates).clone();
+        let (s, o) = match shape.topology {
+            Topology::Chain => (nodes[i].clone(), nodes[i + 1].clone()),
+            Topology::Tree => (nodes[gen.below(i + 1)].clone(), nodes[i + 1].clone()),
+            Topology::Random => {
+                let s = gen.choose(&nodes).clone();
+                let o = if gen.ratio(shape.literal_ratio) {
+                    arbitrary_literal(gen)
+                } else {
+                    gen.choose(&nodes).clone()
+                };
+                (s, o)
+            }
+        };
+        let triple = [s, p, o];
+        if !triples.contains(&triple) {
+            triples.push(triple);
+        }
+    }
+    triples
+}
+
+/// Smaller variants of `triples`, for minimizing a failing test case:
+/// first its halves, then `triples` without each one of its triples.
+pub fn shrink_graph(triples: &[[BoxTerm; 3]]) -> impl Iterator<Item = Vec<[BoxTerm; 3]>> + '_ {
+    let n = triples.len();
+    let halves = if n >= 4 {
+        vec![triples[..n / 2].to_vec(), triples[n / 2..].to_vec()]
+    } else {
+        vec![]
+    };
+    halves.into_iter().chain((0..n).map(move |i| {
+        let mut smaller = triples.to_vec();
+        smaller.remove(i);
+        smaller
+    }))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nt;
+    use crate::serializer::nt::NtSerializer;
+    use crate::serializer::{Stringifier, TripleSerializer};
+    use crate::triple::stream::TripleSource;
+    use sophia_api::term::{TTerm, TermKind};
+    use std::collections::HashSet;
+
+    #[test]
+    fn deterministic() {
+        let mut g1 = Gen::new(1);
+        let mut g2 = Gen::new(1);
+        for _ in 0..100 {
+            assert_eq!(BoxTerm::arbitrary(&mut g1), BoxTerm::arbitrary(&mut g2));
+        }
+        let mut g3 = Gen::new(2);
+        let a: Vec<_> = (0..10).map(|_| BoxTerm::arbitrary(&mut g1)).collect();
+        let b: Vec<_> = (0..10).map(|_| BoxTerm::arbitrary(&mut g3)).collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn valid_terms() -> Result<(), Box<dyn std::error::Error>> {
+        let mut gen = Gen::with_size(3, 20);
+        let mut kinds = HashSet::new();
+        let triples: Vec<[BoxTerm; 3]> = (0..500).map(|_| Arbitrary::arbitrary(&mut gen)).collect();
+        for [s, p, o] in &triples {
+            assert_ne!(s.kind(), TermKind::Literal);
+            assert_eq!(p.kind(), TermKind::Iri);
+            kinds.insert(o.kind());
+        }
+        assert_eq!(kinds.len(), 3);
+        // generated terms survive a round-trip through N-Triples
+        let txt = NtSerializer::new_stringifier()
+            .serialize_graph(&triples)?
+            .to_string();
+        let parsed: Vec<[BoxTerm; 3]> = nt::parse_str(&txt).collect_triples()?;
+        assert_eq!(parsed, triples);
+        Ok(())
+    }
+
+    #[test]
+    fn random_graph() {
+        let mut shape = GraphShape::default();
+        shape
+            .set_triples(50)
+            .set_nodes(5)
+            .set_predicates(2)
+            .set_literal_ratio(0.0)
+            .set_bnode_ratio(1.0);
+        let triples = arbitrary_graph(&mut Gen::new(4), &shape);
+        assert!(triples.len() <= 50);
+        let distinct: HashSet<_> = triples.iter().collect();
+        assert_eq!(distinct.len(), triples.len());
+        for [s, p, o] in &triples {
+            assert_eq!(s.kind(), TermKind::BlankNode);
+            assert_eq!(o.kind(), TermKind::BlankNode);
+            assert!(p.value().ends_with("p0") || p.value().ends_with("p1"));
+        }
+        let nodes: HashSet<_> = triples.iter().flat_map(|t| vec![&t[0], &t[2]]).collect();
+        assert!(nodes.len() <= 5);
+    }
+
+    #[test]
+    fn chain_and_tree() {
+        let mut shape = GraphShape::default();
+        shape.set_triples(15).set_topology(Topology::Chain);
+        let chain = arbitrary_graph(&mut Gen::new(5), &shape);
+        assert_eq!(chain.len(), 15);
+        for pair in chain.windows(2) {
+            assert_eq!(pair[0][2], pair[1][0]);
+        }
+
+        shape.set_topology(Topology::Tree);
+        let tree = arbitrary_graph(&mut Gen::new(5), &shape);
+        assert_eq!(tree.len(), 15);
+        let objects: HashSet<_> = tree.iter().map(|t| &t[2]).collect();
+        assert_eq!(objects.len(), 15); // each node has at most one parent
+        for (i, t) in tree.iter().enumerate() {
+            // the subject is the root or the object of an earlier triple
+            assert!(t[0] == tree[0][0] || tree[..i].iter().any(|u| u[2] == t[0]));
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let shape = GraphShape::default();
+        let triples = arbitrary_graph(&mut Gen::new(6), &shape);
+        let n = triples.len();
+        let smaller: Vec<_> = shrink_graph(&triples).collect();
+        assert_eq!(smaller.len(), n + 2);
+        assert_eq!(smaller[0].len(), n / 2);
+        assert!(smaller[2..].iter().all(|g| g.len() == n - 1));
+        assert_eq!(shrink_graph(&triples[..1]).next(), Some(vec![]));
+    }
+}
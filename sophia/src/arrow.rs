@@ -0,0 +1,1070 @@
+//! Export and import of triples and quads as [Apache Arrow] tables.
+//!
+//! Dataframe and OLAP tools (Polars, DataFusion, DuckDB...) exchange data
+//! as Arrow tables, typically serialized in the [Arrow IPC streaming format].
+//! This module converts RDF data into such tables, and back, according to two [`Layout`]s:
+//! * with [`Layout::Strings`], a single table has a `subject`, a `predicate`, an `object`
+//!   and (for quads) a `graph` column, containing terms in N-Triples syntax;
+//! * with [`Layout::Dictionary`], a `terms` table maps numeric identifiers (`id`)
+//!   to terms in N-Triples syntax (`term`), and the `quads` table contains identifiers.
+//!
+//! In both cases, the `graph` column is null for triples in the default graph.
+//! [`Table::write_ipc`] and [`Table::read_ipc`] implement the Arrow IPC streaming format
+//! for the column types used by these layouts (32-bit unsigned integers and UTF-8 strings),
+//! without compression.
+//! They do not rely on the `arrow` crate, and only support this subset of the format;
+//! in particular, Parquet is not supported.
+//!
+//! This module requires the `arrow` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::arrow::{quads_to_tables, Layout, RdfTables, Table};
+//! use sophia::parser::nq;
+//!
+//! let data = r#"
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:bob> <tag:name> "Bob" <tag:g>.
+//! "#;
+//! let tables = quads_to_tables(nq::parse_str(data), Layout::Strings)?;
+//! let mut ipc = vec![];
+//! tables.quads.write_ipc(&mut ipc)?;
+//!
+//! let quads = Table::read_ipc(&ipc[..])?;
+//! assert_eq!(quads.len(), 2);
+//! let back = RdfTables { terms: None, quads }.to_quads()?;
+//! assert_eq!(back.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Apache Arrow]: https://arrow.apache.org/
+//! [Arrow IPC streaming format]: https://arrow.apache.org/docs/format/Columnar.html#ipc-streaming-format
+//! [`Layout`]: enum.Layout.html
+//! [`Layout::Strings`]: enum.Layout.html#variant.Strings
+//! [`Layout::Dictionary`]: enum.Layout.html#variant.Dictionary
+//! [`Table::write_ipc`]: struct.Table.html#method.write_ipc
+//! [`Table::read_ipc`]: struct.Table.html#method.read_ipc
+
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::io::{self, Read, Write};
+
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_to_string, CopyTerm, TTerm};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::nq12;
+use crate::syntax::dynamic::BoxQuad;
+
+/// This error is raised when reading Arrow data, or converting tables to quads, fails.
+#[derive(Debug, Error)]
+pub enum ArrowError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data does not comply with the Arrow IPC format.
+    #[error("Invalid Arrow data: {0}")]
+    Format(String),
+    /// The data uses a feature of the Arrow IPC format not supported by this module.
+    #[error("Unsupported Arrow feature: {0}")]
+    Unsupported(String),
+    /// The table does not have the expected layout.
+    #[error("Invalid table: {0}")]
+    Table(String),
+}
+
+/// How RDF data is laid out in tables.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Terms are stored as strings in the quad table.
+    Strings,
+    /// Terms are stored in a separate table, and referred to by their identifier.
+    Dictionary,
+}
+
+/// A column of a [`Table`](struct.Table.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// A column of 32-bit unsigned integers.
+    UInt32(Vec<Option<u32>>),
+    /// A column of UTF-8 strings.
+    Utf8(Vec<Option<String>>),
+}
+
+impl Column {
+    /// The number of rows of this column.
+    pub fn len(&self) -> usize {
+        match self {
+            Column::UInt32(v) => v.len(),
+            Column::Utf8(v) => v.len(),
+        }
+    }
+
+    /// Whether this column has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn null_count(&self) -> usize {
+        match self {
+            Column::UInt32(v) => v.iter().filter(|x| x.is_none()).count(),
+            Column::Utf8(v) => v.iter().filter(|x| x.is_none()).count(),
+        }
+    }
+}
+
+/// A table of named columns, corresponding to an Arrow record batch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Table {
+    columns: Vec<(String, Column)>,
+}
+
+impl Table {
+    /// Construct an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a column to this table.
+    ///
+    /// # Panics
+    /// if the column does not have the same number of rows as the other columns.
+    pub fn push_column<T: Into<String>>(&mut self, name: T, column: Column) -> &mut Self {
+        assert!(
+            self.columns.is_empty() || column.len() == self.len(),
+            "all columns must have the same length"
+        );
+        self.columns.push((name.into(), column));
+        self
+    }
+
+    /// The column with the given name, if any.
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, column)| column)
+    }
+
+    /// The names and columns of this table.
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &Column)> + '_ {
+        self.columns.iter().map(|(n, c)| (n.as_str(), c))
+    }
+
+    /// The number of rows of this table.
+    pub fn len(&self) -> usize {
+        self.columns.first().map(|(_, c)| c.len()).unwrap_or(0)
+    }
+
+    /// Whether this table has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write this table in the Arrow IPC streaming format,
+    /// as a schema followed by a single record batch.
+    pub fn write_ipc<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write_message(&mut w, self.schema_message(), &[])?;
+        let (batch, body) = self.record_batch();
+        write_message(&mut w, batch, &body)?;
+        // end of stream
+        w.write_all(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0])
+    }
+
+    /// Read a table in the Arrow IPC streaming format.
+    ///
+    /// All the record batches of the stream are concatenated.
+    pub fn read_ipc<R: Read>(mut r: R) -> Result<Table, ArrowError> {
+        let mut fields: Option<Vec<(String, FieldType)>> = None;
+        let mut table = Table::new();
+        while let Some((metadata, body)) = read_message(&mut r)? {
+            let message = FbRef::root(&metadata)?;
+            match message.u8(1, 0)? {
+                1 => {
+                    let schema = message.table(2)?.ok_or_else(|| format_error("no schema"))?;
+                    let fs = read_schema(&schema)?;
+                    for (name, ft) in &fs {
+                        let column = match ft {
+                            FieldType::Int { .. } => Column::UInt32(vec![]),
+                            FieldType::Utf8 { .. } => Column::Utf8(vec![]),
+                        };
+                        table.columns.push((name.clone(), column));
+                    }
+                    fields = Some(fs);
+                }
+                2 => return Err(ArrowError::Unsupported("dictionary batches".into())),
+                3 => {
+                    let fields = fields
+                        .as_ref()
+                        .ok_or_else(|| format_error("record batch before schema"))?;
+                    let batch = message
+                        .table(2)?
+                        .ok_or_else(|| format_error("no record batch"))?;
+                    read_record_batch(&batch, &body, fields, &mut table)?;
+                }
+                other => {
+                    return Err(ArrowError::Unsupported(format!("message type {}", other)));
+                }
+            }
+        }
+        if fields.is_none() {
+            return Err(format_error("no schema"));
+        }
+        Ok(table)
+    }
+
+    fn schema_message(&self) -> Vec<Option<Fb>> {
+        let fields = self
+            .columns
+            .iter()
+            .map(|(name, column)| {
+                let (type_type, type_table) = match column {
+                    Column::UInt32(_) => (2, vec![Some(Fb::I32(32)), Some(Fb::Bool(false))]),
+                    Column::Utf8(_) => (5, vec![]),
+                };
+                vec![
+                    Some(Fb::Str(name.clone())),
+                    Some(Fb::Bool(column.null_count() > 0)),
+                    Some(Fb::U8(type_type)),
+                    Some(Fb::Table(type_table)),
+                    None,
+                    Some(Fb::Tables(vec![])),
+                ]
+            })
+            .collect();
+        let schema = vec![None, Some(Fb::Tables(fields))];
+        message(1, schema, 0)
+    }
+
+    fn record_batch(&self) -> (Vec<Option<Fb>>, Vec<u8>) {
+        let mut body = vec![];
+        let mut nodes = vec![];
+        let mut buffers = vec![];
+        let mut push_buffer = |body: &mut Vec<u8>, data: &[u8]| {
+            buffers.push([body.len() as i64, data.len() as i64]);
+            body.extend_from_slice(data);
+            body.resize(align8(body.len()), 0);
+        };
+        for (_, column) in &self.columns {
+            let null_count = column.null_count();
+            nodes.push([column.len() as i64, null_count as i64]);
+            let validity = match column {
+                _ if null_count == 0 => vec![],
+                Column::UInt32(v) => bitmap(v.iter().map(Option::is_some)),
+                Column::Utf8(v) => bitmap(v.iter().map(Option::is_some)),
+            };
+            push_buffer(&mut body, &validity);
+            match column {
+                Column::UInt32(v) => {
+                    let values: Vec<u8> = v
+                        .iter()
+                        .flat_map(|x| x.unwrap_or(0).to_le_bytes())
+                        .collect();
+                    push_buffer(&mut body, &values);
+                }
+                Column::Utf8(v) => {
+                    let mut offsets = vec![0_u8; 4];
+                    let mut data = vec![];
+                    for x in v {
+                        data.extend_from_slice(x.as_deref().unwrap_or("").as_bytes());
+                        offsets.extend_from_slice(&(data.len() as i32).to_le_bytes());
+                    }
+                    push_buffer(&mut body, &offsets);
+                    push_buffer(&mut body, &data);
+                }
+            }
+        }
+        let batch = vec![
+            Some(Fb::I64(self.len() as i64)),
+            Some(Fb::Structs(nodes)),
+            Some(Fb::Structs(buffers)),
+        ];
+        let body_len = body.len();
+        (message(3, batch, body_len), body)
+    }
+}
+
+/// RDF data as Arrow [`Table`](struct.Table.html)s.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RdfTables {
+    /// The `terms` table, with [`Layout::Dictionary`](enum.Layout.html#variant.Dictionary).
+    pub terms: Option<Table>,
+    /// The `quads` table.
+    pub quads: Table,
+}
+
+impl RdfTables {
+    /// Convert these tables back into quads.
+    pub fn to_quads(&self) -> Result<Vec<BoxQuad>, ArrowError> {
+        let column = |name: &str| {
+            self.quads
+                .column(name)
+                .ok_or_else(|| ArrowError::Table(format!("no {} column", name)))
+        };
+        let spo = [column("subject")?, column("predicate")?, column("object")?];
+        let graph = self.quads.column("graph");
+        let dictionary = match &self.terms {
+            None => None,
+            Some(terms) => Some(read_dictionary(terms)?),
+        };
+        let term = |column: &Column, row: usize| -> Result<Option<BoxTerm>, ArrowError> {
+            match (column, &dictionary) {
+                (Column::Utf8(v), None) => v[row].as_deref().map(parse_term).transpose(),
+                (Column::UInt32(v), Some(dictionary)) => v[row]
+                    .map(|id| {
+                        dictionary.get(&id).cloned().ok_or_else(|| {
+                            ArrowError::Table(format!("unknown term identifier {}", id))
+                        })
+                    })
+                    .transpose(),
+                _ => Err(ArrowError::Table("unexpected column type".into())),
+            }
+        };
+        (0..self.quads.len())
+            .map(|row| {
+                let mut terms = spo.iter().map(|column| {
+                    term(column, row)?
+                        .ok_or_else(|| ArrowError::Table(format!("null term in row {}", row)))
+                });
+                let s = terms.next().unwrap()?;
+                let p = terms.next().unwrap()?;
+                let o = terms.next().unwrap()?;
+                let g = match graph {
+                    Some(column) => term(column, row)?,
+                    None => None,
+                };
+                Ok(([s, p, o], g))
+            })
+            .collect()
+    }
+}
+
+/// Convert the triples of `source` into tables, with the given layout.
+///
+/// The resulting quad table has no `graph` column.
+pub fn triples_to_tables<TS>(mut source: TS, layout: Layout) -> Result<RdfTables, TS::Error>
+where
+    TS: TripleSource,
+{
+    let mut builder = TableBuilder::new(layout, false);
+    source.for_each_triple(|t| builder.push([t.s(), t.p(), t.o()], None))?;
+    Ok(builder.finish())
+}
+
+/// Convert the quads of `source` into tables, with the given layout.
+pub fn quads_to_tables<QS>(mut source: QS, layout: Layout) -> Result<RdfTables, QS::Error>
+where
+    QS: QuadSource,
+{
+    let mut builder = TableBuilder::new(layout, true);
+    source.for_each_quad(|q| builder.push([q.s(), q.p(), q.o()], q.g()))?;
+    Ok(builder.finish())
+}
+
+/// Accumulates the rows of an [`RdfTables`](struct.RdfTables.html).
+struct TableBuilder {
+    graphs: bool,
+    ids: Option<HashMap<BoxTerm, u32>>,
+    terms: Vec<Option<String>>,
+    strings: [Vec<Option<String>>; 4],
+    numbers: [Vec<Option<u32>>; 4],
+}
+
+impl TableBuilder {
+    fn new(layout: Layout, graphs: bool) -> Self {
+        TableBuilder {
+            graphs,
+            ids: match layout {
+                Layout::Strings => None,
+                Layout::Dictionary => Some(HashMap::new()),
+            },
+            terms: vec![],
+            strings: Default::default(),
+            numbers: Default::default(),
+        }
+    }
+
+    fn push<T: TTerm + ?Sized>(&mut self, spo: [&T; 3], g: Option<&T>) {
+        let terms = [Some(spo[0]), Some(spo[1]), Some(spo[2]), g];
+        let n = if self.graphs { 4 } else { 3 };
+        let TableBuilder {
+            ids,
+            terms: dictionary,
+            strings,
+            numbers,
+            ..
+        } = self;
+        for (i, t) in terms.iter().take(n).enumerate() {
+            match ids {
+                None => strings[i].push(t.map(term_to_string)),
+                Some(ids) => {
+                    let id = t.map(|t| {
+                        let next = ids.len() as u32;
+                        *ids.entry(BoxTerm::copy(t)).or_insert_with(|| {
+                            dictionary.push(Some(term_to_string(t)));
+                            next
+                        })
+                    });
+                    numbers[i].push(id);
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> RdfTables {
+        let names = ["subject", "predicate", "object", "graph"];
+        let n = if self.graphs { 4 } else { 3 };
+        let mut quads = Table::new();
+        let TableBuilder {
+            ids,
+            terms,
+            strings,
+            numbers,
+            ..
+        } = self;
+        let dictionary = ids.is_some();
+        for ((name, s), u) in names.iter().zip(strings).zip(numbers).take(n) {
+            let column = if dictionary {
+                Column::UInt32(u)
+            } else {
+                Column::Utf8(s)
+            };
+            quads.push_column(*name, column);
+        }
+        let terms = if dictionary {
+            let mut table = Table::new();
+            table.push_column(
+                "id",
+                Column::UInt32((0..terms.len() as u32).map(Some).collect()),
+            );
+            table.push_column("term", Column::Utf8(terms));
+            Some(table)
+        } else {
+            None
+        };
+        RdfTables { terms, quads }
+    }
+}
+
+fn read_dictionary(terms: &Table) -> Result<HashMap<u32, BoxTerm>, ArrowError> {
+    match (terms.column("id"), terms.column("term")) {
+        (Some(Column::UInt32(ids)), Some(Column::Utf8(terms))) => ids
+            .iter()
+            .zip(terms)
+            .map(|pair| match pair {
+                (Some(id), Some(term)) => Ok((*id, parse_term(term)?)),
+                _ => Err(ArrowError::Table("null in the terms table".into())),
+            })
+            .collect(),
+        _ => Err(ArrowError::Table(
+            "the terms table must have an id and a term column".into(),
+        )),
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                  IPC messages
+// ---------------------------------------------------------------------------------
+
+/// The types of fields supported when reading.
+enum FieldType {
+    /// Integers of the given width in bytes, converted to `u32`.
+    Int { width: usize },
+    /// Strings with 32-bit (`Utf8`) or 64-bit (`LargeUtf8`) offsets.
+    Utf8 { large: bool },
+}
+
+/// A `Message` table, with the given header.
+fn message(header_type: u8, header: Vec<Option<Fb>>, body_len: usize) -> Vec<Option<Fb>> {
+    vec![
+        Some(Fb::I16(4)), // MetadataVersion V5
+        Some(Fb::U8(header_type)),
+        Some(Fb::Table(header)),
+        Some(Fb::I64(body_len as i64)),
+    ]
+}
+
+/// The metadata and body of a message.
+type MessageBytes = (Vec<u8>, Vec<u8>);
+
+fn write_message<W: Write>(w: &mut W, message: Vec<Option<Fb>>, body: &[u8]) -> io::Result<()> {
+    let mut metadata = FbWriter::finish(&message);
+    metadata.resize(align8(metadata.len()), 0);
+    w.write_all(&[0xff, 0xff, 0xff, 0xff])?;
+    w.write_all(&(metadata.len() as i32).to_le_bytes())?;
+    w.write_all(&metadata)?;
+    w.write_all(body)
+}
+
+/// Read the metadata and body of the next message, or `None` at the end of the stream.
+fn read_message<R: Read>(r: &mut R) -> Result<Option<MessageBytes>, ArrowError> {
+    let mut word = [0; 4];
+    match r.read_exact(&mut word) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        other => other?,
+    }
+    if word == [0xff; 4] {
+        r.read_exact(&mut word)?;
+    }
+    let len = i32::from_le_bytes(word);
+    if len <= 0 {
+        return Ok(None);
+    }
+    let mut metadata = vec![0; len as usize];
+    r.read_exact(&mut metadata)?;
+    let body_len = FbRef::root(&metadata)?.i64(3, 0)?;
+    let body_len = usize::try_from(body_len).map_err(|_| format_error("negative body length"))?;
+    let mut body = vec![];
+    r.take(body_len as u64).read_to_end(&mut body)?;
+    if body.len() != body_len {
+        return Err(format_error("truncated message body"));
+    }
+    Ok(Some((metadata, body)))
+}
+
+fn read_schema(schema: &FbRef) -> Result<Vec<(String, FieldType)>, ArrowError> {
+    if schema.i16(0, 0)? != 0 {
+        return Err(ArrowError::Unsupported("big endian data".into()));
+    }
+    schema
+        .tables(1)?
+        .iter()
+        .map(|field| {
+            let name = field.string(0)?.unwrap_or_default().to_string();
+            if field.table(4)?.is_some() {
+                return Err(ArrowError::Unsupported("dictionary-encoded fields".into()));
+            }
+            let ft = match field.u8(2, 0)? {
+                2 => {
+                    let bits = field.table(3)?.map(|t| t.i32(0, 0)).transpose()?;
+                    match bits {
+                        Some(bits @ (8 | 16 | 32 | 64)) => FieldType::Int {
+                            width: bits as usize / 8,
+                        },
+                        _ => return Err(format_error("invalid integer width")),
+                    }
+                }
+                5 => FieldType::Utf8 { large: false },
+                20 => FieldType::Utf8 { large: true },
+                other => return Err(ArrowError::Unsupported(format!("field type {}", other))),
+            };
+            Ok((name, ft))
+        })
+        .collect()
+}
+
+fn read_record_batch(
+    batch: &FbRef,
+    body: &[u8],
+    fields: &[(String, FieldType)],
+    table: &mut Table,
+) -> Result<(), ArrowError> {
+    if batch.table(3)?.is_some() {
+        return Err(ArrowError::Unsupported("compressed record batches".into()));
+    }
+    let nodes = batch.structs(1)?;
+    let mut buffers = batch.structs(2)?.into_iter().map(|[offset, length]| {
+        let start = usize::try_from(offset).map_err(|_| format_error("negative offset"))?;
+        let length = usize::try_from(length).map_err(|_| format_error("negative length"))?;
+        body.get(start..start + length)
+            .ok_or_else(|| format_error("buffer out of the message body"))
+    });
+    let mut next_buffer = || {
+        buffers
+            .next()
+            .unwrap_or_else(|| Err(format_error("missing buffer")))
+    };
+    if nodes.len() != fields.len() {
+        return Err(format_error("wrong number of field nodes"));
+    }
+    for (((_, ft), [length, _]), (_, column)) in fields.iter().zip(&nodes).zip(&mut table.columns) {
+        let length = usize::try_from(*length).map_err(|_| format_error("negative length"))?;
+        let validity = next_buffer()?;
+        let valid = |i: usize| validity.is_empty() || validity[i / 8] & (1 << (i % 8)) != 0;
+        if !validity.is_empty() && validity.len() * 8 < length {
+            return Err(format_error("validity bitmap too short"));
+        }
+        match (ft, column) {
+            (FieldType::Int { width }, Column::UInt32(v)) => {
+                let values = next_buffer()?;
+                if values.len() < length * width {
+                    return Err(format_error("values buffer too short"));
+                }
+                for i in 0..length {
+                    if !valid(i) {
+                        v.push(None);
+                        continue;
+                    }
+                    let mut bytes = [0; 8];
+                    bytes[..*width].copy_from_slice(&values[i * width..(i + 1) * width]);
+                    let value = u32::try_from(u64::from_le_bytes(bytes))
+                        .map_err(|_| ArrowError::Unsupported("integers above 2^32".into()))?;
+                    v.push(Some(value));
+                }
+            }
+            (FieldType::Utf8 { large }, Column::Utf8(v)) => {
+                let offsets = next_buffer()?;
+                let data = next_buffer()?;
+                let width = if *large { 8 } else { 4 };
+                if offsets.len() < (length + 1) * width {
+                    return Err(format_error("offsets buffer too short"));
+                }
+                let offset = |i: usize| {
+                    let mut bytes = [0; 8];
+                    bytes[..width].copy_from_slice(&offsets[i * width..(i + 1) * width]);
+                    u64::from_le_bytes(bytes) as usize
+                };
+                for i in 0..length {
+                    if !valid(i) {
+                        v.push(None);
+                        continue;
+                    }
+                    let txt = data
+                        .get(offset(i)..offset(i + 1))
+                        .ok_or_else(|| format_error("string out of the data buffer"))?;
+                    let txt = std::str::from_utf8(txt)
+                        .map_err(|_| format_error("invalid UTF-8 string"))?;
+                    v.push(Some(txt.to_string()));
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+fn bitmap<I: Iterator<Item = bool>>(bits: I) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (i, bit) in bits.enumerate() {
+        if i % 8 == 0 {
+            bytes.push(0);
+        }
+        if bit {
+            *bytes.last_mut().unwrap() |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn align8(n: usize) -> usize {
+    n.div_ceil(8) * 8
+}
+
+fn parse_term(txt: &str) -> Result<BoxTerm, ArrowError> {
+    nq12::parse_term(txt).map_err(|e| ArrowError::Table(format!("invalid term {}: {}", txt, e)))
+}
+
+fn format_error(msg: &str) -> ArrowError {
+    ArrowError::Format(msg.to_string())
+}
+
+// ---------------------------------------------------------------------------------
+//                                  flatbuffers
+// ---------------------------------------------------------------------------------
+
+// The metadata of Arrow IPC messages are encoded as flatbuffers.
+// The following is a minimal flatbuffer encoder and decoder,
+// supporting only the constructs used by the Arrow schema.
+
+/// A field value in a flatbuffer table.
+enum Fb {
+    U8(u8),
+    Bool(bool),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Str(String),
+    Table(Vec<Option<Fb>>),
+    Tables(Vec<Vec<Option<Fb>>>),
+    /// A vector of structs made of two longs (such as `FieldNode` and `Buffer`)
+    Structs(Vec<[i64; 2]>),
+}
+
+impl Fb {
+    fn inline_size(&self) -> usize {
+        match self {
+            Fb::U8(_) | Fb::Bool(_) => 1,
+            Fb::I16(_) => 2,
+            Fb::I64(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// Writes flatbuffers front to back,
+/// placing each object before the objects it refers to.
+struct FbWriter {
+    buf: Vec<u8>,
+}
+
+impl FbWriter {
+    fn finish(root: &[Option<Fb>]) -> Vec<u8> {
+        let mut w = FbWriter { buf: vec![0; 4] };
+        let root = w.table(root);
+        w.patch(0, root);
+        w.buf
+    }
+
+    /// Pad the buffer until `len + shift` is a multiple of `align`.
+    fn pad(&mut self, align: usize, shift: usize) {
+        while (self.buf.len() + shift) % align != 0 {
+            self.buf.push(0);
+        }
+    }
+
+    /// Write at `pos` the offset from `pos` to `target`.
+    fn patch(&mut self, pos: usize, target: usize) {
+        let offset = (target - pos) as u32;
+        self.buf[pos..pos + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    fn table(&mut self, fields: &[Option<Fb>]) -> usize {
+        // larger fields first, to minimize padding
+        let mut order: Vec<usize> = (0..fields.len()).filter(|i| fields[*i].is_some()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(fields[*i].as_ref().unwrap().inline_size()));
+        let mut layout = vec![0_u16; fields.len()];
+        let mut size = 4_usize;
+        for i in &order {
+            let field_size = fields[*i].as_ref().unwrap().inline_size();
+            size = size.div_ceil(field_size) * field_size;
+            layout[*i] = size as u16;
+            size += field_size;
+        }
+        // vtable
+        self.pad(2, 0);
+        let vtable = self.buf.len();
+        self.buf
+            .extend_from_slice(&(4 + 2 * fields.len() as u16).to_le_bytes());
+        self.buf.extend_from_slice(&(size as u16).to_le_bytes());
+        for offset in &layout {
+            self.buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        // table
+        self.pad(8, 0);
+        let start = self.buf.len();
+        self.buf.resize(start + size, 0);
+        let soffset = (start - vtable) as i32;
+        self.buf[start..start + 4].copy_from_slice(&soffset.to_le_bytes());
+        let mut children = vec![];
+        for i in order {
+            let pos = start + layout[i] as usize;
+            let field = fields[i].as_ref().unwrap();
+            let mut put = |bytes: &[u8]| self.buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+            match field {
+                Fb::U8(x) => put(&[*x]),
+                Fb::Bool(x) => put(&[*x as u8]),
+                Fb::I16(x) => put(&x.to_le_bytes()),
+                Fb::I32(x) => put(&x.to_le_bytes()),
+                Fb::I64(x) => put(&x.to_le_bytes()),
+                _ => children.push((pos, field)),
+            }
+        }
+        for (pos, child) in children {
+            let target = match child {
+                Fb::Str(txt) => {
+                    self.pad(4, 0);
+                    let target = self.buf.len();
+                    self.buf
+                        .extend_from_slice(&(txt.len() as u32).to_le_bytes());
+                    self.buf.extend_from_slice(txt.as_bytes());
+                    self.buf.push(0);
+                    target
+                }
+                Fb::Table(fields) => self.table(fields),
+                Fb::Tables(tables) => {
+                    self.pad(4, 0);
+                    let target = self.buf.len();
+                    self.buf
+                        .extend_from_slice(&(tables.len() as u32).to_le_bytes());
+                    self.buf.resize(target + 4 + 4 * tables.len(), 0);
+                    for (k, fields) in tables.iter().enumerate() {
+                        let table = self.table(fields);
+                        self.patch(target + 4 + 4 * k, table);
+                    }
+                    target
+                }
+                Fb::Structs(structs) => {
+                    self.pad(8, 4);
+                    let target = self.buf.len();
+                    self.buf
+                        .extend_from_slice(&(structs.len() as u32).to_le_bytes());
+                    for x in structs.iter().flatten() {
+                        self.buf.extend_from_slice(&x.to_le_bytes());
+                    }
+                    target
+                }
+                _ => unreachable!(),
+            };
+            self.patch(pos, target);
+        }
+        start
+    }
+}
+
+/// A table in a flatbuffer.
+struct FbRef<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FbRef<'a> {
+    fn root(buf: &'a [u8]) -> Result<Self, ArrowError> {
+        let pos = read_u32(buf, 0)? as usize;
+        Ok(FbRef { buf, pos })
+    }
+
+    /// The position of the given field, if present.
+    fn field(&self, id: usize) -> Result<Option<usize>, ArrowError> {
+        let soffset = read_u32(self.buf, self.pos)? as i32 as i64;
+        let vtable = usize::try_from(self.pos as i64 - soffset)
+            .map_err(|_| format_error("invalid vtable offset"))?;
+        let vtable_size = read_u16(self.buf, vtable)? as usize;
+        if 4 + 2 * id + 2 > vtable_size {
+            return Ok(None);
+        }
+        match read_u16(self.buf, vtable + 4 + 2 * id)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    fn scalar<const N: usize>(&self, id: usize) -> Result<Option<[u8; N]>, ArrowError> {
+        match self.field(id)? {
+            None => Ok(None),
+            Some(pos) => {
+                let bytes = self
+                    .buf
+                    .get(pos..pos + N)
+                    .ok_or_else(|| format_error("field out of the buffer"))?;
+                Ok(Some(bytes.try_into().unwrap()))
+            }
+        }
+    }
+
+    fn u8(&self, id: usize, default: u8) -> Result<u8, ArrowError> {
+        Ok(self.scalar::<1>(id)?.map(|b| b[0]).unwrap_or(default))
+    }
+
+    fn i16(&self, id: usize, default: i16) -> Result<i16, ArrowError> {
+        Ok(self.scalar(id)?.map(i16::from_le_bytes).unwrap_or(default))
+    }
+
+    fn i32(&self, id: usize, default: i32) -> Result<i32, ArrowError> {
+        Ok(self.scalar(id)?.map(i32::from_le_bytes).unwrap_or(default))
+    }
+
+    fn i64(&self, id: usize, default: i64) -> Result<i64, ArrowError> {
+        Ok(self.scalar(id)?.map(i64::from_le_bytes).unwrap_or(default))
+    }
+
+    /// The target of the offset stored at `pos`.
+    fn follow(&self, pos: usize) -> Result<usize, ArrowError> {
+        Ok(pos + read_u32(self.buf, pos)? as usize)
+    }
+
+    fn table(&self, id: usize) -> Result<Option<FbRef<'a>>, ArrowError> {
+        match self.field(id)? {
+            None => Ok(None),
+            Some(pos) => Ok(Some(FbRef {
+                buf: self.buf,
+                pos: self.follow(pos)?,
+            })),
+        }
+    }
+
+    fn string(&self, id: usize) -> Result<Option<&'a str>, ArrowError> {
+        match self.vector(id)? {
+            None => Ok(None),
+            Some((len, start)) => {
+                let bytes = self
+                    .buf
+                    .get(start..start + len)
+                    .ok_or_else(|| format_error("string out of the buffer"))?;
+                std::str::from_utf8(bytes)
+                    .map(Some)
+                    .map_err(|_| format_error("invalid UTF-8 string"))
+            }
+        }
+    }
+
+    /// The length and the position of the first element of the given vector field.
+    fn vector(&self, id: usize) -> Result<Option<(usize, usize)>, ArrowError> {
+        match self.field(id)? {
+            None => Ok(None),
+            Some(pos) => {
+                let target = self.follow(pos)?;
+                Ok(Some((read_u32(self.buf, target)? as usize, target + 4)))
+            }
+        }
+    }
+
+    fn tables(&self, id: usize) -> Result<Vec<FbRef<'a>>, ArrowError> {
+        let (len, start) = self.vector(id)?.unwrap_or((0, 0));
+        (0..len)
+            .map(|k| {
+                Ok(FbRef {
+                    buf: self.buf,
+                    pos: self.follow(start + 4 * k)?,
+                })
+            })
+            .collect()
+    }
+
+    fn structs(&self, id: usize) -> Result<Vec<[i64; 2]>, ArrowError> {
+        let (len, start) = self.vector(id)?.unwrap_or((0, 0));
+        (0..len)
+            .map(|k| {
+                let pos = start + 16 * k;
+                let bytes = self
+                    .buf
+                    .get(pos..pos + 16)
+                    .ok_or_else(|| format_error("vector out of the buffer"))?;
+                Ok([
+                    i64::from_le_bytes(bytes[..8].try_into().unwrap()),
+                    i64::from_le_bytes(bytes[8..].try_into().unwrap()),
+                ])
+            })
+            .collect()
+    }
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> Result<u32, ArrowError> {
+    buf.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| format_error("offset out of the buffer"))
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> Result<u16, ArrowError> {
+    buf.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| format_error("offset out of the buffer"))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+
+    const DATA: &str = r#"
+        <tag:alice> <tag:knows> <tag:bob>.
+        <tag:alice> <tag:name> "Alice"@en <tag:g>.
+        _:x <tag:knows> <tag:alice> <tag:g>.
+    "#;
+
+    #[test]
+    fn strings() -> Result<(), Box<dyn std::error::Error>> {
+        let tables = quads_to_tables(nq::parse_str(DATA), Layout::Strings)?;
+        assert!(tables.terms.is_none());
+        assert_eq!(
+            tables.quads.column("object"),
+            Some(&Column::Utf8(vec![
+                Some("<tag:bob>".into()),
+                Some("\"Alice\"@en".into()),
+                Some("<tag:alice>".into()),
+            ]))
+        );
+        assert_eq!(
+            tables.quads.column("graph"),
+            Some(&Column::Utf8(vec![
+                None,
+                Some("<tag:g>".into()),
+                Some("<tag:g>".into()),
+            ]))
+        );
+        let quads = tables.to_quads()?;
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[1].0[2].language(), Some("en"));
+        assert!(quads[0].1.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary() -> Result<(), Box<dyn std::error::Error>> {
+        let tables = quads_to_tables(nq::parse_str(DATA), Layout::Dictionary)?;
+        let terms = tables.terms.as_ref().unwrap();
+        assert_eq!(terms.len(), 7);
+        assert_eq!(
+            tables.quads.column("subject"),
+            Some(&Column::UInt32(vec![Some(0), Some(0), Some(6)]))
+        );
+        let quads = tables.to_quads()?;
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[2].0[2].value(), "tag:alice");
+        Ok(())
+    }
+
+    #[test]
+    fn triples() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::turtle;
+        let tables =
+            triples_to_tables(turtle::parse_str("<tag:a> <tag:b> 1, 2."), Layout::Strings)?;
+        assert!(tables.quads.column("graph").is_none());
+        assert_eq!(tables.to_quads()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn ipc_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        for layout in [Layout::Strings, Layout::Dictionary] {
+            let tables = quads_to_tables(nq::parse_str(DATA), layout)?;
+            let round_trip = |table: &Table| -> Result<Table, Box<dyn std::error::Error>> {
+                let mut ipc = vec![];
+                table.write_ipc(&mut ipc)?;
+                assert_eq!(&ipc[..4], &[0xff; 4]);
+                assert_eq!(ipc.len() % 8, 0);
+                Ok(Table::read_ipc(&ipc[..])?)
+            };
+            assert_eq!(round_trip(&tables.quads)?, tables.quads);
+            if let Some(terms) = &tables.terms {
+                assert_eq!(&round_trip(terms)?, terms);
+            }
+        }
+        assert_eq!(
+            Table::read_ipc(&[][..]).unwrap_err().to_string(),
+            "Invalid Arrow data: no schema"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ipc_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let mut table = Table::new();
+        table.push_column("n", Column::UInt32(vec![Some(7), None]));
+        let mut ipc = vec![];
+        table.write_ipc(&mut ipc)?;
+        // schema message
+        let len = i32::from_le_bytes(ipc[4..8].try_into()?) as usize;
+        assert_eq!(len % 8, 0);
+        let schema = FbRef::root(&ipc[8..8 + len])?;
+        assert_eq!(schema.i16(0, 0)?, 4);
+        assert_eq!(schema.u8(1, 0)?, 1);
+        let fields = schema.table(2)?.unwrap().tables(1)?;
+        assert_eq!(fields[0].string(0)?, Some("n"));
+        assert_eq!(fields[0].u8(1, 0)?, 1);
+        assert_eq!(fields[0].u8(2, 0)?, 2);
+        // record batch message
+        let ipc = &ipc[8 + len..];
+        let len = i32::from_le_bytes(ipc[4..8].try_into()?) as usize;
+        let batch = FbRef::root(&ipc[8..8 + len])?;
+        assert_eq!(batch.u8(1, 0)?, 3);
+        assert_eq!(batch.i64(3, 0)?, 16);
+        let header = batch.table(2)?.unwrap();
+        assert_eq!(header.i64(0, 0)?, 2);
+        assert_eq!(header.structs(1)?, vec![[2, 1]]);
+        assert_eq!(header.structs(2)?, vec![[0, 1], [8, 8]]);
+        let body = &ipc[8 + len..8 + len + 16];
+        assert_eq!(body, &[1, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+//! A fluent API for populating graphs.
+//!
+//! The [`GraphBuilder`] trait, implemented by every [`MutableGraph`],
+//! provides a [`describe`] method, returning a [`Description`] of a subject.
+//! Properties of that subject can then be added by chaining calls to
+//! [`with`], [`with_literal`], [`with_list`] (for RDF collections)
+//! and [`with_anon`] (for nested anonymous resources).
+//!
+//! In order to keep the chain fluent,
+//! errors raised by the graph are recorded and only reported by [`done`],
+//! which must therefore be called at the end of the chain.
+//!
+//! # Example
+//! ```
+//! use sophia::builder::GraphBuilder;
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::ns::{rdf, Namespace};
+//!
+//! let ex = Namespace::new("http://example.org/")?;
+//! let foaf = Namespace::new("http://xmlns.com/foaf/0.1/")?;
+//! let name = foaf.get("name")?;
+//! let mut g = FastGraph::new();
+//! g.describe(&ex.get("alice")?)
+//!     .with(&rdf::type_, &foaf.get("Person")?)
+//!     .with_literal(&name, "Alice")
+//!     .with_list(&ex.get("items")?, &[ex.get("i1")?, ex.get("i2")?])
+//!     .with_anon(&foaf.get("knows")?, |bob| {
+//!         bob.with_literal(&name, "Bob")
+//!     })
+//!     .done()?;
+//! assert_eq!(g.triples().count(), 9);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`GraphBuilder`]: trait.GraphBuilder.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+//! [`describe`]: trait.GraphBuilder.html#method.describe
+//! [`Description`]: struct.Description.html
+//! [`with`]: struct.Description.html#method.with
+//! [`with_literal`]: struct.Description.html#method.with_literal
+//! [`with_list`]: struct.Description.html#method.with_list
+//! [`with_anon`]: struct.Description.html#method.with_anon
+//! [`done`]: struct.Description.html#method.done
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sophia_api::graph::{MGResult, MutableGraph};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::literal::convert::AsLiteral;
+use sophia_term::BoxTerm;
+
+use crate::ns::rdf;
+
+/// Extension trait providing a fluent API to every [`MutableGraph`].
+///
+/// See [module documentation](index.html).
+///
+/// [`MutableGraph`]: ../graph/trait.MutableGraph.html
+pub trait GraphBuilder: MutableGraph {
+    /// Start describing `subject` in this graph.
+    fn describe<T: TTerm + ?Sized>(&mut self, subject: &T) -> Description<'_, Self> {
+        Description {
+            graph: self,
+            subject: BoxTerm::copy(subject),
+            error: None,
+        }
+    }
+}
+
+impl<G: MutableGraph + ?Sized> GraphBuilder for G {}
+
+/// The description of a subject, being added to a graph.
+///
+/// See [module documentation](index.html).
+#[must_use = "errors are only reported by the `done` method"]
+pub struct Description<'g, G: MutableGraph + ?Sized> {
+    graph: &'g mut G,
+    subject: BoxTerm,
+    error: Option<G::MutationError>,
+}
+
+impl<G: MutableGraph + ?Sized> Description<'_, G> {
+    /// The subject being described.
+    pub fn subject(&self) -> &BoxTerm {
+        &self.subject
+    }
+
+    /// Add a triple with the described subject, `pred` and `obj`.
+    pub fn with<P, O>(mut self, pred: &P, obj: &O) -> Self
+    where
+        P: TTerm + ?Sized,
+        O: TTerm + ?Sized,
+    {
+        if self.error.is_none() {
+            if let Err(err) = self.graph.insert(&self.subject, pred, obj) {
+                self.error = Some(err);
+            }
+        }
+        self
+    }
+
+    /// Add a triple with the described subject, `pred`,
+    /// and the literal representing the native `value`.
+    pub fn with_literal<P, V>(self, pred: &P, value: V) -> Self
+    where
+        P: TTerm + ?Sized,
+        V: AsLiteral,
+    {
+        self.with(pred, &value.as_literal())
+    }
+
+    /// Add a triple with the described subject, `pred`,
+    /// and an RDF collection (`rdf:first`/`rdf:rest`) containing `items`.
+    pub fn with_list<P, T>(mut self, pred: &P, items: &[T]) -> Self
+    where
+        P: TTerm + ?Sized,
+        T: TTerm,
+    {
+        let nodes: Vec<_> = items.iter().map(|_| fresh_bnode()).collect();
+        let nil = BoxTerm::copy(&rdf::nil);
+        self = self.with(pred, nodes.first().unwrap_or(&nil));
+        for (i, item) in items.iter().enumerate() {
+            let rest = nodes.get(i + 1).unwrap_or(&nil);
+            self.nest(nodes[i].clone(), |d| {
+                d.with(&rdf::first, item).with(&rdf::rest, rest)
+            });
+        }
+        self
+    }
+
+    /// Add a triple with the described subject, `pred`,
+    /// and a fresh blank node, described by `build`.
+    pub fn with_anon<P, F>(mut self, pred: &P, build: F) -> Self
+    where
+        P: TTerm + ?Sized,
+        F: FnOnce(Description<'_, G>) -> Description<'_, G>,
+    {
+        let node = fresh_bnode();
+        self = self.with(pred, &node);
+        self.nest(node, build);
+        self
+    }
+
+    /// End this description,
+    /// returning its subject, or the first error raised by the graph.
+    pub fn done(self) -> MGResult<G, BoxTerm> {
+        match self.error {
+            None => Ok(self.subject),
+            Some(err) => Err(err),
+        }
+    }
+
+    /// Describe `subject` with `build`, sharing the error state of this description.
+    fn nest<F>(&mut self, subject: BoxTerm, build: F)
+    where
+        F: FnOnce(Description<'_, G>) -> Description<'_, G>,
+    {
+        self.error = build(Description {
+            graph: &mut *self.graph,
+            subject,
+            error: self.error.take(),
+        })
+        .error;
+    }
+}
+
+/// Generate a blank node that was never generated before in this process.
+fn fresh_bnode() -> BoxTerm {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    BoxTerm::new_bnode_unchecked(format!("anon{}", n))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::ns::xsd;
+    use crate::test_util::t;
+    use crate::triple::Triple;
+
+    #[test]
+    fn describe() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        let s = g
+            .describe(&t("s"))
+            .with(&rdf::type_, &t("C"))
+            .with_literal(&t("age"), 42)
+            .with_anon(&t("p"), |d| {
+                d.with(&rdf::type_, &t("D"))
+                    .with_anon(&t("q"), |d| d.with(&t("r"), &t("o")))
+            })
+            .done()?;
+        assert_eq!(s, t("s"));
+        assert_eq!(g.triples().count(), 6);
+        assert!(g.contains(
+            &t("s"),
+            &t("age"),
+            &BoxTerm::new_literal_dt("42", xsd::int)?
+        )?);
+        let anon = BoxTerm::copy(g.triples_with_sp(&t("s"), &t("p")).next().unwrap()?.o());
+        assert!(g.contains(&anon, &rdf::type_, &t("D"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn lists() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.describe(&t("s"))
+            .with_list(&t("items"), &[t("a"), t("b"), t("c")])
+            .with_list::<_, BoxTerm>(&t("empty"), &[])
+            .done()?;
+        assert_eq!(g.triples().count(), 1 + 3 * 2 + 1);
+        assert!(g.contains(&t("s"), &t("empty"), &rdf::nil)?);
+
+        let mut node = BoxTerm::copy(g.triples_with_sp(&t("s"), &t("items")).next().unwrap()?.o());
+        let mut items = vec![];
+        while !sophia_api::term::term_eq(&node, &rdf::nil) {
+            let first = g.triples_with_sp(&node, &rdf::first).next().unwrap()?;
+            items.push(BoxTerm::copy(first.o()));
+            let rest = g.triples_with_sp(&node, &rdf::rest).next().unwrap()?;
+            node = BoxTerm::copy(rest.o());
+        }
+        assert_eq!(items, vec![t("a"), t("b"), t("c")]);
+        Ok(())
+    }
+}
@@ -0,0 +1,514 @@
+//! Typed reader and writer for data catalogs,
+//! described with [DCAT] and [VoID].
+//!
+//! [`Catalog`], [`Dataset`] and [`Distribution`] are plain structs,
+//! which can be read from any [`Graph`] (with [`Catalog::read`], [`Dataset::read`]
+//! or [`read_datasets`]), and written to any [`MutableGraph`] (with their `write` methods).
+//! This allows data portals to publish their catalog as RDF,
+//! and harvesters to consume the catalogs of other portals.
+//!
+//! [`Dataset`] covers both the DCAT vocabulary (title, keywords, distributions...)
+//! and the VoID vocabulary (number of triples, SPARQL endpoint, data dumps...),
+//! and a dataset is recognized if it is an instance of `dcat:Dataset` or `void:Dataset`.
+//! When written, it is typed with `void:Dataset` only if it has VoID properties.
+//!
+//! Only the first value of single-valued properties is read;
+//! properties not covered by these structs are ignored.
+//!
+//! # Example
+//! ```
+//! use sophia::catalog::{Catalog, Dataset, Distribution};
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::term::BoxTerm;
+//!
+//! let mut dataset = Dataset::new(BoxTerm::new_iri("http://example.org/ds")?);
+//! dataset.title = Some(BoxTerm::new_literal_lang("Bus stops", "en")?);
+//! dataset.triples = Some(1234);
+//! let mut dist = Distribution::new(BoxTerm::new_iri("http://example.org/ds.ttl")?);
+//! dist.download_url = Some(BoxTerm::new_iri("http://example.org/files/ds.ttl")?);
+//! dist.media_type = Some("text/turtle".to_string());
+//! dataset.distributions.push(dist);
+//! let mut catalog = Catalog::new(BoxTerm::new_iri("http://example.org/catalog")?);
+//! catalog.datasets.push(dataset);
+//!
+//! let mut g = FastGraph::new();
+//! catalog.write(&mut g)?;
+//! let read = Catalog::read(&g, &catalog.id)?;
+//! assert_eq!(read, catalog);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [DCAT]: https://www.w3.org/TR/vocab-dcat-2/
+//! [VoID]: https://www.w3.org/TR/void/
+//! [`Catalog`]: struct.Catalog.html
+//! [`Catalog::read`]: struct.Catalog.html#method.read
+//! [`Dataset`]: struct.Dataset.html
+//! [`Dataset::read`]: struct.Dataset.html#method.read
+//! [`Distribution`]: struct.Distribution.html
+//! [`read_datasets`]: fn.read_datasets.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+
+use std::collections::BTreeSet;
+
+use sophia_api::graph::{GResult, Graph, MGResult, MutableGraph};
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+use crate::ns::{rdf, xsd};
+
+/// The DCAT namespace.
+#[allow(missing_docs)]
+pub mod dcat {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/ns/dcat#",
+        Catalog,
+        Dataset,
+        Distribution,
+        accessURL,
+        dataset,
+        distribution,
+        downloadURL,
+        keyword,
+        landingPage,
+        mediaType
+    );
+}
+
+/// The Dublin Core terms namespace (only the terms used by DCAT).
+#[allow(missing_docs)]
+pub mod dcterms {
+    use sophia_api::namespace;
+    namespace!(
+        "http://purl.org/dc/terms/",
+        description,
+        format,
+        license,
+        publisher,
+        title
+    );
+}
+
+/// The VoID namespace.
+#[allow(missing_docs)]
+pub mod void {
+    use sophia_api::namespace;
+    namespace!(
+        "http://rdfs.org/ns/void#",
+        Dataset,
+        dataDump,
+        distinctObjects,
+        distinctSubjects,
+        properties,
+        sparqlEndpoint,
+        triples,
+        uriSpace,
+        vocabulary
+    );
+}
+
+/// A `dcat:Catalog`.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    /// The IRI (or blank node) identifying the catalog
+    pub id: BoxTerm,
+    /// `dcterms:title`
+    pub title: Option<BoxTerm>,
+    /// `dcterms:description`
+    pub description: Option<BoxTerm>,
+    /// `dcterms:publisher`
+    pub publisher: Option<BoxTerm>,
+    /// `dcat:dataset`
+    pub datasets: Vec<Dataset>,
+}
+
+/// A `dcat:Dataset` and/or `void:Dataset`.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    /// The IRI (or blank node) identifying the dataset
+    pub id: BoxTerm,
+    /// `dcterms:title`
+    pub title: Option<BoxTerm>,
+    /// `dcterms:description`
+    pub description: Option<BoxTerm>,
+    /// `dcterms:license`
+    pub license: Option<BoxTerm>,
+    /// `dcat:keyword`
+    pub keywords: Vec<BoxTerm>,
+    /// `dcat:landingPage`
+    pub landing_page: Option<BoxTerm>,
+    /// `dcat:distribution`
+    pub distributions: Vec<Distribution>,
+    /// `void:triples`
+    pub triples: Option<u64>,
+    /// `void:sparqlEndpoint`
+    pub sparql_endpoint: Option<BoxTerm>,
+    /// `void:dataDump`
+    pub data_dumps: Vec<BoxTerm>,
+    /// `void:vocabulary`
+    pub vocabularies: Vec<BoxTerm>,
+    /// `void:uriSpace`
+    pub uri_space: Option<String>,
+}
+
+/// A `dcat:Distribution`, i.e. a specific representation of a dataset.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    /// The IRI (or blank node) identifying the distribution
+    pub id: BoxTerm,
+    /// `dcterms:title`
+    pub title: Option<BoxTerm>,
+    /// `dcat:accessURL`
+    pub access_url: Option<BoxTerm>,
+    /// `dcat:downloadURL`
+    pub download_url: Option<BoxTerm>,
+    /// `dcat:mediaType`, as a media type string (e.g. `text/turtle`)
+    pub media_type: Option<String>,
+}
+
+impl Catalog {
+    /// An empty catalog identified by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Catalog {
+            id,
+            title: None,
+            description: None,
+            publisher: None,
+            datasets: vec![],
+        }
+    }
+
+    /// Read the catalog identified by `id` from `graph`.
+    pub fn read<G, T>(graph: &G, id: &T) -> GResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut datasets = vec![];
+        for ds in objects(graph, id, &dcat::dataset)? {
+            datasets.push(Dataset::read(graph, &ds)?);
+        }
+        Ok(Catalog {
+            id: BoxTerm::copy(id),
+            title: object(graph, id, &dcterms::title)?,
+            description: object(graph, id, &dcterms::description)?,
+            publisher: object(graph, id, &dcterms::publisher)?,
+            datasets,
+        })
+    }
+
+    /// Read all the catalogs (instances of `dcat:Catalog`) of `graph`.
+    pub fn read_all<G: Graph>(graph: &G) -> GResult<G, Vec<Self>> {
+        instances(graph, &[&dcat::Catalog])?
+            .iter()
+            .map(|id| Catalog::read(graph, id))
+            .collect()
+    }
+
+    /// Write this catalog, and all its datasets, into `graph`.
+    pub fn write<G: MutableGraph>(&self, graph: &mut G) -> MGResult<G, ()> {
+        let id = &self.id;
+        graph.insert(id, &rdf::type_, &dcat::Catalog)?;
+        insert_opt(graph, id, &dcterms::title, &self.title)?;
+        insert_opt(graph, id, &dcterms::description, &self.description)?;
+        insert_opt(graph, id, &dcterms::publisher, &self.publisher)?;
+        for ds in &self.datasets {
+            graph.insert(id, &dcat::dataset, &ds.id)?;
+            ds.write(graph)?;
+        }
+        Ok(())
+    }
+}
+
+impl Dataset {
+    /// An empty dataset identified by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Dataset {
+            id,
+            title: None,
+            description: None,
+            license: None,
+            keywords: vec![],
+            landing_page: None,
+            distributions: vec![],
+            triples: None,
+            sparql_endpoint: None,
+            data_dumps: vec![],
+            vocabularies: vec![],
+            uri_space: None,
+        }
+    }
+
+    /// Read the dataset identified by `id` from `graph`.
+    pub fn read<G, T>(graph: &G, id: &T) -> GResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut distributions = vec![];
+        for dist in objects(graph, id, &dcat::distribution)? {
+            distributions.push(Distribution::read(graph, &dist)?);
+        }
+        Ok(Dataset {
+            id: BoxTerm::copy(id),
+            title: object(graph, id, &dcterms::title)?,
+            description: object(graph, id, &dcterms::description)?,
+            license: object(graph, id, &dcterms::license)?,
+            keywords: objects(graph, id, &dcat::keyword)?,
+            landing_page: object(graph, id, &dcat::landingPage)?,
+            distributions,
+            triples: object(graph, id, &void::triples)?.and_then(|n| n.value().parse().ok()),
+            sparql_endpoint: object(graph, id, &void::sparqlEndpoint)?,
+            data_dumps: objects(graph, id, &void::dataDump)?,
+            vocabularies: objects(graph, id, &void::vocabulary)?,
+            uri_space: object(graph, id, &void::uriSpace)?.map(|t| t.value().to_string()),
+        })
+    }
+
+    /// Whether this dataset has any VoID-specific property.
+    pub fn is_void(&self) -> bool {
+        self.triples.is_some()
+            || self.sparql_endpoint.is_some()
+            || !self.data_dumps.is_empty()
+            || !self.vocabularies.is_empty()
+            || self.uri_space.is_some()
+    }
+
+    /// Write this dataset, and all its distributions, into `graph`.
+    pub fn write<G: MutableGraph>(&self, graph: &mut G) -> MGResult<G, ()> {
+        let id = &self.id;
+        graph.insert(id, &rdf::type_, &dcat::Dataset)?;
+        if self.is_void() {
+            graph.insert(id, &rdf::type_, &void::Dataset)?;
+        }
+        insert_opt(graph, id, &dcterms::title, &self.title)?;
+        insert_opt(graph, id, &dcterms::description, &self.description)?;
+        insert_opt(graph, id, &dcterms::license, &self.license)?;
+        for kw in &self.keywords {
+            graph.insert(id, &dcat::keyword, kw)?;
+        }
+        insert_opt(graph, id, &dcat::landingPage, &self.landing_page)?;
+        for dist in &self.distributions {
+            graph.insert(id, &dcat::distribution, &dist.id)?;
+            dist.write(graph)?;
+        }
+        if let Some(n) = self.triples {
+            let n = BoxTerm::new_literal_dt_unchecked(n.to_string(), xsd::integer);
+            graph.insert(id, &void::triples, &n)?;
+        }
+        insert_opt(graph, id, &void::sparqlEndpoint, &self.sparql_endpoint)?;
+        for dump in &self.data_dumps {
+            graph.insert(id, &void::dataDump, dump)?;
+        }
+        for voc in &self.vocabularies {
+            graph.insert(id, &void::vocabulary, voc)?;
+        }
+        if let Some(space) = &self.uri_space {
+            let space = BoxTerm::new_literal_dt_unchecked(space.as_str(), xsd::string);
+            graph.insert(id, &void::uriSpace, &space)?;
+        }
+        Ok(())
+    }
+}
+
+impl Distribution {
+    /// An empty distribution identified by `id`.
+    pub fn new(id: BoxTerm) -> Self {
+        Distribution {
+            id,
+            title: None,
+            access_url: None,
+            download_url: None,
+            media_type: None,
+        }
+    }
+
+    /// Read the distribution identified by `id` from `graph`.
+    ///
+    /// The media type can be given as a literal or as an IANA IRI
+    /// (e.g. `<https://www.iana.org/assignments/media-types/text/turtle>`);
+    /// `dcterms:format` is used if `dcat:mediaType` is absent.
+    pub fn read<G, T>(graph: &G, id: &T) -> GResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let media_type = match object(graph, id, &dcat::mediaType)? {
+            Some(mt) => Some(mt),
+            None => object(graph, id, &dcterms::format)?,
+        };
+        Ok(Distribution {
+            id: BoxTerm::copy(id),
+            title: object(graph, id, &dcterms::title)?,
+            access_url: object(graph, id, &dcat::accessURL)?,
+            download_url: object(graph, id, &dcat::downloadURL)?,
+            media_type: media_type.map(|mt| {
+                let value = mt.value();
+                match value.strip_prefix(IANA_MEDIA_TYPES) {
+                    Some(suffix) if mt.kind() == TermKind::Iri => suffix.to_string(),
+                    _ => value.to_string(),
+                }
+            }),
+        })
+    }
+
+    /// Write this distribution into `graph`.
+    ///
+    /// The media type is written as an IANA IRI, as recommended by DCAT.
+    pub fn write<G: MutableGraph>(&self, graph: &mut G) -> MGResult<G, ()> {
+        let id = &self.id;
+        graph.insert(id, &rdf::type_, &dcat::Distribution)?;
+        insert_opt(graph, id, &dcterms::title, &self.title)?;
+        insert_opt(graph, id, &dcat::accessURL, &self.access_url)?;
+        insert_opt(graph, id, &dcat::downloadURL, &self.download_url)?;
+        if let Some(mt) = &self.media_type {
+            let mt = BoxTerm::new_iri_suffixed_unchecked(IANA_MEDIA_TYPES, mt.as_str());
+            graph.insert(id, &dcat::mediaType, &mt)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read all the datasets (instances of `dcat:Dataset` or `void:Dataset`) of `graph`,
+/// whether or not they belong to a catalog.
+pub fn read_datasets<G: Graph>(graph: &G) -> GResult<G, Vec<Dataset>> {
+    instances(graph, &[&dcat::Dataset, &void::Dataset])?
+        .iter()
+        .map(|id| Dataset::read(graph, id))
+        .collect()
+}
+
+const IANA_MEDIA_TYPES: &str = "https://www.iana.org/assignments/media-types/";
+
+fn instances<G: Graph>(
+    graph: &G,
+    classes: &[&sophia_api::term::SimpleIri<'static>],
+) -> GResult<G, Vec<BoxTerm>> {
+    let mut found = BTreeSet::new();
+    for class in classes {
+        for t in graph.triples_with_po(&rdf::type_, *class) {
+            found.insert(BoxTerm::copy(t?.s()));
+        }
+    }
+    Ok(found.into_iter().collect())
+}
+
+fn object<G, T, P>(graph: &G, s: &T, p: &P) -> GResult<G, Option<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+    P: TTerm + ?Sized,
+{
+    match graph.triples_with_sp(s, p).next() {
+        Some(t) => Ok(Some(BoxTerm::copy(t?.o()))),
+        None => Ok(None),
+    }
+}
+
+fn objects<G, T, P>(graph: &G, s: &T, p: &P) -> GResult<G, Vec<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+    P: TTerm + ?Sized,
+{
+    let mut found = BTreeSet::new();
+    for t in graph.triples_with_sp(s, p) {
+        found.insert(BoxTerm::copy(t?.o()));
+    }
+    Ok(found.into_iter().collect())
+}
+
+fn insert_opt<G, P>(graph: &mut G, s: &BoxTerm, p: &P, o: &Option<BoxTerm>) -> MGResult<G, ()>
+where
+    G: MutableGraph,
+    P: TTerm + ?Sized,
+{
+    if let Some(o) = o {
+        graph.insert(s, p, o)?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use crate::triple::stream::TripleSource;
+
+    #[test]
+    fn read() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix dcat: <http://www.w3.org/ns/dcat#>.
+            @prefix dct: <http://purl.org/dc/terms/>.
+            @prefix void: <http://rdfs.org/ns/void#>.
+            <tag:cat> a dcat:Catalog; dct:title "Portal"; dcat:dataset <tag:ds1>.
+            <tag:ds1> a dcat:Dataset;
+                dcat:keyword "bus", "transport";
+                dcat:distribution [
+                    dcat:accessURL <tag:api>;
+                    dct:format "application/json"
+                ].
+            <tag:ds2> a void:Dataset;
+                void:triples 42;
+                void:sparqlEndpoint <tag:sparql>;
+                void:uriSpace "tag:ds2/".
+            "#,
+        )
+        .collect_triples()?;
+
+        let catalogs = Catalog::read_all(&g)?;
+        assert_eq!(catalogs.len(), 1);
+        let catalog = &catalogs[0];
+        assert_eq!(catalog.title.as_ref().unwrap().value(), "Portal");
+        assert_eq!(catalog.datasets.len(), 1);
+        let ds1 = &catalog.datasets[0];
+        assert_eq!(ds1.keywords.len(), 2);
+        assert!(!ds1.is_void());
+        let dist = &ds1.distributions[0];
+        assert_eq!(dist.access_url, Some(t("api")));
+        assert_eq!(dist.media_type.as_deref(), Some("application/json"));
+
+        let datasets = read_datasets(&g)?;
+        assert_eq!(datasets.len(), 2);
+        let ds2 = &datasets[1];
+        assert_eq!(ds2.id, t("ds2"));
+        assert_eq!(ds2.triples, Some(42));
+        assert_eq!(ds2.sparql_endpoint, Some(t("sparql")));
+        assert_eq!(ds2.uri_space.as_deref(), Some("tag:ds2/"));
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut ds = Dataset::new(t("ds"));
+        ds.keywords.push(BoxTerm::new_literal_lang("bus", "en")?);
+        ds.data_dumps.push(t("dump.nt"));
+        ds.vocabularies.push(t("voc"));
+        let mut dist = Distribution::new(t("dist"));
+        dist.media_type = Some("text/turtle".to_string());
+        ds.distributions.push(dist);
+
+        let mut g = FastGraph::new();
+        ds.write(&mut g)?;
+        assert!(g.contains(&t("ds"), &rdf::type_, &void::Dataset)?);
+        let iana = BoxTerm::new_iri(format!("{}text/turtle", IANA_MEDIA_TYPES))?;
+        assert!(g.contains(&t("dist"), &dcat::mediaType, &iana)?);
+        assert_eq!(read_datasets(&g)?, vec![ds]);
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+//! Extraction of the [Concise Bounded Description] (CBD) of a resource.
+//!
+//! The CBD of a resource is made of all the triples having that resource as their subject,
+//! and, recursively, of the CBD of every blank node appearing as the object of those triples.
+//! This is the standard answer of Linked Data servers to "describe this resource".
+//!
+//! The *symmetric* CBD additionally contains all the triples having the resource as their object,
+//! and, recursively, the triples having as their object
+//! every blank node appearing as the subject of those triples.
+//!
+//! [`cbd`] and [`symmetric_cbd`] return a [`TripleSource`],
+//! lazily querying the graph as the description is consumed.
+//! It can be collected into a new graph with [`collect_triples`].
+//!
+//! Note that, unlike the original definition,
+//! the reifications of the described triples are not included.
+//!
+//! # Example
+//! ```
+//! use sophia::cbd::cbd;
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:address> [ <tag:city> "Paris" ].
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:bob> <tag:name> "Bob".
+//! "#).collect_triples()?;
+//!
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! let description: FastGraph = cbd(&g, &alice).collect_triples()?;
+//! assert_eq!(description.triples().count(), 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Concise Bounded Description]: https://www.w3.org/Submission/CBD/
+//! [`cbd`]: fn.cbd.html
+//! [`symmetric_cbd`]: fn.symmetric_cbd.html
+//! [`TripleSource`]: ../triple/stream/trait.TripleSource.html
+//! [`collect_triples`]: ../triple/stream/trait.TripleSource.html#method.collect_triples
+
+use std::collections::{HashSet, VecDeque};
+
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+
+use crate::graph::Graph;
+use crate::triple::Triple;
+
+/// The Concise Bounded Description of `resource` in `graph`.
+///
+/// See [module documentation](index.html).
+pub fn cbd<'g, G, T>(graph: &'g G, resource: &T) -> Cbd<'g, G>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    Cbd::new(graph, resource, false)
+}
+
+/// The Symmetric Concise Bounded Description of `resource` in `graph`.
+///
+/// See [module documentation](index.html).
+pub fn symmetric_cbd<'g, G, T>(graph: &'g G, resource: &T) -> Cbd<'g, G>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    Cbd::new(graph, resource, true)
+}
+
+/// The triple source returned by [`cbd`] and [`symmetric_cbd`].
+///
+/// [`cbd`]: fn.cbd.html
+/// [`symmetric_cbd`]: fn.symmetric_cbd.html
+pub struct Cbd<'g, G> {
+    graph: &'g G,
+    /// The nodes remaining to describe, with whether they must be described inbound
+    queue: VecDeque<(BoxTerm, bool)>,
+    /// The nodes already queued, with whether they are described inbound
+    queued: HashSet<(BoxTerm, bool)>,
+    /// The triples found, but not yet yielded
+    buffer: VecDeque<[BoxTerm; 3]>,
+    /// Only used for the symmetric CBD, where a triple may be found in both directions
+    found: Option<HashSet<[BoxTerm; 3]>>,
+}
+
+impl<'g, G: Graph> Cbd<'g, G> {
+    fn new<T: TTerm + ?Sized>(graph: &'g G, resource: &T, symmetric: bool) -> Self {
+        let mut cbd = Cbd {
+            graph,
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+            buffer: VecDeque::new(),
+            found: if symmetric {
+                Some(HashSet::new())
+            } else {
+                None
+            },
+        };
+        let resource = BoxTerm::copy(resource);
+        cbd.enqueue(resource.clone(), false);
+        if symmetric {
+            cbd.enqueue(resource, true);
+        }
+        cbd
+    }
+
+    fn enqueue(&mut self, node: BoxTerm, inbound: bool) {
+        if self.queued.insert((node.clone(), inbound)) {
+            self.queue.push_back((node, inbound));
+        }
+    }
+
+    fn describe(&mut self, node: &BoxTerm, inbound: bool) -> Result<(), G::Error> {
+        if inbound {
+            for t in self.graph.triples_with_o(node) {
+                let t = t?;
+                let triple = [BoxTerm::copy(t.s()), BoxTerm::copy(t.p()), node.clone()];
+                if t.s().kind() == TermKind::BlankNode {
+                    self.enqueue(triple[0].clone(), true);
+                }
+                self.push(triple);
+            }
+        } else {
+            for t in self.graph.triples_with_s(node) {
+                let t = t?;
+                let triple = [node.clone(), BoxTerm::copy(t.p()), BoxTerm::copy(t.o())];
+                if t.o().kind() == TermKind::BlankNode {
+                    self.enqueue(triple[2].clone(), false);
+                }
+                self.push(triple);
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, triple: [BoxTerm; 3]) {
+        if let Some(found) = &mut self.found {
+            if !found.insert(triple.clone()) {
+                return;
+            }
+        }
+        self.buffer.push_back(triple);
+    }
+}
+
+impl<G: Graph> Iterator for Cbd<'_, G> {
+    type Item = Result<[BoxTerm; 3], G::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(triple) = self.buffer.pop_front() {
+                return Some(Ok(triple));
+            }
+            let (node, inbound) = self.queue.pop_front()?;
+            if let Err(err) = self.describe(&node, inbound) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const TTL: &str = r#"
+        <tag:a> <tag:p> _:b1, <tag:c>.
+        _:b1 <tag:p> _:b2, <tag:d>.
+        _:b2 <tag:p> _:b1.
+        <tag:c> <tag:p> _:b3.
+        _:b4 <tag:p> <tag:a>.
+        _:b5 <tag:p> _:b4.
+        <tag:e> <tag:p> _:b5.
+        <tag:a> <tag:p> <tag:a>.
+    "#;
+
+    #[test]
+    fn concise() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let a = BoxTerm::new_iri("tag:a")?;
+        let description: Vec<_> = cbd(&g, &a).collect::<Result<_, _>>()?;
+        // 3 triples about tag:a, 2 about _:b1, 1 about _:b2
+        assert_eq!(description.len(), 6);
+        let as_graph: FastGraph = cbd(&g, &a).collect_triples()?;
+        assert_eq!(as_graph.triples().count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn symmetric() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let a = BoxTerm::new_iri("tag:a")?;
+        let description: Vec<_> = symmetric_cbd(&g, &a).collect::<Result<_, _>>()?;
+        // the 6 triples of the CBD, plus 3 inbound triples
+        // (the loop on tag:a being counted only once)
+        assert_eq!(description.len(), 9);
+        let d = BoxTerm::new_iri("tag:d")?;
+        // _:b1 and _:b2 are described inbound, but not tag:a
+        assert_eq!(symmetric_cbd(&g, &d).count(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! Coercion of literals to more specific datatypes, for cleaning messy data.
+//!
+//! Data converted from spreadsheets or loosely typed formats often contains
+//! numbers, booleans or dates as plain strings (e.g. `"42"` rather than `"42"^^xsd:integer`).
+//! A [`Coercer`] retypes such literals according to a list of [`CoercionRule`]s.
+//! Each rule has a source datatype, a target datatype,
+//! and optionally a predicate restricting the triples it applies to.
+//!
+//! A literal is retyped by the first rule
+//! * whose source datatype is the datatype of the literal,
+//! * whose predicate (if any) is the predicate of the triple, and
+//! * whose target datatype accepts the lexical form of the literal
+//!   (as checked by [`check_literal`]).
+//!
+//! Surrounding whitespace is removed from the lexical form of retyped literals.
+//! Literals to which no rule applies are left unchanged,
+//! as well as language-tagged strings.
+//!
+//! NB: [`check_literal`] accepts any lexical form for the datatypes it does not support,
+//! so rules targeting such datatypes apply to all literals of their source datatype.
+//!
+//! # Example
+//! ```
+//! use sophia::coerce::Coercer;
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::ns::xsd;
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let src = turtle::parse_str(r#"
+//!     <tag:alice> <tag:age> "42"; <tag:birth> "1980-05-17"; <tag:name> "Alice".
+//! "#);
+//! let coercer = Coercer::with_common_rules();
+//! let g: FastGraph = coercer.coerce_triples(src).collect_triples()?;
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! let age = BoxTerm::new_iri("tag:age")?;
+//! let age_value = BoxTerm::new_literal_dt("42", xsd::integer)?;
+//! assert!(g.contains(&alice, &age, &age_value)?);
+//! let birth = BoxTerm::new_iri("tag:birth")?;
+//! let date = BoxTerm::new_literal_dt("1980-05-17", xsd::date)?;
+//! assert!(g.contains(&alice, &birth, &date)?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Coercer`]: struct.Coercer.html
+//! [`CoercionRule`]: struct.CoercionRule.html
+//! [`check_literal`]: ../validation/fn.check_literal.html
+
+use sophia_api::graph::MutableGraph;
+use sophia_api::ns::xsd;
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{StreamError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+use crate::validation::check_literal;
+
+/// A rule of a [`Coercer`](struct.Coercer.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoercionRule {
+    /// The datatype of the literals to retype.
+    pub from: BoxTerm,
+    /// The datatype to give them.
+    pub to: BoxTerm,
+    /// If set, only the objects of triples with this predicate are retyped.
+    pub predicate: Option<BoxTerm>,
+}
+
+/// Retypes literals according to a list of rules.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Coercer {
+    rules: Vec<CoercionRule>,
+}
+
+impl Coercer {
+    /// Build a coercer with no rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a coercer retyping `xsd:string` literals
+    /// to `xsd:integer`, `xsd:decimal`, `xsd:double`, `xsd:boolean`, `xsd:date` or `xsd:dateTime`
+    /// (tried in that order).
+    ///
+    /// NB: `"1"` and `"0"` are retyped to `xsd:integer` rather than `xsd:boolean`.
+    pub fn with_common_rules() -> Self {
+        let mut coercer = Self::new();
+        for to in &[
+            xsd::integer,
+            xsd::decimal,
+            xsd::double,
+            xsd::boolean,
+            xsd::date,
+            xsd::dateTime,
+        ] {
+            coercer.add_rule(&xsd::string, to);
+        }
+        coercer
+    }
+
+    /// The rules of this coercer, in the order in which they are tried.
+    pub fn rules(&self) -> &[CoercionRule] {
+        &self.rules
+    }
+
+    /// Add a rule retyping literals of datatype `from` to datatype `to`.
+    pub fn add_rule<T, U>(&mut self, from: &T, to: &U) -> &mut Self
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.rules.push(CoercionRule {
+            from: BoxTerm::copy(from),
+            to: BoxTerm::copy(to),
+            predicate: None,
+        });
+        self
+    }
+
+    /// Add a rule retyping literals of datatype `from` to datatype `to`,
+    /// only when they are the object of `predicate`.
+    pub fn add_rule_for<P, T, U>(&mut self, predicate: &P, from: &T, to: &U) -> &mut Self
+    where
+        P: TTerm + ?Sized,
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.rules.push(CoercionRule {
+            from: BoxTerm::copy(from),
+            to: BoxTerm::copy(to),
+            predicate: Some(BoxTerm::copy(predicate)),
+        });
+        self
+    }
+
+    /// Retype `term`, used as the object of `predicate` (if known),
+    /// or return `None` if no rule applies.
+    pub fn coerce<T, P>(&self, term: &T, predicate: Option<&P>) -> Option<BoxTerm>
+    where
+        T: TTerm + ?Sized,
+        P: TTerm + ?Sized,
+    {
+        if term.kind() != TermKind::Literal || term.language().is_some() {
+            return None;
+        }
+        let datatype = term.datatype()?;
+        let value = term.value();
+        let lex = value.trim();
+        self.rules
+            .iter()
+            .filter(|rule| term_eq(&rule.from, &datatype))
+            .filter(|rule| match (&rule.predicate, predicate) {
+                (None, _) => true,
+                (Some(p), Some(q)) => term_eq(p, q),
+                (Some(_), None) => false,
+            })
+            .find_map(|rule| {
+                let candidate = BoxTerm::new_literal_dt(lex, rule.to.clone()).ok()?;
+                check_literal(&candidate).ok().map(|_| candidate)
+            })
+    }
+
+    /// Retype the objects of the triples of `source` on the fly.
+    pub fn coerce_triples<'a, TS>(&'a self, source: TS) -> impl TripleSource<Error = TS::Error> + 'a
+    where
+        TS: TripleSource + 'a,
+    {
+        source.map_triples(move |t| self.coerce_triple(t.s(), t.p(), t.o()))
+    }
+
+    /// Retype the objects of the quads of `source` on the fly.
+    pub fn coerce_quads<'a, QS>(&'a self, source: QS) -> impl QuadSource<Error = QS::Error> + 'a
+    where
+        QS: QuadSource + 'a,
+    {
+        source.map_quads(move |q| {
+            (
+                self.coerce_triple(q.s(), q.p(), q.o()),
+                q.g().map(BoxTerm::copy),
+            )
+        })
+    }
+
+    /// Retype the objects of the triples of `graph` in place,
+    /// and return the number of triples that were changed.
+    pub fn coerce_graph<G: MutableGraph>(
+        &self,
+        graph: &mut G,
+    ) -> StreamResult<usize, G::Error, G::MutationError> {
+        let mut changes = vec![];
+        for t in graph.triples() {
+            let t = t.map_err(StreamError::SourceError)?;
+            if let Some(o) = self.coerce(t.o(), Some(t.p())) {
+                changes.push(([t.s(), t.p(), t.o()].map(BoxTerm::copy), o));
+            }
+        }
+        for ([s, p, o], _) in &changes {
+            graph.remove(s, p, o).map_err(StreamError::SinkError)?;
+        }
+        for ([s, p, _], o) in &changes {
+            graph.insert(s, p, o).map_err(StreamError::SinkError)?;
+        }
+        Ok(changes.len())
+    }
+
+    fn coerce_triple<T>(&self, s: &T, p: &T, o: &T) -> [BoxTerm; 3]
+    where
+        T: TTerm + ?Sized,
+    {
+        let o = self.coerce(o, Some(p)).unwrap_or_else(|| BoxTerm::copy(o));
+        [BoxTerm::copy(s), BoxTerm::copy(p), o]
+    }
+}
+
+// ---- tests ----
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::parser::nq;
+    use crate::test_util::t;
+    use sophia_api::dataset::Dataset;
+
+    fn lit(lex: &str, dt: sophia_api::term::SimpleIri<'static>) -> BoxTerm {
+        BoxTerm::new_literal_dt(lex, dt).unwrap()
+    }
+
+    #[test]
+    fn common_rules() {
+        let coercer = Coercer::with_common_rules();
+        let coerce = |lex| coercer.coerce(&lit(lex, xsd::string), None::<&BoxTerm>);
+        assert_eq!(coerce("42"), Some(lit("42", xsd::integer)));
+        assert_eq!(coerce(" 4.2 "), Some(lit("4.2", xsd::decimal)));
+        assert_eq!(coerce("4.2e1"), Some(lit("4.2e1", xsd::double)));
+        assert_eq!(coerce("true"), Some(lit("true", xsd::boolean)));
+        assert_eq!(coerce("2020-02-29"), Some(lit("2020-02-29", xsd::date)));
+        assert_eq!(
+            coerce("2020-02-29T12:00:00Z"),
+            Some(lit("2020-02-29T12:00:00Z", xsd::dateTime))
+        );
+        assert_eq!(coerce("2021-02-29"), None);
+        assert_eq!(coerce("Alice"), None);
+
+        let tagged = BoxTerm::new_literal_lang("42", "en").unwrap();
+        assert_eq!(coercer.coerce(&tagged, None::<&BoxTerm>), None);
+        assert_eq!(coercer.coerce(&t("x"), None::<&BoxTerm>), None);
+        assert_eq!(
+            coercer.coerce(&lit("42", xsd::integer), None::<&BoxTerm>),
+            None
+        );
+    }
+
+    #[test]
+    fn predicate_rules() {
+        let mut coercer = Coercer::new();
+        coercer.add_rule_for(&t("zip"), &xsd::string, &xsd::integer);
+        let zip = lit("01234", xsd::string);
+        assert_eq!(coercer.coerce(&zip, None::<&BoxTerm>), None);
+        assert_eq!(coercer.coerce(&zip, Some(&t("code"))), None);
+        assert_eq!(
+            coercer.coerce(&zip, Some(&t("zip"))),
+            Some(lit("01234", xsd::integer))
+        );
+    }
+
+    #[test]
+    fn coerce_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("a"), &t("age"), &lit("42", xsd::string))?;
+        g.insert(&t("a"), &t("name"), &lit("Alice", xsd::string))?;
+        assert_eq!(Coercer::with_common_rules().coerce_graph(&mut g)?, 1);
+        assert_eq!(g.triples().count(), 2);
+        assert!(g.contains(&t("a"), &t("age"), &lit("42", xsd::integer))?);
+        Ok(())
+    }
+
+    #[test]
+    fn coerce_quads() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::dataset::inmem::FastDataset;
+        let src = nq::parse_str("<tag:a> <tag:age> \"42\" <tag:g>.\n");
+        let coercer = Coercer::with_common_rules();
+        let d: FastDataset = coercer.coerce_quads(src).collect_quads()?;
+        assert!(d.contains(&t("a"), &t("age"), &lit("42", xsd::integer), Some(&t("g")))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! Transparent decompression of the input of parsers.
+//!
+//! Public RDF dumps are commonly distributed compressed
+//! (e.g. `.nt.gz` or `.ttl.bz2`).
+//! The [`decompress`] function detects the compression format of a [`BufRead`]
+//! from its first bytes,
+//! and wraps it into the appropriate decoder,
+//! so that its result can be passed directly to any parser:
+//!
+//! ```
+//! # use sophia::compression::decompress;
+//! # use sophia::parser::turtle;
+//! # use sophia::triple::stream::TripleSource;
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = std::io::BufReader::new(std::fs::File::open("dump.ttl.gz")?);
+//! let triples = turtle::parse_bufread(decompress(file)?);
+//! # Ok(()) }
+//! ```
+//!
+//! Each decoder is only available if the corresponding feature of this crate
+//! (`gzip`, `bzip2` or `zstd`) is enabled;
+//! compressed data in an unsupported format is reported as an [`io::Error`].
+//!
+//! [`decompress`]: fn.decompress.html
+//! [`BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
+//! [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+
+use std::io::{self, BufRead, Read};
+
+/// The length of the longest magic number recognized by [`Compression::from_magic_bytes`].
+const MAX_MAGIC_LEN: usize = 4;
+
+/// A compression format.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Compression {
+    /// No compression
+    Identity,
+    /// [gzip](https://tools.ietf.org/html/rfc1952) (requires the `gzip` feature)
+    Gzip,
+    /// [bzip2](https://sourceware.org/bzip2/) (requires the `bzip2` feature)
+    Bzip2,
+    /// [Zstandard](https://tools.ietf.org/html/rfc8878) (requires the `zstd` feature)
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the first bytes of some data.
+    ///
+    /// Return [`Identity`](#variant.Identity) if no known magic number is found.
+    pub fn from_magic_bytes(data: &[u8]) -> Compression {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if data.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::Identity
+        }
+    }
+
+    /// Detect the compression format from the extension of a file name, a path or an IRI.
+    ///
+    /// Return [`Identity`](#variant.Identity) if the extension is not a known compression extension.
+    pub fn from_extension(name: &str) -> Compression {
+        let path = name.split(['?', '#']).next().unwrap();
+        let file_name = path.rsplit(['/', '\\']).next().unwrap();
+        match file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+        {
+            Some(ext) if ext == "gz" => Compression::Gzip,
+            Some(ext) if ext == "bz2" => Compression::Bzip2,
+            Some(ext) if ext == "zst" => Compression::Zstd,
+            _ => Compression::Identity,
+        }
+    }
+
+    /// The usual file extension of this compression format (without the leading dot).
+    pub fn file_extension(self) -> Option<&'static str> {
+        match self {
+            Compression::Identity => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Bzip2 => Some("bz2"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Whether the decoder for this compression format is available
+    /// (given the enabled features).
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::Identity => true,
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Bzip2 => cfg!(feature = "bzip2"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Wrap `read` into a decoder for this compression format.
+    ///
+    /// Fail if the corresponding feature is not enabled.
+    pub fn decoder<'a, R>(self, read: R) -> io::Result<Box<dyn BufRead + 'a>>
+    where
+        R: BufRead + 'a,
+    {
+        match self {
+            Compression::Identity => Ok(Box::new(read)),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(Box::new(io::BufReader::new(
+                flate2::bufread::MultiGzDecoder::new(read),
+            ))),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Ok(Box::new(io::BufReader::new(
+                bzip2_crate::bufread::MultiBzDecoder::new(read),
+            ))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(io::BufReader::new(
+                zstd_crate::stream::read::Decoder::with_buffer(read)?,
+            ))),
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?}-compressed data is not supported", self),
+            )),
+        }
+    }
+}
+
+/// Detect the compression format of `read` (see [`Compression::from_magic_bytes`]),
+/// and wrap it into the appropriate decoder.
+///
+/// Uncompressed data is returned unchanged.
+///
+/// If the first read of `read` returns too few bytes to detect the format,
+/// `read` is read further, and the bytes already read are put back in front of it.
+///
+/// [`Compression::from_magic_bytes`]: enum.Compression.html#method.from_magic_bytes
+pub fn decompress<'a, R>(mut read: R) -> io::Result<Box<dyn BufRead + 'a>>
+where
+    R: BufRead + 'a,
+{
+    let buf = read.fill_buf()?;
+    if buf.len() >= MAX_MAGIC_LEN || buf.is_empty() {
+        let compression = Compression::from_magic_bytes(buf);
+        return compression.decoder(read);
+    }
+    let mut head = Vec::with_capacity(MAX_MAGIC_LEN);
+    while head.len() < MAX_MAGIC_LEN {
+        let buf = match read.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            break;
+        }
+        let n = buf.len().min(MAX_MAGIC_LEN - head.len());
+        head.extend_from_slice(&buf[..n]);
+        read.consume(n);
+    }
+    let compression = Compression::from_magic_bytes(&head);
+    compression.decoder(io::Cursor::new(head).chain(read))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    const NT: &str = "<tag:a> <tag:b> <tag:c>.\n";
+
+    #[test_case("dump.nt.gz", Compression::Gzip)]
+    #[test_case("http://example.org/dump.ttl.BZ2?x=y", Compression::Bzip2)]
+    #[test_case("dump.nq.zst", Compression::Zstd)]
+    #[test_case("dump.nq", Compression::Identity)]
+    #[test_case("http://example.org/a.gz/data", Compression::Identity)]
+    #[test_case("dir.bz2/file", Compression::Identity)]
+    #[test_case("dir.bz2\\file.nt.zst", Compression::Zstd)]
+    fn extension(name: &str, expected: Compression) {
+        assert_eq!(Compression::from_extension(name), expected);
+    }
+
+    #[test]
+    fn identity() {
+        let mut txt = String::new();
+        decompress(NT.as_bytes())
+            .unwrap()
+            .read_to_string(&mut txt)
+            .unwrap();
+        assert_eq!(txt, NT);
+    }
+
+    #[test]
+    fn identity_short_reads() {
+        for txt in &[NT, "<a", ""] {
+            let read = io::BufReader::with_capacity(1, txt.as_bytes());
+            let mut out = String::new();
+            decompress(read).unwrap().read_to_string(&mut out).unwrap();
+            assert_eq!(&out, txt);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        use std::io::Write;
+        let mut enc = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        enc.write_all(NT.as_bytes()).unwrap();
+        let data = enc.finish().unwrap();
+        assert_eq!(Compression::from_magic_bytes(&data), Compression::Gzip);
+        let mut txt = String::new();
+        decompress(&data[..])
+            .unwrap()
+            .read_to_string(&mut txt)
+            .unwrap();
+        assert_eq!(txt, NT);
+        // the magic number is split across several reads
+        let mut txt = String::new();
+        decompress(io::BufReader::with_capacity(1, &data[..]))
+            .unwrap()
+            .read_to_string(&mut txt)
+            .unwrap();
+        assert_eq!(txt, NT);
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn bzip2() {
+        use std::io::Write;
+        let mut enc = bzip2_crate::write::BzEncoder::new(vec![], bzip2_crate::Compression::best());
+        enc.write_all(NT.as_bytes()).unwrap();
+        let data = enc.finish().unwrap();
+        assert_eq!(Compression::from_magic_bytes(&data), Compression::Bzip2);
+        let mut txt = String::new();
+        decompress(&data[..])
+            .unwrap()
+            .read_to_string(&mut txt)
+            .unwrap();
+        assert_eq!(txt, NT);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd() {
+        let data = zstd_crate::stream::encode_all(NT.as_bytes(), 0).unwrap();
+        assert_eq!(Compression::from_magic_bytes(&data), Compression::Zstd);
+        let mut txt = String::new();
+        decompress(&data[..])
+            .unwrap()
+            .read_to_string(&mut txt)
+            .unwrap();
+        assert_eq!(txt, NT);
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn unsupported() {
+        assert!(decompress(&[0x1f, 0x8b, 0, 0][..]).is_err());
+        assert!(decompress(io::BufReader::with_capacity(1, &[0x1f, 0x8b, 0, 0][..])).is_err());
+    }
+}
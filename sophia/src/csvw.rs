@@ -0,0 +1,596 @@
+//! Conversion of tabular data (CSV, TSV) to RDF,
+//! following the *minimal mode* of [CSV on the Web] (CSVW).
+//!
+//! Each row of the table describes a subject
+//! (a fresh blank node, or the IRI produced by the `aboutUrl` of the column),
+//! and each non-null cell produces a triple
+//! whose predicate is the `propertyUrl` of the column
+//! (by default, the table URL with the column name as fragment identifier),
+//! and whose object is either the IRI produced by the `valueUrl` of the column,
+//! or a literal with the column's `datatype` and `lang`.
+//!
+//! Table descriptions are read from [JSON metadata],
+//! supporting `url`, `dialect` (`delimiter`, `header`) and `tableSchema`,
+//! as well as the inherited properties
+//! `aboutUrl`, `propertyUrl`, `valueUrl`, `datatype`, `default`, `lang`, `null` and `separator`,
+//! and the column properties `name`, `titles`, `suppressOutput` and `virtual`.
+//! Datatype formats and constraints are not checked.
+//!
+//! This module requires the `csvw` feature.
+//!
+//! ```
+//! # use sophia::csvw::{csv_to_rdf, TableMetadata};
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::triple::stream::TripleSource;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let csv = "id,name,age\n1,Alice,42\n2,Bob,\n";
+//! let metadata = TableMetadata::from_json(r#"{
+//!     "tableSchema": {
+//!         "aboutUrl": "people/{id}",
+//!         "columns": [
+//!             { "name": "id", "suppressOutput": true },
+//!             { "name": "name", "propertyUrl": "http://schema.org/name" },
+//!             { "name": "age", "datatype": "integer" }
+//!         ]
+//!     }
+//! }"#)?;
+//! let mut g = FastGraph::new();
+//! csv_to_rdf(csv.as_bytes(), "http://example.org/people.csv", &metadata)?
+//!     .add_to_graph(&mut g)?;
+//! assert_eq!(g.triples().count(), 3);
+//! # Ok(()) }
+//! ```
+//!
+//! [CSV on the Web]: https://www.w3.org/TR/csv2rdf/
+//! [JSON metadata]: https://www.w3.org/TR/tabular-metadata/
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter};
+use json::JsonValue;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::TTerm;
+use sophia_term::iri::resolve::{IriParsed, Resolve};
+use sophia_term::iri::Iri;
+use sophia_term::{BoxTerm, TermError};
+use thiserror::Error;
+
+/// An error raised while converting tabular data to RDF.
+#[derive(Debug, Error)]
+pub enum CsvwError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] json::Error),
+    #[error("Invalid metadata: {0}")]
+    Metadata(String),
+    #[error("Invalid term: {0}")]
+    Term(#[from] TermError),
+}
+
+/// Properties that columns inherit from the table and its schema.
+#[derive(Clone, Debug)]
+struct Inherited {
+    about_url: Option<String>,
+    property_url: Option<String>,
+    value_url: Option<String>,
+    datatype: Option<String>,
+    default: String,
+    lang: Option<String>,
+    null: Vec<String>,
+    separator: Option<String>,
+}
+
+impl Default for Inherited {
+    fn default() -> Self {
+        Inherited {
+            about_url: None,
+            property_url: None,
+            value_url: None,
+            datatype: None,
+            default: String::new(),
+            lang: None,
+            null: vec![String::new()],
+            separator: None,
+        }
+    }
+}
+
+impl Inherited {
+    /// Override the properties defined in `obj`.
+    fn update(&mut self, obj: &JsonValue) -> Result<(), CsvwError> {
+        let string = |key: &str| -> Result<Option<String>, CsvwError> {
+            match &obj[key] {
+                JsonValue::Null => Ok(None),
+                v => v
+                    .as_str()
+                    .map(|s| Some(s.to_string()))
+                    .ok_or_else(|| CsvwError::Metadata(format!("{} must be a string", key))),
+            }
+        };
+        if let Some(v) = string("aboutUrl")? {
+            self.about_url = Some(v);
+        }
+        if let Some(v) = string("propertyUrl")? {
+            self.property_url = Some(v);
+        }
+        if let Some(v) = string("valueUrl")? {
+            self.value_url = Some(v);
+        }
+        if let Some(v) = string("default")? {
+            self.default = v;
+        }
+        if let Some(v) = string("lang")? {
+            self.lang = Some(v);
+        }
+        if let Some(v) = string("separator")? {
+            self.separator = Some(v);
+        }
+        match &obj["datatype"] {
+            JsonValue::Null => (),
+            dt => {
+                let dt = dt
+                    .as_str()
+                    .or_else(|| dt["@id"].as_str())
+                    .or_else(|| dt["base"].as_str())
+                    .ok_or_else(|| CsvwError::Metadata("invalid datatype".to_string()))?;
+                self.datatype = Some(datatype_iri(dt));
+            }
+        }
+        match &obj["null"] {
+            JsonValue::Null => (),
+            v if v.is_array() => {
+                self.null = v
+                    .members()
+                    .filter_map(|m| m.as_str().map(str::to_string))
+                    .collect()
+            }
+            v => match v.as_str() {
+                Some(s) => self.null = vec![s.to_string()],
+                None => return Err(CsvwError::Metadata("null must be a string".to_string())),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// A column of a [`TableMetadata`](struct.TableMetadata.html).
+#[derive(Clone, Debug)]
+struct Column {
+    name: String,
+    suppress_output: bool,
+    virtual_: bool,
+    props: Inherited,
+}
+
+/// The description of a table, as defined by [CSVW metadata](https://www.w3.org/TR/tabular-metadata/).
+#[derive(Clone, Debug)]
+pub struct TableMetadata {
+    url: Option<String>,
+    delimiter: u8,
+    header: bool,
+    props: Inherited,
+    columns: Vec<Column>,
+}
+
+impl Default for TableMetadata {
+    fn default() -> Self {
+        TableMetadata {
+            url: None,
+            delimiter: b',',
+            header: true,
+            props: Inherited::default(),
+            columns: vec![],
+        }
+    }
+}
+
+impl TableMetadata {
+    /// Default metadata for tab-separated values.
+    pub fn tsv() -> Self {
+        TableMetadata {
+            delimiter: b'\t',
+            ..Self::default()
+        }
+    }
+
+    /// Parse a table description (or a table group description, of which the first table is used).
+    pub fn from_json(txt: &str) -> Result<Self, CsvwError> {
+        let root = json::parse(txt)?;
+        let table = if root["tables"].is_array() {
+            &root["tables"][0]
+        } else {
+            &root
+        };
+        if !table.is_object() {
+            return Err(CsvwError::Metadata("no table description".to_string()));
+        }
+        let mut md = TableMetadata {
+            url: table["url"].as_str().map(str::to_string),
+            ..TableMetadata::default()
+        };
+        for dialect in [&root["dialect"], &table["dialect"]].iter() {
+            if let Some(delimiter) = dialect["delimiter"].as_str() {
+                match delimiter.as_bytes() {
+                    [d] => md.delimiter = *d,
+                    _ => return Err(CsvwError::Metadata("invalid delimiter".to_string())),
+                }
+            }
+            if let Some(header) = dialect["header"].as_bool() {
+                md.header = header;
+            }
+        }
+        md.props.update(&root)?;
+        md.props.update(table)?;
+        let schema = &table["tableSchema"];
+        md.props.update(schema)?;
+        for (i, col) in schema["columns"].members().enumerate() {
+            let mut props = md.props.clone();
+            props.update(col)?;
+            let name = match col["name"].as_str() {
+                Some(name) => name.to_string(),
+                None => match titles(col).first() {
+                    Some(title) => encode(title, false),
+                    None => format!("_col.{}", i + 1),
+                },
+            };
+            md.columns.push(Column {
+                name,
+                suppress_output: col["suppressOutput"].as_bool().unwrap_or(false),
+                virtual_: col["virtual"].as_bool().unwrap_or(false),
+                props,
+            });
+        }
+        Ok(md)
+    }
+
+    /// Set the character separating cells.
+    pub fn set_delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set whether the first row of the data is a header row.
+    pub fn set_header(&mut self, header: bool) -> &mut Self {
+        self.header = header;
+        self
+    }
+}
+
+/// Convert the tabular data in `data` to RDF,
+/// according to `metadata`.
+///
+/// `url` is the URL of the tabular data;
+/// it is used to resolve the `url` of `metadata` (if any),
+/// which in turn is used to resolve the IRIs produced by `aboutUrl`, `propertyUrl` and `valueUrl`.
+pub fn csv_to_rdf<R: Read>(
+    data: R,
+    url: &str,
+    metadata: &TableMetadata,
+) -> Result<CsvwSource<R>, CsvwError> {
+    let base = match &metadata.url {
+        None => url.to_string(),
+        Some(md_url) => resolve(url, md_url)?,
+    };
+    let mut records = ReaderBuilder::new()
+        .delimiter(metadata.delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data)
+        .into_records();
+    let mut columns = metadata.columns.clone();
+    if metadata.header {
+        if let Some(header) = records.next() {
+            let header = header?;
+            if columns.is_empty() {
+                columns = header
+                    .iter()
+                    .map(|title| Column {
+                        name: encode(title, false),
+                        suppress_output: false,
+                        virtual_: false,
+                        props: metadata.props.clone(),
+                    })
+                    .collect();
+            }
+        }
+    }
+    Ok(CsvwSource {
+        records,
+        base,
+        columns,
+        default_props: metadata.props.clone(),
+        row: 0,
+        buffer: vec![],
+    })
+}
+
+/// The [`TripleSource`](../triple/stream/trait.TripleSource.html)
+/// returned by [`csv_to_rdf`](fn.csv_to_rdf.html).
+pub struct CsvwSource<R> {
+    records: StringRecordsIntoIter<R>,
+    base: String,
+    columns: Vec<Column>,
+    default_props: Inherited,
+    row: usize,
+    buffer: Vec<[BoxTerm; 3]>,
+}
+
+impl<R: Read> CsvwSource<R> {
+    /// Convert `record` into triples, and store them in the buffer.
+    fn convert_row(&mut self, record: StringRecord) -> Result<(), CsvwError> {
+        self.row += 1;
+        // complete missing columns, if no metadata was given
+        while self.columns.len() < record.len() {
+            self.columns.push(Column {
+                name: format!("_col.{}", self.columns.len() + 1),
+                suppress_output: false,
+                virtual_: false,
+                props: self.default_props.clone(),
+            });
+        }
+        let mut values = HashMap::new();
+        values.insert("_row".to_string(), self.row.to_string());
+        let mut cells = vec![];
+        let mut record = record.iter();
+        for col in self.columns.iter() {
+            let raw = if col.virtual_ { None } else { record.next() };
+            let value = match raw {
+                Some("") | None => &col.props.default[..],
+                Some(raw) => raw,
+            };
+            let value = if col.props.null.iter().any(|n| n == value) {
+                None
+            } else {
+                values.insert(col.name.clone(), value.to_string());
+                Some(value)
+            };
+            cells.push(value);
+        }
+        let row_node = BoxTerm::new_bnode(format!("row{}", self.row))?;
+        for (i, (col, value)) in self.columns.iter().zip(cells).enumerate() {
+            if col.suppress_output || (value.is_none() && col.props.value_url.is_none()) {
+                continue;
+            }
+            values.insert("_column".to_string(), (i + 1).to_string());
+            values.insert("_name".to_string(), col.name.clone());
+            let s = match &col.props.about_url {
+                Some(tpl) => BoxTerm::new_iri(resolve(&self.base, &expand(tpl, &values))?)?,
+                None => row_node.clone(),
+            };
+            let p = match &col.props.property_url {
+                Some(tpl) => expand(tpl, &values),
+                None => format!("#{}", col.name),
+            };
+            let p = BoxTerm::new_iri(resolve(&self.base, &p)?)?;
+            if let Some(tpl) = &col.props.value_url {
+                let o = BoxTerm::new_iri(resolve(&self.base, &expand(tpl, &values))?)?;
+                self.buffer.push([s, p, o]);
+                continue;
+            }
+            let value = value.unwrap();
+            let items: Vec<&str> = match &col.props.separator {
+                None => vec![value],
+                Some(sep) => value.split(&sep[..]).collect(),
+            };
+            for item in items {
+                let dt = col.props.datatype.as_deref();
+                let o = match (dt, &col.props.lang) {
+                    (None, Some(lang)) => BoxTerm::new_literal_lang(item, &lang[..])?,
+                    (None, None) => BoxTerm::new_literal_dt(item, xsd::string)?,
+                    (Some(dt), Some(lang)) if xsd::string.value() == dt => {
+                        BoxTerm::new_literal_lang(item, &lang[..])?
+                    }
+                    (Some(dt), _) => BoxTerm::new_literal_dt(item, Iri::<Box<str>>::new(dt)?)?,
+                };
+                self.buffer.push([s.clone(), p.clone(), o]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for CsvwSource<R> {
+    type Item = Result<[BoxTerm; 3], CsvwError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.is_empty() {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if let Err(err) = self.convert_row(record) {
+                return Some(Err(err));
+            }
+            self.buffer.reverse();
+        }
+        self.buffer.pop().map(Ok)
+    }
+}
+
+/// The titles of a column description.
+fn titles(col: &JsonValue) -> Vec<String> {
+    let titles = &col["titles"];
+    if let Some(title) = titles.as_str() {
+        vec![title.to_string()]
+    } else if titles.is_array() {
+        titles
+            .members()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect()
+    } else {
+        titles
+            .entries()
+            .flat_map(|(_, t)| {
+                if t.is_array() {
+                    t.members().filter_map(JsonValue::as_str).collect()
+                } else {
+                    t.as_str().into_iter().collect::<Vec<_>>()
+                }
+            })
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// The IRI of a [CSVW datatype](https://www.w3.org/TR/tabular-metadata/#datatypes).
+fn datatype_iri(name: &str) -> String {
+    match name {
+        "any" => format!("{}anyAtomicType", xsd::PREFIX),
+        "binary" => format!("{}base64Binary", xsd::PREFIX),
+        "datetime" => format!("{}dateTime", xsd::PREFIX),
+        "number" => format!("{}double", xsd::PREFIX),
+        "html" => rdf::HTML.value().to_string(),
+        "json" => "http://www.w3.org/ns/csvw#JSON".to_string(),
+        "xml" => rdf::XMLLiteral.value().to_string(),
+        "string" => xsd::string.value().to_string(),
+        _ if name.contains(':') => name.to_string(),
+        _ => format!("{}{}", xsd::PREFIX, name),
+    }
+}
+
+/// Expand a [URI template](https://tools.ietf.org/html/rfc6570)
+/// (only simple, reserved and fragment expansions are supported).
+fn expand(template: &str, values: &HashMap<String, String>) -> String {
+    let mut res = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        res.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let expr = &rest[start + 1..end];
+        let (prefix, reserved, expr) = match expr.as_bytes().first() {
+            Some(b'+') => ("", true, &expr[1..]),
+            Some(b'#') => ("#", true, &expr[1..]),
+            _ => ("", false, expr),
+        };
+        let expanded: Vec<_> = expr
+            .split(',')
+            .filter_map(|var| values.get(var))
+            .map(|val| encode(val, reserved))
+            .collect();
+        if !expanded.is_empty() {
+            res.push_str(prefix);
+            res.push_str(&expanded.join(","));
+        }
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Percent-encode `txt`, except for unreserved characters
+/// (and reserved characters and percent-encoded triplets, if `reserved` is true).
+fn encode(txt: &str, reserved: bool) -> String {
+    let mut res = String::with_capacity(txt.len());
+    for (i, b) in txt.bytes().enumerate() {
+        let keep = b.is_ascii_alphanumeric()
+            || b"-._~".contains(&b)
+            || (reserved && b":/?#[]@!$&'()*+,;=".contains(&b))
+            || (reserved
+                && b == b'%'
+                && txt.as_bytes()[i + 1..]
+                    .iter()
+                    .take(2)
+                    .filter(|b| b.is_ascii_hexdigit())
+                    .count()
+                    == 2);
+        if keep {
+            res.push(b as char);
+        } else {
+            res.push_str(&format!("%{:02X}", b));
+        }
+    }
+    res
+}
+
+/// Resolve `iri` against `base`.
+fn resolve(base: &str, iri: &str) -> Result<String, CsvwError> {
+    let invalid = |_| CsvwError::Metadata(format!("can not resolve <{}> against <{}>", iri, base));
+    let base = IriParsed::new(base).map_err(invalid)?;
+    let resolved: Result<_, _> = base.resolve(iri);
+    Ok(resolved.map_err(invalid)?.to_string())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serializer::nt::NtSerializer;
+    use crate::serializer::{Stringifier, TripleSerializer};
+
+    fn to_nt(csv: &str, metadata: &TableMetadata) -> String {
+        let source = csv_to_rdf(csv.as_bytes(), "http://example.org/data.csv", metadata).unwrap();
+        let mut ser = NtSerializer::new_stringifier();
+        ser.serialize_triples(source).unwrap();
+        ser.as_str().to_string()
+    }
+
+    #[test]
+    fn no_metadata() {
+        let nt = to_nt("a,b c\n1,\n", &TableMetadata::default());
+        assert_eq!(nt, "_:row1 <http://example.org/data.csv#a> \"1\".\n");
+    }
+
+    #[test]
+    fn tsv_without_header() {
+        let mut md = TableMetadata::tsv();
+        md.set_header(false);
+        let nt = to_nt("x\ty\n", &md);
+        assert_eq!(
+            nt,
+            "_:row1 <http://example.org/data.csv#_col.1> \"x\".\n\
+             _:row1 <http://example.org/data.csv#_col.2> \"y\".\n"
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let md = TableMetadata::from_json(
+            r##"{
+            "url": "people.csv",
+            "tableSchema": {
+                "aboutUrl": "#person-{id}",
+                "columns": [
+                    { "titles": "id", "suppressOutput": true },
+                    { "name": "name", "propertyUrl": "http://schema.org/name", "lang": "en" },
+                    { "name": "age", "datatype": "integer", "null": "N/A" },
+                    { "name": "tags", "separator": " " },
+                    { "name": "type", "virtual": true,
+                      "propertyUrl": "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+                      "valueUrl": "http://schema.org/Person" }
+                ]
+            }
+        }"##,
+        )
+        .unwrap();
+        let nt = to_nt("id,name,age,tags\n1,Alice,N/A,a b\n", &md);
+        let p = "<http://example.org/people.csv#person-1>";
+        assert_eq!(
+            nt,
+            format!(
+                "{p} <http://schema.org/name> \"Alice\"@en.\n\
+                 {p} <http://example.org/people.csv#tags> \"a\".\n\
+                 {p} <http://example.org/people.csv#tags> \"b\".\n\
+                 {p} <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>.\n",
+                p = p
+            )
+        );
+    }
+
+    #[test]
+    fn uri_templates() {
+        let mut values = HashMap::new();
+        values.insert("a".to_string(), "x y/z".to_string());
+        assert_eq!(expand("p/{a}", &values), "p/x%20y%2Fz");
+        assert_eq!(expand("p/{+a}", &values), "p/x%20y/z");
+        assert_eq!(expand("p{#a}", &values), "p#x%20y/z");
+        assert_eq!(expand("p{#b}", &values), "p");
+    }
+}
@@ -0,0 +1,546 @@
+//! Change logs of datasets, in the [RDF Patch] format of [RDF Delta].
+//!
+//! [`RecordingDataset`] wraps another dataset,
+//! and records every quad actually inserted into or removed from it.
+//! The recorded changes can be [exported] at any time as a [`Patch`],
+//! which can be serialized, sent to a replica (or any downstream system),
+//! [parsed] back and [applied] to another dataset.
+//!
+//! Each exported patch is wrapped in a transaction (`TX` ... `TC`),
+//! and patches can carry headers (`H`),
+//! such as the `id` and `prev` headers used by RDF Delta to chain patches.
+//! When a patch is applied, the changes of a transaction are only applied when it is committed (`TC`);
+//! aborted transactions (`TA`) are ignored.
+//! Prefix declarations (`PA`, `PD`) are preserved, but have no effect on the dataset.
+//!
+//! Terms are written and parsed in N-Quads syntax;
+//! prefixed names are not supported.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, Dataset, MutableDataset};
+//! use sophia::dataset::delta::{Patch, RecordingDataset};
+//! use sophia::ns::{rdf, rdfs};
+//! use sophia::term::BoxTerm;
+//!
+//! let mut primary = RecordingDataset::new(FastDataset::new());
+//! primary.insert(&rdf::type_, &rdf::type_, &rdf::Property, None as Option<&BoxTerm>)?;
+//! primary.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, None as Option<&BoxTerm>)?;
+//! primary.remove(&rdf::type_, &rdf::type_, &rdf::Property, None as Option<&BoxTerm>)?;
+//! let log = primary.take_patch().to_string();
+//!
+//! let mut replica = FastDataset::new();
+//! log.parse::<Patch>()?.apply(&mut replica)?;
+//! assert_eq!(replica.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [RDF Delta]: https://afs.github.io/rdf-delta/
+//! [`RecordingDataset`]: struct.RecordingDataset.html
+//! [exported]: struct.RecordingDataset.html#method.take_patch
+//! [`Patch`]: struct.Patch.html
+//! [parsed]: struct.Patch.html#impl-FromStr
+//! [applied]: struct.Patch.html#method.apply
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use sophia_api::dataset::{DQuadSource, DResult, Dataset, MDResult, MutableDataset, SetDataset};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::nq;
+use crate::serializer::nt::write_term;
+
+/// The type of quads in a [`Patch`](struct.Patch.html).
+pub type PatchQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// A row of a [`Patch`](struct.Patch.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchRow {
+    /// `H`: a header, with its name and value.
+    Header(String, BoxTerm),
+    /// `TX`: the beginning of a transaction.
+    Begin,
+    /// `TC`: the commit of the current transaction.
+    Commit,
+    /// `TA`: the abortion of the current transaction.
+    Abort,
+    /// `PA`: the declaration of a prefix, with its IRI.
+    AddPrefix(String, String),
+    /// `PD`: the removal of a prefix declaration, with its IRI.
+    DeletePrefix(String, String),
+    /// `A`: the addition of a quad.
+    Add(PatchQuad),
+    /// `D`: the deletion of a quad.
+    Delete(PatchQuad),
+}
+
+/// This error is raised when parsing or applying a [`Patch`](struct.Patch.html) fails.
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// A row of the patch is malformed.
+    #[error("Invalid patch at line {line}: {message}")]
+    Syntax {
+        /// The line of the malformed row (starting at 1).
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// The transactions of the patch are not properly nested.
+    #[error("Invalid transaction: {0}")]
+    Transaction(String),
+    /// The target dataset raised an error.
+    #[error("Error in dataset: {0}")]
+    Dataset(String),
+}
+
+/// A sequence of changes, in the [RDF Patch] format.
+///
+/// See [module documentation](index.html).
+///
+/// [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch {
+    rows: Vec<PatchRow>,
+}
+
+impl Patch {
+    /// Build an empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rows of this patch.
+    pub fn rows(&self) -> &[PatchRow] {
+        &self.rows
+    }
+
+    /// Append a row to this patch.
+    pub fn push(&mut self, row: PatchRow) {
+        self.rows.push(row);
+    }
+
+    /// Whether this patch has no row.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The value of the first header with the given name, if any.
+    pub fn header(&self, name: &str) -> Option<&BoxTerm> {
+        self.rows.iter().find_map(|row| match row {
+            PatchRow::Header(n, value) if n == name => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Apply the changes of this patch to `dataset`.
+    ///
+    /// Changes outside of any transaction are applied immediately;
+    /// changes inside a transaction are applied when the transaction is committed.
+    /// Fails (without applying the pending transaction)
+    /// if transactions are not properly nested.
+    pub fn apply<D>(&self, dataset: &mut D) -> Result<(), PatchError>
+    where
+        D: MutableDataset + ?Sized,
+    {
+        let mut tx: Option<Vec<&PatchRow>> = None;
+        for row in &self.rows {
+            match (row, &mut tx) {
+                (PatchRow::Begin, Some(_)) => {
+                    return Err(PatchError::Transaction("nested TX".to_string()))
+                }
+                (PatchRow::Begin, None) => tx = Some(vec![]),
+                (PatchRow::Commit, Some(rows)) => {
+                    for row in rows.iter() {
+                        apply_row(row, dataset)?;
+                    }
+                    tx = None;
+                }
+                (PatchRow::Abort, Some(_)) => tx = None,
+                (PatchRow::Commit, None) | (PatchRow::Abort, None) => {
+                    return Err(PatchError::Transaction(
+                        "TC or TA outside of a transaction".to_string(),
+                    ))
+                }
+                (_, Some(rows)) => rows.push(row),
+                (_, None) => apply_row(row, dataset)?,
+            }
+        }
+        match tx {
+            Some(_) => Err(PatchError::Transaction("unterminated TX".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize this patch into `write`.
+    pub fn write_to<W: io::Write>(&self, write: &mut W) -> io::Result<()> {
+        for row in &self.rows {
+            match row {
+                PatchRow::Header(name, value) => {
+                    write!(write, "H {} ", name)?;
+                    write_term(write, value)?;
+                }
+                PatchRow::Begin => write.write_all(b"TX")?,
+                PatchRow::Commit => write.write_all(b"TC")?,
+                PatchRow::Abort => write.write_all(b"TA")?,
+                PatchRow::AddPrefix(prefix, iri) => write!(write, "PA {}: <{}>", prefix, iri)?,
+                PatchRow::DeletePrefix(prefix, iri) => write!(write, "PD {}: <{}>", prefix, iri)?,
+                PatchRow::Add(quad) => write_quad(write, "A", quad)?,
+                PatchRow::Delete(quad) => write_quad(write, "D", quad)?,
+            }
+            write.write_all(b" .\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut txt = vec![];
+        self.write_to(&mut txt).map_err(|_| fmt::Error)?;
+        // write_term only produces valid UTF-8
+        f.write_str(&String::from_utf8(txt).unwrap())
+    }
+}
+
+impl FromStr for Patch {
+    type Err = PatchError;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        let mut patch = Patch::new();
+        for (i, line) in txt.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let syntax = |message: &str| PatchError::Syntax {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let (code, rest) = line.split_at(line.find(' ').unwrap_or(line.len()));
+            let rest = rest.trim();
+            let body = rest
+                .strip_suffix('.')
+                .map(str::trim_end)
+                .ok_or_else(|| syntax("missing final '.'"))?;
+            let row = match code {
+                "TX" | "TC" | "TA" if !body.is_empty() => return Err(syntax("unexpected content")),
+                "TX" => PatchRow::Begin,
+                "TC" => PatchRow::Commit,
+                "TA" => PatchRow::Abort,
+                "H" => {
+                    let (name, value) = body.split_at(body.find(' ').unwrap_or(body.len()));
+                    let value =
+                        parse_term(value.trim()).ok_or_else(|| syntax("invalid header value"))?;
+                    PatchRow::Header(name.to_string(), value)
+                }
+                "PA" | "PD" => {
+                    let (prefix, iri) = body
+                        .split_once(':')
+                        .ok_or_else(|| syntax("invalid prefix declaration"))?;
+                    let iri = iri
+                        .trim()
+                        .strip_prefix('<')
+                        .and_then(|iri| iri.strip_suffix('>'))
+                        .ok_or_else(|| syntax("invalid prefix IRI"))?;
+                    if code == "PA" {
+                        PatchRow::AddPrefix(prefix.trim().to_string(), iri.to_string())
+                    } else {
+                        PatchRow::DeletePrefix(prefix.trim().to_string(), iri.to_string())
+                    }
+                }
+                "A" | "D" => {
+                    let quad = parse_quad(body).ok_or_else(|| syntax("invalid quad"))?;
+                    if code == "A" {
+                        PatchRow::Add(quad)
+                    } else {
+                        PatchRow::Delete(quad)
+                    }
+                }
+                _ => return Err(syntax("unknown row code")),
+            };
+            patch.push(row);
+        }
+        Ok(patch)
+    }
+}
+
+/// A dataset wrapper recording the changes applied to it.
+///
+/// See [module documentation](index.html).
+pub struct RecordingDataset<D> {
+    inner: D,
+    changes: Vec<PatchRow>,
+}
+
+impl<D> RecordingDataset<D>
+where
+    D: Dataset,
+{
+    /// Wrap `inner`, with no recorded change.
+    pub fn new(inner: D) -> Self {
+        RecordingDataset {
+            inner,
+            changes: vec![],
+        }
+    }
+
+    /// The wrapped dataset.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped dataset, dropping the recorded changes.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// The changes recorded since the last call to [`take_patch`](#method.take_patch).
+    pub fn pending(&self) -> &[PatchRow] {
+        &self.changes
+    }
+
+    /// Export the recorded changes as a single transaction, and forget them.
+    ///
+    /// Return an empty patch if no change was recorded.
+    pub fn take_patch(&mut self) -> Patch {
+        self.take_patch_with_headers(vec![])
+    }
+
+    /// Export the recorded changes as a single transaction, preceded by the given headers,
+    /// and forget them.
+    ///
+    /// Return an empty patch (without headers) if no change was recorded.
+    pub fn take_patch_with_headers(&mut self, headers: Vec<(String, BoxTerm)>) -> Patch {
+        if self.changes.is_empty() {
+            return Patch::new();
+        }
+        let mut rows: Vec<_> = headers
+            .into_iter()
+            .map(|(name, value)| PatchRow::Header(name, value))
+            .collect();
+        rows.push(PatchRow::Begin);
+        rows.append(&mut self.changes);
+        rows.push(PatchRow::Commit);
+        Patch { rows }
+    }
+}
+
+impl<D> Dataset for RecordingDataset<D>
+where
+    D: Dataset,
+{
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.inner.quads()
+    }
+
+    fn is_set(&self) -> bool {
+        self.inner.is_set()
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.contains(s, p, o, g)
+    }
+}
+
+impl<D> MutableDataset for RecordingDataset<D>
+where
+    D: MutableDataset,
+{
+    type MutationError = D::MutationError;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let inserted = self.inner.insert(s, p, o, g)?;
+        if inserted {
+            self.changes.push(PatchRow::Add(copy_quad(s, p, o, g)));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let removed = self.inner.remove(s, p, o, g)?;
+        if removed {
+            self.changes.push(PatchRow::Delete(copy_quad(s, p, o, g)));
+        }
+        Ok(removed)
+    }
+}
+
+impl<D> SetDataset for RecordingDataset<D> where D: SetDataset {}
+
+fn copy_quad<TS, TP, TO, TG>(s: &TS, p: &TP, o: &TO, g: Option<&TG>) -> PatchQuad
+where
+    TS: TTerm + ?Sized,
+    TP: TTerm + ?Sized,
+    TO: TTerm + ?Sized,
+    TG: TTerm + ?Sized,
+{
+    (
+        [BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)],
+        g.map(BoxTerm::copy),
+    )
+}
+
+fn apply_row<D>(row: &PatchRow, dataset: &mut D) -> Result<(), PatchError>
+where
+    D: MutableDataset + ?Sized,
+{
+    let error = |err: D::MutationError| PatchError::Dataset(err.to_string());
+    match row {
+        PatchRow::Add(q) => dataset.insert(q.s(), q.p(), q.o(), q.g()).map_err(error)?,
+        PatchRow::Delete(q) => dataset.remove(q.s(), q.p(), q.o(), q.g()).map_err(error)?,
+        _ => true,
+    };
+    Ok(())
+}
+
+fn write_quad<W: io::Write>(write: &mut W, code: &str, quad: &PatchQuad) -> io::Result<()> {
+    write.write_all(code.as_bytes())?;
+    for t in quad.0.iter().chain(quad.1.iter()) {
+        write.write_all(b" ")?;
+        write_term(write, t)?;
+    }
+    Ok(())
+}
+
+/// Parse `txt` as the terms of a quad, in N-Quads syntax.
+fn parse_quad(txt: &str) -> Option<PatchQuad> {
+    let mut quads: Vec<PatchQuad> = nq::parse_str(&format!("{} .\n", txt))
+        .collect_quads()
+        .ok()?;
+    if quads.len() == 1 {
+        quads.pop()
+    } else {
+        None
+    }
+}
+
+/// Parse `txt` as a single term, in N-Quads syntax.
+fn parse_term(txt: &str) -> Option<BoxTerm> {
+    let ([_, _, o], g) = parse_quad(&format!("<tag:s> <tag:p> {}", txt))?;
+    match g {
+        None => Some(o),
+        Some(_) => None,
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use sophia_api::ns::{rdf, rdfs};
+
+    const NO_G: Option<&BoxTerm> = None;
+
+    #[test]
+    fn record_and_replay() -> Result<(), Box<dyn std::error::Error>> {
+        let g = BoxTerm::new_iri("tag:g")?;
+        let mut d = RecordingDataset::new(FastDataset::new());
+        assert!(d.take_patch().is_empty());
+        d.insert(&rdf::type_, &rdf::type_, &rdf::Property, Some(&g))?;
+        d.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, NO_G)?;
+        // no-ops are not recorded
+        d.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, NO_G)?;
+        d.remove(&rdfs::label, &rdf::type_, &rdf::Property, NO_G)?;
+        assert_eq!(d.pending().len(), 2);
+
+        let id = BoxTerm::new_iri("uuid:0a1b")?;
+        let p1 = d.take_patch_with_headers(vec![("id".to_string(), id.clone())]);
+        assert!(d.pending().is_empty());
+        assert_eq!(p1.header("id"), Some(&id));
+        d.remove(&rdf::type_, &rdf::type_, &rdf::Property, Some(&g))?;
+        let p2 = d.take_patch();
+
+        let mut replica = FastDataset::new();
+        for patch in &[p1, p2] {
+            let parsed: Patch = patch.to_string().parse()?;
+            assert_eq!(&parsed, patch);
+            parsed.apply(&mut replica)?;
+        }
+        assert_eq!(replica.quads().count(), 1);
+        assert!(replica.contains(&rdfs::Class, &rdf::type_, &rdfs::Class, NO_G)?);
+        Ok(())
+    }
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn std::error::Error>> {
+        let patch: Patch = r#"
+            H id <uuid:1> .
+            PA rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            TX .
+            A <tag:a> <tag:b> "c"@en <tag:g> .
+            A _:x <tag:b> "d" .
+            TC .
+            TX .
+            D <tag:a> <tag:b> "c"@en <tag:g> .
+            TA .
+        "#
+        .parse()?;
+        assert_eq!(patch.rows().len(), 9);
+        assert_eq!(
+            patch.rows()[1],
+            PatchRow::AddPrefix("rdfs".into(), rdfs::PREFIX.into())
+        );
+        let mut d = FastDataset::new();
+        patch.apply(&mut d)?;
+        // the second transaction was aborted
+        assert_eq!(d.quads().count(), 2);
+
+        assert!("A <tag:a> <tag:b> .".parse::<Patch>().is_err());
+        assert!("X .".parse::<Patch>().is_err());
+        assert!("TX".parse::<Patch>().is_err());
+        let unterminated: Patch = "TX .\nA <tag:a> <tag:b> <tag:c> .".parse()?;
+        assert!(unterminated.apply(&mut d).is_err());
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+}
@@ -1,10 +1,15 @@
 //! A utility trait for building datasets using indexed terms.
 
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
-use sophia_api::term::TTerm;
+use sophia_api::dataset::{DResult, Dataset};
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm};
 use sophia_term::*;
 
+use crate::graph::indexed::{Compaction, MemoryUsage};
+
 /// A utility trait for implementing [`Dataset`] and [`MutableDataset`]
 /// based on an internal [`TermIndexMap`] for efficient storage.
 ///
@@ -25,9 +30,110 @@ pub trait IndexedDataset {
     /// Construct a new empty dataset, provisioning for storing `capacity` quads.
     fn with_capacity(capacity: usize) -> Self;
 
+    /// Reserve capacity for at least `additional` more quads;
+    /// the default implementation does nothing.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
     /// Shrink the memory consumption of the dataset as much as possible.
     fn shrink_to_fit(&mut self);
 
+    /// Return an estimate of the heap memory (in bytes) used by the dataset,
+    /// including the textual data of its terms.
+    fn heap_size(&self) -> usize;
+
+    /// Return an estimate of the heap memory (in bytes) used by the term dictionary of the dataset;
+    /// this is included in [`heap_size`](#tymethod.heap_size).
+    fn dictionary_heap_size(&self) -> usize;
+
+    /// Return an estimate of the heap memory used by the dataset,
+    /// broken down into its term dictionary and its quad indexes.
+    ///
+    /// See also [`graph_memory_usage`](#method.graph_memory_usage).
+    fn memory_usage(&self) -> MemoryUsage {
+        let dictionary = self.dictionary_heap_size();
+        MemoryUsage {
+            dictionary,
+            indexes: self.heap_size().saturating_sub(dictionary),
+        }
+    }
+
+    /// Break down the [`memory_usage`](#method.memory_usage) of the dataset per graph.
+    ///
+    /// Memory is not actually allocated per graph, so this is an apportionment:
+    /// each graph is charged a share of the indexes proportional to its number of quads,
+    /// and a share of the dictionary proportional to its number of distinct terms
+    /// (terms used in several graphs are charged to each of them).
+    /// The shares add up to the total `memory_usage`, up to rounding.
+    ///
+    /// This requires a full scan of the dataset.
+    fn graph_memory_usage(&self) -> DResult<Self, HashMap<Option<BoxTerm>, GraphMemoryUsage>>
+    where
+        Self: Dataset + Sized,
+    {
+        #[allow(clippy::type_complexity)]
+        let mut graphs: HashMap<Option<Self::Index>, (usize, HashSet<Self::Index>)> =
+            HashMap::new();
+        for q in self.quads() {
+            let q = q?;
+            let gi = q.g().and_then(|g| self.get_index(g));
+            let (quads, terms) = graphs.entry(gi).or_default();
+            *quads += 1;
+            terms.extend(
+                [q.s(), q.p(), q.o()]
+                    .iter()
+                    .filter_map(|t| self.get_index(*t)),
+            );
+        }
+        let total_quads: usize = graphs.values().map(|(quads, _)| *quads).sum();
+        let total_terms: usize = graphs.values().map(|(_, terms)| terms.len()).sum();
+        let usage = self.memory_usage();
+        let share = |bytes: usize, part: usize, total: usize| {
+            (bytes as u128 * part as u128 / total.max(1) as u128) as usize
+        };
+        Ok(graphs
+            .into_iter()
+            .map(|(gi, (quads, terms))| {
+                let name = gi.and_then(|gi| self.get_term(gi)).map(BoxTerm::copy);
+                let usage = GraphMemoryUsage {
+                    quads,
+                    terms: terms.len(),
+                    memory: MemoryUsage {
+                        dictionary: share(usage.dictionary, terms.len(), total_terms),
+                        indexes: share(usage.indexes, quads, total_quads),
+                    },
+                };
+                (name, usage)
+            })
+            .collect())
+    }
+
+    /// Rebuild the dataset from scratch, so that its terms are densely re-indexed,
+    /// then shrink it with [`shrink_to_fit`](#tymethod.shrink_to_fit).
+    ///
+    /// See [`IndexedGraph::compact`](../../graph/indexed/trait.IndexedGraph.html#method.compact).
+    ///
+    /// NB: indices previously returned by this dataset are invalidated.
+    fn compact(&mut self) -> DResult<Self, Compaction>
+    where
+        Self: Dataset + Sized,
+    {
+        let before = self.heap_size();
+        let mut compacted = Self::with_capacity(self.quads().size_hint().0);
+        for q in self.quads() {
+            let q = q?;
+            compacted.insert_indexed(q.s(), q.p(), q.o(), q.g());
+        }
+        compacted.shrink_to_fit();
+        *self = compacted;
+        Ok(Compaction {
+            before,
+            after: self.heap_size(),
+        })
+    }
+
     /// Return the index for the given term, if it exists.
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -82,6 +188,21 @@ pub trait IndexedDataset {
         TG: TTerm + ?Sized;
 }
 
+/// The share of a graph in the memory used by an [`IndexedDataset`],
+/// as returned by [`IndexedDataset::graph_memory_usage`].
+///
+/// [`IndexedDataset`]: trait.IndexedDataset.html
+/// [`IndexedDataset::graph_memory_usage`]: trait.IndexedDataset.html#method.graph_memory_usage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GraphMemoryUsage {
+    /// The number of quads in the graph.
+    pub quads: usize,
+    /// The number of distinct terms used by the quads of the graph (graph name excluded).
+    pub terms: usize,
+    /// The estimated heap memory charged to the graph.
+    pub memory: MemoryUsage,
+}
+
 /// Defines the implementation of [`CollectibleDataset`] for [`IndexedDataset`].
 ///
 /// [`CollectibleDataset`]: dataset/trait.CollectibleDataset.html
@@ -155,10 +276,62 @@ macro_rules! impl_mutable_dataset_for_indexed_dataset {
         {
             Ok(self.remove_indexed(s, p, o, g).is_some())
         }
+        fn reserve_quads(&mut self, additional: usize) {
+            $crate::dataset::indexed::IndexedDataset::reserve(self, additional)
+        }
     };
 }
 
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::MutableDataset;
+    use crate::ns::rdf;
+    use crate::test_util::t;
+
+    #[test]
+    fn compact() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = FastDataset::new();
+        for i in 0..200 {
+            let g = t(&format!("g{}", i));
+            d.insert(&t(&format!("s{}", i)), &rdf::value, &t("o"), Some(&g))?;
+        }
+        for i in 0..199 {
+            let g = t(&format!("g{}", i));
+            d.remove(&t(&format!("s{}", i)), &rdf::value, &t("o"), Some(&g))?;
+        }
+
+        let report = d.compact()?;
+        assert!(report.reclaimed() > 0);
+        assert_eq!(report.after, d.heap_size());
+        assert_eq!(d.quads().count(), 1);
+        assert!(d.contains(&t("s199"), &rdf::value, &t("o"), Some(&t("g199")))?);
+        assert!(d.get_index(&t("g0")).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn graph_memory_usage() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = FastDataset::new();
+        for i in 0..30 {
+            let g = if i < 10 { None } else { Some(t("g")) };
+            d.insert(&t(&format!("s{}", i)), &rdf::value, &t("o"), g.as_ref())?;
+        }
+
+        let usage = d.memory_usage();
+        assert_eq!(usage.dictionary, d.dictionary_heap_size());
+        assert_eq!(usage.total(), d.heap_size());
+
+        let per_graph = d.graph_memory_usage()?;
+        assert_eq!(per_graph.len(), 2);
+        let default = &per_graph[&None];
+        let named = &per_graph[&Some(t("g"))];
+        assert_eq!((default.quads, default.terms), (10, 12));
+        assert_eq!((named.quads, named.terms), (20, 22));
+        assert!(named.memory.indexes > default.memory.indexes);
+        let total: usize = per_graph.values().map(|u| u.memory.total()).sum();
+        assert!(total <= usage.total() && total + 4 >= usage.total());
+        Ok(())
+    }
 }
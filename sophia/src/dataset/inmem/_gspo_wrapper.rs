@@ -6,8 +6,9 @@ use std::iter::empty;
 use super::*;
 
 use crate::graph::indexed::*;
-use sophia_api::dataset::{DQuadSource, DResultTermSet};
+use sophia_api::dataset::{DQuadSource, DResult, DResultTermSet};
 use sophia_api::quad::streaming_mode::{ByTermRefs, StreamedQuad};
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
 use sophia_api::term::TTerm;
 
 /// A [`DatasetWrapper`](trait.DatasetWrapper.html)
@@ -40,6 +41,62 @@ where
     }
 }
 
+impl<T> GspoWrapper<T>
+where
+    T: IndexedDataset,
+{
+    /// The number of quads with the given graph name.
+    fn count_g<TG>(&self, g: Option<&TG>) -> usize
+    where
+        TG: TTerm + ?Sized,
+    {
+        match self.wrapped.get_index_for_graph_name(g) {
+            Some(gi) => self.g2s.get(&gi).map_or(0, |sis| {
+                sis.iter().map(|si| self.count_indexed_gs(gi, *si)).sum()
+            }),
+            None => 0,
+        }
+    }
+
+    /// The number of quads with the given graph name and subject.
+    fn count_gs<TG, TS>(&self, g: Option<&TG>, s: &TS) -> usize
+    where
+        TG: TTerm + ?Sized,
+        TS: TTerm + ?Sized,
+    {
+        match (
+            self.wrapped.get_index_for_graph_name(g),
+            self.wrapped.get_index(s),
+        ) {
+            (Some(gi), Some(si)) => self.count_indexed_gs(gi, si),
+            _ => 0,
+        }
+    }
+
+    fn count_indexed_gs(&self, gi: T::Index, si: T::Index) -> usize {
+        self.gs2p.get(&[gi, si]).map_or(0, |pis| {
+            pis.iter().map(|pi| self.gsp2o[&[gi, si, *pi]].len()).sum()
+        })
+    }
+
+    /// The number of quads with the given graph name, subject and predicate.
+    fn count_gsp<TG, TS, TP>(&self, g: Option<&TG>, s: &TS, p: &TP) -> usize
+    where
+        TG: TTerm + ?Sized,
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        match (
+            self.wrapped.get_index_for_graph_name(g),
+            self.wrapped.get_index(s),
+            self.wrapped.get_index(p),
+        ) {
+            (Some(gi), Some(si), Some(pi)) => self.gsp2o.get(&[gi, si, pi]).map_or(0, Vec::len),
+            _ => 0,
+        }
+    }
+}
+
 impl<T> DatasetWrapper for GspoWrapper<T>
 where
     T: IndexedDataset + Dataset<Quad = ByTermRefs<Term<<T as IndexedDataset>::TermData>>>,
@@ -134,6 +191,61 @@ where
         Box::new(empty())
     }
 
+    fn dw_count_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> DResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        if mo.is_any() {
+            match (mg.constant(), ms.constant(), mp.constant()) {
+                (Some(g), Some(s), Some(p)) => return Ok(self.count_gsp(g, s, p)),
+                (Some(g), Some(s), None) if mp.is_any() => return Ok(self.count_gs(g, s)),
+                (Some(g), None, None) if ms.is_any() && mp.is_any() => return Ok(self.count_g(g)),
+                _ => (),
+            }
+        }
+        self.wrapped.count_quads_matching(ms, mp, mo, mg)
+    }
+
+    fn dw_estimate_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        let estimate = self.wrapped.estimate_quads_matching(ms, mp, mo, mg);
+        match (mg.constant(), ms.constant(), mp.constant()) {
+            (Some(g), Some(s), Some(p)) => {
+                let count = self.count_gsp(g, s, p);
+                refine_estimate(estimate, count, mo.is_any())
+            }
+            (Some(g), Some(s), None) => {
+                let exact = mp.is_any() && mo.is_any();
+                refine_estimate(estimate, self.count_gs(g, s), exact)
+            }
+            (Some(g), None, _) => {
+                let exact = ms.is_any() && mp.is_any() && mo.is_any();
+                refine_estimate(estimate, self.count_g(g), exact)
+            }
+            _ => estimate,
+        }
+    }
+
     fn dw_graph_names(&self) -> DResultTermSet<Self::Wrapped> {
         let graph_names: HashSet<_> = self
             .g2s
@@ -189,6 +301,10 @@ where
         self.gs2p.shrink_to_fit();
         self.gsp2o.shrink_to_fit();
     }
+
+    fn idw_heap_size(&self) -> usize {
+        index_heap_size(&self.g2s) + index_heap_size(&self.gs2p) + index_heap_size(&self.gsp2o)
+    }
 }
 
 impl<T> Dataset for GspoWrapper<T>
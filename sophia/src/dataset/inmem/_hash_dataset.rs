@@ -7,6 +7,7 @@ use crate::dataset::indexed::IndexedDataset;
 use crate::dataset::*;
 use sophia_api::quad::stream::QuadSource;
 use sophia_api::quad::streaming_mode::{ByTermRefs, StreamedQuad};
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
 use sophia_api::term::TTerm;
 use sophia_api::triple::stream::StreamResult;
 use sophia_term::factory::TermFactory;
@@ -72,12 +73,25 @@ where
         }
     }
 
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.quads.reserve(additional);
+    }
+
     #[inline]
     fn shrink_to_fit(&mut self) {
         self.terms.shrink_to_fit();
         self.quads.shrink_to_fit();
     }
 
+    fn heap_size(&self) -> usize {
+        self.terms.heap_size() + self.quads.capacity() * std::mem::size_of::<[I::Index; 4]>()
+    }
+
+    fn dictionary_heap_size(&self) -> usize {
+        self.terms.heap_size()
+    }
+
     #[inline]
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -187,6 +201,84 @@ where
             ))
         }))
     }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let si = self.get_index(s);
+        let pi = self.get_index(p);
+        let oi = self.get_index(o);
+        let gi = self.get_index_for_graph_name(g);
+        Ok(match (si, pi, oi, gi) {
+            (Some(si), Some(pi), Some(oi), Some(gi)) => self.quads.contains(&[si, pi, oi, gi]),
+            _ => false,
+        })
+    }
+
+    fn count_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> DResult<Self, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        if ms.is_any() && mp.is_any() && mo.is_any() && mg.is_any() {
+            return Ok(self.quads.len());
+        }
+        if let (Some(s), Some(p), Some(o), Some(g)) =
+            (ms.constant(), mp.constant(), mo.constant(), mg.constant())
+        {
+            return self.contains(s, p, o, g).map(usize::from);
+        }
+        Ok(self.quads_matching(ms, mp, mo, mg).count())
+    }
+
+    fn estimate_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        let len = self.quads.len();
+        if ms.is_any() && mp.is_any() && mo.is_any() && mg.is_any() {
+            (len, Some(len))
+        } else if ms.constant().is_some()
+            && mp.constant().is_some()
+            && mo.constant().is_some()
+            && mg.constant().is_some()
+        {
+            (0, Some(len.min(1)))
+        } else {
+            (0, Some(len))
+        }
+    }
 }
 
 impl<I> CollectibleDataset for HashDataset<I>
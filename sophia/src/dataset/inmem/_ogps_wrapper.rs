@@ -5,8 +5,9 @@ use std::iter::empty;
 
 use super::*;
 use crate::graph::indexed::*;
-use sophia_api::dataset::{DQuadSource, DResultTermSet};
+use sophia_api::dataset::{DQuadSource, DResult, DResultTermSet};
 use sophia_api::quad::streaming_mode::{ByTermRefs, StreamedQuad};
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
 use sophia_api::term::TTerm;
 
 /// A [`DatasetWrapper`](trait.DatasetWrapper.html)
@@ -39,6 +40,62 @@ where
     }
 }
 
+impl<T> OgpsWrapper<T>
+where
+    T: IndexedDataset,
+{
+    /// The number of quads with the given object.
+    fn count_o<TO>(&self, o: &TO) -> usize
+    where
+        TO: TTerm + ?Sized,
+    {
+        match self.wrapped.get_index(o) {
+            Some(oi) => self.o2g.get(&oi).map_or(0, |gis| {
+                gis.iter().map(|gi| self.count_indexed_og(oi, *gi)).sum()
+            }),
+            None => 0,
+        }
+    }
+
+    /// The number of quads with the given object and graph name.
+    fn count_og<TO, TG>(&self, o: &TO, g: Option<&TG>) -> usize
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        match (
+            self.wrapped.get_index(o),
+            self.wrapped.get_index_for_graph_name(g),
+        ) {
+            (Some(oi), Some(gi)) => self.count_indexed_og(oi, gi),
+            _ => 0,
+        }
+    }
+
+    fn count_indexed_og(&self, oi: T::Index, gi: T::Index) -> usize {
+        self.og2p.get(&[oi, gi]).map_or(0, |pis| {
+            pis.iter().map(|pi| self.ogp2s[&[oi, gi, *pi]].len()).sum()
+        })
+    }
+
+    /// The number of quads with the given object, graph name and predicate.
+    fn count_ogp<TO, TG, TP>(&self, o: &TO, g: Option<&TG>, p: &TP) -> usize
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        match (
+            self.wrapped.get_index(o),
+            self.wrapped.get_index_for_graph_name(g),
+            self.wrapped.get_index(p),
+        ) {
+            (Some(oi), Some(gi), Some(pi)) => self.ogp2s.get(&[oi, gi, pi]).map_or(0, Vec::len),
+            _ => 0,
+        }
+    }
+}
+
 impl<T> DatasetWrapper for OgpsWrapper<T>
 where
     T: IndexedDataset + Dataset<Quad = ByTermRefs<Term<<T as IndexedDataset>::TermData>>>,
@@ -134,6 +191,61 @@ where
         Box::new(empty())
     }
 
+    fn dw_count_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> DResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        if ms.is_any() {
+            match (mo.constant(), mg.constant(), mp.constant()) {
+                (Some(o), Some(g), Some(p)) => return Ok(self.count_ogp(o, g, p)),
+                (Some(o), Some(g), None) if mp.is_any() => return Ok(self.count_og(o, g)),
+                (Some(o), None, None) if mg.is_any() && mp.is_any() => return Ok(self.count_o(o)),
+                _ => (),
+            }
+        }
+        self.wrapped.count_quads_matching(ms, mp, mo, mg)
+    }
+
+    fn dw_estimate_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        let estimate = self.wrapped.estimate_quads_matching(ms, mp, mo, mg);
+        match (mo.constant(), mg.constant(), mp.constant()) {
+            (Some(o), Some(g), Some(p)) => {
+                let count = self.count_ogp(o, g, p);
+                refine_estimate(estimate, count, ms.is_any())
+            }
+            (Some(o), Some(g), None) => {
+                let exact = mp.is_any() && ms.is_any();
+                refine_estimate(estimate, self.count_og(o, g), exact)
+            }
+            (Some(o), None, _) => {
+                let exact = mg.is_any() && mp.is_any() && ms.is_any();
+                refine_estimate(estimate, self.count_o(o), exact)
+            }
+            _ => estimate,
+        }
+    }
+
     fn dw_objects(&self) -> DResultTermSet<Self::Wrapped> {
         let objects: HashSet<_> = self
             .o2g
@@ -188,6 +300,10 @@ where
         self.og2p.shrink_to_fit();
         self.ogp2s.shrink_to_fit();
     }
+
+    fn idw_heap_size(&self) -> usize {
+        index_heap_size(&self.o2g) + index_heap_size(&self.og2p) + index_heap_size(&self.ogp2s)
+    }
 }
 
 impl<T> Dataset for OgpsWrapper<T>
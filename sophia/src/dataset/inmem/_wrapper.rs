@@ -3,6 +3,7 @@
 use super::*;
 use crate::dataset::indexed::IndexedDataset;
 use sophia_api::dataset::{DQuadSource, DResult, DResultTermSet, DTerm};
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
 use sophia_api::term::TTerm;
 use std::hash::Hash;
 
@@ -241,6 +242,42 @@ pub trait DatasetWrapper {
         self.get_wrapped().contains(s, p, o, g)
     }
 
+    #[inline]
+    /// Mimmic the [`count_quads_matching`](../trait.Dataset.html#method.count_quads_matching) method.
+    fn dw_count_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> DResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        self.get_wrapped().count_quads_matching(ms, mp, mo, mg)
+    }
+
+    #[inline]
+    /// Mimmic the [`estimate_quads_matching`](../trait.Dataset.html#method.estimate_quads_matching) method.
+    fn dw_estimate_quads_matching<S, P, O, G>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+        mg: &G,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+        G: GraphNameMatcher + ?Sized,
+    {
+        self.get_wrapped().estimate_quads_matching(ms, mp, mo, mg)
+    }
+
     #[inline]
     /// Mimmic the [`subjects`](../trait.Dataset.html#method.subjects) method.
     fn dw_subjects(&self) -> DResultTermSet<Self::Wrapped>
@@ -334,6 +371,10 @@ macro_rules! impl_dataset_for_wrapper {
             DatasetWrapper::dw_quads(self)
         }
         #[inline]
+        fn is_set(&self) -> bool {
+            $crate::dataset::Dataset::is_set(DatasetWrapper::get_wrapped(self))
+        }
+        #[inline]
         fn quads_with_s<'s_, TS_>(
             &'s_ self,
             s: &'s_ TS_,
@@ -536,6 +577,40 @@ macro_rules! impl_dataset_for_wrapper {
             DatasetWrapper::dw_contains(self, s, p, o, g)
         }
 
+        #[inline]
+        fn count_quads_matching<S_, P_, O_, G_>(
+            &self,
+            ms: &S_,
+            mp: &P_,
+            mo: &O_,
+            mg: &G_,
+        ) -> $crate::dataset::DResult<Self, usize>
+        where
+            S_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            P_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            O_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            G_: sophia_api::term::matcher::GraphNameMatcher + ?Sized,
+        {
+            DatasetWrapper::dw_count_quads_matching(self, ms, mp, mo, mg)
+        }
+
+        #[inline]
+        fn estimate_quads_matching<S_, P_, O_, G_>(
+            &self,
+            ms: &S_,
+            mp: &P_,
+            mo: &O_,
+            mg: &G_,
+        ) -> (usize, Option<usize>)
+        where
+            S_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            P_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            O_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            G_: sophia_api::term::matcher::GraphNameMatcher + ?Sized,
+        {
+            DatasetWrapper::dw_estimate_quads_matching(self, ms, mp, mo, mg)
+        }
+
         #[inline]
         fn subjects(&self) -> $crate::dataset::DResultTermSet<Self> {
             DatasetWrapper::dw_subjects(self)
@@ -603,6 +678,11 @@ where
     /// Hook to be executed at the end of
     /// [`IndexedDataset::shrink_to_fit`](../indexed/trait.IndexedDataset.html#tymethod.shrink_to_fit).
     fn idw_hook_shrink_to_fit(&mut self);
+
+    /// Return an estimate of the heap memory (in bytes) used by this wrapper,
+    /// in addition to the wrapped dataset;
+    /// used by [`IndexedDataset::heap_size`](../indexed/trait.IndexedDataset.html#tymethod.heap_size).
+    fn idw_heap_size(&self) -> usize;
 }
 
 /// Defines the implementation of [`IndexedDataset`] for [`DatasetWrapper`] around another [`IndexedDataset`].
@@ -620,12 +700,27 @@ macro_rules! impl_indexed_dataset_for_wrapper {
             Self::idw_wrap_empty(T::with_capacity(capacity))
         }
 
+        #[inline]
+        fn reserve(&mut self, additional: usize) {
+            self.get_wrapped_mut().reserve(additional);
+        }
+
         #[inline]
         fn shrink_to_fit(&mut self) {
             self.get_wrapped_mut().shrink_to_fit();
             self.idw_hook_shrink_to_fit();
         }
 
+        #[inline]
+        fn heap_size(&self) -> usize {
+            self.get_wrapped().heap_size() + self.idw_heap_size()
+        }
+
+        #[inline]
+        fn dictionary_heap_size(&self) -> usize {
+            self.get_wrapped().dictionary_heap_size()
+        }
+
         #[inline]
         fn get_index<U>(&self, t: &U) -> Option<Self::Index>
         where
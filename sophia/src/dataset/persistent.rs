@@ -0,0 +1,1237 @@
+//! A [`MutableDataset`] stored in an ordered key-value store, persisted by a write-ahead log.
+//!
+//! [`PersistentDataset`] stores its quads in a [`KvStore`], an ordered key-value store interface
+//! with several column families:
+//! * [`Terms`] maps numeric term identifiers to terms in N-Triples syntax;
+//! * [`Spog`], [`Posg`] and [`Ospg`] are three indexes of the quads,
+//!   whose keys are the identifiers of their terms in different orders.
+//!
+//! Term identifiers are big-endian 64-bit integers, starting at 1;
+//! the identifier 0 denotes the default graph.
+//! Quads are matched by scanning the index whose key starts with the given terms.
+//! The term dictionary is loaded in memory when the dataset is opened,
+//! while the quads are only read from the store.
+//! Terms are never removed from the dictionary, even when no quad uses them anymore.
+//!
+//! Each insertion or removal is written to the store as an atomic [`WriteBatch`];
+//! [`insert_all`](struct.PersistentDataset.html#method.insert_all)
+//! groups insertions into batches of [`batch_size`] quads.
+//!
+//! This module provides two implementations of [`KvStore`]:
+//! [`MemStore`] is not persistent, and [`LogStore`] appends batches to a write-ahead log file,
+//! with a configurable [`SyncPolicy`] and recovery from interrupted writes.
+//! The consistency of a log can be checked with [`check_log`],
+//! and the consistency of the indexes of a dataset with [`PersistentDataset::check`].
+//!
+//! [`LogStore`] is not an embedded database:
+//! it keeps all its entries in memory, and replays the whole log when it is opened,
+//! so the dataset must fit in memory.
+//! No binding to an embedded database is provided,
+//! but one (e.g. sled or RocksDB) can be used by implementing [`KvStore`]
+//! with one tree (resp. column family) per [`ColumnFamily`].
+//!
+//! This module requires the `persistent` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::persistent::{LogStore, PersistentDataset};
+//! use sophia::dataset::{Dataset, MutableDataset};
+//! use sophia::term::BoxTerm;
+//!
+//! let path = std::env::temp_dir().join("sophia-persistent-doctest.log");
+//! # std::fs::remove_file(&path).ok();
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! let knows = BoxTerm::new_iri("tag:knows")?;
+//! let bob = BoxTerm::new_iri("tag:bob")?;
+//! {
+//!     let mut d = PersistentDataset::open(LogStore::open(&path)?)?;
+//!     d.insert(&alice, &knows, &bob, None as Option<&BoxTerm>)?;
+//! }
+//! let d = PersistentDataset::open(LogStore::open(&path)?)?;
+//! assert_eq!(d.quads_with_s(&alice).count(), 1);
+//! # std::fs::remove_file(&path).ok();
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`MutableDataset`]: ../trait.MutableDataset.html
+//! [`PersistentDataset`]: struct.PersistentDataset.html
+//! [`KvStore`]: trait.KvStore.html
+//! [`Terms`]: enum.ColumnFamily.html#variant.Terms
+//! [`Spog`]: enum.ColumnFamily.html#variant.Spog
+//! [`Posg`]: enum.ColumnFamily.html#variant.Posg
+//! [`Ospg`]: enum.ColumnFamily.html#variant.Ospg
+//! [`ColumnFamily`]: enum.ColumnFamily.html
+//! [`WriteBatch`]: struct.WriteBatch.html
+//! [`batch_size`]: struct.PersistentDataset.html#method.batch_size
+//! [`MemStore`]: struct.MemStore.html
+//! [`LogStore`]: struct.LogStore.html
+//! [`SyncPolicy`]: enum.SyncPolicy.html
+//! [`check_log`]: fn.check_log.html
+//! [`PersistentDataset::check`]: struct.PersistentDataset.html#method.check
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::{Infallible, TryInto};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use sophia_api::dataset::{
+    CollectibleDataset, DQuadSource, DResult, Dataset, MDResult, MutableDataset, SetDataset,
+};
+use sophia_api::quad::stream::{QuadSource, StreamError, StreamResult};
+use sophia_api::quad::streaming_mode::{ByValue, StreamedQuad};
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_to_string, CopyTerm, TTerm};
+use sophia_term::*;
+use thiserror::Error;
+
+use crate::parser::nq12;
+
+/// This error is raised by [`PersistentDataset`](struct.PersistentDataset.html).
+#[derive(Debug, Error)]
+pub enum PersistentError<E: Error + 'static> {
+    /// The underlying store failed.
+    #[error("Store error: {0}")]
+    Store(#[source] E),
+    /// The store contains data not complying with the layout of this module.
+    #[error("Invalid stored data: {0}")]
+    Data(String),
+}
+
+/// The column families of a [`KvStore`](trait.KvStore.html).
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ColumnFamily {
+    /// Maps term identifiers to terms.
+    Terms,
+    /// Indexes quads by subject, predicate, object and graph name.
+    Spog,
+    /// Indexes quads by predicate, object, subject and graph name.
+    Posg,
+    /// Indexes quads by object, subject, predicate and graph name.
+    Ospg,
+}
+
+impl ColumnFamily {
+    /// All the column families.
+    pub const ALL: [ColumnFamily; 4] = [
+        ColumnFamily::Terms,
+        ColumnFamily::Spog,
+        ColumnFamily::Posg,
+        ColumnFamily::Ospg,
+    ];
+
+    /// The name of this column family.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnFamily::Terms => "terms",
+            ColumnFamily::Spog => "spog",
+            ColumnFamily::Posg => "posg",
+            ColumnFamily::Ospg => "ospg",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// The positions (in `[s, p, o, g]`) of the identifiers in the keys of this index.
+    fn order(&self) -> [usize; 4] {
+        match self {
+            ColumnFamily::Terms | ColumnFamily::Spog => [0, 1, 2, 3],
+            ColumnFamily::Posg => [1, 2, 0, 3],
+            ColumnFamily::Ospg => [2, 0, 1, 3],
+        }
+    }
+}
+
+/// A write of a [`WriteBatch`](struct.WriteBatch.html):
+/// a column family, a key, and a value (or `None` for a removal).
+pub type BatchOp = (ColumnFamily, Vec<u8>, Option<Vec<u8>>);
+
+/// A set of writes to be applied atomically to a [`KvStore`](trait.KvStore.html).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    /// Construct an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of `key` in column family `cf`.
+    pub fn put(&mut self, cf: ColumnFamily, key: Vec<u8>, value: Vec<u8>) -> &mut Self {
+        self.ops.push((cf, key, Some(value)));
+        self
+    }
+
+    /// Remove `key` from column family `cf`.
+    pub fn delete(&mut self, cf: ColumnFamily, key: Vec<u8>) -> &mut Self {
+        self.ops.push((cf, key, None));
+        self
+    }
+
+    /// The writes of this batch, in order; `None` values denote removals.
+    pub fn ops(&self) -> &[BatchOp] {
+        &self.ops
+    }
+
+    /// The number of writes in this batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether this batch has no write.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// An entry of a [`KvStore`](trait.KvStore.html).
+pub type KvEntry = (Vec<u8>, Vec<u8>);
+
+/// An ordered key-value store with [column families](enum.ColumnFamily.html).
+///
+/// See [module documentation](index.html).
+pub trait KvStore {
+    /// The error type raised by this store.
+    type Error: Error + 'static;
+
+    /// The value of `key` in column family `cf`, if any.
+    fn get(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// The entries of column family `cf` whose key starts with `prefix`, in key order.
+    fn scan_prefix<'a>(
+        &'a self,
+        cf: ColumnFamily,
+        prefix: &[u8],
+    ) -> Box<dyn Iterator<Item = Result<KvEntry, Self::Error>> + 'a>;
+
+    /// Apply all the writes of `batch` atomically.
+    fn write(&mut self, batch: WriteBatch) -> Result<(), Self::Error>;
+}
+
+/// A non-persistent [`KvStore`](trait.KvStore.html), keeping its entries in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemStore {
+    cfs: [BTreeMap<Vec<u8>, Vec<u8>>; 4],
+}
+
+impl MemStore {
+    /// Construct an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn apply(&mut self, batch: WriteBatch) {
+        for (cf, key, value) in batch.ops {
+            let map = &mut self.cfs[cf.index()];
+            match value {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+    }
+}
+
+impl KvStore for MemStore {
+    type Error = Infallible;
+
+    fn get(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>, Infallible> {
+        Ok(self.cfs[cf.index()].get(key).cloned())
+    }
+
+    fn scan_prefix<'a>(
+        &'a self,
+        cf: ColumnFamily,
+        prefix: &[u8],
+    ) -> Box<dyn Iterator<Item = Result<KvEntry, Infallible>> + 'a> {
+        let prefix = prefix.to_vec();
+        Box::new(
+            self.cfs[cf.index()]
+                .range(prefix.clone()..)
+                .take_while(move |(k, _)| k.starts_with(&prefix))
+                .map(|(k, v)| Ok((k.clone(), v.clone()))),
+        )
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), Infallible> {
+        self.apply(batch);
+        Ok(())
+    }
+}
+
+/// When a [`LogStore`](struct.LogStore.html) forces its log to durable storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// After every batch: a batch is durable as soon as it is written.
+    Always,
+    /// After every `n` batches: up to `n-1` batches may be lost on a system crash.
+    Every(usize),
+    /// Never (leaving it to the operating system),
+    /// except on [`sync`](struct.LogStore.html#method.sync), [`checkpoint`](struct.LogStore.html#method.checkpoint)
+    /// and when the store is dropped.
+    Never,
+}
+
+/// The result of reading a log, returned by [`check_log`](fn.check_log.html)
+/// and [`LogStore::recovery`](struct.LogStore.html#method.recovery).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LogReport {
+    /// The number of valid batches in the log.
+    pub batches: usize,
+    /// The size of the valid part of the log, in bytes.
+    pub valid_bytes: u64,
+    /// The size of the invalid tail of the log (incomplete or corrupted batches), in bytes.
+    pub invalid_bytes: u64,
+}
+
+impl LogReport {
+    /// Whether the log has no invalid tail.
+    pub fn is_clean(&self) -> bool {
+        self.invalid_bytes == 0
+    }
+}
+
+/// A persistent [`KvStore`](trait.KvStore.html),
+/// keeping its entries in memory and appending each batch to a write-ahead log file.
+///
+/// Each batch is logged with its length and checksum,
+/// so that a batch which was only partially written (e.g. because of a crash)
+/// is detected when the log is replayed.
+/// Such an invalid tail is discarded when the store is [opened](#method.open),
+/// which amounts to rolling back the batches whose writing was interrupted.
+/// [`check_log`](fn.check_log.html) inspects a log without modifying it.
+///
+/// The log grows with every batch, including removals;
+/// [`checkpoint`](#method.checkpoint) rewrites it with only the current entries.
+#[derive(Debug)]
+pub struct LogStore {
+    mem: MemStore,
+    file: File,
+    path: PathBuf,
+    policy: SyncPolicy,
+    unsynced: usize,
+    recovery: LogReport,
+}
+
+impl LogStore {
+    /// Open the store logged in the file at `path`, creating it if it does not exist,
+    /// and discarding the invalid tail of the log, if any.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let mut log = vec![];
+        file.read_to_end(&mut log)?;
+        let mut mem = MemStore::new();
+        let recovery = read_log(&log, |batch| mem.apply(batch));
+        if !recovery.is_clean() {
+            file.set_len(recovery.valid_bytes)?;
+            file.sync_all()?;
+        }
+        Ok(LogStore {
+            mem,
+            file,
+            path,
+            policy: SyncPolicy::Always,
+            unsynced: 0,
+            recovery,
+        })
+    }
+
+    /// The state of the log when this store was opened.
+    ///
+    /// If it was not clean, its invalid tail has been discarded.
+    pub fn recovery(&self) -> LogReport {
+        self.recovery
+    }
+
+    /// The sync policy of this store (default: [`Always`](enum.SyncPolicy.html#variant.Always)).
+    pub fn sync_policy(&self) -> SyncPolicy {
+        self.policy
+    }
+
+    /// Set the sync policy of this store.
+    pub fn set_sync_policy(&mut self, policy: SyncPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Force all the batches written so far to durable storage.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// Rewrite the log with a single batch containing the current entries of the store.
+    ///
+    /// The new log is written to a temporary file, which then replaces the log,
+    /// so that a crash during a checkpoint leaves either the old or the new log.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let mut batch = WriteBatch::new();
+        for cf in &ColumnFamily::ALL {
+            for (k, v) in &self.mem.cfs[cf.index()] {
+                batch.put(*cf, k.clone(), v.clone());
+            }
+        }
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            if !batch.is_empty() {
+                tmp.write_all(&encode_record(&batch))?;
+            }
+            tmp.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        self.unsynced = 0;
+        Ok(())
+    }
+}
+
+impl KvStore for LogStore {
+    type Error = io::Error;
+
+    fn get(&self, cf: ColumnFamily, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.mem.cfs[cf.index()].get(key).cloned())
+    }
+
+    fn scan_prefix<'a>(
+        &'a self,
+        cf: ColumnFamily,
+        prefix: &[u8],
+    ) -> Box<dyn Iterator<Item = io::Result<KvEntry>> + 'a> {
+        Box::new(self.mem.scan_prefix(cf, prefix).map(|e| Ok(e.unwrap())))
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> io::Result<()> {
+        self.file.write_all(&encode_record(&batch))?;
+        self.file.flush()?;
+        self.unsynced += 1;
+        match self.policy {
+            SyncPolicy::Always => self.sync()?,
+            SyncPolicy::Every(n) if self.unsynced >= n => self.sync()?,
+            _ => (),
+        }
+        self.mem.apply(batch);
+        Ok(())
+    }
+}
+
+impl Drop for LogStore {
+    fn drop(&mut self) {
+        if self.unsynced > 0 {
+            let _ = self.file.sync_data();
+        }
+    }
+}
+
+/// Inspect the log file at `path` without modifying it.
+pub fn check_log<P: AsRef<Path>>(path: P) -> io::Result<LogReport> {
+    let log = std::fs::read(path)?;
+    Ok(read_log(&log, |_| ()))
+}
+
+/// Pass each valid batch of `log` to `apply`, stopping at the first invalid record.
+fn read_log<F: FnMut(WriteBatch)>(log: &[u8], mut apply: F) -> LogReport {
+    let mut report = LogReport::default();
+    let mut rest = log;
+    while rest.len() >= 8 {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let crc = u32::from_be_bytes(rest[4..8].try_into().unwrap());
+        let payload = match rest.get(8..8 + len) {
+            Some(payload) if crc32(payload) == crc => payload,
+            _ => break,
+        };
+        match decode_batch(&mut &payload[..]) {
+            Ok(batch) => apply(batch),
+            Err(_) => break,
+        }
+        rest = &rest[8 + len..];
+        report.batches += 1;
+    }
+    report.valid_bytes = (log.len() - rest.len()) as u64;
+    report.invalid_bytes = rest.len() as u64;
+    report
+}
+
+// A batch is logged as a record made of the length (u32) and CRC-32 (u32) of its payload,
+// followed by the payload: the number of writes (u32),
+// followed by each write: column family (u8), key length (u32), key,
+// and either 0u8 (removal) or 1u8, value length (u32) and value.
+
+fn encode_record(batch: &WriteBatch) -> Vec<u8> {
+    let payload = encode_batch(batch);
+    let mut buf = Vec::with_capacity(payload.len() + 8);
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&crc32(&payload).to_be_bytes());
+    buf.extend_from_slice(&payload);
+    buf
+}
+
+/// CRC-32 (IEEE 802.3), as used by gzip and zip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+fn encode_batch(batch: &WriteBatch) -> Vec<u8> {
+    let mut buf = vec![];
+    buf.extend_from_slice(&(batch.len() as u32).to_be_bytes());
+    for (cf, key, value) in batch.ops() {
+        buf.push(cf.index() as u8);
+        buf.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        buf.extend_from_slice(key);
+        match value {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                buf.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                buf.extend_from_slice(value);
+            }
+        }
+    }
+    buf
+}
+
+fn decode_batch(input: &mut &[u8]) -> io::Result<WriteBatch> {
+    fn take<'a>(input: &mut &'a [u8], n: usize) -> io::Result<&'a [u8]> {
+        if input.len() < n {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated log record",
+            ));
+        }
+        let (head, tail) = input.split_at(n);
+        *input = tail;
+        Ok(head)
+    }
+    fn take_u32(input: &mut &[u8]) -> io::Result<usize> {
+        Ok(u32::from_be_bytes(take(input, 4)?.try_into().unwrap()) as usize)
+    }
+    let mut batch = WriteBatch::new();
+    for _ in 0..take_u32(input)? {
+        let cf = *ColumnFamily::ALL
+            .get(take(input, 1)?[0] as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unknown column family"))?;
+        let len = take_u32(input)?;
+        let key = take(input, len)?.to_vec();
+        match take(input, 1)?[0] {
+            0 => batch.delete(cf, key),
+            _ => {
+                let len = take_u32(input)?;
+                batch.put(cf, key, take(input, len)?.to_vec())
+            }
+        };
+    }
+    Ok(batch)
+}
+
+/// The result of [`PersistentDataset::check`](struct.PersistentDataset.html#method.check).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Consistency {
+    /// The number of quads in the dataset (according to the `spog` index).
+    pub quads: usize,
+    /// A description of each inconsistency found.
+    pub problems: Vec<String>,
+}
+
+impl Consistency {
+    /// Whether no inconsistency was found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A dataset persisted in a [`KvStore`](trait.KvStore.html).
+///
+/// See [module documentation](index.html).
+#[derive(Debug)]
+pub struct PersistentDataset<K> {
+    store: K,
+    id2term: HashMap<u64, RcTerm>,
+    term2id: HashMap<RcTerm, u64>,
+    next_id: u64,
+    batch_size: usize,
+}
+
+type PQuad = ([RcTerm; 3], Option<RcTerm>);
+
+/// Changes staged before being written to the store.
+#[derive(Default)]
+struct Staged {
+    batch: WriteBatch,
+    terms: HashMap<RcTerm, u64>,
+    quads: HashSet<[u64; 4]>,
+}
+
+impl<K: KvStore> PersistentDataset<K> {
+    /// Open the dataset persisted in `store`, loading its term dictionary.
+    pub fn open(store: K) -> Result<Self, PersistentError<K::Error>> {
+        let mut id2term = HashMap::new();
+        let mut term2id = HashMap::new();
+        for entry in store.scan_prefix(ColumnFamily::Terms, &[]) {
+            let (key, value) = entry.map_err(PersistentError::Store)?;
+            let id = decode_ids(&key, 1)?[0];
+            let txt = std::str::from_utf8(&value)
+                .map_err(|e| PersistentError::Data(format!("invalid term {}: {}", id, e)))?;
+            let term = nq12::parse_term(txt)
+                .map(|t| RcTerm::copy(&t))
+                .map_err(|e| PersistentError::Data(format!("invalid term {}: {}", txt, e)))?;
+            id2term.insert(id, term.clone());
+            term2id.insert(term, id);
+        }
+        let next_id = id2term.keys().copied().max().unwrap_or(0) + 1;
+        Ok(PersistentDataset {
+            store,
+            id2term,
+            term2id,
+            next_id,
+            batch_size: 1000,
+        })
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &K {
+        &self.store
+    }
+
+    /// Close this dataset and return the underlying store.
+    pub fn into_store(self) -> K {
+        self.store
+    }
+
+    /// The number of distinct terms ever stored in this dataset.
+    pub fn term_count(&self) -> usize {
+        self.id2term.len()
+    }
+
+    /// Check that the three indexes of this dataset contain the same quads,
+    /// and that all the terms they use are in the dictionary.
+    ///
+    /// NB: this reads the whole store, and keeps the set of its quads in memory.
+    pub fn check(&self) -> Result<Consistency, PersistentError<K::Error>> {
+        let mut report = Consistency::default();
+        let mut indexes = vec![];
+        for cf in &ColumnFamily::ALL[1..] {
+            let mut quads = HashSet::new();
+            for entry in self.store.scan_prefix(*cf, &[]) {
+                let (key, _) = entry.map_err(PersistentError::Store)?;
+                match decode_key::<K::Error>(*cf, &key) {
+                    Err(e) => report.problems.push(format!("{}: {}", cf.name(), e)),
+                    Ok(ids) => {
+                        let unknown = ids.iter().enumerate().find(|(i, id)| {
+                            !(*i == 3 && **id == 0 || self.id2term.contains_key(id))
+                        });
+                        if let Some((_, id)) = unknown {
+                            report.problems.push(format!(
+                                "{}: unknown term identifier {}",
+                                cf.name(),
+                                id
+                            ));
+                        }
+                        quads.insert(ids);
+                    }
+                }
+            }
+            indexes.push((*cf, quads));
+        }
+        let (_, spog) = &indexes[0];
+        report.quads = spog.len();
+        for (cf, quads) in &indexes[1..] {
+            let missing = spog.difference(quads).count();
+            if missing > 0 {
+                report
+                    .problems
+                    .push(format!("{}: {} quads missing", cf.name(), missing));
+            }
+            let extra = quads.difference(spog).count();
+            if extra > 0 {
+                report
+                    .problems
+                    .push(format!("{}: {} quads not in spog", cf.name(), extra));
+            }
+        }
+        Ok(report)
+    }
+
+    /// The maximum number of quads written in a single batch by
+    /// [`insert_all`](#method.insert_all) (default: 1000).
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Set the maximum number of quads written in a single batch by
+    /// [`insert_all`](#method.insert_all).
+    ///
+    /// # Panics
+    /// if `batch_size` is 0.
+    pub fn set_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        assert!(batch_size > 0, "batch size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn id<T: TTerm + ?Sized>(&self, t: &T) -> Option<u64> {
+        self.term2id.get(&RcTerm::copy(t)).copied()
+    }
+
+    fn graph_id<T: TTerm + ?Sized>(&self, g: Option<&T>) -> Option<u64> {
+        match g {
+            None => Some(0),
+            Some(g) => self.id(g),
+        }
+    }
+
+    /// The identifier of `t`, allocated in `staged` if `t` is a new term.
+    fn stage_term<T: TTerm + ?Sized>(&self, staged: &mut Staged, t: &T) -> u64 {
+        let t = RcTerm::copy(t);
+        if let Some(id) = self.term2id.get(&t) {
+            return *id;
+        }
+        if let Some(id) = staged.terms.get(&t) {
+            return *id;
+        }
+        let id = self.next_id + staged.terms.len() as u64;
+        staged.batch.put(
+            ColumnFamily::Terms,
+            id.to_be_bytes().to_vec(),
+            term_to_string(&t).into_bytes(),
+        );
+        staged.terms.insert(t, id);
+        id
+    }
+
+    /// Stage the insertion of a quad, and return whether it is new.
+    fn stage_insert<TS, TP, TO, TG>(
+        &self,
+        staged: &mut Staged,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> Result<bool, PersistentError<K::Error>>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let ids = [
+            self.stage_term(staged, s),
+            self.stage_term(staged, p),
+            self.stage_term(staged, o),
+            match g {
+                None => 0,
+                Some(g) => self.stage_term(staged, g),
+            },
+        ];
+        if staged.quads.contains(&ids) || self.contains_ids(ids)? {
+            return Ok(false);
+        }
+        for cf in &ColumnFamily::ALL[1..] {
+            staged.batch.put(*cf, encode_key(*cf, ids), vec![]);
+        }
+        staged.quads.insert(ids);
+        Ok(true)
+    }
+
+    fn commit(&mut self, staged: Staged) -> Result<(), PersistentError<K::Error>> {
+        if staged.batch.is_empty() {
+            return Ok(());
+        }
+        self.store
+            .write(staged.batch)
+            .map_err(PersistentError::Store)?;
+        self.next_id += staged.terms.len() as u64;
+        for (term, id) in staged.terms {
+            self.id2term.insert(id, term.clone());
+            self.term2id.insert(term, id);
+        }
+        Ok(())
+    }
+
+    fn contains_ids(&self, ids: [u64; 4]) -> Result<bool, PersistentError<K::Error>> {
+        let key = encode_key(ColumnFamily::Spog, ids);
+        Ok(self
+            .store
+            .get(ColumnFamily::Spog, &key)
+            .map_err(PersistentError::Store)?
+            .is_some())
+    }
+
+    /// The quads of index `cf` whose key starts with `prefix`
+    /// (or no quad if a term of `prefix` is unknown).
+    fn scan<'s>(&'s self, cf: ColumnFamily, prefix: &[Option<u64>]) -> DQuadSource<'s, Self> {
+        let prefix: Option<Vec<u8>> = prefix
+            .iter()
+            .map(|id| id.map(|id| id.to_be_bytes()))
+            .collect::<Option<Vec<_>>>()
+            .map(|ids| ids.concat());
+        match prefix {
+            None => Box::new(std::iter::empty()),
+            Some(prefix) => Box::new(self.store.scan_prefix(cf, &prefix).map(move |entry| {
+                let (key, _) = entry.map_err(PersistentError::Store)?;
+                Ok(StreamedQuad::by_value(self.quad(decode_key(cf, &key)?)?))
+            })),
+        }
+    }
+
+    fn quad(&self, [s, p, o, g]: [u64; 4]) -> Result<PQuad, PersistentError<K::Error>> {
+        let term = |id| {
+            self.id2term
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| PersistentError::Data(format!("unknown term identifier {}", id)))
+        };
+        let g = if g == 0 { None } else { Some(term(g)?) };
+        Ok(([term(s)?, term(p)?, term(o)?], g))
+    }
+}
+
+fn encode_key(cf: ColumnFamily, spog: [u64; 4]) -> Vec<u8> {
+    cf.order()
+        .iter()
+        .flat_map(|pos| spog[*pos].to_be_bytes().to_vec())
+        .collect()
+}
+
+fn decode_key<E: Error + 'static>(
+    cf: ColumnFamily,
+    key: &[u8],
+) -> Result<[u64; 4], PersistentError<E>> {
+    let ids = decode_ids(key, 4)?;
+    let mut spog = [0; 4];
+    for (i, pos) in cf.order().iter().enumerate() {
+        spog[*pos] = ids[i];
+    }
+    Ok(spog)
+}
+
+fn decode_ids<E: Error + 'static>(key: &[u8], n: usize) -> Result<Vec<u64>, PersistentError<E>> {
+    if key.len() != 8 * n {
+        return Err(PersistentError::Data(format!(
+            "invalid key length {}",
+            key.len()
+        )));
+    }
+    Ok(key
+        .chunks(8)
+        .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+impl<K: KvStore> Dataset for PersistentDataset<K> {
+    type Quad = ByValue<PQuad>;
+    type Error = PersistentError<K::Error>;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.scan(ColumnFamily::Spog, &[])
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Spog, &[self.id(s)])
+    }
+
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Posg, &[self.id(p)])
+    }
+
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Ospg, &[self.id(o)])
+    }
+
+    fn quads_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Spog, &[self.id(s), self.id(p)])
+    }
+
+    fn quads_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Ospg, &[self.id(o), self.id(s)])
+    }
+
+    fn quads_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Posg, &[self.id(p), self.id(o)])
+    }
+
+    fn quads_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.scan(ColumnFamily::Spog, &[self.id(s), self.id(p), self.id(o)])
+    }
+
+    fn quads_with_spog<'s, TS, TP, TO, TG>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+        g: Option<&'s TG>,
+    ) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let ids = [self.id(s), self.id(p), self.id(o), self.graph_id(g)];
+        self.scan(ColumnFamily::Spog, &ids)
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        match (self.id(s), self.id(p), self.id(o), self.graph_id(g)) {
+            (Some(s), Some(p), Some(o), Some(g)) => self.contains_ids([s, p, o, g]),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl<K: KvStore + Default> CollectibleDataset for PersistentDataset<K> {
+    fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Self::Error> {
+        let mut d = PersistentDataset::open(K::default()).map_err(StreamError::SinkError)?;
+        d.insert_all(quads).map(|_| d)
+    }
+}
+
+impl<K: KvStore> MutableDataset for PersistentDataset<K> {
+    type MutationError = PersistentError<K::Error>;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let mut staged = Staged::default();
+        let inserted = self.stage_insert(&mut staged, s, p, o, g)?;
+        if inserted {
+            self.commit(staged)?;
+        }
+        Ok(inserted)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let ids = match (self.id(s), self.id(p), self.id(o), self.graph_id(g)) {
+            (Some(s), Some(p), Some(o), Some(g)) => [s, p, o, g],
+            _ => return Ok(false),
+        };
+        if !self.contains_ids(ids)? {
+            return Ok(false);
+        }
+        let mut batch = WriteBatch::new();
+        for cf in &ColumnFamily::ALL[1..] {
+            batch.delete(*cf, encode_key(*cf, ids));
+        }
+        self.store.write(batch).map_err(PersistentError::Store)?;
+        Ok(true)
+    }
+
+    /// Insert all quads from `src`, writing them to the store
+    /// in batches of [`batch_size`](#method.batch_size) quads.
+    fn insert_all<QS>(&mut self, src: QS) -> StreamResult<usize, QS::Error, Self::MutationError>
+    where
+        QS: QuadSource,
+    {
+        let mut src = src;
+        let mut count = 0;
+        let mut staged = Staged::default();
+        src.try_for_each_quad(|q| -> MDResult<Self, ()> {
+            if self.stage_insert(&mut staged, q.s(), q.p(), q.o(), q.g())? {
+                count += 1;
+            }
+            if staged.quads.len() >= self.batch_size {
+                self.commit(std::mem::take(&mut staged))?;
+            }
+            Ok(())
+        })?;
+        self.commit(staged).map_err(StreamError::SinkError)?;
+        Ok(count)
+    }
+}
+
+impl<K: KvStore> SetDataset for PersistentDataset<K> {}
+
+#[cfg(test)]
+type MemDataset = PersistentDataset<MemStore>;
+#[cfg(test)]
+sophia_api::test_dataset_impl!(test_persistent, MemDataset);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+    use crate::test_util::t;
+
+    const DATA: &str = r#"
+        <tag:a> <tag:p> <tag:b>.
+        <tag:a> <tag:p> "a" <tag:g>.
+        <tag:b> <tag:q> <tag:a> <tag:g>.
+        <tag:b> <tag:q> <tag:a> <tag:g>.
+    "#;
+
+    #[test]
+    fn indexes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = PersistentDataset::open(MemStore::new())?;
+        d.set_batch_size(2);
+        assert_eq!(d.insert_all(nq::parse_str(DATA))?, 3);
+        assert_eq!(d.term_count(), 6);
+        assert_eq!(d.store().cfs[ColumnFamily::Spog.index()].len(), 3);
+        assert_eq!(d.quads_with_s(&t("a")).count(), 2);
+        assert_eq!(d.quads_with_p(&t("q")).count(), 1);
+        assert_eq!(d.quads_with_o(&t("a")).count(), 1);
+        assert_eq!(d.quads_with_so(&t("b"), &t("a")).count(), 1);
+        assert_eq!(d.quads_with_s(&t("z")).count(), 0);
+        assert!(d.contains(&t("b"), &t("q"), &t("a"), Some(&t("g")))?);
+        assert!(!d.contains(&t("b"), &t("q"), &t("a"), None as Option<&BoxTerm>)?);
+
+        assert!(d.remove(&t("a"), &t("p"), &t("b"), None as Option<&BoxTerm>)?);
+        assert_eq!(d.quads_with_p(&t("p")).count(), 1);
+        for cf in &ColumnFamily::ALL[1..] {
+            assert_eq!(d.store().cfs[cf.index()].len(), 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn batches() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = PersistentDataset::open(MemStore::new())?;
+        d.set_batch_size(2);
+        d.insert_all(nq::parse_str(DATA))?;
+        let mut log = vec![];
+        let store = d.into_store();
+        // replaying the content of the store yields the same dataset
+        for cf in &ColumnFamily::ALL {
+            let mut batch = WriteBatch::new();
+            for (k, v) in &store.cfs[cf.index()] {
+                batch.put(*cf, k.clone(), v.clone());
+            }
+            log.extend(encode_batch(&batch));
+        }
+        let mut rest = &log[..];
+        let mut replayed = MemStore::new();
+        while !rest.is_empty() {
+            replayed.apply(decode_batch(&mut rest)?);
+        }
+        let d = PersistentDataset::open(replayed)?;
+        assert_eq!(d.quads().count(), 3);
+        assert!(decode_batch(&mut &log[..log.len() - 1]).is_ok());
+        assert!(decode_batch(&mut &log[..3]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn log_store() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("sophia-log-store-{}", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        {
+            let mut d = PersistentDataset::open(LogStore::open(&path)?)?;
+            d.insert_all(nq::parse_str(DATA))?;
+            d.remove(&t("a"), &t("p"), &t("b"), None as Option<&BoxTerm>)?;
+        }
+        let mut d = PersistentDataset::open(LogStore::open(&path)?)?;
+        assert_eq!(d.quads().count(), 2);
+        assert!(d.insert(&t("c"), &t("p"), &t("a"), None as Option<&BoxTerm>)?);
+        assert_eq!(d.term_count(), 7);
+        drop(d);
+        let d = PersistentDataset::open(LogStore::open(&path)?)?;
+        assert_eq!(d.quads_with_s(&t("c")).count(), 1);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    fn temp_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sophia-{}-{}", name, std::process::id()));
+        std::fs::remove_file(&path).ok();
+        path
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn recovery() -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_log("log-recovery");
+        {
+            let mut store = LogStore::open(&path)?;
+            store.set_sync_policy(SyncPolicy::Every(2));
+            let mut d = PersistentDataset::open(store)?;
+            d.insert(&t("a"), &t("p"), &t("b"), None as Option<&BoxTerm>)?;
+            d.insert(&t("a"), &t("p"), &t("c"), None as Option<&BoxTerm>)?;
+        }
+        let clean = check_log(&path)?;
+        assert!(clean.is_clean());
+        assert_eq!(clean.batches, 2);
+
+        // simulate a crash in the middle of the second batch
+        let len = std::fs::metadata(&path)?.len();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(len - 3)?;
+        let report = check_log(&path)?;
+        assert_eq!(report.batches, 1);
+        assert!(!report.is_clean());
+        assert_eq!(report.valid_bytes + report.invalid_bytes, len - 3);
+
+        let store = LogStore::open(&path)?;
+        assert_eq!(store.recovery(), report);
+        let mut d = PersistentDataset::open(store)?;
+        assert_eq!(d.quads().count(), 1);
+        d.insert(&t("a"), &t("p"), &t("d"), None as Option<&BoxTerm>)?;
+        drop(d);
+        assert!(check_log(&path)?.is_clean());
+
+        // a corrupted batch is discarded, as well as the following ones
+        let mut log = std::fs::read(&path)?;
+        let last = log.len() - 1;
+        log[last] ^= 0xff;
+        std::fs::write(&path, &log)?;
+        let d = PersistentDataset::open(LogStore::open(&path)?)?;
+        assert_eq!(d.quads().count(), 1);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_log("log-checkpoint");
+        let mut store = LogStore::open(&path)?;
+        store.set_sync_policy(SyncPolicy::Never);
+        let mut d = PersistentDataset::open(store)?;
+        d.set_batch_size(1);
+        d.insert_all(nq::parse_str(DATA))?;
+        d.remove(&t("a"), &t("p"), &t("b"), None as Option<&BoxTerm>)?;
+        assert_eq!(check_log(&path)?.batches, 4);
+        let mut store = d.into_store();
+        store.checkpoint()?;
+        assert_eq!(check_log(&path)?.batches, 1);
+        let mut d = PersistentDataset::open(store)?;
+        d.insert(&t("c"), &t("p"), &t("a"), None as Option<&BoxTerm>)?;
+        drop(d);
+        let d = PersistentDataset::open(LogStore::open(&path)?)?;
+        assert_eq!(d.quads().count(), 3);
+        assert!(d.check()?.is_ok());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn check() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = PersistentDataset::open(MemStore::new())?;
+        d.insert_all(nq::parse_str(DATA))?;
+        let report = d.check()?;
+        assert!(report.is_ok());
+        assert_eq!(report.quads, 3);
+
+        let mut batch = WriteBatch::new();
+        batch.put(
+            ColumnFamily::Posg,
+            encode_key(ColumnFamily::Posg, [1, 2, 99, 0]),
+            vec![],
+        );
+        batch.delete(
+            ColumnFamily::Ospg,
+            encode_key(ColumnFamily::Ospg, [1, 2, 3, 0]),
+        );
+        d.store.write(batch)?;
+        let report = d.check()?;
+        assert_eq!(
+            report.problems,
+            vec![
+                "posg: unknown term identifier 99",
+                "posg: 1 quads not in spog",
+                "ospg: 1 quads missing"
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+//! Memory quotas for in-memory datasets.
+//!
+//! [`QuotaDataset`] wraps an [`IndexedDataset`] (such as [`FastDataset`]),
+//! and rejects insertions that would make it exceed a configurable [`Quota`],
+//! expressed as a number of quads and/or an estimated heap size.
+//! A rejected insertion leaves the dataset unchanged,
+//! and fails with a [`QuotaExceeded`] error,
+//! which makes it possible to host several datasets in the same process
+//! (e.g. one per tenant of a service) without any of them starving the others.
+//!
+//! The heap size is estimated by [`IndexedDataset::heap_size`],
+//! which includes the textual data of the terms,
+//! so that a few quads with very long literals can not escape the byte limit.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, Dataset, MutableDataset};
+//! use sophia::dataset::quota::{Quota, QuotaDataset, QuotaExceeded};
+//! use sophia::ns::{rdf, rdfs};
+//! use sophia::term::BoxTerm;
+//!
+//! let mut quota = Quota::new();
+//! quota.set_max_quads(Some(1));
+//! let mut d = QuotaDataset::new(FastDataset::new(), quota);
+//! d.insert(&rdf::type_, &rdf::type_, &rdf::Property, None as Option<&BoxTerm>)?;
+//! let res = d.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, None as Option<&BoxTerm>);
+//! assert!(matches!(res, Err(QuotaExceeded::Quads { limit: 1 })));
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`QuotaDataset`]: struct.QuotaDataset.html
+//! [`Quota`]: struct.Quota.html
+//! [`QuotaExceeded`]: enum.QuotaExceeded.html
+//! [`IndexedDataset`]: ../indexed/trait.IndexedDataset.html
+//! [`IndexedDataset::heap_size`]: ../indexed/trait.IndexedDataset.html#tymethod.heap_size
+//! [`FastDataset`]: ../inmem/type.FastDataset.html
+
+use sophia_api::dataset::{DQuadSource, DResult, Dataset, MDResult, MutableDataset, SetDataset};
+use sophia_api::term::TTerm;
+use thiserror::Error;
+
+use crate::dataset::indexed::IndexedDataset;
+use crate::graph::indexed::MemoryUsage;
+
+/// The limits enforced by a [`QuotaDataset`](struct.QuotaDataset.html).
+///
+/// By default, no limit is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quota {
+    max_quads: Option<usize>,
+    max_bytes: Option<usize>,
+    check_interval: usize,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Quota {
+            max_quads: None,
+            max_bytes: None,
+            check_interval: 1,
+        }
+    }
+}
+
+impl Quota {
+    /// A quota with no limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of quads.
+    pub fn max_quads(&self) -> Option<usize> {
+        self.max_quads
+    }
+
+    /// Set the maximum number of quads (`None` for no limit).
+    pub fn set_max_quads(&mut self, max_quads: Option<usize>) -> &mut Self {
+        self.max_quads = max_quads;
+        self
+    }
+
+    /// The maximum estimated heap size, in bytes.
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+
+    /// Set the maximum estimated heap size, in bytes (`None` for no limit).
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) -> &mut Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// How often the heap size is measured.
+    pub fn check_interval(&self) -> usize {
+        self.check_interval
+    }
+
+    /// Measure the heap size only once every `check_interval` insertions (default: 1).
+    ///
+    /// Measuring the heap size of a dataset takes a time proportional to the size of its indexes;
+    /// a larger interval makes insertions cheaper,
+    /// but the byte limit may then be exceeded by up to `check_interval - 1` insertions
+    /// before an insertion is rejected.
+    /// The quad limit is always checked exactly.
+    ///
+    /// # Panics
+    /// If `check_interval` is 0.
+    pub fn set_check_interval(&mut self, check_interval: usize) -> &mut Self {
+        assert!(check_interval > 0, "check_interval must be positive");
+        self.check_interval = check_interval;
+        self
+    }
+}
+
+/// This error is raised when an insertion into a [`QuotaDataset`](struct.QuotaDataset.html)
+/// is rejected.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    /// The insertion would exceed the maximum number of quads.
+    #[error("Quota exceeded: dataset is limited to {limit} quads")]
+    Quads {
+        /// The maximum number of quads.
+        limit: usize,
+    },
+    /// The insertion would exceed the maximum heap size.
+    #[error("Quota exceeded: dataset would use {used} bytes, limit is {limit}")]
+    Bytes {
+        /// The maximum heap size, in bytes.
+        limit: usize,
+        /// The heap size (in bytes) the dataset would have reached.
+        used: usize,
+    },
+}
+
+/// A dataset wrapper enforcing a [`Quota`](struct.Quota.html).
+///
+/// See [module documentation](index.html).
+///
+/// Only insertions are constrained:
+/// if the quota is lowered below the current content of the dataset,
+/// the dataset is left as is, but all insertions fail until enough quads are removed.
+/// Removals never fail.
+pub struct QuotaDataset<D> {
+    inner: D,
+    quota: Quota,
+    len: usize,
+    unchecked: usize,
+}
+
+impl<D> QuotaDataset<D>
+where
+    D: IndexedDataset + Dataset,
+{
+    /// Wrap `inner`, enforcing `quota` on subsequent insertions.
+    pub fn new(inner: D, quota: Quota) -> Self {
+        let len = inner.quads().count();
+        QuotaDataset {
+            inner,
+            quota,
+            len,
+            unchecked: 0,
+        }
+    }
+
+    /// The wrapped dataset.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap the wrapped dataset.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// The enforced quota.
+    pub fn quota(&self) -> &Quota {
+        &self.quota
+    }
+
+    /// Change the enforced quota.
+    pub fn set_quota(&mut self, quota: Quota) -> &mut Self {
+        self.quota = quota;
+        self.unchecked = 0;
+        self
+    }
+
+    /// The number of quads in the dataset.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the dataset is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The memory currently used by the wrapped dataset.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.inner.memory_usage()
+    }
+
+    /// The number of bytes that can still be used before reaching the byte limit,
+    /// or `None` if there is no such limit.
+    pub fn remaining_bytes(&self) -> Option<usize> {
+        self.quota
+            .max_bytes
+            .map(|max| max.saturating_sub(self.inner.heap_size()))
+    }
+
+    /// Check the quota after `self.inner` was modified by an insertion.
+    fn check(&mut self) -> Result<(), QuotaExceeded> {
+        if let Some(limit) = self.quota.max_quads {
+            if self.len > limit {
+                return Err(QuotaExceeded::Quads { limit });
+            }
+        }
+        if let Some(limit) = self.quota.max_bytes {
+            self.unchecked += 1;
+            if self.unchecked >= self.quota.check_interval {
+                self.unchecked = 0;
+                let used = self.inner.heap_size();
+                if used > limit {
+                    return Err(QuotaExceeded::Bytes { limit, used });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D> Dataset for QuotaDataset<D>
+where
+    D: Dataset,
+{
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.inner.quads()
+    }
+
+    fn is_set(&self) -> bool {
+        self.inner.is_set()
+    }
+
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.inner.quads_with_s(s)
+    }
+
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.inner.quads_with_p(p)
+    }
+
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.inner.quads_with_o(o)
+    }
+
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.inner.quads_with_g(g)
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.inner.contains(s, p, o, g)
+    }
+}
+
+impl<D> MutableDataset for QuotaDataset<D>
+where
+    D: IndexedDataset + Dataset,
+{
+    type MutationError = QuotaExceeded;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        if self.inner.insert_indexed(s, p, o, g).is_none() {
+            return Ok(false);
+        }
+        self.len += 1;
+        if let Err(err) = self.check() {
+            self.inner.remove_indexed(s, p, o, g);
+            self.len -= 1;
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let removed = self.inner.remove_indexed(s, p, o, g).is_some();
+        if removed {
+            self.len -= 1;
+        }
+        Ok(removed)
+    }
+}
+
+impl<D> SetDataset for QuotaDataset<D> where D: IndexedDataset + SetDataset {}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::test_util::t;
+    use sophia_term::BoxTerm;
+
+    fn fill(d: &mut QuotaDataset<FastDataset>, n: usize) -> Result<(), QuotaExceeded> {
+        for i in 0..n {
+            let g = t(&format!("g{}", i % 2));
+            d.insert(&t(&format!("s{}", i)), &t("p"), &t("o"), Some(&g))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn no_limit() {
+        let mut d = QuotaDataset::new(FastDataset::new(), Quota::new());
+        fill(&mut d, 100).unwrap();
+        assert_eq!(d.len(), 100);
+        assert_eq!(d.remaining_bytes(), None);
+    }
+
+    #[test]
+    fn max_quads() {
+        let mut quota = Quota::new();
+        quota.set_max_quads(Some(10));
+        let mut d = QuotaDataset::new(FastDataset::new(), quota);
+        assert_eq!(fill(&mut d, 20), Err(QuotaExceeded::Quads { limit: 10 }));
+        assert_eq!(d.len(), 10);
+        assert_eq!(d.quads().count(), 10);
+        // re-inserting an existing quad is not an insertion
+        assert_eq!(
+            d.insert(&t("s0"), &t("p"), &t("o"), Some(&t("g0"))),
+            Ok(false)
+        );
+        // removing makes room again
+        assert_eq!(
+            d.remove(&t("s0"), &t("p"), &t("o"), Some(&t("g0"))),
+            Ok(true)
+        );
+        assert_eq!(
+            d.insert(&t("s99"), &t("p"), &t("o"), Some(&t("g0"))),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn max_bytes() {
+        let mut probe = FastDataset::new();
+        for i in 0..10 {
+            probe.insert_indexed(&t(&format!("s{}", i)), &t("p"), &t("o"), Some(&t("g")));
+        }
+        let limit = probe.heap_size();
+
+        let mut quota = Quota::new();
+        quota.set_max_bytes(Some(limit));
+        let mut d = QuotaDataset::new(FastDataset::new(), quota);
+        let err = fill(&mut d, 1000).unwrap_err();
+        match err {
+            QuotaExceeded::Bytes { limit: l, used } => {
+                assert_eq!(l, limit);
+                assert!(used > limit);
+            }
+            _ => panic!("unexpected error {:?}", err),
+        }
+        assert!(!d.is_empty() && d.len() < 1000);
+        assert_eq!(d.quads().count(), d.len());
+    }
+
+    #[test]
+    fn max_bytes_counts_term_data() {
+        let mut quota = Quota::new();
+        quota.set_max_bytes(Some(100_000));
+        let mut d = QuotaDataset::new(FastDataset::new(), quota);
+        let long = BoxTerm::new_literal_dt("x".repeat(1_000_000), t("dt")).unwrap();
+        let res = d.insert(&t("s"), &t("p"), &long, None as Option<&BoxTerm>);
+        assert!(matches!(res, Err(QuotaExceeded::Bytes { used, .. }) if used > 1_000_000));
+        assert!(d.is_empty());
+        assert!(d.inner().get_index(&long).is_none());
+    }
+
+    #[test]
+    fn rejected_insertion_leaves_dataset_unchanged() {
+        let mut quota = Quota::new();
+        quota.set_max_quads(Some(1));
+        let mut d = QuotaDataset::new(FastDataset::new(), quota);
+        d.insert(&t("s"), &t("p"), &t("o"), None as Option<&BoxTerm>)
+            .unwrap();
+        let before = d.memory_usage();
+        assert!(d
+            .insert(&t("new_s"), &t("new_p"), &t("new_o"), Some(&t("new_g")))
+            .is_err());
+        assert!(d.inner().get_index(&t("new_s")).is_none());
+        assert!(d.inner().get_index(&t("new_g")).is_none());
+        assert!(!d
+            .contains(&t("new_s"), &t("new_p"), &t("new_o"), Some(&t("new_g")))
+            .unwrap());
+        assert_eq!(d.quads().count(), 1);
+        assert!(d.memory_usage().dictionary >= before.dictionary);
+    }
+
+    #[test]
+    fn check_interval() {
+        let mut quota = Quota::new();
+        quota.set_max_bytes(Some(0)).set_check_interval(5);
+        let mut d = QuotaDataset::new(FastDataset::new(), quota);
+        assert!(matches!(fill(&mut d, 10), Err(QuotaExceeded::Bytes { .. })));
+        assert_eq!(d.len(), 4);
+    }
+
+    #[test]
+    fn existing_content() {
+        let mut inner = FastDataset::new();
+        inner.insert_indexed(&t("s"), &t("p"), &t("o"), None as Option<&BoxTerm>);
+        let mut quota = Quota::new();
+        quota.set_max_quads(Some(1));
+        let mut d = QuotaDataset::new(inner, quota);
+        assert_eq!(d.len(), 1);
+        assert!(d
+            .insert(&t("s2"), &t("p"), &t("o"), None as Option<&BoxTerm>)
+            .is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! A [`Dataset`] storing each of its graphs separately, with graph management operations.
+//!
+//! [`GraphStore`] keeps a distinct indexed [`FastGraph`] for each of its graphs,
+//! so that queries restricted to one graph only ever look at that graph.
+//! It also keeps track of empty named graphs,
+//! and provides the graph management operations of [SPARQL 1.1 Update]
+//! ([`create_graph`], [`drop_graph`], [`clear_graph`],
+//! [`add_graph`], [`copy_graph`] and [`move_graph`]),
+//! which are the building blocks of the [Graph Store Protocol].
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{store::GraphStore, Dataset, MutableDataset};
+//! use sophia::ns::rdf;
+//! use sophia::term::BoxTerm;
+//!
+//! let g1 = BoxTerm::new_iri("tag:g1")?;
+//! let g2 = BoxTerm::new_iri("tag:g2")?;
+//! let mut d = GraphStore::new();
+//! d.insert(&rdf::type_, &rdf::type_, &rdf::Property, Some(&g1))?;
+//! assert!(d.copy_graph(Some(&g1), Some(&g2)));
+//! assert!(d.drop_graph(Some(&g1)));
+//! assert_eq!(d.quads_with_g(Some(&g2)).count(), 1);
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Dataset`]: ../trait.Dataset.html
+//! [`GraphStore`]: struct.GraphStore.html
+//! [`FastGraph`]: ../../graph/inmem/type.FastGraph.html
+//! [SPARQL 1.1 Update]: https://www.w3.org/TR/sparql11-update/#graphManagement
+//! [Graph Store Protocol]: https://www.w3.org/TR/sparql11-http-rdf-update/
+//! [`create_graph`]: struct.GraphStore.html#method.create_graph
+//! [`drop_graph`]: struct.GraphStore.html#method.drop_graph
+//! [`clear_graph`]: struct.GraphStore.html#method.clear_graph
+//! [`add_graph`]: struct.GraphStore.html#method.add_graph
+//! [`copy_graph`]: struct.GraphStore.html#method.copy_graph
+//! [`move_graph`]: struct.GraphStore.html#method.move_graph
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use sophia_api::dataset::{
+    CollectibleDataset, DQuadSource, DResult, Dataset, MDResult, MutableDataset, SetDataset,
+};
+use sophia_api::graph::{GTripleSource, Graph, MutableGraph};
+use sophia_api::quad::stream::{QuadSource, StreamResult};
+use sophia_api::quad::streaming_mode::{ByValue, StreamedQuad};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::*;
+
+use crate::graph::inmem::FastGraph;
+
+type StoreQuad = ([RcTerm; 3], Option<RcTerm>);
+
+/// A dataset storing each of its graphs in a separate [`FastGraph`].
+///
+/// See [module documentation](index.html).
+///
+/// [`FastGraph`]: ../../graph/inmem/type.FastGraph.html
+#[derive(Default)]
+pub struct GraphStore {
+    default: FastGraph,
+    named: HashMap<RcTerm, FastGraph>,
+}
+
+impl GraphStore {
+    /// Construct an empty dataset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Borrow the graph with the given name (`None` for the default graph), if it exists.
+    pub fn get_graph<T>(&self, name: Option<&T>) -> Option<&FastGraph>
+    where
+        T: TTerm + ?Sized,
+    {
+        match name {
+            None => Some(&self.default),
+            Some(name) => self.named.get(&RcTerm::copy(name)),
+        }
+    }
+
+    /// Borrow mutably the graph with the given name (`None` for the default graph), if it exists.
+    pub fn get_graph_mut<T>(&mut self, name: Option<&T>) -> Option<&mut FastGraph>
+    where
+        T: TTerm + ?Sized,
+    {
+        match name {
+            None => Some(&mut self.default),
+            Some(name) => self.named.get_mut(&RcTerm::copy(name)),
+        }
+    }
+
+    /// The names of all the named graphs of this dataset, including empty ones.
+    pub fn named_graphs(&self) -> impl Iterator<Item = &RcTerm> {
+        self.named.keys()
+    }
+
+    /// Create an empty named graph,
+    /// and return whether it did not already exist.
+    pub fn create_graph<T>(&mut self, name: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        let name = RcTerm::copy(name);
+        if self.named.contains_key(&name) {
+            return false;
+        }
+        self.named.insert(name, FastGraph::new());
+        true
+    }
+
+    /// Remove the given graph from this dataset,
+    /// and return whether it existed.
+    ///
+    /// As the default graph always exists, dropping it amounts to clearing it.
+    pub fn drop_graph<T>(&mut self, name: Option<&T>) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        match name {
+            None => {
+                self.default = FastGraph::new();
+                true
+            }
+            Some(name) => self.named.remove(&RcTerm::copy(name)).is_some(),
+        }
+    }
+
+    /// Remove all the triples from the given graph,
+    /// and return whether it existed.
+    pub fn clear_graph<T>(&mut self, name: Option<&T>) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        match self.get_graph_mut(name) {
+            None => false,
+            Some(graph) => {
+                *graph = FastGraph::new();
+                true
+            }
+        }
+    }
+
+    /// Insert all the triples of graph `from` into graph `to`,
+    /// creating `to` if necessary.
+    ///
+    /// Return false (and do nothing) if `from` does not exist.
+    pub fn add_graph<T, U>(&mut self, from: Option<&T>, to: Option<&U>) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.transfer(from, to, false, false)
+    }
+
+    /// Replace the content of graph `to` by the triples of graph `from`,
+    /// creating `to` if necessary.
+    ///
+    /// Return false (and do nothing) if `from` does not exist.
+    pub fn copy_graph<T, U>(&mut self, from: Option<&T>, to: Option<&U>) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.transfer(from, to, true, false)
+    }
+
+    /// Replace the content of graph `to` by the triples of graph `from`,
+    /// creating `to` if necessary, then drop `from`.
+    ///
+    /// Return false (and do nothing) if `from` does not exist.
+    pub fn move_graph<T, U>(&mut self, from: Option<&T>, to: Option<&U>) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.transfer(from, to, true, true)
+    }
+
+    fn transfer<T, U>(&mut self, from: Option<&T>, to: Option<&U>, clear: bool, drop: bool) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        let from = from.map(RcTerm::copy);
+        let to = to.map(RcTerm::copy);
+        let triples: Vec<[RcTerm; 3]> = match self.get_graph(from.as_ref()) {
+            None => return false,
+            Some(_) if from == to => return true,
+            Some(graph) => graph
+                .triples()
+                .map(|t| t.map(|t| [t.s().clone(), t.p().clone(), t.o().clone()]))
+                .collect::<Result<_, Infallible>>()
+                .unwrap(),
+        };
+        if drop {
+            self.drop_graph(from.as_ref());
+        }
+        let target = self.graph_entry(to);
+        if clear {
+            *target = FastGraph::new();
+        }
+        target
+            .insert_all(triples.into_iter().map(Ok::<_, Infallible>))
+            .unwrap();
+        true
+    }
+
+    fn graph_entry(&mut self, name: Option<RcTerm>) -> &mut FastGraph {
+        match name {
+            None => &mut self.default,
+            Some(name) => self.named.entry(name).or_default(),
+        }
+    }
+
+    /// The quads of the graph named `name`, selected by `select`.
+    fn quads_in<'s, T, F>(&'s self, name: Option<&'s T>, select: F) -> DQuadSource<'s, Self>
+    where
+        T: TTerm + ?Sized,
+        F: FnOnce(&'s FastGraph) -> GTripleSource<'s, FastGraph>,
+    {
+        match name {
+            None => to_quads(None, select(&self.default)),
+            Some(name) => match self.named.get_key_value(&RcTerm::copy(name)) {
+                None => Box::new(std::iter::empty()),
+                Some((name, graph)) => to_quads(Some(name), select(graph)),
+            },
+        }
+    }
+}
+
+fn to_quads<'s>(
+    name: Option<&'s RcTerm>,
+    triples: GTripleSource<'s, FastGraph>,
+) -> DQuadSource<'s, GraphStore> {
+    Box::new(triples.map(move |t| {
+        t.map(|t| {
+            StreamedQuad::by_value(([t.s().clone(), t.p().clone(), t.o().clone()], name.cloned()))
+        })
+    }))
+}
+
+impl Dataset for GraphStore {
+    type Quad = ByValue<StoreQuad>;
+    type Error = Infallible;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        Box::new(
+            Some((None, &self.default))
+                .into_iter()
+                .chain(self.named.iter().map(|(name, graph)| (Some(name), graph)))
+                .flat_map(|(name, graph)| to_quads(name, graph.triples())),
+        )
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.quads_in(g, |graph| graph.triples())
+    }
+
+    fn quads_with_sg<'s, TS, TG>(&'s self, s: &'s TS, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.quads_in(g, |graph| graph.triples_with_s(s))
+    }
+
+    fn quads_with_pg<'s, TP, TG>(&'s self, p: &'s TP, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.quads_in(g, |graph| graph.triples_with_p(p))
+    }
+
+    fn quads_with_og<'s, TO, TG>(&'s self, o: &'s TO, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.quads_in(g, |graph| graph.triples_with_o(o))
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        match self.get_graph(g) {
+            None => Ok(false),
+            Some(graph) => graph.contains(s, p, o),
+        }
+    }
+}
+
+impl CollectibleDataset for GraphStore {
+    fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Infallible> {
+        let mut d = GraphStore::new();
+        d.insert_all(quads).map(|_| d)
+    }
+}
+
+impl MutableDataset for GraphStore {
+    type MutationError = Infallible;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.graph_entry(g.map(RcTerm::copy)).insert(s, p, o)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        match self.get_graph_mut(g) {
+            None => Ok(false),
+            Some(graph) => graph.remove(s, p, o),
+        }
+    }
+}
+
+impl SetDataset for GraphStore {}
+
+#[cfg(test)]
+sophia_api::test_dataset_impl!(test_store, GraphStore);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::{rdf, rdfs};
+    use sophia_term::BoxTerm;
+
+    fn g(suffix: &str) -> Option<BoxTerm> {
+        Some(BoxTerm::new_iri(format!("tag:{}", suffix)).unwrap())
+    }
+
+    fn count(d: &GraphStore, name: Option<BoxTerm>) -> usize {
+        d.quads_with_g(name.as_ref()).count()
+    }
+
+    #[test]
+    fn management() -> Result<(), Box<dyn std::error::Error>> {
+        let dg: Option<BoxTerm> = None;
+        let mut d = GraphStore::new();
+        assert!(d.create_graph(&g("empty").unwrap()));
+        assert!(!d.create_graph(&g("empty").unwrap()));
+        d.insert(&rdf::type_, &rdf::type_, &rdf::Property, g("g1").as_ref())?;
+        d.insert(&rdfs::Class, &rdf::type_, &rdfs::Class, g("g1").as_ref())?;
+        d.insert(&rdf::type_, &rdfs::label, &rdf::type_, dg.as_ref())?;
+        assert_eq!(d.named_graphs().count(), 2);
+        assert_eq!(d.graph_names()?.len(), 1);
+
+        assert!(d.add_graph(dg.as_ref(), g("g1").as_ref()));
+        assert_eq!(count(&d, g("g1")), 3);
+        assert_eq!(count(&d, None), 1);
+
+        assert!(d.copy_graph(g("empty").as_ref(), g("g1").as_ref()));
+        assert_eq!(count(&d, g("g1")), 0);
+        assert!(d.copy_graph(dg.as_ref(), g("g2").as_ref()));
+        assert!(d.move_graph(g("g2").as_ref(), g("g3").as_ref()));
+        assert!(d.get_graph(g("g2").as_ref()).is_none());
+        assert_eq!(count(&d, g("g3")), 1);
+        assert!(!d.move_graph(g("g2").as_ref(), g("g3").as_ref()));
+        assert!(d.move_graph(g("g3").as_ref(), g("g3").as_ref()));
+        assert_eq!(count(&d, g("g3")), 1);
+
+        assert!(d.clear_graph(g("g3").as_ref()));
+        assert!(d.get_graph(g("g3").as_ref()).is_some());
+        assert!(d.drop_graph(g("g3").as_ref()));
+        assert!(!d.drop_graph(g("g3").as_ref()));
+        assert!(d.drop_graph(dg.as_ref()));
+        assert_eq!(d.quads().count(), 0);
+        Ok(())
+    }
+}
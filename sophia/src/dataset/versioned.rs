@@ -0,0 +1,489 @@
+//! A [`Dataset`] recording the history of its modifications.
+//!
+//! [`VersionedDataset`] wraps another dataset,
+//! and records every quad inserted into or removed from it.
+//! These pending changes can be [committed] (with an author and a message),
+//! producing a new *version* of the dataset,
+//! or [rolled back].
+//! Any past version can then be [checked out] as a new dataset,
+//! and the [differences] between any two versions can be computed,
+//! in the spirit of [Quit Store] and other RDF archives.
+//!
+//! Versions are numbered from 0 (the content of the wrapped dataset when the history started),
+//! and commit number `i` leads from version `i` to version `i+1`.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, versioned::VersionedDataset, Dataset, MutableDataset};
+//! use sophia::ns::{rdf, rdfs};
+//! use sophia::term::BoxTerm;
+//!
+//! let g = BoxTerm::new_iri("tag:g")?;
+//! let mut d = VersionedDataset::new(FastDataset::new());
+//! d.insert(&rdf::type_, &rdf::type_, &rdf::Property, Some(&g))?;
+//! assert_eq!(d.commit("alice", "add rdf:type"), 1);
+//! d.insert(&rdfs::label, &rdf::type_, &rdf::Property, Some(&g))?;
+//! d.commit("bob", "add rdfs:label");
+//!
+//! let diff = d.diff(1, 2);
+//! assert_eq!(diff.added().len(), 1);
+//! assert_eq!(diff.removed().len(), 0);
+//! let v1: FastDataset = d.checkout(1).unwrap();
+//! assert_eq!(v1.quads().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Dataset`]: ../trait.Dataset.html
+//! [`VersionedDataset`]: struct.VersionedDataset.html
+//! [committed]: struct.VersionedDataset.html#method.commit
+//! [rolled back]: struct.VersionedDataset.html#method.rollback
+//! [checked out]: struct.VersionedDataset.html#method.checkout
+//! [differences]: struct.VersionedDataset.html#method.diff
+//! [Quit Store]: https://github.com/AKSW/QuitStore
+
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use sophia_api::dataset::{
+    CollectibleDataset, DQuadSource, DResult, Dataset, MDResult, MutableDataset, SetDataset,
+};
+use sophia_api::quad::stream::{AsQuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::RcTerm;
+
+/// The type of quads recorded in the history of a [`VersionedDataset`](struct.VersionedDataset.html).
+pub type VersionedQuad = ([RcTerm; 3], Option<RcTerm>);
+
+/// A set of quads added to, and a set of quads removed from, a dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChangeSet {
+    added: HashSet<VersionedQuad>,
+    removed: HashSet<VersionedQuad>,
+}
+
+impl ChangeSet {
+    /// The quads added by this change set.
+    pub fn added(&self) -> &HashSet<VersionedQuad> {
+        &self.added
+    }
+
+    /// The quads removed by this change set.
+    pub fn removed(&self) -> &HashSet<VersionedQuad> {
+        &self.removed
+    }
+
+    /// Whether this change set leaves the dataset unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// The change set undoing this one.
+    pub fn reversed(&self) -> ChangeSet {
+        ChangeSet {
+            added: self.removed.clone(),
+            removed: self.added.clone(),
+        }
+    }
+
+    /// Record that `quad` was actually inserted in the dataset.
+    fn record_insert(&mut self, quad: VersionedQuad) {
+        if !self.removed.remove(&quad) {
+            self.added.insert(quad);
+        }
+    }
+
+    /// Record that `quad` was actually removed from the dataset.
+    fn record_remove(&mut self, quad: VersionedQuad) {
+        if !self.added.remove(&quad) {
+            self.removed.insert(quad);
+        }
+    }
+
+    /// Extend this change set with the changes of `other`, applied after it.
+    fn then(&mut self, other: &ChangeSet) {
+        for quad in &other.added {
+            self.record_insert(quad.clone());
+        }
+        for quad in &other.removed {
+            self.record_remove(quad.clone());
+        }
+    }
+
+    /// Apply this change set to a set of quads.
+    fn apply_to(&self, quads: &mut HashSet<VersionedQuad>) {
+        for quad in &self.removed {
+            quads.remove(quad);
+        }
+        for quad in &self.added {
+            quads.insert(quad.clone());
+        }
+    }
+}
+
+/// A commit in the history of a [`VersionedDataset`](struct.VersionedDataset.html).
+#[derive(Clone, Debug)]
+pub struct Commit {
+    author: String,
+    message: String,
+    timestamp: SystemTime,
+    changes: ChangeSet,
+}
+
+impl Commit {
+    /// The author of this commit.
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// The message of this commit.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// When this commit was made.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The changes made by this commit.
+    pub fn changes(&self) -> &ChangeSet {
+        &self.changes
+    }
+}
+
+/// A dataset wrapper recording the history of its modifications.
+///
+/// See [module documentation](index.html).
+///
+/// Note that only the modifications made through this wrapper are recorded,
+/// and that the wrapped dataset must be a [`SetDataset`](../trait.SetDataset.html),
+/// in order to know which modifications were actually made.
+pub struct VersionedDataset<D> {
+    head: D,
+    commits: Vec<Commit>,
+    pending: ChangeSet,
+}
+
+impl<D> VersionedDataset<D>
+where
+    D: Dataset,
+{
+    /// Start recording the history of `dataset`, whose current content is version 0.
+    pub fn new(dataset: D) -> Self {
+        VersionedDataset {
+            head: dataset,
+            commits: vec![],
+            pending: ChangeSet::default(),
+        }
+    }
+
+    /// The current version (i.e. the number of commits).
+    pub fn version(&self) -> usize {
+        self.commits.len()
+    }
+
+    /// All the commits, from the oldest to the most recent.
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
+
+    /// The changes made since the last commit.
+    pub fn pending(&self) -> &ChangeSet {
+        &self.pending
+    }
+
+    /// Borrow the wrapped dataset (including pending changes).
+    pub fn head(&self) -> &D {
+        &self.head
+    }
+
+    /// Stop recording history, and return the wrapped dataset (including pending changes).
+    pub fn into_head(self) -> D {
+        self.head
+    }
+
+    /// Commit the pending changes, and return the new version.
+    ///
+    /// Commits without any change are allowed.
+    pub fn commit(&mut self, author: &str, message: &str) -> usize {
+        self.commits.push(Commit {
+            author: author.to_string(),
+            message: message.to_string(),
+            timestamp: SystemTime::now(),
+            changes: std::mem::take(&mut self.pending),
+        });
+        self.version()
+    }
+
+    /// The changes leading from version `from` to version `to`.
+    ///
+    /// If `from` is greater than `to`, these changes undo the commits in between.
+    ///
+    /// # Panics
+    /// Panics if either version is greater than the [current version](#method.version).
+    pub fn diff(&self, from: usize, to: usize) -> ChangeSet {
+        if from > to {
+            return self.diff(to, from).reversed();
+        }
+        let mut diff = ChangeSet::default();
+        for commit in &self.commits[from..to] {
+            diff.then(&commit.changes);
+        }
+        diff
+    }
+
+    /// Build a new dataset containing the given version of this dataset.
+    ///
+    /// Errors raised while reading this dataset are wrapped in `SourceError`,
+    /// errors raised while building the new dataset are wrapped in `SinkError`.
+    ///
+    /// # Panics
+    /// Panics if `version` is greater than the [current version](#method.version).
+    pub fn checkout<E>(&self, version: usize) -> StreamResult<E, D::Error, E::Error>
+    where
+        E: CollectibleDataset,
+    {
+        let mut quads = HashSet::new();
+        for q in self.head.quads() {
+            let q = q.map_err(SourceError)?;
+            quads.insert(copy_quad(q.s(), q.p(), q.o(), q.g()));
+        }
+        let mut changes = self.pending.reversed();
+        changes.then(&self.diff(self.version(), version));
+        changes.apply_to(&mut quads);
+        E::from_quad_source(quads.iter().as_quad_source())
+            .map_err(|err| SinkError(err.unwrap_sink_error()))
+    }
+}
+
+impl<D> VersionedDataset<D>
+where
+    D: MutableDataset + SetDataset,
+{
+    /// Undo the changes made since the last commit.
+    pub fn rollback(&mut self) -> MDResult<D, ()> {
+        let pending = std::mem::take(&mut self.pending);
+        for ([s, p, o], g) in &pending.added {
+            self.head.remove(s, p, o, g.as_ref())?;
+        }
+        for ([s, p, o], g) in &pending.removed {
+            self.head.insert(s, p, o, g.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl<D> Dataset for VersionedDataset<D>
+where
+    D: Dataset,
+{
+    type Quad = D::Quad;
+    type Error = D::Error;
+
+    fn quads(&self) -> DQuadSource<'_, Self> {
+        self.head.quads()
+    }
+
+    fn is_set(&self) -> bool {
+        self.head.is_set()
+    }
+
+    fn quads_with_s<'s, TS>(&'s self, s: &'s TS) -> DQuadSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.head.quads_with_s(s)
+    }
+
+    fn quads_with_p<'s, TP>(&'s self, p: &'s TP) -> DQuadSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.head.quads_with_p(p)
+    }
+
+    fn quads_with_o<'s, TO>(&'s self, o: &'s TO) -> DQuadSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.head.quads_with_o(o)
+    }
+
+    fn quads_with_g<'s, TG>(&'s self, g: Option<&'s TG>) -> DQuadSource<'s, Self>
+    where
+        TG: TTerm + ?Sized,
+    {
+        self.head.quads_with_g(g)
+    }
+
+    fn contains<TS, TP, TO, TG>(
+        &self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> DResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        self.head.contains(s, p, o, g)
+    }
+}
+
+impl<D> MutableDataset for VersionedDataset<D>
+where
+    D: MutableDataset + SetDataset,
+{
+    type MutationError = D::MutationError;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let inserted = self.head.insert(s, p, o, g)?;
+        if inserted {
+            self.pending.record_insert(copy_quad(s, p, o, g));
+        }
+        Ok(inserted)
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+        g: Option<&TG>,
+    ) -> MDResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+        TG: TTerm + ?Sized,
+    {
+        let removed = self.head.remove(s, p, o, g)?;
+        if removed {
+            self.pending.record_remove(copy_quad(s, p, o, g));
+        }
+        Ok(removed)
+    }
+}
+
+impl<D> SetDataset for VersionedDataset<D> where D: SetDataset {}
+
+fn copy_quad<TS, TP, TO, TG>(s: &TS, p: &TP, o: &TO, g: Option<&TG>) -> VersionedQuad
+where
+    TS: TTerm + ?Sized,
+    TP: TTerm + ?Sized,
+    TO: TTerm + ?Sized,
+    TG: TTerm + ?Sized,
+{
+    (
+        [RcTerm::copy(s), RcTerm::copy(p), RcTerm::copy(o)],
+        g.map(RcTerm::copy),
+    )
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::test_util::t;
+    use sophia_term::BoxTerm;
+
+    fn insert(d: &mut VersionedDataset<FastDataset>, s: &str, g: Option<&str>) -> bool {
+        d.insert(&t(s), &t("p"), &t("o"), g.map(t).as_ref())
+            .unwrap()
+    }
+
+    fn remove(d: &mut VersionedDataset<FastDataset>, s: &str, g: Option<&str>) -> bool {
+        d.remove(&t(s), &t("p"), &t("o"), g.map(t).as_ref())
+            .unwrap()
+    }
+
+    fn subjects(d: &FastDataset) -> Vec<String> {
+        let mut subjects: Vec<_> = d
+            .quads()
+            .map(|q| q.unwrap().s().value().to_string())
+            .collect();
+        subjects.sort();
+        subjects
+    }
+
+    #[test]
+    fn history() -> Result<(), Box<dyn std::error::Error>> {
+        let mut initial = FastDataset::new();
+        initial.insert(&t("init"), &t("p"), &t("o"), None as Option<&BoxTerm>)?;
+        let mut d = VersionedDataset::new(initial);
+
+        assert!(insert(&mut d, "a", None));
+        assert!(insert(&mut d, "b", Some("g")));
+        assert!(!insert(&mut d, "b", Some("g")));
+        assert_eq!(d.commit("alice", "first"), 1);
+        assert!(remove(&mut d, "a", None));
+        assert!(insert(&mut d, "c", None));
+        assert!(remove(&mut d, "init", None));
+        assert_eq!(d.commit("bob", "second"), 2);
+
+        assert_eq!(d.commits()[0].author(), "alice");
+        assert_eq!(d.commits()[1].message(), "second");
+        assert_eq!(d.commits()[1].changes().removed().len(), 2);
+
+        let v0: FastDataset = d.checkout(0).unwrap();
+        assert_eq!(subjects(&v0), vec!["tag:init"]);
+        let v1: FastDataset = d.checkout(1).unwrap();
+        assert_eq!(subjects(&v1), vec!["tag:a", "tag:b", "tag:init"]);
+        let v2: FastDataset = d.checkout(2).unwrap();
+        assert_eq!(subjects(&v2), vec!["tag:b", "tag:c"]);
+
+        let diff = d.diff(0, 2);
+        assert_eq!(diff.added().len(), 2);
+        assert_eq!(diff.removed().len(), 1);
+        assert_eq!(d.diff(2, 0), diff.reversed());
+        assert!(d.diff(1, 1).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn pending() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = VersionedDataset::new(FastDataset::new());
+        insert(&mut d, "a", None);
+        d.commit("alice", "first");
+
+        // changes cancelling each other are not recorded
+        insert(&mut d, "b", None);
+        remove(&mut d, "b", None);
+        remove(&mut d, "a", None);
+        insert(&mut d, "a", None);
+        assert!(d.pending().is_empty());
+
+        insert(&mut d, "c", None);
+        remove(&mut d, "a", None);
+        assert_eq!(subjects(d.head()), vec!["tag:c"]);
+        // pending changes are not part of any version
+        let v1: FastDataset = d.checkout(1).unwrap();
+        assert_eq!(subjects(&v1), vec!["tag:a"]);
+
+        d.rollback()?;
+        assert!(d.pending().is_empty());
+        assert_eq!(subjects(d.head()), vec!["tag:a"]);
+        assert_eq!(d.version(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,450 @@
+//! Pluggable handlers for custom datatypes.
+//!
+//! Sophia natively understands the common XSD datatypes
+//! (see [`check_literal`] and [`XsdValue`]).
+//! A [`DatatypeRegistry`] lets applications give the same first-class treatment
+//! to their own datatypes (units of measure, chemical formulas, identifiers...),
+//! by registering a [`DatatypeHandler`] for each datatype IRI.
+//! A handler parses lexical forms into native values,
+//! and can define the canonical lexical form and the order of those values.
+//!
+//! The registry is then consulted by
+//! * the literal validation of the [`validation`] module
+//!   ([`validate_literals_with`] and [`QuadValidator::set_registry`]);
+//! * the value-based comparison of literals ([`DatatypeRegistry::value_eq`] and [`DatatypeRegistry::value_cmp`]);
+//! * the comparison operators of the [`expression`] library ([`Expr::eval_with`]).
+//!
+//! # Example
+//! ```
+//! use sophia::datatype::{DatatypeHandler, DatatypeRegistry};
+//! use sophia::term::{BoxTerm, TTerm};
+//!
+//! /// Chemical formulas such as `H2O`, whose elements are counted regardless of their order.
+//! struct Formula;
+//!
+//! impl DatatypeHandler for Formula {
+//!     type Value = Vec<(String, u32)>;
+//!
+//!     fn parse(&self, lex: &str) -> Result<Self::Value, String> {
+//!         let mut elements: Vec<(String, u32)> = vec![];
+//!         let mut chars = lex.chars().peekable();
+//!         while let Some(c) = chars.next() {
+//!             if !c.is_ascii_uppercase() {
+//!                 return Err(format!("unexpected {:?}", c));
+//!             }
+//!             let mut symbol = c.to_string();
+//!             while let Some(c) = chars.next_if(|c| c.is_ascii_lowercase()) {
+//!                 symbol.push(c);
+//!             }
+//!             let mut count = String::new();
+//!             while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+//!                 count.push(c);
+//!             }
+//!             let count = if count.is_empty() { 1 } else { count.parse().unwrap() };
+//!             elements.push((symbol, count));
+//!         }
+//!         elements.sort();
+//!         Ok(elements)
+//!     }
+//!
+//!     fn canonical(&self, value: &Self::Value) -> Option<String> {
+//!         Some(value.iter().map(|(s, n)| format!("{}{}", s, n)).collect())
+//!     }
+//! }
+//!
+//! let formula = BoxTerm::new_iri("tag:formula")?;
+//! let mut registry = DatatypeRegistry::new();
+//! registry.register(&formula, Formula);
+//!
+//! let water = BoxTerm::new_literal_dt("H2O", formula.clone())?;
+//! let also_water = BoxTerm::new_literal_dt("OH2", formula.clone())?;
+//! let invalid = BoxTerm::new_literal_dt("h2o", formula.clone())?;
+//! assert!(registry.check_literal(&water).is_ok());
+//! assert!(registry.check_literal(&invalid).is_err());
+//! assert_eq!(registry.value_eq(&water, &also_water), Some(true));
+//! assert_eq!(registry.canonicalize(&water).unwrap().value(), "H2O1");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`check_literal`]: ../validation/fn.check_literal.html
+//! [`XsdValue`]: ../term/literal/valued/enum.XsdValue.html
+//! [`DatatypeRegistry`]: struct.DatatypeRegistry.html
+//! [`DatatypeHandler`]: trait.DatatypeHandler.html
+//! [`validation`]: ../validation/index.html
+//! [`validate_literals_with`]: ../validation/fn.validate_literals_with.html
+//! [`QuadValidator::set_registry`]: ../validation/struct.QuadValidator.html#method.set_registry
+//! [`DatatypeRegistry::value_eq`]: struct.DatatypeRegistry.html#method.value_eq
+//! [`DatatypeRegistry::value_cmp`]: struct.DatatypeRegistry.html#method.value_cmp
+//! [`expression`]: ../expression/index.html
+//! [`Expr::eval_with`]: ../expression/enum.Expr.html#method.eval_with
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use sophia_api::term::{term_eq, term_to_string, TTerm, TermKind};
+use sophia_term::literal::valued::XsdValue;
+use sophia_term::BoxTerm;
+
+use crate::validation::check_literal;
+
+/// The behaviour of a custom datatype.
+///
+/// Only [`parse`](#tymethod.parse) is required;
+/// without [`canonical`](#method.canonical), lexical forms are kept as they are,
+/// and without [`compare`](#method.compare),
+/// two values are only considered equal if they have the same canonical lexical form.
+pub trait DatatypeHandler: Send + Sync + 'static {
+    /// The native type of the values of this datatype.
+    type Value: 'static;
+
+    /// Parse `lex` into a value,
+    /// or return the reason why it is not in the lexical space of this datatype.
+    fn parse(&self, lex: &str) -> Result<Self::Value, String>;
+
+    /// The canonical lexical form of `value`, if this datatype defines one.
+    fn canonical(&self, value: &Self::Value) -> Option<String> {
+        let _ = value;
+        None
+    }
+
+    /// The order of `a` and `b`, or `None` if they are not comparable.
+    fn compare(&self, a: &Self::Value, b: &Self::Value) -> Option<Ordering> {
+        let _ = (a, b);
+        None
+    }
+}
+
+/// The object-safe counterpart of [`DatatypeHandler`], working on lexical forms.
+trait ErasedHandler: Send + Sync {
+    fn check(&self, lex: &str) -> Result<(), String>;
+    fn canonical(&self, lex: &str) -> Option<String>;
+    fn compare(&self, a: &str, b: &str) -> Option<Ordering>;
+    fn parse_any(&self, lex: &str) -> Option<Box<dyn Any>>;
+}
+
+impl<H: DatatypeHandler> ErasedHandler for H {
+    fn check(&self, lex: &str) -> Result<(), String> {
+        self.parse(lex).map(|_| ())
+    }
+
+    fn canonical(&self, lex: &str) -> Option<String> {
+        let value = self.parse(lex).ok()?;
+        Some(DatatypeHandler::canonical(self, &value).unwrap_or_else(|| lex.to_string()))
+    }
+
+    fn compare(&self, a: &str, b: &str) -> Option<Ordering> {
+        let (va, vb) = (self.parse(a).ok()?, self.parse(b).ok()?);
+        if let Some(ord) = DatatypeHandler::compare(self, &va, &vb) {
+            return Some(ord);
+        }
+        let ca = DatatypeHandler::canonical(self, &va).unwrap_or_else(|| a.to_string());
+        let cb = DatatypeHandler::canonical(self, &vb).unwrap_or_else(|| b.to_string());
+        if ca == cb {
+            Some(Ordering::Equal)
+        } else {
+            None
+        }
+    }
+
+    fn parse_any(&self, lex: &str) -> Option<Box<dyn Any>> {
+        self.parse(lex)
+            .ok()
+            .map(|value| Box::new(value) as Box<dyn Any>)
+    }
+}
+
+/// A set of [`DatatypeHandler`](trait.DatatypeHandler.html)s, indexed by datatype IRI.
+///
+/// See [module documentation](index.html).
+///
+/// Cloning a registry is cheap, as handlers are shared.
+#[derive(Clone, Default)]
+pub struct DatatypeRegistry {
+    handlers: HashMap<String, Arc<dyn ErasedHandler>>,
+}
+
+impl DatatypeRegistry {
+    /// Build an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for `datatype`, replacing any previous handler for that datatype.
+    ///
+    /// Handlers can also be registered for XSD datatypes,
+    /// in which case they take precedence over the native support of Sophia.
+    pub fn register<T, H>(&mut self, datatype: &T, handler: H) -> &mut Self
+    where
+        T: TTerm + ?Sized,
+        H: DatatypeHandler,
+    {
+        self.handlers
+            .insert(datatype.value().to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Whether a handler is registered for `datatype`.
+    pub fn handles<T>(&self, datatype: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        self.handlers.contains_key(&datatype.value()[..])
+    }
+
+    /// The number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Whether no handler is registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Check that the lexical form of `term` is valid for its datatype,
+    /// using the registered handler if any,
+    /// and [`check_literal`](../validation/fn.check_literal.html) otherwise.
+    pub fn check_literal<T>(&self, term: &T) -> Result<(), String>
+    where
+        T: TTerm + ?Sized,
+    {
+        match self.handler(term) {
+            Some(handler) => handler.check(&term.value()).map_err(|reason| {
+                format!(
+                    "{:?} is not a valid lexical form for {}: {}",
+                    &term.value()[..],
+                    term_to_string(&term.datatype().unwrap()),
+                    reason
+                )
+            }),
+            None => check_literal(term),
+        }
+    }
+
+    /// A copy of `term` with the canonical lexical form of its value,
+    /// or `None` if its datatype has no registered handler or its lexical form is invalid.
+    pub fn canonicalize<T>(&self, term: &T) -> Option<BoxTerm>
+    where
+        T: TTerm + ?Sized,
+    {
+        let canonical = self.handler(term)?.canonical(&term.value())?;
+        Some(BoxTerm::new_literal_dt_unchecked(
+            canonical,
+            term.datatype()?,
+        ))
+    }
+
+    /// The value of `term`, as parsed by the handler of its datatype,
+    /// or `None` if there is no such handler,
+    /// if the lexical form is invalid,
+    /// or if `V` is not the value type of the handler.
+    pub fn value<V, T>(&self, term: &T) -> Option<V>
+    where
+        V: 'static,
+        T: TTerm + ?Sized,
+    {
+        let value = self.handler(term)?.parse_any(&term.value())?;
+        value.downcast().ok().map(|v| *v)
+    }
+
+    /// Compare the values of two literals.
+    ///
+    /// If both literals have the same datatype and a handler is registered for it,
+    /// their values are compared by the handler;
+    /// otherwise, their values are compared as [`XsdValue`]s.
+    /// Returns `None` if they are not comparable.
+    ///
+    /// [`XsdValue`]: ../term/literal/valued/enum.XsdValue.html
+    pub fn value_cmp<T, U>(&self, a: &T, b: &U) -> Option<Ordering>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        match (self.handler(a), self.handler(b)) {
+            (Some(handler), Some(_)) if term_eq(&a.datatype()?, &b.datatype()?) => {
+                handler.compare(&a.value(), &b.value())
+            }
+            (None, None) => XsdValue::from_term(a)?.value_cmp(&XsdValue::from_term(b)?),
+            _ => None,
+        }
+    }
+
+    /// Whether two terms have the same value.
+    ///
+    /// Returns `Some(true)` if they are the same term or have equal values
+    /// (see [`value_cmp`](#method.value_cmp)),
+    /// `Some(false)` if they are not literals, or have comparable but different values,
+    /// and `None` if their values can not be compared.
+    pub fn value_eq<T, U>(&self, a: &T, b: &U) -> Option<bool>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        if term_eq(a, b) {
+            return Some(true);
+        }
+        if a.kind() != TermKind::Literal || b.kind() != TermKind::Literal {
+            return Some(false);
+        }
+        match self.value_cmp(a, b) {
+            Some(ord) => Some(ord == Ordering::Equal),
+            // distinct values of the same custom datatype, with no order
+            None if self.handler(a).is_some()
+                && term_eq(&a.datatype()?, &b.datatype()?)
+                && self.check_literal(a).is_ok()
+                && self.check_literal(b).is_ok() =>
+            {
+                Some(false)
+            }
+            None => None,
+        }
+    }
+
+    /// The handler registered for the datatype of `term`, if any.
+    fn handler<T>(&self, term: &T) -> Option<&dyn ErasedHandler>
+    where
+        T: TTerm + ?Sized,
+    {
+        if term.kind() != TermKind::Literal || self.handlers.is_empty() {
+            return None;
+        }
+        self.handlers
+            .get(&term.datatype()?.value()[..])
+            .map(|h| h.as_ref())
+    }
+}
+
+impl fmt::Debug for DatatypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut datatypes: Vec<_> = self.handlers.keys().collect();
+        datatypes.sort();
+        f.debug_struct("DatatypeRegistry")
+            .field("datatypes", &datatypes)
+            .finish()
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::xsd;
+
+    /// Temperatures in Celsius or Fahrenheit, e.g. `"20C"` or `"68F"`.
+    struct Temperature;
+
+    impl DatatypeHandler for Temperature {
+        type Value = f64;
+
+        fn parse(&self, lex: &str) -> Result<f64, String> {
+            let (number, unit) = lex.split_at(lex.len().saturating_sub(1));
+            let number: f64 = number.parse().map_err(|_| "invalid number".to_string())?;
+            match unit {
+                "C" => Ok(number),
+                "F" => Ok((number - 32.0) * 5.0 / 9.0),
+                _ => Err("unknown unit".to_string()),
+            }
+        }
+
+        fn canonical(&self, value: &f64) -> Option<String> {
+            Some(format!("{}C", value))
+        }
+
+        fn compare(&self, a: &f64, b: &f64) -> Option<Ordering> {
+            a.partial_cmp(b)
+        }
+    }
+
+    /// Identifiers compared case-insensitively, with no order.
+    struct Code;
+
+    impl DatatypeHandler for Code {
+        type Value = String;
+
+        fn parse(&self, lex: &str) -> Result<String, String> {
+            Ok(lex.to_ascii_uppercase())
+        }
+
+        fn canonical(&self, value: &String) -> Option<String> {
+            Some(value.clone())
+        }
+    }
+
+    fn registry() -> DatatypeRegistry {
+        let mut r = DatatypeRegistry::new();
+        r.register(&dt("temp"), Temperature)
+            .register(&dt("code"), Code);
+        r
+    }
+
+    fn dt(name: &str) -> BoxTerm {
+        BoxTerm::new_iri(format!("tag:{}", name)).unwrap()
+    }
+
+    fn lit(lex: &str, datatype: &str) -> BoxTerm {
+        BoxTerm::new_literal_dt(lex, dt(datatype)).unwrap()
+    }
+
+    #[test]
+    fn check() {
+        let r = registry();
+        assert_eq!(r.len(), 2);
+        assert!(r.handles(&dt("temp")));
+        assert!(!r.handles(&dt("other")));
+        assert!(r.check_literal(&lit("20C", "temp")).is_ok());
+        let err = r.check_literal(&lit("20K", "temp")).unwrap_err();
+        assert!(err.contains("unknown unit"), "{}", err);
+        // unknown datatypes are always valid, XSD datatypes are natively checked
+        assert!(r.check_literal(&lit("whatever", "other")).is_ok());
+        let bad_int = BoxTerm::new_literal_dt("x", xsd::integer).unwrap();
+        assert!(r.check_literal(&bad_int).is_err());
+    }
+
+    #[test]
+    fn values() {
+        let r = registry();
+        assert_eq!(r.value::<f64, _>(&lit("212F", "temp")), Some(100.0));
+        assert_eq!(r.value::<String, _>(&lit("212F", "temp")), None);
+        assert_eq!(r.value::<f64, _>(&lit("x", "temp")), None);
+        assert_eq!(
+            r.canonicalize(&lit("50F", "temp")).unwrap(),
+            lit("10C", "temp")
+        );
+        assert_eq!(r.canonicalize(&lit("50F", "other")), None);
+    }
+
+    #[test]
+    fn comparison() {
+        let r = registry();
+        assert_eq!(
+            r.value_cmp(&lit("20C", "temp"), &lit("50F", "temp")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            r.value_eq(&lit("100C", "temp"), &lit("212F", "temp")),
+            Some(true)
+        );
+        assert_eq!(
+            r.value_eq(&lit("abc", "code"), &lit("ABC", "code")),
+            Some(true)
+        );
+        assert_eq!(
+            r.value_eq(&lit("abc", "code"), &lit("abd", "code")),
+            Some(false)
+        );
+        assert_eq!(r.value_cmp(&lit("abc", "code"), &lit("abd", "code")), None);
+        // different datatypes
+        assert_eq!(r.value_eq(&lit("1C", "temp"), &lit("1C", "code")), None);
+        // XSD values
+        let one = BoxTerm::new_literal_dt("1", xsd::integer).unwrap();
+        let one_dec = BoxTerm::new_literal_dt("1.0", xsd::decimal).unwrap();
+        assert_eq!(r.value_eq(&one, &one_dec), Some(true));
+        assert_eq!(r.value_eq(&dt("temp"), &one), Some(false));
+    }
+}
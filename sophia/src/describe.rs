@@ -0,0 +1,367 @@
+//! Pluggable strategies for describing a resource, as SPARQL `DESCRIBE` does.
+//!
+//! The SPARQL specification leaves the content of the answer to a `DESCRIBE` query
+//! up to the implementation.
+//! The [`Describe`] trait lets servers and query engines choose (or provide) their own behavior;
+//! this module provides the following strategies:
+//! * [`ConciseBounded`], the [Concise Bounded Description] (see [`cbd`](../cbd/index.html));
+//! * [`SymmetricConciseBounded`], its symmetric variant;
+//! * [`ForwardProperties`], only the triples having the resource as their subject;
+//! * [`DepthLimited`], all the triples reachable from the resource in a given number of steps.
+//!
+//! [`Strategy`] wraps all of them, so that the strategy can be chosen at run time
+//! (e.g. from a configuration file).
+//!
+//! All strategies lazily query the graph as the description is consumed,
+//! and yield each triple of the description once.
+//!
+//! # Example
+//! ```
+//! use sophia::describe::{Describe, DepthLimited, ForwardProperties, Strategy};
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::traversal::Direction;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:bob> <tag:knows> <tag:carol>.
+//! "#).collect_triples()?;
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//!
+//! assert_eq!(ForwardProperties.describe(&g, &alice).count(), 1);
+//! let strategy: Strategy = DepthLimited::new(2, Direction::Forward).into();
+//! let description: FastGraph = strategy.describe(&g, &alice).collect_triples()?;
+//! assert_eq!(description.triples().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Describe`]: trait.Describe.html
+//! [`ConciseBounded`]: struct.ConciseBounded.html
+//! [`SymmetricConciseBounded`]: struct.SymmetricConciseBounded.html
+//! [`ForwardProperties`]: struct.ForwardProperties.html
+//! [`DepthLimited`]: struct.DepthLimited.html
+//! [`Strategy`]: enum.Strategy.html
+//! [Concise Bounded Description]: https://www.w3.org/Submission/CBD/
+
+use std::collections::{HashSet, VecDeque};
+
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use crate::cbd::{cbd, symmetric_cbd};
+use crate::graph::Graph;
+use crate::traversal::Direction;
+use crate::triple::Triple;
+
+/// The triples describing a resource, as returned by [`Describe::describe`].
+///
+/// This is an iterator of results, hence a [`TripleSource`].
+///
+/// [`Describe::describe`]: trait.Describe.html#tymethod.describe
+/// [`TripleSource`]: ../triple/stream/trait.TripleSource.html
+pub type Description<'g, G> =
+    Box<dyn Iterator<Item = Result<[BoxTerm; 3], <G as Graph>::Error>> + 'g>;
+
+/// A strategy for describing resources.
+///
+/// See [module documentation](index.html).
+pub trait Describe {
+    /// The description of `resource` in `graph`.
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized;
+
+    /// The description of all `resources` in `graph`.
+    ///
+    /// Triples belonging to the description of several resources are yielded only once.
+    fn describe_all<'g, G, I, T>(&self, graph: &'g G, resources: I) -> Description<'g, G>
+    where
+        G: Graph,
+        I: IntoIterator<Item = T>,
+        T: TTerm,
+    {
+        let descriptions: Vec<_> = resources
+            .into_iter()
+            .map(|r| self.describe(graph, &r))
+            .collect();
+        let mut found = HashSet::new();
+        Box::new(
+            descriptions
+                .into_iter()
+                .flatten()
+                .filter(move |res| match res {
+                    Ok(triple) => found.insert(triple.clone()),
+                    Err(_) => true,
+                }),
+        )
+    }
+}
+
+/// The [Concise Bounded Description](../cbd/index.html) of resources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConciseBounded;
+
+impl Describe for ConciseBounded {
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        Box::new(cbd(graph, resource))
+    }
+}
+
+/// The [Symmetric Concise Bounded Description](../cbd/index.html) of resources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SymmetricConciseBounded;
+
+impl Describe for SymmetricConciseBounded {
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        Box::new(symmetric_cbd(graph, resource))
+    }
+}
+
+/// Describe resources by the triples having them as their subject
+/// (without following blank nodes, unlike [`ConciseBounded`](struct.ConciseBounded.html)).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForwardProperties;
+
+impl Describe for ForwardProperties {
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        DepthLimited::new(1, Direction::Forward).describe(graph, resource)
+    }
+}
+
+/// Describe resources by the triples reachable from them
+/// in at most `depth` steps in the given `direction`,
+/// whatever the kind of the nodes along the way.
+///
+/// A depth of 1 gives the triples having the resource as their subject (when going forward),
+/// as their object (when going backward), or either (when going both ways).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthLimited {
+    /// The maximum number of steps from the described resource
+    pub depth: usize,
+    /// The direction in which triples are followed
+    pub direction: Direction,
+}
+
+impl DepthLimited {
+    /// Build a new depth-limited strategy.
+    pub fn new(depth: usize, direction: Direction) -> Self {
+        DepthLimited { depth, direction }
+    }
+}
+
+impl Describe for DepthLimited {
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let resource = BoxTerm::copy(resource);
+        let mut visited = HashSet::new();
+        visited.insert(resource.clone());
+        let mut queue = VecDeque::new();
+        if self.depth > 0 {
+            queue.push_back((resource, 1));
+        }
+        Box::new(Reachable {
+            graph,
+            strategy: *self,
+            queue,
+            visited,
+            buffer: VecDeque::new(),
+            found: HashSet::new(),
+        })
+    }
+}
+
+/// Any of the strategies provided by this module, chosen at run time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// See [`ConciseBounded`](struct.ConciseBounded.html) (the default)
+    #[default]
+    ConciseBounded,
+    /// See [`SymmetricConciseBounded`](struct.SymmetricConciseBounded.html)
+    SymmetricConciseBounded,
+    /// See [`ForwardProperties`](struct.ForwardProperties.html)
+    ForwardProperties,
+    /// See [`DepthLimited`](struct.DepthLimited.html)
+    DepthLimited(DepthLimited),
+}
+
+impl From<DepthLimited> for Strategy {
+    fn from(other: DepthLimited) -> Self {
+        Strategy::DepthLimited(other)
+    }
+}
+
+impl Describe for Strategy {
+    fn describe<'g, G, T>(&self, graph: &'g G, resource: &T) -> Description<'g, G>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        match self {
+            Strategy::ConciseBounded => ConciseBounded.describe(graph, resource),
+            Strategy::SymmetricConciseBounded => SymmetricConciseBounded.describe(graph, resource),
+            Strategy::ForwardProperties => ForwardProperties.describe(graph, resource),
+            Strategy::DepthLimited(d) => d.describe(graph, resource),
+        }
+    }
+}
+
+/// The description returned by [`DepthLimited`](struct.DepthLimited.html).
+struct Reachable<'g, G> {
+    graph: &'g G,
+    strategy: DepthLimited,
+    /// The nodes remaining to explore, with their distance to the described resource
+    queue: VecDeque<(BoxTerm, usize)>,
+    /// The nodes already queued
+    visited: HashSet<BoxTerm>,
+    /// The triples found, but not yet yielded
+    buffer: VecDeque<[BoxTerm; 3]>,
+    /// The triples already found
+    found: HashSet<[BoxTerm; 3]>,
+}
+
+impl<G: Graph> Reachable<'_, G> {
+    fn explore(&mut self, node: &BoxTerm, distance: usize) -> Result<(), G::Error> {
+        let mut neighbours = vec![];
+        if self.strategy.direction != Direction::Backward {
+            for t in self.graph.triples_with_s(node) {
+                let t = t?;
+                let triple = [node.clone(), BoxTerm::copy(t.p()), BoxTerm::copy(t.o())];
+                neighbours.push(triple[2].clone());
+                self.push(triple);
+            }
+        }
+        if self.strategy.direction != Direction::Forward {
+            for t in self.graph.triples_with_o(node) {
+                let t = t?;
+                let triple = [BoxTerm::copy(t.s()), BoxTerm::copy(t.p()), node.clone()];
+                neighbours.push(triple[0].clone());
+                self.push(triple);
+            }
+        }
+        if distance < self.strategy.depth {
+            for n in neighbours {
+                if self.visited.insert(n.clone()) {
+                    self.queue.push_back((n, distance + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, triple: [BoxTerm; 3]) {
+        if self.found.insert(triple.clone()) {
+            self.buffer.push_back(triple);
+        }
+    }
+}
+
+impl<G: Graph> Iterator for Reachable<'_, G> {
+    type Item = Result<[BoxTerm; 3], G::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(triple) = self.buffer.pop_front() {
+                return Some(Ok(triple));
+            }
+            let (node, distance) = self.queue.pop_front()?;
+            if let Err(err) = self.explore(&node, distance) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const TTL: &str = r#"
+        <tag:a> <tag:p> _:b1, <tag:c>.
+        _:b1 <tag:p> <tag:d>.
+        <tag:c> <tag:p> <tag:e>.
+        <tag:e> <tag:p> <tag:f>.
+        <tag:g> <tag:p> <tag:a>.
+    "#;
+
+    fn describe<D: Describe>(strategy: D, iri: &str) -> usize {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples().unwrap();
+        let resource = BoxTerm::new_iri(iri).unwrap();
+        let description: Vec<_> = strategy
+            .describe(&g, &resource)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        description.len()
+    }
+
+    #[test]
+    fn strategies() {
+        assert_eq!(describe(ConciseBounded, "tag:a"), 3);
+        assert_eq!(describe(SymmetricConciseBounded, "tag:a"), 4);
+        assert_eq!(describe(ForwardProperties, "tag:a"), 2);
+        assert_eq!(describe(Strategy::default(), "tag:a"), 3);
+        assert_eq!(
+            describe(DepthLimited::new(0, Direction::Forward), "tag:a"),
+            0
+        );
+        assert_eq!(
+            describe(DepthLimited::new(2, Direction::Forward), "tag:a"),
+            4
+        );
+        assert_eq!(
+            describe(DepthLimited::new(9, Direction::Forward), "tag:a"),
+            5
+        );
+        assert_eq!(
+            describe(DepthLimited::new(1, Direction::Backward), "tag:e"),
+            1
+        );
+        assert_eq!(
+            describe(
+                Strategy::from(DepthLimited::new(1, Direction::Both)),
+                "tag:c"
+            ),
+            2
+        );
+        // each triple is yielded once, even if it is found from both its subject and object
+        assert_eq!(describe(DepthLimited::new(2, Direction::Both), "tag:c"), 5);
+    }
+
+    #[test]
+    fn describe_all() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        // tag:g <tag:p> tag:a is in both descriptions, but is yielded once
+        let description: Vec<_> = SymmetricConciseBounded
+            .describe_all(
+                &g,
+                vec![BoxTerm::new_iri("tag:a")?, BoxTerm::new_iri("tag:g")?],
+            )
+            .collect::<Result<_, _>>()?;
+        assert_eq!(description.len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+//! Surgical edition of Turtle documents, preserving their original formatting.
+//!
+//! A [`TurtleDocument`] is parsed with the [spanned Turtle parser],
+//! so that it knows which statement of the source produced each triple.
+//! Triples can then be [inserted] or [removed],
+//! and the document [rendered] back to Turtle, touching only the regions affected by the edits:
+//! * a statement whose triples are all removed is deleted
+//!   (together with its line, if it stands alone on it);
+//! * a statement whose triples are partly removed is rewritten,
+//!   reusing the source text of the remaining terms when possible;
+//! * inserted triples are appended at the end of the document,
+//!   using the prefixes declared in it.
+//!
+//! Everything else (other statements, comments, whitespace, directives) is kept byte for byte.
+//! Note however that comments *inside* a rewritten statement are lost.
+//!
+//! # Example
+//! ```
+//! use sophia::edit::TurtleDocument;
+//! use sophia::term::BoxTerm;
+//!
+//! let mut doc = TurtleDocument::parse(r#"
+//!     @prefix : <http://example.org/> .
+//!     :alice :knows :bob, :carol . # friends
+//!     :bob :knows :alice . # mutual
+//! "#)?;
+//! let alice = BoxTerm::new_iri("http://example.org/alice")?;
+//! let knows = BoxTerm::new_iri("http://example.org/knows")?;
+//! let bob = BoxTerm::new_iri("http://example.org/bob")?;
+//! let dan = BoxTerm::new_iri("http://example.org/dan")?;
+//! doc.remove(&alice, &knows, &bob);
+//! doc.insert(&alice, &knows, &dan);
+//! assert_eq!(doc.render(), r#"
+//!     @prefix : <http://example.org/> .
+//!     :alice :knows :carol . # friends
+//!     :bob :knows :alice . # mutual
+//! :alice :knows :dan .
+//! "#);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TurtleDocument`]: struct.TurtleDocument.html
+//! [spanned Turtle parser]: ../parser/turtle/spanned/index.html
+//! [inserted]: struct.TurtleDocument.html#method.insert
+//! [removed]: struct.TurtleDocument.html#method.remove
+//! [rendered]: struct.TurtleDocument.html#method.render
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use sophia_api::parser::ParseError;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::RcTerm;
+
+use crate::parser::turtle::TurtleParser;
+use crate::serializer::turtle::term_to_turtle;
+
+/// The type of triples in a [`TurtleDocument`](struct.TurtleDocument.html).
+pub type EditTriple = [RcTerm; 3];
+
+/// The predicates of a subject, each with its objects.
+type PredicateObjects<'a> = Vec<(&'a RcTerm, Vec<&'a RcTerm>)>;
+
+/// A triple parsed from the source, with the spans of its terms and statement.
+#[derive(Clone, Debug)]
+struct Located {
+    terms: EditTriple,
+    spans: [Range<usize>; 3],
+    statement: Range<usize>,
+}
+
+/// A Turtle document, with pending edits.
+///
+/// See the [module documentation](index.html) for details.
+#[derive(Clone, Debug)]
+pub struct TurtleDocument {
+    txt: String,
+    parser: TurtleParser,
+    located: Vec<Located>,
+    prefixes: Vec<(String, String)>,
+    present: HashSet<EditTriple>,
+    removed: HashSet<EditTriple>,
+    inserted: Vec<EditTriple>,
+}
+
+impl TurtleDocument {
+    /// Parse `txt` as a Turtle document without a base IRI.
+    pub fn parse<S: Into<String>>(txt: S) -> Result<Self, Box<ParseError>> {
+        Self::parse_with(txt, &TurtleParser::default())
+    }
+
+    /// Parse `txt` as a Turtle document with the given `parser` (e.g. to set a base IRI).
+    pub fn parse_with<S: Into<String>>(
+        txt: S,
+        parser: &TurtleParser,
+    ) -> Result<Self, Box<ParseError>> {
+        let txt = txt.into();
+        let mut source = parser.parse_spanned(&txt);
+        let mut located = vec![];
+        for triple in source.by_ref() {
+            let triple = triple?;
+            let spans = triple.spans().clone();
+            let statement = triple.statement_span();
+            let [s, p, o] = triple.into_terms();
+            located.push(Located {
+                terms: [RcTerm::copy(&s), RcTerm::copy(&p), RcTerm::copy(&o)],
+                spans,
+                statement,
+            });
+        }
+        let mut prefixes: Vec<_> = source
+            .prefixes()
+            .iter()
+            .map(|(p, ns)| (p.clone(), ns.clone()))
+            .collect();
+        drop(source);
+        // longest namespaces first, so that the most specific prefix is used
+        prefixes.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+        let present = located.iter().map(|l| l.terms.clone()).collect();
+        Ok(TurtleDocument {
+            txt,
+            parser: parser.clone(),
+            located,
+            prefixes,
+            present,
+            removed: HashSet::new(),
+            inserted: vec![],
+        })
+    }
+
+    /// The source of this document, as parsed (i.e. ignoring pending edits).
+    pub fn as_str(&self) -> &str {
+        &self.txt
+    }
+
+    /// The triples of this document, including pending edits.
+    ///
+    /// Triples stated several times in the source are yielded several times.
+    pub fn triples(&self) -> impl Iterator<Item = &EditTriple> + '_ {
+        self.located
+            .iter()
+            .map(|l| &l.terms)
+            .filter(move |t| !self.removed.contains(*t))
+            .chain(self.inserted.iter())
+    }
+
+    /// Whether this document contains the given triple, including pending edits.
+    pub fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let t = copy_triple(s, p, o);
+        (self.present.contains(&t) && !self.removed.contains(&t)) || self.inserted.contains(&t)
+    }
+
+    /// Insert the given triple, and return whether this changed the document.
+    pub fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let t = copy_triple(s, p, o);
+        if self.present.contains(&t) {
+            self.removed.remove(&t)
+        } else if self.inserted.contains(&t) {
+            false
+        } else {
+            self.inserted.push(t);
+            true
+        }
+    }
+
+    /// Remove the given triple (all its occurrences in the source),
+    /// and return whether this changed the document.
+    pub fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let t = copy_triple(s, p, o);
+        if let Some(i) = self.inserted.iter().position(|i| i == &t) {
+            self.inserted.remove(i);
+            true
+        } else if self.present.contains(&t) {
+            self.removed.insert(t)
+        } else {
+            false
+        }
+    }
+
+    /// Whether this document has pending edits.
+    pub fn is_modified(&self) -> bool {
+        !self.removed.is_empty() || !self.inserted.is_empty()
+    }
+
+    /// Render this document, with its pending edits, as Turtle.
+    ///
+    /// If there are no pending edits, this is the source of the document.
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(self.txt.len());
+        let mut pos = 0;
+        let mut i = 0;
+        while i < self.located.len() {
+            let statement = self.located[i].statement.clone();
+            let mut j = i;
+            while j < self.located.len() && self.located[j].statement == statement {
+                j += 1;
+            }
+            let group = &self.located[i..j];
+            i = j;
+            if group.iter().all(|l| !self.removed.contains(&l.terms)) {
+                continue;
+            }
+            let kept: Vec<&Located> = group
+                .iter()
+                .filter(|l| !self.removed.contains(&l.terms))
+                .collect();
+            if kept.is_empty() {
+                let region = self.line_region(statement);
+                out.push_str(&self.txt[pos..region.start]);
+                pos = region.end;
+            } else {
+                out.push_str(&self.txt[pos..statement.start]);
+                if is_flat(&self.txt, group) {
+                    self.write_flat(&mut out, &kept);
+                } else {
+                    let triples: Vec<_> = kept.iter().map(|l| &l.terms).collect();
+                    self.write_triples(&mut out, &triples, " ");
+                }
+                pos = statement.end;
+            }
+        }
+        out.push_str(&self.txt[pos..]);
+        if !self.inserted.is_empty() {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            let triples: Vec<_> = self.inserted.iter().collect();
+            self.write_triples(&mut out, &triples, "\n");
+        }
+        out
+    }
+
+    /// Apply the pending edits, replacing the source of this document by its [rendering].
+    ///
+    /// [rendering]: #method.render
+    pub fn commit(&mut self) -> Result<(), Box<ParseError>> {
+        if self.is_modified() {
+            *self = Self::parse_with(self.render(), &self.parser)?;
+        }
+        Ok(())
+    }
+
+    /// The region to delete for removing `statement`,
+    /// extended to its whole line if nothing else is on it.
+    fn line_region(&self, statement: Range<usize>) -> Range<usize> {
+        let before = &self.txt[..statement.start];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let after = &self.txt[statement.end..];
+        let line_end = after.find('\n').map(|i| statement.end + i + 1);
+        match line_end {
+            Some(line_end)
+                if is_blank(&before[line_start..])
+                    && is_blank(&self.txt[statement.end..line_end - 1]) =>
+            {
+                line_start..line_end
+            }
+            None if is_blank(&before[line_start..]) && is_blank(after) => {
+                line_start..self.txt.len()
+            }
+            _ => statement,
+        }
+    }
+
+    /// Write the statement `kept`, reusing the source text of its terms.
+    ///
+    /// All triples in `kept` must come from the same [flat](fn.is_flat.html) statement.
+    fn write_flat(&self, out: &mut String, kept: &[&Located]) {
+        let text = |span: &Range<usize>| &self.txt[span.clone()];
+        out.push_str(text(&kept[0].spans[0]));
+        let mut groups: Vec<(&RcTerm, &str, Vec<&str>)> = vec![];
+        for l in kept {
+            let object = text(&l.spans[2]);
+            match groups.iter_mut().find(|g| g.0 == &l.terms[1]) {
+                Some(g) => g.2.push(object),
+                None => groups.push((&l.terms[1], text(&l.spans[1]), vec![object])),
+            }
+        }
+        for (i, (_, predicate, objects)) in groups.iter().enumerate() {
+            out.push_str(if i == 0 { " " } else { " ;\n    " });
+            out.push_str(predicate);
+            out.push(' ');
+            out.push_str(&objects.join(", "));
+        }
+        out.push_str(" .");
+    }
+
+    /// Write `triples` in Turtle, grouped by subject and predicate,
+    /// with `sep` between (and, if `sep` is a newline, after) statements.
+    fn write_triples(&self, out: &mut String, triples: &[&EditTriple], sep: &str) {
+        let mut subjects: Vec<(&RcTerm, PredicateObjects)> = vec![];
+        for [s, p, o] in triples.iter().copied() {
+            let i = match subjects.iter().position(|g| g.0 == s) {
+                Some(i) => i,
+                None => {
+                    subjects.push((s, vec![]));
+                    subjects.len() - 1
+                }
+            };
+            let predicates = &mut subjects[i].1;
+            match predicates.iter_mut().find(|g| g.0 == p) {
+                Some(g) => g.1.push(o),
+                None => predicates.push((p, vec![o])),
+            }
+        }
+        for (i, (s, predicates)) in subjects.iter().enumerate() {
+            if i > 0 {
+                out.push_str(sep);
+            }
+            out.push_str(&term_to_turtle(*s, &self.prefixes, false));
+            for (j, (p, objects)) in predicates.iter().enumerate() {
+                out.push_str(if j == 0 { " " } else { " ;\n    " });
+                out.push_str(&term_to_turtle(*p, &self.prefixes, true));
+                out.push(' ');
+                let objects: Vec<_> = objects
+                    .iter()
+                    .map(|o| term_to_turtle(*o, &self.prefixes, false))
+                    .collect();
+                out.push_str(&objects.join(", "));
+            }
+            out.push_str(" .");
+        }
+        if sep == "\n" && !subjects.is_empty() {
+            out.push('\n');
+        }
+    }
+}
+
+fn copy_triple<TS, TP, TO>(s: &TS, p: &TP, o: &TO) -> EditTriple
+where
+    TS: TTerm + ?Sized,
+    TP: TTerm + ?Sized,
+    TO: TTerm + ?Sized,
+{
+    [RcTerm::copy(s), RcTerm::copy(p), RcTerm::copy(o)]
+}
+
+/// Whether the triples of a statement all come from a plain `subject predicateObjectList`,
+/// without blank node property lists or collections,
+/// so that the source text of each term stands for the term itself.
+fn is_flat(txt: &str, group: &[Located]) -> bool {
+    let subject = &group[0].spans[0];
+    group.iter().all(|l| {
+        &l.spans[0] == subject
+            && l.spans[1] != l.spans[2]
+            && l.spans
+                .iter()
+                .all(|s| !txt[s.clone()].starts_with(['[', '(']))
+    })
+}
+
+fn is_blank(txt: &str) -> bool {
+    txt.trim_start_matches([' ', '\t', '\r']).is_empty()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::t;
+    use sophia_api::ns::rdf;
+    use sophia_term::BoxTerm;
+
+    const TTL: &str = "PREFIX : <tag:>\n\
+                       # first\n\
+                       :a :p :b, :c ; # objects\n  :q :d .\n\
+                       :b :p :c .   \n\
+                       :c :p :a . # trailing\n";
+
+    #[test]
+    fn untouched() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = TurtleDocument::parse(TTL)?;
+        assert_eq!(doc.triples().count(), 5);
+        assert!(!doc.is_modified());
+        assert_eq!(doc.render(), TTL);
+        assert!(doc.remove(&t("a"), &t("p"), &t("b")));
+        assert!(doc.insert(&t("a"), &t("p"), &t("b")));
+        assert!(!doc.insert(&t("a"), &t("p"), &t("b")));
+        assert!(!doc.is_modified());
+        assert!(!doc.remove(&t("x"), &t("p"), &t("b")));
+        assert_eq!(doc.render(), TTL);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_statements() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = TurtleDocument::parse(TTL)?;
+        doc.remove(&t("b"), &t("p"), &t("c"));
+        doc.remove(&t("c"), &t("p"), &t("a"));
+        assert_eq!(
+            doc.render(),
+            "PREFIX : <tag:>\n# first\n:a :p :b, :c ; # objects\n  :q :d .\n # trailing\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn remove_part_of_statement() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = TurtleDocument::parse(TTL)?;
+        doc.remove(&t("a"), &t("p"), &t("b"));
+        assert_eq!(
+            doc.render(),
+            "PREFIX : <tag:>\n# first\n:a :p :c ;\n    :q :d .\n:b :p :c .   \n:c :p :a . # trailing\n"
+        );
+        doc.remove(&t("a"), &t("p"), &t("c"));
+        assert!(doc.render().contains("\n:a :q :d .\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn remove_in_nested_statement() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = "PREFIX : <tag:>\n:a :p [ :q :b ], :c .\n:z :p ( :x ) .\n";
+        let mut doc = TurtleDocument::parse(ttl)?;
+        assert!(doc.remove(&t("a"), &t("p"), &t("c")));
+        let rendered = doc.render();
+        assert!(rendered.ends_with(":z :p ( :x ) .\n"));
+        doc.commit()?;
+        assert_eq!(doc.as_str(), rendered);
+        assert_eq!(doc.triples().count(), 5);
+        assert!(!doc.contains(&t("a"), &t("p"), &t("c")));
+        assert_eq!(doc.triples().filter(|tr| tr[0] == t("z")).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn insert() -> Result<(), Box<dyn std::error::Error>> {
+        let mut doc = TurtleDocument::parse("PREFIX : <tag:>\n:a :p :b .")?;
+        let lit = BoxTerm::new_literal_lang("hé", "en")?;
+        assert!(doc.insert(&t("a"), &rdf::type_, &t("C")));
+        assert!(doc.insert(&t("a"), &t("p"), &lit));
+        assert!(doc.insert(&t("x/y"), &t("p"), &t("a")));
+        assert_eq!(
+            doc.render(),
+            "PREFIX : <tag:>\n:a :p :b .\n:a a :C ;\n    :p \"hé\"@en .\n<tag:x/y> :p :a .\n"
+        );
+        assert!(doc.remove(&t("a"), &rdf::type_, &t("C")));
+        doc.commit()?;
+        assert!(!doc.is_modified());
+        assert_eq!(doc.triples().count(), 3);
+        assert!(doc.contains(&t("a"), &t("p"), &lit));
+        Ok(())
+    }
+}
@@ -0,0 +1,1558 @@
+//! SPARQL-compatible expressions and builtin functions, evaluated over terms.
+//!
+//! An [`Expr`] is a constant term, a variable, or a call to one of the builtin [`Function`]s
+//! of [SPARQL 1.1] (operators, term and string functions, arithmetic, hash functions,
+//! date accessors...).
+//! Expressions are [evaluated](struct.Expr.html#method.eval)
+//! against a [`BindingMap`] (as produced by [`query`]),
+//! and produce either a term or an [`EvalError`],
+//! which corresponds to a *type error* in SPARQL.
+//! They can be used as filters, either on binding maps with [`Expr::holds`],
+//! or in [`triples_matching`] with [`Expr::matcher`].
+//!
+//! Numeric operations follow the type promotion rules of SPARQL
+//! (`xsd:integer`, then `xsd:decimal`, `xsd:float` and `xsd:double`),
+//! with integers and decimals represented exactly, with up to 38 significant digits.
+//! Larger integers and decimals are still numeric (e.g. for `isNUMERIC` and comparisons),
+//! but arithmetic on them raises a type error.
+//! The `+` and `-` operators also add a duration
+//! (`xsd:dayTimeDuration`, `xsd:yearMonthDuration` or `xsd:duration`)
+//! to an `xsd:dateTime` or an `xsd:date`, or subtract it,
+//! as specified by [XPath](https://www.w3.org/TR/xpath-functions/#dateTime-arithmetic)
+//! (see [`add_duration`]).
+//!
+//! Literals of custom datatypes can be compared (with `=`, `<`, `IN`...)
+//! by evaluating expressions with a [`DatatypeRegistry`]
+//! (see [`Expr::eval_with`] and [`Expr::matcher_with`]).
+//!
+//! Functions with side effects or depending on the query execution
+//! (`BNODE`, `RAND`, `NOW`, `UUID`, `STRUUID`) are not supported;
+//! `REPLACE` requires the `regex` feature,
+//! and the hash functions (`MD5`, `SHA1`...) require the `hash` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::expression::{Expr, Function::*};
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::ns::xsd;
+//! use sophia::parser::turtle;
+//! use sophia::term::{matcher::ANY, BoxTerm, TTerm};
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:name> "Alice"; <tag:age> 42.
+//!     <tag:bob> <tag:name> "Bob"; <tag:age> 17.
+//! "#).collect_triples()?;
+//!
+//! // ?o >= 18
+//! let adult = Expr::call(Ge, vec![
+//!     Expr::var("o"),
+//!     Expr::constant(&BoxTerm::new_literal_dt("18", xsd::integer)?),
+//! ]);
+//! let age = BoxTerm::new_iri("tag:age")?;
+//! let adult = adult.matcher("o");
+//! assert_eq!(g.triples_matching(&ANY, &age, &adult).count(), 1);
+//!
+//! // UCASE(SUBSTR("Alice", 1, 3))
+//! let e = Expr::call(UCase, vec![Expr::call(SubStr, vec![
+//!     Expr::constant(&BoxTerm::new_literal_dt("Alice", xsd::string)?),
+//!     Expr::constant(&BoxTerm::new_literal_dt("1", xsd::integer)?),
+//!     Expr::constant(&BoxTerm::new_literal_dt("3", xsd::integer)?),
+//! ])]);
+//! assert_eq!(e.eval(&Default::default())?.value(), "ALI");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SPARQL 1.1]: https://www.w3.org/TR/sparql11-query/#SparqlOps
+//! [`Expr`]: enum.Expr.html
+//! [`Function`]: enum.Function.html
+//! [`BindingMap`]: ../query/type.BindingMap.html
+//! [`query`]: ../query/index.html
+//! [`EvalError`]: enum.EvalError.html
+//! [`Expr::holds`]: enum.Expr.html#method.holds
+//! [`triples_matching`]: ../graph/trait.Graph.html#method.triples_matching
+//! [`Expr::matcher`]: enum.Expr.html#method.matcher
+//! [`add_duration`]: ../term/literal/datetime/fn.add_duration.html
+//! [`DatatypeRegistry`]: ../datatype/struct.DatatypeRegistry.html
+//! [`Expr::eval_with`]: enum.Expr.html#method.eval_with
+//! [`Expr::matcher_with`]: enum.Expr.html#method.matcher_with
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_term::literal::canonical::canonical_numeric;
+use sophia_term::literal::datetime::{add_duration, subtract_duration, DateTime, Duration};
+use sophia_term::literal::matcher::RegexMatcher;
+use sophia_term::RcTerm;
+use thiserror::Error;
+
+use crate::datatype::DatatypeRegistry;
+use crate::query::BindingMap;
+
+mod _decimal;
+#[cfg(feature = "hash")]
+mod _digest;
+use self::_decimal::Decimal;
+
+/// This error is raised when evaluating an expression fails.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum EvalError {
+    /// A variable of the expression is not bound.
+    #[error("Unbound variable ?{0}")]
+    Unbound(String),
+    /// A function was called with a wrong number of arguments.
+    #[error("Wrong number of arguments for {function}: {found}")]
+    Arity { function: Function, found: usize },
+    /// The arguments of a function are not of the expected type.
+    #[error("Type error: {0}")]
+    Type(String),
+    /// No function has the given name.
+    #[error("Unknown function {0}")]
+    UnknownFunction(String),
+}
+
+fn type_error<T>(msg: impl Into<String>) -> Result<T, EvalError> {
+    Err(EvalError::Type(msg.into()))
+}
+
+/// The builtin functions and operators of SPARQL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Function {
+    // functional forms
+    Bound,
+    If,
+    Coalesce,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    NotIn,
+    SameTerm,
+    // functions on RDF terms
+    IsIri,
+    IsBlank,
+    IsLiteral,
+    IsNumeric,
+    Str,
+    Lang,
+    Datatype,
+    Iri,
+    StrDt,
+    StrLang,
+    // functions on strings
+    StrLen,
+    SubStr,
+    UCase,
+    LCase,
+    StrStarts,
+    StrEnds,
+    Contains,
+    StrBefore,
+    StrAfter,
+    EncodeForUri,
+    Concat,
+    LangMatches,
+    Regex,
+    #[cfg(feature = "regex")]
+    Replace,
+    // functions on numerics
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    UnaryMinus,
+    UnaryPlus,
+    Abs,
+    Round,
+    Ceil,
+    Floor,
+    // hash functions
+    #[cfg(feature = "hash")]
+    Md5,
+    #[cfg(feature = "hash")]
+    Sha1,
+    #[cfg(feature = "hash")]
+    Sha256,
+    #[cfg(feature = "hash")]
+    Sha384,
+    #[cfg(feature = "hash")]
+    Sha512,
+    // functions on dates and times
+    Year,
+    Month,
+    Day,
+    Hours,
+    Minutes,
+    Seconds,
+    Timezone,
+    Tz,
+}
+
+/// The functions that can be retrieved by name (i.e. excluding operators).
+const NAMED: &[Function] = {
+    use Function::*;
+    &[
+        Bound,
+        If,
+        Coalesce,
+        SameTerm,
+        IsIri,
+        IsBlank,
+        IsLiteral,
+        IsNumeric,
+        Str,
+        Lang,
+        Datatype,
+        Iri,
+        StrDt,
+        StrLang,
+        StrLen,
+        SubStr,
+        UCase,
+        LCase,
+        StrStarts,
+        StrEnds,
+        Contains,
+        StrBefore,
+        StrAfter,
+        EncodeForUri,
+        Concat,
+        LangMatches,
+        Regex,
+        #[cfg(feature = "regex")]
+        Replace,
+        Abs,
+        Round,
+        Ceil,
+        Floor,
+        #[cfg(feature = "hash")]
+        Md5,
+        #[cfg(feature = "hash")]
+        Sha1,
+        #[cfg(feature = "hash")]
+        Sha256,
+        #[cfg(feature = "hash")]
+        Sha384,
+        #[cfg(feature = "hash")]
+        Sha512,
+        Year,
+        Month,
+        Day,
+        Hours,
+        Minutes,
+        Seconds,
+        Timezone,
+        Tz,
+    ]
+};
+
+impl Function {
+    /// The SPARQL name of this function (or the symbol of this operator).
+    pub fn name(&self) -> &'static str {
+        use Function::*;
+        match self {
+            Bound => "BOUND",
+            If => "IF",
+            Coalesce => "COALESCE",
+            Not => "!",
+            And => "&&",
+            Or => "||",
+            Eq => "=",
+            Ne => "!=",
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            In => "IN",
+            NotIn => "NOT IN",
+            SameTerm => "sameTerm",
+            IsIri => "isIRI",
+            IsBlank => "isBLANK",
+            IsLiteral => "isLITERAL",
+            IsNumeric => "isNUMERIC",
+            Str => "STR",
+            Lang => "LANG",
+            Datatype => "DATATYPE",
+            Iri => "IRI",
+            StrDt => "STRDT",
+            StrLang => "STRLANG",
+            StrLen => "STRLEN",
+            SubStr => "SUBSTR",
+            UCase => "UCASE",
+            LCase => "LCASE",
+            StrStarts => "STRSTARTS",
+            StrEnds => "STRENDS",
+            Contains => "CONTAINS",
+            StrBefore => "STRBEFORE",
+            StrAfter => "STRAFTER",
+            EncodeForUri => "ENCODE_FOR_URI",
+            Concat => "CONCAT",
+            LangMatches => "langMatches",
+            Regex => "REGEX",
+            #[cfg(feature = "regex")]
+            Replace => "REPLACE",
+            Add | UnaryPlus => "+",
+            Subtract | UnaryMinus => "-",
+            Multiply => "*",
+            Divide => "/",
+            Abs => "ABS",
+            Round => "ROUND",
+            Ceil => "CEIL",
+            Floor => "FLOOR",
+            #[cfg(feature = "hash")]
+            Md5 => "MD5",
+            #[cfg(feature = "hash")]
+            Sha1 => "SHA1",
+            #[cfg(feature = "hash")]
+            Sha256 => "SHA256",
+            #[cfg(feature = "hash")]
+            Sha384 => "SHA384",
+            #[cfg(feature = "hash")]
+            Sha512 => "SHA512",
+            Year => "YEAR",
+            Month => "MONTH",
+            Day => "DAY",
+            Hours => "HOURS",
+            Minutes => "MINUTES",
+            Seconds => "SECONDS",
+            Timezone => "TIMEZONE",
+            Tz => "TZ",
+        }
+    }
+
+    /// The minimum and maximum (if any) number of arguments of this function.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        use Function::*;
+        match self {
+            Coalesce | Concat => (0, None),
+            In | NotIn => (1, None),
+            And | Or | Eq | Ne | Lt | Le | Gt | Ge | SameTerm | StrDt | StrLang | StrStarts
+            | StrEnds | Contains | StrBefore | StrAfter | LangMatches | Add | Subtract
+            | Multiply | Divide => (2, Some(2)),
+            Regex | SubStr => (2, Some(3)),
+            If => (3, Some(3)),
+            #[cfg(feature = "regex")]
+            Replace => (3, Some(4)),
+            _ => (1, Some(1)),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Function {
+    type Err = EvalError;
+
+    /// Retrieve a function by its SPARQL name (case-insensitive).
+    ///
+    /// Operators can not be retrieved by name.
+    fn from_str(name: &str) -> Result<Self, EvalError> {
+        NAMED
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| EvalError::UnknownFunction(name.to_string()))
+    }
+}
+
+/// A SPARQL expression.
+///
+/// See the [module documentation](index.html) for details.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// A constant term.
+    Const(RcTerm),
+    /// A variable (without its leading `?`), evaluated to its value in the binding map.
+    Var(String),
+    /// A call to a builtin function.
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    /// An expression evaluating to (a copy of) `t`.
+    pub fn constant<T: TTerm + ?Sized>(t: &T) -> Expr {
+        Expr::Const(RcTerm::copy(t))
+    }
+
+    /// An expression evaluating to the value of variable `name` (without its leading `?`).
+    pub fn var<S: Into<String>>(name: S) -> Expr {
+        Expr::Var(name.into())
+    }
+
+    /// An expression calling `function` with `args`.
+    ///
+    /// The number of arguments is checked on evaluation.
+    pub fn call(function: Function, args: Vec<Expr>) -> Expr {
+        Expr::Call(function, args)
+    }
+
+    /// Evaluate this expression with the given bindings.
+    pub fn eval(&self, bindings: &BindingMap) -> Result<RcTerm, EvalError> {
+        self.eval_in(bindings, None)
+    }
+
+    /// Evaluate this expression with the given bindings,
+    /// comparing literals of custom datatypes with the handlers of `registry`.
+    pub fn eval_with(
+        &self,
+        bindings: &BindingMap,
+        registry: &DatatypeRegistry,
+    ) -> Result<RcTerm, EvalError> {
+        self.eval_in(bindings, Some(registry))
+    }
+
+    /// Evaluate the [effective boolean value] of this expression with the given bindings.
+    ///
+    /// [effective boolean value]: https://www.w3.org/TR/sparql11-query/#ebv
+    pub fn ebv(&self, bindings: &BindingMap) -> Result<bool, EvalError> {
+        self.ebv_in(bindings, None)
+    }
+
+    /// Whether this expression holds as a SPARQL filter with the given bindings,
+    /// i.e. whether its effective boolean value is true (errors count as false).
+    pub fn holds(&self, bindings: &BindingMap) -> bool {
+        self.ebv(bindings).unwrap_or(false)
+    }
+
+    /// Same as [`holds`](#method.holds),
+    /// comparing literals of custom datatypes with the handlers of `registry`.
+    pub fn holds_with(&self, bindings: &BindingMap, registry: &DatatypeRegistry) -> bool {
+        self.ebv_in(bindings, Some(registry)).unwrap_or(false)
+    }
+
+    /// A [term matcher](../term/matcher/trait.TermMatcher.html)
+    /// matching the terms for which this expression holds,
+    /// when bound to variable `var`.
+    pub fn matcher<S: Into<String>>(self, var: S) -> ExprMatcher {
+        ExprMatcher {
+            expr: self,
+            var: var.into(),
+            registry: None,
+        }
+    }
+
+    /// Same as [`matcher`](#method.matcher),
+    /// comparing literals of custom datatypes with the handlers of `registry`.
+    pub fn matcher_with<S: Into<String>>(
+        self,
+        var: S,
+        registry: Arc<DatatypeRegistry>,
+    ) -> ExprMatcher {
+        ExprMatcher {
+            expr: self,
+            var: var.into(),
+            registry: Some(registry),
+        }
+    }
+
+    fn eval_in(
+        &self,
+        bindings: &BindingMap,
+        reg: Option<&DatatypeRegistry>,
+    ) -> Result<RcTerm, EvalError> {
+        match self {
+            Expr::Const(t) => Ok(t.clone()),
+            Expr::Var(name) => bindings
+                .get(name)
+                .cloned()
+                .ok_or_else(|| EvalError::Unbound(name.clone())),
+            Expr::Call(function, args) => eval_call(*function, args, bindings, reg),
+        }
+    }
+
+    fn ebv_in(
+        &self,
+        bindings: &BindingMap,
+        reg: Option<&DatatypeRegistry>,
+    ) -> Result<bool, EvalError> {
+        ebv(&self.eval_in(bindings, reg)?)
+    }
+}
+
+/// A term matcher based on an expression,
+/// as returned by [`Expr::matcher`](enum.Expr.html#method.matcher).
+#[derive(Clone, Debug)]
+pub struct ExprMatcher {
+    expr: Expr,
+    var: String,
+    registry: Option<Arc<DatatypeRegistry>>,
+}
+
+impl TermMatcher for ExprMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        let mut bindings = BindingMap::new();
+        bindings.insert(self.var.clone(), RcTerm::copy(t));
+        match &self.registry {
+            Some(registry) => self.expr.holds_with(&bindings, registry),
+            None => self.expr.holds(&bindings),
+        }
+    }
+}
+
+fn eval_call(
+    function: Function,
+    args: &[Expr],
+    b: &BindingMap,
+    reg: Option<&DatatypeRegistry>,
+) -> Result<RcTerm, EvalError> {
+    use Function::*;
+    let (min, max) = function.arity();
+    if args.len() < min || max.is_some_and(|max| args.len() > max) {
+        return Err(EvalError::Arity {
+            function,
+            found: args.len(),
+        });
+    }
+    // functional forms do not evaluate all their arguments eagerly
+    match function {
+        Bound => match &args[0] {
+            Expr::Var(name) => Ok(boolean(b.contains_key(name))),
+            _ => type_error("BOUND expects a variable"),
+        },
+        If => {
+            if args[0].ebv_in(b, reg)? {
+                args[1].eval_in(b, reg)
+            } else {
+                args[2].eval_in(b, reg)
+            }
+        }
+        Coalesce => args
+            .iter()
+            .find_map(|a| a.eval_in(b, reg).ok())
+            .ok_or_else(|| EvalError::Type("no argument of COALESCE has a value".into())),
+        And | Or => {
+            let decisive = function == Or;
+            let left = args[0].ebv_in(b, reg);
+            if left == Ok(decisive) {
+                return Ok(boolean(decisive));
+            }
+            let right = args[1].ebv_in(b, reg);
+            match (left, right) {
+                (_, Ok(r)) if r == decisive => Ok(boolean(decisive)),
+                (Ok(_), Ok(_)) => Ok(boolean(!decisive)),
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            }
+        }
+        In | NotIn => {
+            let left = args[0].eval_in(b, reg)?;
+            let mut error = None;
+            for arg in &args[1..] {
+                match arg
+                    .eval_in(b, reg)
+                    .and_then(|right| equals(&left, &right, reg))
+                {
+                    Ok(true) => return Ok(boolean(function == In)),
+                    Ok(false) => {}
+                    Err(e) => error = Some(e),
+                }
+            }
+            match error {
+                Some(e) => Err(e),
+                None => Ok(boolean(function == NotIn)),
+            }
+        }
+        _ => {
+            let values = args
+                .iter()
+                .map(|a| a.eval_in(b, reg))
+                .collect::<Result<Vec<_>, _>>()?;
+            apply(function, &values, reg)
+        }
+    }
+}
+
+/// Apply a function evaluating all its arguments.
+fn apply(
+    function: Function,
+    v: &[RcTerm],
+    reg: Option<&DatatypeRegistry>,
+) -> Result<RcTerm, EvalError> {
+    use Function::*;
+    match function {
+        Not => Ok(boolean(!ebv(&v[0])?)),
+        Eq => equals(&v[0], &v[1], reg).map(boolean),
+        Ne => equals(&v[0], &v[1], reg).map(|eq| boolean(!eq)),
+        Lt | Le | Gt | Ge => {
+            let ord = compare(&v[0], &v[1], reg)?;
+            Ok(boolean(match (function, ord) {
+                (_, None) => false,
+                (Lt, Some(o)) => o == Ordering::Less,
+                (Le, Some(o)) => o != Ordering::Greater,
+                (Gt, Some(o)) => o == Ordering::Greater,
+                (_, Some(o)) => o != Ordering::Less,
+            }))
+        }
+        SameTerm => Ok(boolean(term_eq(&v[0], &v[1]))),
+        IsIri => Ok(boolean(v[0].kind() == TermKind::Iri)),
+        IsBlank => Ok(boolean(v[0].kind() == TermKind::BlankNode)),
+        IsLiteral => Ok(boolean(v[0].kind() == TermKind::Literal)),
+        IsNumeric => Ok(boolean(is_numeric_literal(&v[0]))),
+        Str => match v[0].kind() {
+            TermKind::Iri | TermKind::Literal => Ok(simple(&v[0].value())),
+            _ => type_error("STR expects an IRI or a literal"),
+        },
+        Lang => match v[0].kind() {
+            TermKind::Literal => Ok(simple(v[0].language().unwrap_or(""))),
+            _ => type_error("LANG expects a literal"),
+        },
+        Datatype => match v[0].datatype() {
+            Some(dt) => Ok(RcTerm::copy(&dt)),
+            None => type_error("DATATYPE expects a literal"),
+        },
+        Iri => match v[0].kind() {
+            TermKind::Iri => Ok(v[0].clone()),
+            _ => RcTerm::new_iri(simple_arg(&v[0])?)
+                .or_else(|e| type_error(format!("invalid IRI: {}", e))),
+        },
+        StrDt => {
+            let lex = simple_arg(&v[0])?;
+            if v[1].kind() != TermKind::Iri {
+                return type_error("STRDT expects an IRI as datatype");
+            }
+            let dt = v[1].value();
+            let dt = SimpleIri::new(&dt, None).or_else(|e| type_error(e.to_string()))?;
+            Ok(RcTerm::new_literal_dt_unchecked(lex, dt))
+        }
+        StrLang => {
+            let lex = simple_arg(&v[0])?;
+            let tag = simple_arg(&v[1])?;
+            RcTerm::new_literal_lang(lex, tag).or_else(|e| type_error(e.to_string()))
+        }
+        StrLen => Ok(integer(string_arg(&v[0])?.0.chars().count() as i64)),
+        SubStr => {
+            let (txt, lang) = string_arg(&v[0])?;
+            let start = number_arg(&v[1])?.round();
+            let len = v.get(2).map(number_arg).transpose()?.map(f64::round);
+            let sub: String = txt
+                .chars()
+                .enumerate()
+                .filter(|(i, _)| {
+                    let pos = (i + 1) as f64;
+                    pos >= start && len.is_none_or(|len| pos < start + len)
+                })
+                .map(|(_, c)| c)
+                .collect();
+            Ok(string(&sub, lang.as_deref()))
+        }
+        UCase | LCase => {
+            let (txt, lang) = string_arg(&v[0])?;
+            let txt = match function {
+                UCase => txt.to_uppercase(),
+                _ => txt.to_lowercase(),
+            };
+            Ok(string(&txt, lang.as_deref()))
+        }
+        StrStarts | StrEnds | Contains => {
+            let (txt, pat) = compatible_args(&v[0], &v[1])?;
+            Ok(boolean(match function {
+                StrStarts => txt.0.starts_with(&pat),
+                StrEnds => txt.0.ends_with(&pat),
+                _ => txt.0.contains(&pat),
+            }))
+        }
+        StrBefore | StrAfter => {
+            let ((txt, lang), pat) = compatible_args(&v[0], &v[1])?;
+            Ok(match txt.find(&pat) {
+                None => simple(""),
+                Some(i) if function == StrBefore => string(&txt[..i], lang.as_deref()),
+                Some(i) => string(&txt[i + pat.len()..], lang.as_deref()),
+            })
+        }
+        EncodeForUri => {
+            let (txt, _) = string_arg(&v[0])?;
+            Ok(simple(&encode_for_uri(&txt)))
+        }
+        Concat => {
+            let args = v.iter().map(string_arg).collect::<Result<Vec<_>, _>>()?;
+            let txt: String = args.iter().map(|a| a.0.as_str()).collect();
+            let lang = match args.first() {
+                Some((_, Some(lang))) if args.iter().all(|a| a.1.as_ref() == Some(lang)) => {
+                    Some(lang.as_str())
+                }
+                _ => None,
+            };
+            Ok(string(&txt, lang))
+        }
+        LangMatches => {
+            let tag = simple_arg(&v[0])?.to_ascii_lowercase();
+            let range = simple_arg(&v[1])?.to_ascii_lowercase();
+            Ok(boolean(if range == "*" {
+                !tag.is_empty()
+            } else {
+                tag == range || tag.starts_with(&format!("{}-", range))
+            }))
+        }
+        Regex => {
+            let (txt, _) = string_arg(&v[0])?;
+            let pattern = regex_pattern(&simple_arg(&v[1])?, v.get(2))?;
+            let matcher = RegexMatcher::new(&pattern).or_else(|e| type_error(format!("{}", e)))?;
+            Ok(boolean(matcher.matches(&simple(&txt))))
+        }
+        #[cfg(feature = "regex")]
+        Replace => {
+            let (txt, lang) = string_arg(&v[0])?;
+            let pattern = regex_pattern(&simple_arg(&v[1])?, v.get(3))?;
+            let replacement = simple_arg(&v[2])?;
+            let re = regex::Regex::new(&pattern).or_else(|e| type_error(format!("{}", e)))?;
+            if re.is_match("") {
+                return type_error("the pattern of REPLACE must not match the empty string");
+            }
+            let txt = re.replace_all(&txt, replacement.as_str());
+            Ok(string(&txt, lang.as_deref()))
+        }
+        Add | Subtract if is_temporal(&v[0]) || is_temporal(&v[1]) => {
+            let result = match (function, is_duration(&v[0]), is_duration(&v[1])) {
+                (Add, false, true) => add_duration(&v[0], &v[1]),
+                (Add, true, false) => add_duration(&v[1], &v[0]),
+                (Subtract, false, true) => subtract_duration(&v[0], &v[1]),
+                _ => return type_error(format!("can not {} {} and {}", function, v[0], v[1])),
+            };
+            match result {
+                Some(t) => Ok(RcTerm::copy(&t)),
+                None => type_error(format!(
+                    "invalid date arithmetic: {} {} {}",
+                    v[0], function, v[1]
+                )),
+            }
+        }
+        Add | Subtract | Multiply | Divide => {
+            let (a, b) = (numeric_arg(&v[0])?, numeric_arg(&v[1])?);
+            Ok(Numeric::arithmetic(function, a, b)?.to_term())
+        }
+        UnaryPlus => Ok(numeric_arg(&v[0])?.to_term()),
+        UnaryMinus | Abs | Round | Ceil | Floor => {
+            let f: fn(f64) -> f64 = match function {
+                UnaryMinus => |x| -x,
+                Abs => f64::abs,
+                // SPARQL rounds halves towards positive infinity
+                Round => |x| (x + 0.5).floor(),
+                Ceil => f64::ceil,
+                _ => f64::floor,
+            };
+            let overflow = || EvalError::Type("numeric overflow".into());
+            let result = match numeric_arg(&v[0])? {
+                Numeric::Integer(i) => match function {
+                    UnaryMinus => i.checked_neg().map(Numeric::Integer),
+                    Abs => i.checked_abs().map(Numeric::Integer),
+                    _ => Some(Numeric::Integer(i)),
+                }
+                .ok_or_else(overflow)?,
+                Numeric::Decimal(d) => match function {
+                    UnaryMinus => d.checked_neg(),
+                    Abs => d.checked_abs(),
+                    Round => d.checked_round(),
+                    Ceil => d.checked_ceil(),
+                    _ => Some(d.floor()),
+                }
+                .map(Numeric::Decimal)
+                .ok_or_else(overflow)?,
+                Numeric::Float(d) => Numeric::Float(f(d as f64) as f32),
+                Numeric::Double(d) => Numeric::Double(f(d)),
+            };
+            Ok(result.to_term())
+        }
+        #[cfg(feature = "hash")]
+        Md5 | Sha1 | Sha256 | Sha384 | Sha512 => {
+            let txt = simple_arg(&v[0])?;
+            let digest = match function {
+                Md5 => _digest::md5(txt.as_bytes()),
+                Sha1 => _digest::sha(&_digest::SHA1, txt.as_bytes()),
+                Sha256 => _digest::sha(&_digest::SHA256, txt.as_bytes()),
+                Sha384 => _digest::sha(&_digest::SHA384, txt.as_bytes()),
+                _ => _digest::sha(&_digest::SHA512, txt.as_bytes()),
+            };
+            Ok(simple(&_digest::hex(&digest)))
+        }
+        Year | Month | Day | Hours | Minutes | Seconds | Timezone | Tz => {
+            let dt = date_time_arg(&v[0])?;
+            Ok(match function {
+                Year => integer(dt.year()),
+                Month => integer(dt.month() as i64),
+                Day => integer(dt.day() as i64),
+                Hours => integer(dt.hour() as i64),
+                Minutes => integer(dt.minute() as i64),
+                Seconds => {
+                    let nanos = dt.second() as i128 * 1_000_000_000 + dt.nanosecond() as i128;
+                    Numeric::Decimal(Decimal::new(nanos, 9).unwrap()).to_term()
+                }
+                Timezone => match dt.timezone() {
+                    Some(tz) => {
+                        let duration = Duration::from_seconds(tz as i64 * 60).to_string();
+                        RcTerm::new_literal_dt_unchecked(duration, xsd::dayTimeDuration)
+                    }
+                    None => return type_error("TIMEZONE expects a dateTime with a timezone"),
+                },
+                _ => simple(&match dt.timezone() {
+                    None => String::new(),
+                    Some(0) => "Z".to_string(),
+                    Some(tz) => {
+                        let sign = if tz < 0 { '-' } else { '+' };
+                        format!("{}{:02}:{:02}", sign, tz.abs() / 60, tz.abs() % 60)
+                    }
+                }),
+            })
+        }
+        Bound | If | Coalesce | And | Or | In | NotIn => unreachable!(),
+    }
+}
+
+/// The effective boolean value of `t`.
+fn ebv(t: &RcTerm) -> Result<bool, EvalError> {
+    if t.kind() == TermKind::Literal {
+        let dt = t.datatype().unwrap();
+        if xsd::boolean == dt {
+            return Ok(matches!(t.value_raw().0, "true" | "1"));
+        }
+        if let Some(n) = Numeric::from_term(t) {
+            let x = n.as_f64();
+            return Ok(x != 0.0 && !x.is_nan());
+        }
+        if is_numeric_literal(t) {
+            return Ok(true); // beyond the supported precision, hence not zero
+        }
+        if is_numeric_datatype(&dt) {
+            return Ok(false); // invalid lexical form
+        }
+        if xsd::string == dt {
+            return Ok(!t.value_raw().0.is_empty());
+        }
+    }
+    type_error("no effective boolean value")
+}
+
+/// The categories of literals that can be compared.
+#[derive(PartialEq)]
+enum Category {
+    Numeric,
+    String,
+    LangString,
+    Boolean,
+    DateTime,
+    Other,
+}
+
+fn category(t: &RcTerm) -> Category {
+    match t.datatype() {
+        None => Category::Other,
+        Some(_) if is_numeric_literal(t) => Category::Numeric,
+        Some(dt) if xsd::string == dt => Category::String,
+        Some(dt) if rdf::langString == dt => Category::LangString,
+        Some(dt) if xsd::boolean == dt => Category::Boolean,
+        Some(dt) if xsd::dateTime == dt => Category::DateTime,
+        Some(_) => Category::Other,
+    }
+}
+
+/// Whether `t` is a literal whose datatype has a handler in `reg`.
+fn is_custom(t: &RcTerm, reg: Option<&DatatypeRegistry>) -> bool {
+    match (reg, t.datatype()) {
+        (Some(reg), Some(dt)) => reg.handles(&dt),
+        _ => false,
+    }
+}
+
+/// The order of `a` and `b`, or `None` if they are unordered (e.g. with NaN).
+fn compare(
+    a: &RcTerm,
+    b: &RcTerm,
+    reg: Option<&DatatypeRegistry>,
+) -> Result<Option<Ordering>, EvalError> {
+    if is_custom(a, reg) || is_custom(b, reg) {
+        return match reg.unwrap().value_cmp(a, b) {
+            Some(o) => Ok(Some(o)),
+            None => type_error(format!("can not compare {} and {}", a, b)),
+        };
+    }
+    match (category(a), category(b)) {
+        (Category::Numeric, Category::Numeric) => {
+            Ok(match (Numeric::from_term(a), Numeric::from_term(b)) {
+                (Some(a), Some(b)) => match (a.as_decimal(), b.as_decimal()) {
+                    (Some(a), Some(b)) => Some(a.cmp(&b)),
+                    _ => a.as_f64().partial_cmp(&b.as_f64()),
+                },
+                // beyond the supported precision
+                _ => approximate(a).partial_cmp(&approximate(b)),
+            })
+        }
+        (Category::String, Category::String) => Ok(Some(a.value_raw().0.cmp(b.value_raw().0))),
+        (Category::Boolean, Category::Boolean) => {
+            let (a, b) = (ebv(a)?, ebv(b)?);
+            Ok(Some(a.cmp(&b)))
+        }
+        (Category::DateTime, Category::DateTime) => {
+            let (a, b) = (date_time_arg(a)?, date_time_arg(b)?);
+            match a.partial_cmp(&b) {
+                Some(o) => Ok(Some(o)),
+                None => type_error("indeterminate comparison of dateTimes"),
+            }
+        }
+        _ => type_error(format!("can not compare {} and {}", a, b)),
+    }
+}
+
+/// Whether `a` and `b` are equal, in the sense of SPARQL's `=` operator.
+fn equals(a: &RcTerm, b: &RcTerm, reg: Option<&DatatypeRegistry>) -> Result<bool, EvalError> {
+    if is_custom(a, reg) || is_custom(b, reg) {
+        return match reg.unwrap().value_eq(a, b) {
+            Some(eq) => Ok(eq),
+            None => type_error(format!("can not compare {} and {}", a, b)),
+        };
+    }
+    let (ca, cb) = (category(a), category(b));
+    if ca == cb && ca != Category::LangString && ca != Category::Other {
+        return compare(a, b, None).map(|o| o == Some(Ordering::Equal));
+    }
+    if term_eq(a, b) {
+        return Ok(true);
+    }
+    if a.kind() == TermKind::Literal
+        && b.kind() == TermKind::Literal
+        && (ca == Category::Other || cb == Category::Other)
+    {
+        return type_error(format!("can not compare {} and {}", a, b));
+    }
+    Ok(false)
+}
+
+/// The lexical form and language tag (if any) of a string literal.
+fn string_arg(t: &RcTerm) -> Result<(String, Option<String>), EvalError> {
+    match category(t) {
+        Category::String | Category::LangString => Ok((
+            t.value_raw().0.to_string(),
+            t.language().map(str::to_string),
+        )),
+        _ => type_error(format!("{} is not a string literal", t)),
+    }
+}
+
+/// The lexical form of a string literal without language tag.
+fn simple_arg(t: &RcTerm) -> Result<String, EvalError> {
+    match string_arg(t)? {
+        (txt, None) => Ok(txt),
+        _ => type_error(format!("{} is not a simple literal", t)),
+    }
+}
+
+/// The arguments of STRSTARTS and similar functions, checking that they are compatible.
+fn compatible_args(
+    a: &RcTerm,
+    b: &RcTerm,
+) -> Result<((String, Option<String>), String), EvalError> {
+    let a = string_arg(a)?;
+    let (txt, lang) = string_arg(b)?;
+    match (&a.1, &lang) {
+        (_, None) => Ok((a, txt)),
+        (Some(l1), Some(l2)) if l1.eq_ignore_ascii_case(l2) => Ok((a, txt)),
+        _ => type_error("incompatible string arguments"),
+    }
+}
+
+fn numeric_arg(t: &RcTerm) -> Result<Numeric, EvalError> {
+    match Numeric::from_term(t) {
+        Some(n) => Ok(n),
+        None if is_numeric_literal(t) => {
+            type_error(format!("{} exceeds the supported precision (38 digits)", t))
+        }
+        None => type_error(format!("{} is not numeric", t)),
+    }
+}
+
+fn number_arg(t: &RcTerm) -> Result<f64, EvalError> {
+    numeric_arg(t).map(|n| n.as_f64())
+}
+
+fn date_time_arg(t: &RcTerm) -> Result<DateTime, EvalError> {
+    match t.datatype() {
+        Some(dt) if xsd::dateTime == dt => t.value_raw().0.parse().or_else(
+            |e: sophia_term::literal::datetime::TemporalParseError| type_error(e.to_string()),
+        ),
+        _ => type_error(format!("{} is not a dateTime", t)),
+    }
+}
+
+/// Whether `t` is a literal of a temporal or duration datatype,
+/// which `+` and `-` handle differently from numbers.
+fn is_temporal(t: &RcTerm) -> bool {
+    match t.datatype() {
+        Some(dt) => xsd::dateTime == dt || xsd::date == dt || is_duration(t),
+        None => false,
+    }
+}
+
+/// Whether `t` is a literal of `xsd:duration` or one of its subtypes.
+fn is_duration(t: &RcTerm) -> bool {
+    match t.datatype() {
+        Some(dt) => {
+            xsd::duration == dt || xsd::dayTimeDuration == dt || xsd::yearMonthDuration == dt
+        }
+        None => false,
+    }
+}
+
+/// The regular expression for a SPARQL pattern with the given flags.
+fn regex_pattern(pattern: &str, flags: Option<&RcTerm>) -> Result<String, EvalError> {
+    let flags = flags.map(simple_arg).transpose()?.unwrap_or_default();
+    if let Some(c) = flags.chars().find(|c| !"imsx".contains(*c)) {
+        return type_error(format!("unsupported regex flag {:?}", c));
+    }
+    if flags.is_empty() {
+        Ok(pattern.to_string())
+    } else {
+        Ok(format!("(?{}){}", flags, pattern))
+    }
+}
+
+fn encode_for_uri(txt: &str) -> String {
+    let mut out = String::with_capacity(txt.len());
+    for b in txt.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn boolean(b: bool) -> RcTerm {
+    RcTerm::new_literal_dt_unchecked(if b { "true" } else { "false" }, xsd::boolean)
+}
+
+fn integer(i: i64) -> RcTerm {
+    RcTerm::new_literal_dt_unchecked(i.to_string(), xsd::integer)
+}
+
+fn simple(txt: &str) -> RcTerm {
+    RcTerm::new_literal_dt_unchecked(txt, xsd::string)
+}
+
+/// A string literal, with the given language tag if any.
+fn string(txt: &str, lang: Option<&str>) -> RcTerm {
+    match lang {
+        Some(lang) => RcTerm::new_literal_lang_unchecked(txt, lang),
+        None => simple(txt),
+    }
+}
+
+/// The XSD datatypes derived from `xsd:integer`.
+const INTEGER_TYPES: &[SimpleIri<'static>] = &[
+    xsd::integer,
+    xsd::long,
+    xsd::int,
+    xsd::short,
+    xsd::byte,
+    xsd::nonNegativeInteger,
+    xsd::positiveInteger,
+    xsd::nonPositiveInteger,
+    xsd::negativeInteger,
+    xsd::unsignedLong,
+    xsd::unsignedInt,
+    xsd::unsignedShort,
+    xsd::unsignedByte,
+];
+
+fn is_numeric_datatype(dt: &SimpleIri) -> bool {
+    INTEGER_TYPES.iter().any(|t| t == dt)
+        || xsd::decimal == *dt
+        || xsd::float == *dt
+        || xsd::double == *dt
+}
+
+/// Whether `t` is a numeric literal with a valid lexical form
+/// (even if it exceeds the precision supported by [`Numeric`]).
+fn is_numeric_literal(t: &RcTerm) -> bool {
+    match t.datatype() {
+        Some(dt) => is_numeric_datatype(&dt) && canonical_numeric(t.value_raw().0, &dt).is_some(),
+        None => false,
+    }
+}
+
+/// An approximation of the value of the numeric literal `t`.
+fn approximate(t: &RcTerm) -> f64 {
+    let dt = t.datatype().unwrap();
+    let lex = t.value_raw().0;
+    let canonical = canonical_numeric(lex, &dt).unwrap();
+    parse_float(&canonical).unwrap_or(f64::NAN)
+}
+
+/// A numeric value, with the type used for operations in SPARQL.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Numeric {
+    Integer(i128),
+    Decimal(Decimal),
+    Float(f32),
+    Double(f64),
+}
+
+impl Numeric {
+    /// The value of `t`, if it is a valid numeric literal within the supported precision.
+    fn from_term<T: TTerm + ?Sized>(t: &T) -> Option<Numeric> {
+        let dt = t.datatype()?;
+        let lex = t.value_raw().0;
+        let canonical = canonical_numeric(lex, &dt)?;
+        if INTEGER_TYPES.iter().any(|t| t == &dt) {
+            canonical.parse().ok().map(Numeric::Integer)
+        } else if xsd::decimal == dt {
+            Decimal::parse(&canonical).map(Numeric::Decimal)
+        } else if xsd::float == dt {
+            parse_float(lex).map(|f| Numeric::Float(f as f32))
+        } else {
+            parse_float(lex).map(Numeric::Double)
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Numeric::Integer(_) => 0,
+            Numeric::Decimal(_) => 1,
+            Numeric::Float(_) => 2,
+            Numeric::Double(_) => 3,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match *self {
+            Numeric::Integer(i) => i as f64,
+            Numeric::Decimal(d) => d.as_f64(),
+            Numeric::Float(f) => f as f64,
+            Numeric::Double(d) => d,
+        }
+    }
+
+    /// The exact value of this number, if it is an integer or a decimal.
+    fn as_decimal(&self) -> Option<Decimal> {
+        match *self {
+            Numeric::Integer(i) => Some(Decimal::from(i)),
+            Numeric::Decimal(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    fn arithmetic(op: Function, a: Numeric, b: Numeric) -> Result<Numeric, EvalError> {
+        let overflow = || EvalError::Type("numeric overflow".into());
+        if let (Numeric::Integer(a), Numeric::Integer(b)) = (a, b) {
+            match op {
+                Function::Add => {
+                    return a.checked_add(b).map(Numeric::Integer).ok_or_else(overflow)
+                }
+                Function::Subtract => {
+                    return a.checked_sub(b).map(Numeric::Integer).ok_or_else(overflow)
+                }
+                Function::Multiply => {
+                    return a.checked_mul(b).map(Numeric::Integer).ok_or_else(overflow)
+                }
+                _ => {} // integer division produces a decimal
+            }
+        }
+        let rank = a.rank().max(b.rank()).max(1);
+        if let (Some(x), Some(y)) = (a.as_decimal(), b.as_decimal()) {
+            if op == Function::Divide && y == Decimal::from(0) {
+                return type_error("division by zero");
+            }
+            let r = match op {
+                Function::Add => x.checked_add(y),
+                Function::Subtract => x.checked_sub(y),
+                Function::Multiply => x.checked_mul(y),
+                _ => x.checked_div(y),
+            };
+            return r.map(Numeric::Decimal).ok_or_else(overflow);
+        }
+        let (x, y) = (a.as_f64(), b.as_f64());
+        let r = match op {
+            Function::Add => x + y,
+            Function::Subtract => x - y,
+            Function::Multiply => x * y,
+            _ => x / y,
+        };
+        Ok(match rank {
+            2 => Numeric::Float(r as f32),
+            _ => Numeric::Double(r),
+        })
+    }
+
+    fn to_term(self) -> RcTerm {
+        let (lex, dt) = match self {
+            Numeric::Integer(i) => (i.to_string(), xsd::integer),
+            Numeric::Decimal(d) => (d.to_string(), xsd::decimal),
+            Numeric::Float(f) => (float_lex(f as f64, &xsd::float), xsd::float),
+            Numeric::Double(d) => (float_lex(d, &xsd::double), xsd::double),
+        };
+        RcTerm::new_literal_dt_unchecked(lex, dt)
+    }
+}
+
+fn parse_float(lex: &str) -> Option<f64> {
+    match lex.trim() {
+        "INF" | "+INF" => Some(f64::INFINITY),
+        "-INF" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        lex => lex.parse().ok(),
+    }
+}
+
+fn float_lex(x: f64, dt: &SimpleIri) -> String {
+    if x.is_nan() {
+        "NaN".to_string()
+    } else if x.is_infinite() {
+        if x > 0.0 { "INF" } else { "-INF" }.to_string()
+    } else {
+        let lex = format!("{:E}", x);
+        canonical_numeric(&lex, dt).unwrap_or(lex)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::Function::*;
+    use super::*;
+
+    fn lit(lex: &str, dt: SimpleIri) -> Expr {
+        Expr::Const(RcTerm::new_literal_dt_unchecked(lex, dt))
+    }
+
+    fn int(i: i64) -> Expr {
+        Expr::Const(integer(i))
+    }
+
+    fn s(txt: &str) -> Expr {
+        Expr::Const(simple(txt))
+    }
+
+    fn en(txt: &str) -> Expr {
+        Expr::Const(RcTerm::new_literal_lang_unchecked(txt, "en"))
+    }
+
+    fn iri(suffix: &str) -> Expr {
+        Expr::Const(RcTerm::new_iri_suffixed_unchecked("tag:", suffix))
+    }
+
+    fn eval(f: Function, args: Vec<Expr>) -> Result<RcTerm, EvalError> {
+        Expr::call(f, args).eval(&BindingMap::new())
+    }
+
+    /// Evaluate, and return the N-Triples form of the result.
+    fn nt(f: Function, args: Vec<Expr>) -> String {
+        let t = eval(f, args).unwrap();
+        match t.datatype() {
+            Some(dt) if xsd::string == dt => format!("{:?}", t.value_raw().0),
+            Some(dt) if rdf::langString != dt => {
+                format!(
+                    "{}^^{}",
+                    t.value_raw().0,
+                    dt.value().rsplit('#').next().unwrap()
+                )
+            }
+            _ => sophia_api::term::term_to_string(&t),
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!("strlen".parse::<Function>(), Ok(StrLen));
+        assert_eq!("ENCODE_FOR_URI".parse::<Function>(), Ok(EncodeForUri));
+        assert!("+".parse::<Function>().is_err());
+        assert_eq!(Eq.to_string(), "=");
+        let err = eval(StrLen, vec![]).unwrap_err();
+        assert_eq!(err.to_string(), "Wrong number of arguments for STRLEN: 0");
+    }
+
+    #[test]
+    fn terms() {
+        assert_eq!(nt(Str, vec![iri("a")]), "\"tag:a\"");
+        assert_eq!(nt(Lang, vec![en("x")]), "\"en\"");
+        assert_eq!(nt(Lang, vec![s("x")]), "\"\"");
+        assert_eq!(
+            nt(Datatype, vec![int(1)]),
+            "<http://www.w3.org/2001/XMLSchema#integer>"
+        );
+        assert_eq!(nt(Iri, vec![s("tag:b")]), "<tag:b>");
+        assert_eq!(
+            nt(StrDt, vec![s("1"), Expr::constant(&xsd::integer)]),
+            "1^^integer"
+        );
+        assert_eq!(nt(StrLang, vec![s("chat"), s("fr")]), "\"chat\"@fr");
+        assert_eq!(nt(IsIri, vec![iri("a")]), "true^^boolean");
+        assert_eq!(
+            nt(IsNumeric, vec![lit("x", xsd::integer)]),
+            "false^^boolean"
+        );
+        assert_eq!(
+            nt(SameTerm, vec![int(1), lit("01", xsd::integer)]),
+            "false^^boolean"
+        );
+        assert!(eval(Str, vec![Expr::Const(RcTerm::new_bnode_unchecked("b"))]).is_err());
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(nt(StrLen, vec![en("chât")]), "4^^integer");
+        assert_eq!(nt(SubStr, vec![s("foobar"), int(4)]), "\"bar\"");
+        assert_eq!(nt(SubStr, vec![en("foobar"), int(0), int(3)]), "\"fo\"@en");
+        assert_eq!(nt(UCase, vec![en("abc")]), "\"ABC\"@en");
+        assert_eq!(nt(StrStarts, vec![en("foobar"), s("foo")]), "true^^boolean");
+        assert!(eval(StrStarts, vec![s("foobar"), en("foo")]).is_err());
+        assert_eq!(nt(StrBefore, vec![en("abc"), s("c")]), "\"ab\"@en");
+        assert_eq!(nt(StrAfter, vec![s("abc"), s("x")]), "\"\"");
+        assert_eq!(
+            nt(EncodeForUri, vec![s("Los Angeles/é")]),
+            "\"Los%20Angeles%2F%C3%A9\""
+        );
+        assert_eq!(nt(Concat, vec![en("a"), en("b")]), "\"ab\"@en");
+        assert_eq!(nt(Concat, vec![en("a"), s("b")]), "\"ab\"");
+        assert_eq!(nt(LangMatches, vec![s("en-GB"), s("EN")]), "true^^boolean");
+        assert_eq!(nt(LangMatches, vec![s(""), s("*")]), "false^^boolean");
+        assert_eq!(
+            nt(Regex, vec![s("Alice"), s("^ali"), s("i")]),
+            "true^^boolean"
+        );
+        assert!(eval(Regex, vec![s("Alice"), s("^ali"), s("q")]).is_err());
+        #[cfg(feature = "regex")]
+        assert_eq!(
+            nt(Replace, vec![en("abab"), s("(b)"), s("[$1]")]),
+            "\"a[b]a[b]\"@en"
+        );
+    }
+
+    #[test]
+    fn numerics() {
+        assert_eq!(nt(Add, vec![int(1), int(2)]), "3^^integer");
+        assert_eq!(nt(Divide, vec![int(1), int(4)]), "0.25^^decimal");
+        assert!(eval(Divide, vec![int(1), int(0)]).is_err());
+        assert_eq!(
+            nt(Divide, vec![int(1), lit("0", xsd::double)]),
+            "INF^^double"
+        );
+        assert_eq!(
+            nt(Multiply, vec![int(2), lit("1.5", xsd::float)]),
+            "3.0E0^^float"
+        );
+        assert_eq!(
+            nt(Subtract, vec![lit("1.5", xsd::decimal), int(1)]),
+            "0.5^^decimal"
+        );
+        assert_eq!(
+            nt(Add, vec![int(i64::MAX), int(1)]),
+            "9223372036854775808^^integer"
+        );
+        let max = lit(&i128::MAX.to_string(), xsd::integer);
+        assert!(eval(Add, vec![max, int(1)]).is_err());
+        assert_eq!(
+            nt(
+                Add,
+                vec![lit("0.1", xsd::decimal), lit("0.2", xsd::decimal)]
+            ),
+            "0.3^^decimal"
+        );
+        assert_eq!(
+            nt(Divide, vec![int(2), int(3)]),
+            "0.666666666666666666666666^^decimal"
+        );
+        let huge = lit("123456789012345678901234567890123456789012", xsd::integer);
+        assert_eq!(nt(IsNumeric, vec![huge.clone()]), "true^^boolean");
+        assert_eq!(nt(Lt, vec![int(1), huge.clone()]), "true^^boolean");
+        let err = eval(Add, vec![huge, int(1)]).unwrap_err();
+        assert!(err.to_string().contains("precision"), "{}", err);
+        assert!(eval(Add, vec![int(1), s("1")]).is_err());
+        assert_eq!(nt(UnaryMinus, vec![int(3)]), "-3^^integer");
+        assert_eq!(nt(Round, vec![lit("-2.5", xsd::decimal)]), "-2^^decimal");
+        assert_eq!(nt(Round, vec![lit("2.5", xsd::decimal)]), "3^^decimal");
+        assert_eq!(nt(Abs, vec![lit("-1.5", xsd::decimal)]), "1.5^^decimal");
+        assert_eq!(nt(Ceil, vec![lit("1.2", xsd::double)]), "2.0E0^^double");
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(
+            nt(Eq, vec![int(1), lit("1.0", xsd::double)]),
+            "true^^boolean"
+        );
+        assert_eq!(nt(Lt, vec![s("a"), s("b")]), "true^^boolean");
+        assert_eq!(nt(Eq, vec![iri("a"), iri("a")]), "true^^boolean");
+        assert_eq!(nt(Ne, vec![iri("a"), int(1)]), "true^^boolean");
+        assert_eq!(nt(Eq, vec![s("1"), int(1)]), "false^^boolean");
+        assert!(eval(Lt, vec![s("a"), int(1)]).is_err());
+        assert!(eval(Eq, vec![lit("a", xsd::gYear), lit("b", xsd::gYear)]).is_err());
+        let nan = lit("NaN", xsd::double);
+        assert_eq!(nt(Ge, vec![nan.clone(), int(1)]), "false^^boolean");
+        assert_eq!(nt(Ne, vec![nan.clone(), nan]), "true^^boolean");
+        let d1 = lit("2020-01-01T00:00:00Z", xsd::dateTime);
+        let d2 = lit("2020-01-01T02:00:00+01:00", xsd::dateTime);
+        assert_eq!(nt(Lt, vec![d1, d2]), "true^^boolean");
+        assert_eq!(nt(In, vec![int(2), int(1), int(2)]), "true^^boolean");
+        assert_eq!(nt(NotIn, vec![int(2)]), "true^^boolean");
+    }
+
+    /// Lengths in metres or kilometres, e.g. `"1500m"` or `"1.5km"`.
+    struct Length;
+
+    impl crate::datatype::DatatypeHandler for Length {
+        type Value = f64;
+
+        fn parse(&self, lex: &str) -> Result<f64, String> {
+            let (number, factor) = match lex.strip_suffix("km") {
+                Some(number) => (number, 1000.0),
+                None => (lex.strip_suffix('m').ok_or("missing unit")?, 1.0),
+            };
+            number
+                .parse::<f64>()
+                .map(|x| x * factor)
+                .map_err(|e| e.to_string())
+        }
+
+        fn compare(&self, a: &f64, b: &f64) -> Option<Ordering> {
+            a.partial_cmp(b)
+        }
+    }
+
+    #[test]
+    fn custom_datatypes() {
+        let length = SimpleIri::new_unchecked("tag:length", None);
+        let mut registry = DatatypeRegistry::new();
+        registry.register(&length, Length);
+        let eval_with = |f, args| Expr::call(f, args).eval_with(&BindingMap::new(), &registry);
+        let km = lit("1.5km", length);
+        let m = lit("1500m", length);
+
+        // without the registry, custom literals are only equal if they are the same term
+        assert!(eval(Eq, vec![km.clone(), m.clone()]).is_err());
+        assert!(eval(Lt, vec![km.clone(), m.clone()]).is_err());
+
+        assert_eq!(
+            eval_with(Eq, vec![km.clone(), m.clone()]).unwrap(),
+            boolean(true)
+        );
+        assert_eq!(
+            eval_with(Lt, vec![lit("900m", length), km.clone()]).unwrap(),
+            boolean(true)
+        );
+        assert_eq!(
+            eval_with(In, vec![m.clone(), int(1500), km.clone()]).unwrap(),
+            boolean(true)
+        );
+        assert!(eval_with(Eq, vec![km.clone(), int(1500)]).is_err());
+        assert!(eval_with(Lt, vec![lit("far", length), km.clone()]).is_err());
+        // builtin datatypes are not affected
+        assert_eq!(
+            eval_with(Eq, vec![int(1), lit("1.0", xsd::double)]).unwrap(),
+            boolean(true)
+        );
+
+        let close = Expr::call(Le, vec![Expr::var("x"), km]);
+        let matcher = close.matcher_with("x", Arc::new(registry.clone()));
+        assert!(matcher.matches(&RcTerm::new_literal_dt_unchecked("1200m", length)));
+        assert!(!matcher.matches(&RcTerm::new_literal_dt_unchecked("2km", length)));
+    }
+
+    #[test]
+    fn logic() -> Result<(), Box<dyn std::error::Error>> {
+        let error = Expr::var("unbound");
+        assert_eq!(nt(Or, vec![error.clone(), s("x")]), "true^^boolean");
+        assert!(eval(Or, vec![error.clone(), s("")]).is_err());
+        assert_eq!(nt(And, vec![error.clone(), int(0)]), "false^^boolean");
+        assert_eq!(nt(Not, vec![s("")]), "true^^boolean");
+        assert_eq!(nt(If, vec![int(0), error.clone(), int(1)]), "1^^integer");
+        assert_eq!(nt(Coalesce, vec![error.clone(), int(2)]), "2^^integer");
+        assert_eq!(nt(Bound, vec![error.clone()]), "false^^boolean");
+        let mut b = BindingMap::new();
+        b.insert("x".into(), integer(5));
+        let e = Expr::call(Gt, vec![Expr::var("x"), int(3)]);
+        assert!(e.holds(&b));
+        assert!(!e.holds(&BindingMap::new()));
+        assert_eq!(
+            e.eval(&BindingMap::new()),
+            Err(EvalError::Unbound("x".into()))
+        );
+        assert!(!Expr::call(Not, vec![iri("a")]).holds(&b));
+        Ok(())
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn hashes() {
+        assert_eq!(
+            nt(Md5, vec![s("abc")]),
+            "\"900150983cd24fb0d6963f7d28e17f72\""
+        );
+        assert_eq!(
+            nt(Sha1, vec![s("abc")]),
+            "\"a9993e364706816aba3e25717850c26c9cd0d89d\""
+        );
+        assert!(eval(Sha256, vec![en("abc")]).is_err());
+    }
+
+    #[test]
+    fn dates() {
+        let d = || lit("2011-01-10T14:45:13.815-05:00", xsd::dateTime);
+        assert_eq!(nt(Year, vec![d()]), "2011^^integer");
+        assert_eq!(nt(Month, vec![d()]), "1^^integer");
+        assert_eq!(nt(Day, vec![d()]), "10^^integer");
+        assert_eq!(nt(Hours, vec![d()]), "14^^integer");
+        assert_eq!(nt(Minutes, vec![d()]), "45^^integer");
+        assert_eq!(nt(Seconds, vec![d()]), "13.815^^decimal");
+        assert_eq!(nt(Timezone, vec![d()]), "-PT5H^^dayTimeDuration");
+        assert_eq!(nt(Tz, vec![d()]), "\"-05:00\"");
+        let utc = lit("2011-01-10T14:45:13Z", xsd::dateTime);
+        assert_eq!(nt(Tz, vec![utc]), "\"Z\"");
+        let local = lit("2011-01-10T14:45:13", xsd::dateTime);
+        assert!(eval(Timezone, vec![local.clone()]).is_err());
+        assert_eq!(nt(Tz, vec![local]), "\"\"");
+        assert!(eval(Year, vec![s("2011")]).is_err());
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        let dt = || lit("2020-01-31T22:00:00-05:00", xsd::dateTime);
+        let month = || lit("P1M", xsd::yearMonthDuration);
+        let hours = || lit("PT3H", xsd::dayTimeDuration);
+        assert_eq!(
+            nt(Add, vec![dt(), month()]),
+            "2020-02-29T22:00:00-05:00^^dateTime"
+        );
+        assert_eq!(
+            nt(Add, vec![hours(), dt()]),
+            "2020-02-01T01:00:00-05:00^^dateTime"
+        );
+        assert_eq!(
+            nt(Subtract, vec![dt(), month()]),
+            "2019-12-31T22:00:00-05:00^^dateTime"
+        );
+        let date = lit("2020-03-01", xsd::date);
+        assert_eq!(
+            nt(Subtract, vec![date.clone(), hours()]),
+            "2020-02-29^^date"
+        );
+        assert!(eval(Subtract, vec![month(), dt()]).is_err());
+        assert!(eval(Add, vec![dt(), dt()]).is_err());
+        assert!(eval(Add, vec![dt(), int(1)]).is_err());
+        assert!(eval(Add, vec![month(), month()]).is_err());
+        assert!(eval(Add, vec![date, lit("P1Y", xsd::dayTimeDuration)]).is_err());
+    }
+}
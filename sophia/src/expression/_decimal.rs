@@ -0,0 +1,284 @@
+//! Exact decimal numbers, for the arithmetic on `xsd:decimal` (and `xsd:integer`).
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The maximum scale of a [`Decimal`], as `10^MAX_SCALE` must fit in an `i128`.
+const MAX_SCALE: u32 = 38;
+
+/// The number of fractional digits computed (at least) by [`Decimal::checked_div`].
+const DIV_SCALE: u32 = 24;
+
+/// An exact decimal number, `mantissa × 10^-scale`,
+/// with up to 38 significant digits.
+///
+/// Decimals are always normalized (without trailing zeros in the mantissa when `scale > 0`),
+/// so that equal numbers have equal representations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The decimal `mantissa × 10^-scale`, or `None` if `scale` is too large.
+    pub fn new(mantissa: i128, scale: u32) -> Option<Decimal> {
+        let mut d = Decimal { mantissa, scale };
+        while d.scale > 0 && d.mantissa % 10 == 0 {
+            d.mantissa /= 10;
+            d.scale -= 1;
+        }
+        if d.scale > MAX_SCALE {
+            None
+        } else {
+            Some(d)
+        }
+    }
+
+    /// Parse a lexical form of `xsd:decimal` (or `xsd:integer`),
+    /// or return `None` if it is invalid or exceeds the supported precision.
+    pub fn parse(lex: &str) -> Option<Decimal> {
+        let (negative, unsigned) = match lex.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, lex.strip_prefix('+').unwrap_or(lex)),
+        };
+        let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let frac = frac.trim_end_matches('0');
+        if int.is_empty() && frac.is_empty() {
+            return None;
+        }
+        let mut mantissa: i128 = 0;
+        for c in int.chars().chain(frac.chars()) {
+            let digit = c.to_digit(10)? as i128;
+            mantissa = mantissa.checked_mul(10)?.checked_add(digit)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Decimal::new(mantissa, frac.len() as u32)
+    }
+
+    /// An approximation of this decimal.
+    pub fn as_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = align(self, other)?;
+        Decimal::new(a.checked_add(b)?, scale)
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = align(self, other)?;
+        Decimal::new(a.checked_sub(b)?, scale)
+    }
+
+    pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        Decimal::new(
+            self.mantissa.checked_mul(other.mantissa)?,
+            self.scale + other.scale,
+        )
+    }
+
+    /// The quotient of `self` by `other`, truncated after (at least) 24 fractional digits,
+    /// or `None` if `other` is zero or the quotient is too large.
+    pub fn checked_div(self, other: Decimal) -> Option<Decimal> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        let divisor = other.mantissa.unsigned_abs();
+        let mut quotient = self.mantissa.unsigned_abs() / divisor;
+        let mut remainder = self.mantissa.unsigned_abs() % divisor;
+        // the scale of the quotient is self.scale + digits - other.scale
+        let mut digits = 0;
+        while remainder != 0 && self.scale + digits < other.scale + DIV_SCALE {
+            let (q, r) = match (quotient.checked_mul(10), remainder.checked_mul(10)) {
+                (Some(q), Some(r)) => (q + r / divisor, r % divisor),
+                _ => break,
+            };
+            quotient = q;
+            remainder = r;
+            digits += 1;
+        }
+        let mut scale = self.scale + digits;
+        if scale < other.scale {
+            quotient = quotient.checked_mul(10_u128.checked_pow(other.scale - scale)?)?;
+            scale = other.scale;
+        }
+        let quotient = i128::try_from(quotient).ok()?;
+        let negative = (self.mantissa < 0) != (other.mantissa < 0);
+        Decimal::new(
+            if negative { -quotient } else { quotient },
+            scale - other.scale,
+        )
+    }
+
+    pub fn checked_neg(self) -> Option<Decimal> {
+        Decimal::new(self.mantissa.checked_neg()?, self.scale)
+    }
+
+    pub fn checked_abs(self) -> Option<Decimal> {
+        Decimal::new(self.mantissa.checked_abs()?, self.scale)
+    }
+
+    /// The largest integer less than or equal to this decimal.
+    pub fn floor(self) -> Decimal {
+        let unit = 10_i128.pow(self.scale);
+        Decimal {
+            mantissa: self.mantissa.div_euclid(unit),
+            scale: 0,
+        }
+    }
+
+    /// The smallest integer greater than or equal to this decimal.
+    pub fn checked_ceil(self) -> Option<Decimal> {
+        self.checked_neg()?.floor().checked_neg()
+    }
+
+    /// The integer closest to this decimal, rounding halves towards positive infinity.
+    pub fn checked_round(self) -> Option<Decimal> {
+        let half = Decimal {
+            mantissa: 5,
+            scale: 1,
+        };
+        Some(self.checked_add(half)?.floor())
+    }
+}
+
+impl From<i128> for Decimal {
+    fn from(i: i128) -> Decimal {
+        Decimal {
+            mantissa: i,
+            scale: 0,
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let (a_int, a_frac) = split(self);
+        let (b_int, b_frac) = split(other);
+        // fractional parts are less than 10^scale, so they can be scaled up to MAX_SCALE
+        let scale = self.scale.max(other.scale);
+        a_int.cmp(&b_int).then_with(|| {
+            let a_frac = a_frac * 10_i128.pow(scale - self.scale);
+            let b_frac = b_frac * 10_i128.pow(scale - other.scale);
+            a_frac.cmp(&b_frac)
+        })
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        if self.mantissa < 0 {
+            f.write_str("-")?;
+        }
+        f.write_str(int)?;
+        if !frac.is_empty() {
+            write!(f, ".{}", frac)?;
+        }
+        Ok(())
+    }
+}
+
+/// The mantissas of `a` and `b` with a common scale (which is also returned).
+fn align(a: Decimal, b: Decimal) -> Option<(i128, i128, u32)> {
+    let scale = a.scale.max(b.scale);
+    let a_mantissa = a.mantissa.checked_mul(10_i128.pow(scale - a.scale))?;
+    let b_mantissa = b.mantissa.checked_mul(10_i128.pow(scale - b.scale))?;
+    Some((a_mantissa, b_mantissa, scale))
+}
+
+/// The integer part of `d` (rounded towards negative infinity) and the remaining fractional part,
+/// as a mantissa with the scale of `d`.
+fn split(d: &Decimal) -> (i128, i128) {
+    let unit = 10_i128.pow(d.scale);
+    (d.mantissa.div_euclid(unit), d.mantissa.rem_euclid(unit))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn d(lex: &str) -> Decimal {
+        Decimal::parse(lex).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for (lex, expected) in &[
+            ("1", "1"),
+            ("+1.50", "1.5"),
+            ("-0.25", "-0.25"),
+            (".5", "0.5"),
+            ("3.", "3"),
+            ("-0.0", "0"),
+            (
+                "0.00000000000000000000000000000000000001",
+                "0.00000000000000000000000000000000000001",
+            ),
+            (
+                "123456789012345678901234567890",
+                "123456789012345678901234567890",
+            ),
+        ] {
+            assert_eq!(d(lex).to_string(), *expected, "{}", lex);
+        }
+        for lex in &[
+            "",
+            ".",
+            "1e3",
+            "1,5",
+            "--1",
+            "1000000000000000000000000000000000000000",
+        ] {
+            assert!(Decimal::parse(lex).is_none(), "{}", lex);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(d("0.1").checked_add(d("0.2")), Some(d("0.3")));
+        assert_eq!(d("1.5").checked_sub(d("2")), Some(d("-0.5")));
+        assert_eq!(d("-1.5").checked_mul(d("0.2")), Some(d("-0.3")));
+        assert_eq!(d("1").checked_div(d("4")), Some(d("0.25")));
+        assert_eq!(d("10").checked_div(d("0.001")), Some(d("10000")));
+        assert_eq!(
+            d("1").checked_div(d("-3")),
+            Some(d("-0.333333333333333333333333"))
+        );
+        assert_eq!(d("1").checked_div(d("0")), None);
+        let max = Decimal::from(i128::MAX);
+        assert_eq!(max.checked_add(d("1")), None);
+        assert_eq!(max.checked_mul(d("2")), None);
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(d("2.5").checked_round(), Some(d("3")));
+        assert_eq!(d("-2.5").checked_round(), Some(d("-2")));
+        assert_eq!(d("-2.6").checked_round(), Some(d("-3")));
+        assert_eq!(d("-1.2").floor(), d("-2"));
+        assert_eq!(d("1.2").checked_ceil(), Some(d("2")));
+        assert_eq!(d("-1.2").checked_ceil(), Some(d("-1")));
+    }
+
+    #[test]
+    fn order() {
+        assert!(d("0.3") > d("0.29999999999999999999"));
+        assert!(d("-1.5") < d("-1.25"));
+        assert!(d("2") > d("1.99"));
+        assert_eq!(d("1.10").cmp(&d("1.1")), Ordering::Equal);
+    }
+}
@@ -0,0 +1,92 @@
+//! The hash functions of SPARQL (MD5, SHA-1, SHA-256, SHA-384 and SHA-512).
+//!
+//! The SHA functions are provided by [`ring`];
+//! as `ring` does not provide MD5, it is implemented here.
+
+use ring::digest::{digest, Algorithm};
+pub use ring::digest::{SHA1_FOR_LEGACY_USE_ONLY as SHA1, SHA256, SHA384, SHA512};
+
+/// The digest of `data` with the given SHA `algorithm`.
+pub fn sha(algorithm: &'static Algorithm, data: &[u8]) -> Vec<u8> {
+    digest(algorithm, data).as_ref().to_vec()
+}
+
+/// The padded message blocks of `data`, as specified by MD5.
+fn padded(data: &[u8]) -> Vec<u8> {
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    let bits = (data.len() as u64).wrapping_mul(8);
+    msg.extend_from_slice(&bits.to_le_bytes());
+    msg
+}
+
+pub fn md5(data: &[u8]) -> Vec<u8> {
+    const S: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let k: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+    let mut h: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in padded(data).chunks(64) {
+        let m: Vec<u32> = chunk
+            .chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = h;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(k[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(S[i]));
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d].iter()) {
+            *h = h.wrapping_add(*v);
+        }
+    }
+    h.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect()
+}
+
+/// The lowercase hexadecimal representation of `bytes`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn digests() {
+        let abc = b"abc";
+        assert_eq!(hex(&md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(&md5(abc)), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hex(&sha(&SHA1, abc)),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(&sha(&SHA384, abc)),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+             8086072ba1e7cc2358baeca134c825a7"
+        );
+        // messages longer than one block
+        let digits = "1234567890".repeat(8);
+        assert_eq!(
+            hex(&md5(digits.as_bytes())),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+}
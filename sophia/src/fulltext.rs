@@ -0,0 +1,370 @@
+//! Full-text search over the literals of a graph.
+//!
+//! A [`TextIndex`] tokenizes the literals of the triples it is fed with
+//! (optionally restricted to some predicates, such as `rdfs:label` and `rdfs:comment`),
+//! and maintains an inverted index of their words.
+//! It can then
+//! * [`search`] for the subjects whose literals best match a keyword query,
+//!   ranked by relevance (according to the [BM25] scoring function), or
+//! * provide a [`matcher`] for the literals matching a query,
+//!   usable in any triple pattern (indexed graphs will look up each matching literal).
+//!
+//! Queries are lists of words; a word ending with `*` matches any word with that prefix.
+//! Words are compared case-insensitively, and split on any non-alphanumeric character.
+//!
+//! The index is not updated when the graph changes;
+//! it must be rebuilt (or fed with the new triples) by the caller.
+//!
+//! This is a small in-memory index, not a search engine:
+//! there is no stemming, stop words, phrase query or persistence.
+//! Large corpora are better served by a dedicated engine (e.g. [tantivy]),
+//! fed with the literals of the graph.
+//!
+//! This module requires the `fulltext` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::fulltext::TextIndex;
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::term::{matcher::ANY, TTerm};
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+//!     <tag:rust> rdfs:label "Rust"; rdfs:comment "A systems programming language".
+//!     <tag:python> rdfs:label "Python"; rdfs:comment "A programming language".
+//!     <tag:snake> rdfs:label "Python (snake)".
+//! "#).collect_triples()?;
+//!
+//! let mut index = TextIndex::new();
+//! index.index_triples(g.triples())?;
+//! let hits = index.search("systems program*", 10);
+//! assert_eq!(hits[0].subject.value(), "tag:rust");
+//! assert_eq!(hits.len(), 2);
+//!
+//! let matcher = index.matcher("python");
+//! assert_eq!(g.triples_matching(&ANY, &ANY, &matcher).count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TextIndex`]: struct.TextIndex.html
+//! [`search`]: struct.TextIndex.html#method.search
+//! [`matcher`]: struct.TextIndex.html#method.matcher
+//! [BM25]: https://en.wikipedia.org/wiki/Okapi_BM25
+//! [tantivy]: https://crates.io/crates/tantivy
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// An inverted index of the literals of a set of triples.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct TextIndex {
+    predicates: Option<Vec<BoxTerm>>,
+    /// The distinct literals, with their length in words.
+    literals: Vec<(BoxTerm, u32)>,
+    literal_ids: HashMap<BoxTerm, u32>,
+    /// The subjects having each literal.
+    subjects: Vec<Vec<BoxTerm>>,
+    /// For each word, the literals containing it, with the number of occurrences.
+    postings: BTreeMap<String, Vec<(u32, u32)>>,
+    total_len: u64,
+}
+
+/// A subject found by [`TextIndex::search`](struct.TextIndex.html#method.search).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    pub subject: BoxTerm,
+    /// The relevance score of the subject (higher is better).
+    pub score: f64,
+    /// The best matching literal of the subject.
+    pub literal: BoxTerm,
+}
+
+impl TextIndex {
+    /// Build an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The predicates whose literals are indexed (all predicates if `None`).
+    pub fn predicates(&self) -> Option<&[BoxTerm]> {
+        self.predicates.as_deref()
+    }
+
+    /// Index only the literals of the given predicates (all predicates if `None`).
+    ///
+    /// This only affects the triples indexed afterwards.
+    pub fn set_predicates(&mut self, predicates: Option<Vec<BoxTerm>>) -> &mut Self {
+        self.predicates = predicates;
+        self
+    }
+
+    /// The number of distinct literals in this index.
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    /// Whether this index contains no literal.
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    /// Index the literals of `source`, and return the number of indexed triples.
+    pub fn index_triples<TS: TripleSource>(&mut self, mut source: TS) -> Result<usize, TS::Error> {
+        let mut count = 0;
+        source.for_each_triple(|t| {
+            if self.index(t.s(), t.p(), t.o()) {
+                count += 1;
+            }
+        })?;
+        Ok(count)
+    }
+
+    /// Index the triple (`s`, `p`, `o`), and return whether it was indexed.
+    fn index<T: TTerm + ?Sized>(&mut self, s: &T, p: &T, o: &T) -> bool {
+        if o.kind() != TermKind::Literal {
+            return false;
+        }
+        if let Some(predicates) = &self.predicates {
+            if !predicates.iter().any(|pred| term_eq(pred, p)) {
+                return false;
+            }
+        }
+        let literal = BoxTerm::copy(o);
+        let id = match self.literal_ids.get(&literal) {
+            Some(id) => *id,
+            None => {
+                let id = self.literals.len() as u32;
+                let mut counts: HashMap<String, u32> = HashMap::new();
+                let mut len = 0;
+                for word in tokenize(&literal.value()) {
+                    *counts.entry(word).or_default() += 1;
+                    len += 1;
+                }
+                for (word, count) in counts {
+                    self.postings.entry(word).or_default().push((id, count));
+                }
+                self.total_len += u64::from(len);
+                self.literals.push((literal.clone(), len));
+                self.literal_ids.insert(literal, id);
+                self.subjects.push(vec![]);
+                id
+            }
+        };
+        let subjects = &mut self.subjects[id as usize];
+        if !subjects.iter().any(|subj| term_eq(subj, s)) {
+            subjects.push(BoxTerm::copy(s));
+        }
+        true
+    }
+
+    /// The subjects whose literals match `query`, ranked by decreasing relevance,
+    /// at most `limit` of them.
+    ///
+    /// The score of a subject is the score of its best matching literal.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit> {
+        let mut best: HashMap<&BoxTerm, (f64, u32)> = HashMap::new();
+        for (id, score) in self.score(query) {
+            for subject in &self.subjects[id as usize] {
+                let entry = best.entry(subject).or_insert((score, id));
+                if score > entry.0 {
+                    *entry = (score, id);
+                }
+            }
+        }
+        let mut hits: Vec<Hit> = best
+            .into_iter()
+            .map(|(subject, (score, id))| Hit {
+                subject: subject.clone(),
+                score,
+                literal: self.literals[id as usize].0.clone(),
+            })
+            .collect();
+        hits.sort_by(|h1, h2| {
+            h2.score
+                .total_cmp(&h1.score)
+                .then_with(|| h1.subject.value().cmp(&h2.subject.value()))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    /// A matcher for the literals matching `query` (i.e. containing at least one of its words).
+    pub fn matcher(&self, query: &str) -> TextMatcher<'_> {
+        TextMatcher {
+            index: self,
+            literals: self.score(query).into_keys().collect(),
+        }
+    }
+
+    /// The BM25 score of each literal matching `query`.
+    fn score(&self, query: &str) -> HashMap<u32, f64> {
+        let n = self.literals.len() as f64;
+        let avg_len = self.total_len as f64 / n.max(1.0);
+        let mut scores = HashMap::new();
+        for word in query.split_whitespace() {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(w) => (w, true),
+                None => (word, false),
+            };
+            for word in tokenize(word) {
+                let postings: Vec<&Vec<(u32, u32)>> = if prefix {
+                    self.postings
+                        .range::<str, _>((Bound::Included(word.as_str()), Bound::Unbounded))
+                        .take_while(|(w, _)| w.starts_with(&word))
+                        .map(|(_, p)| p)
+                        .collect()
+                } else {
+                    self.postings.get(&word).into_iter().collect()
+                };
+                for postings in postings {
+                    let df = postings.len() as f64;
+                    let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                    for (id, tf) in postings {
+                        let tf = f64::from(*tf);
+                        let len = f64::from(self.literals[*id as usize].1);
+                        let score =
+                            idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg_len));
+                        *scores.entry(*id).or_insert(0.0) += score;
+                    }
+                }
+            }
+        }
+        scores
+    }
+}
+
+/// Matches the literals of a [`TextIndex`](struct.TextIndex.html) matching a query.
+///
+/// See [`TextIndex::matcher`](struct.TextIndex.html#method.matcher).
+pub struct TextMatcher<'a> {
+    index: &'a TextIndex,
+    literals: HashSet<u32>,
+}
+
+impl TermMatcher for TextMatcher<'_> {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn candidates(&self) -> Option<Vec<&dyn TTerm>> {
+        Some(
+            self.literals
+                .iter()
+                .map(|id| self.index.literals[*id as usize].0.as_dyn())
+                .collect(),
+        )
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        t.kind() == TermKind::Literal
+            && self
+                .index
+                .literal_ids
+                .get(&BoxTerm::copy(t))
+                .is_some_and(|id| self.literals.contains(id))
+    }
+}
+
+/// Split `txt` into lowercase alphanumeric words.
+fn tokenize(txt: &str) -> impl Iterator<Item = String> + '_ {
+    txt.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use sophia_api::graph::Graph;
+    use sophia_api::ns::rdfs;
+    use sophia_api::term::matcher::ANY;
+
+    const DATA: &str = r#"
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+        <tag:a> rdfs:label "Red apple"; rdfs:comment "An apple, red like a red rose.".
+        <tag:b> rdfs:label "Green apple"; <tag:note> "Red? No, green.".
+        <tag:c> rdfs:label "Rose"@en, "Rosa"@es.
+        <tag:d> <tag:size> 42; rdfs:seeAlso <tag:a>.
+    "#;
+
+    fn graph() -> FastGraph {
+        turtle::parse_str(DATA).collect_triples().unwrap()
+    }
+
+    #[test]
+    fn search() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let mut index = TextIndex::new();
+        assert_eq!(index.index_triples(g.triples())?, 7);
+        let hits = index.search("red", 10);
+        let subjects: Vec<_> = hits.iter().map(|h| h.subject.clone()).collect();
+        assert_eq!(subjects, vec![t("a"), t("b")]);
+        assert!(hits[0].score > hits[1].score);
+        assert_eq!(hits[0].literal.value(), "Red apple");
+
+        assert_eq!(index.search("RED", 1).len(), 1);
+        assert_eq!(index.search("ros*", 10)[0].subject, t("c"));
+        assert_eq!(index.search("rose apple", 10).len(), 3);
+        assert!(index.search("banana", 10).is_empty());
+        assert_eq!(index.search("42", 10)[0].subject, t("d"));
+        Ok(())
+    }
+
+    #[test]
+    fn predicates() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let mut index = TextIndex::new();
+        index.set_predicates(Some(vec![BoxTerm::copy(&rdfs::label)]));
+        assert_eq!(index.index_triples(g.triples())?, 4);
+        assert_eq!(index.len(), 4);
+        let hits = index.search("red", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].literal.value(), "Red apple");
+        Ok(())
+    }
+
+    #[test]
+    fn matcher() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let mut index = TextIndex::new();
+        index.index_triples(g.triples())?;
+        let m = index.matcher("apple");
+        assert_eq!(m.candidates().unwrap().len(), 3);
+        let subjects: HashSet<BoxTerm> = g
+            .triples_matching(&ANY, &ANY, &m)
+            .map(|t| BoxTerm::copy(t.unwrap().s()))
+            .collect();
+        assert_eq!(subjects, vec![t("a"), t("b")].into_iter().collect());
+        assert!(!m.matches(&t("a")));
+        assert_eq!(
+            g.triples_matching(&ANY, &rdfs::label, &index.matcher("rosa"))
+                .count(),
+            1
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+//! A [`Graph`] storing the text of all its terms in an arena, for bulk ingest.
+//!
+//! [`ArenaGraph`] copies the text of the terms it receives into large chunks of memory,
+//! which are only released when the graph is dropped.
+//! Inserting a triple is therefore little more than a few memory copies,
+//! and dropping the graph frees a handful of chunks,
+//! instead of one allocation per term.
+//!
+//! This is useful for ETL jobs that load a lot of data, transform it, serialize it,
+//! and drop it. On the other hand, [`ArenaGraph`] is not indexed,
+//! does not detect duplicate triples,
+//! and never reclaims the memory of removed triples;
+//! long-lived graphs should use one of the [`inmem`](../inmem/index.html) graphs instead.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{arena::ArenaGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: ArenaGraph = turtle::parse_str("<tag:a> <tag:b> <tag:c>, <tag:d>.").collect_triples()?;
+//! assert_eq!(g.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`ArenaGraph`]: struct.ArenaGraph.html
+
+use std::convert::Infallible;
+
+use sophia_api::graph::{CollectibleGraph, GTripleSource, Graph, MGResult, MutableGraph};
+use sophia_api::term::{term_eq, TTerm};
+use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::ScopedTripleMode;
+use sophia_api::triple::Triple;
+use sophia_term::{RefTerm, StaticTerm};
+
+/// The default size (in bytes) of the chunks allocated by an [`ArenaGraph`](struct.ArenaGraph.html).
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A triple borrowing its terms from an [`ArenaGraph`](struct.ArenaGraph.html).
+pub type ArenaTriple<'a> = [RefTerm<'a>; 3];
+sophia_api::make_scoped_triple_streaming_mode!(ScopedArenaTriple, ArenaTriple);
+
+/// A graph storing the text of its terms in an arena.
+///
+/// See [module documentation](index.html).
+#[derive(Debug)]
+pub struct ArenaGraph {
+    arena: Arena,
+    triples: Vec<[StaticTerm; 3]>,
+}
+
+// Implementation note:
+//
+// The terms in `triples` pretend to be static,
+// while they actually borrow their text from `arena`.
+// This is sound because:
+// - the chunks of the arena are never reallocated nor released before the graph is dropped,
+// - the terms are only exposed with the lifetime of the graph (see `ArenaTriple`).
+
+impl ArenaGraph {
+    /// Construct an empty graph, allocating chunks of [`DEFAULT_CHUNK_SIZE`](constant.DEFAULT_CHUNK_SIZE.html) bytes.
+    pub fn new() -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Construct an empty graph, allocating chunks of `chunk_size` bytes.
+    ///
+    /// Terms longer than `chunk_size` get a chunk of their own.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        ArenaGraph {
+            arena: Arena {
+                chunks: vec![],
+                chunk_size: chunk_size.max(1),
+            },
+            triples: vec![],
+        }
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// The number of bytes allocated by the arena of this graph.
+    pub fn allocated(&self) -> usize {
+        self.arena.chunks.iter().map(String::capacity).sum()
+    }
+
+    /// Remove all triples from this graph, and release its arena.
+    pub fn clear(&mut self) {
+        // terms must be dropped before the text they borrow
+        self.triples.clear();
+        self.arena.chunks.clear();
+    }
+
+    fn store<T: TTerm + ?Sized>(&mut self, t: &T) -> StaticTerm {
+        let arena = &mut self.arena;
+        RefTerm::from(t).clone_map(|txt| arena.alloc(txt))
+    }
+}
+
+impl Default for ArenaGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Graph for ArenaGraph {
+    type Triple = ScopedArenaTriple;
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        Box::new(
+            self.triples
+                .iter()
+                .map(|[s, p, o]| Ok(ScopedArenaTriple::scoped([*s, *p, *o]))),
+        )
+    }
+}
+
+impl CollectibleGraph for ArenaGraph {
+    fn from_triple_source<TS: TripleSource>(
+        mut triples: TS,
+    ) -> StreamResult<Self, TS::Error, Infallible> {
+        let (tmin, tmax) = triples.size_hint_triples();
+        let mut g = ArenaGraph::new();
+        g.triples.reserve(tmax.unwrap_or(tmin));
+        triples.try_for_each_triple(|t| -> Result<(), Infallible> {
+            let triple = [g.store(t.s()), g.store(t.p()), g.store(t.o())];
+            g.triples.push(triple);
+            Ok(())
+        })?;
+        Ok(g)
+    }
+}
+
+impl MutableGraph for ArenaGraph {
+    type MutationError = Infallible;
+
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [self.store(s), self.store(p), self.store(o)];
+        self.triples.push(triple);
+        Ok(true)
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let i = self
+            .triples
+            .iter()
+            .position(|t| term_eq(s, t.s()) && term_eq(p, t.p()) && term_eq(o, t.o()));
+        if let Some(i) = i {
+            self.triples.swap_remove(i);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// Append-only storage for strings.
+#[derive(Debug)]
+struct Arena {
+    /// Each chunk is allocated with a fixed capacity, and never grows beyond it,
+    /// so that its content never moves.
+    chunks: Vec<String>,
+    chunk_size: usize,
+}
+
+impl Arena {
+    /// Copy `txt` into the arena.
+    ///
+    /// The returned reference is only valid as long as the arena is not dropped nor cleared.
+    fn alloc(&mut self, txt: &str) -> &'static str {
+        let fits = self
+            .chunks
+            .last()
+            .is_some_and(|chunk| chunk.capacity() - chunk.len() >= txt.len());
+        if !fits {
+            self.chunks
+                .push(String::with_capacity(self.chunk_size.max(txt.len())));
+        }
+        let chunk = self.chunks.last_mut().unwrap();
+        let start = chunk.len();
+        chunk.push_str(txt);
+        let stored: *const str = &chunk[start..];
+        // SAFETY: the chunk never reallocates (see above),
+        // and the caller ensures that the reference does not outlive it
+        unsafe { &*stored }
+    }
+}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_arena, ArenaGraph, false);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::{rdf, xsd};
+
+    #[test]
+    fn chunks() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = ArenaGraph::with_chunk_size(16);
+        let long = StaticTerm::new_iri("http://example.org/a-rather-long-iri")?;
+        let lit = StaticTerm::new_literal_dt("42", xsd::integer)?;
+        g.insert(&long, &rdf::value, &lit)?;
+        g.insert(&long, &rdf::value, &lit)?;
+        assert_eq!(g.len(), 2);
+        assert!(g.allocated() >= 2 * (long.value().len() + lit.value().len()));
+        for t in g.triples() {
+            let t = t?;
+            assert!(term_eq(t.s(), &long));
+            assert!(term_eq(t.p(), &rdf::value));
+            assert!(term_eq(t.o(), &lit));
+        }
+        assert!(g.remove(&long, &rdf::value, &lit)?);
+        assert_eq!(g.len(), 1);
+        g.clear();
+        assert!(g.is_empty());
+        assert_eq!(g.allocated(), 0);
+        Ok(())
+    }
+}
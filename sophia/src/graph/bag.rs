@@ -0,0 +1,269 @@
+//! A [`Graph`] with bag semantics, counting how many times each triple was inserted.
+//!
+//! Most graphs are sets of triples (see [`SetGraph`]):
+//! inserting a triple they already contain has no effect.
+//! [`BagGraph`], on the other hand, keeps track of the *multiplicity* of each triple:
+//! inserting a triple increments its multiplicity, removing it decrements it,
+//! and [`triples`] yields each triple as many times as its multiplicity.
+//!
+//! This is useful, e.g., to count how many sources assert a given triple.
+//! [`counts`] and [`distinct_triples`] convert a [`BagGraph`] back to set semantics,
+//! while [`from_graph`] builds one from any other graph.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{bag::BagGraph, inmem::FastGraph, Graph, MutableGraph};
+//! use sophia::ns::{rdf, rdfs};
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let mut g = BagGraph::new();
+//! g.insert_all(turtle::parse_str("<tag:a> a <tag:C>, <tag:D>."))?;
+//! g.insert_all(turtle::parse_str("<tag:a> a <tag:C>."))?;
+//! g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+//! assert_eq!(g.len(), 4);
+//! assert_eq!(g.distinct_len(), 3);
+//! assert_eq!(g.multiplicity(&rdfs::Class, &rdf::type_, &rdfs::Class), 1);
+//!
+//! let set: FastGraph = g.distinct_triples().collect_triples()?;
+//! assert_eq!(set.triples().count(), 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`SetGraph`]: ../trait.SetGraph.html
+//! [`BagGraph`]: struct.BagGraph.html
+//! [`triples`]: ../trait.Graph.html#tymethod.triples
+//! [`counts`]: struct.BagGraph.html#method.counts
+//! [`distinct_triples`]: struct.BagGraph.html#method.distinct_triples
+//! [`from_graph`]: struct.BagGraph.html#method.from_graph
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::convert::Infallible;
+use std::iter::repeat_n;
+
+use sophia_api::graph::{CollectibleGraph, GResult, GTripleSource, Graph, MGResult, MutableGraph};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::stream::{AsTripleSource, StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::{ByRef, StreamedTriple};
+use sophia_term::ArcTerm;
+
+/// A graph where each triple has a multiplicity.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct BagGraph {
+    counts: HashMap<[ArcTerm; 3], usize>,
+    len: usize,
+}
+
+impl BagGraph {
+    /// Construct an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a graph containing the triples of `g`, with their multiplicity in `g`.
+    ///
+    /// Note that if `g` is a [`SetGraph`](../trait.SetGraph.html),
+    /// all multiplicities will be 1.
+    pub fn from_graph<G: Graph>(g: &G) -> Result<Self, G::Error> {
+        let mut bag = Self::new();
+        bag.insert_all(g.triples())
+            .map_err(|err| err.unwrap_source_error())?;
+        Ok(bag)
+    }
+
+    /// The number of triples in this graph, counting duplicates.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of distinct triples in this graph.
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of times the given triple occurs in this graph (possibly 0).
+    pub fn multiplicity<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> usize
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        self.counts.get(&triple).copied().unwrap_or(0)
+    }
+
+    /// An iterator over the distinct triples of this graph, with their multiplicity.
+    pub fn counts(&self) -> impl Iterator<Item = (&[ArcTerm; 3], usize)> + '_ {
+        self.counts.iter().map(|(t, n)| (t, *n))
+    }
+
+    /// A triple source yielding each triple of this graph only once.
+    ///
+    /// This can be used to collect this graph into any [`SetGraph`](../trait.SetGraph.html).
+    pub fn distinct_triples(&self) -> GTripleSource<'_, Self> {
+        Box::new(
+            self.counts
+                .keys()
+                .map(StreamedTriple::by_ref)
+                .as_triple_source(),
+        )
+    }
+
+    /// Remove all occurrences of the given triple,
+    /// and return how many they were.
+    pub fn remove_all_occurrences<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> usize
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        let n = self.counts.remove(&triple).unwrap_or(0);
+        self.len -= n;
+        n
+    }
+}
+
+impl Graph for BagGraph {
+    type Triple = ByRef<[ArcTerm; 3]>;
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        Box::new(
+            self.counts
+                .iter()
+                .flat_map(|(t, n)| repeat_n(t, *n))
+                .map(StreamedTriple::by_ref)
+                .as_triple_source(),
+        )
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.multiplicity(s, p, o) > 0)
+    }
+}
+
+impl CollectibleGraph for BagGraph {
+    fn from_triple_source<TS: TripleSource>(
+        triples: TS,
+    ) -> StreamResult<Self, TS::Error, Infallible> {
+        let mut g = BagGraph::new();
+        g.insert_all(triples).map(|_| g)
+    }
+}
+
+impl MutableGraph for BagGraph {
+    type MutationError = Infallible;
+
+    /// Increment the multiplicity of the given triple.
+    ///
+    /// This always returns `true`, as the graph is always modified.
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        *self.counts.entry(triple).or_insert(0) += 1;
+        self.len += 1;
+        Ok(true)
+    }
+
+    /// Decrement the multiplicity of the given triple.
+    ///
+    /// Return `false` if the triple was not in the graph.
+    /// See also [`remove_all_occurrences`](struct.BagGraph.html#method.remove_all_occurrences).
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        match self.counts.entry(triple) {
+            Entry::Vacant(_) => Ok(false),
+            Entry::Occupied(mut e) => {
+                if *e.get() == 1 {
+                    e.remove();
+                } else {
+                    *e.get_mut() -= 1;
+                }
+                self.len -= 1;
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_bag, BagGraph, false);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::ns::{rdf, rdfs};
+
+    #[test]
+    fn multiplicities() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = BagGraph::new();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+        assert!(!g.is_set());
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.distinct_len(), 2);
+        assert_eq!(g.triples().count(), 3);
+        assert_eq!(g.distinct_triples().count(), 2);
+        assert_eq!(g.multiplicity(&rdf::type_, &rdf::type_, &rdf::Property), 2);
+        assert_eq!(g.multiplicity(&rdfs::label, &rdf::type_, &rdf::Property), 0);
+
+        assert!(g.remove(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert_eq!(g.multiplicity(&rdf::type_, &rdf::type_, &rdf::Property), 1);
+        assert!(g.contains(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        assert_eq!(
+            g.remove_all_occurrences(&rdf::type_, &rdf::type_, &rdf::Property),
+            2
+        );
+        assert!(!g.contains(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert!(!g.remove(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert_eq!(g.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn conversions() -> Result<(), Box<dyn std::error::Error>> {
+        let mut set = FastGraph::new();
+        set.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        set.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+
+        let mut bag = BagGraph::from_graph(&set)?;
+        bag.insert_all(set.triples())?;
+        assert_eq!(bag.len(), 4);
+        assert!(bag.counts().all(|(_, n)| n == 2));
+
+        let back: FastGraph = bag.distinct_triples().collect_triples()?;
+        assert_eq!(back.triples().count(), 2);
+        assert!(back.contains(&rdfs::Class, &rdf::type_, &rdfs::Class)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,754 @@
+//! A column-oriented [`Graph`], for analytics over large amounts of data.
+//!
+//! [`ColumnarGraph`] encodes every term as a `u32` identifier
+//! (in a dictionary shared by subjects, predicates and objects),
+//! and stores its triples as three columns of identifiers, sorted by subject, predicate and object.
+//! Two permutations of the rows (sorted by predicate, object and subject,
+//! and by object, subject and predicate) make any triple pattern a binary search
+//! followed by a contiguous scan.
+//! Each triple therefore uses 20 bytes (plus the dictionary),
+//! which is much less than hash-based graphs,
+//! and the columns can be scanned directly (see [`columns`] and [`triple_ids`]),
+//! e.g. to compute statistics or joins on identifiers, without touching the terms.
+//!
+//! Insertions and removals are buffered in small sorted runs,
+//! which are merged into the columns when they grow too large,
+//! or when [`flush`] is called.
+//! [`ColumnarGraph`] is therefore best suited to data that is loaded in bulk,
+//! then queried extensively.
+//!
+//! Triples can also be iterated page by page, in the order of the columns,
+//! either by position ([`triples_page`]) or after the last triple of the previous page
+//! ([`triples_after`]), which remains stable when the graph is modified between two pages.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{columnar::ColumnarGraph, indexed::IndexedGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: ColumnarGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:knows> <tag:bob>, <tag:carol>.
+//!     <tag:bob> <tag:knows> <tag:carol>.
+//! "#).collect_triples()?;
+//!
+//! // a join on identifiers: who knows someone who knows carol?
+//! let knows = g.get_index(&BoxTerm::new_iri("tag:knows")?);
+//! let carol = g.get_index(&BoxTerm::new_iri("tag:carol")?);
+//! let mut answers = vec![];
+//! for [x, _, y] in g.triple_ids(None, knows, carol) {
+//!     for [z, _, _] in g.triple_ids(None, knows, Some(x)) {
+//!         answers.push((z, y));
+//!     }
+//! }
+//! assert_eq!(answers.len(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`ColumnarGraph`]: struct.ColumnarGraph.html
+//! [`columns`]: struct.ColumnarGraph.html#method.columns
+//! [`triple_ids`]: struct.ColumnarGraph.html#method.triple_ids
+//! [`flush`]: struct.ColumnarGraph.html#method.flush
+//! [`triples_page`]: ../trait.Graph.html#method.triples_page
+//! [`triples_after`]: struct.ColumnarGraph.html#method.triples_after
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+
+use thiserror::Error;
+
+use sophia_api::graph::{CollectibleGraph, GResult, GTripleSource, Graph, MutableGraph, SetGraph};
+use sophia_api::term::TTerm;
+use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::{ByTermRefs, StreamedTriple};
+use sophia_term::factory::{ArcTermFactory, FTerm};
+use sophia_term::index_map::TermIndexMap;
+use sophia_term::RefTerm;
+
+use crate::graph::indexed::IndexedGraph;
+use crate::graph::inmem::TermIndexMapU;
+
+/// Runs smaller than this are never merged automatically.
+const MIN_RUN: usize = 1024;
+
+/// A column-oriented graph.
+///
+/// See [module documentation](index.html).
+#[derive(Default)]
+pub struct ColumnarGraph {
+    terms: TermIndexMapU<u32, ArcTermFactory>,
+    // the columns, sorted by (s, p, o)
+    s: Vec<u32>,
+    p: Vec<u32>,
+    o: Vec<u32>,
+    // the rows of the columns, sorted by (p, o, s) and by (o, s, p)
+    pos: Vec<u32>,
+    osp: Vec<u32>,
+    // triples inserted in, or removed from, the columns since the last flush
+    inserted: BTreeSet<[u32; 3]>,
+    removed: BTreeSet<[u32; 3]>,
+}
+
+/// The sort orders of the rows of a [`ColumnarGraph`](struct.ColumnarGraph.html).
+#[derive(Clone, Copy)]
+enum Order {
+    Spo,
+    Pos,
+    Osp,
+}
+
+impl ColumnarGraph {
+    /// Construct an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.s.len() - self.removed.len() + self.inserted.len()
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Merge the pending insertions and removals into the columns.
+    pub fn flush(&mut self) {
+        if self.inserted.is_empty() && self.removed.is_empty() {
+            return;
+        }
+        let len = self.len();
+        let removed = std::mem::take(&mut self.removed);
+        let (ms, mp, mo) = (
+            std::mem::take(&mut self.s),
+            std::mem::take(&mut self.p),
+            std::mem::take(&mut self.o),
+        );
+        let mut main = (0..ms.len())
+            .map(|r| [ms[r], mp[r], mo[r]])
+            .filter(|t| !removed.contains(t))
+            .peekable();
+        let mut inserted = std::mem::take(&mut self.inserted).into_iter().peekable();
+        let (mut s, mut p, mut o) = (
+            Vec::with_capacity(len),
+            Vec::with_capacity(len),
+            Vec::with_capacity(len),
+        );
+        loop {
+            let next = match (main.peek(), inserted.peek()) {
+                (Some(t1), Some(t2)) if t1 < t2 => main.next(),
+                (_, Some(_)) => inserted.next(),
+                (Some(_), None) => main.next(),
+                (None, None) => break,
+            };
+            let [ts, tp, to] = next.unwrap();
+            s.push(ts);
+            p.push(tp);
+            o.push(to);
+        }
+        let mut pos: Vec<u32> = (0..len as u32).collect();
+        pos.sort_unstable_by_key(|r| {
+            let r = *r as usize;
+            (p[r], o[r], s[r])
+        });
+        let mut osp: Vec<u32> = (0..len as u32).collect();
+        osp.sort_unstable_by_key(|r| {
+            let r = *r as usize;
+            (o[r], s[r], p[r])
+        });
+        self.s = s;
+        self.p = p;
+        self.o = o;
+        self.pos = pos;
+        self.osp = osp;
+    }
+
+    /// The subject, predicate and object columns, sorted by subject, predicate and object.
+    ///
+    /// Pending insertions and removals are [flushed](#method.flush) first.
+    /// Identifiers can be converted back to terms with
+    /// [`get_term`](../indexed/trait.IndexedGraph.html#tymethod.get_term).
+    pub fn columns(&mut self) -> [&[u32]; 3] {
+        self.flush();
+        [&self.s, &self.p, &self.o]
+    }
+
+    /// The identifiers of the triples matching the given identifiers
+    /// (`None` matching any term).
+    ///
+    /// Identifiers can be obtained with
+    /// [`get_index`](../indexed/trait.IndexedGraph.html#tymethod.get_index).
+    pub fn triple_ids(
+        &self,
+        s: Option<u32>,
+        p: Option<u32>,
+        o: Option<u32>,
+    ) -> Box<dyn Iterator<Item = [u32; 3]> + '_> {
+        let (order, prefix) = match (s, p, o) {
+            (Some(s), Some(p), Some(o)) => (Order::Spo, vec![s, p, o]),
+            (Some(s), Some(p), None) => (Order::Spo, vec![s, p]),
+            (Some(s), None, None) => (Order::Spo, vec![s]),
+            (None, Some(p), Some(o)) => (Order::Pos, vec![p, o]),
+            (None, Some(p), None) => (Order::Pos, vec![p]),
+            (Some(s), None, Some(o)) => (Order::Osp, vec![o, s]),
+            (None, None, Some(o)) => (Order::Osp, vec![o]),
+            (None, None, None) => (Order::Spo, vec![]),
+        };
+        let matches = move |t: &[u32; 3]| {
+            s.is_none_or(|s| s == t[0])
+                && p.is_none_or(|p| p == t[1])
+                && o.is_none_or(|o| o == t[2])
+        };
+        let main = self
+            .main_range(order, &prefix)
+            .map(move |i| self.spo(self.row(order, i)))
+            .filter(move |t| !self.removed.contains(t));
+        let pending = self.inserted.iter().copied().filter(matches);
+        Box::new(main.chain(pending))
+    }
+
+    fn row(&self, order: Order, i: usize) -> usize {
+        match order {
+            Order::Spo => i,
+            Order::Pos => self.pos[i] as usize,
+            Order::Osp => self.osp[i] as usize,
+        }
+    }
+
+    fn spo(&self, r: usize) -> [u32; 3] {
+        [self.s[r], self.p[r], self.o[r]]
+    }
+
+    fn key(&self, order: Order, r: usize) -> [u32; 3] {
+        let [s, p, o] = self.spo(r);
+        match order {
+            Order::Spo => [s, p, o],
+            Order::Pos => [p, o, s],
+            Order::Osp => [o, s, p],
+        }
+    }
+
+    /// The positions (in the given order) of the rows whose key starts with `prefix`.
+    fn main_range(&self, order: Order, prefix: &[u32]) -> std::ops::Range<usize> {
+        let k = prefix.len();
+        let n = self.s.len();
+        let start = partition_point(n, |i| self.key(order, self.row(order, i))[..k] < *prefix);
+        let end = partition_point(n, |i| self.key(order, self.row(order, i))[..k] <= *prefix);
+        start..end
+    }
+
+    fn main_contains(&self, t: &[u32; 3]) -> bool {
+        !self.main_range(Order::Spo, t).is_empty()
+    }
+
+    fn contains_ids(&self, t: &[u32; 3]) -> bool {
+        self.inserted.contains(t) || (self.main_contains(t) && !self.removed.contains(t))
+    }
+
+    fn maybe_flush(&mut self) {
+        if self.inserted.len() + self.removed.len() > MIN_RUN.max(self.s.len() / 8) {
+            self.flush();
+        }
+    }
+
+    fn ids<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> Option<[u32; 3]>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Some([self.get_index(s)?, self.get_index(p)?, self.get_index(o)?])
+    }
+
+    /// The identifiers of all the triples following `after` (or of all the triples),
+    /// sorted by subject, predicate and object.
+    ///
+    /// Seeking to `after` is a binary search, so each page of [`triples_after`]
+    /// costs a logarithmic time plus the size of the page.
+    ///
+    /// [`triples_after`]: #method.triples_after
+    pub fn triple_ids_after(
+        &self,
+        after: Option<PageToken>,
+    ) -> Box<dyn Iterator<Item = [u32; 3]> + '_> {
+        use std::ops::Bound::{Excluded, Unbounded};
+        let (start, lower) = match after {
+            Some(PageToken(t)) => (
+                partition_point(self.s.len(), |r| self.spo(r) <= t),
+                Excluded(t),
+            ),
+            None => (0, Unbounded),
+        };
+        let mut main = (start..self.s.len())
+            .map(move |r| self.spo(r))
+            .filter(move |t| !self.removed.contains(t))
+            .peekable();
+        let mut pending = self.inserted.range((lower, Unbounded)).copied().peekable();
+        Box::new(std::iter::from_fn(move || {
+            match (main.peek(), pending.peek()) {
+                (Some(t1), Some(t2)) if t1 < t2 => main.next(),
+                (_, Some(_)) => pending.next(),
+                (Some(_), None) => main.next(),
+                (None, None) => None,
+            }
+        }))
+    }
+
+    /// One page of at most `limit` triples (at least one) following `after`
+    /// (or starting from the first triple), sorted by subject, predicate and object,
+    /// with the token of the next page (`None` if this is the last page).
+    ///
+    /// Unlike [`triples_page`], tokens remain meaningful when the graph is modified
+    /// between two pages: triples present all along are yielded exactly once,
+    /// while triples inserted or removed in the meantime may or may not be.
+    /// Tokens can be sent to and received from clients, as strings.
+    ///
+    /// [`triples_page`]: ../trait.Graph.html#method.triples_page
+    pub fn triples_after(
+        &self,
+        after: Option<PageToken>,
+        limit: usize,
+    ) -> (GTripleSource<'_, Self>, Option<PageToken>) {
+        let limit = limit.max(1);
+        let mut ids: Vec<[u32; 3]> = self.triple_ids_after(after).take(limit + 1).collect();
+        let next = if ids.len() > limit {
+            ids.truncate(limit);
+            ids.last().copied().map(PageToken)
+        } else {
+            None
+        };
+        let triples = ids.into_iter().map(move |t| Ok(self.streamed(t)));
+        (Box::new(triples), next)
+    }
+
+    fn streamed(
+        &self,
+        [s, p, o]: [u32; 3],
+    ) -> StreamedTriple<'_, ByTermRefs<FTerm<ArcTermFactory>>> {
+        StreamedTriple::by_term_refs(
+            self.terms.get_term(s).unwrap(),
+            self.terms.get_term(p).unwrap(),
+            self.terms.get_term(o).unwrap(),
+        )
+    }
+
+    fn triples_with_ids(
+        &self,
+        s: Option<u32>,
+        p: Option<u32>,
+        o: Option<u32>,
+    ) -> GTripleSource<'_, Self> {
+        Box::new(self.triple_ids(s, p, o).map(move |t| Ok(self.streamed(t))))
+    }
+}
+
+/// The position of a [`ColumnarGraph`](struct.ColumnarGraph.html) page,
+/// as returned by [`triples_after`](struct.ColumnarGraph.html#method.triples_after).
+///
+/// It is displayed as (and can be parsed from) three hexadecimal identifiers
+/// separated by `-`, e.g. `2a-3-1f`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageToken([u32; 3]);
+
+impl std::fmt::Display for PageToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let [s, p, o] = self.0;
+        write!(f, "{:x}-{:x}-{:x}", s, p, o)
+    }
+}
+
+impl std::str::FromStr for PageToken {
+    type Err = InvalidPageToken;
+
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        let mut ids = txt.split('-').map(|id| u32::from_str_radix(id, 16).ok());
+        match (ids.next(), ids.next(), ids.next(), ids.next()) {
+            (Some(Some(s)), Some(Some(p)), Some(Some(o)), None) => Ok(PageToken([s, p, o])),
+            _ => Err(InvalidPageToken(txt.to_string())),
+        }
+    }
+}
+
+/// The error raised when parsing an invalid [`PageToken`](struct.PageToken.html).
+#[derive(Debug, Error)]
+#[error("invalid page token {0:?}")]
+pub struct InvalidPageToken(pub String);
+
+/// The index of the first of `0..n` for which `pred` is false,
+/// assuming that `pred` is true for a prefix of `0..n`.
+fn partition_point<F: Fn(usize) -> bool>(n: usize, pred: F) -> usize {
+    let (mut lo, mut hi) = (0, n);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+impl IndexedGraph for ColumnarGraph {
+    type Index = u32;
+    type TermData = std::sync::Arc<str>;
+
+    fn with_capacity(capacity: usize) -> Self {
+        ColumnarGraph {
+            s: Vec::with_capacity(capacity),
+            p: Vec::with_capacity(capacity),
+            o: Vec::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.flush();
+        self.terms.shrink_to_fit();
+        for column in [
+            &mut self.s,
+            &mut self.p,
+            &mut self.o,
+            &mut self.pos,
+            &mut self.osp,
+        ] {
+            column.shrink_to_fit();
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        let columns = self.s.capacity()
+            + self.p.capacity()
+            + self.o.capacity()
+            + self.pos.capacity()
+            + self.osp.capacity();
+        let pending = self.inserted.len() + self.removed.len();
+        self.terms.heap_size()
+            + columns * std::mem::size_of::<u32>()
+            + pending * std::mem::size_of::<[u32; 3]>()
+    }
+
+    fn dictionary_heap_size(&self) -> usize {
+        self.terms.heap_size()
+    }
+
+    fn get_index<T>(&self, t: &T) -> Option<u32>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.terms.get_index(&RefTerm::from(t))
+    }
+
+    fn get_term(&self, i: u32) -> Option<&FTerm<ArcTermFactory>> {
+        self.terms.get_term(i)
+    }
+
+    fn insert_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[u32; 3]>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let t = [
+            self.terms.make_index(&RefTerm::from(s)),
+            self.terms.make_index(&RefTerm::from(p)),
+            self.terms.make_index(&RefTerm::from(o)),
+        ];
+        let modified =
+            self.removed.remove(&t) || (!self.main_contains(&t) && self.inserted.insert(t));
+        if !modified {
+            t.iter().for_each(|i| self.terms.dec_ref(*i));
+            return None;
+        }
+        self.maybe_flush();
+        Some(t)
+    }
+
+    fn remove_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[u32; 3]>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let t = self.ids(s, p, o)?;
+        let modified =
+            self.inserted.remove(&t) || (self.main_contains(&t) && self.removed.insert(t));
+        if !modified {
+            return None;
+        }
+        t.iter().for_each(|i| self.terms.dec_ref(*i));
+        self.maybe_flush();
+        Some(t)
+    }
+}
+
+impl Graph for ColumnarGraph {
+    type Triple = ByTermRefs<FTerm<ArcTermFactory>>;
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.triples_with_ids(None, None, None)
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    /// Seek directly to the requested rows when no insertion or removal is pending;
+    /// otherwise, merge the pending runs with the columns up to the end of the page.
+    ///
+    /// Pages are sorted by subject, predicate and object.
+    fn triples_page(&self, offset: usize, limit: usize) -> GTripleSource<'_, Self> {
+        if self.inserted.is_empty() && self.removed.is_empty() {
+            let n = self.s.len();
+            let rows = offset.min(n)..offset.saturating_add(limit).min(n);
+            Box::new(rows.map(move |r| Ok(self.streamed(self.spo(r)))))
+        } else {
+            let ids = self.triple_ids_after(None).skip(offset).take(limit);
+            Box::new(ids.map(move |t| Ok(self.streamed(t))))
+        }
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        match self.get_index(s) {
+            Some(s) => self.triples_with_ids(Some(s), None, None),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        match self.get_index(p) {
+            Some(p) => self.triples_with_ids(None, Some(p), None),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        match self.get_index(o) {
+            Some(o) => self.triples_with_ids(None, None, Some(o)),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        match (self.get_index(s), self.get_index(p)) {
+            (Some(s), Some(p)) => self.triples_with_ids(Some(s), Some(p), None),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        match (self.get_index(s), self.get_index(o)) {
+            (Some(s), Some(o)) => self.triples_with_ids(Some(s), None, Some(o)),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        match (self.get_index(p), self.get_index(o)) {
+            (Some(p), Some(o)) => self.triples_with_ids(None, Some(p), Some(o)),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        match self.ids(s, p, o) {
+            Some([s, p, o]) => self.triples_with_ids(Some(s), Some(p), Some(o)),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.ids(s, p, o).is_some_and(|t| self.contains_ids(&t)))
+    }
+
+    fn index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        match bound {
+            [false, false, false] => None,
+            [_, false, true] => Some(("osp", bound)),
+            [false, true, _] => Some(("pos", bound)),
+            _ => Some(("spo", bound)),
+        }
+    }
+}
+
+impl CollectibleGraph for ColumnarGraph {
+    fn from_triple_source<TS: TripleSource>(
+        triples: TS,
+    ) -> StreamResult<Self, TS::Error, Infallible> {
+        let mut g = ColumnarGraph::new();
+        g.insert_all(triples)?;
+        g.flush();
+        Ok(g)
+    }
+}
+
+impl MutableGraph for ColumnarGraph {
+    crate::impl_mutable_graph_for_indexed_graph!();
+}
+
+impl SetGraph for ColumnarGraph {}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_columnar, ColumnarGraph);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::t;
+    use sophia_api::triple::Triple;
+
+    #[test]
+    fn runs() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = ColumnarGraph::new();
+        let n = 3 * MIN_RUN;
+        for i in 0..n {
+            g.insert(&t(&(i % 100).to_string()), &t("p"), &t(&i.to_string()))?;
+        }
+        // some runs were merged, and the others are pending
+        assert!(!g.s.is_empty());
+        assert!(!g.inserted.is_empty());
+        assert_eq!(g.len(), n);
+        assert!(!g.insert(&t("0"), &t("p"), &t("0"))?);
+        assert!(g.remove(&t("0"), &t("p"), &t("0"))?);
+        assert!(!g.contains(&t("0"), &t("p"), &t("0"))?);
+        assert_eq!(g.len(), n - 1);
+        let per_subject = |s| (0..n).filter(|i| i % 100 == s).count();
+        assert_eq!(g.triples_with_s(&t("1")).count(), per_subject(1));
+        assert_eq!(g.triples_with_o(&t("1")).count(), 1);
+        assert_eq!(g.triples_with_so(&t("1"), &t("101")).count(), 1);
+        assert_eq!(g.triples_with_p(&t("p")).count(), n - 1);
+
+        let pid = g.get_index(&t("p"));
+        let [s, p, o] = g.columns();
+        assert_eq!(s.len(), n - 1);
+        assert!(s.windows(2).all(|w| w[0] <= w[1]));
+        assert!(p.iter().all(|p| Some(*p) == pid));
+        assert_eq!(o.len(), n - 1);
+        assert!(g.inserted.is_empty() && g.removed.is_empty());
+        assert!(g.insert(&t("0"), &t("p"), &t("0"))?);
+        assert_eq!(g.triples_with_s(&t("0")).count(), per_subject(0));
+        Ok(())
+    }
+
+    #[test]
+    fn ids() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = ColumnarGraph::new();
+        g.insert(&t("a"), &t("p"), &t("b"))?;
+        g.insert(&t("b"), &t("p"), &t("c"))?;
+        g.flush();
+        g.insert(&t("c"), &t("p"), &t("a"))?;
+        let p = g.get_index(&t("p"));
+        let b = g.get_index(&t("b"));
+        assert_eq!(g.triple_ids(None, p, None).count(), 3);
+        let objects: Vec<_> = g
+            .triple_ids(b, p, None)
+            .map(|[_, _, o]| g.get_term(o).unwrap().value().to_string())
+            .collect();
+        assert_eq!(objects, vec!["tag:c"]);
+        assert!(g.get_index(&t("z")).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn explain() -> Result<(), Box<dyn std::error::Error>> {
+        use sophia_api::term::matcher::ANY;
+        let mut g = ColumnarGraph::new();
+        g.insert(&t("a"), &t("p"), &t("b"))?;
+        assert_eq!(
+            g.explain_triples_matching(&t("a"), &ANY, &ANY).index,
+            Some("spo")
+        );
+        assert_eq!(
+            g.explain_triples_matching(&ANY, &t("p"), &ANY).index,
+            Some("pos")
+        );
+        assert_eq!(
+            g.explain_triples_matching(&ANY, &ANY, &t("b")).index,
+            Some("osp")
+        );
+        let e = g.explain_triples_matching(&ANY, &t("p"), &t("b"));
+        assert_eq!(e.index, Some("pos"));
+        assert!(!e.filtered);
+        assert!(g.explain_triples_matching(&ANY, &ANY, &ANY).is_full_scan());
+        Ok(())
+    }
+
+    #[test]
+    fn pages() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = ColumnarGraph::new();
+        for i in 0..10 {
+            g.insert(&t(&i.to_string()), &t("p"), &t("o"))?;
+        }
+        g.flush();
+        let subjects = |page: GTripleSource<ColumnarGraph>| -> Vec<String> {
+            page.map(|t| t.unwrap().s().value()[4..].to_string())
+                .collect()
+        };
+        assert_eq!(subjects(g.triples_page(8, 5)), vec!["8", "9"]);
+        g.insert(&t("10"), &t("p"), &t("o"))?;
+        g.remove(&t("8"), &t("p"), &t("o"))?;
+        assert_eq!(subjects(g.triples_page(8, 5)), vec!["9", "10"]);
+        assert_eq!(g.triples_page(9, 5).count(), 1);
+
+        // keyset pagination, with modifications between pages
+        let (page, next) = g.triples_after(None, 4);
+        assert_eq!(subjects(page), vec!["0", "1", "2", "3"]);
+        let next: PageToken = next.unwrap().to_string().parse()?;
+        g.remove(&t("2"), &t("p"), &t("o"))?;
+        g.remove(&t("4"), &t("p"), &t("o"))?;
+        let (page, next) = g.triples_after(Some(next), 4);
+        assert_eq!(subjects(page), vec!["5", "6", "7", "9"]);
+        let (page, next) = g.triples_after(next, 4);
+        assert_eq!(subjects(page), vec!["10"]);
+        assert!(next.is_none());
+
+        assert!("1-2".parse::<PageToken>().is_err());
+        assert!("1-2-x".parse::<PageToken>().is_err());
+        assert!("1-2-3-4".parse::<PageToken>().is_err());
+        Ok(())
+    }
+}
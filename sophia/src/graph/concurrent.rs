@@ -0,0 +1,296 @@
+//! A [`Graph`] supporting concurrent insertions from multiple threads.
+//!
+//! [`ConcurrentGraph`] distributes its triples (according to their subject)
+//! among a fixed number of shards, each of them protected by its own lock.
+//! Its [`insert_shared`], [`remove_shared`] and [`insert_all_shared`] methods
+//! only require a shared reference to the graph,
+//! so that several threads (e.g. several parsers) can insert triples simultaneously,
+//! and only contend when they hit the same shard.
+//!
+//! As locks can not be held while iterating over a graph,
+//! [`triples`] yields copies of the triples (which is cheap, as terms are reference-counted),
+//! one shard at a time.
+//! The result is consistent within a shard, but not across shards
+//! if the graph is modified during the iteration.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::concurrent::ConcurrentGraph;
+//! use sophia::parser::turtle;
+//! use std::sync::Arc;
+//!
+//! let g = Arc::new(ConcurrentGraph::new());
+//! let handles: Vec<_> = ["<tag:a> <tag:b> <tag:c>.", "<tag:d> <tag:e> <tag:f>."]
+//!     .iter()
+//!     .map(|ttl| {
+//!         let g = g.clone();
+//!         std::thread::spawn(move || g.insert_all_shared(turtle::parse_str(ttl)))
+//!     })
+//!     .collect();
+//! for h in handles {
+//!     h.join().unwrap()?;
+//! }
+//! assert_eq!(g.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`ConcurrentGraph`]: struct.ConcurrentGraph.html
+//! [`insert_shared`]: struct.ConcurrentGraph.html#method.insert_shared
+//! [`remove_shared`]: struct.ConcurrentGraph.html#method.remove_shared
+//! [`insert_all_shared`]: struct.ConcurrentGraph.html#method.insert_all_shared
+//! [`triples`]: ../trait.Graph.html#tymethod.triples
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use sophia_api::graph::{
+    CollectibleGraph, GResult, GTripleSource, Graph, MGResult, MutableGraph, SetGraph,
+};
+use sophia_api::term::{term_hash, CopyTerm, TTerm};
+use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::{ByValue, StreamedTriple};
+use sophia_api::triple::Triple;
+use sophia_term::ArcTerm;
+
+/// The default number of shards of a [`ConcurrentGraph`](struct.ConcurrentGraph.html).
+pub const DEFAULT_SHARDS: usize = 64;
+
+type Shard = HashSet<[ArcTerm; 3]>;
+
+/// A graph that can be modified concurrently through a shared reference.
+///
+/// See [module documentation](index.html).
+#[derive(Debug)]
+pub struct ConcurrentGraph {
+    shards: Box<[RwLock<Shard>]>,
+    hasher: RandomState,
+}
+
+impl ConcurrentGraph {
+    /// Construct an empty graph with [`DEFAULT_SHARDS`](constant.DEFAULT_SHARDS.html) shards.
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Construct an empty graph with the given number of shards.
+    ///
+    /// More shards reduce contention between threads.
+    pub fn with_shards(shards: usize) -> Self {
+        ConcurrentGraph {
+            shards: (0..shards.max(1)).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.read(i).len()).sum()
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.read(i).is_empty())
+    }
+
+    /// Insert the given triple in this graph,
+    /// and return whether it was not already present.
+    ///
+    /// Unlike [`MutableGraph::insert`](../trait.MutableGraph.html#tymethod.insert),
+    /// this method can be called concurrently from several threads.
+    pub fn insert_shared<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        self.write(self.shard_index(s)).insert(triple)
+    }
+
+    /// Remove the given triple from this graph,
+    /// and return whether it was present.
+    ///
+    /// Unlike [`MutableGraph::remove`](../trait.MutableGraph.html#tymethod.remove),
+    /// this method can be called concurrently from several threads.
+    pub fn remove_shared<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        self.write(self.shard_index(s)).remove(&triple)
+    }
+
+    /// Insert all the triples from the given source,
+    /// and return the number of triples actually inserted.
+    ///
+    /// Unlike [`MutableGraph::insert_all`](../trait.MutableGraph.html#method.insert_all),
+    /// this method can be called concurrently from several threads.
+    pub fn insert_all_shared<TS>(&self, mut src: TS) -> StreamResult<usize, TS::Error, Infallible>
+    where
+        TS: TripleSource,
+    {
+        let mut c = 0;
+        src.try_for_each_triple(|t| -> Result<(), Infallible> {
+            if self.insert_shared(t.s(), t.p(), t.o()) {
+                c += 1;
+            }
+            Ok(())
+        })?;
+        Ok(c)
+    }
+
+    fn shard_index<T: TTerm + ?Sized>(&self, s: &T) -> usize {
+        let mut hasher = self.hasher.build_hasher();
+        term_hash(s, &mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    // A shard is never left in an inconsistent state by a panicking thread,
+    // so poisoned locks can safely be ignored.
+
+    fn read(&self, i: usize) -> RwLockReadGuard<'_, Shard> {
+        self.shards[i]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self, i: usize) -> RwLockWriteGuard<'_, Shard> {
+        self.shards[i]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for ConcurrentGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Graph for ConcurrentGraph {
+    type Triple = ByValue<[ArcTerm; 3]>;
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        Box::new((0..self.shards.len()).flat_map(move |i| {
+            let shard: Vec<_> = self.read(i).iter().cloned().collect();
+            shard.into_iter().map(|t| Ok(StreamedTriple::by_value(t)))
+        }))
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        let s = ArcTerm::copy(s);
+        let shard: Vec<_> = self
+            .read(self.shard_index(&s))
+            .iter()
+            .filter(|t| t.s() == &s)
+            .cloned()
+            .collect();
+        Box::new(shard.into_iter().map(|t| Ok(StreamedTriple::by_value(t))))
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        Ok(self.read(self.shard_index(s)).contains(&triple))
+    }
+
+    fn index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        // triples_with_s only enumerates the shard of the subject,
+        // on which the other triples_with_* methods rely when the subject is given
+        if bound[0] {
+            Some(("subject shards", [true, false, false]))
+        } else {
+            None
+        }
+    }
+}
+
+impl CollectibleGraph for ConcurrentGraph {
+    fn from_triple_source<TS: TripleSource>(
+        triples: TS,
+    ) -> StreamResult<Self, TS::Error, Infallible> {
+        let g = ConcurrentGraph::new();
+        g.insert_all_shared(triples).map(|_| g)
+    }
+}
+
+impl MutableGraph for ConcurrentGraph {
+    type MutationError = Infallible;
+
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.insert_shared(s, p, o))
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.remove_shared(s, p, o))
+    }
+}
+
+impl SetGraph for ConcurrentGraph {}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_concurrent, ConcurrentGraph);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn threads() -> Result<(), Box<dyn std::error::Error>> {
+        let g = Arc::new(ConcurrentGraph::with_shards(8));
+        let handles: Vec<_> = (0..4)
+            .map(|n| {
+                let g = g.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        // the same 50 triples are inserted by all threads
+                        let s = ArcTerm::new_iri(format!("tag:s{}", i % 50)).unwrap();
+                        let o = ArcTerm::new_iri(format!("tag:o{}", n % 2)).unwrap();
+                        g.insert_shared(&s, &sophia_api::ns::rdf::value, &o);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(g.len(), 100);
+        assert_eq!(g.triples().count(), 100);
+        let s = ArcTerm::new_iri("tag:s7")?;
+        assert_eq!(g.triples_with_s(&s).count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! A [`Graph`] with copy-on-write snapshots.
+//!
+//! Cloning a [`CowGraph`] does not copy its triples:
+//! the clone shares them with the original graph,
+//! and each of them only copies the part of the data it modifies (and only the first time).
+//!
+//! More precisely, triples are distributed (according to their subject)
+//! among a fixed number of shards, each of them stored behind an `Arc`.
+//! Taking a [`snapshot`] is `O(1)` with respect to the size of the graph,
+//! and modifying a graph while snapshots of it are alive
+//! copies at most one shard per modification.
+//!
+//! As `CowGraph` is `Send` and `Sync`,
+//! this allows a writer thread to keep mutating a graph,
+//! while reader threads work on consistent snapshots of it.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{cow::CowGraph, Graph, MutableGraph};
+//! use sophia::ns::rdf;
+//!
+//! let mut g = CowGraph::new();
+//! g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+//! let snapshot = g.snapshot();
+//! g.remove(&rdf::type_, &rdf::type_, &rdf::Property)?;
+//! assert_eq!(g.len(), 0);
+//! assert_eq!(snapshot.len(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`CowGraph`]: struct.CowGraph.html
+//! [`snapshot`]: struct.CowGraph.html#method.snapshot
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+
+use sophia_api::graph::{
+    CollectibleGraph, GResult, GTripleSource, Graph, MGResult, MutableGraph, SetGraph,
+};
+use sophia_api::term::{term_eq, term_hash, CopyTerm, TTerm};
+use sophia_api::triple::stream::{AsTripleSource, StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::{ByRef, StreamedTriple};
+use sophia_api::triple::Triple;
+use sophia_term::{ArcTerm, RefTerm};
+
+/// The default number of shards of a [`CowGraph`](struct.CowGraph.html).
+pub const DEFAULT_SHARDS: usize = 64;
+
+type Shard = HashSet<[ArcTerm; 3]>;
+
+/// A graph whose clones share their data until they are modified.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct CowGraph {
+    shards: Arc<[Arc<Shard>]>,
+    hasher: RandomState,
+    len: usize,
+}
+
+impl CowGraph {
+    /// Construct an empty graph with [`DEFAULT_SHARDS`](constant.DEFAULT_SHARDS.html) shards.
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Construct an empty graph with the given number of shards.
+    ///
+    /// More shards make modifications cheaper when snapshots are alive,
+    /// but make snapshots (slightly) more expensive.
+    pub fn with_shards(shards: usize) -> Self {
+        CowGraph {
+            shards: (0..shards.max(1)).map(|_| Arc::default()).collect(),
+            hasher: RandomState::new(),
+            len: 0,
+        }
+    }
+
+    /// Take a snapshot of this graph,
+    /// which will not be affected by subsequent modifications of this graph
+    /// (and vice-versa).
+    ///
+    /// This is equivalent to `clone`.
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn shard_index<T: TTerm + ?Sized>(&self, s: &T) -> usize {
+        let mut hasher = self.hasher.build_hasher();
+        term_hash(s, &mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// Get a mutable access to the shard for subject `s`,
+    /// copying the shard (and the list of shards) if they are shared.
+    fn shard_mut<T: TTerm + ?Sized>(&mut self, s: &T) -> &mut Shard {
+        let i = self.shard_index(s);
+        if Arc::get_mut(&mut self.shards).is_none() {
+            self.shards = self.shards.iter().cloned().collect();
+        }
+        let shards = Arc::get_mut(&mut self.shards).unwrap();
+        Arc::make_mut(&mut shards[i])
+    }
+}
+
+impl Default for CowGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Graph for CowGraph {
+    type Triple = ByRef<[ArcTerm; 3]>;
+    type Error = Infallible;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        Box::new(
+            self.shards
+                .iter()
+                .flat_map(|shard| shard.iter())
+                .map(StreamedTriple::by_ref)
+                .as_triple_source(),
+        )
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        let s = RefTerm::from(s);
+        Box::new(
+            self.shards[self.shard_index(&s)]
+                .iter()
+                .filter(move |t| term_eq(t.s(), &s))
+                .map(StreamedTriple::by_ref)
+                .as_triple_source(),
+        )
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        Ok(self.shards[self.shard_index(s)].contains(&triple))
+    }
+
+    fn index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        // triples_with_s only enumerates the shard of the subject,
+        // on which the other triples_with_* methods rely when the subject is given
+        if bound[0] {
+            Some(("subject shards", [true, false, false]))
+        } else {
+            None
+        }
+    }
+}
+
+impl CollectibleGraph for CowGraph {
+    fn from_triple_source<TS: TripleSource>(
+        triples: TS,
+    ) -> StreamResult<Self, TS::Error, Infallible> {
+        let mut g = CowGraph::new();
+        g.insert_all(triples).map(|_| g)
+    }
+}
+
+impl MutableGraph for CowGraph {
+    type MutationError = Infallible;
+
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        if self.shards[self.shard_index(s)].contains(&triple) {
+            return Ok(false);
+        }
+        self.shard_mut(s).insert(triple);
+        self.len += 1;
+        Ok(true)
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [ArcTerm::copy(s), ArcTerm::copy(p), ArcTerm::copy(o)];
+        // check first, in order to avoid copying a shared shard for nothing
+        if !self.shards[self.shard_index(s)].contains(&triple) {
+            return Ok(false);
+        }
+        self.shard_mut(s).remove(&triple);
+        self.len -= 1;
+        Ok(true)
+    }
+}
+
+impl SetGraph for CowGraph {}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_cow, CowGraph);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::{rdf, rdfs};
+
+    #[test]
+    fn snapshots() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = CowGraph::with_shards(4);
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+        let s1 = g.snapshot();
+        assert!(Arc::ptr_eq(&g.shards, &s1.shards));
+
+        g.remove(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdfs::label, &rdf::type_, &rdf::Property)?;
+        let s2 = g.snapshot();
+        g.insert(&rdfs::comment, &rdf::type_, &rdf::Property)?;
+        // only the modified shards are copied
+        let shared = (0..4)
+            .filter(|i| Arc::ptr_eq(&s1.shards[*i], &g.shards[*i]))
+            .count();
+        assert!(shared >= 1);
+
+        assert_eq!(s1.len(), 2);
+        assert!(s1.contains(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert!(!s1.contains(&rdfs::label, &rdf::type_, &rdf::Property)?);
+        assert_eq!(s2.len(), 2);
+        assert_eq!(s2.triples().count(), 2);
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.triples_with_s(&rdfs::comment).count(), 1);
+
+        let reader = std::thread::spawn(move || s1.triples().count());
+        assert_eq!(reader.join().unwrap(), 2);
+        Ok(())
+    }
+}
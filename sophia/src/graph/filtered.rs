@@ -0,0 +1,367 @@
+//! A read-only view of a [`Graph`], hiding the triples rejected by a [`TripleFilter`].
+//!
+//! A [`TripleFilter`] is made of one [`TermFilter`] per position (subject, predicate, object).
+//! Each [`TermFilter`] holds an (optional) allowlist and a denylist
+//! of IRIs and namespaces.
+//! A [`FilteredGraph`] borrows a source graph, and exposes only the triples accepted by its filter.
+//! Nothing is copied: triples are filtered lazily, as they are enumerated.
+//!
+//! This is typically used to redact sensitive properties before serving or serializing data.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::graph::filtered::{FilteredGraph, TermFilter, TripleFilter};
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix foaf: <http://xmlns.com/foaf/0.1/>.
+//!     @prefix hr: <http://example.org/hr#>.
+//!     <tag:alice> foaf:name "Alice"; foaf:mbox <mailto:alice@example.org>; hr:salary 42000.
+//! "#).collect_triples()?;
+//!
+//! let filter = TripleFilter::new().predicates(
+//!     TermFilter::new()
+//!         .deny_namespace("http://example.org/hr#")
+//!         .deny_iri("http://xmlns.com/foaf/0.1/mbox"),
+//! );
+//! let public = FilteredGraph::new(&g, filter);
+//! assert_eq!(public.triples().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`TripleFilter`]: struct.TripleFilter.html
+//! [`TermFilter`]: struct.TermFilter.html
+//! [`FilteredGraph`]: struct.FilteredGraph.html
+
+use sophia_api::graph::{GResult, GTripleSource, Graph, SetGraph};
+use sophia_api::term::{TTerm, TermKind};
+use sophia_api::triple::Triple;
+
+/// An allowlist and a denylist of IRIs and namespaces,
+/// applying to the terms at one position of a triple.
+///
+/// A term is accepted if
+/// * it is not an IRI (lists only constrain IRIs, so literals and blank nodes are always accepted), or
+/// * it matches the allowlist (if any), and does not match the denylist.
+///
+/// By default, the allowlist is absent, and the denylist is empty,
+/// so every term is accepted.
+#[derive(Clone, Debug, Default)]
+pub struct TermFilter {
+    allowed: Option<Vec<IriPattern>>,
+    denied: Vec<IriPattern>,
+}
+
+#[derive(Clone, Debug)]
+enum IriPattern {
+    Iri(String),
+    Namespace(String),
+}
+
+impl IriPattern {
+    fn matches(&self, iri: &str) -> bool {
+        match self {
+            IriPattern::Iri(txt) => iri == txt,
+            IriPattern::Namespace(ns) => iri.starts_with(ns.as_str()),
+        }
+    }
+}
+
+impl TermFilter {
+    /// Build a filter accepting every term.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the given IRI to the allowlist (creating it if necessary).
+    pub fn allow_iri(self, iri: &str) -> Self {
+        self.allow(IriPattern::Iri(iri.to_string()))
+    }
+
+    /// Add all the IRIs starting with `ns` to the allowlist (creating it if necessary).
+    pub fn allow_namespace(self, ns: &str) -> Self {
+        self.allow(IriPattern::Namespace(ns.to_string()))
+    }
+
+    /// Add the given IRI to the denylist.
+    pub fn deny_iri(mut self, iri: &str) -> Self {
+        self.denied.push(IriPattern::Iri(iri.to_string()));
+        self
+    }
+
+    /// Add all the IRIs starting with `ns` to the denylist.
+    pub fn deny_namespace(mut self, ns: &str) -> Self {
+        self.denied.push(IriPattern::Namespace(ns.to_string()));
+        self
+    }
+
+    /// Whether this filter accepts `t`.
+    pub fn accepts<T: TTerm + ?Sized>(&self, t: &T) -> bool {
+        if t.kind() != TermKind::Iri {
+            return true;
+        }
+        let iri = t.value();
+        let allowed = match &self.allowed {
+            None => true,
+            Some(allowed) => allowed.iter().any(|pat| pat.matches(&iri)),
+        };
+        allowed && !self.denied.iter().any(|pat| pat.matches(&iri))
+    }
+
+    fn allow(mut self, pattern: IriPattern) -> Self {
+        self.allowed.get_or_insert_with(Vec::new).push(pattern);
+        self
+    }
+}
+
+/// A [`TermFilter`](struct.TermFilter.html) for each position of a triple.
+///
+/// A triple is accepted if each of its terms is accepted by the corresponding filter.
+#[derive(Clone, Debug, Default)]
+pub struct TripleFilter {
+    subject: TermFilter,
+    predicate: TermFilter,
+    object: TermFilter,
+}
+
+impl TripleFilter {
+    /// Build a filter accepting every triple.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the filter applying to subjects.
+    pub fn subjects(mut self, filter: TermFilter) -> Self {
+        self.subject = filter;
+        self
+    }
+
+    /// Set the filter applying to predicates.
+    pub fn predicates(mut self, filter: TermFilter) -> Self {
+        self.predicate = filter;
+        self
+    }
+
+    /// Set the filter applying to objects.
+    pub fn objects(mut self, filter: TermFilter) -> Self {
+        self.object = filter;
+        self
+    }
+
+    /// Whether this filter accepts the triple `t`.
+    pub fn accepts<T: Triple + ?Sized>(&self, t: &T) -> bool {
+        self.accepts_terms(t.s(), t.p(), t.o())
+    }
+
+    fn accepts_terms<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> bool
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.subject.accepts(s) && self.predicate.accepts(p) && self.object.accepts(o)
+    }
+}
+
+/// A read-only view of a graph, exposing only the triples accepted by a
+/// [`TripleFilter`](struct.TripleFilter.html).
+///
+/// See [module documentation](index.html).
+pub struct FilteredGraph<'a, G: ?Sized> {
+    source: &'a G,
+    filter: TripleFilter,
+}
+
+impl<'a, G> FilteredGraph<'a, G>
+where
+    G: Graph + ?Sized,
+{
+    /// Build a view of `source` through `filter`.
+    pub fn new(source: &'a G, filter: TripleFilter) -> Self {
+        FilteredGraph { source, filter }
+    }
+
+    /// The source graph.
+    pub fn source(&self) -> &'a G {
+        self.source
+    }
+
+    /// The filter of this view.
+    pub fn filter(&self) -> &TripleFilter {
+        &self.filter
+    }
+
+    fn filtered<'s>(&'s self, triples: GTripleSource<'s, G>) -> GTripleSource<'s, Self> {
+        Box::new(triples.filter(move |res| match res {
+            Ok(t) => self.filter.accepts(t),
+            Err(_) => true,
+        }))
+    }
+}
+
+impl<'a, G> Graph for FilteredGraph<'a, G>
+where
+    G: Graph + ?Sized,
+{
+    type Triple = G::Triple;
+    type Error = G::Error;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.filtered(self.source.triples())
+    }
+
+    fn is_set(&self) -> bool {
+        self.source.is_set()
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        if !self.filter.subject.accepts(s) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_s(s))
+    }
+
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        if !self.filter.predicate.accepts(p) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_p(p))
+    }
+
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        if !self.filter.object.accepts(o) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_o(o))
+    }
+
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        if !self.filter.subject.accepts(s) || !self.filter.predicate.accepts(p) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_sp(s, p))
+    }
+
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if !self.filter.subject.accepts(s) || !self.filter.object.accepts(o) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_so(s, o))
+    }
+
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if !self.filter.predicate.accepts(p) || !self.filter.object.accepts(o) {
+            return Box::new(std::iter::empty());
+        }
+        self.filtered(self.source.triples_with_po(p, o))
+    }
+
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if !self.filter.accepts_terms(s, p, o) {
+            return Box::new(std::iter::empty());
+        }
+        self.source.triples_with_spo(s, p, o)
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        Ok(self.filter.accepts_terms(s, p, o) && self.source.contains(s, p, o)?)
+    }
+
+    fn indexed_positions(&self) -> [bool; 3] {
+        self.source.indexed_positions()
+    }
+}
+
+impl<'a, G> SetGraph for FilteredGraph<'a, G> where G: SetGraph + ?Sized {}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::graph::MutableGraph;
+    use sophia_api::ns::{rdf, rdfs, xsd};
+    use sophia_term::StaticTerm;
+
+    fn make_graph() -> FastGraph {
+        let mut g = FastGraph::new();
+        let lit = StaticTerm::new_literal_dt("42", xsd::integer).unwrap();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property).unwrap();
+        g.insert(&rdf::type_, &rdfs::label, &lit).unwrap();
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class).unwrap();
+        g.insert(&rdfs::Class, &rdfs::comment, &lit).unwrap();
+        g
+    }
+
+    #[test]
+    fn denylist() -> Result<(), Box<dyn std::error::Error>> {
+        let g = make_graph();
+        let filter = TripleFilter::new().predicates(TermFilter::new().deny_namespace(rdfs::PREFIX));
+        let v = FilteredGraph::new(&g, filter);
+        assert_eq!(v.triples().count(), 2);
+        assert_eq!(v.triples_with_s(&rdfs::Class).count(), 1);
+        assert_eq!(v.triples_with_p(&rdfs::label).count(), 0);
+        assert!(v.contains(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        let lit = StaticTerm::new_literal_dt("42", xsd::integer)?;
+        assert!(g.contains(&rdfs::Class, &rdfs::comment, &lit)?);
+        assert!(!v.contains(&rdfs::Class, &rdfs::comment, &lit)?);
+        Ok(())
+    }
+
+    #[test]
+    fn allowlist() -> Result<(), Box<dyn std::error::Error>> {
+        let g = make_graph();
+        let filter = TripleFilter::new()
+            .subjects(TermFilter::new().allow_namespace(rdf::PREFIX))
+            .objects(TermFilter::new().deny_iri(&rdf::Property.value()));
+        let v = FilteredGraph::new(&g, filter);
+        // literals are not constrained by the object filter
+        assert_eq!(v.triples().count(), 1);
+        assert_eq!(v.triples_with_o(&rdf::Property).count(), 0);
+        assert_eq!(v.triples_with_s(&rdfs::Class).count(), 0);
+        assert_eq!(v.triples_with_sp(&rdf::type_, &rdfs::label).count(), 1);
+        Ok(())
+    }
+}
@@ -4,7 +4,9 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::hash::Hash;
 
+use sophia_api::graph::{GResult, Graph};
 use sophia_api::term::TTerm;
+use sophia_api::triple::Triple;
 use sophia_term::*;
 
 /// A utility trait for implementing [`Graph`] and [`MutableGraph`]
@@ -27,9 +29,60 @@ pub trait IndexedGraph {
     /// Construct a new empty graph, provisioning for storing `capacity` triples.
     fn with_capacity(capacity: usize) -> Self;
 
+    /// Reserve capacity for at least `additional` more triples;
+    /// the default implementation does nothing.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
     /// Shrink the memory consumption of the graph as much as possible.
     fn shrink_to_fit(&mut self);
 
+    /// Return an estimate of the heap memory (in bytes) used by the graph,
+    /// including the textual data of its terms.
+    fn heap_size(&self) -> usize;
+
+    /// Return an estimate of the heap memory (in bytes) used by the term dictionary of the graph;
+    /// this is included in [`heap_size`](#tymethod.heap_size).
+    fn dictionary_heap_size(&self) -> usize;
+
+    /// Return an estimate of the heap memory used by the graph,
+    /// broken down into its term dictionary and its triple indexes.
+    fn memory_usage(&self) -> MemoryUsage {
+        let dictionary = self.dictionary_heap_size();
+        MemoryUsage {
+            dictionary,
+            indexes: self.heap_size().saturating_sub(dictionary),
+        }
+    }
+
+    /// Rebuild the graph from scratch, so that its terms are densely re-indexed,
+    /// then shrink it with [`shrink_to_fit`](#tymethod.shrink_to_fit).
+    ///
+    /// Term indices freed by removed triples are only recycled by subsequent insertions,
+    /// so a graph whose content churns a lot may keep large, sparsely used dictionaries;
+    /// compacting it drops the unused slots, at the cost of a full copy of the graph.
+    ///
+    /// NB: indices previously returned by this graph are invalidated.
+    fn compact(&mut self) -> GResult<Self, Compaction>
+    where
+        Self: Graph + Sized,
+    {
+        let before = self.heap_size();
+        let mut compacted = Self::with_capacity(self.triples().size_hint().0);
+        for t in self.triples() {
+            let t = t?;
+            compacted.insert_indexed(t.s(), t.p(), t.o());
+        }
+        compacted.shrink_to_fit();
+        *self = compacted;
+        Ok(Compaction {
+            before,
+            after: self.heap_size(),
+        })
+    }
+
     /// Return the index for the given term, if it exists.
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -54,6 +107,46 @@ pub trait IndexedGraph {
         TO: TTerm + ?Sized;
 }
 
+/// The outcome of [`IndexedGraph::compact`] or [`IndexedDataset::compact`],
+/// as estimates of the heap memory used before and after the compaction.
+///
+/// [`IndexedGraph::compact`]: trait.IndexedGraph.html#method.compact
+/// [`IndexedDataset::compact`]: ../../dataset/indexed/trait.IndexedDataset.html#method.compact
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compaction {
+    /// The estimated heap size (in bytes) before compaction.
+    pub before: usize,
+    /// The estimated heap size (in bytes) after compaction.
+    pub after: usize,
+}
+
+impl Compaction {
+    /// The estimated number of bytes reclaimed by the compaction.
+    pub fn reclaimed(&self) -> usize {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+/// An estimate of the heap memory used by an [`IndexedGraph`] or an [`IndexedDataset`],
+/// as returned by their `memory_usage` method.
+///
+/// [`IndexedGraph`]: trait.IndexedGraph.html
+/// [`IndexedDataset`]: ../../dataset/indexed/trait.IndexedDataset.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The estimated heap size (in bytes) of the term dictionary.
+    pub dictionary: usize,
+    /// The estimated heap size (in bytes) of the triple or quad indexes.
+    pub indexes: usize,
+}
+
+impl MemoryUsage {
+    /// The estimated total heap size (in bytes).
+    pub fn total(&self) -> usize {
+        self.dictionary + self.indexes
+    }
+}
+
 /// Defines the implementation of [`CollectibleGraph`] for [`IndexedGraph`].
 ///
 /// [`CollectibleGraph`]: graph/trait.CollectibleGraph.html
@@ -123,6 +216,9 @@ macro_rules! impl_mutable_graph_for_indexed_graph {
         {
             Ok(self.remove_indexed(s, p, o).is_some())
         }
+        fn reserve_triples(&mut self, additional: usize) {
+            $crate::graph::indexed::IndexedGraph::reserve(self, additional)
+        }
     };
 }
 
@@ -189,7 +285,77 @@ where
     }
 }
 
+/// Return an estimate of the heap memory (in bytes) used by an index
+/// as managed by [`insert_in_index`] and [`remove_from_index`].
+pub(crate) fn index_heap_size<K, W>(hm: &HashMap<K, Vec<W>>) -> usize {
+    use std::mem::size_of;
+    hm.capacity() * (size_of::<K>() + size_of::<Vec<W>>())
+        + hm.values()
+            .map(|ws| ws.capacity() * size_of::<W>())
+            .sum::<usize>()
+}
+
+/// Refine an `estimate` (as returned by `estimate_triples_matching` or `estimate_quads_matching`)
+/// with the size `count` of an index entry covering all the matching elements.
+///
+/// If `exact` is `true`, the index entry contains *only* matching elements,
+/// so `count` is both a lower and an upper bound.
+pub(crate) fn refine_estimate(
+    estimate: (usize, Option<usize>),
+    count: usize,
+    exact: bool,
+) -> (usize, Option<usize>) {
+    if exact {
+        return (count, Some(count));
+    }
+    let (min, max) = estimate;
+    (min, Some(max.map_or(count, |max| max.min(count))))
+}
+
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::MutableGraph;
+    use crate::ns::rdf;
+    use crate::test_util::t;
+    use sophia_api::term::matcher::ANY;
+
+    #[test]
+    fn compact() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        for i in 0..200 {
+            g.insert(&t(&format!("s{}", i)), &rdf::value, &t(&format!("o{}", i)))?;
+        }
+        for i in 0..199 {
+            g.remove(&t(&format!("s{}", i)), &rdf::value, &t(&format!("o{}", i)))?;
+        }
+        assert_eq!(g.triples().count(), 1);
+
+        let report = g.compact()?;
+        assert!(report.reclaimed() > 0);
+        assert_eq!(report.after, g.heap_size());
+        assert_eq!(g.triples().count(), 1);
+        assert!(g.contains(&t("s199"), &rdf::value, &t("o199"))?);
+        assert_eq!(g.triples_matching(&t("s199"), &ANY, &ANY).count(), 1);
+        assert!(g.get_index(&t("s0")).is_none());
+
+        g.insert(&t("s0"), &rdf::value, &t("o0"))?;
+        assert_eq!(g.triples().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn memory_usage() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        let empty = g.memory_usage();
+        for i in 0..100 {
+            g.insert(&t(&format!("s{}", i)), &rdf::value, &t("o"))?;
+        }
+        let usage = g.memory_usage();
+        assert_eq!(usage.total(), g.heap_size());
+        assert!(usage.dictionary > empty.dictionary);
+        assert!(usage.indexes > empty.indexes);
+        Ok(())
+    }
 }
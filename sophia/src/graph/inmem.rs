@@ -42,6 +42,8 @@ mod _wrapper;
 pub use self::_wrapper::*;
 mod _hash_graph;
 pub use self::_hash_graph::*;
+mod _literal_index;
+pub use self::_literal_index::*;
 mod _spo_wrapper;
 pub use self::_spo_wrapper::*;
 mod _ops_wrapper;
@@ -55,7 +57,10 @@ pub use self::_term_index_map_u::*;
 /// implements [`TermIndexMap`](../../term/index_map/trait.TermIndexMap.html),
 /// typically `u16` or `u32`.
 ///
-/// `F` must implement [`TermFactory`](../../term/factory/trait.TermFactory.html).
+/// `F` must implement [`TermFactory`](../../term/factory/trait.TermFactory.html),
+/// and its `TermData` must implement [`StableTermData`](../../term/trait.StableTermData.html)
+/// (which excludes [`SmallTermFactory`](../../term/factory/struct.SmallTermFactory.html);
+/// a `HashSet<[SmallTerm; 3]>` can be used instead).
 ///
 pub type GenericGraph<I, F> = HashGraph<TermIndexMapU<I, F>>;
 
@@ -110,3 +115,59 @@ pub mod sync {
     #[cfg(all(test, feature = "all_tests"))]
     sophia_api::test_graph_impl!(test_lightg, LightGraph);
 }
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::t;
+    use sophia_api::term::matcher::ANY;
+
+    #[test]
+    fn explain_fast_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("a"), &t("p"), &t("b"))?;
+        g.insert(&t("a"), &t("q"), &t("c"))?;
+        g.insert(&t("b"), &t("p"), &t("c"))?;
+
+        let e = g.explain_triples_matching(&t("a"), &ANY, &ANY);
+        assert_eq!(e.index, Some("s2p"));
+        assert!(!e.filtered);
+        let e = g.explain_triples_matching(&t("a"), &t("p"), &ANY);
+        assert_eq!(e.index, Some("sp2o"));
+        assert_eq!(e.covered, [true, true, false]);
+        let e = g.explain_triples_matching(&ANY, &t("p"), &t("c"));
+        assert_eq!(e.index, Some("po2s"));
+        // subject-object lookups are delegated to the unindexed HashGraph
+        let e = g.explain_triples_matching(&t("a"), &ANY, &t("c"));
+        assert!(e.is_full_scan());
+        assert!(e.filtered);
+        let e = g.explain_triples_matching(&ANY, &t("p"), &ANY);
+        assert!(e.is_full_scan());
+        assert!(e.filtered);
+        assert_eq!(e.to_string(), "full scan + filter, 0..=3 triples");
+        let e = g.explain_triples_matching(&ANY, &ANY, &ANY);
+        assert!(e.is_full_scan());
+        assert!(!e.filtered);
+        assert_eq!(e.estimate, (3, Some(3)));
+        let (a, b) = (t("a"), t("b"));
+        let e = g.explain_triples_matching(&[&a, &b, &a], &ANY, &ANY);
+        assert_eq!(e.candidates, Some(2));
+        assert_eq!(e.index, Some("s2p"));
+        assert_eq!(e.lookup, [true, false, false]);
+        Ok(())
+    }
+
+    #[test]
+    fn explain_light_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = LightGraph::new();
+        g.insert(&t("a"), &t("p"), &t("b"))?;
+        let e = g.explain_triples_matching(&t("a"), &t("p"), &ANY);
+        assert!(e.is_full_scan());
+        assert!(e.filtered);
+        Ok(())
+    }
+}
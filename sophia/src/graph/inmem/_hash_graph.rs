@@ -8,6 +8,7 @@ use crate::graph::indexed::IndexedGraph;
 use crate::graph::*;
 use crate::triple::stream::{StreamResult, TripleSource};
 use crate::triple::streaming_mode::{ByTermRefs, StreamedTriple};
+use sophia_api::term::matcher::TermMatcher;
 use sophia_api::term::TTerm;
 use sophia_term::factory::TermFactory;
 use sophia_term::index_map::TermIndexMap;
@@ -70,12 +71,25 @@ where
         }
     }
 
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.triples.reserve(additional);
+    }
+
     #[inline]
     fn shrink_to_fit(&mut self) {
         self.terms.shrink_to_fit();
         self.triples.shrink_to_fit();
     }
 
+    fn heap_size(&self) -> usize {
+        self.terms.heap_size() + self.triples.capacity() * std::mem::size_of::<[I::Index; 3]>()
+    }
+
+    fn dictionary_heap_size(&self) -> usize {
+        self.terms.heap_size()
+    }
+
     #[inline]
     fn get_index<T>(&self, t: &T) -> Option<Self::Index>
     where
@@ -149,6 +163,56 @@ where
             ))
         }))
     }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let si = self.get_index(s);
+        let pi = self.get_index(p);
+        let oi = self.get_index(o);
+        Ok(match (si, pi, oi) {
+            (Some(si), Some(pi), Some(oi)) => self.triples.contains(&[si, pi, oi]),
+            _ => false,
+        })
+    }
+
+    fn count_triples_matching<S, P, O>(&self, ms: &S, mp: &P, mo: &O) -> GResult<Self, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        if ms.is_any() && mp.is_any() && mo.is_any() {
+            return Ok(self.triples.len());
+        }
+        if let (Some(s), Some(p), Some(o)) = (ms.constant(), mp.constant(), mo.constant()) {
+            return self.contains(s, p, o).map(usize::from);
+        }
+        Ok(self.triples_matching(ms, mp, mo).count())
+    }
+
+    fn estimate_triples_matching<S, P, O>(&self, ms: &S, mp: &P, mo: &O) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let len = self.triples.len();
+        if ms.is_any() && mp.is_any() && mo.is_any() {
+            (len, Some(len))
+        } else if ms.constant().is_some() && mp.constant().is_some() && mo.constant().is_some() {
+            (0, Some(len.min(1)))
+        } else {
+            (0, Some(len))
+        }
+    }
 }
 
 impl<I> CollectibleGraph for HashGraph<I>
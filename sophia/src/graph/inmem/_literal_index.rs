@@ -0,0 +1,496 @@
+// this module is transparently re-exported by its parent `graph::inmem`
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{Bound, RangeBounds};
+
+use super::*;
+use crate::graph::indexed::{insert_in_index, remove_from_index};
+use crate::ns::xsd;
+use crate::triple::streaming_mode::{ByTermRefs, StreamedTriple};
+use sophia_api::graph::GTripleSource;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{TTerm, TermKind};
+use sophia_term::literal::datetime::{Date, DateTime};
+
+/// A [`GraphWrapper`](trait.GraphWrapper.html)
+/// maintaining an inverted index of the literals used as objects,
+/// in order to answer faceted search queries without scanning the whole graph:
+///
+/// * [`search_text`] finds string literals containing some words
+///   (e.g. subjects whose `foaf:name` contains "smith"),
+/// * [`search_numbers`] finds numeric literals in a given range,
+/// * [`search_dates`] finds `xsd:date` and `xsd:dateTime` literals in a given range.
+///
+/// The index is kept in sync with the wrapped graph through the mutation hooks of
+/// [`IndexedGraphWrapper`](trait.IndexedGraphWrapper.html).
+///
+/// Since it must be able to produce triples instead of the underlying graphs,
+/// it is limited to wrapping graphs whose triples are `[&Term<H>;3]`.
+///
+/// ```
+/// use sophia::graph::inmem::{LightGraph, LiteralIndexWrapper};
+/// use sophia::graph::MutableGraph;
+/// use sophia::ns::Namespace;
+/// use sophia::term::literal::convert::AsLiteral;
+/// use sophia::term::matcher::ANY;
+///
+/// let foaf = Namespace::new("http://xmlns.com/foaf/0.1/")?;
+/// let mut g = LiteralIndexWrapper::<LightGraph>::new();
+/// g.insert(&foaf.get("alice")?, &foaf.get("name")?, &"Alice Smith".as_literal())?;
+/// g.insert(&foaf.get("bob")?, &foaf.get("name")?, &"Bob Jones".as_literal())?;
+/// assert_eq!(g.search_text(&foaf.get("name")?, "smith").count(), 1);
+/// assert_eq!(g.search_text(&ANY, "SMITH alice").count(), 1);
+/// assert_eq!(g.search_numbers(&ANY, 0.0..).count(), 0);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`search_text`]: #method.search_text
+/// [`search_numbers`]: #method.search_numbers
+/// [`search_dates`]: #method.search_dates
+#[derive(Default)]
+pub struct LiteralIndexWrapper<T>
+where
+    T: IndexedGraph,
+{
+    wrapped: T,
+    /// The keys of every indexed literal, and the number of triples using it
+    literals: HashMap<T::Index, (LiteralKeys, usize)>,
+    words: HashMap<String, Vec<[T::Index; 3]>>,
+    numbers: BTreeMap<Number, Vec<[T::Index; 3]>>,
+    dates: BTreeMap<i128, Vec<[T::Index; 3]>>,
+}
+
+impl<T> LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Default,
+    T::Index: Default,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    /// Iter over the triples whose predicate matches `mp`,
+    /// and whose object is a string literal containing all the words of `text`.
+    ///
+    /// Words are sequences of alphanumeric characters, and are compared case-insensitively.
+    pub fn search_text<'s, M>(&'s self, mp: &'s M, text: &str) -> GTripleSource<'s, Self>
+    where
+        M: TermMatcher + ?Sized,
+    {
+        let words = tokenize(text);
+        let mut lists = words.iter().map(|w| self.words.get(w));
+        let first = match lists.next() {
+            Some(Some(first)) => first,
+            _ => return Box::new(std::iter::empty()),
+        };
+        let others = match lists
+            .map(|list| list.map(|list| list.iter().collect::<HashSet<_>>()))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(others) => others,
+            None => return Box::new(std::iter::empty()),
+        };
+        let found = first
+            .iter()
+            .filter(|t| others.iter().all(|set| set.contains(t)))
+            .copied()
+            .collect::<Vec<_>>();
+        self.make_triples(mp, found)
+    }
+
+    /// Iter over the triples whose predicate matches `mp`,
+    /// and whose object is a numeric literal whose value is in `range`.
+    pub fn search_numbers<'s, M, R>(&'s self, mp: &'s M, range: R) -> GTripleSource<'s, Self>
+    where
+        M: TermMatcher + ?Sized,
+        R: RangeBounds<f64>,
+    {
+        let range = (
+            map_bound(range.start_bound(), |n| Number(*n)),
+            map_bound(range.end_bound(), |n| Number(*n)),
+        );
+        let found = self.numbers.range(range).flat_map(|(_, ts)| ts).copied();
+        self.make_triples(mp, found.collect())
+    }
+
+    /// Iter over the triples whose predicate matches `mp`,
+    /// and whose object is an `xsd:date` or `xsd:dateTime` literal whose value is in `range`.
+    ///
+    /// Dates are considered to start at midnight,
+    /// and values without a timezone are considered to be in UTC.
+    pub fn search_dates<'s, M, R>(&'s self, mp: &'s M, range: R) -> GTripleSource<'s, Self>
+    where
+        M: TermMatcher + ?Sized,
+        R: RangeBounds<DateTime>,
+    {
+        let range = (
+            map_bound(range.start_bound(), date_key),
+            map_bound(range.end_bound(), date_key),
+        );
+        let found = self.dates.range(range).flat_map(|(_, ts)| ts).copied();
+        self.make_triples(mp, found.collect())
+    }
+
+    fn make_triples<'s, M>(
+        &'s self,
+        mp: &'s M,
+        found: Vec<[T::Index; 3]>,
+    ) -> GTripleSource<'s, Self>
+    where
+        M: TermMatcher + ?Sized,
+    {
+        Box::new(found.into_iter().filter_map(move |[si, pi, oi]| {
+            let p = self.wrapped.get_term(pi).unwrap();
+            if !mp.matches(p) {
+                return None;
+            }
+            let s = self.wrapped.get_term(si).unwrap();
+            let o = self.wrapped.get_term(oi).unwrap();
+            Some(Ok(StreamedTriple::by_term_refs(s, p, o)))
+        }))
+    }
+}
+
+impl<T> GraphWrapper for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    type Wrapped = T;
+
+    fn get_wrapped(&self) -> &T {
+        &self.wrapped
+    }
+
+    fn get_wrapped_mut(&mut self) -> &mut T {
+        &mut self.wrapped
+    }
+}
+
+impl<T> IndexedGraphWrapper<T> for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph,
+{
+    #[inline]
+    fn igw_wrap_empty(graph: T) -> Self {
+        LiteralIndexWrapper {
+            wrapped: graph,
+            literals: HashMap::default(),
+            words: HashMap::default(),
+            numbers: BTreeMap::default(),
+            dates: BTreeMap::default(),
+        }
+    }
+
+    fn igw_hook_insert_indexed(&mut self, modified: &Option<[T::Index; 3]>) {
+        if let Some(triple) = *modified {
+            let oi = triple[2];
+            if !self.literals.contains_key(&oi) {
+                // the object has just been inserted, so it is known to the wrapped graph
+                match LiteralKeys::new(self.wrapped.get_term(oi).unwrap()) {
+                    Some(keys) => self.literals.insert(oi, (keys, 0)),
+                    None => return,
+                };
+            }
+            let (keys, count) = self.literals.get_mut(&oi).unwrap();
+            *count += 1;
+            for word in &keys.words {
+                insert_in_index(&mut self.words, word.clone(), triple);
+            }
+            if let Some(number) = keys.number {
+                self.numbers.entry(number).or_default().push(triple);
+            }
+            if let Some(date) = keys.date {
+                self.dates.entry(date).or_default().push(triple);
+            }
+        }
+    }
+
+    fn igw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 3]>) {
+        if let Some(triple) = *modified {
+            let oi = triple[2];
+            let (keys, count) = match self.literals.get_mut(&oi) {
+                Some(entry) => entry,
+                None => return,
+            };
+            for word in &keys.words {
+                remove_from_index(&mut self.words, word.clone(), triple);
+            }
+            if let Some(number) = keys.number {
+                remove_from_btree(&mut self.numbers, number, triple);
+            }
+            if let Some(date) = keys.date {
+                remove_from_btree(&mut self.dates, date, triple);
+            }
+            *count -= 1;
+            if *count == 0 {
+                self.literals.remove(&oi);
+            }
+        }
+    }
+
+    #[inline]
+    fn igw_hook_shrink_to_fit(&mut self) {
+        self.literals.shrink_to_fit();
+        self.words.shrink_to_fit();
+    }
+
+    fn igw_heap_size(&self) -> usize {
+        use std::mem::size_of;
+        fn btree_heap_size<K, W>(bt: &BTreeMap<K, Vec<W>>) -> usize {
+            bt.values()
+                .map(|ws| size_of::<K>() + size_of::<Vec<W>>() + ws.capacity() * size_of::<W>())
+                .sum()
+        }
+        let keys: usize = self
+            .literals
+            .values()
+            .map(|(keys, _)| {
+                keys.words.capacity() * size_of::<String>()
+                    + keys.words.iter().map(String::capacity).sum::<usize>()
+            })
+            .sum();
+        self.literals.capacity() * (size_of::<T::Index>() + size_of::<(LiteralKeys, usize)>())
+            + keys
+            + index_heap_size(&self.words)
+            + self.words.keys().map(String::capacity).sum::<usize>()
+            + btree_heap_size(&self.numbers)
+            + btree_heap_size(&self.dates)
+    }
+}
+
+impl<T> Graph for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    impl_graph_for_wrapper!();
+}
+
+impl<T> IndexedGraph for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    impl_indexed_graph_for_wrapper!();
+}
+
+impl<T> CollectibleGraph for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    crate::impl_collectible_graph_for_indexed_graph!();
+}
+
+impl<T> MutableGraph for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+{
+    crate::impl_mutable_graph_for_indexed_graph!();
+}
+
+impl<T> SetGraph for LiteralIndexWrapper<T>
+where
+    T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
+    T: SetGraph,
+{
+}
+
+/// The keys under which a literal is indexed.
+struct LiteralKeys {
+    words: Vec<String>,
+    number: Option<Number>,
+    date: Option<i128>,
+}
+
+impl LiteralKeys {
+    fn new<TD: TermData>(term: &Term<TD>) -> Option<Self> {
+        if term.kind() != TermKind::Literal {
+            return None;
+        }
+        let value = term.value_raw().0;
+        let mut keys = LiteralKeys {
+            words: vec![],
+            number: None,
+            date: None,
+        };
+        let dt = term.datatype().unwrap();
+        if term.language().is_some() || xsd::string == dt {
+            keys.words = tokenize(value);
+        } else if NUMERIC_TYPES.iter().any(|t| **t == dt) {
+            keys.number = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| !n.is_nan())
+                .map(Number);
+        } else if xsd::dateTime == dt {
+            keys.date = value.trim().parse::<DateTime>().ok().map(|d| date_key(&d));
+        } else if xsd::date == dt {
+            keys.date = value
+                .trim()
+                .parse::<Date>()
+                .ok()
+                .map(|d| date_key(&d.start()));
+        }
+        Some(keys)
+    }
+}
+
+static NUMERIC_TYPES: [&sophia_api::term::SimpleIri; 16] = [
+    &xsd::integer,
+    &xsd::decimal,
+    &xsd::double,
+    &xsd::float,
+    &xsd::long,
+    &xsd::int,
+    &xsd::short,
+    &xsd::byte,
+    &xsd::nonNegativeInteger,
+    &xsd::positiveInteger,
+    &xsd::nonPositiveInteger,
+    &xsd::negativeInteger,
+    &xsd::unsignedLong,
+    &xsd::unsignedInt,
+    &xsd::unsignedShort,
+    &xsd::unsignedByte,
+];
+
+/// A non-NaN float, usable as a key of a `BTreeMap`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Number(f64);
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // -0.0 and 0.0 must be equal, as they are in ranges
+        (self.0 + 0.0).total_cmp(&(other.0 + 0.0))
+    }
+}
+
+/// The lower-cased words of `text`, without duplicates.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+/// Nanoseconds since 1970-01-01T00:00:00Z, assuming UTC if `d` has no timezone.
+fn date_key(d: &DateTime) -> i128 {
+    let epoch = DateTime::new(1970, 1, 1, 0, 0, 0, 0, d.timezone().map(|_| 0)).unwrap();
+    d.duration_since(&epoch).unwrap().nanoseconds()
+}
+
+fn map_bound<T, U, F: Fn(&T) -> U>(bound: Bound<&T>, f: F) -> Bound<U> {
+    match bound {
+        Bound::Included(x) => Bound::Included(f(x)),
+        Bound::Excluded(x) => Bound::Excluded(f(x)),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn remove_from_btree<K: Ord, W: Eq>(map: &mut BTreeMap<K, Vec<W>>, k: K, w: W) {
+    if let Some(ws) = map.get_mut(&k) {
+        if let Some(i) = ws.iter().position(|w2| *w2 == w) {
+            ws.swap_remove(i);
+        }
+        if ws.is_empty() {
+            map.remove(&k);
+        }
+    }
+}
+
+#[cfg(test)]
+type LiteralIndexGraph = LiteralIndexWrapper<LightGraph>;
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_literal_index, LiteralIndexGraph);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use crate::triple::stream::TripleSource;
+    use crate::triple::Triple;
+    use sophia_api::term::matcher::ANY;
+    use sophia_term::literal::convert::AsLiteral;
+
+    fn subjects(triples: GTripleSource<'_, LiteralIndexGraph>) -> Vec<String> {
+        let mut subjects = triples
+            .map(|t| t.unwrap().s().value().to_string())
+            .collect::<Vec<_>>();
+        subjects.sort();
+        subjects
+    }
+
+    #[test]
+    fn search() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g: LiteralIndexGraph = turtle::parse_str(
+            r#"
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            <tag:alice> <tag:name> "Alice Smith"@en; <tag:age> 42;
+                <tag:born> "1980-05-01"^^xsd:date.
+            <tag:bob> <tag:name> "Bob Smith-Jones"; <tag:nick> "smith"; <tag:age> 17.5;
+                <tag:born> "2005-01-01T12:00:00Z"^^xsd:dateTime.
+            <tag:carol> <tag:name> "Carol"; <tag:age> "-3"^^xsd:int; <tag:born> "not a date"^^xsd:date.
+        "#,
+        )
+        .collect_triples()?;
+
+        assert_eq!(
+            subjects(g.search_text(&t("name"), "SMITH")),
+            ["tag:alice", "tag:bob"]
+        );
+        assert_eq!(
+            subjects(g.search_text(&t("name"), "jones smith")),
+            ["tag:bob"]
+        );
+        assert_eq!(g.search_text(&ANY, "smith").count(), 3);
+        assert_eq!(g.search_text(&ANY, "smith unknown").count(), 0);
+        assert_eq!(g.search_text(&ANY, "").count(), 0);
+
+        assert_eq!(
+            subjects(g.search_numbers(&ANY, 17.5..)),
+            ["tag:alice", "tag:bob"]
+        );
+        assert_eq!(subjects(g.search_numbers(&t("age"), ..=0.0)), ["tag:carol"]);
+        assert_eq!(
+            subjects(g.search_numbers(&t("age"), 18.0..42.0)),
+            Vec::<String>::new()
+        );
+
+        let y2000: DateTime = "2000-01-01T00:00:00Z".parse()?;
+        assert_eq!(subjects(g.search_dates(&ANY, y2000..)), ["tag:bob"]);
+        assert_eq!(subjects(g.search_dates(&t("born"), ..y2000)), ["tag:alice"]);
+
+        // the index is kept in sync with the graph
+        g.remove(&t("bob"), &t("name"), &"Bob Smith-Jones".as_literal())?;
+        g.insert(&t("carol"), &t("age"), &t("unknown"))?;
+        g.insert(&t("dan"), &t("name"), &"Dan Smith".as_literal())?;
+        assert_eq!(
+            subjects(g.search_text(&t("name"), "smith")),
+            ["tag:alice", "tag:dan"]
+        );
+        g.remove_matching(&t("alice"), &ANY, &ANY)?;
+        assert_eq!(
+            subjects(g.search_numbers(&ANY, ..)),
+            ["tag:bob", "tag:carol"]
+        );
+        assert_eq!(g.search_dates(&ANY, ..).count(), 1);
+        Ok(())
+    }
+}
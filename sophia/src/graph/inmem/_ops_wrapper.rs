@@ -4,8 +4,10 @@ use std::collections::{HashMap, HashSet};
 use std::iter::empty;
 
 use super::*;
+use crate::graph::indexed::refine_estimate;
 use crate::triple::streaming_mode::{ByTermRefs, StreamedTriple};
-use sophia_api::graph::{GResultTermSet, GTripleSource};
+use sophia_api::graph::{GResult, GResultTermSet, GTripleSource};
+use sophia_api::term::matcher::TermMatcher;
 use sophia_api::term::TTerm;
 
 /// A [`GraphWrapper`](trait.GraphWrapper.html)
@@ -37,6 +39,38 @@ where
     }
 }
 
+impl<T> OpsWrapper<T>
+where
+    T: IndexedGraph,
+{
+    /// The number of triples with the given object.
+    fn count_o<TO>(&self, o: &TO) -> usize
+    where
+        TO: TTerm + ?Sized,
+    {
+        match self
+            .wrapped
+            .get_index(o)
+            .and_then(|oi| self.o2p.get(&oi).map(|pis| (oi, pis)))
+        {
+            Some((oi, pis)) => pis.iter().map(|pi| self.po2s[&[*pi, oi]].len()).sum(),
+            None => 0,
+        }
+    }
+
+    /// The number of triples with the given object and predicate.
+    fn count_op<TO, TP>(&self, o: &TO, p: &TP) -> usize
+    where
+        TO: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        match (self.wrapped.get_index(o), self.wrapped.get_index(p)) {
+            (Some(oi), Some(pi)) => self.po2s.get(&[pi, oi]).map_or(0, Vec::len),
+            _ => 0,
+        }
+    }
+}
+
 impl<T> GraphWrapper for OpsWrapper<T>
 where
     T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
@@ -95,6 +129,63 @@ where
         Box::new(empty())
     }
 
+    fn gw_indexed_positions(&self) -> [bool; 3] {
+        let mut indexed = self.wrapped.indexed_positions();
+        indexed[2] = true;
+        indexed
+    }
+
+    fn gw_index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        match bound {
+            [false, false, true] => Some(("o2p", bound)),
+            [false, true, true] => Some(("po2s", bound)),
+            _ => self.wrapped.index_for(bound),
+        }
+    }
+
+    fn gw_count_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> GResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        if ms.is_any() {
+            match (mo.constant(), mp.constant()) {
+                (Some(o), Some(p)) => return Ok(self.count_op(o, p)),
+                (Some(o), None) if mp.is_any() => return Ok(self.count_o(o)),
+                _ => (),
+            }
+        }
+        self.wrapped.count_triples_matching(ms, mp, mo)
+    }
+
+    fn gw_estimate_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let estimate = self.wrapped.estimate_triples_matching(ms, mp, mo);
+        match (mo.constant(), mp.constant()) {
+            (Some(o), Some(p)) => refine_estimate(estimate, self.count_op(o, p), ms.is_any()),
+            (Some(o), None) => {
+                let exact = mp.is_any() && ms.is_any();
+                refine_estimate(estimate, self.count_o(o), exact)
+            }
+            _ => estimate,
+        }
+    }
+
     fn gw_objects(&self) -> GResultTermSet<Self::Wrapped> {
         let objects: HashSet<_> = self
             .o2p
@@ -141,6 +232,10 @@ where
         self.o2p.shrink_to_fit();
         self.po2s.shrink_to_fit();
     }
+
+    fn igw_heap_size(&self) -> usize {
+        index_heap_size(&self.o2p) + index_heap_size(&self.po2s)
+    }
 }
 
 impl<T> Graph for OpsWrapper<T>
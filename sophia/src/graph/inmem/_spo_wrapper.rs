@@ -4,8 +4,10 @@ use std::collections::{HashMap, HashSet};
 use std::iter::empty;
 
 use super::*;
+use crate::graph::indexed::refine_estimate;
 use crate::triple::streaming_mode::{ByTermRefs, StreamedTriple};
-use sophia_api::graph::{GResultTermSet, GTripleSource};
+use sophia_api::graph::{GResult, GResultTermSet, GTripleSource};
+use sophia_api::term::matcher::TermMatcher;
 use sophia_api::term::TTerm;
 
 /// A [`GraphWrapper`](trait.GraphWrapper.html)
@@ -37,6 +39,38 @@ where
     }
 }
 
+impl<T> SpoWrapper<T>
+where
+    T: IndexedGraph,
+{
+    /// The number of triples with the given subject.
+    fn count_s<TS>(&self, s: &TS) -> usize
+    where
+        TS: TTerm + ?Sized,
+    {
+        match self
+            .wrapped
+            .get_index(s)
+            .and_then(|si| self.s2p.get(&si).map(|pis| (si, pis)))
+        {
+            Some((si, pis)) => pis.iter().map(|pi| self.sp2o[&[si, *pi]].len()).sum(),
+            None => 0,
+        }
+    }
+
+    /// The number of triples with the given subject and predicate.
+    fn count_sp<TS, TP>(&self, s: &TS, p: &TP) -> usize
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        match (self.wrapped.get_index(s), self.wrapped.get_index(p)) {
+            (Some(si), Some(pi)) => self.sp2o.get(&[si, pi]).map_or(0, Vec::len),
+            _ => 0,
+        }
+    }
+}
+
 impl<T> GraphWrapper for SpoWrapper<T>
 where
     T: IndexedGraph + Graph<Triple = ByTermRefs<Term<<T as IndexedGraph>::TermData>>>,
@@ -95,6 +129,63 @@ where
         Box::new(empty())
     }
 
+    fn gw_indexed_positions(&self) -> [bool; 3] {
+        let mut indexed = self.wrapped.indexed_positions();
+        indexed[0] = true;
+        indexed
+    }
+
+    fn gw_index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        match bound {
+            [true, false, false] => Some(("s2p", bound)),
+            [true, true, false] => Some(("sp2o", bound)),
+            _ => self.wrapped.index_for(bound),
+        }
+    }
+
+    fn gw_count_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> GResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        if mo.is_any() {
+            match (ms.constant(), mp.constant()) {
+                (Some(s), Some(p)) => return Ok(self.count_sp(s, p)),
+                (Some(s), None) if mp.is_any() => return Ok(self.count_s(s)),
+                _ => (),
+            }
+        }
+        self.wrapped.count_triples_matching(ms, mp, mo)
+    }
+
+    fn gw_estimate_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        let estimate = self.wrapped.estimate_triples_matching(ms, mp, mo);
+        match (ms.constant(), mp.constant()) {
+            (Some(s), Some(p)) => refine_estimate(estimate, self.count_sp(s, p), mo.is_any()),
+            (Some(s), None) => {
+                let exact = mp.is_any() && mo.is_any();
+                refine_estimate(estimate, self.count_s(s), exact)
+            }
+            _ => estimate,
+        }
+    }
+
     fn gw_subjects(&self) -> GResultTermSet<Self::Wrapped> {
         let subjects: HashSet<_> = self
             .s2p
@@ -141,6 +232,10 @@ where
         self.s2p.shrink_to_fit();
         self.sp2o.shrink_to_fit();
     }
+
+    fn igw_heap_size(&self) -> usize {
+        index_heap_size(&self.s2p) + index_heap_size(&self.sp2o)
+    }
 }
 
 impl<T> Graph for SpoWrapper<T>
@@ -2,12 +2,16 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
 
+use sophia_api::term::{RawValue, TTerm};
 use sophia_term::factory::{FTerm, TermFactory};
 use sophia_term::index_map::TermIndexMap;
 use sophia_term::*;
 
 /// An in-memory implementation of [`TermIndexMap`](../../term/index_map/trait.TermIndexMap.html)
 /// with unsigned integers as indices.
+///
+/// The data of the terms produced by `F` must implement
+/// [`StableTermData`](../../term/trait.StableTermData.html).
 pub struct TermIndexMapU<I, F>
 where
     F: TermFactory,
@@ -16,6 +20,7 @@ where
     i2t: Vec<Option<FTerm<F>>>,
     i2c: Vec<I>,
     t2i: HashMap<StaticTerm, I>,
+    data_len: usize,
     // factory is used to make new terms (used by make_term)
     // i2t (index to term) maps
     // - each used index to Some(Term)
@@ -24,6 +29,7 @@ where
     // - each used index to its ref counter (used by inc_ref and dec_ref)
     // - each free index to the next free index
     // t2i (term to index) maps each term to its index
+    // data_len is the total length of the textual data of the terms in i2t
     //
     // 0 is the null_index (index not mapped to any Term), so
     // - i2t[0] is initialized to None but never used,
@@ -37,6 +43,8 @@ where
 // while in fact they have a shorter lifetime.
 // However, we ensure that keys do not exist longer than the data they borrow
 // (inside i2t)...
+// This also requires that this data does not move when i2t is reallocated,
+// hence the StableTermData bound.
 
 impl<I, F> TermIndexMapU<I, F>
 where
@@ -59,6 +67,7 @@ where
             i2c: vec![I::ONE],
             i2t: vec![None],
             t2i: HashMap::default(),
+            data_len: 0,
         }
     }
 }
@@ -90,6 +99,7 @@ impl<T, F> TermIndexMap for TermIndexMapU<T, F>
 where
     T: Unsigned,
     F: TermFactory + Default,
+    F::TermData: StableTermData,
 {
     type Index = T;
     type Factory = F;
@@ -110,6 +120,7 @@ where
         }
         let i = self.next_free();
         self.t2i.insert(rt, i);
+        self.data_len += data_len(&t);
         let i = i.as_usize();
         if i == self.i2t.len() {
             self.inc_next_free();
@@ -149,18 +160,47 @@ where
         if self.i2c[i] == T::ZERO {
             let t: FTerm<F> = self.i2t[i].take().unwrap();
             self.t2i.remove(unsafe { &fake_static(&t) });
+            self.data_len -= data_len(&t);
             self.i2c[i] = self.next_free();
             self.set_next_free(T::from_usize(i));
         }
     }
 
     fn shrink_to_fit(&mut self) {
+        // drop the free slots at the end of i2t and i2c,
+        // and rebuild the list of the remaining free slots
+        let used = self.i2t.iter().rposition(Option::is_some).unwrap_or(0) + 1;
+        if used < self.i2t.len() {
+            self.i2t.truncate(used);
+            self.i2c.truncate(used);
+            let mut next = T::from_usize(used);
+            for i in (1..used).rev() {
+                if self.i2t[i].is_none() {
+                    self.i2c[i] = next;
+                    next = T::from_usize(i);
+                }
+            }
+            self.set_next_free(next);
+        }
         self.factory.shrink_to_fit();
         self.i2c.shrink_to_fit();
         self.i2t.shrink_to_fit();
         self.t2i.shrink_to_fit();
         debug_assert_eq!(self.i2c.len(), self.i2t.len());
     }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.t2i.len()
+    }
+
+    fn heap_size(&self) -> usize {
+        use std::mem::size_of;
+        self.i2t.capacity() * size_of::<Option<FTerm<F>>>()
+            + self.i2c.capacity() * size_of::<T>()
+            + self.t2i.capacity() * (size_of::<StaticTerm>() + size_of::<T>())
+            + self.data_len
+    }
 }
 
 /// This trait is used by [`TermIndexMapU`](struct.TermIndexMapU.html)
@@ -205,6 +245,19 @@ impl_unsigned_for!(u16);
 impl_unsigned_for!(u32);
 impl_unsigned_for!(u64);
 
+/// The length of the textual data stored in `t`.
+///
+/// The datatype of language-tagged literals is implicit, so it is not counted.
+fn data_len<T: TTerm + ?Sized>(t: &T) -> usize {
+    let raw_len = |raw: RawValue| raw.0.len() + raw.1.map_or(0, str::len);
+    let extra = match (t.language(), t.datatype()) {
+        (Some(tag), _) => tag.len(),
+        (None, Some(dt)) => raw_len(dt.value_raw()),
+        (None, None) => 0,
+    };
+    raw_len(t.value_raw()) + extra
+}
+
 /// Unsafely converts a term into a StaticTerm.
 /// This is to be used *only* when we can guarantee that the produced StaticTerm
 /// will not outlive the source term.
@@ -299,4 +352,71 @@ mod test {
         assert_eq!(ti.i2t.len(), 5);
         assert_eq!(ti.next_free(), 5);
     }
+
+    #[test]
+    fn test_term_index_shrink() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+
+        use sophia_api::ns::rdf;
+
+        for t in &[rdf::subject, rdf::predicate, rdf::object, rdf::type_] {
+            ti.make_index(&t.into());
+        }
+        assert_eq!(ti.len(), 4);
+        ti.dec_ref(2);
+        ti.dec_ref(4);
+        ti.dec_ref(3);
+        assert_eq!(ti.len(), 1);
+        assert_eq!(ti.i2t.len(), 5);
+        let before = ti.heap_size();
+
+        ti.shrink_to_fit();
+        assert_eq!(ti.len(), 1);
+        assert_eq!(ti.i2t.len(), 2);
+        assert_eq!(ti.i2c.len(), 2);
+        assert_eq!(ti.next_free(), 2);
+        assert!(ti.heap_size() < before);
+        assert_eq!(ti.get_index(&rdf::subject.into()), Some(1));
+
+        assert_eq!(ti.make_index(&rdf::object.into()), 2);
+        assert_eq!(ti.next_free(), 3);
+        assert_eq!(ti.i2t.len(), 3);
+    }
+
+    #[test]
+    fn test_term_index_data_len() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+        let empty = ti.heap_size();
+        let long = "x".repeat(1_000_000);
+        let lit = RefTerm::new_literal_dt_unchecked(&long[..], iri::Iri::new_unchecked("tag:dt"));
+        let i = ti.make_index(&lit);
+        assert_eq!(ti.data_len, 1_000_006);
+        assert!(ti.heap_size() > empty + 1_000_000);
+        ti.make_index(&lit);
+        assert_eq!(ti.data_len, 1_000_006);
+        ti.dec_ref(i);
+        ti.dec_ref(i);
+        assert_eq!(ti.data_len, 0);
+    }
+
+    #[test]
+    fn test_term_index_shrink_keeps_holes() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+
+        use sophia_api::ns::rdf;
+
+        for t in &[rdf::subject, rdf::predicate, rdf::object, rdf::type_] {
+            ti.make_index(&t.into());
+        }
+        ti.dec_ref(1);
+        ti.dec_ref(3);
+        ti.dec_ref(4);
+        ti.shrink_to_fit();
+        assert_eq!(ti.i2t.len(), 3);
+        assert_eq!(ti.next_free(), 1);
+        assert_eq!(ti.i2c[1], 3);
+        assert_eq!(ti.make_index(&rdf::type_.into()), 1);
+        assert_eq!(ti.make_index(&rdf::object.into()), 3);
+        assert_eq!(ti.make_index(&rdf::subject.into()), 4);
+    }
 }
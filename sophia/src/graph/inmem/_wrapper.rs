@@ -2,6 +2,7 @@
 
 use super::*;
 use sophia_api::graph::{GResult, GResultTermSet, GTerm, GTripleSource};
+use sophia_api::term::matcher::TermMatcher;
 use sophia_api::term::TTerm;
 use std::hash::Hash;
 
@@ -124,6 +125,50 @@ pub trait GraphWrapper {
         self.get_wrapped().contains(s, p, o)
     }
 
+    #[inline]
+    /// Mimmic the [`count_triples_matching`](../trait.Graph.html#method.count_triples_matching) method.
+    fn gw_count_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> GResult<Self::Wrapped, usize>
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        self.get_wrapped().count_triples_matching(ms, mp, mo)
+    }
+
+    #[inline]
+    /// Mimmic the [`estimate_triples_matching`](../trait.Graph.html#method.estimate_triples_matching) method.
+    fn gw_estimate_triples_matching<S, P, O>(
+        &self,
+        ms: &S,
+        mp: &P,
+        mo: &O,
+    ) -> (usize, Option<usize>)
+    where
+        S: TermMatcher + ?Sized,
+        P: TermMatcher + ?Sized,
+        O: TermMatcher + ?Sized,
+    {
+        self.get_wrapped().estimate_triples_matching(ms, mp, mo)
+    }
+
+    #[inline]
+    /// Mimmic the [`indexed_positions`](../trait.Graph.html#method.indexed_positions) method.
+    fn gw_indexed_positions(&self) -> [bool; 3] {
+        self.get_wrapped().indexed_positions()
+    }
+
+    #[inline]
+    /// Mimmic the [`index_for`](../trait.Graph.html#method.index_for) method.
+    fn gw_index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+        self.get_wrapped().index_for(bound)
+    }
+
     #[inline]
     /// Mimmic the [`subjects`](../trait.Graph.html#method.subjects) method.
     fn gw_subjects(&self) -> GResultTermSet<Self::Wrapped>
@@ -210,6 +255,10 @@ macro_rules! impl_graph_for_wrapper {
             $crate::graph::inmem::GraphWrapper::gw_triples(self)
         }
         #[inline]
+        fn is_set(&self) -> bool {
+            $crate::graph::Graph::is_set($crate::graph::inmem::GraphWrapper::get_wrapped(self))
+        }
+        #[inline]
         fn triples_with_s<'s_, TS_>(
             &'s_ self,
             s: &'s_ TS_,
@@ -305,6 +354,46 @@ macro_rules! impl_graph_for_wrapper {
             $crate::graph::inmem::GraphWrapper::gw_contains(self, s, p, o)
         }
 
+        #[inline]
+        fn count_triples_matching<S_, P_, O_>(
+            &self,
+            ms: &S_,
+            mp: &P_,
+            mo: &O_,
+        ) -> $crate::graph::GResult<Self, usize>
+        where
+            S_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            P_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            O_: sophia_api::term::matcher::TermMatcher + ?Sized,
+        {
+            $crate::graph::inmem::GraphWrapper::gw_count_triples_matching(self, ms, mp, mo)
+        }
+
+        #[inline]
+        fn estimate_triples_matching<S_, P_, O_>(
+            &self,
+            ms: &S_,
+            mp: &P_,
+            mo: &O_,
+        ) -> (usize, Option<usize>)
+        where
+            S_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            P_: sophia_api::term::matcher::TermMatcher + ?Sized,
+            O_: sophia_api::term::matcher::TermMatcher + ?Sized,
+        {
+            $crate::graph::inmem::GraphWrapper::gw_estimate_triples_matching(self, ms, mp, mo)
+        }
+
+        #[inline]
+        fn indexed_positions(&self) -> [bool; 3] {
+            $crate::graph::inmem::GraphWrapper::gw_indexed_positions(self)
+        }
+
+        #[inline]
+        fn index_for(&self, bound: [bool; 3]) -> Option<(&'static str, [bool; 3])> {
+            $crate::graph::inmem::GraphWrapper::gw_index_for(self, bound)
+        }
+
         #[inline]
         fn subjects(
             &self,
@@ -407,6 +496,11 @@ where
     /// Hook to be executed at the end of
     /// [`IndexedGraph::shrink_to_fit`](../indexed/trait.IndexedGraph.html#tymethod.shrink_to_fit).
     fn igw_hook_shrink_to_fit(&mut self);
+
+    /// Return an estimate of the heap memory (in bytes) used by this wrapper,
+    /// in addition to the wrapped graph;
+    /// used by [`IndexedGraph::heap_size`](../indexed/trait.IndexedGraph.html#tymethod.heap_size).
+    fn igw_heap_size(&self) -> usize;
 }
 
 /// Defines the implementation of [`IndexedGraph`] for [`GraphWrapper`] around another [`IndexedGraph`].
@@ -424,12 +518,27 @@ macro_rules! impl_indexed_graph_for_wrapper {
             Self::igw_wrap_empty(T::with_capacity(capacity))
         }
 
+        #[inline]
+        fn reserve(&mut self, additional: usize) {
+            self.get_wrapped_mut().reserve(additional);
+        }
+
         #[inline]
         fn shrink_to_fit(&mut self) {
             self.get_wrapped_mut().shrink_to_fit();
             self.igw_hook_shrink_to_fit();
         }
 
+        #[inline]
+        fn heap_size(&self) -> usize {
+            self.get_wrapped().heap_size() + self.igw_heap_size()
+        }
+
+        #[inline]
+        fn dictionary_heap_size(&self) -> usize {
+            self.get_wrapped().dictionary_heap_size()
+        }
+
         #[inline]
         fn get_index<U_>(&self, t: &U_) -> Option<Self::Index>
         where
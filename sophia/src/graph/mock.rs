@@ -0,0 +1,352 @@
+//! A [`Graph`] with failure injection, for testing error handling.
+//!
+//! Code generic over [`Graph`] or [`MutableGraph`] rarely sees errors
+//! when tested with in-memory graphs, whose error type is `Infallible`.
+//! A [`MockGraph`] stores its triples in memory,
+//! but can be configured to fail on the Nth insertion or removal,
+//! to return an error while its triples are iterated,
+//! or to delay every operation (to simulate a slow store).
+//! It also counts the operations performed on it.
+//!
+//! Without any failure configured, it behaves as a regular in-memory graph.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{mock::{MockError, MockGraph}, Graph, MutableGraph};
+//! use sophia::ns::{rdf, rdfs};
+//!
+//! let mut g = MockGraph::new();
+//! g.set_insert_failure(Some(2));
+//! g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+//! let err = g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class).unwrap_err();
+//! assert_eq!(err, MockError::Insertion(2));
+//! assert_eq!(g.len(), 1);
+//!
+//! g.set_iteration_failure(Some(0));
+//! assert!(g.triples().next().unwrap().is_err());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`MutableGraph`]: ../trait.MutableGraph.html
+//! [`MockGraph`]: struct.MockGraph.html
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use sophia_api::graph::{CollectibleGraph, GTripleSource, Graph, MGResult, MutableGraph, SetGraph};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::{ByRef, StreamedTriple};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The errors injected by a [`MockGraph`](struct.MockGraph.html).
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum MockError {
+    /// Injected failure on the given insertion (counting from 1).
+    #[error("Injected failure on insertion #{0}")]
+    Insertion(usize),
+    /// Injected failure on the given removal (counting from 1).
+    #[error("Injected failure on removal #{0}")]
+    Removal(usize),
+    /// Injected failure after iterating over the given number of triples.
+    #[error("Injected failure after iterating over {0} triples")]
+    Iteration(usize),
+}
+
+/// An in-memory graph with failure injection.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct MockGraph {
+    triples: Vec<[BoxTerm; 3]>,
+    insert_failure: Option<usize>,
+    remove_failure: Option<usize>,
+    iteration_failure: Option<usize>,
+    delay: Option<Duration>,
+    insertions: usize,
+    removals: usize,
+    iterations: Cell<usize>,
+}
+
+impl MockGraph {
+    /// Construct an empty graph, with no failure configured.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of triples in this graph.
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Whether this graph contains no triple.
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// The insertion (counting from 1) from which insertions fail, if any.
+    pub fn insert_failure(&self) -> Option<usize> {
+        self.insert_failure
+    }
+
+    /// Make the `n`-th insertion (counting from 1), and all subsequent ones, fail;
+    /// or no insertion fail if `n` is `None`.
+    ///
+    /// Insertions are counted from the creation of the graph
+    /// (see [`insertions`](#method.insertions)),
+    /// including insertions of triples already present.
+    pub fn set_insert_failure(&mut self, n: Option<usize>) -> &mut Self {
+        self.insert_failure = n;
+        self
+    }
+
+    /// The removal (counting from 1) from which removals fail, if any.
+    pub fn remove_failure(&self) -> Option<usize> {
+        self.remove_failure
+    }
+
+    /// Make the `n`-th removal (counting from 1), and all subsequent ones, fail;
+    /// or no removal fail if `n` is `None`.
+    pub fn set_remove_failure(&mut self, n: Option<usize>) -> &mut Self {
+        self.remove_failure = n;
+        self
+    }
+
+    /// The number of triples after which iterations fail, if any.
+    pub fn iteration_failure(&self) -> Option<usize> {
+        self.iteration_failure
+    }
+
+    /// Make every iteration over the triples of this graph
+    /// (including by `triples_matching` and `contains`)
+    /// yield an error after `n` triples (or after all triples, if there are less than `n`);
+    /// or no iteration fail if `n` is `None`.
+    pub fn set_iteration_failure(&mut self, n: Option<usize>) -> &mut Self {
+        self.iteration_failure = n;
+        self
+    }
+
+    /// The delay applied to every operation, if any.
+    pub fn delay(&self) -> Option<Duration> {
+        self.delay
+    }
+
+    /// Make every operation (insertion, removal, and start of an iteration)
+    /// sleep for the given duration.
+    pub fn set_delay(&mut self, delay: Option<Duration>) -> &mut Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The number of insertions attempted on this graph (including failed ones).
+    pub fn insertions(&self) -> usize {
+        self.insertions
+    }
+
+    /// The number of removals attempted on this graph (including failed ones).
+    pub fn removals(&self) -> usize {
+        self.removals
+    }
+
+    /// The number of iterations started on this graph.
+    pub fn iterations(&self) -> usize {
+        self.iterations.get()
+    }
+
+    fn wait(&self) {
+        if let Some(delay) = self.delay {
+            std::thread::sleep(delay);
+        }
+    }
+
+    fn position<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> Option<usize>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let triple = [BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)];
+        self.triples.iter().position(|t| t == &triple)
+    }
+}
+
+impl Graph for MockGraph {
+    type Triple = ByRef<[BoxTerm; 3]>;
+    type Error = MockError;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.wait();
+        self.iterations.set(self.iterations.get() + 1);
+        let ok = self.triples.iter().map(|t| Ok(StreamedTriple::by_ref(t)));
+        match self.iteration_failure {
+            None => Box::new(ok),
+            Some(n) => {
+                let n = n.min(self.triples.len());
+                Box::new(
+                    ok.take(n)
+                        .chain(std::iter::once(Err(MockError::Iteration(n)))),
+                )
+            }
+        }
+    }
+
+    fn is_set(&self) -> bool {
+        true
+    }
+}
+
+impl CollectibleGraph for MockGraph {
+    fn from_triple_source<TS: TripleSource>(
+        triples: TS,
+    ) -> StreamResult<Self, TS::Error, MockError> {
+        let mut g = MockGraph::new();
+        g.insert_all(triples).map(|_| g)
+    }
+}
+
+impl MutableGraph for MockGraph {
+    type MutationError = MockError;
+
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.wait();
+        self.insertions += 1;
+        if self.insert_failure.is_some_and(|n| self.insertions >= n) {
+            return Err(MockError::Insertion(self.insertions));
+        }
+        if self.position(s, p, o).is_some() {
+            return Ok(false);
+        }
+        self.triples
+            .push([BoxTerm::copy(s), BoxTerm::copy(p), BoxTerm::copy(o)]);
+        Ok(true)
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.wait();
+        self.removals += 1;
+        if self.remove_failure.is_some_and(|n| self.removals >= n) {
+            return Err(MockError::Removal(self.removals));
+        }
+        match self.position(s, p, o) {
+            None => Ok(false),
+            Some(i) => {
+                self.triples.remove(i);
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl SetGraph for MockGraph {}
+
+#[cfg(test)]
+sophia_api::test_graph_impl!(test_mock, MockGraph);
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::turtle;
+    use sophia_api::ns::{rdf, rdfs};
+    use sophia_api::term::matcher::ANY;
+    use sophia_api::triple::stream::StreamError;
+    use std::time::Instant;
+
+    #[test]
+    fn insert_failure() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = MockGraph::new();
+        g.set_insert_failure(Some(3));
+        assert!(g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert!(!g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert_eq!(
+            g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class),
+            Err(MockError::Insertion(3))
+        );
+        assert_eq!(
+            g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class),
+            Err(MockError::Insertion(4))
+        );
+        assert_eq!(g.insertions(), 4);
+        assert_eq!(g.len(), 1);
+
+        // errors are propagated by insert_all
+        let ttl = "<tag:a> <tag:b> <tag:c>, <tag:d>.";
+        let mut g = MockGraph::new();
+        g.set_insert_failure(Some(2));
+        let res = g.insert_all(turtle::parse_str(ttl));
+        assert!(matches!(
+            res,
+            Err(StreamError::SinkError(MockError::Insertion(2)))
+        ));
+        assert_eq!(g.len(), 1);
+
+        g.set_insert_failure(None);
+        assert_eq!(g.insert_all(turtle::parse_str(ttl))?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_failure() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = MockGraph::new();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+        g.set_remove_failure(Some(2));
+        assert!(g.remove(&rdf::type_, &rdf::type_, &rdf::Property)?);
+        assert_eq!(
+            g.remove(&rdfs::Class, &rdf::type_, &rdfs::Class),
+            Err(MockError::Removal(2))
+        );
+        assert_eq!(g.removals(), 2);
+        assert_eq!(g.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn iteration_failure() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = MockGraph::new();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        g.insert(&rdfs::Class, &rdf::type_, &rdfs::Class)?;
+        g.set_iteration_failure(Some(1));
+        let results: Vec<_> = g.triples().map(|r| r.map(|_| ())).collect();
+        assert_eq!(results, vec![Ok(()), Err(MockError::Iteration(1))]);
+        assert_eq!(
+            g.contains(&rdfs::Class, &rdf::type_, &rdfs::Class),
+            Err(MockError::Iteration(1))
+        );
+        assert!(g
+            .triples_matching(&ANY, &rdf::type_, &ANY)
+            .any(|r| r.is_err()));
+        // more than the number of triples: the error comes at the end
+        g.set_iteration_failure(Some(10));
+        assert_eq!(g.triples().count(), 3);
+        assert!(g.triples().last().unwrap().is_err());
+        assert_eq!(g.iterations(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn delay() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = MockGraph::new();
+        g.set_delay(Some(Duration::from_millis(20)));
+        let start = Instant::now();
+        g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+        assert_eq!(g.triples().count(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        Ok(())
+    }
+}
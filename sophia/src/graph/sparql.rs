@@ -0,0 +1,459 @@
+//! A read-only [`Graph`] backed by a remote SPARQL endpoint.
+//!
+//! This module is only available with the `http` feature.
+//!
+//! [`SparqlGraph`] translates each call to [`triples`] and `triples_with_*`
+//! (and hence [`triples_matching`] and [`contains`])
+//! into a SPARQL SELECT query sent to the endpoint,
+//! and reads the results in the [TSV results format].
+//! The results of the most recently used queries are kept in a LRU cache,
+//! so that repeated calls do not hit the network again.
+//!
+//! Blank nodes returned by the endpoint are only meaningful in the scope of a query,
+//! so a pattern containing a blank node never matches anything.
+//!
+//! Besides, [`construct`] and [`describe`] send CONSTRUCT and DESCRIBE queries
+//! to the endpoint, and return the resulting triples as a [`TripleSource`],
+//! parsed as they are received (and not cached).
+//! They can therefore be piped into a serializer or a graph
+//! without materializing the whole result in memory.
+//!
+//! # Example
+//! ```no_run
+//! use sophia::graph::{sparql::SparqlGraph, Graph};
+//! use sophia::ns::rdf;
+//!
+//! let g = SparqlGraph::new("https://query.wikidata.org/sparql");
+//! let alice = sophia::term::StaticTerm::new_iri("http://example.org/alice")?;
+//! for t in g.triples_with_s(&alice) {
+//!     let t = t?;
+//!     // do something with t
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ```no_run
+//! use sophia::graph::sparql::SparqlGraph;
+//! use sophia::serializer::{nt::NtSerializer, TripleSerializer};
+//!
+//! let g = SparqlGraph::new("https://query.wikidata.org/sparql");
+//! let triples = g.construct("CONSTRUCT { ?s a ?t } WHERE { ?s a ?t } LIMIT 1000000")?;
+//! NtSerializer::new(std::io::stdout()).serialize_triples(triples)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Graph`]: ../trait.Graph.html
+//! [`SparqlGraph`]: struct.SparqlGraph.html
+//! [`triples`]: ../trait.Graph.html#tymethod.triples
+//! [`triples_matching`]: ../trait.Graph.html#method.triples_matching
+//! [`contains`]: ../trait.Graph.html#method.contains
+//! [`construct`]: struct.SparqlGraph.html#method.construct
+//! [`describe`]: struct.SparqlGraph.html#method.describe
+//! [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+//! [TSV results format]: https://www.w3.org/TR/sparql11-results-csv-tsv/
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+use std::iter::once;
+use std::rc::Rc;
+
+use sophia_api::graph::{GTripleSource, Graph};
+use sophia_api::parser::TripleParser;
+use sophia_api::term::{term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::streaming_mode::{ByValue, StreamedTriple};
+use sophia_term::RcTerm;
+use thiserror::Error;
+
+use crate::parser::nt::NTriplesParser;
+use crate::sparql_results::{ResultsError, SelectResults};
+
+/// The default number of queries whose results are cached by a [`SparqlGraph`](struct.SparqlGraph.html).
+pub const DEFAULT_CACHE_CAPACITY: usize = 100;
+
+/// This error is raised when querying a SPARQL endpoint fails.
+#[derive(Debug, Error)]
+pub enum SparqlGraphError {
+    /// The HTTP request failed (network error, or an HTTP error status).
+    #[error("Failed to query <{endpoint}>: {source}")]
+    Http {
+        /// The IRI of the endpoint.
+        endpoint: String,
+        /// The underlying error.
+        source: Box<ureq::Error>,
+    },
+    /// The results returned by the endpoint could not be read.
+    #[error("Invalid results from <{endpoint}>: {source}")]
+    Results {
+        /// The IRI of the endpoint.
+        endpoint: String,
+        /// The underlying error.
+        source: ResultsError,
+    },
+}
+
+type Triples = Rc<Vec<[RcTerm; 3]>>;
+
+/// The triple source returned by [`SparqlGraph::construct`](struct.SparqlGraph.html#method.construct)
+/// and [`SparqlGraph::describe`](struct.SparqlGraph.html#method.describe),
+/// parsing the response of the endpoint as N-Triples.
+pub type ConstructSource =
+    <NTriplesParser as TripleParser<BufReader<Box<dyn Read + Send + Sync>>>>::Source;
+
+/// A read-only graph backed by a SPARQL endpoint.
+///
+/// See [module documentation](index.html) for more detail.
+pub struct SparqlGraph {
+    endpoint: String,
+    agent: ureq::Agent,
+    cache: RefCell<LruCache>,
+}
+
+impl SparqlGraph {
+    /// Build a graph querying `endpoint`,
+    /// with a cache of [`DEFAULT_CACHE_CAPACITY`](constant.DEFAULT_CACHE_CAPACITY.html) queries.
+    pub fn new<T: Into<String>>(endpoint: T) -> Self {
+        Self::with_capacity(endpoint, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Build a graph querying `endpoint`,
+    /// with a cache of `capacity` queries (0 disables caching).
+    pub fn with_capacity<T: Into<String>>(endpoint: T, capacity: usize) -> Self {
+        SparqlGraph {
+            endpoint: endpoint.into(),
+            agent: ureq::agent(),
+            cache: RefCell::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// The IRI of the SPARQL endpoint.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Empty the cache, so that subsequent calls query the endpoint again.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Send the CONSTRUCT (or DESCRIBE) `query` to the endpoint,
+    /// and stream the resulting triples.
+    ///
+    /// Only the HTTP request is performed by this method;
+    /// the triples are parsed lazily, as the returned source is consumed,
+    /// and syntax errors in the response are reported by the source.
+    pub fn construct(&self, query: &str) -> Result<ConstructSource, SparqlGraphError> {
+        let response = self
+            .agent
+            .get(&self.endpoint)
+            .query("query", query)
+            .set("Accept", "application/n-triples")
+            .call()
+            .map_err(|source| SparqlGraphError::Http {
+                endpoint: self.endpoint.clone(),
+                source: Box::new(source),
+            })?;
+        Ok(NTriplesParser {}.parse(BufReader::new(response.into_reader())))
+    }
+
+    /// Send a DESCRIBE query for `resource` to the endpoint,
+    /// and stream the resulting triples (see [`construct`](#method.construct)).
+    pub fn describe<T>(&self, resource: &T) -> Result<ConstructSource, SparqlGraphError>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.construct(&format!("DESCRIBE {}", term_to_string(resource)))
+    }
+
+    /// The triples matching the given pattern (`None` meaning "any term").
+    fn matching(&self, pattern: [Option<RcTerm>; 3]) -> GTripleSource<'_, Self> {
+        if pattern
+            .iter()
+            .flatten()
+            .any(|t| t.kind() == TermKind::BlankNode)
+        {
+            return Box::new(std::iter::empty());
+        }
+        match self.fetch(&pattern) {
+            Ok(triples) => Box::new(
+                (0..triples.len()).map(move |i| Ok(StreamedTriple::by_value(triples[i].clone()))),
+            ),
+            Err(err) => Box::new(once(Err(err))),
+        }
+    }
+
+    fn fetch(&self, pattern: &[Option<RcTerm>; 3]) -> Result<Triples, SparqlGraphError> {
+        let query = select_query(pattern);
+        if let Some(triples) = self.cache.borrow_mut().get(&query) {
+            return Ok(triples);
+        }
+        let response = self
+            .agent
+            .get(&self.endpoint)
+            .query("query", &query)
+            .set("Accept", "text/tab-separated-values")
+            .call()
+            .map_err(|source| SparqlGraphError::Http {
+                endpoint: self.endpoint.clone(),
+                source: Box::new(source),
+            })?;
+        let results =
+            SelectResults::from_tsv(BufReader::new(response.into_reader())).map_err(|source| {
+                SparqlGraphError::Results {
+                    endpoint: self.endpoint.clone(),
+                    source,
+                }
+            })?;
+        let triples: Vec<_> = results
+            .solutions()
+            .iter()
+            .filter_map(|b| {
+                let mut terms = VARIABLES.iter().zip(pattern.iter()).map(|(v, t)| match t {
+                    Some(t) => Some(t.clone()),
+                    None => b.get(*v).cloned(),
+                });
+                Some([terms.next()??, terms.next()??, terms.next()??])
+            })
+            .collect();
+        let triples = Rc::new(triples);
+        self.cache.borrow_mut().insert(query, triples.clone());
+        Ok(triples)
+    }
+}
+
+impl Graph for SparqlGraph {
+    type Triple = ByValue<[RcTerm; 3]>;
+    type Error = SparqlGraphError;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.matching([None, None, None])
+    }
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.matching([Some(RcTerm::copy(s)), None, None])
+    }
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.matching([None, Some(RcTerm::copy(p)), None])
+    }
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.matching([None, None, Some(RcTerm::copy(o))])
+    }
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.matching([Some(RcTerm::copy(s)), Some(RcTerm::copy(p)), None])
+    }
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.matching([Some(RcTerm::copy(s)), None, Some(RcTerm::copy(o))])
+    }
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.matching([None, Some(RcTerm::copy(p)), Some(RcTerm::copy(o))])
+    }
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.matching([
+            Some(RcTerm::copy(s)),
+            Some(RcTerm::copy(p)),
+            Some(RcTerm::copy(o)),
+        ])
+    }
+}
+
+const VARIABLES: [&str; 3] = ["s", "p", "o"];
+
+/// Build the SELECT query for the given pattern.
+///
+/// Bound positions are constrained with a `VALUES` clause,
+/// so that every query has at least one projected variable.
+fn select_query(pattern: &[Option<RcTerm>; 3]) -> String {
+    let mut query = String::from("SELECT ?s ?p ?o WHERE {");
+    for (v, t) in VARIABLES.iter().zip(pattern.iter()) {
+        if let Some(t) = t {
+            query.push_str(&format!(" VALUES ?{} {{ {} }}", v, term_to_string(t)));
+        }
+    }
+    query.push_str(" ?s ?p ?o }");
+    query
+}
+
+/// A minimal least-recently-used cache of query results.
+struct LruCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (u64, Triples)>,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, query: &str) -> Option<Triples> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(query).map(|(last_used, triples)| {
+            *last_used = tick;
+            triples.clone()
+        })
+    }
+
+    fn insert(&mut self, query: String, triples: Triples) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&query) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(query, _)| query.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(query, (self.tick, triples));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ns::rdf;
+    use crate::serializer::nt::NtSerializer;
+    use crate::serializer::{Stringifier, TripleSerializer};
+    use crate::triple::stream::TripleSource;
+    use sophia_term::StaticTerm;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Serve the given TSV results (one per connection) on a local port,
+    /// and return the IRI of the endpoint, and a receiver for the received requests.
+    fn serve(results: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for (stream, body) in listener.incoming().zip(results) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                tx.send(String::from_utf8_lossy(&buf[..n]).to_string())
+                    .unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/tab-separated-values\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://{}/sparql", addr), rx)
+    }
+
+    #[test]
+    fn triples_with_s_and_cache() -> Result<(), Box<dyn std::error::Error>> {
+        let (endpoint, requests) = serve(vec![
+            "?s\t?p\t?o\n<tag:a>\t<tag:p>\t\"x\"\n<tag:a>\t<tag:q>\t_:b\n",
+            "?s\t?p\t?o\n<tag:a>\t<tag:p>\t\"x\"\n",
+        ]);
+        let g = SparqlGraph::with_capacity(endpoint, 1);
+        let a = StaticTerm::new_iri("tag:a")?;
+        let p = StaticTerm::new_iri("tag:p")?;
+        assert_eq!(g.triples_with_s(&a).count(), 2);
+        let request = requests.recv()?;
+        assert!(request.starts_with("GET /sparql?query=SELECT"));
+        assert!(request.contains("VALUES"));
+        // served from the cache
+        assert_eq!(g.triples_with_s(&a).count(), 2);
+        assert!(g.contains(
+            &a,
+            &p,
+            &StaticTerm::new_literal_dt("x", crate::ns::xsd::string)?
+        )?);
+        requests.recv()?;
+        // the first query has been evicted, and the server does not answer anymore
+        assert!(g.triples_with_s(&a).next().unwrap().is_err());
+        // patterns with blank nodes do not match anything
+        let b = StaticTerm::new_bnode("b")?;
+        assert_eq!(g.triples_with_o(&b).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn construct_streaming() -> Result<(), Box<dyn std::error::Error>> {
+        let (endpoint, requests) = serve(vec![
+            "<tag:a> <tag:p> \"x\" .\n<tag:a> <tag:q> _:b .\n",
+            "<tag:a> <tag:p> \"x\" .\n<tag:a> garbage\n",
+        ]);
+        let g = SparqlGraph::new(endpoint);
+        let source = g.construct("CONSTRUCT { ?s ?p ?o } WHERE { ?s ?p ?o }")?;
+        let mut ser = NtSerializer::new_stringifier();
+        let out = ser.serialize_triples(source)?.as_str().to_string();
+        assert_eq!(out, "<tag:a> <tag:p> \"x\".\n<tag:a> <tag:q> _:b.\n");
+        let request = requests.recv()?;
+        assert!(request.starts_with("GET /sparql?query=CONSTRUCT"));
+        assert!(request.contains("application/n-triples"));
+
+        let a = StaticTerm::new_iri("tag:a")?;
+        let mut source = g.describe(&a)?;
+        assert!(requests
+            .recv()?
+            .starts_with("GET /sparql?query=DESCRIBE+%3Ctag%3Aa%3E"));
+        let mut count = 0;
+        assert!(source.for_each_triple(|_| count += 1).is_err());
+        assert_eq!(count, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn query() -> Result<(), Box<dyn std::error::Error>> {
+        let pattern = [None, Some(RcTerm::copy(&rdf::type_)), None];
+        assert_eq!(
+            select_query(&pattern),
+            "SELECT ?s ?p ?o WHERE { VALUES ?p { <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> } ?s ?p ?o }"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,519 @@
+//! A GraphQL-like façade, exposing resources of a graph as nested JSON objects.
+//!
+//! This module is only available with the `graphql` feature.
+//!
+//! A [`Selection`] describes, in a syntax close to GraphQL,
+//! which properties of a resource should be extracted,
+//! and recursively which properties of the related resources.
+//! Executing it on a resource (or a list of resources) of a graph
+//! yields a JSON value, directly consumable by front-end code.
+//!
+//! # Syntax
+//!
+//! A selection is a list of fields between braces.
+//! Each field is
+//! * `id`, the IRI of the resource (or the identifier of a blank node, prefixed by `_:`),
+//! * or a predicate, written as a prefixed name or as an IRI between angle brackets,
+//!   optionally preceded by `^` to follow it backward,
+//!   and optionally followed by a nested selection,
+//!   applied to each value of the predicate.
+//!
+//! The JSON key of a field is its text (e.g. `"foaf:name"`),
+//! unless an alias is given before it, followed by a colon and a space
+//! (`name: foaf:name`).
+//!
+//! By default, a field yields an array of values (sorted, for determinism).
+//! The `@one` directive (e.g. `foaf:name @one`) yields the first value only (or `null`),
+//! and the `lang` argument (e.g. `rdfs:label(lang: "fr, en")`) yields the literal
+//! best matching the given language preference (see [`LanguagePreference`]).
+//!
+//! Literals of numeric and boolean datatypes are converted to JSON numbers and booleans;
+//! other literals, and IRIs, are converted to JSON strings.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::graphql::Selection;
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix foaf: <http://xmlns.com/foaf/0.1/>.
+//!     <tag:alice> foaf:name "Alice"; foaf:age 42; foaf:knows <tag:bob>.
+//!     <tag:bob> foaf:name "Bob".
+//! "#).collect_triples()?;
+//!
+//! let selection = Selection::parse(
+//!     "{ id name: foaf:name @one age: foaf:age @one friends: foaf:knows { foaf:name } }",
+//!     &[("foaf", "http://xmlns.com/foaf/0.1/")],
+//! )?;
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! assert_eq!(
+//!     selection.execute(&g, &alice)?.dump(),
+//!     r#"{"id":"tag:alice","name":"Alice","age":42,"friends":[{"foaf:name":["Bob"]}]}"#,
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Selection`]: struct.Selection.html
+//! [`LanguagePreference`]: ../term/literal/lang/struct.LanguagePreference.html
+
+use std::collections::BTreeSet;
+
+use json::JsonValue;
+use sophia_api::ns::xsd;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::literal::lang::LanguagePreference;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+/// An error raised when parsing a [`Selection`](struct.Selection.html).
+#[derive(Debug, Error)]
+pub enum SelectionError {
+    /// The selection is syntactically invalid.
+    #[error("{message} at byte {offset}")]
+    Syntax {
+        /// A description of the error
+        message: String,
+        /// The byte offset of the error
+        offset: usize,
+    },
+    /// A prefixed name uses an undeclared prefix.
+    #[error("Unknown prefix {0:?}")]
+    UnknownPrefix(String),
+}
+
+/// A nested selection of properties.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Selection {
+    fields: Vec<Field>,
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    key: String,
+    kind: FieldKind,
+    cardinality: Cardinality,
+    selection: Option<Selection>,
+}
+
+#[derive(Clone, Debug)]
+enum FieldKind {
+    Id,
+    Predicate(BoxTerm, bool),
+}
+
+#[derive(Clone, Debug)]
+enum Cardinality {
+    Many,
+    One,
+    Lang(LanguagePreference),
+}
+
+impl Selection {
+    /// Parse a selection,
+    /// where prefixed names are resolved with `prefixes` (pairs of prefix and namespace).
+    pub fn parse(txt: &str, prefixes: &[(&str, &str)]) -> Result<Self, SelectionError> {
+        let mut parser = Parser {
+            txt,
+            pos: 0,
+            prefixes,
+        };
+        let selection = parser.selection()?;
+        parser.skip_ws();
+        if parser.pos < txt.len() {
+            return Err(parser.error("Unexpected text after selection"));
+        }
+        Ok(selection)
+    }
+
+    /// Apply this selection to `resource` in `graph`, yielding a JSON object.
+    pub fn execute<G, T>(&self, graph: &G, resource: &T) -> GResult<G, JsonValue>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut obj = JsonValue::new_object();
+        for field in &self.fields {
+            let values = match &field.kind {
+                FieldKind::Id => {
+                    obj[field.key.as_str()] = node_id(resource).into();
+                    continue;
+                }
+                FieldKind::Predicate(pred, inverse) => values(graph, resource, pred, *inverse)?,
+            };
+            let values = match &field.cardinality {
+                Cardinality::Many => values,
+                Cardinality::One => values.into_iter().take(1).collect(),
+                Cardinality::Lang(pref) => pref.select(&values).cloned().into_iter().collect(),
+            };
+            let mut converted = Vec::with_capacity(values.len());
+            for v in &values {
+                converted.push(match &field.selection {
+                    Some(sel) if v.kind() != TermKind::Literal => sel.execute(graph, v)?,
+                    _ => to_json(v),
+                });
+            }
+            obj[field.key.as_str()] = match field.cardinality {
+                Cardinality::Many => JsonValue::Array(converted),
+                _ => converted.pop().unwrap_or(JsonValue::Null),
+            };
+        }
+        Ok(obj)
+    }
+
+    /// Apply this selection to each of `resources` in `graph`, yielding a JSON array.
+    pub fn execute_all<G, I, T>(&self, graph: &G, resources: I) -> GResult<G, JsonValue>
+    where
+        G: Graph,
+        I: IntoIterator<Item = T>,
+        T: TTerm,
+    {
+        let mut array = vec![];
+        for r in resources {
+            array.push(self.execute(graph, &r)?);
+        }
+        Ok(JsonValue::Array(array))
+    }
+}
+
+/// The sorted values of `pred` for `node` (or the subjects, if `inverse`).
+fn values<G, T>(graph: &G, node: &T, pred: &BoxTerm, inverse: bool) -> GResult<G, Vec<BoxTerm>>
+where
+    G: Graph,
+    T: TTerm + ?Sized,
+{
+    let mut found = BTreeSet::new();
+    if inverse {
+        for t in graph.triples_with_po(pred, node) {
+            found.insert(BoxTerm::copy(t?.s()));
+        }
+    } else {
+        for t in graph.triples_with_sp(node, pred) {
+            found.insert(BoxTerm::copy(t?.o()));
+        }
+    }
+    Ok(found.into_iter().collect())
+}
+
+fn node_id<T: TTerm + ?Sized>(term: &T) -> String {
+    match term.kind() {
+        TermKind::BlankNode => format!("_:{}", term.value()),
+        _ => term.value().to_string(),
+    }
+}
+
+fn to_json(term: &BoxTerm) -> JsonValue {
+    if term.kind() != TermKind::Literal {
+        return node_id(term).into();
+    }
+    let value = term.value();
+    let dt = term.datatype().unwrap();
+    if dt == xsd::boolean {
+        match &value[..] {
+            "true" | "1" => return true.into(),
+            "false" | "0" => return false.into(),
+            _ => (),
+        }
+    } else if INTEGER_TYPES.iter().any(|t| dt == **t) {
+        if let Ok(i) = value.trim().parse::<i64>() {
+            return i.into();
+        }
+    } else if dt == xsd::decimal || dt == xsd::double || dt == xsd::float {
+        if let Ok(f) = value.trim().parse::<f64>() {
+            if f.is_finite() {
+                return f.into();
+            }
+        }
+    }
+    value.to_string().into()
+}
+
+const INTEGER_TYPES: [&sophia_api::term::SimpleIri<'static>; 13] = [
+    &xsd::integer,
+    &xsd::long,
+    &xsd::int,
+    &xsd::short,
+    &xsd::byte,
+    &xsd::nonNegativeInteger,
+    &xsd::positiveInteger,
+    &xsd::nonPositiveInteger,
+    &xsd::negativeInteger,
+    &xsd::unsignedLong,
+    &xsd::unsignedInt,
+    &xsd::unsignedShort,
+    &xsd::unsignedByte,
+];
+
+struct Parser<'a> {
+    txt: &'a str,
+    pos: usize,
+    prefixes: &'a [(&'a str, &'a str)],
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> SelectionError {
+        SelectionError::Syntax {
+            message: message.to_string(),
+            offset: self.pos,
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.txt[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        // commas are insignificant in GraphQL
+        self.pos += rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_whitespace() || c == ',')
+                .len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// A sequence of characters other than whitespace and punctuation.
+    fn word(&mut self) -> &str {
+        self.skip_ws();
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || "{}(),@\"".contains(c))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.txt[start..self.pos]
+    }
+
+    fn selection(&mut self) -> Result<Selection, SelectionError> {
+        if !self.eat("{") {
+            return Err(self.error("Expected '{'"));
+        }
+        let mut fields = vec![];
+        while !self.eat("}") {
+            if self.rest().is_empty() {
+                return Err(self.error("Expected '}'"));
+            }
+            fields.push(self.field()?);
+        }
+        Ok(Selection { fields })
+    }
+
+    fn field(&mut self) -> Result<Field, SelectionError> {
+        self.skip_ws();
+        let start = self.pos;
+        let mut text = self.word().to_string();
+        let mut key = None;
+        // an alias is a name followed by a colon and whitespace
+        if let Some(alias) = text.strip_suffix(':') {
+            if alias.is_empty() || !alias.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(SelectionError::Syntax {
+                    message: format!("Invalid alias {:?}", alias),
+                    offset: start,
+                });
+            }
+            key = Some(alias.to_string());
+            text = self.word().to_string();
+        }
+        if text.is_empty() {
+            return Err(self.error("Expected field"));
+        }
+        let kind = if text == "id" {
+            FieldKind::Id
+        } else {
+            let (name, inverse) = match text.strip_prefix('^') {
+                Some(name) => (name, true),
+                None => (&text[..], false),
+            };
+            FieldKind::Predicate(self.iri(name, start)?, inverse)
+        };
+        let mut cardinality = Cardinality::Many;
+        if self.eat("(") {
+            if self.word() != "lang:" {
+                return Err(self.error("Expected argument 'lang:'"));
+            }
+            cardinality = Cardinality::Lang(LanguagePreference::parse(&self.string()?));
+            if !self.eat(")") {
+                return Err(self.error("Expected ')'"));
+            }
+        }
+        while self.eat("@") {
+            match self.word() {
+                "one" => {
+                    if let Cardinality::Many = cardinality {
+                        cardinality = Cardinality::One;
+                    }
+                }
+                _ => return Err(self.error("Unknown directive")),
+            }
+        }
+        self.skip_ws();
+        let selection = if self.rest().starts_with('{') {
+            Some(self.selection()?)
+        } else {
+            None
+        };
+        Ok(Field {
+            key: key.unwrap_or(text),
+            kind,
+            cardinality,
+            selection,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, SelectionError> {
+        if !self.eat("\"") {
+            return Err(self.error("Expected string"));
+        }
+        match self.rest().find('"') {
+            Some(len) => {
+                let value = self.rest()[..len].to_string();
+                self.pos += len + 1;
+                Ok(value)
+            }
+            None => Err(self.error("Unclosed string")),
+        }
+    }
+
+    fn iri(&self, name: &str, offset: usize) -> Result<BoxTerm, SelectionError> {
+        let iri = if let Some(iri) = name.strip_prefix('<') {
+            iri.strip_suffix('>').map(str::to_string)
+        } else {
+            match name.split_once(':') {
+                Some((prefix, suffix)) => {
+                    let (_, ns) = self
+                        .prefixes
+                        .iter()
+                        .find(|(p, _)| *p == prefix)
+                        .ok_or_else(|| SelectionError::UnknownPrefix(prefix.to_string()))?;
+                    Some(format!("{}{}", ns, suffix))
+                }
+                None => None,
+            }
+        };
+        iri.and_then(|iri| BoxTerm::new_iri(iri).ok())
+            .ok_or_else(|| SelectionError::Syntax {
+                message: format!("Invalid field {:?}", name),
+                offset,
+            })
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const PREFIXES: &[(&str, &str)] = &[("ex", "tag:")];
+
+    fn data() -> FastGraph {
+        turtle::parse_str(
+            r#"
+            <tag:alice> <tag:name> "Alice"; <tag:age> "42"^^<http://www.w3.org/2001/XMLSchema#int>;
+                <tag:knows> <tag:bob>, [ <tag:name> "Carol" ];
+                <tag:label> "Alice"@en, "Alix"@fr;
+                <tag:admin> true; <tag:score> 1.5.
+            <tag:bob> <tag:name> "Bob"; <tag:knows> <tag:alice>.
+            "#,
+        )
+        .collect_triples()
+        .unwrap()
+    }
+
+    fn run(selection: &str, resource: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let s = Selection::parse(selection, PREFIXES)?;
+        Ok(s.execute(&data(), &BoxTerm::new_iri(resource)?)?.dump())
+    }
+
+    #[test]
+    fn scalars() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            run(
+                "{ id ex:name age: ex:age @one, admin: ex:admin @one ex:score }",
+                "tag:alice"
+            )?,
+            r#"{"id":"tag:alice","ex:name":["Alice"],"age":42,"admin":true,"ex:score":[1.5]}"#
+        );
+        assert_eq!(
+            run(
+                r#"{ fr: ex:label(lang: "fr-CA, en") en: ex:label(lang: "de, en") }"#,
+                "tag:alice"
+            )?,
+            r#"{"fr":"Alix","en":"Alice"}"#
+        );
+        assert_eq!(
+            run("{ x: ex:none @one ex:none }", "tag:bob")?,
+            r#"{"x":null,"ex:none":[]}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            run("{ friends: ex:knows { ex:name @one } }", "tag:alice")?,
+            r#"{"friends":[{"ex:name":"Bob"},{"ex:name":"Carol"}]}"#
+        );
+        assert_eq!(
+            run("{ knownBy: ^<tag:knows> { id } }", "tag:alice")?,
+            r#"{"knownBy":[{"id":"tag:bob"}]}"#
+        );
+        let s = Selection::parse("{ ex:name @one }", PREFIXES)?;
+        let people = vec![BoxTerm::new_iri("tag:alice")?, BoxTerm::new_iri("tag:bob")?];
+        assert_eq!(
+            s.execute_all(&data(), people)?.dump(),
+            r#"[{"ex:name":"Alice"},{"ex:name":"Bob"}]"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let err = |txt| Selection::parse(txt, PREFIXES).unwrap_err();
+        assert!(matches!(
+            err("{ foo:bar }"),
+            SelectionError::UnknownPrefix(_)
+        ));
+        assert!(matches!(
+            err("{ ex:name"),
+            SelectionError::Syntax { offset: 9, .. }
+        ));
+        assert!(matches!(
+            err("ex:name"),
+            SelectionError::Syntax { offset: 0, .. }
+        ));
+        assert!(matches!(
+            err("{ name }"),
+            SelectionError::Syntax { offset: 2, .. }
+        ));
+        assert!(matches!(
+            err("{ ex:name @many }"),
+            SelectionError::Syntax { .. }
+        ));
+        assert!(matches!(
+            err("{ id } }"),
+            SelectionError::Syntax { offset: 7, .. }
+        ));
+    }
+}
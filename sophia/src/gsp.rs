@@ -0,0 +1,464 @@
+//! Server-side handlers for the [SPARQL 1.1 Graph Store HTTP Protocol].
+//!
+//! This module is independent of any HTTP framework:
+//! the application converts the incoming HTTP request into a [`GspRequest`],
+//! passes it to [`handle`] along with the dataset to operate on,
+//! and converts the returned [`GspResponse`] back into an HTTP response.
+//!
+//! The graph targeted by the request is identified by the query string
+//! (`?default` or `?graph=<iri>`, see [`GraphTarget::from_query`]).
+//! `GET` and `HEAD` negotiate the syntax of the response
+//! among the triple serializers available in [`Syntax`],
+//! according to the `Accept` header;
+//! `PUT` and `POST` parse the request body according to its `Content-Type`.
+//! The body is parsed entirely before the dataset is modified,
+//! so that an invalid payload leaves the dataset untouched.
+//!
+//! As a generic [`Dataset`] does not keep track of empty graphs,
+//! a named graph is considered to exist if and only if it contains at least one triple.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::inmem::FastDataset;
+//! use sophia::gsp::{handle, GraphTarget, GspRequest, Method};
+//!
+//! let mut dataset = FastDataset::new();
+//! let target = GraphTarget::from_query("graph=http%3A%2F%2Fexample.org%2Fg")?;
+//! let put = GspRequest {
+//!     method: Method::Put,
+//!     target: target.clone(),
+//!     accept: None,
+//!     content_type: Some("text/turtle"),
+//!     body: b"<tag:a> <tag:b> <tag:c>.",
+//! };
+//! assert_eq!(handle(&mut dataset, &put).status, 201);
+//!
+//! let get = GspRequest {
+//!     method: Method::Get,
+//!     target,
+//!     accept: Some("application/n-triples"),
+//!     content_type: None,
+//!     body: b"",
+//! };
+//! let response = handle(&mut dataset, &get);
+//! assert_eq!(response.status, 200);
+//! assert_eq!(response.body, b"<tag:a> <tag:b> <tag:c>.\n");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SPARQL 1.1 Graph Store HTTP Protocol]: https://www.w3.org/TR/sparql11-http-rdf-update/
+//! [`GspRequest`]: struct.GspRequest.html
+//! [`GspResponse`]: struct.GspResponse.html
+//! [`handle`]: fn.handle.html
+//! [`GraphTarget::from_query`]: enum.GraphTarget.html#method.from_query
+//! [`Syntax`]: ../syntax/enum.Syntax.html
+//! [`Dataset`]: ../dataset/trait.Dataset.html
+
+use std::convert::Infallible;
+use std::fmt;
+
+use sophia_api::dataset::{DTerm, Dataset, MutableDataset};
+use sophia_api::quad::Quad;
+use sophia_api::term::matcher::ANY;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::syntax::dynamic::BoxTriple;
+use crate::syntax::{guess, Syntax};
+
+/// The HTTP methods supported by the Graph Store Protocol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    /// Retrieve a graph.
+    Get,
+    /// Same as `Get`, without the response body.
+    Head,
+    /// Replace a graph.
+    Put,
+    /// Add triples to a graph.
+    Post,
+    /// Remove a graph.
+    Delete,
+}
+
+/// The graph targeted by a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GraphTarget {
+    /// The default graph (`?default`).
+    Default,
+    /// The graph with the given IRI (`?graph=...`).
+    Named(String),
+}
+
+/// This error is raised when the query string of a request does not identify a graph.
+#[derive(Debug, Error)]
+#[error("Invalid graph target: {0}")]
+pub struct GraphTargetError(String);
+
+impl GraphTarget {
+    /// Extract the target graph from the query string of a request (without the leading `?`).
+    ///
+    /// ```
+    /// # use sophia::gsp::GraphTarget;
+    /// assert_eq!(GraphTarget::from_query("default").unwrap(), GraphTarget::Default);
+    /// assert_eq!(
+    ///     GraphTarget::from_query("graph=tag%3Ag1").unwrap(),
+    ///     GraphTarget::Named("tag:g1".to_string()),
+    /// );
+    /// ```
+    pub fn from_query(query: &str) -> Result<Self, GraphTargetError> {
+        let mut targets = query.split('&').filter_map(|param| {
+            let mut kv = param.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("default"), None) | (Some("default"), Some("")) => {
+                    Some(Ok(GraphTarget::Default))
+                }
+                (Some("graph"), Some(iri)) => Some(
+                    percent_decode(iri)
+                        .map(GraphTarget::Named)
+                        .ok_or_else(|| GraphTargetError(format!("invalid encoding: {}", iri))),
+                ),
+                _ => None,
+            }
+        });
+        match (targets.next(), targets.next()) {
+            (Some(target), None) => target,
+            (None, _) => Err(GraphTargetError(
+                "no `default` nor `graph` parameter".into(),
+            )),
+            (Some(_), Some(_)) => Err(GraphTargetError("more than one target".into())),
+        }
+    }
+
+    fn name(&self) -> Result<Option<BoxTerm>, GspResponse> {
+        match self {
+            GraphTarget::Default => Ok(None),
+            GraphTarget::Named(iri) => BoxTerm::new_iri(iri.as_str())
+                .map(Some)
+                .map_err(|err| GspResponse::error(400, err)),
+        }
+    }
+}
+
+/// A Graph Store Protocol request, as extracted from an HTTP request.
+#[derive(Clone, Debug)]
+pub struct GspRequest<'a> {
+    /// The HTTP method.
+    pub method: Method,
+    /// The target graph (see [`GraphTarget::from_query`](enum.GraphTarget.html#method.from_query)).
+    pub target: GraphTarget,
+    /// The value of the `Accept` header, if any.
+    pub accept: Option<&'a str>,
+    /// The value of the `Content-Type` header, if any.
+    pub content_type: Option<&'a str>,
+    /// The request body.
+    pub body: &'a [u8],
+}
+
+/// The response to a [`GspRequest`](struct.GspRequest.html),
+/// to be converted into an HTTP response.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GspResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The value of the `Content-Type` header, if any.
+    pub content_type: Option<&'static str>,
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl GspResponse {
+    fn empty(status: u16) -> Self {
+        GspResponse {
+            status,
+            content_type: None,
+            body: vec![],
+        }
+    }
+
+    fn error<E: fmt::Display>(status: u16, err: E) -> Self {
+        GspResponse {
+            status,
+            content_type: Some("text/plain"),
+            body: err.to_string().into_bytes(),
+        }
+    }
+}
+
+/// Process `request` against `dataset`.
+///
+/// See [module documentation](index.html).
+pub fn handle<D>(dataset: &mut D, request: &GspRequest) -> GspResponse
+where
+    D: MutableDataset,
+    DTerm<D>: Clone,
+    <D as Dataset>::Error: Into<D::MutationError>,
+{
+    match try_handle(dataset, request) {
+        Ok(response) => response,
+        Err(response) => response,
+    }
+}
+
+fn try_handle<D>(dataset: &mut D, request: &GspRequest) -> Result<GspResponse, GspResponse>
+where
+    D: MutableDataset,
+    DTerm<D>: Clone,
+    <D as Dataset>::Error: Into<D::MutationError>,
+{
+    let name = request.target.name()?;
+    let name = name.as_ref();
+    let exists = match name {
+        None => true,
+        Some(_) => dataset
+            .quads_with_g(name)
+            .next()
+            .transpose()
+            .map_err(internal)?
+            .is_some(),
+    };
+    match request.method {
+        Method::Get | Method::Head => {
+            if !exists {
+                return Ok(GspResponse::empty(404));
+            }
+            let syntax = negotiate(request.accept)
+                .ok_or_else(|| GspResponse::error(406, "No acceptable syntax"))?;
+            let mut body = vec![];
+            if request.method == Method::Get {
+                let triples: Vec<BoxTriple> = dataset
+                    .quads_with_g(name)
+                    .map(|q| {
+                        q.map(|q| {
+                            [
+                                BoxTerm::copy(q.s()),
+                                BoxTerm::copy(q.p()),
+                                BoxTerm::copy(q.o()),
+                            ]
+                        })
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(internal)?;
+                syntax
+                    .triple_serializer(&mut body)
+                    .unwrap()
+                    .serialize_dyn_triples(Box::new(triples.into_iter().map(Ok)))
+                    .map_err(internal)?;
+            }
+            Ok(GspResponse {
+                status: 200,
+                content_type: Some(syntax.media_type()),
+                body,
+            })
+        }
+        Method::Put | Method::Post => {
+            let syntax = request
+                .content_type
+                .and_then(guess::from_media_type)
+                .ok_or_else(|| GspResponse::error(415, "Unsupported Content-Type"))?;
+            let base = name.map(|n| n.value().to_string());
+            let triples: Vec<BoxTriple> = syntax
+                .parse_triples(request.body, base)
+                .collect::<Result<_, _>>()
+                .map_err(|err| GspResponse::error(400, err))?;
+            if request.method == Method::Put {
+                dataset
+                    .remove_matching(&ANY, &ANY, &ANY, &name)
+                    .map_err(internal)?;
+            }
+            let quads = triples
+                .into_iter()
+                .map(|t| Ok::<_, Infallible>((t, name.cloned())));
+            dataset.insert_all(quads).map_err(internal)?;
+            Ok(GspResponse::empty(if exists { 204 } else { 201 }))
+        }
+        Method::Delete => {
+            if !exists {
+                return Ok(GspResponse::empty(404));
+            }
+            dataset
+                .remove_matching(&ANY, &ANY, &ANY, &name)
+                .map_err(internal)?;
+            Ok(GspResponse::empty(204))
+        }
+    }
+}
+
+fn internal<E: fmt::Display>(err: E) -> GspResponse {
+    GspResponse::error(500, err)
+}
+
+/// The syntaxes that can be used in the responses to `GET` requests.
+pub fn response_syntaxes() -> impl Iterator<Item = Syntax> {
+    Syntax::ALL
+        .iter()
+        .copied()
+        .filter(|s| !s.is_quad_syntax() && s.triple_serializer(std::io::sink()).is_some())
+}
+
+/// Select the preferred syntax among [`response_syntaxes`](fn.response_syntaxes.html),
+/// according to the given `Accept` header.
+fn negotiate(accept: Option<&str>) -> Option<Syntax> {
+    let accept = match accept {
+        None => return response_syntaxes().next(),
+        Some(accept) => accept,
+    };
+    let ranges: Vec<(&str, f32)> = accept
+        .split(',')
+        .map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media_range = parts.next().unwrap_or("");
+            let q = parts
+                .filter_map(|p| p.strip_prefix("q="))
+                .find_map(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            (media_range, q)
+        })
+        .collect();
+    let mut best = None;
+    for syntax in response_syntaxes() {
+        let media_type = syntax.media_type();
+        let main_type = media_type.split('/').next().unwrap();
+        // the most specific matching range determines the quality
+        let q = ranges
+            .iter()
+            .filter_map(|(range, q)| {
+                if range.eq_ignore_ascii_case(media_type) {
+                    Some((2, *q))
+                } else if range.strip_suffix("/*") == Some(main_type) {
+                    Some((1, *q))
+                } else if *range == "*/*" {
+                    Some((0, *q))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, q)| q)
+            .unwrap_or(0.0);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((syntax, q));
+        }
+    }
+    best.map(|(syntax, _)| syntax)
+}
+
+/// Decode percent-encoded triplets (and `+` as space) in a URL query component.
+fn percent_decode(txt: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(txt.len());
+    let mut i = 0;
+    let txt = txt.as_bytes();
+    while i < txt.len() {
+        match txt[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(txt.get(i + 1..i + 3)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                bytes.push(b' ');
+                i += 1;
+            }
+            b => {
+                bytes.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+
+    fn request<'a>(method: Method, target: &GraphTarget, body: &'a [u8]) -> GspRequest<'a> {
+        GspRequest {
+            method,
+            target: target.clone(),
+            accept: None,
+            content_type: Some("text/turtle"),
+            body,
+        }
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(
+            GraphTarget::from_query("foo=bar&default").unwrap(),
+            GraphTarget::Default
+        );
+        assert_eq!(
+            GraphTarget::from_query("graph=http://ex.org/%C3%A9").unwrap(),
+            GraphTarget::Named("http://ex.org/é".into())
+        );
+        assert!(GraphTarget::from_query("foo=bar").is_err());
+        assert!(GraphTarget::from_query("default&graph=tag:g").is_err());
+        assert!(GraphTarget::from_query("graph=%C3").is_err());
+    }
+
+    #[test]
+    fn negotiation() {
+        let nt = Some(Syntax::NTriples);
+        assert_eq!(negotiate(None), nt);
+        assert_eq!(negotiate(Some("*/*")), nt);
+        assert_eq!(negotiate(Some("text/turtle, application/*;q=0.5")), nt);
+        assert_eq!(negotiate(Some("text/turtle")), None);
+        assert_eq!(negotiate(Some("application/n-triples;q=0, */*")), None);
+    }
+
+    #[test]
+    fn protocol() {
+        let mut d = FastDataset::new();
+        let g = GraphTarget::Named("tag:g".into());
+        let dg = GraphTarget::Default;
+
+        let get = request(Method::Get, &g, b"");
+        assert_eq!(handle(&mut d, &get).status, 404);
+        let put = request(Method::Put, &g, b"<tag:a> <tag:b> <tag:c>, <tag:d>.");
+        assert_eq!(handle(&mut d, &put).status, 201);
+        let post = request(Method::Post, &g, b"<tag:a> <tag:b> <tag:e>.");
+        assert_eq!(handle(&mut d, &post).status, 204);
+        let response = handle(&mut d, &get);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, Some("application/n-triples"));
+        assert_eq!(response.body.iter().filter(|b| **b == b'\n').count(), 3);
+        let head = request(Method::Head, &g, b"");
+        assert!(handle(&mut d, &head).body.is_empty());
+
+        // replace, and check that the default graph is not affected
+        assert_eq!(
+            handle(
+                &mut d,
+                &request(Method::Post, &dg, b"<tag:x> <tag:y> <tag:z>.")
+            )
+            .status,
+            204
+        );
+        let put = request(Method::Put, &g, b"<tag:a> <tag:b> <tag:f>.");
+        assert_eq!(handle(&mut d, &put).status, 204);
+        assert_eq!(d.quads().count(), 2);
+
+        // errors leave the dataset untouched
+        let bad = request(Method::Put, &g, b"<tag:a> <tag:b>");
+        assert_eq!(handle(&mut d, &bad).status, 400);
+        let mut bad_type = request(Method::Put, &g, b"");
+        bad_type.content_type = Some("text/plain");
+        assert_eq!(handle(&mut d, &bad_type).status, 415);
+        let mut not_acceptable = request(Method::Get, &g, b"");
+        not_acceptable.accept = Some("image/png");
+        assert_eq!(handle(&mut d, &not_acceptable).status, 406);
+        assert_eq!(d.quads().count(), 2);
+
+        let delete = request(Method::Delete, &g, b"");
+        assert_eq!(handle(&mut d, &delete).status, 204);
+        assert_eq!(handle(&mut d, &delete).status, 404);
+        assert_eq!(d.quads().count(), 1);
+    }
+}
@@ -0,0 +1,520 @@
+//! Loading the `owl:imports` closure of an ontology.
+//!
+//! An OWL ontology may import other ontologies with `owl:imports`,
+//! which may in turn import others.
+//! An [`ImportResolver`] starts from one ontology document,
+//! follows `owl:imports` recursively,
+//! and loads every ontology of the closure into a named graph of a dataset
+//! (the name of the graph being the IRI of the ontology).
+//!
+//! Documents are looked up first in a local catalog,
+//! mapping IRIs to local files (or to in-memory documents),
+//! possibly complemented by a shared [`IriCatalog`];
+//! IRIs missing from the catalog are fetched over HTTP(S)
+//! if an [`HttpLoader`] is configured (this requires the `http` feature).
+//!
+//! Resolution never stops at the first problem:
+//! ontologies that can not be retrieved or parsed are recorded as failures,
+//! and import cycles are recorded as well,
+//! in the returned [`ImportReport`].
+//! Each ontology is loaded only once, even if it is imported several times.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, Dataset};
+//! use sophia::imports::ImportResolver;
+//! use sophia::syntax::Syntax;
+//!
+//! let mut resolver = ImportResolver::new();
+//! resolver.add_document(
+//!     "http://example.org/a",
+//!     Syntax::Turtle,
+//!     "<http://example.org/a> <http://www.w3.org/2002/07/owl#imports> <http://example.org/b>.",
+//! );
+//! resolver.add_document(
+//!     "http://example.org/b",
+//!     Syntax::Turtle,
+//!     "<http://example.org/b> <http://www.w3.org/2002/07/owl#imports> <http://example.org/c>.",
+//! );
+//!
+//! let mut dataset = FastDataset::new();
+//! let report = resolver.resolve("http://example.org/a", &mut dataset)?;
+//! assert_eq!(report.loaded(), &["http://example.org/a", "http://example.org/b"]);
+//! assert_eq!(report.failures().len(), 1);
+//! assert_eq!(report.failures()[0].iri, "http://example.org/c");
+//! assert_eq!(dataset.quads().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`ImportResolver`]: struct.ImportResolver.html
+//! [`ImportReport`]: struct.ImportReport.html
+//! [`HttpLoader`]: ../loader/struct.HttpLoader.html
+//! [`IriCatalog`]: ../iri_catalog/struct.IriCatalog.html
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::ns::owl;
+use sophia_api::term::{TTerm, TermKind};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::iri_catalog::IriCatalog;
+#[cfg(feature = "http")]
+use crate::loader::{HttpLoader, LoaderError};
+use crate::syntax::dynamic::{BoxTriple, DynError};
+use crate::syntax::{guess, Syntax};
+
+/// This error is raised when an ontology of the import closure can not be loaded.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    /// The IRI is not in the local catalog, and can not be fetched remotely.
+    #[error("No document available for <{0}>")]
+    NotFound(String),
+    /// The local file mapped to the IRI could not be read.
+    #[error("Failed to read {path:?} for <{iri}>: {source}")]
+    Io {
+        /// The IRI of the ontology.
+        iri: String,
+        /// The local file mapped to the IRI.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    /// The syntax of the local file could not be determined.
+    #[error("Unsupported syntax for {path:?} (mapped to <{iri}>)")]
+    UnsupportedSyntax {
+        /// The IRI of the ontology.
+        iri: String,
+        /// The local file mapped to the IRI.
+        path: PathBuf,
+    },
+    /// The document could not be parsed.
+    #[error("Failed to parse <{iri}>: {source}")]
+    Parse {
+        /// The IRI of the ontology.
+        iri: String,
+        /// The underlying error.
+        source: DynError,
+    },
+    /// The document could not be fetched over HTTP(S), or not parsed.
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    Http(#[from] LoaderError),
+}
+
+/// An ontology of the import closure that could not be loaded.
+#[derive(Debug)]
+pub struct ImportFailure {
+    /// The IRI of the ontology.
+    pub iri: String,
+    /// The ontology importing it (`None` for the starting ontology).
+    pub importer: Option<String>,
+    /// The reason of the failure.
+    pub error: ImportError,
+}
+
+impl fmt::Display for ImportFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.importer {
+            None => write!(f, "{}", self.error),
+            Some(importer) => write!(f, "{} (imported by <{}>)", self.error, importer),
+        }
+    }
+}
+
+/// The outcome of [`ImportResolver::resolve`](struct.ImportResolver.html#method.resolve).
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    loaded: Vec<String>,
+    imports: Vec<(String, String)>,
+    cycles: Vec<Vec<String>>,
+    failures: Vec<ImportFailure>,
+}
+
+impl ImportReport {
+    /// The IRIs of the ontologies successfully loaded, in the order they were loaded.
+    pub fn loaded(&self) -> &[String] {
+        &self.loaded
+    }
+
+    /// The `owl:imports` relations found, as (importing, imported) pairs.
+    pub fn imports(&self) -> &[(String, String)] {
+        &self.imports
+    }
+
+    /// The import cycles found.
+    ///
+    /// Each cycle is given as a path of ontology IRIs,
+    /// starting and ending with the same IRI
+    /// (e.g. `[a, b, a]` when `a` imports `b` which imports `a`).
+    pub fn cycles(&self) -> &[Vec<String>] {
+        &self.cycles
+    }
+
+    /// The ontologies that could not be loaded.
+    pub fn failures(&self) -> &[ImportFailure] {
+        &self.failures
+    }
+
+    /// Whether the whole closure was loaded without failure.
+    ///
+    /// NB: cycles are not considered as failures.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A document of the local catalog.
+#[derive(Clone, Debug)]
+enum LocalDocument {
+    File(PathBuf),
+    Data(Syntax, String),
+}
+
+/// Loads the `owl:imports` closure of an ontology into a dataset.
+///
+/// See [module documentation](index.html) for more detail.
+#[derive(Clone, Debug, Default)]
+pub struct ImportResolver {
+    documents: HashMap<String, LocalDocument>,
+    catalog: Option<Arc<IriCatalog>>,
+    #[cfg(feature = "http")]
+    http_loader: Option<HttpLoader>,
+}
+
+impl ImportResolver {
+    /// Construct a resolver with an empty catalog, and no HTTP loader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `iri` to the local file at `path`.
+    ///
+    /// The syntax of the file is guessed from its extension,
+    /// or from its first bytes (see [`syntax::guess`](../syntax/guess/index.html)).
+    /// Relative IRIs in the file are resolved against `iri`.
+    pub fn add_file<I, P>(&mut self, iri: I, path: P) -> &mut Self
+    where
+        I: Into<String>,
+        P: Into<PathBuf>,
+    {
+        self.documents
+            .insert(iri.into(), LocalDocument::File(path.into()));
+        self
+    }
+
+    /// Map `iri` to the in-memory document `data`, in the given `syntax`.
+    ///
+    /// Relative IRIs in `data` are resolved against `iri`.
+    pub fn add_document<I, D>(&mut self, iri: I, syntax: Syntax, data: D) -> &mut Self
+    where
+        I: Into<String>,
+        D: Into<String>,
+    {
+        self.documents
+            .insert(iri.into(), LocalDocument::Data(syntax, data.into()));
+        self
+    }
+
+    /// Whether `iri` is mapped to a local document
+    /// (by this resolver, or by its [`IriCatalog`](../iri_catalog/struct.IriCatalog.html)).
+    pub fn is_local(&self, iri: &str) -> bool {
+        self.documents.contains_key(iri) || self.catalog.as_ref().is_some_and(|c| c.contains(iri))
+    }
+
+    /// The shared catalog of local files used by this resolver, if any.
+    pub fn catalog(&self) -> Option<&Arc<IriCatalog>> {
+        self.catalog.as_ref()
+    }
+
+    /// Set the shared catalog of local files used by this resolver,
+    /// for IRIs not mapped by [`add_file`](#method.add_file) or [`add_document`](#method.add_document).
+    pub fn set_catalog(&mut self, catalog: Option<Arc<IriCatalog>>) -> &mut Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// The loader used for IRIs missing from the local catalog, if any.
+    #[cfg(feature = "http")]
+    pub fn http_loader(&self) -> Option<&HttpLoader> {
+        self.http_loader.as_ref()
+    }
+
+    /// Set the loader used for IRIs missing from the local catalog;
+    /// if `None`, such IRIs are reported as failures.
+    #[cfg(feature = "http")]
+    pub fn set_http_loader(&mut self, http_loader: Option<HttpLoader>) -> &mut Self {
+        self.http_loader = http_loader;
+        self
+    }
+
+    /// Load the ontology at `iri`, and all the ontologies it (transitively) imports,
+    /// into `dataset`, each in the named graph of its IRI.
+    ///
+    /// Ontologies that can not be loaded, and import cycles,
+    /// are recorded in the returned report;
+    /// an error is only returned if `dataset` fails to insert a quad.
+    pub fn resolve<D>(&self, iri: &str, dataset: &mut D) -> Result<ImportReport, D::MutationError>
+    where
+        D: MutableDataset,
+    {
+        let mut state = Resolution {
+            report: ImportReport::default(),
+            visited: HashSet::new(),
+            stack: Vec::new(),
+        };
+        self.visit(iri, None, dataset, &mut state)?;
+        Ok(state.report)
+    }
+
+    fn visit<D>(
+        &self,
+        iri: &str,
+        importer: Option<&str>,
+        dataset: &mut D,
+        state: &mut Resolution,
+    ) -> Result<(), D::MutationError>
+    where
+        D: MutableDataset,
+    {
+        if let Some(pos) = state.stack.iter().position(|i| i == iri) {
+            let mut cycle = state.stack[pos..].to_vec();
+            cycle.push(iri.to_string());
+            state.report.cycles.push(cycle);
+            return Ok(());
+        }
+        if !state.visited.insert(iri.to_string()) {
+            return Ok(());
+        }
+        let triples = match self.fetch(iri) {
+            Ok(triples) => triples,
+            Err(error) => {
+                state.report.failures.push(ImportFailure {
+                    iri: iri.to_string(),
+                    importer: importer.map(str::to_string),
+                    error,
+                });
+                return Ok(());
+            }
+        };
+        let graph_name = BoxTerm::new_iri_unchecked(iri.to_string());
+        let mut imported = Vec::new();
+        for [s, p, o] in triples.iter() {
+            dataset.insert(s, p, o, Some(&graph_name))?;
+            if owl::imports == *p && o.kind() == TermKind::Iri {
+                let o = o.value().to_string();
+                if !imported.contains(&o) {
+                    imported.push(o);
+                }
+            }
+        }
+        state.report.loaded.push(iri.to_string());
+        state.stack.push(iri.to_string());
+        for target in imported {
+            state.report.imports.push((iri.to_string(), target.clone()));
+            self.visit(&target, Some(iri), dataset, state)?;
+        }
+        state.stack.pop();
+        Ok(())
+    }
+
+    /// Retrieve and parse the document of `iri`.
+    fn fetch(&self, iri: &str) -> Result<Vec<BoxTriple>, ImportError> {
+        match self.documents.get(iri) {
+            Some(LocalDocument::Data(syntax, data)) => parse(iri, *syntax, data.as_bytes()),
+            Some(LocalDocument::File(path)) => read_file(iri, path),
+            None => match self.catalog.as_ref().and_then(|c| c.resolve(iri)) {
+                Some(path) => read_file(iri, &path),
+                None => self.fetch_remote(iri),
+            },
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn fetch_remote(&self, iri: &str) -> Result<Vec<BoxTriple>, ImportError> {
+        use sophia_api::triple::stream::StreamError::*;
+        let loader = self
+            .http_loader
+            .as_ref()
+            .ok_or_else(|| ImportError::NotFound(iri.to_string()))?;
+        let mut triples = Vec::new();
+        match loader.load_graph(iri, &mut triples) {
+            Ok(_) => Ok(triples),
+            Err(SourceError(err)) => Err(err.into()),
+            Err(SinkError(never)) => match never {},
+        }
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn fetch_remote(&self, iri: &str) -> Result<Vec<BoxTriple>, ImportError> {
+        Err(ImportError::NotFound(iri.to_string()))
+    }
+}
+
+/// Read and parse the local file at `path`, mapped to `iri`.
+fn read_file(iri: &str, path: &Path) -> Result<Vec<BoxTriple>, ImportError> {
+    let data = std::fs::read(path).map_err(|source| ImportError::Io {
+        iri: iri.to_string(),
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let syntax =
+        guess::guess(None, path.to_str(), &data).ok_or_else(|| ImportError::UnsupportedSyntax {
+            iri: iri.to_string(),
+            path: path.to_path_buf(),
+        })?;
+    parse(iri, syntax, &data)
+}
+
+/// Parse `data` (the document of `iri`) in the given `syntax`.
+fn parse(iri: &str, syntax: Syntax, data: &[u8]) -> Result<Vec<BoxTriple>, ImportError> {
+    syntax
+        .parse_triples(data, Some(iri.to_string()))
+        .collect::<Result<_, _>>()
+        .map_err(|source| ImportError::Parse {
+            iri: iri.to_string(),
+            source,
+        })
+}
+
+/// The state of an ongoing resolution.
+struct Resolution {
+    report: ImportReport,
+    visited: HashSet<String>,
+    stack: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use sophia_api::dataset::Dataset;
+    use sophia_api::term::matcher::ANY;
+
+    fn ontology(iri: &str, imports: &[&str]) -> String {
+        let mut ttl = format!("<{}> a <http://www.w3.org/2002/07/owl#Ontology>.\n", iri);
+        for imported in imports {
+            ttl.push_str(&format!(
+                "<{}> <http://www.w3.org/2002/07/owl#imports> <{}>.\n",
+                iri, imported
+            ));
+        }
+        ttl
+    }
+
+    fn resolver(ontologies: &[(&str, &[&str])]) -> ImportResolver {
+        let mut resolver = ImportResolver::new();
+        for (iri, imports) in ontologies {
+            resolver.add_document(*iri, Syntax::Turtle, ontology(iri, imports));
+        }
+        resolver
+    }
+
+    #[test]
+    fn closure_in_named_graphs() -> Result<(), Box<dyn std::error::Error>> {
+        let resolver = resolver(&[
+            ("tag:a", &["tag:b", "tag:c"]),
+            ("tag:b", &["tag:d"]),
+            ("tag:c", &["tag:d"]),
+            ("tag:d", &[]),
+            ("tag:unrelated", &[]),
+        ]);
+        let mut d = FastDataset::new();
+        let report = resolver.resolve("tag:a", &mut d)?;
+        assert_eq!(report.loaded(), &["tag:a", "tag:b", "tag:d", "tag:c"]);
+        assert_eq!(report.imports().len(), 4);
+        assert!(report.cycles().is_empty());
+        assert!(report.is_complete());
+        assert_eq!(d.quads().count(), 8);
+        let d_name = BoxTerm::new_iri("tag:d")?;
+        assert_eq!(
+            d.quads_matching(&ANY, &ANY, &ANY, &Some(&d_name)).count(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cycles() -> Result<(), Box<dyn std::error::Error>> {
+        let resolver = resolver(&[
+            ("tag:a", &["tag:b"]),
+            ("tag:b", &["tag:c", "tag:b"]),
+            ("tag:c", &["tag:a"]),
+        ]);
+        let mut d = FastDataset::new();
+        let report = resolver.resolve("tag:a", &mut d)?;
+        assert_eq!(report.loaded().len(), 3);
+        assert_eq!(
+            report.cycles(),
+            &[
+                vec!["tag:a", "tag:b", "tag:c", "tag:a"],
+                vec!["tag:b", "tag:b"],
+            ]
+        );
+        assert!(report.is_complete());
+        Ok(())
+    }
+
+    #[test]
+    fn failures() -> Result<(), Box<dyn std::error::Error>> {
+        let mut resolver = resolver(&[("tag:a", &["tag:b", "tag:missing", "tag:file"])]);
+        resolver.add_document("tag:b", Syntax::NTriples, "not N-Triples\n");
+        resolver.add_file("tag:file", "/nonexistent/ontology.ttl");
+        let mut d = FastDataset::new();
+        let report = resolver.resolve("tag:a", &mut d)?;
+        assert_eq!(report.loaded(), &["tag:a"]);
+        assert!(!report.is_complete());
+        let failures = report.failures();
+        assert_eq!(failures.len(), 3);
+        assert!(matches!(failures[0].error, ImportError::Parse { .. }));
+        assert!(matches!(failures[1].error, ImportError::NotFound(_)));
+        assert!(matches!(failures[2].error, ImportError::Io { .. }));
+        assert!(failures
+            .iter()
+            .all(|f| f.importer.as_deref() == Some("tag:a")));
+        assert!(failures[1].to_string().ends_with("(imported by <tag:a>)"));
+
+        let report = resolver.resolve("tag:missing", &mut d)?;
+        assert!(report.loaded().is_empty());
+        assert_eq!(report.failures()[0].importer, None);
+        Ok(())
+    }
+
+    #[test]
+    fn local_file() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("sophia-imports-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("b.ttl");
+        std::fs::write(&path, ontology("tag:b", &[]))?;
+        let mut resolver1 = resolver(&[("tag:a", &["tag:b"])]);
+        resolver1.add_file("tag:b", &path);
+        assert!(resolver1.is_local("tag:b"));
+        let mut d = FastDataset::new();
+        let report = resolver1.resolve("tag:a", &mut d);
+
+        // the same, through a shared catalog
+        let mut catalog = IriCatalog::new();
+        catalog.add_prefix("tag:", &dir);
+        let mut resolver2 = resolver(&[("tag:a", &["tag:b.ttl"])]);
+        resolver2.set_catalog(Some(Arc::new(catalog)));
+        assert!(resolver2.is_local("tag:b.ttl"));
+        assert!(!resolver2.is_local("other:c.ttl"));
+        let mut d2 = FastDataset::new();
+        let report2 = resolver2.resolve("tag:a", &mut d2);
+
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(report?.loaded(), &["tag:a", "tag:b"]);
+        assert_eq!(d.quads().count(), 3);
+        assert_eq!(report2?.loaded(), &["tag:a", "tag:b.ttl"]);
+        assert_eq!(d2.quads().count(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+//! Bulk ingestion of RDF files into a dataset.
+//!
+//! The [`Ingester`] walks a directory (or takes an explicit list of files),
+//! parses every file in a recognized syntax in parallel,
+//! and inserts the result into a [`MutableDataset`].
+//! Errors are reported per file, and do not prevent the other files from being loaded.
+//!
+//! The syntax of each file is determined from its extension
+//! (see [`guess::from_extension`]),
+//! and compressed files are transparently decompressed
+//! (see the [`compression`] module).
+//!
+//! ```
+//! # use sophia::dataset::inmem::FastDataset;
+//! # use sophia::ingest::Ingester;
+//! # fn f() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut dataset = FastDataset::new();
+//! let report = Ingester::default().ingest_dir("ontologies", &mut dataset)?;
+//! for (path, error) in &report.errors {
+//!     eprintln!("could not load {}: {}", path.display(), error);
+//! }
+//! # Ok(()) }
+//! ```
+//!
+//! [`Ingester`]: struct.Ingester.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+//! [`guess::from_extension`]: ../syntax/guess/fn.from_extension.html
+//! [`compression`]: ../compression/index.html
+
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use sophia_api::dataset::MutableDataset;
+use thiserror::Error;
+
+use crate::compression::decompress;
+use crate::syntax::dynamic::{BoxQuad, DynError};
+use crate::syntax::{guess, Syntax};
+
+/// An error preventing a given file from being ingested.
+#[derive(Debug, Error)]
+pub enum IngestError {
+    /// The file could not be read.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The file could not be parsed.
+    #[error("{0}")]
+    Parse(#[from] DynError),
+    /// The syntax of the file could not be determined from its extension.
+    #[error("Could not determine the syntax of this file")]
+    UnknownSyntax,
+}
+
+/// The outcome of an ingestion.
+#[derive(Debug, Default)]
+pub struct IngestReport {
+    /// The files successfully ingested, with the number of quads they contributed.
+    pub loaded: Vec<(PathBuf, usize)>,
+    /// The files that could not be ingested, with the corresponding error.
+    pub errors: Vec<(PathBuf, IngestError)>,
+}
+
+/// Parses a collection of RDF files in parallel, and inserts them in a dataset.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Ingester {
+    recursive: bool,
+    threads: usize,
+}
+
+impl Default for Ingester {
+    fn default() -> Ingester {
+        Ingester {
+            recursive: true,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Ingester {
+    /// Set whether sub-directories are walked by [`ingest_dir`](#method.ingest_dir)
+    /// (defaults to `true`).
+    pub fn set_recursive(&mut self, recursive: bool) -> &mut Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Set the number of files parsed concurrently
+    /// (defaults to the available parallelism of the machine).
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// List the files of `dir` in a recognized syntax, sorted by path.
+    ///
+    /// Symbolic links to files are followed, but symbolic links to directories are skipped,
+    /// so that cycles in the tree do not cause infinite recursion.
+    pub fn files<P: AsRef<Path>>(&self, dir: P) -> io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        let mut dirs = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                let path = entry.path();
+                if file_type.is_dir() {
+                    if self.recursive {
+                        dirs.push(path);
+                    }
+                } else if file_type.is_symlink() && path.is_dir() {
+                    continue;
+                } else if guess_syntax(&path).is_some() {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Ingest all the files of `dir` in a recognized syntax into `dataset`.
+    ///
+    /// Fail only if `dir` can not be walked, or if `dataset` can not be mutated;
+    /// errors related to individual files are collected in the returned [`IngestReport`].
+    ///
+    /// [`IngestReport`]: struct.IngestReport.html
+    pub fn ingest_dir<P, D>(
+        &self,
+        dir: P,
+        dataset: &mut D,
+    ) -> Result<IngestReport, Box<dyn std::error::Error>>
+    where
+        P: AsRef<Path>,
+        D: MutableDataset,
+    {
+        let files = self.files(dir)?;
+        Ok(self.ingest_files(files, dataset)?)
+    }
+
+    /// Ingest the given files into `dataset`.
+    ///
+    /// This can be used, for example, with the result of a glob pattern.
+    /// Each file is ingested atomically:
+    /// a file containing an error contributes no quad to `dataset`.
+    /// Triples from triple-based syntaxes are inserted in the default graph.
+    ///
+    /// Fail only if `dataset` can not be mutated;
+    /// errors related to individual files are collected in the returned [`IngestReport`].
+    ///
+    /// [`IngestReport`]: struct.IngestReport.html
+    pub fn ingest_files<I, D>(
+        &self,
+        files: I,
+        dataset: &mut D,
+    ) -> Result<IngestReport, D::MutationError>
+    where
+        I: IntoIterator<Item = PathBuf>,
+        D: MutableDataset,
+    {
+        let queue = Mutex::new(files.into_iter().collect::<Vec<_>>().into_iter());
+        let (sender, receiver) = mpsc::sync_channel(self.threads);
+        let mut report = IngestReport::default();
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let sender = sender.clone();
+                let queue = &queue;
+                scope.spawn(move || loop {
+                    let path = match queue.lock().unwrap().next() {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    let quads = parse_file(&path);
+                    if sender.send((path, quads)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            for (path, quads) in receiver {
+                match quads {
+                    Ok(quads) => {
+                        for (spo, g) in &quads {
+                            dataset.insert(&spo[0], &spo[1], &spo[2], g.as_ref())?;
+                        }
+                        report.loaded.push((path, quads.len()));
+                    }
+                    Err(err) => report.errors.push((path, err)),
+                }
+            }
+            Ok(())
+        })?;
+        report.loaded.sort_by(|a, b| a.0.cmp(&b.0));
+        report.errors.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(report)
+    }
+}
+
+/// Parse a whole file, using its absolute `file:` IRI as the base IRI.
+fn parse_file(path: &Path) -> Result<Vec<BoxQuad>, IngestError> {
+    let syntax = guess_syntax(path).ok_or(IngestError::UnknownSyntax)?;
+    let read = decompress(BufReader::new(File::open(path)?))?;
+    let base = file_iri(&fs::canonicalize(path)?);
+    Ok(syntax
+        .parse_quads(read, Some(base))
+        .collect::<Result<_, _>>()?)
+}
+
+/// The syntax of the file at `path`, guessed from the extension of its name
+/// (the rest of the path may contain characters such as `#`, which are significant in IRIs).
+fn guess_syntax(path: &Path) -> Option<Syntax> {
+    guess::from_extension(&path.file_name()?.to_string_lossy())
+}
+
+/// The characters to percent-encode in a path segment of an IRI,
+/// in addition to non-ASCII characters.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// The `file:` IRI of the absolute path `path`, with its segments percent-encoded.
+fn file_iri(path: &Path) -> String {
+    let mut iri = String::from("file://");
+    for component in path.components() {
+        match component {
+            // a Windows drive or UNC prefix, e.g. `C:`
+            Component::Prefix(prefix) => {
+                iri.push('/');
+                iri.push_str(&prefix.as_os_str().to_string_lossy().replace('\\', "/"));
+            }
+            Component::RootDir => (),
+            component => {
+                let segment = component.as_os_str().to_string_lossy();
+                iri.push('/');
+                iri.extend(utf8_percent_encode(&segment, SEGMENT));
+            }
+        }
+    }
+    iri
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::quad::Quad;
+    use sophia_api::term::TTerm;
+
+    /// A temporary directory, removed when dropped (even if the test fails).
+    struct TempDir(PathBuf);
+
+    impl std::ops::Deref for TempDir {
+        type Target = Path;
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempDir {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn make_dir(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir =
+            std::env::temp_dir().join(format!("sophia-ingest-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let dir = TempDir(dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycle() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_dir("symlink", &[("sub/a.ttl", "<tag:a> <tag:b> <tag:c>.")]);
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop"))?;
+        std::os::unix::fs::symlink(dir.join("sub/a.ttl"), dir.join("b.ttl"))?;
+        let files = Ingester::default().files(&dir)?;
+        assert_eq!(files, vec![dir.join("b.ttl"), dir.join("sub/a.ttl")]);
+        Ok(())
+    }
+
+    #[test]
+    fn ingest_dir() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_dir(
+            "dir",
+            &[
+                ("a.ttl", "<tag:a> <tag:b> <tag:c>, <tag:d>."),
+                ("sub/b.nq", "<tag:a> <tag:b> <tag:c> <tag:g>.\n"),
+                ("sub/c.nt", "<tag:a> <tag:b> ."),
+                ("readme.txt", "not RDF"),
+            ],
+        );
+        let mut dataset = FastDataset::new();
+        let report = Ingester::default()
+            .set_threads(2)
+            .ingest_dir(&dir, &mut dataset)?;
+        assert_eq!(
+            report.loaded,
+            vec![(dir.join("a.ttl"), 2), (dir.join("sub/b.nq"), 1)]
+        );
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, dir.join("sub/c.nt"));
+        assert!(matches!(report.errors[0].1, IngestError::Parse(_)));
+        assert_eq!(dataset.quads().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn not_recursive() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_dir(
+            "flat",
+            &[("a.ttl", "<tag:a> <tag:b> <tag:c>."), ("sub/b.nt", "")],
+        );
+        let files = Ingester::default().set_recursive(false).files(&dir)?;
+        assert_eq!(files, vec![dir.join("a.ttl")]);
+        Ok(())
+    }
+
+    #[test]
+    fn relative_iris() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_dir("base", &[("a.ttl", "<#me> <#knows> <b.ttl#you>.")]);
+        let mut dataset = FastDataset::new();
+        let report = Ingester::default().ingest_dir(&dir, &mut dataset)?;
+        assert!(report.errors.is_empty());
+        let base = file_iri(&fs::canonicalize(dir.join("a.ttl"))?);
+        let q = dataset.quads().next().unwrap()?;
+        assert_eq!(q.s().value().to_string(), format!("{}#me", base));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_iri_escapes() {
+        assert_eq!(
+            file_iri(Path::new("/tmp/a b/c#d%e?.ttl")),
+            "file:///tmp/a%20b/c%23d%25e%3F.ttl"
+        );
+        assert_eq!(file_iri(Path::new("/tmp/é.ttl")), "file:///tmp/%C3%A9.ttl");
+        let dir = make_dir("escaped #1", &[("a.ttl", "<#me> <#knows> <b.ttl#you>.")]);
+        let mut dataset = FastDataset::new();
+        let report = Ingester::default().ingest_dir(&dir, &mut dataset).unwrap();
+        assert!(report.errors.is_empty());
+        let q = dataset.quads().next().unwrap().unwrap();
+        let s = q.s().value();
+        assert!(s.contains("/sophia-ingest-escaped%20%231-"), "{}", s);
+        assert!(s.ends_with("/a.ttl#me"), "{}", s);
+    }
+
+    #[test]
+    fn unknown_syntax() {
+        let mut dataset = FastDataset::new();
+        let report = Ingester::default()
+            .ingest_files(vec![PathBuf::from("foo.txt")], &mut dataset)
+            .unwrap();
+        assert!(matches!(report.errors[0].1, IngestError::UnknownSyntax));
+    }
+}
@@ -0,0 +1,361 @@
+//! Redirection of remote IRIs to local files, for offline and reproducible processing.
+//!
+//! An [`IriCatalog`] maps IRIs to local files, either one by one,
+//! or by replacing an IRI prefix with a local directory.
+//! It is used by every component of Sophia that dereferences IRIs,
+//! to read the local copy instead of fetching the remote document:
+//! the [HTTP loader] (see [`HttpLoader::set_catalog`]),
+//! the [`owl:imports` resolver] (see [`ImportResolver::set_catalog`]),
+//! and the JSON-LD parser of `sophia_jsonld` (for remote contexts).
+//!
+//! A catalog can be built programmatically, or read from a file
+//! in a subset of the [TOML] syntax:
+//! keys and values are strings,
+//! entries before any table (or in the `[iris]` table) map an IRI to a file,
+//! and entries in the `[prefixes]` table map an IRI prefix to a directory.
+//! Relative paths are resolved against the directory of the catalog file.
+//!
+//! ```toml
+//! # exact IRIs
+//! "http://xmlns.com/foaf/0.1/" = "vocab/foaf.ttl"
+//!
+//! [prefixes]
+//! # http://example.org/onto/core.ttl is read from ./onto/core.ttl
+//! "http://example.org/onto/" = "onto/"
+//! ```
+//!
+//! The fragment identifier of an IRI (if any) is ignored when looking it up.
+//!
+//! # Example
+//! ```
+//! use sophia::iri_catalog::IriCatalog;
+//! use std::path::Path;
+//!
+//! let toml = r#"
+//!     "http://xmlns.com/foaf/0.1/" = "vocab/foaf.ttl"
+//!     [prefixes]
+//!     "http://example.org/onto/" = "onto/"
+//! "#;
+//! let catalog = IriCatalog::from_toml_str(toml, "/data")?;
+//! assert_eq!(
+//!     catalog.resolve("http://xmlns.com/foaf/0.1/#Person"),
+//!     Some(Path::new("/data/vocab/foaf.ttl").to_path_buf()),
+//! );
+//! assert_eq!(
+//!     catalog.resolve("http://example.org/onto/core.ttl"),
+//!     Some(Path::new("/data/onto/core.ttl").to_path_buf()),
+//! );
+//! assert_eq!(catalog.resolve("http://example.org/other"), None);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`IriCatalog`]: struct.IriCatalog.html
+//! [HTTP loader]: ../loader/index.html
+//! [`HttpLoader::set_catalog`]: ../loader/struct.HttpLoader.html#method.set_catalog
+//! [`owl:imports` resolver]: ../imports/index.html
+//! [`ImportResolver::set_catalog`]: ../imports/struct.ImportResolver.html#method.set_catalog
+//! [TOML]: https://toml.io/
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// This error is raised when a catalog file can not be read.
+#[derive(Debug, Error)]
+pub enum IriCatalogError {
+    /// The catalog file could not be read.
+    #[error("Failed to read catalog {path:?}: {source}")]
+    Io {
+        /// The path of the catalog file.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    /// The catalog is not valid.
+    #[error("Invalid catalog (line {line}): {message}")]
+    Syntax {
+        /// The line of the error (counting from 1).
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+}
+
+/// A mapping from IRIs to local files.
+///
+/// See [module documentation](index.html) for more detail.
+#[derive(Clone, Debug, Default)]
+pub struct IriCatalog {
+    iris: HashMap<String, PathBuf>,
+    prefixes: Vec<(String, PathBuf)>,
+}
+
+impl IriCatalog {
+    /// Build an empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a catalog file (see [module documentation](index.html) for its syntax).
+    ///
+    /// Relative paths in the catalog are resolved against the directory of `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, IriCatalogError> {
+        let path = path.as_ref();
+        let txt = std::fs::read_to_string(path).map_err(|source| IriCatalogError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml_str(&txt, path.parent().unwrap_or_else(|| Path::new("")))
+    }
+
+    /// Parse a catalog (see [module documentation](index.html) for its syntax).
+    ///
+    /// Relative paths in the catalog are resolved against `base_dir`.
+    pub fn from_toml_str<P: AsRef<Path>>(txt: &str, base_dir: P) -> Result<Self, IriCatalogError> {
+        let base_dir = base_dir.as_ref();
+        let mut catalog = IriCatalog::new();
+        let mut in_prefixes = false;
+        for (i, line) in txt.lines().enumerate() {
+            let syntax_err = |message: &str| IriCatalogError::Syntax {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[') {
+                in_prefixes = match strip_comment(table.trim_end_matches(|c| c != ']')) {
+                    "iris]" => false,
+                    "prefixes]" => true,
+                    _ => return Err(syntax_err("unknown table (expected [iris] or [prefixes])")),
+                };
+                continue;
+            }
+            let (key, rest) =
+                parse_string(line).ok_or_else(|| syntax_err("expected a string key"))?;
+            let rest = rest
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or_else(|| syntax_err("expected '='"))?;
+            let (value, rest) = parse_string(rest.trim_start())
+                .ok_or_else(|| syntax_err("expected a string value"))?;
+            if !strip_comment(rest).is_empty() {
+                return Err(syntax_err("unexpected text after value"));
+            }
+            let path = base_dir.join(value);
+            if in_prefixes {
+                catalog.add_prefix(key, path);
+            } else {
+                catalog.add_iri(key, path);
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Map `iri` to the local file at `path`.
+    pub fn add_iri<I, P>(&mut self, iri: I, path: P) -> &mut Self
+    where
+        I: Into<String>,
+        P: Into<PathBuf>,
+    {
+        self.iris.insert(iri.into(), path.into());
+        self
+    }
+
+    /// Map every IRI starting with `prefix` to a file in the local directory `dir`,
+    /// whose relative path is the rest of the IRI.
+    pub fn add_prefix<I, P>(&mut self, prefix: I, dir: P) -> &mut Self
+    where
+        I: Into<String>,
+        P: Into<PathBuf>,
+    {
+        let prefix = prefix.into();
+        self.prefixes.retain(|(p, _)| *p != prefix);
+        self.prefixes.push((prefix, dir.into()));
+        self
+    }
+
+    /// The number of entries (IRIs and prefixes) in this catalog.
+    pub fn len(&self) -> usize {
+        self.iris.len() + self.prefixes.len()
+    }
+
+    /// Whether this catalog has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Merge the entries of `other` into this catalog
+    /// (entries of `other` take precedence).
+    pub fn extend(&mut self, other: &IriCatalog) -> &mut Self {
+        for (iri, path) in &other.iris {
+            self.add_iri(iri.clone(), path.clone());
+        }
+        for (prefix, dir) in &other.prefixes {
+            self.add_prefix(prefix.clone(), dir.clone());
+        }
+        self
+    }
+
+    /// The local file that `iri` is redirected to, if any.
+    ///
+    /// Exact IRIs take precedence over prefixes,
+    /// and longer prefixes take precedence over shorter ones.
+    /// A prefix does not apply if the rest of the IRI is empty,
+    /// or contains a `..` segment.
+    pub fn resolve(&self, iri: &str) -> Option<PathBuf> {
+        let iri = match iri.find('#') {
+            Some(pos) => &iri[..pos],
+            None => iri,
+        };
+        if let Some(path) = self.iris.get(iri) {
+            return Some(path.clone());
+        }
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, dir)| Some((prefix, dir, iri.strip_prefix(prefix.as_str())?)))
+            .filter(|(_, _, rest)| !rest.is_empty() && !rest.split('/').any(|seg| seg == ".."))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|(_, dir, rest)| dir.join(rest.trim_start_matches('/')))
+    }
+
+    /// Whether `iri` is redirected to a local file.
+    pub fn contains(&self, iri: &str) -> bool {
+        self.resolve(iri).is_some()
+    }
+}
+
+/// Remove a trailing comment from `txt` (which must not contain strings), and trim it.
+fn strip_comment(txt: &str) -> &str {
+    match txt.find('#') {
+        Some(pos) => txt[..pos].trim(),
+        None => txt.trim(),
+    }
+}
+
+/// Parse a TOML basic string ("...") or literal string ('...') at the start of `txt`,
+/// and return it with the rest of `txt`.
+fn parse_string(txt: &str) -> Option<(String, &str)> {
+    if let Some(txt) = txt.strip_prefix('\'') {
+        let end = txt.find('\'')?;
+        return Some((txt[..end].to_string(), &txt[end + 1..]));
+    }
+    let txt = txt.strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = txt.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &txt[pos + 1..])),
+            '\\' => match chars.next()?.1 {
+                '"' => value.push('"'),
+                '\\' => value.push('\\'),
+                't' => value.push('\t'),
+                'n' => value.push('\n'),
+                _ => return None,
+            },
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let mut catalog = IriCatalog::new();
+        catalog
+            .add_iri("http://example.org/onto/special", "/special.ttl")
+            .add_prefix("http://example.org/", "/all")
+            .add_prefix("http://example.org/onto/", "/onto");
+        assert_eq!(catalog.len(), 3);
+        let resolve = |iri| {
+            catalog
+                .resolve(iri)
+                .map(|p| p.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            resolve("http://example.org/onto/special#x").as_deref(),
+            Some("/special.ttl")
+        );
+        assert_eq!(
+            resolve("http://example.org/onto/core.ttl").as_deref(),
+            Some("/onto/core.ttl")
+        );
+        assert_eq!(
+            resolve("http://example.org/data/x.nt").as_deref(),
+            Some("/all/data/x.nt")
+        );
+        assert_eq!(resolve("http://example.org/"), None);
+        assert_eq!(resolve("http://example.org/onto/../../etc/passwd"), None);
+        assert_eq!(resolve("http://example.com/"), None);
+        assert!(!catalog.contains("http://example.com/"));
+    }
+
+    #[test]
+    fn toml() -> Result<(), IriCatalogError> {
+        let toml = r#"
+            # a comment
+            "tag:a" = "a.ttl" # another comment
+            'tag:b' = 'b\n.ttl'
+            [prefixes]
+            "tag:dir/" = "dir"
+            [iris] # back to IRIs
+            "tag:c" = "/abs/c.nt"
+        "#;
+        let catalog = IriCatalog::from_toml_str(toml, "/base")?;
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(catalog.resolve("tag:a"), Some(PathBuf::from("/base/a.ttl")));
+        assert_eq!(
+            catalog.resolve("tag:b"),
+            Some(PathBuf::from("/base/b\\n.ttl"))
+        );
+        assert_eq!(catalog.resolve("tag:c"), Some(PathBuf::from("/abs/c.nt")));
+        assert_eq!(
+            catalog.resolve("tag:dir/x"),
+            Some(PathBuf::from("/base/dir/x"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn toml_errors() {
+        for (toml, line) in &[
+            ("[other]", 1),
+            ("\n\"tag:a\" \"a.ttl\"", 2),
+            ("tag:a = \"a.ttl\"", 1),
+            ("\"tag:a\" = a.ttl", 1),
+            ("\"tag:a\" = \"a.ttl\" x", 1),
+            ("\"tag:a\" = \"a.ttl", 1),
+        ] {
+            match IriCatalog::from_toml_str(toml, "") {
+                Err(IriCatalogError::Syntax { line: l, .. }) => assert_eq!(l, *line, "{}", toml),
+                other => panic!("{:?} for {}", other, toml),
+            }
+        }
+    }
+
+    #[test]
+    fn from_file() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("sophia-iri-catalog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("catalog.toml");
+        std::fs::write(&path, "\"tag:a\" = \"a.ttl\"\n")?;
+        let catalog = IriCatalog::from_file(&path);
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(catalog?.resolve("tag:a"), Some(dir.join("a.ttl")));
+        assert!(matches!(
+            IriCatalog::from_file(dir.join("missing.toml")),
+            Err(IriCatalogError::Io { .. })
+        ));
+        Ok(())
+    }
+}
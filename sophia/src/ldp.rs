@@ -0,0 +1,535 @@
+//! A client for [Linked Data Platform] (LDP) servers.
+//!
+//! This module is only available with the `http` feature.
+//!
+//! [`LdpClient`] reads, creates, replaces, patches and deletes RDF resources
+//! on an LDP server (e.g. a Solid pod).
+//! Resources are read with an [`HttpLoader`] (so content negotiation and redirections
+//! work as in the [`loader`] module) into any [`MutableGraph`],
+//! and written from any [`Graph`].
+//!
+//! The [`LdpResource`] returned by [`LdpClient::get`] keeps the `ETag` of the resource
+//! (to be used for conditional updates),
+//! and its LDP interaction models (from the `Link: <...>; rel="type"` headers),
+//! so that containers and their members can be identified.
+//!
+//! To access protected resources (e.g. on Solid pods),
+//! set an [`Authenticator`] on the loader used by the client
+//! (see [`LdpClient::with_loader`]).
+//!
+//! # Example
+//! ```no_run
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::ldp::LdpClient;
+//!
+//! let client = LdpClient::new();
+//! let container = client.get::<FastGraph>("https://pod.example.org/notes/")?;
+//! if container.is_container() {
+//!     for member in container.members()? {
+//!         let note = client.get::<FastGraph>(&member)?;
+//!         // do something with note.graph
+//!     }
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Linked Data Platform]: https://www.w3.org/TR/ldp/
+//! [`LdpClient`]: struct.LdpClient.html
+//! [`LdpClient::get`]: struct.LdpClient.html#method.get
+//! [`LdpClient::with_loader`]: struct.LdpClient.html#method.with_loader
+//! [`LdpResource`]: struct.LdpResource.html
+//! [`Authenticator`]: ../loader/trait.Authenticator.html
+//! [`HttpLoader`]: ../loader/struct.HttpLoader.html
+//! [`loader`]: ../loader/index.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+//! [`Graph`]: ../graph/trait.Graph.html
+
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::serializer::{Stringifier, TripleSerializer};
+use sophia_api::term::TTerm;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_api::triple::Triple;
+use sophia_term::iri::resolve::{IriParsed, Resolve};
+use sophia_term::RefTerm;
+use thiserror::Error;
+
+use crate::loader::{parse_into_graph, read_response, HttpLoader, LoaderError};
+use crate::serializer::nt::NtSerializer;
+
+/// The LDP namespace.
+#[allow(missing_docs, clippy::module_inception)]
+pub mod ldp {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/ns/ldp#",
+        BasicContainer,
+        Container,
+        DirectContainer,
+        IndirectContainer,
+        NonRDFSource,
+        RDFSource,
+        Resource,
+        contains,
+        hasMemberRelation,
+        member,
+        membershipResource
+    );
+}
+
+/// This error is raised when an interaction with an LDP server fails.
+#[derive(Debug, Error)]
+pub enum LdpError {
+    /// The resource could not be read.
+    #[error("{0}")]
+    Load(#[from] LoaderError),
+    /// The HTTP request failed (network error, or an HTTP error status).
+    #[error("Failed to {method} <{iri}>: {source}")]
+    Http {
+        /// The HTTP method of the request.
+        method: &'static str,
+        /// The IRI of the request.
+        iri: String,
+        /// The underlying error.
+        source: Box<ureq::Error>,
+    },
+    /// The graph to send could not be serialized.
+    #[error("Failed to serialize graph for <{iri}>: {message}")]
+    Serialization {
+        /// The IRI of the request.
+        iri: String,
+        /// The error message.
+        message: String,
+    },
+    /// The server did not return the location of a created resource.
+    #[error("No valid Location returned when creating a resource in <{iri}>")]
+    MissingLocation {
+        /// The IRI of the container.
+        iri: String,
+    },
+}
+
+/// An RDF resource retrieved from an LDP server.
+#[derive(Clone, Debug)]
+pub struct LdpResource<G> {
+    /// The IRI of the resource (after redirections).
+    pub iri: String,
+    /// The content of the resource.
+    pub graph: G,
+    /// The `ETag` of the resource, if any.
+    pub etag: Option<String>,
+    /// The interaction models (`Link` headers with `rel="type"`) of the resource.
+    pub types: Vec<String>,
+}
+
+impl<G> LdpResource<G> {
+    /// Whether the server advertises this resource as a container.
+    pub fn is_container(&self) -> bool {
+        let containers = [
+            &ldp::Container,
+            &ldp::BasicContainer,
+            &ldp::DirectContainer,
+            &ldp::IndirectContainer,
+        ];
+        self.types
+            .iter()
+            .any(|t| containers.iter().any(|c| c.value() == t.as_str()))
+    }
+}
+
+impl<G: Graph> LdpResource<G> {
+    /// The IRIs of the members of this container,
+    /// i.e. the objects of its `ldp:contains` triples.
+    ///
+    /// Returns an empty vector if this resource is not a container.
+    pub fn members(&self) -> Result<Vec<String>, G::Error> {
+        let container = match RefTerm::new_iri(self.iri.as_str()) {
+            Ok(container) => container,
+            Err(_) => return Ok(vec![]),
+        };
+        let mut members = vec![];
+        for t in self.graph.triples_with_sp(&container, &ldp::contains) {
+            let t = t?;
+            if t.o().kind() == sophia_api::term::TermKind::Iri {
+                members.push(t.o().value().to_string());
+            }
+        }
+        Ok(members)
+    }
+}
+
+/// A client for an LDP server.
+///
+/// See [module documentation](index.html) for more detail.
+#[derive(Clone, Debug, Default)]
+pub struct LdpClient {
+    loader: HttpLoader,
+}
+
+impl LdpClient {
+    /// Build a client with a default [`HttpLoader`](../loader/struct.HttpLoader.html).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a client using `loader` to retrieve resources.
+    pub fn with_loader(loader: HttpLoader) -> Self {
+        LdpClient { loader }
+    }
+
+    /// Retrieve the resource at `iri` into a new graph.
+    pub fn get<G>(&self, iri: &str) -> StreamResult<LdpResource<G>, LdpError, G::MutationError>
+    where
+        G: MutableGraph + Default,
+    {
+        let response = self.loader.get(iri).map_err(|e| SourceError(e.into()))?;
+        let etag = response.header("ETag").map(str::to_string);
+        let types = link_types(&response);
+        let (iri, syntax, body) = read_response(response).map_err(|e| SourceError(e.into()))?;
+        let mut graph = G::default();
+        parse_into_graph(&iri, syntax, body, &mut graph).map_err(|e| match e {
+            SourceError(e) => SourceError(e.into()),
+            SinkError(e) => SinkError(e),
+        })?;
+        Ok(LdpResource {
+            iri,
+            graph,
+            etag,
+            types,
+        })
+    }
+
+    /// Create or replace the resource at `iri` with the content of `graph`.
+    ///
+    /// If `if_match` is provided (typically the [`etag`] of a previously retrieved resource),
+    /// the server will only replace the resource if it has not changed in the meantime.
+    ///
+    /// Returns the new `ETag` of the resource, if the server provides one.
+    ///
+    /// [`etag`]: struct.LdpResource.html#structfield.etag
+    pub fn put<G: Graph>(
+        &self,
+        iri: &str,
+        graph: &G,
+        if_match: Option<&str>,
+    ) -> Result<Option<String>, LdpError> {
+        let body = serialize(iri, graph)?;
+        let mut request = self
+            .loader
+            .request("PUT", iri)
+            .set("Content-Type", "text/turtle");
+        if let Some(etag) = if_match {
+            request = request.set("If-Match", etag);
+        }
+        let response = request
+            .send_string(&body)
+            .map_err(|e| http_error("PUT", iri, e))?;
+        Ok(response.header("ETag").map(str::to_string))
+    }
+
+    /// Create a new resource in `container` with the content of `graph`,
+    /// optionally suggesting a name for it with `slug`.
+    ///
+    /// Returns the IRI of the created resource.
+    pub fn post<G: Graph>(
+        &self,
+        container: &str,
+        graph: &G,
+        slug: Option<&str>,
+    ) -> Result<String, LdpError> {
+        let body = serialize(container, graph)?;
+        self.create(container, &body, slug, &ldp::Resource)
+    }
+
+    /// Create a new, empty, basic container in `container`,
+    /// optionally suggesting a name for it with `slug`.
+    ///
+    /// Returns the IRI of the created container.
+    pub fn create_container(
+        &self,
+        container: &str,
+        slug: Option<&str>,
+    ) -> Result<String, LdpError> {
+        self.create(container, "", slug, &ldp::BasicContainer)
+    }
+
+    /// Modify the resource at `iri` by removing the triples of `delete`
+    /// and adding the triples of `insert`,
+    /// with a SPARQL Update `PATCH` request.
+    ///
+    /// NB: `delete` should not contain blank nodes,
+    /// as they can not be matched against the existing content of the resource.
+    pub fn patch<G: Graph, H: Graph>(
+        &self,
+        iri: &str,
+        delete: &G,
+        insert: &H,
+    ) -> Result<(), LdpError> {
+        let delete = serialize(iri, delete)?;
+        let insert = serialize(iri, insert)?;
+        let update = sparql_update(&delete, &insert);
+        if update.is_empty() {
+            return Ok(());
+        }
+        self.loader
+            .request("PATCH", iri)
+            .set("Content-Type", "application/sparql-update")
+            .send_string(&update)
+            .map_err(|e| http_error("PATCH", iri, e))?;
+        Ok(())
+    }
+
+    /// Delete the resource at `iri`.
+    pub fn delete(&self, iri: &str) -> Result<(), LdpError> {
+        self.loader
+            .request("DELETE", iri)
+            .call()
+            .map_err(|e| http_error("DELETE", iri, e))?;
+        Ok(())
+    }
+
+    fn create<T: TTerm>(
+        &self,
+        container: &str,
+        body: &str,
+        slug: Option<&str>,
+        model: &T,
+    ) -> Result<String, LdpError> {
+        let mut request = self
+            .loader
+            .request("POST", container)
+            .set("Content-Type", "text/turtle")
+            .set("Link", &format!("<{}>; rel=\"type\"", model.value()));
+        if let Some(slug) = slug {
+            request = request.set("Slug", slug);
+        }
+        let response = request
+            .send_string(body)
+            .map_err(|e| http_error("POST", container, e))?;
+        let missing = || LdpError::MissingLocation {
+            iri: container.to_string(),
+        };
+        let location = response.header("Location").ok_or_else(missing)?;
+        let base = IriParsed::new(container).map_err(|_| missing())?;
+        let location = IriParsed::new(location).map_err(|_| missing())?;
+        Ok(base.resolve(&location).to_string())
+    }
+}
+
+fn http_error(method: &'static str, iri: &str, source: ureq::Error) -> LdpError {
+    LdpError::Http {
+        method,
+        iri: iri.to_string(),
+        source: Box::new(source),
+    }
+}
+
+fn serialize<G: Graph>(iri: &str, graph: &G) -> Result<String, LdpError> {
+    let mut stringifier = NtSerializer::new_stringifier();
+    stringifier.serialize_graph(graph).map_err(|e| {
+        let message = match e {
+            SourceError(e) => e.to_string(),
+            SinkError(e) => e.to_string(),
+        };
+        LdpError::Serialization {
+            iri: iri.to_string(),
+            message,
+        }
+    })?;
+    Ok(stringifier.as_str().to_string())
+}
+
+/// Build a SPARQL Update request deleting then inserting the given N-Triples.
+///
+/// Returns an empty string if there is nothing to delete nor insert.
+fn sparql_update(delete: &str, insert: &str) -> String {
+    let mut parts = vec![];
+    if !delete.is_empty() {
+        parts.push(format!("DELETE DATA {{\n{}}}", delete));
+    }
+    if !insert.is_empty() {
+        parts.push(format!("INSERT DATA {{\n{}}}", insert));
+    }
+    parts.join(";\n")
+}
+
+/// Extract the targets of the `Link` headers with `rel="type"`.
+fn link_types(response: &ureq::Response) -> Vec<String> {
+    response
+        .all("Link")
+        .into_iter()
+        .flat_map(|header| header.split(','))
+        .filter_map(|link| {
+            let mut parts = link.split(';');
+            let target = parts.next()?.trim();
+            let is_type = parts.any(|param| {
+                let param = param.trim().replace(' ', "");
+                param == "rel=\"type\"" || param == "rel=type"
+            });
+            if is_type && target.starts_with('<') && target.ends_with('>') {
+                Some(target[1..target.len() - 1].to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Serve the given raw responses (one per connection) on a local port,
+    /// and return the base IRI of the server, and a receiver for the received requests.
+    fn serve(responses: Vec<String>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut request = vec![];
+                let mut buf = [0; 4096];
+                // read headers and (Content-Length) body
+                loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Content-Length: "))
+                            .map(|l| l.trim().parse::<usize>().unwrap())
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                tx.send(String::from_utf8_lossy(&request).to_string())
+                    .unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://{}/", addr), rx)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\n", status);
+        for h in headers {
+            response.push_str(h);
+            response.push_str("\r\n");
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        response
+    }
+
+    #[test]
+    fn get_container() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "@prefix ldp: <http://www.w3.org/ns/ldp#>.\n<> a ldp:BasicContainer; ldp:contains <a>, <b>.\n";
+        let (base, requests) = serve(vec![response(
+            "200 OK",
+            &[
+                "Content-Type: text/turtle",
+                "ETag: \"v1\"",
+                "Link: <http://www.w3.org/ns/ldp#BasicContainer>; rel=\"type\", <http://www.w3.org/ns/ldp#Resource>; rel=\"type\"",
+            ],
+            body,
+        )]);
+        let container = LdpClient::new().get::<FastGraph>(&base)?;
+        assert!(requests.recv()?.starts_with("GET / "));
+        assert_eq!(container.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(container.types.len(), 2);
+        assert!(container.is_container());
+        let mut members = container.members()?;
+        members.sort();
+        assert_eq!(members, vec![format!("{}a", base), format!("{}b", base)]);
+        Ok(())
+    }
+
+    #[test]
+    fn write_resources() -> Result<(), Box<dyn std::error::Error>> {
+        let (base, requests) = serve(vec![
+            response("201 Created", &["Location: notes/1"], ""),
+            response("204 No Content", &["ETag: \"v2\""], ""),
+            response("204 No Content", &[], ""),
+            response("204 No Content", &[], ""),
+        ]);
+        let client = LdpClient::new();
+        let g: FastGraph = turtle::parse_str("<tag:s> <tag:p> \"v1\".").collect_triples()?;
+        let h: FastGraph = turtle::parse_str("<tag:s> <tag:p> \"v2\".").collect_triples()?;
+
+        let iri = client.post(&base, &g, Some("1"))?;
+        assert_eq!(iri, format!("{}notes/1", base));
+        let request = requests.recv()?;
+        assert!(request.starts_with("POST / "));
+        assert!(request.contains("Slug: 1\r\n"));
+        assert!(request.contains("rel=\"type\""));
+        assert!(request.ends_with("<tag:s> <tag:p> \"v1\".\n"));
+
+        let etag = client.put(&iri, &h, Some("\"v1\""))?;
+        assert_eq!(etag.as_deref(), Some("\"v2\""));
+        let request = requests.recv()?;
+        assert!(request.starts_with("PUT /notes/1 "));
+        assert!(request.contains("If-Match: \"v1\"\r\n"));
+
+        client.patch(&iri, &h, &g)?;
+        let request = requests.recv()?;
+        assert!(request.starts_with("PATCH /notes/1 "));
+        assert!(request.contains("application/sparql-update"));
+        assert!(request.ends_with(
+            "DELETE DATA {\n<tag:s> <tag:p> \"v2\".\n};\nINSERT DATA {\n<tag:s> <tag:p> \"v1\".\n}"
+        ));
+
+        client.delete(&iri)?;
+        assert!(requests.recv()?.starts_with("DELETE /notes/1 "));
+        Ok(())
+    }
+
+    #[test]
+    fn authenticated() -> Result<(), Box<dyn std::error::Error>> {
+        let (base, requests) = serve(vec![
+            response("200 OK", &["Content-Type: text/turtle"], "<> a <tag:c>."),
+            response("204 No Content", &[], ""),
+        ]);
+        let mut loader = HttpLoader::default();
+        loader.set_authenticator(|method: &str, iri: &str| {
+            vec![
+                ("Authorization".to_string(), "DPoP token".to_string()),
+                ("DPoP".to_string(), format!("proof for {} {}", method, iri)),
+            ]
+        });
+        let client = LdpClient::with_loader(loader);
+        let resource = client.get::<FastGraph>(&base)?;
+        assert!(!resource.is_container());
+        let request = requests.recv()?;
+        assert!(request.contains("Authorization: DPoP token\r\n"));
+        assert!(request.contains(&format!("DPoP: proof for GET {}\r\n", base)));
+        client.delete(&base)?;
+        let request = requests.recv()?;
+        assert!(request.contains(&format!("DPoP: proof for DELETE {}\r\n", base)));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_patch() {
+        assert_eq!(sparql_update("", ""), "");
+    }
+}
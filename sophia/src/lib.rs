@@ -63,23 +63,112 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
+pub mod aggregate;
+pub mod annotation;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod builder;
+pub mod catalog;
+pub mod cbd;
+pub mod coerce;
+pub mod compression;
+#[cfg(feature = "csvw")]
+pub mod csvw;
+pub mod datatype;
+pub mod describe;
+pub mod edit;
+pub mod expression;
+#[cfg(feature = "fulltext")]
+pub mod fulltext;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod gsp;
+pub mod imports;
+pub mod ingest;
+pub mod iri_catalog;
+#[cfg(feature = "http")]
+pub mod ldp;
+pub mod lint;
+#[cfg(feature = "http")]
+pub mod loader;
+pub mod merge;
+pub mod metrics;
+#[cfg(feature = "signing")]
+pub mod nanopub;
+#[cfg(feature = "nfc")]
+pub mod nfc;
+pub mod ontology;
+pub mod partition;
+pub mod path;
+pub mod pattern;
+pub mod property_graph;
 pub mod query;
+pub mod resource;
+pub mod rewrite;
+#[cfg(feature = "rml")]
+pub mod rml;
+pub mod rule;
+pub mod sample;
+pub mod shapes;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod skos;
+pub mod smush;
+pub mod sort;
+pub mod sparql_results;
+#[cfg(feature = "sql")]
+pub mod sql;
+pub mod summary;
+pub mod syntax;
+#[cfg(feature = "template")]
+pub mod template;
+pub mod test_suite;
+pub mod transcode;
+pub mod traversal;
+#[cfg(feature = "signing")]
+pub mod trusty;
+#[cfg(feature = "units")]
+pub mod units;
+pub mod validation;
+pub mod view;
+pub mod viz;
+pub mod vocab;
+
+#[cfg(feature = "macros")]
+pub use sophia_macros::static_graph;
 
 /// This module re-exports symbols from
 /// [`sophia_api::dataset`](https://docs.rs/sophia_api/latest/sophia_api/dataset/),
 /// and also provides some implementations of its traits.
 pub mod dataset {
     pub use sophia_api::dataset::*;
+    pub mod delta;
     pub mod indexed;
     pub mod inmem;
+    #[cfg(feature = "persistent")]
+    pub mod persistent;
+    pub mod quota;
+    pub mod store;
+    pub mod versioned;
 }
 /// This module re-exports symbols from
 /// [`sophia_api::graph`](https://docs.rs/sophia_api/latest/sophia_api/graph/),
 /// and also provides some implementations of its traits.
 pub mod graph {
     pub use sophia_api::graph::*;
+    pub mod arena;
+    pub mod bag;
+    pub mod columnar;
+    pub mod concurrent;
+    pub mod cow;
+    pub mod filtered;
     pub mod indexed;
     pub mod inmem;
+    pub mod mock;
+    #[cfg(feature = "http")]
+    pub mod sparql;
 }
 /// This module re-exports symbols from
 /// [`sophia_api::ns`](https://docs.rs/sophia_api/latest/sophia_api/ns/).
@@ -91,9 +180,14 @@ pub mod ns {
 /// and also provides some implementations of its traits.
 pub mod parser {
     pub use sophia_api::parser::*;
+    pub mod config;
     pub mod gtrig;
     pub mod nq;
+    pub mod nq12;
     pub mod nt;
+    pub mod nt12;
+    #[cfg(feature = "rdfa")]
+    pub mod rdfa;
     pub mod rio_common;
     pub mod trig;
     pub mod turtle;
@@ -112,8 +206,13 @@ pub mod quad {
 /// and also provides some implementations of its traits.
 pub mod serializer {
     pub use sophia_api::serializer::*;
+    mod buffer;
+    pub use buffer::DEFAULT_BUFFER_SIZE;
     pub mod nq;
     pub mod nt;
+    pub mod per_graph;
+    mod star;
+    pub mod turtle;
 }
 /// This module re-exports symbols from
 /// [`sophia_api::term`](https://docs.rs/sophia_api/latest/sophia_api/term/)
@@ -128,3 +227,6 @@ pub mod term {
 pub mod triple {
     pub use sophia_api::triple::*;
 }
+
+#[cfg(test)]
+mod test_util;
@@ -0,0 +1,511 @@
+//! Quality checks ("linting") for RDF graphs, with pluggable rules.
+//!
+//! A [`Linter`] runs a set of [`LintRule`]s over a graph,
+//! and gathers the problems they find in a [`LintReport`],
+//! which can be used as a quality gate before publishing data
+//! (e.g. by failing if [`LintReport::has_errors`]).
+//!
+//! The following rules are provided:
+//! * [`IllTypedLiterals`] reports literals whose lexical form is invalid for their datatype
+//!   (see [`validation`](../validation/index.html));
+//! * [`UndefinedTerms`] reports classes and properties
+//!   that are not defined by the vocabulary they seem to belong to;
+//! * [`DeprecatedTerms`] reports the use of terms marked as `owl:deprecated` in a vocabulary;
+//! * [`OrphanBlankNodes`] reports blank nodes that are described but never referenced.
+//!
+//! Other rules can be added by implementing [`LintRule`].
+//! The severity of each rule can be overridden, and rules can be disabled,
+//! with [`Linter::set_severity`].
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, MutableGraph};
+//! # use sophia::ns::{rdf, rdfs, xsd};
+//! # use sophia::term::StaticTerm;
+//! use sophia::lint::{IllTypedLiterals, Linter, Severity, UndefinedTerms};
+//!
+//! let person = StaticTerm::new_iri("http://schema.org/Person")?;
+//! let persn = StaticTerm::new_iri("http://schema.org/Persn")?;
+//! let mut vocab = FastGraph::new();
+//! vocab.insert(&person, &rdf::type_, &rdfs::Class)?;
+//!
+//! let alice = StaticTerm::new_iri("http://example.org/alice")?;
+//! let age = StaticTerm::new_iri("http://example.org/age")?;
+//! let mut data = FastGraph::new();
+//! data.insert(&alice, &rdf::type_, &persn)?;
+//! data.insert(&alice, &age, &StaticTerm::new_literal_dt("forty", xsd::integer)?)?;
+//!
+//! let mut linter = Linter::new();
+//! linter
+//!     .add_rule(IllTypedLiterals)
+//!     .add_rule(UndefinedTerms::new(&vocab)?);
+//! let report = linter.run(&data)?;
+//! assert_eq!(report.lints().len(), 2);
+//! assert!(report.has_errors());
+//! assert_eq!(report.with_severity(Severity::Warning).count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Linter`]: struct.Linter.html
+//! [`Linter::set_severity`]: struct.Linter.html#method.set_severity
+//! [`LintRule`]: trait.LintRule.html
+//! [`LintReport`]: struct.LintReport.html
+//! [`LintReport::has_errors`]: struct.LintReport.html#method.has_errors
+//! [`IllTypedLiterals`]: struct.IllTypedLiterals.html
+//! [`UndefinedTerms`]: struct.UndefinedTerms.html
+//! [`DeprecatedTerms`]: struct.DeprecatedTerms.html
+//! [`OrphanBlankNodes`]: struct.OrphanBlankNodes.html
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use sophia_api::ns::{owl, rdf, xsd};
+use sophia_api::term::{term_eq, term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+use crate::validation::check_literal;
+
+/// The severity of a [`Lint`](struct.Lint.html).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A problem found by a [`LintRule`](trait.LintRule.html).
+#[derive(Clone, Debug)]
+pub struct Lint {
+    /// The name of the rule that found the problem.
+    pub rule: String,
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The offending triple.
+    pub triple: [BoxTerm; 3],
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {} in {} {} {}",
+            self.severity,
+            self.rule,
+            self.message,
+            term_to_string(&self.triple[0]),
+            term_to_string(&self.triple[1]),
+            term_to_string(&self.triple[2]),
+        )
+    }
+}
+
+/// A check run by a [`Linter`](struct.Linter.html) over graphs of type `G`.
+pub trait LintRule<G: Graph + ?Sized> {
+    /// The name of this rule, used to identify it in reports and in the linter configuration.
+    fn name(&self) -> &str;
+
+    /// The default severity of the problems found by this rule.
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Check `graph`, and return the offending triples, with a description of the problem.
+    fn check(&self, graph: &G) -> GResult<G, Vec<([BoxTerm; 3], String)>>;
+}
+
+/// The problems found by a [`Linter`](struct.Linter.html).
+#[derive(Clone, Debug, Default)]
+pub struct LintReport {
+    lints: Vec<Lint>,
+}
+
+impl LintReport {
+    /// All the problems found, sorted by decreasing severity.
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    /// The problems with the given severity.
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Lint> + '_ {
+        self.lints.iter().filter(move |l| l.severity == severity)
+    }
+
+    /// Whether a problem with severity `Error` was found.
+    pub fn has_errors(&self) -> bool {
+        self.lints.iter().any(|l| l.severity == Severity::Error)
+    }
+
+    /// Whether no problem was found.
+    pub fn is_empty(&self) -> bool {
+        self.lints.is_empty()
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for lint in &self.lints {
+            writeln!(f, "{}", lint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a set of [`LintRule`](trait.LintRule.html)s over graphs of type `G`.
+pub struct Linter<G: Graph + ?Sized> {
+    rules: Vec<Box<dyn LintRule<G>>>,
+    severities: HashMap<String, Option<Severity>>,
+}
+
+impl<G: Graph + ?Sized> Default for Linter<G> {
+    fn default() -> Self {
+        Linter {
+            rules: vec![],
+            severities: HashMap::new(),
+        }
+    }
+}
+
+impl<G: Graph + ?Sized> Linter<G> {
+    /// A linter with no rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule to this linter.
+    pub fn add_rule<R: LintRule<G> + 'static>(&mut self, rule: R) -> &mut Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// The names of the rules of this linter.
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.rules.iter().map(|r| r.name())
+    }
+
+    /// Override the severity of the rule named `rule`,
+    /// or disable it if `severity` is `None`.
+    pub fn set_severity<S: Into<String>>(
+        &mut self,
+        rule: S,
+        severity: Option<Severity>,
+    ) -> &mut Self {
+        self.severities.insert(rule.into(), severity);
+        self
+    }
+
+    /// Run all the enabled rules over `graph`.
+    pub fn run(&self, graph: &G) -> GResult<G, LintReport> {
+        let mut lints = vec![];
+        for rule in &self.rules {
+            let severity = match self.severities.get(rule.name()) {
+                Some(None) => continue,
+                Some(Some(severity)) => *severity,
+                None => rule.severity(),
+            };
+            for (triple, message) in rule.check(graph)? {
+                lints.push(Lint {
+                    rule: rule.name().to_string(),
+                    severity,
+                    triple,
+                    message,
+                });
+            }
+        }
+        lints.sort_by_key(|l| std::cmp::Reverse(l.severity));
+        Ok(LintReport { lints })
+    }
+}
+
+/// Reports literals whose lexical form is not valid for their datatype
+/// (see [`check_literal`](../validation/fn.check_literal.html)).
+///
+/// Its name is `ill-typed-literal`, and its default severity is `Error`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IllTypedLiterals;
+
+impl<G: Graph + ?Sized> LintRule<G> for IllTypedLiterals {
+    fn name(&self) -> &str {
+        "ill-typed-literal"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn check(&self, graph: &G) -> GResult<G, Vec<([BoxTerm; 3], String)>> {
+        let mut found = vec![];
+        for t in graph.triples() {
+            let t = t?;
+            if let Err(reason) = check_literal(t.o()) {
+                found.push((copy_triple(&t), reason));
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Reports classes (objects of `rdf:type`) and properties (predicates)
+/// that are not defined in a vocabulary graph,
+/// although their namespace is that of terms defined in this vocabulary
+/// (typically a misspelling, such as `schema:Persn`).
+///
+/// A term is considered defined if it is the subject of a triple in the vocabulary.
+/// Terms in namespaces unknown to the vocabulary are not reported,
+/// nor are terms from the RDF and XSD namespaces.
+///
+/// Its name is `undefined-term`, and its default severity is `Warning`.
+pub struct UndefinedTerms {
+    defined: HashSet<String>,
+    namespaces: HashSet<String>,
+}
+
+impl UndefinedTerms {
+    /// Check terms against the given vocabulary graph
+    /// (e.g. the union of the ontologies used by the data).
+    pub fn new<V: Graph>(vocabulary: &V) -> GResult<V, Self> {
+        let mut defined = HashSet::new();
+        let mut namespaces = HashSet::new();
+        for t in vocabulary.triples() {
+            let t = t?;
+            if t.s().kind() == TermKind::Iri {
+                let iri = t.s().value().to_string();
+                namespaces.insert(namespace(&iri).to_string());
+                defined.insert(iri);
+            }
+        }
+        namespaces.remove(namespace(rdf::PREFIX));
+        namespaces.remove(namespace(xsd::PREFIX));
+        Ok(UndefinedTerms {
+            defined,
+            namespaces,
+        })
+    }
+
+    fn is_undefined<T: TTerm + ?Sized>(&self, term: &T) -> bool {
+        if term.kind() != TermKind::Iri {
+            return false;
+        }
+        let iri = term.value();
+        self.namespaces.contains(namespace(&iri)) && !self.defined.contains(&iri[..])
+    }
+}
+
+impl<G: Graph + ?Sized> LintRule<G> for UndefinedTerms {
+    fn name(&self) -> &str {
+        "undefined-term"
+    }
+    fn check(&self, graph: &G) -> GResult<G, Vec<([BoxTerm; 3], String)>> {
+        let mut found = vec![];
+        for t in graph.triples() {
+            let t = t?;
+            if self.is_undefined(t.p()) {
+                let msg = format!("undefined property {}", term_to_string(t.p()));
+                found.push((copy_triple(&t), msg));
+            }
+            if term_eq(t.p(), &rdf::type_) && self.is_undefined(t.o()) {
+                let msg = format!("undefined class {}", term_to_string(t.o()));
+                found.push((copy_triple(&t), msg));
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Reports the use of terms (as class, property or value)
+/// marked as `owl:deprecated true` in a vocabulary graph.
+///
+/// Its name is `deprecated-term`, and its default severity is `Warning`.
+pub struct DeprecatedTerms {
+    deprecated: HashSet<BoxTerm>,
+}
+
+impl DeprecatedTerms {
+    /// Find the deprecated terms of the given vocabulary graph.
+    pub fn new<V: Graph>(vocabulary: &V) -> GResult<V, Self> {
+        let deprecated_p = BoxTerm::new_iri_suffixed_unchecked(owl::PREFIX, "deprecated");
+        let mut deprecated = HashSet::new();
+        for t in vocabulary.triples_with_p(&deprecated_p) {
+            let t = t?;
+            if t.o().datatype().is_some_and(|dt| xsd::boolean == dt)
+                && matches!(&t.o().value()[..], "true" | "1")
+            {
+                deprecated.insert(BoxTerm::copy(t.s()));
+            }
+        }
+        Ok(DeprecatedTerms { deprecated })
+    }
+}
+
+impl<G: Graph + ?Sized> LintRule<G> for DeprecatedTerms {
+    fn name(&self) -> &str {
+        "deprecated-term"
+    }
+    fn check(&self, graph: &G) -> GResult<G, Vec<([BoxTerm; 3], String)>> {
+        let mut found = vec![];
+        for t in graph.triples() {
+            let t = t?;
+            for term in [t.p(), t.o()].iter() {
+                if term.kind() == TermKind::Iri && self.deprecated.contains(&BoxTerm::copy(*term)) {
+                    let msg = format!("deprecated term {}", term_to_string(*term));
+                    found.push((copy_triple(&t), msg));
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Reports blank nodes that are the subject of some triples,
+/// but are never used as an object, and are therefore unreachable from any other node.
+///
+/// One triple is reported for each such blank node.
+///
+/// Its name is `orphan-bnode`, and its default severity is `Info`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrphanBlankNodes;
+
+impl<G: Graph + ?Sized> LintRule<G> for OrphanBlankNodes {
+    fn name(&self) -> &str {
+        "orphan-bnode"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn check(&self, graph: &G) -> GResult<G, Vec<([BoxTerm; 3], String)>> {
+        let mut found = vec![];
+        let mut seen = HashSet::new();
+        for t in graph.triples() {
+            let t = t?;
+            let s = t.s();
+            if s.kind() != TermKind::BlankNode || !seen.insert(BoxTerm::copy(s)) {
+                continue;
+            }
+            if graph.triples_with_o(s).next().is_none() {
+                let msg = format!("blank node {} is never referenced", term_to_string(s));
+                found.push((copy_triple(&t), msg));
+            }
+        }
+        Ok(found)
+    }
+}
+
+fn copy_triple<T: Triple>(t: &T) -> [BoxTerm; 3] {
+    [
+        BoxTerm::copy(t.s()),
+        BoxTerm::copy(t.p()),
+        BoxTerm::copy(t.o()),
+    ]
+}
+
+/// The namespace of `iri`, i.e. the part of `iri` up to its last `#` or `/`.
+fn namespace(iri: &str) -> &str {
+    match iri.rfind(['#', '/']) {
+        Some(i) => &iri[..=i],
+        None => iri,
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::MutableGraph;
+    use crate::test_util::t;
+    use sophia_api::ns::rdfs;
+
+    fn vocab() -> FastGraph {
+        let mut v = FastGraph::new();
+        v.insert(&t("vocab/C"), &rdf::type_, &rdfs::Class).unwrap();
+        v.insert(&t("vocab/p"), &rdf::type_, &rdf::Property)
+            .unwrap();
+        v.insert(&t("vocab/old"), &rdf::type_, &rdf::Property)
+            .unwrap();
+        let deprecated = BoxTerm::new_iri_suffixed_unchecked(owl::PREFIX, "deprecated");
+        let yes = BoxTerm::new_literal_dt("true", xsd::boolean).unwrap();
+        v.insert(&t("vocab/old"), &deprecated, &yes).unwrap();
+        v
+    }
+
+    #[test]
+    fn rules() -> Result<(), Box<dyn std::error::Error>> {
+        let x = BoxTerm::new_iri("tag:data/x")?;
+        let b = BoxTerm::new_bnode("b")?;
+        let c = BoxTerm::new_bnode("c")?;
+        let mut g = FastGraph::new();
+        g.insert(&x, &rdf::type_, &t("vocab/C"))?;
+        g.insert(&x, &rdf::type_, &t("vocab/D"))?;
+        g.insert(&x, &t("vocab/p"), &b)?;
+        g.insert(
+            &x,
+            &t("vocab/q"),
+            &BoxTerm::new_literal_dt("1.5", xsd::integer)?,
+        )?;
+        g.insert(&b, &t("vocab/old"), &x)?;
+        g.insert(&c, &t("vocab/p"), &x)?;
+
+        let mut linter = Linter::new();
+        linter
+            .add_rule(IllTypedLiterals)
+            .add_rule(UndefinedTerms::new(&vocab())?)
+            .add_rule(DeprecatedTerms::new(&vocab())?)
+            .add_rule(OrphanBlankNodes);
+        assert_eq!(
+            linter.rule_names().collect::<Vec<_>>(),
+            vec![
+                "ill-typed-literal",
+                "undefined-term",
+                "deprecated-term",
+                "orphan-bnode"
+            ]
+        );
+        let report = linter.run(&g)?;
+        let rules: Vec<_> = report.lints().iter().map(|l| l.rule.as_str()).collect();
+        assert_eq!(
+            rules,
+            vec![
+                "ill-typed-literal",
+                "undefined-term",
+                "undefined-term",
+                "deprecated-term",
+                "orphan-bnode"
+            ]
+        );
+        assert_eq!(report.lints()[4].triple[0], c);
+        assert!(report.to_string().starts_with("error[ill-typed-literal]: "));
+
+        linter
+            .set_severity("ill-typed-literal", Some(Severity::Info))
+            .set_severity("undefined-term", None);
+        let report = linter.run(&g)?;
+        assert!(!report.has_errors());
+        assert_eq!(report.lints().len(), 3);
+        assert_eq!(report.with_severity(Severity::Info).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn clean() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("vocab/x"), &rdf::type_, &t("vocab/C"))?;
+        g.insert(&BoxTerm::new_iri("tag:other/x")?, &t("vocab/p"), &rdf::nil)?;
+        let mut linter = Linter::new();
+        linter.add_rule(UndefinedTerms::new(&vocab())?);
+        assert!(linter.run(&g)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,626 @@
+//! Loading RDF data from remote IRIs over HTTP(S).
+//!
+//! This module is only available with the `http` feature.
+//!
+//! The [`HttpLoader`] fetches a given IRI
+//! with an `Accept` header listing the RDF syntaxes supported by Sophia,
+//! follows redirects,
+//! and picks the appropriate parser based on the `Content-Type` of the response.
+//! If the `Content-Type` is missing or not informative
+//! (e.g. `text/plain` or `application/octet-stream`),
+//! the parser is chosen from the extension of the (final) IRI,
+//! and finally by sniffing the first bytes of the response
+//! (see [`syntax::guess`](../syntax/guess/index.html)).
+//!
+//! The triples or quads are streamed directly into a
+//! [`MutableGraph`] or a [`MutableDataset`].
+//!
+//! An [`IriCatalog`] can be set on the loader,
+//! to read selected IRIs from local files instead of fetching them;
+//! in offline mode, IRIs missing from the catalog are not fetched either,
+//! but raise an error.
+//!
+//! # Example
+//! ```no_run
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::loader::HttpLoader;
+//!
+//! let mut g = FastGraph::new();
+//! HttpLoader::default().load_graph("http://xmlns.com/foaf/0.1/", &mut g)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`HttpLoader`]: struct.HttpLoader.html
+//! [`IriCatalog`]: ../iri_catalog/struct.IriCatalog.html
+//! [`MutableGraph`]: ../graph/trait.MutableGraph.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+
+use std::error::Error;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::graph::MutableGraph;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::triple::stream::{
+    SinkError, SourceError, StreamError, StreamResult, TSTerm, TripleSource,
+};
+use sophia_api::triple::Triple;
+use thiserror::Error;
+
+use crate::iri_catalog::IriCatalog;
+use crate::parser::{nq, nt, trig, turtle};
+use crate::syntax::{guess, Syntax};
+
+/// The default value of the `Accept` header sent by [`HttpLoader`](struct.HttpLoader.html).
+#[cfg(not(feature = "xml"))]
+pub const DEFAULT_ACCEPT: &str = "text/turtle,application/trig;q=0.9,application/n-triples;q=0.8,application/n-quads;q=0.8,*/*;q=0.1";
+/// The default value of the `Accept` header sent by [`HttpLoader`](struct.HttpLoader.html).
+#[cfg(feature = "xml")]
+pub const DEFAULT_ACCEPT: &str = "text/turtle,application/trig;q=0.9,application/n-triples;q=0.8,application/n-quads;q=0.8,application/rdf+xml;q=0.7,*/*;q=0.1";
+
+/// This error is raised when loading a remote IRI fails.
+#[derive(Debug, Error)]
+pub enum LoaderError {
+    /// The HTTP request failed (network error, or an HTTP error status).
+    #[error("Failed to fetch <{iri}>: {source}")]
+    Http {
+        /// The requested IRI.
+        iri: String,
+        /// The underlying error.
+        source: Box<ureq::Error>,
+    },
+    /// The syntax of the response could not be determined, or is not supported.
+    #[error("Unsupported syntax for <{iri}> (Content-Type: {content_type:?})")]
+    UnsupportedSyntax {
+        /// The IRI that the data was retrieved from (after redirections).
+        iri: String,
+        /// The `Content-Type` of the response, if any.
+        content_type: Option<String>,
+    },
+    /// The response could not be read.
+    #[error("Failed to read <{iri}>: {source}")]
+    Io {
+        /// The IRI that the data was retrieved from (after redirections).
+        iri: String,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    /// The IRI is not in the catalog of a loader in offline mode.
+    #[error("Can not fetch <{iri}> in offline mode")]
+    Offline {
+        /// The requested IRI.
+        iri: String,
+    },
+    /// The response could not be parsed.
+    #[error("Failed to parse <{iri}>: {source}")]
+    Parse {
+        /// The IRI that the data was retrieved from (after redirections).
+        iri: String,
+        /// The underlying error.
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+/// A loader retrieving RDF data over HTTP(S).
+///
+/// See [module documentation](index.html) for more detail.
+#[derive(Clone)]
+pub struct HttpLoader {
+    accept: String,
+    max_redirects: u32,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    catalog: Option<Arc<IriCatalog>>,
+    offline: bool,
+}
+
+impl Default for HttpLoader {
+    fn default() -> Self {
+        HttpLoader {
+            accept: DEFAULT_ACCEPT.to_string(),
+            max_redirects: 10,
+            authenticator: None,
+            catalog: None,
+            offline: false,
+        }
+    }
+}
+
+impl fmt::Debug for HttpLoader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpLoader")
+            .field("accept", &self.accept)
+            .field("max_redirects", &self.max_redirects)
+            .field("authenticated", &self.authenticator.is_some())
+            .field("catalog", &self.catalog)
+            .field("offline", &self.offline)
+            .finish()
+    }
+}
+
+/// A hook adding authentication headers to the requests sent by an [`HttpLoader`].
+///
+/// It is called before each request, with the HTTP method and the IRI of the request,
+/// so that per-request credentials (such as [DPoP] proofs) can be computed.
+/// Note that redirections are followed without calling the hook again.
+///
+/// This trait is implemented by closures of type `Fn(&str, &str) -> Vec<(String, String)>`.
+///
+/// # Example
+/// ```
+/// use sophia::loader::HttpLoader;
+///
+/// let token = "some OIDC access token".to_string();
+/// let mut loader = HttpLoader::default();
+/// loader.set_authenticator(move |_method: &str, _iri: &str| {
+///     vec![("Authorization".to_string(), format!("Bearer {}", token))]
+/// });
+/// ```
+///
+/// [`HttpLoader`]: struct.HttpLoader.html
+/// [DPoP]: https://datatracker.ietf.org/doc/html/rfc9449
+pub trait Authenticator: Send + Sync {
+    /// The headers (name, value) to add to a `method` request on `iri`.
+    fn headers(&self, method: &str, iri: &str) -> Vec<(String, String)>;
+}
+
+impl<F> Authenticator for F
+where
+    F: Fn(&str, &str) -> Vec<(String, String)> + Send + Sync,
+{
+    fn headers(&self, method: &str, iri: &str) -> Vec<(String, String)> {
+        self(method, iri)
+    }
+}
+
+impl HttpLoader {
+    /// Set the `Accept` header sent by this loader.
+    pub fn set_accept<T: Into<String>>(&mut self, accept: T) -> &mut Self {
+        self.accept = accept.into();
+        self
+    }
+
+    /// Set the maximum number of redirections followed by this loader.
+    pub fn set_max_redirects(&mut self, max_redirects: u32) -> &mut Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Set the [`Authenticator`](trait.Authenticator.html) used by this loader.
+    pub fn set_authenticator<A: Authenticator + 'static>(&mut self, authenticator: A) -> &mut Self {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// The catalog of local files used by this loader, if any.
+    pub fn catalog(&self) -> Option<&Arc<IriCatalog>> {
+        self.catalog.as_ref()
+    }
+
+    /// Set the catalog of local files used by this loader:
+    /// IRIs redirected by the catalog are read from the corresponding local file
+    /// instead of being fetched.
+    ///
+    /// The syntax of local files is guessed from their extension or their first bytes.
+    pub fn set_catalog(&mut self, catalog: Option<Arc<IriCatalog>>) -> &mut Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Whether this loader is in offline mode.
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Set the offline mode of this loader:
+    /// if `true`, IRIs that are not redirected by the catalog
+    /// raise [`LoaderError::Offline`](enum.LoaderError.html#variant.Offline)
+    /// instead of being fetched.
+    pub fn set_offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Load the RDF data available at `iri` into `graph`.
+    ///
+    /// If the data is in a quad-based syntax (N-Quads, TriG),
+    /// only its default graph is loaded.
+    ///
+    /// Relative IRIs in the data are resolved against the final IRI
+    /// (after following redirections).
+    ///
+    /// # Return value
+    /// The `usize` value returned in case of success is
+    /// **not significant unless** `graph` also implements
+    /// [`SetGraph`](../graph/trait.SetGraph.html)
+    /// (see [`MutableGraph::insert_all`](../graph/trait.MutableGraph.html#method.insert_all)).
+    pub fn load_graph<G>(
+        &self,
+        iri: &str,
+        graph: &mut G,
+    ) -> StreamResult<usize, LoaderError, G::MutationError>
+    where
+        G: MutableGraph,
+    {
+        let (iri, syntax, body) = self.fetch(iri).map_err(SourceError)?;
+        parse_into_graph(&iri, syntax, body, graph)
+    }
+
+    /// Load the RDF data available at `iri` into `dataset`.
+    ///
+    /// If the data is in a triple-based syntax (N-Triples, Turtle, RDF/XML),
+    /// it is loaded in the default graph of `dataset`.
+    ///
+    /// Relative IRIs in the data are resolved against the final IRI
+    /// (after following redirections).
+    ///
+    /// # Return value
+    /// The `usize` value returned in case of success is
+    /// **not significant unless** `dataset` also implements
+    /// [`SetDataset`](../dataset/trait.SetDataset.html)
+    /// (see [`MutableDataset::insert_all`](../dataset/trait.MutableDataset.html#method.insert_all)).
+    pub fn load_dataset<D>(
+        &self,
+        iri: &str,
+        dataset: &mut D,
+    ) -> StreamResult<usize, LoaderError, D::MutationError>
+    where
+        D: MutableDataset,
+    {
+        let (iri, syntax, body) = self.fetch(iri).map_err(SourceError)?;
+        let base = Some(iri.clone());
+        use Syntax::*;
+        match syntax {
+            NTriples => insert_triples_in_dataset(&iri, nt::NTriplesParser {}.parse(body), dataset),
+            Turtle => {
+                insert_triples_in_dataset(&iri, turtle::TurtleParser { base }.parse(body), dataset)
+            }
+            #[cfg(feature = "xml")]
+            RdfXml => insert_triples_in_dataset(
+                &iri,
+                crate::parser::xml::RdfXmlParser { base }.parse(body),
+                dataset,
+            ),
+            NQuads => insert_quads_in_dataset(&iri, nq::NQuadsParser {}.parse(body), dataset),
+            TriG => insert_quads_in_dataset(&iri, trig::TriGParser { base }.parse(body), dataset),
+        }
+    }
+
+    /// Fetch `iri` (or read it from the catalog),
+    /// and return the final IRI, the detected syntax and the body of the response.
+    fn fetch(
+        &self,
+        iri: &str,
+    ) -> Result<(String, Syntax, Box<dyn BufRead + Send + Sync>), LoaderError> {
+        if let Some(path) = self.catalog.as_ref().and_then(|c| c.resolve(iri)) {
+            return read_local_file(iri, &path);
+        }
+        if self.offline {
+            return Err(LoaderError::Offline {
+                iri: iri.to_string(),
+            });
+        }
+        let response = self.get(iri)?;
+        read_response(response)
+    }
+
+    /// Build a `method` request on `iri`, configured according to this loader
+    /// (including authentication headers, if any).
+    pub(crate) fn request(&self, method: &str, iri: &str) -> ureq::Request {
+        let mut request = ureq::AgentBuilder::new()
+            .redirects(self.max_redirects)
+            .build()
+            .request(method, iri);
+        if let Some(authenticator) = &self.authenticator {
+            for (name, value) in authenticator.headers(method, iri) {
+                request = request.set(&name, &value);
+            }
+        }
+        request
+    }
+
+    /// Send a GET request for `iri`, with the appropriate `Accept` header.
+    pub(crate) fn get(&self, iri: &str) -> Result<ureq::Response, LoaderError> {
+        self.request("GET", iri)
+            .set("Accept", &self.accept)
+            .call()
+            .map_err(|source| LoaderError::Http {
+                iri: iri.to_string(),
+                source: Box::new(source),
+            })
+    }
+}
+
+/// Return the final IRI, the detected syntax and the body of `response`.
+pub(crate) fn read_response(
+    response: ureq::Response,
+) -> Result<(String, Syntax, Box<dyn BufRead + Send + Sync>), LoaderError> {
+    let final_iri = response.get_url().to_string();
+    let content_type = response.header("Content-Type").map(str::to_string);
+    let mut body = BufReader::new(response.into_reader());
+    let first_bytes = body.fill_buf().map_err(|source| LoaderError::Io {
+        iri: final_iri.clone(),
+        source,
+    })?;
+    let syntax = guess::guess(content_type.as_deref(), Some(&final_iri), first_bytes);
+    match syntax {
+        Some(syntax) => Ok((final_iri, syntax, Box::new(body))),
+        None => Err(LoaderError::UnsupportedSyntax {
+            iri: final_iri,
+            content_type,
+        }),
+    }
+}
+
+/// Return `iri`, the detected syntax and the content of the local file at `path`.
+fn read_local_file(
+    iri: &str,
+    path: &std::path::Path,
+) -> Result<(String, Syntax, Box<dyn BufRead + Send + Sync>), LoaderError> {
+    let io_error = |source| LoaderError::Io {
+        iri: iri.to_string(),
+        source,
+    };
+    let mut body = BufReader::new(std::fs::File::open(path).map_err(io_error)?);
+    let first_bytes = body.fill_buf().map_err(io_error)?;
+    match guess::guess(None, path.to_str(), first_bytes) {
+        Some(syntax) => Ok((iri.to_string(), syntax, Box::new(body))),
+        None => Err(LoaderError::UnsupportedSyntax {
+            iri: iri.to_string(),
+            content_type: None,
+        }),
+    }
+}
+
+/// Parse `body` (retrieved from `iri`) into `graph`, using the appropriate parser for `syntax`.
+///
+/// See [`HttpLoader::load_graph`](struct.HttpLoader.html#method.load_graph).
+pub(crate) fn parse_into_graph<G>(
+    iri: &str,
+    syntax: Syntax,
+    body: Box<dyn BufRead + Send + Sync>,
+    graph: &mut G,
+) -> StreamResult<usize, LoaderError, G::MutationError>
+where
+    G: MutableGraph,
+{
+    let base = Some(iri.to_string());
+    use Syntax::*;
+    match syntax {
+        NTriples => insert_triples_in_graph(iri, nt::NTriplesParser {}.parse(body), graph),
+        Turtle => insert_triples_in_graph(iri, turtle::TurtleParser { base }.parse(body), graph),
+        #[cfg(feature = "xml")]
+        RdfXml => insert_triples_in_graph(
+            iri,
+            crate::parser::xml::RdfXmlParser { base }.parse(body),
+            graph,
+        ),
+        NQuads => insert_default_graph_in_graph(iri, nq::NQuadsParser {}.parse(body), graph),
+        TriG => insert_default_graph_in_graph(iri, trig::TriGParser { base }.parse(body), graph),
+    }
+}
+
+/// Convert the source error of a parser into a [`LoaderError`](enum.LoaderError.html).
+fn parse_error<E, F>(iri: &str, err: StreamError<E, F>) -> StreamError<LoaderError, F>
+where
+    E: Error + Send + Sync + 'static,
+    F: Error + 'static,
+{
+    match err {
+        SourceError(err) => SourceError(LoaderError::Parse {
+            iri: iri.to_string(),
+            source: Box::new(err),
+        }),
+        SinkError(err) => SinkError(err),
+    }
+}
+
+fn insert_triples_in_graph<TS, G>(
+    iri: &str,
+    triples: TS,
+    graph: &mut G,
+) -> StreamResult<usize, LoaderError, G::MutationError>
+where
+    TS: TripleSource,
+    TS::Error: Send + Sync,
+    G: MutableGraph,
+{
+    graph
+        .insert_all(triples)
+        .map_err(|err| parse_error(iri, err))
+}
+
+fn insert_default_graph_in_graph<QS, G>(
+    iri: &str,
+    mut quads: QS,
+    graph: &mut G,
+) -> StreamResult<usize, LoaderError, G::MutationError>
+where
+    QS: QuadSource,
+    QS::Error: Send + Sync,
+    G: MutableGraph,
+{
+    let mut c = 0;
+    quads
+        .try_for_each_quad(|q| {
+            if q.g().is_none() && graph.insert(q.s(), q.p(), q.o())? {
+                c += 1;
+            }
+            Ok(())
+        })
+        .map_err(|err| parse_error(iri, err))
+        .and(Ok(c))
+}
+
+fn insert_triples_in_dataset<TS, D>(
+    iri: &str,
+    mut triples: TS,
+    dataset: &mut D,
+) -> StreamResult<usize, LoaderError, D::MutationError>
+where
+    TS: TripleSource,
+    TS::Error: Send + Sync,
+    D: MutableDataset,
+{
+    let mut c = 0;
+    triples
+        .try_for_each_triple(|t| {
+            if dataset.insert(t.s(), t.p(), t.o(), None as Option<&TSTerm<TS>>)? {
+                c += 1;
+            }
+            Ok(())
+        })
+        .map_err(|err| parse_error(iri, err))
+        .and(Ok(c))
+}
+
+fn insert_quads_in_dataset<QS, D>(
+    iri: &str,
+    quads: QS,
+    dataset: &mut D,
+) -> StreamResult<usize, LoaderError, D::MutationError>
+where
+    QS: QuadSource,
+    QS::Error: Send + Sync,
+    D: MutableDataset,
+{
+    dataset
+        .insert_all(quads)
+        .map_err(|err| parse_error(iri, err))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve the given responses (one per connection) on a local port,
+    /// and return the base IRI of the server.
+    fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}/", addr)
+    }
+
+    fn ok(content_type: Option<&str>, body: &str) -> String {
+        let ct = match content_type {
+            Some(ct) => format!("Content-Type: {}\r\n", ct),
+            None => String::new(),
+        };
+        format!(
+            "HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            ct,
+            body.len(),
+            body
+        )
+    }
+
+    const TTL: &str = "@prefix : <http://example.org/>. <#me> :knows :alice, :bob.";
+
+    #[test]
+    fn load_graph_turtle() -> Result<(), Box<dyn std::error::Error>> {
+        let base = serve(vec![ok(Some("text/turtle"), TTL)]);
+        let mut g = FastGraph::new();
+        let c = HttpLoader::default().load_graph(&base, &mut g)?;
+        assert_eq!(c, 2);
+        let me = sophia_term::BoxTerm::new_iri(format!("{}#me", base))?;
+        assert_eq!(g.triples_with_s(&me).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn load_graph_sniffed_after_redirect() -> Result<(), Box<dyn std::error::Error>> {
+        let base = serve(vec![
+            "HTTP/1.1 303 See Other\r\nLocation: /data\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            ok(Some("text/plain"), TTL),
+        ]);
+        let mut g = FastGraph::new();
+        let c = HttpLoader::default().load_graph(&format!("{}doc", base), &mut g)?;
+        assert_eq!(c, 2);
+        // relative IRIs are resolved against the final IRI
+        let me = sophia_term::BoxTerm::new_iri(format!("{}data#me", base))?;
+        assert_eq!(g.triples_with_s(&me).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn load_dataset_nquads() -> Result<(), Box<dyn std::error::Error>> {
+        let nq =
+            "<http://a> <http://b> <http://c> <http://g>.\n<http://a> <http://b> <http://d>.\n";
+        let base = serve(vec![ok(Some("application/n-quads"), nq)]);
+        let mut d = FastDataset::new();
+        let c = HttpLoader::default().load_dataset(&base, &mut d)?;
+        assert_eq!(c, 2);
+        assert_eq!(d.quads().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_syntax() {
+        let base = serve(vec![ok(None, "hello world")]);
+        let mut g = FastGraph::new();
+        let err = HttpLoader::default()
+            .load_graph(&base, &mut g)
+            .unwrap_err()
+            .unwrap_source_error();
+        assert!(matches!(err, LoaderError::UnsupportedSyntax { .. }));
+    }
+
+    #[test]
+    fn parse_error() {
+        let base = serve(vec![ok(Some("text/turtle"), "this is not turtle")]);
+        let mut g = FastGraph::new();
+        let err = HttpLoader::default()
+            .load_graph(&base, &mut g)
+            .unwrap_err()
+            .unwrap_source_error();
+        assert!(matches!(err, LoaderError::Parse { .. }));
+    }
+
+    #[test]
+    fn catalog_and_offline() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("sophia-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("foaf.ttl"), TTL)?;
+        let mut catalog = IriCatalog::new();
+        catalog.add_iri("http://xmlns.com/foaf/0.1/", dir.join("foaf.ttl"));
+        let mut loader = HttpLoader::default();
+        loader
+            .set_catalog(Some(Arc::new(catalog)))
+            .set_offline(true);
+        let mut g = FastGraph::new();
+        let res = loader.load_graph("http://xmlns.com/foaf/0.1/", &mut g);
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(res?, 2);
+        let me = sophia_term::BoxTerm::new_iri("http://xmlns.com/foaf/0.1/#me")?;
+        assert_eq!(g.triples_with_s(&me).count(), 2);
+
+        let err = loader
+            .load_graph("http://example.org/", &mut g)
+            .unwrap_err()
+            .unwrap_source_error();
+        assert!(matches!(err, LoaderError::Offline { .. }));
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+//! Graph merging with conflict resolution for functional properties.
+//!
+//! Merging RDF graphs is usually a plain set union.
+//! But when a property is *functional* (it can have at most one value per subject,
+//! e.g. a birth date, or any `owl:FunctionalProperty`),
+//! merging sources that disagree silently produces contradictory data.
+//! A [`Merger`] knows a set of functional properties,
+//! each with a [`ConflictPolicy`] deciding what happens
+//! when a source provides a value different from the one already in the target graph:
+//! keep the first value, keep the last one, or fail.
+//!
+//! Sources are merged one at a time into the target graph, each with a name.
+//! A [`MergeReport`] records which source provided the current value
+//! of each functional property, and every conflict encountered,
+//! with the source of the value that was kept and of the one that was discarded.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::merge::{ConflictPolicy, MergeReport, Merger};
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//!
+//! let birth = BoxTerm::new_iri("tag:birth")?;
+//! let mut merger = Merger::new();
+//! merger.add_functional(&birth, ConflictPolicy::KeepFirst);
+//!
+//! let mut target = FastGraph::new();
+//! let mut report = MergeReport::new();
+//! let src1 = r#"<tag:alice> <tag:birth> "1990"; <tag:knows> <tag:bob>."#;
+//! let src2 = r#"<tag:alice> <tag:birth> "1991"; <tag:knows> <tag:carol>."#;
+//! merger.merge(turtle::parse_str(src1), "src1", &mut target, &mut report)?;
+//! merger.merge(turtle::parse_str(src2), "src2", &mut target, &mut report)?;
+//!
+//! assert_eq!(target.triples().count(), 3);
+//! assert_eq!(report.conflicts().len(), 1);
+//! let conflict = &report.conflicts()[0];
+//! assert_eq!(conflict.kept_source.as_deref(), Some("src1"));
+//! assert_eq!(conflict.discarded_source, "src2");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Merger`]: struct.Merger.html
+//! [`ConflictPolicy`]: enum.ConflictPolicy.html
+//! [`MergeReport`]: struct.MergeReport.html
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use sophia_api::graph::{GResult, Graph, MutableGraph};
+use sophia_api::ns::{owl, rdf};
+use sophia_api::term::matcher::ANY;
+use sophia_api::term::{term_to_string, CopyTerm, TTerm};
+use sophia_api::triple::stream::{StreamError, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// What to do when a functional property receives a second, different value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the value already in the target graph, and discard the new one.
+    KeepFirst,
+    /// Replace the value already in the target graph by the new one.
+    KeepLast,
+    /// Fail with [`MergeError::Conflict`](enum.MergeError.html#variant.Conflict).
+    Error,
+}
+
+/// A conflict between two values of a functional property.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The subject of the conflicting triples.
+    pub subject: BoxTerm,
+    /// The functional property.
+    pub predicate: BoxTerm,
+    /// The value kept in the target graph.
+    pub kept: BoxTerm,
+    /// The source of the kept value,
+    /// or `None` if it was in the target graph before the merge.
+    pub kept_source: Option<String>,
+    /// The value discarded.
+    pub discarded: BoxTerm,
+    /// The source of the discarded value,
+    /// or an empty string if it was in the target graph before the merge.
+    pub discarded_source: String,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} has conflicting values {} and {} (from {})",
+            term_to_string(&self.subject),
+            term_to_string(&self.predicate),
+            term_to_string(&self.kept),
+            term_to_string(&self.discarded),
+            self.discarded_source,
+        )
+    }
+}
+
+/// This error is raised when a merge fails.
+#[derive(Debug, Error)]
+pub enum MergeError<SE, TE>
+where
+    SE: Error + 'static,
+    TE: Error + 'static,
+{
+    /// The source failed.
+    #[error("Source error: {0}")]
+    Source(#[source] SE),
+    /// The target graph failed.
+    #[error("Target error: {0}")]
+    Target(#[source] TE),
+    /// Two values of a functional property with the [`Error`](enum.ConflictPolicy.html#variant.Error) policy.
+    #[error("Merge conflict: {0}")]
+    Conflict(Box<Conflict>),
+}
+
+/// The outcome of one or several merges.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    origins: HashMap<(BoxTerm, BoxTerm), String>,
+    conflicts: Vec<Conflict>,
+}
+
+impl MergeReport {
+    /// Build an empty report, for a new sequence of merges.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The conflicts encountered so far (except those causing an error).
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// The name of the source that provided the current value of
+    /// functional property `p` for subject `s`,
+    /// or `None` if the value was already in the target graph (or if there is no value).
+    pub fn origin<TS, TP>(&self, s: &TS, p: &TP) -> Option<&str>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.origins
+            .get(&(BoxTerm::copy(s), BoxTerm::copy(p)))
+            .map(String::as_str)
+    }
+}
+
+/// Merges triple sources into a graph,
+/// resolving conflicts on functional properties.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Merger {
+    policies: HashMap<BoxTerm, ConflictPolicy>,
+}
+
+impl Merger {
+    /// Build a merger with no functional property
+    /// (merging is then a plain union).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare `predicate` as functional, with the given conflict policy.
+    pub fn add_functional<T>(&mut self, predicate: &T, policy: ConflictPolicy) -> &mut Self
+    where
+        T: TTerm + ?Sized,
+    {
+        self.policies.insert(BoxTerm::copy(predicate), policy);
+        self
+    }
+
+    /// Declare every `owl:FunctionalProperty` of `ontology` as functional,
+    /// with the given conflict policy,
+    /// and return the number of such properties.
+    pub fn add_functional_properties<G: Graph>(
+        &mut self,
+        ontology: &G,
+        policy: ConflictPolicy,
+    ) -> GResult<G, usize> {
+        let mut n = 0;
+        for t in ontology.triples_matching(&ANY, &rdf::type_, &owl::FunctionalProperty) {
+            self.add_functional(t?.s(), policy);
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// The conflict policy of `predicate`, or `None` if it is not functional.
+    pub fn policy<T>(&self, predicate: &T) -> Option<ConflictPolicy>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.policies.get(&BoxTerm::copy(predicate)).copied()
+    }
+
+    /// Merge the triples of `source` (named `name`) into `target`,
+    /// recording the origin of functional values and the conflicts in `report`,
+    /// and return the number of triples inserted in `target`.
+    ///
+    /// Values of functional properties already in `target` before the first merge
+    /// are considered as coming first.
+    /// When a conflict causes an error, the triples processed before it remain in `target`.
+    pub fn merge<TS, G>(
+        &self,
+        mut source: TS,
+        name: &str,
+        target: &mut G,
+        report: &mut MergeReport,
+    ) -> Result<usize, MergeError<TS::Error, G::MutationError>>
+    where
+        TS: TripleSource,
+        G: MutableGraph,
+        <G as Graph>::Error: Into<G::MutationError>,
+    {
+        let mut n = 0;
+        let res = source.try_for_each_triple(|t| -> Result<(), MergeError<TS::Error, _>> {
+            let [s, p, o] = [t.s(), t.p(), t.o()].map(BoxTerm::copy);
+            let policy = match self.policies.get(&p) {
+                None => {
+                    if target.insert(&s, &p, &o).map_err(MergeError::Target)? {
+                        n += 1;
+                    }
+                    return Ok(());
+                }
+                Some(policy) => *policy,
+            };
+            let existing = target
+                .triples_with_sp(&s, &p)
+                .map(|t| t.map(|t| BoxTerm::copy(t.o())))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| MergeError::Target(e.into()))?;
+            let key = (s, p);
+            if existing.is_empty() {
+                target
+                    .insert(&key.0, &key.1, &o)
+                    .map_err(MergeError::Target)?;
+                n += 1;
+                report.origins.insert(key, name.to_string());
+                return Ok(());
+            }
+            if existing.contains(&o) {
+                return Ok(());
+            }
+            let previous = existing[0].clone();
+            let previous_source = report.origins.get(&key).cloned();
+            let (subject, predicate) = key.clone();
+            match policy {
+                ConflictPolicy::KeepFirst => {
+                    report.conflicts.push(Conflict {
+                        subject,
+                        predicate,
+                        kept: previous,
+                        kept_source: previous_source,
+                        discarded: o,
+                        discarded_source: name.to_string(),
+                    });
+                }
+                ConflictPolicy::KeepLast => {
+                    for old in &existing {
+                        target
+                            .remove(&key.0, &key.1, old)
+                            .map_err(MergeError::Target)?;
+                    }
+                    target
+                        .insert(&key.0, &key.1, &o)
+                        .map_err(MergeError::Target)?;
+                    n += 1;
+                    report.conflicts.push(Conflict {
+                        subject,
+                        predicate,
+                        kept: o,
+                        kept_source: Some(name.to_string()),
+                        discarded: previous,
+                        discarded_source: previous_source.unwrap_or_default(),
+                    });
+                    report.origins.insert(key, name.to_string());
+                }
+                ConflictPolicy::Error => {
+                    return Err(MergeError::Conflict(Box::new(Conflict {
+                        subject,
+                        predicate,
+                        kept: previous,
+                        kept_source: previous_source,
+                        discarded: o,
+                        discarded_source: name.to_string(),
+                    })));
+                }
+            }
+            Ok(())
+        });
+        match res {
+            Ok(()) => Ok(n),
+            Err(StreamError::SourceError(e)) => Err(MergeError::Source(e)),
+            Err(StreamError::SinkError(e)) => Err(e),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use sophia_api::triple::stream::TripleSource;
+
+    fn lit(txt: &str) -> BoxTerm {
+        BoxTerm::new_literal_dt(txt, sophia_api::ns::xsd::string).unwrap()
+    }
+
+    const SRC1: &str = r#"<tag:a> <tag:f> "1"; <tag:g> "x"; <tag:n> "y"."#;
+    const SRC2: &str = r#"<tag:a> <tag:f> "2"; <tag:g> "x"; <tag:n> "z"."#;
+
+    #[test]
+    fn plain_union() -> Result<(), Box<dyn std::error::Error>> {
+        let mut target = FastGraph::new();
+        let mut report = MergeReport::new();
+        let merger = Merger::new();
+        assert_eq!(
+            merger.merge(turtle::parse_str(SRC1), "1", &mut target, &mut report)?,
+            3
+        );
+        assert_eq!(
+            merger.merge(turtle::parse_str(SRC2), "2", &mut target, &mut report)?,
+            2
+        );
+        assert_eq!(target.triples().count(), 5);
+        assert!(report.conflicts().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn keep_first() -> Result<(), Box<dyn std::error::Error>> {
+        let mut target = FastGraph::new();
+        let mut report = MergeReport::new();
+        let mut merger = Merger::new();
+        merger
+            .add_functional(&t("f"), ConflictPolicy::KeepFirst)
+            .add_functional(&t("g"), ConflictPolicy::KeepFirst);
+        merger.merge(turtle::parse_str(SRC1), "1", &mut target, &mut report)?;
+        merger.merge(turtle::parse_str(SRC2), "2", &mut target, &mut report)?;
+        assert_eq!(target.triples().count(), 4);
+        assert_eq!(target.triples_with_sp(&t("a"), &t("f")).count(), 1);
+        assert!(target.contains(&t("a"), &t("f"), &lit("1"))?);
+        assert_eq!(report.origin(&t("a"), &t("f")), Some("1"));
+        assert_eq!(
+            report.conflicts(),
+            &[Conflict {
+                subject: t("a"),
+                predicate: t("f"),
+                kept: lit("1"),
+                kept_source: Some("1".to_string()),
+                discarded: lit("2"),
+                discarded_source: "2".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keep_last() -> Result<(), Box<dyn std::error::Error>> {
+        let mut target: FastGraph =
+            turtle::parse_str(r#"<tag:a> <tag:f> "0"."#).collect_triples()?;
+        let mut report = MergeReport::new();
+        let mut merger = Merger::new();
+        merger.add_functional(&t("f"), ConflictPolicy::KeepLast);
+        merger.merge(turtle::parse_str(SRC1), "1", &mut target, &mut report)?;
+        merger.merge(turtle::parse_str(SRC2), "2", &mut target, &mut report)?;
+        assert_eq!(target.triples_with_sp(&t("a"), &t("f")).count(), 1);
+        assert!(target.contains(&t("a"), &t("f"), &lit("2"))?);
+        assert_eq!(report.origin(&t("a"), &t("f")), Some("2"));
+        assert_eq!(report.conflicts().len(), 2);
+        assert_eq!(report.conflicts()[0].discarded, lit("0"));
+        assert_eq!(report.conflicts()[0].discarded_source, "");
+        assert_eq!(report.conflicts()[1].discarded_source, "1");
+        Ok(())
+    }
+
+    #[test]
+    fn error() -> Result<(), Box<dyn std::error::Error>> {
+        let mut target = FastGraph::new();
+        let mut report = MergeReport::new();
+        let mut merger = Merger::new();
+        merger.add_functional(&t("f"), ConflictPolicy::Error);
+        merger.merge(turtle::parse_str(SRC1), "1", &mut target, &mut report)?;
+        let err = merger
+            .merge(turtle::parse_str(SRC2), "2", &mut target, &mut report)
+            .unwrap_err();
+        match err {
+            MergeError::Conflict(c) => {
+                assert_eq!(c.kept, lit("1"));
+                assert_eq!(c.discarded, lit("2"));
+            }
+            _ => panic!("unexpected error {}", err),
+        }
+        // a conflict within a single source
+        let mut target = FastGraph::new();
+        let res = merger.merge(
+            turtle::parse_str(r#"<tag:a> <tag:f> "1", "2"."#),
+            "1",
+            &mut target,
+            &mut report,
+        );
+        assert!(matches!(res, Err(MergeError::Conflict(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn functional_properties_from_ontology() -> Result<(), Box<dyn std::error::Error>> {
+        let onto: FastGraph = turtle::parse_str(
+            r#"
+            @prefix owl: <http://www.w3.org/2002/07/owl#>.
+            <tag:f> a owl:FunctionalProperty.
+            <tag:g> a owl:ObjectProperty.
+        "#,
+        )
+        .collect_triples()?;
+        let mut merger = Merger::new();
+        assert_eq!(
+            merger.add_functional_properties(&onto, ConflictPolicy::KeepLast)?,
+            1
+        );
+        assert_eq!(merger.policy(&t("f")), Some(ConflictPolicy::KeepLast));
+        assert_eq!(merger.policy(&t("g")), None);
+        Ok(())
+    }
+}
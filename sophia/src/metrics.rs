@@ -0,0 +1,369 @@
+//! Structural metrics of graphs: degrees, connected components and PageRank.
+//!
+//! [`Metrics`] considers a graph as a directed multigraph,
+//! where each triple is an edge from its subject to its object;
+//! triples whose object is a literal are ignored,
+//! as literals are values rather than nodes.
+//! The edges can optionally be restricted to a set of predicates
+//! (e.g. only `foaf:knows` for a social network).
+//!
+//! It then computes
+//! * the in- and out-degree of each node, and the [degree distribution](struct.Metrics.html#method.degree_distribution),
+//! * the [weakly connected components](struct.Metrics.html#method.components) of the graph,
+//! * the [PageRank](struct.Metrics.html#method.page_rank) of each node,
+//!
+//! which are useful for profiling data and for ranking entities by importance.
+//! Results are returned as terms, or can be [written](struct.Metrics.html#method.write_to)
+//! as triples into another graph.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::metrics::{Direction, Metrics};
+//! use sophia::parser::turtle;
+//! use sophia::term::TTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:knows> <tag:bob>, <tag:carol>.
+//!     <tag:bob> <tag:knows> <tag:carol>.
+//!     <tag:dave> <tag:knows> <tag:erin>.
+//! "#).collect_triples()?;
+//!
+//! let metrics = Metrics::new(&g)?;
+//! assert_eq!(metrics.len(), 5);
+//! assert_eq!(metrics.components().len(), 2);
+//! assert_eq!(metrics.degree_distribution(Direction::In)[&1], 2);
+//! let ranks = metrics.page_rank(0.85, 100);
+//! assert_eq!(ranks[0].0.value(), "tag:carol");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Metrics`]: struct.Metrics.html
+
+use std::collections::{BTreeMap, HashMap};
+
+use sophia_api::graph::{GResult, GTerm, Graph, MGResult, MutableGraph};
+use sophia_api::ns::Namespace;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::literal::convert::AsLiteral;
+use sophia_term::BoxTerm;
+
+/// Which edges are counted in the degree of a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Incoming edges only.
+    In,
+    /// Outgoing edges only.
+    Out,
+    /// Incoming and outgoing edges.
+    Both,
+}
+
+/// The structure of a graph, considered as a directed multigraph.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    nodes: Vec<BoxTerm>,
+    index: HashMap<BoxTerm, usize>,
+    edges: Vec<(usize, usize)>,
+    in_degrees: Vec<usize>,
+    out_degrees: Vec<usize>,
+}
+
+impl Metrics {
+    /// Compute the structure of `graph`, using all its triples.
+    pub fn new<G: Graph>(graph: &G) -> GResult<G, Self> {
+        Self::build(graph, |_| true)
+    }
+
+    /// Compute the structure of `graph`,
+    /// using only the triples whose predicate is one of `predicates`.
+    pub fn with_predicates<G, T>(graph: &G, predicates: &[T]) -> GResult<G, Self>
+    where
+        G: Graph,
+        T: TTerm,
+    {
+        Self::build(graph, |p| predicates.iter().any(|q| term_eq(p, q)))
+    }
+
+    fn build<G, F>(graph: &G, mut keep: F) -> GResult<G, Self>
+    where
+        G: Graph,
+        F: FnMut(&GTerm<G>) -> bool,
+    {
+        let mut metrics = Metrics::default();
+        for t in graph.triples() {
+            let t = t?;
+            if t.o().kind() == TermKind::Literal || !keep(t.p()) {
+                continue;
+            }
+            let s = metrics.node(t.s());
+            let o = metrics.node(t.o());
+            metrics.edges.push((s, o));
+            metrics.out_degrees[s] += 1;
+            metrics.in_degrees[o] += 1;
+        }
+        Ok(metrics)
+    }
+
+    /// The index of `term`, adding it as a new node if needed.
+    fn node<T: TTerm + ?Sized>(&mut self, term: &T) -> usize {
+        let term = BoxTerm::copy(term);
+        if let Some(i) = self.index.get(&term) {
+            return *i;
+        }
+        self.nodes.push(term.clone());
+        self.in_degrees.push(0);
+        self.out_degrees.push(0);
+        self.index.insert(term, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// The number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the graph has no node.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The nodes, in the order in which they were first encountered.
+    pub fn nodes(&self) -> &[BoxTerm] {
+        &self.nodes
+    }
+
+    /// The degree of `node` in the given direction (0 if `node` is not in the graph).
+    pub fn degree<T: TTerm + ?Sized>(&self, node: &T, direction: Direction) -> usize {
+        match self.index.get(&BoxTerm::copy(node)) {
+            Some(i) => self.degree_of(*i, direction),
+            None => 0,
+        }
+    }
+
+    fn degree_of(&self, i: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::In => self.in_degrees[i],
+            Direction::Out => self.out_degrees[i],
+            Direction::Both => self.in_degrees[i] + self.out_degrees[i],
+        }
+    }
+
+    /// The number of nodes having each degree, in the given direction.
+    pub fn degree_distribution(&self, direction: Direction) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for i in 0..self.nodes.len() {
+            *distribution
+                .entry(self.degree_of(i, direction))
+                .or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    /// The weakly connected components of the graph,
+    /// from the largest to the smallest.
+    ///
+    /// Components of the same size, and nodes inside each component,
+    /// are in the order in which they were first encountered.
+    pub fn components(&self) -> Vec<Vec<BoxTerm>> {
+        let mut parent: Vec<usize> = (0..self.nodes.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for (s, o) in &self.edges {
+            let (rs, ro) = (root(&mut parent, *s), root(&mut parent, *o));
+            if rs != ro {
+                parent[rs.max(ro)] = rs.min(ro);
+            }
+        }
+        let mut components: Vec<Vec<BoxTerm>> = vec![];
+        let mut component_of = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let r = root(&mut parent, i);
+            let c = *component_of.entry(r).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[c].push(node.clone());
+        }
+        // sort_by_key is stable, so equal components keep their order
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        components
+    }
+
+    /// The PageRank of each node, from the highest to the lowest.
+    ///
+    /// `damping` is the probability to follow an edge rather than jump to a random node
+    /// (0.85 is the usual value).
+    /// The computation stops after `iterations` iterations,
+    /// or earlier if the ranks have converged.
+    /// The ranks sum to 1; the rank of nodes without outgoing edges
+    /// is distributed evenly to all nodes.
+    pub fn page_rank(&self, damping: f64, iterations: usize) -> Vec<(BoxTerm, f64)> {
+        let n = self.nodes.len();
+        if n == 0 {
+            return vec![];
+        }
+        let mut ranks = vec![1.0 / n as f64; n];
+        for _ in 0..iterations {
+            let dangling: f64 = (0..n)
+                .filter(|i| self.out_degrees[*i] == 0)
+                .map(|i| ranks[i])
+                .sum();
+            let base = (1.0 - damping + damping * dangling) / n as f64;
+            let mut next = vec![base; n];
+            for (s, o) in &self.edges {
+                next[*o] += damping * ranks[*s] / self.out_degrees[*s] as f64;
+            }
+            let delta: f64 = ranks.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+            ranks = next;
+            if delta < 1e-12 {
+                break;
+            }
+        }
+        let mut ranked: Vec<_> = self.nodes.iter().cloned().zip(ranks).collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
+
+    /// Write the metrics of every node into `graph`,
+    /// using the following properties from namespace `ns`:
+    /// * `inDegree` and `outDegree`,
+    /// * `component`: the position of the node's component in [`components`](#method.components),
+    /// * `pageRank`: as computed by [`page_rank`](#method.page_rank)
+    ///   with `damping` and `iterations`.
+    pub fn write_to<G, N>(
+        &self,
+        graph: &mut G,
+        ns: &Namespace<N>,
+        damping: f64,
+        iterations: usize,
+    ) -> MGResult<G, ()>
+    where
+        G: MutableGraph,
+        N: AsRef<str>,
+    {
+        let p = |suffix| ns.get(suffix).expect("namespace and suffix are valid");
+        let (in_degree, out_degree) = (p("inDegree"), p("outDegree"));
+        let (component, page_rank) = (p("component"), p("pageRank"));
+        for (i, node) in self.nodes.iter().enumerate() {
+            graph.insert(node, &in_degree, &(self.in_degrees[i] as u64).as_literal())?;
+            graph.insert(
+                node,
+                &out_degree,
+                &(self.out_degrees[i] as u64).as_literal(),
+            )?;
+        }
+        for (c, nodes) in self.components().iter().enumerate() {
+            for node in nodes {
+                graph.insert(node, &component, &(c as u64).as_literal())?;
+            }
+        }
+        for (node, rank) in self.page_rank(damping, iterations) {
+            graph.insert(&node, &page_rank, &rank.as_literal())?;
+        }
+        Ok(())
+    }
+}
+
+// ---- tests ----
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::ns::{rdf, xsd};
+    use crate::test_util::t;
+
+    fn example() -> Result<FastGraph, Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("a"), &t("knows"), &t("b"))?;
+        g.insert(&t("a"), &t("knows"), &t("c"))?;
+        g.insert(&t("b"), &t("knows"), &t("c"))?;
+        g.insert(&t("c"), &t("likes"), &t("a"))?;
+        g.insert(&t("d"), &t("knows"), &t("e"))?;
+        g.insert(
+            &t("a"),
+            &rdf::value,
+            &BoxTerm::new_literal_dt("a", xsd::string)?,
+        )?;
+        Ok(g)
+    }
+
+    #[test]
+    fn degrees() -> Result<(), Box<dyn std::error::Error>> {
+        let g = example()?;
+        let m = Metrics::new(&g)?;
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.edge_count(), 5);
+        assert_eq!(m.degree(&t("a"), Direction::Out), 2);
+        assert_eq!(m.degree(&t("a"), Direction::In), 1);
+        assert_eq!(m.degree(&t("c"), Direction::Both), 3);
+        assert_eq!(m.degree(&t("z"), Direction::Both), 0);
+        let distribution = m.degree_distribution(Direction::Out);
+        assert_eq!(distribution[&0], 1);
+        assert_eq!(distribution[&1], 3);
+        assert_eq!(distribution[&2], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn with_predicates() -> Result<(), Box<dyn std::error::Error>> {
+        let g = example()?;
+        let m = Metrics::with_predicates(&g, &[t("knows")])?;
+        assert_eq!(m.edge_count(), 4);
+        assert_eq!(m.degree(&t("a"), Direction::In), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn components() -> Result<(), Box<dyn std::error::Error>> {
+        let g = example()?;
+        let m = Metrics::new(&g)?;
+        let components = m.components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].len(), 3);
+        assert!(components[0].contains(&t("c")));
+        assert_eq!(components[1], vec![t("d"), t("e")]);
+        Ok(())
+    }
+
+    #[test]
+    fn page_rank() -> Result<(), Box<dyn std::error::Error>> {
+        let g = example()?;
+        let m = Metrics::new(&g)?;
+        let ranks = m.page_rank(0.85, 100);
+        assert_eq!(ranks.len(), 5);
+        let total: f64 = ranks.iter().map(|(_, r)| r).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert_eq!(ranks[0].0, t("c"));
+        assert!(Metrics::default().page_rank(0.85, 10).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn write_to() -> Result<(), Box<dyn std::error::Error>> {
+        let g = example()?;
+        let m = Metrics::new(&g)?;
+        let ns = Namespace::new("tag:metrics#")?;
+        let mut results = FastGraph::new();
+        m.write_to(&mut results, &ns, 0.85, 100)?;
+        assert_eq!(results.triples().count(), 20);
+        assert!(results.contains(&t("a"), &ns.get("outDegree")?, &2_u64.as_literal())?);
+        assert!(results.contains(&t("e"), &ns.get("component")?, &1_u64.as_literal())?);
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! Assembly and validation of [nanopublications](http://nanopub.org/guidelines/working_draft/)
+//! (requires the `signing` feature).
+//!
+//! A nanopublication is a dataset made of four named graphs:
+//! * the *head*, linking the nanopublication to the three other graphs,
+//! * the *assertion*, which is the actual content of the nanopublication,
+//! * the *provenance* of the assertion,
+//! * the *publication info*, describing the nanopublication itself.
+//!
+//! [`assemble`] builds a nanopublication from the last three graphs,
+//! and identifies it with a [trusty IRI].
+//! The provenance and publication info graphs will usually refer to the assertion graph
+//! and to the nanopublication itself:
+//! they must do so through the temporary IRIs returned by [`temp_iris`],
+//! which are replaced by their trusty counterparts during assembly.
+//!
+//! [`validate`] checks the structure of a nanopublication,
+//! and (if it is identified by a trusty IRI) its integrity.
+//!
+//! # Example
+//! ```
+//! use sophia::nanopub::{assemble, temp_iris, validate};
+//! use sophia::ns::rdfs;
+//! use sophia::term::{BoxTerm, CopyTerm, TTerm};
+//!
+//! let base = "http://example.org/np/";
+//! let [np, _, assertion, _, _] = temp_iris(base);
+//! let alice = BoxTerm::new_iri("http://example.org/alice")?;
+//! let prov = BoxTerm::new_iri("http://www.w3.org/ns/prov#wasAttributedTo")?;
+//! let created = BoxTerm::new_iri("http://purl.org/dc/terms/creator")?;
+//!
+//! let assertion_graph = vec![[alice.clone(), BoxTerm::copy(&rdfs::label), "Alice".to_string().into()]];
+//! let provenance_graph = vec![[assertion, prov, alice.clone()]];
+//! let pubinfo_graph = vec![[np, created, alice]];
+//! let (iri, dataset) = assemble(base, &assertion_graph, &provenance_graph, &pubinfo_graph)?;
+//!
+//! let nanopub = validate(&dataset)?;
+//! assert_eq!(nanopub.iri.value().as_ref(), iri);
+//! assert_eq!(nanopub.trusty, Some(true));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`assemble`]: fn.assemble.html
+//! [trusty IRI]: ../trusty/index.html
+//! [`temp_iris`]: fn.temp_iris.html
+//! [`validate`]: fn.validate.html
+
+use std::collections::HashSet;
+
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::Graph;
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_eq, CopyTerm, SimpleIri, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::ns::rdf;
+use crate::trusty::{artifact_code_of, make_trusty, verify_trusty, TrustyError, TrustyQuad};
+
+/// The Nanopublication schema.
+#[allow(missing_docs)]
+pub mod np {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.nanopub.org/nschema#",
+        Nanopublication,
+        hasAssertion,
+        hasProvenance,
+        hasPublicationInfo
+    );
+}
+
+/// This error is raised when assembling or validating a nanopublication fails.
+#[derive(Debug, Error)]
+pub enum NanopubError {
+    /// The source graphs or dataset raised an error.
+    #[error("Error in source: {0}")]
+    Source(String),
+    /// The dataset is not a well-formed nanopublication.
+    #[error("Invalid nanopublication: {0}")]
+    Invalid(String),
+}
+
+impl From<TrustyError> for NanopubError {
+    fn from(err: TrustyError) -> Self {
+        NanopubError::Source(err.to_string())
+    }
+}
+
+/// The structure of a valid nanopublication, as returned by [`validate`](fn.validate.html).
+#[derive(Clone, Debug)]
+pub struct Nanopub {
+    /// The IRI of the nanopublication.
+    pub iri: BoxTerm,
+    /// The name of the head graph.
+    pub head: BoxTerm,
+    /// The name of the assertion graph.
+    pub assertion: BoxTerm,
+    /// The name of the provenance graph.
+    pub provenance: BoxTerm,
+    /// The name of the publication info graph.
+    pub pubinfo: BoxTerm,
+    /// Whether the nanopublication matches its trusty IRI
+    /// (`None` if its IRI is not a trusty IRI).
+    pub trusty: Option<bool>,
+}
+
+/// The temporary IRIs used by [`assemble`](fn.assemble.html) for the given `base`,
+/// namely the IRI of the nanopublication,
+/// and the names of its head, assertion, provenance and publication info graphs.
+pub fn temp_iris(base: &str) -> [BoxTerm; 5] {
+    let iri = |suffix: &str| BoxTerm::new_iri_unchecked(format!("{}{}", base, suffix));
+    [
+        iri(""),
+        iri("#Head"),
+        iri("#assertion"),
+        iri("#provenance"),
+        iri("#pubinfo"),
+    ]
+}
+
+/// Assemble a nanopublication from its assertion, provenance and publication info graphs,
+/// and return its trusty IRI together with its quads.
+///
+/// `base` is the temporary IRI of the nanopublication (see [`temp_iris`](fn.temp_iris.html));
+/// every IRI starting with `base` is rewritten to start with the trusty IRI instead.
+pub fn assemble<GA, GP, GI>(
+    base: &str,
+    assertion: &GA,
+    provenance: &GP,
+    pubinfo: &GI,
+) -> Result<(String, Vec<TrustyQuad>), NanopubError>
+where
+    GA: Graph + ?Sized,
+    GP: Graph + ?Sized,
+    GI: Graph + ?Sized,
+{
+    let [iri, head, a, p, i] = temp_iris(base);
+    let mut quads = vec![
+        (
+            [
+                iri.clone(),
+                BoxTerm::copy(&rdf::type_),
+                BoxTerm::copy(&np::Nanopublication),
+            ],
+            Some(head.clone()),
+        ),
+        (
+            [iri.clone(), BoxTerm::copy(&np::hasAssertion), a.clone()],
+            Some(head.clone()),
+        ),
+        (
+            [iri.clone(), BoxTerm::copy(&np::hasProvenance), p.clone()],
+            Some(head.clone()),
+        ),
+        (
+            [iri, BoxTerm::copy(&np::hasPublicationInfo), i.clone()],
+            Some(head),
+        ),
+    ];
+    add_graph(&mut quads, assertion, a)?;
+    add_graph(&mut quads, provenance, p)?;
+    add_graph(&mut quads, pubinfo, i)?;
+    Ok(make_trusty(&quads, base)?)
+}
+
+/// Check that `dataset` is a well-formed nanopublication, and return its structure.
+///
+/// More precisely, this checks that
+/// * the dataset contains no triple in its default graph,
+/// * exactly one of its graphs declares a `np:Nanopublication`,
+///   with exactly one assertion, provenance and publication info graph,
+/// * these four graphs are distinct, non-empty, and the only graphs of the dataset,
+/// * the provenance graph is about the assertion graph,
+/// * the publication info graph is about the nanopublication.
+///
+/// If the IRI of the nanopublication is a trusty IRI, its integrity is also checked
+/// (see [`Nanopub::trusty`](struct.Nanopub.html#structfield.trusty)).
+pub fn validate<D>(dataset: &D) -> Result<Nanopub, NanopubError>
+where
+    D: Dataset + ?Sized,
+{
+    let invalid = |msg: &str| NanopubError::Invalid(msg.to_string());
+    let source = |err: D::Error| NanopubError::Source(err.to_string());
+    let quads: Vec<TrustyQuad> = dataset
+        .quads()
+        .map(|q| {
+            q.map(|q| {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(source)?;
+
+    let mut heads = quads
+        .iter()
+        .filter(|q| term_eq(q.p(), &rdf::type_) && term_eq(q.o(), &np::Nanopublication));
+    let (iri, head) = match (heads.next(), heads.next()) {
+        (Some(q), None) => match q.g() {
+            Some(g) => (q.s().clone(), g.clone()),
+            None => return Err(invalid("np:Nanopublication declared in the default graph")),
+        },
+        _ => return Err(invalid("expected exactly one np:Nanopublication")),
+    };
+    let part = |pred: &SimpleIri| -> Result<BoxTerm, NanopubError> {
+        let mut values = quads
+            .iter()
+            .filter(|q| q.g() == Some(&head) && term_eq(q.s(), &iri) && term_eq(q.p(), pred));
+        match (values.next(), values.next()) {
+            (Some(q), None) => Ok(q.o().clone()),
+            _ => Err(NanopubError::Invalid(format!(
+                "expected exactly one {}",
+                pred.value()
+            ))),
+        }
+    };
+    let assertion = part(&np::hasAssertion)?;
+    let provenance = part(&np::hasProvenance)?;
+    let pubinfo = part(&np::hasPublicationInfo)?;
+
+    let graphs = [&head, &assertion, &provenance, &pubinfo];
+    if graphs.iter().collect::<HashSet<_>>().len() != graphs.len() {
+        return Err(invalid(
+            "the four graphs of a nanopublication must be distinct",
+        ));
+    }
+    for q in &quads {
+        match q.g() {
+            None => return Err(invalid("unexpected triple in the default graph")),
+            Some(g) if !graphs.contains(&g) => {
+                return Err(NanopubError::Invalid(format!(
+                    "unexpected graph {}",
+                    g.value()
+                )))
+            }
+            _ => {}
+        }
+    }
+    if in_graph(&quads, &assertion).next().is_none() {
+        return Err(invalid("the assertion graph is empty"));
+    }
+    if !in_graph(&quads, &provenance).any(|q| term_eq(q.s(), &assertion)) {
+        return Err(invalid(
+            "the provenance graph must describe the assertion graph",
+        ));
+    }
+    if !in_graph(&quads, &pubinfo).any(|q| term_eq(q.s(), &iri)) {
+        return Err(invalid(
+            "the publication info graph must describe the nanopublication",
+        ));
+    }
+
+    let trusty = match artifact_code_of(&iri.value()) {
+        Some(_) => Some(verify_trusty(&quads, &iri.value())?),
+        None => None,
+    };
+    Ok(Nanopub {
+        iri,
+        head,
+        assertion,
+        provenance,
+        pubinfo,
+        trusty,
+    })
+}
+
+fn in_graph<'a>(quads: &'a [TrustyQuad], g: &'a BoxTerm) -> impl Iterator<Item = &'a TrustyQuad> {
+    quads.iter().filter(move |q| q.g() == Some(g))
+}
+
+fn add_graph<G>(quads: &mut Vec<TrustyQuad>, graph: &G, name: BoxTerm) -> Result<(), NanopubError>
+where
+    G: Graph + ?Sized,
+{
+    for t in graph.triples() {
+        let t = t.map_err(|err| NanopubError::Source(err.to_string()))?;
+        quads.push((
+            [
+                BoxTerm::copy(t.s()),
+                BoxTerm::copy(t.p()),
+                BoxTerm::copy(t.o()),
+            ],
+            Some(name.clone()),
+        ));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ns::rdfs;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    fn example() -> (String, Vec<TrustyQuad>) {
+        let [np, _, assertion, _, _] = temp_iris("tag:np/");
+        let assertion_graph = vec![[
+            iri("tag:alice"),
+            BoxTerm::copy(&rdfs::label),
+            "Alice".to_string().into(),
+        ]];
+        let provenance_graph = vec![[assertion, iri("tag:source"), iri("tag:bob")]];
+        let pubinfo_graph = vec![[np, iri("tag:creator"), iri("tag:bob")]];
+        assemble(
+            "tag:np/",
+            &assertion_graph,
+            &provenance_graph,
+            &pubinfo_graph,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn assemble_and_validate() -> Result<(), Box<dyn std::error::Error>> {
+        let (np_iri, quads) = example();
+        assert!(np_iri.starts_with("tag:np/RA"));
+        assert_eq!(quads.len(), 7);
+        let np = validate(&quads)?;
+        assert_eq!(np.iri.value().as_ref(), np_iri);
+        assert_eq!(
+            np.assertion.value().as_ref(),
+            format!("{}#assertion", np_iri)
+        );
+        assert_eq!(np.trusty, Some(true));
+        Ok(())
+    }
+
+    #[test]
+    fn tampered() -> Result<(), Box<dyn std::error::Error>> {
+        let (_, mut quads) = example();
+        let a = quads[4].clone();
+        quads[4].0[2] = "Eve".to_string().into();
+        assert_eq!(validate(&quads)?.trusty, Some(false));
+
+        quads[4] = (a.0, None);
+        assert!(validate(&quads).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let [node, head, assertion, provenance, pubinfo] = temp_iris("tag:np");
+        let mut quads: Vec<TrustyQuad> = vec![
+            (
+                [
+                    node.clone(),
+                    BoxTerm::copy(&rdf::type_),
+                    BoxTerm::copy(&np::Nanopublication),
+                ],
+                Some(head.clone()),
+            ),
+            (
+                [
+                    node.clone(),
+                    BoxTerm::copy(&np::hasAssertion),
+                    assertion.clone(),
+                ],
+                Some(head.clone()),
+            ),
+            (
+                [
+                    node.clone(),
+                    BoxTerm::copy(&np::hasProvenance),
+                    provenance.clone(),
+                ],
+                Some(head.clone()),
+            ),
+            (
+                [
+                    node.clone(),
+                    BoxTerm::copy(&np::hasPublicationInfo),
+                    pubinfo.clone(),
+                ],
+                Some(head),
+            ),
+        ];
+        // empty assertion graph
+        assert!(validate(&quads).is_err());
+        quads.push((
+            [iri("tag:a"), iri("tag:b"), iri("tag:c")],
+            Some(assertion.clone()),
+        ));
+        // provenance not about the assertion
+        assert!(validate(&quads).is_err());
+        quads.push(([assertion, iri("tag:b"), iri("tag:c")], Some(provenance)));
+        // pubinfo not about the nanopublication
+        assert!(validate(&quads).is_err());
+        quads.push(([node, iri("tag:b"), iri("tag:c")], Some(pubinfo)));
+        let nanopub = validate(&quads).unwrap();
+        assert_eq!(nanopub.trusty, None);
+    }
+}
@@ -0,0 +1,185 @@
+//! Unicode [Normalization Form C] (NFC) of literals and IRIs.
+//!
+//! RDF compares literals and IRIs character by character,
+//! so that the same text, encoded with precomposed or decomposed characters
+//! (e.g. `é` as U+00E9, or as `e` followed by U+0301),
+//! yields different terms.
+//! This module provides an opt-in normalization of the lexical form of literals to NFC,
+//! and a check that IRIs are in NFC
+//! (IRIs are never normalized, as [RFC 3987] requires them to be compared as is).
+//!
+//! Normalization can be applied
+//! * while parsing, with [`ParserConfig::set_nfc`] and [`ParserConfig::set_nfc_iris`], or
+//! * to an existing graph, with [`normalize_graph`] and [`non_nfc_iris`].
+//!
+//! This module requires the `nfc` feature.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph, MutableGraph};
+//! # use sophia::term::BoxTerm;
+//! use sophia::nfc::normalize_graph;
+//!
+//! let s = BoxTerm::new_iri("http://example.org/s")?;
+//! let p = BoxTerm::new_iri("http://example.org/p")?;
+//! let mut g = FastGraph::new();
+//! g.insert(&s, &p, &BoxTerm::new_literal_lang("Cafe\u{301}", "fr")?)?;
+//! g.insert(&s, &p, &BoxTerm::new_literal_lang("Caf\u{e9}", "fr")?)?;
+//! assert_eq!(normalize_graph(&mut g)?, 1);
+//! assert_eq!(g.triples().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Normalization Form C]: https://unicode.org/reports/tr15/
+//! [RFC 3987]: https://tools.ietf.org/html/rfc3987#section-5.3.1
+//! [`ParserConfig::set_nfc`]: ../parser/config/struct.ParserConfig.html#method.set_nfc
+//! [`ParserConfig::set_nfc_iris`]: ../parser/config/struct.ParserConfig.html#method.set_nfc_iris
+//! [`normalize_graph`]: fn.normalize_graph.html
+//! [`non_nfc_iris`]: fn.non_nfc_iris.html
+
+use std::borrow::Cow;
+
+use icu_normalizer::ComposingNormalizerBorrowed;
+use sophia_api::graph::{GResult, Graph, MutableGraph};
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+const NFC: ComposingNormalizerBorrowed<'static> = ComposingNormalizerBorrowed::new_nfc();
+
+/// Whether `txt` is in NFC.
+pub fn is_nfc(txt: &str) -> bool {
+    NFC.is_normalized(txt)
+}
+
+/// Normalize `txt` to NFC (borrowing `txt` if it already is).
+pub fn to_nfc(txt: &str) -> Cow<'_, str> {
+    NFC.normalize(txt)
+}
+
+/// If `term` is a literal whose lexical form is not in NFC,
+/// return the same literal with its lexical form normalized;
+/// otherwise, return `None`.
+pub fn nfc_literal<T>(term: &T) -> Option<BoxTerm>
+where
+    T: TTerm + ?Sized,
+{
+    if term.kind() != TermKind::Literal {
+        return None;
+    }
+    let value = term.value();
+    let normalized = match to_nfc(&value) {
+        Cow::Borrowed(_) => return None,
+        Cow::Owned(normalized) => normalized,
+    };
+    Some(match (term.language(), term.base_direction()) {
+        (Some(tag), Some(dir)) => BoxTerm::new_literal_dir_lang_unchecked(normalized, tag, dir),
+        (Some(tag), None) => BoxTerm::new_literal_lang_unchecked(normalized, tag),
+        (None, _) => {
+            BoxTerm::new_literal_dt_unchecked(normalized, BoxTerm::copy(&term.datatype().unwrap()))
+        }
+    })
+}
+
+/// Normalize to NFC the lexical form of all literals in `graph`,
+/// and return the number of triples that were modified.
+///
+/// Errors raised while querying `graph` are wrapped in `SourceError`,
+/// errors raised while modifying `graph` are wrapped in `SinkError`.
+pub fn normalize_graph<G>(graph: &mut G) -> StreamResult<usize, G::Error, G::MutationError>
+where
+    G: MutableGraph,
+{
+    let mut changes = vec![];
+    for t in graph.triples() {
+        let t = t.map_err(SourceError)?;
+        let (s, o) = (nfc_literal(t.s()), nfc_literal(t.o()));
+        if s.is_some() || o.is_some() {
+            let old = [
+                BoxTerm::copy(t.s()),
+                BoxTerm::copy(t.p()),
+                BoxTerm::copy(t.o()),
+            ];
+            let new = [
+                s.unwrap_or_else(|| old[0].clone()),
+                old[1].clone(),
+                o.unwrap_or_else(|| old[2].clone()),
+            ];
+            changes.push((old, new));
+        }
+    }
+    for (old, new) in &changes {
+        graph.remove(&old[0], &old[1], &old[2]).map_err(SinkError)?;
+        graph.insert(&new[0], &new[1], &new[2]).map_err(SinkError)?;
+    }
+    Ok(changes.len())
+}
+
+/// Return the IRIs of `graph` (including datatypes) that are not in NFC.
+pub fn non_nfc_iris<G>(graph: &G) -> GResult<G, Vec<BoxTerm>>
+where
+    G: Graph,
+{
+    let mut found: Vec<BoxTerm> = vec![];
+    for t in graph.triples() {
+        let t = t?;
+        for term in &[t.s(), t.p(), t.o()] {
+            let iri = match term.kind() {
+                TermKind::Iri => BoxTerm::copy(*term),
+                TermKind::Literal => BoxTerm::copy(&term.datatype().unwrap()),
+                _ => continue,
+            };
+            if !is_nfc(&iri.value()) && !found.iter().any(|f| f == &iri) {
+                found.push(iri);
+            }
+        }
+    }
+    Ok(found)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use sophia_api::ns::xsd;
+    use sophia_api::term::BaseDirection;
+
+    #[test]
+    fn literals() {
+        assert!(is_nfc("Caf\u{e9}"));
+        assert!(!is_nfc("Cafe\u{301}"));
+        assert_eq!(to_nfc("Cafe\u{301}"), "Caf\u{e9}");
+
+        let lit = BoxTerm::new_literal_dt_unchecked("e\u{301}", xsd::token);
+        let norm = nfc_literal(&lit).unwrap();
+        assert_eq!(&norm.value()[..], "\u{e9}");
+        assert_eq!(norm.datatype().unwrap(), xsd::token);
+
+        let lit = BoxTerm::new_literal_dir_lang_unchecked("e\u{301}", "fr", BaseDirection::Ltr);
+        let norm = nfc_literal(&lit).unwrap();
+        assert_eq!(norm.language(), Some("fr"));
+        assert_eq!(norm.base_direction(), Some(BaseDirection::Ltr));
+
+        assert!(nfc_literal(&BoxTerm::new_literal_lang_unchecked("\u{e9}", "fr")).is_none());
+        assert!(nfc_literal(&BoxTerm::new_iri_unchecked("tag:e\u{301}")).is_none());
+    }
+
+    #[test]
+    fn graph() -> Result<(), Box<dyn std::error::Error>> {
+        let s = BoxTerm::new_iri("tag:s")?;
+        let bad = BoxTerm::new_iri("tag:e\u{301}")?;
+        let mut g = FastGraph::new();
+        g.insert(&s, &bad, &BoxTerm::new_literal_lang("e\u{301}", "fr")?)?;
+        g.insert(&s, &bad, &BoxTerm::new_literal_dt("1", bad.clone())?)?;
+        g.insert(&s, &s, &BoxTerm::new_literal_lang("\u{e9}", "fr")?)?;
+        assert_eq!(non_nfc_iris(&g)?, vec![bad.clone()]);
+        assert_eq!(normalize_graph(&mut g)?, 1);
+        assert!(g.contains(&s, &bad, &BoxTerm::new_literal_lang("\u{e9}", "fr")?)?);
+        assert_eq!(normalize_graph(&mut g)?, 0);
+        Ok(())
+    }
+}
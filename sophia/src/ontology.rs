@@ -0,0 +1,427 @@
+//! Introspection of RDFS and OWL ontologies.
+//!
+//! An [`Ontology`] extracts from a graph the classes and properties it declares,
+//! their hierarchies (`rdfs:subClassOf` and `rdfs:subPropertyOf`),
+//! the domains and ranges of properties, and the labels of all these terms.
+//! The transitive closures of the hierarchies are computed once,
+//! when the ontology is built,
+//! so that they can be queried repeatedly (e.g. when building a user interface)
+//! without crunching triples again.
+//!
+//! Note that an `Ontology` is a snapshot:
+//! it does not reflect later changes of the graph it was built from.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, MutableGraph};
+//! # use sophia::ns::{rdf, rdfs};
+//! # use sophia::term::StaticTerm;
+//! use sophia::ontology::Ontology;
+//!
+//! let animal = StaticTerm::new_iri("http://example.org/Animal")?;
+//! let dog = StaticTerm::new_iri("http://example.org/Dog")?;
+//! let puppy = StaticTerm::new_iri("http://example.org/Puppy")?;
+//! let mut g = FastGraph::new();
+//! g.insert(&dog, &rdfs::subClassOf, &animal)?;
+//! g.insert(&puppy, &rdfs::subClassOf, &dog)?;
+//!
+//! let onto = Ontology::new(&g)?;
+//! assert_eq!(onto.classes().count(), 3);
+//! assert!(onto.is_sub_class_of(&puppy, &animal));
+//! assert_eq!(onto.root_classes().collect::<Vec<_>>(), vec![&animal]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Ontology`]: struct.Ontology.html
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use sophia_api::ns::{owl, rdf, rdfs};
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_term::literal::lang::LanguagePreference;
+use sophia_term::RcTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+type Hierarchy = BTreeMap<RcTerm, BTreeSet<RcTerm>>;
+
+/// The classes and properties described by a graph, with their hierarchies.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Ontology {
+    classes: BTreeSet<RcTerm>,
+    properties: BTreeSet<RcTerm>,
+    /// direct super-classes
+    super_classes: Hierarchy,
+    /// all super-classes (transitive closure)
+    all_super_classes: Hierarchy,
+    /// direct super-properties
+    super_properties: Hierarchy,
+    /// all super-properties (transitive closure)
+    all_super_properties: Hierarchy,
+    domains: Hierarchy,
+    ranges: Hierarchy,
+    labels: Hierarchy,
+}
+
+impl Ontology {
+    /// Extract the ontology described by `graph`.
+    ///
+    /// Classes are the instances of `rdfs:Class` and `owl:Class`,
+    /// as well as the terms related by `rdfs:subClassOf`
+    /// or used as the domain or the range of a property.
+    /// Properties are the instances of `rdf:Property` and of the OWL property types,
+    /// as well as the terms related by `rdfs:subPropertyOf`
+    /// or having a domain or a range.
+    pub fn new<G: Graph>(graph: &G) -> GResult<G, Ontology> {
+        let mut onto = Ontology::default();
+        for t in graph.triples() {
+            let t = t?;
+            let (s, p, o) = (t.s(), t.p(), t.o());
+            if term_eq(p, &rdf::type_) {
+                if CLASS_TYPES.iter().any(|c| term_eq(o, *c)) {
+                    onto.classes.insert(RcTerm::copy(s));
+                } else if PROPERTY_TYPES.iter().any(|c| term_eq(o, *c)) {
+                    onto.properties.insert(RcTerm::copy(s));
+                }
+            } else if term_eq(p, &rdfs::subClassOf) {
+                insert(&mut onto.super_classes, s, o);
+                onto.classes.insert(RcTerm::copy(s));
+                onto.classes.insert(RcTerm::copy(o));
+            } else if term_eq(p, &rdfs::subPropertyOf) {
+                insert(&mut onto.super_properties, s, o);
+                onto.properties.insert(RcTerm::copy(s));
+                onto.properties.insert(RcTerm::copy(o));
+            } else if term_eq(p, &rdfs::domain) {
+                insert(&mut onto.domains, s, o);
+                onto.properties.insert(RcTerm::copy(s));
+                onto.classes.insert(RcTerm::copy(o));
+            } else if term_eq(p, &rdfs::range) {
+                insert(&mut onto.ranges, s, o);
+                onto.properties.insert(RcTerm::copy(s));
+                // datatypes are not classes of individuals
+                if !graph.contains(o, &rdf::type_, &rdfs::Datatype)? && !is_xsd(o) {
+                    onto.classes.insert(RcTerm::copy(o));
+                }
+            } else if term_eq(p, &rdfs::label) && o.kind() == TermKind::Literal {
+                insert(&mut onto.labels, s, o);
+            }
+        }
+        // blank nodes are used by OWL for anonymous class expressions
+        onto.classes.retain(|c| c.kind() == TermKind::Iri);
+        onto.properties.retain(|p| p.kind() == TermKind::Iri);
+        onto.all_super_classes = closure(&onto.super_classes);
+        onto.all_super_properties = closure(&onto.super_properties);
+        Ok(onto)
+    }
+
+    /// The classes of this ontology, sorted.
+    pub fn classes(&self) -> impl Iterator<Item = &RcTerm> + '_ {
+        self.classes.iter()
+    }
+
+    /// The properties of this ontology, sorted.
+    pub fn properties(&self) -> impl Iterator<Item = &RcTerm> + '_ {
+        self.properties.iter()
+    }
+
+    /// Whether `term` is a class of this ontology.
+    pub fn is_class<T: TTerm + ?Sized>(&self, term: &T) -> bool {
+        self.classes.contains(&RcTerm::copy(term))
+    }
+
+    /// Whether `term` is a property of this ontology.
+    pub fn is_property<T: TTerm + ?Sized>(&self, term: &T) -> bool {
+        self.properties.contains(&RcTerm::copy(term))
+    }
+
+    /// The classes having no super-class (other than themselves) in this ontology.
+    pub fn root_classes(&self) -> impl Iterator<Item = &RcTerm> + '_ {
+        self.classes.iter().filter(move |c| {
+            self.all_super_classes
+                .get(*c)
+                .is_none_or(|sup| sup.iter().all(|s| s == *c))
+        })
+    }
+
+    /// The direct super-classes of `class`.
+    pub fn direct_super_classes<T: TTerm + ?Sized>(&self, class: &T) -> Vec<&RcTerm> {
+        get(&self.super_classes, class)
+    }
+
+    /// All the super-classes of `class` (transitively).
+    pub fn super_classes<T: TTerm + ?Sized>(&self, class: &T) -> Vec<&RcTerm> {
+        get(&self.all_super_classes, class)
+    }
+
+    /// The direct sub-classes of `class`.
+    pub fn direct_sub_classes<T: TTerm + ?Sized>(&self, class: &T) -> Vec<&RcTerm> {
+        inverse(&self.super_classes, class)
+    }
+
+    /// All the sub-classes of `class` (transitively).
+    pub fn sub_classes<T: TTerm + ?Sized>(&self, class: &T) -> Vec<&RcTerm> {
+        inverse(&self.all_super_classes, class)
+    }
+
+    /// Whether `sub` is a sub-class of `sup` (transitively).
+    ///
+    /// As in RDFS, every class is a sub-class of itself.
+    pub fn is_sub_class_of<T, U>(&self, sub: &T, sup: &U) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        term_eq(sub, sup) || self.super_classes(sub).iter().any(|c| term_eq(*c, sup))
+    }
+
+    /// The direct super-properties of `property`.
+    pub fn direct_super_properties<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        get(&self.super_properties, property)
+    }
+
+    /// All the super-properties of `property` (transitively).
+    pub fn super_properties<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        get(&self.all_super_properties, property)
+    }
+
+    /// The direct sub-properties of `property`.
+    pub fn direct_sub_properties<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        inverse(&self.super_properties, property)
+    }
+
+    /// All the sub-properties of `property` (transitively).
+    pub fn sub_properties<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        inverse(&self.all_super_properties, property)
+    }
+
+    /// Whether `sub` is a sub-property of `sup` (transitively).
+    ///
+    /// As in RDFS, every property is a sub-property of itself.
+    pub fn is_sub_property_of<T, U>(&self, sub: &T, sup: &U) -> bool
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        term_eq(sub, sup) || self.super_properties(sub).iter().any(|p| term_eq(*p, sup))
+    }
+
+    /// The domains of `property`, including those inherited from its super-properties.
+    pub fn domains<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        self.inherited(&self.domains, property)
+    }
+
+    /// The ranges of `property`, including those inherited from its super-properties.
+    pub fn ranges<T: TTerm + ?Sized>(&self, property: &T) -> Vec<&RcTerm> {
+        self.inherited(&self.ranges, property)
+    }
+
+    /// The properties whose domain includes `class` or one of its super-classes,
+    /// i.e. the properties applicable to the instances of `class`.
+    pub fn properties_of<T: TTerm + ?Sized>(&self, class: &T) -> Vec<&RcTerm> {
+        self.properties
+            .iter()
+            .filter(|p| {
+                self.domains(*p)
+                    .iter()
+                    .any(|d| self.is_sub_class_of(class, *d))
+            })
+            .collect()
+    }
+
+    /// The `rdfs:label`s of `term`.
+    pub fn labels<T: TTerm + ?Sized>(&self, term: &T) -> Vec<&RcTerm> {
+        get(&self.labels, term)
+    }
+
+    /// The `rdfs:label` of `term` best matching the given language preference
+    /// (see [`LanguagePreference::select`]).
+    ///
+    /// [`LanguagePreference::select`]: ../term/literal/lang/struct.LanguagePreference.html#method.select
+    pub fn label<T: TTerm + ?Sized>(
+        &self,
+        term: &T,
+        preference: &LanguagePreference,
+    ) -> Option<&RcTerm> {
+        preference.select(self.labels.get(&RcTerm::copy(term))?)
+    }
+
+    fn inherited<'s, T: TTerm + ?Sized>(
+        &'s self,
+        map: &'s Hierarchy,
+        property: &T,
+    ) -> Vec<&'s RcTerm> {
+        let mut found: BTreeSet<&RcTerm> = get(map, property).into_iter().collect();
+        for sup in self.super_properties(property) {
+            found.extend(get(map, sup));
+        }
+        found.into_iter().collect()
+    }
+}
+
+const CLASS_TYPES: [&sophia_api::term::SimpleIri<'static>; 2] = [&rdfs::Class, &owl::Class];
+
+const PROPERTY_TYPES: [&sophia_api::term::SimpleIri<'static>; 7] = [
+    &rdf::Property,
+    &owl::ObjectProperty,
+    &owl::DatatypeProperty,
+    &owl::AnnotationProperty,
+    &owl::FunctionalProperty,
+    &owl::TransitiveProperty,
+    &owl::SymmetricProperty,
+];
+
+fn insert<S, O>(map: &mut Hierarchy, s: &S, o: &O)
+where
+    S: TTerm + ?Sized,
+    O: TTerm + ?Sized,
+{
+    map.entry(RcTerm::copy(s))
+        .or_default()
+        .insert(RcTerm::copy(o));
+}
+
+fn get<'a, T: TTerm + ?Sized>(map: &'a Hierarchy, term: &T) -> Vec<&'a RcTerm> {
+    map.get(&RcTerm::copy(term))
+        .map(|set| set.iter().collect())
+        .unwrap_or_default()
+}
+
+fn inverse<'a, T: TTerm + ?Sized>(map: &'a Hierarchy, term: &T) -> Vec<&'a RcTerm> {
+    let term = RcTerm::copy(term);
+    map.iter()
+        .filter(|(_, sup)| sup.contains(&term))
+        .map(|(sub, _)| sub)
+        .collect()
+}
+
+/// Compute the transitive closure of `direct` (cycles are allowed).
+fn closure(direct: &Hierarchy) -> Hierarchy {
+    let mut all = Hierarchy::new();
+    for start in direct.keys() {
+        let mut reached = BTreeSet::new();
+        let mut todo: Vec<&RcTerm> = direct[start].iter().collect();
+        while let Some(next) = todo.pop() {
+            if reached.insert(next.clone()) {
+                if let Some(sup) = direct.get(next) {
+                    todo.extend(sup.iter());
+                }
+            }
+        }
+        all.insert(start.clone(), reached);
+    }
+    all
+}
+
+fn is_xsd<T: TTerm + ?Sized>(term: &T) -> bool {
+    term.kind() == TermKind::Iri && term.value().starts_with(sophia_api::ns::xsd::PREFIX)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::MutableGraph;
+    use crate::test_util::t;
+    use sophia_api::ns::xsd;
+    use sophia_term::BoxTerm;
+
+    fn names(terms: Vec<&RcTerm>) -> Vec<String> {
+        terms.iter().map(|t| t.value()[4..].to_string()).collect()
+    }
+
+    fn data() -> FastGraph {
+        let mut g = FastGraph::new();
+        g.insert(&t("Agent"), &rdf::type_, &owl::Class).unwrap();
+        g.insert(&t("Person"), &rdfs::subClassOf, &t("Agent"))
+            .unwrap();
+        g.insert(&t("Student"), &rdfs::subClassOf, &t("Person"))
+            .unwrap();
+        g.insert(&t("Org"), &rdfs::subClassOf, &t("Agent")).unwrap();
+        g.insert(&t("name"), &rdf::type_, &owl::DatatypeProperty)
+            .unwrap();
+        g.insert(&t("name"), &rdfs::domain, &t("Agent")).unwrap();
+        g.insert(&t("name"), &rdfs::range, &xsd::string).unwrap();
+        g.insert(&t("nick"), &rdfs::subPropertyOf, &t("name"))
+            .unwrap();
+        g.insert(&t("studies"), &rdfs::domain, &t("Student"))
+            .unwrap();
+        let label = |txt, tag| BoxTerm::new_literal_lang(txt, tag).unwrap();
+        g.insert(&t("Person"), &rdfs::label, &label("person", "en"))
+            .unwrap();
+        g.insert(&t("Person"), &rdfs::label, &label("personne", "fr"))
+            .unwrap();
+        g
+    }
+
+    #[test]
+    fn classes() -> Result<(), Box<dyn std::error::Error>> {
+        let onto = Ontology::new(&data())?;
+        let classes: Vec<_> = onto.classes().collect();
+        assert_eq!(names(classes), vec!["Agent", "Org", "Person", "Student"]);
+        assert_eq!(names(onto.root_classes().collect()), vec!["Agent"]);
+        assert_eq!(
+            names(onto.direct_super_classes(&t("Student"))),
+            vec!["Person"]
+        );
+        assert_eq!(
+            names(onto.super_classes(&t("Student"))),
+            vec!["Agent", "Person"]
+        );
+        assert_eq!(
+            names(onto.direct_sub_classes(&t("Agent"))),
+            vec!["Org", "Person"]
+        );
+        assert_eq!(
+            names(onto.sub_classes(&t("Agent"))),
+            vec!["Org", "Person", "Student"]
+        );
+        assert!(onto.is_sub_class_of(&t("Student"), &t("Agent")));
+        assert!(onto.is_sub_class_of(&t("Org"), &t("Org")));
+        assert!(!onto.is_sub_class_of(&t("Agent"), &t("Person")));
+        assert!(!onto.is_class(&xsd::string));
+        Ok(())
+    }
+
+    #[test]
+    fn properties() -> Result<(), Box<dyn std::error::Error>> {
+        let onto = Ontology::new(&data())?;
+        assert_eq!(
+            names(onto.properties().collect()),
+            vec!["name", "nick", "studies"]
+        );
+        assert!(onto.is_sub_property_of(&t("nick"), &t("name")));
+        assert_eq!(names(onto.sub_properties(&t("name"))), vec!["nick"]);
+        assert_eq!(names(onto.domains(&t("nick"))), vec!["Agent"]);
+        assert_eq!(onto.ranges(&t("nick")), vec![&RcTerm::copy(&xsd::string)]);
+        assert_eq!(
+            names(onto.properties_of(&t("Student"))),
+            vec!["name", "nick", "studies"]
+        );
+        assert_eq!(names(onto.properties_of(&t("Org"))), vec!["name", "nick"]);
+        Ok(())
+    }
+
+    #[test]
+    fn labels_and_cycles() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = data();
+        g.insert(&t("Agent"), &rdfs::subClassOf, &t("Student"))?;
+        let onto = Ontology::new(&g)?;
+        assert!(onto.is_sub_class_of(&t("Agent"), &t("Person")));
+        assert_eq!(onto.root_classes().count(), 0);
+
+        assert_eq!(onto.labels(&t("Person")).len(), 2);
+        let fr = LanguagePreference::parse("fr-CA, en");
+        assert_eq!(
+            &onto.label(&t("Person"), &fr).unwrap().value()[..],
+            "personne"
+        );
+        assert!(onto.label(&t("Agent"), &fr).is_none());
+        Ok(())
+    }
+}
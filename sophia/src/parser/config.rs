@@ -0,0 +1,1336 @@
+//! Configuration of the strictness of parsers, shared across all syntaxes.
+//!
+//! Real-world data often deviates from the specifications.
+//! A [`ParserConfig`] lets applications decide how much laxness they accept,
+//! when parsing data through [`Syntax::parse_triples_with`] or [`Syntax::parse_quads_with`],
+//! or directly with the `parse_with_config` method of the [Turtle] and [TriG] parsers:
+//!
+//! ```
+//! # use sophia::parser::config::ParserConfig;
+//! # use sophia::syntax::Syntax;
+//! let mut config = ParserConfig::strict();
+//! config.set_max_literal_length(Some(1024));
+//! let data = r#"<tag:a> <tag:b> "hello"@en-US."#;
+//! let triples: Result<Vec<_>, _> = Syntax::Turtle
+//!     .parse_triples_with(data.as_bytes(), &config)
+//!     .collect();
+//! assert_eq!(triples?.len(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Prefixed names with an undefined prefix are rejected,
+//! unless [`set_undefined_prefixes`] is enabled, in which case they are kept verbatim as IRIs.
+//! This is only supported by the Turtle parser, which then reads the whole data before parsing it;
+//! the other parsers always reject them.
+//! Other aspects of the grammar are always enforced by the parsers.
+//!
+//! # Resource limits
+//!
+//! Services parsing untrusted data can also bound the resources used by the parsers,
+//! with [`set_max_input_size`], [`set_max_statements`], [`set_max_term_length`],
+//! [`set_max_nesting`] and [`set_timeout`].
+//! Exceeding a limit aborts parsing with a [`ConfigError`].
+//! The size, nesting and term length limits are checked while the data is read,
+//! so that a pathological document is rejected before the parser buffers it
+//! (or overflows its stack on deeply nested structures).
+//!
+//! ```
+//! # use sophia::parser::config::{ConfigError, ParserConfig};
+//! # use sophia::syntax::Syntax;
+//! let mut config = ParserConfig::default();
+//! config.set_max_nesting(Some(16));
+//! let data = format!("<tag:s> <tag:p> {}{}.", "(".repeat(10_000), ")".repeat(10_000));
+//! let err = Syntax::Turtle
+//!     .parse_triples_with(data.as_bytes(), &config)
+//!     .find_map(Result::err)
+//!     .unwrap();
+//! assert!(matches!(
+//!     err.0.downcast_ref::<ConfigError>(),
+//!     Some(ConfigError::TooDeeplyNested { max: 16 })
+//! ));
+//! ```
+//!
+//! [`ParserConfig`]: struct.ParserConfig.html
+//! [`Syntax::parse_triples_with`]: ../../syntax/enum.Syntax.html#method.parse_triples_with
+//! [`Syntax::parse_quads_with`]: ../../syntax/enum.Syntax.html#method.parse_quads_with
+//! [`ConfigError`]: enum.ConfigError.html
+//! [Turtle]: ../turtle/struct.TurtleParser.html#method.parse_with_config
+//! [TriG]: ../trig/struct.TriGParser.html#method.parse_with_config
+//! [`set_undefined_prefixes`]: struct.ParserConfig.html#method.set_undefined_prefixes
+//! [`set_max_input_size`]: struct.ParserConfig.html#method.set_max_input_size
+//! [`set_max_statements`]: struct.ParserConfig.html#method.set_max_statements
+//! [`set_max_term_length`]: struct.ParserConfig.html#method.set_max_term_length
+//! [`set_max_nesting`]: struct.ParserConfig.html#method.set_max_nesting
+//! [`set_timeout`]: struct.ParserConfig.html#method.set_timeout
+
+use std::io::{self, BufRead, Read};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::blank_node::generator::{BnodeIdGenerator, SharedBnodeIds};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::syntax::dynamic::{BoxQuad, BoxTriple, DynError, DynQuadSource, DynTripleSource};
+use crate::syntax::RdfVersion;
+
+/// The placeholder base IRI used by parsers when no base IRI is provided.
+pub(crate) const NO_BASE: &str = "x-no-base:///";
+
+/// This error is raised when parsed data does not comply with a [`ParserConfig`].
+///
+/// [`ParserConfig`]: struct.ParserConfig.html
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Relative IRI <{0}> with no base IRI")]
+    RelativeIri(String),
+    #[error("Invalid language tag {0:?}")]
+    InvalidLanguageTag(String),
+    #[error("Literal of length {length} exceeds the maximum length {max}")]
+    LiteralTooLong { length: usize, max: usize },
+    #[error("Statement only valid in generalized RDF: {0}")]
+    Generalized(String),
+    #[error("Term only valid in RDF 1.2: {0}")]
+    Rdf12(String),
+    #[error("Term of length {length} exceeds the maximum length {max}")]
+    TermTooLong { length: usize, max: usize },
+    #[error("Data exceeds the maximum size of {max} bytes")]
+    InputTooLarge { max: u64 },
+    #[error("Data exceeds the maximum number of {max} statements")]
+    TooManyStatements { max: usize },
+    #[error("Data exceeds the maximum nesting depth {max}")]
+    TooDeeplyNested { max: usize },
+    #[error("Parsing exceeds the timeout of {0:?}")]
+    Timeout(Duration),
+    #[cfg(feature = "nfc")]
+    #[error("IRI <{0}> is not in Unicode Normalization Form C")]
+    NonNfcIri(String),
+}
+
+/// Parser configuration, shared across all syntaxes.
+///
+/// The default configuration is lax,
+/// while [`strict`](#method.strict) enforces all the checks.
+#[derive(Clone, Debug)]
+pub struct ParserConfig {
+    base: Option<String>,
+    relative_iris: bool,
+    strict_language_tags: bool,
+    undefined_prefixes: bool,
+    max_literal_length: Option<usize>,
+    max_term_length: Option<usize>,
+    max_input_size: Option<u64>,
+    max_statements: Option<usize>,
+    max_nesting: Option<usize>,
+    timeout: Option<Duration>,
+    generalized: bool,
+    rdf_version: RdfVersion,
+    bnode_ids: Option<SharedBnodeIds>,
+    #[cfg(feature = "nfc")]
+    nfc: bool,
+    #[cfg(feature = "nfc")]
+    nfc_iris: bool,
+}
+
+impl Default for ParserConfig {
+    fn default() -> ParserConfig {
+        ParserConfig {
+            base: None,
+            relative_iris: true,
+            strict_language_tags: false,
+            undefined_prefixes: false,
+            max_literal_length: None,
+            max_term_length: None,
+            max_input_size: None,
+            max_statements: None,
+            max_nesting: None,
+            timeout: None,
+            generalized: false,
+            rdf_version: RdfVersion::Rdf12,
+            bnode_ids: None,
+            #[cfg(feature = "nfc")]
+            nfc: false,
+            #[cfg(feature = "nfc")]
+            nfc_iris: false,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// A configuration rejecting relative IRIs without a base IRI,
+    /// and language tags that are not well-formed according to [BCP47].
+    ///
+    /// [BCP47]: https://tools.ietf.org/html/bcp47
+    pub fn strict() -> ParserConfig {
+        ParserConfig {
+            relative_iris: false,
+            strict_language_tags: true,
+            ..ParserConfig::default()
+        }
+    }
+
+    /// The base IRI used to resolve relative IRIs.
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// Set the base IRI used to resolve relative IRIs.
+    pub fn set_base(&mut self, base: Option<String>) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// Whether relative IRIs are accepted (and kept relative) when no base IRI is set.
+    pub fn relative_iris(&self) -> bool {
+        self.relative_iris
+    }
+
+    /// Set whether relative IRIs are accepted (and kept relative) when no base IRI is set.
+    pub fn set_relative_iris(&mut self, relative_iris: bool) -> &mut Self {
+        self.relative_iris = relative_iris;
+        self
+    }
+
+    /// Whether language tags must be well-formed according to [BCP47].
+    ///
+    /// [BCP47]: https://tools.ietf.org/html/bcp47
+    pub fn strict_language_tags(&self) -> bool {
+        self.strict_language_tags
+    }
+
+    /// Set whether language tags must be well-formed according to [BCP47].
+    ///
+    /// [BCP47]: https://tools.ietf.org/html/bcp47
+    pub fn set_strict_language_tags(&mut self, strict: bool) -> &mut Self {
+        self.strict_language_tags = strict;
+        self
+    }
+
+    /// Whether prefixed names with an undefined prefix are accepted.
+    ///
+    /// This is `false` by default, even in the lax configuration.
+    pub fn undefined_prefixes(&self) -> bool {
+        self.undefined_prefixes
+    }
+
+    /// Set whether prefixed names with an undefined prefix are accepted,
+    /// in which case they are kept verbatim as IRIs (e.g. `ex:alice` becomes `<ex:alice>`).
+    ///
+    /// This is only supported by the [Turtle parser](../turtle/struct.TurtleParser.html#method.parse_with_config),
+    /// which then reads the whole data before yielding the first triple.
+    pub fn set_undefined_prefixes(&mut self, undefined_prefixes: bool) -> &mut Self {
+        self.undefined_prefixes = undefined_prefixes;
+        self
+    }
+
+    /// The maximum length (in characters) of literals, if any.
+    pub fn max_literal_length(&self) -> Option<usize> {
+        self.max_literal_length
+    }
+
+    /// Set the maximum length (in characters) of literals.
+    pub fn set_max_literal_length(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_literal_length = max;
+        self
+    }
+
+    /// The maximum length (in bytes) of the value of any term
+    /// (IRI, blank node identifier or lexical form), if any.
+    pub fn max_term_length(&self) -> Option<usize> {
+        self.max_term_length
+    }
+
+    /// Set the maximum length (in bytes) of the value of any term.
+    ///
+    /// With Turtle-based syntaxes, grossly oversized terms are rejected while the data is read.
+    pub fn set_max_term_length(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_term_length = max;
+        self
+    }
+
+    /// The maximum size (in bytes) of the parsed data, if any.
+    pub fn max_input_size(&self) -> Option<u64> {
+        self.max_input_size
+    }
+
+    /// Set the maximum size (in bytes) of the parsed data.
+    pub fn set_max_input_size(&mut self, max: Option<u64>) -> &mut Self {
+        self.max_input_size = max;
+        self
+    }
+
+    /// The maximum number of statements (triples or quads) yielded by the parser, if any.
+    pub fn max_statements(&self) -> Option<usize> {
+        self.max_statements
+    }
+
+    /// Set the maximum number of statements (triples or quads) yielded by the parser.
+    pub fn set_max_statements(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_statements = max;
+        self
+    }
+
+    /// The maximum nesting depth of the parsed data, if any.
+    pub fn max_nesting(&self) -> Option<usize> {
+        self.max_nesting
+    }
+
+    /// Set the maximum nesting depth of the parsed data.
+    ///
+    /// This applies to the Turtle-based syntaxes, where blank node property lists,
+    /// collections, quoted triples and graphs can be nested.
+    /// The RDF/XML parser does not recurse on nested elements,
+    /// and is not concerned by this limit.
+    pub fn set_max_nesting(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_nesting = max;
+        self
+    }
+
+    /// The maximum duration of parsing, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Set the maximum duration of parsing,
+    /// counted from the first read of the data.
+    ///
+    /// As parsing is lazy, this includes the time spent by the consumer of the statements.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether statements only valid in the [generalized RDF model] are accepted
+    /// (e.g. literals as subjects, or blank nodes as predicates).
+    ///
+    /// This is `false` by default, even in the lax configuration.
+    ///
+    /// [generalized RDF model]: ../../index.html#generalized-vs-strict-rdf-model
+    pub fn generalized(&self) -> bool {
+        self.generalized
+    }
+
+    /// Set whether statements only valid in the [generalized RDF model] are accepted.
+    ///
+    /// Standard syntaxes can not express such statements,
+    /// but [Generalized TriG](../gtrig/index.html) can;
+    /// its output can be checked with [`check_quad`](#method.check_quad).
+    ///
+    /// [generalized RDF model]: ../../index.html#generalized-vs-strict-rdf-model
+    pub fn set_generalized(&mut self, generalized: bool) -> &mut Self {
+        self.generalized = generalized;
+        self
+    }
+
+    /// The version of RDF that parsed data must comply with.
+    ///
+    /// This is [RDF 1.2](../../syntax/enum.RdfVersion.html) by default,
+    /// even in the strict configuration.
+    pub fn rdf_version(&self) -> RdfVersion {
+        self.rdf_version
+    }
+
+    /// Set the version of RDF that parsed data must comply with.
+    ///
+    /// In RDF 1.1 mode, terms introduced by RDF 1.2
+    /// (e.g. directional language-tagged strings, as parsed by [`nt12`](../nt12/index.html))
+    /// and quoted triples in the Turtle-based syntaxes
+    /// are rejected with [`ConfigError::Rdf12`](enum.ConfigError.html#variant.Rdf12).
+    pub fn set_rdf_version(&mut self, rdf_version: RdfVersion) -> &mut Self {
+        self.rdf_version = rdf_version;
+        self
+    }
+
+    /// The generator used to relabel blank nodes, if any.
+    pub fn bnode_ids(&self) -> Option<&SharedBnodeIds> {
+        self.bnode_ids.as_ref()
+    }
+
+    /// Set the generator used to relabel blank nodes
+    /// (see [`generator`](../../term/blank_node/generator/index.html)).
+    ///
+    /// By default, blank nodes keep the identifier chosen by the parser
+    /// (which is the label used in the document, if any).
+    /// With a generator, each blank node is relabelled
+    /// from the identifier chosen by the parser.
+    /// The generator is shared by all clones of this configuration,
+    /// and by all the documents parsed with it.
+    pub fn set_bnode_ids(&mut self, bnode_ids: Option<SharedBnodeIds>) -> &mut Self {
+        self.bnode_ids = bnode_ids;
+        self
+    }
+
+    /// Start a new blank node scope in the generator of this configuration, if any
+    /// (see [scopes](../../term/blank_node/generator/index.html#scopes)).
+    ///
+    /// Blank node labels of the terms checked afterwards
+    /// (i.e. of the triples or quads consumed afterwards, as parsing is lazy)
+    /// are distinct from those of any other scope,
+    /// until the matching call to [`leave_bnode_scope`](#method.leave_bnode_scope).
+    /// Entering a scope before each document therefore prevents
+    /// blank nodes with the same label in different documents from being conflated.
+    ///
+    /// Without a generator, blank nodes keep their label, and this method does nothing.
+    pub fn enter_bnode_scope(&self) {
+        if let Some(bnode_ids) = &self.bnode_ids {
+            bnode_ids.clone().enter_scope();
+        }
+    }
+
+    /// Leave the current blank node scope in the generator of this configuration, if any.
+    ///
+    /// # Panics
+    /// if no scope has been entered (with the built-in generators).
+    pub fn leave_bnode_scope(&self) {
+        if let Some(bnode_ids) = &self.bnode_ids {
+            bnode_ids.clone().leave_scope();
+        }
+    }
+
+    /// Whether the lexical form of literals is normalized to
+    /// [Unicode Normalization Form C](../../nfc/index.html).
+    ///
+    /// This is `false` by default, even in the strict configuration.
+    /// Requires the `nfc` feature.
+    #[cfg(feature = "nfc")]
+    pub fn nfc(&self) -> bool {
+        self.nfc
+    }
+
+    /// Set whether the lexical form of literals is normalized to
+    /// [Unicode Normalization Form C](../../nfc/index.html).
+    ///
+    /// Requires the `nfc` feature.
+    #[cfg(feature = "nfc")]
+    pub fn set_nfc(&mut self, nfc: bool) -> &mut Self {
+        self.nfc = nfc;
+        self
+    }
+
+    /// Whether IRIs that are not in [Unicode Normalization Form C](../../nfc/index.html)
+    /// are rejected.
+    ///
+    /// This is `false` by default, even in the strict configuration.
+    /// Requires the `nfc` feature.
+    #[cfg(feature = "nfc")]
+    pub fn nfc_iris(&self) -> bool {
+        self.nfc_iris
+    }
+
+    /// Set whether IRIs that are not in [Unicode Normalization Form C](../../nfc/index.html)
+    /// are rejected.
+    ///
+    /// Requires the `nfc` feature.
+    #[cfg(feature = "nfc")]
+    pub fn set_nfc_iris(&mut self, nfc_iris: bool) -> &mut Self {
+        self.nfc_iris = nfc_iris;
+        self
+    }
+
+    /// Check that a triple complies with this configuration,
+    /// and fix the IRIs resolved against the placeholder base IRI.
+    pub fn check_triple(&self, triple: BoxTriple) -> Result<BoxTriple, ConfigError> {
+        if !self.generalized && triple.is_generalized() {
+            return Err(ConfigError::Generalized(format!(
+                "{} {} {}",
+                triple.s(),
+                triple.p(),
+                triple.o()
+            )));
+        }
+        let [s, p, o] = triple;
+        Ok([
+            self.check_term(s)?,
+            self.check_term(p)?,
+            self.check_term(o)?,
+        ])
+    }
+
+    /// Check that a quad complies with this configuration,
+    /// and fix the IRIs resolved against the placeholder base IRI.
+    pub fn check_quad(&self, quad: BoxQuad) -> Result<BoxQuad, ConfigError> {
+        if !self.generalized && quad.is_generalized() {
+            let (spo, g) = &quad;
+            return Err(ConfigError::Generalized(match g {
+                None => format!("{} {} {}", spo[0], spo[1], spo[2]),
+                Some(g) => format!("{} {} {} {}", spo[0], spo[1], spo[2], g),
+            }));
+        }
+        let (spo, g) = quad;
+        Ok((
+            self.check_triple(spo)?,
+            g.map(|g| self.check_term(g)).transpose()?,
+        ))
+    }
+
+    fn check_term(&self, term: BoxTerm) -> Result<BoxTerm, ConfigError> {
+        if let Some(max) = self.max_term_length {
+            let length = term.value().len();
+            if length > max {
+                return Err(ConfigError::TermTooLong { length, max });
+            }
+        }
+        match term.kind() {
+            TermKind::Iri => self.check_iri(term),
+            TermKind::Literal => {
+                if !self.rdf_version.allows(&term) {
+                    return Err(ConfigError::Rdf12(term.to_string()));
+                }
+                if let Some(max) = self.max_literal_length {
+                    let length = term.value().chars().count();
+                    if length > max {
+                        return Err(ConfigError::LiteralTooLong { length, max });
+                    }
+                }
+                if self.strict_language_tags {
+                    if let Some(tag) = term.language() {
+                        if !is_valid_language_tag(tag) {
+                            return Err(ConfigError::InvalidLanguageTag(tag.to_string()));
+                        }
+                    }
+                }
+                #[cfg(feature = "nfc")]
+                let term = match self.nfc {
+                    true => crate::nfc::nfc_literal(&term).unwrap_or(term),
+                    false => term,
+                };
+                if let Some(dt) = term.datatype() {
+                    if dt.value().starts_with(NO_BASE) {
+                        let dt = self.check_iri(BoxTerm::copy(&dt))?;
+                        return Ok(BoxTerm::new_literal_dt_unchecked(&*term.value(), dt));
+                    }
+                    #[cfg(feature = "nfc")]
+                    if self.nfc_iris {
+                        self.check_iri(BoxTerm::copy(&dt))?;
+                    }
+                }
+                Ok(term)
+            }
+            TermKind::BlankNode => match &self.bnode_ids {
+                Some(bnode_ids) => {
+                    let id = bnode_ids.clone().generate(Some(&term.value()));
+                    Ok(BoxTerm::new_bnode_unchecked(id))
+                }
+                None => Ok(term),
+            },
+            _ => Ok(term),
+        }
+    }
+
+    fn check_iri(&self, iri: BoxTerm) -> Result<BoxTerm, ConfigError> {
+        let relative = iri.value().strip_prefix(NO_BASE).map(str::to_string);
+        let relative = match relative {
+            #[cfg(feature = "nfc")]
+            None if self.nfc_iris && !crate::nfc::is_nfc(&iri.value()) => {
+                return Err(ConfigError::NonNfcIri(iri.value().to_string()))
+            }
+            None => return Ok(iri),
+            Some(relative) => relative,
+        };
+        if self.relative_iris {
+            #[cfg(feature = "nfc")]
+            if self.nfc_iris && !crate::nfc::is_nfc(&relative) {
+                return Err(ConfigError::NonNfcIri(relative));
+            }
+            Ok(BoxTerm::new_iri_unchecked(relative))
+        } else {
+            Err(ConfigError::RelativeIri(relative))
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Parse `data` with `parse`, enforcing this configuration on the input and the triples.
+    ///
+    /// `turtle` indicates whether `data` is in a Turtle-based syntax
+    /// (see [`guard_input`](#method.guard_input)).
+    pub(crate) fn parse_triples<'a, B, F>(
+        &self,
+        data: B,
+        turtle: bool,
+        parse: F,
+    ) -> DynTripleSource<'a>
+    where
+        B: BufRead + 'a,
+        F: FnOnce(GuardedRead<B>) -> DynTripleSource<'a>,
+    {
+        let config = self.clone();
+        let data = config.guard_input(data, turtle);
+        let tripwire = data.tripwire();
+        let triples = parse(data);
+        Box::new(
+            config
+                .guard_statements(tripwire, triples)
+                .map(move |res| res.and_then(|t| config.check_triple(t).map_err(DynError::new))),
+        )
+    }
+
+    /// Parse `data` with `parse`, enforcing this configuration on the input and the quads.
+    ///
+    /// `turtle` indicates whether `data` is in a Turtle-based syntax
+    /// (see [`guard_input`](#method.guard_input)).
+    pub(crate) fn parse_quads<'a, B, F>(&self, data: B, turtle: bool, parse: F) -> DynQuadSource<'a>
+    where
+        B: BufRead + 'a,
+        F: FnOnce(GuardedRead<B>) -> DynQuadSource<'a>,
+    {
+        let config = self.clone();
+        let data = config.guard_input(data, turtle);
+        let tripwire = data.tripwire();
+        let quads = parse(data);
+        Box::new(
+            config
+                .guard_statements(tripwire, quads)
+                .map(move |res| res.and_then(|q| config.check_quad(q).map_err(DynError::new))),
+        )
+    }
+
+    /// Wrap `data` in a reader enforcing the limits of this configuration.
+    ///
+    /// `turtle` indicates whether `data` is in a Turtle-based syntax,
+    /// in which case nesting, term lengths and quoted triples are also checked.
+    pub(crate) fn guard_input<R: BufRead>(&self, data: R, turtle: bool) -> GuardedRead<R> {
+        let scanner = match (self.max_nesting, self.max_term_length) {
+            _ if !turtle => None,
+            (None, None) if self.rdf_version.allows_quoted_triples() => None,
+            (max_nesting, max_term_length) => {
+                Some(Scanner::new(max_nesting, max_term_length, self.rdf_version))
+            }
+        };
+        GuardedRead {
+            inner: data,
+            max_input_size: self.max_input_size,
+            timeout: self.timeout,
+            scanner,
+            scanned: 0,
+            total: 0,
+            tripwire: Arc::default(),
+        }
+    }
+
+    /// Wrap `statements`, parsed from a reader returned by [`guard_input`](#method.guard_input),
+    /// in an iterator enforcing the limits of this configuration.
+    pub(crate) fn guard_statements<'a, T: 'a>(
+        &self,
+        tripwire: Arc<Mutex<Tripwire>>,
+        mut statements: Box<dyn Iterator<Item = Result<T, DynError>> + 'a>,
+    ) -> Box<dyn Iterator<Item = Result<T, DynError>> + 'a> {
+        let max_statements = self.max_statements;
+        let mut count = 0;
+        let mut done = false;
+        Box::new(std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let item = statements.next()?;
+            let tripped = {
+                let mut tripwire = tripwire.lock().unwrap();
+                tripwire.error.take().or_else(|| tripwire.check_deadline())
+            };
+            let error = match (tripped, &item) {
+                (Some(error), _) => error,
+                (None, Ok(_)) => {
+                    count += 1;
+                    match max_statements {
+                        Some(max) if count > max => ConfigError::TooManyStatements { max },
+                        _ => return Some(item),
+                    }
+                }
+                (None, Err(_)) => return Some(item),
+            };
+            done = true;
+            Some(Err(DynError::new(error)))
+        }))
+    }
+}
+
+/// The state shared by a [`GuardedRead`] and the statements parsed from it.
+#[derive(Debug, Default)]
+pub(crate) struct Tripwire {
+    error: Option<ConfigError>,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl Tripwire {
+    fn check_deadline(&self) -> Option<ConfigError> {
+        match self.deadline {
+            Some((deadline, timeout)) if Instant::now() > deadline => {
+                Some(ConfigError::Timeout(timeout))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A reader enforcing the limits of a [`ParserConfig`] on the data it reads.
+///
+/// When a limit is exceeded, the reader fails with an I/O error,
+/// and the corresponding [`ConfigError`] is stored in its tripwire.
+pub(crate) struct GuardedRead<R> {
+    inner: R,
+    max_input_size: Option<u64>,
+    timeout: Option<Duration>,
+    scanner: Option<Scanner>,
+    /// The number of bytes at the start of the buffer of `inner` that have already been checked.
+    scanned: usize,
+    total: u64,
+    tripwire: Arc<Mutex<Tripwire>>,
+}
+
+impl<R> GuardedRead<R> {
+    /// The tripwire of this reader, to be passed to
+    /// [`ParserConfig::guard_statements`](struct.ParserConfig.html#method.guard_statements).
+    pub(crate) fn tripwire(&self) -> Arc<Mutex<Tripwire>> {
+        self.tripwire.clone()
+    }
+}
+
+impl<R: BufRead> BufRead for GuardedRead<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let GuardedRead {
+            inner,
+            max_input_size,
+            timeout,
+            scanner,
+            scanned,
+            total,
+            tripwire,
+        } = self;
+        let mut tripwire = tripwire.lock().unwrap();
+        if tripwire.error.is_some() {
+            return Err(limit_exceeded());
+        }
+        match (tripwire.deadline, timeout) {
+            (None, Some(timeout)) => {
+                tripwire.deadline = Some((Instant::now() + *timeout, *timeout))
+            }
+            _ => tripwire.error = tripwire.check_deadline(),
+        }
+        let data = inner.fill_buf()?;
+        if *scanned < data.len() {
+            let new = &data[*scanned..];
+            *scanned = data.len();
+            *total += new.len() as u64;
+            match max_input_size {
+                Some(max) if *total > *max => {
+                    tripwire.error = Some(ConfigError::InputTooLarge { max: *max })
+                }
+                _ => {
+                    if let Some(scanner) = scanner {
+                        tripwire.error = scanner.scan(new).err();
+                    }
+                }
+            }
+        }
+        if tripwire.error.is_some() {
+            return Err(limit_exceeded());
+        }
+        Ok(data)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.scanned = self.scanned.saturating_sub(amt);
+    }
+}
+
+impl<R: BufRead> Read for GuardedRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill_buf()?;
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+fn limit_exceeded() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "parser limit exceeded")
+}
+
+/// An escape sequence (such as `\u0041`) is at most 6 times longer than the character it encodes,
+/// so a term spelled with more than `6*max` bytes is necessarily longer than `max` bytes.
+const MAX_ESCAPE_RATIO: usize = 6;
+
+/// The lexical state of a [`Scanner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Lex {
+    Normal,
+    /// After a backslash outside strings and IRIs (in a prefixed name).
+    Escape,
+    Comment,
+    /// After `<`: either an IRI or a quoted triple.
+    Lt,
+    /// After `>`: possibly the end of a quoted triple.
+    Gt,
+    Iri,
+    /// After `n` opening quotes `q`.
+    Quote {
+        q: u8,
+        n: u8,
+    },
+    Str {
+        q: u8,
+        escape: bool,
+    },
+    /// Inside a long string, after `run` closing quotes `q`.
+    LongStr {
+        q: u8,
+        run: u8,
+        escape: bool,
+    },
+}
+
+/// A minimal lexer for the Turtle-based syntaxes,
+/// tracking the nesting depth and the length of IRIs and strings,
+/// and spotting quoted triples.
+#[derive(Debug)]
+struct Scanner {
+    state: Lex,
+    depth: usize,
+    token: usize,
+    max_nesting: Option<usize>,
+    /// The maximum term length, and the corresponding maximum token length.
+    max_token: Option<(usize, usize)>,
+    rdf_version: RdfVersion,
+}
+
+impl Scanner {
+    fn new(
+        max_nesting: Option<usize>,
+        max_term_length: Option<usize>,
+        rdf_version: RdfVersion,
+    ) -> Self {
+        Scanner {
+            state: Lex::Normal,
+            depth: 0,
+            token: 0,
+            max_nesting,
+            max_token: max_term_length.map(|max| (max, max.saturating_mul(MAX_ESCAPE_RATIO))),
+            rdf_version,
+        }
+    }
+
+    fn scan(&mut self, data: &[u8]) -> Result<(), ConfigError> {
+        for b in data {
+            while !self.step(*b)? {}
+        }
+        Ok(())
+    }
+
+    /// Process byte `b`, and return whether it was consumed.
+    fn step(&mut self, b: u8) -> Result<bool, ConfigError> {
+        self.state = match (self.state, b) {
+            (Lex::Normal, b'#') => Lex::Comment,
+            (Lex::Normal, b'\\') => Lex::Escape,
+            (Lex::Normal, b'"') | (Lex::Normal, b'\'') => Lex::Quote { q: b, n: 1 },
+            (Lex::Normal, b'<') => Lex::Lt,
+            (Lex::Normal, b'>') => Lex::Gt,
+            (Lex::Normal, b'[') | (Lex::Normal, b'(') | (Lex::Normal, b'{') => {
+                self.nest()?;
+                Lex::Normal
+            }
+            (Lex::Normal, b']') | (Lex::Normal, b')') | (Lex::Normal, b'}') => {
+                self.depth = self.depth.saturating_sub(1);
+                Lex::Normal
+            }
+            (Lex::Normal, _) | (Lex::Escape, _) => Lex::Normal,
+            (Lex::Comment, b'\n') | (Lex::Comment, b'\r') => Lex::Normal,
+            (Lex::Comment, _) => Lex::Comment,
+            (Lex::Lt, b'<') => {
+                if !self.rdf_version.allows_quoted_triples() {
+                    return Err(ConfigError::Rdf12("<<".to_string()));
+                }
+                self.nest()?;
+                Lex::Normal
+            }
+            (Lex::Lt, _) => {
+                self.token = 0;
+                self.state = Lex::Iri;
+                return Ok(false);
+            }
+            (Lex::Gt, b'>') => {
+                self.depth = self.depth.saturating_sub(1);
+                Lex::Normal
+            }
+            (Lex::Gt, _) => {
+                self.state = Lex::Normal;
+                return Ok(false);
+            }
+            (Lex::Iri, b'>') => Lex::Normal,
+            (Lex::Iri, _) => {
+                self.grow()?;
+                Lex::Iri
+            }
+            (Lex::Quote { q, n: 1 }, b) if b == q => Lex::Quote { q, n: 2 },
+            (Lex::Quote { q, .. }, b) if b == q => {
+                self.token = 0;
+                Lex::LongStr {
+                    q,
+                    run: 0,
+                    escape: false,
+                }
+            }
+            (Lex::Quote { n: 2, .. }, _) => {
+                self.state = Lex::Normal;
+                return Ok(false);
+            }
+            (Lex::Quote { q, .. }, _) => {
+                self.token = 0;
+                self.state = Lex::Str { q, escape: false };
+                return Ok(false);
+            }
+            (Lex::Str { q, escape: false }, b) if b == q => Lex::Normal,
+            (Lex::Str { q, escape }, b) => {
+                self.grow()?;
+                Lex::Str {
+                    q,
+                    escape: !escape && b == b'\\',
+                }
+            }
+            (Lex::LongStr { q, run, escape }, b) => {
+                self.grow()?;
+                match b {
+                    _ if escape => Lex::LongStr {
+                        q,
+                        run: 0,
+                        escape: false,
+                    },
+                    b'\\' => Lex::LongStr {
+                        q,
+                        run: 0,
+                        escape: true,
+                    },
+                    _ if b == q && run == 2 => Lex::Normal,
+                    _ if b == q => Lex::LongStr {
+                        q,
+                        run: run + 1,
+                        escape: false,
+                    },
+                    _ => Lex::LongStr {
+                        q,
+                        run: 0,
+                        escape: false,
+                    },
+                }
+            }
+        };
+        Ok(true)
+    }
+
+    fn nest(&mut self) -> Result<(), ConfigError> {
+        self.depth += 1;
+        match self.max_nesting {
+            Some(max) if self.depth > max => Err(ConfigError::TooDeeplyNested { max }),
+            _ => Ok(()),
+        }
+    }
+
+    fn grow(&mut self) -> Result<(), ConfigError> {
+        self.token += 1;
+        match self.max_token {
+            Some((max, max_token)) if self.token > max_token => Err(ConfigError::TermTooLong {
+                length: self.token,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Check that `tag` is a well-formed [BCP47] language tag.
+///
+/// [BCP47]: https://tools.ietf.org/html/bcp47
+pub(crate) fn is_valid_language_tag(tag: &str) -> bool {
+    let valid =
+        |s: &str| (1..=8).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphanumeric());
+    let mut subtags = tag.split('-').peekable();
+    let first = subtags.next().unwrap();
+    if first.eq_ignore_ascii_case("x") || first.eq_ignore_ascii_case("i") {
+        // private use or grandfathered tag
+        return subtags.peek().is_some() && subtags.all(valid);
+    }
+    if !first.bytes().all(|b| b.is_ascii_alphabetic()) || !matches!(first.len(), 2..=3 | 5..=8) {
+        return false;
+    }
+    while let Some(subtag) = subtags.next() {
+        if !valid(subtag) {
+            return false;
+        }
+        if subtag.len() == 1 {
+            if subtag.eq_ignore_ascii_case("x") {
+                // the remaining subtags are private use
+                return subtags.peek().is_some() && subtags.all(valid);
+            }
+            // an extension must be followed by at least one (non-singleton) subtag
+            if !matches!(subtags.peek(), Some(next) if next.len() > 1) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("en", true)]
+    #[test_case("en-US", true)]
+    #[test_case("zh-Hant-TW", true)]
+    #[test_case("de-CH-1901", true)]
+    #[test_case("en-a-bbb-x-a-ccc", true)]
+    #[test_case("x-whatever", true)]
+    #[test_case("e", false)]
+    #[test_case("english", true; "7 letters is reserved but well-formed")]
+    #[test_case("englishes", false)]
+    #[test_case("en-", false)]
+    #[test_case("en-a", false)]
+    #[test_case("en-US-toolongsubtag", false)]
+    fn language_tag(tag: &str, expected: bool) {
+        assert_eq!(is_valid_language_tag(tag), expected);
+    }
+
+    #[test]
+    fn relative_iris() {
+        let iri = BoxTerm::new_iri_unchecked(format!("{}foo#bar", NO_BASE));
+        let lax = ParserConfig::default().check_term(iri.clone()).unwrap();
+        assert_eq!(&lax.value()[..], "foo#bar");
+        assert!(ParserConfig::strict().check_term(iri).is_err());
+    }
+
+    #[test]
+    fn literal_length() {
+        let lit = BoxTerm::new_literal_lang_unchecked("hello", "en");
+        let mut config = ParserConfig::default();
+        config.set_max_literal_length(Some(5));
+        assert!(config.check_term(lit.clone()).is_ok());
+        config.set_max_literal_length(Some(4));
+        assert!(matches!(
+            config.check_term(lit),
+            Err(ConfigError::LiteralTooLong { length: 5, max: 4 })
+        ));
+    }
+
+    #[test]
+    fn generalized() {
+        let quad = (
+            [
+                BoxTerm::new_literal_dt_unchecked("a", sophia_api::ns::xsd::string),
+                BoxTerm::new_bnode_unchecked("b"),
+                BoxTerm::new_iri_unchecked("tag:c"),
+            ],
+            None,
+        );
+        assert!(ParserConfig::default().check_quad(quad.clone()).is_err());
+        let mut config = ParserConfig::strict();
+        config.set_generalized(true);
+        assert!(config.check_quad(quad.clone()).is_ok());
+        assert!(config.check_triple(quad.0).is_ok());
+    }
+
+    #[test]
+    fn rdf_version() -> Result<(), Box<dyn std::error::Error>> {
+        use sophia_api::term::BaseDirection;
+
+        let lit = BoxTerm::new_literal_dir_lang("hello", "en", BaseDirection::Rtl)?;
+        let triple = [
+            BoxTerm::new_iri_unchecked("tag:a"),
+            BoxTerm::new_iri_unchecked("tag:b"),
+            lit.clone(),
+        ];
+        assert!(ParserConfig::strict().check_triple(triple.clone()).is_ok());
+        let mut config = ParserConfig::default();
+        config.set_rdf_version(RdfVersion::Rdf11);
+        assert!(config
+            .check_term(BoxTerm::new_literal_lang_unchecked("hello", "en"))
+            .is_ok());
+        match config.check_triple(triple) {
+            Err(ConfigError::Rdf12(msg)) => assert!(msg.contains("--rtl")),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        use crate::syntax::Syntax;
+        let ttl = "<< <tag:a> <tag:b> <tag:c> >> <tag:p> \"<<\", <tag:o> . # <<";
+        for syntax in &[Syntax::Turtle, Syntax::TriG] {
+            assert!(matches!(
+                limit_error(*syntax, ttl, &config),
+                ConfigError::Rdf12(token) if token == "<<"
+            ));
+        }
+        let ttl = "<tag:a> <tag:p> \"<<\", <tag:o> . # <<";
+        assert_eq!(
+            Syntax::TriG
+                .parse_quads_with(ttl.as_bytes(), &config)
+                .count(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bnode_ids() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::syntax::Syntax;
+        use sophia_term::blank_node::generator::Sequential;
+
+        let mut config = ParserConfig::default();
+        config.set_bnode_ids(Some(SharedBnodeIds::new(Sequential::new("x"))));
+        let data = "_:foo <tag:p> _:bar, [], _:foo.";
+        let triples: Vec<_> = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .collect::<Result<_, _>>()?;
+        let objects: Vec<_> = triples.iter().map(|t| t.o().value().to_string()).collect();
+        assert_eq!(&triples[0].s().value()[..], "x0");
+        assert_eq!(objects, vec!["x1", "x2", "x0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn bnode_scopes() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::graph::{inmem::FastGraph, Graph, MutableGraph};
+        use crate::syntax::Syntax;
+        use sophia_term::blank_node::generator::Sequential;
+
+        let mut config = ParserConfig::default();
+        config.set_bnode_ids(Some(SharedBnodeIds::new(Sequential::default())));
+        let mut g = FastGraph::new();
+        for doc in &["_:b1 <tag:p> 1.", "_:b1 <tag:p> 2."] {
+            config.enter_bnode_scope();
+            g.insert_all(Syntax::Turtle.parse_triples_with(doc.as_bytes(), &config))?;
+            config.leave_bnode_scope();
+        }
+        assert_eq!(g.subjects()?.len(), 2);
+        Ok(())
+    }
+
+    fn limit_error(
+        syntax: crate::syntax::Syntax,
+        data: &str,
+        config: &ParserConfig,
+    ) -> ConfigError {
+        let err = syntax
+            .parse_quads_with(data.as_bytes(), config)
+            .find_map(Result::err)
+            .expect("an error");
+        *err.0.downcast::<ConfigError>().expect("a ConfigError")
+    }
+
+    #[test]
+    fn undefined_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::syntax::Syntax;
+
+        let data = "@prefix : <tag:>.\n:a ex:p <b>.";
+        let mut config = ParserConfig::default();
+        assert!(Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .any(|res| res.is_err()));
+        config.set_undefined_prefixes(true);
+        let triples: Vec<_> = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(triples.len(), 1);
+        assert_eq!(triples[0][0].value(), "tag:a");
+        assert_eq!(triples[0][1].value(), "ex:p");
+        assert_eq!(triples[0][2].value(), "b");
+        let quads: Vec<_> = Syntax::Turtle
+            .parse_quads_with(data.as_bytes(), &config)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(quads[0].1, None);
+
+        // the other options are still enforced
+        config.set_relative_iris(false);
+        let err = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(
+            err.0.downcast_ref::<ConfigError>(),
+            Some(ConfigError::RelativeIri(iri)) if iri == "b"
+        ));
+        config.set_relative_iris(true).set_max_input_size(Some(10));
+        assert!(matches!(
+            limit_error(Syntax::Turtle, data, &config),
+            ConfigError::InputTooLarge { max: 10 }
+        ));
+
+        // syntax errors are still reported
+        config.set_max_input_size(None);
+        assert!(Syntax::Turtle
+            .parse_triples_with("ex:a ex:b".as_bytes(), &config)
+            .any(|res| res.is_err()));
+        // undefined prefixes are not supported in TriG
+        assert!(Syntax::TriG
+            .parse_quads_with(data.as_bytes(), &config)
+            .any(|res| res.is_err()));
+        Ok(())
+    }
+
+    #[test]
+    fn concrete_parsers() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::{trig::TriGParser, turtle::TurtleParser};
+
+        let ttl = "<a> <tag:p> <b>.";
+        let strict = ParserConfig::strict();
+        assert!(TurtleParser::default()
+            .parse_with_config(ttl.as_bytes(), &strict)
+            .any(|res| res.is_err()));
+        let parser = TurtleParser {
+            base: Some("http://example.org/".into()),
+        };
+        let triples: Vec<_> = parser
+            .parse_with_config(ttl.as_bytes(), &strict)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(triples[0][0].value(), "http://example.org/a");
+        // the base IRI of the configuration takes precedence
+        let mut config = ParserConfig::strict();
+        config.set_base(Some("http://example.com/".into()));
+        let triples: Vec<_> = parser
+            .parse_with_config(ttl.as_bytes(), &config)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(triples[0][2].value(), "http://example.com/b");
+
+        let trig = "<tag:g> { <a> <tag:p> <tag:b> }";
+        assert!(TriGParser::default()
+            .parse_with_config(trig.as_bytes(), &strict)
+            .any(|res| res.is_err()));
+        let quads: Vec<_> = TriGParser::default()
+            .parse_with_config(trig.as_bytes(), &config)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(quads[0].0[0].value(), "http://example.com/a");
+        Ok(())
+    }
+
+    #[test]
+    fn scanner() {
+        let mut scanner = Scanner::new(Some(2), None, RdfVersion::Rdf12);
+        let ok = r#"
+            # a comment with [[[ and <<<
+            [ <tag:p> ( "[[[" '((' """ ""[[""" '''((''' ex:a\#b ) ] .
+            << <tag:s> <tag:p> [] >> <tag:p> <tag:o> .
+        "#;
+        assert!(scanner.scan(ok.as_bytes()).is_ok());
+        assert_eq!(scanner.depth, 0);
+        assert_eq!(scanner.state, Lex::Normal);
+        assert!(matches!(
+            scanner.scan(b"<< << << <tag:s>"),
+            Err(ConfigError::TooDeeplyNested { max: 2 })
+        ));
+
+        let mut scanner = Scanner::new(None, Some(1), RdfVersion::Rdf12);
+        assert!(scanner.scan(br#"<abcdef> "\u0041" """ab""" "#).is_ok());
+        assert!(matches!(
+            scanner.scan(b"<abcdefg>"),
+            Err(ConfigError::TermTooLong { length: 7, max: 1 })
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        use crate::syntax::Syntax;
+
+        let data = "<tag:a> <tag:p> <tag:b>.\n<tag:a> <tag:p> <tag:c>.\n<tag:a> <tag:p> <tag:d>.\n";
+        let mut config = ParserConfig::default();
+        config.set_max_statements(Some(2));
+        let results: Vec<_> = Syntax::NTriples
+            .parse_triples_with(data.as_bytes(), &config)
+            .collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_ok());
+        assert!(matches!(
+            limit_error(Syntax::NQuads, data, &config),
+            ConfigError::TooManyStatements { max: 2 }
+        ));
+
+        let mut config = ParserConfig::default();
+        config.set_max_input_size(Some(30));
+        // parsing is aborted after the limit is exceeded
+        let results: Vec<_> = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .collect();
+        assert!(results.last().unwrap().is_err());
+        assert!(matches!(
+            limit_error(Syntax::Turtle, data, &config),
+            ConfigError::InputTooLarge { max: 30 }
+        ));
+        config.set_max_input_size(Some(data.len() as u64));
+        assert_eq!(
+            Syntax::Turtle
+                .parse_quads_with(data.as_bytes(), &config)
+                .count(),
+            3
+        );
+
+        let mut config = ParserConfig::default();
+        config.set_max_term_length(Some(4));
+        assert!(matches!(
+            limit_error(Syntax::TriG, data, &config),
+            ConfigError::TermTooLong { length: 5, max: 4 }
+        ));
+        let long = format!("<tag:a> <tag:p> \"{}\".", "x".repeat(1000));
+        assert!(matches!(
+            limit_error(Syntax::Turtle, &long, &config),
+            ConfigError::TermTooLong { length: 25, max: 4 }
+        ));
+
+        let mut config = ParserConfig::default();
+        config.set_max_nesting(Some(3));
+        let nested = "<tag:a> <tag:p> [ <tag:p> [ <tag:p> [ <tag:p> <tag:b> ] ] ].";
+        assert_eq!(
+            Syntax::Turtle
+                .parse_quads_with(nested.as_bytes(), &config)
+                .count(),
+            4
+        );
+        config.set_max_nesting(Some(2));
+        assert!(matches!(
+            limit_error(Syntax::Turtle, nested, &config),
+            ConfigError::TooDeeplyNested { max: 2 }
+        ));
+    }
+
+    #[test]
+    fn timeout() {
+        use crate::syntax::Syntax;
+
+        let data = "<tag:a> <tag:p> <tag:b>.\n<tag:a> <tag:p> <tag:c>.\n";
+        let mut config = ParserConfig::default();
+        config.set_timeout(Some(Duration::from_millis(1)));
+        let mut triples = Syntax::NTriples.parse_triples_with(data.as_bytes(), &config);
+        assert!(triples.next().unwrap().is_ok());
+        std::thread::sleep(Duration::from_millis(5));
+        let err = triples.next().unwrap().unwrap_err();
+        assert!(matches!(
+            err.0.downcast_ref::<ConfigError>(),
+            Some(ConfigError::Timeout(_))
+        ));
+        assert!(triples.next().is_none());
+    }
+
+    #[cfg(feature = "nfc")]
+    #[test]
+    fn nfc() {
+        let lit = BoxTerm::new_literal_lang_unchecked("e\u{301}", "fr");
+        let iri = BoxTerm::new_iri_unchecked("tag:e\u{301}");
+        let mut config = ParserConfig::default();
+        assert_eq!(config.check_term(lit.clone()).unwrap(), lit);
+        assert!(config.check_term(iri.clone()).is_ok());
+        config.set_nfc(true);
+        assert_eq!(&config.check_term(lit).unwrap().value()[..], "\u{e9}");
+        config.set_nfc_iris(true);
+        assert!(matches!(
+            config.check_term(iri),
+            Err(ConfigError::NonNfcIri(_))
+        ));
+        let lit =
+            BoxTerm::new_literal_dt_unchecked("1", BoxTerm::new_iri_unchecked("tag:e\u{301}"));
+        assert!(config.check_term(lit).is_err());
+    }
+}
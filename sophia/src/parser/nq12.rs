@@ -0,0 +1,944 @@
+//! Native parser for [N-Quads 1.2].
+//!
+//! Unlike the [`nq`](../nq/index.html) parser (based on RIO),
+//! this parser supports the [directional language-tagged strings] introduced by RDF 1.2
+//! (e.g. `"hello"@en--ltr`).
+//!
+//! When [`star`] is enabled, the [RDF-star] quoted triples (`<< s p o >>`) are also accepted.
+//! As quoted triples can not be represented as terms yet,
+//! each of them is replaced by a blank node, described with the [RDF reification] vocabulary
+//! (`rdf:type rdf:Statement`, `rdf:subject`, `rdf:predicate` and `rdf:object`).
+//! The same quoted triple is always replaced by the same blank node within a source,
+//! and its description is produced, in each graph where it appears,
+//! before the first statement of that graph containing it.
+//! These blank nodes are labelled `quoted0`, `quoted1`, etc.;
+//! to prevent any collision, the blank nodes of the document whose label starts with `quoted`
+//! are relabelled by inserting an underscore (e.g. `_:quoted0` becomes `_:quoted_0`).
+//! Otherwise (the default), or when [`version`] is [`RdfVersion::Rdf11`],
+//! the parser rejects quoted triples.
+//!
+//! The [Turtle parser](../turtle/index.html) and the serializers
+//! for [N-Triples](../../serializer/nt/index.html), [N-Quads](../../serializer/nq/index.html)
+//! and [Turtle](../../serializer/turtle/index.html) support the same representation of quoted triples.
+//!
+//! # RDF 1.1 mode
+//!
+//! Setting [`version`] to [`RdfVersion::Rdf11`] restricts the parser to the N-Quads 1.1 grammar:
+//! directional language-tagged strings are then rejected with an explicit error,
+//! which is useful to check that some data can be consumed by RDF 1.1 implementations.
+//!
+//! ```
+//! use sophia::parser::nq12::NQuads12Parser;
+//! use sophia::parser::QuadParser;
+//! use sophia::quad::stream::QuadSource;
+//! use sophia::syntax::RdfVersion;
+//!
+//! let data = r#"<tag:a> <tag:b> "hello"@en--ltr <tag:g>."#;
+//! let parser = NQuads12Parser {
+//!     version: RdfVersion::Rdf11,
+//!     ..NQuads12Parser::default()
+//! };
+//! let err = parser.parse_str(data).for_each_quad(|_| ()).unwrap_err();
+//! assert!(err.to_string().contains("only valid in RDF 1.2"));
+//! ```
+//!
+//! # Zero-copy parsing
+//!
+//! For applications processing statements on the fly and discarding them,
+//! [`parse_borrowed`] parses a string already in memory without allocating:
+//! the terms of the produced statements borrow directly from the input
+//! (as [`MownTerm`]s), except for the few ones containing escape sequences.
+//! This mode does not support quoted triples.
+//!
+//! [`star`]: struct.NQuads12Parser.html#structfield.star
+//! [`version`]: struct.NQuads12Parser.html#structfield.version
+//! [`RdfVersion::Rdf11`]: ../../syntax/enum.RdfVersion.html#variant.Rdf11
+//! [`parse_borrowed`]: struct.NQuads12Parser.html#method.parse_borrowed
+//! [`MownTerm`]: ../../term/type.MownTerm.html
+//! [RDF-star]: https://www.w3.org/2021/12/rdf-star.html
+//! [RDF reification]: https://www.w3.org/TR/rdf11-mt/#reification
+//! [N-Quads 1.2]: https://www.w3.org/TR/rdf12-n-quads/
+//! [directional language-tagged strings]: https://www.w3.org/TR/rdf12-concepts/#section-text-direction
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::io::BufRead;
+
+use mownstr::MownStr;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::{ParseError, QuadParser};
+use sophia_api::quad::stream::{QuadSource, StreamResult};
+use sophia_api::quad::streaming_mode::{ByValue as QuadByValue, StreamedQuad};
+use sophia_api::term::{BaseDirection, CopyTerm};
+use sophia_api::triple::stream::{SourceError, StreamError, TripleSource};
+use sophia_api::triple::streaming_mode::{ByValue as TripleByValue, StreamedTriple};
+use sophia_term::iri::{error::InvalidIri, is_absolute_iri_ref};
+use sophia_term::Term;
+use sophia_term::{BoxTerm, MownTerm};
+
+use crate::serializer::nt::write_term;
+use crate::syntax::dynamic::{BoxQuad, BoxTriple};
+use crate::syntax::RdfVersion;
+
+/// N-Quads 1.2 parser.
+#[derive(Clone, Debug, Default)]
+pub struct NQuads12Parser {
+    /// Whether RDF-star quoted triples are accepted (see [module documentation](index.html)).
+    pub star: bool,
+    /// The version of RDF that parsed data must comply with
+    /// (see [module documentation](index.html#rdf-11-mode)).
+    pub version: RdfVersion,
+}
+
+impl<B: BufRead> QuadParser<B> for NQuads12Parser {
+    type Source = LineSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        LineSource::new(data, true, self.star, self.version)
+    }
+}
+
+impl NQuads12Parser {
+    /// Parse `txt` without copying it (see [module documentation](index.html)).
+    ///
+    /// Quoted triples are rejected, regardless of [`star`](#structfield.star).
+    pub fn parse_borrowed<'a>(&self, txt: &'a str) -> BorrowingSource<'a> {
+        BorrowingSource::new(txt, true, self.version)
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(NQuads12Parser, QuadParser);
+
+/// A quad whose terms borrow from the parsed text, whenever possible.
+pub type MownQuad<'a> = ([MownTerm<'a>; 3], Option<MownTerm<'a>>);
+
+/// TripleSource / QuadSource for line-based syntaxes (N-Triples 1.2 and N-Quads 1.2).
+pub struct LineSource<B> {
+    read: B,
+    quads: bool,
+    version: RdfVersion,
+    line: String,
+    line_number: usize,
+    offset: usize,
+    done: bool,
+    reifier: Option<Reifier>,
+    pending: VecDeque<BoxQuad>,
+}
+
+impl<B: BufRead> LineSource<B> {
+    pub(crate) fn new(read: B, quads: bool, star: bool, version: RdfVersion) -> Self {
+        LineSource {
+            read,
+            quads,
+            version,
+            line: String::new(),
+            line_number: 0,
+            offset: 0,
+            done: false,
+            reifier: if star && version.allows_quoted_triples() {
+                Some(Reifier::default())
+            } else {
+                None
+            },
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Parse the next line containing a statement, if any.
+    fn next_quad(&mut self) -> Result<Option<BoxQuad>, Box<ParseError>> {
+        if let Some(quad) = self.pending.pop_front() {
+            return Ok(Some(quad));
+        }
+        while !self.done {
+            self.offset += self.line.len();
+            self.line.clear();
+            match self.read.read_line(&mut self.line) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    self.line_number += 1;
+                    let mut parser = LineParser {
+                        txt: self.line.trim_end_matches(['\n', '\r']),
+                        pos: 0,
+                        reifier: self.reifier.as_mut(),
+                        version: self.version,
+                    };
+                    match parser.parse_statement(self.quads) {
+                        Ok(None) => continue,
+                        Ok(Some(([s, p, o], g))) => {
+                            let quad = (
+                                [s.map_into(), p.map_into(), o.map_into()],
+                                g.map(Term::map_into),
+                            );
+                            if let Some(reifier) = &mut self.reifier {
+                                for triple in reifier.descriptions(&quad.1) {
+                                    self.pending.push_back((triple, quad.1.clone()));
+                                }
+                            }
+                            self.pending.push_back(quad);
+                            return Ok(self.pending.pop_front());
+                        }
+                        Err(error) => {
+                            self.done = true;
+                            return Err(parser.locate(error, self.line_number, self.offset));
+                        }
+                    }
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Err(Box::new(
+                        ParseError::new(error.to_string()).with_source(error),
+                    ));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<B: BufRead> QuadSource for LineSource<B> {
+    type Error = ParseError;
+    type Quad = QuadByValue<BoxQuad>;
+
+    fn try_for_some_quad<F, E>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, E>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.next_quad().map_err(|e| SourceError(*e))? {
+            None => Ok(false),
+            Some(quad) => {
+                f(StreamedQuad::by_value(quad)).map_err(StreamError::SinkError)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<B: BufRead> TripleSource for LineSource<B> {
+    type Error = ParseError;
+    type Triple = TripleByValue<BoxTriple>;
+
+    fn try_for_some_triple<F, E>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, E>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.next_quad().map_err(|e| SourceError(*e))? {
+            None => Ok(false),
+            Some((triple, _)) => {
+                f(StreamedTriple::by_value(triple)).map_err(StreamError::SinkError)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// TripleSource / QuadSource for line-based syntaxes, borrowing its terms from the parsed text.
+///
+/// See [`NQuads12Parser::parse_borrowed`](struct.NQuads12Parser.html#method.parse_borrowed)
+/// and [`NTriples12Parser::parse_borrowed`](../nt12/struct.NTriples12Parser.html#method.parse_borrowed).
+pub struct BorrowingSource<'a> {
+    txt: &'a str,
+    quads: bool,
+    version: RdfVersion,
+    line_number: usize,
+    offset: usize,
+}
+
+impl<'a> BorrowingSource<'a> {
+    pub(crate) fn new(txt: &'a str, quads: bool, version: RdfVersion) -> Self {
+        BorrowingSource {
+            txt,
+            quads,
+            version,
+            line_number: 0,
+            offset: 0,
+        }
+    }
+
+    /// Parse the next line containing a statement, if any.
+    fn next_quad(&mut self) -> Result<Option<MownQuad<'a>>, Box<ParseError>> {
+        let txt = self.txt;
+        while self.offset < txt.len() {
+            let rest = &txt[self.offset..];
+            let line = match rest.find('\n') {
+                Some(i) => &rest[..=i],
+                None => rest,
+            };
+            let offset = self.offset;
+            self.offset += line.len();
+            self.line_number += 1;
+            let mut parser = LineParser {
+                txt: line.trim_end_matches(['\n', '\r']),
+                pos: 0,
+                reifier: None,
+                version: self.version,
+            };
+            match parser.parse_statement(self.quads) {
+                Ok(None) => continue,
+                Ok(quad) => return Ok(quad),
+                Err(error) => {
+                    self.offset = txt.len();
+                    return Err(parser.locate(error, self.line_number, offset));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> QuadSource for BorrowingSource<'a> {
+    type Error = ParseError;
+    type Quad = QuadByValue<MownQuad<'a>>;
+
+    fn try_for_some_quad<F, E>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, E>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.next_quad().map_err(|e| SourceError(*e))? {
+            None => Ok(false),
+            Some(quad) => {
+                f(StreamedQuad::by_value(quad)).map_err(StreamError::SinkError)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<'a> TripleSource for BorrowingSource<'a> {
+    type Error = ParseError;
+    type Triple = TripleByValue<[MownTerm<'a>; 3]>;
+
+    fn try_for_some_triple<F, E>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, E>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), E>,
+        E: Error,
+    {
+        match self.next_quad().map_err(|e| SourceError(*e))? {
+            None => Ok(false),
+            Some((triple, _)) => {
+                f(StreamedTriple::by_value(triple)).map_err(StreamError::SinkError)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Parse a single term (IRI, blank node or literal) in N-Triples syntax.
+///
+/// The whole of `txt` must be consumed.
+pub(crate) fn parse_term(txt: &str) -> Result<BoxTerm, Box<ParseError>> {
+    let mut parser = LineParser {
+        txt,
+        pos: 0,
+        reifier: None,
+        version: RdfVersion::Rdf12,
+    };
+    let term = match parser.peek() {
+        Some('<') => parser.parse_iri(),
+        Some('_') => parser.parse_bnode(),
+        Some('"') => parser.parse_literal(),
+        _ => Err(parser.unexpected(&["IRI", "blank node", "literal"])),
+    }
+    .map_err(|(_, err)| err)?;
+    if !parser.at_end() {
+        return Err(parser.unexpected(&["end of term"]).1);
+    }
+    Ok(term.map_into())
+}
+
+/// Parse a single N-Triples statement (without quoted triples).
+///
+/// The whole of `txt` must be consumed.
+pub(crate) fn parse_triple(txt: &str) -> Result<BoxTriple, Box<ParseError>> {
+    let mut parser = LineParser {
+        txt,
+        pos: 0,
+        reifier: None,
+        version: RdfVersion::Rdf12,
+    };
+    match parser.parse_statement(false) {
+        Ok(Some(([s, p, o], _))) => Ok([s.map_into(), p.map_into(), o.map_into()]),
+        Ok(None) => Err(parser.locate(parser.unexpected(&["triple"]), 1, 0)),
+        Err(err) => Err(parser.locate(err, 1, 0)),
+    }
+}
+
+/// An error, with its byte position in the line.
+type LineError = (usize, Box<ParseError>);
+
+/// The prefix of the labels of the blank nodes replacing quoted triples.
+const QUOTED_PREFIX: &str = "quoted";
+
+/// Replaces quoted triples by blank nodes, and keeps track of their descriptions.
+#[derive(Debug, Default)]
+struct Reifier {
+    /// The blank nodes replacing quoted triples, indexed by their N-Triples serialization.
+    nodes: HashMap<Vec<u8>, BoxTerm>,
+    /// The quoted triples encountered in the current statement, with the blank nodes replacing them.
+    quoted: Vec<(BoxTerm, BoxTriple)>,
+    /// The blank nodes already described, with the graph where they are described.
+    described: HashSet<(BoxTerm, Option<BoxTerm>)>,
+}
+
+impl Reifier {
+    fn reify(&mut self, [s, p, o]: [MownTerm; 3]) -> BoxTerm {
+        let triple: BoxTriple = [s.map_into(), p.map_into(), o.map_into()];
+        let mut key = vec![];
+        for t in triple.iter() {
+            // writing to a Vec can not fail
+            write_term(&mut key, t).unwrap();
+            key.push(b' ');
+        }
+        let len = self.nodes.len();
+        let node = self
+            .nodes
+            .entry(key)
+            .or_insert_with(|| BoxTerm::new_bnode_unchecked(format!("{}{}", QUOTED_PREFIX, len)))
+            .clone();
+        self.quoted.push((node.clone(), triple));
+        node
+    }
+
+    /// The descriptions of the quoted triples of the current statement
+    /// that are not described yet in graph `g`.
+    fn descriptions(&mut self, g: &Option<BoxTerm>) -> Vec<BoxTriple> {
+        let mut descriptions = vec![];
+        for (node, [s, p, o]) in self.quoted.drain(..) {
+            if !self.described.insert((node.clone(), g.clone())) {
+                continue;
+            }
+            descriptions.push([
+                node.clone(),
+                BoxTerm::copy(&rdf::type_),
+                BoxTerm::copy(&rdf::Statement),
+            ]);
+            descriptions.push([node.clone(), BoxTerm::copy(&rdf::subject), s]);
+            descriptions.push([node.clone(), BoxTerm::copy(&rdf::predicate), p]);
+            descriptions.push([node, BoxTerm::copy(&rdf::object), o]);
+        }
+        descriptions
+    }
+}
+
+/// A parser for a single line.
+///
+/// The terms it produces borrow from the line, unless they contain escape sequences.
+struct LineParser<'a, 'r> {
+    txt: &'a str,
+    pos: usize,
+    /// `None` if quoted triples are rejected
+    reifier: Option<&'r mut Reifier>,
+    version: RdfVersion,
+}
+
+impl<'a, 'r> LineParser<'a, 'r> {
+    fn parse_statement(&mut self, quads: bool) -> Result<Option<MownQuad<'a>>, LineError> {
+        self.skip_ws();
+        if self.at_end() {
+            return Ok(None);
+        }
+        let [s, p, o] = self.parse_triple()?;
+        self.skip_ws();
+        let g = match self.peek() {
+            Some('<') if quads => Some(self.parse_iri()?),
+            Some('_') if quads => Some(self.parse_bnode()?),
+            _ => None,
+        };
+        self.skip_ws();
+        if self.peek() != Some('.') {
+            return Err(self.unexpected(&["'.'"]));
+        }
+        self.pos += 1;
+        self.skip_ws();
+        if !self.at_end() {
+            return Err(self.unexpected(&["end of line"]));
+        }
+        Ok(Some(([s, p, o], g)))
+    }
+
+    /// Parse a subject, a predicate and an object, separated by whitespace.
+    fn parse_triple(&mut self) -> Result<[MownTerm<'a>; 3], LineError> {
+        let s = match self.peek() {
+            Some('<') if self.txt[self.pos..].starts_with("<<") => self.parse_quoted()?,
+            Some('<') => self.parse_iri()?,
+            Some('_') => self.parse_bnode()?,
+            _ => return Err(self.unexpected(&["IRI", "blank node"])),
+        };
+        self.skip_ws();
+        let p = match self.peek() {
+            Some('<') if self.txt[self.pos..].starts_with("<<") => {
+                return Err(self.unexpected(&["IRI"]))
+            }
+            Some('<') => self.parse_iri()?,
+            _ => return Err(self.unexpected(&["IRI"])),
+        };
+        self.skip_ws();
+        let o = match self.peek() {
+            Some('<') if self.txt[self.pos..].starts_with("<<") => self.parse_quoted()?,
+            Some('<') => self.parse_iri()?,
+            Some('_') => self.parse_bnode()?,
+            Some('"') => self.parse_literal()?,
+            _ => return Err(self.unexpected(&["IRI", "blank node", "literal"])),
+        };
+        Ok([s, p, o])
+    }
+
+    /// Parse a quoted triple, and return the blank node replacing it.
+    fn parse_quoted(&mut self) -> Result<MownTerm<'a>, LineError> {
+        if !self.version.allows_quoted_triples() {
+            let message = format!(
+                "quoted triples are only valid in RDF 1.2, not in {}",
+                self.version
+            );
+            return Err(self.error(&message, 2));
+        }
+        if self.reifier.is_none() {
+            return Err(self.error("quoted triples are not enabled", 2));
+        }
+        self.pos += 2; // '<<'
+        self.skip_ws();
+        let triple = self.parse_triple()?;
+        self.skip_ws();
+        if !self.txt[self.pos..].starts_with(">>") {
+            return Err(self.unexpected(&["'>>'"]));
+        }
+        self.pos += 2;
+        match &mut self.reifier {
+            Some(reifier) => Ok(reifier.reify(triple).map_into()),
+            None => unreachable!(),
+        }
+    }
+
+    fn parse_iri(&mut self) -> Result<MownTerm<'a>, LineError> {
+        let start = self.pos;
+        self.pos += 1; // '<'
+        let mut unescaped = None;
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected(&["'>'"])),
+                Some('>') => break,
+                Some('\\') => {
+                    let mut buf = self.copy_from(start + 1, unescaped.take());
+                    buf.push(self.parse_uchar()?);
+                    unescaped = Some(buf);
+                }
+                Some(c) if c <= ' ' || "<\"{}|^`".contains(c) => {
+                    return Err(self.unexpected(&["'>'"]));
+                }
+                Some(c) => {
+                    if let Some(buf) = &mut unescaped {
+                        buf.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        let iri = self.borrow_from(start + 1, unescaped);
+        self.pos += 1; // '>'
+        if !is_absolute_iri_ref(&iri) {
+            let err = ParseError::new(format!("invalid IRI <{}>", iri))
+                .with_token(&self.txt[start..self.pos])
+                .with_source(InvalidIri::new(iri.to_string()));
+            return Err((start, Box::new(err)));
+        }
+        Ok(MownTerm::new_iri_unchecked(iri))
+    }
+
+    fn parse_bnode(&mut self) -> Result<MownTerm<'a>, LineError> {
+        if !self.txt[self.pos..].starts_with("_:") {
+            return Err(self.unexpected(&["'_:'"]));
+        }
+        self.pos += 2;
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if is_pn_chars(c) && c != '-' || c.is_ascii_digit() => (),
+            _ => return Err(self.unexpected(&["blank node label"])),
+        }
+        while let Some(c) = self.peek() {
+            if is_pn_chars(c) || c == '.' {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        // the label can not end with '.'
+        while self.txt[..self.pos].ends_with('.') {
+            self.pos -= 1;
+        }
+        let label = &self.txt[start..self.pos];
+        if self.reifier.is_some() && label.starts_with(QUOTED_PREFIX) {
+            // avoid collisions with the blank nodes replacing quoted triples
+            let label = format!("{}_{}", QUOTED_PREFIX, &label[QUOTED_PREFIX.len()..]);
+            return Ok(MownTerm::new_bnode_unchecked(label));
+        }
+        Ok(MownTerm::new_bnode_unchecked(label))
+    }
+
+    fn parse_literal(&mut self) -> Result<MownTerm<'a>, LineError> {
+        self.pos += 1; // '"'
+        let start = self.pos;
+        let mut unescaped = None;
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected(&["'\"'"])),
+                Some('"') => break,
+                Some('\\') => {
+                    let mut buf = self.copy_from(start, unescaped.take());
+                    buf.push(self.parse_echar()?);
+                    unescaped = Some(buf);
+                }
+                Some(c) => {
+                    if let Some(buf) = &mut unescaped {
+                        buf.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        let txt = self.borrow_from(start, unescaped);
+        self.pos += 1; // '"'
+        if self.txt[self.pos..].starts_with("^^") {
+            self.pos += 2;
+            if self.peek() != Some('<') {
+                return Err(self.unexpected(&["IRI"]));
+            }
+            let dt = self.parse_iri()?;
+            Ok(MownTerm::new_literal_dt_unchecked(txt, dt))
+        } else if self.peek() == Some('@') {
+            self.parse_lang_dir(txt)
+        } else {
+            Ok(MownTerm::new_literal_dt_unchecked(txt, xsd::string))
+        }
+    }
+
+    fn parse_lang_dir(&mut self, txt: MownStr<'a>) -> Result<MownTerm<'a>, LineError> {
+        let start = self.pos;
+        self.pos += 1; // '@'
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let token = &self.txt[start..self.pos];
+        let (tag, dir) = match token[1..].split_once("--") {
+            None => (&token[1..], None),
+            Some(_) if self.version < RdfVersion::Rdf12 => {
+                let err = ParseError::new(format!(
+                    "directional language-tagged strings are only valid in RDF 1.2, not in {}",
+                    self.version
+                ))
+                .with_token(token);
+                return Err((start, Box::new(err)));
+            }
+            Some((tag, dir)) => match BaseDirection::parse(dir) {
+                Some(dir) => (tag, Some(dir)),
+                None => {
+                    let err = ParseError::new(format!("invalid base direction '{}'", dir))
+                        .with_token(token)
+                        .with_expected(vec!["'ltr'", "'rtl'"]);
+                    return Err((start, Box::new(err)));
+                }
+            },
+        };
+        let lit = match dir {
+            None => MownTerm::new_literal_lang(txt, tag),
+            Some(dir) => MownTerm::new_literal_dir_lang(txt, tag, dir),
+        };
+        lit.map_err(|error| {
+            let err = ParseError::new(format!("invalid language tag '{}': {}", tag, error))
+                .with_token(token);
+            (start, Box::new(err))
+        })
+    }
+
+    /// Parse an escape sequence in a string literal.
+    fn parse_echar(&mut self) -> Result<char, LineError> {
+        let c = match self.txt[self.pos..].chars().nth(1) {
+            Some('t') => '\t',
+            Some('b') => '\u{8}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('f') => '\u{C}',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('\\') => '\\',
+            _ => return self.parse_uchar(),
+        };
+        self.pos += 2;
+        Ok(c)
+    }
+
+    /// Parse a `\uXXXX` or `\UXXXXXXXX` escape sequence.
+    fn parse_uchar(&mut self) -> Result<char, LineError> {
+        let len = match self.txt[self.pos..].chars().nth(1) {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("invalid escape sequence", 2)),
+        };
+        let c = self
+            .txt
+            .get(self.pos + 2..self.pos + 2 + len)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32);
+        match c {
+            Some(c) => {
+                self.pos += 2 + len;
+                Ok(c)
+            }
+            None => Err(self.error("invalid escape sequence", 2 + len)),
+        }
+    }
+
+    /// The text read since `start`, copied in `unescaped` if not done yet
+    /// (when the first escape sequence is encountered).
+    fn copy_from(&self, start: usize, unescaped: Option<String>) -> String {
+        unescaped.unwrap_or_else(|| self.txt[start..self.pos].to_string())
+    }
+
+    /// The text read since `start`, borrowed from the line if it contained no escape sequence.
+    fn borrow_from(&self, start: usize, unescaped: Option<String>) -> MownStr<'a> {
+        match unescaped {
+            None => self.txt[start..self.pos].into(),
+            Some(buf) => buf.into(),
+        }
+    }
+
+    /// Add the position of `error` in the source.
+    fn locate(
+        &self,
+        (pos, error): LineError,
+        line_number: usize,
+        offset: usize,
+    ) -> Box<ParseError> {
+        Box::new(
+            (*error)
+                .with_line_column(line_number, pos + 1)
+                .with_offset(offset + pos)
+                .with_snippet(self.txt),
+        )
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.txt[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        if self.peek() == Some('#') {
+            self.pos = self.txt.len();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.txt[self.pos..].chars().next()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.txt.len()
+    }
+
+    fn unexpected(&self, expected: &[&str]) -> LineError {
+        let err = match self.peek() {
+            None => ParseError::new("unexpected end of line"),
+            Some(c) => ParseError::new(format!("unexpected character {:?}", c)).with_token(c),
+        };
+        (
+            self.pos,
+            Box::new(err.with_expected(expected.iter().copied())),
+        )
+    }
+
+    fn error(&self, message: &str, len: usize) -> LineError {
+        let end = (self.pos + len).min(self.txt.len());
+        let token = self.txt.get(self.pos..end).unwrap_or_default();
+        (
+            self.pos,
+            Box::new(ParseError::new(message).with_token(token)),
+        )
+    }
+}
+
+/// See <https://www.w3.org/TR/n-triples/#grammar-production-PN_CHARS>
+pub(crate) fn is_pn_chars(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(c, '_' | ':' | '-' | '\u{B7}')
+        || matches!(c, '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{37D}')
+        || matches!(c, '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{203F}'..='\u{2040}')
+        || matches!(c, '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}')
+        || matches!(c, '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+        || matches!(c, '\u{300}'..='\u{36F}')
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::quad::Quad;
+    use sophia_api::ns::rdf;
+    use sophia_api::term::TTerm;
+
+    #[test]
+    fn simple() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = r#"
+            # a comment
+            <tag:a> <tag:b> _:b1 .
+            _:b1 <tag:b> "hello\nworld" <tag:g>.
+            _:b1 <tag:b> "chat"@fr--ltr _:g. # another comment
+            _:b1 <tag:b> "42"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            <tag:é> <tag:b> "\U0001F600"@en .
+        "#;
+        let mut d = FastDataset::new();
+        let c = parse_str(nq).add_to_dataset(&mut d)?;
+        assert_eq!(c, 5);
+        let q = d
+            .quads()
+            .map(Result::unwrap)
+            .find(|q| q.o().base_direction().is_some())
+            .unwrap();
+        assert_eq!(&q.o().value()[..], "chat");
+        assert_eq!(q.o().language(), Some("fr"));
+        assert_eq!(q.o().base_direction(), Some(BaseDirection::Ltr));
+        assert_eq!(&q.o().datatype().unwrap(), &rdf::dirLangString);
+        Ok(())
+    }
+
+    #[test]
+    fn error() {
+        let nq = "<tag:a> <tag:b> <tag:c>.\n<tag:a> <tag:b> \"x\"@en--up .\n";
+        let err = parse_str(nq).for_each_quad(|_| ()).unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.column(), Some(20));
+        assert_eq!(err.offset(), Some(44));
+        assert_eq!(err.token(), Some("@en--up"));
+        assert_eq!(err.snippet(), Some("<tag:a> <tag:b> \"x\"@en--up ."));
+    }
+
+    #[test]
+    fn unexpected() {
+        let err = parse_str("<tag:a> \"b\" <tag:c>.")
+            .for_each_quad(|_| ())
+            .unwrap_err();
+        assert_eq!(err.column(), Some(9));
+        assert_eq!(err.token(), Some("\""));
+        assert_eq!(err.expected(), &["IRI".to_string()]);
+    }
+
+    #[test]
+    fn relative_iri() {
+        assert!(parse_str("<a> <tag:b> <tag:c>.")
+            .for_each_quad(|_| ())
+            .is_err());
+    }
+
+    #[test]
+    fn star() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = "<< <tag:a> <tag:b> << _:x <tag:c> \"x\" >> >> <tag:d> <tag:e> <tag:g>.\n\
+                  <tag:f> <tag:d> <<<tag:a> <tag:b> << _:x <tag:c> \"x\">>>>.\n";
+        let err = parse_str(nq).for_each_quad(|_| ()).unwrap_err();
+        assert_eq!(err.column(), Some(1));
+        assert_eq!(err.token(), Some("<<"));
+
+        let parser = NQuads12Parser {
+            star: true,
+            ..NQuads12Parser::default()
+        };
+        let d: FastDataset = parser.parse_str(nq).collect_quads()?;
+        // 2 statements, and 4 triples describing each of the 2 quoted triples in each graph
+        assert_eq!(d.quads().count(), 18);
+        let g = BoxTerm::new_iri("tag:g")?;
+        assert_eq!(d.quads_with_g(Some(&g)).count(), 9);
+        assert_eq!(d.quads_with_o(&rdf::Statement).count(), 4);
+        let tag_d = BoxTerm::new_iri("tag:d")?;
+        let q = d
+            .quads_with_p(&tag_d)
+            .map(Result::unwrap)
+            .find(|q| q.g().is_none())
+            .unwrap();
+        assert!(d.contains(q.o(), &rdf::subject, &BoxTerm::new_iri("tag:a")?, Some(&g))?);
+        assert!(d.contains(
+            q.o(),
+            &rdf::subject,
+            &BoxTerm::new_iri("tag:a")?,
+            None as Option<&BoxTerm>
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn star_graphs() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = "_:quoted0 <tag:p> << <tag:a> <tag:b> <tag:c> >> <tag:g1>.\n\
+                  << <tag:a> <tag:b> <tag:c> >> <tag:p> <tag:o> <tag:g2>.\n\
+                  << <tag:a> <tag:b> <tag:c> >> <tag:p> <tag:o2> <tag:g2>.\n";
+        let parser = NQuads12Parser {
+            star: true,
+            ..NQuads12Parser::default()
+        };
+        let quads: Vec<BoxQuad> = parser.parse_str(nq).collect_quads()?;
+        // 3 statements, and the quoted triple is described once in each graph
+        assert_eq!(quads.len(), 11);
+        assert_eq!(quads.iter().filter(|q| rdf::Statement == *q.o()).count(), 2);
+        let node = quads[3].s();
+        assert_eq!(node.value(), "quoted0");
+        assert_eq!(quads[4].s().value(), "quoted_0");
+        assert!(quads
+            .iter()
+            .all(|q| q.s() != node || q.p() != &rdf::subject || q.g().is_some()));
+
+        let parser = NQuads12Parser {
+            star: true,
+            version: RdfVersion::Rdf11,
+        };
+        let err = parser.parse_str(nq).for_each_quad(|_| ()).unwrap_err();
+        assert_eq!(err.token(), Some("<<"));
+        assert!(err.to_string().contains("only valid in RDF 1.2"));
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = "# comment\n<tag:a> <tag:b> \"plain\" _:g .\r\n\n<tag:\\u00E9> <tag:b> \"a\\tb\"@en <tag:g>.";
+        let mut quads = vec![];
+        NQuads12Parser::default()
+            .parse_borrowed(nq)
+            .for_each_quad(|q| {
+                quads.push((
+                    [q.s().clone(), q.p().clone(), q.o().clone()],
+                    q.g().cloned(),
+                ))
+            })?;
+        assert_eq!(quads.len(), 2);
+        match (
+            &quads[0].0[0],
+            &quads[0].0[2],
+            &quads[1].0[0],
+            &quads[1].0[2],
+        ) {
+            (Term::Iri(s0), Term::Literal(o0), Term::Iri(s1), Term::Literal(o1)) => {
+                assert!(s0.ns().is_borrowed());
+                assert!(o0.txt().is_borrowed());
+                assert_eq!(&s1.ns()[..], "tag:é");
+                assert!(s1.ns().is_owned());
+                assert_eq!(&o1.txt()[..], "a\tb");
+                assert!(o1.txt().is_owned());
+            }
+            _ => panic!("unexpected terms {:?}", quads),
+        }
+        assert_eq!(quads[1].1.as_ref().unwrap().value(), "tag:g");
+
+        let err = NQuads12Parser {
+            star: true,
+            ..NQuads12Parser::default()
+        }
+        .parse_borrowed("<tag:a> <tag:b> <tag:c>.\n<< <tag:a> <tag:b> <tag:c> >> <tag:d> <tag:e>.")
+        .for_each_quad(|_| ())
+        .unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.offset(), Some(25));
+        Ok(())
+    }
+}
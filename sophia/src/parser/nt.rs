@@ -72,4 +72,20 @@ mod test {
             .is_some());
         Ok(())
     }
+    #[test]
+    fn test_feed_triples_batched() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nt = r#"
+            <http://localhost/ex#me> <http://example.org/ns/knows> _:b1.
+            _:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/Person>.
+            _:b1 <http://example.org/ns/name> "Alice".
+        "#;
+
+        let mut g = FastGraph::new();
+        NTriplesParser {}
+            .parse_str(nt)
+            .feed_triples_batched::<_, sophia_term::BoxTerm>(&mut &mut g, 2)
+            .map_err(|e| e.to_string())?;
+        assert_eq!(g.triples().count(), 3);
+        Ok(())
+    }
 }
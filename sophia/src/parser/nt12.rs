@@ -0,0 +1,182 @@
+//! Native parser for [N-Triples 1.2].
+//!
+//! Unlike the [`nt`](../nt/index.html) parser (based on RIO),
+//! this parser supports the [directional language-tagged strings] introduced by RDF 1.2
+//! (e.g. `"hello"@en--ltr`).
+//!
+//! When [`star`] is enabled, the [RDF-star] quoted triples (`<< s p o >>`) are also accepted,
+//! and replaced by reified blank nodes (see [`nq12`](../nq12/index.html)).
+//! Otherwise (the default), the parser rejects quoted triples.
+//!
+//! Strings already in memory can also be parsed without copying them,
+//! with [`parse_borrowed`] (see [`nq12`](../nq12/index.html#zero-copy-parsing)).
+//!
+//! [`parse_borrowed`]: struct.NTriples12Parser.html#method.parse_borrowed
+//! [`star`]: struct.NTriples12Parser.html#structfield.star
+//! [RDF-star]: https://www.w3.org/2021/12/rdf-star.html
+//!
+//! [N-Triples 1.2]: https://www.w3.org/TR/rdf12-n-triples/
+//! [directional language-tagged strings]: https://www.w3.org/TR/rdf12-concepts/#section-text-direction
+
+use std::io::BufRead;
+
+use sophia_api::parser::{ParseError, TripleParser};
+
+use crate::parser::nq12::{self, BorrowingSource, LineSource};
+use crate::syntax::dynamic::BoxTriple;
+use crate::syntax::RdfVersion;
+
+/// N-Triples 1.2 parser.
+#[derive(Clone, Debug, Default)]
+pub struct NTriples12Parser {
+    /// Whether RDF-star quoted triples are accepted (see [module documentation](index.html)).
+    pub star: bool,
+    /// The version of RDF that parsed data must comply with
+    /// (see [`nq12`](../nq12/index.html#rdf-11-mode)).
+    pub version: RdfVersion,
+}
+
+impl<B: BufRead> TripleParser<B> for NTriples12Parser {
+    type Source = LineSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        LineSource::new(data, false, self.star, self.version)
+    }
+}
+
+impl NTriples12Parser {
+    /// Parse `txt` without copying it (see [`nq12`](../nq12/index.html#zero-copy-parsing)).
+    ///
+    /// Quoted triples are rejected, regardless of [`star`](#structfield.star).
+    pub fn parse_borrowed<'a>(&self, txt: &'a str) -> BorrowingSource<'a> {
+        BorrowingSource::new(txt, false, self.version)
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(NTriples12Parser, TripleParser);
+
+/// Parse a single N-Triples statement (e.g. from a configuration file or a command line argument).
+///
+/// Surrounding whitespace and a trailing comment are allowed,
+/// but `line` must contain exactly one triple, terminated by `.`.
+/// Quoted triples are rejected.
+///
+/// # Example
+/// ```
+/// use sophia::parser::nt12::parse_triple;
+/// use sophia::term::TTerm;
+/// use sophia::triple::Triple;
+///
+/// let t = parse_triple(r#"<http://example.org/a> <http://example.org/b> "c"@en ."#)?;
+/// assert_eq!(t.o().value(), "c");
+/// assert!(parse_triple("<http://example.org/a> <http://example.org/b>").is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn parse_triple(line: &str) -> Result<BoxTriple, Box<ParseError>> {
+    nq12::parse_triple(line)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serializer::nt::NtSerializer;
+    use crate::serializer::{Stringifier, TripleSerializer};
+    use sophia_api::triple::stream::TripleSource;
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = "<tag:a> <tag:b> \"\\u0627\\u0644\\u0633\\u0644\\u0627\\u0645\"@ar--rtl.\n";
+        let mut ser = NtSerializer::new_stringifier();
+        ser.serialize_triples(parse_str(nt))?;
+        assert_eq!(ser.as_str(), "<tag:a> <tag:b> \"السلام\"@ar--rtl.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn graph_name() {
+        assert!(parse_str("<tag:a> <tag:b> <tag:c> <tag:g>.")
+            .for_each_triple(|_| ())
+            .is_err());
+    }
+
+    #[test]
+    fn star() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = "<< <tag:a> <tag:b> <tag:c> >> <tag:d> <tag:e>.\n";
+        assert!(parse_str(nt).for_each_triple(|_| ()).is_err());
+        let parser = NTriples12Parser {
+            star: true,
+            ..NTriples12Parser::default()
+        };
+        let mut ser = NtSerializer::new_stringifier();
+        ser.serialize_triples(parser.parse_str(nt))?;
+        assert_eq!(
+            ser.as_str(),
+            "_:quoted0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Statement>.\n\
+             _:quoted0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#subject> <tag:a>.\n\
+             _:quoted0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#predicate> <tag:b>.\n\
+             _:quoted0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#object> <tag:c>.\n\
+             _:quoted0 <tag:d> <tag:e>.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rdf11() -> Result<(), Box<dyn std::error::Error>> {
+        use sophia_term::BoxTerm;
+
+        let parser = NTriples12Parser {
+            version: RdfVersion::Rdf11,
+            ..NTriples12Parser::default()
+        };
+        let nt = "<tag:a> <tag:b> \"c\"@en.\n";
+        assert_eq!(
+            parser
+                .parse_str(nt)
+                .collect_triples::<Vec<[BoxTerm; 3]>>()?
+                .len(),
+            1
+        );
+        let nt = "<tag:a> <tag:b> \"c\"@en--ltr.\n";
+        let err = parser.parse_str(nt).for_each_triple(|_| ()).unwrap_err();
+        assert!(err.to_string().contains("only valid in RDF 1.2"));
+        assert!(parser.parse_borrowed(nt).for_each_triple(|_| ()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = "<tag:a> <tag:b> \"c\"@en--ltr.\n<tag:a> <tag:b> <tag:c> <tag:g>.\n";
+        let mut ser = NtSerializer::new_stringifier();
+        assert!(ser
+            .serialize_triples(NTriples12Parser::default().parse_borrowed(nt))
+            .is_err());
+        let nt = "<tag:a> <tag:b> \"c\"@en--ltr.\n";
+        let mut ser = NtSerializer::new_stringifier();
+        ser.serialize_triples(NTriples12Parser::default().parse_borrowed(nt))?;
+        assert_eq!(ser.as_str(), nt);
+        Ok(())
+    }
+
+    #[test]
+    fn single_triple() -> Result<(), Box<dyn std::error::Error>> {
+        use sophia_api::triple::Triple;
+        use sophia_term::BoxTerm;
+
+        let t = parse_triple("  <tag:a> <tag:b> _:c . # comment")?;
+        assert_eq!(t.s(), &BoxTerm::new_iri("tag:a")?);
+        assert_eq!(t.o(), &BoxTerm::new_bnode("c")?);
+        for line in &[
+            "",
+            "<tag:a> <tag:b> <tag:c>",
+            "<tag:a> <tag:b> <tag:c>. <tag:a> <tag:b> <tag:c>.",
+            "<tag:a> <tag:b> <tag:c> <tag:g>.",
+            "<< <tag:a> <tag:b> <tag:c> >> <tag:d> <tag:e>.",
+        ] {
+            assert!(parse_triple(line).is_err(), "{}", line);
+        }
+        Ok(())
+    }
+}
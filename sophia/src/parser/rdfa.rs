@@ -0,0 +1,656 @@
+//! Parser extracting [RDFa 1.1] data from HTML documents.
+//!
+//! This parser implements the [processing rules] of RDFa Core 1.1,
+//! with the [HTML+RDFa] specific rules about `@rel` and `@property`,
+//! and the prefixes and terms of the [initial context].
+//! Lists (`@inlist`) and XML literals are not supported:
+//! `@inlist` is ignored, and `rdf:XMLLiteral` values are produced from the inner HTML.
+//!
+//! As is customary for RDFa processors,
+//! values that can not be converted to valid terms (e.g. malformed IRIs) are silently ignored,
+//! so the only errors raised by this parser are I/O errors.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::parser::rdfa::RdfaParser;
+//! # use sophia::parser::TripleParser;
+//! # use sophia::triple::stream::TripleSource;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let html = r##"<html><body vocab="http://schema.org/">
+//!     <div typeof="Person" about="#me">
+//!         <span property="name">Alice</span>
+//!     </div>
+//! </body></html>"##;
+//! let parser = RdfaParser {
+//!     base: Some("http://example.org/alice".into()),
+//! };
+//! let g: FastGraph = parser.parse_str(html).collect_triples()?;
+//! // rdf:type, schema:name, and rdfa:usesVocabulary
+//! assert_eq!(g.triples().count(), 3);
+//! # Ok(()) }
+//! ```
+//!
+//! [RDFa 1.1]: https://www.w3.org/TR/rdfa-core/
+//! [processing rules]: https://www.w3.org/TR/rdfa-core/#s_sequence
+//! [HTML+RDFa]: https://www.w3.org/TR/html-rdfa/
+//! [initial context]: https://www.w3.org/2011/rdfa-context/rdfa-1.1
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, BufRead};
+
+use scraper::{ElementRef, Html};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::TripleParser;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::iri::resolve::{IriParsed, Resolve};
+use sophia_term::iri::{is_absolute_iri_ref, Iri};
+use sophia_term::BoxTerm;
+
+/// The source returned by [`RdfaParser`](struct.RdfaParser.html).
+pub type RdfaSource = std::vec::IntoIter<Result<[BoxTerm; 3], io::Error>>;
+
+/// RDFa parser for HTML documents.
+#[derive(Clone, Debug, Default)]
+pub struct RdfaParser {
+    /// The base IRI of the document,
+    /// used if the document does not contain a `<base>` element.
+    pub base: Option<String>,
+}
+
+impl<B: BufRead> TripleParser<B> for RdfaParser {
+    type Source = RdfaSource;
+    fn parse(&self, mut data: B) -> Self::Source {
+        let mut txt = String::new();
+        let res = match data.read_to_string(&mut txt) {
+            Ok(_) => extract(&txt, self.base.as_deref())
+                .into_iter()
+                .map(Ok)
+                .collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        };
+        res.into_iter()
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(RdfaParser, TripleParser);
+
+/// Extract the RDFa triples of the HTML document `html`.
+fn extract(html: &str, base: Option<&str>) -> Vec<[BoxTerm; 3]> {
+    let doc = Html::parse_document(html);
+    let root = doc.root_element();
+    let base = root
+        .descendent_elements()
+        .find(|e| e.value().name() == "base")
+        .and_then(|e| e.attr("href"))
+        .map(|href| match base {
+            Some(base) => resolve(base, href).unwrap_or_else(|| href.to_string()),
+            None => href.to_string(),
+        })
+        .or_else(|| base.map(str::to_string))
+        .unwrap_or_default();
+    let base = base.split('#').next().unwrap_or_default().to_string();
+    let mut processor = Processor {
+        triples: vec![],
+        bnodes: 0,
+        base: base.clone(),
+    };
+    let context = Context {
+        parent_subject: processor.iri(&base),
+        parent_object: None,
+        incomplete: vec![],
+        prefixes: initial_prefixes(),
+        terms: INITIAL_TERMS
+            .iter()
+            .map(|(t, iri)| (t.to_string(), iri.to_string()))
+            .collect(),
+        vocab: None,
+        lang: None,
+    };
+    processor.process(root, &context, true);
+    processor.triples
+}
+
+/// The direction of an incomplete triple.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Forward,
+    Reverse,
+}
+
+/// The [evaluation context](https://www.w3.org/TR/rdfa-core/#s_evaluation_context).
+#[derive(Clone, Debug)]
+struct Context {
+    parent_subject: Option<BoxTerm>,
+    parent_object: Option<BoxTerm>,
+    incomplete: Vec<(BoxTerm, Direction)>,
+    prefixes: HashMap<String, String>,
+    terms: HashMap<String, String>,
+    vocab: Option<String>,
+    lang: Option<String>,
+}
+
+struct Processor {
+    triples: Vec<[BoxTerm; 3]>,
+    bnodes: usize,
+    base: String,
+}
+
+impl Processor {
+    /// Apply the processing sequence to `elt` and its descendants.
+    fn process(&mut self, elt: ElementRef, parent: &Context, is_root: bool) {
+        let attr = |name: &str| elt.attr(name);
+        let mut ctx = parent.clone();
+        ctx.incomplete = vec![];
+        let mut skip = false;
+        let mut new_subject: Option<BoxTerm>;
+        let mut current_object = None;
+        let mut typed_resource = None;
+
+        // step 2: default vocabulary
+        if let Some(vocab) = attr("vocab") {
+            ctx.vocab = if vocab.is_empty() {
+                None
+            } else {
+                let vocab = self.resolve(vocab);
+                if let (Some(base), Some(v)) = (self.iri(&self.base), self.iri(&vocab)) {
+                    self.triples.push([base, rdfa_uses_vocabulary(), v]);
+                }
+                Some(vocab)
+            };
+        }
+        // step 3: IRI mappings
+        for (name, value) in elt.value().attrs() {
+            if let Some(prefix) = name.strip_prefix("xmlns:") {
+                ctx.prefixes
+                    .insert(prefix.to_lowercase(), value.to_string());
+            }
+        }
+        if let Some(prefixes) = attr("prefix") {
+            let mut tokens = prefixes.split_whitespace();
+            while let (Some(prefix), Some(iri)) = (tokens.next(), tokens.next()) {
+                if let Some(prefix) = prefix.strip_suffix(':') {
+                    if prefix != "_" {
+                        ctx.prefixes.insert(prefix.to_lowercase(), iri.to_string());
+                    }
+                }
+            }
+        }
+        // step 4: language
+        if let Some(lang) = attr("xml:lang").or_else(|| attr("lang")) {
+            ctx.lang = if lang.is_empty() {
+                None
+            } else {
+                Some(lang.to_string())
+            };
+        }
+
+        let property = attr("property");
+        let mut rel = attr("rel");
+        let mut rev = attr("rev");
+        // HTML+RDFa: non-CURIE @rel/@rev values are ignored if @property is present
+        if property.is_some() {
+            let has_curie = |v: &&str| v.split_whitespace().any(|t| t.contains(':'));
+            rel = rel.filter(has_curie);
+            rev = rev.filter(has_curie);
+        }
+        let rels = rel.map(|r| self.predicates(r, &ctx, property.is_some()));
+        let revs = rev.map(|r| self.predicates(r, &ctx, property.is_some()));
+        let about = attr("about").and_then(|v| self.safe_curie_or_iri(v, &ctx));
+        let resource = attr("resource")
+            .and_then(|v| self.safe_curie_or_iri(v, &ctx))
+            .or_else(|| attr("href").and_then(|v| self.iri(&self.resolve(v))))
+            .or_else(|| attr("src").and_then(|v| self.iri(&self.resolve(v))));
+        let typeof_ = attr("typeof");
+
+        if rel.is_none() && rev.is_none() {
+            // step 5
+            if property.is_some() && attr("content").is_none() && attr("datatype").is_none() {
+                new_subject = about.clone();
+                if new_subject.is_none() {
+                    if is_root {
+                        new_subject = self.iri(&self.base);
+                    } else {
+                        new_subject = parent.parent_object.clone();
+                    }
+                }
+                if typeof_.is_some() {
+                    typed_resource = match (&about, is_root) {
+                        (Some(about), _) => Some(about.clone()),
+                        (None, true) => self.iri(&self.base),
+                        (None, false) => Some(resource.clone().unwrap_or_else(|| self.bnode())),
+                    };
+                    current_object = typed_resource.clone();
+                }
+            } else {
+                new_subject = about.clone().or_else(|| resource.clone());
+                if new_subject.is_none() {
+                    if is_root {
+                        new_subject = self.iri(&self.base);
+                    } else if typeof_.is_some() {
+                        new_subject = Some(self.bnode());
+                    } else if parent.parent_object.is_some() {
+                        new_subject = parent.parent_object.clone();
+                        skip = property.is_none();
+                    }
+                }
+                if typeof_.is_some() {
+                    typed_resource = new_subject.clone();
+                }
+            }
+        } else {
+            // step 6
+            new_subject = about.clone();
+            if typeof_.is_some() {
+                typed_resource = new_subject.clone();
+            }
+            if new_subject.is_none() {
+                new_subject = if is_root {
+                    self.iri(&self.base)
+                } else {
+                    parent.parent_object.clone()
+                };
+            }
+            current_object = resource.clone();
+            if current_object.is_none() && typeof_.is_some() && about.is_none() {
+                current_object = Some(self.bnode());
+            }
+            if typeof_.is_some() && about.is_none() {
+                typed_resource = current_object.clone();
+            }
+        }
+
+        // step 7: types
+        if let (Some(typed), Some(types)) = (&typed_resource, typeof_) {
+            for t in types.split_whitespace() {
+                if let Some(t) = self.term_or_curie_or_iri(t, &ctx) {
+                    self.triples
+                        .push([typed.clone(), BoxTerm::copy(&rdf::type_), t]);
+                }
+            }
+        }
+
+        // steps 9 and 10: relations
+        if let Some(subject) = &new_subject {
+            if let Some(object) = &current_object {
+                for p in rels.iter().flatten() {
+                    self.triples
+                        .push([subject.clone(), p.clone(), object.clone()]);
+                }
+                for p in revs.iter().flatten() {
+                    self.triples
+                        .push([object.clone(), p.clone(), subject.clone()]);
+                }
+            } else if rels.is_some() || revs.is_some() {
+                current_object = Some(self.bnode());
+                for p in rels.iter().flatten() {
+                    ctx.incomplete.push((p.clone(), Direction::Forward));
+                }
+                for p in revs.iter().flatten() {
+                    ctx.incomplete.push((p.clone(), Direction::Reverse));
+                }
+            }
+        }
+
+        // step 11: properties
+        if let (Some(subject), Some(property)) = (&new_subject, property) {
+            let predicates = self.predicates(property, &ctx, false);
+            if let Some(value) = self.property_value(elt, &ctx, &typed_resource, &resource) {
+                for p in predicates {
+                    self.triples.push([subject.clone(), p, value.clone()]);
+                }
+            }
+        }
+
+        // step 12: complete the incomplete triples of the parent
+        if !skip {
+            if let Some(subject) = &new_subject {
+                if let Some(parent_subject) = &parent.parent_subject {
+                    for (p, dir) in &parent.incomplete {
+                        self.triples.push(match dir {
+                            Direction::Forward => {
+                                [parent_subject.clone(), p.clone(), subject.clone()]
+                            }
+                            Direction::Reverse => {
+                                [subject.clone(), p.clone(), parent_subject.clone()]
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        // step 13: children
+        if skip {
+            ctx.parent_subject = parent.parent_subject.clone();
+            ctx.parent_object = parent.parent_object.clone();
+            ctx.incomplete = parent.incomplete.clone();
+        } else {
+            ctx.parent_subject = new_subject
+                .clone()
+                .or_else(|| parent.parent_subject.clone());
+            ctx.parent_object = current_object
+                .clone()
+                .or_else(|| new_subject.clone())
+                .or_else(|| parent.parent_subject.clone());
+        }
+        for child in elt.child_elements() {
+            self.process(child, &ctx, false);
+        }
+    }
+
+    /// The object of the triples generated by `@property` (step 11).
+    fn property_value(
+        &mut self,
+        elt: ElementRef,
+        ctx: &Context,
+        typed_resource: &Option<BoxTerm>,
+        resource: &Option<BoxTerm>,
+    ) -> Option<BoxTerm> {
+        let content =
+            elt.attr("content")
+                .map(str::to_string)
+                .or_else(|| match elt.value().name() {
+                    "time" => elt.attr("datetime").map(str::to_string),
+                    _ => None,
+                });
+        let text = || content.clone().unwrap_or_else(|| elt.text().collect());
+        let plain = |txt: String| match &ctx.lang {
+            Some(lang) => BoxTerm::new_literal_lang(txt, &lang[..]).ok(),
+            None => BoxTerm::new_literal_dt(txt, xsd::string).ok(),
+        };
+        match elt.attr("datatype") {
+            Some("") => plain(text()),
+            Some(dt) => {
+                let dt = self.term_or_curie_or_iri(dt, ctx)?;
+                let txt =
+                    if dt.value() == rdf::XMLLiteral.value() || dt.value() == rdf::HTML.value() {
+                        elt.inner_html()
+                    } else {
+                        text()
+                    };
+                BoxTerm::new_literal_dt(txt, Iri::try_from(dt).ok()?).ok()
+            }
+            None if content.is_some() => plain(text()),
+            None if elt.attr("rel").is_none()
+                && elt.attr("rev").is_none()
+                && resource.is_some() =>
+            {
+                resource.clone()
+            }
+            None if elt.attr("typeof").is_some() && elt.attr("about").is_none() => {
+                typed_resource.clone()
+            }
+            None => plain(text()),
+        }
+    }
+
+    /// The predicates in the whitespace-separated list `value`
+    /// (blank nodes are not allowed as predicates).
+    fn predicates(&mut self, value: &str, ctx: &Context, curie_only: bool) -> Vec<BoxTerm> {
+        value
+            .split_whitespace()
+            .filter(|t| !curie_only || t.contains(':'))
+            .filter_map(|t| self.term_or_curie_or_iri(t, ctx))
+            .filter(|t| t.kind() != TermKind::BlankNode)
+            .collect()
+    }
+
+    /// Interpret a [TERMorCURIEorAbsIRI](https://www.w3.org/TR/rdfa-core/#s_terms).
+    fn term_or_curie_or_iri(&mut self, value: &str, ctx: &Context) -> Option<BoxTerm> {
+        if value.contains(':') {
+            self.curie(value, ctx).or_else(|| {
+                if is_absolute_iri_ref(value) {
+                    self.iri(value)
+                } else {
+                    None
+                }
+            })
+        } else if let Some(vocab) = &ctx.vocab {
+            self.iri(&format!("{}{}", vocab, value))
+        } else {
+            let iri = ctx
+                .terms
+                .get(value)
+                .or_else(|| ctx.terms.get(&value.to_lowercase()))?
+                .clone();
+            self.iri(&iri)
+        }
+    }
+
+    /// Interpret a [SafeCURIEorCURIEorIRI](https://www.w3.org/TR/rdfa-core/#s_curies).
+    fn safe_curie_or_iri(&mut self, value: &str, ctx: &Context) -> Option<BoxTerm> {
+        if let Some(safe) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            return self.curie(safe, ctx);
+        }
+        self.curie(value, ctx)
+            .or_else(|| self.iri(&self.resolve(value)))
+    }
+
+    /// Interpret a CURIE, if its prefix is known.
+    fn curie(&mut self, value: &str, ctx: &Context) -> Option<BoxTerm> {
+        let (prefix, reference) = match value.find(':') {
+            Some(i) => (&value[..i], &value[i + 1..]),
+            None => return None,
+        };
+        if reference.starts_with("//") {
+            // an IRI such as http://example.org/, not a CURIE
+            return None;
+        }
+        match prefix {
+            "_" => {
+                let label: String = reference
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                BoxTerm::new_bnode(format!("b{}", label)).ok()
+            }
+            "" => self.iri(&format!("http://www.w3.org/1999/xhtml/vocab#{}", reference)),
+            _ => {
+                let ns = ctx.prefixes.get(&prefix.to_lowercase())?.clone();
+                self.iri(&format!("{}{}", ns, reference))
+            }
+        }
+    }
+
+    /// Resolve `iri` against the base IRI.
+    fn resolve(&self, iri: &str) -> String {
+        if is_absolute_iri_ref(iri) {
+            iri.to_string()
+        } else {
+            resolve(&self.base, iri).unwrap_or_else(|| iri.to_string())
+        }
+    }
+
+    /// Build an IRI term, if `iri` is a valid absolute IRI.
+    fn iri(&self, iri: &str) -> Option<BoxTerm> {
+        if is_absolute_iri_ref(iri) {
+            BoxTerm::new_iri(iri).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Build a fresh blank node.
+    fn bnode(&mut self) -> BoxTerm {
+        self.bnodes += 1;
+        BoxTerm::new_bnode(format!("rdfa{}", self.bnodes)).unwrap()
+    }
+}
+
+/// Resolve `iri` against `base`.
+fn resolve(base: &str, iri: &str) -> Option<String> {
+    let base = IriParsed::new(base).ok()?;
+    let resolved: Result<_, _> = base.resolve(iri);
+    resolved.ok().map(|iri| iri.to_string())
+}
+
+fn rdfa_uses_vocabulary() -> BoxTerm {
+    BoxTerm::new_iri("http://www.w3.org/ns/rdfa#usesVocabulary").unwrap()
+}
+
+/// The prefixes of the RDFa 1.1 initial context.
+fn initial_prefixes() -> HashMap<String, String> {
+    INITIAL_PREFIXES
+        .iter()
+        .map(|(p, iri)| (p.to_string(), iri.to_string()))
+        .collect()
+}
+
+const INITIAL_PREFIXES: &[(&str, &str)] = &[
+    ("as", "https://www.w3.org/ns/activitystreams#"),
+    ("cc", "http://creativecommons.org/ns#"),
+    ("csvw", "http://www.w3.org/ns/csvw#"),
+    ("ctag", "http://commontag.org/ns#"),
+    ("dc", "http://purl.org/dc/terms/"),
+    ("dc11", "http://purl.org/dc/elements/1.1/"),
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("gr", "http://purl.org/goodrelations/v1#"),
+    ("ical", "http://www.w3.org/2002/12/cal/icaltzd#"),
+    ("og", "http://ogp.me/ns#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfa", "http://www.w3.org/ns/rdfa#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("rev", "http://purl.org/stuff/rev#"),
+    ("rif", "http://www.w3.org/2007/rif#"),
+    ("schema", "http://schema.org/"),
+    ("sioc", "http://rdfs.org/sioc/ns#"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("skosxl", "http://www.w3.org/2008/05/skos-xl#"),
+    ("v", "http://rdf.data-vocabulary.org/#"),
+    ("vcard", "http://www.w3.org/2006/vcard/ns#"),
+    ("void", "http://rdfs.org/ns/void#"),
+    ("xhv", "http://www.w3.org/1999/xhtml/vocab#"),
+    ("xml", "http://www.w3.org/XML/1998/namespace"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+const INITIAL_TERMS: &[(&str, &str)] = &[
+    (
+        "describedby",
+        "http://www.w3.org/2007/05/powder-s#describedby",
+    ),
+    ("license", "http://www.w3.org/1999/xhtml/vocab#license"),
+    ("role", "http://www.w3.org/1999/xhtml/vocab#role"),
+];
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::triple::stream::TripleSource;
+    use crate::triple::Triple;
+    use sophia_term::matcher::ANY;
+    use sophia_term::StaticTerm;
+
+    fn parse(html: &str) -> FastGraph {
+        let parser = RdfaParser {
+            base: Some("http://example.org/doc".into()),
+        };
+        parser.parse_str(html).collect_triples().unwrap()
+    }
+
+    fn iri(iri: &'static str) -> StaticTerm {
+        StaticTerm::new_iri(iri).unwrap()
+    }
+
+    #[test]
+    fn properties_and_types() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(
+            r##"<html prefix="ex: http://example.org/ns#"><head><title>T</title></head>
+            <body>
+              <div about="#alice" typeof="foaf:Person">
+                <span property="foaf:name" lang="fr">Alice</span>
+                <span property="ex:age" datatype="xsd:integer" content="42">forty-two</span>
+                <a rel="foaf:knows" href="bob">Bob</a>
+              </div>
+            </body></html>"##,
+        );
+        let alice = iri("http://example.org/doc#alice");
+        assert!(g.contains(
+            &alice,
+            &rdf::type_,
+            &iri("http://xmlns.com/foaf/0.1/Person")
+        )?);
+        let name = StaticTerm::new_literal_lang("Alice", "fr")?;
+        assert!(g.contains(&alice, &iri("http://xmlns.com/foaf/0.1/name"), &name)?);
+        let age = StaticTerm::new_literal_dt("42", xsd::integer)?;
+        assert!(g.contains(&alice, &iri("http://example.org/ns#age"), &age)?);
+        assert!(g.contains(
+            &alice,
+            &iri("http://xmlns.com/foaf/0.1/knows"),
+            &iri("http://example.org/bob")
+        )?);
+        assert_eq!(g.triples().count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn vocab_and_chaining() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(
+            r##"<html><body><div vocab="http://schema.org/" typeof="Event">
+                <span property="name">Concert</span>
+                <div property="location" typeof="Place">
+                  <span property="name">Hall</span>
+                </div>
+            </div></body></html>"##,
+        );
+        let uses_vocab = g
+            .triples_matching(&ANY, &iri("http://www.w3.org/ns/rdfa#usesVocabulary"), &ANY)
+            .count();
+        assert_eq!(uses_vocab, 1);
+        // 2 types, 2 names, 1 location, 1 usesVocabulary
+        assert_eq!(g.triples().count(), 6);
+        let place = g
+            .triples_matching(&ANY, &rdf::type_, &iri("http://schema.org/Place"))
+            .next()
+            .unwrap()?
+            .s()
+            .clone();
+        assert!(g
+            .triples_matching(&ANY, &iri("http://schema.org/location"), &place)
+            .next()
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn hanging_rel() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(
+            r##"<html><body><div about="http://example.org/a" rel="http://example.org/p">
+                <span about="http://example.org/b"></span>
+                <span about="http://example.org/c"></span>
+            </div></body></html>"##,
+        );
+        let a = iri("http://example.org/a");
+        let p = iri("http://example.org/p");
+        assert!(g.contains(&a, &p, &iri("http://example.org/b"))?);
+        assert!(g.contains(&a, &p, &iri("http://example.org/c"))?);
+        assert_eq!(g.triples().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn html_base() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(
+            r##"<html><head><base href="http://example.com/"></head>
+            <body><p property="dc:title">Hello</p></body></html>"##,
+        );
+        let title = StaticTerm::new_literal_dt("Hello", xsd::string)?;
+        assert!(g.contains(
+            &iri("http://example.com/"),
+            &iri("http://purl.org/dc/terms/title"),
+            &title
+        )?);
+        Ok(())
+    }
+}
@@ -5,7 +5,9 @@ use std::result::Result as StdResult;
 
 use rio_api::model::*;
 use rio_api::parser::*;
+use rio_turtle::TurtleError;
 
+use sophia_api::parser::ParseError;
 use sophia_api::quad::stream::*;
 use sophia_api::quad::streaming_mode::StreamedQuad;
 use sophia_api::triple::stream::*;
@@ -42,15 +44,15 @@ enum MyStreamError<E1, E2> {
 }
 impl<E1, E2> MyStreamError<E1, E2>
 where
-    E1: Error + 'static,
+    E1: IntoParseError,
     E2: Error + 'static,
 {
     fn from_sink_error(err: E2) -> Self {
         MyStreamError::Sink(err)
     }
-    fn into_stream_error(self) -> StreamError<E1, E2> {
+    fn into_stream_error(self) -> StreamError<ParseError, E2> {
         match self {
-            MyStreamError::Source(err) => SourceError(err),
+            MyStreamError::Source(err) => SourceError(err.into_parse_error()),
             MyStreamError::Sink(err) => SinkError(err),
         }
     }
@@ -71,19 +73,19 @@ sophia_api::make_scoped_triple_streaming_mode!(ScopedRioSourceTriple, RioSourceT
 impl<T, E> TripleSource for StrictRioSource<T, E>
 where
     T: TriplesParser<Error = E>,
-    E: Error + 'static,
+    E: IntoParseError,
 {
-    type Error = E;
+    type Error = ParseError;
     //type Triple = crate::triple::streaming_mode::ByValue<RioSourceTriple<'static>>;
     type Triple = ScopedRioSourceTriple;
 
-    fn try_for_some_triple<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    fn try_for_some_triple<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, EF>
     where
         F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), EF>,
         EF: Error,
     {
         match self {
-            StrictRioSource::Error(opt) => Err(SourceError(consume_err(opt))),
+            StrictRioSource::Error(opt) => Err(SourceError(consume_err(opt).into_parse_error())),
             StrictRioSource::Parser(parser) => {
                 if parser.is_end() {
                     return Ok(false);
@@ -110,18 +112,18 @@ sophia_api::make_scoped_quad_streaming_mode!(ScopedRioSourceQuad, RioSourceQuad)
 impl<T, E> QuadSource for StrictRioSource<T, E>
 where
     T: QuadsParser<Error = E>,
-    E: Error + 'static,
+    E: IntoParseError,
 {
-    type Error = E;
+    type Error = ParseError;
     type Quad = ScopedRioSourceQuad;
 
-    fn try_for_some_quad<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    fn try_for_some_quad<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, EF>
     where
         F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), EF>,
         EF: Error,
     {
         match self {
-            StrictRioSource::Error(opt) => Err(SourceError(consume_err(opt))),
+            StrictRioSource::Error(opt) => Err(SourceError(consume_err(opt).into_parse_error())),
             StrictRioSource::Parser(parser) => {
                 if parser.is_end() {
                     return Ok(false);
@@ -163,18 +165,20 @@ impl<T, E> From<StdResult<T, E>> for GeneralizedRioSource<T, E> {
 impl<T, E> QuadSource for GeneralizedRioSource<T, E>
 where
     T: GeneralizedQuadsParser<Error = E>,
-    E: Error + 'static,
+    E: IntoParseError,
 {
-    type Error = E;
+    type Error = ParseError;
     type Quad = ScopedRioSourceQuad;
 
-    fn try_for_some_quad<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    fn try_for_some_quad<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, ParseError, EF>
     where
         F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), EF>,
         EF: Error,
     {
         match self {
-            GeneralizedRioSource::Error(opt) => Err(SourceError(consume_err(opt))),
+            GeneralizedRioSource::Error(opt) => {
+                Err(SourceError(consume_err(opt).into_parse_error()))
+            }
             GeneralizedRioSource::Parser(parser) => {
                 if parser.is_end() {
                     return Ok(false);
@@ -198,6 +202,70 @@ where
     }
 }
 
+/// Conversion of the errors raised by RIO parsers into [`ParseError`]s.
+///
+/// [`ParseError`]: ../../../sophia_api/parser/struct.ParseError.html
+pub trait IntoParseError: Error + Sized + 'static {
+    /// Convert this error into a [`ParseError`].
+    ///
+    /// [`ParseError`]: ../../../sophia_api/parser/struct.ParseError.html
+    fn into_parse_error(self) -> ParseError;
+}
+
+impl IntoParseError for TurtleError {
+    fn into_parse_error(self) -> ParseError {
+        let position = rio_api::parser::ParseError::textual_position(&self);
+        let mut message = self.to_string();
+        if position.is_some() {
+            if let Some(i) = message.rfind(" on line ") {
+                message.truncate(i);
+            }
+        }
+        let mut error = ParseError::new(message.as_str());
+        if let Some(pos) = position {
+            // despite their documentation, RIO positions start at 1
+            error = error.with_line_column(pos.line_number(), pos.byte_number());
+        }
+        if let Some(prefix) = quoted(&message, "unknown prefix ") {
+            error = error.with_token(format!("{}:", prefix)).with_hint(format!(
+                "declare the prefix with '@prefix {}: <...> .'",
+                prefix
+            ));
+        } else if let Some(c) = quoted(&message, "unexpected character ") {
+            error = error.with_token(c);
+        } else if let Some(iri) = quoted(&message, "error while parsing IRI ") {
+            error = error
+                .with_token(iri)
+                .with_hint("special characters in IRIs must be percent-encoded");
+        } else if let Some(tag) = quoted(&message, "error while parsing language tag ") {
+            error = error.with_token(tag);
+        } else if let Some(point) = quoted(&message, "invalid unicode code point ") {
+            error = error.with_token(point);
+        } else if message == "premature end of file" {
+            error = error.with_hint(
+                "the data may be truncated, or a closing bracket or final '.' may be missing",
+            );
+        }
+        error.with_source(self)
+    }
+}
+
+#[cfg(feature = "xml")]
+impl IntoParseError for rio_xml::RdfXmlError {
+    fn into_parse_error(self) -> ParseError {
+        ParseError::new(self.to_string()).with_source(self)
+    }
+}
+
+/// Extract the single-quoted part of `message` following `prefix`.
+fn quoted<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = message.strip_prefix(prefix)?.strip_prefix('\'')?;
+    match rest.find("': ") {
+        Some(i) => Some(&rest[..i]),
+        None => rest.strip_suffix('\''),
+    }
+}
+
 /// Consume inner error and convert it to Error
 fn consume_err<E>(opt: &mut Option<E>) -> E {
     opt.take().unwrap_or_else(|| {
@@ -1,6 +1,10 @@
 //! Adapter for the TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
+//!
+//! TriG documents can be tokenized with the [Turtle lexer](../turtle/lexer/index.html).
 
+use crate::parser::config::{ParserConfig, NO_BASE};
 use crate::parser::rio_common::*;
+use crate::syntax::dynamic::{box_quads, DynQuadSource};
 use rio_turtle::{TriGParser as RioTriGParser, TurtleError};
 use sophia_api::parser::QuadParser;
 use std::io::BufRead;
@@ -16,12 +20,31 @@ impl<B: BufRead> QuadParser<B> for TriGParser {
     fn parse(&self, data: B) -> Self::Source {
         let base: &str = match &self.base {
             Some(base) => &base,
-            None => "x-no-base:///",
+            None => NO_BASE,
         };
         StrictRioSource::from(RioTriGParser::new(data, base))
     }
 }
 
+impl TriGParser {
+    /// Parse `data`, enforcing the given [configuration](../config/index.html).
+    ///
+    /// The base IRI of `config`, if any, takes precedence over the base IRI of this parser.
+    /// Undefined prefixes are always rejected.
+    pub fn parse_with_config<'a, B>(&self, data: B, config: &ParserConfig) -> DynQuadSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        let base = config
+            .base()
+            .map(str::to_string)
+            .or_else(|| self.base.clone());
+        config.parse_quads(data, true, |data| {
+            box_quads(TriGParser { base }.parse(data))
+        })
+    }
+}
+
 sophia_api::def_mod_functions_for_bufread_parser!(TriGParser, QuadParser);
 
 // ---------------------------------------------------------------------------------
@@ -1,13 +1,22 @@
 //! Adapter for the Turtle parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
 
-use std::io::BufRead;
+use std::io::{BufRead, Read};
 
+use crate::parser::config::{ParserConfig, NO_BASE};
 use crate::parser::rio_common::*;
+use crate::syntax::dynamic::{box_triples, BoxTriple, DynError, DynTripleSource};
 use rio_api::parser::ParseError;
 use rio_turtle::{TurtleError, TurtleParser as RioTurtleParser};
 use sophia_api::parser::{Location, TripleParser, WithLocation};
+use sophia_api::term::CopyTerm;
+use sophia_term::BoxTerm;
 use thiserror::Error;
 
+pub mod incremental;
+pub mod lexer;
+pub mod metadata;
+pub mod spanned;
+
 /// Turtle parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct TurtleParser {
@@ -19,12 +28,81 @@ impl<B: BufRead> TripleParser<B> for TurtleParser {
     fn parse(&self, data: B) -> Self::Source {
         let base: &str = match &self.base {
             Some(base) => &base,
-            None => "x-no-base:///",
+            None => NO_BASE,
         };
         StrictRioSource::from(RioTurtleParser::new(data, base))
     }
 }
 
+impl TurtleParser {
+    /// Parse `txt` natively, reporting the span of each triple and term in `txt`
+    /// (see [`spanned`](spanned/index.html)).
+    pub fn parse_spanned<'a>(&self, txt: &'a str) -> spanned::SpannedSource<'a> {
+        spanned::SpannedSource::new(txt, self.base.clone())
+    }
+
+    /// Parse `txt` as [`parse_spanned`](#method.parse_spanned) does,
+    /// also accepting RDF-star quoted triples
+    /// (see [`spanned`](spanned/index.html#quoted-triples)).
+    pub fn parse_star<'a>(&self, txt: &'a str) -> spanned::SpannedSource<'a> {
+        self.parse_spanned(txt).accepting_quoted_triples()
+    }
+
+    /// Parse `txt` as [`parse_spanned`](#method.parse_spanned) does,
+    /// also capturing its comments and directives
+    /// (see [`metadata`](metadata/index.html)).
+    pub fn parse_with_metadata<'a>(&self, txt: &'a str) -> spanned::SpannedSource<'a> {
+        self.parse_spanned(txt).capturing_metadata()
+    }
+
+    /// Parse `data`, enforcing the given [configuration](../config/index.html).
+    ///
+    /// The base IRI of `config`, if any, takes precedence over the base IRI of this parser.
+    /// If `config` [accepts undefined prefixes](../config/struct.ParserConfig.html#method.set_undefined_prefixes),
+    /// `data` is parsed natively, after being read entirely.
+    pub fn parse_with_config<'a, B>(&self, data: B, config: &ParserConfig) -> DynTripleSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        let base = config
+            .base()
+            .map(str::to_string)
+            .or_else(|| self.base.clone());
+        if !config.undefined_prefixes() {
+            return config.parse_triples(data, true, |data| {
+                box_triples(TurtleParser { base }.parse(data))
+            });
+        }
+        config.parse_triples(data, true, |mut data| {
+            // the source is only read when the first triple is requested, as with the RIO parser
+            let lazy = std::iter::once_with(move || {
+                let mut txt = String::new();
+                if let Err(err) = data.read_to_string(&mut txt) {
+                    return vec![Err(DynError::new(err))];
+                }
+                let base = base.unwrap_or_else(|| NO_BASE.to_string());
+                spanned::SpannedSource::new(&txt, Some(base))
+                    .accepting_undefined_prefixes()
+                    .map(|res| {
+                        res.map(|t| -> BoxTriple {
+                            let [s, p, o] = t.into_terms();
+                            [BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)]
+                        })
+                        .map_err(DynError::new)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            Box::new(lazy.flatten())
+        })
+    }
+
+    /// Parse `text` into a document that can be edited and parsed incrementally
+    /// (see [`incremental`](incremental/index.html)).
+    pub fn parse_document<S: Into<String>>(&self, text: S) -> incremental::TurtleDocument {
+        incremental::TurtleDocument::new(text.into(), self.base.clone())
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("{0}")]
 pub struct SophiaTurtleError(pub TurtleError);
@@ -33,7 +111,7 @@ impl WithLocation for SophiaTurtleError {
     fn location(&self) -> Location {
         match self.0.textual_position() {
             None => Location::Unknown,
-            Some(pos) => Location::from_lico(pos.line_number() + 1, pos.byte_number() + 1),
+            Some(pos) => Location::from_lico(pos.line_number(), pos.byte_number()),
         }
     }
 }
@@ -53,6 +131,7 @@ mod test {
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::term::matcher::ANY;
     use sophia_term::StaticTerm;
+    use std::error::Error;
 
     #[test]
     fn test_simple_turtle_string() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -94,4 +173,27 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_parse_error() {
+        let turtle = "@prefix : <tag:>.\n:a :b :c.\n:a ex:b :c.\n";
+        let err = parse_str(turtle).for_each_triple(|_| ()).unwrap_err();
+        assert_eq!(err.message(), "unknown prefix 'ex'");
+        assert_eq!(err.token(), Some("ex:"));
+        assert!(err.hint().unwrap().contains("@prefix ex:"));
+        let err = err.with_snippet_from(turtle);
+        assert_eq!(err.snippet(), Some(":a ex:b :c."));
+        assert_eq!(err.line(), Some(3));
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let err = parse_str("<tag:a> <tag:b> <tag:c> ]")
+            .for_each_triple(|_| ())
+            .unwrap_err();
+        assert_eq!(err.token(), Some("]"));
+        assert_eq!(err.line(), Some(1));
+        assert_eq!(err.column(), Some(25));
+        assert!(err.source().is_some());
+    }
 }
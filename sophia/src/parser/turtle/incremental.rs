@@ -0,0 +1,521 @@
+//! Incremental parsing of Turtle documents, for editors and language servers.
+//!
+//! A [`TurtleDocument`] owns the text of a Turtle document,
+//! with the triples and diagnostics (parse errors) it contains.
+//! When the text is [edited](struct.TurtleDocument.html#method.edit),
+//! only the statements around the edit are parsed again:
+//! the following statements are reused (with their spans shifted),
+//! unless the edit changed the prefixes or base IRI in force after it.
+//!
+//! Unlike [`TurtleParser::parse_spanned`], parsing does not stop at the first error:
+//! an erroneous statement produces a [`Diagnostic`],
+//! and parsing resumes after the next `.` (see [`lexer`]).
+//!
+//! # Example
+//! ```
+//! use sophia::parser::turtle::TurtleParser;
+//!
+//! let mut doc = TurtleParser::default().parse_document(
+//!     "@prefix : <http://example.org/> .\n:a :p :b .\n:b :p :c .\n:c :p :d .\n",
+//! );
+//! assert_eq!(doc.triples().count(), 3);
+//!
+//! // break the second statement
+//! let reparsed = doc.edit(51..53, "");
+//! assert_eq!(&doc.text()[reparsed], "\n:b :p  .");
+//! assert_eq!(doc.triples().count(), 2);
+//! assert_eq!(doc.diagnostics().count(), 1);
+//!
+//! // and fix it
+//! doc.edit(51..51, ":z");
+//! assert_eq!(doc.triples().count(), 3);
+//! assert_eq!(doc.diagnostics().count(), 0);
+//! ```
+//!
+//! [`TurtleDocument`]: struct.TurtleDocument.html
+//! [`TurtleParser::parse_spanned`]: ../struct.TurtleParser.html#method.parse_spanned
+//! [`Diagnostic`]: struct.Diagnostic.html
+//! [`lexer`]: ../lexer/index.html
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use sophia_api::parser::ParseError;
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+use super::lexer::{tokenize, TokenKind};
+use super::spanned::SpannedSource;
+
+/// A Turtle document, parsed incrementally (see [module documentation](index.html)).
+#[derive(Clone, Debug)]
+pub struct TurtleDocument {
+    text: String,
+    base: Option<String>,
+    /// A prefix for generated blank node labels, not used in `text`
+    bnode_prefix: String,
+    /// The number of blank node labels generated so far (including for replaced statements)
+    bnodes: usize,
+    statements: Vec<Statement>,
+}
+
+/// A statement of a [`TurtleDocument`](struct.TurtleDocument.html).
+#[derive(Clone, Debug)]
+struct Statement {
+    span: Range<usize>,
+    triples: Vec<DocumentTriple>,
+    diagnostic: Option<Diagnostic>,
+    /// The base and prefixes after this statement, if it is a directive (or an error)
+    state: Option<Arc<State>>,
+}
+
+/// The base and prefixes in force at some point of a document.
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    base: Option<String>,
+    prefixes: HashMap<String, String>,
+}
+
+/// A triple of a [`TurtleDocument`](struct.TurtleDocument.html),
+/// with the span of each of its terms in the text
+/// (see [`SpannedTriple`](../spanned/struct.SpannedTriple.html)).
+#[derive(Clone, Debug)]
+pub struct DocumentTriple {
+    terms: [BoxTerm; 3],
+    spans: [Range<usize>; 3],
+    statement: Range<usize>,
+}
+
+impl DocumentTriple {
+    /// The byte ranges of the subject, predicate and object in the text.
+    pub fn spans(&self) -> &[Range<usize>; 3] {
+        &self.spans
+    }
+
+    /// The byte range of the whole statement producing this triple.
+    pub fn statement_span(&self) -> Range<usize> {
+        self.statement.clone()
+    }
+}
+
+impl Triple for DocumentTriple {
+    type Term = BoxTerm;
+    fn s(&self) -> &BoxTerm {
+        &self.terms[0]
+    }
+    fn p(&self) -> &BoxTerm {
+        &self.terms[1]
+    }
+    fn o(&self) -> &BoxTerm {
+        &self.terms[2]
+    }
+}
+
+/// A parse error in a [`TurtleDocument`](struct.TurtleDocument.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    span: Range<usize>,
+    message: String,
+    expected: Vec<String>,
+    hint: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(error: &ParseError, fallback: usize) -> Self {
+        Diagnostic {
+            span: error.byte_range().unwrap_or(fallback..fallback),
+            message: error.message().to_string(),
+            expected: error.expected().to_vec(),
+            hint: error.hint().map(str::to_string),
+        }
+    }
+
+    /// The byte range of the offending token in the text.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The message of this diagnostic.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The tokens that were expected instead of the offending token.
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    /// A hint on how to fix this error.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+impl TurtleDocument {
+    pub(crate) fn new(text: String, base: Option<String>) -> Self {
+        let mut doc = TurtleDocument {
+            text,
+            base,
+            bnode_prefix: String::new(),
+            bnodes: 0,
+            statements: vec![],
+        };
+        doc.reparse_all();
+        doc
+    }
+
+    /// The text of this document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The triples of this document, in the order of the text.
+    pub fn triples(&self) -> impl Iterator<Item = &DocumentTriple> + '_ {
+        self.statements.iter().flat_map(|s| s.triples.iter())
+    }
+
+    /// The parse errors of this document, in the order of the text.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> + '_ {
+        self.statements.iter().filter_map(|s| s.diagnostic.as_ref())
+    }
+
+    /// The prefixes in force at byte `offset` of the text
+    /// (e.g. to suggest completions).
+    pub fn prefixes_at(&self, offset: usize) -> HashMap<String, String> {
+        let before = self.statements.partition_point(|s| s.span.end <= offset);
+        self.state_before(before).prefixes
+    }
+
+    /// Replace the bytes in `range` by `new_text`, and update the triples and diagnostics.
+    ///
+    /// Return the byte range of the (new) text that was parsed again.
+    ///
+    /// # Panics
+    /// If `range` is out of bounds, or does not lie on character boundaries
+    /// (as [`String::replace_range`]).
+    pub fn edit(&mut self, range: Range<usize>, new_text: &str) -> Range<usize> {
+        self.text.replace_range(range.clone(), new_text);
+        let delta = new_text.len() as isize - range.len() as isize;
+        let edit_end = range.start + new_text.len();
+        // generated blank node labels must not clash with the labels written in the text
+        let label = format!("_:{}", self.bnode_prefix);
+        let mut from = range.start.saturating_sub(label.len());
+        while !self.text.is_char_boundary(from) {
+            from -= 1;
+        }
+        let mut to = (edit_end + label.len()).min(self.text.len());
+        while !self.text.is_char_boundary(to) {
+            to += 1;
+        }
+        if self.text[from..to].contains(&label) {
+            self.reparse_all();
+            return 0..self.text.len();
+        }
+
+        let first = self
+            .statements
+            .iter()
+            .position(|s| s.span.end >= range.start)
+            .unwrap_or(self.statements.len());
+        let restart = match first {
+            0 => 0,
+            _ => self.statements[first - 1].span.end,
+        };
+        let state = self.state_before(first);
+        let mut tail = self.statements.split_off(first);
+        // old statements ending after the edit: their ends are where parsing may synchronize
+        let after = tail
+            .iter()
+            .position(|s| s.span.end >= range.end)
+            .unwrap_or(tail.len());
+        let old_state = tail[..after]
+            .iter()
+            .rev()
+            .find_map(|s| s.state.clone())
+            .unwrap_or_else(|| Arc::new(state.clone()));
+        let tail = tail.split_off(after);
+        let end = self.parse(restart, state, tail, old_state, edit_end, delta);
+        restart..end
+    }
+
+    fn reparse_all(&mut self) {
+        self.bnode_prefix = SpannedSource::new(&self.text, None).bnodes().0.to_string();
+        self.bnodes = 0;
+        self.statements.clear();
+        let state = self.state_before(0);
+        let old_state = Arc::new(state.clone());
+        self.parse(0, state, vec![], old_state, 0, 0);
+    }
+
+    /// The base and prefixes in force before statement `i`.
+    fn state_before(&self, i: usize) -> State {
+        match self.statements[..i]
+            .iter()
+            .rev()
+            .find_map(|s| s.state.as_ref())
+        {
+            Some(state) => State::clone(state),
+            None => State {
+                base: self.base.clone(),
+                prefixes: HashMap::new(),
+            },
+        }
+    }
+
+    /// Parse statements from `restart` (in state `state`),
+    /// until the statements of `tail` can be reused after `edit_end`.
+    ///
+    /// `tail` and `old_state` (the state before `tail`) are relative to the text before the edit,
+    /// which shifted the text after `edit_end` by `delta` bytes.
+    /// Return the end of the last parsed statement.
+    fn parse(
+        &mut self,
+        restart: usize,
+        state: State,
+        tail: Vec<Statement>,
+        mut old_state: Arc<State>,
+        edit_end: usize,
+        delta: isize,
+    ) -> usize {
+        let text = &self.text;
+        let mut source = SpannedSource::resume(
+            text,
+            restart,
+            state.base,
+            state.prefixes,
+            self.bnode_prefix.clone(),
+            self.bnodes,
+        );
+        let mut tail = tail.into_iter().peekable();
+        let mut end = restart;
+        while let Some((span, result)) = source.next_statement() {
+            let statement = match result {
+                Ok(triples) => {
+                    let state = if triples.is_empty() {
+                        Some(snapshot(&source))
+                    } else {
+                        None
+                    };
+                    let triples = triples
+                        .into_iter()
+                        .map(|t| DocumentTriple {
+                            spans: t.spans().clone(),
+                            statement: t.statement_span(),
+                            terms: [
+                                BoxTerm::copy(t.s()),
+                                BoxTerm::copy(t.p()),
+                                BoxTerm::copy(t.o()),
+                            ],
+                        })
+                        .collect();
+                    Statement {
+                        span,
+                        triples,
+                        diagnostic: None,
+                        state,
+                    }
+                }
+                Err(error) => {
+                    let recovery = recovery_point(text, span.start, span.end);
+                    source.seek(recovery);
+                    Statement {
+                        span: span.start..recovery,
+                        triples: vec![],
+                        diagnostic: Some(Diagnostic::new(&error, span.end)),
+                        state: Some(snapshot(&source)),
+                    }
+                }
+            };
+            end = statement.span.end;
+            self.statements.push(statement);
+            if end < edit_end {
+                continue;
+            }
+            // skip the old statements overlapped by the parsed ones,
+            // and reuse the others if they start exactly here, in the same state
+            while let Some(old) = tail.peek() {
+                let old_end = (old.span.end as isize + delta) as usize;
+                if old_end > end {
+                    break;
+                }
+                if let Some(state) = tail.next().unwrap().state {
+                    old_state = state;
+                }
+                if old_end == end && *old_state == *snapshot(&source) {
+                    self.bnodes = source.bnodes().1;
+                    self.statements.extend(tail.map(|mut s| {
+                        s.shift(delta);
+                        s
+                    }));
+                    return end;
+                }
+            }
+        }
+        self.bnodes = source.bnodes().1;
+        end
+    }
+}
+
+impl Statement {
+    fn shift(&mut self, delta: isize) {
+        shift(&mut self.span, delta);
+        if let Some(diagnostic) = &mut self.diagnostic {
+            shift(&mut diagnostic.span, delta);
+        }
+        for triple in &mut self.triples {
+            shift(&mut triple.statement, delta);
+            for span in &mut triple.spans {
+                shift(span, delta);
+            }
+        }
+    }
+}
+
+fn shift(range: &mut Range<usize>, delta: isize) {
+    range.start = (range.start as isize + delta) as usize;
+    range.end = (range.end as isize + delta) as usize;
+}
+
+fn snapshot(source: &SpannedSource) -> Arc<State> {
+    Arc::new(State {
+        base: source.base().cloned(),
+        prefixes: source.prefixes().clone(),
+    })
+}
+
+/// Where to resume parsing after an error at `error` in the statement starting at `start`:
+/// after the first `.` following the error.
+fn recovery_point(text: &str, start: usize, error: usize) -> usize {
+    tokenize(&text[start..])
+        .map(|t| (t.kind(), t.text(), start + t.span().end))
+        .find(|(kind, txt, end)| *kind == TokenKind::Punctuation && *txt == "." && *end > error)
+        .map(|(_, _, end)| end)
+        .unwrap_or(text.len())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::term::{TTerm, TermKind};
+
+    /// The triples and diagnostics of `doc`, ignoring blank node labels.
+    fn summary(doc: &TurtleDocument) -> (Vec<String>, Vec<Diagnostic>) {
+        let term = |t: &BoxTerm| match t.kind() {
+            TermKind::BlankNode => "_".to_string(),
+            _ => t.value().to_string(),
+        };
+        let triples = doc
+            .triples()
+            .map(|t| {
+                format!(
+                    "{} {} {} {:?} {:?}",
+                    term(t.s()),
+                    term(t.p()),
+                    term(t.o()),
+                    t.spans(),
+                    t.statement_span()
+                )
+            })
+            .collect();
+        (triples, doc.diagnostics().cloned().collect())
+    }
+
+    fn check_edit(text: &str, range: Range<usize>, new_text: &str) -> Range<usize> {
+        let mut doc = TurtleDocument::new(text.to_string(), None);
+        let reparsed = doc.edit(range.clone(), new_text);
+        let mut expected = text.to_string();
+        expected.replace_range(range, new_text);
+        let full = TurtleDocument::new(expected, None);
+        assert_eq!(doc.text(), full.text());
+        assert_eq!(summary(&doc), summary(&full), "{:?}", doc.text());
+        reparsed
+    }
+
+    const TTL: &str = "@prefix : <tag:> .\n\
+                       :a :p [ :q 1 ] .\n\
+                       :b :p ( 1 2 ) .\n\
+                       :c :p \"c\" .\n\
+                       :d :p :e .\n";
+
+    #[test]
+    fn errors() {
+        let doc = TurtleDocument::new(":a :p .\n<tag:b> <tag:p> <tag:c> .\n:d".to_string(), None);
+        assert_eq!(doc.triples().count(), 1);
+        let diagnostics: Vec<_> = doc.diagnostics().collect();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message(), "unknown prefix ''");
+        assert_eq!(diagnostics[0].span(), 0..1);
+        assert!(diagnostics[0].hint().is_some());
+        assert_eq!(diagnostics[1].span(), 34..35);
+    }
+
+    #[test]
+    fn local_edits() {
+        // changing an object only reparses its statement
+        let start = TTL.find(":c").unwrap();
+        let reparsed = check_edit(TTL, start + 7..start + 8, "cc");
+        assert_eq!(&TTL[start..start + 11], ":c :p \"c\" .");
+        assert_eq!(reparsed, start - 1..start + 12);
+        // removing a statement
+        check_edit(TTL, start..start + 12, "");
+        // inserting a statement
+        check_edit(TTL, start..start, ":x :p [] .\n");
+        // merging two statements
+        check_edit(TTL, start - 2..start - 1, ";");
+        // editing inside a blank node and a collection
+        check_edit(TTL, 30..31, "2");
+        check_edit(TTL, 46..47, "3 4");
+        // breaking and fixing a statement
+        check_edit(TTL, start + 10..start + 11, "");
+        check_edit(TTL, 0..0, "# comment\n");
+        check_edit(TTL, TTL.len()..TTL.len(), ":z :p :q");
+    }
+
+    #[test]
+    fn all_positions() {
+        for i in 0..TTL.len() {
+            check_edit(TTL, i..i + 1, "");
+            check_edit(TTL, i..i, " .");
+            check_edit(TTL, i..i, "\"");
+        }
+    }
+
+    #[test]
+    fn prefix_edits() {
+        // changing a prefix reparses everything after it
+        let reparsed = check_edit(TTL, 11..15, "other:");
+        assert_eq!(reparsed.end, TTL.len() + 1);
+        check_edit(TTL, 0..19, "");
+        check_edit(TTL, 0..1, "");
+    }
+
+    #[test]
+    fn label_clash() {
+        let mut doc = TurtleDocument::new(TTL.to_string(), None);
+        let prefix = doc.bnode_prefix.clone();
+        let end = doc.text().len();
+        let reparsed = doc.edit(end..end, &format!("_:{}1 :p :q .", prefix));
+        assert_eq!(reparsed, 0..doc.text().len());
+        assert_ne!(doc.bnode_prefix, prefix);
+        let bnodes: std::collections::HashSet<_> = doc
+            .triples()
+            .flat_map(|t| vec![t.s().clone(), t.o().clone()])
+            .filter(|t| t.kind() == TermKind::BlankNode)
+            .collect();
+        assert_eq!(bnodes.len(), 4);
+    }
+
+    #[test]
+    fn prefixes_at() {
+        let doc = TurtleDocument::new(format!("{}@prefix x: <tag:x/> .\n", TTL), None);
+        assert_eq!(doc.prefixes_at(0).len(), 0);
+        assert_eq!(doc.prefixes_at(20).len(), 1);
+        assert_eq!(doc.prefixes_at(TTL.len() + 25).len(), 2);
+    }
+}
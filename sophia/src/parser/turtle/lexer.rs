@@ -0,0 +1,477 @@
+//! Error-tolerant tokenizer for Turtle and TriG, for tooling.
+//!
+//! [`tokenize`] splits a document into [`Token`]s, each with its [`TokenKind`]
+//! and its byte range (span) in the source.
+//! Unlike the parsers, it never stops at the first error:
+//! malformed tokens (e.g. an unterminated string, or an invalid escape sequence)
+//! are still produced, with an [error message](struct.Token.html#method.error),
+//! and unrecognized characters are grouped into [`Unknown`] tokens.
+//! Whitespace and comments are tokens too,
+//! so the concatenation of all tokens is always the whole source.
+//!
+//! This makes it suitable for syntax highlighters, formatters and editors,
+//! which must cope with documents being typed.
+//! It does not check the grammar (i.e. the order of tokens),
+//! nor whether prefixes are declared.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::turtle::lexer::{tokenize, TokenKind};
+//!
+//! let ttl = "<tag:s> a \"oops .";
+//! let tokens: Vec<_> = tokenize(ttl).collect();
+//! let kinds: Vec<_> = tokens.iter().map(|t| t.kind()).collect();
+//! assert_eq!(
+//!     kinds,
+//!     vec![
+//!         TokenKind::IriRef,
+//!         TokenKind::Whitespace,
+//!         TokenKind::Keyword,
+//!         TokenKind::Whitespace,
+//!         TokenKind::String,
+//!     ]
+//! );
+//! assert_eq!(tokens[4].error(), Some("unterminated string"));
+//! assert_eq!(&ttl[tokens[4].span()], "\"oops .");
+//! ```
+//!
+//! [`tokenize`]: fn.tokenize.html
+//! [`Token`]: struct.Token.html
+//! [`TokenKind`]: enum.TokenKind.html
+//! [`Unknown`]: enum.TokenKind.html#variant.Unknown
+
+use std::ops::Range;
+
+use crate::parser::nq12::is_pn_chars;
+
+/// The kinds of [`Token`](struct.Token.html)s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Spaces, tabs and line breaks.
+    Whitespace,
+    /// A comment, from `#` to the end of the line (excluded).
+    Comment,
+    /// An IRI between angle brackets, e.g. `<http://example.org/>`.
+    IriRef,
+    /// A prefixed name (e.g. `ex:alice`), or a prefix in a declaration (e.g. `ex:`).
+    PrefixedName,
+    /// A labelled blank node, e.g. `_:b1`.
+    BlankNode,
+    /// A quoted string (short or long, with single or double quotes),
+    /// without its language tag or datatype.
+    String,
+    /// A language tag following a string, including the `@`, e.g. `@en`.
+    LangTag,
+    /// An integer, e.g. `42`.
+    Integer,
+    /// A decimal, e.g. `4.2`.
+    Decimal,
+    /// A double, e.g. `4.2e1`.
+    Double,
+    /// `true` or `false`.
+    Boolean,
+    /// `a`, a directive (`@prefix`, `@base`, `PREFIX`, `BASE`) or `GRAPH`.
+    Keyword,
+    /// One of `.` `;` `,` `[` `]` `(` `)` `{` `}` `^^` `<<` `>>` `{|` `|}`.
+    Punctuation,
+    /// Characters that can not start any token.
+    Unknown,
+}
+
+impl TokenKind {
+    /// Whether this token is ignored by parsers (whitespace and comments).
+    pub fn is_trivia(self) -> bool {
+        matches!(self, TokenKind::Whitespace | TokenKind::Comment)
+    }
+}
+
+/// A token of a Turtle or TriG document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    error: Option<&'static str>,
+}
+
+impl<'a> Token<'a> {
+    /// The kind of this token.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// The text of this token, as it appears in the source.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The byte range of this token in the source.
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.start + self.text.len()
+    }
+
+    /// Why this token is malformed, if it is.
+    ///
+    /// [`Unknown`](enum.TokenKind.html#variant.Unknown) tokens always have an error.
+    pub fn error(&self) -> Option<&'static str> {
+        self.error
+    }
+}
+
+/// Split `txt` into tokens (see [module documentation](index.html)).
+pub fn tokenize(txt: &str) -> Lexer<'_> {
+    Lexer { txt, pos: 0 }
+}
+
+/// Iterator produced by [`tokenize`](fn.tokenize.html).
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    txt: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    /// The byte offset of the next token in the source.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Lex one token starting at the current position, and return its kind, length and error.
+    fn lex(&self) -> (TokenKind, usize, Option<&'static str>) {
+        use TokenKind::*;
+        let rest = &self.txt[self.pos..];
+        let c = rest.chars().next().unwrap();
+        match c {
+            ' ' | '\t' | '\r' | '\n' => {
+                let len = rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+                (Whitespace, len, None)
+            }
+            '#' => (Comment, rest.find(['\r', '\n']).unwrap_or(rest.len()), None),
+            '<' if rest.starts_with("<<") => (Punctuation, 2, None),
+            '<' => lex_iriref(rest),
+            '>' if rest.starts_with(">>") => (Punctuation, 2, None),
+            '{' if rest.starts_with("{|") => (Punctuation, 2, None),
+            '|' if rest.starts_with("|}") => (Punctuation, 2, None),
+            '^' if rest.starts_with("^^") => (Punctuation, 2, None),
+            '.' if !rest[1..].starts_with(|c: char| c.is_ascii_digit()) => (Punctuation, 1, None),
+            ';' | ',' | '[' | ']' | '(' | ')' | '{' | '}' => (Punctuation, 1, None),
+            '"' | '\'' => lex_string(rest, c),
+            '@' => {
+                let len = 1 + word_len(&rest[1..], |c| c.is_ascii_alphanumeric() || c == '-');
+                match &rest[..len] {
+                    "@prefix" | "@base" => (Keyword, len, None),
+                    "@" => (LangTag, len, Some("empty language tag")),
+                    _ => (LangTag, len, None),
+                }
+            }
+            '_' if rest.starts_with("_:") => {
+                let len = 2 + name_len(&rest[2..]);
+                let error = if len == 2 {
+                    Some("empty blank node label")
+                } else {
+                    None
+                };
+                (BlankNode, len, error)
+            }
+            '+' | '-' | '.' | '0'..='9' => lex_number(rest),
+            c if c == ':' || is_pn_chars(c) => lex_name(rest),
+            _ => {
+                let len = word_len(rest, |c| {
+                    !c.is_whitespace() && !"<>\"'#.;,[](){}".contains(c) && !is_pn_chars(c)
+                });
+                (Unknown, len.max(c.len_utf8()), Some("unexpected character"))
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos >= self.txt.len() {
+            return None;
+        }
+        let (kind, len, error) = self.lex();
+        let start = self.pos;
+        self.pos += len;
+        Some(Token {
+            kind,
+            text: &self.txt[start..self.pos],
+            start,
+            error,
+        })
+    }
+}
+
+fn lex_iriref(rest: &str) -> (TokenKind, usize, Option<&'static str>) {
+    let mut error = None;
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            '>' => return (TokenKind::IriRef, i + 1, error),
+            '\\' if escape_len(&rest[i..], false).is_none() => {
+                error = error.or(Some("invalid escape sequence"))
+            }
+            c if c <= ' ' => return (TokenKind::IriRef, i, Some("unterminated IRI")),
+            '<' | '"' | '{' | '}' | '|' | '^' | '`' => {
+                error = error.or(Some("invalid character in IRI"))
+            }
+            _ => (),
+        }
+    }
+    (TokenKind::IriRef, rest.len(), Some("unterminated IRI"))
+}
+
+fn lex_string(rest: &str, quote: char) -> (TokenKind, usize, Option<&'static str>) {
+    let long_delimiter = [quote; 3].iter().collect::<String>();
+    let long = rest.starts_with(&long_delimiter);
+    let mut i = if long { 3 } else { 1 };
+    let mut error = None;
+    while let Some(c) = rest[i..].chars().next() {
+        if long && rest[i..].starts_with(&long_delimiter) {
+            // in long strings, up to 2 quotes can precede the closing delimiter
+            let quotes = rest[i..].len() - rest[i..].trim_start_matches(quote).len();
+            return (TokenKind::String, i + quotes, error);
+        }
+        match c {
+            c if c == quote && !long => return (TokenKind::String, i + 1, error),
+            '\r' | '\n' if !long => return (TokenKind::String, i, Some("unterminated string")),
+            '\\' => match escape_len(&rest[i..], true) {
+                Some(len) => {
+                    i += len;
+                    continue;
+                }
+                None => error = error.or(Some("invalid escape sequence")),
+            },
+            _ => (),
+        }
+        i += c.len_utf8();
+    }
+    (TokenKind::String, rest.len(), Some("unterminated string"))
+}
+
+/// The length of the escape sequence at the start of `txt`, if valid.
+///
+/// `echar` indicates whether the escape sequences of strings (e.g. `\n`) are allowed.
+fn escape_len(txt: &str, echar: bool) -> Option<usize> {
+    match txt[1..].chars().next()? {
+        'u' | 'U' => {
+            let len = if txt[1..].starts_with('u') { 4 } else { 8 };
+            let hex = txt.get(2..2 + len)?;
+            if hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some(2 + len)
+            } else {
+                None
+            }
+        }
+        't' | 'b' | 'n' | 'r' | 'f' | '"' | '\'' | '\\' if echar => Some(2),
+        _ => None,
+    }
+}
+
+fn lex_number(rest: &str) -> (TokenKind, usize, Option<&'static str>) {
+    let digits = |txt: &str| txt.len() - txt.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let mut i = 0;
+    if rest.starts_with(['+', '-']) {
+        i += 1;
+    }
+    let int_digits = digits(&rest[i..]);
+    i += int_digits;
+    let mut kind = TokenKind::Integer;
+    if rest[i..].starts_with('.') && rest[i + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+        kind = TokenKind::Decimal;
+        i += 1 + digits(&rest[i + 1..]);
+    }
+    if rest[i..].starts_with(['e', 'E']) {
+        let mut j = i + 1;
+        if rest[j..].starts_with(['+', '-']) {
+            j += 1;
+        }
+        let exp_digits = digits(&rest[j..]);
+        if exp_digits > 0 {
+            kind = TokenKind::Double;
+            i = j + exp_digits;
+        }
+    }
+    if i == 0 || (int_digits == 0 && kind == TokenKind::Integer) {
+        // a lone sign
+        return (TokenKind::Unknown, i.max(1), Some("unexpected character"));
+    }
+    (kind, i, None)
+}
+
+/// Lex a prefixed name or a bare word (keyword or boolean).
+fn lex_name(rest: &str) -> (TokenKind, usize, Option<&'static str>) {
+    let prefix_len = word_len(rest, |c| c != ':' && (is_pn_chars(c) || c == '.'));
+    if rest[prefix_len..].starts_with(':') {
+        let local_len = local_name_len(&rest[prefix_len + 1..]);
+        let error = if rest[..prefix_len].starts_with('.') || rest[..prefix_len].ends_with('.') {
+            Some("invalid prefix")
+        } else {
+            None
+        };
+        return (
+            TokenKind::PrefixedName,
+            prefix_len + 1 + local_len.0,
+            error.or(local_len.1),
+        );
+    }
+    let len = name_len(rest);
+    let word = &rest[..len];
+    let kind = if word == "a"
+        || ["PREFIX", "BASE", "GRAPH"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(word))
+    {
+        TokenKind::Keyword
+    } else if word == "true" || word == "false" {
+        TokenKind::Boolean
+    } else {
+        return (TokenKind::Unknown, len, Some("unknown keyword"));
+    };
+    (kind, len, None)
+}
+
+/// The length of the local part of a prefixed name at the start of `txt`,
+/// and the error it contains, if any.
+fn local_name_len(txt: &str) -> (usize, Option<&'static str>) {
+    let mut i = 0;
+    let mut error = None;
+    while let Some(c) = txt[i..].chars().next() {
+        if is_pn_chars(c) || c == '.' {
+            i += c.len_utf8();
+        } else if c == '%' {
+            let hex = txt.get(i + 1..i + 3);
+            if !hex.is_some_and(|h| h.chars().all(|c| c.is_ascii_hexdigit())) {
+                error = error.or(Some("invalid percent-encoding"));
+            }
+            i += 1;
+        } else if c == '\\' {
+            match txt[i + 1..].chars().next() {
+                Some(e) if "_~.-!$&'()*+,;=/?#@%".contains(e) => i += 2,
+                _ => {
+                    error = error.or(Some("invalid escape sequence"));
+                    i += 1;
+                }
+            }
+        } else {
+            break;
+        }
+    }
+    // the local name can not end with '.'
+    while txt[..i].ends_with('.') && !txt[..i].ends_with("\\.") {
+        i -= 1;
+    }
+    (i, error)
+}
+
+/// The length of a blank node label or bare word at the start of `txt`.
+fn name_len(txt: &str) -> usize {
+    let mut len = word_len(txt, |c| c != ':' && (is_pn_chars(c) || c == '.'));
+    // names can not end with '.'
+    while txt[..len].ends_with('.') {
+        len -= 1;
+    }
+    len
+}
+
+/// The length of the longest prefix of `txt` made of characters satisfying `pred`.
+fn word_len<P: Fn(char) -> bool>(txt: &str, pred: P) -> usize {
+    txt.find(|c: char| !pred(c)).unwrap_or(txt.len())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use TokenKind::*;
+
+    fn lex(txt: &str) -> Vec<(TokenKind, &str, Option<&'static str>)> {
+        tokenize(txt)
+            .filter(|t| !t.kind().is_trivia())
+            .map(|t| (t.kind(), t.text(), t.error()))
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let ttl = r#"@prefix ex: <http://example.org/> .
+PREFIX : <tag:>
+ex:s a ex:C ; ex:p "x"@en-GB, 'y'^^ex:dt, """long "quoted" text""", -1, 2.5, .5e3, true ;
+  ex:q [ ex:r ( _:b1 :local.name\,esc ) ] . # done
+GRAPH ex:g { << ex:s ex:p ex:o >> ex:q 1 {| ex:r 2 |} . }
+"#;
+        let tokens = lex(ttl);
+        assert!(tokens.iter().all(|t| t.2.is_none()), "{:?}", tokens);
+        let get = |kind| {
+            tokens
+                .iter()
+                .filter(|t| t.0 == kind)
+                .map(|t| t.1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(get(Keyword), vec!["@prefix", "PREFIX", "a", "GRAPH"]);
+        assert_eq!(
+            get(String),
+            vec!["\"x\"", "'y'", "\"\"\"long \"quoted\" text\"\"\""]
+        );
+        assert_eq!(get(LangTag), vec!["@en-GB"]);
+        assert_eq!(get(Integer), vec!["-1", "1", "2"]);
+        assert_eq!(get(Decimal), vec!["2.5"]);
+        assert_eq!(get(Double), vec![".5e3"]);
+        assert_eq!(get(Boolean), vec!["true"]);
+        assert_eq!(get(BlankNode), vec!["_:b1"]);
+        assert!(get(PrefixedName).contains(&":local.name\\,esc"));
+        assert!(get(PrefixedName).contains(&"ex:"));
+        assert_eq!(
+            get(Punctuation).join(" "),
+            ". ; , ^^ , , , , , ; [ ( ) ] . { << >> {| |} . }"
+        );
+        assert_eq!(get(IriRef), vec!["<http://example.org/>", "<tag:>"]);
+    }
+
+    #[test]
+    fn lossless() {
+        let txt = "@prefix : <tag:> . # comment\r\n:a :b \"c\\qd\n<e f> §§ 'x";
+        let tokens: Vec<_> = tokenize(txt).collect();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.text())
+                .collect::<std::string::String>(),
+            txt
+        );
+        for t in &tokens {
+            assert_eq!(&txt[t.span()], t.text());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            lex(":a :b \"c\\qd\n<e f> §§ 'x"),
+            vec![
+                (PrefixedName, ":a", None),
+                (PrefixedName, ":b", None),
+                (String, "\"c\\qd", Some("unterminated string")),
+                (IriRef, "<e", Some("unterminated IRI")),
+                (Unknown, "f", Some("unknown keyword")),
+                (Unknown, ">", Some("unexpected character")),
+                (Unknown, "§§", Some("unexpected character")),
+                (String, "'x", Some("unterminated string")),
+            ]
+        );
+        assert_eq!(
+            lex("\"a\\qb\" _: @ +"),
+            vec![
+                (String, "\"a\\qb\"", Some("invalid escape sequence")),
+                (BlankNode, "_:", Some("empty blank node label")),
+                (LangTag, "@", Some("empty language tag")),
+                (Unknown, "+", Some("unexpected character")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Comments and directives of a Turtle document, captured for lossless round-trips.
+//!
+//! [`TurtleParser::parse_with_metadata`] works as [`parse_spanned`],
+//! but also records the parts of the document that do not produce triples:
+//! comments, prefix and base declarations (in their original order and syntax),
+//! and blank lines.
+//! Each of them is [anchored] to the statements around it,
+//! so that the [Turtle serializer] can replay them at the right place,
+//! even if the triples were modified in between.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::turtle::TurtleParser;
+//! use sophia::serializer::turtle::{TurtleConfig, TurtleSerializer};
+//! use sophia::serializer::{Stringifier, TripleSerializer};
+//!
+//! let ttl = "PREFIX : <http://example.org/>\n# people\n:alice :knows :bob . # old friends\n";
+//! let mut source = TurtleParser::default().parse_with_metadata(ttl);
+//! let triples: Vec<_> = source.by_ref().collect::<Result<_, _>>()?;
+//! let mut config = TurtleConfig::default();
+//! config.set_metadata(Some(source.metadata()));
+//! let out = TurtleSerializer::new_stringifier_with_config(config)
+//!     .serialize_graph(&triples)?
+//!     .to_string();
+//! assert_eq!(out, ttl);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TurtleParser::parse_with_metadata`]: ../struct.TurtleParser.html#method.parse_with_metadata
+//! [`parse_spanned`]: ../struct.TurtleParser.html#method.parse_spanned
+//! [anchored]: enum.Anchor.html
+//! [Turtle serializer]: ../../../serializer/turtle/index.html
+
+use std::ops::Range;
+
+use sophia_term::BoxTerm;
+
+/// A part of a Turtle document not producing any triple.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetadataItem {
+    /// A comment, without its leading `#`.
+    Comment(String),
+    /// A prefix declaration, in the `PREFIX` syntax if `sparql` is true,
+    /// in the `@prefix` syntax otherwise.
+    Prefix {
+        prefix: String,
+        iri: String,
+        sparql: bool,
+    },
+    /// A base declaration, in the `BASE` syntax if `sparql` is true,
+    /// in the `@base` syntax otherwise.
+    Base { iri: String, sparql: bool },
+    /// One or more consecutive blank lines.
+    BlankLine,
+}
+
+/// The position of a [`MetadataItem`](enum.MetadataItem.html) relative to the statements of the document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Anchor {
+    /// Before the first statement.
+    Start,
+    /// Before the statement about the given subject
+    /// (including items inside that statement).
+    Before(BoxTerm),
+    /// At the end of the line of the statement about the given subject
+    /// (only for comments).
+    After(BoxTerm),
+    /// After the last statement.
+    End,
+}
+
+/// A [`MetadataItem`](enum.MetadataItem.html), with its anchor and span in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetadataEntry {
+    pub(crate) item: MetadataItem,
+    pub(crate) anchor: Anchor,
+    pub(crate) span: Range<usize>,
+}
+
+impl MetadataEntry {
+    /// The item of this entry.
+    pub fn item(&self) -> &MetadataItem {
+        &self.item
+    }
+
+    /// Where this entry is in the document, relative to statements.
+    pub fn anchor(&self) -> &Anchor {
+        &self.anchor
+    }
+
+    /// The byte range of this entry in the source.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+/// The comments and directives of a Turtle document, in the order of the source.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceMetadata {
+    pub(crate) entries: Vec<MetadataEntry>,
+}
+
+impl SourceMetadata {
+    /// The entries of this metadata, in the order of the source.
+    pub fn entries(&self) -> &[MetadataEntry] {
+        &self.entries
+    }
+
+    /// Whether no metadata was captured.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,1246 @@
+//! Native Turtle parser reporting the position of triples and terms in the source.
+//!
+//! [`TurtleParser::parse_spanned`] parses a string already in memory,
+//! and produces [`SpannedTriple`]s, which give the byte range (span)
+//! of each of their terms in the source document.
+//! This allows editors and linters to map triples back to their location,
+//! e.g. to report diagnostics or to rewrite part of the document.
+//!
+//! As with the [zero-copy N-Quads parser], the terms of the produced triples
+//! borrow from the source whenever possible (as [`MownTerm`]s);
+//! IRIs built from prefixed names or resolved against a base IRI,
+//! and literals containing escape sequences, are copied.
+//!
+//! Some terms are not written as such in the source.
+//! * The span of `a` is used for `rdf:type`.
+//! * Blank nodes introduced by `[ ... ]` have the span of the opening bracket
+//!   as the subject of the triples inside the brackets,
+//!   and the span of the whole brackets otherwise.
+//! * In collections, list nodes, `rdf:first` and `rdf:rest` have the span of the corresponding item,
+//!   and `rdf:nil` has the span of the closing parenthesis.
+//! * The blank nodes replacing quoted triples (see below)
+//!   and the predicates of their descriptions have the span of the whole quoted triple.
+//!
+//! ```
+//! # use sophia::triple::Triple;
+//! # use sophia_api::term::{CopyTerm, TTerm};
+//! use sophia::parser::turtle::TurtleParser;
+//!
+//! let ttl = "@prefix : <http://example.org/>.\n:alice :knows :bob, :carol.";
+//! let triples: Vec<_> = TurtleParser::default()
+//!     .parse_spanned(ttl)
+//!     .collect::<Result<_, _>>()?;
+//! assert_eq!(triples.len(), 2);
+//! assert_eq!(triples[1].o().value(), "http://example.org/carol");
+//! assert_eq!(&ttl[triples[1].spans()[2].clone()], ":carol");
+//! assert_eq!(&ttl[triples[1].span()], ":alice :knows :bob, :carol");
+//! assert_eq!(&ttl[triples[1].statement_span()], ":alice :knows :bob, :carol.");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Quoted triples
+//!
+//! [`TurtleParser::parse_star`] also accepts the [RDF-star] quoted triples (`<< s p o >>`),
+//! as subject or object of other triples.
+//! As in the [N-Quads 1.2 parser], each of them is replaced by a blank node
+//! (always the same for the same quoted triple),
+//! described with the RDF reification vocabulary before the first statement containing it.
+//!
+//! ```
+//! # use sophia::triple::Triple;
+//! # use sophia_api::ns::rdf;
+//! use sophia::parser::turtle::TurtleParser;
+//!
+//! let ttl = "@prefix : <http://example.org/>.\n<< :alice :knows :bob >> :since 2020.";
+//! let triples: Vec<_> = TurtleParser::default()
+//!     .parse_star(ttl)
+//!     .collect::<Result<_, _>>()?;
+//! assert_eq!(triples.len(), 5);
+//! assert!(triples.iter().any(|t| rdf::subject == *t.p()));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TurtleParser::parse_spanned`]: ../struct.TurtleParser.html#method.parse_spanned
+//! [`TurtleParser::parse_star`]: ../struct.TurtleParser.html#method.parse_star
+//! [RDF-star]: https://www.w3.org/2021/12/rdf-star.html
+//! [N-Quads 1.2 parser]: ../../nq12/index.html
+//! [`SpannedTriple`]: struct.SpannedTriple.html
+//! [zero-copy N-Quads parser]: ../../nq12/index.html#zero-copy-parsing
+//! [`MownTerm`]: ../../../term/type.MownTerm.html
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use mownstr::MownStr;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::ParseError;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::Triple;
+use sophia_term::iri::resolve::{IriParsed, Resolve};
+use sophia_term::iri::{is_absolute_iri_ref, Iri};
+use sophia_term::{BoxTerm, MownTerm};
+
+use super::metadata::{Anchor, MetadataEntry, MetadataItem, SourceMetadata};
+
+use crate::parser::nq12::is_pn_chars;
+
+/// A triple, with the span of each of its terms in the source.
+#[derive(Clone, Debug)]
+pub struct SpannedTriple<'a> {
+    terms: [MownTerm<'a>; 3],
+    spans: [Range<usize>; 3],
+    statement: Range<usize>,
+}
+
+impl<'a> SpannedTriple<'a> {
+    /// The byte ranges of the subject, predicate and object in the source.
+    pub fn spans(&self) -> &[Range<usize>; 3] {
+        &self.spans
+    }
+
+    /// The byte range covering the subject, predicate and object in the source.
+    ///
+    /// Note that, in abbreviated statements (using `;` or `,`),
+    /// this range also covers other triples.
+    pub fn span(&self) -> Range<usize> {
+        let start = self.spans.iter().map(|s| s.start).min().unwrap();
+        let end = self.spans.iter().map(|s| s.end).max().unwrap();
+        start..end
+    }
+
+    /// The byte range of the whole statement (including the final `.`) producing this triple.
+    ///
+    /// All the triples produced by the same statement have the same statement span.
+    pub fn statement_span(&self) -> Range<usize> {
+        self.statement.clone()
+    }
+
+    /// The terms of this triple.
+    pub fn into_terms(self) -> [MownTerm<'a>; 3] {
+        self.terms
+    }
+}
+
+impl<'a> Triple for SpannedTriple<'a> {
+    type Term = MownTerm<'a>;
+    fn s(&self) -> &MownTerm<'a> {
+        &self.terms[0]
+    }
+    fn p(&self) -> &MownTerm<'a> {
+        &self.terms[1]
+    }
+    fn o(&self) -> &MownTerm<'a> {
+        &self.terms[2]
+    }
+}
+
+/// A term, with its span in the source.
+type Spanned<'a> = (MownTerm<'a>, Range<usize>);
+
+/// Iterator (and therefore TripleSource) produced by
+/// [`TurtleParser::parse_spanned`](../struct.TurtleParser.html#method.parse_spanned).
+///
+/// Triples are produced one statement at a time;
+/// the source stops after the first error.
+pub struct SpannedSource<'a> {
+    txt: &'a str,
+    pos: usize,
+    base: Option<String>,
+    prefixes: HashMap<String, String>,
+    /// A prefix for generated blank node labels, not used in `txt`
+    bnode_prefix: String,
+    bnodes: usize,
+    pending: VecDeque<SpannedTriple<'a>>,
+    failed: bool,
+    /// Whether comments and directives are captured (see [`metadata`](../metadata/index.html))
+    capture: bool,
+    metadata: Vec<MetadataEntry>,
+    /// Captured items waiting for the next statement to be anchored
+    floating: Vec<(MetadataItem, Range<usize>)>,
+    /// The subject and end of the last statement
+    last: Option<(BoxTerm, usize)>,
+    /// Whether quoted triples are accepted (see [module documentation](index.html#quoted-triples))
+    star: bool,
+    /// The blank nodes replacing the quoted triples encountered so far
+    quoted: HashMap<[BoxTerm; 3], MownTerm<'a>>,
+    /// Whether prefixed names with an undefined prefix are kept verbatim as IRIs
+    undefined_prefixes: bool,
+}
+
+impl<'a> SpannedSource<'a> {
+    pub(crate) fn new(txt: &'a str, base: Option<String>) -> Self {
+        let mut bnode_prefix = "b".to_string();
+        while txt.contains(&format!("_:{}", bnode_prefix)) {
+            bnode_prefix.push('b');
+        }
+        SpannedSource {
+            txt,
+            pos: 0,
+            base,
+            prefixes: HashMap::new(),
+            bnode_prefix,
+            bnodes: 0,
+            pending: VecDeque::new(),
+            failed: false,
+            capture: false,
+            metadata: vec![],
+            floating: vec![],
+            last: None,
+            star: false,
+            quoted: HashMap::new(),
+            undefined_prefixes: false,
+        }
+    }
+
+    /// Capture comments and directives while parsing.
+    pub(crate) fn capturing_metadata(mut self) -> Self {
+        self.capture = true;
+        self
+    }
+
+    /// Accept quoted triples while parsing.
+    pub(crate) fn accepting_quoted_triples(mut self) -> Self {
+        self.star = true;
+        self
+    }
+
+    /// Accept prefixed names with an undefined prefix, keeping them verbatim as IRIs
+    /// (see [`ParserConfig::set_undefined_prefixes`](../../config/struct.ParserConfig.html#method.set_undefined_prefixes)).
+    pub(crate) fn accepting_undefined_prefixes(mut self) -> Self {
+        self.undefined_prefixes = true;
+        self
+    }
+
+    /// The comments and directives parsed so far,
+    /// if this source was created by
+    /// [`TurtleParser::parse_with_metadata`](../struct.TurtleParser.html#method.parse_with_metadata)
+    /// (otherwise, this is always empty).
+    ///
+    /// Items after the last statement parsed so far are anchored to the [end](../metadata/enum.Anchor.html#variant.End)
+    /// of the document, so this should be called once the source is exhausted.
+    pub fn metadata(&self) -> SourceMetadata {
+        let mut entries = self.metadata.clone();
+        entries.extend(self.floating.iter().map(|(item, span)| MetadataEntry {
+            item: item.clone(),
+            anchor: Anchor::End,
+            span: span.clone(),
+        }));
+        SourceMetadata { entries }
+    }
+
+    /// The prefixes declared so far in the source.
+    pub fn prefixes(&self) -> &HashMap<String, String> {
+        &self.prefixes
+    }
+
+    /// Resume parsing `txt` at `pos` (which must be between two statements),
+    /// with the given base, prefixes and blank node labels.
+    pub(crate) fn resume(
+        txt: &'a str,
+        pos: usize,
+        base: Option<String>,
+        prefixes: HashMap<String, String>,
+        bnode_prefix: String,
+        bnodes: usize,
+    ) -> Self {
+        SpannedSource {
+            txt,
+            pos,
+            prefixes,
+            bnode_prefix,
+            bnodes,
+            ..SpannedSource::new("", base)
+        }
+    }
+
+    /// The base IRI declared so far in the source (or given to the parser).
+    pub(crate) fn base(&self) -> Option<&String> {
+        self.base.as_ref()
+    }
+
+    /// The prefix of generated blank node labels, and the number of labels generated so far.
+    pub(crate) fn bnodes(&self) -> (&str, usize) {
+        (&self.bnode_prefix, self.bnodes)
+    }
+
+    /// Parse the next statement, and return its span with the triples it produced.
+    ///
+    /// In case of error, the span ends at the error,
+    /// and parsing can be resumed with [`seek`](#method.seek).
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn next_statement(
+        &mut self,
+    ) -> Option<(Range<usize>, Result<Vec<SpannedTriple<'a>>, ParseError>)> {
+        self.skip_ws();
+        let start = self.pos;
+        match self.parse_statement() {
+            Ok(false) => None,
+            Ok(true) => Some((start..self.pos, Ok(self.pending.drain(..).collect()))),
+            Err(error) => {
+                self.pending.clear();
+                Some((start..self.pos, Err(error.with_snippet_from(self.txt))))
+            }
+        }
+    }
+
+    /// Move to `pos` (which must be on a character boundary).
+    pub(crate) fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Parse the next statement, and return false if the end of the source was reached.
+    fn parse_statement(&mut self) -> Result<bool, Box<ParseError>> {
+        self.skip_ws();
+        if self.at_end() {
+            return Ok(false);
+        }
+        let start = self.pos;
+        if self.starts_with("@prefix") {
+            self.pos += 7;
+            let (prefix, iri) = self.parse_prefix()?;
+            self.expect('.')?;
+            let sparql = false;
+            self.record(
+                MetadataItem::Prefix {
+                    prefix,
+                    iri,
+                    sparql,
+                },
+                start,
+            );
+        } else if self.starts_with("@base") {
+            self.pos += 5;
+            let iri = self.parse_base()?;
+            self.expect('.')?;
+            self.record(MetadataItem::Base { iri, sparql: false }, start);
+        } else if self.starts_with_keyword("PREFIX") {
+            self.pos += 6;
+            let (prefix, iri) = self.parse_prefix()?;
+            let sparql = true;
+            self.record(
+                MetadataItem::Prefix {
+                    prefix,
+                    iri,
+                    sparql,
+                },
+                start,
+            );
+        } else if self.starts_with_keyword("BASE") {
+            self.pos += 4;
+            let iri = self.parse_base()?;
+            self.record(MetadataItem::Base { iri, sparql: true }, start);
+        } else {
+            self.parse_triples()?;
+            self.expect('.')?;
+            for triple in self.pending.iter_mut() {
+                triple.statement = start..self.pos;
+            }
+            if self.capture {
+                // nested blank nodes and collections are emitted before the triples containing them,
+                // so the last triple is about the subject of the statement
+                let subject = BoxTerm::copy(self.pending.back().unwrap().s());
+                let anchor = match self.last {
+                    None => Anchor::Start,
+                    Some(_) => Anchor::Before(subject.clone()),
+                };
+                for (item, span) in self.floating.drain(..) {
+                    let anchor = anchor.clone();
+                    self.metadata.push(MetadataEntry { item, anchor, span });
+                }
+                self.last = Some((subject, self.pos));
+            }
+        }
+        Ok(true)
+    }
+
+    fn parse_prefix(&mut self) -> Result<(String, String), Box<ParseError>> {
+        self.skip_ws();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c != ':' && (is_pn_chars(c) || c == '.') {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let prefix = self.txt[start..self.pos].to_string();
+        self.expect(':')?;
+        self.skip_ws();
+        if self.peek() != Some('<') {
+            return Err(self.unexpected(&["IRI"]));
+        }
+        let (iri, _) = self.parse_iriref()?;
+        let iri = iri.value().to_string();
+        self.prefixes.insert(prefix.clone(), iri.clone());
+        Ok((prefix, iri))
+    }
+
+    fn parse_base(&mut self) -> Result<String, Box<ParseError>> {
+        self.skip_ws();
+        if self.peek() != Some('<') {
+            return Err(self.unexpected(&["IRI"]));
+        }
+        let (iri, _) = self.parse_iriref()?;
+        let iri = iri.value().to_string();
+        self.base = Some(iri.clone());
+        Ok(iri)
+    }
+
+    fn parse_triples(&mut self) -> Result<(), Box<ParseError>> {
+        match self.peek() {
+            Some('[') if !self.is_anon() => {
+                let subject = self.parse_bnode_property_list()?;
+                self.skip_ws();
+                if self.peek() != Some('.') {
+                    self.parse_predicate_object_list(&subject)?;
+                }
+            }
+            Some('(') => {
+                let subject = self.parse_collection()?;
+                self.skip_ws();
+                self.parse_predicate_object_list(&subject)?;
+            }
+            _ => {
+                let subject = self.parse_subject()?;
+                self.skip_ws();
+                self.parse_predicate_object_list(&subject)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_subject(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        match self.peek() {
+            Some('<') if self.starts_with("<<") => self.parse_quoted(),
+            Some('<') => self.parse_iriref(),
+            Some('_') if self.starts_with("_:") => self.parse_bnode(),
+            Some('[') => self.parse_anon(),
+            Some(c) if c == ':' || is_pn_chars(c) => self.parse_pname(),
+            _ => Err(self.unexpected(&["IRI", "blank node", "collection"])),
+        }
+    }
+
+    fn parse_predicate_object_list(
+        &mut self,
+        subject: &Spanned<'a>,
+    ) -> Result<(), Box<ParseError>> {
+        loop {
+            let predicate = self.parse_verb()?;
+            self.skip_ws();
+            self.parse_object_list(subject, &predicate)?;
+            self.skip_ws();
+            if self.peek() != Some(';') {
+                return Ok(());
+            }
+            while self.peek() == Some(';') {
+                self.pos += 1;
+                self.skip_ws();
+            }
+            if matches!(self.peek(), Some('.') | Some(']') | None) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_verb(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        if self.peek() == Some('a') {
+            let next = self.txt[self.pos + 1..].chars().next();
+            if !next.is_some_and(|c| is_pn_chars(c) || c == '.') {
+                self.pos += 1;
+                return Ok((rdf_term("type"), self.pos - 1..self.pos));
+            }
+        }
+        match self.peek() {
+            Some('<') => self.parse_iriref(),
+            Some(c) if c == ':' || is_pn_chars(c) => self.parse_pname(),
+            _ => Err(self.unexpected(&["IRI", "'a'"])),
+        }
+    }
+
+    fn parse_object_list(
+        &mut self,
+        subject: &Spanned<'a>,
+        predicate: &Spanned<'a>,
+    ) -> Result<(), Box<ParseError>> {
+        loop {
+            let object = self.parse_object()?;
+            self.emit(subject, predicate, object);
+            self.skip_ws();
+            if self.peek() != Some(',') {
+                return Ok(());
+            }
+            self.pos += 1;
+            self.skip_ws();
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        match self.peek() {
+            Some('<') if self.starts_with("<<") => self.parse_quoted(),
+            Some('<') => self.parse_iriref(),
+            Some('_') if self.starts_with("_:") => self.parse_bnode(),
+            Some('[') if self.is_anon() => self.parse_anon(),
+            Some('[') => self.parse_bnode_property_list(),
+            Some('(') => self.parse_collection(),
+            Some('"') | Some('\'') => self.parse_literal(),
+            Some(c) if c.is_ascii_digit() || "+-.".contains(c) => self.parse_number(),
+            Some(_) if self.starts_with_boolean() => {
+                let start = self.pos;
+                let value = if self.starts_with("true") {
+                    "true"
+                } else {
+                    "false"
+                };
+                self.pos += value.len();
+                Ok((
+                    MownTerm::new_literal_dt_unchecked(&self.txt[start..self.pos], xsd::boolean),
+                    start..self.pos,
+                ))
+            }
+            Some(c) if c == ':' || is_pn_chars(c) => self.parse_pname(),
+            _ => Err(self.unexpected(&["IRI", "blank node", "collection", "literal"])),
+        }
+    }
+
+    /// Parse `<< s p o >>`, and return the blank node replacing it.
+    fn parse_quoted(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        if !self.star {
+            return Err(self.error("quoted triples are not allowed in strict RDF 1.1", 2));
+        }
+        let start = self.pos;
+        self.pos += 2; // '<<'
+        self.skip_ws();
+        let s = match self.peek() {
+            Some('<') if self.starts_with("<<") => self.parse_quoted()?,
+            Some('[') if self.is_anon() => self.parse_anon()?,
+            Some('[') | Some('(') => return Err(self.unexpected(&["IRI", "blank node"])),
+            _ => self.parse_subject()?,
+        };
+        self.skip_ws();
+        let p = self.parse_verb()?;
+        self.skip_ws();
+        let o = match self.peek() {
+            Some('[') if !self.is_anon() => {
+                return Err(self.unexpected(&["IRI", "blank node", "literal"]))
+            }
+            Some('(') => return Err(self.unexpected(&["IRI", "blank node", "literal"])),
+            _ => self.parse_object()?,
+        };
+        self.skip_ws();
+        if !self.starts_with(">>") {
+            return Err(self.unexpected(&["'>>'"]));
+        }
+        self.pos += 2;
+        let span = start..self.pos;
+        let key = [
+            BoxTerm::copy(&s.0),
+            BoxTerm::copy(&p.0),
+            BoxTerm::copy(&o.0),
+        ];
+        if let Some(node) = self.quoted.get(&key) {
+            return Ok((node.clone(), span));
+        }
+        let node = (self.fresh_bnode(), span.clone());
+        self.quoted.insert(key, node.0.clone());
+        let predicate = |suffix| (rdf_term(suffix), span.clone());
+        let statement = (rdf_term("Statement"), span.clone());
+        self.emit(&node, &predicate("type"), statement);
+        self.emit(&node, &predicate("subject"), s);
+        self.emit(&node, &predicate("predicate"), p);
+        self.emit(&node, &predicate("object"), o);
+        Ok(node)
+    }
+
+    /// Parse `[ predicateObjectList ]`, and return the blank node standing for it.
+    fn parse_bnode_property_list(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        self.pos += 1; // '['
+        self.skip_ws();
+        let node = (self.fresh_bnode(), start..start + 1);
+        self.parse_predicate_object_list(&node)?;
+        self.skip_ws();
+        self.expect(']')?;
+        Ok((node.0, start..self.pos))
+    }
+
+    /// Parse `[]` (possibly with whitespace inside).
+    fn parse_anon(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        self.pos += 1; // '['
+        self.skip_ws();
+        self.expect(']')?;
+        Ok((self.fresh_bnode(), start..self.pos))
+    }
+
+    /// Parse `( object* )`, and return the head of the list.
+    fn parse_collection(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        self.pos += 1; // '('
+        self.skip_ws();
+        let mut items = vec![];
+        while self.peek() != Some(')') {
+            if self.at_end() {
+                return Err(self.unexpected(&["')'"]));
+            }
+            items.push(self.parse_object()?);
+            self.skip_ws();
+        }
+        self.pos += 1; // ')'
+        let mut next = (rdf_term("nil"), self.pos - 1..self.pos);
+        let mut triples = vec![];
+        for (item, span) in items.into_iter().rev() {
+            let node = (self.fresh_bnode(), span.clone());
+            let first = (rdf_term("first"), span.clone());
+            let rest = (rdf_term("rest"), span.clone());
+            triples.push(spanned(&node, &rest, next));
+            triples.push(spanned(&node, &first, (item, span)));
+            next = node;
+        }
+        if triples.is_empty() {
+            next.1 = start..self.pos;
+        }
+        self.pending.extend(triples.into_iter().rev());
+        Ok(next)
+    }
+
+    fn parse_iriref(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        self.pos += 1; // '<'
+        let mut unescaped: Option<String> = None;
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected(&["'>'"])),
+                Some('>') => break,
+                Some('\\') => {
+                    let mut buf = unescaped
+                        .take()
+                        .unwrap_or_else(|| self.txt[start + 1..self.pos].to_string());
+                    buf.push(self.parse_uchar()?);
+                    unescaped = Some(buf);
+                }
+                Some(c) if c <= ' ' || "<\"{}|^`".contains(c) => {
+                    return Err(self.unexpected(&["'>'"]));
+                }
+                Some(c) => {
+                    if let Some(buf) = &mut unescaped {
+                        buf.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        self.pos += 1; // '>'
+        let iri: MownStr<'a> = match unescaped {
+            None => self.txt[start + 1..self.pos - 1].into(),
+            Some(buf) => buf.into(),
+        };
+        let iri = if is_absolute_iri_ref(&iri) {
+            iri
+        } else {
+            match &self.base {
+                None => iri,
+                Some(base) => match resolve(base, &iri) {
+                    Some(resolved) => resolved.into(),
+                    None => {
+                        return Err(Box::new(
+                            ParseError::new(format!("invalid IRI <{}>", iri))
+                                .with_token(&self.txt[start..self.pos])
+                                .with_offset(start),
+                        ))
+                    }
+                },
+            }
+        };
+        Ok((MownTerm::new_iri_unchecked(iri), start..self.pos))
+    }
+
+    fn parse_pname(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c != ':' && (is_pn_chars(c) || c == '.') {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        if self.peek() != Some(':') {
+            self.pos = start;
+            return Err(self.unexpected(&["IRI", "prefixed name"]));
+        }
+        let prefix = &self.txt[start..self.pos];
+        self.pos += 1; // ':'
+        let local_start = self.pos;
+        let mut local = String::new();
+        while let Some(c) = self.peek() {
+            if is_pn_chars(c) || c == '.' {
+                local.push(c);
+                self.pos += c.len_utf8();
+            } else if c == '%' {
+                let hex = self.txt.get(self.pos + 1..self.pos + 3);
+                if !hex.is_some_and(|h| h.chars().all(|c| c.is_ascii_hexdigit())) {
+                    return Err(self.error("invalid percent-encoding", 3));
+                }
+                local.push_str(&self.txt[self.pos..self.pos + 3]);
+                self.pos += 3;
+            } else if c == '\\' {
+                match self.txt[self.pos + 1..].chars().next() {
+                    Some(e) if "_~.-!$&'()*+,;=/?#@%".contains(e) => {
+                        local.push(e);
+                        self.pos += 2;
+                    }
+                    _ => return Err(self.error("invalid escape sequence", 2)),
+                }
+            } else {
+                break;
+            }
+        }
+        // the local name can not end with '.'
+        while self.txt[local_start..self.pos].ends_with('.') {
+            self.pos -= 1;
+            local.pop();
+        }
+        let undefined;
+        let ns = match self.prefixes.get(prefix) {
+            Some(ns) => ns,
+            None if self.undefined_prefixes => {
+                undefined = format!("{}:", prefix);
+                &undefined
+            }
+            None => {
+                let err = ParseError::new(format!("unknown prefix '{}'", prefix))
+                    .with_token(&self.txt[start..=start + prefix.len()])
+                    .with_offset(start)
+                    .with_hint(format!("add a declaration @prefix {}: <...>.", prefix));
+                return Err(Box::new(err));
+            }
+        };
+        Ok((
+            MownTerm::new_iri_unchecked(format!("{}{}", ns, local)),
+            start..self.pos,
+        ))
+    }
+
+    fn parse_bnode(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        self.pos += 2; // '_:'
+        match self.peek() {
+            Some(c) if c != ':' && c != '-' && is_pn_chars(c) || c.is_ascii_digit() => (),
+            _ => return Err(self.unexpected(&["blank node label"])),
+        }
+        while let Some(c) = self.peek() {
+            if c != ':' && (is_pn_chars(c) || c == '.') {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        // the label can not end with '.'
+        while self.txt[..self.pos].ends_with('.') {
+            self.pos -= 1;
+        }
+        Ok((
+            MownTerm::new_bnode_unchecked(&self.txt[start + 2..self.pos]),
+            start..self.pos,
+        ))
+    }
+
+    fn parse_literal(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        let quote = &self.txt[self.pos..self.pos + 1];
+        let long = self.txt[self.pos..].starts_with(&quote.repeat(3));
+        let delimiter = if long {
+            quote.repeat(3)
+        } else {
+            quote.to_string()
+        };
+        self.pos += delimiter.len();
+        let txt_start = self.pos;
+        let mut unescaped: Option<String> = None;
+        loop {
+            let rest = &self.txt[self.pos..];
+            if rest.starts_with(delimiter.as_str()) {
+                // in long strings, up to 2 quotes can precede the closing delimiter
+                if long && rest[3..].starts_with(quote) {
+                    if let Some(buf) = &mut unescaped {
+                        buf.push_str(quote);
+                    }
+                    self.pos += 1;
+                    continue;
+                }
+                break;
+            }
+            match rest.chars().next() {
+                None => return Err(self.unexpected(&[&format!("'{}'", delimiter)])),
+                Some('\n') | Some('\r') if !long => {
+                    return Err(self.unexpected(&[&format!("'{}'", delimiter)]))
+                }
+                Some('\\') => {
+                    let mut buf = unescaped
+                        .take()
+                        .unwrap_or_else(|| self.txt[txt_start..self.pos].to_string());
+                    buf.push(self.parse_echar()?);
+                    unescaped = Some(buf);
+                }
+                Some(c) => {
+                    if let Some(buf) = &mut unescaped {
+                        buf.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        let txt: MownStr<'a> = match unescaped {
+            None => self.txt[txt_start..self.pos].into(),
+            Some(buf) => buf.into(),
+        };
+        self.pos += delimiter.len();
+        if self.starts_with("^^") {
+            self.pos += 2;
+            let (dt, _) = match self.peek() {
+                Some('<') => self.parse_iriref()?,
+                _ => self.parse_pname()?,
+            };
+            let dt: Iri<MownStr<'a>> = match dt {
+                MownTerm::Iri(iri) => iri,
+                _ => unreachable!(),
+            };
+            Ok((MownTerm::new_literal_dt_unchecked(txt, dt), start..self.pos))
+        } else if self.peek() == Some('@') {
+            let tag_start = self.pos;
+            self.pos += 1;
+            while let Some(c) = self.peek() {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            let tag = &self.txt[tag_start + 1..self.pos];
+            match MownTerm::new_literal_lang(txt, tag) {
+                Ok(lit) => Ok((lit, start..self.pos)),
+                Err(error) => Err(Box::new(
+                    ParseError::new(format!("invalid language tag '{}': {}", tag, error))
+                        .with_token(&self.txt[tag_start..self.pos])
+                        .with_offset(tag_start),
+                )),
+            }
+        } else {
+            Ok((
+                MownTerm::new_literal_dt_unchecked(txt, xsd::string),
+                start..self.pos,
+            ))
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Spanned<'a>, Box<ParseError>> {
+        let start = self.pos;
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.pos += 1;
+        }
+        let int_digits = self.skip_digits();
+        let mut frac_digits = None;
+        if self.peek() == Some('.') {
+            let after = self.txt[self.pos + 1..].chars().next();
+            let exp_follows = int_digits > 0 && matches!(after, Some('e') | Some('E'));
+            if after.is_some_and(|c| c.is_ascii_digit()) || exp_follows {
+                self.pos += 1;
+                frac_digits = Some(self.skip_digits());
+            }
+        }
+        if int_digits == 0 && frac_digits.unwrap_or(0) == 0 {
+            self.pos = start;
+            return Err(self.unexpected(&["number"]));
+        }
+        let mut datatype = match frac_digits {
+            None => xsd::integer,
+            Some(_) => xsd::decimal,
+        };
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let before_exp = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some('+') | Some('-')) {
+                self.pos += 1;
+            }
+            if self.skip_digits() > 0 {
+                datatype = xsd::double;
+            } else {
+                self.pos = before_exp;
+            }
+        }
+        if datatype == xsd::decimal && frac_digits == Some(0) {
+            // "1." followed by an invalid exponent
+            self.pos -= 1;
+            datatype = xsd::integer;
+        }
+        Ok((
+            MownTerm::new_literal_dt_unchecked(&self.txt[start..self.pos], datatype),
+            start..self.pos,
+        ))
+    }
+
+    /// Parse an escape sequence in a string literal.
+    fn parse_echar(&mut self) -> Result<char, Box<ParseError>> {
+        let c = match self.txt[self.pos..].chars().nth(1) {
+            Some('t') => '\t',
+            Some('b') => '\u{8}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('f') => '\u{C}',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('\\') => '\\',
+            _ => return self.parse_uchar(),
+        };
+        self.pos += 2;
+        Ok(c)
+    }
+
+    /// Parse a `\uXXXX` or `\UXXXXXXXX` escape sequence.
+    fn parse_uchar(&mut self) -> Result<char, Box<ParseError>> {
+        let len = match self.txt[self.pos..].chars().nth(1) {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("invalid escape sequence", 2)),
+        };
+        let c = self
+            .txt
+            .get(self.pos + 2..self.pos + 2 + len)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32);
+        match c {
+            Some(c) => {
+                self.pos += 2 + len;
+                Ok(c)
+            }
+            None => Err(self.error("invalid escape sequence", 2 + len)),
+        }
+    }
+
+    fn emit(&mut self, s: &Spanned<'a>, p: &Spanned<'a>, o: Spanned<'a>) {
+        self.pending.push_back(spanned(s, p, o));
+    }
+
+    fn fresh_bnode(&mut self) -> MownTerm<'a> {
+        self.bnodes += 1;
+        MownTerm::new_bnode_unchecked(format!("{}{}", self.bnode_prefix, self.bnodes))
+    }
+
+    /// Whether the current position is on `[]` (possibly with whitespace inside).
+    fn is_anon(&self) -> bool {
+        self.txt[self.pos + 1..]
+            .trim_start_matches([' ', '\t', '\r', '\n'])
+            .starts_with(']')
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.txt[self.pos..].starts_with(prefix)
+    }
+
+    /// Whether the current position is on the case-insensitive `keyword`, followed by whitespace.
+    fn starts_with_keyword(&self, keyword: &str) -> bool {
+        let rest = &self.txt[self.pos..];
+        rest.get(..keyword.len())
+            .is_some_and(|k| k.eq_ignore_ascii_case(keyword))
+            && rest[keyword.len()..].starts_with([' ', '\t', '\r', '\n'])
+    }
+
+    fn starts_with_boolean(&self) -> bool {
+        ["true", "false"].iter().any(|b| {
+            self.starts_with(b)
+                && !self.txt[self.pos + b.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(is_pn_chars)
+        })
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let rest = &self.txt[self.pos..];
+        let n = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        self.pos += n;
+        n
+    }
+
+    fn skip_ws(&mut self) {
+        loop {
+            let rest = &self.txt[self.pos..];
+            let len = rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+            if self.capture && rest[..len].matches('\n').count() > 1 {
+                self.floating
+                    .push((MetadataItem::BlankLine, self.pos..self.pos + len));
+            }
+            self.pos += len;
+            if self.peek() != Some('#') {
+                return;
+            }
+            let start = self.pos;
+            let rest = &self.txt[self.pos..];
+            self.pos += rest.find('\n').unwrap_or(rest.len());
+            if self.capture {
+                let text = self.txt[start + 1..self.pos].trim_end_matches('\r');
+                self.record(MetadataItem::Comment(text.to_string()), start);
+            }
+        }
+    }
+
+    /// Capture `item`, spanning from `start` to the current position.
+    ///
+    /// Comments on the same line as the end of the previous statement are anchored after it;
+    /// other items are anchored once the next statement is parsed.
+    fn record(&mut self, item: MetadataItem, start: usize) {
+        if !self.capture {
+            return;
+        }
+        let span = start..self.pos;
+        if let (MetadataItem::Comment(_), Some((subject, end))) = (&item, &self.last) {
+            if self.txt[*end..start].trim_matches([' ', '\t']).is_empty() {
+                let anchor = Anchor::After(subject.clone());
+                self.metadata.push(MetadataEntry { item, anchor, span });
+                return;
+            }
+        }
+        self.floating.push((item, span));
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Box<ParseError>> {
+        self.skip_ws();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected(&[&format!("'{}'", c)]));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.txt[self.pos..].chars().next()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.txt.len()
+    }
+
+    fn unexpected(&self, expected: &[&str]) -> Box<ParseError> {
+        let err = match self.peek() {
+            None => ParseError::new("unexpected end of file"),
+            Some(c) => ParseError::new(format!("unexpected character {:?}", c)).with_token(c),
+        };
+        Box::new(
+            err.with_offset(self.pos)
+                .with_expected(expected.iter().copied()),
+        )
+    }
+
+    fn error(&self, message: &str, len: usize) -> Box<ParseError> {
+        let end = (self.pos + len).min(self.txt.len());
+        let token = self.txt.get(self.pos..end).unwrap_or_default();
+        Box::new(
+            ParseError::new(message)
+                .with_token(token)
+                .with_offset(self.pos),
+        )
+    }
+}
+
+impl<'a> Iterator for SpannedSource<'a> {
+    type Item = Result<SpannedTriple<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(triple) = self.pending.pop_front() {
+                return Some(Ok(triple));
+            }
+            if self.failed {
+                return None;
+            }
+            match self.parse_statement() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(error) => {
+                    self.failed = true;
+                    // triples of the erroneous statement are discarded
+                    self.pending.clear();
+                    return Some(Err(error.with_snippet_from(self.txt)));
+                }
+            }
+        }
+    }
+}
+
+fn rdf_term(suffix: &'static str) -> MownTerm<'static> {
+    MownTerm::new_iri_suffixed_unchecked(rdf::PREFIX, suffix)
+}
+
+fn spanned<'a>(s: &Spanned<'a>, p: &Spanned<'a>, o: Spanned<'a>) -> SpannedTriple<'a> {
+    SpannedTriple {
+        terms: [s.0.clone(), p.0.clone(), o.0],
+        spans: [s.1.clone(), p.1.clone(), o.1],
+        statement: 0..0, // set once the statement is complete
+    }
+}
+
+/// Resolve `iri` against `base`.
+fn resolve(base: &str, iri: &str) -> Option<String> {
+    let base = IriParsed::new(base).ok()?;
+    let resolved: Result<_, _> = base.resolve(iri);
+    resolved.ok().map(|iri| iri.to_string())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::{isomorphic_graphs, Graph};
+    use crate::parser::nt12::NTriples12Parser;
+    use crate::parser::turtle::TurtleParser;
+    use sophia_api::parser::TripleParser;
+    use sophia_api::term::TermKind;
+    use sophia_api::triple::stream::TripleSource;
+    use sophia_term::Term;
+
+    fn parse(ttl: &str) -> Vec<SpannedTriple<'_>> {
+        TurtleParser::default()
+            .parse_spanned(ttl)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn spans() {
+        let ttl = "PREFIX : <tag:>\n:a a :C ; :p \"x\"@en, 42, [ :q _:b ] .";
+        let triples = parse(ttl);
+        assert_eq!(triples.len(), 5);
+        let texts: Vec<Vec<&str>> = triples
+            .iter()
+            .map(|t| t.spans().iter().map(|s| &ttl[s.clone()]).collect())
+            .collect();
+        assert_eq!(texts[0], vec![":a", "a", ":C"]);
+        assert_eq!(texts[1], vec![":a", ":p", "\"x\"@en"]);
+        assert_eq!(texts[2], vec![":a", ":p", "42"]);
+        assert_eq!(texts[3], vec!["[", ":q", "_:b"]);
+        assert_eq!(texts[4], vec![":a", ":p", "[ :q _:b ]"]);
+        assert_eq!(triples[2].o().datatype().unwrap(), xsd::integer);
+        assert_eq!(triples[3].s(), triples[4].o());
+        assert_eq!(triples[1].p().value(), "tag:p");
+        for t in &triples {
+            assert_eq!(&ttl[t.statement_span()], &ttl[16..]);
+        }
+    }
+
+    #[test]
+    fn same_as_rio() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @base <http://example.org/dir/> .
+            @prefix : <ns#> .
+            # a comment
+            <a> :list (1 2.5 -3e2 ( )) ;
+                :str 'single', """long "quoted" ""string""", "esc\tapeé" ;
+                :bool true, false ;
+                :dt "1"^^:int, "2"^^<http://www.w3.org/2001/XMLSchema#byte> ;
+                :local :a.b\,c, :%41 .
+            [] :p [ :q [] ] .
+            ( :x ) :p :y .
+            _:n :p <../up> .
+        "#;
+        let native: FastGraph = TurtleParser::default()
+            .parse_spanned(ttl)
+            .collect_triples()?;
+        let rio: FastGraph = TurtleParser::default().parse_str(ttl).collect_triples()?;
+        assert_eq!(native.triples().count(), rio.triples().count());
+        for t in rio.triples() {
+            let t = t?;
+            if [t.s(), t.p(), t.o()]
+                .iter()
+                .any(|t| t.kind() == TermKind::BlankNode)
+            {
+                continue;
+            }
+            assert!(
+                native.contains(t.s(), t.p(), t.o())?,
+                "{:?}",
+                [t.s(), t.p(), t.o()]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed() {
+        let ttl = "<tag:a> <tag:b> \"plain\", \"esc\\\"aped\" .";
+        let triples = parse(ttl);
+        match (triples[0].s(), triples[0].o(), triples[1].o()) {
+            (Term::Iri(s), Term::Literal(o0), Term::Literal(o1)) => {
+                assert!(s.ns().is_borrowed());
+                assert!(o0.txt().is_borrowed());
+                assert_eq!(&o1.txt()[..], "esc\"aped");
+                assert!(o1.txt().is_owned());
+            }
+            _ => panic!("unexpected terms {:?}", triples),
+        }
+    }
+
+    #[test]
+    fn error() {
+        let ttl = "@prefix : <tag:>.\n:a :b :c.\n:a ex:b :c.\n";
+        let mut source = TurtleParser::default().parse_spanned(ttl);
+        assert!(source.next().unwrap().is_ok());
+        let err = source.next().unwrap().unwrap_err();
+        assert_eq!(err.message(), "unknown prefix 'ex'");
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.column(), Some(4));
+        assert_eq!(err.byte_range(), Some(31..34));
+        assert!(source.next().is_none());
+
+        let err = TurtleParser::default()
+            .parse_spanned("<tag:a> <tag:b> <tag:c>, .")
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(err.column(), Some(26));
+        assert_eq!(err.token(), Some("."));
+    }
+
+    #[test]
+    fn quoted() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = "@prefix : <tag:>.\n\
+                   << :a :b << _:x :c \"x\" >> >> :d :e.\n\
+                   :f :d << :a :b << _:x :c \"x\" >> >>, << [] :c 42 >>.";
+        let err = TurtleParser::default()
+            .parse_spanned(ttl)
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.token(), Some("<<"));
+
+        let triples = TurtleParser::default()
+            .parse_star(ttl)
+            .collect::<Result<Vec<_>, _>>()?;
+        // 3 statements, and 4 triples describing each of the 3 quoted triples
+        assert_eq!(triples.len(), 15);
+        assert_eq!(&ttl[triples[0].spans()[0].clone()], "<< _:x :c \"x\" >>");
+        assert_eq!(triples[2].o().value(), "tag:c");
+        assert_eq!(triples[8].s(), triples[4].s());
+        assert_eq!(triples[9].o(), triples[4].s());
+        assert!(triples[10].s().value().starts_with('b'));
+
+        let nt = "<< <tag:a> <tag:b> << _:x <tag:c> \"x\" >> >> <tag:d> <tag:e>.\n\
+                  <tag:f> <tag:d> << <tag:a> <tag:b> << _:x <tag:c> \"x\" >> >>.\n";
+        let star = NTriples12Parser {
+            star: true,
+            ..Default::default()
+        };
+        let g: FastGraph = star.parse_str(nt).collect_triples()?;
+        let ttl = format!("{}.", &ttl[..ttl.rfind(',').unwrap()]);
+        let h: FastGraph = TurtleParser::default().parse_star(&ttl).collect_triples()?;
+        assert!(isomorphic_graphs(&g, &h)?);
+
+        let err = TurtleParser::default()
+            .parse_star("<< <tag:a> <tag:b> ( ) >> <tag:d> <tag:e>.")
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(err.token(), Some("("));
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use std::io::BufRead;
 
 use rio_xml::{RdfXmlError, RdfXmlParser as RioRdfXmlParser};
 
+use crate::parser::config::NO_BASE;
 use crate::parser::rio_common::*;
 use crate::parser::TripleParser;
 
@@ -20,7 +21,7 @@ impl<B: BufRead> TripleParser<B> for RdfXmlParser {
     fn parse(&self, data: B) -> Self::Source {
         let base: &str = match &self.base {
             Some(base) => &base,
-            None => "x-no-base:///",
+            None => NO_BASE,
         };
         StrictRioSource::from(RioRdfXmlParser::new(data, base))
     }
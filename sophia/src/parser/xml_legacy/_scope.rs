@@ -170,7 +170,7 @@ impl<F: TermFactory> Scope<F> {
         } else if is_absolute_iri_ref(iri) {
             Ok(factory.iri(iri)?)
         } else {
-            Err(TermError::InvalidIri(iri.to_owned()).into())
+            Err(TermError::InvalidIri(sophia_iri::error::InvalidIri::new(iri)).into())
         }
     }
 
@@ -0,0 +1,304 @@
+//! Splitting quad streams and datasets into partitions.
+//!
+//! Vertically partitioned storage layouts keep the quads of each predicate
+//! (or of each named graph) in a separate file or table,
+//! while sharded layouts distribute subjects over a fixed number of shards.
+//! A [`Partitioning`] computes the [`PartitionKey`] of each quad, and
+//! * [`partition_quads`] collects the quads of a source in memory, grouped by partition;
+//! * a [`PartitionWriter`] streams each quad to the serializer of its partition,
+//!   creating serializers on demand.
+//!
+//! Datasets are partitioned through their [`quads`] source.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nq;
+//! use sophia::partition::{Partitioning, PartitionWriter};
+//! use sophia::serializer::{nq::NqSerializer, Stringifier};
+//!
+//! let data = r#"
+//!     <tag:alice> <tag:name> "Alice".
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:bob> <tag:name> "Bob".
+//! "#;
+//! let mut writer = PartitionWriter::new(Partitioning::Predicate, |_key| {
+//!     Ok(NqSerializer::new_stringifier())
+//! });
+//! writer.write_quads(nq::parse_str(data))?;
+//! let partitions = writer.into_serializers();
+//! assert_eq!(partitions.len(), 2);
+//! for (key, serializer) in partitions.iter() {
+//!     println!("{}:\n{}", key, serializer.as_str());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Partitioning`]: enum.Partitioning.html
+//! [`PartitionKey`]: enum.PartitionKey.html
+//! [`partition_quads`]: fn.partition_quads.html
+//! [`PartitionWriter`]: struct.PartitionWriter.html
+//! [`quads`]: ../dataset/trait.Dataset.html#tymethod.quads
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt;
+
+use sophia_api::quad::stream::{QuadSource, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::QuadSerializer;
+use sophia_api::term::{term_to_string, CopyTerm};
+use sophia_api::triple::stream::StreamError;
+use sophia_term::BoxTerm;
+
+use crate::syntax::dynamic::BoxQuad;
+
+/// How quads are assigned to partitions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Partitioning {
+    /// One partition per predicate.
+    Predicate,
+    /// One partition per named graph, plus one for the default graph.
+    Graph,
+    /// The given number of partitions (shards),
+    /// each quad going to the shard of its subject.
+    ///
+    /// Subjects are assigned to shards by a hash function
+    /// that is stable across platforms and versions,
+    /// so the same subject always goes to the same shard.
+    SubjectHash(u32),
+}
+
+impl Partitioning {
+    /// The partition of `quad`.
+    ///
+    /// # Panics
+    /// if `self` is `SubjectHash(0)`.
+    pub fn key<Q: Quad>(&self, quad: &Q) -> PartitionKey {
+        match self {
+            Partitioning::Predicate => PartitionKey::Predicate(BoxTerm::copy(quad.p())),
+            Partitioning::Graph => PartitionKey::Graph(quad.g().map(BoxTerm::copy)),
+            Partitioning::SubjectHash(shards) => {
+                assert!(*shards > 0, "the number of shards must be positive");
+                let hash = fnv1a(&term_to_string(quad.s()));
+                PartitionKey::Shard((hash % u64::from(*shards)) as u32)
+            }
+        }
+    }
+}
+
+/// The identifier of a partition, as computed by a [`Partitioning`](enum.Partitioning.html).
+///
+/// Its `Display` implementation is suitable for logging, not for file names,
+/// as it may contain any character allowed in IRIs.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PartitionKey {
+    /// The partition of the quads with this predicate.
+    Predicate(BoxTerm),
+    /// The partition of the quads in this named graph (or in the default graph).
+    Graph(Option<BoxTerm>),
+    /// The partition of the subjects hashed to this shard.
+    Shard(u32),
+}
+
+impl fmt::Display for PartitionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartitionKey::Predicate(p) => write!(f, "{}", term_to_string(p)),
+            PartitionKey::Graph(Some(g)) => write!(f, "{}", term_to_string(g)),
+            PartitionKey::Graph(None) => write!(f, "default graph"),
+            PartitionKey::Shard(n) => write!(f, "shard {}", n),
+        }
+    }
+}
+
+/// Collect the quads of `source`, grouped by partition.
+pub fn partition_quads<QS>(
+    mut source: QS,
+    by: Partitioning,
+) -> Result<BTreeMap<PartitionKey, Vec<BoxQuad>>, QS::Error>
+where
+    QS: QuadSource,
+{
+    let mut partitions: BTreeMap<PartitionKey, Vec<BoxQuad>> = BTreeMap::new();
+    source.for_each_quad(|q| {
+        partitions
+            .entry(by.key(&q))
+            .or_default()
+            .push(copy_quad(&q));
+    })?;
+    Ok(partitions)
+}
+
+/// Writes each quad through the serializer of its partition.
+///
+/// Serializers are created by a closure, the first time a quad is found in their partition
+/// (typically opening a file named after the partition key).
+///
+/// Each quad is passed to its serializer as soon as it is read,
+/// so this suits serializers that can be fed repeatedly,
+/// such as those of line-based formats (N-Triples, N-Quads).
+/// For other formats, use [`partition_quads`](fn.partition_quads.html),
+/// and serialize each partition as a whole.
+pub struct PartitionWriter<S, F> {
+    by: Partitioning,
+    make_serializer: F,
+    serializers: BTreeMap<PartitionKey, S>,
+}
+
+impl<S, F> PartitionWriter<S, F>
+where
+    S: QuadSerializer,
+    F: FnMut(&PartitionKey) -> Result<S, S::Error>,
+{
+    /// Build a writer partitioning quads according to `by`,
+    /// and creating serializers with `make_serializer`.
+    pub fn new(by: Partitioning, make_serializer: F) -> Self {
+        PartitionWriter {
+            by,
+            make_serializer,
+            serializers: BTreeMap::new(),
+        }
+    }
+
+    /// Write all the quads of `source`.
+    ///
+    /// This method can be called several times (e.g. for several input files);
+    /// the serializers are kept from one call to the next.
+    pub fn write_quads<QS>(&mut self, mut source: QS) -> StreamResult<(), QS::Error, S::Error>
+    where
+        QS: QuadSource,
+    {
+        let PartitionWriter {
+            by,
+            make_serializer,
+            serializers,
+        } = self;
+        source.try_for_each_quad(|q| {
+            let key = by.key(&q);
+            let serializer = match serializers.get_mut(&key) {
+                Some(serializer) => serializer,
+                None => {
+                    let serializer = make_serializer(&key)?;
+                    serializers.entry(key).or_insert(serializer)
+                }
+            };
+            let quad = std::iter::once(Ok::<_, Infallible>(copy_quad(&q)));
+            serializer
+                .serialize_quads(quad)
+                .map_err(StreamError::unwrap_sink_error)?;
+            Ok(())
+        })
+    }
+
+    /// The keys of the partitions found so far.
+    pub fn partitions(&self) -> impl Iterator<Item = &PartitionKey> + '_ {
+        self.serializers.keys()
+    }
+
+    /// The serializer of each partition found so far.
+    pub fn serializers(&self) -> &BTreeMap<PartitionKey, S> {
+        &self.serializers
+    }
+
+    /// Consume this writer, and return the serializer of each partition
+    /// (e.g. in order to flush them).
+    pub fn into_serializers(self) -> BTreeMap<PartitionKey, S> {
+        self.serializers
+    }
+}
+
+fn copy_quad<Q: Quad>(quad: &Q) -> BoxQuad {
+    (
+        [
+            BoxTerm::copy(quad.s()),
+            BoxTerm::copy(quad.p()),
+            BoxTerm::copy(quad.o()),
+        ],
+        quad.g().map(BoxTerm::copy),
+    )
+}
+
+/// The 64-bit FNV-1a hash of `txt`, which is stable across platforms and versions.
+fn fnv1a(txt: &str) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for b in txt.bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+    use crate::serializer::nq::NqSerializer;
+    use crate::serializer::Stringifier;
+    use crate::test_util::t;
+
+    const DATA: &str = r#"
+        <tag:a> <tag:p> "1".
+        <tag:a> <tag:q> "2" <tag:g>.
+        <tag:b> <tag:p> "3" <tag:g>.
+        <tag:c> <tag:p> "4".
+    "#;
+
+    #[test]
+    fn by_predicate_and_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let by_p = partition_quads(nq::parse_str(DATA), Partitioning::Predicate)?;
+        let keys: Vec<_> = by_p.keys().cloned().collect();
+        assert_eq!(
+            keys,
+            vec![
+                PartitionKey::Predicate(t("p")),
+                PartitionKey::Predicate(t("q"))
+            ]
+        );
+        assert_eq!(by_p[&PartitionKey::Predicate(t("p"))].len(), 3);
+
+        let by_g = partition_quads(nq::parse_str(DATA), Partitioning::Graph)?;
+        assert_eq!(by_g[&PartitionKey::Graph(None)].len(), 2);
+        assert_eq!(by_g[&PartitionKey::Graph(Some(t("g")))].len(), 2);
+        assert_eq!(PartitionKey::Graph(None).to_string(), "default graph");
+        Ok(())
+    }
+
+    #[test]
+    fn by_subject_hash() -> Result<(), Box<dyn std::error::Error>> {
+        let shards = partition_quads(nq::parse_str(DATA), Partitioning::SubjectHash(2))?;
+        assert_eq!(shards.values().map(Vec::len).sum::<usize>(), 4);
+        for quads in shards.values() {
+            for q in quads {
+                let key = Partitioning::SubjectHash(2).key(q);
+                assert_eq!(shards[&key].len(), quads.len());
+            }
+        }
+        // the same subject always goes to the same shard
+        let key = |s: &str| Partitioning::SubjectHash(16).key(&([t(s), t("p"), t("o")], None));
+        assert_eq!(key("a"), key("a"));
+        Ok(())
+    }
+
+    #[test]
+    fn writer() -> Result<(), Box<dyn std::error::Error>> {
+        let mut created = vec![];
+        let mut writer = PartitionWriter::new(Partitioning::Graph, |key| {
+            created.push(key.to_string());
+            Ok(NqSerializer::new_stringifier())
+        });
+        writer.write_quads(nq::parse_str(DATA))?;
+        writer.write_quads(nq::parse_str("<tag:d> <tag:p> \"5\" <tag:g>."))?;
+        assert_eq!(writer.partitions().count(), 2);
+        let serializers = writer.into_serializers();
+        assert_eq!(
+            serializers[&PartitionKey::Graph(Some(t("g")))].as_str(),
+            "<tag:a> <tag:q> \"2\" <tag:g>.\n<tag:b> <tag:p> \"3\" <tag:g>.\n<tag:d> <tag:p> \"5\" <tag:g>.\n"
+        );
+        assert_eq!(created, vec!["default graph", "<tag:g>"]);
+        Ok(())
+    }
+}
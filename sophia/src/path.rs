@@ -0,0 +1,368 @@
+//! Evaluation of [SPARQL property paths] over graphs.
+//!
+//! A [`PropertyPath`] is built from predicates,
+//! combined with the same operators as in SPARQL:
+//! inverse (`^p`), sequence (`p1/p2`), alternative (`p1|p2`),
+//! zero or more (`p*`), one or more (`p+`), zero or one (`p?`),
+//! and negated property sets (`!(p1|^p2)`).
+//!
+//! It can then be evaluated against any [`Graph`],
+//! either from a given node ([`targets`] and [`sources`]),
+//! or as a whole ([`pairs`]).
+//! Evaluation is performed on sets of nodes,
+//! so cycles in the graph do not cause infinite loops.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::ns::rdfs;
+//! use sophia::parser::turtle;
+//! use sophia::path::PropertyPath;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+//!     <tag:Cat> rdfs:subClassOf <tag:Mammal>.
+//!     <tag:Dog> rdfs:subClassOf <tag:Mammal>.
+//!     <tag:Mammal> rdfs:subClassOf <tag:Animal>.
+//! "#).collect_triples()?;
+//!
+//! // all the (transitive) subclasses of tag:Animal
+//! let path = PropertyPath::predicate(&rdfs::subClassOf).one_or_more();
+//! let animal = BoxTerm::new_iri("tag:Animal")?;
+//! assert_eq!(path.sources(&g, &animal)?.len(), 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SPARQL property paths]: https://www.w3.org/TR/sparql11-query/#propertypaths
+//! [`PropertyPath`]: enum.PropertyPath.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`targets`]: enum.PropertyPath.html#method.targets
+//! [`sources`]: enum.PropertyPath.html#method.sources
+//! [`pairs`]: enum.PropertyPath.html#method.pairs
+
+use std::collections::HashSet;
+
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+/// A SPARQL property path.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub enum PropertyPath {
+    /// A single predicate (`p`).
+    Predicate(BoxTerm),
+    /// The inverse of a path (`^path`).
+    Inverse(Box<PropertyPath>),
+    /// A path followed by another one (`path1/path2`).
+    Sequence(Box<PropertyPath>, Box<PropertyPath>),
+    /// Either of two paths (`path1|path2`).
+    Alternative(Box<PropertyPath>, Box<PropertyPath>),
+    /// A path repeated any number of times, including zero (`path*`).
+    ZeroOrMore(Box<PropertyPath>),
+    /// A path repeated at least once (`path+`).
+    OneOrMore(Box<PropertyPath>),
+    /// An optional path (`path?`).
+    ZeroOrOne(Box<PropertyPath>),
+    /// Any predicate except the ones in `forward`,
+    /// or the inverse of any predicate except the ones in `inverse`
+    /// (`!(p1|...|pn|^q1|...|^qm)`).
+    ///
+    /// As in SPARQL, the inverse part is only considered when `inverse` is not empty,
+    /// and the forward part is only ignored if `forward` is empty and `inverse` is not.
+    NegatedSet {
+        /// The excluded predicates.
+        forward: Vec<BoxTerm>,
+        /// The excluded inverse predicates.
+        inverse: Vec<BoxTerm>,
+    },
+}
+
+use PropertyPath::*;
+
+impl PropertyPath {
+    /// A path made of a single predicate.
+    pub fn predicate<T: TTerm + ?Sized>(p: &T) -> Self {
+        Predicate(BoxTerm::copy(p))
+    }
+
+    /// A negated property set.
+    pub fn negated(forward: Vec<BoxTerm>, inverse: Vec<BoxTerm>) -> Self {
+        NegatedSet { forward, inverse }
+    }
+
+    /// The inverse of this path (`^self`).
+    pub fn inverse(self) -> Self {
+        Inverse(Box::new(self))
+    }
+
+    /// This path followed by `other` (`self/other`).
+    pub fn then(self, other: Self) -> Self {
+        Sequence(Box::new(self), Box::new(other))
+    }
+
+    /// Either this path or `other` (`self|other`).
+    pub fn or(self, other: Self) -> Self {
+        Alternative(Box::new(self), Box::new(other))
+    }
+
+    /// This path, repeated any number of times (`self*`).
+    pub fn zero_or_more(self) -> Self {
+        ZeroOrMore(Box::new(self))
+    }
+
+    /// This path, repeated at least once (`self+`).
+    pub fn one_or_more(self) -> Self {
+        OneOrMore(Box::new(self))
+    }
+
+    /// This path, optionally (`self?`).
+    pub fn zero_or_one(self) -> Self {
+        ZeroOrOne(Box::new(self))
+    }
+
+    /// The nodes reachable from `start` through this path.
+    pub fn targets<G, T>(&self, graph: &G, start: &T) -> GResult<G, HashSet<BoxTerm>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        self.step(
+            graph,
+            &Some(BoxTerm::copy(start)).into_iter().collect(),
+            true,
+        )
+    }
+
+    /// The nodes from which `end` is reachable through this path.
+    pub fn sources<G, T>(&self, graph: &G, end: &T) -> GResult<G, HashSet<BoxTerm>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        self.step(
+            graph,
+            &Some(BoxTerm::copy(end)).into_iter().collect(),
+            false,
+        )
+    }
+
+    /// All the pairs of nodes of `graph` connected by this path.
+    ///
+    /// Only the subjects and objects of `graph` are considered as possible starting points;
+    /// in particular, paths of length zero only connect those nodes to themselves.
+    pub fn pairs<G: Graph>(&self, graph: &G) -> GResult<G, Vec<(BoxTerm, BoxTerm)>> {
+        let mut nodes = HashSet::new();
+        for t in graph.triples() {
+            let t = t?;
+            nodes.insert(BoxTerm::copy(t.s()));
+            nodes.insert(BoxTerm::copy(t.o()));
+        }
+        let mut pairs = vec![];
+        for node in nodes {
+            for target in self.targets(graph, &node)? {
+                pairs.push((node.clone(), target));
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// The nodes reachable from `nodes` through this path
+    /// (or through its inverse, if `forward` is false).
+    fn step<G: Graph>(
+        &self,
+        graph: &G,
+        nodes: &HashSet<BoxTerm>,
+        forward: bool,
+    ) -> GResult<G, HashSet<BoxTerm>> {
+        let mut result = HashSet::new();
+        match self {
+            Predicate(p) => {
+                for n in nodes {
+                    if forward {
+                        for t in graph.triples_with_sp(n, p) {
+                            result.insert(BoxTerm::copy(t?.o()));
+                        }
+                    } else {
+                        for t in graph.triples_with_po(p, n) {
+                            result.insert(BoxTerm::copy(t?.s()));
+                        }
+                    }
+                }
+            }
+            Inverse(path) => return path.step(graph, nodes, !forward),
+            Sequence(first, second) => {
+                return if forward {
+                    second.step(graph, &first.step(graph, nodes, true)?, true)
+                } else {
+                    first.step(graph, &second.step(graph, nodes, false)?, false)
+                }
+            }
+            Alternative(path1, path2) => {
+                result = path1.step(graph, nodes, forward)?;
+                result.extend(path2.step(graph, nodes, forward)?);
+            }
+            ZeroOrMore(path) => {
+                result = nodes.clone();
+                path.closure(graph, nodes.clone(), forward, &mut result)?;
+            }
+            OneOrMore(path) => {
+                let first = path.step(graph, nodes, forward)?;
+                result = first.clone();
+                path.closure(graph, first, forward, &mut result)?;
+            }
+            ZeroOrOne(path) => {
+                result = path.step(graph, nodes, forward)?;
+                result.extend(nodes.iter().cloned());
+            }
+            NegatedSet {
+                forward: excluded,
+                inverse: excluded_inv,
+            } => {
+                for n in nodes {
+                    if !excluded.is_empty() || excluded_inv.is_empty() {
+                        // forward part
+                        if forward {
+                            for t in graph.triples_with_s(n) {
+                                let t = t?;
+                                if !excluded_in(t.p(), excluded) {
+                                    result.insert(BoxTerm::copy(t.o()));
+                                }
+                            }
+                        } else {
+                            for t in graph.triples_with_o(n) {
+                                let t = t?;
+                                if !excluded_in(t.p(), excluded) {
+                                    result.insert(BoxTerm::copy(t.s()));
+                                }
+                            }
+                        }
+                    }
+                    if !excluded_inv.is_empty() {
+                        // inverse part
+                        if forward {
+                            for t in graph.triples_with_o(n) {
+                                let t = t?;
+                                if !excluded_in(t.p(), excluded_inv) {
+                                    result.insert(BoxTerm::copy(t.s()));
+                                }
+                            }
+                        } else {
+                            for t in graph.triples_with_s(n) {
+                                let t = t?;
+                                if !excluded_in(t.p(), excluded_inv) {
+                                    result.insert(BoxTerm::copy(t.o()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add to `visited` all the nodes reachable from `frontier`
+    /// by repeating this path (at least once).
+    fn closure<G: Graph>(
+        &self,
+        graph: &G,
+        mut frontier: HashSet<BoxTerm>,
+        forward: bool,
+        visited: &mut HashSet<BoxTerm>,
+    ) -> GResult<G, ()> {
+        while !frontier.is_empty() {
+            frontier = self
+                .step(graph, &frontier, forward)?
+                .into_iter()
+                .filter(|n| visited.insert(n.clone()))
+                .collect();
+        }
+        Ok(())
+    }
+}
+
+fn excluded_in<T: TTerm + ?Sized>(p: &T, excluded: &[BoxTerm]) -> bool {
+    excluded.iter().any(|q| term_eq(p, q))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use crate::triple::stream::TripleSource;
+
+    const TTL: &str = r#"
+        <tag:a> <tag:p> <tag:b>.
+        <tag:b> <tag:p> <tag:c>.
+        <tag:c> <tag:p> <tag:a>.
+        <tag:c> <tag:q> <tag:d>.
+        <tag:e> <tag:r> <tag:a>.
+    "#;
+
+    fn p(suffix: &str) -> PropertyPath {
+        PropertyPath::predicate(&t(suffix))
+    }
+
+    fn names(nodes: HashSet<BoxTerm>) -> Vec<String> {
+        let mut names: Vec<_> = nodes.iter().map(|n| n.value()[4..].to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn paths() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let a = t("a");
+        assert_eq!(names(p("p").targets(&g, &a)?), ["b"]);
+        assert_eq!(names(p("p").inverse().targets(&g, &a)?), ["c"]);
+        assert_eq!(names(p("p").then(p("p")).targets(&g, &a)?), ["c"]);
+        assert_eq!(
+            names(p("p").or(p("r").inverse()).targets(&g, &a)?),
+            ["b", "e"]
+        );
+        assert_eq!(names(p("p").zero_or_one().targets(&g, &a)?), ["a", "b"]);
+        // cycles are handled
+        assert_eq!(
+            names(p("p").one_or_more().targets(&g, &a)?),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            names(p("p").zero_or_more().then(p("q")).targets(&g, &a)?),
+            ["d"]
+        );
+        assert_eq!(
+            names(p("p").zero_or_more().then(p("q")).sources(&g, &t("d"))?),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            names(PropertyPath::negated(vec![t("p")], vec![]).targets(&g, &t("c"))?),
+            ["d"]
+        );
+        assert_eq!(
+            names(PropertyPath::negated(vec![], vec![t("p")]).targets(&g, &a)?),
+            ["e"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pairs() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let pairs = p("r").then(p("p").one_or_more()).pairs(&g)?;
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs.iter().all(|(s, _)| s == &t("e")));
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+//! Triple patterns, and macros to write them (and triples) concisely.
+//!
+//! A [`TriplePattern`] groups the three [matchers] of a query,
+//! so that it can be stored, passed around,
+//! and applied to several graphs or triples.
+//! The [`pattern!`] macro builds a [`TriplePattern`] from a SPARQL-like syntax,
+//! where each position is one of:
+//! * a variable (`?name`) or `_`, matching any term;
+//!   note that the name of variables is ignored,
+//!   so `pattern!(?x, p, ?x)` matches triples whose subject and object differ;
+//! * a Rust literal (`"chat"`, `42`, `true`...),
+//!   matching the corresponding RDF literal (see [`AsLiteral`]);
+//!   note that, as in Rust, `42` is an `i32`, i.e. an `xsd:int`, not an `xsd:integer`;
+//! * any other expression, used as a matcher (typically a term);
+//!   it is *moved* into the pattern,
+//!   so terms that must be kept should be cloned
+//!   (arrays of references, like `[&t1, &t2]`, are also valid matchers).
+//!
+//! The [`triple!`] macro uses the same syntax (except for `_`)
+//! to build a triple of [`BoxTerm`]s, where `?name` produces a variable.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, *};
+//! use sophia::ns::{rdf, rdfs};
+//! use sophia::{pattern, triple};
+//!
+//! let mut g = FastGraph::new();
+//! let [s, p, o] = triple!(rdf::type_, rdfs::label, "type");
+//! g.insert(&s, &p, &o)?;
+//! let [s, p, o] = triple!(rdf::type_, rdf::type_, rdf::Property);
+//! g.insert(&s, &p, &o)?;
+//!
+//! let properties = pattern!(?p, rdf::type_, rdf::Property);
+//! assert_eq!(properties.triples_in(&g).count(), 1);
+//! assert_eq!(pattern!(_, rdfs::label, "type").triples_in(&g).count(), 1);
+//! assert_eq!(pattern!(_, rdfs::label, "other").triples_in(&g).count(), 0);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`TriplePattern`]: struct.TriplePattern.html
+//! [`pattern!`]: ../macro.pattern.html
+//! [`triple!`]: ../macro.triple.html
+//! [matchers]: ../term/matcher/index.html
+//! [`AsLiteral`]: ../term/literal/convert/trait.AsLiteral.html
+//! [`BoxTerm`]: ../term/type.BoxTerm.html
+
+use sophia_api::graph::{GTerm, GTripleSource, Graph, MGResult, MutableGraph};
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::triple::Triple;
+
+/// A triple pattern, made of a matcher for each position of a triple.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TriplePattern<S, P, O> {
+    /// The subject matcher.
+    pub s: S,
+    /// The predicate matcher.
+    pub p: P,
+    /// The object matcher.
+    pub o: O,
+}
+
+impl<S, P, O> TriplePattern<S, P, O>
+where
+    S: TermMatcher,
+    P: TermMatcher,
+    O: TermMatcher,
+{
+    /// Build a pattern from its three matchers.
+    pub fn new(s: S, p: P, o: O) -> Self {
+        TriplePattern { s, p, o }
+    }
+
+    /// Whether `t` matches this pattern.
+    pub fn matches<T>(&self, t: &T) -> bool
+    where
+        T: Triple + ?Sized,
+    {
+        self.s.matches(t.s()) && self.p.matches(t.p()) && self.o.matches(t.o())
+    }
+
+    /// The triples of `g` matching this pattern
+    /// (see [`Graph::triples_matching`](../graph/trait.Graph.html#method.triples_matching)).
+    pub fn triples_in<'s, G>(&'s self, g: &'s G) -> GTripleSource<'s, G>
+    where
+        G: Graph,
+    {
+        g.triples_matching(&self.s, &self.p, &self.o)
+    }
+
+    /// Remove the triples of `g` matching this pattern
+    /// (see [`MutableGraph::remove_matching`](../graph/trait.MutableGraph.html#method.remove_matching)).
+    pub fn remove_from<G>(&self, g: &mut G) -> MGResult<G, usize>
+    where
+        G: MutableGraph,
+        GTerm<G>: Clone,
+        G::Error: Into<G::MutationError>,
+    {
+        g.remove_matching(&self.s, &self.p, &self.o)
+    }
+}
+
+/// Build a [`TriplePattern`](pattern/struct.TriplePattern.html)
+/// from three positions, each being `?name`, `_`, a Rust literal or an expression.
+///
+/// See [`pattern` module](pattern/index.html) for details and an example.
+#[macro_export]
+macro_rules! pattern {
+    ($($tokens:tt)*) => {
+        $crate::__split_positions!(__pattern_build [] [] $($tokens)*)
+    };
+}
+
+/// Build a triple of [`BoxTerm`](term/type.BoxTerm.html)s
+/// from three positions, each being `?name`, a Rust literal or an expression.
+///
+/// See [`pattern` module](pattern/index.html) for details and an example.
+#[macro_export]
+macro_rules! triple {
+    ($($tokens:tt)*) => {
+        $crate::__split_positions!(__triple_build [] [] $($tokens)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __split_positions {
+    ($cb:ident [$($done:tt)*] []) => {
+        $crate::$cb!($($done)*)
+    };
+    ($cb:ident [$($done:tt)*] [$($cur:tt)+]) => {
+        $crate::$cb!($($done)* [$($cur)+])
+    };
+    ($cb:ident [$($done:tt)*] [$($cur:tt)+] , $($rest:tt)*) => {
+        $crate::__split_positions!($cb [$($done)* [$($cur)+]] [] $($rest)*)
+    };
+    ($cb:ident [$($done:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__split_positions!($cb [$($done)*] [$($cur)* $next] $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pattern_build {
+    ([$($s:tt)+] [$($p:tt)+] [$($o:tt)+]) => {
+        $crate::pattern::TriplePattern::new(
+            $crate::__pattern_matcher!($($s)+),
+            $crate::__pattern_matcher!($($p)+),
+            $crate::__pattern_matcher!($($o)+),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pattern_matcher {
+    (? $var:ident) => {
+        $crate::term::matcher::ANY
+    };
+    (_) => {
+        $crate::term::matcher::ANY
+    };
+    ($lit:literal) => {
+        $crate::term::literal::convert::AsLiteral::as_literal(&$lit)
+    };
+    ($e:expr) => {
+        $e
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __triple_build {
+    ([$($s:tt)+] [$($p:tt)+] [$($o:tt)+]) => {
+        [
+            $crate::__triple_term!($($s)+),
+            $crate::__triple_term!($($p)+),
+            $crate::__triple_term!($($o)+),
+        ]
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __triple_term {
+    (? $var:ident) => {
+        $crate::term::BoxTerm::new_variable_unchecked(stringify!($var))
+    };
+    ($lit:literal) => {
+        <$crate::term::BoxTerm as $crate::term::CopyTerm>::copy(
+            &$crate::term::literal::convert::AsLiteral::as_literal(&$lit),
+        )
+    };
+    ($e:expr) => {
+        <$crate::term::BoxTerm as $crate::term::CopyTerm>::copy(&$e)
+    };
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use sophia_api::ns::{rdf, rdfs, xsd};
+    use sophia_api::triple::stream::TripleSource;
+    use sophia_term::BoxTerm;
+
+    const TTL: &str = r#"
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+        <tag:a> a rdfs:Class; rdfs:label "A", "B"@en; <tag:count> 42, true.
+        <tag:b> a rdfs:Class; rdfs:subClassOf <tag:a>.
+    "#;
+
+    #[test]
+    fn pattern_matching() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let a = BoxTerm::new_iri("tag:a")?;
+        let count = BoxTerm::new_iri("tag:count")?;
+        assert_eq!(
+            pattern!(?s, rdf::type_, rdfs::Class).triples_in(&g).count(),
+            2
+        );
+        assert_eq!(pattern!(_, _, _).triples_in(&g).count(), 7);
+        assert_eq!(pattern!(a.clone(), _, ?o).triples_in(&g).count(), 5);
+        assert_eq!(pattern!(?s, rdfs::label, "A").triples_in(&g).count(), 1);
+        assert_eq!(pattern!(?s, ?p, true).triples_in(&g).count(), 1);
+        // 42 is an i32, i.e. an xsd:int
+        assert_eq!(pattern!(_, count.clone(), 42).triples_in(&g).count(), 0);
+        let integer = BoxTerm::new_literal_dt("42", xsd::integer)?;
+        assert_eq!(pattern!(_, count, integer).triples_in(&g).count(), 1);
+        // trailing comma, and complex expressions
+        let p = pattern!(
+            BoxTerm::new_iri("tag:b")?,
+            [&rdfs::subClassOf, &rdfs::label],
+            a,
+        );
+        assert_eq!(p.triples_in(&g).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn pattern_matches_and_removes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let p = pattern!(?s, rdf::type_, ?o);
+        assert!(p.matches(&[rdf::type_, rdf::type_, rdf::Property]));
+        assert!(!p.matches(&[rdf::type_, rdfs::label, rdf::Property]));
+        assert_eq!(p.remove_from(&mut g)?, 2);
+        assert_eq!(g.triples().count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn triple_macro() -> Result<(), Box<dyn std::error::Error>> {
+        let t = triple!(?s, rdfs::label, "chat");
+        assert_eq!(t[0], BoxTerm::new_variable("s")?);
+        assert_eq!(t[1], rdfs::label);
+        assert_eq!(t[2], BoxTerm::new_literal_dt("chat", xsd::string)?);
+        let t = triple!(BoxTerm::new_iri("tag:a")?, rdf::value, 1.5);
+        assert_eq!(t[2], BoxTerm::new_literal_dt("1.5", xsd::double)?);
+        Ok(())
+    }
+}
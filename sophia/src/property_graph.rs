@@ -0,0 +1,605 @@
+//! Conversion between RDF graphs and labeled property graphs
+//! (as used by graph databases such as Neo4j or TinkerPop/Gremlin).
+//!
+//! A [`PgMapping`] converts a graph into a [`PropertyGraph`], and back:
+//! * IRIs and blank nodes become nodes, identified by their IRI,
+//!   or by their blank node identifier prefixed by `_:`;
+//! * the `rdf:type`s of a node become its labels;
+//! * triples with a literal object become node properties,
+//!   as well as triples whose predicate has been declared with
+//!   [`set_node_property`] (their object is then stored as a string);
+//! * other triples become edges;
+//! * annotations of a triple (see the [`annotation`] module)
+//!   become properties of the corresponding edge.
+//!
+//! Labels, property keys and edge labels are *names*, derived from IRIs:
+//! a name can be explicitly given to an IRI with [`set_name`];
+//! otherwise, the IRIs in the namespace of the mapping are named after their suffix,
+//! and other IRIs after their local name (after the last `#` or `/`).
+//! Conversely, names without an explicit IRI are appended to the namespace.
+//!
+//! Literals of numeric and boolean datatypes are converted to numbers and booleans;
+//! other literals are converted to strings, losing their datatype or language tag.
+//! Therefore the conversion is not lossless in general,
+//! but a property graph converted to RDF and back is unchanged.
+//!
+//! [`PropertyGraph::to_cypher`] produces a Cypher statement creating the property graph.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::{nt12::NTriples12Parser, TripleParser};
+//! use sophia::property_graph::{PgMapping, Value};
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = NTriples12Parser { star: true, ..NTriples12Parser::default() }.parse_str(r#"
+//!     <tag:alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>.
+//!     <tag:alice> <http://schema.org/name> "Alice".
+//!     <tag:alice> <http://schema.org/knows> <tag:bob>.
+//!     << <tag:alice> <http://schema.org/knows> <tag:bob> >> <http://schema.org/startDate> "2001"^^<http://www.w3.org/2001/XMLSchema#integer>.
+//! "#).collect_triples()?;
+//!
+//! let mapping = PgMapping::new("http://schema.org/");
+//! let pg = mapping.to_property_graph(&g)?;
+//! let alice = &pg.nodes["tag:alice"];
+//! assert!(alice.labels.contains("Person"));
+//! assert_eq!(alice.properties["name"], vec![Value::String("Alice".into())]);
+//! assert_eq!(pg.edges[0].label, "knows");
+//! assert_eq!(pg.edges[0].properties["startDate"], vec![Value::Integer(2001)]);
+//!
+//! let mut back = FastGraph::new();
+//! mapping.to_rdf(&pg, &mut back)?;
+//! assert_eq!(mapping.to_property_graph(&back)?, pg);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`PgMapping`]: struct.PgMapping.html
+//! [`PropertyGraph`]: struct.PropertyGraph.html
+//! [`PropertyGraph::to_cypher`]: struct.PropertyGraph.html#method.to_cypher
+//! [`set_node_property`]: struct.PgMapping.html#method.set_node_property
+//! [`set_name`]: struct.PgMapping.html#method.set_name
+//! [`annotation`]: ../annotation/index.html
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+use sophia_api::graph::{GResult, Graph, MutableGraph};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, StreamResult};
+use sophia_api::triple::Triple;
+use sophia_term::iri::is_absolute_iri_ref;
+use sophia_term::BoxTerm;
+
+use crate::annotation::assert_annotated;
+
+/// The value of a property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+/// The properties of a node or an edge.
+///
+/// As RDF properties can have several values, each key is mapped to a list of values.
+pub type Properties = BTreeMap<String, Vec<Value>>;
+
+/// A node of a [`PropertyGraph`](struct.PropertyGraph.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Node {
+    pub labels: BTreeSet<String>,
+    pub properties: Properties,
+}
+
+/// An edge of a [`PropertyGraph`](struct.PropertyGraph.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    /// The identifier of the source node.
+    pub source: String,
+    pub label: String,
+    /// The identifier of the target node.
+    pub target: String,
+    pub properties: Properties,
+}
+
+/// A labeled property graph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PropertyGraph {
+    /// The nodes, indexed by their identifier.
+    pub nodes: BTreeMap<String, Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl PropertyGraph {
+    /// A Cypher `CREATE` statement creating this property graph.
+    ///
+    /// The identifier of each node is stored in its `uri` property.
+    pub fn to_cypher(&self) -> String {
+        let mut vars = HashMap::new();
+        let mut patterns = vec![];
+        for (i, (id, node)) in self.nodes.iter().enumerate() {
+            vars.insert(id.as_str(), i);
+            let mut pattern = format!("(n{}", i);
+            for label in &node.labels {
+                write!(pattern, ":{}", cypher_name(label)).unwrap();
+            }
+            let mut properties = node.properties.clone();
+            properties.insert("uri".into(), vec![Value::String(id.clone())]);
+            write!(pattern, " {})", cypher_map(&properties)).unwrap();
+            patterns.push(pattern);
+        }
+        for edge in &self.edges {
+            let mut pattern = format!(
+                "(n{})-[:{}",
+                vars[edge.source.as_str()],
+                cypher_name(&edge.label)
+            );
+            if !edge.properties.is_empty() {
+                write!(pattern, " {}", cypher_map(&edge.properties)).unwrap();
+            }
+            write!(pattern, "]->(n{})", vars[edge.target.as_str()]).unwrap();
+            patterns.push(pattern);
+        }
+        if patterns.is_empty() {
+            return String::new();
+        }
+        format!("CREATE\n  {};\n", patterns.join(",\n  "))
+    }
+}
+
+/// Converts RDF graphs to property graphs, and back.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct PgMapping {
+    namespace: String,
+    names: BTreeMap<String, String>,
+    node_properties: HashSet<String>,
+}
+
+impl PgMapping {
+    /// Build a mapping with the given namespace.
+    pub fn new<T: Into<String>>(namespace: T) -> Self {
+        PgMapping {
+            namespace: namespace.into(),
+            names: BTreeMap::new(),
+            node_properties: HashSet::new(),
+        }
+    }
+
+    /// The namespace of this mapping.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Give `name` to `iri`, in both directions.
+    pub fn set_name<T: Into<String>, U: Into<String>>(&mut self, iri: T, name: U) -> &mut Self {
+        self.names.insert(iri.into(), name.into());
+        self
+    }
+
+    /// Store the values of `predicate` as node properties, even when they are not literals.
+    pub fn set_node_property<T: Into<String>>(&mut self, predicate: T) -> &mut Self {
+        self.node_properties.insert(predicate.into());
+        self
+    }
+
+    /// Whether the values of `predicate` are stored as node properties.
+    pub fn is_node_property(&self, predicate: &str) -> bool {
+        self.node_properties.contains(predicate)
+    }
+
+    /// The name of `iri`.
+    pub fn name(&self, iri: &str) -> String {
+        if let Some(name) = self.names.get(iri) {
+            return name.clone();
+        }
+        if let Some(suffix) = iri.strip_prefix(self.namespace.as_str()) {
+            if !suffix.is_empty() {
+                return suffix.to_string();
+            }
+        }
+        match iri.rfind(['#', '/']) {
+            Some(i) if i + 1 < iri.len() => iri[i + 1..].to_string(),
+            _ => iri.to_string(),
+        }
+    }
+
+    /// The IRI of `name`.
+    pub fn iri(&self, name: &str) -> String {
+        match self.names.iter().find(|(_, n)| *n == name) {
+            Some((iri, _)) => iri.clone(),
+            None => format!("{}{}", self.namespace, name),
+        }
+    }
+
+    /// Convert `graph` into a property graph.
+    ///
+    /// Annotated triples are converted to edges, whether they are asserted or not.
+    pub fn to_property_graph<G: Graph>(&self, graph: &G) -> GResult<G, PropertyGraph> {
+        // the nodes standing for annotated triples
+        let mut reified = HashMap::new();
+        for t in graph.triples_with_p(&rdf::subject) {
+            let node = BoxTerm::copy(t?.s());
+            let value = |p| -> GResult<G, Option<BoxTerm>> {
+                match graph.triples_with_sp(&node, p).next() {
+                    Some(t) => Ok(Some(BoxTerm::copy(t?.o()))),
+                    None => Ok(None),
+                }
+            };
+            if let (Some(s), Some(p), Some(o)) = (
+                value(&rdf::subject)?,
+                value(&rdf::predicate)?,
+                value(&rdf::object)?,
+            ) {
+                reified.insert(node, [s, p, o]);
+            }
+        }
+
+        let mut pg = PropertyGraph::default();
+        let mut edges = BTreeMap::new();
+        let mut annotations = vec![];
+        for t in graph.triples() {
+            let t = t?;
+            let (s, p, o) = (t.s(), t.p(), t.o());
+            if let Some(triple) = reified.get(&BoxTerm::copy(s)) {
+                if !is_reification(p, o) {
+                    annotations.push((triple.clone(), BoxTerm::copy(p), BoxTerm::copy(o)));
+                }
+                continue;
+            }
+            let node = pg.nodes.entry(node_id(s)).or_default();
+            if term_eq(p, &rdf::type_) && o.kind() == TermKind::Iri {
+                node.labels.insert(self.name(&o.value()));
+            } else if o.kind() == TermKind::Literal || self.is_node_property(&p.value()) {
+                node.properties
+                    .entry(self.name(&p.value()))
+                    .or_default()
+                    .push(to_value(o));
+            } else {
+                self.add_edge(&mut pg, &mut edges, s, p, o);
+            }
+        }
+        for ([s, p, o], key, value) in annotations {
+            let edge = self.add_edge(&mut pg, &mut edges, &s, &p, &o);
+            pg.edges[edge]
+                .properties
+                .entry(self.name(&key.value()))
+                .or_default()
+                .push(to_value(&value));
+        }
+
+        // sort edges and values, for determinism
+        let mut order: Vec<usize> = edges.into_values().collect();
+        let mut edges: Vec<Option<Edge>> = pg.edges.drain(..).map(Some).collect();
+        order.sort_unstable_by_key(|i| {
+            let e = edges[*i].as_ref().unwrap();
+            (e.source.clone(), e.label.clone(), e.target.clone())
+        });
+        pg.edges = order
+            .into_iter()
+            .map(|i| edges[i].take().unwrap())
+            .collect();
+        let properties = pg
+            .nodes
+            .values_mut()
+            .map(|n| &mut n.properties)
+            .chain(pg.edges.iter_mut().map(|e| &mut e.properties));
+        for values in properties.flat_map(|p| p.values_mut()) {
+            values.sort_by(|v1, v2| format!("{:?}", v1).cmp(&format!("{:?}", v2)));
+            values.dedup();
+        }
+        Ok(pg)
+    }
+
+    /// Add the triples representing `pg` to `graph`.
+    ///
+    /// Edge properties are inserted as annotations of the corresponding triple
+    /// (see the [`annotation`](../annotation/index.html) module).
+    ///
+    /// Errors raised while querying `graph` are wrapped in `SourceError`,
+    /// errors raised while inserting into `graph` are wrapped in `SinkError`.
+    pub fn to_rdf<G: MutableGraph>(
+        &self,
+        pg: &PropertyGraph,
+        graph: &mut G,
+    ) -> StreamResult<(), G::Error, G::MutationError> {
+        for (id, node) in &pg.nodes {
+            let s = self.node(id);
+            for label in &node.labels {
+                let o = iri(self.iri(label));
+                graph.insert(&s, &rdf::type_, &o).map_err(SinkError)?;
+            }
+            for (key, values) in &node.properties {
+                let p = iri(self.iri(key));
+                let as_node = self.is_node_property(&p.value());
+                for value in values {
+                    let o = match value {
+                        Value::String(txt) if as_node => self.node(txt),
+                        _ => to_literal(value),
+                    };
+                    graph.insert(&s, &p, &o).map_err(SinkError)?;
+                }
+            }
+        }
+        for edge in &pg.edges {
+            let triple = [
+                self.node(&edge.source),
+                iri(self.iri(&edge.label)),
+                self.node(&edge.target),
+            ];
+            graph
+                .insert(&triple[0], &triple[1], &triple[2])
+                .map_err(SinkError)?;
+            for (key, values) in &edge.properties {
+                let p = iri(self.iri(key));
+                for value in values {
+                    assert_annotated(graph, &triple, &p, &to_literal(value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add (if needed) the edge s-p->o, and return its index.
+    fn add_edge<T: TTerm + ?Sized>(
+        &self,
+        pg: &mut PropertyGraph,
+        edges: &mut BTreeMap<[String; 3], usize>,
+        s: &T,
+        p: &T,
+        o: &T,
+    ) -> usize {
+        let key = [node_id(s), p.value().to_string(), node_id(o)];
+        if let Some(i) = edges.get(&key) {
+            return *i;
+        }
+        pg.nodes.entry(key[0].clone()).or_default();
+        pg.nodes.entry(key[2].clone()).or_default();
+        pg.edges.push(Edge {
+            source: key[0].clone(),
+            label: self.name(&key[1]),
+            target: key[2].clone(),
+            properties: Properties::new(),
+        });
+        edges.insert(key, pg.edges.len() - 1);
+        pg.edges.len() - 1
+    }
+
+    /// The term identified by the node identifier `id`.
+    fn node(&self, id: &str) -> BoxTerm {
+        match id.strip_prefix("_:") {
+            Some(bnode) => BoxTerm::new_bnode(bnode)
+                .unwrap_or_else(|_| iri(format!("{}{}", self.namespace, id))),
+            None if is_absolute_iri_ref(id) => iri(id.to_string()),
+            None => iri(format!("{}{}", self.namespace, id)),
+        }
+    }
+}
+
+fn iri(txt: String) -> BoxTerm {
+    BoxTerm::new_iri(txt.as_str()).unwrap_or_else(|_| BoxTerm::new_iri_unchecked(txt))
+}
+
+fn node_id<T: TTerm + ?Sized>(term: &T) -> String {
+    match term.kind() {
+        TermKind::BlankNode => format!("_:{}", term.value()),
+        _ => term.value().to_string(),
+    }
+}
+
+/// Whether (`p`, `o`) belongs to the reification vocabulary.
+fn is_reification<P, O>(p: &P, o: &O) -> bool
+where
+    P: TTerm + ?Sized,
+    O: TTerm + ?Sized,
+{
+    term_eq(p, &rdf::subject)
+        || term_eq(p, &rdf::predicate)
+        || term_eq(p, &rdf::object)
+        || (term_eq(p, &rdf::type_) && term_eq(o, &rdf::Statement))
+}
+
+fn to_value<T: TTerm + ?Sized>(term: &T) -> Value {
+    let value = term.value();
+    if term.kind() != TermKind::Literal {
+        return Value::String(node_id(term));
+    }
+    let dt = term.datatype().unwrap();
+    if dt == xsd::boolean {
+        match &value[..] {
+            "true" | "1" => return Value::Boolean(true),
+            "false" | "0" => return Value::Boolean(false),
+            _ => (),
+        }
+    } else if INTEGER_TYPES.iter().any(|t| dt == **t) {
+        if let Ok(i) = value.trim().parse::<i64>() {
+            return Value::Integer(i);
+        }
+    } else if dt == xsd::decimal || dt == xsd::double || dt == xsd::float {
+        if let Ok(f) = value.trim().parse::<f64>() {
+            if f.is_finite() {
+                return Value::Float(f);
+            }
+        }
+    }
+    Value::String(value.to_string())
+}
+
+fn to_literal(value: &Value) -> BoxTerm {
+    match value {
+        Value::String(txt) => BoxTerm::new_literal_dt(txt.as_str(), xsd::string),
+        Value::Integer(i) => BoxTerm::new_literal_dt(i.to_string(), xsd::integer),
+        Value::Float(f) => BoxTerm::new_literal_dt(format!("{:e}", f), xsd::double),
+        Value::Boolean(b) => BoxTerm::new_literal_dt(b.to_string(), xsd::boolean),
+    }
+    .unwrap()
+}
+
+const INTEGER_TYPES: [&sophia_api::term::SimpleIri<'static>; 13] = [
+    &xsd::integer,
+    &xsd::long,
+    &xsd::int,
+    &xsd::short,
+    &xsd::byte,
+    &xsd::nonNegativeInteger,
+    &xsd::positiveInteger,
+    &xsd::nonPositiveInteger,
+    &xsd::negativeInteger,
+    &xsd::unsignedLong,
+    &xsd::unsignedInt,
+    &xsd::unsignedShort,
+    &xsd::unsignedByte,
+];
+
+fn cypher_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn cypher_value(value: &Value) -> String {
+    match value {
+        Value::String(txt) => format!("'{}'", txt.replace('\\', "\\\\").replace('\'', "\\'")),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => format!("{:?}", f),
+        Value::Boolean(b) => b.to_string(),
+    }
+}
+
+fn cypher_map(properties: &Properties) -> String {
+    let entries: Vec<String> = properties
+        .iter()
+        .map(|(key, values)| {
+            let value = match &values[..] {
+                [value] => cypher_value(value),
+                _ => {
+                    let values: Vec<_> = values.iter().map(cypher_value).collect();
+                    format!("[{}]", values.join(", "))
+                }
+            };
+            format!("{}: {}", cypher_name(key), value)
+        })
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::nt12::NTriples12Parser;
+    use sophia_api::parser::TripleParser;
+    use sophia_api::triple::stream::TripleSource;
+
+    const DATA: &str = r#"
+        <tag:alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://xmlns.com/foaf/0.1/Person>.
+        <tag:alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <tag:Admin>.
+        <tag:alice> <http://xmlns.com/foaf/0.1/name> "Alice".
+        <tag:alice> <http://xmlns.com/foaf/0.1/name> "Alicia"@es.
+        <tag:alice> <tag:age> "42"^^<http://www.w3.org/2001/XMLSchema#integer>.
+        <tag:alice> <tag:homepage> <http://alice.example/>.
+        <tag:alice> <http://xmlns.com/foaf/0.1/knows> <tag:bob>.
+        <tag:alice> <http://xmlns.com/foaf/0.1/knows> _:c.
+        << <tag:alice> <http://xmlns.com/foaf/0.1/knows> <tag:bob> >> <tag:since> "2001"^^<http://www.w3.org/2001/XMLSchema#gYear>.
+        << <tag:alice> <http://xmlns.com/foaf/0.1/knows> <tag:bob> >> <tag:weight> "0.5"^^<http://www.w3.org/2001/XMLSchema#decimal>.
+    "#;
+
+    fn parse(nt: &str) -> Result<FastGraph, Box<dyn std::error::Error>> {
+        Ok(NTriples12Parser {
+            star: true,
+            ..NTriples12Parser::default()
+        }
+        .parse_str(nt)
+        .collect_triples()?)
+    }
+
+    #[test]
+    fn to_property_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(DATA)?;
+        let mut mapping = PgMapping::new("tag:");
+        mapping
+            .set_name("http://xmlns.com/foaf/0.1/name", "fullName")
+            .set_node_property("tag:homepage");
+        let pg = mapping.to_property_graph(&g)?;
+        assert_eq!(pg.nodes.len(), 3);
+        let alice = &pg.nodes["tag:alice"];
+        assert_eq!(
+            alice.labels.iter().collect::<Vec<_>>(),
+            vec!["Admin", "Person"]
+        );
+        assert_eq!(
+            alice.properties["fullName"],
+            vec![
+                Value::String("Alice".into()),
+                Value::String("Alicia".into())
+            ]
+        );
+        assert_eq!(alice.properties["age"], vec![Value::Integer(42)]);
+        assert_eq!(
+            alice.properties["homepage"],
+            vec![Value::String("http://alice.example/".into())]
+        );
+        assert!(pg.nodes["tag:bob"].labels.is_empty());
+
+        assert_eq!(pg.edges.len(), 2);
+        assert!(pg.edges[0].target.starts_with("_:"));
+        let bob = &pg.edges[1];
+        assert_eq!(
+            (bob.label.as_str(), bob.target.as_str()),
+            ("knows", "tag:bob")
+        );
+        assert_eq!(bob.properties["since"], vec![Value::String("2001".into())]);
+        assert_eq!(bob.properties["weight"], vec![Value::Float(0.5)]);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(DATA)?;
+        let mut mapping = PgMapping::new("tag:");
+        mapping.set_node_property("tag:homepage");
+        let pg = mapping.to_property_graph(&g)?;
+
+        let mut g2 = FastGraph::new();
+        mapping.to_rdf(&pg, &mut g2)?;
+        assert_eq!(mapping.to_property_graph(&g2)?, pg);
+        // names without explicit IRI go to the namespace
+        assert!(g2.contains(
+            &BoxTerm::new_iri("tag:alice")?,
+            &rdf::type_,
+            &BoxTerm::new_iri("tag:Person")?
+        )?);
+        // node properties are converted back to IRIs
+        assert!(g2.contains(
+            &BoxTerm::new_iri("tag:alice")?,
+            &BoxTerm::new_iri("tag:homepage")?,
+            &BoxTerm::new_iri("http://alice.example/")?
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn cypher() -> Result<(), Box<dyn std::error::Error>> {
+        let g = parse(
+            "<tag:a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <tag:T>.
+             <tag:a> <tag:n> \"it's\".
+             <tag:a> <tag:p> <tag:b>.
+             << <tag:a> <tag:p> <tag:b> >> <tag:w> \"1\"^^<http://www.w3.org/2001/XMLSchema#int>.
+             << <tag:a> <tag:p> <tag:b> >> <tag:w> \"2\"^^<http://www.w3.org/2001/XMLSchema#int>.",
+        )?;
+        let pg = PgMapping::new("tag:").to_property_graph(&g)?;
+        assert_eq!(
+            pg.to_cypher(),
+            "CREATE\n  (n0:`T` {`n`: 'it\\'s', `uri`: 'tag:a'}),\n  (n1 {`uri`: 'tag:b'}),\n  \
+             (n0)-[:`p` {`w`: [1, 2]}]->(n1);\n"
+        );
+        assert_eq!(PropertyGraph::default().to_cypher(), "");
+        Ok(())
+    }
+}
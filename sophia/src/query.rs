@@ -12,6 +12,8 @@ use sophia_api::term::{CopyTerm, TTerm};
 use sophia_term::*;
 
 use crate::graph::*;
+use crate::triple::stream::TripleSource;
+use crate::triple::streaming_mode::ByValue;
 use crate::triple::*;
 
 /// A map associating variable names to [`term`](../term/enum.Term.html)s.
@@ -76,6 +78,125 @@ impl Query {
             Query::Triples(triples) => bindings_for_triples(graph, triples, initial_bindings),
         }
     }
+
+    /// Join this query with the given table of bindings,
+    /// as with a [SPARQL `VALUES`](https://www.w3.org/TR/sparql11-query/#inline-data) clause,
+    /// and return an fallible iterator of the extended BindingMaps.
+    ///
+    /// Each row of `values` is used as the initial bindings of the query;
+    /// variables absent from a row are unconstrained (as with `UNDEF` in SPARQL).
+    /// The rows of `values` typically come from
+    /// [`SelectResults::solutions`](../sparql_results/struct.SelectResults.html#method.solutions)
+    /// or from a previous query.
+    ///
+    /// The iterator may fail (i.e. yield `Err`) if an operation on the graph fails.
+    pub fn process_values<'s, G, I>(
+        &'s mut self,
+        graph: &'s G,
+        values: I,
+    ) -> Box<dyn Iterator<Item = GResult<G, BindingMap>> + 's>
+    where
+        G: Graph,
+        I: IntoIterator<Item = BindingMap>,
+        I::IntoIter: 's,
+    {
+        self.prepare(graph, &BindingMap::new());
+        match self {
+            Query::Triples(triples) => {
+                let triples = &*triples;
+                Box::new(
+                    values
+                        .into_iter()
+                        .flat_map(move |row| bindings_for_triples(graph, triples, row)),
+                )
+            }
+        }
+    }
+}
+
+/// A graph template, as in [SPARQL CONSTRUCT queries](https://www.w3.org/TR/sparql11-query/#construct),
+/// producing triples from a sequence of binding maps.
+///
+/// For each binding map, each triple of the template is instantiated
+/// by replacing its variables with their value in the map.
+/// As in SPARQL, blank nodes of the template are renamed for each binding map,
+/// and instantiated triples that still contain a variable,
+/// or that are otherwise not valid in strict RDF (e.g. with a literal as subject),
+/// are skipped.
+pub struct Template {
+    triples: Vec<[RcTerm; 3]>,
+}
+
+impl Template {
+    /// Build a template from the given triples.
+    pub fn new<I>(triples: I) -> Template
+    where
+        I: IntoIterator,
+        I::Item: Triple,
+    {
+        let triples = triples
+            .into_iter()
+            .map(|t| {
+                [
+                    RcTerm::copy(t.s()),
+                    RcTerm::copy(t.p()),
+                    RcTerm::copy(t.o()),
+                ]
+            })
+            .collect();
+        Template { triples }
+    }
+
+    /// The triples of this template.
+    pub fn triples(&self) -> &[[RcTerm; 3]] {
+        &self.triples
+    }
+
+    /// Instantiate this template with the given binding map.
+    ///
+    /// Blank nodes are suffixed with `_{solution}`,
+    /// so distinct values of `solution` produce distinct blank nodes.
+    pub fn instantiate(&self, bindings: &BindingMap, solution: usize) -> Vec<[RcTerm; 3]> {
+        let subst = |t: &RcTerm| match t {
+            Term::Variable(var) => bindings
+                .get(var.as_str())
+                .cloned()
+                .unwrap_or_else(|| t.clone()),
+            Term::BNode(bnode) => {
+                RcTerm::new_bnode_unchecked(format!("{}_{}", bnode.as_str(), solution))
+            }
+            _ => t.clone(),
+        };
+        self.triples
+            .iter()
+            .map(|t| [subst(t.s()), subst(t.p()), subst(t.o())])
+            .filter(|t| !t.is_generalized())
+            .collect()
+    }
+
+    /// Instantiate this template with each binding map of `bindings`,
+    /// and return the resulting triples as a `TripleSource`.
+    ///
+    /// `bindings` is typically the result of [`Query::process`](enum.Query.html#method.process),
+    /// but could come from any other source (e.g. a remote SPARQL endpoint).
+    /// Note that the resulting triples may contain duplicates.
+    pub fn construct<'a, I, E>(
+        &'a self,
+        bindings: I,
+    ) -> impl TripleSource<Error = E, Triple = ByValue<[RcTerm; 3]>> + 'a
+    where
+        I: IntoIterator<Item = Result<BindingMap, E>> + 'a,
+        E: std::error::Error + 'static,
+    {
+        bindings.into_iter().enumerate().flat_map(
+            move |(i, res)| -> Box<dyn Iterator<Item = Result<_, E>>> {
+                match res {
+                    Ok(b) => Box::new(self.instantiate(&b, i).into_iter().map(Ok)),
+                    Err(err) => Box::new(once(Err(err))),
+                }
+            },
+        )
+    }
 }
 
 /// Iter over the bindings of all triples in `q` for graph `g`, given the binding `b`.
@@ -334,6 +455,94 @@ mod test {
         assert_eq!(results[2], "http://example.org/charlie Charlie");
     }
 
+    #[test]
+    fn test_query_values() {
+        let g = data();
+
+        let schema = Namespace::new("http://schema.org/").unwrap();
+        let s_name = schema.get("name").unwrap();
+        let s_member = schema.get("member").unwrap();
+
+        let v1 = RcTerm::new_variable("v1").unwrap();
+        let v2 = RcTerm::new_variable("v2").unwrap();
+
+        let mut q = Query::Triples(vec![[v1.clone(), s_name.copied(), v2.clone()]]);
+        let csv = "v1,org\r\n\
+                   http://example.org/alice,http://example.org/alice_n_bob\r\n\
+                   http://example.org/dan,\r\n\
+                   http://example.org/eve,\r\n";
+        let values = crate::sparql_results::SelectResults::from_csv(csv.as_bytes()).unwrap();
+        let results: Result<Vec<BindingMap>, _> = q
+            .process_values(&g, values.solutions().iter().cloned())
+            .collect();
+        let mut results: Vec<_> = results
+            .unwrap()
+            .into_iter()
+            .map(|b| {
+                format!(
+                    "{} {} {}",
+                    b.get("v1").unwrap().value(),
+                    b.get("v2").unwrap().value(),
+                    b.get("org")
+                        .map(|t| t.value().to_string())
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                "http://example.org/alice Alice http://example.org/alice_n_bob",
+                "http://example.org/dan Dan ",
+            ]
+        );
+
+        // rows leaving the join variables unbound match everything
+        let mut q = Query::Triples(vec![[v1.clone(), s_member.copied(), v2.clone()]]);
+        let rows = vec![BindingMap::new(), BindingMap::new()];
+        assert_eq!(q.process_values(&g, rows).count(), 4);
+    }
+
+    #[test]
+    fn test_template_construct() {
+        let g = data();
+
+        let schema = Namespace::new("http://schema.org/").unwrap();
+        let s_person = schema.get("Person").unwrap();
+        let s_name = schema.get("name").unwrap();
+        let foaf = Namespace::new("http://xmlns.com/foaf/0.1/").unwrap();
+        let f_name = foaf.get("name").unwrap();
+        let f_account = foaf.get("account").unwrap();
+
+        let v1 = RcTerm::new_variable("v1").unwrap();
+        let v2 = RcTerm::new_variable("v2").unwrap();
+        let v3 = RcTerm::new_variable("v3").unwrap();
+        let b = RcTerm::new_bnode("b").unwrap();
+
+        let mut q = Query::Triples(vec![
+            [v1.clone(), s_name.copied(), v2.clone()],
+            [v1.clone(), rdf::type_.copied(), s_person.copied()],
+        ]);
+        let template = Template::new(vec![
+            [v1.clone(), f_name.copied(), v2.clone()],
+            [v1.clone(), f_account.copied(), b.clone()],
+            // skipped, because v3 is unbound
+            [v1.clone(), f_name.copied(), v3.clone()],
+            // skipped, because v2 is bound to a literal
+            [v2.clone(), f_name.copied(), v1.clone()],
+        ]);
+
+        let mut h = FastGraph::new();
+        let n = template
+            .construct(q.process(&g))
+            .add_to_graph(&mut h)
+            .unwrap();
+        assert_eq!(n, 6);
+        assert_eq!(h.triples_with_p(&f_name).count(), 3);
+        assert_eq!(h.bnodes().unwrap().len(), 3);
+    }
+
     fn data() -> FastGraph {
         let schema = Namespace::new("http://schema.org/").unwrap();
         let s_person = schema.get("Person").unwrap();
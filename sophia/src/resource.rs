@@ -0,0 +1,322 @@
+//! A resource-centric API on top of graphs.
+//!
+//! A [`Resource`] is a handle wrapping a graph and a node of that graph,
+//! providing accessors to the values of its properties,
+//! and navigation to related resources.
+//! A [`ResourceMut`] additionally allows to modify those properties.
+//!
+//! This is more convenient than working at the triple level
+//! for CRUD-style code.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::ns::Namespace;
+//! use sophia::resource::{Resource, ResourceMut};
+//! use sophia::term::literal::convert::AsLiteral;
+//! use sophia::term::BoxTerm;
+//!
+//! let foaf = Namespace::new("http://xmlns.com/foaf/0.1/")?;
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! let bob = BoxTerm::new_iri("tag:bob")?;
+//!
+//! let mut g = FastGraph::new();
+//! let mut r = ResourceMut::new(&mut g, &alice);
+//! r.set(&foaf.get("age")?, &41.as_literal())?;
+//! r.set(&foaf.get("age")?, &42.as_literal())?;
+//! r.add(&foaf.get("knows")?, &bob)?;
+//!
+//! let r = Resource::new(&g, &alice);
+//! assert_eq!(r.get_literal::<i32>(&foaf.get("age")?)?, Some(42));
+//! let friend = r.get_resource(&foaf.get("knows")?)?.unwrap();
+//! assert_eq!(friend.subject(), &bob);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Resource`]: struct.Resource.html
+//! [`ResourceMut`]: struct.ResourceMut.html
+
+use std::error::Error;
+use std::str::FromStr;
+
+use sophia_api::graph::{GTerm, MGResult, MutableGraph};
+use sophia_api::term::matcher::ANY;
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::literal::convert::TryConvertTerm;
+use sophia_term::{BoxTerm, TermError};
+use thiserror::Error;
+
+use crate::graph::{GResult, Graph};
+use crate::ns::rdf;
+use crate::triple::Triple;
+
+/// An error raised by [`Resource::get_literal`](struct.Resource.html#method.get_literal).
+#[derive(Debug, Error)]
+pub enum ResourceError<E>
+where
+    E: Error + 'static,
+{
+    /// The underlying graph raised an error.
+    #[error("{0}")]
+    Graph(E),
+    /// The value can not be converted to the requested type.
+    #[error("{0}")]
+    Term(#[from] TermError),
+}
+
+/// A handle on a node of a graph.
+///
+/// See [module documentation](index.html).
+#[derive(Debug)]
+pub struct Resource<'g, G: ?Sized> {
+    graph: &'g G,
+    subject: BoxTerm,
+}
+
+impl<G: ?Sized> Clone for Resource<'_, G> {
+    fn clone(&self) -> Self {
+        Resource {
+            graph: self.graph,
+            subject: self.subject.clone(),
+        }
+    }
+}
+
+impl<'g, G: Graph + ?Sized> Resource<'g, G> {
+    /// A handle on `subject` in `graph`.
+    pub fn new<T: TTerm + ?Sized>(graph: &'g G, subject: &T) -> Self {
+        Resource {
+            graph,
+            subject: BoxTerm::copy(subject),
+        }
+    }
+
+    /// The underlying graph.
+    pub fn graph(&self) -> &'g G {
+        self.graph
+    }
+
+    /// The node wrapped by this handle.
+    pub fn subject(&self) -> &BoxTerm {
+        &self.subject
+    }
+
+    /// One of the values of `pred` for this resource, if any.
+    ///
+    /// If this resource has several values for `pred`, which one is returned is unspecified.
+    pub fn get<T: TTerm + ?Sized>(&self, pred: &T) -> GResult<G, Option<BoxTerm>> {
+        match self.graph.triples_with_sp(&self.subject, pred).next() {
+            None => Ok(None),
+            Some(t) => Ok(Some(BoxTerm::copy(t?.o()))),
+        }
+    }
+
+    /// All the values of `pred` for this resource.
+    pub fn get_all<T: TTerm + ?Sized>(&self, pred: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.graph
+            .triples_with_sp(&self.subject, pred)
+            .map(|t| t.map(|t| BoxTerm::copy(t.o())))
+            .collect()
+    }
+
+    /// One of the values of `pred` for this resource, converted to a native type.
+    ///
+    /// Fails if that value is not a literal of the datatype corresponding to `U`.
+    pub fn get_literal<U>(
+        &self,
+        pred: &(impl TTerm + ?Sized),
+    ) -> Result<Option<U>, ResourceError<G::Error>>
+    where
+        U: TryConvertTerm,
+        <U as FromStr>::Err: Error + 'static,
+    {
+        match self.get(pred).map_err(ResourceError::Graph)? {
+            None => Ok(None),
+            Some(value) => Ok(Some(U::try_convert(&value)?)),
+        }
+    }
+
+    /// Whether this resource has `value` among the values of `pred`.
+    pub fn has<T, U>(&self, pred: &T, value: &U) -> GResult<G, bool>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.graph.contains(&self.subject, pred, value)
+    }
+
+    /// The types (values of `rdf:type`) of this resource.
+    pub fn types(&self) -> GResult<G, Vec<BoxTerm>> {
+        self.get_all(&rdf::type_)
+    }
+
+    /// A handle on one of the values of `pred` for this resource, if any.
+    pub fn get_resource<T: TTerm + ?Sized>(&self, pred: &T) -> GResult<G, Option<Self>> {
+        Ok(self.get(pred)?.map(|subject| Resource {
+            graph: self.graph,
+            subject,
+        }))
+    }
+
+    /// Handles on all the values of `pred` for this resource.
+    pub fn get_resources<T: TTerm + ?Sized>(&self, pred: &T) -> GResult<G, Vec<Self>> {
+        Ok(self.related(self.get_all(pred)?))
+    }
+
+    /// Handles on all the resources having this resource as a value of `pred`.
+    pub fn referrers<T: TTerm + ?Sized>(&self, pred: &T) -> GResult<G, Vec<Self>> {
+        let subjects = self
+            .graph
+            .triples_with_po(pred, &self.subject)
+            .map(|t| t.map(|t| BoxTerm::copy(t.s())))
+            .collect::<Result<_, _>>()?;
+        Ok(self.related(subjects))
+    }
+
+    fn related(&self, subjects: Vec<BoxTerm>) -> Vec<Self> {
+        subjects
+            .into_iter()
+            .map(|subject| Resource {
+                graph: self.graph,
+                subject,
+            })
+            .collect()
+    }
+}
+
+/// A handle on a node of a mutable graph.
+///
+/// See [module documentation](index.html).
+#[derive(Debug)]
+pub struct ResourceMut<'g, G: ?Sized> {
+    graph: &'g mut G,
+    subject: BoxTerm,
+}
+
+impl<'g, G: MutableGraph + ?Sized> ResourceMut<'g, G> {
+    /// A mutable handle on `subject` in `graph`.
+    pub fn new<T: TTerm + ?Sized>(graph: &'g mut G, subject: &T) -> Self {
+        ResourceMut {
+            graph,
+            subject: BoxTerm::copy(subject),
+        }
+    }
+
+    /// The node wrapped by this handle.
+    pub fn subject(&self) -> &BoxTerm {
+        &self.subject
+    }
+
+    /// A read-only handle on the same node, giving access to its properties.
+    pub fn as_resource(&self) -> Resource<'_, G> {
+        Resource {
+            graph: self.graph,
+            subject: self.subject.clone(),
+        }
+    }
+
+    /// Add `value` to the values of `pred` for this resource.
+    pub fn add<T, U>(&mut self, pred: &T, value: &U) -> MGResult<G, &mut Self>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.graph.insert(&self.subject, pred, value)?;
+        Ok(self)
+    }
+
+    /// Remove `value` from the values of `pred` for this resource.
+    pub fn remove<T, U>(&mut self, pred: &T, value: &U) -> MGResult<G, &mut Self>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.graph.remove(&self.subject, pred, value)?;
+        Ok(self)
+    }
+
+    /// Remove all the values of `pred` for this resource.
+    pub fn remove_all<T>(&mut self, pred: &T) -> MGResult<G, &mut Self>
+    where
+        T: TTerm + ?Sized,
+        GTerm<G>: Clone,
+        <G as Graph>::Error: Into<G::MutationError>,
+    {
+        self.graph.remove_matching(&self.subject, pred, &ANY)?;
+        Ok(self)
+    }
+
+    /// Replace all the values of `pred` for this resource by `value`.
+    pub fn set<T, U>(&mut self, pred: &T, value: &U) -> MGResult<G, &mut Self>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+        GTerm<G>: Clone,
+        <G as Graph>::Error: Into<G::MutationError>,
+    {
+        self.remove_all(pred)?.add(pred, value)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use crate::triple::stream::TripleSource;
+
+    #[test]
+    fn read() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            <tag:alice> a <tag:Person>; <tag:age> 42; <tag:name> "Alice";
+                <tag:knows> <tag:bob>, <tag:carol>.
+            <tag:carol> <tag:knows> <tag:alice>.
+        "#,
+        )
+        .collect_triples()?;
+        let alice = Resource::new(&g, &t("alice"));
+        assert_eq!(alice.types()?, vec![t("Person")]);
+        // xsd:integer is not xsd:long
+        assert!(alice.get_literal::<i64>(&t("age")).is_err());
+        assert_eq!(alice.get_literal::<String>(&t("name"))?.unwrap(), "Alice");
+        assert!(alice.get_literal::<bool>(&t("name")).is_err());
+        assert_eq!(alice.get_literal::<bool>(&t("missing"))?, None);
+        assert!(alice.get(&t("missing"))?.is_none());
+        assert_eq!(alice.get_all(&t("knows"))?.len(), 2);
+        assert!(alice.has(&t("knows"), &t("bob"))?);
+
+        let referrers = alice.referrers(&t("knows"))?;
+        assert_eq!(referrers.len(), 1);
+        assert_eq!(referrers[0].subject(), &t("carol"));
+        let friends_of_friends: usize = alice
+            .get_resources(&t("knows"))?
+            .iter()
+            .map(|r| r.get_all(&t("knows")).unwrap().len())
+            .sum();
+        assert_eq!(friends_of_friends, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn write() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        let mut alice = ResourceMut::new(&mut g, &t("alice"));
+        alice
+            .add(&t("knows"), &t("bob"))?
+            .add(&t("knows"), &t("carol"))?
+            .add(&rdf::type_, &t("Person"))?;
+        assert_eq!(alice.as_resource().get_all(&t("knows"))?.len(), 2);
+        alice.set(&t("knows"), &t("dan"))?;
+        assert_eq!(alice.as_resource().get_all(&t("knows"))?, vec![t("dan")]);
+        alice.remove(&t("knows"), &t("dan"))?;
+        assert!(alice.as_resource().get(&t("knows"))?.is_none());
+        assert_eq!(g.triples().count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+//! Rewriting of IRIs, e.g. for migrating data to a new namespace.
+//!
+//! An [`IriRewriter`] holds a list of rules, each of them rewriting some IRIs:
+//! * a prefix rule replaces a given prefix by another one
+//!   (e.g. `http://example.org/` by `https://example.org/`, or `http://schema.org/v1/` by `http://schema.org/v2/`);
+//! * a regex rule (requires the `regex` feature) replaces the first match of a regular expression.
+//!
+//! The first rule applying to an IRI is used, and the others are ignored.
+//!
+//! The same rules are applied consistently to subjects, predicates, objects and graph names,
+//! either on the fly to a triple or quad source
+//! (see [`rewrite_triples`] and [`rewrite_quads`]),
+//! or in place in a graph or dataset
+//! (see [`rewrite_graph`] and [`rewrite_dataset`]).
+//! Datatype IRIs of literals are never rewritten,
+//! so that broad rules (e.g. `http://` to `https://`) do not alter standard datatypes.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::rewrite::IriRewriter;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let mut rewriter = IriRewriter::new();
+//! rewriter.add_prefix("http://example.org/", "https://example.org/");
+//!
+//! let src = turtle::parse_str(r#"
+//!     <http://example.org/alice> <http://xmlns.com/foaf/0.1/knows> <http://example.org/bob>.
+//! "#);
+//! let g: FastGraph = rewriter.rewrite_triples(src).collect_triples()?;
+//! let alice = BoxTerm::new_iri("https://example.org/alice")?;
+//! assert_eq!(g.triples_with_s(&alice).count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`IriRewriter`]: struct.IriRewriter.html
+//! [`rewrite_triples`]: struct.IriRewriter.html#method.rewrite_triples
+//! [`rewrite_quads`]: struct.IriRewriter.html#method.rewrite_quads
+//! [`rewrite_graph`]: struct.IriRewriter.html#method.rewrite_graph
+//! [`rewrite_dataset`]: struct.IriRewriter.html#method.rewrite_dataset
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::graph::MutableGraph;
+use sophia_api::quad::stream::{QSTerm, QuadSource};
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{StreamError, StreamResult, TSTerm, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// A rule of an [`IriRewriter`](struct.IriRewriter.html).
+#[derive(Clone, Debug)]
+pub enum RewriteRule {
+    /// Replace the prefix `from` of IRIs by `to`.
+    Prefix {
+        /// The prefix to replace.
+        from: String,
+        /// The replacement prefix.
+        to: String,
+    },
+    /// Replace the first match of `pattern` in IRIs by `replacement`,
+    /// where `replacement` can refer to capture groups (see [`Regex::replace`]).
+    ///
+    /// [`Regex::replace`]: https://docs.rs/regex/latest/regex/struct.Regex.html#method.replace
+    #[cfg(feature = "regex")]
+    Regex {
+        /// The pattern to search.
+        pattern: regex::Regex,
+        /// The replacement of the first match.
+        replacement: String,
+    },
+}
+
+impl RewriteRule {
+    /// Apply this rule to `iri`, or return `None` if it does not apply.
+    pub fn apply(&self, iri: &str) -> Option<String> {
+        match self {
+            RewriteRule::Prefix { from, to } => iri
+                .strip_prefix(from.as_str())
+                .map(|suffix| format!("{}{}", to, suffix)),
+            #[cfg(feature = "regex")]
+            RewriteRule::Regex {
+                pattern,
+                replacement,
+            } => {
+                if pattern.is_match(iri) {
+                    Some(pattern.replace(iri, replacement.as_str()).into_owned())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Rewrites IRIs according to a list of rules.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct IriRewriter {
+    rules: Vec<RewriteRule>,
+}
+
+impl IriRewriter {
+    /// Build a rewriter with no rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rules of this rewriter, in the order in which they are tried.
+    pub fn rules(&self) -> &[RewriteRule] {
+        &self.rules
+    }
+
+    /// Add a rule.
+    pub fn add_rule(&mut self, rule: RewriteRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Add a rule replacing the prefix `from` by `to`.
+    pub fn add_prefix<T: Into<String>, U: Into<String>>(&mut self, from: T, to: U) -> &mut Self {
+        self.add_rule(RewriteRule::Prefix {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+
+    /// Add a rule replacing the first match of `pattern` by `replacement`.
+    ///
+    /// Fails if `pattern` is not a valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn add_regex<T: Into<String>>(
+        &mut self,
+        pattern: &str,
+        replacement: T,
+    ) -> Result<&mut Self, regex::Error> {
+        Ok(self.add_rule(RewriteRule::Regex {
+            pattern: regex::Regex::new(pattern)?,
+            replacement: replacement.into(),
+        }))
+    }
+
+    /// Rewrite `iri` with the first applicable rule,
+    /// or return `None` if no rule applies.
+    pub fn rewrite_iri(&self, iri: &str) -> Option<String> {
+        self.rules.iter().find_map(|rule| rule.apply(iri))
+    }
+
+    /// Rewrite `term` if it is an IRI, or copy it unchanged otherwise.
+    ///
+    /// IRIs for which rewriting would produce an invalid IRI are left unchanged.
+    pub fn rewrite<T: TTerm + ?Sized>(&self, term: &T) -> BoxTerm {
+        self.try_rewrite(term)
+            .unwrap_or_else(|| BoxTerm::copy(term))
+    }
+
+    /// Rewrite `term` if it is an IRI and some rule applies to it.
+    fn try_rewrite<T: TTerm + ?Sized>(&self, term: &T) -> Option<BoxTerm> {
+        if term.kind() != TermKind::Iri {
+            return None;
+        }
+        let iri = self.rewrite_iri(&term.value())?;
+        BoxTerm::new_iri(iri).ok()
+    }
+
+    /// Rewrite the triples of `source` on the fly.
+    pub fn rewrite_triples<'a, TS>(
+        &'a self,
+        source: TS,
+    ) -> impl TripleSource<Error = TS::Error> + 'a
+    where
+        TS: TripleSource + 'a,
+    {
+        source.map_triple_terms(move |t: &TSTerm<TS>| self.rewrite(t))
+    }
+
+    /// Rewrite the quads of `source` on the fly.
+    pub fn rewrite_quads<'a, QS>(&'a self, source: QS) -> impl QuadSource<Error = QS::Error> + 'a
+    where
+        QS: QuadSource + 'a,
+    {
+        source.map_quad_terms(move |t: &QSTerm<QS>| self.rewrite(t))
+    }
+
+    /// Rewrite the triples of `graph` in place,
+    /// and return the number of triples that were rewritten.
+    pub fn rewrite_graph<G: MutableGraph>(
+        &self,
+        graph: &mut G,
+    ) -> StreamResult<usize, G::Error, G::MutationError> {
+        let mut changes = vec![];
+        for t in graph.triples() {
+            let t = t.map_err(StreamError::SourceError)?;
+            let old = [t.s(), t.p(), t.o()].map(BoxTerm::copy);
+            let new = old.clone().map(|term| self.rewrite(&term));
+            if new != old {
+                changes.push((old, new));
+            }
+        }
+        for ([s, p, o], _) in &changes {
+            graph.remove(s, p, o).map_err(StreamError::SinkError)?;
+        }
+        for (_, [s, p, o]) in &changes {
+            graph.insert(s, p, o).map_err(StreamError::SinkError)?;
+        }
+        Ok(changes.len())
+    }
+
+    /// Rewrite the quads of `dataset` in place,
+    /// and return the number of quads that were rewritten.
+    pub fn rewrite_dataset<D: MutableDataset>(
+        &self,
+        dataset: &mut D,
+    ) -> StreamResult<usize, D::Error, D::MutationError> {
+        let mut changes = vec![];
+        for q in dataset.quads() {
+            let q = q.map_err(StreamError::SourceError)?;
+            let old = [q.s(), q.p(), q.o()].map(BoxTerm::copy);
+            let old_g = q.g().map(BoxTerm::copy);
+            let new = old.clone().map(|term| self.rewrite(&term));
+            let new_g = old_g.as_ref().map(|g| self.rewrite(g));
+            if new != old || new_g != old_g {
+                changes.push(((old, old_g), (new, new_g)));
+            }
+        }
+        for (([s, p, o], g), _) in &changes {
+            dataset
+                .remove(s, p, o, g.as_ref())
+                .map_err(StreamError::SinkError)?;
+        }
+        for (_, ([s, p, o], g)) in &changes {
+            dataset
+                .insert(s, p, o, g.as_ref())
+                .map_err(StreamError::SinkError)?;
+        }
+        Ok(changes.len())
+    }
+}
+
+// ---- tests ----
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use crate::ns::xsd;
+
+    fn iri(txt: &str) -> BoxTerm {
+        BoxTerm::new_iri(txt).unwrap()
+    }
+
+    fn rewriter() -> IriRewriter {
+        let mut rewriter = IriRewriter::new();
+        rewriter
+            .add_prefix("http://example.org/old/", "http://example.org/new/")
+            .add_prefix("http://", "https://");
+        rewriter
+    }
+
+    #[test]
+    fn rewrite_iri() {
+        let rewriter = rewriter();
+        assert_eq!(
+            rewriter.rewrite_iri("http://example.org/old/a").as_deref(),
+            Some("http://example.org/new/a")
+        );
+        assert_eq!(
+            rewriter.rewrite_iri("http://example.org/a").as_deref(),
+            Some("https://example.org/a")
+        );
+        assert_eq!(rewriter.rewrite_iri("tag:a"), None);
+    }
+
+    #[test]
+    fn rewrite_terms() -> Result<(), Box<dyn std::error::Error>> {
+        let rewriter = rewriter();
+        assert_eq!(
+            rewriter.rewrite(&iri("http://example.org/a")),
+            iri("https://example.org/a")
+        );
+        let lit = BoxTerm::new_literal_dt("http://example.org/a", xsd::anyURI)?;
+        assert_eq!(rewriter.rewrite(&lit), lit);
+        let bnode = BoxTerm::new_bnode("b")?;
+        assert_eq!(rewriter.rewrite(&bnode), bnode);
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&iri("http://example.org/a"), &iri("tag:p"), &iri("tag:b"))?;
+        g.insert(&iri("tag:a"), &iri("tag:p"), &iri("tag:b"))?;
+        g.insert(
+            &iri("tag:a"),
+            &iri("tag:p"),
+            &BoxTerm::new_literal_dt("42", xsd::integer)?,
+        )?;
+        assert_eq!(rewriter().rewrite_graph(&mut g)?, 1);
+        assert_eq!(g.triples().count(), 3);
+        assert!(g.contains(&iri("https://example.org/a"), &iri("tag:p"), &iri("tag:b"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_dataset() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = FastDataset::new();
+        let g = iri("http://example.org/old/g");
+        d.insert(&iri("tag:a"), &iri("tag:p"), &iri("tag:b"), Some(&g))?;
+        d.insert(
+            &iri("tag:a"),
+            &iri("tag:p"),
+            &iri("tag:b"),
+            None as Option<&BoxTerm>,
+        )?;
+        assert_eq!(rewriter().rewrite_dataset(&mut d)?, 1);
+        assert_eq!(d.quads().count(), 2);
+        assert!(d.contains(
+            &iri("tag:a"),
+            &iri("tag:p"),
+            &iri("tag:b"),
+            Some(&iri("http://example.org/new/g"))
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_quads() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::nq;
+        let src = nq::parse_str("<http://example.org/s> <tag:p> <tag:o> <http://example.org/g>.\n");
+        let d: FastDataset = rewriter().rewrite_quads(src).collect_quads()?;
+        assert!(d.contains(
+            &iri("https://example.org/s"),
+            &iri("tag:p"),
+            &iri("tag:o"),
+            Some(&iri("https://example.org/g"))
+        )?);
+        Ok(())
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() -> Result<(), Box<dyn std::error::Error>> {
+        let mut rewriter = IriRewriter::new();
+        rewriter.add_regex(r"/v(\d+)/", "/version-$1/")?;
+        assert_eq!(
+            rewriter
+                .rewrite_iri("http://example.org/v2/Thing")
+                .as_deref(),
+            Some("http://example.org/version-2/Thing")
+        );
+        assert!(rewriter.add_regex("(", "").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,998 @@
+//! Processor for [R2RML] and [RML] mappings.
+//!
+//! A mapping is an RDF graph describing how to generate RDF data from logical sources.
+//! An [`RmlProcessor`] is built from such a graph,
+//! then provided with the data of each logical source it uses,
+//! and finally executed to produce quads in any [`MutableDataset`].
+//!
+//! Supported logical sources are
+//! * CSV data (`rml:referenceFormulation ql:CSV`),
+//! * JSON data (`rml:referenceFormulation ql:JSONPath`),
+//!   with a subset of JSONPath (`$`, `.name`, `['name']`, `[index]`, `[*]` and `.*`)
+//!   for iterators and references,
+//! * R2RML tables (`rr:tableName`), whose content must be provided as CSV data
+//!   (SQL queries are not supported).
+//!
+//! Supported term maps are constant-, reference- (or column-) and template-valued term maps,
+//! with their term type, datatype and language,
+//! as well as referencing object maps (with join conditions).
+//!
+//! This module requires the `rml` feature.
+//!
+//! ```
+//! # use sophia::dataset::{inmem::FastDataset, Dataset};
+//! # use sophia::graph::inmem::FastGraph;
+//! # use sophia::parser::turtle;
+//! # use sophia::rml::RmlProcessor;
+//! # use sophia::triple::stream::TripleSource;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mapping: FastGraph = turtle::parse_str(r#"
+//!     @prefix rr: <http://www.w3.org/ns/r2rml#>.
+//!     @prefix rml: <http://semweb.mmlab.be/ns/rml#>.
+//!     @prefix ql: <http://semweb.mmlab.be/ns/ql#>.
+//!     @prefix s: <http://schema.org/>.
+//!
+//!     [] rml:logicalSource [
+//!             rml:source "people.csv";
+//!             rml:referenceFormulation ql:CSV
+//!         ];
+//!         rr:subjectMap [
+//!             rr:template "http://example.org/people/{id}";
+//!             rr:class s:Person
+//!         ];
+//!         rr:predicateObjectMap [
+//!             rr:predicate s:name;
+//!             rr:objectMap [ rml:reference "name" ]
+//!         ].
+//! "#).collect_triples()?;
+//!
+//! let mut processor = RmlProcessor::new(&mapping)?;
+//! processor.add_csv_source("people.csv", "id,name\n1,Alice\n2,Bob\n".as_bytes())?;
+//! let mut dataset = FastDataset::new();
+//! assert_eq!(processor.execute(&mut dataset)?, 4);
+//! # Ok(()) }
+//! ```
+//!
+//! [R2RML]: https://www.w3.org/TR/r2rml/
+//! [RML]: https://rml.io/specs/rml/
+//! [`RmlProcessor`]: struct.RmlProcessor.html
+//! [`MutableDataset`]: ../dataset/trait.MutableDataset.html
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::Read;
+
+use json::JsonValue;
+use sophia_api::ns::rdf;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::iri::resolve::{IriParsed, Resolve};
+use sophia_term::iri::{is_absolute_iri_ref, Iri};
+use sophia_term::{BoxTerm, TermError};
+use thiserror::Error;
+
+use crate::dataset::MutableDataset;
+use crate::graph::Graph;
+use crate::triple::Triple;
+
+/// The R2RML namespace.
+#[allow(missing_docs)]
+pub mod rr {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/ns/r2rml#",
+        BlankNode,
+        IRI,
+        Literal,
+        child,
+        class,
+        column,
+        constant,
+        datatype,
+        defaultGraph,
+        graph,
+        graphMap,
+        joinCondition,
+        language,
+        logicalTable,
+        object,
+        objectMap,
+        parent,
+        parentTriplesMap,
+        predicate,
+        predicateMap,
+        predicateObjectMap,
+        sqlQuery,
+        subject,
+        subjectMap,
+        tableName,
+        template,
+        termType
+    );
+}
+
+/// The RML namespace.
+#[allow(missing_docs, clippy::module_inception)]
+pub mod rml {
+    use sophia_api::namespace;
+    namespace!(
+        "http://semweb.mmlab.be/ns/rml#",
+        iterator,
+        logicalSource,
+        reference,
+        referenceFormulation,
+        source
+    );
+}
+
+/// The namespace of RML reference formulations.
+#[allow(missing_docs)]
+pub mod ql {
+    use sophia_api::namespace;
+    namespace!("http://semweb.mmlab.be/ns/ql#", CSV, JSONPath);
+}
+
+/// An error raised while loading or executing a mapping.
+#[derive(Debug, Error)]
+pub enum RmlError {
+    #[error("Invalid mapping: {0}")]
+    Mapping(String),
+    #[error("Unsupported mapping feature: {0}")]
+    Unsupported(String),
+    #[error("No data provided for logical source {0:?}")]
+    UnknownSource(String),
+    #[error("Logical source {0:?} is not in the expected format")]
+    SourceFormat(String),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] json::Error),
+    #[error("Invalid term: {0}")]
+    Term(#[from] TermError),
+}
+
+/// A record of a logical source.
+enum Record<'a> {
+    Csv(&'a HashMap<String, String>),
+    Json(&'a JsonValue),
+}
+
+impl<'a> Record<'a> {
+    /// The values of `reference` in this record (NULL values are omitted).
+    fn values(&self, reference: &str) -> Vec<String> {
+        match self {
+            Record::Csv(row) => row
+                .get(reference)
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .into_iter()
+                .collect(),
+            Record::Json(value) => {
+                let path = if reference.starts_with('$') {
+                    reference.to_string()
+                } else if reference.starts_with('[') {
+                    format!("${}", reference)
+                } else {
+                    format!("$.{}", reference)
+                };
+                json_path(value, &path)
+                    .into_iter()
+                    .filter_map(|v| match v {
+                        JsonValue::Short(_) | JsonValue::String(_) => {
+                            v.as_str().map(str::to_string)
+                        }
+                        JsonValue::Number(_) | JsonValue::Boolean(_) => Some(v.dump()),
+                        _ => None,
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A quad generated by a mapping.
+type MappedQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The data of a logical source.
+enum SourceData {
+    Csv(Vec<HashMap<String, String>>),
+    Json(JsonValue),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Formulation {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Debug)]
+struct LogicalSource {
+    name: String,
+    formulation: Formulation,
+    iterator: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+enum TermMapValue {
+    Constant(BoxTerm),
+    Reference(String),
+    Template(String),
+    /// A blank node term map with no value: one fresh blank node per record
+    Fresh,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TermType {
+    Iri,
+    BlankNode,
+    Literal,
+}
+
+#[derive(Clone, Debug)]
+struct TermMap {
+    value: TermMapValue,
+    term_type: TermType,
+    datatype: Option<BoxTerm>,
+    language: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+enum ObjectMap {
+    Term(TermMap),
+    Ref {
+        parent: usize,
+        joins: Vec<(String, String)>,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct PredicateObjectMap {
+    predicates: Vec<TermMap>,
+    objects: Vec<ObjectMap>,
+    graphs: Vec<TermMap>,
+}
+
+#[derive(Clone, Debug)]
+struct TriplesMap {
+    source: LogicalSource,
+    subject: TermMap,
+    classes: Vec<BoxTerm>,
+    graphs: Vec<TermMap>,
+    poms: Vec<PredicateObjectMap>,
+}
+
+/// Executes an R2RML or RML mapping.
+///
+/// See the [module documentation](index.html) for more details.
+pub struct RmlProcessor {
+    maps: Vec<TriplesMap>,
+    sources: HashMap<String, SourceData>,
+    base: Option<String>,
+}
+
+impl RmlProcessor {
+    /// Load the mapping described in `mapping`.
+    pub fn new<G: Graph>(mapping: &G) -> Result<RmlProcessor, RmlError> {
+        MappingLoader { graph: mapping }.load()
+    }
+
+    /// Set the base IRI used to resolve relative IRIs produced by the mapping.
+    pub fn set_base(&mut self, base: Option<String>) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// Provide the CSV data of the logical source (or R2RML table) named `name`.
+    ///
+    /// The first row of the data must contain the column names.
+    pub fn add_csv_source<R: Read>(&mut self, name: &str, data: R) -> Result<&mut Self, RmlError> {
+        let mut reader = csv::Reader::from_reader(data);
+        let headers = reader.headers()?.clone();
+        let mut rows = vec![];
+        for record in reader.records() {
+            let record = record?;
+            rows.push(
+                headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(h, v)| (h.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        self.sources.insert(name.to_string(), SourceData::Csv(rows));
+        Ok(self)
+    }
+
+    /// Provide the JSON data of the logical source named `name`.
+    pub fn add_json_source(&mut self, name: &str, data: &str) -> Result<&mut Self, RmlError> {
+        let data = json::parse(data)?;
+        self.sources
+            .insert(name.to_string(), SourceData::Json(data));
+        Ok(self)
+    }
+
+    /// Execute the mapping, inserting the generated quads into `dataset`,
+    /// and return the number of quads actually inserted.
+    ///
+    /// Errors raised while executing the mapping are wrapped in `SourceError`,
+    /// errors raised while inserting into `dataset` are wrapped in `SinkError`.
+    pub fn execute<D>(&self, dataset: &mut D) -> StreamResult<usize, RmlError, D::MutationError>
+    where
+        D: MutableDataset,
+    {
+        let mut count = 0;
+        let mut bnodes = 0;
+        for map in &self.maps {
+            let records = self.records(&map.source).map_err(SourceError)?;
+            for record in &records {
+                bnodes += 1;
+                let quads = self
+                    .record_quads(map, record, bnodes)
+                    .map_err(SourceError)?;
+                for ([s, p, o], g) in &quads {
+                    if dataset.insert(s, p, o, g.as_ref()).map_err(SinkError)? {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// The quads generated by triples map `map` for `record`.
+    fn record_quads(
+        &self,
+        map: &TriplesMap,
+        record: &Record,
+        bnodes: usize,
+    ) -> Result<Vec<MappedQuad>, RmlError> {
+        let mut quads = vec![];
+        let subjects = self.generate(&map.subject, record, bnodes)?;
+        if subjects.is_empty() {
+            return Ok(quads);
+        }
+        let sm_graphs = self.generate_graphs(&map.graphs, record, bnodes)?;
+        let mut push = |s: &BoxTerm, p: &BoxTerm, o: &BoxTerm, graphs: &[Option<BoxTerm>]| {
+            if graphs.is_empty() {
+                quads.push(([s.clone(), p.clone(), o.clone()], None));
+            }
+            for g in graphs {
+                quads.push(([s.clone(), p.clone(), o.clone()], g.clone()));
+            }
+        };
+        let rdf_type = BoxTerm::copy(&rdf::type_);
+        for s in &subjects {
+            for class in &map.classes {
+                push(s, &rdf_type, class, &sm_graphs);
+            }
+        }
+        for pom in &map.poms {
+            let mut graphs = sm_graphs.clone();
+            for g in self.generate_graphs(&pom.graphs, record, bnodes)? {
+                if !graphs.contains(&g) {
+                    graphs.push(g);
+                }
+            }
+            let mut predicates = vec![];
+            for pm in &pom.predicates {
+                predicates.extend(self.generate(pm, record, bnodes)?);
+            }
+            let mut objects = vec![];
+            for om in &pom.objects {
+                match om {
+                    ObjectMap::Term(tm) => objects.extend(self.generate(tm, record, bnodes)?),
+                    ObjectMap::Ref { parent, joins } => {
+                        objects.extend(self.join(*parent, joins, record)?)
+                    }
+                }
+            }
+            for s in &subjects {
+                for p in &predicates {
+                    for o in &objects {
+                        push(s, p, o, &graphs);
+                    }
+                }
+            }
+        }
+        Ok(quads)
+    }
+
+    /// The records of the given logical source.
+    fn records(&self, source: &LogicalSource) -> Result<Vec<Record<'_>>, RmlError> {
+        let data = self
+            .sources
+            .get(&source.name)
+            .ok_or_else(|| RmlError::UnknownSource(source.name.clone()))?;
+        match (data, source.formulation) {
+            (SourceData::Csv(rows), Formulation::Csv) => Ok(rows.iter().map(Record::Csv).collect()),
+            (SourceData::Json(value), Formulation::Json) => {
+                let iterator = source.iterator.as_deref().unwrap_or("$");
+                Ok(json_path(value, iterator)
+                    .into_iter()
+                    .map(Record::Json)
+                    .collect())
+            }
+            _ => Err(RmlError::SourceFormat(source.name.clone())),
+        }
+    }
+
+    /// The subjects of the triples map `parent` that join with `record`.
+    fn join(
+        &self,
+        parent: usize,
+        joins: &[(String, String)],
+        record: &Record,
+    ) -> Result<Vec<BoxTerm>, RmlError> {
+        let parent_map = &self.maps[parent];
+        if joins.is_empty() {
+            // same logical source: the parent subject is generated from the same record
+            return self.generate(&parent_map.subject, record, 0);
+        }
+        let mut res = vec![];
+        let child_values: Vec<_> = joins.iter().map(|(c, _)| record.values(c)).collect();
+        for parent_record in &self.records(&parent_map.source)? {
+            let joined = joins.iter().zip(&child_values).all(|((_, p), cv)| {
+                let pv = parent_record.values(p);
+                cv.iter().any(|v| pv.contains(v))
+            });
+            if joined {
+                res.extend(self.generate(&parent_map.subject, parent_record, 0)?);
+            }
+        }
+        Ok(res)
+    }
+
+    /// The graph names generated by `maps` (`None` being the default graph).
+    fn generate_graphs(
+        &self,
+        maps: &[TermMap],
+        record: &Record,
+        bnodes: usize,
+    ) -> Result<Vec<Option<BoxTerm>>, RmlError> {
+        let default_graph = BoxTerm::copy(&rr::defaultGraph);
+        let mut res = vec![];
+        for gm in maps {
+            for g in self.generate(gm, record, bnodes)? {
+                res.push(if g == default_graph { None } else { Some(g) });
+            }
+        }
+        Ok(res)
+    }
+
+    /// The terms generated by `tm` for `record`.
+    fn generate(
+        &self,
+        tm: &TermMap,
+        record: &Record,
+        bnodes: usize,
+    ) -> Result<Vec<BoxTerm>, RmlError> {
+        let values = match &tm.value {
+            TermMapValue::Constant(term) => return Ok(vec![term.clone()]),
+            TermMapValue::Fresh => return Ok(vec![BoxTerm::new_bnode(format!("rml{}", bnodes))?]),
+            TermMapValue::Reference(reference) => record.values(reference),
+            TermMapValue::Template(template) => {
+                expand(template, record, tm.term_type == TermType::Iri)
+            }
+        };
+        values
+            .into_iter()
+            .map(|value| match tm.term_type {
+                TermType::Iri => {
+                    let iri = if is_absolute_iri_ref(&value) {
+                        value
+                    } else {
+                        match &self.base {
+                            Some(base) => resolve(base, &value)?,
+                            None => value,
+                        }
+                    };
+                    Ok(BoxTerm::new_iri(iri)?)
+                }
+                TermType::BlankNode => Ok(BoxTerm::new_bnode(encode(&value))?),
+                TermType::Literal => Ok(match (&tm.datatype, &tm.language) {
+                    (Some(dt), _) => BoxTerm::new_literal_dt(value, Iri::try_from(dt.clone())?)?,
+                    (None, Some(lang)) => BoxTerm::new_literal_lang(value, &lang[..])?,
+                    (None, None) => BoxTerm::new_literal_dt(value, sophia_api::ns::xsd::string)?,
+                }),
+            })
+            .collect()
+    }
+}
+
+/// Loads the triples maps of a mapping graph.
+struct MappingLoader<'a, G> {
+    graph: &'a G,
+}
+
+impl<'a, G: Graph> MappingLoader<'a, G> {
+    fn load(&self) -> Result<RmlProcessor, RmlError> {
+        let mut nodes: Vec<BoxTerm> = vec![];
+        for p in [&rml::logicalSource, &rr::logicalTable].iter() {
+            for t in self.graph.triples_with_p(*p) {
+                let t = t.map_err(|e| RmlError::Mapping(e.to_string()))?;
+                let node = BoxTerm::copy(t.s());
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+        }
+        let mut maps = vec![];
+        for node in &nodes {
+            maps.push(self.triples_map(node, &nodes)?);
+        }
+        Ok(RmlProcessor {
+            maps,
+            sources: HashMap::new(),
+            base: None,
+        })
+    }
+
+    fn triples_map(&self, node: &BoxTerm, nodes: &[BoxTerm]) -> Result<TriplesMap, RmlError> {
+        let source = self.logical_source(node)?;
+        let subject = match self.object(node, &rr::subject)? {
+            Some(constant) => constant_map(constant),
+            None => {
+                let sm = self
+                    .object(node, &rr::subjectMap)?
+                    .ok_or_else(|| RmlError::Mapping(format!("no subject map for {}", node)))?;
+                self.term_map(&sm, TermType::Iri)?
+            }
+        };
+        let sm = self.object(node, &rr::subjectMap)?;
+        let (classes, graphs) = match &sm {
+            Some(sm) => (self.objects(sm, &rr::class)?, self.graph_maps(sm)?),
+            None => (vec![], vec![]),
+        };
+        let mut poms = vec![];
+        for pom in self.objects(node, &rr::predicateObjectMap)? {
+            let mut predicates: Vec<_> = self
+                .objects(&pom, &rr::predicate)?
+                .into_iter()
+                .map(constant_map)
+                .collect();
+            for pm in self.objects(&pom, &rr::predicateMap)? {
+                predicates.push(self.term_map(&pm, TermType::Iri)?);
+            }
+            let mut objects: Vec<_> = self
+                .objects(&pom, &rr::object)?
+                .into_iter()
+                .map(|o| ObjectMap::Term(constant_map(o)))
+                .collect();
+            for om in self.objects(&pom, &rr::objectMap)? {
+                objects.push(self.object_map(&om, nodes)?);
+            }
+            poms.push(PredicateObjectMap {
+                predicates,
+                objects,
+                graphs: self.graph_maps(&pom)?,
+            });
+        }
+        Ok(TriplesMap {
+            source,
+            subject,
+            classes,
+            graphs,
+            poms,
+        })
+    }
+
+    fn logical_source(&self, node: &BoxTerm) -> Result<LogicalSource, RmlError> {
+        if let Some(ls) = self.object(node, &rml::logicalSource)? {
+            let name = self
+                .literal(&ls, &rml::source)?
+                .ok_or_else(|| RmlError::Mapping(format!("no rml:source for {}", node)))?;
+            let formulation = match self.object(&ls, &rml::referenceFormulation)? {
+                None => Formulation::Csv,
+                Some(f) if f == ql::CSV => Formulation::Csv,
+                Some(f) if f == ql::JSONPath => Formulation::Json,
+                Some(f) => {
+                    return Err(RmlError::Unsupported(format!(
+                        "reference formulation {}",
+                        f
+                    )))
+                }
+            };
+            let iterator = self.literal(&ls, &rml::iterator)?;
+            return Ok(LogicalSource {
+                name,
+                formulation,
+                iterator,
+            });
+        }
+        let lt = self
+            .object(node, &rr::logicalTable)?
+            .ok_or_else(|| RmlError::Mapping(format!("no logical source for {}", node)))?;
+        if self.literal(&lt, &rr::sqlQuery)?.is_some() {
+            return Err(RmlError::Unsupported("rr:sqlQuery".to_string()));
+        }
+        let name = self
+            .literal(&lt, &rr::tableName)?
+            .ok_or_else(|| RmlError::Mapping(format!("no rr:tableName for {}", node)))?;
+        Ok(LogicalSource {
+            name: name.trim_matches('"').to_string(),
+            formulation: Formulation::Csv,
+            iterator: None,
+        })
+    }
+
+    fn object_map(&self, om: &BoxTerm, nodes: &[BoxTerm]) -> Result<ObjectMap, RmlError> {
+        match self.object(om, &rr::parentTriplesMap)? {
+            None => Ok(ObjectMap::Term(self.term_map(om, TermType::Literal)?)),
+            Some(parent) => {
+                let parent = nodes
+                    .iter()
+                    .position(|n| n == &parent)
+                    .ok_or_else(|| RmlError::Mapping(format!("unknown triples map {}", parent)))?;
+                let mut joins = vec![];
+                for jc in self.objects(om, &rr::joinCondition)? {
+                    let child = self.literal(&jc, &rr::child)?;
+                    let parent = self.literal(&jc, &rr::parent)?;
+                    match (child, parent) {
+                        (Some(c), Some(p)) => joins.push((c, p)),
+                        _ => {
+                            return Err(RmlError::Mapping("incomplete join condition".to_string()))
+                        }
+                    }
+                }
+                Ok(ObjectMap::Ref { parent, joins })
+            }
+        }
+    }
+
+    fn graph_maps(&self, node: &BoxTerm) -> Result<Vec<TermMap>, RmlError> {
+        let mut graphs: Vec<_> = self
+            .objects(node, &rr::graph)?
+            .into_iter()
+            .map(constant_map)
+            .collect();
+        for gm in self.objects(node, &rr::graphMap)? {
+            graphs.push(self.term_map(&gm, TermType::Iri)?);
+        }
+        Ok(graphs)
+    }
+
+    /// Load a term map; `default_type` is the term type of reference-valued term maps
+    /// (if not explicitly specified).
+    fn term_map(&self, node: &BoxTerm, default_type: TermType) -> Result<TermMap, RmlError> {
+        let term_type = match self.object(node, &rr::termType)? {
+            None => None,
+            Some(t) if t == rr::IRI => Some(TermType::Iri),
+            Some(t) if t == rr::BlankNode => Some(TermType::BlankNode),
+            Some(t) if t == rr::Literal => Some(TermType::Literal),
+            Some(t) => return Err(RmlError::Mapping(format!("invalid term type {}", t))),
+        };
+        let datatype = self.object(node, &rr::datatype)?;
+        let language = self.literal(node, &rr::language)?;
+        if let Some(constant) = self.object(node, &rr::constant)? {
+            return Ok(constant_map(constant));
+        }
+        let reference = match self.literal(node, &rml::reference)? {
+            Some(reference) => Some(reference),
+            None => self.literal(node, &rr::column)?,
+        };
+        let (value, default_type) = match (reference, self.literal(node, &rr::template)?) {
+            (Some(reference), _) => (TermMapValue::Reference(reference), default_type),
+            (None, Some(template)) => (TermMapValue::Template(template), TermType::Iri),
+            (None, None) if term_type == Some(TermType::BlankNode) => {
+                (TermMapValue::Fresh, TermType::BlankNode)
+            }
+            (None, None) => {
+                return Err(RmlError::Mapping(format!("no value for term map {}", node)));
+            }
+        };
+        let term_type = term_type.unwrap_or(if datatype.is_some() || language.is_some() {
+            TermType::Literal
+        } else {
+            default_type
+        });
+        Ok(TermMap {
+            value,
+            term_type,
+            datatype,
+            language,
+        })
+    }
+
+    fn objects<T: TTerm>(&self, s: &BoxTerm, p: &T) -> Result<Vec<BoxTerm>, RmlError> {
+        self.graph
+            .triples_with_sp(s, p)
+            .map(|t| {
+                t.map(|t| BoxTerm::copy(t.o()))
+                    .map_err(|e| RmlError::Mapping(e.to_string()))
+            })
+            .collect()
+    }
+
+    fn object<T: TTerm>(&self, s: &BoxTerm, p: &T) -> Result<Option<BoxTerm>, RmlError> {
+        Ok(self.objects(s, p)?.into_iter().next())
+    }
+
+    fn literal<T: TTerm>(&self, s: &BoxTerm, p: &T) -> Result<Option<String>, RmlError> {
+        match self.object(s, p)? {
+            None => Ok(None),
+            Some(o) if o.kind() == TermKind::Literal => Ok(Some(o.value().to_string())),
+            Some(o) => Err(RmlError::Mapping(format!("{} should be a literal", o))),
+        }
+    }
+}
+
+fn constant_map(term: BoxTerm) -> TermMap {
+    TermMap {
+        value: TermMapValue::Constant(term),
+        term_type: TermType::Iri,
+        datatype: None,
+        language: None,
+    }
+}
+
+/// Expand a [string template](https://www.w3.org/TR/r2rml/#from-template),
+/// producing one value per combination of the values of the references,
+/// or none if any reference has no value.
+fn expand(template: &str, record: &Record, iri_safe: bool) -> Vec<String> {
+    let mut results = vec![String::new()];
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    results.iter_mut().for_each(|r| r.push(c));
+                }
+            }
+            '{' => {
+                let reference: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let values = record.values(&reference);
+                results = results
+                    .iter()
+                    .flat_map(|r| {
+                        values.iter().map(move |v| {
+                            if iri_safe {
+                                format!("{}{}", r, encode(v))
+                            } else {
+                                format!("{}{}", r, v)
+                            }
+                        })
+                    })
+                    .collect();
+            }
+            c => results.iter_mut().for_each(|r| r.push(c)),
+        }
+    }
+    results
+}
+
+/// Percent-encode all characters of `txt` except the [IRI-safe](https://www.w3.org/TR/r2rml/#dfn-iri-safe) ones.
+fn encode(txt: &str) -> String {
+    let mut res = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        if c.is_ascii_alphanumeric() || "-._~".contains(c) || !c.is_ascii() {
+            res.push(c);
+        } else {
+            res.push_str(&format!("%{:02X}", c as u32));
+        }
+    }
+    res
+}
+
+/// Resolve `iri` against `base`.
+fn resolve(base: &str, iri: &str) -> Result<String, RmlError> {
+    let invalid = |_| RmlError::Mapping(format!("can not resolve <{}> against <{}>", iri, base));
+    let base = IriParsed::new(base).map_err(invalid)?;
+    let resolved: Result<_, _> = base.resolve(iri);
+    Ok(resolved.map_err(invalid)?.to_string())
+}
+
+/// A step of a JSONPath expression.
+enum PathStep {
+    Wildcard,
+    Field(String),
+    Index(usize),
+}
+
+/// Evaluate a (subset of) [JSONPath](https://goessner.net/articles/JsonPath/) expression.
+fn json_path<'a>(root: &'a JsonValue, path: &str) -> Vec<&'a JsonValue> {
+    let mut current = vec![root];
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    while !rest.is_empty() {
+        let (step, next) = parse_step(rest);
+        current = current
+            .into_iter()
+            .flat_map(|v| -> Vec<&'a JsonValue> {
+                match &step {
+                    PathStep::Wildcard if v.is_array() => v.members().collect(),
+                    PathStep::Wildcard => v.entries().map(|(_, v)| v).collect(),
+                    PathStep::Field(key) if v.is_object() && v.has_key(key) => vec![&v[&key[..]]],
+                    PathStep::Index(i) if v.is_array() && *i < v.len() => vec![&v[*i]],
+                    _ => vec![],
+                }
+            })
+            .collect();
+        rest = next;
+    }
+    current
+}
+
+/// Parse the first step of a JSONPath expression,
+/// and return it with the rest of the expression.
+fn parse_step(path: &str) -> (PathStep, &str) {
+    if let Some(rest) = path.strip_prefix(".*").or_else(|| path.strip_prefix("[*]")) {
+        (PathStep::Wildcard, rest)
+    } else if let Some(rest) = path.strip_prefix("['") {
+        let end = rest.find("']").unwrap_or(rest.len());
+        let key = rest[..end].to_string();
+        (PathStep::Field(key), rest.get(end + 2..).unwrap_or(""))
+    } else if let Some(rest) = path.strip_prefix('[') {
+        let end = rest.find(']').unwrap_or(rest.len());
+        // unsupported indexes (e.g. slices or filters) select nothing
+        let index = rest[..end].trim().parse().unwrap_or(usize::MAX);
+        (PathStep::Index(index), rest.get(end + 1..).unwrap_or(""))
+    } else {
+        let rest = path.strip_prefix('.').unwrap_or(path);
+        let end = rest.find(['.', '[']).unwrap_or(rest.len());
+        (PathStep::Field(rest[..end].to_string()), &rest[end..])
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::dataset::Dataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const PREFIXES: &str = r#"
+        @prefix rr: <http://www.w3.org/ns/r2rml#>.
+        @prefix rml: <http://semweb.mmlab.be/ns/rml#>.
+        @prefix ql: <http://semweb.mmlab.be/ns/ql#>.
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+        @prefix ex: <http://example.org/>.
+    "#;
+
+    fn processor(mapping: &str) -> RmlProcessor {
+        let mapping: FastGraph = turtle::parse_str(&format!("{}{}", PREFIXES, mapping))
+            .collect_triples()
+            .unwrap();
+        RmlProcessor::new(&mapping).unwrap()
+    }
+
+    fn contains(d: &FastDataset, s: &str, p: &str, o: &BoxTerm, g: Option<&str>) -> bool {
+        let s = BoxTerm::new_iri(s).unwrap();
+        let p = BoxTerm::new_iri(p).unwrap();
+        let g = g.map(|g| BoxTerm::new_iri(g).unwrap());
+        d.contains(&s, &p, o, g.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn r2rml_join() {
+        let mut p = processor(
+            r#"
+            ex:Emp rr:logicalTable [ rr:tableName "EMP" ];
+                rr:subjectMap [ rr:template "http://example.org/emp/{ID}"; rr:graph ex:g ];
+                rr:predicateObjectMap [
+                    rr:predicate ex:age;
+                    rr:objectMap [ rr:column "AGE"; rr:datatype xsd:integer ]
+                ], [
+                    rr:predicate ex:dept;
+                    rr:objectMap [
+                        rr:parentTriplesMap ex:Dept;
+                        rr:joinCondition [ rr:child "DEPT"; rr:parent "ID" ]
+                    ]
+                ].
+            ex:Dept rr:logicalTable [ rr:tableName "DEPT" ];
+                rr:subjectMap [ rr:template "http://example.org/dept/{NAME}" ].
+            "#,
+        );
+        p.add_csv_source("EMP", "ID,AGE,DEPT\n1,42,10\n2,,20\n".as_bytes())
+            .unwrap();
+        p.add_csv_source("DEPT", "ID,NAME\n10,R&D\n20,Sales\n".as_bytes())
+            .unwrap();
+        let mut d = FastDataset::new();
+        assert_eq!(p.execute(&mut d).unwrap(), 3);
+        let age = BoxTerm::new_literal_dt("42", sophia_api::ns::xsd::integer).unwrap();
+        assert!(contains(
+            &d,
+            "http://example.org/emp/1",
+            "http://example.org/age",
+            &age,
+            Some("http://example.org/g")
+        ));
+        let dept = BoxTerm::new_iri("http://example.org/dept/R%26D").unwrap();
+        assert!(contains(
+            &d,
+            "http://example.org/emp/1",
+            "http://example.org/dept",
+            &dept,
+            Some("http://example.org/g")
+        ));
+    }
+
+    #[test]
+    fn rml_json() {
+        let mut p = processor(
+            r#"
+            [] rml:logicalSource [
+                    rml:source "data.json";
+                    rml:referenceFormulation ql:JSONPath;
+                    rml:iterator "$.people[*]"
+                ];
+                rr:subjectMap [ rr:template "http://example.org/{id}" ];
+                rr:predicateObjectMap [
+                    rr:predicate ex:name;
+                    rr:objectMap [ rml:reference "name"; rr:language "en" ]
+                ], [
+                    rr:predicate ex:knows;
+                    rr:objectMap [ rr:template "http://example.org/{knows[*]}" ]
+                ].
+            "#,
+        );
+        p.add_json_source(
+            "data.json",
+            r#"{"people": [
+                {"id": 1, "name": "Alice", "knows": [2, 3]},
+                {"id": 2, "name": "Bob"}
+            ]}"#,
+        )
+        .unwrap();
+        let mut d = FastDataset::new();
+        assert_eq!(p.execute(&mut d).unwrap(), 4);
+        let alice = BoxTerm::new_literal_lang("Alice", "en").unwrap();
+        assert!(contains(
+            &d,
+            "http://example.org/1",
+            "http://example.org/name",
+            &alice,
+            None
+        ));
+        let three = BoxTerm::new_iri("http://example.org/3").unwrap();
+        assert!(contains(
+            &d,
+            "http://example.org/1",
+            "http://example.org/knows",
+            &three,
+            None
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let p = processor(
+            r#"
+            [] rml:logicalSource [ rml:source "missing.csv" ];
+                rr:subjectMap [ rr:termType rr:BlankNode ].
+            "#,
+        );
+        assert!(matches!(
+            p.execute(&mut FastDataset::new()),
+            Err(SourceError(RmlError::UnknownSource(_)))
+        ));
+
+        let mapping: FastGraph = turtle::parse_str(&format!(
+            r#"{}
+            [] rr:logicalTable [ rr:sqlQuery "SELECT 1" ];
+                rr:subjectMap [ rr:template "{{x}}" ].
+            "#,
+            PREFIXES
+        ))
+        .collect_triples()
+        .unwrap();
+        assert!(matches!(
+            RmlProcessor::new(&mapping),
+            Err(RmlError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn json_paths() {
+        let v = json::parse(r#"{"a": [{"b": 1}, {"b": 2}, {"c": 3}], "d e": true}"#).unwrap();
+        assert_eq!(json_path(&v, "$.a[*].b").len(), 2);
+        assert_eq!(json_path(&v, "$.a[1].b")[0].as_u8(), Some(2));
+        assert_eq!(json_path(&v, "$['d e']")[0].as_bool(), Some(true));
+        assert_eq!(json_path(&v, "$.*").len(), 2);
+        assert!(json_path(&v, "$.x").is_empty());
+    }
+}
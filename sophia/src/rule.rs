@@ -0,0 +1,290 @@
+//! Forward-chaining inference with user-defined rules.
+//!
+//! A [`Rule`] is made of a *body* and a *head*,
+//! both graph patterns (i.e. sequences of triples that may contain variables).
+//! Whenever the body matches a graph,
+//! the head (with its variables replaced accordingly) is inferred.
+//!
+//! [`apply_rules`] applies a set of rules to a graph until no new triple can be inferred,
+//! using [semi-naive evaluation]:
+//! after the first round, only the matches involving at least one triple
+//! inferred in the previous round are considered.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph, MutableGraph};
+//! # use sophia::ns::{rdf, rdfs};
+//! # use sophia::rule::{apply_rules, Rule};
+//! # use sophia::term::StaticTerm;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let x = StaticTerm::new_variable("x")?;
+//! let c1 = StaticTerm::new_variable("c1")?;
+//! let c2 = StaticTerm::new_variable("c2")?;
+//! let type_ = StaticTerm::from(rdf::type_);
+//! let sub_class_of = StaticTerm::from(rdfs::subClassOf);
+//! let rdfs9 = Rule::new(
+//!     vec![[c1, sub_class_of, c2], [x, type_, c1]],
+//!     vec![[x, type_, c2]],
+//! )?;
+//!
+//! let mut g = FastGraph::new();
+//! let alice = StaticTerm::new_iri("http://example.org/alice")?;
+//! let person = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/Person")?;
+//! let agent = StaticTerm::new_iri("http://xmlns.com/foaf/0.1/Agent")?;
+//! g.insert(&person, &rdfs::subClassOf, &agent)?;
+//! g.insert(&alice, &rdf::type_, &person)?;
+//!
+//! assert_eq!(apply_rules(&mut g, &[rdfs9])?, 1);
+//! assert!(g.contains(&alice, &rdf::type_, &agent)?);
+//! # Ok(()) }
+//! ```
+//!
+//! [`Rule`]: struct.Rule.html
+//! [`apply_rules`]: fn.apply_rules.html
+//! [semi-naive evaluation]: https://en.wikipedia.org/wiki/Datalog#Evaluation
+
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::RcTerm;
+use thiserror::Error;
+
+use crate::graph::MutableGraph;
+use crate::query::{BindingMap, Query};
+use crate::triple::Triple;
+
+/// This error is raised when building an invalid [`Rule`](struct.Rule.html).
+#[derive(Debug, Error)]
+pub enum RuleError {
+    #[error("The body of a rule can not be empty")]
+    EmptyBody,
+    #[error("Variable ?{0} occurs in the head of the rule but not in its body")]
+    UnboundVariable(String),
+}
+
+/// A rule, inferring the triples of its head whenever its body matches a graph.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    body: Vec<[RcTerm; 3]>,
+    head: Vec<[RcTerm; 3]>,
+}
+
+impl Rule {
+    /// Build a rule from its body and its head.
+    ///
+    /// Fails if the body is empty,
+    /// or if the head contains a variable that does not occur in the body.
+    pub fn new<B, H>(body: B, head: H) -> Result<Rule, RuleError>
+    where
+        B: IntoIterator,
+        B::Item: Triple,
+        H: IntoIterator,
+        H::Item: Triple,
+    {
+        let body: Vec<_> = body.into_iter().map(|t| copy_triple(&t)).collect();
+        let head: Vec<_> = head.into_iter().map(|t| copy_triple(&t)).collect();
+        if body.is_empty() {
+            return Err(RuleError::EmptyBody);
+        }
+        let variables: HashSet<_> = body
+            .iter()
+            .flat_map(|t| t.iter())
+            .filter(|t| t.kind() == TermKind::Variable)
+            .map(|t| t.value().to_string())
+            .collect();
+        for term in head.iter().flat_map(|t| t.iter()) {
+            if term.kind() == TermKind::Variable && !variables.contains(&term.value()[..]) {
+                return Err(RuleError::UnboundVariable(term.value().to_string()));
+            }
+        }
+        Ok(Rule { body, head })
+    }
+
+    /// The body of this rule.
+    pub fn body(&self) -> &[[RcTerm; 3]] {
+        &self.body
+    }
+
+    /// The head of this rule.
+    pub fn head(&self) -> &[[RcTerm; 3]] {
+        &self.head
+    }
+}
+
+/// Apply `rules` to `graph` until a fixpoint is reached,
+/// and return the number of inferred triples.
+///
+/// Errors raised while querying `graph` are wrapped in `SourceError`,
+/// errors raised while inserting into `graph` are wrapped in `SinkError`.
+pub fn apply_rules<G>(
+    graph: &mut G,
+    rules: &[Rule],
+) -> StreamResult<usize, G::Error, G::MutationError>
+where
+    G: MutableGraph,
+{
+    let mut delta = Vec::new();
+    for t in graph.triples() {
+        delta.push(copy_triple(&t.map_err(SourceError)?));
+    }
+    let mut inferred = 0;
+    while !delta.is_empty() {
+        let mut new = HashSet::new();
+        for rule in rules {
+            for (i, pattern) in rule.body.iter().enumerate() {
+                let mut rest = rule.body.clone();
+                rest.remove(i);
+                for triple in &delta {
+                    let binding = match match_triple(pattern, triple) {
+                        Some(binding) => binding,
+                        None => continue,
+                    };
+                    let mut query = Query::Triples(rest.clone());
+                    for binding in query.process_with(&*graph, binding) {
+                        let binding = binding.map_err(SourceError)?;
+                        for template in &rule.head {
+                            let t = instantiate(template, &binding);
+                            if !new.contains(&t)
+                                && !graph.contains(&t[0], &t[1], &t[2]).map_err(SourceError)?
+                            {
+                                new.insert(t);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for t in &new {
+            if graph.insert(&t[0], &t[1], &t[2]).map_err(SinkError)? {
+                inferred += 1;
+            }
+        }
+        delta = new.into_iter().collect();
+    }
+    Ok(inferred)
+}
+
+pub(crate) fn copy_triple<T: Triple>(t: &T) -> [RcTerm; 3] {
+    [
+        RcTerm::copy(t.s()),
+        RcTerm::copy(t.p()),
+        RcTerm::copy(t.o()),
+    ]
+}
+
+/// Match `pattern` against the ground `triple`,
+/// returning the bindings of the variables of `pattern`.
+pub(crate) fn match_triple(pattern: &[RcTerm; 3], triple: &[RcTerm; 3]) -> Option<BindingMap> {
+    let mut binding = HashMap::new();
+    for (p, t) in pattern.iter().zip(triple.iter()) {
+        if p.kind() == TermKind::Variable {
+            match binding.get(&p.value()[..]) {
+                Some(bound) if !term_eq(bound, t) => return None,
+                Some(_) => (),
+                None => {
+                    binding.insert(p.value().to_string(), t.clone());
+                }
+            }
+        } else if !term_eq(p, t) {
+            return None;
+        }
+    }
+    Some(binding)
+}
+
+/// Replace the variables of `template` by their value in `binding`.
+pub(crate) fn instantiate(template: &[RcTerm; 3], binding: &BindingMap) -> [RcTerm; 3] {
+    let subst = |t: &RcTerm| match t.kind() {
+        TermKind::Variable => binding[&t.value()[..]].clone(),
+        _ => t.clone(),
+    };
+    [
+        subst(&template[0]),
+        subst(&template[1]),
+        subst(&template[2]),
+    ]
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use sophia_api::ns::rdf;
+    use sophia_term::StaticTerm;
+
+    fn var(name: &'static str) -> StaticTerm {
+        StaticTerm::new_variable(name).unwrap()
+    }
+
+    fn iri(iri: &'static str) -> StaticTerm {
+        StaticTerm::new_iri(iri).unwrap()
+    }
+
+    #[test]
+    fn transitive_closure() -> Result<(), Box<dyn std::error::Error>> {
+        let (x, y, z, p) = (var("x"), var("y"), var("z"), iri("tag:p"));
+        let rule = Rule::new(vec![[x, p, y], [y, p, z]], vec![[x, p, z]])?;
+        let nodes = ["tag:0", "tag:1", "tag:2", "tag:3", "tag:4", "tag:5"];
+        let mut g = FastGraph::new();
+        for pair in nodes.windows(2) {
+            g.insert(&iri(pair[0]), &p, &iri(pair[1]))?;
+        }
+        // 6 nodes in a chain: 5 + 4 + 3 + 2 + 1 pairs, 5 of them asserted
+        assert_eq!(apply_rules(&mut g, std::slice::from_ref(&rule))?, 10);
+        assert!(g.contains(&iri("tag:0"), &p, &iri("tag:5"))?);
+        assert_eq!(apply_rules(&mut g, &[rule])?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn several_rules() -> Result<(), Box<dyn std::error::Error>> {
+        let (x, y) = (var("x"), var("y"));
+        let (parent, ancestor, person) =
+            (iri("tag:parent"), iri("tag:ancestor"), iri("tag:Person"));
+        let type_ = StaticTerm::from(rdf::type_);
+        let rules = [
+            Rule::new(vec![[x, parent, y]], vec![[x, ancestor, y]])?,
+            Rule::new(
+                vec![[x, ancestor, y]],
+                vec![[x, type_, person], [y, type_, person]],
+            )?,
+        ];
+        let mut g = FastGraph::new();
+        g.insert(&iri("tag:alice"), &parent, &iri("tag:bob"))?;
+        assert_eq!(apply_rules(&mut g, &rules)?, 3);
+        assert!(g.contains(&iri("tag:bob"), &type_, &person)?);
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_variable() -> Result<(), Box<dyn std::error::Error>> {
+        let x = var("x");
+        let (p, q) = (iri("tag:p"), iri("tag:q"));
+        let rule = Rule::new(vec![[x, p, x]], vec![[x, q, x]])?;
+        let mut g = FastGraph::new();
+        g.insert(&iri("tag:a"), &p, &iri("tag:a"))?;
+        g.insert(&iri("tag:a"), &p, &iri("tag:b"))?;
+        assert_eq!(apply_rules(&mut g, &[rule])?, 1);
+        assert!(g.contains(&iri("tag:a"), &q, &iri("tag:a"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let (x, y, p) = (var("x"), var("y"), iri("tag:p"));
+        let no_body: Vec<[StaticTerm; 3]> = vec![];
+        assert!(matches!(
+            Rule::new(no_body, vec![[p, p, p]]),
+            Err(RuleError::EmptyBody)
+        ));
+        assert!(matches!(
+            Rule::new(vec![[x, p, x]], vec![[x, p, y]]),
+            Err(RuleError::UnboundVariable(v)) if v == "y"
+        ));
+    }
+}
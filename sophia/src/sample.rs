@@ -0,0 +1,238 @@
+//! Extraction of small, representative samples from large graphs.
+//!
+//! This module provides three sampling strategies:
+//! * [`first_per_predicate`] keeps the first triples of each predicate,
+//!   so that every predicate of the source is represented;
+//! * [`random`] keeps a uniform random sample of a given size,
+//!   reproducible from a seed;
+//! * [`neighborhood`] keeps the triples around a set of seed resources,
+//!   up to a given distance.
+//!
+//! The first two consume a [`TripleSource`] in a single pass, with a memory footprint
+//! proportional to the size of the sample (not of the source),
+//! so they can be applied to huge files while they are parsed.
+//! All of them return a `Vec` of triples, which is itself a [`Graph`]
+//! and can be serialized or inserted into another graph.
+//! They are typically used to produce test fixtures or previews from production data.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::turtle;
+//! use sophia::sample::first_per_predicate;
+//!
+//! let sample = first_per_predicate(
+//!     turtle::parse_str(r#"
+//!         <tag:a> <tag:name> "a"; <tag:knows> <tag:b>, <tag:c>.
+//!         <tag:b> <tag:name> "b"; <tag:knows> <tag:c>.
+//!     "#),
+//!     1,
+//! )?;
+//! assert_eq!(sample.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`first_per_predicate`]: fn.first_per_predicate.html
+//! [`random`]: fn.random.html
+//! [`neighborhood`]: fn.neighborhood.html
+//! [`TripleSource`]: ../triple/stream/trait.TripleSource.html
+//! [`Graph`]: ../graph/trait.Graph.html
+
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::traversal::Direction;
+use crate::triple::stream::TripleSource;
+use crate::triple::Triple;
+
+/// Keep the first `n` triples of each predicate of `source`,
+/// in the order of the source.
+pub fn first_per_predicate<TS>(mut source: TS, n: usize) -> Result<Vec<[BoxTerm; 3]>, TS::Error>
+where
+    TS: TripleSource,
+{
+    let mut counts: HashMap<BoxTerm, usize> = HashMap::new();
+    let mut sample = vec![];
+    source.for_each_triple(|t| {
+        let count = counts.entry(BoxTerm::copy(t.p())).or_default();
+        if *count < n {
+            *count += 1;
+            sample.push(copy_triple(&t));
+        }
+    })?;
+    Ok(sample)
+}
+
+/// Keep `size` triples of `source`, chosen uniformly at random,
+/// in the order of the source.
+///
+/// The same `seed` always yields the same sample for the same source.
+/// If the source has less than `size` triples, all of them are kept.
+pub fn random<TS>(mut source: TS, size: usize, seed: u64) -> Result<Vec<[BoxTerm; 3]>, TS::Error>
+where
+    TS: TripleSource,
+{
+    // reservoir sampling (Algorithm R), keeping the rank of each triple in the source
+    let mut rng = SplitMix64(seed);
+    let mut reservoir: Vec<(usize, [BoxTerm; 3])> = Vec::with_capacity(size);
+    let mut i = 0;
+    source.for_each_triple(|t| {
+        if reservoir.len() < size {
+            reservoir.push((i, copy_triple(&t)));
+        } else {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            if j < size {
+                reservoir[j] = (i, copy_triple(&t));
+            }
+        }
+        i += 1;
+    })?;
+    reservoir.sort_unstable_by_key(|(i, _)| *i);
+    Ok(reservoir.into_iter().map(|(_, t)| t).collect())
+}
+
+/// Keep the triples of `graph` reachable from any of `seeds`
+/// in at most `depth` steps in the given `direction`.
+///
+/// A depth of 1 yields the triples having a seed as their subject (when going forward),
+/// as their object (when going backward) or either (when going both ways).
+/// If `max_per_node` is provided, at most that many triples are kept around each node,
+/// which avoids including the whole graph through highly connected nodes.
+pub fn neighborhood<G, I, T>(
+    graph: &G,
+    seeds: I,
+    depth: usize,
+    direction: Direction,
+    max_per_node: Option<usize>,
+) -> GResult<G, Vec<[BoxTerm; 3]>>
+where
+    G: Graph,
+    I: IntoIterator<Item = T>,
+    T: TTerm,
+{
+    let mut visited: HashSet<BoxTerm> = HashSet::new();
+    let mut frontier: Vec<BoxTerm> = seeds
+        .into_iter()
+        .map(|s| BoxTerm::copy(&s))
+        .filter(|s| visited.insert(s.clone()))
+        .collect();
+    let mut kept: HashSet<[BoxTerm; 3]> = HashSet::new();
+    let mut sample = vec![];
+    let limit = max_per_node.unwrap_or(usize::MAX);
+    for _ in 0..depth {
+        let mut next = vec![];
+        for node in &frontier {
+            let mut triples = vec![];
+            if direction != Direction::Backward {
+                for t in graph.triples_with_s(node) {
+                    triples.push((copy_triple(&t?), 2));
+                }
+            }
+            if direction != Direction::Forward {
+                for t in graph.triples_with_o(node) {
+                    triples.push((copy_triple(&t?), 0));
+                }
+            }
+            for (t, other) in triples.into_iter().take(limit) {
+                if visited.insert(t[other].clone()) {
+                    next.push(t[other].clone());
+                }
+                if kept.insert(t.clone()) {
+                    sample.push(t);
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok(sample)
+}
+
+fn copy_triple<T: Triple>(t: &T) -> [BoxTerm; 3] {
+    [
+        BoxTerm::copy(t.s()),
+        BoxTerm::copy(t.p()),
+        BoxTerm::copy(t.o()),
+    ]
+}
+
+/// A small, fast pseudo-random generator (not suitable for cryptography).
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use std::convert::Infallible;
+
+    fn chain(n: usize) -> Vec<[BoxTerm; 3]> {
+        (0..n)
+            .map(|i| [t(&i.to_string()), t("next"), t(&(i + 1).to_string())])
+            .collect()
+    }
+
+    #[test]
+    fn per_predicate() -> Result<(), Box<dyn std::error::Error>> {
+        let sample = first_per_predicate(
+            turtle::parse_str("<tag:a> <tag:p> 1, 2, 3; <tag:q> 4, 5. <tag:b> <tag:p> 6."),
+            2,
+        )?;
+        let values: Vec<_> = sample.iter().map(|t| t[2].value().to_string()).collect();
+        assert_eq!(values, vec!["1", "2", "4", "5"]);
+        Ok(())
+    }
+
+    #[test]
+    fn random_sample() -> Result<(), Box<dyn std::error::Error>> {
+        let source = || chain(1000).into_iter().map(Ok::<_, Infallible>);
+        let s1 = random(source(), 10, 42)?;
+        let s2 = random(source(), 10, 42)?;
+        let s3 = random(source(), 10, 43)?;
+        assert_eq!(s1.len(), 10);
+        assert_eq!(s1, s2);
+        assert_ne!(s1, s3);
+        // the source order is preserved
+        let ranks: Vec<usize> = s1
+            .iter()
+            .map(|t| t[0].value()[4..].parse().unwrap())
+            .collect();
+        assert!(ranks.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(random(source(), 2000, 0)?.len(), 1000);
+        Ok(())
+    }
+
+    #[test]
+    fn neighbors() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = chain(10)
+            .into_iter()
+            .map(Ok::<_, Infallible>)
+            .collect_triples()?;
+        let around = |depth, dir, max| neighborhood(&g, vec![t("5")], depth, dir, max);
+        assert_eq!(around(2, Direction::Forward, None)?, chain(7)[5..].to_vec());
+        assert_eq!(
+            around(1, Direction::Backward, None)?,
+            chain(5)[4..].to_vec()
+        );
+        assert_eq!(around(2, Direction::Both, None)?.len(), 4);
+        assert_eq!(around(3, Direction::Both, Some(1))?.len(), 3);
+        assert!(around(0, Direction::Both, None)?.is_empty());
+        Ok(())
+    }
+}
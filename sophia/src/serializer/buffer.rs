@@ -0,0 +1,110 @@
+//! A bounded output buffer shared by the serializers of this crate.
+
+use std::io;
+
+/// The default capacity of the output buffer of serializers.
+pub const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A write buffer holding at most `capacity` bytes before writing them to the underlying writer,
+/// and counting the bytes actually written to it.
+///
+/// Unlike [`BufWriter`](https://doc.rust-lang.org/std/io/struct.BufWriter.html),
+/// it also exposes a way to [`drain`](#method.drain) the buffer without flushing the underlying writer.
+pub(crate) struct BoundedBuffer<W> {
+    inner: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+    written: u64,
+}
+
+impl<W: io::Write> BoundedBuffer<W> {
+    pub fn new(inner: W, capacity: usize) -> Self {
+        BoundedBuffer {
+            inner,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            written: 0,
+        }
+    }
+
+    /// Write the buffered bytes to the underlying writer (without flushing it).
+    pub fn drain(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.inner.write_all(&self.buffer)?;
+            self.written += self.buffer.len() as u64;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// The underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The number of bytes written to the underlying writer so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl<W: io::Write> io::Write for BoundedBuffer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() + buf.len() > self.capacity {
+            self.drain()?;
+        }
+        if buf.len() >= self.capacity {
+            self.inner.write_all(buf)?;
+            self.written += buf.len() as u64;
+        } else {
+            self.buffer.extend_from_slice(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    /// A writer recording the size of each write.
+    #[derive(Default)]
+    struct Recorder(Vec<usize>);
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.push(buf.len());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bounded() -> io::Result<()> {
+        let mut b = BoundedBuffer::new(Recorder::default(), 10);
+        b.write_all(b"abcd")?;
+        b.write_all(b"efgh")?;
+        assert_eq!(b.written(), 0);
+        b.write_all(b"ijkl")?;
+        assert_eq!(b.get_ref().0, vec![8]);
+        b.write_all(b"0123456789abc")?;
+        assert_eq!(b.get_ref().0, vec![8, 4, 13]);
+        b.write_all(b"xy")?;
+        b.flush()?;
+        assert_eq!(b.get_ref().0, vec![8, 4, 13, 2]);
+        assert_eq!(b.written(), 27);
+        Ok(())
+    }
+}
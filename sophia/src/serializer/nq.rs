@@ -1,36 +1,104 @@
 //! Serializer for the [N-Quads] concrete syntax of RDF.
 //!
+//! The serializer buffers its output internally,
+//! in a buffer of bounded size (see [`NqConfig::set_buffer_size`]),
+//! which is written to the target at the end of each call to `serialize_quads`,
+//! or whenever it is full.
+//! Use [`flush`] or [`serialize_quads_chunked`] to control more precisely
+//! when data is sent to the target.
+//!
 //! **Important**:
-//! the methods in this module accepting a [`Write`]
-//! make no effort to minimize the number of write operations.
-//! Hence, in most cased, they should be passed a [`BufWriter`].
+//! [`write_term`](../nt/fn.write_term.html) makes no effort to minimize the number of write operations.
+//! Hence, in most cased, it should be passed a [`BufWriter`].
+//!
+//! With [`NqConfig::set_star`], quoted triples are written as `<< s p o >>`
+//! (see [`NtConfig::set_star`](../nt/struct.NtConfig.html#method.set_star));
+//! they are detected separately in each graph.
 //!
 //! [N-Quads]: https://www.w3.org/TR/n-quads/
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+//! [`NqConfig::set_buffer_size`]: struct.NqConfig.html#method.set_buffer_size
+//! [`NqConfig::set_star`]: struct.NqConfig.html#method.set_star
+//! [`flush`]: ../trait.QuadSerializer.html#method.flush
+//! [`serialize_quads_chunked`]: ../trait.QuadSerializer.html#method.serialize_quads_chunked
 
-use super::nt::write_term;
+use super::buffer::{BoundedBuffer, DEFAULT_BUFFER_SIZE};
+use super::nt::{check_rdf_version, write_term_relabeled};
+use super::star::QuotedTriples;
+use crate::syntax::RdfVersion;
 use sophia_api::quad::{stream::*, Quad};
 use sophia_api::serializer::*;
-use std::io;
+use sophia_api::term::CopyTerm;
+use sophia_term::blank_node::generator::SharedBnodeIds;
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
+use std::io::{self, Write};
 
 /// N-Quads serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct NqConfig {
     ascii: bool,
+    generalized: bool,
+    rdf_version: RdfVersion,
+    star: bool,
+    buffer_size: Option<usize>,
+    bnode_ids: Option<SharedBnodeIds>,
 }
 
 impl NqConfig {
+    /// Set whether the output is pure ASCII
+    /// (see [`NtConfig::set_ascii`](../nt/struct.NtConfig.html#method.set_ascii)).
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
         self.ascii = ascii;
         self
     }
+
+    /// Set whether quads only valid in the [generalized RDF model] are serialized
+    /// (e.g. with literals as subjects, or blank nodes as predicates).
+    ///
+    /// By default, such quads cause an error.
+    ///
+    /// [generalized RDF model]: ../../index.html#generalized-vs-strict-rdf-model
+    pub fn set_generalized(&mut self, generalized: bool) -> &mut Self {
+        self.generalized = generalized;
+        self
+    }
+
+    /// Set the version of RDF that the output must comply with
+    /// (see [`NtConfig::set_rdf_version`](../nt/struct.NtConfig.html#method.set_rdf_version)).
+    pub fn set_rdf_version(&mut self, rdf_version: RdfVersion) -> &mut Self {
+        self.rdf_version = rdf_version;
+        self
+    }
+
+    /// Set whether quoted triples are written as `<< s p o >>`
+    /// (see [module documentation](index.html)).
+    pub fn set_star(&mut self, star: bool) -> &mut Self {
+        self.star = star;
+        self
+    }
+
+    /// Set the maximum number of bytes buffered by the serializer
+    /// before writing them to its target
+    /// (defaults to [`DEFAULT_BUFFER_SIZE`](../constant.DEFAULT_BUFFER_SIZE.html)).
+    pub fn set_buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Set the generator used to relabel blank nodes
+    /// (see [`NtConfig::set_bnode_ids`](../nt/struct.NtConfig.html#method.set_bnode_ids)).
+    pub fn set_bnode_ids(&mut self, bnode_ids: Option<SharedBnodeIds>) -> &mut Self {
+        self.bnode_ids = bnode_ids;
+        self
+    }
 }
 
 // N-Quads serializer.
 pub struct NqSerializer<W> {
     config: NqConfig,
-    write: W,
+    write: BoundedBuffer<W>,
 }
 
 impl<W> NqSerializer<W>
@@ -45,13 +113,51 @@ where
 
     /// Build a new N-Quads serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: NqConfig) -> NqSerializer<W> {
-        NqSerializer { write, config }
+        let capacity = config.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        NqSerializer {
+            write: BoundedBuffer::new(write, capacity),
+            config,
+        }
     }
 
     /// Borrow this serializer's configuration.
     pub fn config(&self) -> &NqConfig {
         &self.config
     }
+
+    /// Fail if `q` can not be written with this serializer's configuration.
+    fn check_quad<Q: Quad>(&self, q: &Q) -> io::Result<()> {
+        if !self.config.generalized && q.is_generalized() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "quad only valid in generalized RDF",
+            ));
+        }
+        for term in [q.s(), q.p(), q.o()].iter().copied().chain(q.g()) {
+            check_rdf_version(self.config.rdf_version, term)?;
+        }
+        Ok(())
+    }
+
+    fn write_quad<Q: Quad>(
+        &mut self,
+        q: &Q,
+        quoted: &QuotedTriples,
+        bnode_ids: &mut Option<SharedBnodeIds>,
+    ) -> io::Result<()> {
+        let ascii = self.config.ascii;
+        let w = &mut self.write;
+        quoted.write_term(w, q.s(), ascii, bnode_ids)?;
+        w.write_all(b" ")?;
+        quoted.write_term(w, q.p(), ascii, bnode_ids)?;
+        w.write_all(b" ")?;
+        quoted.write_term(w, q.o(), ascii, bnode_ids)?;
+        if let Some(n) = q.g() {
+            w.write_all(b" ")?;
+            write_term_relabeled(w, n, ascii, bnode_ids)?;
+        }
+        w.write_all(b".\n")
+    }
 }
 
 impl<W> QuadSerializer for NqSerializer<W>
@@ -67,27 +173,55 @@ where
     where
         QS: QuadSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Quads is not implemented yet")
-        }
-        source
-            .try_for_each_quad(|q| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, q.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.o())?;
-                    if let Some(n) = q.g() {
-                        w.write_all(b" ")?;
-                        write_term(w, n)?;
-                    }
-                    w.write_all(b".\n")
+        let mut bnode_ids = self.config.bnode_ids.clone();
+        if self.config.star && self.config.rdf_version >= RdfVersion::Rdf12 {
+            let mut quads = vec![];
+            source.try_for_each_quad(|q| {
+                self.check_quad(&q)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                quads.push((
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                ));
+                Ok(())
+            })?;
+            let mut graphs: HashMap<&Option<BoxTerm>, Vec<&[BoxTerm; 3]>> = HashMap::new();
+            for (t, g) in quads.iter() {
+                graphs.entry(g).or_default().push(t);
+            }
+            let quoted: HashMap<_, _> = graphs
+                .into_iter()
+                .map(|(g, triples)| (g, QuotedTriples::new(triples.into_iter())))
+                .collect();
+            for q in quads.iter() {
+                let quoted = &quoted[&q.1];
+                if !quoted.is_description(&q.0) {
+                    self.write_quad(q, quoted, &mut bnode_ids)
+                        .map_err(|e| SinkError(io::Error::new(io::ErrorKind::Other, e)))?;
                 }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-            })
-            .map(|_| self)
+            }
+        } else {
+            let quoted = QuotedTriples::default();
+            source.try_for_each_quad(|q| {
+                self.check_quad(&q)
+                    .and_then(|_| self.write_quad(&q, &quoted, &mut bnode_ids))
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            })?;
+        }
+        self.write.drain().map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        io::Write::flush(&mut self.write)
+    }
+
+    fn bytes_written(&self) -> Option<u64> {
+        Some(self.write.written())
     }
 }
 
@@ -106,7 +240,7 @@ impl NqSerializer<Vec<u8>> {
 
 impl Stringifier for NqSerializer<Vec<u8>> {
     fn as_utf8(&self) -> &[u8] {
-        &self.write[..]
+        &self.write.get_ref()[..]
     }
 }
 
@@ -153,4 +287,28 @@ pub(crate) mod test {
 "#
         );
     }
+
+    #[test]
+    fn star() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::nq12::NQuads12Parser;
+        use sophia_api::parser::QuadParser;
+
+        let nq = "<< <tag:a> <tag:b> <tag:c> >> <tag:d> <tag:e> <tag:g>.\n\
+                  <tag:f> <tag:d> << <tag:a> <tag:b> <tag:c> >>.\n\
+                  <tag:f> <tag:d> << <tag:a> <tag:b> <tag:c> >> <tag:g>.\n";
+        let parser = NQuads12Parser {
+            star: true,
+            ..NQuads12Parser::default()
+        };
+        let d: Vec<([BoxTerm; 3], Option<BoxTerm>)> = parser.parse_str(nq).collect_quads()?;
+        assert_eq!(d.len(), 11);
+
+        let mut config = NqConfig::default();
+        config.set_star(true);
+        let s = NqSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)?
+            .to_string();
+        assert_eq!(&s, nq);
+        Ok(())
+    }
 }
@@ -1,38 +1,120 @@
 //! Serializer for the [N-Triples] concrete syntax of RDF.
 //!
+//! The serializer buffers its output internally,
+//! in a buffer of bounded size (see [`NtConfig::set_buffer_size`]),
+//! which is written to the target at the end of each call to `serialize_triples`,
+//! or whenever it is full.
+//! Use [`flush`] or [`serialize_triples_chunked`] to control more precisely
+//! when data is sent to the target.
+//!
 //! **Important**:
-//! the methods in this module accepting a [`Write`]
-//! make no effort to minimize the number of write operations.
-//! Hence, in most cased, they should be passed a [`BufWriter`].
+//! [`write_term`](../nt/fn.write_term.html) makes no effort to minimize the number of write operations.
+//! Hence, in most cased, it should be passed a [`BufWriter`].
+//!
+//! With [`NtConfig::set_star`], the quoted triples produced by the parsers accepting [RDF-star]
+//! (described with the RDF reification vocabulary, see [`nq12`](../../parser/nq12/index.html))
+//! are written back as `<< s p o >>`;
+//! the whole source is then buffered before being written.
 //!
 //! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [RDF-star]: https://www.w3.org/2021/12/rdf-star.html
+//! [`NtConfig::set_star`]: struct.NtConfig.html#method.set_star
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+//! [`NtConfig::set_buffer_size`]: struct.NtConfig.html#method.set_buffer_size
+//! [`flush`]: ../trait.TripleSerializer.html#method.flush
+//! [`serialize_triples_chunked`]: ../trait.TripleSerializer.html#method.serialize_triples_chunked
 
+use super::buffer::{BoundedBuffer, DEFAULT_BUFFER_SIZE};
+use super::star::QuotedTriples;
+use crate::syntax::RdfVersion;
 use sophia_api::ns::xsd;
 use sophia_api::serializer::*;
-use sophia_api::term::{TTerm, TermKind};
-use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::term::{term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
 use sophia_api::triple::Triple;
-use std::io;
+use sophia_term::blank_node::generator::{BnodeIdGenerator, SharedBnodeIds};
+use sophia_term::BoxTerm;
+use std::io::{self, Write};
 
 /// N-Triples serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct NtConfig {
     ascii: bool,
+    generalized: bool,
+    rdf_version: RdfVersion,
+    star: bool,
+    buffer_size: Option<usize>,
+    bnode_ids: Option<SharedBnodeIds>,
 }
 
 impl NtConfig {
+    /// Set whether the output is pure ASCII,
+    /// with non-ASCII characters in IRIs and literals written as `\u`/`\U` escape sequences
+    /// (for consumers that do not support UTF-8).
+    ///
+    /// By default, non-ASCII characters are written as raw UTF-8.
+    /// Note that non-ASCII characters can not be escaped in blank node identifiers,
+    /// so blank nodes with such identifiers cause an error in pure-ASCII mode.
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
         self.ascii = ascii;
         self
     }
+
+    /// Set whether triples only valid in the [generalized RDF model] are serialized
+    /// (e.g. with literals as subjects, or blank nodes as predicates).
+    ///
+    /// By default, such triples cause an error.
+    ///
+    /// [generalized RDF model]: ../../index.html#generalized-vs-strict-rdf-model
+    pub fn set_generalized(&mut self, generalized: bool) -> &mut Self {
+        self.generalized = generalized;
+        self
+    }
+
+    /// Set the version of RDF that the output must comply with.
+    ///
+    /// By default, the output may contain the terms introduced by RDF 1.2
+    /// (e.g. directional language-tagged strings, as `"hello"@en--ltr`).
+    /// In RDF 1.1 mode, such terms cause an error.
+    pub fn set_rdf_version(&mut self, rdf_version: RdfVersion) -> &mut Self {
+        self.rdf_version = rdf_version;
+        self
+    }
+
+    /// Set whether quoted triples are written as `<< s p o >>`
+    /// (see [module documentation](index.html)).
+    ///
+    /// By default, and in RDF 1.1 mode, their descriptions are written as any other triples.
+    pub fn set_star(&mut self, star: bool) -> &mut Self {
+        self.star = star;
+        self
+    }
+
+    /// Set the maximum number of bytes buffered by the serializer
+    /// before writing them to its target
+    /// (defaults to [`DEFAULT_BUFFER_SIZE`](../constant.DEFAULT_BUFFER_SIZE.html)).
+    pub fn set_buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Set the generator used to relabel blank nodes
+    /// (see [`generator`](../../term/blank_node/generator/index.html)).
+    ///
+    /// By default, blank nodes are written with their own identifier.
+    /// The generator is shared by all clones of this configuration,
+    /// so the same blank node is relabelled consistently across serializers.
+    pub fn set_bnode_ids(&mut self, bnode_ids: Option<SharedBnodeIds>) -> &mut Self {
+        self.bnode_ids = bnode_ids;
+        self
+    }
 }
 
 // N-Triples serializer.
 pub struct NtSerializer<W> {
     config: NtConfig,
-    write: W,
+    write: BoundedBuffer<W>,
 }
 
 impl<W> NtSerializer<W>
@@ -47,13 +129,47 @@ where
 
     /// Build a new N-Triples serializer writing to `write`, with the given config.
     pub fn new_with_config(write: W, config: NtConfig) -> NtSerializer<W> {
-        NtSerializer { write, config }
+        let capacity = config.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        NtSerializer {
+            write: BoundedBuffer::new(write, capacity),
+            config,
+        }
     }
 
     /// Borrow this serializer's configuration.
     pub fn config(&self) -> &NtConfig {
         &self.config
     }
+
+    /// Fail if `t` can not be written with this serializer's configuration.
+    fn check_triple<T: Triple>(&self, t: &T) -> io::Result<()> {
+        if !self.config.generalized && t.is_generalized() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "triple only valid in generalized RDF",
+            ));
+        }
+        for term in [t.s(), t.p(), t.o()] {
+            check_rdf_version(self.config.rdf_version, term)?;
+        }
+        Ok(())
+    }
+
+    fn write_triple<T: Triple>(
+        &mut self,
+        t: &T,
+        quoted: &QuotedTriples,
+        bnode_ids: &mut Option<SharedBnodeIds>,
+    ) -> io::Result<()> {
+        let ascii = self.config.ascii;
+        let w = &mut self.write;
+        quoted.write_term(w, t.s(), ascii, bnode_ids)?;
+        w.write_all(b" ")?;
+        quoted.write_term(w, t.p(), ascii, bnode_ids)?;
+        w.write_all(b" ")?;
+        quoted.write_term(w, t.o(), ascii, bnode_ids)?;
+        w.write_all(b".\n")
+    }
 }
 
 impl<W> TripleSerializer for NtSerializer<W>
@@ -69,23 +185,42 @@ where
     where
         TS: TripleSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Triples is not implemented yet")
+        let mut bnode_ids = self.config.bnode_ids.clone();
+        if self.config.star && self.config.rdf_version >= RdfVersion::Rdf12 {
+            let mut triples = vec![];
+            source.try_for_each_triple(|t| {
+                self.check_triple(&t)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                triples.push([
+                    BoxTerm::copy(t.s()),
+                    BoxTerm::copy(t.p()),
+                    BoxTerm::copy(t.o()),
+                ]);
+                Ok(())
+            })?;
+            let quoted = QuotedTriples::new(triples.iter());
+            for t in triples.iter().filter(|t| !quoted.is_description(t)) {
+                self.write_triple(t, &quoted, &mut bnode_ids)
+                    .map_err(|e| SinkError(io::Error::new(io::ErrorKind::Other, e)))?;
+            }
+        } else {
+            let quoted = QuotedTriples::default();
+            source.try_for_each_triple(|t| {
+                self.check_triple(&t)
+                    .and_then(|_| self.write_triple(&t, &quoted, &mut bnode_ids))
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            })?;
         }
-        source
-            .try_for_each_triple(|t| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, t.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.o())?;
-                    w.write_all(b".\n")
-                }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-            })
-            .map(|_| self)
+        self.write.drain().map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        io::Write::flush(&mut self.write)
+    }
+
+    fn bytes_written(&self) -> Option<u64> {
+        Some(self.write.written())
     }
 }
 
@@ -104,12 +239,65 @@ impl NtSerializer<Vec<u8>> {
 
 impl Stringifier for NtSerializer<Vec<u8>> {
     fn as_utf8(&self) -> &[u8] {
-        &self.write[..]
+        &self.write.get_ref()[..]
+    }
+}
+
+/// Fail if the given term can not be expressed in the given version of RDF.
+pub(crate) fn check_rdf_version<T>(version: RdfVersion, t: &T) -> io::Result<()>
+where
+    T: TTerm + ?Sized,
+{
+    if version.allows(t) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "term {} only valid in RDF 1.2, not in {}",
+                term_to_string(t),
+                version
+            ),
+        ))
+    }
+}
+
+/// Write the given term as [`write_term_with`] does,
+/// but relabel blank nodes with `bnode_ids` (if any).
+///
+/// [`write_term_with`]: fn.write_term_with.html
+pub(crate) fn write_term_relabeled<W, T>(
+    w: &mut W,
+    t: &T,
+    ascii: bool,
+    bnode_ids: &mut Option<SharedBnodeIds>,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    match bnode_ids {
+        Some(bnode_ids) if t.kind() == TermKind::BlankNode => {
+            let id = bnode_ids.generate(Some(&t.value()));
+            write_term_with(w, &BoxTerm::new_bnode_unchecked(id), ascii)
+        }
+        _ => write_term_with(w, t, ascii),
     }
 }
 
 /// Write the given term into the given write in the N-Triples format.
 pub fn write_term<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    write_term_with(w, t, false)
+}
+
+/// Write the given term into the given write in the N-Triples format,
+/// escaping all non-ASCII characters if `ascii` is true
+/// (see [`NtConfig::set_ascii`](struct.NtConfig.html#method.set_ascii)).
+pub fn write_term_with<W, T>(w: &mut W, t: &T, ascii: bool) -> io::Result<()>
 where
     W: io::Write,
     T: TTerm + ?Sized,
@@ -119,25 +307,30 @@ where
         Iri => {
             w.write_all(b"<")?;
             let v = t.value_raw();
-            w.write_all(v.0.as_bytes())?;
+            escaped(w, v.0, ascii, no_escape)?;
             if let Some(suffix) = v.1 {
-                w.write_all(suffix.as_bytes())?;
+                escaped(w, suffix, ascii, no_escape)?;
             }
             w.write_all(b">")
         }
         Literal => {
             w.write_all(b"\"")?;
-            quoted_string(w, t.value_raw().0.as_bytes())?;
+            escaped(w, t.value_raw().0, ascii, string_escape)?;
             match t.language() {
                 Some(tag) => {
                     w.write_all(b"\"@")?;
-                    w.write_all(tag.as_bytes())
+                    w.write_all(tag.as_bytes())?;
+                    if let Some(dir) = t.base_direction() {
+                        w.write_all(b"--")?;
+                        w.write_all(dir.as_str().as_bytes())?;
+                    }
+                    Ok(())
                 }
                 None => {
                     let dt = t.datatype().unwrap();
                     if xsd::string != dt {
                         w.write_all(b"\"^^")?;
-                        write_term(w, &dt)
+                        write_term_with(w, &dt, ascii)
                     } else {
                         w.write_all(b"\"")
                     }
@@ -145,8 +338,15 @@ where
             }
         }
         BlankNode => {
+            let id = t.value_raw().0;
+            if ascii && !id.is_ascii() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("blank node identifier _:{} can not be written in ASCII", id),
+                ));
+            }
             w.write_all(b"_:")?;
-            w.write_all(t.value_raw().0.as_bytes())
+            w.write_all(id.as_bytes())
         }
         Variable => {
             w.write_all(b"?")?;
@@ -155,40 +355,43 @@ where
     }
 }
 
-fn quoted_string<W: io::Write>(w: &mut W, txt: &[u8]) -> io::Result<()> {
-    let mut cut = txt.len();
-    let mut cutchar = b'\0';
-    for (pos, chr) in txt.iter().enumerate() {
-        let chr = *chr;
-        if chr <= b'\\' && (chr == b'\n' || chr == b'\r' || chr == b'\\' || chr == b'"') {
-            cut = pos;
-            cutchar = chr;
-            break;
-        }
+fn no_escape(_: char) -> Option<&'static str> {
+    None
+}
+
+fn string_escape(c: char) -> Option<&'static str> {
+    match c {
+        '\n' => Some("\\n"),
+        '\r' => Some("\\r"),
+        '"' => Some("\\\""),
+        '\\' => Some("\\\\"),
+        _ => None,
     }
-    w.write_all(&txt[..cut])?;
-    if cut < txt.len() {
-        match cutchar {
-            b'\n' => {
-                w.write_all(b"\\n")?;
-            }
-            b'\r' => {
-                w.write_all(b"\\r")?;
-            }
-            b'"' => {
-                w.write_all(b"\\\"")?;
-            }
-            b'\\' => {
-                w.write_all(b"\\\\")?;
-            }
-            _ => unreachable!(),
+}
+
+/// Write `txt`, replacing the characters for which `escape` returns a value,
+/// and non-ASCII characters if `ascii` is true (as `\u` or `\U` sequences).
+fn escaped<W: io::Write>(
+    w: &mut W,
+    txt: &str,
+    ascii: bool,
+    escape: fn(char) -> Option<&'static str>,
+) -> io::Result<()> {
+    let mut start = 0;
+    for (pos, chr) in txt.char_indices() {
+        let special = escape(chr);
+        if special.is_none() && (chr.is_ascii() || !ascii) {
+            continue;
         }
-    };
-    if cut + 1 >= txt.len() {
-        Ok(())
-    } else {
-        quoted_string(w, &txt[cut + 1..])
+        w.write_all(&txt.as_bytes()[start..pos])?;
+        match special {
+            Some(special) => w.write_all(special.as_bytes())?,
+            None if (chr as u32) <= 0xFFFF => write!(w, "\\u{:04X}", chr as u32)?,
+            None => write!(w, "\\U{:08X}", chr as u32)?,
+        }
+        start = pos + chr.len_utf8();
     }
+    w.write_all(&txt.as_bytes()[start..])
 }
 
 // ---------------------------------------------------------------------------------
@@ -228,4 +431,191 @@ pub(crate) mod test {
 "#
         );
     }
+
+    #[test]
+    fn bnode_ids() {
+        use sophia_term::blank_node::generator::Sequential;
+
+        let g = vec![[
+            StaticTerm::new_bnode_unchecked("x"),
+            StaticTerm::new_iri_unchecked("tag:p"),
+            StaticTerm::new_bnode_unchecked("y"),
+        ]];
+        let mut config = NtConfig::default();
+        config.set_bnode_ids(Some(SharedBnodeIds::new(Sequential::new("n"))));
+        let s = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(&s, "_:n0 <tag:p> _:n1.\n");
+    }
+
+    #[test]
+    fn generalized() {
+        let g = vec![[
+            StaticTerm::new_literal_dt_unchecked("a", xsd::string),
+            StaticTerm::new_bnode_unchecked("b"),
+            StaticTerm::new_iri_unchecked("tag:c"),
+        ]];
+        assert!(NtSerializer::new_stringifier().serialize_graph(&g).is_err());
+        let mut config = NtConfig::default();
+        config.set_generalized(true);
+        let s = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(&s, "\"a\" _:b <tag:c>.\n");
+    }
+
+    #[test]
+    fn rdf_version() {
+        use sophia_api::term::BaseDirection;
+
+        let g = vec![[
+            BoxTerm::new_iri_unchecked("tag:a"),
+            BoxTerm::new_iri_unchecked("tag:b"),
+            BoxTerm::new_literal_dir_lang("hello", "en", BaseDirection::Ltr).unwrap(),
+        ]];
+        let s = NtSerializer::new_stringifier()
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(&s, "<tag:a> <tag:b> \"hello\"@en--ltr.\n");
+        let mut config = NtConfig::default();
+        config.set_rdf_version(RdfVersion::Rdf11);
+        let mut ser = NtSerializer::new_stringifier_with_config(config);
+        let err = ser.serialize_graph(&g).err().unwrap();
+        assert!(err.to_string().contains("only valid in RDF 1.2"));
+    }
+
+    #[test]
+    fn star() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::nt12::NTriples12Parser;
+        use sophia_api::parser::TripleParser;
+        use sophia_api::triple::stream::TripleSource;
+
+        let nt = "<< <tag:a> <tag:b> << _:x <tag:c> \"x\" >> >> <tag:d> <tag:e>.\n\
+                  <tag:f> <tag:d> << <tag:a> <tag:b> << _:x <tag:c> \"x\" >> >>.\n";
+        let parser = NTriples12Parser {
+            star: true,
+            ..NTriples12Parser::default()
+        };
+        let g: Vec<[BoxTerm; 3]> = parser.parse_str(nt).collect_triples()?;
+        assert_eq!(g.len(), 10);
+
+        let mut config = NtConfig::default();
+        config.set_star(true);
+        let s = NtSerializer::new_stringifier_with_config(config.clone())
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(&s, nt);
+
+        // without star, or in RDF 1.1, the descriptions are written
+        let s = NtSerializer::new_stringifier()
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(s.lines().count(), 10);
+        config.set_rdf_version(RdfVersion::Rdf11);
+        let s = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(!s.contains("<<"));
+        Ok(())
+    }
+
+    /// A writer recording the number of bytes written at each flush.
+    #[derive(Default)]
+    struct Flushes {
+        data: Vec<u8>,
+        flushes: Vec<usize>,
+    }
+
+    impl io::Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes.push(self.data.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn chunked() {
+        let g: Vec<_> = (0..5)
+            .map(|i| {
+                [
+                    StaticTerm::new_iri_unchecked("tag:s"),
+                    StaticTerm::new_iri_unchecked("tag:p"),
+                    StaticTerm::new_literal_dt_unchecked(
+                        ["0", "1", "2", "3", "4"][i],
+                        xsd::integer,
+                    ),
+                ]
+            })
+            .collect();
+        let line = "<tag:s> <tag:p> \"0\"^^<http://www.w3.org/2001/XMLSchema#integer>.\n".len();
+        let mut w = Flushes::default();
+        let mut config = NtConfig::default();
+        config.set_buffer_size(100);
+        let mut ser = NtSerializer::new_with_config(&mut w, config);
+        ser.serialize_triples_chunked(g.iter().map(Ok::<_, std::convert::Infallible>), 2)
+            .unwrap();
+        assert_eq!(ser.bytes_written(), Some(5 * line as u64));
+        assert_eq!(w.flushes, vec![2 * line, 4 * line, 5 * line]);
+    }
+
+    #[test]
+    fn bounded_buffer() {
+        let g = vec![[
+            StaticTerm::new_iri_unchecked("tag:s"),
+            StaticTerm::new_iri_unchecked("tag:p"),
+            StaticTerm::new_iri_unchecked("tag:o"),
+        ]];
+        let mut w = Flushes::default();
+        let mut config = NtConfig::default();
+        config.set_buffer_size(4);
+        let mut ser = NtSerializer::new_with_config(&mut w, config);
+        ser.serialize_graph(&g).unwrap();
+        ser.flush().unwrap();
+        assert_eq!(ser.bytes_written(), Some(25));
+        assert_eq!(w.flushes, vec![25]);
+    }
+
+    #[test]
+    fn ascii() {
+        let g = vec![[
+            StaticTerm::new_iri_unchecked("tag:caf\u{e9}"),
+            StaticTerm::new_iri_unchecked("tag:p"),
+            StaticTerm::new_literal_lang_unchecked("\u{e9}t\u{e9} \"\u{1F600}\"\n", "fr"),
+        ]];
+        let s = NtSerializer::new_stringifier()
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            "<tag:caf\u{e9}> <tag:p> \"\u{e9}t\u{e9} \\\"\u{1F600}\\\"\\n\"@fr.\n"
+        );
+        let mut config = NtConfig::default();
+        config.set_ascii(true);
+        let s = NtSerializer::new_stringifier_with_config(config.clone())
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"<tag:caf\u00E9> <tag:p> "\u00E9t\u00E9 \"\U0001F600\"\n"@fr.
+"#
+        );
+        let g = vec![[
+            StaticTerm::new_bnode_unchecked("caf\u{e9}"),
+            StaticTerm::new_iri_unchecked("tag:p"),
+            StaticTerm::new_iri_unchecked("tag:o"),
+        ]];
+        assert!(NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .is_err());
+    }
 }
@@ -0,0 +1,295 @@
+//! Serialization of datasets as one triple-based document per graph.
+//!
+//! A [`PerGraphSerializer`] lifts a [`TripleSerializer`] to an [`RdfSerializer`],
+//! by creating a new triple serializer for each graph of the dataset
+//! (e.g. one file per named graph),
+//! through a factory receiving the graph name (`None` for the default graph).
+//! [`graph_file_name`] provides a naming scheme for such files.
+//!
+//! # Example
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, MutableDataset};
+//! use sophia::ns::rdf;
+//! use sophia::serializer::nt::NtSerializer;
+//! use sophia::serializer::per_graph::{graph_file_name, PerGraphSerializer};
+//! use sophia::serializer::RdfSerializer;
+//! use sophia::term::BoxTerm;
+//!
+//! let mut d = FastDataset::new();
+//! let g1 = BoxTerm::new_iri("http://example.org/g1")?;
+//! d.insert(&rdf::type_, &rdf::type_, &rdf::Property, Some(&g1))?;
+//! d.insert(&rdf::type_, &rdf::type_, &rdf::Property, None as Option<&BoxTerm>)?;
+//!
+//! let dir = std::env::temp_dir().join(format!("sophia-per-graph-doc-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir)?;
+//! let mut serializer = PerGraphSerializer::new(|name: Option<&BoxTerm>| {
+//!     let file = std::fs::File::create(dir.join(graph_file_name(name, "nt")))?;
+//!     Ok(NtSerializer::new(file))
+//! });
+//! serializer.write_dataset(&d)?;
+//! assert!(dir.join("default.nt").exists());
+//! assert!(dir.join("http%3A%2F%2Fexample.org%2Fg1.nt").exists());
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`PerGraphSerializer`]: struct.PerGraphSerializer.html
+//! [`TripleSerializer`]: ../trait.TripleSerializer.html
+//! [`RdfSerializer`]: ../trait.RdfSerializer.html
+//! [`graph_file_name`]: fn.graph_file_name.html
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::Graph;
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{RdfSerializer, TripleSerializer};
+use sophia_api::term::{term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::StreamError::{SinkError, SourceError};
+use sophia_api::triple::stream::StreamResult;
+use sophia_term::BoxTerm;
+
+/// Serializes each graph of a dataset with its own triple serializer.
+///
+/// See [module documentation](index.html) for more detail.
+#[derive(Clone, Debug)]
+pub struct PerGraphSerializer<F> {
+    factory: F,
+}
+
+impl<F, S> PerGraphSerializer<F>
+where
+    F: FnMut(Option<&BoxTerm>) -> Result<S, S::Error>,
+    S: TripleSerializer,
+{
+    /// Build a serializer calling `factory` to create the triple serializer of each graph.
+    ///
+    /// Each triple serializer is flushed and dropped once its graph is serialized.
+    pub fn new(factory: F) -> Self {
+        PerGraphSerializer { factory }
+    }
+
+    /// Serialize `graph` with a new triple serializer created for `graph_name`.
+    fn write_one<G>(
+        &mut self,
+        graph_name: Option<&BoxTerm>,
+        graph: &G,
+    ) -> StreamResult<(), G::Error, S::Error>
+    where
+        G: Graph,
+    {
+        let mut serializer = (self.factory)(graph_name).map_err(SinkError)?;
+        serializer.serialize_graph(graph)?;
+        serializer.flush().map_err(SinkError)
+    }
+}
+
+impl<F, S> RdfSerializer for PerGraphSerializer<F>
+where
+    F: FnMut(Option<&BoxTerm>) -> Result<S, S::Error>,
+    S: TripleSerializer,
+{
+    type Error = S::Error;
+
+    /// Serialize `graph` as the default graph.
+    fn write_graph<G>(&mut self, graph: &G) -> StreamResult<(), G::Error, Self::Error>
+    where
+        G: Graph,
+    {
+        self.write_one(None, graph)
+    }
+
+    /// Serialize each graph of `dataset` with a new triple serializer,
+    /// starting with the default graph (unless it is empty),
+    /// then the named graphs, sorted by their N-Triples representation.
+    fn write_dataset<D>(&mut self, dataset: &D) -> StreamResult<(), D::Error, Self::Error>
+    where
+        D: Dataset,
+    {
+        let mut names = BTreeSet::new();
+        let mut has_default = false;
+        for q in dataset.quads() {
+            let q = q.map_err(SourceError)?;
+            match q.g() {
+                Some(g) => {
+                    names.insert((term_to_string(g), BoxTerm::copy(g)));
+                }
+                None => has_default = true,
+            }
+        }
+        if has_default {
+            self.write_one(None, &dataset.graph(None as Option<&BoxTerm>))?;
+        }
+        for (_, name) in &names {
+            self.write_one(Some(name), &dataset.graph(Some(name)))?;
+        }
+        Ok(())
+    }
+
+    /// Does nothing, as triple serializers are flushed after each graph.
+    fn flush_target(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A file name for the graph named `graph_name` (`None` for the default graph),
+/// with the given `extension`.
+///
+/// The default graph is named `default`;
+/// named graphs are named after their IRI (or `_:` followed by their blank node identifier),
+/// where every character other than ASCII letters, digits, `-`, `_` and `.`
+/// is percent-encoded (e.g. `http%3A%2F%2Fexample.org%2Fg1`).
+/// Distinct graph names therefore get distinct file names.
+pub fn graph_file_name<T>(graph_name: Option<&T>, extension: &str) -> String
+where
+    T: TTerm + ?Sized,
+{
+    let name = match graph_name {
+        None => return format!("default.{}", extension),
+        Some(name) => match name.kind() {
+            TermKind::Iri => name.value().to_string(),
+            TermKind::BlankNode => format!("_:{}", name.value()),
+            _ => term_to_string(name),
+        },
+    };
+    let mut file_name = String::with_capacity(name.len() + extension.len() + 1);
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.' {
+            file_name.push(b as char);
+        } else {
+            write!(file_name, "%{:02X}", b).unwrap();
+        }
+    }
+    file_name.push('.');
+    file_name.push_str(extension);
+    file_name
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::{trig, turtle};
+    use crate::serializer::nq::NqSerializer;
+    use crate::serializer::nt::NtSerializer;
+    use sophia_api::quad::stream::QuadSource;
+    use sophia_api::serializer::{DefaultGraphSerializer, Stringifier};
+    use sophia_api::triple::stream::TripleSource;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const TRIG: &str = r#"
+        <tag:s> <tag:p> <tag:o1>.
+        <tag:g2> { <tag:s> <tag:p> <tag:o2>. }
+        _:g1 { <tag:s> <tag:p> <tag:o3>, <tag:o4>. }
+    "#;
+
+    /// Serialize `d` with a PerGraphSerializer,
+    /// and return the file name and N-Triples output of each graph.
+    fn per_graph(d: &FastDataset) -> Vec<(String, String)> {
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+        let outputs2 = outputs.clone();
+        let mut serializer = PerGraphSerializer::new(move |name: Option<&BoxTerm>| {
+            outputs2
+                .borrow_mut()
+                .push((graph_file_name(name, "nt"), Vec::new()));
+            Ok(NtSerializer::new(Output(outputs2.clone())))
+        });
+        serializer.write_dataset(d).unwrap();
+        drop(serializer);
+        let outputs = outputs.borrow();
+        outputs
+            .iter()
+            .map(|(name, data)| (name.clone(), String::from_utf8(data.clone()).unwrap()))
+            .collect()
+    }
+
+    /// The file name and content of each output.
+    type Outputs = Rc<RefCell<Vec<(String, Vec<u8>)>>>;
+
+    /// A writer appending to the last output of a shared list.
+    struct Output(Outputs);
+
+    impl std::io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().last_mut().unwrap().1.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn per_graph_serializer() -> Result<(), Box<dyn std::error::Error>> {
+        let d: FastDataset = trig::parse_str(TRIG).collect_quads()?;
+        let outputs = per_graph(&d);
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0].0, "default.nt");
+        assert_eq!(outputs[0].1, "<tag:s> <tag:p> <tag:o1>.\n");
+        assert_eq!(outputs[1].0, "tag%3Ag2.nt");
+        assert_eq!(outputs[1].1, "<tag:s> <tag:p> <tag:o2>.\n");
+        assert!(outputs[2].0.starts_with("_%3A"));
+        assert_eq!(outputs[2].1.lines().count(), 2);
+
+        // no default graph
+        let d: FastDataset =
+            trig::parse_str("<tag:g> { <tag:s> <tag:p> <tag:o>. }").collect_quads()?;
+        let outputs = per_graph(&d);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].0, "tag%3Ag.nt");
+        Ok(())
+    }
+
+    #[test]
+    fn single_code_path() -> Result<(), Box<dyn std::error::Error>> {
+        fn write_all<S: RdfSerializer>(
+            s: &mut S,
+            g: &FastGraph,
+            d: &FastDataset,
+        ) -> Result<(), Box<dyn std::error::Error>>
+        where
+            S::Error: Send + Sync,
+        {
+            s.write_graph(g).map_err(|e| e.to_string())?;
+            s.write_dataset(d).map_err(|e| e.to_string())?;
+            s.flush_target()?;
+            Ok(())
+        }
+        let g: FastGraph = turtle::parse_str("<tag:a> <tag:b> <tag:c>.").collect_triples()?;
+        let d: FastDataset = trig::parse_str(TRIG).collect_quads()?;
+
+        let mut nq = NqSerializer::new_stringifier();
+        write_all(&mut nq, &g, &d)?;
+        assert_eq!(nq.as_str().lines().count(), 5);
+
+        let mut nt = DefaultGraphSerializer(NtSerializer::new_stringifier());
+        write_all(&mut nt, &g, &d)?;
+        assert_eq!(
+            nt.into_inner().as_str(),
+            "<tag:a> <tag:b> <tag:c>.\n<tag:s> <tag:p> <tag:o1>.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn file_names() -> Result<(), Box<dyn std::error::Error>> {
+        let iri = BoxTerm::new_iri("http://example.org/é?x=1#f")?;
+        assert_eq!(
+            graph_file_name(Some(&iri), "ttl"),
+            "http%3A%2F%2Fexample.org%2F%C3%A9%3Fx%3D1%23f.ttl"
+        );
+        let bn = BoxTerm::new_bnode("b1")?;
+        assert_eq!(graph_file_name(Some(&bn), "nt"), "_%3Ab1.nt");
+        assert_eq!(
+            graph_file_name(None as Option<&BoxTerm>, "nq"),
+            "default.nq"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Detection of the quoted triples described with the RDF reification vocabulary,
+//! as produced by the parsers accepting RDF-star (see [`nq12`](../../parser/nq12/index.html)).
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use sophia_api::ns::rdf;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_term::blank_node::generator::SharedBnodeIds;
+use sophia_term::BoxTerm;
+
+use super::nt::write_term_relabeled;
+
+/// The blank nodes of a graph standing for quoted triples.
+///
+/// A blank node stands for a quoted triple if
+/// * it is described by exactly one triple for each of `rdf:type rdf:Statement`,
+///   `rdf:subject`, `rdf:predicate` (with an IRI) and `rdf:object`,
+/// * it is used in other triples of the graph, but never as predicate,
+/// * the quoted triple does not contain itself.
+#[derive(Debug, Default)]
+pub(crate) struct QuotedTriples {
+    quoted: HashMap<BoxTerm, [BoxTerm; 3]>,
+}
+
+impl QuotedTriples {
+    /// Detect the quoted triples described in `triples`.
+    pub(crate) fn new<'a, I>(triples: I) -> Self
+    where
+        I: Iterator<Item = &'a [BoxTerm; 3]> + Clone,
+    {
+        let mut descriptions: HashMap<&BoxTerm, [Vec<&BoxTerm>; 4]> = HashMap::new();
+        for [s, p, o] in triples.clone() {
+            if s.kind() == TermKind::BlankNode {
+                if let Some(i) = description_index(p, o) {
+                    descriptions.entry(s).or_default()[i].push(o);
+                }
+            }
+        }
+        let mut quoted: HashMap<BoxTerm, [BoxTerm; 3]> = descriptions
+            .into_iter()
+            .filter(|(_, d)| d.iter().all(|values| values.len() == 1))
+            .filter(|(_, d)| d[1][0].kind() != TermKind::Literal && d[2][0].kind() == TermKind::Iri)
+            .map(|(node, d)| {
+                (
+                    node.clone(),
+                    [d[1][0].clone(), d[2][0].clone(), d[3][0].clone()],
+                )
+            })
+            .collect();
+        let mut used = HashSet::new();
+        let mut predicates = HashSet::new();
+        for t in triples {
+            // the values of a description are used in the quoted triple (which may be nested)
+            used.insert(&t[2]);
+            if quoted.contains_key(&t[0]) && description_index(&t[1], &t[2]).is_some() {
+                continue;
+            }
+            used.insert(&t[0]);
+            predicates.insert(&t[1]);
+        }
+        quoted.retain(|node, _| used.contains(node) && !predicates.contains(node));
+        // quoted triples containing themselves can not be written
+        while let Some(node) = quoted
+            .keys()
+            .find(|node| contains(&quoted, node, node, &mut HashSet::new()))
+            .cloned()
+        {
+            quoted.remove(&node);
+        }
+        QuotedTriples { quoted }
+    }
+
+    /// Whether no quoted triple was detected.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.quoted.is_empty()
+    }
+
+    /// The quoted triple that `node` stands for, if any.
+    pub(crate) fn get(&self, node: &BoxTerm) -> Option<&[BoxTerm; 3]> {
+        self.quoted.get(node)
+    }
+
+    /// The quoted triples detected.
+    pub(crate) fn triples(&self) -> impl Iterator<Item = &[BoxTerm; 3]> {
+        self.quoted.values()
+    }
+
+    /// Whether `t` is part of the description of a quoted triple,
+    /// and should therefore not be written.
+    pub(crate) fn is_description(&self, t: &[BoxTerm; 3]) -> bool {
+        self.quoted.contains_key(&t[0]) && description_index(&t[1], &t[2]).is_some()
+    }
+
+    /// Write `t` in the N-Triples format, as `write_term_relabeled` does,
+    /// except for blank nodes standing for quoted triples, which are written as `<< s p o >>`.
+    pub(crate) fn write_term<W, T>(
+        &self,
+        w: &mut W,
+        t: &T,
+        ascii: bool,
+        bnode_ids: &mut Option<SharedBnodeIds>,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+        T: TTerm + ?Sized,
+    {
+        if self.is_empty() || t.kind() != TermKind::BlankNode {
+            return write_term_relabeled(w, t, ascii, bnode_ids);
+        }
+        match self.get(&BoxTerm::copy(t)) {
+            None => write_term_relabeled(w, t, ascii, bnode_ids),
+            Some([s, p, o]) => {
+                w.write_all(b"<< ")?;
+                self.write_term(w, s, ascii, bnode_ids)?;
+                w.write_all(b" ")?;
+                self.write_term(w, p, ascii, bnode_ids)?;
+                w.write_all(b" ")?;
+                self.write_term(w, o, ascii, bnode_ids)?;
+                w.write_all(b" >>")
+            }
+        }
+    }
+}
+
+/// The index of the description triple with predicate `p` and object `o`
+/// (0 for `rdf:type rdf:Statement`, then 1, 2, 3 for `rdf:subject`, `rdf:predicate`, `rdf:object`).
+fn description_index(p: &BoxTerm, o: &BoxTerm) -> Option<usize> {
+    if term_eq(p, &rdf::type_) && term_eq(o, &rdf::Statement) {
+        Some(0)
+    } else if term_eq(p, &rdf::subject) {
+        Some(1)
+    } else if term_eq(p, &rdf::predicate) {
+        Some(2)
+    } else if term_eq(p, &rdf::object) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Whether the quoted triple of `node` contains `target` (at any depth).
+fn contains<'a>(
+    quoted: &'a HashMap<BoxTerm, [BoxTerm; 3]>,
+    node: &'a BoxTerm,
+    target: &BoxTerm,
+    visited: &mut HashSet<&'a BoxTerm>,
+) -> bool {
+    if !visited.insert(node) {
+        return false;
+    }
+    quoted.get(node).is_some_and(|triple| {
+        triple
+            .iter()
+            .any(|t| t == target || contains(quoted, t, target, visited))
+    })
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn t(txt: &str) -> BoxTerm {
+        crate::parser::nq12::parse_term(txt).unwrap()
+    }
+
+    #[test]
+    fn detection() {
+        let statement = || {
+            [
+                t("_:q"),
+                BoxTerm::copy(&rdf::type_),
+                BoxTerm::copy(&rdf::Statement),
+            ]
+        };
+        let description = vec![
+            statement(),
+            [t("_:q"), BoxTerm::copy(&rdf::subject), t("<tag:a>")],
+            [t("_:q"), BoxTerm::copy(&rdf::predicate), t("<tag:b>")],
+            [t("_:q"), BoxTerm::copy(&rdf::object), t("\"c\"")],
+        ];
+        let used = [t("<tag:x>"), t("<tag:p>"), t("_:q")];
+
+        let mut g = description.clone();
+        g.push(used.clone());
+        let quoted = QuotedTriples::new(g.iter());
+        assert_eq!(quoted.get(&t("_:q")).unwrap()[2], t("\"c\""));
+        assert_eq!(g.iter().filter(|t| quoted.is_description(t)).count(), 4);
+        let mut out = vec![];
+        quoted
+            .write_term(&mut out, &t("_:q"), false, &mut None)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<< <tag:a> <tag:b> \"c\" >>"
+        );
+
+        // not used elsewhere
+        assert!(QuotedTriples::new(description.iter()).is_empty());
+        // described twice
+        let mut g = description.clone();
+        g.push(used.clone());
+        g.push([t("_:q"), BoxTerm::copy(&rdf::object), t("\"d\"")]);
+        assert!(QuotedTriples::new(g.iter()).is_empty());
+        // used as predicate
+        let mut g = description.clone();
+        g.push([t("<tag:x>"), t("_:q"), t("<tag:y>")]);
+        assert!(QuotedTriples::new(g.iter()).is_empty());
+        // containing itself
+        let mut g = description;
+        g.push(used);
+        g[1][2] = t("_:q");
+        assert!(QuotedTriples::new(g.iter()).is_empty());
+    }
+}
@@ -0,0 +1,849 @@
+//! Serializer for the [Turtle] concrete syntax of RDF.
+//!
+//! Triples are grouped by subject (in the order where subjects first appear),
+//! then by predicate, and IRIs are abbreviated with the prefixes of the [`TurtleConfig`].
+//! The whole graph is buffered before being written,
+//! so that triples about the same subject are grouped even if they are not contiguous.
+//!
+//! The serializer can also replay the comments and directives of a parsed document
+//! (see [`TurtleConfig::set_metadata`] and [`metadata`]),
+//! so that round-trips of documents under version control are (nearly) lossless.
+//!
+//! With [`TurtleConfig::set_star`], quoted triples are written as `<< s p o >>`
+//! (see [`NtConfig::set_star`](../nt/struct.NtConfig.html#method.set_star)),
+//! which [`TurtleParser::parse_star`] reads back.
+//!
+//! [Turtle]: https://www.w3.org/TR/turtle/
+//! [`TurtleConfig`]: struct.TurtleConfig.html
+//! [`TurtleConfig::set_metadata`]: struct.TurtleConfig.html#method.set_metadata
+//! [`metadata`]: ../../parser/turtle/metadata/index.html
+//! [`TurtleConfig::set_star`]: struct.TurtleConfig.html#method.set_star
+//! [`TurtleParser::parse_star`]: ../../parser/turtle/struct.TurtleParser.html#method.parse_star
+
+use super::buffer::{BoundedBuffer, DEFAULT_BUFFER_SIZE};
+use super::nt::write_term;
+use super::star::QuotedTriples;
+use crate::parser::nq12::is_pn_chars;
+use crate::parser::turtle::lexer::{tokenize, TokenKind};
+use crate::parser::turtle::metadata::{Anchor, MetadataEntry, MetadataItem, SourceMetadata};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::serializer::*;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+pub mod format;
+
+/// Turtle serializer configuration.
+#[derive(Clone, Debug, Default)]
+pub struct TurtleConfig {
+    prefixes: Vec<(String, String)>,
+    metadata: Option<SourceMetadata>,
+    buffer_size: Option<usize>,
+    indent: Option<usize>,
+    predicate_order: PredicateOrder,
+    objects_per_line: bool,
+    blank_line_between_subjects: bool,
+    pretty: bool,
+    star: bool,
+}
+
+/// The order in which the predicates of a subject are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PredicateOrder {
+    /// The order where predicates first appear in the graph.
+    #[default]
+    Source,
+    /// `rdf:type` first, then the other predicates in source order.
+    TypeFirst,
+    /// `rdf:type` first, then the other predicates in the alphabetical order
+    /// of their serialization (i.e. of their prefixed name, if any).
+    Alphabetical,
+}
+
+impl TurtleConfig {
+    /// Declare `prefix` for namespace `ns` (replacing any previous declaration of `prefix`).
+    ///
+    /// Prefixes are ignored when [metadata](#method.set_metadata) is set,
+    /// as the prefix declarations of the metadata are used instead.
+    pub fn add_prefix<P: Into<String>, N: Into<String>>(&mut self, prefix: P, ns: N) -> &mut Self {
+        set_prefix(&mut self.prefixes, prefix.into(), ns.into());
+        self
+    }
+
+    /// The prefixes declared in this configuration.
+    pub fn prefixes(&self) -> &[(String, String)] {
+        &self.prefixes
+    }
+
+    /// Set the comments and directives to replay,
+    /// as captured by [`TurtleParser::parse_with_metadata`].
+    ///
+    /// Each item is written at its [anchor]:
+    /// items anchored to a subject which is not in the serialized graph
+    /// are written at the end, before the items anchored to the end.
+    ///
+    /// [`TurtleParser::parse_with_metadata`]: ../../parser/turtle/struct.TurtleParser.html#method.parse_with_metadata
+    /// [anchor]: ../../parser/turtle/metadata/enum.Anchor.html
+    pub fn set_metadata(&mut self, metadata: Option<SourceMetadata>) -> &mut Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// The metadata replayed by the serializer, if any.
+    pub fn metadata(&self) -> Option<&SourceMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Set the maximum number of bytes buffered by the serializer
+    /// before writing them to its target
+    /// (defaults to [`DEFAULT_BUFFER_SIZE`](../constant.DEFAULT_BUFFER_SIZE.html)).
+    pub fn set_buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Set the number of spaces used for each level of indentation (defaults to 4).
+    pub fn set_indent(&mut self, indent: usize) -> &mut Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Set the order in which the predicates of each subject are written
+    /// (defaults to [`PredicateOrder::Source`](enum.PredicateOrder.html#variant.Source)).
+    pub fn set_predicate_order(&mut self, order: PredicateOrder) -> &mut Self {
+        self.predicate_order = order;
+        self
+    }
+
+    /// Set whether each object of a predicate is written on its own line
+    /// (defaults to false, writing them on the same line, separated by commas).
+    pub fn set_objects_per_line(&mut self, objects_per_line: bool) -> &mut Self {
+        self.objects_per_line = objects_per_line;
+        self
+    }
+
+    /// Set whether statements are separated by a blank line (defaults to false).
+    ///
+    /// Blank lines replayed from [metadata](#method.set_metadata) are written in addition to these.
+    pub fn set_blank_line_between_subjects(&mut self, blank_line: bool) -> &mut Self {
+        self.blank_line_between_subjects = blank_line;
+        self
+    }
+
+    /// Set whether the abbreviated syntaxes of Turtle are used (defaults to false).
+    ///
+    /// If true,
+    /// * blank nodes used once as object (or not at all) are written inline, as `[ ... ]`,
+    ///   unless some [metadata](#method.set_metadata) is anchored to them;
+    /// * well-formed lists of such blank nodes are written as collections, i.e. `( ... )`;
+    /// * numbers and booleans are written without quotes nor datatype,
+    ///   and other datatypes are abbreviated with the prefixes.
+    pub fn set_pretty(&mut self, pretty: bool) -> &mut Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Set whether quoted triples are written as `<< s p o >>`
+    /// (see [module documentation](index.html)).
+    ///
+    /// By default, their descriptions are written as any other triples.
+    pub fn set_star(&mut self, star: bool) -> &mut Self {
+        self.star = star;
+        self
+    }
+}
+
+/// Turtle serializer.
+pub struct TurtleSerializer<W> {
+    config: TurtleConfig,
+    write: BoundedBuffer<W>,
+}
+
+impl<W> TurtleSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new Turtle serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> TurtleSerializer<W> {
+        Self::new_with_config(write, TurtleConfig::default())
+    }
+
+    /// Build a new Turtle serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: TurtleConfig) -> TurtleSerializer<W> {
+        let capacity = config.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        TurtleSerializer {
+            write: BoundedBuffer::new(write, capacity),
+            config,
+        }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &TurtleConfig {
+        &self.config
+    }
+
+    fn write_graph(&mut self, triples: Vec<[BoxTerm; 3]>) -> io::Result<()> {
+        let quoted = if self.config.star {
+            QuotedTriples::new(triples.iter())
+        } else {
+            QuotedTriples::default()
+        };
+        let mut subjects: Vec<(BoxTerm, PredicateObjects)> = vec![];
+        for [s, p, o] in triples.into_iter().filter(|t| !quoted.is_description(t)) {
+            let i = match subjects.iter().position(|g| g.0 == s) {
+                Some(i) => i,
+                None => {
+                    subjects.push((s, vec![]));
+                    subjects.len() - 1
+                }
+            };
+            let predicates = &mut subjects[i].1;
+            match predicates.iter_mut().find(|g| g.0 == p) {
+                Some(g) => {
+                    if !g.1.contains(&o) {
+                        g.1.push(o)
+                    }
+                }
+                None => predicates.push((p, vec![o])),
+            }
+        }
+        let metadata = self.config.metadata.clone();
+        let entries = metadata.as_ref().map(|m| m.entries()).unwrap_or_default();
+        let mut prefixes = vec![];
+        if metadata.is_none() {
+            for (prefix, ns) in self.config.prefixes.clone() {
+                let item = MetadataItem::Prefix {
+                    prefix,
+                    iri: ns,
+                    sparql: false,
+                };
+                self.write_item(&item, &mut prefixes)?;
+            }
+            if !prefixes.is_empty() && !subjects.is_empty() {
+                self.write.write_all(b"\n")?;
+            }
+        }
+        let mut used = vec![false; entries.len()];
+        let mut replay = Replay {
+            entries,
+            used: &mut used,
+            prefixes: &mut prefixes,
+        };
+        self.replay(&mut replay, |a| a == &Anchor::Start)?;
+        let config = self.config.clone();
+        let layout = Layout::new(&config, &subjects, &quoted, entries);
+        let mut first = true;
+        for (s, predicates) in &subjects {
+            if layout.inlined.contains(s) {
+                continue;
+            }
+            if self.config.blank_line_between_subjects && !first {
+                self.write.write_all(b"\n")?;
+            }
+            first = false;
+            self.replay(&mut replay, |a| a == &Anchor::Before(s.clone()))?;
+            let statement = layout.statement(s, predicates, &replay.prefixes[..]);
+            self.write.write_all(statement.as_bytes())?;
+            for (i, entry) in entries.iter().enumerate() {
+                if let (Anchor::After(subject), MetadataItem::Comment(txt)) =
+                    (entry.anchor(), entry.item())
+                {
+                    if !replay.used[i] && subject == s {
+                        replay.used[i] = true;
+                        write!(self.write, " #{}", txt)?;
+                    }
+                }
+            }
+            self.write.write_all(b"\n")?;
+        }
+        self.replay(&mut replay, |a| a != &Anchor::End)?;
+        self.replay(&mut replay, |_| true)
+    }
+
+    /// Write the entries of `replay` not written yet whose anchor passes `test`.
+    fn replay<F>(&mut self, replay: &mut Replay, test: F) -> io::Result<()>
+    where
+        F: Fn(&Anchor) -> bool,
+    {
+        for (i, entry) in replay.entries.iter().enumerate() {
+            if !replay.used[i] && test(entry.anchor()) {
+                replay.used[i] = true;
+                self.write_item(entry.item(), replay.prefixes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `item` on its own line, updating `prefixes` if it is a prefix declaration.
+    fn write_item(
+        &mut self,
+        item: &MetadataItem,
+        prefixes: &mut Vec<(String, String)>,
+    ) -> io::Result<()> {
+        let w = &mut self.write;
+        match item {
+            MetadataItem::Comment(txt) => writeln!(w, "#{}", txt),
+            MetadataItem::Prefix {
+                prefix,
+                iri,
+                sparql,
+            } => {
+                set_prefix(prefixes, prefix.clone(), iri.clone());
+                let iri = term_to_turtle(&BoxTerm::new_iri_unchecked(iri.as_str()), &[], false);
+                if *sparql {
+                    writeln!(w, "PREFIX {}: {}", prefix, iri)
+                } else {
+                    writeln!(w, "@prefix {}: {} .", prefix, iri)
+                }
+            }
+            MetadataItem::Base { iri, sparql } => {
+                let iri = term_to_turtle(&BoxTerm::new_iri_unchecked(iri.as_str()), &[], false);
+                if *sparql {
+                    writeln!(w, "BASE {}", iri)
+                } else {
+                    writeln!(w, "@base {} .", iri)
+                }
+            }
+            MetadataItem::BlankLine => w.write_all(b"\n"),
+        }
+    }
+}
+
+/// The state of the metadata being replayed.
+struct Replay<'a> {
+    entries: &'a [MetadataEntry],
+    used: &'a mut [bool],
+    prefixes: &'a mut Vec<(String, String)>,
+}
+
+/// The predicates of a subject, each with its objects.
+type PredicateObjects = Vec<(BoxTerm, Vec<BoxTerm>)>;
+
+/// How the statements of a graph are written.
+struct Layout<'a> {
+    config: &'a TurtleConfig,
+    subjects: HashMap<&'a BoxTerm, &'a PredicateObjects>,
+    quoted: &'a QuotedTriples,
+    /// Blank nodes used as object (or in a quoted triple)
+    referenced: HashSet<&'a BoxTerm>,
+    /// Blank nodes written inline, where they are used as object
+    inlined: HashSet<&'a BoxTerm>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(
+        config: &'a TurtleConfig,
+        subjects: &'a [(BoxTerm, PredicateObjects)],
+        quoted: &'a QuotedTriples,
+        entries: &[MetadataEntry],
+    ) -> Self {
+        let mut layout = Layout {
+            config,
+            subjects: subjects.iter().map(|(s, p)| (s, p)).collect(),
+            quoted,
+            referenced: HashSet::new(),
+            inlined: HashSet::new(),
+        };
+        if !config.pretty {
+            return layout;
+        }
+        let mut refs: HashMap<&BoxTerm, usize> = HashMap::new();
+        for (_, predicates) in subjects {
+            for o in predicates.iter().flat_map(|(_, objects)| objects) {
+                if o.kind() == TermKind::BlankNode && quoted.get(o).is_none() {
+                    *refs.entry(o).or_default() += 1;
+                }
+            }
+        }
+        // blank nodes in quoted triples can not be written inline
+        for o in quoted.triples().flatten() {
+            if o.kind() == TermKind::BlankNode && quoted.get(o).is_none() {
+                *refs.entry(o).or_default() += 2;
+            }
+        }
+        let anchored = |t: &BoxTerm| {
+            entries.iter().any(|e| match e.anchor() {
+                Anchor::Before(a) | Anchor::After(a) => a == t,
+                _ => false,
+            })
+        };
+        layout.referenced = refs.keys().copied().collect();
+        layout.inlined = refs
+            .into_iter()
+            .filter(|(t, n)| *n == 1 && !anchored(t))
+            .map(|(t, _)| t)
+            .collect();
+        // blank nodes only reachable from each other (in a cycle) must be written as statements,
+        // otherwise they would not be written at all
+        loop {
+            let mut reached = HashSet::new();
+            let mut stack: Vec<&BoxTerm> = subjects
+                .iter()
+                .map(|(s, _)| s)
+                .filter(|s| !layout.inlined.contains(s))
+                .collect();
+            while let Some(node) = stack.pop() {
+                for (_, objects) in layout.subjects.get(node).iter().flat_map(|p| p.iter()) {
+                    for o in objects {
+                        if layout.inlined.contains(o) && reached.insert(o) {
+                            stack.push(o);
+                        }
+                    }
+                }
+            }
+            match subjects
+                .iter()
+                .find(|(s, _)| layout.inlined.contains(s) && !reached.contains(s))
+            {
+                Some((s, _)) => layout.inlined.remove(s),
+                None => break,
+            };
+        }
+        layout
+    }
+
+    /// Serialize the statement about `s`, up to the final `.` (included).
+    fn statement(
+        &self,
+        s: &BoxTerm,
+        predicates: &PredicateObjects,
+        prefixes: &[(String, String)],
+    ) -> String {
+        let mut out = String::new();
+        if self.config.pretty
+            && s.kind() == TermKind::BlankNode
+            && !self.referenced.contains(s)
+            && self.quoted.get(s).is_none()
+        {
+            out.push_str("[\n");
+            self.indent(&mut out, 1);
+            self.predicates(&mut out, predicates, 0, prefixes);
+            out.push_str("\n]");
+        } else {
+            out.push_str(&self.term(s, prefixes, false));
+            out.push(' ');
+            self.predicates(&mut out, predicates, 0, prefixes);
+        }
+        out.push_str(" .");
+        out
+    }
+
+    /// Serialize the predicates and objects of a node, nested `level` times.
+    fn predicates(
+        &self,
+        out: &mut String,
+        predicates: &PredicateObjects,
+        level: usize,
+        prefixes: &[(String, String)],
+    ) {
+        let mut sorted: Vec<_> = predicates.iter().collect();
+        let not_type = |p: &BoxTerm| !term_eq(p, &rdf::type_);
+        match self.config.predicate_order {
+            PredicateOrder::Source => (),
+            PredicateOrder::TypeFirst => sorted.sort_by_key(|(p, _)| not_type(p)),
+            PredicateOrder::Alphabetical => {
+                sorted.sort_by_cached_key(|(p, _)| (not_type(p), term_to_turtle(p, prefixes, true)))
+            }
+        }
+        for (j, (p, objects)) in sorted.into_iter().enumerate() {
+            if j > 0 {
+                out.push_str(" ;\n");
+                self.indent(out, level + 1);
+            }
+            out.push_str(&term_to_turtle(p, prefixes, true));
+            for (k, o) in objects.iter().enumerate() {
+                if k == 0 {
+                    out.push(' ');
+                } else if self.config.objects_per_line {
+                    out.push_str(",\n");
+                    self.indent(out, level + 2);
+                } else {
+                    out.push_str(", ");
+                }
+                self.object(out, o, level, prefixes);
+            }
+        }
+    }
+
+    /// Serialize an object of a node nested `level` times.
+    fn object(&self, out: &mut String, o: &BoxTerm, level: usize, prefixes: &[(String, String)]) {
+        if !self.inlined.contains(o) {
+            if self.config.pretty && term_eq(o, &rdf::nil) {
+                out.push_str("()");
+            } else {
+                out.push_str(&self.term(o, prefixes, false));
+            }
+        } else if let Some(items) = self.list_items(o) {
+            out.push('(');
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                self.object(out, item, level, prefixes);
+            }
+            out.push(')');
+        } else {
+            match self.subjects.get(o) {
+                None => out.push_str("[]"),
+                Some(predicates) => {
+                    out.push_str("[\n");
+                    self.indent(out, level + 2);
+                    self.predicates(out, predicates, level + 1, prefixes);
+                    out.push('\n');
+                    self.indent(out, level + 1);
+                    out.push(']');
+                }
+            }
+        }
+    }
+
+    /// The items of the list starting at `head`, if it can be written as a collection.
+    fn list_items(&self, head: &'a BoxTerm) -> Option<Vec<&'a BoxTerm>> {
+        let mut items = vec![];
+        let mut node = head;
+        loop {
+            let predicates = self.subjects.get(node)?;
+            if !self.inlined.contains(node) || predicates.len() != 2 {
+                return None;
+            }
+            let single = |p| match predicates.iter().find(|(q, _)| term_eq(q, p)) {
+                Some((_, objects)) if objects.len() == 1 => Some(&objects[0]),
+                _ => None,
+            };
+            items.push(single(&rdf::first)?);
+            let rest = single(&rdf::rest)?;
+            if term_eq(rest, &rdf::nil) {
+                return Some(items);
+            }
+            node = rest;
+        }
+    }
+
+    fn term(&self, t: &BoxTerm, prefixes: &[(String, String)], predicate: bool) -> String {
+        if let Some([s, p, o]) = self.quoted.get(t) {
+            format!(
+                "<< {} {} {} >>",
+                self.term(s, prefixes, false),
+                self.term(p, prefixes, true),
+                self.term(o, prefixes, false),
+            )
+        } else if self.config.pretty && t.kind() == TermKind::Literal {
+            literal_to_turtle(t, prefixes)
+        } else {
+            term_to_turtle(t, prefixes, predicate)
+        }
+    }
+
+    fn indent(&self, out: &mut String, level: usize) {
+        let width = self.config.indent.unwrap_or(4) * level;
+        out.push_str(&" ".repeat(width));
+    }
+}
+
+impl<W> TripleSerializer for TurtleSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_triples<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+    {
+        let mut triples = vec![];
+        source.try_for_each_triple(|t| {
+            if t.is_generalized() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "triple only valid in generalized RDF",
+                ));
+            }
+            triples.push([
+                BoxTerm::copy(t.s()),
+                BoxTerm::copy(t.p()),
+                BoxTerm::copy(t.o()),
+            ]);
+            Ok(())
+        })?;
+        self.write_graph(triples).map_err(SinkError)?;
+        self.write.drain().map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        io::Write::flush(&mut self.write)
+    }
+
+    fn bytes_written(&self) -> Option<u64> {
+        Some(self.write.written())
+    }
+}
+
+impl TurtleSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        TurtleSerializer::new(Vec::new())
+    }
+    /// Create a new serializer which targets a `String` with a custom config.
+    #[inline]
+    pub fn new_stringifier_with_config(config: TurtleConfig) -> Self {
+        TurtleSerializer::new_with_config(Vec::new(), config)
+    }
+}
+
+impl Stringifier for TurtleSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write.get_ref()[..]
+    }
+}
+
+/// Serialize `t` in Turtle, abbreviating IRIs with the longest matching namespace in `prefixes`,
+/// and writing `rdf:type` as `a` if `predicate` is true.
+pub(crate) fn term_to_turtle<T>(t: &T, prefixes: &[(String, String)], predicate: bool) -> String
+where
+    T: TTerm + ?Sized,
+{
+    if t.kind() == TermKind::Iri {
+        if predicate && term_eq(&rdf::type_, t) {
+            return "a".to_string();
+        }
+        let iri = t.value();
+        let best = prefixes
+            .iter()
+            .filter_map(|(prefix, ns)| {
+                let local = iri.strip_prefix(ns.as_str())?;
+                Some((prefix, ns.len(), local)).filter(|_| is_simple_local(local))
+            })
+            .max_by_key(|(_, len, _)| *len);
+        if let Some((prefix, _, local)) = best {
+            return format!("{}:{}", prefix, local);
+        }
+    }
+    let mut buf = vec![];
+    write_term(&mut buf, t).unwrap(); // writing to a Vec can not fail
+    String::from_utf8(buf).unwrap() // write_term only writes valid UTF-8
+}
+
+/// Serialize literal `t` in Turtle, without quotes nor datatype for numbers and booleans,
+/// and abbreviating its datatype with `prefixes` otherwise.
+fn literal_to_turtle<T>(t: &T, prefixes: &[(String, String)]) -> String
+where
+    T: TTerm + ?Sized,
+{
+    if t.language().is_none() {
+        let value = t.value();
+        let dt = t.datatype().unwrap();
+        let kind = [
+            (xsd::integer, TokenKind::Integer),
+            (xsd::decimal, TokenKind::Decimal),
+            (xsd::double, TokenKind::Double),
+            (xsd::boolean, TokenKind::Boolean),
+        ]
+        .iter()
+        .find(|(d, _)| *d == dt)
+        .map(|(_, k)| *k);
+        if let Some(kind) = kind {
+            // the lexical form is valid as is if it is lexed as a single token of the right kind
+            let mut tokens = tokenize(&value);
+            if let (Some(token), None) = (tokens.next(), tokens.next()) {
+                if token.kind() == kind && token.error().is_none() {
+                    return value.to_string();
+                }
+            }
+        }
+        if xsd::string != dt {
+            let lex = BoxTerm::new_literal_dt_unchecked(&*value, xsd::string);
+            return format!(
+                "{}^^{}",
+                term_to_turtle(&lex, prefixes, false),
+                term_to_turtle(&dt, prefixes, false)
+            );
+        }
+    }
+    term_to_turtle(t, prefixes, false)
+}
+
+/// Whether `local` can be written as the local part of a prefixed name without escaping.
+fn is_simple_local(local: &str) -> bool {
+    let mut chars = local.chars();
+    match chars.next() {
+        None => true,
+        Some(c) if c == '-' || !is_pn_chars(c) => false,
+        Some(_) => chars.all(is_pn_chars),
+    }
+}
+
+fn set_prefix(prefixes: &mut Vec<(String, String)>, prefix: String, ns: String) {
+    match prefixes.iter_mut().find(|(p, _)| p == &prefix) {
+        Some(entry) => entry.1 = ns,
+        None => prefixes.push((prefix, ns)),
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::turtle::TurtleParser;
+    use crate::test_util::t;
+    use sophia_api::ns::xsd;
+    use sophia_term::*;
+
+    #[test]
+    fn graph() -> Result<(), Box<dyn std::error::Error>> {
+        let g = vec![
+            [t("a"), BoxTerm::copy(&rdf::type_), t("C")],
+            [t("b"), t("p"), t("a")],
+            [t("a"), t("p"), BoxTerm::new_literal_dt("42", xsd::integer)?],
+            [t("a"), t("p"), t("x/y")],
+            [t("a"), t("q"), BoxTerm::new_bnode("n")?],
+        ];
+        let mut config = TurtleConfig::default();
+        config.add_prefix("", "tag:").add_prefix("x", "tag:x/");
+        let s = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert_eq!(
+            s,
+            "@prefix : <tag:> .\n@prefix x: <tag:x/> .\n\n\
+             :a a :C ;\n    :p \"42\"^^<http://www.w3.org/2001/XMLSchema#integer>, x:y ;\n    :q _:n .\n\
+             :b :p :a .\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn generalized() {
+        let g = vec![[
+            StaticTerm::new_literal_dt_unchecked("a", xsd::string),
+            StaticTerm::new_iri_unchecked("tag:b"),
+            StaticTerm::new_iri_unchecked("tag:c"),
+        ]];
+        assert!(TurtleSerializer::new_stringifier()
+            .serialize_graph(&g)
+            .is_err());
+    }
+
+    const TTL: &str = "@prefix : <tag:> .\n# people\n:a :p :b, :c ;\n    :q :d . # trailing\n\n\
+                       # others\nBASE <tag:>\n:b :p :c .\n# end\n";
+
+    fn round_trip(
+        ttl: &str,
+        edit: impl Fn(&mut Vec<[BoxTerm; 3]>),
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut source = TurtleParser::default().parse_with_metadata(ttl);
+        let mut triples = vec![];
+        for tr in source.by_ref() {
+            let [s, p, o] = tr?.into_terms();
+            triples.push([BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)]);
+        }
+        edit(&mut triples);
+        let mut config = TurtleConfig::default();
+        config.set_metadata(Some(source.metadata()));
+        Ok(TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&triples)?
+            .to_string())
+    }
+
+    #[test]
+    fn metadata() -> Result<(), Box<dyn std::error::Error>> {
+        let mut source = TurtleParser::default().parse_with_metadata(TTL);
+        assert_eq!(source.by_ref().count(), 4);
+        let metadata = source.metadata();
+        let anchors: Vec<_> = metadata.entries().iter().map(|e| e.anchor()).collect();
+        assert_eq!(
+            anchors,
+            vec![
+                &Anchor::Start,
+                &Anchor::Start,
+                &Anchor::After(t("a")),
+                &Anchor::Before(t("b")),
+                &Anchor::Before(t("b")),
+                &Anchor::Before(t("b")),
+                &Anchor::End,
+            ]
+        );
+        assert_eq!(&TTL[metadata.entries()[2].span()], "# trailing");
+        assert_eq!(
+            metadata.entries()[5].item(),
+            &MetadataItem::Base {
+                iri: "tag:".into(),
+                sparql: true
+            }
+        );
+        let source = TurtleParser::default().parse_spanned(TTL);
+        assert!(source.metadata().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn lossless() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(round_trip(TTL, |_| ())?, TTL);
+        Ok(())
+    }
+
+    #[test]
+    fn edited() -> Result<(), Box<dyn std::error::Error>> {
+        let out = round_trip(TTL, |g| {
+            g.retain(|tr| tr[0] != t("b"));
+            g.push([t("e"), t("p"), t("a")]);
+        })?;
+        assert_eq!(
+            out,
+            "@prefix : <tag:> .\n# people\n:a :p :b, :c ;\n    :q :d . # trailing\n\
+             :e :p :a .\n\n# others\nBASE <tag:>\n# end\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn star() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = "@prefix : <tag:> .\n\n\
+                   << :a a << _:x :p [] >> >> :q :b ;\n    :r [\n        :s << :a :p _:y >>\n    ] .\n\
+                   _:x :p :c .\n";
+        let parse = |ttl| -> Result<Vec<[BoxTerm; 3]>, Box<dyn std::error::Error>> {
+            let mut triples = vec![];
+            for tr in TurtleParser::default().parse_star(ttl) {
+                let [s, p, o] = tr?.into_terms();
+                triples.push([BoxTerm::copy(&s), BoxTerm::copy(&p), BoxTerm::copy(&o)]);
+            }
+            Ok(triples)
+        };
+        let g = parse(ttl)?;
+        assert_eq!(g.len(), 16);
+
+        let mut config = TurtleConfig::default();
+        config
+            .add_prefix("", "tag:")
+            .set_pretty(true)
+            .set_star(true);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(out.contains(":q :b"), "{}", out);
+        assert!(out.contains(":s << :a :p _:"), "{}", out);
+        assert_eq!(out.matches("<<").count(), 3, "{}", out);
+        assert!(!out.contains("Statement"), "{}", out);
+        let g2 = parse(&out)?;
+        assert!(crate::graph::isomorphic_graphs(&g, &g2)?);
+
+        // without star, the descriptions are written as any other triples
+        let out = TurtleSerializer::new_stringifier()
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(!out.contains("<<"));
+        Ok(())
+    }
+}
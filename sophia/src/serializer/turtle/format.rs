@@ -0,0 +1,352 @@
+//! Formatting of Turtle documents according to a configurable style.
+//!
+//! [`format_turtle`] parses a Turtle document and writes it back
+//! with normalized whitespace, indentation, predicate order and directives,
+//! keeping its comments (see [`metadata`]).
+//! It is meant to enforce a consistent style on version-controlled files,
+//! e.g. by checking in CI that formatting a file does not change it.
+//!
+//! Formatting is idempotent: formatting a formatted document does not change it.
+//! Note however that
+//! * relative IRIs are written as absolute IRIs (or prefixed names),
+//! * blank node labels may change, and blank nodes used only once are written inline,
+//! * the statements about the same subject are merged.
+//!
+//! # Example
+//! ```
+//! use sophia::serializer::turtle::format::{format_turtle, FormatStyle};
+//! use sophia::serializer::turtle::PredicateOrder;
+//!
+//! let ttl = "@prefix : <http://example.org/> .\n\
+//!            :alice   :name \"Alice\" ; a :Person;:knows [ :name \"Bob\" ] .\n";
+//! let mut style = FormatStyle::default();
+//! style.set_predicate_order(PredicateOrder::Alphabetical);
+//! assert_eq!(
+//!     format_turtle(ttl, &style)?,
+//!     "@prefix : <http://example.org/> .\n\
+//!      :alice a :Person ;\n    \
+//!          :knows [\n        \
+//!              :name \"Bob\"\n    \
+//!          ] ;\n    \
+//!          :name \"Alice\" .\n"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`format_turtle`]: fn.format_turtle.html
+//! [`metadata`]: ../../../parser/turtle/metadata/index.html
+
+use std::convert::Infallible;
+
+use sophia_api::parser::ParseError;
+use sophia_api::serializer::{Stringifier, TripleSerializer};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+use super::{PredicateOrder, TurtleConfig, TurtleSerializer};
+use crate::parser::turtle::lexer::{tokenize, TokenKind};
+use crate::parser::turtle::metadata::{Anchor, MetadataEntry, MetadataItem, SourceMetadata};
+use crate::parser::turtle::TurtleParser;
+
+/// The syntax of prefix and base declarations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DirectiveStyle {
+    /// Keep the syntax of each declaration.
+    #[default]
+    Keep,
+    /// Use the `@prefix` and `@base` syntax.
+    Turtle,
+    /// Use the `PREFIX` and `BASE` syntax.
+    Sparql,
+}
+
+/// The style applied by [`format_turtle`](fn.format_turtle.html).
+#[derive(Clone, Debug)]
+pub struct FormatStyle {
+    config: TurtleConfig,
+    blank_line_between_subjects: bool,
+    directives: DirectiveStyle,
+    sort_prefixes: bool,
+    prune_prefixes: bool,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        let mut config = TurtleConfig::default();
+        config
+            .set_pretty(true)
+            .set_predicate_order(PredicateOrder::TypeFirst);
+        FormatStyle {
+            config,
+            blank_line_between_subjects: false,
+            directives: DirectiveStyle::default(),
+            sort_prefixes: false,
+            prune_prefixes: false,
+        }
+    }
+}
+
+impl FormatStyle {
+    /// Set the number of spaces used for each level of indentation (defaults to 4).
+    pub fn set_indent(&mut self, indent: usize) -> &mut Self {
+        self.config.set_indent(indent);
+        self
+    }
+
+    /// Set the order in which the predicates of each subject are written
+    /// (defaults to [`PredicateOrder::TypeFirst`](../enum.PredicateOrder.html#variant.TypeFirst)).
+    pub fn set_predicate_order(&mut self, order: PredicateOrder) -> &mut Self {
+        self.config.set_predicate_order(order);
+        self
+    }
+
+    /// Set whether each object of a predicate is written on its own line (defaults to false).
+    pub fn set_objects_per_line(&mut self, objects_per_line: bool) -> &mut Self {
+        self.config.set_objects_per_line(objects_per_line);
+        self
+    }
+
+    /// Set whether statements are separated by exactly one blank line (defaults to false).
+    ///
+    /// If false, blank lines between statements are kept as in the source
+    /// (consecutive blank lines being merged).
+    pub fn set_blank_line_between_subjects(&mut self, blank_line: bool) -> &mut Self {
+        self.blank_line_between_subjects = blank_line;
+        self
+    }
+
+    /// Set the syntax of prefix and base declarations
+    /// (defaults to [`DirectiveStyle::Keep`](enum.DirectiveStyle.html#variant.Keep)).
+    pub fn set_directives(&mut self, directives: DirectiveStyle) -> &mut Self {
+        self.directives = directives;
+        self
+    }
+
+    /// Set whether consecutive prefix declarations are sorted by prefix (defaults to false).
+    pub fn set_sort_prefixes(&mut self, sort: bool) -> &mut Self {
+        self.sort_prefixes = sort;
+        self
+    }
+
+    /// Set whether the declarations of prefixes not used in the document are removed
+    /// (defaults to false).
+    pub fn set_prune_prefixes(&mut self, prune: bool) -> &mut Self {
+        self.prune_prefixes = prune;
+        self
+    }
+}
+
+/// Format the Turtle document `txt` according to `style`
+/// (see [module documentation](index.html)).
+pub fn format_turtle(txt: &str, style: &FormatStyle) -> Result<String, Box<ParseError>> {
+    let mut source = TurtleParser::default().parse_with_metadata(txt);
+    let mut triples = vec![];
+    for t in source.by_ref() {
+        let t = t.map_err(Box::new)?;
+        triples.push([
+            BoxTerm::copy(t.s()),
+            BoxTerm::copy(t.p()),
+            BoxTerm::copy(t.o()),
+        ]);
+    }
+    let mut entries = source.metadata().entries().to_vec();
+    if style.blank_line_between_subjects {
+        entries.retain(|e| {
+            e.item() != &MetadataItem::BlankLine || !matches!(e.anchor(), Anchor::Before(_))
+        });
+    }
+    for entry in &mut entries {
+        if let MetadataItem::Prefix { sparql, .. } | MetadataItem::Base { sparql, .. } =
+            &mut entry.item
+        {
+            match style.directives {
+                DirectiveStyle::Keep => (),
+                DirectiveStyle::Turtle => *sparql = false,
+                DirectiveStyle::Sparql => *sparql = true,
+            }
+        }
+    }
+    if style.sort_prefixes {
+        sort_prefixes(&mut entries);
+    }
+    let formatted = serialize(&triples, &entries, style);
+    if !style.prune_prefixes {
+        return Ok(formatted);
+    }
+    let used = used_prefixes(&formatted);
+    entries.retain(|e| match e.item() {
+        MetadataItem::Prefix { prefix, .. } => used.contains(prefix),
+        _ => true,
+    });
+    Ok(serialize(&triples, &entries, style))
+}
+
+fn serialize(triples: &[[BoxTerm; 3]], entries: &[MetadataEntry], style: &FormatStyle) -> String {
+    let mut config = style.config.clone();
+    config
+        .set_metadata(Some(SourceMetadata {
+            entries: entries.to_vec(),
+        }))
+        .set_blank_line_between_subjects(style.blank_line_between_subjects);
+    TurtleSerializer::new_stringifier_with_config(config)
+        .serialize_triples(triples.iter().map(Ok::<_, Infallible>))
+        .expect("triples parsed from Turtle can be serialized in Turtle")
+        .to_string()
+}
+
+/// Sort each run of consecutive prefix declarations having the same anchor.
+fn sort_prefixes(entries: &mut [MetadataEntry]) {
+    let is_prefix = |e: &MetadataEntry| matches!(e.item(), MetadataItem::Prefix { .. });
+    let mut start = 0;
+    while start < entries.len() {
+        let mut end = start;
+        while end < entries.len()
+            && is_prefix(&entries[end])
+            && entries[end].anchor() == entries[start].anchor()
+        {
+            end += 1;
+        }
+        entries[start..end].sort_by(|a, b| match (a.item(), b.item()) {
+            (MetadataItem::Prefix { prefix: p1, .. }, MetadataItem::Prefix { prefix: p2, .. }) => {
+                p1.cmp(p2)
+            }
+            _ => unreachable!(),
+        });
+        start = end.max(start + 1);
+    }
+}
+
+/// The prefixes used by prefixed names in `ttl`, outside of prefix declarations.
+fn used_prefixes(ttl: &str) -> Vec<String> {
+    let mut used = vec![];
+    let mut in_declaration = false;
+    for token in tokenize(ttl).filter(|t| !t.kind().is_trivia()) {
+        match token.kind() {
+            TokenKind::Keyword => {
+                let keyword = token.text();
+                in_declaration = keyword == "@prefix" || keyword.eq_ignore_ascii_case("PREFIX");
+            }
+            TokenKind::PrefixedName if !in_declaration => {
+                let prefix = &token.text()[..token.text().find(':').unwrap()];
+                if !used.iter().any(|p| p == prefix) {
+                    used.push(prefix.to_string());
+                }
+            }
+            _ => in_declaration = false,
+        }
+    }
+    used
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TTL: &str = r#"PREFIX ex: <http://example.org/>
+@prefix unused: <http://unused.example/> .
+@prefix a: <http://a.example/> .
+# people
+ex:alice ex:knows ex:bob,ex:carol;a ex:Person ;
+  ex:age "42"^^<http://www.w3.org/2001/XMLSchema#integer> ; ex:list ( 1 "two" [ ex:p a:x ] ) . # Alice
+
+
+ex:bob ex:age 12.50 ; ex:pet _:rex .
+_:rex ex:name "Rex" . ex:carol ex:pet _:rex .
+"#;
+
+    #[test]
+    fn default_style() -> Result<(), Box<ParseError>> {
+        let out = format_turtle(TTL, &FormatStyle::default())?;
+        assert_eq!(
+            out,
+            r#"PREFIX ex: <http://example.org/>
+@prefix unused: <http://unused.example/> .
+@prefix a: <http://a.example/> .
+# people
+ex:alice a ex:Person ;
+    ex:knows ex:bob, ex:carol ;
+    ex:age 42 ;
+    ex:list (1 "two" [
+        ex:p a:x
+    ]) . # Alice
+
+ex:bob ex:age 12.50 ;
+    ex:pet _:rex .
+_:rex ex:name "Rex" .
+ex:carol ex:pet _:rex .
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn custom_style() -> Result<(), Box<ParseError>> {
+        let mut style = FormatStyle::default();
+        style
+            .set_indent(2)
+            .set_predicate_order(PredicateOrder::Alphabetical)
+            .set_objects_per_line(true)
+            .set_blank_line_between_subjects(true)
+            .set_directives(DirectiveStyle::Turtle)
+            .set_sort_prefixes(true)
+            .set_prune_prefixes(true);
+        let out = format_turtle(TTL, &style)?;
+        assert_eq!(
+            out,
+            r#"@prefix a: <http://a.example/> .
+@prefix ex: <http://example.org/> .
+# people
+ex:alice a ex:Person ;
+  ex:age 42 ;
+  ex:knows ex:bob,
+    ex:carol ;
+  ex:list (1 "two" [
+    ex:p a:x
+  ]) . # Alice
+
+ex:bob ex:age 12.50 ;
+  ex:pet _:rex .
+
+_:rex ex:name "Rex" .
+
+ex:carol ex:pet _:rex .
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn idempotent() -> Result<(), Box<ParseError>> {
+        let mut style = FormatStyle::default();
+        let once = format_turtle(TTL, &style)?;
+        assert_eq!(format_turtle(&once, &style)?, once);
+        style.set_objects_per_line(true).set_prune_prefixes(true);
+        let once = format_turtle(TTL, &style)?;
+        assert_eq!(format_turtle(&once, &style)?, once);
+        Ok(())
+    }
+
+    #[test]
+    fn anonymous_subject() -> Result<(), Box<ParseError>> {
+        let out = format_turtle(
+            "[ <tag:p> <tag:o> ] <tag:q> [] . _:a <tag:r> _:b . _:b <tag:r> _:a .",
+            &FormatStyle::default(),
+        )?;
+        assert_eq!(
+            out,
+            "[\n    <tag:p> <tag:o> ;\n    <tag:q> []\n] .\n_:a <tag:r> [\n        <tag:r> _:a\n    ] .\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn error() {
+        let err = format_turtle("<tag:s> <tag:p> .", &FormatStyle::default()).unwrap_err();
+        assert_eq!(err.line(), Some(1));
+    }
+}
@@ -0,0 +1,542 @@
+//! Induction of [SHACL] shapes from instance data.
+//!
+//! A [`ShapeInducer`] builds one [`NodeShape`] per class instantiated in a graph,
+//! describing the properties used by the instances of that class:
+//! * how many instances use them, which gives their `sh:minCount`
+//!   (1 if every instance uses the property);
+//! * how many values each instance has, which gives their `sh:maxCount`
+//!   (if it never exceeds a [configurable limit](struct.ShapeInducer.html#method.set_max_count_limit));
+//! * the kind of their values, which gives their `sh:datatype` (if all values are literals of the same datatype),
+//!   their `sh:class` (if all values are instances of a common class),
+//!   or their `sh:nodeKind` otherwise.
+//!
+//! The resulting shapes can then be [written](fn.write_shapes.html) into a shapes graph,
+//! to bootstrap the validation of datasets that lack a formal schema.
+//! They are only a starting point, and should be reviewed by a human.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::shapes::{write_shapes, ShapeInducer};
+//! use sophia::term::TTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let data: FastGraph = turtle::parse_str(r#"
+//!     @prefix s: <http://schema.org/>.
+//!     <tag:alice> a s:Person; s:name "Alice"; s:knows <tag:bob>.
+//!     <tag:bob> a s:Person; s:name "Bob".
+//! "#).collect_triples()?;
+//!
+//! let shapes = ShapeInducer::new("http://example.org/shapes#")?.induce(&data)?;
+//! assert_eq!(shapes.len(), 1);
+//! assert_eq!(shapes[0].id.value(), "http://example.org/shapes#Person");
+//! let name = &shapes[0].properties[1];
+//! assert_eq!(name.path.value(), "http://schema.org/name");
+//! assert_eq!((name.min_count, name.max_count), (Some(1), Some(1)));
+//!
+//! let mut shapes_graph = FastGraph::new();
+//! write_shapes(&shapes, &mut shapes_graph)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SHACL]: https://www.w3.org/TR/shacl/
+//! [`ShapeInducer`]: struct.ShapeInducer.html
+//! [`NodeShape`]: struct.NodeShape.html
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use sophia_api::graph::{GResult, Graph, MGResult, MutableGraph};
+use sophia_api::term::{term_eq, term_to_string as nt, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::{BoxTerm, TermError};
+
+use crate::ns::{rdf, xsd};
+
+/// The SHACL namespace (only the terms used by shape induction).
+#[allow(missing_docs)]
+pub mod sh {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/ns/shacl#",
+        BlankNode,
+        BlankNodeOrIRI,
+        BlankNodeOrLiteral,
+        IRI,
+        IRIOrLiteral,
+        Literal,
+        NodeShape,
+        PropertyShape,
+        class,
+        datatype,
+        maxCount,
+        minCount,
+        nodeKind,
+        path,
+        property,
+        targetClass
+    );
+}
+
+/// A shape describing the instances of a class, as induced by a [`ShapeInducer`].
+///
+/// [`ShapeInducer`]: struct.ShapeInducer.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeShape {
+    /// The IRI of this shape.
+    pub id: BoxTerm,
+    /// The class targeted by this shape.
+    pub target_class: BoxTerm,
+    /// The number of instances of the target class.
+    pub instances: usize,
+    /// The shapes of the properties of the instances, sorted by path.
+    pub properties: Vec<PropertyShape>,
+}
+
+/// A shape describing the values of a property, as induced by a [`ShapeInducer`].
+///
+/// [`ShapeInducer`]: struct.ShapeInducer.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyShape {
+    /// The IRI of this shape.
+    pub id: BoxTerm,
+    /// The property described by this shape.
+    pub path: BoxTerm,
+    /// The number of instances of the target class using this property.
+    pub support: usize,
+    /// The minimum number of values, if any.
+    pub min_count: Option<usize>,
+    /// The maximum number of values, if any.
+    pub max_count: Option<usize>,
+    /// The datatype of all the values, if they are literals of the same datatype.
+    pub datatype: Option<BoxTerm>,
+    /// A class of which all the values are instances, if any.
+    pub class: Option<BoxTerm>,
+    /// The kind of the values (`sh:IRI`, `sh:Literal`, `sh:BlankNodeOrIRI`...),
+    /// if neither `datatype` nor `class` is set, and not all kinds of nodes are used.
+    pub node_kind: Option<BoxTerm>,
+}
+
+/// Induces SHACL shapes from the instances of a graph.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct ShapeInducer {
+    base: String,
+    min_support: f64,
+    max_count_limit: usize,
+}
+
+impl ShapeInducer {
+    /// Build a shape inducer,
+    /// whose shapes are identified by IRIs starting with `base`
+    /// (typically ending with `#` or `/`).
+    pub fn new(base: &str) -> Result<Self, TermError> {
+        BoxTerm::new_iri(base)?;
+        Ok(ShapeInducer {
+            base: base.to_string(),
+            min_support: 0.0,
+            max_count_limit: 1,
+        })
+    }
+
+    /// The minimum proportion of the instances of a class that must use a property,
+    /// for that property to be included in its shape (0 by default).
+    pub fn min_support(&self) -> f64 {
+        self.min_support
+    }
+
+    /// Set the minimum proportion of the instances of a class that must use a property,
+    /// for that property to be included in its shape.
+    ///
+    /// # Panics
+    /// If `min_support` is not between 0 and 1.
+    pub fn set_min_support(&mut self, min_support: f64) -> &mut Self {
+        assert!((0.0..=1.0).contains(&min_support));
+        self.min_support = min_support;
+        self
+    }
+
+    /// The highest `sh:maxCount` emitted (1 by default).
+    pub fn max_count_limit(&self) -> usize {
+        self.max_count_limit
+    }
+
+    /// Set the highest `sh:maxCount` emitted:
+    /// properties with more values for some instance have no maximum count.
+    /// With a limit of 0, no `sh:maxCount` is ever emitted.
+    pub fn set_max_count_limit(&mut self, limit: usize) -> &mut Self {
+        self.max_count_limit = limit;
+        self
+    }
+
+    /// Induce the shapes of all the classes instantiated in `graph`, sorted by class.
+    pub fn induce<G: Graph>(&self, graph: &G) -> GResult<G, Vec<NodeShape>> {
+        let mut types: HashMap<BoxTerm, BTreeSet<String>> = HashMap::new();
+        let mut instances: BTreeMap<String, (BoxTerm, Vec<BoxTerm>)> = BTreeMap::new();
+        for t in graph.triples_with_p(&rdf::type_) {
+            let t = t?;
+            let class = nt(t.o());
+            types
+                .entry(BoxTerm::copy(t.s()))
+                .or_default()
+                .insert(class.clone());
+            instances
+                .entry(class)
+                .or_insert_with(|| (BoxTerm::copy(t.o()), vec![]))
+                .1
+                .push(BoxTerm::copy(t.s()));
+        }
+
+        let class_terms: HashMap<&str, &BoxTerm> = instances
+            .iter()
+            .filter(|(_, (class, _))| class.kind() == TermKind::Iri)
+            .map(|(key, (class, _))| (key.as_str(), class))
+            .collect();
+
+        let mut names = HashSet::new();
+        let mut shapes = vec![];
+        for (class, members) in instances.values() {
+            let mut usages: BTreeMap<String, PropertyUsage> = BTreeMap::new();
+            for s in members {
+                let mut counts: HashMap<String, usize> = HashMap::new();
+                for t in graph.triples_with_s(s) {
+                    let t = t?;
+                    if term_eq(t.p(), &rdf::type_) {
+                        continue;
+                    }
+                    let p = nt(t.p());
+                    *counts.entry(p.clone()).or_insert(0) += 1;
+                    usages
+                        .entry(p)
+                        .or_insert_with(|| PropertyUsage::new(t.p()))
+                        .add_value(t.o(), &types);
+                }
+                for (p, n) in counts {
+                    let usage = usages.get_mut(&p).unwrap();
+                    usage.support += 1;
+                    usage.max = usage.max.max(n);
+                }
+            }
+
+            let id = self.mint(class, &mut names);
+            let mut properties = vec![];
+            for usage in usages.into_values() {
+                if (usage.support as f64) < self.min_support * members.len() as f64 {
+                    continue;
+                }
+                let ps_id = self.mint(&usage.path, &mut names);
+                properties.push(self.property_shape(ps_id, usage, members.len(), &class_terms));
+            }
+            shapes.push(NodeShape {
+                id,
+                target_class: class.clone(),
+                instances: members.len(),
+                properties,
+            });
+        }
+        Ok(shapes)
+    }
+
+    fn property_shape(
+        &self,
+        id: BoxTerm,
+        usage: PropertyUsage,
+        instances: usize,
+        class_terms: &HashMap<&str, &BoxTerm>,
+    ) -> PropertyShape {
+        let datatype = match (usage.kinds.as_slice(), usage.datatypes.len()) {
+            ([TermKind::Literal], 1) => usage.datatypes.into_values().next(),
+            _ => None,
+        };
+        let class = match usage.classes {
+            Some(classes) if !usage.kinds.contains(&TermKind::Literal) => classes
+                .iter()
+                .find_map(|c| class_terms.get(c.as_str()))
+                .map(|c| (*c).clone()),
+            _ => None,
+        };
+        let node_kind = if datatype.is_some() || class.is_some() {
+            None
+        } else {
+            let kinds = &usage.kinds;
+            let has = |kind| kinds.contains(&kind);
+            match (
+                has(TermKind::Iri),
+                has(TermKind::BlankNode),
+                has(TermKind::Literal),
+            ) {
+                (true, false, false) => Some(&sh::IRI),
+                (false, true, false) => Some(&sh::BlankNode),
+                (false, false, true) => Some(&sh::Literal),
+                (true, true, false) => Some(&sh::BlankNodeOrIRI),
+                (true, false, true) => Some(&sh::IRIOrLiteral),
+                (false, true, true) => Some(&sh::BlankNodeOrLiteral),
+                _ => None,
+            }
+            .map(BoxTerm::copy)
+        };
+        PropertyShape {
+            id,
+            path: usage.path,
+            support: usage.support,
+            min_count: if usage.support == instances {
+                Some(1)
+            } else {
+                None
+            },
+            max_count: if usage.max <= self.max_count_limit {
+                Some(usage.max)
+            } else {
+                None
+            },
+            datatype,
+            class,
+            node_kind,
+        }
+    }
+
+    /// Mint a new shape IRI, based on the local name of `term`.
+    fn mint(&self, term: &BoxTerm, names: &mut HashSet<String>) -> BoxTerm {
+        let iri = term.value();
+        let local = iri.rsplit(['#', '/', ':']).next().unwrap_or("");
+        let mut name: String = local
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        if name.is_empty() {
+            name.push_str("shape");
+        }
+        let mut candidate = name.clone();
+        let mut i = 1;
+        while !names.insert(candidate.clone()) {
+            i += 1;
+            candidate = format!("{}_{}", name, i);
+        }
+        BoxTerm::new_iri(format!("{}{}", self.base, candidate))
+            .expect("base is a valid IRI and local names are sanitized")
+    }
+}
+
+/// What is known about the values of a property, for the instances of a class.
+struct PropertyUsage {
+    path: BoxTerm,
+    support: usize,
+    max: usize,
+    kinds: Vec<TermKind>,
+    datatypes: BTreeMap<String, BoxTerm>,
+    /// The classes common to all the (non-literal) values, `None` before the first value.
+    classes: Option<BTreeSet<String>>,
+}
+
+impl PropertyUsage {
+    fn new<T: TTerm + ?Sized>(path: &T) -> Self {
+        PropertyUsage {
+            path: BoxTerm::copy(path),
+            support: 0,
+            max: 0,
+            kinds: vec![],
+            datatypes: BTreeMap::new(),
+            classes: None,
+        }
+    }
+
+    fn add_value<T>(&mut self, value: &T, types: &HashMap<BoxTerm, BTreeSet<String>>)
+    where
+        T: TTerm + ?Sized,
+    {
+        let kind = value.kind();
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+        if kind == TermKind::Literal {
+            if let Some(dt) = value.datatype() {
+                self.datatypes
+                    .entry(nt(&dt))
+                    .or_insert_with(|| BoxTerm::copy(&dt));
+            }
+            return;
+        }
+        let value_types = types
+            .get(&BoxTerm::copy(value))
+            .cloned()
+            .unwrap_or_default();
+        self.classes = Some(match self.classes.take() {
+            None => value_types,
+            Some(classes) => classes.intersection(&value_types).cloned().collect(),
+        });
+    }
+}
+
+impl NodeShape {
+    /// Write this shape, and its property shapes, into `graph`.
+    pub fn write<G: MutableGraph>(&self, graph: &mut G) -> MGResult<G, ()> {
+        let id = &self.id;
+        graph.insert(id, &rdf::type_, &sh::NodeShape)?;
+        graph.insert(id, &sh::targetClass, &self.target_class)?;
+        for ps in &self.properties {
+            graph.insert(id, &sh::property, &ps.id)?;
+            ps.write(graph)?;
+        }
+        Ok(())
+    }
+}
+
+impl PropertyShape {
+    /// Write this shape into `graph`.
+    pub fn write<G: MutableGraph>(&self, graph: &mut G) -> MGResult<G, ()> {
+        let id = &self.id;
+        graph.insert(id, &rdf::type_, &sh::PropertyShape)?;
+        graph.insert(id, &sh::path, &self.path)?;
+        if let Some(n) = self.min_count {
+            let n = BoxTerm::new_literal_dt_unchecked(n.to_string(), xsd::integer);
+            graph.insert(id, &sh::minCount, &n)?;
+        }
+        if let Some(n) = self.max_count {
+            let n = BoxTerm::new_literal_dt_unchecked(n.to_string(), xsd::integer);
+            graph.insert(id, &sh::maxCount, &n)?;
+        }
+        if let Some(dt) = &self.datatype {
+            graph.insert(id, &sh::datatype, dt)?;
+        }
+        if let Some(class) = &self.class {
+            graph.insert(id, &sh::class, class)?;
+        }
+        if let Some(kind) = &self.node_kind {
+            graph.insert(id, &sh::nodeKind, kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write all `shapes` into `graph`.
+pub fn write_shapes<G: MutableGraph>(shapes: &[NodeShape], graph: &mut G) -> MGResult<G, ()> {
+    for shape in shapes {
+        shape.write(graph)?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const DATA: &str = r#"
+        @prefix s: <http://schema.org/>.
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+        <tag:alice> a s:Person; s:name "Alice"; s:age 42;
+            s:knows <tag:bob>, <tag:carol>; s:address [ s:city "Paris" ].
+        <tag:bob> a s:Person, s:Agent; s:name "Bob"; s:age "unknown";
+            s:knows <tag:alice>.
+        <tag:carol> a s:Person, s:Agent; s:name "Carol"@en; s:worksFor <tag:acme>.
+        <tag:acme> a s:Organization; s:name "Acme"; s:url <http://acme.example/>.
+    "#;
+
+    fn induce(inducer: &ShapeInducer) -> Vec<NodeShape> {
+        let g: FastGraph = turtle::parse_str(DATA).collect_triples().unwrap();
+        inducer.induce(&g).unwrap()
+    }
+
+    fn property<'a>(shape: &'a NodeShape, name: &str) -> &'a PropertyShape {
+        shape
+            .properties
+            .iter()
+            .find(|p| p.path.value()[..] == format!("http://schema.org/{}", name))
+            .unwrap()
+    }
+
+    #[test]
+    fn shapes() {
+        let shapes = induce(&ShapeInducer::new("http://ex.org/shapes/").unwrap());
+        let ids: Vec<_> = shapes.iter().map(|s| s.id.value().to_string()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "http://ex.org/shapes/Agent",
+                "http://ex.org/shapes/Organization",
+                "http://ex.org/shapes/Person",
+            ]
+        );
+        let person = &shapes[2];
+        assert_eq!(person.instances, 3);
+        assert_eq!(person.properties.len(), 5);
+
+        let name = property(person, "name");
+        assert_eq!(
+            (name.support, name.min_count, name.max_count),
+            (3, Some(1), Some(1))
+        );
+        assert_eq!(name.datatype, None); // xsd:string and rdf:langString
+        assert_eq!(name.node_kind.as_ref().unwrap(), &sh::Literal);
+
+        let age = property(person, "age");
+        assert_eq!(age.min_count, None);
+        assert_eq!(age.datatype, None); // xsd:integer and xsd:string
+
+        let knows = property(person, "knows");
+        assert_eq!(knows.max_count, None);
+        assert_eq!(
+            knows.class.as_ref().unwrap(),
+            &BoxTerm::new_iri("http://schema.org/Person").unwrap()
+        );
+        assert_eq!(knows.node_kind, None);
+
+        let address = property(person, "address");
+        assert_eq!(address.class, None);
+        assert_eq!(address.node_kind.as_ref().unwrap(), &sh::BlankNode);
+
+        let org = &shapes[1];
+        let url = property(org, "url");
+        assert_eq!(url.node_kind.as_ref().unwrap(), &sh::IRI);
+        let name = property(org, "name");
+        assert_eq!(name.datatype.as_ref().unwrap(), &xsd::string);
+        // the name of property shapes are disambiguated
+        assert_eq!(name.id.value(), "http://ex.org/shapes/name_2");
+    }
+
+    #[test]
+    fn options() {
+        let mut inducer = ShapeInducer::new("http://ex.org/shapes#").unwrap();
+        inducer.set_min_support(0.5).set_max_count_limit(2);
+        let shapes = induce(&inducer);
+        let person = &shapes[2];
+        let paths: Vec<_> = person
+            .properties
+            .iter()
+            .map(|p| p.path.value().to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "http://schema.org/age",
+                "http://schema.org/knows",
+                "http://schema.org/name"
+            ]
+        );
+        assert_eq!(property(person, "knows").max_count, Some(2));
+        assert!(ShapeInducer::new("not an IRI").is_err());
+    }
+
+    #[test]
+    fn write() -> Result<(), Box<dyn std::error::Error>> {
+        let shapes = induce(&ShapeInducer::new("http://ex.org/shapes#")?);
+        let mut g = FastGraph::new();
+        write_shapes(&shapes, &mut g)?;
+        let person = BoxTerm::new_iri("http://ex.org/shapes#Person")?;
+        assert_eq!(g.triples_with_sp(&person, &sh::property).count(), 5);
+        assert_eq!(g.triples_with_po(&rdf::type_, &sh::NodeShape).count(), 3);
+        let name = BoxTerm::new_iri("http://ex.org/shapes#name")?;
+        let one = BoxTerm::new_literal_dt("1", xsd::integer)?;
+        assert!(g.contains(&name, &sh::minCount, &one)?);
+        assert!(g.contains(&name, &sh::maxCount, &one)?);
+        assert!(g.contains(&name, &sh::nodeKind, &sh::Literal)?);
+        Ok(())
+    }
+}
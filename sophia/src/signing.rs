@@ -0,0 +1,811 @@
+//! Signature and verification of RDF datasets with
+//! [Data Integrity](https://www.w3.org/TR/vc-data-integrity/) proofs
+//! (requires the `signing` feature).
+//!
+//! [`sign`] produces a proof graph, describing a `sec:DataIntegrityProof`,
+//! for a dataset (or a graph, through [`Graph::as_dataset`]);
+//! [`verify`] checks such a proof against the dataset.
+//! The following cryptosuites are supported:
+//! * [`eddsa-rdfc-2022`](https://www.w3.org/TR/vc-di-eddsa/) (Ed25519),
+//! * [`ecdsa-rdfc-2019`](https://www.w3.org/TR/vc-di-ecdsa/) (ECDSA with the P-256 curve).
+//!
+//! Both cryptosuites rely on [`canonicalize`],
+//! which implements [RDF Dataset Canonicalization (RDFC-1.0)](https://www.w3.org/TR/rdf-canon/)
+//! with SHA-256.
+//! Note that no limit is imposed on the complexity of the canonicalization,
+//! so datasets from untrusted sources with many indistinguishable blank nodes
+//! may take a very long time to process.
+//!
+//! Resolving the verification method of a proof to a public key is out of the scope of this module:
+//! [`verify`] expects the raw bytes of the public key
+//! (32 bytes for Ed25519, an uncompressed SEC1 point for P-256).
+//!
+//! # Example
+//! ```
+//! use sophia::signing::{sign, verify, ProofOptions, SigningKey};
+//! use sophia::graph::Graph;
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let doc: Vec<[sophia::term::BoxTerm; 3]> =
+//!     turtle::parse_str("<tag:alice> <tag:knows> [ <tag:name> \"Bob\" ].").collect_triples()?;
+//! let key = SigningKey::ed25519_from_seed(&[42; 32])?;
+//! let options = ProofOptions::new("did:example:alice#key-1");
+//! let proof = sign(&doc.as_dataset(), &key, &options)?;
+//! assert!(verify(&doc.as_dataset(), &proof, key.public_key())?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`sign`]: fn.sign.html
+//! [`verify`]: fn.verify.html
+//! [`canonicalize`]: fn.canonicalize.html
+//! [`Graph::as_dataset`]: ../graph/trait.Graph.html#method.as_dataset
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, UnparsedPublicKey, VerificationAlgorithm,
+    ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, ED25519,
+};
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::Graph;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, SimpleIri, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::ns::{rdf, xsd};
+use crate::serializer::nt::write_term;
+
+/// The Security vocabulary, used to describe proofs.
+#[allow(missing_docs)]
+pub mod sec {
+    use sophia_api::namespace;
+    namespace!(
+        "https://w3id.org/security#",
+        DataIntegrityProof,
+        assertionMethod,
+        cryptosuite,
+        cryptosuiteString,
+        multibase,
+        proof,
+        proofPurpose,
+        proofValue,
+        verificationMethod
+    );
+}
+
+/// The IRI of `dcterms:created`.
+const DCTERMS_CREATED: &str = "http://purl.org/dc/terms/created";
+
+/// This error is raised when signing or verifying a dataset fails.
+#[derive(Debug, Error)]
+pub enum SigningError {
+    /// The dataset or graph raised an error.
+    #[error("Error in source: {0}")]
+    Source(String),
+    /// The key is not valid for the requested algorithm.
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    /// The proof graph is not a valid proof.
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
+    /// The underlying cryptographic library failed.
+    #[error("Cryptographic error")]
+    Crypto,
+}
+
+/// A Data Integrity cryptosuite.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Cryptosuite {
+    /// `eddsa-rdfc-2022`
+    EddsaRdfc2022,
+    /// `ecdsa-rdfc-2019` (with the P-256 curve)
+    EcdsaRdfc2019,
+}
+
+impl Cryptosuite {
+    /// The name of this cryptosuite, as used in proofs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cryptosuite::EddsaRdfc2022 => "eddsa-rdfc-2022",
+            Cryptosuite::EcdsaRdfc2019 => "ecdsa-rdfc-2019",
+        }
+    }
+
+    /// The cryptosuite with the given name, if supported.
+    pub fn from_name(name: &str) -> Option<Cryptosuite> {
+        match name {
+            "eddsa-rdfc-2022" => Some(Cryptosuite::EddsaRdfc2022),
+            "ecdsa-rdfc-2019" => Some(Cryptosuite::EcdsaRdfc2019),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Cryptosuite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A private key, used to [`sign`](fn.sign.html) datasets.
+pub struct SigningKey(KeyImpl);
+
+enum KeyImpl {
+    Ed25519(Ed25519KeyPair),
+    EcdsaP256(EcdsaKeyPair),
+}
+
+impl SigningKey {
+    /// An Ed25519 key (for `eddsa-rdfc-2022`), built from its 32-byte seed.
+    pub fn ed25519_from_seed(seed: &[u8]) -> Result<Self, SigningError> {
+        Ed25519KeyPair::from_seed_unchecked(seed)
+            .map(|pair| SigningKey(KeyImpl::Ed25519(pair)))
+            .map_err(|err| SigningError::InvalidKey(err.to_string()))
+    }
+
+    /// An ECDSA P-256 key (for `ecdsa-rdfc-2019`), built from its PKCS#8 (v1) document.
+    pub fn ecdsa_p256_from_pkcs8(pkcs8: &[u8]) -> Result<Self, SigningError> {
+        EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            pkcs8,
+            &SystemRandom::new(),
+        )
+        .map(|pair| SigningKey(KeyImpl::EcdsaP256(pair)))
+        .map_err(|err| SigningError::InvalidKey(err.to_string()))
+    }
+
+    /// The cryptosuite used by this key.
+    pub fn cryptosuite(&self) -> Cryptosuite {
+        match &self.0 {
+            KeyImpl::Ed25519(_) => Cryptosuite::EddsaRdfc2022,
+            KeyImpl::EcdsaP256(_) => Cryptosuite::EcdsaRdfc2019,
+        }
+    }
+
+    /// The raw bytes of the public key corresponding to this key.
+    pub fn public_key(&self) -> &[u8] {
+        match &self.0 {
+            KeyImpl::Ed25519(pair) => pair.public_key().as_ref(),
+            KeyImpl::EcdsaP256(pair) => pair.public_key().as_ref(),
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SigningError> {
+        match &self.0 {
+            KeyImpl::Ed25519(pair) => Ok(pair.sign(data).as_ref().to_vec()),
+            KeyImpl::EcdsaP256(pair) => pair
+                .sign(&SystemRandom::new(), data)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|_| SigningError::Crypto),
+        }
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SigningKey({})", self.cryptosuite())
+    }
+}
+
+/// The options of the proofs produced by [`sign`](fn.sign.html).
+#[derive(Clone, Debug)]
+pub struct ProofOptions {
+    verification_method: String,
+    proof_purpose: String,
+    created: Option<String>,
+}
+
+impl ProofOptions {
+    /// Options for a proof whose public key is identified by the IRI `verification_method`,
+    /// with the purpose `sec:assertionMethod`, and without creation date.
+    pub fn new<T: Into<String>>(verification_method: T) -> Self {
+        ProofOptions {
+            verification_method: verification_method.into(),
+            proof_purpose: sec::assertionMethod.value().to_string(),
+            created: None,
+        }
+    }
+
+    /// The IRI identifying the public key.
+    pub fn verification_method(&self) -> &str {
+        &self.verification_method
+    }
+
+    /// The IRI of the purpose of the proof.
+    pub fn proof_purpose(&self) -> &str {
+        &self.proof_purpose
+    }
+
+    /// Set the IRI of the purpose of the proof.
+    pub fn set_proof_purpose<T: Into<String>>(&mut self, proof_purpose: T) -> &mut Self {
+        self.proof_purpose = proof_purpose.into();
+        self
+    }
+
+    /// The creation date of the proof, as an `xsd:dateTime` lexical form.
+    pub fn created(&self) -> Option<&str> {
+        self.created.as_deref()
+    }
+
+    /// Set the creation date of the proof, as an `xsd:dateTime` lexical form.
+    pub fn set_created(&mut self, created: Option<String>) -> &mut Self {
+        self.created = created;
+        self
+    }
+}
+
+/// Sign `dataset` with `key`, and return the corresponding proof graph.
+///
+/// The proof graph describes a single blank node of type `sec:DataIntegrityProof`.
+pub fn sign<D>(
+    dataset: &D,
+    key: &SigningKey,
+    options: &ProofOptions,
+) -> Result<Vec<[BoxTerm; 3]>, SigningError>
+where
+    D: Dataset + ?Sized,
+{
+    let iri = |iri: &str| {
+        BoxTerm::new_iri(iri).map_err(|err| SigningError::InvalidProof(err.to_string()))
+    };
+    let node = BoxTerm::new_bnode_unchecked("proof");
+    let triple = |p: BoxTerm, o: BoxTerm| [node.clone(), p, o];
+    let mut proof = vec![
+        triple(
+            BoxTerm::copy(&rdf::type_),
+            BoxTerm::copy(&sec::DataIntegrityProof),
+        ),
+        triple(
+            BoxTerm::copy(&sec::cryptosuite),
+            BoxTerm::new_literal_dt_unchecked(key.cryptosuite().name(), sec::cryptosuiteString),
+        ),
+        triple(
+            BoxTerm::copy(&sec::verificationMethod),
+            iri(&options.verification_method)?,
+        ),
+        triple(
+            BoxTerm::copy(&sec::proofPurpose),
+            iri(&options.proof_purpose)?,
+        ),
+    ];
+    if let Some(created) = &options.created {
+        proof.push(triple(
+            iri(DCTERMS_CREATED)?,
+            BoxTerm::new_literal_dt_unchecked(created.as_str(), xsd::dateTime),
+        ));
+    }
+    let data = hash_data(&proof, &collect(dataset)?);
+    let value = format!("z{}", base58_encode(&key.sign(&data)?));
+    proof.push(triple(
+        BoxTerm::copy(&sec::proofValue),
+        BoxTerm::new_literal_dt_unchecked(value, sec::multibase),
+    ));
+    Ok(proof)
+}
+
+/// Check that `proof` is a valid proof for `dataset`, given the raw bytes of the `public_key`.
+///
+/// Return `Ok(false)` if the signature does not match,
+/// and an error if `proof` is not a valid proof graph.
+pub fn verify<D, G>(dataset: &D, proof: &G, public_key: &[u8]) -> Result<bool, SigningError>
+where
+    D: Dataset + ?Sized,
+    G: Graph + ?Sized,
+{
+    let invalid = |msg: &str| SigningError::InvalidProof(msg.to_string());
+    let source = |err: G::Error| SigningError::Source(err.to_string());
+    let mut nodes = proof.triples_with_po(&rdf::type_, &sec::DataIntegrityProof);
+    let node = match (nodes.next(), nodes.next()) {
+        (Some(t), None) => BoxTerm::copy(t.map_err(source)?.s()),
+        _ => return Err(invalid("expected exactly one sec:DataIntegrityProof")),
+    };
+    let value_of = |pred: &SimpleIri| -> Result<String, SigningError> {
+        let mut values = proof.triples_with_sp(&node, pred);
+        match (values.next(), values.next()) {
+            (Some(t), None) => Ok(t.map_err(source)?.o().value().to_string()),
+            _ => Err(SigningError::InvalidProof(format!(
+                "expected exactly one {}",
+                pred.value()
+            ))),
+        }
+    };
+    let suite_name = value_of(&sec::cryptosuite)?;
+    let suite = Cryptosuite::from_name(&suite_name).ok_or_else(|| {
+        SigningError::InvalidProof(format!("unsupported cryptosuite {}", suite_name))
+    })?;
+    let value = value_of(&sec::proofValue)?;
+    let signature = value
+        .strip_prefix('z')
+        .and_then(base58_decode)
+        .ok_or_else(|| invalid("proof value is not base58-btc multibase"))?;
+    let config = proof
+        .triples()
+        .filter(|t| {
+            t.as_ref().map_or(true, |t| {
+                !sophia_api::term::term_eq(t.p(), &sec::proofValue)
+            })
+        })
+        .map(|t| {
+            t.map(|t| {
+                [
+                    BoxTerm::copy(t.s()),
+                    BoxTerm::copy(t.p()),
+                    BoxTerm::copy(t.o()),
+                ]
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(source)?;
+    let data = hash_data(&config, &collect(dataset)?);
+    let algorithm: &dyn VerificationAlgorithm = match suite {
+        Cryptosuite::EddsaRdfc2022 => &ED25519,
+        Cryptosuite::EcdsaRdfc2019 => &ECDSA_P256_SHA256_FIXED,
+    };
+    Ok(UnparsedPublicKey::new(algorithm, public_key)
+        .verify(&data, &signature)
+        .is_ok())
+}
+
+/// Serialize `dataset` in the canonical N-Quads form defined by
+/// [RDFC-1.0](https://www.w3.org/TR/rdf-canon/),
+/// where blank nodes are relabelled deterministically (`c14n0`, `c14n1`...).
+///
+/// Two isomorphic datasets always have the same canonical form.
+pub fn canonicalize<D>(dataset: &D) -> Result<String, SigningError>
+where
+    D: Dataset + ?Sized,
+{
+    Ok(Canonicalizer::new(&collect(dataset)?).run())
+}
+
+type CQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+fn collect<D: Dataset + ?Sized>(dataset: &D) -> Result<Vec<CQuad>, SigningError> {
+    dataset
+        .quads()
+        .map(|q| {
+            q.map(|q| {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|err| SigningError::Source(err.to_string()))
+}
+
+/// The data to sign: the hash of the canonical proof configuration,
+/// followed by the hash of the canonical document.
+fn hash_data(proof_config: &[[BoxTerm; 3]], document: &[CQuad]) -> Vec<u8> {
+    let config = proof_config
+        .iter()
+        .map(|t| (t.clone(), None))
+        .collect::<Vec<_>>();
+    let mut data = digest(&SHA256, Canonicalizer::new(&config).run().as_bytes())
+        .as_ref()
+        .to_vec();
+    data.extend_from_slice(digest(&SHA256, Canonicalizer::new(document).run().as_bytes()).as_ref());
+    data
+}
+
+fn sha256_hex(data: &str) -> String {
+    digest(&SHA256, data.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn bnode_id(t: &BoxTerm) -> Option<&str> {
+    match t.kind() {
+        TermKind::BlankNode => Some(t.value_raw().0),
+        _ => None,
+    }
+}
+
+/// Serialize `t` in canonical N-Triples.
+fn term_string(t: &BoxTerm) -> String {
+    let mut txt = vec![];
+    // writing to a Vec can not fail
+    write_term(&mut txt, t).unwrap();
+    // write_term only produces valid UTF-8
+    String::from_utf8(txt).unwrap()
+}
+
+/// Serialize `quad` as canonical N-Quads, relabelling blank nodes with `label`.
+fn nquad<'a, F>(quad: &'a CQuad, label: F) -> String
+where
+    F: Fn(&'a str) -> String,
+{
+    let mut line = String::new();
+    for t in quad.0.iter().chain(quad.1.iter()) {
+        match bnode_id(t) {
+            Some(id) => {
+                line.push_str("_:");
+                line.push_str(&label(id));
+            }
+            None => line.push_str(&term_string(t)),
+        }
+        line.push(' ');
+    }
+    line.push_str(".\n");
+    line
+}
+
+/// The "identifier issuer" of RDFC-1.0.
+#[derive(Clone)]
+struct IdIssuer {
+    prefix: &'static str,
+    issued: HashMap<String, String>,
+    order: Vec<String>,
+}
+
+impl IdIssuer {
+    fn new(prefix: &'static str) -> Self {
+        IdIssuer {
+            prefix,
+            issued: HashMap::new(),
+            order: vec![],
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<&str> {
+        self.issued.get(id).map(String::as_str)
+    }
+
+    fn issue(&mut self, id: &str) -> String {
+        if let Some(issued) = self.issued.get(id) {
+            return issued.clone();
+        }
+        let issued = format!("{}{}", self.prefix, self.order.len());
+        self.issued.insert(id.to_string(), issued.clone());
+        self.order.push(id.to_string());
+        issued
+    }
+}
+
+/// The state of the RDFC-1.0 algorithm.
+struct Canonicalizer<'a> {
+    quads: &'a [CQuad],
+    mentions: HashMap<&'a str, Vec<&'a CQuad>>,
+    first_degree: HashMap<&'a str, String>,
+    canonical: IdIssuer,
+}
+
+impl<'a> Canonicalizer<'a> {
+    fn new(quads: &'a [CQuad]) -> Self {
+        let mut mentions: HashMap<_, Vec<&CQuad>> = HashMap::new();
+        for q in quads {
+            for t in q.0.iter().chain(q.1.iter()) {
+                if let Some(id) = bnode_id(t) {
+                    let list = mentions.entry(id).or_default();
+                    if !list.last().is_some_and(|last| std::ptr::eq(*last, q)) {
+                        list.push(q);
+                    }
+                }
+            }
+        }
+        Canonicalizer {
+            quads,
+            mentions,
+            first_degree: HashMap::new(),
+            canonical: IdIssuer::new("c14n"),
+        }
+    }
+
+    fn run(mut self) -> String {
+        let mut by_hash: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (&id, quads) in &self.mentions {
+            let mut lines = quads
+                .iter()
+                .map(|q| nquad(q, |b| if b == id { "a" } else { "z" }.to_string()))
+                .collect::<Vec<_>>();
+            lines.sort();
+            let hash = sha256_hex(&lines.concat());
+            self.first_degree.insert(id, hash.clone());
+            by_hash.entry(hash).or_default().push(id);
+        }
+        for ids in by_hash.values() {
+            if let [id] = ids[..] {
+                self.canonical.issue(id);
+            }
+        }
+        for ids in by_hash.values().filter(|ids| ids.len() > 1) {
+            let mut results = vec![];
+            for id in ids {
+                if self.canonical.get(id).is_some() {
+                    continue;
+                }
+                let mut issuer = IdIssuer::new("b");
+                issuer.issue(id);
+                results.push(self.hash_n_degree(id, &issuer));
+            }
+            results.sort_by(|r1, r2| r1.0.cmp(&r2.0));
+            for (_, issuer) in results {
+                for id in &issuer.order {
+                    self.canonical.issue(id);
+                }
+            }
+        }
+        let canonical = &self.canonical;
+        let mut lines = self
+            .quads
+            .iter()
+            .map(|q| nquad(q, |b| canonical.get(b).unwrap().to_string()))
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines.concat()
+    }
+
+    fn hash_related(
+        &self,
+        related: &str,
+        quad: &CQuad,
+        issuer: &IdIssuer,
+        position: &str,
+    ) -> String {
+        let mut input = position.to_string();
+        if position != "g" {
+            input.push_str(&term_string(&quad.0[1]));
+        }
+        match self.canonical.get(related).or_else(|| issuer.get(related)) {
+            Some(id) => {
+                input.push_str("_:");
+                input.push_str(id);
+            }
+            None => input.push_str(&self.first_degree[related]),
+        }
+        sha256_hex(&input)
+    }
+
+    fn hash_n_degree(&self, id: &str, issuer: &IdIssuer) -> (String, IdIssuer) {
+        let mut related_by_hash: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for quad in &self.mentions[id] {
+            let components = [(&quad.0[0], "s"), (&quad.0[2], "o")];
+            let graph = quad.1.as_ref().map(|g| (g, "g"));
+            for (t, position) in components.iter().copied().chain(graph) {
+                if let Some(related) = bnode_id(t).filter(|related| *related != id) {
+                    let hash = self.hash_related(related, quad, issuer, position);
+                    related_by_hash.entry(hash).or_default().push(related);
+                }
+            }
+        }
+        let mut data = String::new();
+        let mut issuer = issuer.clone();
+        for (hash, mut related) in related_by_hash {
+            data.push_str(&hash);
+            let mut chosen: Option<(String, IdIssuer)> = None;
+            for_each_permutation(&mut related, &mut |permutation| {
+                let mut issuer_copy = issuer.clone();
+                let mut path = String::new();
+                let mut recursion = vec![];
+                let worse = |path: &String| {
+                    chosen
+                        .as_ref()
+                        .is_some_and(|(chosen, _)| path.len() >= chosen.len() && path > chosen)
+                };
+                for related in permutation {
+                    path.push_str("_:");
+                    match self.canonical.get(related) {
+                        Some(canonical) => path.push_str(canonical),
+                        None => {
+                            if issuer_copy.get(related).is_none() {
+                                recursion.push(*related);
+                            }
+                            path.push_str(&issuer_copy.issue(related));
+                        }
+                    }
+                    if worse(&path) {
+                        return;
+                    }
+                }
+                for related in recursion {
+                    let (hash, result_issuer) = self.hash_n_degree(related, &issuer_copy);
+                    path.push_str("_:");
+                    path.push_str(&issuer_copy.issue(related));
+                    path.push('<');
+                    path.push_str(&hash);
+                    path.push('>');
+                    issuer_copy = result_issuer;
+                    if worse(&path) {
+                        return;
+                    }
+                }
+                if chosen.as_ref().is_none_or(|(chosen, _)| &path < chosen) {
+                    chosen = Some((path, issuer_copy));
+                }
+            });
+            // there is at least one permutation, which is chosen if no other was
+            let (path, chosen_issuer) = chosen.unwrap();
+            data.push_str(&path);
+            issuer = chosen_issuer;
+        }
+        (sha256_hex(&data), issuer)
+    }
+}
+
+/// Call `f` on every permutation of `items` (Heap's algorithm).
+fn for_each_permutation<T, F: FnMut(&[T])>(items: &mut [T], f: &mut F) {
+    fn generate<T, F: FnMut(&[T])>(k: usize, items: &mut [T], f: &mut F) {
+        if k <= 1 {
+            f(items);
+            return;
+        }
+        generate(k - 1, items, f);
+        for i in 0..k - 1 {
+            if k.is_multiple_of(2) {
+                items.swap(i, k - 1);
+            } else {
+                items.swap(0, k - 1);
+            }
+            generate(k - 1, items, f);
+        }
+    }
+    generate(items.len(), items, f)
+}
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58_encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|b| **b == 0).count();
+    // little-endian base-58 digits
+    let mut digits: Vec<u8> = vec![];
+    for byte in &data[zeros..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n(b'1', zeros)
+        .chain(digits.iter().rev().map(|d| BASE58_ALPHABET[*d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+fn base58_decode(txt: &str) -> Option<Vec<u8>> {
+    let zeros = txt.bytes().take_while(|b| *b == b'1').count();
+    // little-endian bytes
+    let mut bytes: Vec<u8> = vec![];
+    for chr in txt.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|c| *c == chr)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    Some(
+        std::iter::repeat_n(0, zeros)
+            .chain(bytes.into_iter().rev())
+            .collect(),
+    )
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+    use crate::quad::stream::QuadSource;
+
+    fn parse(nquads: &str) -> Vec<([BoxTerm; 3], Option<BoxTerm>)> {
+        nq::parse_str(nquads).collect_quads().unwrap()
+    }
+
+    #[test]
+    fn base58() {
+        assert_eq!(base58_encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(base58_encode(&[0, 0, 1]), "112");
+        for data in &[&b""[..], b"\0\0\xff\x10", b"Hello World!"] {
+            assert_eq!(base58_decode(&base58_encode(data)).unwrap(), *data);
+        }
+        assert!(base58_decode("0OIl").is_none());
+    }
+
+    #[test]
+    fn canonical_form() -> Result<(), Box<dyn std::error::Error>> {
+        let c1 = canonicalize(&parse(
+            "_:x <tag:p> \"v\" <tag:g>.\n<tag:s> <tag:p> _:x.\n_:y <tag:q> _:x.\n",
+        ))?;
+        assert_eq!(
+            c1,
+            "<tag:s> <tag:p> _:c14n1 .\n\
+             _:c14n0 <tag:q> _:c14n1 .\n\
+             _:c14n1 <tag:p> \"v\" <tag:g> .\n"
+        );
+        let c2 = canonicalize(&parse(
+            "_:b <tag:q> _:a.\n<tag:s> <tag:p> _:a.\n_:a <tag:p> \"v\" <tag:g>.\n",
+        ))?;
+        assert_eq!(c1, c2);
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_form_symmetric() -> Result<(), Box<dyn std::error::Error>> {
+        // all blank nodes have the same first degree hash
+        let c1 = canonicalize(&parse(
+            "_:a <tag:p> _:b.\n_:b <tag:p> _:c.\n_:c <tag:p> _:a.\n_:d <tag:p> _:e.\n_:e <tag:p> _:d.\n",
+        ))?;
+        let c2 = canonicalize(&parse(
+            "_:3 <tag:p> _:1.\n_:x <tag:p> _:y.\n_:2 <tag:p> _:3.\n_:y <tag:p> _:x.\n_:1 <tag:p> _:2.\n",
+        ))?;
+        assert_eq!(c1, c2);
+        for i in 0..5 {
+            assert!(c1.contains(&format!("_:c14n{} ", i)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sign_and_verify() -> Result<(), Box<dyn std::error::Error>> {
+        let doc = parse("<tag:s> <tag:p> _:x.\n_:x <tag:q> \"v\" <tag:g>.\n");
+        let renamed = parse("_:y <tag:q> \"v\" <tag:g>.\n<tag:s> <tag:p> _:y.\n");
+        let tampered = parse("<tag:s> <tag:p> _:x.\n_:x <tag:q> \"w\" <tag:g>.\n");
+
+        let key = SigningKey::ed25519_from_seed(&[7; 32])?;
+        let mut options = ProofOptions::new("tag:key");
+        options.set_created(Some("2021-01-01T00:00:00Z".into()));
+        let proof = sign(&doc, &key, &options)?;
+        assert_eq!(proof.len(), 6);
+        assert!(verify(&doc, &proof, key.public_key())?);
+        assert!(verify(&renamed, &proof, key.public_key())?);
+        assert!(!verify(&tampered, &proof, key.public_key())?);
+
+        let other = SigningKey::ed25519_from_seed(&[8; 32])?;
+        assert!(!verify(&doc, &proof, other.public_key())?);
+
+        let mut forged = proof.clone();
+        forged.retain(|t| !sophia_api::term::term_eq(&t[1], &sec::verificationMethod));
+        assert!(!verify(&doc, &forged, key.public_key())?);
+        forged.retain(|t| !sophia_api::term::term_eq(&t[1], &sec::proofValue));
+        assert!(matches!(
+            verify(&doc, &forged, key.public_key()),
+            Err(SigningError::InvalidProof(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn ecdsa() -> Result<(), Box<dyn std::error::Error>> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .map_err(|_| SigningError::Crypto)?;
+        let key = SigningKey::ecdsa_p256_from_pkcs8(pkcs8.as_ref())?;
+        assert_eq!(key.cryptosuite(), Cryptosuite::EcdsaRdfc2019);
+        let doc = parse("<tag:s> <tag:p> <tag:o>.\n");
+        let proof = sign(&doc, &key, &ProofOptions::new("tag:key"))?;
+        assert!(verify(&doc, &proof, key.public_key())?);
+        assert!(!verify(
+            &parse("<tag:s> <tag:p> <tag:x>.\n"),
+            &proof,
+            key.public_key()
+        )?);
+        assert!(SigningKey::ecdsa_p256_from_pkcs8(b"garbage").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+//! Utilities for [SKOS] thesauri, taxonomies and classification schemes.
+//!
+//! A [`Thesaurus`] wraps a graph containing SKOS data,
+//! and provides
+//! * access to concept schemes and their top concepts,
+//! * traversal of the `skos:broader`/`skos:narrower` hierarchy
+//!   (each relation being inferred from its inverse),
+//! * lookup of the preferred and alternative labels of a concept,
+//!   according to a [`LanguagePreference`],
+//!   and lookup of concepts by label,
+//! * detection of cycles in the hierarchy, which SKOS does not forbid,
+//!   but which most applications do not expect.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::skos::Thesaurus;
+//! use sophia::term::{literal::lang::LanguagePreference, BoxTerm, TTerm};
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix skos: <http://www.w3.org/2004/02/skos/core#>.
+//!     <tag:animals> skos:prefLabel "animals"@en, "animaux"@fr.
+//!     <tag:cats> skos:broader <tag:animals>;
+//!         skos:prefLabel "cats"@en, "chats"@fr;
+//!         skos:altLabel "felines"@en.
+//! "#).collect_triples()?;
+//!
+//! let thesaurus = Thesaurus::new(&g);
+//! let cats = BoxTerm::new_iri("tag:cats")?;
+//! let animals = BoxTerm::new_iri("tag:animals")?;
+//! assert_eq!(thesaurus.narrower(&animals)?, vec![cats.clone()]);
+//! assert_eq!(thesaurus.find_by_label("felines")?, vec![cats.clone()]);
+//! let fr = LanguagePreference::parse("fr");
+//! assert_eq!(&thesaurus.pref_label(&cats, &fr)?.unwrap().value()[..], "chats");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [SKOS]: https://www.w3.org/TR/skos-reference/
+//! [`Thesaurus`]: struct.Thesaurus.html
+//! [`LanguagePreference`]: ../term/literal/lang/struct.LanguagePreference.html
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::literal::lang::LanguagePreference;
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::ns::rdf;
+use crate::triple::Triple;
+
+/// The SKOS namespace.
+#[allow(missing_docs, clippy::module_inception)]
+pub mod skos {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/2004/02/skos/core#",
+        Collection,
+        Concept,
+        ConceptScheme,
+        altLabel,
+        broader,
+        broaderTransitive,
+        definition,
+        hasTopConcept,
+        hiddenLabel,
+        inScheme,
+        member,
+        narrower,
+        narrowerTransitive,
+        notation,
+        prefLabel,
+        related,
+        topConceptOf
+    );
+}
+
+/// A view of a graph as a SKOS thesaurus.
+///
+/// See [module documentation](index.html).
+pub struct Thesaurus<'g, G> {
+    graph: &'g G,
+}
+
+impl<'g, G: Graph> Thesaurus<'g, G> {
+    /// Wrap `graph` as a thesaurus.
+    pub fn new(graph: &'g G) -> Self {
+        Thesaurus { graph }
+    }
+
+    /// The concepts of this thesaurus,
+    /// i.e. the instances of `skos:Concept`
+    /// and the terms involved in a `skos:broader` or `skos:narrower` relation.
+    pub fn concepts(&self) -> GResult<G, Vec<BoxTerm>> {
+        let mut concepts = BTreeSet::new();
+        for t in self.graph.triples_with_po(&rdf::type_, &skos::Concept) {
+            concepts.insert(BoxTerm::copy(t?.s()));
+        }
+        for p in &[&skos::broader, &skos::narrower] {
+            for t in self.graph.triples_with_p(*p) {
+                let t = t?;
+                concepts.insert(BoxTerm::copy(t.s()));
+                concepts.insert(BoxTerm::copy(t.o()));
+            }
+        }
+        Ok(concepts.into_iter().collect())
+    }
+
+    /// The concept schemes of this thesaurus (instances of `skos:ConceptScheme`).
+    pub fn schemes(&self) -> GResult<G, Vec<BoxTerm>> {
+        let mut schemes = BTreeSet::new();
+        for t in self
+            .graph
+            .triples_with_po(&rdf::type_, &skos::ConceptScheme)
+        {
+            schemes.insert(BoxTerm::copy(t?.s()));
+        }
+        Ok(schemes.into_iter().collect())
+    }
+
+    /// The top concepts of `scheme`
+    /// (related to it by `skos:hasTopConcept` or `skos:topConceptOf`).
+    pub fn top_concepts<T: TTerm + ?Sized>(&self, scheme: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.related(scheme, &skos::hasTopConcept, &skos::topConceptOf)
+    }
+
+    /// The concepts declared `skos:inScheme` of `scheme`.
+    pub fn members<T: TTerm + ?Sized>(&self, scheme: &T) -> GResult<G, Vec<BoxTerm>> {
+        let mut members = BTreeSet::new();
+        for t in self.graph.triples_with_po(&skos::inScheme, scheme) {
+            members.insert(BoxTerm::copy(t?.s()));
+        }
+        Ok(members.into_iter().collect())
+    }
+
+    /// The direct broader concepts of `concept`.
+    pub fn broader<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.related(concept, &skos::broader, &skos::narrower)
+    }
+
+    /// The direct narrower concepts of `concept`.
+    pub fn narrower<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.related(concept, &skos::narrower, &skos::broader)
+    }
+
+    /// All the broader concepts of `concept` (transitively),
+    /// ordered by increasing distance.
+    ///
+    /// This terminates even if the hierarchy contains cycles.
+    pub fn ancestors<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.closure(concept, Self::broader)
+    }
+
+    /// All the narrower concepts of `concept` (transitively),
+    /// ordered by increasing distance.
+    ///
+    /// This terminates even if the hierarchy contains cycles.
+    pub fn descendants<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.closure(concept, Self::narrower)
+    }
+
+    /// The concepts of this thesaurus having no broader concept.
+    pub fn roots(&self) -> GResult<G, Vec<BoxTerm>> {
+        let mut roots = vec![];
+        for c in self.concepts()? {
+            if self.broader(&c)?.is_empty() {
+                roots.push(c);
+            }
+        }
+        Ok(roots)
+    }
+
+    /// The `skos:prefLabel` of `concept` best matching `preference`.
+    pub fn pref_label<T: TTerm + ?Sized>(
+        &self,
+        concept: &T,
+        preference: &LanguagePreference,
+    ) -> GResult<G, Option<BoxTerm>> {
+        let labels = self.objects(concept, &skos::prefLabel)?;
+        Ok(preference.select(&labels).cloned())
+    }
+
+    /// The `skos:altLabel` of `concept` best matching `preference`.
+    pub fn alt_label<T: TTerm + ?Sized>(
+        &self,
+        concept: &T,
+        preference: &LanguagePreference,
+    ) -> GResult<G, Option<BoxTerm>> {
+        let labels = self.objects(concept, &skos::altLabel)?;
+        Ok(preference.select(&labels).cloned())
+    }
+
+    /// All the `skos:prefLabel`s of `concept` (one per language, in principle).
+    pub fn pref_labels<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.objects(concept, &skos::prefLabel)
+    }
+
+    /// All the `skos:altLabel`s of `concept`.
+    pub fn alt_labels<T: TTerm + ?Sized>(&self, concept: &T) -> GResult<G, Vec<BoxTerm>> {
+        self.objects(concept, &skos::altLabel)
+    }
+
+    /// The concepts having `label` as their preferred, alternative or hidden label,
+    /// in any language.
+    ///
+    /// The comparison is case-insensitive.
+    pub fn find_by_label(&self, label: &str) -> GResult<G, Vec<BoxTerm>> {
+        let label = label.to_lowercase();
+        let mut found = BTreeSet::new();
+        for p in &[&skos::prefLabel, &skos::altLabel, &skos::hiddenLabel] {
+            for t in self.graph.triples_with_p(*p) {
+                let t = t?;
+                if t.o().value().to_lowercase() == label {
+                    found.insert(BoxTerm::copy(t.s()));
+                }
+            }
+        }
+        Ok(found.into_iter().collect())
+    }
+
+    /// The cycles of the broader/narrower hierarchy.
+    ///
+    /// Each cycle is returned as the sorted list of the concepts involved in it
+    /// (more precisely, the strongly connected components of the hierarchy);
+    /// a concept that is broader than itself forms a cycle on its own.
+    pub fn cycles(&self) -> GResult<G, Vec<Vec<BoxTerm>>> {
+        let mut broader = BTreeMap::new();
+        for c in self.concepts()? {
+            let b = self.broader(&c)?;
+            broader.insert(c, b);
+        }
+        let mut tarjan = Tarjan {
+            broader: &broader,
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: vec![],
+            cycles: vec![],
+        };
+        for c in broader.keys() {
+            if !tarjan.index.contains_key(c) {
+                tarjan.visit(c);
+            }
+        }
+        Ok(tarjan.cycles)
+    }
+
+    /// The terms related to `term` by `direct`, or by the inverse of `inverse`.
+    fn related<T, P, Q>(&self, term: &T, direct: &P, inverse: &Q) -> GResult<G, Vec<BoxTerm>>
+    where
+        T: TTerm + ?Sized,
+        P: TTerm + ?Sized,
+        Q: TTerm + ?Sized,
+    {
+        let mut related = BTreeSet::new();
+        for t in self.graph.triples_with_sp(term, direct) {
+            related.insert(BoxTerm::copy(t?.o()));
+        }
+        for t in self.graph.triples_with_po(inverse, term) {
+            related.insert(BoxTerm::copy(t?.s()));
+        }
+        Ok(related.into_iter().collect())
+    }
+
+    fn objects<T, P>(&self, s: &T, p: &P) -> GResult<G, Vec<BoxTerm>>
+    where
+        T: TTerm + ?Sized,
+        P: TTerm + ?Sized,
+    {
+        self.graph
+            .triples_with_sp(s, p)
+            .map(|t| t.map(|t| BoxTerm::copy(t.o())))
+            .collect()
+    }
+
+    fn closure<T, F>(&self, start: &T, step: F) -> GResult<G, Vec<BoxTerm>>
+    where
+        T: TTerm + ?Sized,
+        F: Fn(&Self, &BoxTerm) -> GResult<G, Vec<BoxTerm>>,
+    {
+        let start = BoxTerm::copy(start);
+        let mut seen = BTreeSet::new();
+        seen.insert(start.clone());
+        let mut result = vec![];
+        let mut frontier = vec![start];
+        while !frontier.is_empty() {
+            let mut next = vec![];
+            for c in &frontier {
+                for n in step(self, c)? {
+                    if seen.insert(n.clone()) {
+                        result.push(n.clone());
+                        next.push(n);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(result)
+    }
+}
+
+/// Tarjan's algorithm for strongly connected components.
+struct Tarjan<'a> {
+    broader: &'a BTreeMap<BoxTerm, Vec<BoxTerm>>,
+    index: BTreeMap<&'a BoxTerm, usize>,
+    low: BTreeMap<&'a BoxTerm, usize>,
+    stack: Vec<&'a BoxTerm>,
+    cycles: Vec<Vec<BoxTerm>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, node: &'a BoxTerm) {
+        let i = self.index.len();
+        self.index.insert(node, i);
+        self.low.insert(node, i);
+        self.stack.push(node);
+        let broader = self.broader;
+        for next in broader.get(node).into_iter().flatten() {
+            // concepts only appearing as broader have no entry, hence cannot be in a cycle
+            let next = match broader.get_key_value(next) {
+                Some((key, _)) => key,
+                None => continue,
+            };
+            if !self.index.contains_key(next) {
+                self.visit(next);
+                let low = self.low[node].min(self.low[next]);
+                self.low.insert(node, low);
+            } else if self.stack.contains(&next) {
+                let low = self.low[node].min(self.index[next]);
+                self.low.insert(node, low);
+            }
+        }
+        if self.low[node] == self.index[node] {
+            let mut component = vec![];
+            while let Some(n) = self.stack.pop() {
+                component.push(n.clone());
+                if n == node {
+                    break;
+                }
+            }
+            let self_loop = broader[node].contains(node);
+            if component.len() > 1 || self_loop {
+                component.sort();
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::MutableGraph;
+    use crate::test_util::t;
+
+    fn lang(txt: &str, tag: &str) -> BoxTerm {
+        BoxTerm::new_literal_lang(txt, tag).unwrap()
+    }
+
+    fn data() -> FastGraph {
+        let mut g = FastGraph::new();
+        g.insert(&t("scheme"), &rdf::type_, &skos::ConceptScheme)
+            .unwrap();
+        g.insert(&t("scheme"), &skos::hasTopConcept, &t("animal"))
+            .unwrap();
+        g.insert(&t("plant"), &skos::topConceptOf, &t("scheme"))
+            .unwrap();
+        g.insert(&t("animal"), &skos::narrower, &t("mammal"))
+            .unwrap();
+        g.insert(&t("mammal"), &skos::narrower, &t("cat")).unwrap();
+        g.insert(&t("cat"), &skos::broader, &t("mammal")).unwrap();
+        g.insert(&t("bird"), &skos::broader, &t("animal")).unwrap();
+        g.insert(&t("plant"), &rdf::type_, &skos::Concept).unwrap();
+        g.insert(&t("cat"), &skos::prefLabel, &lang("cat", "en"))
+            .unwrap();
+        g.insert(&t("cat"), &skos::prefLabel, &lang("chat", "fr"))
+            .unwrap();
+        g.insert(&t("cat"), &skos::altLabel, &lang("Minou", "fr"))
+            .unwrap();
+        g.insert(&t("cat"), &skos::altLabel, &lang("feline", "en"))
+            .unwrap();
+        g.insert(&t("cat"), &skos::hiddenLabel, &lang("kat", "en"))
+            .unwrap();
+        g
+    }
+
+    #[test]
+    fn hierarchy() -> Result<(), Box<dyn std::error::Error>> {
+        let g = data();
+        let th = Thesaurus::new(&g);
+        assert_eq!(th.schemes()?, vec![t("scheme")]);
+        assert_eq!(
+            th.top_concepts(&t("scheme"))?,
+            vec![t("animal"), t("plant")]
+        );
+        assert_eq!(th.concepts()?.len(), 5);
+        assert_eq!(th.roots()?, vec![t("animal"), t("plant")]);
+        assert_eq!(th.broader(&t("cat"))?, vec![t("mammal")]);
+        assert_eq!(th.narrower(&t("animal"))?, vec![t("bird"), t("mammal")]);
+        assert_eq!(th.ancestors(&t("cat"))?, vec![t("mammal"), t("animal")]);
+        assert_eq!(
+            th.descendants(&t("animal"))?,
+            vec![t("bird"), t("mammal"), t("cat")]
+        );
+        assert!(th.cycles()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn labels() -> Result<(), Box<dyn std::error::Error>> {
+        let g = data();
+        let th = Thesaurus::new(&g);
+        let fr = LanguagePreference::parse("fr-BE, en");
+        let de = LanguagePreference::parse("de");
+        assert_eq!(th.pref_label(&t("cat"), &fr)?, Some(lang("chat", "fr")));
+        assert_eq!(th.alt_label(&t("cat"), &fr)?, Some(lang("Minou", "fr")));
+        assert_eq!(th.pref_label(&t("cat"), &de)?, None);
+        assert_eq!(th.pref_labels(&t("cat"))?.len(), 2);
+        assert!(th.alt_labels(&t("dog"))?.is_empty());
+        assert_eq!(th.find_by_label("minou")?, vec![t("cat")]);
+        assert_eq!(th.find_by_label("kat")?, vec![t("cat")]);
+        assert!(th.find_by_label("dog")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn cycles() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = data();
+        g.insert(&t("animal"), &skos::broader, &t("cat"))?;
+        g.insert(&t("plant"), &skos::broader, &t("plant"))?;
+        let th = Thesaurus::new(&g);
+        assert_eq!(
+            th.cycles()?,
+            vec![vec![t("animal"), t("cat"), t("mammal")], vec![t("plant")]]
+        );
+        assert_eq!(th.ancestors(&t("cat"))?, vec![t("mammal"), t("animal")]);
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Identity consolidation ("smushing") of equivalent terms.
+//!
+//! When data from several sources are integrated,
+//! the same entity is often identified by several IRIs (or blank nodes),
+//! linked by `owl:sameAs` statements or by an external mapping.
+//! A [`Smusher`] groups such identifiers into equivalence classes,
+//! picks a canonical term for each class,
+//! and rewrites triples so that every identifier is replaced by its canonical term.
+//!
+//! The canonical term of a class is chosen among its [preferred](struct.Smusher.html#method.prefer) terms
+//! if it has any, or among all its terms otherwise.
+//! In both cases, IRIs are preferred over other kinds of terms,
+//! and the smallest term (in N-Triples) is picked,
+//! so that the result does not depend on the order in which links were added.
+//!
+//! Links that become trivial (`<x> owl:sameAs <x>`) are dropped by the rewriting.
+//! Optionally, the merged identifiers can be kept as provenance
+//! (see [`Smusher::set_provenance`]).
+//!
+//! # Example
+//! ```
+//! use sophia::graph::{inmem::FastGraph, Graph};
+//! use sophia::parser::turtle;
+//! use sophia::smush::Smusher;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix owl: <http://www.w3.org/2002/07/owl#>.
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:alice> owl:sameAs <tag:a>.
+//!     <tag:a> <tag:name> "Alice".
+//! "#).collect_triples()?;
+//!
+//! let mut smusher = Smusher::new();
+//! smusher.add_links(&g)?;
+//! let mut smushed = FastGraph::new();
+//! smusher.smush(g.triples(), &mut smushed)?;
+//! assert_eq!(smushed.triples().count(), 2);
+//! let a = BoxTerm::new_iri("tag:a")?;
+//! assert_eq!(smushed.triples_with_s(&a).count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Smusher`]: struct.Smusher.html
+//! [`Smusher::set_provenance`]: struct.Smusher.html#method.set_provenance
+
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::graph::{GResult, Graph, MutableGraph};
+use sophia_api::ns::owl;
+use sophia_api::term::matcher::ANY;
+use sophia_api::term::{term_eq, term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::{StreamError::SinkError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+
+/// Groups equivalent terms, and rewrites triples to use one canonical term per group.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Smusher {
+    class_of: HashMap<BoxTerm, usize>,
+    classes: Vec<Vec<BoxTerm>>,
+    preferred: HashSet<BoxTerm>,
+    provenance: Option<BoxTerm>,
+}
+
+impl Smusher {
+    /// Build a smusher with no equivalence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare that `a` and `b` identify the same entity.
+    pub fn add_same_as<T, U>(&mut self, a: &T, b: &U)
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        let ca = self.class(a);
+        let cb = self.class(b);
+        if ca == cb {
+            return;
+        }
+        // move the smaller class into the larger one
+        let (into, from) = if self.classes[ca].len() >= self.classes[cb].len() {
+            (ca, cb)
+        } else {
+            (cb, ca)
+        };
+        let moved = std::mem::take(&mut self.classes[from]);
+        for t in &moved {
+            self.class_of.insert(t.clone(), into);
+        }
+        self.classes[into].extend(moved);
+    }
+
+    /// Declare that `term` must be replaced by `canonical`.
+    ///
+    /// This is a shortcut for [`add_same_as`](#method.add_same_as)
+    /// followed by [`prefer`](#method.prefer)`(canonical)`.
+    pub fn add_mapping<T, U>(&mut self, term: &T, canonical: &U)
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        self.add_same_as(term, canonical);
+        self.prefer(canonical);
+    }
+
+    /// Declare the `owl:sameAs` links of `graph`,
+    /// and return their number.
+    pub fn add_links<G: Graph>(&mut self, graph: &G) -> GResult<G, usize> {
+        let mut n = 0;
+        for t in graph.triples_matching(&ANY, &owl::sameAs, &ANY) {
+            let t = t?;
+            self.add_same_as(t.s(), t.o());
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// Prefer `term` as the canonical term of its equivalence class.
+    pub fn prefer<T: TTerm + ?Sized>(&mut self, term: &T) {
+        self.class(term);
+        self.preferred.insert(BoxTerm::copy(term));
+    }
+
+    /// The predicate used to keep track of merged identifiers, if any.
+    pub fn provenance(&self) -> Option<&BoxTerm> {
+        self.provenance.as_ref()
+    }
+
+    /// Set the predicate used to keep track of merged identifiers.
+    ///
+    /// If set, [`smush`](#method.smush) adds a triple `<canonical> <predicate> <merged>`
+    /// for every merged identifier that occurred in the rewritten triples
+    /// (`owl:sameAs` is a natural choice of predicate).
+    /// By default, merged identifiers are not kept.
+    pub fn set_provenance<T: TTerm + ?Sized>(&mut self, predicate: Option<&T>) -> &mut Self {
+        self.provenance = predicate.map(BoxTerm::copy);
+        self
+    }
+
+    /// The canonical term replacing `term`,
+    /// or `None` if `term` is not equivalent to any other term.
+    pub fn canonical<T: TTerm + ?Sized>(&self, term: &T) -> Option<BoxTerm> {
+        let class = &self.classes[*self.class_of.get(&BoxTerm::copy(term))?];
+        if class.len() < 2 {
+            return None;
+        }
+        Some(self.pick(class).clone())
+    }
+
+    /// The equivalence classes with more than one term.
+    pub fn classes(&self) -> impl Iterator<Item = &[BoxTerm]> + '_ {
+        self.classes
+            .iter()
+            .filter(|c| c.len() > 1)
+            .map(Vec::as_slice)
+    }
+
+    /// Copy the triples of `source` into `target`,
+    /// replacing every term by its canonical term,
+    /// and return the number of triples inserted in `target`.
+    pub fn smush<TS, G>(
+        &self,
+        mut source: TS,
+        target: &mut G,
+    ) -> StreamResult<usize, TS::Error, G::MutationError>
+    where
+        TS: TripleSource,
+        G: MutableGraph,
+    {
+        let canonical = self.canonical_map();
+        let rewrite = |t: BoxTerm| canonical.get(&t).cloned().unwrap_or(t);
+        let mut merged = HashSet::new();
+        let mut n = 0;
+        source.try_for_each_triple(|t| {
+            let [s, p, o] = [t.s(), t.p(), t.o()].map(BoxTerm::copy);
+            if self.provenance.is_some() {
+                for term in [&s, &p, &o] {
+                    if canonical.contains_key(term) {
+                        merged.insert(term.clone());
+                    }
+                }
+            }
+            let [s, p, o] = [s, p, o].map(rewrite);
+            if s == o && term_eq(&p, &owl::sameAs) {
+                return Ok(());
+            }
+            if target.insert(&s, &p, &o)? {
+                n += 1;
+            }
+            Ok(())
+        })?;
+        if let Some(predicate) = &self.provenance {
+            for term in merged {
+                if target
+                    .insert(&canonical[&term], predicate, &term)
+                    .map_err(SinkError)?
+                {
+                    n += 1;
+                }
+            }
+        }
+        Ok(n)
+    }
+
+    /// The index of the class of `term`, creating a singleton class if needed.
+    fn class<T: TTerm + ?Sized>(&mut self, term: &T) -> usize {
+        let term = BoxTerm::copy(term);
+        if let Some(c) = self.class_of.get(&term) {
+            return *c;
+        }
+        self.classes.push(vec![term.clone()]);
+        self.class_of.insert(term, self.classes.len() - 1);
+        self.classes.len() - 1
+    }
+
+    /// The canonical term of `class`.
+    fn pick<'a>(&self, class: &'a [BoxTerm]) -> &'a BoxTerm {
+        let key = |t: &&BoxTerm| (t.kind() != TermKind::Iri, term_to_string(*t));
+        class
+            .iter()
+            .filter(|t| self.preferred.contains(*t))
+            .min_by_key(key)
+            .or_else(|| class.iter().min_by_key(key))
+            .unwrap()
+    }
+
+    /// A map from every non-canonical term to its canonical term.
+    fn canonical_map(&self) -> HashMap<BoxTerm, BoxTerm> {
+        let mut map = HashMap::new();
+        for class in self.classes() {
+            let canonical = self.pick(class);
+            for t in class {
+                if t != canonical {
+                    map.insert(t.clone(), canonical.clone());
+                }
+            }
+        }
+        map
+    }
+}
+
+// ---- tests ----
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::ns::{rdf, xsd};
+    use crate::test_util::t;
+
+    #[test]
+    fn classes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut smusher = Smusher::new();
+        smusher.add_same_as(&t("b"), &t("c"));
+        smusher.add_same_as(&t("d"), &t("e"));
+        smusher.add_same_as(&t("c"), &t("a"));
+        smusher.add_same_as(&t("a"), &t("b"));
+        assert_eq!(smusher.classes().count(), 2);
+        assert_eq!(smusher.canonical(&t("c")), Some(t("a")));
+        assert_eq!(smusher.canonical(&t("a")), Some(t("a")));
+        assert_eq!(smusher.canonical(&t("e")), Some(t("d")));
+        assert_eq!(smusher.canonical(&t("z")), None);
+
+        smusher.prefer(&t("b"));
+        assert_eq!(smusher.canonical(&t("c")), Some(t("b")));
+        smusher.prefer(&t("z"));
+        assert_eq!(smusher.canonical(&t("z")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn iris_before_bnodes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut smusher = Smusher::new();
+        smusher.add_same_as(&BoxTerm::new_bnode("a")?, &t("x"));
+        assert_eq!(smusher.canonical(&BoxTerm::new_bnode("a")?), Some(t("x")));
+        smusher.add_mapping(&t("x"), &t("y"));
+        assert_eq!(smusher.canonical(&t("x")), Some(t("y")));
+        Ok(())
+    }
+
+    #[test]
+    fn smush() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("alice"), &t("knows"), &t("bob"))?;
+        g.insert(&t("alice"), &owl::sameAs, &t("a"))?;
+        g.insert(&t("a"), &rdf::type_, &t("Person"))?;
+        g.insert(&t("b"), &owl::sameAs, &t("bob"))?;
+        g.insert(
+            &t("bob"),
+            &rdf::value,
+            &BoxTerm::new_literal_dt("bob", xsd::string)?,
+        )?;
+
+        let mut smusher = Smusher::new();
+        assert_eq!(smusher.add_links(&g)?, 2);
+        let mut h = FastGraph::new();
+        assert_eq!(smusher.smush(g.triples(), &mut h)?, 3);
+        assert!(h.contains(&t("a"), &t("knows"), &t("b"))?);
+        assert!(h.contains(&t("a"), &rdf::type_, &t("Person"))?);
+        assert_eq!(h.triples_with_s(&t("alice")).count(), 0);
+        assert_eq!(h.triples_with_p(&owl::sameAs).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn provenance() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&t("alice"), &t("knows"), &t("bob"))?;
+        g.insert(&t("alice"), &owl::sameAs, &t("a"))?;
+
+        let mut smusher = Smusher::new();
+        smusher.add_links(&g)?;
+        smusher.set_provenance(Some(&owl::sameAs));
+        let mut h = FastGraph::new();
+        assert_eq!(smusher.smush(g.triples(), &mut h)?, 2);
+        assert!(h.contains(&t("a"), &t("knows"), &t("bob"))?);
+        assert!(h.contains(&t("a"), &owl::sameAs, &t("alice"))?);
+        Ok(())
+    }
+}
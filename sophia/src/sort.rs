@@ -0,0 +1,464 @@
+//! Utilities for sorted N-Triples and N-Quads streams.
+//!
+//! Sorted, duplicate-free N-Triples/N-Quads files are a convenient canonical form for huge dumps:
+//! they can be compared, merged or joined by scanning them once,
+//! without ever loading them in memory.
+//!
+//! [`merge_sorted`] performs a k-way merge of such streams,
+//! eliminating duplicates across them,
+//! and writes the result as a single sorted stream.
+//!
+//! [`ExternalSorter`] produces such a stream from any triple or quad source,
+//! even if it does not fit in memory:
+//! statements are accumulated in memory up to a configurable budget,
+//! then sorted and spilled to a temporary file;
+//! all temporary files are finally merged with [`merge_sorted`].
+//!
+//! # Ordering
+//!
+//! Every statement is first normalized
+//! (using the formatting of Sophia's N-Quads serializer),
+//! so that insignificant differences (e.g. whitespace) do not prevent duplicate detection.
+//! Statements are then ordered by the byte order of their normalized form,
+//! which is also the order produced by `LC_ALL=C sort` on files produced by Sophia.
+//! Inputs that are not sorted in this order are reported as an error.
+//!
+//! Note that blank node labels are not renamed:
+//! a blank node with the same label in two different inputs is considered to be the same node.
+//!
+//! [`merge_sorted`]: fn.merge_sorted.html
+//! [`ExternalSorter`]: struct.ExternalSorter.html
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sophia_api::parser::ParseError;
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{QuadSerializer, Stringifier};
+use sophia_api::term::TTerm;
+use sophia_api::triple::stream::{StreamError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::nq;
+use crate::serializer::nq::{NqConfig, NqSerializer};
+use crate::serializer::nt::write_term;
+
+/// An error raised when manipulating sorted streams.
+#[derive(Debug, Error)]
+pub enum SortError {
+    /// An I/O error occurred while reading or writing a stream.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// A line of one of the inputs is not a valid N-Quads statement.
+    #[error("Parse error in input {input}, line {line}: {source}")]
+    Parse {
+        /// The index of the offending input.
+        input: usize,
+        /// The line number (starting at 1) in the offending input.
+        line: usize,
+        /// The underlying error.
+        source: Box<ParseError>,
+    },
+    /// One of the inputs is not sorted.
+    #[error("Input {input} is not sorted at line {line}")]
+    Unsorted {
+        /// The index of the offending input.
+        input: usize,
+        /// The line number (starting at 1) in the offending input.
+        line: usize,
+    },
+}
+
+/// Merge the sorted N-Triples or N-Quads `inputs` into `output`,
+/// eliminating duplicates.
+///
+/// Return the number of statements written.
+///
+/// See [module documentation](index.html) for the expected order.
+pub fn merge_sorted<R, W>(inputs: Vec<R>, mut output: W) -> Result<usize, SortError>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut runs = inputs
+        .into_iter()
+        .enumerate()
+        .map(|(index, input)| SortedRun::new(index, input))
+        .collect::<Vec<_>>();
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for run in runs.iter_mut() {
+        if let Some(statement) = run.next_statement()? {
+            heap.push(Reverse((statement, run.index)));
+        }
+    }
+    let mut count = 0;
+    let mut last: Option<String> = None;
+    while let Some(Reverse((statement, index))) = heap.pop() {
+        if let Some(next) = runs[index].next_statement()? {
+            heap.push(Reverse((next, index)));
+        }
+        if last.as_ref() != Some(&statement) {
+            output.write_all(statement.as_bytes())?;
+            count += 1;
+            last = Some(statement);
+        }
+    }
+    output.flush()?;
+    Ok(count)
+}
+
+/// A spill-to-disk sorter for triple and quad streams.
+///
+/// The output of [`sort_triples`] and [`sort_quads`] is sorted and duplicate-free,
+/// in the order described in the [module documentation](index.html).
+///
+/// The memory budget is approximate;
+/// it bounds the size of the statements held in memory before being spilled to disk.
+/// Note that all temporary files are opened simultaneously during the final merge,
+/// so the budget should be large enough to keep their number reasonable.
+///
+/// [`sort_triples`]: #method.sort_triples
+/// [`sort_quads`]: #method.sort_quads
+#[derive(Clone, Debug)]
+pub struct ExternalSorter {
+    memory_budget: usize,
+    temp_dir: PathBuf,
+}
+
+impl Default for ExternalSorter {
+    fn default() -> Self {
+        ExternalSorter {
+            memory_budget: 64 * 1024 * 1024,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+impl ExternalSorter {
+    /// A sorter with a memory budget of 64 MiB, using the system's temporary directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of bytes of statements held in memory before spilling them to disk.
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// Set the number of bytes of statements held in memory before spilling them to disk.
+    pub fn set_memory_budget(&mut self, memory_budget: usize) -> &mut Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// The directory where temporary files are created.
+    pub fn temp_dir(&self) -> &Path {
+        &self.temp_dir
+    }
+
+    /// Set the directory where temporary files are created.
+    pub fn set_temp_dir<P: Into<PathBuf>>(&mut self, temp_dir: P) -> &mut Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Write the triples of `source` to `output` as sorted N-Triples, eliminating duplicates.
+    ///
+    /// Return the number of triples written.
+    pub fn sort_triples<TS, W>(
+        &self,
+        mut source: TS,
+        output: W,
+    ) -> StreamResult<usize, TS::Error, SortError>
+    where
+        TS: TripleSource,
+        W: Write,
+    {
+        let mut runs = Runs::new(self);
+        source.try_for_each_triple(|t| runs.push(&[t.s(), t.p(), t.o()], None::<&BoxTerm>))?;
+        runs.finish(output).map_err(StreamError::SinkError)
+    }
+
+    /// Write the quads of `source` to `output` as sorted N-Quads, eliminating duplicates.
+    ///
+    /// Return the number of quads written.
+    pub fn sort_quads<QS, W>(
+        &self,
+        mut source: QS,
+        output: W,
+    ) -> StreamResult<usize, QS::Error, SortError>
+    where
+        QS: QuadSource,
+        W: Write,
+    {
+        let mut runs = Runs::new(self);
+        source.try_for_each_quad(|q| runs.push(&[q.s(), q.p(), q.o()], q.g()))?;
+        runs.finish(output).map_err(StreamError::SinkError)
+    }
+}
+
+/// The state of an external sort:
+/// the statements currently in memory, and the runs already spilled to disk.
+struct Runs<'a> {
+    sorter: &'a ExternalSorter,
+    buffer: Vec<String>,
+    used: usize,
+    files: Vec<PathBuf>,
+}
+
+impl<'a> Runs<'a> {
+    fn new(sorter: &'a ExternalSorter) -> Self {
+        Runs {
+            sorter,
+            buffer: vec![],
+            used: 0,
+            files: vec![],
+        }
+    }
+
+    fn push<T, U>(&mut self, spo: &[&T; 3], g: Option<&U>) -> Result<(), SortError>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        let mut line = vec![];
+        for t in spo {
+            write_term(&mut line, *t)?;
+            line.push(b' ');
+        }
+        match g {
+            Some(g) => write_term(&mut line, g)?,
+            None => {
+                line.pop();
+            }
+        }
+        line.extend_from_slice(b".\n");
+        // write_term only produces valid UTF-8
+        let line = String::from_utf8(line).unwrap();
+        self.used += line.len() + size_of::<String>();
+        self.buffer.push(line);
+        if self.used >= self.sorter.memory_budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Sort and deduplicate the statements in memory.
+    fn sort_buffer(&mut self) {
+        self.buffer.sort_unstable();
+        self.buffer.dedup();
+    }
+
+    fn spill(&mut self) -> Result<(), SortError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        self.sort_buffer();
+        let path = self.sorter.temp_dir.join(format!(
+            "sophia-sort-{}-{}.nq",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        self.files.push(path);
+        let mut file = BufWriter::new(file);
+        for line in self.buffer.drain(..) {
+            file.write_all(line.as_bytes())?;
+        }
+        file.flush()?;
+        self.used = 0;
+        Ok(())
+    }
+
+    fn finish<W: Write>(mut self, mut output: W) -> Result<usize, SortError> {
+        if self.files.is_empty() {
+            self.sort_buffer();
+            for line in &self.buffer {
+                output.write_all(line.as_bytes())?;
+            }
+            output.flush()?;
+            return Ok(self.buffer.len());
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+        let inputs = self
+            .files
+            .iter()
+            .map(|path| File::open(path).map(BufReader::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        merge_sorted(inputs, output)
+    }
+}
+
+impl Drop for Runs<'_> {
+    fn drop(&mut self) {
+        for path in &self.files {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Normalize an N-Quads `line`, including its final newline,
+/// or return `None` if it contains no statement (empty line or comment).
+pub(crate) fn normalize(line: &str) -> Result<Option<String>, Box<ParseError>> {
+    let mut config = NqConfig::default();
+    config.set_generalized(true);
+    let mut stringifier = NqSerializer::new_stringifier_with_config(config);
+    stringifier
+        .serialize_quads(nq::parse_str(line))
+        .map_err(|err| match err {
+            StreamError::SourceError(err) => Box::new(err),
+            // writing to a Vec in generalized mode can not fail
+            StreamError::SinkError(err) => Box::new(ParseError::new(err.to_string())),
+        })?;
+    match stringifier.as_str() {
+        "" => Ok(None),
+        normalized => Ok(Some(normalized.to_string())),
+    }
+}
+
+/// One of the inputs of a merge, checked for sortedness as it is read.
+struct SortedRun<R> {
+    index: usize,
+    lines: Lines<R>,
+    line: usize,
+    last: Option<String>,
+}
+
+impl<R: BufRead> SortedRun<R> {
+    fn new(index: usize, input: R) -> Self {
+        SortedRun {
+            index,
+            lines: input.lines(),
+            line: 0,
+            last: None,
+        }
+    }
+
+    fn next_statement(&mut self) -> Result<Option<String>, SortError> {
+        for line in &mut self.lines {
+            let line = line?;
+            self.line += 1;
+            let (input, line_number) = (self.index, self.line);
+            let normalized = normalize(&line).map_err(|source| SortError::Parse {
+                input,
+                line: line_number,
+                source,
+            })?;
+            if let Some(statement) = normalized {
+                if self.last.as_ref().is_some_and(|last| last > &statement) {
+                    return Err(SortError::Unsorted {
+                        input: self.index,
+                        line: self.line,
+                    });
+                }
+                self.last = Some(statement.clone());
+                return Ok(Some(statement));
+            }
+        }
+        Ok(None)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn merge(inputs: &[&str]) -> Result<(usize, String), SortError> {
+        let mut output = vec![];
+        let inputs = inputs.iter().map(|i| i.as_bytes()).collect();
+        let n = merge_sorted(inputs, &mut output)?;
+        Ok((n, String::from_utf8(output).unwrap()))
+    }
+
+    #[test]
+    fn merge_and_dedup() -> Result<(), Box<dyn std::error::Error>> {
+        let (n, output) = merge(&[
+            "<tag:a> <tag:p> <tag:x>.\n<tag:c> <tag:p> <tag:x>.\n",
+            "# comment\n<tag:a>  <tag:p>  <tag:x> .\n\n<tag:b> <tag:p> <tag:x>.\n",
+            "<tag:b> <tag:p> <tag:x> <tag:g>.\n<tag:b> <tag:p> <tag:x>.\n",
+            "",
+        ])?;
+        assert_eq!(n, 4);
+        assert_eq!(
+            output,
+            "<tag:a> <tag:p> <tag:x>.\n\
+             <tag:b> <tag:p> <tag:x> <tag:g>.\n\
+             <tag:b> <tag:p> <tag:x>.\n\
+             <tag:c> <tag:p> <tag:x>.\n"
+        );
+        assert_eq!(merge(&[])?.0, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn external_sort() -> Result<(), Box<dyn std::error::Error>> {
+        let data = "<tag:c> <tag:p> <tag:x>.\n\
+                    <tag:a> <tag:p> \"hello world\"@en <tag:g>.\n\
+                    <tag:b> <tag:p> _:b1.\n\
+                    <tag:c> <tag:p> <tag:x>.\n\
+                    <tag:a> <tag:p> <tag:x>.\n";
+        let expected = "<tag:a> <tag:p> \"hello world\"@en <tag:g>.\n\
+                        <tag:a> <tag:p> <tag:x>.\n\
+                        <tag:b> <tag:p> _:b1.\n\
+                        <tag:c> <tag:p> <tag:x>.\n";
+        let temp_dir =
+            std::env::temp_dir().join(format!("sophia-sort-test-{}", std::process::id()));
+        fs::create_dir_all(&temp_dir)?;
+        for budget in &[usize::MAX, 1, 100] {
+            let mut sorter = ExternalSorter::new();
+            sorter.set_memory_budget(*budget).set_temp_dir(&temp_dir);
+            let mut output = vec![];
+            let n = sorter.sort_quads(nq::parse_str(data), &mut output)?;
+            assert_eq!(n, 4);
+            assert_eq!(String::from_utf8(output)?, expected);
+            assert_eq!(fs::read_dir(&temp_dir)?.count(), 0);
+        }
+
+        let mut output = vec![];
+        let n = ExternalSorter::new().sort_triples(
+            crate::parser::nt::parse_str("<tag:b> <tag:p> <tag:x>.\n<tag:a> <tag:p> <tag:x>.\n"),
+            &mut output,
+        )?;
+        assert_eq!(n, 2);
+        assert_eq!(
+            String::from_utf8(output)?,
+            "<tag:a> <tag:p> <tag:x>.\n<tag:b> <tag:p> <tag:x>.\n"
+        );
+        fs::remove_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            merge(&[
+                "<tag:a> <tag:p> <tag:x>.\n",
+                "<tag:b> <tag:p> <tag:x>.\n<tag:a> <tag:p> <tag:x>.\n"
+            ]),
+            Err(SortError::Unsorted { input: 1, line: 2 })
+        ));
+        assert!(matches!(
+            merge(&["<tag:a> <tag:p> <tag:x>.\n<tag:b> <tag:p>.\n"]),
+            Err(SortError::Parse {
+                input: 0,
+                line: 2,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Materialization of SPARQL SELECT results into triples.
+//!
+//! [`SelectResults`] reads the results of a SPARQL SELECT query,
+//! in the [TSV or CSV] results format,
+//! and converts them into triples, either
+//! * from the `?s`, `?p` and `?o` variables ([`triples`]),
+//! * from user-specified variables ([`triples_with`]), or
+//! * by instantiating an arbitrary [`Template`] ([`construct`]).
+//!
+//! Solutions where one of the variables is unbound,
+//! or producing an invalid triple (e.g. with a literal as subject), are skipped.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::sparql_results::SelectResults;
+//! # use sophia::triple::stream::TripleSource;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let tsv = "?s\t?p\t?o\n\
+//!            <http://example.org/a>\t<http://example.org/p>\t\"foo\"@en\n\
+//!            <http://example.org/a>\t<http://example.org/q>\t42\n";
+//! let results = SelectResults::from_tsv(tsv.as_bytes())?;
+//! let g: FastGraph = results.triples()?.collect_triples()?;
+//! assert_eq!(g.triples().count(), 2);
+//! # Ok(()) }
+//! ```
+//!
+//! [TSV or CSV]: https://www.w3.org/TR/sparql11-results-csv-tsv/
+//! [`SelectResults`]: struct.SelectResults.html
+//! [`triples`]: struct.SelectResults.html#method.triples
+//! [`triples_with`]: struct.SelectResults.html#method.triples_with
+//! [`construct`]: struct.SelectResults.html#method.construct
+//! [`Template`]: ../query/struct.Template.html
+
+use std::convert::Infallible;
+use std::io::{self, BufRead};
+
+use sophia_api::ns::xsd;
+use sophia_api::parser::ParseError;
+use sophia_api::term::CopyTerm;
+use sophia_term::iri::is_absolute_iri_ref;
+use sophia_term::RcTerm;
+use thiserror::Error;
+
+use crate::parser::nq12::parse_term;
+use crate::query::{BindingMap, Template};
+use crate::triple::stream::TripleSource;
+use crate::triple::streaming_mode::ByValue;
+
+/// An error raised while reading SPARQL results.
+#[derive(Debug, Error)]
+pub enum ResultsError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid SPARQL results at line {line}: {error}")]
+    Syntax { line: usize, error: Box<ParseError> },
+    #[error("Unknown variable ?{0}")]
+    UnknownVariable(String),
+}
+
+/// The results of a SPARQL SELECT query.
+#[derive(Clone, Debug, Default)]
+pub struct SelectResults {
+    variables: Vec<String>,
+    solutions: Vec<BindingMap>,
+}
+
+impl SelectResults {
+    /// Read results in the [TSV](https://www.w3.org/TR/sparql11-results-csv-tsv/#tsv) format.
+    pub fn from_tsv<R: BufRead>(read: R) -> Result<SelectResults, ResultsError> {
+        let mut lines = read.lines();
+        let header = match lines.next() {
+            Some(header) => header?,
+            None => return Ok(SelectResults::default()),
+        };
+        let variables = header
+            .trim_end_matches('\r')
+            .split('\t')
+            .map(|v| v.trim_start_matches(['?', '$']).to_string())
+            .collect::<Vec<_>>();
+        let mut solutions = vec![];
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            let mut solution = BindingMap::new();
+            for (var, value) in variables.iter().zip(line.split('\t')) {
+                if !value.is_empty() {
+                    let term = tsv_term(value)
+                        .map_err(|error| ResultsError::Syntax { line: i + 2, error })?;
+                    solution.insert(var.clone(), term);
+                }
+            }
+            solutions.push(solution);
+        }
+        Ok(SelectResults {
+            variables,
+            solutions,
+        })
+    }
+
+    /// Read results in the [CSV](https://www.w3.org/TR/sparql11-results-csv-tsv/#csv) format.
+    ///
+    /// As this format does not distinguish IRIs from literals,
+    /// values of the form `_:label` are interpreted as blank nodes,
+    /// values that are valid absolute IRIs are interpreted as IRIs,
+    /// and any other value as a literal of type `xsd:string`.
+    pub fn from_csv<R: BufRead>(mut read: R) -> Result<SelectResults, ResultsError> {
+        let mut txt = String::new();
+        read.read_to_string(&mut txt)?;
+        let mut records = csv_records(&txt).into_iter();
+        let variables = match records.next() {
+            Some(header) => header,
+            None => return Ok(SelectResults::default()),
+        };
+        let solutions = records
+            .map(|record| {
+                variables
+                    .iter()
+                    .zip(record)
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(var, value)| (var.clone(), csv_term(value)))
+                    .collect()
+            })
+            .collect();
+        Ok(SelectResults {
+            variables,
+            solutions,
+        })
+    }
+
+    /// The variables of these results (without their leading `?`).
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// The solutions of these results.
+    pub fn solutions(&self) -> &[BindingMap] {
+        &self.solutions
+    }
+
+    /// The triples bound to the variables `?s`, `?p` and `?o`.
+    pub fn triples(
+        &self,
+    ) -> Result<
+        impl TripleSource<Error = Infallible, Triple = ByValue<[RcTerm; 3]>> + '_,
+        ResultsError,
+    > {
+        self.triples_with("s", "p", "o")
+    }
+
+    /// The triples bound to the given variables (without their leading `?`).
+    ///
+    /// Fails if one of the variables does not occur in these results.
+    pub fn triples_with(
+        &self,
+        s: &str,
+        p: &str,
+        o: &str,
+    ) -> Result<
+        impl TripleSource<Error = Infallible, Triple = ByValue<[RcTerm; 3]>> + '_,
+        ResultsError,
+    > {
+        let mut vars = vec![];
+        for v in [s, p, o].iter() {
+            if !self.variables.iter().any(|var| var == v) {
+                return Err(ResultsError::UnknownVariable(v.to_string()));
+            }
+            vars.push(
+                RcTerm::new_variable(*v)
+                    .map_err(|_| ResultsError::UnknownVariable(v.to_string()))?,
+            );
+        }
+        let template = Template::new(vec![[vars[0].clone(), vars[1].clone(), vars[2].clone()]]);
+        Ok(self
+            .solutions
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, b)| template.instantiate(b, i))
+            .map(Ok))
+    }
+
+    /// The triples obtained by instantiating `template` with each solution.
+    ///
+    /// See [`Template::construct`](../query/struct.Template.html#method.construct).
+    pub fn construct<'a>(
+        &'a self,
+        template: &'a Template,
+    ) -> impl TripleSource<Error = Infallible, Triple = ByValue<[RcTerm; 3]>> + 'a {
+        template.construct(self.solutions.iter().cloned().map(Ok))
+    }
+}
+
+/// Parse a term in the TSV results format,
+/// i.e. in N-Triples syntax, or a bare number or boolean.
+fn tsv_term(value: &str) -> Result<RcTerm, Box<ParseError>> {
+    let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let is_number = unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && value.parse::<f64>().is_ok();
+    let dt = if value == "true" || value == "false" {
+        Some(xsd::boolean)
+    } else if !is_number {
+        None
+    } else if unsigned.bytes().all(|b| b.is_ascii_digit()) {
+        Some(xsd::integer)
+    } else if value.contains(['e', 'E']) {
+        Some(xsd::double)
+    } else {
+        Some(xsd::decimal)
+    };
+    match dt {
+        Some(dt) => Ok(RcTerm::new_literal_dt_unchecked(value, dt)),
+        None => parse_term(value).map(|t| RcTerm::copy(&t)),
+    }
+}
+
+/// Interpret a value in the CSV results format.
+fn csv_term(value: String) -> RcTerm {
+    if let Some(label) = value.strip_prefix("_:") {
+        if let Ok(bnode) = RcTerm::new_bnode(label) {
+            return bnode;
+        }
+    }
+    if value.contains(':') && is_absolute_iri_ref(&value) {
+        if let Ok(iri) = RcTerm::new_iri(&value[..]) {
+            return iri;
+        }
+    }
+    RcTerm::new_literal_dt_unchecked(value, xsd::string)
+}
+
+/// Split `txt` into [RFC 4180](https://tools.ietf.org/html/rfc4180) records.
+fn csv_records(txt: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = txt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::graph::Graph;
+    use sophia_api::term::TTerm;
+    use sophia_term::StaticTerm;
+
+    #[test]
+    fn tsv() -> Result<(), Box<dyn std::error::Error>> {
+        let tsv = "?x\t?name\t?age\n\
+                   <tag:alice>\t\"Alice\"@en\t42\n\
+                   _:b\t\"Bob\\tB.\"\t\n";
+        let results = SelectResults::from_tsv(tsv.as_bytes())?;
+        assert_eq!(results.variables(), &["x", "name", "age"]);
+        assert_eq!(results.solutions().len(), 2);
+        assert_eq!(results.solutions()[1].len(), 2);
+        assert_eq!(&results.solutions()[1]["name"].value()[..], "Bob\tB.");
+        assert!(matches!(
+            results.triples(),
+            Err(ResultsError::UnknownVariable(v)) if v == "s"
+        ));
+
+        let name = StaticTerm::new_iri("tag:name")?;
+        let template = Template::new(vec![[
+            StaticTerm::new_variable("x")?,
+            name,
+            StaticTerm::new_variable("name")?,
+        ]]);
+        let g: FastGraph = results.construct(&template).collect_triples()?;
+        assert_eq!(g.triples().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn csv() -> Result<(), Box<dyn std::error::Error>> {
+        let csv = "s,p,o,other\r\n\
+                   http://example.org/a,http://example.org/p,\"hello, \"\"world\"\"\",x\r\n\
+                   _:b,http://example.org/p,http://example.org/c,\r\n\
+                   not an iri,http://example.org/p,c,\r\n";
+        let results = SelectResults::from_csv(csv.as_bytes())?;
+        assert_eq!(results.solutions().len(), 3);
+        let g: FastGraph = results.triples_with("s", "p", "o")?.collect_triples()?;
+        // the last solution has a literal as subject
+        assert_eq!(g.triples().count(), 2);
+        let hello = StaticTerm::new_literal_dt("hello, \"world\"", xsd::string)?;
+        assert!(g.contains(
+            &StaticTerm::new_iri("http://example.org/a")?,
+            &StaticTerm::new_iri("http://example.org/p")?,
+            &hello
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn tsv_error() {
+        let tsv = "?s\n<tag:a\n";
+        assert!(matches!(
+            SelectResults::from_tsv(tsv.as_bytes()),
+            Err(ResultsError::Syntax { line: 2, .. })
+        ));
+    }
+}
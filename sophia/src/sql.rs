@@ -0,0 +1,531 @@
+//! Mapping of datasets to a relational schema, for storage through an application-provided SQL connection.
+//!
+//! Applications with an existing relational database (SQLite, PostgreSQL...)
+//! can store RDF data in it without adopting a triple store.
+//! This module maps quads to a simple relational [`Schema`] with two tables:
+//! * a terms table, mapping numeric identifiers (`id`) to terms in N-Triples syntax (`term`);
+//! * a quads table, with a `subject`, a `predicate`, an `object` and a `graph` column,
+//!   containing term identifiers.
+//!
+//! Term identifiers start at 1; the identifier 0 in the `graph` column denotes the default graph
+//! (this allows the quads table to have a primary key, as a `NULL` column could not be part of it).
+//!
+//! This module does not depend on any database driver.
+//! Instead, applications implement the [`SqlConnection`] trait
+//! with the driver of their choice (e.g. `rusqlite` or `sqlx`),
+//! and then use [`create_tables`], [`store_quads`] and [`load_quads`].
+//! It is advisable to call [`store_quads`] inside a transaction,
+//! both for atomicity and performance.
+//!
+//! Alternatively, [`write_script`] dumps quads as a SQL script,
+//! which can be fed to the command-line client of the database (`sqlite3`, `psql`...).
+//!
+//! Only storing and loading whole datasets is supported:
+//! quads are not queried through SQL, and the statements are only written
+//! for the SQLite and PostgreSQL [`Dialect`]s.
+//!
+//! This module requires the `sql` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nq;
+//! use sophia::sql::{write_script, Dialect, Schema};
+//!
+//! let data = r#"
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:bob> <tag:name> "Bob" <tag:g>.
+//! "#;
+//! let schema = Schema::new(Dialect::Sqlite);
+//! let mut script = vec![];
+//! write_script(nq::parse_str(data), &schema, &mut script)?;
+//! let script = String::from_utf8(script)?;
+//! assert!(script.starts_with("CREATE TABLE IF NOT EXISTS rdf_terms"));
+//! assert!(script.contains(r#"INSERT INTO rdf_terms VALUES (5, '"Bob"');"#));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Schema`]: struct.Schema.html
+//! [`SqlConnection`]: trait.SqlConnection.html
+//! [`create_tables`]: fn.create_tables.html
+//! [`store_quads`]: fn.store_quads.html
+//! [`load_quads`]: fn.load_quads.html
+//! [`write_script`]: fn.write_script.html
+//! [`Dialect`]: enum.Dialect.html
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+
+use sophia_api::dataset::MutableDataset;
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::term::term_to_string;
+use sophia_api::triple::stream::{StreamError, StreamResult};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::parser::nq12;
+
+/// This error is raised when storing or loading quads fails.
+#[derive(Debug, Error)]
+pub enum SqlError<E: Error + 'static> {
+    /// The connection to the database failed.
+    #[error("SQL connection error: {0}")]
+    Connection(#[source] E),
+    /// The database contains data not complying with the [`Schema`](struct.Schema.html).
+    #[error("Invalid SQL data: {0}")]
+    Data(String),
+}
+
+/// The SQL dialect of a database, which determines the syntax of query parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    /// Parameters are written `?` (SQLite, MySQL).
+    Sqlite,
+    /// Parameters are written `$1`, `$2`... (PostgreSQL).
+    Postgres,
+}
+
+impl Dialect {
+    fn params(&self, n: usize) -> String {
+        let params: Vec<_> = (1..=n)
+            .map(|i| match self {
+                Dialect::Sqlite => "?".to_string(),
+                Dialect::Postgres => format!("${}", i),
+            })
+            .collect();
+        params.join(", ")
+    }
+}
+
+/// A value exchanged with a [`SqlConnection`](trait.SqlConnection.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SqlValue {
+    /// The SQL `NULL` value.
+    Null,
+    /// A 64-bit integer.
+    Integer(i64),
+    /// A character string.
+    Text(String),
+}
+
+/// A connection to a SQL database, to be implemented over a database driver.
+///
+/// See [module documentation](index.html).
+pub trait SqlConnection {
+    /// The error type raised by this connection.
+    type Error: Error + 'static;
+
+    /// Execute the statement `sql` with the given parameters.
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<(), Self::Error>;
+
+    /// Execute the query `sql` (which has no parameter), and pass each row of the result to `row`.
+    fn query(&mut self, sql: &str, row: &mut dyn FnMut(&[SqlValue])) -> Result<(), Self::Error>;
+}
+
+/// The tables in which quads are stored.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    dialect: Dialect,
+    terms_table: String,
+    quads_table: String,
+}
+
+impl Schema {
+    /// Build a schema for the given dialect,
+    /// with tables named `rdf_terms` and `rdf_quads`.
+    pub fn new(dialect: Dialect) -> Self {
+        Schema {
+            dialect,
+            terms_table: "rdf_terms".into(),
+            quads_table: "rdf_quads".into(),
+        }
+    }
+
+    /// The SQL dialect of this schema.
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// The name of the terms table.
+    pub fn terms_table(&self) -> &str {
+        &self.terms_table
+    }
+
+    /// The name of the quads table.
+    pub fn quads_table(&self) -> &str {
+        &self.quads_table
+    }
+
+    /// Set the name of the terms table.
+    pub fn set_terms_table<T: Into<String>>(&mut self, name: T) -> &mut Self {
+        self.terms_table = name.into();
+        self
+    }
+
+    /// Set the name of the quads table.
+    pub fn set_quads_table<T: Into<String>>(&mut self, name: T) -> &mut Self {
+        self.quads_table = name.into();
+        self
+    }
+
+    /// The statements creating the tables of this schema (if they do not exist).
+    pub fn create_statements(&self) -> Vec<String> {
+        vec![
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (id BIGINT PRIMARY KEY, term TEXT NOT NULL UNIQUE)",
+                self.terms_table
+            ),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (subject BIGINT NOT NULL, predicate BIGINT NOT NULL, object BIGINT NOT NULL, graph BIGINT NOT NULL, PRIMARY KEY (subject, predicate, object, graph))",
+                self.quads_table
+            ),
+        ]
+    }
+
+    fn insert_term(&self) -> String {
+        format!(
+            "INSERT INTO {} (id, term) VALUES ({})",
+            self.terms_table,
+            self.dialect.params(2)
+        )
+    }
+
+    fn insert_quad(&self) -> String {
+        format!(
+            "INSERT INTO {} (subject, predicate, object, graph) VALUES ({}) ON CONFLICT DO NOTHING",
+            self.quads_table,
+            self.dialect.params(4)
+        )
+    }
+
+    fn select_terms(&self) -> String {
+        format!("SELECT id, term FROM {}", self.terms_table)
+    }
+
+    fn select_quads(&self) -> String {
+        format!(
+            "SELECT s.term, p.term, o.term, g.term FROM {} q JOIN {t} s ON q.subject = s.id JOIN {t} p ON q.predicate = p.id JOIN {t} o ON q.object = o.id LEFT JOIN {t} g ON q.graph = g.id",
+            self.quads_table,
+            t = self.terms_table
+        )
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Schema::new(Dialect::Sqlite)
+    }
+}
+
+/// Create the tables of `schema` in the database, if they do not exist.
+pub fn create_tables<C: SqlConnection>(
+    connection: &mut C,
+    schema: &Schema,
+) -> Result<(), C::Error> {
+    for statement in schema.create_statements() {
+        connection.execute(&statement, &[])?;
+    }
+    Ok(())
+}
+
+/// Store the quads of `source` in the database,
+/// reusing the identifiers of the terms already stored,
+/// and return the number of quads read from `source`.
+///
+/// Quads already present in the database are ignored.
+pub fn store_quads<QS, C>(
+    mut source: QS,
+    connection: &mut C,
+    schema: &Schema,
+) -> StreamResult<usize, QS::Error, SqlError<C::Error>>
+where
+    QS: QuadSource,
+    C: SqlConnection,
+{
+    let mut ids = read_term_ids(connection, schema).map_err(StreamError::SinkError)?;
+    let mut next = ids.values().copied().max().unwrap_or(0) + 1;
+    let insert_term = schema.insert_term();
+    let insert_quad = schema.insert_quad();
+    let mut count = 0;
+    source.try_for_each_quad(|q| -> Result<(), SqlError<C::Error>> {
+        let mut id = |txt: String| -> Result<i64, SqlError<C::Error>> {
+            if let Some(id) = ids.get(&txt) {
+                return Ok(*id);
+            }
+            let params = [SqlValue::Integer(next), SqlValue::Text(txt.clone())];
+            connection
+                .execute(&insert_term, &params)
+                .map_err(SqlError::Connection)?;
+            ids.insert(txt, next);
+            next += 1;
+            Ok(next - 1)
+        };
+        let params = [
+            SqlValue::Integer(id(term_to_string(q.s()))?),
+            SqlValue::Integer(id(term_to_string(q.p()))?),
+            SqlValue::Integer(id(term_to_string(q.o()))?),
+            SqlValue::Integer(match q.g() {
+                Some(g) => id(term_to_string(g))?,
+                None => 0,
+            }),
+        ];
+        connection
+            .execute(&insert_quad, &params)
+            .map_err(SqlError::Connection)?;
+        count += 1;
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Load all the quads stored in the database into `dataset`,
+/// and return the number of quads actually inserted.
+pub fn load_quads<C, D>(
+    connection: &mut C,
+    schema: &Schema,
+    dataset: &mut D,
+) -> StreamResult<usize, SqlError<C::Error>, D::MutationError>
+where
+    C: SqlConnection,
+    D: MutableDataset,
+{
+    let mut rows = vec![];
+    connection
+        .query(&schema.select_quads(), &mut |row| rows.push(row.to_vec()))
+        .map_err(|e| StreamError::SourceError(SqlError::Connection(e)))?;
+    let mut count = 0;
+    for row in rows {
+        let terms = row
+            .iter()
+            .map(|value| match value {
+                SqlValue::Text(txt) => parse_term(txt).map(Some),
+                SqlValue::Null => Ok(None),
+                SqlValue::Integer(i) => Err(SqlError::Data(format!("unexpected integer {}", i))),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StreamError::SourceError)?;
+        match &terms[..] {
+            [Some(s), Some(p), Some(o), g] => {
+                if dataset
+                    .insert(s, p, o, g.as_ref())
+                    .map_err(StreamError::SinkError)?
+                {
+                    count += 1;
+                }
+            }
+            _ => {
+                return Err(StreamError::SourceError(SqlError::Data(format!(
+                    "unexpected row {:?}",
+                    row
+                ))))
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Write the quads of `source` as a SQL script,
+/// creating the tables of `schema` and populating them,
+/// and return the number of quads read from `source`.
+///
+/// NB: the tables are expected to be empty before the script is run,
+/// as term identifiers are allocated from 1.
+pub fn write_script<QS, W>(
+    mut source: QS,
+    schema: &Schema,
+    mut write: W,
+) -> StreamResult<usize, QS::Error, io::Error>
+where
+    QS: QuadSource,
+    W: Write,
+{
+    for statement in schema.create_statements() {
+        writeln!(write, "{};", statement).map_err(StreamError::SinkError)?;
+    }
+    let mut ids = HashMap::new();
+    let mut count = 0;
+    source.try_for_each_quad(|q| -> io::Result<()> {
+        let mut id = |txt: String| -> io::Result<usize> {
+            if let Some(id) = ids.get(&txt) {
+                return Ok(*id);
+            }
+            let id = ids.len() + 1;
+            writeln!(
+                write,
+                "INSERT INTO {} VALUES ({}, '{}');",
+                schema.terms_table,
+                id,
+                txt.replace('\'', "''")
+            )?;
+            ids.insert(txt, id);
+            Ok(id)
+        };
+        let s = id(term_to_string(q.s()))?;
+        let p = id(term_to_string(q.p()))?;
+        let o = id(term_to_string(q.o()))?;
+        let g = match q.g() {
+            Some(g) => id(term_to_string(g))?,
+            None => 0,
+        };
+        writeln!(
+            write,
+            "INSERT INTO {} VALUES ({}, {}, {}, {}) ON CONFLICT DO NOTHING;",
+            schema.quads_table, s, p, o, g
+        )?;
+        count += 1;
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+fn read_term_ids<C: SqlConnection>(
+    connection: &mut C,
+    schema: &Schema,
+) -> Result<HashMap<String, i64>, SqlError<C::Error>> {
+    let mut rows = vec![];
+    connection
+        .query(&schema.select_terms(), &mut |row| rows.push(row.to_vec()))
+        .map_err(SqlError::Connection)?;
+    rows.into_iter()
+        .map(|row| match &row[..] {
+            [SqlValue::Integer(id), SqlValue::Text(term)] => Ok((term.clone(), *id)),
+            _ => Err(SqlError::Data(format!("unexpected row {:?}", row))),
+        })
+        .collect()
+}
+
+fn parse_term<E: Error + 'static>(txt: &str) -> Result<BoxTerm, SqlError<E>> {
+    nq12::parse_term(txt).map_err(|e| SqlError::Data(format!("invalid term {}: {}", txt, e)))
+}
+
+// ---- tests ----
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::inmem::FastDataset;
+    use crate::parser::nq;
+    use crate::test_util::t;
+    use sophia_api::dataset::Dataset;
+    use std::convert::Infallible;
+
+    /// A fake connection, recording the rows inserted in each table,
+    /// and answering the queries of this module from them.
+    #[derive(Default)]
+    struct Fake {
+        terms: Vec<Vec<SqlValue>>,
+        quads: Vec<Vec<SqlValue>>,
+    }
+
+    impl SqlConnection for Fake {
+        type Error = Infallible;
+
+        fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<(), Infallible> {
+            if sql.starts_with("INSERT INTO rdf_terms") {
+                self.terms.push(params.to_vec());
+            } else if sql.starts_with("INSERT INTO rdf_quads")
+                && !self.quads.iter().any(|q| q == params)
+            {
+                self.quads.push(params.to_vec());
+            }
+            Ok(())
+        }
+
+        fn query(&mut self, sql: &str, row: &mut dyn FnMut(&[SqlValue])) -> Result<(), Infallible> {
+            if sql.starts_with("SELECT id, term") {
+                self.terms.iter().for_each(|t| row(t));
+            } else {
+                let term = |id: &SqlValue| {
+                    self.terms
+                        .iter()
+                        .find(|t| &t[0] == id)
+                        .map(|t| t[1].clone())
+                        .unwrap_or(SqlValue::Null)
+                };
+                for q in &self.quads {
+                    let values: Vec<_> = q.iter().map(term).collect();
+                    row(&values);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn statements() {
+        let mut schema = Schema::new(Dialect::Postgres);
+        schema.set_quads_table("q");
+        assert_eq!(
+            schema.insert_quad(),
+            "INSERT INTO q (subject, predicate, object, graph) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING"
+        );
+        assert_eq!(
+            Schema::default().insert_term(),
+            "INSERT INTO rdf_terms (id, term) VALUES (?, ?)"
+        );
+        assert_eq!(schema.create_statements().len(), 2);
+    }
+
+    #[test]
+    fn store_and_load() -> Result<(), Box<dyn std::error::Error>> {
+        let schema = Schema::default();
+        let mut connection = Fake::default();
+        create_tables(&mut connection, &schema)?;
+        let data = "<tag:a> <tag:p> <tag:b>.\n<tag:b> <tag:p> \"it's\"@en <tag:g>.\n";
+        assert_eq!(
+            store_quads(nq::parse_str(data), &mut connection, &schema)?,
+            2
+        );
+        assert_eq!(connection.terms.len(), 5);
+        assert_eq!(
+            connection.quads[0],
+            vec![
+                SqlValue::Integer(1),
+                SqlValue::Integer(2),
+                SqlValue::Integer(3),
+                SqlValue::Integer(0)
+            ]
+        );
+
+        // storing again reuses the identifiers
+        let data = "<tag:a> <tag:p> <tag:b>.\n<tag:a> <tag:p> <tag:c>.\n";
+        assert_eq!(
+            store_quads(nq::parse_str(data), &mut connection, &schema)?,
+            2
+        );
+        assert_eq!(connection.terms.len(), 6);
+        assert_eq!(connection.quads.len(), 3);
+
+        let mut d = FastDataset::new();
+        assert_eq!(load_quads(&mut connection, &schema, &mut d)?, 3);
+        assert!(d.contains(&t("a"), &t("p"), &t("c"), None as Option<&BoxTerm>)?);
+        let lit = BoxTerm::new_literal_lang("it's", "en")?;
+        assert!(d.contains(&t("b"), &t("p"), &lit, Some(&t("g")))?);
+        Ok(())
+    }
+
+    #[test]
+    fn script() -> Result<(), Box<dyn std::error::Error>> {
+        let data = "<tag:a> <tag:p> \"it's\".\n<tag:a> <tag:p> <tag:b> <tag:g>.\n";
+        let mut script = vec![];
+        write_script(nq::parse_str(data), &Schema::default(), &mut script)?;
+        let script = String::from_utf8(script)?;
+        let lines: Vec<_> = script.lines().skip(2).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "INSERT INTO rdf_terms VALUES (1, '<tag:a>');",
+                "INSERT INTO rdf_terms VALUES (2, '<tag:p>');",
+                "INSERT INTO rdf_terms VALUES (3, '\"it''s\"');",
+                "INSERT INTO rdf_quads VALUES (1, 2, 3, 0) ON CONFLICT DO NOTHING;",
+                "INSERT INTO rdf_terms VALUES (4, '<tag:b>');",
+                "INSERT INTO rdf_terms VALUES (5, '<tag:g>');",
+                "INSERT INTO rdf_quads VALUES (1, 2, 4, 5) ON CONFLICT DO NOTHING;",
+            ]
+        );
+        Ok(())
+    }
+}
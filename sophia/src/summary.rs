@@ -0,0 +1,222 @@
+//! Summaries of the vocabulary used by a graph ("schema sniffing").
+//!
+//! [`summarize`] describes which predicates occur in a graph
+//! (with the classes of their subjects, the classes or datatypes of their objects,
+//! and a few example values),
+//! and which classes are instantiated, and how often.
+//! This is useful to get a first idea of an unknown dataset;
+//! the [`shapes`](../shapes/index.html) module goes further,
+//! and induces SHACL shapes describing the instances of each class.
+//!
+//! Terms are represented by their N-Triples serialization (e.g. `<http://schema.org/name>`),
+//! which is also how [`GraphSummary`] displays them.
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, MutableGraph};
+//! # use sophia::ns::{rdf, xsd};
+//! # use sophia::summary::summarize;
+//! # use sophia::term::StaticTerm;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let alice = StaticTerm::new_iri("http://example.org/alice")?;
+//! let person = StaticTerm::new_iri("http://schema.org/Person")?;
+//! let name = StaticTerm::new_iri("http://schema.org/name")?;
+//! let mut g = FastGraph::new();
+//! g.insert(&alice, &rdf::type_, &person)?;
+//! g.insert(&alice, &name, &StaticTerm::new_literal_dt("Alice", xsd::string)?)?;
+//!
+//! let summary = summarize(&g, 3)?;
+//! assert_eq!(summary.classes["<http://schema.org/Person>"], 1);
+//! let usage = &summary.predicates["<http://schema.org/name>"];
+//! assert_eq!(usage.subject_types["<http://schema.org/Person>"], 1);
+//! assert_eq!(
+//!     usage.object_types["<http://www.w3.org/2001/XMLSchema#string>"],
+//!     1
+//! );
+//! assert_eq!(usage.examples, vec!["\"Alice\"".to_string()]);
+//! # Ok(()) }
+//! ```
+//!
+//! [`summarize`]: fn.summarize.html
+//! [`GraphSummary`]: struct.GraphSummary.html
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use sophia_api::ns::rdf;
+use sophia_api::term::{term_to_string as nt, TTerm, TermKind};
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+/// A summary of the vocabulary used by a graph.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct GraphSummary {
+    /// The number of triples in the graph.
+    pub triples: usize,
+    /// The usage of each predicate.
+    pub predicates: BTreeMap<String, PredicateUsage>,
+    /// The number of instances of each class.
+    pub classes: BTreeMap<String, usize>,
+}
+
+/// How a predicate is used in a graph.
+#[derive(Clone, Debug, Default)]
+pub struct PredicateUsage {
+    /// The number of triples using this predicate.
+    pub triples: usize,
+    /// The number of distinct subjects of these triples.
+    pub subjects: usize,
+    /// The number of distinct objects of these triples.
+    pub objects: usize,
+    /// The number of literal objects among these triples.
+    pub literals: usize,
+    /// For each class, the number of these triples whose subject is an instance of it.
+    pub subject_types: BTreeMap<String, usize>,
+    /// For each class or datatype, the number of these triples
+    /// whose object is an instance of it (or a literal of that datatype).
+    pub object_types: BTreeMap<String, usize>,
+    /// A few distinct example objects.
+    pub examples: Vec<String>,
+}
+
+/// Summarize the vocabulary used by `graph`,
+/// keeping at most `max_examples` example objects for each predicate.
+pub fn summarize<G: Graph>(graph: &G, max_examples: usize) -> GResult<G, GraphSummary> {
+    let mut summary = GraphSummary::default();
+    let mut types: HashMap<String, Vec<String>> = HashMap::new();
+    for t in graph.triples_with_p(&rdf::type_) {
+        let t = t?;
+        let class = nt(t.o());
+        *summary.classes.entry(class.clone()).or_insert(0) += 1;
+        types.entry(nt(t.s())).or_default().push(class);
+    }
+
+    let mut distinct: HashMap<String, (HashSet<String>, HashSet<String>)> = HashMap::new();
+    for t in graph.triples() {
+        let t = t?;
+        summary.triples += 1;
+        let p = nt(t.p());
+        let s = nt(t.s());
+        let o = nt(t.o());
+        let usage = summary.predicates.entry(p.clone()).or_default();
+        usage.triples += 1;
+        for class in types.get(&s).into_iter().flatten() {
+            *usage.subject_types.entry(class.clone()).or_insert(0) += 1;
+        }
+        if t.o().kind() == TermKind::Literal {
+            usage.literals += 1;
+            if let Some(dt) = t.o().datatype() {
+                *usage.object_types.entry(nt(&dt)).or_insert(0) += 1;
+            }
+        } else {
+            for class in types.get(&o).into_iter().flatten() {
+                *usage.object_types.entry(class.clone()).or_insert(0) += 1;
+            }
+        }
+        let (subjects, objects) = distinct.entry(p).or_default();
+        subjects.insert(s);
+        if !objects.contains(&o) {
+            if usage.examples.len() < max_examples {
+                usage.examples.push(o.clone());
+            }
+            objects.insert(o);
+        }
+    }
+    for (p, (subjects, objects)) in distinct {
+        let usage = summary.predicates.get_mut(&p).unwrap();
+        usage.subjects = subjects.len();
+        usage.objects = objects.len();
+    }
+    Ok(summary)
+}
+
+impl fmt::Display for GraphSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} triples", self.triples)?;
+        writeln!(f, "classes:")?;
+        for (class, count) in &self.classes {
+            writeln!(f, "  {} ({} instances)", class, count)?;
+        }
+        writeln!(f, "predicates:")?;
+        for (p, usage) in &self.predicates {
+            writeln!(
+                f,
+                "  {} ({} triples, {} subjects, {} objects, {} literals)",
+                p, usage.triples, usage.subjects, usage.objects, usage.literals
+            )?;
+            write_counts(f, "subject types", &usage.subject_types)?;
+            write_counts(f, "object types", &usage.object_types)?;
+            if !usage.examples.is_empty() {
+                writeln!(f, "    examples: {}", usage.examples.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_counts(
+    f: &mut fmt::Formatter,
+    label: &str,
+    counts: &BTreeMap<String, usize>,
+) -> fmt::Result {
+    if counts.is_empty() {
+        return Ok(());
+    }
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|(k, n)| format!("{} ({})", k, n))
+        .collect();
+    writeln!(f, "    {}: {}", label, counts.join(", "))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    #[test]
+    fn summary() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix s: <http://schema.org/>.
+            @prefix : <http://example.org/>.
+            :alice a s:Person; s:name "Alice"; s:knows :bob, :carol.
+            :bob a s:Person, s:Agent; s:name "Bob"; s:knows :carol.
+            :carol s:name "Carol"@en.
+            :acme a s:Organization; s:name "Acme".
+            "#,
+        )
+        .collect_triples()?;
+        let summary = summarize(&g, 2)?;
+        assert_eq!(summary.triples, 11);
+        assert_eq!(summary.classes.len(), 3);
+        assert_eq!(summary.classes["<http://schema.org/Person>"], 2);
+
+        let name = &summary.predicates["<http://schema.org/name>"];
+        assert_eq!(name.triples, 4);
+        assert_eq!(name.subjects, 4);
+        assert_eq!(name.literals, 4);
+        assert_eq!(name.subject_types["<http://schema.org/Person>"], 2);
+        assert_eq!(name.subject_types["<http://schema.org/Agent>"], 1);
+        assert_eq!(name.examples.len(), 2);
+        let lang_string = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#langString>";
+        assert_eq!(name.object_types[lang_string], 1);
+
+        let knows = &summary.predicates["<http://schema.org/knows>"];
+        assert_eq!((knows.triples, knows.subjects, knows.objects), (3, 2, 2));
+        assert_eq!(knows.object_types["<http://schema.org/Person>"], 1);
+
+        let report = summary.to_string();
+        assert!(report.starts_with("11 triples\n"));
+        assert!(report.contains("  <http://schema.org/Organization> (1 instances)\n"));
+        Ok(())
+    }
+}
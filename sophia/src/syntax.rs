@@ -0,0 +1,454 @@
+//! Identification of the RDF concrete syntaxes supported by Sophia.
+//!
+//! The [`Syntax`] enum identifies each concrete syntax,
+//! and gives access to its standard media type and file extension.
+//! The [`guess`] module provides functions to determine the syntax of some data
+//! from its file extension, its media type or its first bytes.
+//!
+//! [`Syntax`] also acts as a single entry point to all the parsers and serializers of Sophia,
+//! through dynamic dispatch (see the [`dynamic`] module).
+//! This allows applications to handle whatever syntax the user chooses
+//! without monomorphizing their code for every concrete parser type:
+//!
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph};
+//! # use sophia::syntax::Syntax;
+//! # use sophia::triple::stream::TripleSource;
+//! let syntax: Syntax = "ttl".parse()?; // e.g. from a command-line option
+//! let data = "@prefix : <http://example.org/>. :alice :knows :bob.";
+//! let graph: FastGraph = syntax.parse_triples(data.as_bytes(), None).collect_triples()?;
+//! assert_eq!(graph.triples().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Syntax`]: enum.Syntax.html
+//! [`guess`]: guess/index.html
+//! [`dynamic`]: dynamic/index.html
+
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::term::TTerm;
+use thiserror::Error;
+
+use crate::parser::config::ParserConfig;
+use crate::parser::{nq, nt, trig, turtle};
+use crate::serializer::{nq::NqSerializer, nt::NtSerializer};
+
+pub mod dynamic;
+pub mod guess;
+
+use dynamic::*;
+
+/// An RDF concrete syntax supported by Sophia.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Syntax {
+    /// [N-Triples](https://www.w3.org/TR/n-triples/)
+    NTriples,
+    /// [Turtle](https://www.w3.org/TR/turtle/)
+    Turtle,
+    /// [N-Quads](https://www.w3.org/TR/n-quads/)
+    NQuads,
+    /// [TriG](https://www.w3.org/TR/trig/)
+    TriG,
+    /// [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/)
+    /// (requires the `xml` feature)
+    #[cfg(feature = "xml")]
+    RdfXml,
+}
+
+impl Syntax {
+    /// All the syntaxes supported by Sophia (given the enabled features).
+    pub const ALL: &'static [Syntax] = &[
+        Syntax::NTriples,
+        Syntax::Turtle,
+        Syntax::NQuads,
+        Syntax::TriG,
+        #[cfg(feature = "xml")]
+        Syntax::RdfXml,
+    ];
+
+    /// The short name of this syntax.
+    pub fn name(self) -> &'static str {
+        match self {
+            Syntax::NTriples => "N-Triples",
+            Syntax::Turtle => "Turtle",
+            Syntax::NQuads => "N-Quads",
+            Syntax::TriG => "TriG",
+            #[cfg(feature = "xml")]
+            Syntax::RdfXml => "RDF/XML",
+        }
+    }
+
+    /// The standard media type of this syntax.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Syntax::NTriples => "application/n-triples",
+            Syntax::Turtle => "text/turtle",
+            Syntax::NQuads => "application/n-quads",
+            Syntax::TriG => "application/trig",
+            #[cfg(feature = "xml")]
+            Syntax::RdfXml => "application/rdf+xml",
+        }
+    }
+
+    /// The usual file extension of this syntax (without the leading dot).
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            Syntax::NTriples => "nt",
+            Syntax::Turtle => "ttl",
+            Syntax::NQuads => "nq",
+            Syntax::TriG => "trig",
+            #[cfg(feature = "xml")]
+            Syntax::RdfXml => "rdf",
+        }
+    }
+
+    /// Whether this syntax describes datasets (quads) rather than graphs (triples).
+    pub fn is_quad_syntax(self) -> bool {
+        matches!(self, Syntax::NQuads | Syntax::TriG)
+    }
+
+    /// Whether this syntax is based on Turtle (i.e. all syntaxes except RDF/XML).
+    fn is_turtle_based(self) -> bool {
+        match self {
+            #[cfg(feature = "xml")]
+            Syntax::RdfXml => false,
+            _ => true,
+        }
+    }
+
+    /// Parse `data` with the parser of this syntax, as triples.
+    ///
+    /// `base` is the base IRI used to resolve relative IRIs
+    /// (ignored by syntaxes that do not support them).
+    /// For quad syntaxes, only the triples of the default graph are yielded.
+    pub fn parse_triples<'a, B>(self, data: B, base: Option<String>) -> DynTripleSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        match self {
+            Syntax::NTriples => box_triples(nt::NTriplesParser {}.parse(data)),
+            Syntax::Turtle => box_triples(turtle::TurtleParser { base }.parse(data)),
+            Syntax::NQuads | Syntax::TriG => {
+                Box::new(self.parse_quads(data, base).filter_map(|res| match res {
+                    Ok((spo, None)) => Some(Ok(spo)),
+                    Ok(_) => None,
+                    Err(err) => Some(Err(err)),
+                }))
+            }
+            #[cfg(feature = "xml")]
+            Syntax::RdfXml => box_triples(crate::parser::xml::RdfXmlParser { base }.parse(data)),
+        }
+    }
+
+    /// Parse `data` with the parser of this syntax, as quads.
+    ///
+    /// `base` is the base IRI used to resolve relative IRIs
+    /// (ignored by syntaxes that do not support them).
+    /// For triple syntaxes, all triples are yielded in the default graph.
+    pub fn parse_quads<'a, B>(self, data: B, base: Option<String>) -> DynQuadSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        match self {
+            Syntax::NQuads => box_quads(nq::NQuadsParser {}.parse(data)),
+            Syntax::TriG => box_quads(trig::TriGParser { base }.parse(data)),
+            _ => Box::new(
+                self.parse_triples(data, base)
+                    .map(|res| res.map(|spo| (spo, None))),
+            ),
+        }
+    }
+
+    /// Parse `data` with the parser of this syntax, as triples,
+    /// checking that they comply with the given [`ParserConfig`].
+    ///
+    /// See [`parse_triples`](#method.parse_triples) for more details.
+    ///
+    /// [`ParserConfig`]: ../parser/config/struct.ParserConfig.html
+    pub fn parse_triples_with<'a, B>(self, data: B, config: &ParserConfig) -> DynTripleSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        match self {
+            Syntax::Turtle => turtle::TurtleParser::default().parse_with_config(data, config),
+            _ => config.parse_triples(data, self.is_turtle_based(), |data| {
+                self.parse_triples(data, config.base().map(str::to_string))
+            }),
+        }
+    }
+
+    /// Parse `data` with the parser of this syntax, as quads,
+    /// checking that they comply with the given [`ParserConfig`].
+    ///
+    /// See [`parse_quads`](#method.parse_quads) for more details.
+    ///
+    /// [`ParserConfig`]: ../parser/config/struct.ParserConfig.html
+    pub fn parse_quads_with<'a, B>(self, data: B, config: &ParserConfig) -> DynQuadSource<'a>
+    where
+        B: BufRead + 'a,
+    {
+        match self {
+            Syntax::TriG => trig::TriGParser::default().parse_with_config(data, config),
+            Syntax::Turtle => Box::new(
+                turtle::TurtleParser::default()
+                    .parse_with_config(data, config)
+                    .map(|res| res.map(|spo| (spo, None))),
+            ),
+            _ => config.parse_quads(data, self.is_turtle_based(), |data| {
+                self.parse_quads(data, config.base().map(str::to_string))
+            }),
+        }
+    }
+
+    /// Build a serializer for this syntax, writing triples to `write`.
+    ///
+    /// Return `None` if Sophia provides no triple serializer for this syntax.
+    pub fn triple_serializer<'a, W>(self, write: W) -> Option<Box<dyn DynTripleSerializer + 'a>>
+    where
+        W: Write + 'a,
+    {
+        match self {
+            Syntax::NTriples => Some(Box::new(NtSerializer::new(write))),
+            _ => None,
+        }
+    }
+
+    /// Build a serializer for this syntax, writing quads to `write`.
+    ///
+    /// Return `None` if Sophia provides no quad serializer for this syntax.
+    pub fn quad_serializer<'a, W>(self, write: W) -> Option<Box<dyn DynQuadSerializer + 'a>>
+    where
+        W: Write + 'a,
+    {
+        match self {
+            Syntax::NQuads => Some(Box::new(NqSerializer::new(write))),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parse a syntax from its name (case insensitive),
+/// its media type or its file extension.
+///
+/// This is typically useful for command-line options.
+///
+/// ```
+/// # use sophia::syntax::Syntax;
+/// assert_eq!("turtle".parse::<Syntax>().unwrap(), Syntax::Turtle);
+/// assert_eq!("nq".parse::<Syntax>().unwrap(), Syntax::NQuads);
+/// assert_eq!("application/n-triples".parse::<Syntax>().unwrap(), Syntax::NTriples);
+/// ```
+impl FromStr for Syntax {
+    type Err = UnknownSyntax;
+
+    fn from_str(txt: &str) -> Result<Syntax, UnknownSyntax> {
+        let lower = txt.to_ascii_lowercase();
+        Syntax::ALL
+            .iter()
+            .copied()
+            .find(|s| s.name().to_ascii_lowercase().replace('-', "") == lower.replace('-', ""))
+            .or_else(|| guess::from_media_type(&lower))
+            .or_else(|| guess::from_extension(&format!(".{}", lower)))
+            .ok_or_else(|| UnknownSyntax(txt.to_string()))
+    }
+}
+
+/// A version of the RDF specifications, to which parsers and serializers can be restricted.
+///
+/// In [`Rdf11`](#variant.Rdf11) mode, the constructs introduced by [RDF 1.2] are rejected
+/// with an explicit error (rather than a mere syntax error).
+/// The constructs supported by Sophia are
+/// the [directional language-tagged string] (e.g. `"hello"@en--ltr`),
+/// and the quoted triples (e.g. `<< s p o >>`) of the [N-Quads 1.2 parser](../parser/nq12/index.html),
+/// of the Turtle and TriG parsers (when parsing with a [`ParserConfig`](../parser/config/struct.ParserConfig.html))
+/// and of the serializers.
+///
+/// See [`ParserConfig::set_rdf_version`], [`NtConfig::set_rdf_version`]
+/// and [`NQuads12Parser::version`].
+///
+/// [RDF 1.2]: https://www.w3.org/TR/rdf12-concepts/
+/// [directional language-tagged string]: https://www.w3.org/TR/rdf12-concepts/#section-text-direction
+/// [`ParserConfig::set_rdf_version`]: ../parser/config/struct.ParserConfig.html#method.set_rdf_version
+/// [`NtConfig::set_rdf_version`]: ../serializer/nt/struct.NtConfig.html#method.set_rdf_version
+/// [`NQuads12Parser::version`]: ../parser/nq12/struct.NQuads12Parser.html#structfield.version
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RdfVersion {
+    /// [RDF 1.1](https://www.w3.org/TR/rdf11-concepts/)
+    Rdf11,
+    /// [RDF 1.2](https://www.w3.org/TR/rdf12-concepts/) (the default)
+    #[default]
+    Rdf12,
+}
+
+impl RdfVersion {
+    /// The version number, e.g. `"1.1"`.
+    pub fn number(self) -> &'static str {
+        match self {
+            RdfVersion::Rdf11 => "1.1",
+            RdfVersion::Rdf12 => "1.2",
+        }
+    }
+
+    /// Whether `term` can be expressed in this version of RDF.
+    pub fn allows<T: TTerm + ?Sized>(self, term: &T) -> bool {
+        self >= RdfVersion::Rdf12 || term.base_direction().is_none()
+    }
+
+    /// Whether quoted triples (`<< s p o >>`) can be expressed in this version of RDF.
+    pub fn allows_quoted_triples(self) -> bool {
+        self >= RdfVersion::Rdf12
+    }
+}
+
+impl fmt::Display for RdfVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RDF {}", self.number())
+    }
+}
+
+/// This error is raised when trying to parse an unknown syntax name.
+#[derive(Debug, Error)]
+#[error("Unknown or unsupported syntax: {0}")]
+pub struct UnknownSyntax(pub String);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::term::TTerm;
+    use test_case::test_case;
+
+    #[test_case("N-Triples", Syntax::NTriples)]
+    #[test_case("ntriples", Syntax::NTriples)]
+    #[test_case("TTL", Syntax::Turtle)]
+    #[test_case("trig", Syntax::TriG)]
+    #[test_case("application/n-quads", Syntax::NQuads)]
+    fn from_str(txt: &str, expected: Syntax) {
+        assert_eq!(txt.parse::<Syntax>().unwrap(), expected);
+    }
+
+    #[test]
+    fn from_str_unknown() {
+        assert!("foo".parse::<Syntax>().is_err());
+    }
+
+    #[test]
+    fn rdf_version() {
+        use sophia_api::term::BaseDirection;
+        use sophia_term::BoxTerm;
+
+        let lit = BoxTerm::new_literal_lang_unchecked("hello", "en");
+        let dir = BoxTerm::new_literal_dir_lang("hello", "en", BaseDirection::Ltr).unwrap();
+        assert!(RdfVersion::Rdf11.allows(&lit));
+        assert!(!RdfVersion::Rdf11.allows(&dir));
+        assert!(RdfVersion::Rdf12.allows(&dir));
+        assert!(!RdfVersion::Rdf11.allows_quoted_triples());
+        assert!(RdfVersion::Rdf12.allows_quoted_triples());
+        assert_eq!(RdfVersion::default(), RdfVersion::Rdf12);
+        assert_eq!(RdfVersion::Rdf11.to_string(), "RDF 1.1");
+    }
+
+    #[test]
+    fn round_trip() {
+        for s in Syntax::ALL {
+            assert_eq!(guess::from_media_type(s.media_type()), Some(*s));
+            assert_eq!(
+                guess::from_extension(&format!("file.{}", s.file_extension())),
+                Some(*s)
+            );
+        }
+    }
+
+    #[test_case(Syntax::NTriples, "<tag:a> <tag:b> <tag:c>.\n", 1)]
+    #[test_case(Syntax::Turtle, "<tag:a> <tag:b> <tag:c>, <tag:d>.", 2)]
+    #[test_case(
+        Syntax::NQuads,
+        "<tag:a> <tag:b> <tag:c>.\n<tag:a> <tag:b> <tag:c> <tag:g>.\n",
+        1
+    )]
+    #[test_case(
+        Syntax::TriG,
+        "<tag:a> <tag:b> <tag:c>. <tag:g> { <tag:a> <tag:b> <tag:d> }",
+        1
+    )]
+    fn parse_triples(syntax: Syntax, data: &str, expected: usize) {
+        let triples: Result<Vec<_>, _> = syntax.parse_triples(data.as_bytes(), None).collect();
+        assert_eq!(triples.unwrap().len(), expected);
+    }
+
+    #[test_case(Syntax::NTriples, "<tag:a> <tag:b> <tag:c>.\n", 1)]
+    #[test_case(Syntax::Turtle, "<tag:a> <tag:b> <tag:c>, <tag:d>.", 2)]
+    #[test_case(
+        Syntax::NQuads,
+        "<tag:a> <tag:b> <tag:c>.\n<tag:a> <tag:b> <tag:c> <tag:g>.\n",
+        2
+    )]
+    #[test_case(
+        Syntax::TriG,
+        "<tag:a> <tag:b> <tag:c>. <tag:g> { <tag:a> <tag:b> <tag:d> }",
+        2
+    )]
+    fn parse_quads(syntax: Syntax, data: &str, expected: usize) {
+        let quads: Result<Vec<_>, _> = syntax.parse_quads(data.as_bytes(), None).collect();
+        assert_eq!(quads.unwrap().len(), expected);
+    }
+
+    #[test]
+    fn parse_relative_iri_with_base() {
+        let triples: Vec<_> = Syntax::Turtle
+            .parse_triples(&b"<a> <b> <c>."[..], Some("http://example.org/".into()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(&triples[0][0].value()[..], "http://example.org/a");
+    }
+
+    #[test]
+    fn parse_with_config() {
+        let data = "<a> <tag:b> \"hello\"@en-US, \"bonjour\"@fr.";
+        let lax: Vec<_> = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &ParserConfig::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(&lax[0][0].value()[..], "a");
+
+        let mut config = ParserConfig::strict();
+        let strict: Vec<_> = Syntax::TriG
+            .parse_quads_with(data.as_bytes(), &config)
+            .collect();
+        assert!(strict[0].is_err());
+
+        config.set_base(Some("http://example.org/".into()));
+        config.set_max_literal_length(Some(5));
+        let strict: Vec<_> = Syntax::Turtle
+            .parse_triples_with(data.as_bytes(), &config)
+            .collect();
+        assert_eq!(
+            &strict[0].as_ref().unwrap()[0].value()[..],
+            "http://example.org/a"
+        );
+        assert!(strict[1].is_err());
+    }
+
+    #[test]
+    fn serializers() {
+        let mut out = vec![];
+        {
+            let mut ser = Syntax::NQuads.quad_serializer(&mut out).unwrap();
+            let quads = Syntax::TriG.parse_quads(&b"<tag:g> { <tag:a> <tag:b> <tag:c> }"[..], None);
+            ser.serialize_dyn_quads(quads).unwrap();
+        }
+        assert_eq!(&out[..], &b"<tag:a> <tag:b> <tag:c> <tag:g>.\n"[..]);
+        assert!(Syntax::NTriples.triple_serializer(vec![]).is_some());
+        assert!(Syntax::Turtle.triple_serializer(vec![]).is_none());
+        assert!(Syntax::TriG.quad_serializer(vec![]).is_none());
+    }
+}
@@ -0,0 +1,274 @@
+//! Object-safe counterparts of the parsing and serializing APIs,
+//! used by [`Syntax`](../enum.Syntax.html) to dispatch to the appropriate parser or serializer
+//! at run-time.
+//!
+//! Parsers and serializers in Sophia are generic,
+//! which allows them to be very efficient,
+//! but requires the concrete syntax to be known at compile time.
+//! This module provides boxed triple/quad sources, and dyn-compatible serializer traits,
+//! so that applications can handle whatever syntax the user chooses through a single code path.
+//!
+//! The price to pay is that every parsed triple (or quad) is copied into [`BoxTerm`]s.
+//!
+//! [`BoxTerm`]: ../../term/type.BoxTerm.html
+
+use std::collections::VecDeque;
+use std::error::Error;
+
+use sophia_api::quad::stream::{QuadSource, StreamError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{QuadSerializer, TripleSerializer};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+/// The error type of dynamic sources and serializers,
+/// wrapping the error of the underlying parser or serializer.
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct DynError(pub Box<dyn Error + Send + Sync + 'static>);
+
+impl DynError {
+    /// Wrap `error` into a `DynError`.
+    pub fn new<E>(error: E) -> DynError
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        DynError(Box::new(error))
+    }
+}
+
+/// A triple made of owned terms, as yielded by [`DynTripleSource`].
+///
+/// [`DynTripleSource`]: type.DynTripleSource.html
+pub type BoxTriple = [BoxTerm; 3];
+
+/// A quad made of owned terms, as yielded by [`DynQuadSource`].
+///
+/// [`DynQuadSource`]: type.DynQuadSource.html
+pub type BoxQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// A boxed [`TripleSource`], hiding the concrete type of the underlying source.
+///
+/// [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+pub type DynTripleSource<'a> = Box<dyn Iterator<Item = Result<BoxTriple, DynError>> + 'a>;
+
+/// A boxed [`QuadSource`], hiding the concrete type of the underlying source.
+///
+/// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+pub type DynQuadSource<'a> = Box<dyn Iterator<Item = Result<BoxQuad, DynError>> + 'a>;
+
+/// Convert any [`TripleSource`] into a [`DynTripleSource`].
+///
+/// The underlying source is consumed lazily.
+///
+/// [`TripleSource`]: ../../triple/stream/trait.TripleSource.html
+/// [`DynTripleSource`]: type.DynTripleSource.html
+pub fn box_triples<'a, TS>(source: TS) -> DynTripleSource<'a>
+where
+    TS: TripleSource + 'a,
+    TS::Error: Send + Sync,
+{
+    Box::new(Buffered::new(
+        source,
+        |source: &mut TS, buffer: &mut VecDeque<BoxTriple>| {
+            source.for_some_triple(&mut |t| {
+                buffer.push_back([
+                    BoxTerm::copy(t.s()),
+                    BoxTerm::copy(t.p()),
+                    BoxTerm::copy(t.o()),
+                ])
+            })
+        },
+    ))
+}
+
+/// Convert any [`QuadSource`] into a [`DynQuadSource`].
+///
+/// The underlying source is consumed lazily.
+///
+/// [`QuadSource`]: ../../quad/stream/trait.QuadSource.html
+/// [`DynQuadSource`]: type.DynQuadSource.html
+pub fn box_quads<'a, QS>(source: QS) -> DynQuadSource<'a>
+where
+    QS: QuadSource + 'a,
+    QS::Error: Send + Sync,
+{
+    Box::new(Buffered::new(
+        source,
+        |source: &mut QS, buffer: &mut VecDeque<BoxQuad>| {
+            source.for_some_quad(&mut |q| {
+                buffer.push_back((
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                ))
+            })
+        },
+    ))
+}
+
+/// An object-safe version of [`TripleSerializer`],
+/// automatically implemented by all triple serializers.
+///
+/// [`TripleSerializer`]: ../../serializer/trait.TripleSerializer.html
+pub trait DynTripleSerializer {
+    /// Serialize all triples from the given source.
+    fn serialize_dyn_triples(
+        &mut self,
+        source: DynTripleSource,
+    ) -> StreamResult<(), DynError, DynError>;
+}
+
+impl<S> DynTripleSerializer for S
+where
+    S: TripleSerializer,
+    S::Error: Send + Sync,
+{
+    fn serialize_dyn_triples(
+        &mut self,
+        source: DynTripleSource,
+    ) -> StreamResult<(), DynError, DynError> {
+        self.serialize_triples(source)
+            .map(|_| ())
+            .map_err(wrap_sink_error)
+    }
+}
+
+/// An object-safe version of [`QuadSerializer`],
+/// automatically implemented by all quad serializers.
+///
+/// [`QuadSerializer`]: ../../serializer/trait.QuadSerializer.html
+pub trait DynQuadSerializer {
+    /// Serialize all quads from the given source.
+    fn serialize_dyn_quads(
+        &mut self,
+        source: DynQuadSource,
+    ) -> StreamResult<(), DynError, DynError>;
+}
+
+impl<S> DynQuadSerializer for S
+where
+    S: QuadSerializer,
+    S::Error: Send + Sync,
+{
+    fn serialize_dyn_quads(
+        &mut self,
+        source: DynQuadSource,
+    ) -> StreamResult<(), DynError, DynError> {
+        self.serialize_quads(source)
+            .map(|_| ())
+            .map_err(wrap_sink_error)
+    }
+}
+
+fn wrap_sink_error<E>(err: StreamError<DynError, E>) -> StreamError<DynError, DynError>
+where
+    E: Error + Send + Sync + 'static,
+{
+    match err {
+        StreamError::SourceError(e) => StreamError::SourceError(e),
+        StreamError::SinkError(e) => StreamError::SinkError(DynError::new(e)),
+    }
+}
+
+/// An iterator pulling items from a triple or quad source,
+/// and buffering those that the source yields in a single step.
+struct Buffered<S, T, F> {
+    source: S,
+    buffer: VecDeque<T>,
+    step: F,
+    done: bool,
+}
+
+impl<S, T, F> Buffered<S, T, F> {
+    fn new(source: S, step: F) -> Self {
+        Buffered {
+            source,
+            buffer: VecDeque::new(),
+            step,
+            done: false,
+        }
+    }
+}
+
+impl<S, T, F, E> Iterator for Buffered<S, T, F>
+where
+    F: FnMut(&mut S, &mut VecDeque<T>) -> Result<bool, E>,
+    E: Error + Send + Sync + 'static,
+{
+    type Item = Result<T, DynError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.pop_front() {
+                return Some(Ok(item));
+            }
+            if self.done {
+                return None;
+            }
+            match (self.step)(&mut self.source, &mut self.buffer) {
+                Ok(more) => self.done = !more,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(DynError::new(err)));
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{nq, turtle};
+    use crate::serializer::nt::NtSerializer;
+    use crate::serializer::Stringifier;
+    use sophia_api::term::TTerm;
+
+    #[test]
+    fn box_triples_is_lazy_and_complete() {
+        let source = turtle::parse_str("<tag:a> <tag:b> <tag:c>, <tag:d>. <tag:e> <tag:f> 42.");
+        let triples: Vec<_> = box_triples(source).collect::<Result<_, _>>().unwrap();
+        assert_eq!(triples.len(), 3);
+        assert_eq!(&triples[2][2].value()[..], "42");
+    }
+
+    #[test]
+    fn box_quads_keeps_graph_names() {
+        let source = nq::parse_str("<tag:a> <tag:b> <tag:c> <tag:g>.\n<tag:a> <tag:b> <tag:c>.\n");
+        let quads: Vec<_> = box_quads(source).collect::<Result<_, _>>().unwrap();
+        assert_eq!(quads.len(), 2);
+        assert!(quads[0].1.is_some());
+        assert!(quads[1].1.is_none());
+    }
+
+    #[test]
+    fn box_triples_error() {
+        let source = turtle::parse_str("<tag:a> <tag:b> <tag:c>. <tag:a> <tag:b>");
+        let results: Vec<_> = box_triples(source).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn dyn_serializer() {
+        let mut ser = NtSerializer::new_stringifier();
+        {
+            let dyn_ser: &mut dyn DynTripleSerializer = &mut ser;
+            let source = turtle::parse_str("<tag:a> <tag:b> <tag:c>.");
+            dyn_ser.serialize_dyn_triples(box_triples(source)).unwrap();
+        }
+        assert_eq!(ser.as_str(), "<tag:a> <tag:b> <tag:c>.\n");
+    }
+}
@@ -0,0 +1,273 @@
+//! Heuristics for guessing the [`Syntax`](../enum.Syntax.html) of some RDF data.
+//!
+//! The syntax can be guessed from
+//! a file name or an IRI ([`from_extension`]),
+//! a media type ([`from_media_type`]),
+//! or the first bytes of the data ([`from_content`]).
+//! The [`guess`] function combines all of them.
+//!
+//! [`from_extension`]: fn.from_extension.html
+//! [`from_media_type`]: fn.from_media_type.html
+//! [`from_content`]: fn.from_content.html
+//! [`guess`]: fn.guess.html
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::Syntax;
+use super::Syntax::*;
+use crate::compression::Compression;
+
+/// The maximum number of bytes inspected by [`from_content`](fn.from_content.html).
+pub const SNIFF_SIZE: usize = 4096;
+
+/// Guess the syntax from the extension of a file name, a path or an IRI.
+///
+/// The query string and fragment identifier of IRIs are ignored,
+/// as well as a trailing [compression](../../compression/index.html) extension.
+///
+/// ```
+/// # use sophia::syntax::{guess, Syntax};
+/// assert_eq!(guess::from_extension("data/foo.ttl"), Some(Syntax::Turtle));
+/// assert_eq!(guess::from_extension("http://example.org/foo.nq?x=y"), Some(Syntax::NQuads));
+/// assert_eq!(guess::from_extension("dump.nt.gz"), Some(Syntax::NTriples));
+/// assert_eq!(guess::from_extension("foo.txt"), None);
+/// ```
+pub fn from_extension(name: &str) -> Option<Syntax> {
+    let path = name.split(['?', '#']).next().unwrap();
+    let mut file_name = path.rsplit(['/', '\\']).next().unwrap();
+    if Compression::from_extension(file_name) != Compression::Identity {
+        file_name = file_name.rsplit_once('.').unwrap().0;
+    }
+    let (_, ext) = file_name.rsplit_once('.')?;
+    match ext.to_ascii_lowercase().as_str() {
+        "nt" | "ntriples" => Some(NTriples),
+        "ttl" | "turtle" => Some(Turtle),
+        "nq" | "nquads" => Some(NQuads),
+        "trig" => Some(TriG),
+        #[cfg(feature = "xml")]
+        "rdf" | "owl" | "xml" => Some(RdfXml),
+        _ => None,
+    }
+}
+
+/// Guess the syntax from a media type, as found for example in a `Content-Type` header.
+///
+/// Media type parameters (e.g. `charset`) are ignored.
+/// Non-informative media types (such as `text/plain`) return `None`.
+///
+/// ```
+/// # use sophia::syntax::{guess, Syntax};
+/// assert_eq!(guess::from_media_type("text/turtle; charset=utf-8"), Some(Syntax::Turtle));
+/// assert_eq!(guess::from_media_type("text/plain"), None);
+/// ```
+pub fn from_media_type(media_type: &str) -> Option<Syntax> {
+    let media_type = media_type.split(';').next().unwrap().trim();
+    match media_type.to_ascii_lowercase().as_str() {
+        "application/n-triples" => Some(NTriples),
+        "text/turtle" | "application/x-turtle" => Some(Turtle),
+        "application/n-quads" | "text/x-nquads" => Some(NQuads),
+        "application/trig" | "application/x-trig" => Some(TriG),
+        #[cfg(feature = "xml")]
+        "application/rdf+xml" => Some(RdfXml),
+        _ => None,
+    }
+}
+
+/// Guess the syntax by inspecting the first bytes of some data
+/// (at most [`SNIFF_SIZE`](constant.SNIFF_SIZE.html) are considered).
+///
+/// This recognizes
+/// XML documents (RDF/XML),
+/// Turtle/SPARQL-style directives (Turtle, or TriG if a graph block is found),
+/// and line-based statements, which are identified as N-Triples or N-Quads
+/// depending on the number of terms on the first statement.
+///
+/// ```
+/// # use sophia::syntax::{guess, Syntax};
+/// let data = b"@prefix : <http://example.org/>.\n:a :b :c.";
+/// assert_eq!(guess::from_content(data), Some(Syntax::Turtle));
+/// let data = b"<http://a.example/s> <http://a.example/p> \"o\" <http://a.example/g> .";
+/// assert_eq!(guess::from_content(data), Some(Syntax::NQuads));
+/// ```
+pub fn from_content(data: &[u8]) -> Option<Syntax> {
+    let data = &data[..data.len().min(SNIFF_SIZE)];
+    let txt = String::from_utf8_lossy(data);
+    let txt = txt.trim_start_matches('\u{feff}');
+    let first = txt
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+
+    if first.starts_with("<?xml") || first.starts_with("<rdf:RDF") {
+        #[cfg(feature = "xml")]
+        return Some(RdfXml);
+        #[cfg(not(feature = "xml"))]
+        return None;
+    }
+    let directive = first.trim_start_matches('@').to_ascii_lowercase();
+    if directive.starts_with("prefix") || directive.starts_with("base") {
+        return Some(if has_graph_block(txt) { TriG } else { Turtle });
+    }
+    if first.starts_with('{') && has_graph_block(txt) {
+        return Some(TriG);
+    }
+    if first.starts_with('<') || first.starts_with("_:") {
+        return match count_terms(first) {
+            Some(3) => Some(NTriples),
+            Some(4) => Some(NQuads),
+            _ if has_graph_block(txt) => Some(TriG),
+            _ => Some(Turtle),
+        };
+    }
+    None
+}
+
+/// Guess the syntax of some data, using all the available information.
+///
+/// The media type is considered first, as it is the most reliable,
+/// then the file extension of `name` (a file name, path or IRI),
+/// and finally the first bytes of the data.
+///
+/// ```
+/// # use sophia::syntax::{guess, Syntax};
+/// let data = b"<http://a.example/s> <http://a.example/p> <http://a.example/o> .";
+/// assert_eq!(guess::guess(Some("text/plain"), Some("foo.txt"), data), Some(Syntax::NTriples));
+/// assert_eq!(guess::guess(None, Some("foo.ttl"), data), Some(Syntax::Turtle));
+/// ```
+pub fn guess(media_type: Option<&str>, name: Option<&str>, data: &[u8]) -> Option<Syntax> {
+    media_type
+        .and_then(from_media_type)
+        .or_else(|| name.and_then(from_extension))
+        .or_else(|| from_content(data))
+}
+
+/// Whether `txt` seems to contain a TriG graph block,
+/// *i.e.* an opening brace outside IRIs and literals.
+fn has_graph_block(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => return true,
+            '<' => skip_until(&mut chars, '>'),
+            '"' | '\'' => skip_until(&mut chars, c),
+            '#' => skip_until(&mut chars, '\n'),
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Count the terms in a line-based statement (ending with a dot),
+/// or return `None` if `line` does not look like a line-based statement.
+fn count_terms(line: &str) -> Option<usize> {
+    let mut chars = line.chars().peekable();
+    let mut count = 0;
+    loop {
+        match chars.next()? {
+            c if c.is_whitespace() => (),
+            '.' => return Some(count),
+            '<' => {
+                skip_until(&mut chars, '>');
+                count += 1;
+            }
+            '"' => {
+                skip_until(&mut chars, '"');
+                // datatype or language tag
+                match chars.peek() {
+                    Some('@') => skip_word(&mut chars),
+                    Some('^') => {
+                        chars.next();
+                        chars.next();
+                        chars.next();
+                        skip_until(&mut chars, '>');
+                    }
+                    _ => (),
+                }
+                count += 1;
+            }
+            '_' => {
+                skip_word(&mut chars);
+                count += 1;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn skip_until<I: Iterator<Item = char>>(chars: &mut I, end: char) {
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == end {
+            return;
+        }
+    }
+}
+
+fn skip_word(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.peek() {
+        if c.is_whitespace() || (*c == '.' && !continues_word(chars)) {
+            return;
+        }
+        chars.next();
+    }
+}
+
+/// Whether the dot at the head of `chars` is followed by a name character
+/// (in which case it belongs to the current word).
+fn continues_word(chars: &Peekable<Chars>) -> bool {
+    let mut lookahead = chars.clone();
+    lookahead.next();
+    matches!(lookahead.next(), Some(c) if c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("foo.nt", Some(NTriples))]
+    #[test_case("/some/dir/foo.TTL", Some(Turtle))]
+    #[test_case("C:\\some\\dir\\foo.nq", Some(NQuads))]
+    #[test_case("http://example.org/foo.trig#bar", Some(TriG))]
+    #[test_case("http://example.org/foo.ttl/bar", None)]
+    #[test_case("dump.ttl.bz2", Some(Turtle))]
+    #[test_case("foo.gz", None)]
+    #[test_case("foo", None)]
+    fn extension(name: &str, expected: Option<Syntax>) {
+        assert_eq!(from_extension(name), expected);
+    }
+
+    #[test_case("application/n-triples", Some(NTriples))]
+    #[test_case("Text/Turtle;charset=UTF-8", Some(Turtle))]
+    #[test_case("application/n-quads", Some(NQuads))]
+    #[test_case("application/trig", Some(TriG))]
+    #[test_case("application/octet-stream", None)]
+    fn media_type(media_type: &str, expected: Option<Syntax>) {
+        assert_eq!(from_media_type(media_type), expected);
+    }
+
+    #[test_case("@prefix : <http://example.org/>.\n:a :b :c.", Some(Turtle); "turtle prefix")]
+    #[test_case("# comment\nBASE <http://example.org/>\n<a> <b> <c>.", Some(Turtle); "turtle base")]
+    #[test_case("PREFIX : <http://example.org/>\n:g { :a :b :c }", Some(TriG); "trig prefix")]
+    #[test_case("{ <http://a/> <http://b/> <http://c/> }", Some(TriG); "trig default graph")]
+    #[test_case("<http://a/> <http://b/> \"c\\\" {\".\n", Some(NTriples); "nt literal with brace")]
+    #[test_case("_:b1 <http://b/> \"c\"@en-us.\n", Some(NTriples); "nt lang")]
+    #[test_case("<http://a/> <http://b/> \"1\"^^<http://c/> _:g.\n", Some(NQuads); "nq")]
+    #[test_case("<http://a/> <http://b/> <http://c/>, <http://d/>.", Some(Turtle); "turtle list")]
+    #[test_case("   \n\n", None; "empty")]
+    #[test_case("hello world", None; "garbage")]
+    fn content(data: &str, expected: Option<Syntax>) {
+        assert_eq!(from_content(data.as_bytes()), expected);
+    }
+
+    #[test]
+    fn guess_precedence() {
+        let data = b"<http://a/> <http://b/> <http://c/>.";
+        assert_eq!(guess(Some("text/turtle"), Some("x.nq"), data), Some(Turtle));
+        assert_eq!(guess(Some("text/plain"), Some("x.nq"), data), Some(NQuads));
+        assert_eq!(guess(None, Some("x.txt"), data), Some(NTriples));
+        assert_eq!(guess(None, None, b""), None);
+    }
+}
@@ -0,0 +1,499 @@
+//! Text generation from graphs, with templates navigating the graph.
+//!
+//! A [`Template`] is a text (e.g. HTML or Markdown) containing tags,
+//! in a syntax inspired by [Handlebars],
+//! whose expressions navigate the graph from the rendered resource:
+//! * `{{ resource.foaf:name }}` is replaced by the values of `foaf:name` for the resource
+//!   (separated by `, ` if there are several values);
+//!   in HTML templates (see [`Template::set_html`]), the values are escaped,
+//!   unless the tag is written with triple braces (`{{{ resource.foaf:name }}}`);
+//! * `{{#each resource.foaf:knows as friend}}...{{/each}}`
+//!   repeats its content for each value of the expression,
+//!   which is available as the variable `friend` inside the block;
+//! * `{{#if resource.foaf:mbox}}...{{else}}...{{/if}}`
+//!   renders its first part if the expression has a value, the second part otherwise.
+//!
+//! An expression is a variable (`resource` for the rendered resource,
+//! or a variable introduced by `#each`), followed by a sequence of predicates,
+//! written as prefixed names or as IRIs between angle brackets,
+//! each of them preceded by a dot.
+//! A predicate can be prefixed with `^` to follow it backward.
+//! Values are rendered as their lexical form (for literals) or IRI (for IRIs),
+//! and are sorted, so that the output is deterministic.
+//!
+//! Only the tags above are supported: this is not a Handlebars implementation,
+//! and there are no helpers, partials or comments.
+//!
+//! This module requires the `template` feature.
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::template::Template;
+//! use sophia::term::BoxTerm;
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix foaf: <http://xmlns.com/foaf/0.1/>.
+//!     <tag:alice> foaf:name "Alice"; foaf:knows <tag:bob>, <tag:carol>.
+//!     <tag:bob> foaf:name "Bob".
+//!     <tag:carol> foaf:name "Carol".
+//! "#).collect_triples()?;
+//!
+//! let template = Template::new(
+//!     "# {{ resource.foaf:name }}\n{{#each resource.foaf:knows as f}}* {{ f.foaf:name }}\n{{/each}}",
+//!     &[("foaf", "http://xmlns.com/foaf/0.1/")],
+//! )?;
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! assert_eq!(template.render(&g, &alice)?, "# Alice\n* Bob\n* Carol\n");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Handlebars]: https://handlebarsjs.com/
+//! [`Template`]: struct.Template.html
+//! [`Template::set_html`]: struct.Template.html#method.set_html
+
+use std::collections::BTreeSet;
+
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+/// The name of the variable bound to the rendered resource.
+pub const ROOT: &str = "resource";
+
+/// An error raised when parsing a [`Template`](struct.Template.html).
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    /// A tag is not closed.
+    #[error("Unclosed tag at byte {0}")]
+    UnclosedTag(usize),
+    /// A block is not closed.
+    #[error("Unclosed block {{{{#{0}}}}}")]
+    UnclosedBlock(String),
+    /// A tag is not allowed here (e.g. `{{/if}}` inside an `#each` block).
+    #[error("Unexpected tag {{{{{tag}}}}} at byte {offset}")]
+    UnexpectedTag {
+        /// The content of the tag
+        tag: String,
+        /// The byte offset of the tag
+        offset: usize,
+    },
+    /// An expression is malformed.
+    #[error("Invalid expression {0:?}")]
+    InvalidExpression(String),
+    /// An expression uses an undeclared prefix.
+    #[error("Unknown prefix {0:?}")]
+    UnknownPrefix(String),
+    /// An expression uses an unbound variable.
+    #[error("Unknown variable {0:?}")]
+    UnknownVariable(String),
+}
+
+/// A template rendering resources of a graph as text.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+    html: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Text(String),
+    Value(Expr, bool),
+    Each(Expr, String, Vec<Node>),
+    If(Expr, Vec<Node>, Vec<Node>),
+}
+
+#[derive(Clone, Debug)]
+struct Expr {
+    var: String,
+    /// predicates, with whether they are followed backward
+    steps: Vec<(BoxTerm, bool)>,
+}
+
+impl Template {
+    /// Parse `txt` as a template,
+    /// where prefixed names are resolved with `prefixes` (pairs of prefix and namespace).
+    pub fn new(txt: &str, prefixes: &[(&str, &str)]) -> Result<Self, TemplateError> {
+        let mut parser = Parser {
+            txt,
+            pos: 0,
+            prefixes,
+            vars: vec![ROOT.to_string()],
+        };
+        let (nodes, end) = parser.parse_nodes()?;
+        match end {
+            None => Ok(Template { nodes, html: false }),
+            Some((tag, offset)) => Err(TemplateError::UnexpectedTag { tag, offset }),
+        }
+    }
+
+    /// Whether values are escaped for HTML (false by default).
+    pub fn html(&self) -> bool {
+        self.html
+    }
+
+    /// Set whether values are escaped for HTML.
+    pub fn set_html(&mut self, html: bool) -> &mut Self {
+        self.html = html;
+        self
+    }
+
+    /// Render this template for `resource` in `graph`.
+    pub fn render<G, T>(&self, graph: &G, resource: &T) -> GResult<G, String>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut env = vec![(ROOT.to_string(), BoxTerm::copy(resource))];
+        let mut out = String::new();
+        self.render_nodes(&self.nodes, graph, &mut env, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_nodes<G: Graph>(
+        &self,
+        nodes: &[Node],
+        graph: &G,
+        env: &mut Vec<(String, BoxTerm)>,
+        out: &mut String,
+    ) -> GResult<G, ()> {
+        for node in nodes {
+            match node {
+                Node::Text(txt) => out.push_str(txt),
+                Node::Value(expr, raw) => {
+                    let values = eval(expr, graph, env)?;
+                    for (i, v) in values.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(", ");
+                        }
+                        if self.html && !raw {
+                            escape_html(&v.value(), out);
+                        } else {
+                            out.push_str(&v.value());
+                        }
+                    }
+                }
+                Node::Each(expr, var, body) => {
+                    for v in eval(expr, graph, env)? {
+                        env.push((var.clone(), v));
+                        let res = self.render_nodes(body, graph, env, out);
+                        env.pop();
+                        res?;
+                    }
+                }
+                Node::If(expr, then, otherwise) => {
+                    let branch = if eval(expr, graph, env)?.is_empty() {
+                        otherwise
+                    } else {
+                        then
+                    };
+                    self.render_nodes(branch, graph, env, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The sorted values of `expr`.
+fn eval<G: Graph>(expr: &Expr, graph: &G, env: &[(String, BoxTerm)]) -> GResult<G, Vec<BoxTerm>> {
+    // variables are checked at parse time
+    let (_, start) = env
+        .iter()
+        .rev()
+        .find(|(name, _)| name == &expr.var)
+        .unwrap();
+    let mut current: BTreeSet<BoxTerm> = Some(start.clone()).into_iter().collect();
+    for (pred, inverse) in &expr.steps {
+        let mut next = BTreeSet::new();
+        for node in &current {
+            if *inverse {
+                for t in graph.triples_with_po(pred, node) {
+                    next.insert(BoxTerm::copy(t?.s()));
+                }
+            } else {
+                for t in graph.triples_with_sp(node, pred) {
+                    next.insert(BoxTerm::copy(t?.o()));
+                }
+            }
+        }
+        current = next;
+    }
+    Ok(current.into_iter().collect())
+}
+
+fn escape_html(txt: &str, out: &mut String) {
+    for c in txt.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// A closing or `else` tag, with its byte offset.
+type Tag = (String, usize);
+
+struct Parser<'a> {
+    txt: &'a str,
+    pos: usize,
+    prefixes: &'a [(&'a str, &'a str)],
+    vars: Vec<String>,
+}
+
+impl Parser<'_> {
+    /// Parse nodes until the end of the text or a closing/else tag,
+    /// which is returned with its offset.
+    fn parse_nodes(&mut self) -> Result<(Vec<Node>, Option<Tag>), TemplateError> {
+        let mut nodes = vec![];
+        loop {
+            let rest = &self.txt[self.pos..];
+            let start = match rest.find("{{") {
+                None => {
+                    if !rest.is_empty() {
+                        nodes.push(Node::Text(rest.to_string()));
+                    }
+                    self.pos = self.txt.len();
+                    return Ok((nodes, None));
+                }
+                Some(i) => self.pos + i,
+            };
+            if start > self.pos {
+                nodes.push(Node::Text(self.txt[self.pos..start].to_string()));
+            }
+            let raw = self.txt[start..].starts_with("{{{");
+            let (open, close) = if raw { (3, "}}}") } else { (2, "}}") };
+            let end = match self.txt[start + open..].find(close) {
+                None => return Err(TemplateError::UnclosedTag(start)),
+                Some(i) => start + open + i,
+            };
+            let tag = self.txt[start + open..end].trim();
+            self.pos = end + close.len();
+            if raw {
+                nodes.push(Node::Value(self.parse_expr(tag)?, true));
+            } else if let Some(block) = tag.strip_prefix('#') {
+                nodes.push(self.parse_block(block.trim(), start)?);
+            } else if tag.starts_with('/') || tag == "else" {
+                return Ok((nodes, Some((tag.to_string(), start))));
+            } else {
+                nodes.push(Node::Value(self.parse_expr(tag)?, false));
+            }
+        }
+    }
+
+    fn parse_block(&mut self, block: &str, offset: usize) -> Result<Node, TemplateError> {
+        let (kind, args) = block.split_at(block.find(char::is_whitespace).unwrap_or(block.len()));
+        let args = args.trim();
+        let unclosed = || TemplateError::UnclosedBlock(kind.to_string());
+        match kind {
+            "each" => {
+                let (expr, var) = match args.rsplit_once(" as ") {
+                    Some((expr, var)) if is_var_name(var.trim()) => (expr.trim(), var.trim()),
+                    _ => return Err(TemplateError::InvalidExpression(args.to_string())),
+                };
+                let expr = self.parse_expr(expr)?;
+                self.vars.push(var.to_string());
+                let (body, end) = self.parse_nodes()?;
+                self.vars.pop();
+                match end {
+                    Some((tag, _)) if tag == "/each" => Ok(Node::Each(expr, var.to_string(), body)),
+                    Some((tag, offset)) => Err(TemplateError::UnexpectedTag { tag, offset }),
+                    None => Err(unclosed()),
+                }
+            }
+            "if" => {
+                let expr = self.parse_expr(args)?;
+                let (then, end) = self.parse_nodes()?;
+                let (otherwise, end) = match end {
+                    Some((tag, _)) if tag == "else" => self.parse_nodes()?,
+                    end => (vec![], end),
+                };
+                match end {
+                    Some((tag, _)) if tag == "/if" => Ok(Node::If(expr, then, otherwise)),
+                    Some((tag, offset)) => Err(TemplateError::UnexpectedTag { tag, offset }),
+                    None => Err(unclosed()),
+                }
+            }
+            _ => Err(TemplateError::UnexpectedTag {
+                tag: format!("#{}", block),
+                offset,
+            }),
+        }
+    }
+
+    fn parse_expr(&self, txt: &str) -> Result<Expr, TemplateError> {
+        let invalid = || TemplateError::InvalidExpression(txt.to_string());
+        let mut parts = split_steps(txt).ok_or_else(invalid)?.into_iter();
+        let var = parts.next().ok_or_else(invalid)?;
+        if !is_var_name(var) {
+            return Err(invalid());
+        }
+        if !self.vars.iter().any(|v| v == var) {
+            return Err(TemplateError::UnknownVariable(var.to_string()));
+        }
+        let mut steps = vec![];
+        for part in parts {
+            let (part, inverse) = match part.strip_prefix('^') {
+                Some(part) => (part, true),
+                None => (part, false),
+            };
+            let iri = if let Some(iri) = part.strip_prefix('<') {
+                iri.strip_suffix('>').ok_or_else(invalid)?.to_string()
+            } else {
+                let (prefix, suffix) = part.split_once(':').ok_or_else(invalid)?;
+                let (_, ns) = self
+                    .prefixes
+                    .iter()
+                    .find(|(p, _)| *p == prefix)
+                    .ok_or_else(|| TemplateError::UnknownPrefix(prefix.to_string()))?;
+                format!("{}{}", ns, suffix)
+            };
+            steps.push((BoxTerm::new_iri(iri).map_err(|_| invalid())?, inverse));
+        }
+        Ok(Expr {
+            var: var.to_string(),
+            steps,
+        })
+    }
+}
+
+/// Split an expression on the dots that are not inside angle brackets.
+fn split_steps(txt: &str) -> Option<Vec<&str>> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut in_iri = false;
+    for (i, c) in txt.char_indices() {
+        match c {
+            '<' => in_iri = true,
+            '>' => in_iri = false,
+            '.' if !in_iri => {
+                parts.push(txt[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(txt[start..].trim());
+    (!in_iri && parts.iter().all(|p| !p.is_empty())).then_some(parts)
+}
+
+fn is_var_name(txt: &str) -> bool {
+    !txt.is_empty() && txt.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    const PREFIXES: &[(&str, &str)] = &[("ex", "tag:")];
+
+    fn data() -> FastGraph {
+        turtle::parse_str(
+            r#"
+            <tag:alice> <tag:name> "Alice <A>"; <tag:knows> <tag:bob>, <tag:carol>.
+            <tag:bob> <tag:name> "Bob"; <tag:mbox> <mailto:bob@example.org>.
+            <tag:carol> <tag:name> "Carol", "Caroline".
+            "#,
+        )
+        .collect_triples()
+        .unwrap()
+    }
+
+    fn render(template: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let alice = BoxTerm::new_iri("tag:alice")?;
+        Ok(Template::new(template, PREFIXES)?.render(&data(), &alice)?)
+    }
+
+    #[test]
+    fn values() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(render("Hello {{ resource.ex:name }}!")?, "Hello Alice <A>!");
+        assert_eq!(render("{{resource}}")?, "tag:alice");
+        assert_eq!(
+            render("{{ resource.<tag:knows>.ex:name }}")?,
+            "Bob, Carol, Caroline"
+        );
+        assert_eq!(render("[{{ resource.ex:unknown }}]")?, "[]");
+        assert_eq!(render("{{ resource.ex:knows.^ex:knows }}")?, "tag:alice");
+
+        let mut template =
+            Template::new("{{ resource.ex:name }}/{{{ resource.ex:name }}}", PREFIXES)?;
+        template.set_html(true);
+        let alice = BoxTerm::new_iri("tag:alice")?;
+        assert_eq!(
+            template.render(&data(), &alice)?,
+            "Alice &lt;A&gt;/Alice <A>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn blocks() -> Result<(), Box<dyn std::error::Error>> {
+        let out = render(
+            "{{#each resource.ex:knows as f}}\
+                {{ f.ex:name }}{{#if f.ex:mbox}} ({{ f.ex:mbox }}){{else}} -{{/if}};\
+            {{/each}}",
+        )?;
+        assert_eq!(out, "Bob (mailto:bob@example.org);Carol, Caroline -;");
+        let out = render(
+            "{{#each resource.ex:knows as f}}{{#each f.ex:name as n}}{{ n }}|{{/each}}{{/each}}",
+        )?;
+        assert_eq!(out, "Bob|Carol|Caroline|");
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let err = |txt| Template::new(txt, PREFIXES).unwrap_err();
+        assert!(matches!(
+            err("a {{ resource"),
+            TemplateError::UnclosedTag(2)
+        ));
+        assert!(matches!(
+            err("{{#if resource}}"),
+            TemplateError::UnclosedBlock(_)
+        ));
+        assert!(matches!(
+            err("{{#if resource}}{{/each}}"),
+            TemplateError::UnexpectedTag { offset: 16, .. }
+        ));
+        assert!(matches!(
+            err("{{ resource.foo:bar }}"),
+            TemplateError::UnknownPrefix(_)
+        ));
+        assert!(matches!(
+            err("{{ f.ex:name }}"),
+            TemplateError::UnknownVariable(_)
+        ));
+        assert!(matches!(
+            err("{{#each resource.ex:knows as f}}{{/each}}{{ f }}"),
+            TemplateError::UnknownVariable(_)
+        ));
+        assert!(matches!(
+            err("{{ resource..ex:p }}"),
+            TemplateError::InvalidExpression(_)
+        ));
+        assert!(matches!(
+            err("{{#each resource}}{{/each}}"),
+            TemplateError::InvalidExpression(_)
+        ));
+    }
+}
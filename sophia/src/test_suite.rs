@@ -0,0 +1,929 @@
+//! A harness for running W3C RDF test suites.
+//!
+//! The test suites of [N-Triples], [N-Quads], [Turtle] and [TriG]
+//! are described by *manifests*
+//! (`manifest.ttl` files using the [test manifest vocabulary]),
+//! which list tests, each having a type, an input file (`mf:action`)
+//! and possibly an expected result (`mf:result`).
+//! [`Manifest::load`] reads such a manifest from a local copy of the test suite,
+//! and [`Manifest::run`] runs all its tests against an [`Implementation`],
+//! returning a [`Report`] with the [`Outcome`] of each test.
+//!
+//! The [`Sophia`] implementation drives the parsers of this crate
+//! (and optionally its serializers, to check that parsed data survives a round-trip).
+//! Other crates can implement [`Implementation`]
+//! to run their own parsers and serializers against the same test suites.
+//!
+//! The JSON-LD test suite uses a JSON-LD manifest (`manifest.jsonld`),
+//! which can be parsed into a graph with a JSON-LD parser,
+//! then read with [`Manifest::from_graph`].
+//! Its `ToRDFTest`s are then recognized
+//! (its other tests are not about RDF, and are reported as [unsupported]).
+//!
+//! # Example
+//! ```
+//! use sophia::test_suite::{Manifest, Outcome, Sophia, Syntax};
+//! # let dir = std::env::temp_dir().join(format!("sophia_test_suite_doc_{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir)?;
+//! # std::fs::write(dir.join("manifest.ttl"), r#"
+//! #   @prefix mf: <http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#> .
+//! #   @prefix rdft: <http://www.w3.org/ns/rdftest#> .
+//! #   <> a mf:Manifest; mf:entries (<#t1> <#t2>).
+//! #   <#t1> a rdft:TestTurtleEval; mf:name "t1"; mf:action <t1.ttl>; mf:result <t1.nt>.
+//! #   <#t2> a rdft:TestTurtleNegativeSyntax; mf:name "t2"; mf:action <t2.ttl>.
+//! # "#)?;
+//! # std::fs::write(dir.join("t1.ttl"), "<s> <p> 'o'.")?;
+//! # std::fs::write(dir.join("t1.nt"), "<http://example.org/tests/s> <http://example.org/tests/p> \"o\".")?;
+//! # std::fs::write(dir.join("t2.ttl"), "<s> <p> <o>")?;
+//! # let path = dir.join("manifest.ttl");
+//!
+//! // path is the local copy of http://example.org/tests/manifest.ttl
+//! let manifest = Manifest::load(&path, "http://example.org/tests/manifest.ttl")?;
+//! assert_eq!(manifest.tests()[0].syntax, Some(Syntax::Turtle));
+//!
+//! let report = manifest.run(&Sophia::default());
+//! assert_eq!(report.passed(), 2);
+//! for result in report.results() {
+//!     if let Outcome::Fail(msg) = &result.outcome {
+//!         println!("{}: {}", result.test.iri, msg);
+//!     }
+//! }
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [N-Triples]: https://w3c.github.io/rdf-tests/rdf/rdf11/rdf-n-triples/
+//! [N-Quads]: https://w3c.github.io/rdf-tests/rdf/rdf11/rdf-n-quads/
+//! [Turtle]: https://w3c.github.io/rdf-tests/rdf/rdf11/rdf-turtle/
+//! [TriG]: https://w3c.github.io/rdf-tests/rdf/rdf11/rdf-trig/
+//! [test manifest vocabulary]: https://www.w3.org/2001/sw/DataAccess/tests/test-manifest
+//! [`Manifest::load`]: struct.Manifest.html#method.load
+//! [`Manifest::run`]: struct.Manifest.html#method.run
+//! [`Manifest::from_graph`]: struct.Manifest.html#method.from_graph
+//! [`Implementation`]: trait.Implementation.html
+//! [`Report`]: struct.Report.html
+//! [`Outcome`]: enum.Outcome.html
+//! [`Sophia`]: struct.Sophia.html
+//! [unsupported]: enum.TestType.html#variant.Unsupported
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sophia_api::dataset::isomorphic_datasets;
+use sophia_api::graph::Graph;
+use sophia_api::ns::{rdf, rdfs};
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::serializer::{QuadSerializer, Stringifier, TripleSerializer};
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::graph::inmem::FastGraph;
+use crate::parser::{nq, nq12::NQuads12Parser, nt, nt12::NTriples12Parser};
+use crate::parser::{trig::TriGParser, turtle::TurtleParser};
+use crate::serializer::nq::{NqConfig, NqSerializer};
+use crate::serializer::nt::{NtConfig, NtSerializer};
+use crate::serializer::turtle::TurtleSerializer;
+use crate::syntax::RdfVersion;
+
+/// The test manifest namespace.
+#[allow(missing_docs)]
+pub mod mf {
+    use sophia_api::namespace;
+    namespace!(
+        "http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#",
+        Manifest,
+        action,
+        entries,
+        include,
+        name,
+        result
+    );
+}
+
+/// The namespace of the JSON-LD test vocabulary.
+const JLD: &str = "https://w3c.github.io/json-ld-api/tests/vocab#";
+
+/// This error is raised when a manifest can not be read.
+#[derive(Debug, Error)]
+pub enum HarnessError {
+    /// A file could not be read.
+    #[error("Could not read {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    /// A manifest could not be parsed.
+    #[error("Could not parse manifest {0}: {1}")]
+    Parse(String, String),
+    /// A manifest is not well-formed.
+    #[error("Invalid manifest {0}: {1}")]
+    InvalidManifest(String, String),
+    /// An IRI is not in the local copy of the test suite.
+    #[error("{0} is not under the manifest directory")]
+    NotLocal(String),
+}
+
+/// A concrete syntax tested by a test suite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    /// N-Triples
+    NTriples,
+    /// N-Quads
+    NQuads,
+    /// Turtle
+    Turtle,
+    /// TriG
+    TriG,
+    /// JSON-LD
+    JsonLd,
+}
+
+impl Syntax {
+    /// Whether this syntax can describe quads, or only triples.
+    pub fn has_quads(&self) -> bool {
+        matches!(self, Syntax::NQuads | Syntax::TriG | Syntax::JsonLd)
+    }
+}
+
+/// What a test checks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestType {
+    /// The action must be parsed without error.
+    PositiveSyntax,
+    /// The action must be rejected by the parser.
+    NegativeSyntax,
+    /// The action must be parsed into a dataset isomorphic to the result
+    /// (in N-Triples or N-Quads).
+    Eval,
+    /// The action must be rejected by the parser
+    /// (though it is syntactically correct).
+    NegativeEval,
+    /// A test of an unsupported type, with the IRIs of its types.
+    Unsupported(Vec<String>),
+}
+
+/// A test, as described in a manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCase {
+    /// The IRI of this test
+    pub iri: String,
+    /// The name of this test (`mf:name`)
+    pub name: Option<String>,
+    /// The description of this test (`rdfs:comment`)
+    pub comment: Option<String>,
+    /// The syntax of the action of this test (if known)
+    pub syntax: Option<Syntax>,
+    /// The type of this test
+    pub test_type: TestType,
+    /// The IRI of the action of this test (`mf:action`)
+    pub action: Option<String>,
+    /// The IRI of the expected result of this test (`mf:result`)
+    pub result: Option<String>,
+}
+
+/// The quads produced by an [`Implementation`](trait.Implementation.html).
+pub type QuadSet = HashSet<([BoxTerm; 3], Option<BoxTerm>)>;
+
+/// An implementation of RDF concrete syntaxes, to be tested against test suites.
+pub trait Implementation {
+    /// Whether this implementation can parse `syntax`.
+    ///
+    /// Tests about unsupported syntaxes are skipped.
+    fn supports(&self, syntax: Syntax) -> bool;
+
+    /// Parse `input` in the given `syntax`, resolving relative IRIs against `base`.
+    ///
+    /// Errors are described by a message.
+    fn parse(&self, syntax: Syntax, input: &str, base: &str) -> Result<QuadSet, String>;
+
+    /// Serialize `quads` in the given `syntax`,
+    /// or return `None` if this implementation has no serializer for this syntax.
+    ///
+    /// When available, the output of the serializer is parsed again
+    /// in evaluation tests, and compared with the expected result.
+    /// The default implementation returns `None`.
+    fn serialize(&self, syntax: Syntax, quads: &QuadSet) -> Option<Result<String, String>> {
+        let _ = (syntax, quads);
+        None
+    }
+}
+
+/// The parsers and serializers of this crate, as an [`Implementation`](trait.Implementation.html).
+///
+/// JSON-LD is not supported,
+/// and round-trips are not checked for TriG (which has no serializer).
+///
+/// By default, the RDF 1.1 test suites are targeted.
+/// To run the [RDF 1.2 test suites], set the RDF version to [`RdfVersion::Rdf12`]:
+/// N-Triples and N-Quads are then parsed with the native [`nt12`] and [`nq12`] parsers,
+/// which support directional language-tagged strings (but not triple terms yet).
+///
+/// [RDF 1.2 test suites]: https://w3c.github.io/rdf-tests/rdf/rdf12/
+/// [`RdfVersion::Rdf12`]: ../syntax/enum.RdfVersion.html#variant.Rdf12
+/// [`nt12`]: ../parser/nt12/index.html
+/// [`nq12`]: ../parser/nq12/index.html
+#[derive(Clone, Debug)]
+pub struct Sophia {
+    round_trip: bool,
+    rdf_version: RdfVersion,
+}
+
+impl Default for Sophia {
+    fn default() -> Sophia {
+        Sophia {
+            round_trip: false,
+            rdf_version: RdfVersion::Rdf11,
+        }
+    }
+}
+
+impl Sophia {
+    /// Whether evaluation tests also check the serializers of this crate
+    /// (defaults to false).
+    pub fn round_trip(&self) -> bool {
+        self.round_trip
+    }
+
+    /// Set whether evaluation tests also check the serializers of this crate.
+    pub fn set_round_trip(&mut self, round_trip: bool) -> &mut Self {
+        self.round_trip = round_trip;
+        self
+    }
+
+    /// The version of RDF targeted by the tested parsers and serializers
+    /// (defaults to RDF 1.1).
+    pub fn rdf_version(&self) -> RdfVersion {
+        self.rdf_version
+    }
+
+    /// Set the version of RDF targeted by the tested parsers and serializers.
+    pub fn set_rdf_version(&mut self, rdf_version: RdfVersion) -> &mut Self {
+        self.rdf_version = rdf_version;
+        self
+    }
+}
+
+impl Implementation for Sophia {
+    fn supports(&self, syntax: Syntax) -> bool {
+        syntax != Syntax::JsonLd
+    }
+
+    fn parse(&self, syntax: Syntax, input: &str, base: &str) -> Result<QuadSet, String> {
+        let base = Some(base.to_string());
+        let version = self.rdf_version;
+        let triples: Result<Vec<[BoxTerm; 3]>, _> = match syntax {
+            Syntax::NTriples if version == RdfVersion::Rdf12 => NTriples12Parser {
+                version,
+                ..NTriples12Parser::default()
+            }
+            .parse_str(input)
+            .collect_triples()
+            .map_err(|e| e.to_string()),
+            Syntax::NTriples => nt::parse_str(input)
+                .collect_triples()
+                .map_err(|e| e.to_string()),
+            Syntax::Turtle => TurtleParser { base }
+                .parse_str(input)
+                .collect_triples()
+                .map_err(|e| e.to_string()),
+            Syntax::NQuads if version == RdfVersion::Rdf12 => {
+                return NQuads12Parser {
+                    version,
+                    ..NQuads12Parser::default()
+                }
+                .parse_str(input)
+                .collect_quads()
+                .map_err(|e| e.to_string())
+            }
+            Syntax::NQuads => {
+                return nq::parse_str(input)
+                    .collect_quads()
+                    .map_err(|e| e.to_string())
+            }
+            Syntax::TriG => {
+                return TriGParser { base }
+                    .parse_str(input)
+                    .collect_quads()
+                    .map_err(|e| e.to_string())
+            }
+            Syntax::JsonLd => return Err("JSON-LD is not supported".into()),
+        };
+        triples.map(|triples| triples.into_iter().map(|t| (t, None)).collect())
+    }
+
+    fn serialize(&self, syntax: Syntax, quads: &QuadSet) -> Option<Result<String, String>> {
+        if !self.round_trip {
+            return None;
+        }
+        let triples = || -> Vec<[BoxTerm; 3]> { quads.iter().map(|(t, _)| t.clone()).collect() };
+        let result = match syntax {
+            Syntax::NTriples => NtSerializer::new_stringifier_with_config(
+                NtConfig::default()
+                    .set_rdf_version(self.rdf_version)
+                    .clone(),
+            )
+            .serialize_graph(&triples())
+            .map(|s| s.to_string())
+            .map_err(|e| e.to_string()),
+            Syntax::Turtle => TurtleSerializer::new_stringifier()
+                .serialize_graph(&triples())
+                .map(|s| s.to_string())
+                .map_err(|e| e.to_string()),
+            Syntax::NQuads => NqSerializer::new_stringifier_with_config(
+                NqConfig::default()
+                    .set_rdf_version(self.rdf_version)
+                    .clone(),
+            )
+            .serialize_dataset(quads)
+            .map(|s| s.to_string())
+            .map_err(|e| e.to_string()),
+            Syntax::TriG | Syntax::JsonLd => return None,
+        };
+        Some(result)
+    }
+}
+
+/// The outcome of a test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The implementation passed the test.
+    Pass,
+    /// The implementation failed the test, for the given reason.
+    Fail(String),
+    /// The test was not run, for the given reason.
+    Skip(String),
+}
+
+/// A test and its outcome.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    /// The test
+    pub test: TestCase,
+    /// Its outcome
+    pub outcome: Outcome,
+}
+
+/// The results of running the tests of a manifest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    results: Vec<TestResult>,
+}
+
+impl Report {
+    /// All the results, in the order of the manifest.
+    pub fn results(&self) -> &[TestResult] {
+        &self.results
+    }
+
+    /// The results of the failed tests.
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> + '_ {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.outcome, Outcome::Fail(_)))
+    }
+
+    /// The number of passed tests.
+    pub fn passed(&self) -> usize {
+        self.count(|o| o == &Outcome::Pass)
+    }
+
+    /// The number of failed tests.
+    pub fn failed(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Fail(_)))
+    }
+
+    /// The number of skipped tests.
+    pub fn skipped(&self) -> usize {
+        self.count(|o| matches!(o, Outcome::Skip(_)))
+    }
+
+    fn count<F: Fn(&Outcome) -> bool>(&self, f: F) -> usize {
+        self.results.iter().filter(|r| f(&r.outcome)).count()
+    }
+}
+
+/// A test manifest, and the local directory containing the test files.
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    iri: String,
+    dir: PathBuf,
+    tests: Vec<TestCase>,
+}
+
+impl Manifest {
+    /// Read the Turtle manifest at `path`,
+    /// which is the local copy of the manifest published at `iri`.
+    ///
+    /// Included manifests (`mf:include`) are also loaded,
+    /// and their tests are appended to the tests of this manifest.
+    /// They, and all the files of the tests,
+    /// must be in the same directory as `path` (or its subdirectories)
+    /// with the same relative paths as their IRIs relative to `iri`.
+    pub fn load<P: AsRef<Path>>(path: P, iri: &str) -> Result<Manifest, HarnessError> {
+        let path = path.as_ref();
+        let txt = fs::read_to_string(path).map_err(|e| HarnessError::Io(path.into(), e))?;
+        let g: FastGraph = TurtleParser {
+            base: Some(iri.to_string()),
+        }
+        .parse_str(&txt)
+        .collect_triples()
+        .map_err(|e| HarnessError::Parse(iri.to_string(), e.to_string()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        let mut manifest = Manifest::from_graph(&g, iri, dir)?;
+        for included in included_manifests(&g, iri)? {
+            let path = manifest.local_path(&included)?;
+            let sub = Manifest::load(path, &included)?;
+            manifest.tests.extend(sub.tests);
+        }
+        Ok(manifest)
+    }
+
+    /// Read the manifest identified by `iri` in graph `g`,
+    /// the test files being in the local directory `dir`.
+    ///
+    /// Included manifests (`mf:include`) are ignored.
+    pub fn from_graph<G, P>(g: &G, iri: &str, dir: P) -> Result<Manifest, HarnessError>
+    where
+        G: Graph,
+        P: Into<PathBuf>,
+    {
+        let invalid = |msg: &str| HarnessError::InvalidManifest(iri.to_string(), msg.to_string());
+        let manifest = BoxTerm::new_iri(iri).map_err(|e| invalid(&e.to_string()))?;
+        let tests = match object(g, &manifest, &mf::entries) {
+            None => vec![],
+            Some(entries) => list_items(g, &entries)
+                .ok_or_else(|| invalid("mf:entries is not a well-formed list"))?
+                .iter()
+                .map(|t| read_test(g, t))
+                .collect(),
+        };
+        Ok(Manifest {
+            iri: iri.to_string(),
+            dir: dir.into(),
+            tests,
+        })
+    }
+
+    /// The IRI of this manifest.
+    pub fn iri(&self) -> &str {
+        &self.iri
+    }
+
+    /// The tests of this manifest.
+    pub fn tests(&self) -> &[TestCase] {
+        &self.tests
+    }
+
+    /// The local path of the file published at `iri`.
+    pub fn local_path(&self, iri: &str) -> Result<PathBuf, HarnessError> {
+        let base = &self.iri[..self.iri.rfind('/').map(|i| i + 1).unwrap_or(0)];
+        let relative = iri
+            .strip_prefix(base)
+            .filter(|rel| !rel.is_empty() && !rel.split('/').any(|part| part == ".."))
+            .ok_or_else(|| HarnessError::NotLocal(iri.to_string()))?;
+        let mut path = self.dir.clone();
+        path.extend(relative.split('/'));
+        Ok(path)
+    }
+
+    /// Run all the tests of this manifest against `implementation`.
+    pub fn run<I: Implementation + ?Sized>(&self, implementation: &I) -> Report {
+        let results = self
+            .tests
+            .iter()
+            .map(|test| TestResult {
+                test: test.clone(),
+                outcome: self.run_test(test, implementation),
+            })
+            .collect();
+        Report { results }
+    }
+
+    /// Run the given test against `implementation`.
+    pub fn run_test<I: Implementation + ?Sized>(
+        &self,
+        test: &TestCase,
+        implementation: &I,
+    ) -> Outcome {
+        let syntax = match (&test.test_type, test.syntax) {
+            (TestType::Unsupported(_), _) | (_, None) => {
+                return Outcome::Skip("unsupported test type".into())
+            }
+            (_, Some(syntax)) if !implementation.supports(syntax) => {
+                return Outcome::Skip(format!("unsupported syntax {:?}", syntax))
+            }
+            (_, Some(syntax)) => syntax,
+        };
+        let action = match &test.action {
+            Some(action) => action,
+            None => return Outcome::Fail("test has no action".into()),
+        };
+        let input = match self.read(action) {
+            Ok(input) => input,
+            Err(e) => return Outcome::Fail(e.to_string()),
+        };
+        let parsed = implementation.parse(syntax, &input, action);
+        match (&test.test_type, parsed) {
+            (TestType::PositiveSyntax, Ok(_)) => Outcome::Pass,
+            (TestType::NegativeSyntax, Err(_)) | (TestType::NegativeEval, Err(_)) => Outcome::Pass,
+            (TestType::NegativeSyntax, Ok(_)) | (TestType::NegativeEval, Ok(_)) => {
+                Outcome::Fail("invalid input was accepted".into())
+            }
+            (_, Err(msg)) => Outcome::Fail(msg),
+            (_, Ok(quads)) => self.check_eval(test, syntax, quads, implementation),
+        }
+    }
+
+    fn check_eval<I: Implementation + ?Sized>(
+        &self,
+        test: &TestCase,
+        syntax: Syntax,
+        quads: QuadSet,
+        implementation: &I,
+    ) -> Outcome {
+        let result = match &test.result {
+            Some(result) => result,
+            None => return Outcome::Fail("evaluation test has no result".into()),
+        };
+        let result_syntax = if syntax.has_quads() {
+            Syntax::NQuads
+        } else {
+            Syntax::NTriples
+        };
+        let expected = match self
+            .read(result)
+            .map_err(|e| e.to_string())
+            .and_then(|txt| implementation.parse(result_syntax, &txt, result))
+        {
+            Ok(expected) => expected,
+            Err(msg) => return Outcome::Fail(format!("could not read result: {}", msg)),
+        };
+        if !isomorphic_datasets(&quads, &expected).unwrap_or(false) {
+            return Outcome::Fail("parsed data is not isomorphic to the result".into());
+        }
+        match implementation.serialize(syntax, &quads) {
+            None => Outcome::Pass,
+            Some(Err(msg)) => Outcome::Fail(format!("could not serialize: {}", msg)),
+            Some(Ok(serialized)) => {
+                match implementation.parse(syntax, &serialized, test.action.as_ref().unwrap()) {
+                    Err(msg) => Outcome::Fail(format!("could not parse serialization: {}", msg)),
+                    Ok(reparsed) if isomorphic_datasets(&reparsed, &expected).unwrap_or(false) => {
+                        Outcome::Pass
+                    }
+                    Ok(_) => Outcome::Fail("serialization is not isomorphic to the result".into()),
+                }
+            }
+        }
+    }
+
+    fn read(&self, iri: &str) -> Result<String, HarnessError> {
+        let path = self.local_path(iri)?;
+        fs::read_to_string(&path).map_err(|e| HarnessError::Io(path, e))
+    }
+}
+
+/// The first object of `s` and `p` in `g`, if any.
+fn object<G, P>(g: &G, s: &BoxTerm, p: &P) -> Option<BoxTerm>
+where
+    G: Graph,
+    P: TTerm + ?Sized,
+{
+    g.triples_with_sp(s, p)
+        .filter_map(Result::ok)
+        .map(|t| BoxTerm::copy(t.o()))
+        .next()
+}
+
+/// The lexical form of the first literal object of `s` and `p` in `g`, if any.
+fn string<G, P>(g: &G, s: &BoxTerm, p: &P) -> Option<String>
+where
+    G: Graph,
+    P: TTerm + ?Sized,
+{
+    object(g, s, p)
+        .filter(|o| o.kind() == TermKind::Literal)
+        .map(|o| o.value().to_string())
+}
+
+/// The IRI of the first IRI object of `s` and `p` in `g`, if any.
+fn iri<G, P>(g: &G, s: &BoxTerm, p: &P) -> Option<String>
+where
+    G: Graph,
+    P: TTerm + ?Sized,
+{
+    object(g, s, p)
+        .filter(|o| o.kind() == TermKind::Iri)
+        .map(|o| o.value().to_string())
+}
+
+/// The items of the RDF list starting at `head`, or None if it is not well-formed.
+fn list_items<G: Graph>(g: &G, head: &BoxTerm) -> Option<Vec<BoxTerm>> {
+    let mut items = vec![];
+    let mut visited = HashSet::new();
+    let mut node = head.clone();
+    while rdf::nil != node {
+        if !visited.insert(node.clone()) {
+            return None; // cyclic list
+        }
+        items.push(object(g, &node, &rdf::first)?);
+        node = object(g, &node, &rdf::rest)?;
+    }
+    Some(items)
+}
+
+fn included_manifests<G: Graph>(g: &G, iri: &str) -> Result<Vec<String>, HarnessError> {
+    let manifest = BoxTerm::new_iri(iri)
+        .map_err(|e| HarnessError::InvalidManifest(iri.to_string(), e.to_string()))?;
+    match object(g, &manifest, &mf::include) {
+        None => Ok(vec![]),
+        Some(list) => list_items(g, &list)
+            .ok_or_else(|| {
+                HarnessError::InvalidManifest(
+                    iri.to_string(),
+                    "mf:include is not a well-formed list".into(),
+                )
+            })
+            .map(|items| items.iter().map(|i| i.value().to_string()).collect()),
+    }
+}
+
+fn read_test<G: Graph>(g: &G, test: &BoxTerm) -> TestCase {
+    let types: Vec<String> = g
+        .triples_with_sp(test, &rdf::type_)
+        .filter_map(Result::ok)
+        .map(|t| t.o().value().to_string())
+        .collect();
+    let (syntax, test_type) = test_kind(&types);
+    TestCase {
+        iri: test.value().to_string(),
+        name: string(g, test, &mf::name),
+        comment: string(g, test, &rdfs::comment),
+        syntax,
+        test_type,
+        action: iri(g, test, &mf::action),
+        result: iri(g, test, &mf::result),
+    }
+}
+
+/// The syntax and type of a test, given the IRIs of its types.
+fn test_kind(types: &[String]) -> (Option<Syntax>, TestType) {
+    const RDFT: &str = "http://www.w3.org/ns/rdftest#";
+    for t in types {
+        let name = match t.strip_prefix(RDFT) {
+            Some(name) => name,
+            None => continue,
+        };
+        let (syntax, rest) = match () {
+            _ if name.starts_with("TestNTriples") => (Syntax::NTriples, &name[12..]),
+            _ if name.starts_with("TestNQuads") => (Syntax::NQuads, &name[10..]),
+            _ if name.starts_with("TestTurtle") => (Syntax::Turtle, &name[10..]),
+            _ if name.starts_with("TestTrig") => (Syntax::TriG, &name[8..]),
+            _ => continue,
+        };
+        let test_type = match rest {
+            "PositiveSyntax" => TestType::PositiveSyntax,
+            "NegativeSyntax" => TestType::NegativeSyntax,
+            "Eval" => TestType::Eval,
+            "NegativeEval" => TestType::NegativeEval,
+            _ => continue,
+        };
+        return (Some(syntax), test_type);
+    }
+    let is_jld = |name: &str| types.iter().any(|t| t.strip_prefix(JLD) == Some(name));
+    if is_jld("ToRDFTest") {
+        if is_jld("PositiveEvaluationTest") {
+            return (Some(Syntax::JsonLd), TestType::Eval);
+        }
+        if is_jld("NegativeEvaluationTest") {
+            return (Some(Syntax::JsonLd), TestType::NegativeEval);
+        }
+        if is_jld("PositiveSyntaxTest") {
+            return (Some(Syntax::JsonLd), TestType::PositiveSyntax);
+        }
+    }
+    (None, TestType::Unsupported(types.to_vec()))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const IRI: &str = "http://example.org/tests/manifest.ttl";
+
+    /// A temporary directory containing the given files.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str, files: &[(&str, &str)]) -> TestDir {
+            let dir = std::env::temp_dir().join(format!(
+                "sophia_test_suite_{}_{}",
+                name,
+                std::process::id()
+            ));
+            for (path, content) in files {
+                let path = dir.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            TestDir(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const MANIFEST: &str = r#"
+        @prefix mf: <http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        @prefix rdft: <http://www.w3.org/ns/rdftest#> .
+        <> a mf:Manifest;
+            mf:entries (<#pos> <#neg> <#eval> <#bad-eval> <#trig> <#other>);
+            mf:include (<sub/manifest.ttl>).
+        <#pos> a rdft:TestTurtlePositiveSyntax;
+            mf:name "pos"; rdfs:comment "a positive syntax test";
+            mf:action <pos.ttl>.
+        <#neg> a rdft:TestTurtleNegativeSyntax; mf:action <neg.ttl>.
+        <#eval> a rdft:TestTurtleEval; mf:action <eval.ttl>; mf:result <eval.nt>.
+        <#bad-eval> a rdft:TestTurtleEval; mf:action <eval.ttl>; mf:result <bad.nt>.
+        <#trig> a rdft:TestTrigEval; mf:action <eval.trig>; mf:result <eval.nq>.
+        <#other> a mf:Something; mf:action <pos.ttl>.
+    "#;
+
+    const SUB_MANIFEST: &str = r#"
+        @prefix mf: <http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#> .
+        @prefix rdft: <http://www.w3.org/ns/rdftest#> .
+        <> a mf:Manifest; mf:entries (<#nt>).
+        <#nt> a rdft:TestNTriplesNegativeSyntax; mf:action <rel.nt>.
+    "#;
+
+    fn make_suite(name: &str) -> TestDir {
+        TestDir::new(
+            name,
+            &[
+                ("manifest.ttl", MANIFEST),
+                ("pos.ttl", "<s> <p> <o>."),
+                ("neg.ttl", "<s> <p> <o>"),
+                ("eval.ttl", "@prefix : <#>. :s :p [ :q 42 ]."),
+                (
+                    "eval.nt",
+                    "<http://example.org/tests/eval.ttl#s> <http://example.org/tests/eval.ttl#p> _:b.\n\
+                     _:b <http://example.org/tests/eval.ttl#q> \"42\"^^<http://www.w3.org/2001/XMLSchema#integer>.\n",
+                ),
+                ("bad.nt", "<tag:s> <tag:p> <tag:o>.\n"),
+                ("eval.trig", "<tag:g> { <tag:s> <tag:p> 'o' }"),
+                ("eval.nq", "<tag:s> <tag:p> \"o\" <tag:g>.\n"),
+                ("sub/manifest.ttl", SUB_MANIFEST),
+                ("sub/rel.nt", "<s> <p> <o>.\n"),
+            ],
+        )
+    }
+
+    #[test]
+    fn load() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_suite("load");
+        let manifest = Manifest::load(dir.0.join("manifest.ttl"), IRI)?;
+        assert_eq!(manifest.iri(), IRI);
+        let tests = manifest.tests();
+        assert_eq!(tests.len(), 7);
+        assert_eq!(
+            tests[0],
+            TestCase {
+                iri: format!("{}#pos", IRI),
+                name: Some("pos".into()),
+                comment: Some("a positive syntax test".into()),
+                syntax: Some(Syntax::Turtle),
+                test_type: TestType::PositiveSyntax,
+                action: Some("http://example.org/tests/pos.ttl".into()),
+                result: None,
+            }
+        );
+        assert_eq!(tests[4].syntax, Some(Syntax::TriG));
+        assert_eq!(
+            tests[5].test_type,
+            TestType::Unsupported(vec![
+                "http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#Something".into()
+            ])
+        );
+        assert_eq!(tests[6].iri, "http://example.org/tests/sub/manifest.ttl#nt");
+        assert_eq!(tests[6].syntax, Some(Syntax::NTriples));
+        assert_eq!(
+            manifest.local_path("http://example.org/tests/sub/rel.nt")?,
+            dir.0.join("sub").join("rel.nt")
+        );
+        assert!(manifest.local_path("http://example.org/other").is_err());
+        assert!(manifest
+            .local_path("http://example.org/tests/../x")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn run() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_suite("run");
+        let manifest = Manifest::load(dir.0.join("manifest.ttl"), IRI)?;
+        let mut sophia = Sophia::default();
+        for round_trip in [false, true] {
+            sophia.set_round_trip(round_trip);
+            let report = manifest.run(&sophia);
+            let outcomes: Vec<_> = report.results().iter().map(|r| &r.outcome).collect();
+            assert_eq!(outcomes[0], &Outcome::Pass);
+            assert_eq!(outcomes[1], &Outcome::Pass);
+            assert_eq!(outcomes[2], &Outcome::Pass);
+            assert!(matches!(outcomes[3], Outcome::Fail(_)));
+            assert_eq!(outcomes[4], &Outcome::Pass);
+            assert!(matches!(outcomes[5], Outcome::Skip(_)));
+            assert_eq!(outcomes[6], &Outcome::Pass);
+            assert_eq!(
+                (report.passed(), report.failed(), report.skipped()),
+                (5, 1, 1)
+            );
+            let failures: Vec<_> = report.failures().map(|r| r.test.iri.as_str()).collect();
+            assert_eq!(
+                failures,
+                vec!["http://example.org/tests/manifest.ttl#bad-eval"]
+            );
+        }
+        Ok(())
+    }
+
+    /// An implementation rejecting everything.
+    struct Strict;
+
+    impl Implementation for Strict {
+        fn supports(&self, syntax: Syntax) -> bool {
+            syntax == Syntax::Turtle
+        }
+        fn parse(&self, _: Syntax, _: &str, _: &str) -> Result<QuadSet, String> {
+            Err("rejected".into())
+        }
+    }
+
+    #[test]
+    fn custom_implementation() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = make_suite("custom");
+        let manifest = Manifest::load(dir.0.join("manifest.ttl"), IRI)?;
+        let report = manifest.run(&Strict);
+        assert_eq!(
+            report.results()[0].outcome,
+            Outcome::Fail("rejected".into())
+        );
+        assert_eq!(report.results()[1].outcome, Outcome::Pass);
+        assert!(matches!(report.results()[4].outcome, Outcome::Skip(_)));
+        assert_eq!(
+            (report.passed(), report.failed(), report.skipped()),
+            (1, 3, 3)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn json_ld_manifest() -> Result<(), Box<dyn std::error::Error>> {
+        // a JSON-LD manifest, as it would be parsed by a JSON-LD parser
+        let g: FastGraph = TurtleParser {
+            base: Some("https://w3c.github.io/json-ld-api/tests/toRdf-manifest".into()),
+        }
+        .parse_str(
+            r#"
+            @prefix mf: <http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#> .
+            @prefix jld: <https://w3c.github.io/json-ld-api/tests/vocab#> .
+            <> mf:entries (<#t0001> <#te001>).
+            <#t0001> a jld:PositiveEvaluationTest, jld:ToRDFTest;
+                mf:action <toRdf/0001-in.jsonld>; mf:result <toRdf/0001-out.nq>.
+            <#te001> a jld:NegativeEvaluationTest, jld:ToRDFTest;
+                mf:action <toRdf/e001-in.jsonld>.
+            "#,
+        )
+        .collect_triples()?;
+        let manifest = Manifest::from_graph(
+            &g,
+            "https://w3c.github.io/json-ld-api/tests/toRdf-manifest",
+            "tests",
+        )?;
+        let tests = manifest.tests();
+        assert_eq!(tests.len(), 2);
+        assert_eq!(
+            (tests[0].syntax, &tests[0].test_type),
+            (Some(Syntax::JsonLd), &TestType::Eval)
+        );
+        assert_eq!(tests[1].test_type, TestType::NegativeEval);
+        assert_eq!(
+            manifest.local_path(tests[0].action.as_ref().unwrap())?,
+            Path::new("tests").join("toRdf").join("0001-in.jsonld")
+        );
+        // JSON-LD is not supported by Sophia
+        assert!(matches!(
+            manifest.run(&Sophia::default()).results()[0].outcome,
+            Outcome::Skip(_)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+//! Helpers shared by the unit tests of this crate.
+
+use sophia_term::BoxTerm;
+
+/// The IRI `tag:{suffix}`.
+pub(crate) fn t(suffix: &str) -> BoxTerm {
+    BoxTerm::new_iri(format!("tag:{}", suffix)).unwrap()
+}
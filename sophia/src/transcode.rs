@@ -0,0 +1,251 @@
+//! Streaming conversion of RDF data from one concrete syntax to another.
+//!
+//! [`transcode`] pipes the parser of the input syntax into the serializer of the output syntax,
+//! so that triples (or quads) are written as soon as they are parsed,
+//! and arbitrarily large files can be converted in constant memory.
+//! This is the core of any `rdf-convert` command-line tool.
+//!
+//! When converting from a quad syntax to a triple syntax,
+//! data in named graphs can not be represented in the output;
+//! it is either reported as an error (the default)
+//! or ignored (see [`TranscodeOptions::set_default_graph_only`]).
+//! When converting from a triple syntax to a quad syntax,
+//! all triples are written in the default graph.
+//!
+//! Note that the serializers currently provided by Sophia
+//! (N-Triples and N-Quads) support neither prefixes nor base IRIs,
+//! so relative IRIs and prefixed names in the input are always written in full.
+//!
+//! # Example
+//! ```
+//! use sophia::syntax::Syntax;
+//! use sophia::transcode::{transcode, TranscodeOptions};
+//!
+//! let input = "@prefix : <http://example.org/>. :alice :knows :bob, :carol.";
+//! let mut output = vec![];
+//! let n = transcode(
+//!     input.as_bytes(),
+//!     Syntax::Turtle,
+//!     &mut output,
+//!     Syntax::NTriples,
+//!     &TranscodeOptions::default(),
+//! )?;
+//! assert_eq!(n, 2);
+//! assert!(String::from_utf8(output)?.starts_with("<http://example.org/alice>"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`transcode`]: fn.transcode.html
+//! [`TranscodeOptions::set_default_graph_only`]: struct.TranscodeOptions.html#method.set_default_graph_only
+
+use std::io::{BufRead, Write};
+
+use sophia_api::triple::stream::StreamError;
+use thiserror::Error;
+
+use crate::parser::config::ParserConfig;
+use crate::syntax::dynamic::{DynError, DynTripleSource};
+use crate::syntax::Syntax;
+
+/// This error is raised when [`transcode`](fn.transcode.html) fails.
+#[derive(Debug, Error)]
+pub enum TranscodeError {
+    /// Sophia provides no serializer for the output syntax.
+    #[error("No serializer available for {0}")]
+    UnsupportedOutput(Syntax),
+    /// The input contains quads in a named graph, which can not be written in the output syntax.
+    #[error("Named graphs can not be written in {0}")]
+    NamedGraph(Syntax),
+    /// The input could not be parsed.
+    #[error("Parse error: {0}")]
+    Parse(DynError),
+    /// The output could not be written.
+    #[error("Serialization error: {0}")]
+    Serialize(DynError),
+}
+
+/// Options of [`transcode`](fn.transcode.html).
+#[derive(Clone, Debug, Default)]
+pub struct TranscodeOptions {
+    parser: ParserConfig,
+    default_graph_only: bool,
+}
+
+impl TranscodeOptions {
+    /// The configuration of the parser (including the base IRI of the input).
+    pub fn parser_config(&self) -> &ParserConfig {
+        &self.parser
+    }
+
+    /// Set the configuration of the parser (including the base IRI of the input).
+    pub fn set_parser_config(&mut self, parser: ParserConfig) -> &mut Self {
+        self.parser = parser;
+        self
+    }
+
+    /// Whether quads in named graphs are silently ignored when the output is a triple syntax
+    /// (rather than raising an error).
+    pub fn default_graph_only(&self) -> bool {
+        self.default_graph_only
+    }
+
+    /// Set whether quads in named graphs are silently ignored when the output is a triple syntax
+    /// (rather than raising an error).
+    pub fn set_default_graph_only(&mut self, default_graph_only: bool) -> &mut Self {
+        self.default_graph_only = default_graph_only;
+        self
+    }
+}
+
+/// Read `input` in the `in_syntax` syntax, and write it to `output` in the `out_syntax` syntax.
+///
+/// Return the number of triples (or quads) written.
+///
+/// See [module documentation](index.html).
+pub fn transcode<R, W>(
+    input: R,
+    in_syntax: Syntax,
+    output: W,
+    out_syntax: Syntax,
+    options: &TranscodeOptions,
+) -> Result<usize, TranscodeError>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut count = 0;
+    let result = if out_syntax.is_quad_syntax() {
+        let mut serializer = out_syntax
+            .quad_serializer(output)
+            .ok_or(TranscodeError::UnsupportedOutput(out_syntax))?;
+        let quads = in_syntax
+            .parse_quads_with(input, &options.parser)
+            .inspect(|res| count += res.is_ok() as usize);
+        serializer.serialize_dyn_quads(Box::new(quads))
+    } else {
+        let mut serializer = out_syntax
+            .triple_serializer(output)
+            .ok_or(TranscodeError::UnsupportedOutput(out_syntax))?;
+        let triples: DynTripleSource = if in_syntax.is_quad_syntax() {
+            let default_graph_only = options.default_graph_only;
+            Box::new(
+                in_syntax
+                    .parse_quads_with(input, &options.parser)
+                    .filter_map(move |res| match res {
+                        Ok((spo, None)) => Some(Ok(spo)),
+                        Ok(_) if default_graph_only => None,
+                        Ok(_) => Some(Err(DynError::new(NamedGraphFound))),
+                        Err(err) => Some(Err(err)),
+                    }),
+            )
+        } else {
+            in_syntax.parse_triples_with(input, &options.parser)
+        };
+        let triples = triples.inspect(|res| count += res.is_ok() as usize);
+        serializer.serialize_dyn_triples(Box::new(triples))
+    };
+    match result {
+        Ok(()) => Ok(count),
+        Err(StreamError::SourceError(err)) if err.0.is::<NamedGraphFound>() => {
+            Err(TranscodeError::NamedGraph(out_syntax))
+        }
+        Err(StreamError::SourceError(err)) => Err(TranscodeError::Parse(err)),
+        Err(StreamError::SinkError(err)) => Err(TranscodeError::Serialize(err)),
+    }
+}
+
+/// Raised by the source when a quad in a named graph is found,
+/// and converted into [`TranscodeError::NamedGraph`] by `transcode`.
+#[derive(Debug, Error)]
+#[error("named graph found")]
+struct NamedGraphFound;
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TRIG: &str = r#"
+        @prefix : <http://example.org/>.
+        :a :b :c.
+        :g { :d :e :f. }
+    "#;
+
+    fn run(
+        input: &str,
+        in_syntax: Syntax,
+        out_syntax: Syntax,
+        options: &TranscodeOptions,
+    ) -> Result<(usize, String), TranscodeError> {
+        let mut output = vec![];
+        let n = transcode(
+            input.as_bytes(),
+            in_syntax,
+            &mut output,
+            out_syntax,
+            options,
+        )?;
+        Ok((n, String::from_utf8(output).unwrap()))
+    }
+
+    #[test]
+    fn quads() -> Result<(), Box<dyn std::error::Error>> {
+        let options = TranscodeOptions::default();
+        let (n, nq) = run(TRIG, Syntax::TriG, Syntax::NQuads, &options)?;
+        assert_eq!(n, 2);
+        assert!(nq.contains(" <http://example.org/g>.\n"));
+        let (n, _) = run(&nq, Syntax::NQuads, Syntax::NQuads, &options)?;
+        assert_eq!(n, 2);
+        let (n, nq) = run(
+            "<tag:a> <tag:b> <tag:c>.",
+            Syntax::Turtle,
+            Syntax::NQuads,
+            &options,
+        )?;
+        assert_eq!(n, 1);
+        assert_eq!(nq, "<tag:a> <tag:b> <tag:c>.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn named_graphs() -> Result<(), Box<dyn std::error::Error>> {
+        let mut options = TranscodeOptions::default();
+        assert!(matches!(
+            run(TRIG, Syntax::TriG, Syntax::NTriples, &options),
+            Err(TranscodeError::NamedGraph(Syntax::NTriples))
+        ));
+        options.set_default_graph_only(true);
+        let (n, nt) = run(TRIG, Syntax::TriG, Syntax::NTriples, &options)?;
+        assert_eq!(n, 1);
+        assert!(!nt.contains("/d>"));
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let options = TranscodeOptions::default();
+        assert!(matches!(
+            run(TRIG, Syntax::TriG, Syntax::Turtle, &options),
+            Err(TranscodeError::UnsupportedOutput(Syntax::Turtle))
+        ));
+        assert!(matches!(
+            run(
+                "<tag:a> <tag:b>",
+                Syntax::Turtle,
+                Syntax::NTriples,
+                &options
+            ),
+            Err(TranscodeError::Parse(_))
+        ));
+
+        let mut config = ParserConfig::default();
+        config.set_base(Some("http://example.org/".into()));
+        let mut options = TranscodeOptions::default();
+        options.set_parser_config(config);
+        let (_, nt) = run("<a> <b> <c>.", Syntax::Turtle, Syntax::NTriples, &options).unwrap();
+        assert!(nt.starts_with("<http://example.org/a>"));
+    }
+}
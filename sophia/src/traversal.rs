@@ -0,0 +1,329 @@
+//! Traversal of graphs, viewed as a set of nodes connected by edges.
+//!
+//! A [`Traversal`] considers the subjects and objects of a [`Graph`] as nodes,
+//! and each triple as an edge labelled by its predicate.
+//! Edges can be followed forward (from subject to object), backward, or both,
+//! and can be restricted to a given set of predicates.
+//!
+//! It provides breadth-first ([`bfs`]) and depth-first ([`dfs`]) visits,
+//! and computes [`shortest_path`]s between two nodes,
+//! answering questions like "how is A connected to B".
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::term::BoxTerm;
+//! use sophia::traversal::{Direction, Traversal};
+//! use sophia::triple::stream::TripleSource;
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     <tag:alice> <tag:knows> <tag:bob>.
+//!     <tag:carol> <tag:knows> <tag:bob>.
+//!     <tag:carol> <tag:worksFor> <tag:acme>.
+//! "#).collect_triples()?;
+//!
+//! let alice = BoxTerm::new_iri("tag:alice")?;
+//! let acme = BoxTerm::new_iri("tag:acme")?;
+//! let path = Traversal::new(&g)
+//!     .direction(Direction::Both)
+//!     .shortest_path(&alice, &acme)?
+//!     .unwrap();
+//! assert_eq!(path.len(), 3);
+//! assert_eq!(path.nodes().nth(2).unwrap(), &BoxTerm::new_iri("tag:carol")?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Traversal`]: struct.Traversal.html
+//! [`Graph`]: ../graph/trait.Graph.html
+//! [`bfs`]: struct.Traversal.html#method.bfs
+//! [`dfs`]: struct.Traversal.html#method.dfs
+//! [`shortest_path`]: struct.Traversal.html#method.shortest_path
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use sophia_api::term::{term_eq, CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+
+/// The direction in which a [`Traversal`](struct.Traversal.html) follows triples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From subject to object.
+    Forward,
+    /// From object to subject.
+    Backward,
+    /// Both ways.
+    Both,
+}
+
+/// One step of a [`Path`](struct.Path.html).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Step {
+    /// The predicate of the triple followed by this step.
+    pub predicate: BoxTerm,
+    /// Whether the triple was followed backward (from object to subject).
+    pub inverse: bool,
+    /// The node reached by this step.
+    pub node: BoxTerm,
+}
+
+/// A path in a graph, made of a starting node and a sequence of steps.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Path {
+    /// The first node of this path.
+    pub start: BoxTerm,
+    /// The steps of this path.
+    pub steps: Vec<Step>,
+}
+
+impl Path {
+    /// The number of steps in this path.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether this path has no step (i.e. only contains its starting node).
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The last node of this path.
+    pub fn end(&self) -> &BoxTerm {
+        self.steps.last().map(|s| &s.node).unwrap_or(&self.start)
+    }
+
+    /// All the nodes of this path, from start to end.
+    pub fn nodes(&self) -> impl Iterator<Item = &BoxTerm> {
+        Some(&self.start)
+            .into_iter()
+            .chain(self.steps.iter().map(|s| &s.node))
+    }
+}
+
+/// Graph traversal utilities.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Traversal<'g, G> {
+    graph: &'g G,
+    direction: Direction,
+    predicates: Option<Vec<BoxTerm>>,
+}
+
+impl<'g, G: Graph> Traversal<'g, G> {
+    /// Traverse `graph` forward, following all predicates.
+    pub fn new(graph: &'g G) -> Self {
+        Traversal {
+            graph,
+            direction: Direction::Forward,
+            predicates: None,
+        }
+    }
+
+    /// Set the direction in which triples are followed.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Only follow the triples whose predicate is one of `predicates`.
+    pub fn predicates<I, T>(mut self, predicates: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: TTerm,
+    {
+        self.predicates = Some(predicates.into_iter().map(|p| BoxTerm::copy(&p)).collect());
+        self
+    }
+
+    /// The nodes reachable from `start` (including itself),
+    /// in breadth-first order.
+    pub fn bfs<T: TTerm + ?Sized>(&self, start: &T) -> GResult<G, Vec<BoxTerm>> {
+        let start = BoxTerm::copy(start);
+        let mut visited: HashSet<_> = Some(start.clone()).into_iter().collect();
+        let mut queue: VecDeque<_> = Some(start).into_iter().collect();
+        let mut order = vec![];
+        while let Some(node) = queue.pop_front() {
+            for step in self.steps_from(&node)? {
+                if visited.insert(step.node.clone()) {
+                    queue.push_back(step.node);
+                }
+            }
+            order.push(node);
+        }
+        Ok(order)
+    }
+
+    /// The nodes reachable from `start` (including itself),
+    /// in depth-first pre-order.
+    pub fn dfs<T: TTerm + ?Sized>(&self, start: &T) -> GResult<G, Vec<BoxTerm>> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![BoxTerm::copy(start)];
+        let mut order = vec![];
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node.clone()) {
+                continue;
+            }
+            let steps = self.steps_from(&node)?;
+            // pushed in reverse order, so that they are visited in order
+            stack.extend(
+                steps
+                    .into_iter()
+                    .rev()
+                    .map(|s| s.node)
+                    .filter(|n| !visited.contains(n)),
+            );
+            order.push(node);
+        }
+        Ok(order)
+    }
+
+    /// A shortest path from `from` to `to`, if any.
+    ///
+    /// If several paths have the same minimal length, which one is returned is unspecified.
+    pub fn shortest_path<T, U>(&self, from: &T, to: &U) -> GResult<G, Option<Path>>
+    where
+        T: TTerm + ?Sized,
+        U: TTerm + ?Sized,
+    {
+        let start = BoxTerm::copy(from);
+        // for each visited node, the step that reached it, and the node it came from
+        let mut parents: HashMap<BoxTerm, Option<(BoxTerm, Step)>> = HashMap::new();
+        parents.insert(start.clone(), None);
+        let mut queue: VecDeque<_> = Some(start.clone()).into_iter().collect();
+        let mut found = term_eq(&start, to).then(|| start.clone());
+        while found.is_none() {
+            let node = match queue.pop_front() {
+                Some(node) => node,
+                None => return Ok(None),
+            };
+            for step in self.steps_from(&node)? {
+                if parents.contains_key(&step.node) {
+                    continue;
+                }
+                let next = step.node.clone();
+                parents.insert(next.clone(), Some((node.clone(), step)));
+                if term_eq(&next, to) {
+                    found = Some(next);
+                    break;
+                }
+                queue.push_back(next);
+            }
+        }
+        let mut steps = vec![];
+        let mut current = found.unwrap();
+        while let Some(Some((previous, step))) = parents.remove(&current) {
+            steps.push(step);
+            current = previous;
+        }
+        steps.reverse();
+        Ok(Some(Path { start, steps }))
+    }
+
+    /// The steps that can be taken from `node`.
+    fn steps_from(&self, node: &BoxTerm) -> GResult<G, Vec<Step>> {
+        let mut steps = vec![];
+        if self.direction != Direction::Backward {
+            for t in self.graph.triples_with_s(node) {
+                let t = t?;
+                if self.follows(t.p()) {
+                    steps.push(Step {
+                        predicate: BoxTerm::copy(t.p()),
+                        inverse: false,
+                        node: BoxTerm::copy(t.o()),
+                    });
+                }
+            }
+        }
+        if self.direction != Direction::Forward {
+            for t in self.graph.triples_with_o(node) {
+                let t = t?;
+                if self.follows(t.p()) {
+                    steps.push(Step {
+                        predicate: BoxTerm::copy(t.p()),
+                        inverse: true,
+                        node: BoxTerm::copy(t.s()),
+                    });
+                }
+            }
+        }
+        Ok(steps)
+    }
+
+    fn follows<T: TTerm + ?Sized>(&self, p: &T) -> bool {
+        match &self.predicates {
+            None => true,
+            Some(predicates) => predicates.iter().any(|q| term_eq(p, q)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::test_util::t;
+    use crate::triple::stream::TripleSource;
+
+    const TTL: &str = r#"
+        <tag:a> <tag:p> <tag:b>.
+        <tag:b> <tag:p> <tag:c>.
+        <tag:c> <tag:p> <tag:a>.
+        <tag:c> <tag:q> <tag:d>.
+        <tag:e> <tag:r> <tag:a>.
+    "#;
+
+    fn names<'a, I: IntoIterator<Item = &'a BoxTerm>>(nodes: I) -> Vec<String> {
+        nodes
+            .into_iter()
+            .map(|n| n.value()[4..].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn visits() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let tr = Traversal::new(&g);
+        assert_eq!(names(&tr.bfs(&t("a"))?), ["a", "b", "c", "d"]);
+        assert_eq!(names(&tr.dfs(&t("a"))?), ["a", "b", "c", "d"]);
+        assert_eq!(names(&tr.bfs(&t("d"))?), ["d"]);
+
+        let tr = Traversal::new(&g).predicates(vec![t("p")]);
+        assert_eq!(names(&tr.bfs(&t("c"))?), ["c", "a", "b"]);
+
+        let tr = Traversal::new(&g).direction(Direction::Backward);
+        let mut nodes = tr.bfs(&t("a"))?;
+        nodes.sort_by(|n1, n2| n1.value().cmp(&n2.value()));
+        assert_eq!(names(&nodes), ["a", "b", "c", "e"]);
+        Ok(())
+    }
+
+    #[test]
+    fn shortest_path() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(TTL).collect_triples()?;
+        let tr = Traversal::new(&g);
+        let path = tr.shortest_path(&t("a"), &t("d"))?.unwrap();
+        assert_eq!(names(path.nodes()), ["a", "b", "c", "d"]);
+        assert_eq!(path.steps[2].predicate, t("q"));
+        assert_eq!(path.end(), &t("d"));
+        assert!(tr.shortest_path(&t("a"), &t("e"))?.is_none());
+        assert!(tr.shortest_path(&t("a"), &t("a"))?.unwrap().is_empty());
+
+        let tr = Traversal::new(&g).direction(Direction::Both);
+        let path = tr.shortest_path(&t("e"), &t("c"))?.unwrap();
+        assert_eq!(names(path.nodes()), ["e", "a", "c"]);
+        assert!(path.steps[1].inverse);
+
+        let tr = tr.predicates(vec![t("p")]);
+        assert!(tr.shortest_path(&t("e"), &t("c"))?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+//! Content-addressed IRIs, in the style of [Trusty URIs](https://trustyuri.net/)
+//! (requires the `signing` feature).
+//!
+//! A trusty IRI ends with an *artifact code*, which is a hash of the content it identifies.
+//! Anyone retrieving the content can therefore check that it has not been altered.
+//! The artifact codes minted by this module consist of the module identifier [`MODULE_ID`],
+//! followed by the SHA-256 hash of the [canonical form] of the dataset,
+//! encoded in 43 characters of URL-safe base64.
+//! Note that, as the canonical form is that of RDFC-1.0,
+//! these codes are not guaranteed to match those computed by the reference implementation
+//! of Trusty URIs for the same content.
+//!
+//! A dataset can refer to its own trusty IRI
+//! (e.g. in the provenance of a [nanopublication]):
+//! when minting, it uses a temporary *base* IRI instead,
+//! and every IRI starting with this base is rewritten by [`make_trusty`]
+//! to start with the trusty IRI instead.
+//! These self-references are excluded from the hash,
+//! by replacing the base (or the trusty IRI, when verifying) with a placeholder.
+//!
+//! # Example
+//! ```
+//! use sophia::parser::nq;
+//! use sophia::quad::stream::QuadSource;
+//! use sophia::term::BoxTerm;
+//! use sophia::trusty::{make_trusty, verify_trusty};
+//!
+//! let doc: Vec<([BoxTerm; 3], Option<BoxTerm>)> =
+//!     nq::parse_str("<http://example.org/doc#it> <tag:name> \"Alice\" .\n").collect_quads()?;
+//! let (iri, trusty_doc) = make_trusty(&doc, "http://example.org/doc")?;
+//! assert!(iri.starts_with("http://example.org/docRA"));
+//! assert!(verify_trusty(&trusty_doc, &iri)?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`MODULE_ID`]: constant.MODULE_ID.html
+//! [canonical form]: ../signing/fn.canonicalize.html
+//! [nanopublication]: ../nanopub/index.html
+//! [`make_trusty`]: fn.make_trusty.html
+
+use ring::digest::{digest, SHA256};
+use sophia_api::dataset::Dataset;
+use sophia_api::quad::Quad;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::signing::canonicalize;
+
+/// The module identifier prefixing the artifact codes minted by this module.
+pub const MODULE_ID: &str = "RA";
+
+/// The length of an artifact code, including its [`MODULE_ID`](constant.MODULE_ID.html).
+pub const CODE_LEN: usize = 45;
+
+/// The IRI replacing self-references when computing an artifact code.
+const PLACEHOLDER: &str = "http://purl.org/trustyuri/v1/self";
+
+/// A quad, as produced by this module.
+pub type TrustyQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// This error is raised when minting or verifying a trusty IRI fails.
+#[derive(Debug, Error)]
+pub enum TrustyError {
+    /// The dataset raised an error.
+    #[error("Error in source: {0}")]
+    Source(String),
+    /// The IRI does not end with an artifact code.
+    #[error("Not a trusty IRI: {0}")]
+    NotTrusty(String),
+}
+
+/// Compute the artifact code of `dataset`,
+/// where the IRIs starting with `base` are considered as self-references.
+pub fn artifact_code<D>(dataset: &D, base: &str) -> Result<String, TrustyError>
+where
+    D: Dataset + ?Sized,
+{
+    let quads = rewrite(dataset, base, PLACEHOLDER)?;
+    let canonical = canonicalize(&quads).map_err(|err| TrustyError::Source(err.to_string()))?;
+    let hash = digest(&SHA256, canonical.as_bytes());
+    Ok(format!("{}{}", MODULE_ID, base64url_encode(hash.as_ref())))
+}
+
+/// Mint the trusty IRI of `dataset` by appending its artifact code to `base`,
+/// and return it with a copy of `dataset`,
+/// where every IRI starting with `base` now starts with the trusty IRI.
+///
+/// Note that `base` is matched as a mere prefix,
+/// so it should end with a character that can not be mistaken for the rest of an IRI
+/// (e.g. `http://example.org/np` would also match `http://example.org/np2`).
+pub fn make_trusty<D>(dataset: &D, base: &str) -> Result<(String, Vec<TrustyQuad>), TrustyError>
+where
+    D: Dataset + ?Sized,
+{
+    let iri = format!("{}{}", base, artifact_code(dataset, base)?);
+    let quads = rewrite(dataset, base, &iri)?;
+    Ok((iri, quads))
+}
+
+/// Check that `dataset` matches the artifact code at the end of `iri`.
+///
+/// Return an error if `iri` does not end with an artifact code.
+pub fn verify_trusty<D>(dataset: &D, iri: &str) -> Result<bool, TrustyError>
+where
+    D: Dataset + ?Sized,
+{
+    let code = artifact_code_of(iri).ok_or_else(|| TrustyError::NotTrusty(iri.to_string()))?;
+    Ok(artifact_code(dataset, iri)? == code)
+}
+
+/// Extract the artifact code at the end of `iri`, if any.
+pub fn artifact_code_of(iri: &str) -> Option<&str> {
+    let start = iri.len().checked_sub(CODE_LEN)?;
+    let code = iri.get(start..)?;
+    let valid = code.starts_with(MODULE_ID)
+        && code[MODULE_ID.len()..]
+            .bytes()
+            .all(|b| BASE64URL_ALPHABET.contains(&b));
+    if valid {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+/// Copy `dataset`, replacing the prefix `from` of IRIs by `to`.
+fn rewrite<D>(dataset: &D, from: &str, to: &str) -> Result<Vec<TrustyQuad>, TrustyError>
+where
+    D: Dataset + ?Sized,
+{
+    dataset
+        .quads()
+        .map(|q| {
+            q.map(|q| {
+                (
+                    [
+                        rewrite_term(q.s(), from, to),
+                        rewrite_term(q.p(), from, to),
+                        rewrite_term(q.o(), from, to),
+                    ],
+                    q.g().map(|g| rewrite_term(g, from, to)),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|err| TrustyError::Source(err.to_string()))
+}
+
+fn rewrite_term<T: TTerm + ?Sized>(t: &T, from: &str, to: &str) -> BoxTerm {
+    if t.kind() == TermKind::Iri {
+        if let Some(suffix) = t.value().strip_prefix(from) {
+            return BoxTerm::new_iri_unchecked(format!("{}{}", to, suffix));
+        }
+    }
+    BoxTerm::copy(t)
+}
+
+const BASE64URL_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode `data` in URL-safe base64, without padding.
+fn base64url_encode(data: &[u8]) -> String {
+    let mut txt = String::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..=chunk.len() {
+            let index = (bits >> (18 - 6 * i)) & 0x3f;
+            txt.push(BASE64URL_ALPHABET[index as usize] as char);
+        }
+    }
+    txt
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::nq;
+    use crate::quad::stream::QuadSource;
+
+    fn parse(nquads: &str) -> Vec<TrustyQuad> {
+        nq::parse_str(nquads).collect_quads().unwrap()
+    }
+
+    #[test]
+    fn base64url() {
+        assert_eq!(base64url_encode(b""), "");
+        assert_eq!(base64url_encode(b"f"), "Zg");
+        assert_eq!(base64url_encode(b"fo"), "Zm8");
+        assert_eq!(base64url_encode(b"foo"), "Zm9v");
+        assert_eq!(base64url_encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(base64url_encode(&[0; 32]).len(), CODE_LEN - MODULE_ID.len());
+    }
+
+    #[test]
+    fn mint_and_verify() -> Result<(), Box<dyn std::error::Error>> {
+        let doc = parse(
+            "<tag:base#a> <tag:p> _:b .\n\
+             _:b <tag:p> <tag:base> <tag:base#g> .\n",
+        );
+        let (iri, trusty) = make_trusty(&doc, "tag:base")?;
+        assert_eq!(artifact_code_of(&iri), Some(&iri["tag:base".len()..]));
+        assert_eq!(trusty[0].0[0].value().as_ref(), format!("{}#a", iri));
+        assert!(verify_trusty(&trusty, &iri)?);
+
+        // blank node labels do not matter
+        let relabelled = parse(
+            "<tag:base#a> <tag:p> _:x .\n\
+             _:x <tag:p> <tag:base> <tag:base#g> .\n",
+        );
+        assert_eq!(
+            artifact_code(&relabelled, "tag:base")?,
+            &iri["tag:base".len()..]
+        );
+
+        // any alteration is detected
+        let mut altered = trusty.clone();
+        altered.pop();
+        assert!(!verify_trusty(&altered, &iri)?);
+        assert!(verify_trusty(&trusty, "tag:base").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,709 @@
+//! Comparison and conversion of quantities with units of measure
+//! (requires the `units` feature).
+//!
+//! Units are identified either by their [UCUM] code (e.g. `km` or `m/s2`),
+//! or by their [QUDT] IRI (e.g. `http://qudt.org/vocab/unit/KiloM`).
+//! A [`Quantity`] is a number with a [`Unit`];
+//! quantities with commensurable units (i.e. units of the same [`Dimension`])
+//! can be converted into one another and compared,
+//! so that `1 km` equals `1000 m`.
+//!
+//! Quantities can be found in RDF data in two forms:
+//! * literals of the [`cdt:ucum`] datatype, such as `"1.5 km"^^cdt:ucum`
+//!   (see [`Quantity::from_term`]);
+//! * QUDT quantity values, i.e. nodes with a `qudt:numericValue` and a `qudt:unit`
+//!   (see [`Quantity::from_qudt`]).
+//!
+//! [`register`] adds a handler for `cdt:ucum` to a [`DatatypeRegistry`],
+//! so that such literals are checked by [`validate_literals_with`]
+//! and compared by their value in [expressions](../expression/enum.Expr.html#method.eval_with).
+//!
+//! UCUM codes are supported for the SI base and derived units (with their prefixes),
+//! and for the most common customary units (inch, foot, mile, pound, degree Fahrenheit...);
+//! parentheses and arbitrary numeric factors are not supported.
+//!
+//! # Example
+//! ```
+//! use sophia::datatype::DatatypeRegistry;
+//! use sophia::expression::{Expr, Function::*};
+//! use sophia::term::BoxTerm;
+//! use sophia::units::{cdt, register, Quantity, Unit};
+//!
+//! let q: Quantity = "1.5 km".parse()?;
+//! assert_eq!(q.convert(&Unit::from_ucum("m")?), Some(1500.0));
+//! assert_eq!(q, "1500 m".parse()?);
+//!
+//! let mut registry = DatatypeRegistry::new();
+//! register(&mut registry);
+//! // "900 m"^^cdt:ucum < "1 km"^^cdt:ucum
+//! let e = Expr::call(Lt, vec![
+//!     Expr::constant(&BoxTerm::new_literal_dt("900 m", cdt::ucum)?),
+//!     Expr::constant(&BoxTerm::new_literal_dt("1 km", cdt::ucum)?),
+//! ]);
+//! assert!(e.holds_with(&Default::default(), &registry));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [UCUM]: https://ucum.org/ucum
+//! [QUDT]: https://qudt.org/
+//! [`Quantity`]: struct.Quantity.html
+//! [`Unit`]: struct.Unit.html
+//! [`Dimension`]: struct.Dimension.html
+//! [`cdt:ucum`]: https://w3id.org/lindt/custom_datatypes#ucum
+//! [`Quantity::from_term`]: struct.Quantity.html#method.from_term
+//! [`Quantity::from_qudt`]: struct.Quantity.html#method.from_qudt
+//! [`register`]: fn.register.html
+//! [`DatatypeRegistry`]: ../datatype/struct.DatatypeRegistry.html
+//! [`validate_literals_with`]: ../validation/fn.validate_literals_with.html
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use sophia_api::graph::{GResult, Graph};
+use sophia_api::term::{TTerm, TermKind};
+use sophia_api::triple::Triple;
+use thiserror::Error;
+
+use crate::datatype::{DatatypeHandler, DatatypeRegistry};
+
+/// The [custom datatypes](https://w3id.org/lindt/custom_datatypes) namespace.
+#[allow(missing_docs)]
+pub mod cdt {
+    use sophia_api::namespace;
+    namespace!("https://w3id.org/cdt/", ucum);
+}
+
+/// The QUDT schema.
+#[allow(missing_docs)]
+pub mod qudt {
+    use sophia_api::namespace;
+    namespace!(
+        "http://qudt.org/schema/qudt/",
+        QuantityValue,
+        numericValue,
+        unit,
+        value
+    );
+}
+
+/// The namespace of QUDT units.
+pub static QUDT_UNIT: &str = "http://qudt.org/vocab/unit/";
+
+/// This error is raised when a unit or a quantity can not be parsed.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum UnitError {
+    /// The unit is unknown, or its code is not supported.
+    #[error("Unknown unit {0:?}")]
+    UnknownUnit(String),
+    /// The quantity is not a number followed by a unit.
+    #[error("Invalid quantity {0:?}")]
+    InvalidQuantity(String),
+}
+
+/// The dimension of a unit,
+/// as the exponents of the seven SI base quantities:
+/// length, mass, time, electric current, temperature, amount of substance and luminous intensity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dimension(pub [i8; 7]);
+
+impl Dimension {
+    /// Whether this dimension is that of dimensionless numbers.
+    pub fn is_dimensionless(&self) -> bool {
+        self.0 == [0; 7]
+    }
+
+    fn pow(self, exponent: i8) -> Dimension {
+        let mut d = self.0;
+        d.iter_mut().for_each(|e| *e *= exponent);
+        Dimension(d)
+    }
+
+    fn mul(self, other: Dimension) -> Dimension {
+        let mut d = self.0;
+        d.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a += b);
+        Dimension(d)
+    }
+}
+
+/// A unit of measure,
+/// defined by its dimension and its conversion to the coherent SI unit of that dimension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Unit {
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+impl Unit {
+    /// Parse a UCUM code (case sensitive), such as `km`, `Cel` or `kg.m/s2`.
+    pub fn from_ucum(code: &str) -> Result<Unit, UnitError> {
+        let unknown = || UnitError::UnknownUnit(code.to_string());
+        if code.is_empty() {
+            return Err(unknown());
+        }
+        if let Some(atom) = ATOMS.iter().find(|a| a.code == code) {
+            return Ok(atom.unit());
+        }
+        let mut unit = Unit::ONE;
+        let mut divide = false;
+        let mut rest = code;
+        if let Some(r) = rest.strip_prefix('/') {
+            divide = true;
+            rest = r;
+        }
+        loop {
+            let end = rest.find(['.', '/']).unwrap_or(rest.len());
+            let (component, exponent) = split_exponent(&rest[..end]).ok_or_else(unknown)?;
+            let component = parse_component(component).ok_or_else(unknown)?;
+            if component.offset != 0.0 {
+                // units with an offset (Cel, [degF]) can not be combined
+                return Err(unknown());
+            }
+            let exponent = if divide { -exponent } else { exponent };
+            unit.dimension = unit.dimension.mul(component.dimension.pow(exponent));
+            unit.factor *= component.factor.powi(exponent.into());
+            if end == rest.len() {
+                return Ok(unit);
+            }
+            divide = &rest[end..=end] == "/";
+            rest = &rest[end + 1..];
+        }
+    }
+
+    /// Identify a unit from its QUDT IRI (e.g. `http://qudt.org/vocab/unit/KiloM`).
+    pub fn from_qudt<T>(iri: &T) -> Result<Unit, UnitError>
+    where
+        T: TTerm + ?Sized,
+    {
+        let iri = iri.value();
+        iri.strip_prefix(QUDT_UNIT)
+            .and_then(|name| QUDT_UNITS.iter().find(|(n, _)| *n == name))
+            .ok_or_else(|| UnitError::UnknownUnit(iri.to_string()))
+            .and_then(|(_, code)| Unit::from_ucum(code))
+    }
+
+    /// The dimension of this unit.
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    /// Whether quantities in this unit can be converted into `other`.
+    pub fn is_commensurable(&self, other: &Unit) -> bool {
+        self.dimension == other.dimension
+    }
+
+    /// Convert `x` (in this unit) into the coherent SI unit of the same dimension.
+    pub fn to_si(&self, x: f64) -> f64 {
+        x * self.factor + self.offset
+    }
+
+    /// Convert `x` (in the coherent SI unit of the same dimension) into this unit.
+    pub fn from_si(&self, x: f64) -> f64 {
+        (x - self.offset) / self.factor
+    }
+
+    const ONE: Unit = Unit {
+        dimension: Dimension([0; 7]),
+        factor: 1.0,
+        offset: 0.0,
+    };
+}
+
+/// A number with a unit.
+///
+/// Quantities are equal (resp. ordered) if their values are,
+/// once converted in the same unit;
+/// values that differ by less than one part in 10<sup>12</sup>
+/// are considered equal, to absorb rounding errors of the conversion.
+/// Quantities with incommensurable units are not comparable.
+#[derive(Clone, Copy, Debug)]
+pub struct Quantity {
+    /// The numeric value of this quantity.
+    pub value: f64,
+    /// The unit of this quantity.
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// Build a quantity.
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+
+    /// The quantity represented by `term`,
+    /// if it is a valid literal of datatype [`cdt:ucum`](cdt/constant.ucum.html).
+    pub fn from_term<T>(term: &T) -> Option<Quantity>
+    where
+        T: TTerm + ?Sized,
+    {
+        if term.kind() != TermKind::Literal || cdt::ucum != term.datatype()? {
+            return None;
+        }
+        term.value().parse().ok()
+    }
+
+    /// The quantity described by `node` in `graph`, with the QUDT vocabulary,
+    /// i.e. through its `qudt:numericValue` (or `qudt:value`) and its `qudt:unit`.
+    ///
+    /// Returns `None` if either is missing, or if the unit is not supported.
+    pub fn from_qudt<G, T>(graph: &G, node: &T) -> GResult<G, Option<Quantity>>
+    where
+        G: Graph,
+        T: TTerm + ?Sized,
+    {
+        let mut value = None;
+        for p in [&qudt::numericValue, &qudt::value].iter() {
+            for t in graph.triples_with_sp(node, *p) {
+                let t = t?;
+                if t.o().kind() == TermKind::Literal {
+                    value = value.or_else(|| parse_number(&t.o().value()));
+                }
+            }
+        }
+        let mut unit = None;
+        for t in graph.triples_with_sp(node, &qudt::unit) {
+            let t = t?;
+            unit = unit.or_else(|| Unit::from_qudt(t.o()).ok());
+        }
+        Ok(value
+            .zip(unit)
+            .map(|(value, unit)| Quantity { value, unit }))
+    }
+
+    /// The value of this quantity in `unit`,
+    /// or `None` if `unit` is not commensurable with the unit of this quantity.
+    pub fn convert(&self, unit: &Unit) -> Option<f64> {
+        if self.unit.is_commensurable(unit) {
+            Some(unit.from_si(self.unit.to_si(self.value)))
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = UnitError;
+
+    /// Parse the lexical form of a `cdt:ucum` literal,
+    /// i.e. a number and a UCUM code, separated by a space.
+    fn from_str(txt: &str) -> Result<Quantity, UnitError> {
+        let invalid = || UnitError::InvalidQuantity(txt.to_string());
+        let (number, code) = txt.trim().split_once(' ').ok_or_else(invalid)?;
+        let value = parse_number(number).ok_or_else(invalid)?;
+        let unit = Unit::from_ucum(code.trim_start())?;
+        Ok(Quantity { value, unit })
+    }
+}
+
+impl PartialEq for Quantity {
+    fn eq(&self, other: &Quantity) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Quantity {
+    fn partial_cmp(&self, other: &Quantity) -> Option<Ordering> {
+        if !self.unit.is_commensurable(&other.unit) {
+            return None;
+        }
+        let (a, b) = (self.unit.to_si(self.value), other.unit.to_si(other.value));
+        if (a - b).abs() <= 1e-12 * a.abs().max(b.abs()) {
+            Some(Ordering::Equal)
+        } else {
+            a.partial_cmp(&b)
+        }
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "1");
+        }
+        let mut first = true;
+        for (symbol, exponent) in ["L", "M", "T", "I", "Θ", "N", "J"]
+            .iter()
+            .zip(self.0.iter())
+        {
+            if *exponent != 0 {
+                if !first {
+                    write!(f, "·")?;
+                }
+                first = false;
+                write!(f, "{}", symbol)?;
+                if *exponent != 1 {
+                    write!(f, "{}", exponent)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The [`DatatypeHandler`](../datatype/trait.DatatypeHandler.html)
+/// of [`cdt:ucum`](cdt/constant.ucum.html).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UcumHandler;
+
+impl DatatypeHandler for UcumHandler {
+    type Value = Quantity;
+
+    fn parse(&self, lex: &str) -> Result<Quantity, String> {
+        lex.parse().map_err(|e: UnitError| e.to_string())
+    }
+
+    fn compare(&self, a: &Quantity, b: &Quantity) -> Option<Ordering> {
+        a.partial_cmp(b)
+    }
+}
+
+/// Register [`UcumHandler`](struct.UcumHandler.html) for `cdt:ucum` in `registry`.
+pub fn register(registry: &mut DatatypeRegistry) -> &mut DatatypeRegistry {
+    registry.register(&cdt::ucum, UcumHandler)
+}
+
+// ---------------------------------------------------------------------------------
+//                                   unit tables
+// ---------------------------------------------------------------------------------
+
+const L: usize = 0;
+const M: usize = 1;
+const T: usize = 2;
+const I: usize = 3;
+const TH: usize = 4;
+const N: usize = 5;
+const J: usize = 6;
+
+const fn dim(exponents: &[(usize, i8)]) -> Dimension {
+    let mut d = [0; 7];
+    let mut i = 0;
+    while i < exponents.len() {
+        d[exponents[i].0] = exponents[i].1;
+        i += 1;
+    }
+    Dimension(d)
+}
+
+/// A UCUM unit atom.
+struct Atom {
+    code: &'static str,
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+    metric: bool,
+}
+
+impl Atom {
+    fn unit(&self) -> Unit {
+        Unit {
+            dimension: self.dimension,
+            factor: self.factor,
+            offset: self.offset,
+        }
+    }
+}
+
+macro_rules! atom {
+    ($code:expr, [$($d:expr),*], $factor:expr, metric) => {
+        Atom { code: $code, dimension: dim(&[$($d),*]), factor: $factor, offset: 0.0, metric: true }
+    };
+    ($code:expr, [$($d:expr),*], $factor:expr) => {
+        Atom { code: $code, dimension: dim(&[$($d),*]), factor: $factor, offset: 0.0, metric: false }
+    };
+}
+
+const ATOMS: &[Atom] = &[
+    // base units
+    atom!("m", [(L, 1)], 1.0, metric),
+    atom!("g", [(M, 1)], 1e-3, metric),
+    atom!("s", [(T, 1)], 1.0, metric),
+    atom!("A", [(I, 1)], 1.0, metric),
+    atom!("K", [(TH, 1)], 1.0, metric),
+    atom!("mol", [(N, 1)], 1.0, metric),
+    atom!("cd", [(J, 1)], 1.0, metric),
+    // derived units
+    atom!("L", [(L, 3)], 1e-3, metric),
+    atom!("l", [(L, 3)], 1e-3, metric),
+    atom!("t", [(M, 1)], 1e3, metric),
+    atom!("N", [(M, 1), (L, 1), (T, -2)], 1.0, metric),
+    atom!("J", [(M, 1), (L, 2), (T, -2)], 1.0, metric),
+    atom!("W", [(M, 1), (L, 2), (T, -3)], 1.0, metric),
+    atom!("Pa", [(M, 1), (L, -1), (T, -2)], 1.0, metric),
+    atom!("bar", [(M, 1), (L, -1), (T, -2)], 1e5, metric),
+    atom!("Hz", [(T, -1)], 1.0, metric),
+    atom!("V", [(M, 1), (L, 2), (T, -3), (I, -1)], 1.0, metric),
+    atom!("%", [], 1e-2),
+    atom!("1", [], 1.0),
+    // time
+    atom!("min", [(T, 1)], 60.0),
+    atom!("h", [(T, 1)], 3600.0),
+    atom!("d", [(T, 1)], 86400.0),
+    atom!("wk", [(T, 1)], 604800.0),
+    atom!("a", [(T, 1)], 31557600.0),
+    // customary units
+    atom!("[in_i]", [(L, 1)], 0.0254),
+    atom!("[ft_i]", [(L, 1)], 0.3048),
+    atom!("[yd_i]", [(L, 1)], 0.9144),
+    atom!("[mi_i]", [(L, 1)], 1609.344),
+    atom!("[nmi_i]", [(L, 1)], 1852.0),
+    atom!("[lb_av]", [(M, 1)], 0.45359237),
+    atom!("[oz_av]", [(M, 1)], 0.028349523125),
+    // temperatures with an offset
+    Atom {
+        code: "Cel",
+        dimension: dim(&[(TH, 1)]),
+        factor: 1.0,
+        offset: 273.15,
+        metric: false,
+    },
+    Atom {
+        code: "[degF]",
+        dimension: dim(&[(TH, 1)]),
+        factor: 5.0 / 9.0,
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+        metric: false,
+    },
+];
+
+const PREFIXES: &[(&str, f64)] = &[
+    ("da", 1e1),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+];
+
+/// QUDT unit local names, and their UCUM code.
+const QUDT_UNITS: &[(&str, &str)] = &[
+    ("M", "m"),
+    ("KiloM", "km"),
+    ("CentiM", "cm"),
+    ("MilliM", "mm"),
+    ("MicroM", "um"),
+    ("NanoM", "nm"),
+    ("IN", "[in_i]"),
+    ("FT", "[ft_i]"),
+    ("YD", "[yd_i]"),
+    ("MI", "[mi_i]"),
+    ("NauticalMile", "[nmi_i]"),
+    ("GM", "g"),
+    ("KiloGM", "kg"),
+    ("MilliGM", "mg"),
+    ("TONNE", "t"),
+    ("LB", "[lb_av]"),
+    ("OZ", "[oz_av]"),
+    ("SEC", "s"),
+    ("MilliSEC", "ms"),
+    ("MIN", "min"),
+    ("HR", "h"),
+    ("DAY", "d"),
+    ("WK", "wk"),
+    ("YR", "a"),
+    ("K", "K"),
+    ("DEG_C", "Cel"),
+    ("DEG_F", "[degF]"),
+    ("M2", "m2"),
+    ("KiloM2", "km2"),
+    ("CentiM2", "cm2"),
+    ("M3", "m3"),
+    ("L", "L"),
+    ("MilliL", "mL"),
+    ("M-PER-SEC", "m/s"),
+    ("KiloM-PER-HR", "km/h"),
+    ("MI-PER-HR", "[mi_i]/h"),
+    ("M-PER-SEC2", "m/s2"),
+    ("N", "N"),
+    ("KiloN", "kN"),
+    ("J", "J"),
+    ("KiloJ", "kJ"),
+    ("W", "W"),
+    ("KiloW", "kW"),
+    ("MegaW", "MW"),
+    ("PA", "Pa"),
+    ("KiloPA", "kPa"),
+    ("BAR", "bar"),
+    ("A", "A"),
+    ("MilliA", "mA"),
+    ("V", "V"),
+    ("MOL", "mol"),
+    ("CD", "cd"),
+    ("HZ", "Hz"),
+    ("PERCENT", "%"),
+    ("UNITLESS", "1"),
+];
+
+/// Split a component of a UCUM code into its symbol and its exponent (1 by default).
+fn split_exponent(component: &str) -> Option<(&str, i8)> {
+    let digits = component
+        .bytes()
+        .rev()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    let mut split = component.len() - digits;
+    if digits == 0 || split == 0 {
+        return Some((component, 1));
+    }
+    if component[..split].ends_with(['+', '-']) {
+        split -= 1;
+    }
+    let exponent = component[split..].parse().ok()?;
+    Some((&component[..split], exponent))
+}
+
+/// Parse a (possibly prefixed) UCUM atom.
+fn parse_component(symbol: &str) -> Option<Unit> {
+    // annotations are ignored
+    let symbol = match symbol.find('{') {
+        Some(0) if symbol.ends_with('}') => "1",
+        Some(i) if symbol.ends_with('}') => &symbol[..i],
+        _ => symbol,
+    };
+    if let Some(atom) = ATOMS.iter().find(|a| a.code == symbol) {
+        return Some(atom.unit());
+    }
+    PREFIXES.iter().find_map(|(prefix, factor)| {
+        let atom = symbol.strip_prefix(prefix)?;
+        let atom = ATOMS.iter().find(|a| a.metric && a.code == atom)?;
+        let mut unit = atom.unit();
+        unit.factor *= factor;
+        Some(unit)
+    })
+}
+
+fn parse_number(txt: &str) -> Option<f64> {
+    txt.trim().parse().ok().filter(|x: &f64| x.is_finite())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+    use crate::validation::validate_literals_with;
+    use sophia_term::BoxTerm;
+
+    fn q(txt: &str) -> Quantity {
+        txt.parse().unwrap()
+    }
+
+    fn u(code: &str) -> Unit {
+        Unit::from_ucum(code).unwrap()
+    }
+
+    #[test]
+    fn ucum_codes() {
+        assert_eq!(u("km").dimension(), u("m").dimension());
+        assert_eq!(u("m/s2").dimension(), dim(&[(L, 1), (T, -2)]));
+        assert_eq!(u("kg.m/s2").dimension(), u("N").dimension());
+        assert_eq!(u("/s").dimension(), u("Hz").dimension());
+        assert_eq!(u("mol").dimension(), dim(&[(N, 1)]));
+        assert_eq!(u("cd").dimension(), dim(&[(J, 1)]));
+        assert_eq!(u("m-2").dimension(), dim(&[(L, -2)]));
+        assert_eq!(u("{count}").dimension(), Dimension::default());
+        assert_eq!(u("kg.m/s2").dimension().to_string(), "L·M·T-2");
+        assert!(u("min").is_commensurable(&u("ms")));
+        assert!(!u("m").is_commensurable(&u("s")));
+        for bad in ["", "xyz", "kmin", "Cel/s", "m//s", "k"].iter() {
+            assert!(Unit::from_ucum(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9;
+        assert_eq!(q("1.5 km").convert(&u("m")), Some(1500.0));
+        assert!(close(q("90 km/h").convert(&u("m/s")), 25.0));
+        assert!(close(q("1 [mi_i]").convert(&u("km")), 1.609344));
+        assert!(close(q("100 Cel").convert(&u("[degF]")), 212.0));
+        assert!(close(q("300 K").convert(&u("Cel")), 26.85));
+        assert!(close(q("2 L").convert(&u("cm3")), 2000.0));
+        assert!(close(q("50 %").convert(&u("1")), 0.5));
+        assert_eq!(q("1 m").convert(&u("s")), None);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(q("1 km"), q("1000 m"));
+        assert_eq!(q("0.1 km"), q("100 m"));
+        assert!(q("900 m") < q("1 km"));
+        assert!(q("1 h") > q("59 min"));
+        assert_eq!(q("1 m").partial_cmp(&q("1 s")), None);
+        assert_ne!(q("1 m"), q("1 s"));
+        for bad in ["1km", "km 1", "NaN m", "1 parsec"].iter() {
+            assert!(bad.parse::<Quantity>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn qudt() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix qudt: <http://qudt.org/schema/qudt/>.
+            @prefix unit: <http://qudt.org/vocab/unit/>.
+            <tag:a> qudt:numericValue 1.2; qudt:unit unit:KiloM.
+            <tag:b> qudt:value "1200"; qudt:unit unit:M.
+            <tag:c> qudt:numericValue 3; qudt:unit unit:UNKNOWN.
+            <tag:d> qudt:numericValue 3.
+            "#,
+        )
+        .collect_triples()?;
+        let node = |name: &str| BoxTerm::new_iri(format!("tag:{}", name)).unwrap();
+        let a = Quantity::from_qudt(&g, &node("a"))?.unwrap();
+        let b = Quantity::from_qudt(&g, &node("b"))?.unwrap();
+        assert_eq!(a, b);
+        assert_eq!(Quantity::from_qudt(&g, &node("c"))?, None);
+        assert_eq!(Quantity::from_qudt(&g, &node("d"))?, None);
+        assert!(Unit::from_qudt(&BoxTerm::new_iri(
+            "http://qudt.org/vocab/unit/KiloM-PER-HR"
+        )?)
+        .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn registry() -> Result<(), Box<dyn std::error::Error>> {
+        let mut registry = DatatypeRegistry::new();
+        register(&mut registry);
+        let lit = |lex: &str| BoxTerm::new_literal_dt(lex, cdt::ucum).unwrap();
+        assert_eq!(Quantity::from_term(&lit("3 kg")), Some(q("3000 g")));
+        assert_eq!(registry.value_eq(&lit("1 km"), &lit("1000 m")), Some(true));
+        assert_eq!(
+            registry.value_cmp(&lit("1 km"), &lit("1001 m")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(registry.value_cmp(&lit("1 km"), &lit("1 s")), None);
+
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix cdt: <https://w3id.org/cdt/>.
+            <tag:a> <tag:height> "1.8 m"^^cdt:ucum, "tall"^^cdt:ucum, "6 parsec"^^cdt:ucum.
+            "#,
+        )
+        .collect_triples()?;
+        let mut violations = validate_literals_with(&g, &registry)?;
+        violations.sort_by_key(|v| v.literal.value().to_string());
+        assert_eq!(violations.len(), 2);
+        assert!(violations[0].reason.contains("Unknown unit"));
+        assert!(violations[1].reason.contains("Invalid quantity"));
+        Ok(())
+    }
+}
@@ -0,0 +1,742 @@
+//! Checking that the literals of a graph are well-formed.
+//!
+//! RDF requires implementations to accept ill-typed literals,
+//! i.e. literals whose lexical form is not in the lexical space of their datatype
+//! (e.g. `"twelve"^^xsd:integer`).
+//! Such literals are however usually mistakes,
+//! which [`validate_literals`] detects before data is published.
+//!
+//! The following datatypes are checked:
+//! * `xsd:integer` and its derived types (including their range),
+//!   `xsd:decimal`, `xsd:double` and `xsd:float`
+//!   (see [`canonical`](../term/literal/canonical/index.html));
+//! * `xsd:dateTime`, `xsd:date` and `xsd:duration`
+//!   (see [`datetime`](../term/literal/datetime/index.html));
+//! * `xsd:boolean`, `xsd:hexBinary` and `xsd:base64Binary`.
+//!
+//! Literals with any other datatype are considered valid,
+//! unless a handler is registered for their datatype in a [`DatatypeRegistry`]
+//! (see [`validate_literals_with`] and [`QuadValidator::set_registry`]).
+//!
+//! When ingesting data from untrusted sources,
+//! a [`QuadValidator`] checks every term flowing through a [`QuadSource`]
+//! (IRIs, language tags, control characters and, optionally, datatypes),
+//! and either stops with an error, drops or repairs the offending quads:
+//!
+//! ```
+//! use sophia::parser::nq;
+//! use sophia::quad::{stream::QuadSource, Quad};
+//! use sophia::validation::{OnInvalid, QuadValidator};
+//! use sophia_api::term::TTerm;
+//!
+//! let data = r#"
+//!     <tag:a> <tag:name> "Alice" <tag:g>.
+//!     <tag:b> <tag:name> "B\u0007ob" <tag:g>.
+//! "#;
+//! let mut validator = QuadValidator::default();
+//! validator.set_on_invalid(OnInvalid::Repair);
+//! let mut names = vec![];
+//! validator
+//!     .validate_quads(nq::parse_str(data))
+//!     .for_each_quad(|q| names.push(q.o().value().to_string()))?;
+//! assert_eq!(names, vec!["Alice", "Bob"]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`validate_literals`]: fn.validate_literals.html
+//! [`DatatypeRegistry`]: ../datatype/struct.DatatypeRegistry.html
+//! [`validate_literals_with`]: fn.validate_literals_with.html
+//! [`QuadValidator::set_registry`]: struct.QuadValidator.html#method.set_registry
+//! [`QuadValidator`]: struct.QuadValidator.html
+//! [`QuadSource`]: ../quad/stream/trait.QuadSource.html
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use sophia_api::ns::xsd;
+use sophia_api::quad::stream::{QuadSource, StreamResult};
+use sophia_api::quad::streaming_mode::{ByValue, StreamedQuad};
+use sophia_api::quad::Quad;
+use sophia_api::term::{term_to_string, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::StreamError::{SinkError, SourceError};
+use sophia_term::iri::is_absolute_iri_ref;
+use sophia_term::literal::canonical::canonical_numeric;
+use sophia_term::literal::datetime::{Date, DateTime, Duration};
+use sophia_term::BoxTerm;
+use thiserror::Error;
+
+use crate::datatype::DatatypeRegistry;
+use crate::graph::{GResult, Graph};
+use crate::parser::config::is_valid_language_tag;
+use crate::syntax::dynamic::BoxQuad;
+use crate::triple::Triple;
+
+/// A literal whose lexical form is not valid for its datatype.
+#[derive(Clone, Debug)]
+pub struct LiteralViolation {
+    /// The triple containing the literal.
+    pub triple: [BoxTerm; 3],
+    /// The faulty literal.
+    pub literal: BoxTerm,
+    /// Why the literal is not valid.
+    pub reason: String,
+}
+
+impl fmt::Display for LiteralViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} {} {}: {}",
+            term_to_string(&self.literal),
+            term_to_string(&self.triple[0]),
+            term_to_string(&self.triple[1]),
+            term_to_string(&self.triple[2]),
+            self.reason
+        )
+    }
+}
+
+/// Check that the lexical form of `term` is valid for its datatype,
+/// returning the reason why it is not otherwise.
+///
+/// Terms that are not literals, and literals with an unsupported datatype, are always valid.
+pub fn check_literal<T>(term: &T) -> Result<(), String>
+where
+    T: TTerm + ?Sized,
+{
+    if term.kind() != TermKind::Literal {
+        return Ok(());
+    }
+    let dt = match term.datatype() {
+        Some(dt) => dt,
+        None => return Ok(()),
+    };
+    let lex = term.value();
+    let valid = if let Some(valid) = numeric(&lex, &dt) {
+        valid
+    } else if xsd::dateTime == dt {
+        lex.parse::<DateTime>().is_ok()
+    } else if xsd::date == dt {
+        lex.parse::<Date>().is_ok()
+    } else if xsd::duration == dt {
+        lex.parse::<Duration>().is_ok()
+    } else if xsd::boolean == dt {
+        matches!(&lex[..], "true" | "false" | "1" | "0")
+    } else if xsd::hexBinary == dt {
+        lex.len().is_multiple_of(2) && lex.bytes().all(|b| b.is_ascii_hexdigit())
+    } else if xsd::base64Binary == dt {
+        is_base64(&lex)
+    } else {
+        true
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is not a valid lexical form for {}",
+            &lex[..],
+            term_to_string(&dt)
+        ))
+    }
+}
+
+/// Check all the literals of `graph` (see [`check_literal`](fn.check_literal.html)),
+/// and return the violations found.
+pub fn validate_literals<G: Graph>(graph: &G) -> GResult<G, Vec<LiteralViolation>> {
+    validate_literals_with(graph, &DatatypeRegistry::default())
+}
+
+/// Check all the literals of `graph` with the handlers of `registry`
+/// (see [`DatatypeRegistry::check_literal`]),
+/// and return the violations found.
+///
+/// [`DatatypeRegistry::check_literal`]: ../datatype/struct.DatatypeRegistry.html#method.check_literal
+pub fn validate_literals_with<G: Graph>(
+    graph: &G,
+    registry: &DatatypeRegistry,
+) -> GResult<G, Vec<LiteralViolation>> {
+    let mut violations = vec![];
+    for t in graph.triples() {
+        let t = t?;
+        for term in [t.s(), t.p(), t.o()].iter() {
+            if let Err(reason) = registry.check_literal(*term) {
+                violations.push(LiteralViolation {
+                    triple: [
+                        BoxTerm::copy(t.s()),
+                        BoxTerm::copy(t.p()),
+                        BoxTerm::copy(t.o()),
+                    ],
+                    literal: BoxTerm::copy(*term),
+                    reason,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+/// What a [`QuadValidator`](struct.QuadValidator.html) does with invalid quads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnInvalid {
+    /// Stop with a [`ValidationError::Invalid`](enum.ValidationError.html#variant.Invalid).
+    Error,
+    /// Silently drop invalid quads.
+    Drop,
+    /// Repair invalid quads when possible, and drop them otherwise.
+    Repair,
+}
+
+/// A quad rejected by a [`QuadValidator`](struct.QuadValidator.html).
+#[derive(Clone, Debug)]
+pub struct InvalidQuad {
+    /// The rejected quad.
+    pub quad: BoxQuad,
+    /// The faulty term.
+    pub term: BoxTerm,
+    /// Why the term is not valid.
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidQuad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (spo, g) = &self.quad;
+        write!(
+            f,
+            "{} in {} {} {}",
+            term_to_string(&self.term),
+            term_to_string(&spo[0]),
+            term_to_string(&spo[1]),
+            term_to_string(&spo[2]),
+        )?;
+        if let Some(g) = g {
+            write!(f, " {}", term_to_string(g))?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+impl Error for InvalidQuad {}
+
+/// The error type of [`ValidatingSource`](struct.ValidatingSource.html).
+#[derive(Debug, Error)]
+pub enum ValidationError<E: Error + 'static> {
+    /// An error raised by the underlying source.
+    #[error(transparent)]
+    Source(E),
+    /// An invalid quad, with [`OnInvalid::Error`](enum.OnInvalid.html#variant.Error).
+    #[error("Invalid quad: {0}")]
+    Invalid(Box<InvalidQuad>),
+}
+
+/// Checks the terms of quads, typically at ingestion time.
+///
+/// The following checks are performed:
+/// * IRIs (including datatype IRIs) must be valid absolute IRIs;
+/// * language tags must be well-formed according to [BCP47];
+/// * the lexical forms of literals must not contain control characters
+///   (other than tabulations and line breaks), Unicode non-characters,
+///   or replacement characters (U+FFFD), which betray encoding problems upstream;
+/// * optionally, the lexical forms of literals must be valid for their datatype
+///   (see [`check_literal`](fn.check_literal.html)).
+///
+/// Each check can be disabled independently.
+/// Repairs are conservative:
+/// invalid characters are percent-encoded in IRIs and removed from lexical forms,
+/// and underscores are replaced by hyphens in language tags.
+/// Ill-typed literals can not be repaired.
+///
+/// [BCP47]: https://tools.ietf.org/html/bcp47
+#[derive(Clone, Debug)]
+pub struct QuadValidator {
+    iris: bool,
+    language_tags: bool,
+    text: bool,
+    datatypes: bool,
+    registry: Option<Arc<DatatypeRegistry>>,
+    on_invalid: OnInvalid,
+}
+
+impl Default for QuadValidator {
+    fn default() -> Self {
+        QuadValidator {
+            iris: true,
+            language_tags: true,
+            text: true,
+            datatypes: false,
+            registry: None,
+            on_invalid: OnInvalid::Error,
+        }
+    }
+}
+
+impl QuadValidator {
+    /// Whether IRIs are checked (`true` by default).
+    pub fn iris(&self) -> bool {
+        self.iris
+    }
+
+    /// Set whether IRIs are checked.
+    pub fn set_iris(&mut self, check: bool) -> &mut Self {
+        self.iris = check;
+        self
+    }
+
+    /// Whether language tags are checked (`true` by default).
+    pub fn language_tags(&self) -> bool {
+        self.language_tags
+    }
+
+    /// Set whether language tags are checked.
+    pub fn set_language_tags(&mut self, check: bool) -> &mut Self {
+        self.language_tags = check;
+        self
+    }
+
+    /// Whether the characters of lexical forms are checked (`true` by default).
+    pub fn text(&self) -> bool {
+        self.text
+    }
+
+    /// Set whether the characters of lexical forms are checked.
+    pub fn set_text(&mut self, check: bool) -> &mut Self {
+        self.text = check;
+        self
+    }
+
+    /// Whether lexical forms are checked against their datatype (`false` by default).
+    pub fn datatypes(&self) -> bool {
+        self.datatypes
+    }
+
+    /// Set whether lexical forms are checked against their datatype.
+    pub fn set_datatypes(&mut self, check: bool) -> &mut Self {
+        self.datatypes = check;
+        self
+    }
+
+    /// The registry used to check custom datatypes, if any (`None` by default).
+    pub fn registry(&self) -> Option<&DatatypeRegistry> {
+        self.registry.as_deref()
+    }
+
+    /// Set the registry used to check custom datatypes.
+    ///
+    /// This only has an effect if [datatypes](#method.datatypes) are checked.
+    pub fn set_registry(&mut self, registry: Option<Arc<DatatypeRegistry>>) -> &mut Self {
+        self.registry = registry;
+        self
+    }
+
+    /// What is done with invalid quads ([`OnInvalid::Error`] by default).
+    ///
+    /// [`OnInvalid::Error`]: enum.OnInvalid.html#variant.Error
+    pub fn on_invalid(&self) -> OnInvalid {
+        self.on_invalid
+    }
+
+    /// Set what is done with invalid quads.
+    pub fn set_on_invalid(&mut self, on_invalid: OnInvalid) -> &mut Self {
+        self.on_invalid = on_invalid;
+        self
+    }
+
+    /// Check `term`, returning the reason why it is not valid otherwise.
+    pub fn check_term<T: TTerm + ?Sized>(&self, term: &T) -> Result<(), String> {
+        match term.kind() {
+            TermKind::Iri => self.check_iri(&term.value()),
+            TermKind::Literal => {
+                if self.text {
+                    check_text(&term.value())?;
+                }
+                if self.language_tags {
+                    if let Some(tag) = term.language() {
+                        if !is_valid_language_tag(tag) {
+                            return Err(format!("invalid language tag {:?}", tag));
+                        }
+                    }
+                }
+                if let Some(dt) = term.datatype() {
+                    self.check_iri(&dt.value())?;
+                }
+                if self.datatypes {
+                    match &self.registry {
+                        Some(registry) => registry.check_literal(term)?,
+                        None => check_literal(term)?,
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return a repaired copy of `term`, or `None` if it can not be repaired.
+    ///
+    /// Valid terms are copied unchanged.
+    pub fn repair_term<T: TTerm + ?Sized>(&self, term: &T) -> Option<BoxTerm> {
+        let repaired = match term.kind() {
+            TermKind::Iri => BoxTerm::new_iri_unchecked(self.repair_iri(&term.value())),
+            TermKind::Literal => {
+                let lex = match self.text {
+                    true => term.value().chars().filter(|c| !is_bad_char(*c)).collect(),
+                    false => term.value().to_string(),
+                };
+                match (term.language(), term.datatype()) {
+                    (Some(tag), _) => {
+                        let tag = match self.language_tags {
+                            true => tag.trim().replace('_', "-"),
+                            false => tag.to_string(),
+                        };
+                        if !is_valid_language_tag(&tag) {
+                            return None;
+                        }
+                        match term.base_direction() {
+                            Some(dir) => BoxTerm::new_literal_dir_lang_unchecked(lex, tag, dir),
+                            None => BoxTerm::new_literal_lang_unchecked(lex, tag),
+                        }
+                    }
+                    (None, Some(dt)) => {
+                        let dt = BoxTerm::new_iri_unchecked(self.repair_iri(&dt.value()));
+                        BoxTerm::new_literal_dt_unchecked(lex, dt)
+                    }
+                    (None, None) => return None,
+                }
+            }
+            _ => BoxTerm::copy(term),
+        };
+        self.check_term(&repaired).ok().map(|_| repaired)
+    }
+
+    /// Check `quad`, and handle it according to [`on_invalid`](#method.on_invalid):
+    /// return the quad (possibly repaired), `None` if it is dropped,
+    /// or an error describing the first invalid term.
+    pub fn validate_quad<Q: Quad>(&self, quad: &Q) -> Result<Option<BoxQuad>, Box<InvalidQuad>> {
+        let mut terms = [Some(quad.s()), Some(quad.p()), Some(quad.o()), quad.g()];
+        let mut repaired: [Option<BoxTerm>; 4] = Default::default();
+        for (i, term) in terms.iter_mut().enumerate() {
+            let term = match term {
+                Some(term) => term,
+                None => continue,
+            };
+            if let Err(reason) = self.check_term(*term) {
+                match self.on_invalid {
+                    OnInvalid::Error => {
+                        return Err(Box::new(InvalidQuad {
+                            quad: copy_quad(quad),
+                            term: BoxTerm::copy(*term),
+                            reason,
+                        }))
+                    }
+                    OnInvalid::Drop => return Ok(None),
+                    OnInvalid::Repair => match self.repair_term(*term) {
+                        Some(t) => repaired[i] = Some(t),
+                        None => return Ok(None),
+                    },
+                }
+            }
+        }
+        let [s, p, o, g] = repaired;
+        Ok(Some((
+            [
+                s.unwrap_or_else(|| BoxTerm::copy(quad.s())),
+                p.unwrap_or_else(|| BoxTerm::copy(quad.p())),
+                o.unwrap_or_else(|| BoxTerm::copy(quad.o())),
+            ],
+            g.or_else(|| quad.g().map(BoxTerm::copy)),
+        )))
+    }
+
+    /// Check all the quads of `source` (see [`validate_quad`](#method.validate_quad)).
+    pub fn validate_quads<S: QuadSource>(&self, source: S) -> ValidatingSource<S> {
+        ValidatingSource {
+            source,
+            validator: self.clone(),
+        }
+    }
+
+    fn check_iri(&self, iri: &str) -> Result<(), String> {
+        if self.iris && !is_absolute_iri_ref(iri) {
+            Err(format!("invalid IRI <{}>", iri))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn repair_iri(&self, iri: &str) -> String {
+        if !self.iris {
+            return iri.to_string();
+        }
+        let mut repaired = String::with_capacity(iri.len());
+        for c in iri.trim().chars() {
+            if c.is_control() || c.is_whitespace() || "<>\"{}|\\^`".contains(c) {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    repaired.push_str(&format!("%{:02X}", b));
+                }
+            } else {
+                repaired.push(c);
+            }
+        }
+        repaired
+    }
+}
+
+/// The result of [`QuadValidator::validate_quads`](struct.QuadValidator.html#method.validate_quads).
+pub struct ValidatingSource<S> {
+    source: S,
+    validator: QuadValidator,
+}
+
+impl<S: QuadSource> QuadSource for ValidatingSource<S> {
+    type Error = ValidationError<S::Error>;
+    type Quad = ByValue<BoxQuad>;
+
+    fn try_for_some_quad<F, E>(&mut self, f: &mut F) -> StreamResult<bool, Self::Error, E>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), E>,
+        E: Error,
+    {
+        let validator = &self.validator;
+        self.source
+            .try_for_some_quad(&mut |q| match validator.validate_quad(&q) {
+                Ok(Some(q)) => f(StreamedQuad::by_value(q)).map_err(Stop::Sink),
+                Ok(None) => Ok(()),
+                Err(invalid) => Err(Stop::Invalid(invalid)),
+            })
+            .map_err(|e| match e {
+                SourceError(e) => SourceError(ValidationError::Source(e)),
+                SinkError(Stop::Sink(e)) => SinkError(e),
+                SinkError(Stop::Invalid(invalid)) => SourceError(ValidationError::Invalid(invalid)),
+            })
+    }
+
+    fn size_hint_quads(&self) -> (usize, Option<usize>) {
+        (0, self.source.size_hint_quads().1)
+    }
+}
+
+/// Used internally by `ValidatingSource` to interrupt the underlying source.
+#[derive(Debug, Error)]
+enum Stop<E: Error> {
+    #[error(transparent)]
+    Sink(E),
+    #[error("{0}")]
+    Invalid(Box<InvalidQuad>),
+}
+
+fn copy_quad<Q: Quad>(quad: &Q) -> BoxQuad {
+    (
+        [
+            BoxTerm::copy(quad.s()),
+            BoxTerm::copy(quad.p()),
+            BoxTerm::copy(quad.o()),
+        ],
+        quad.g().map(BoxTerm::copy),
+    )
+}
+
+/// Check that `txt` contains no suspicious character.
+fn check_text(txt: &str) -> Result<(), String> {
+    match txt.chars().find(|c| is_bad_char(*c)) {
+        Some(c) => Err(format!("invalid character {:?}", c)),
+        None => Ok(()),
+    }
+}
+
+/// Control characters (except tabulations and line breaks),
+/// non-characters and replacement characters.
+fn is_bad_char(c: char) -> bool {
+    let cp = c as u32;
+    (c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        || (0xFDD0..=0xFDEF).contains(&cp)
+        || cp & 0xFFFE == 0xFFFE
+        || c == '\u{FFFD}'
+}
+
+/// Whether `lex` is valid for `dt`, if `dt` is a numeric datatype.
+fn numeric<T: TTerm + ?Sized>(lex: &str, dt: &T) -> Option<bool> {
+    let numeric = [
+        &xsd::decimal,
+        &xsd::double,
+        &xsd::float,
+        &xsd::integer,
+        &xsd::nonNegativeInteger,
+        &xsd::positiveInteger,
+        &xsd::nonPositiveInteger,
+        &xsd::negativeInteger,
+        &xsd::long,
+        &xsd::int,
+        &xsd::short,
+        &xsd::byte,
+        &xsd::unsignedLong,
+        &xsd::unsignedInt,
+        &xsd::unsignedShort,
+        &xsd::unsignedByte,
+    ];
+    numeric
+        .iter()
+        .find(|ndt| **ndt == dt)
+        .map(|ndt| canonical_numeric(lex, ndt).is_some())
+}
+
+fn is_base64(lex: &str) -> bool {
+    let chars: Vec<u8> = lex.bytes().filter(|b| *b != b' ').collect();
+    if !chars.len().is_multiple_of(4) {
+        return false;
+    }
+    let padding = chars.iter().rev().take_while(|b| **b == b'=').count();
+    padding <= 2
+        && chars[..chars.len() - padding]
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/')
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+    use sophia_term::StaticTerm;
+
+    #[test]
+    fn literals() {
+        let lit = |lex, dt| StaticTerm::new_literal_dt_unchecked(lex, dt);
+        assert!(check_literal(&lit("12", xsd::integer)).is_ok());
+        assert!(check_literal(&lit("twelve", xsd::integer)).is_err());
+        assert!(check_literal(&lit("-1", xsd::unsignedInt)).is_err());
+        assert!(check_literal(&lit("1e3", xsd::double)).is_ok());
+        assert!(check_literal(&lit("2021-13-01", xsd::date)).is_err());
+        assert!(check_literal(&lit("yes", xsd::boolean)).is_err());
+        assert!(check_literal(&lit("0FB7", xsd::hexBinary)).is_ok());
+        assert!(check_literal(&lit("0FB", xsd::hexBinary)).is_err());
+        assert!(check_literal(&lit("aGVsbG8=", xsd::base64Binary)).is_ok());
+        assert!(check_literal(&lit("aGVsbG8", xsd::base64Binary)).is_err());
+        assert!(check_literal(&lit("anything", xsd::string)).is_ok());
+        assert!(check_literal(&StaticTerm::new_iri_unchecked("tag:x")).is_ok());
+    }
+
+    #[test]
+    fn graph() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            @prefix xsd: <http://www.w3.org/2001/XMLSchema#>.
+            <tag:a> <tag:age> "42"^^xsd:integer, "forty-two"^^xsd:integer;
+                <tag:birth> "1980-02-30"^^xsd:date;
+                <tag:name> "A".
+            "#,
+        )
+        .collect_triples()?;
+        let mut violations = validate_literals(&g)?;
+        violations.sort_by_key(|v| v.literal.value().to_string());
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].literal.value(), "1980-02-30");
+        assert_eq!(violations[1].triple[1].value(), "tag:age");
+        assert_eq!(
+            violations[1].to_string(),
+            "\"forty-two\"^^<http://www.w3.org/2001/XMLSchema#integer> in <tag:a> <tag:age> \"forty-two\"^^<http://www.w3.org/2001/XMLSchema#integer>: \"forty-two\" is not a valid lexical form for <http://www.w3.org/2001/XMLSchema#integer>"
+        );
+        Ok(())
+    }
+
+    struct Even;
+
+    impl crate::datatype::DatatypeHandler for Even {
+        type Value = u64;
+
+        fn parse(&self, lex: &str) -> Result<u64, String> {
+            match lex.parse::<u64>() {
+                Ok(n) if n % 2 == 0 => Ok(n),
+                _ => Err("not an even number".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_datatypes() -> Result<(), Box<dyn std::error::Error>> {
+        let g: FastGraph = turtle::parse_str(
+            r#"
+            <tag:a> <tag:p> "2"^^<tag:even>, "3"^^<tag:even>, "3"^^<tag:odd>.
+            "#,
+        )
+        .collect_triples()?;
+        assert!(validate_literals(&g)?.is_empty());
+        let mut registry = DatatypeRegistry::new();
+        registry.register(&StaticTerm::new_iri_unchecked("tag:even"), Even);
+        let violations = validate_literals_with(&g, &registry)?;
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].literal.value(), "3");
+        assert_eq!(
+            violations[0].reason,
+            "\"3\" is not a valid lexical form for <tag:even>: not an even number"
+        );
+
+        let three =
+            StaticTerm::new_literal_dt_unchecked("3", StaticTerm::new_iri_unchecked("tag:even"));
+        let mut v = QuadValidator::default();
+        v.set_datatypes(true);
+        assert!(v.check_term(&three).is_ok());
+        v.set_registry(Some(Arc::new(registry)));
+        assert!(v.registry().is_some());
+        assert!(v.check_term(&three).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn quad_validator() {
+        let v = QuadValidator::default();
+        let lang = |tag| StaticTerm::new_literal_lang_unchecked("chat", tag);
+        assert!(v
+            .check_term(&StaticTerm::new_iri_unchecked("tag:x"))
+            .is_ok());
+        assert!(v.check_term(&StaticTerm::new_iri_unchecked("x")).is_err());
+        assert!(v.check_term(&lang("fr-FR")).is_ok());
+        assert_eq!(v.repair_term(&lang("fr-FR")).unwrap(), lang("fr-FR"));
+        let bad = StaticTerm::new_literal_dt_unchecked("a\u{1}b\u{FFFD}", xsd::string);
+        assert!(v.check_term(&bad).is_err());
+        assert_eq!(v.repair_term(&bad).unwrap().value(), "ab");
+        let ill_typed = StaticTerm::new_literal_dt_unchecked("twelve", xsd::integer);
+        assert!(v.check_term(&ill_typed).is_ok());
+        let mut v = v;
+        v.set_datatypes(true);
+        assert!(v.check_term(&ill_typed).is_err());
+        assert!(v.repair_term(&ill_typed).is_none());
+    }
+
+    #[test]
+    fn validating_source() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::parser::nq;
+        use crate::quad::stream::QuadSource;
+
+        let data = r#"
+            <tag:a> <tag:p> "ok" <tag:g>.
+            <tag:a> <tag:p> "b\u0000d".
+            <tag:a> <tag:p> "twelve"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            <tag:a> <tag:p> "last".
+        "#;
+        let run = |on_invalid| {
+            let mut v = QuadValidator::default();
+            v.set_on_invalid(on_invalid).set_datatypes(true);
+            let mut values = vec![];
+            v.validate_quads(nq::parse_str(data))
+                .for_each_quad(|q| values.push(q.o().value().to_string()))
+                .map(|_| values)
+                .map_err(Box::new)
+        };
+        assert_eq!(run(OnInvalid::Drop)?, vec!["ok", "last"]);
+        assert_eq!(run(OnInvalid::Repair)?, vec!["ok", "bd", "last"]);
+        match run(OnInvalid::Error).map_err(|e| *e) {
+            Err(ValidationError::Invalid(invalid)) => {
+                assert_eq!(invalid.term.value(), "b\u{0}d");
+                assert!(invalid.quad.1.is_none());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+//! Materialized views over a graph, maintained incrementally.
+//!
+//! A [`View`] is defined by a set of [rules]:
+//! its content is the set of triples inferred by applying these rules to a *source* graph.
+//! Contrarily to [`apply_rules`], inferred triples are not added to the source graph,
+//! so rules are not applied recursively to them.
+//! Views defined by a simple graph pattern
+//! (similar to a SPARQL `CONSTRUCT WHERE` query)
+//! can be built with [`View::from_pattern`].
+//!
+//! A [`ViewedGraph`] wraps a source graph, and keeps a number of views up to date.
+//! Whenever a triple is inserted into (or removed from) a `ViewedGraph`,
+//! only the matches of the rules involving this triple are evaluated,
+//! rather than recomputing the views from scratch.
+//! Each triple of a view keeps track of the number of matches that produced it,
+//! so that it disappears from the view exactly when its last match disappears.
+//!
+//! # Example
+//! ```
+//! # use sophia::graph::{inmem::FastGraph, Graph, MutableGraph};
+//! # use sophia::term::StaticTerm;
+//! use sophia::rule::Rule;
+//! use sophia::view::{View, ViewedGraph};
+//!
+//! let (x, y) = (StaticTerm::new_variable("x")?, StaticTerm::new_variable("y")?);
+//! let parent = StaticTerm::new_iri("http://example.org/parent")?;
+//! let child = StaticTerm::new_iri("http://example.org/child")?;
+//! let inverse = Rule::new(vec![[x, parent, y]], vec![[y, child, x]])?;
+//!
+//! let mut g = ViewedGraph::new(FastGraph::new());
+//! let children = g.add_view(View::new(vec![inverse]))?;
+//! let alice = StaticTerm::new_iri("http://example.org/alice")?;
+//! let bob = StaticTerm::new_iri("http://example.org/bob")?;
+//!
+//! g.insert(&alice, &parent, &bob)?;
+//! assert!(g.views()[children].graph().contains(&bob, &child, &alice)?);
+//! g.remove(&alice, &parent, &bob)?;
+//! assert_eq!(g.views()[children].len(), 0);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [rules]: ../rule/struct.Rule.html
+//! [`apply_rules`]: ../rule/fn.apply_rules.html
+//! [`View`]: struct.View.html
+//! [`View::from_pattern`]: struct.View.html#method.from_pattern
+//! [`ViewedGraph`]: struct.ViewedGraph.html
+
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::graph::{GResult, GTripleSource, Graph, MGResult, MutableGraph, SetGraph};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamError};
+use sophia_api::triple::Triple;
+use sophia_term::RcTerm;
+
+use crate::graph::inmem::FastGraph;
+use crate::query::{BindingMap, Query};
+use crate::rule::{copy_triple, instantiate, match_triple, Rule, RuleError};
+
+/// A set of triples derived from a source graph by a set of rules.
+///
+/// See [module documentation](index.html).
+pub struct View {
+    rules: Vec<Rule>,
+    support: HashMap<[RcTerm; 3], usize>,
+    graph: FastGraph,
+}
+
+impl View {
+    /// Build an (empty) view defined by the given rules.
+    ///
+    /// The view is populated when added to a [`ViewedGraph`](struct.ViewedGraph.html).
+    pub fn new(rules: Vec<Rule>) -> View {
+        View {
+            rules,
+            support: HashMap::new(),
+            graph: FastGraph::new(),
+        }
+    }
+
+    /// Build an (empty) view containing all the instances of the given graph pattern.
+    ///
+    /// Fails if the pattern is empty.
+    pub fn from_pattern<P>(pattern: P) -> Result<View, RuleError>
+    where
+        P: IntoIterator,
+        P::Item: Triple,
+    {
+        let pattern: Vec<_> = pattern.into_iter().map(|t| copy_triple(&t)).collect();
+        Ok(View::new(vec![Rule::new(pattern.clone(), pattern)?]))
+    }
+
+    /// The rules defining this view.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The triples of this view.
+    pub fn graph(&self) -> &FastGraph {
+        &self.graph
+    }
+
+    /// The number of triples in this view.
+    pub fn len(&self) -> usize {
+        self.support.len()
+    }
+
+    /// Whether this view is empty.
+    pub fn is_empty(&self) -> bool {
+        self.support.is_empty()
+    }
+
+    /// The number of rule matches producing the given triple in this view
+    /// (0 if the triple is not in this view).
+    pub fn support<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> usize
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        let key = [RcTerm::copy(s), RcTerm::copy(p), RcTerm::copy(o)];
+        self.support.get(&key).copied().unwrap_or(0)
+    }
+
+    /// Compute this view from scratch.
+    fn materialize<G: Graph>(&mut self, source: &G) -> GResult<G, ()> {
+        self.support.clear();
+        self.graph = FastGraph::new();
+        let mut produced = vec![];
+        for rule in &self.rules {
+            let mut query = Query::Triples(rule.body().to_vec());
+            for binding in query.process(source) {
+                let binding = binding?;
+                for template in rule.head() {
+                    produced.push(instantiate(template, &binding));
+                }
+            }
+        }
+        for t in produced {
+            self.add(t);
+        }
+        Ok(())
+    }
+
+    /// Update this view after `triple` was inserted into `source` (if `inserted` is true),
+    /// or before it is removed from `source` (if `inserted` is false).
+    ///
+    /// In both cases, `source` must contain `triple`.
+    fn update<G: Graph>(
+        &mut self,
+        source: &G,
+        triple: &[RcTerm; 3],
+        inserted: bool,
+    ) -> GResult<G, ()> {
+        let mut produced = vec![];
+        for rule in &self.rules {
+            // a match may involve `triple` several times, but must only be counted once
+            let mut seen = HashSet::new();
+            for (i, pattern) in rule.body().iter().enumerate() {
+                let binding = match match_triple(pattern, triple) {
+                    Some(binding) => binding,
+                    None => continue,
+                };
+                let mut rest = rule.body().to_vec();
+                rest.remove(i);
+                let mut query = Query::Triples(rest);
+                for binding in query.process_with(source, binding) {
+                    let binding = binding?;
+                    if seen.insert(binding_key(&binding)) {
+                        for template in rule.head() {
+                            produced.push(instantiate(template, &binding));
+                        }
+                    }
+                }
+            }
+        }
+        for t in produced {
+            if inserted {
+                self.add(t);
+            } else {
+                self.retract(t);
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, t: [RcTerm; 3]) {
+        let count = self.support.entry(t.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            // FastGraph never fails
+            self.graph.insert(&t[0], &t[1], &t[2]).unwrap();
+        }
+    }
+
+    fn retract(&mut self, t: [RcTerm; 3]) {
+        if let Some(count) = self.support.get_mut(&t) {
+            *count -= 1;
+            if *count == 0 {
+                self.support.remove(&t);
+                // FastGraph never fails
+                self.graph.remove(&t[0], &t[1], &t[2]).unwrap();
+            }
+        }
+    }
+}
+
+/// A graph wrapper keeping a number of [views](struct.View.html) up to date.
+///
+/// See [module documentation](index.html).
+///
+/// Note that the views are only maintained when the graph is modified through this wrapper.
+pub struct ViewedGraph<G> {
+    source: G,
+    views: Vec<View>,
+}
+
+impl<G> ViewedGraph<G>
+where
+    G: Graph,
+{
+    /// Wrap the given source graph, without any view.
+    pub fn new(source: G) -> Self {
+        ViewedGraph {
+            source,
+            views: vec![],
+        }
+    }
+
+    /// The source graph.
+    pub fn source(&self) -> &G {
+        &self.source
+    }
+
+    /// Unwrap the source graph.
+    pub fn into_source(self) -> G {
+        self.source
+    }
+
+    /// The views of this graph, in the order in which they were added.
+    pub fn views(&self) -> &[View] {
+        &self.views
+    }
+
+    /// Add a view to this graph, and return its position in [`views`](#method.views).
+    ///
+    /// The view is computed from the current content of the source graph.
+    pub fn add_view(&mut self, mut view: View) -> GResult<G, usize> {
+        view.materialize(&self.source)?;
+        self.views.push(view);
+        Ok(self.views.len() - 1)
+    }
+
+    /// Remove the view at the given position, and return it.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove_view(&mut self, index: usize) -> View {
+        self.views.remove(index)
+    }
+
+    fn update_views(&mut self, triple: &[RcTerm; 3], inserted: bool) -> GResult<G, ()> {
+        for view in &mut self.views {
+            view.update(&self.source, triple, inserted)?;
+        }
+        Ok(())
+    }
+}
+
+impl<G> Graph for ViewedGraph<G>
+where
+    G: Graph,
+{
+    type Triple = G::Triple;
+    type Error = G::Error;
+
+    fn triples(&self) -> GTripleSource<'_, Self> {
+        self.source.triples()
+    }
+
+    fn is_set(&self) -> bool {
+        self.source.is_set()
+    }
+
+    fn triples_with_s<'s, TS>(&'s self, s: &'s TS) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+    {
+        self.source.triples_with_s(s)
+    }
+
+    fn triples_with_p<'s, TP>(&'s self, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+    {
+        self.source.triples_with_p(p)
+    }
+
+    fn triples_with_o<'s, TO>(&'s self, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TO: TTerm + ?Sized,
+    {
+        self.source.triples_with_o(o)
+    }
+
+    fn triples_with_sp<'s, TS, TP>(&'s self, s: &'s TS, p: &'s TP) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+    {
+        self.source.triples_with_sp(s, p)
+    }
+
+    fn triples_with_so<'s, TS, TO>(&'s self, s: &'s TS, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.source.triples_with_so(s, o)
+    }
+
+    fn triples_with_po<'s, TP, TO>(&'s self, p: &'s TP, o: &'s TO) -> GTripleSource<'s, Self>
+    where
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.source.triples_with_po(p, o)
+    }
+
+    fn triples_with_spo<'s, TS, TP, TO>(
+        &'s self,
+        s: &'s TS,
+        p: &'s TP,
+        o: &'s TO,
+    ) -> GTripleSource<'s, Self>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.source.triples_with_spo(s, p, o)
+    }
+
+    fn contains<TS, TP, TO>(&self, s: &TS, p: &TP, o: &TO) -> GResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        self.source.contains(s, p, o)
+    }
+
+    fn indexed_positions(&self) -> [bool; 3] {
+        self.source.indexed_positions()
+    }
+}
+
+/// Views are only maintained on top of [`SetGraph`]s,
+/// as their incremental maintenance relies on knowing whether a triple was actually inserted or removed.
+///
+/// Errors raised while evaluating the rules of the views are wrapped in `SourceError`,
+/// errors raised while modifying the source graph are wrapped in `SinkError`.
+///
+/// [`SetGraph`]: ../graph/trait.SetGraph.html
+impl<G> MutableGraph for ViewedGraph<G>
+where
+    G: MutableGraph + SetGraph,
+{
+    type MutationError = StreamError<G::Error, G::MutationError>;
+
+    fn insert<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if !self.source.insert(s, p, o).map_err(SinkError)? {
+            return Ok(false);
+        }
+        let triple = [RcTerm::copy(s), RcTerm::copy(p), RcTerm::copy(o)];
+        self.update_views(&triple, true).map_err(SourceError)?;
+        Ok(true)
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> MGResult<Self, bool>
+    where
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        if !self.source.contains(s, p, o).map_err(SourceError)? {
+            return Ok(false);
+        }
+        let triple = [RcTerm::copy(s), RcTerm::copy(p), RcTerm::copy(o)];
+        self.update_views(&triple, false).map_err(SourceError)?;
+        self.source.remove(s, p, o).map_err(SinkError)?;
+        Ok(true)
+    }
+}
+
+impl<G> SetGraph for ViewedGraph<G> where G: SetGraph {}
+
+/// A hashable representation of a binding map.
+fn binding_key(binding: &BindingMap) -> Vec<(String, RcTerm)> {
+    let mut key: Vec<_> = binding
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    key.sort_by(|a, b| a.0.cmp(&b.0));
+    key
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::rdf;
+    use sophia_term::StaticTerm;
+
+    fn var(name: &'static str) -> StaticTerm {
+        StaticTerm::new_variable(name).unwrap()
+    }
+
+    fn iri(iri: &'static str) -> StaticTerm {
+        StaticTerm::new_iri(iri).unwrap()
+    }
+
+    fn grandparent() -> Rule {
+        let (x, y, z) = (var("x"), var("y"), var("z"));
+        let (parent, grandparent) = (iri("tag:parent"), iri("tag:grandparent"));
+        Rule::new(
+            vec![[x, parent, y], [y, parent, z]],
+            vec![[x, grandparent, z]],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn incremental() -> Result<(), Box<dyn std::error::Error>> {
+        let parent = iri("tag:parent");
+        let people = ["tag:a", "tag:b", "tag:c", "tag:d"];
+        let mut g = ViewedGraph::new(FastGraph::new());
+        g.insert(&iri(people[0]), &parent, &iri(people[1]))?;
+        let v = g.add_view(View::new(vec![grandparent()]))?;
+        assert_eq!(g.views()[v].len(), 0);
+
+        // apply a sequence of modifications, and compare with a view computed from scratch
+        let edits = [
+            (true, 1, 2),
+            (true, 2, 3),
+            (true, 0, 2),
+            (true, 1, 1),
+            (false, 1, 2),
+            (true, 1, 2),
+            (false, 1, 1),
+            (false, 0, 1),
+            (false, 3, 0),
+        ];
+        for (insert, s, o) in edits.iter().copied() {
+            let (s, o) = (iri(people[s]), iri(people[o]));
+            if insert {
+                g.insert(&s, &parent, &o)?;
+            } else {
+                g.remove(&s, &parent, &o)?;
+            }
+            let mut expected = View::new(vec![grandparent()]);
+            expected.materialize(g.source())?;
+            assert_eq!(g.views()[v].support, expected.support);
+            assert_eq!(g.views()[v].graph().triples().count(), expected.len());
+        }
+        let grandparent = iri("tag:grandparent");
+        assert_eq!(
+            g.views()[v].support(&iri("tag:b"), &grandparent, &iri("tag:d")),
+            1
+        );
+        assert_eq!(g.views()[v].len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn self_join() -> Result<(), Box<dyn std::error::Error>> {
+        // both patterns of the body can match the same triple
+        let parent = iri("tag:parent");
+        let mut g = ViewedGraph::new(FastGraph::new());
+        let v = g.add_view(View::new(vec![grandparent()]))?;
+        g.insert(&iri("tag:a"), &parent, &iri("tag:a"))?;
+        assert_eq!(
+            g.views()[v].support(&iri("tag:a"), &iri("tag:grandparent"), &iri("tag:a")),
+            1
+        );
+        g.remove(&iri("tag:a"), &parent, &iri("tag:a"))?;
+        assert!(g.views()[v].is_empty());
+        assert!(!g.remove(&iri("tag:a"), &parent, &iri("tag:a"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn pattern() -> Result<(), Box<dyn std::error::Error>> {
+        let (x, type_, person) = (var("x"), StaticTerm::from(rdf::type_), iri("tag:Person"));
+        let name = iri("tag:name");
+        let mut g = ViewedGraph::new(FastGraph::new());
+        let v = g.add_view(View::from_pattern(vec![
+            [x, type_, person],
+            [x, name, var("n")],
+        ])?)?;
+        g.insert(&iri("tag:a"), &type_, &person)?;
+        assert!(g.views()[v].is_empty());
+        g.insert(&iri("tag:a"), &name, &iri("tag:alice"))?;
+        assert_eq!(g.views()[v].len(), 2);
+        assert!(g.views()[v]
+            .graph()
+            .contains(&iri("tag:a"), &type_, &person)?);
+        g.remove(&iri("tag:a"), &type_, &person)?;
+        assert!(g.views()[v].is_empty());
+        assert_eq!(g.source().triples().count(), 1);
+        assert_eq!(g.remove_view(v).len(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,502 @@
+//! Rendering of small graphs as diagrams, in [Graphviz DOT] or [Mermaid].
+//!
+//! [`to_dot`] and [`to_mermaid`] draw each subject and object of a graph as a node,
+//! and each triple as an edge labelled with its predicate.
+//! This is mostly useful for visually inspecting data during development,
+//! and is only practical for small graphs:
+//! larger ones can first be reduced to the [neighborhood] of some resources.
+//!
+//! When labels are enabled (the default), IRIs are labelled with their `rdfs:label` in the graph,
+//! or else with the label provided by the [vocabulary registry]
+//! (e.g. `rdfs:subClassOf` is labelled "sub class of"),
+//! or else with their prefixed name (if any).
+//! The appearance of nodes (per kind of term) and edges (per predicate)
+//! can be customized through a [`VizConfig`].
+//!
+//! # Example
+//! ```
+//! use sophia::graph::inmem::FastGraph;
+//! use sophia::parser::turtle;
+//! use sophia::triple::stream::TripleSource;
+//! use sophia::viz::{to_dot, to_mermaid, VizConfig};
+//!
+//! let g: FastGraph = turtle::parse_str(r#"
+//!     @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+//!     <tag:alice> rdfs:label "Alice"; rdfs:seeAlso <tag:bob>.
+//! "#).collect_triples()?;
+//! let dot = to_dot(&g, &VizConfig::default())?;
+//! assert!(dot.starts_with("digraph {"));
+//! assert!(dot.contains(r#"label="Alice""#));
+//! assert!(dot.contains(r#"label="see also""#));
+//! let mermaid = to_mermaid(&g, &VizConfig::default())?;
+//! assert!(mermaid.starts_with("flowchart LR"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [Graphviz DOT]: https://graphviz.org/doc/info/lang.html
+//! [Mermaid]: https://mermaid.js.org/syntax/flowchart.html
+//! [`to_dot`]: fn.to_dot.html
+//! [`to_mermaid`]: fn.to_mermaid.html
+//! [neighborhood]: ../sample/fn.neighborhood.html
+//! [vocabulary registry]: ../vocab/index.html
+//! [`VizConfig`]: struct.VizConfig.html
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::PoisonError;
+
+use sophia_api::ns::rdfs;
+use sophia_api::term::{CopyTerm, TTerm, TermKind};
+use sophia_term::BoxTerm;
+
+use crate::graph::{GResult, Graph};
+use crate::triple::Triple;
+use crate::vocab::{self, VocabRegistry};
+
+/// The shape of a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// A rectangle
+    Box,
+    /// A rectangle with rounded corners
+    Rounded,
+    /// An ellipse (or a stadium, in Mermaid)
+    Ellipse,
+    /// A circle
+    Circle,
+}
+
+/// The appearance of a node.
+///
+/// Colors are passed as is to the rendering tool (e.g. `"red"` or `"#ff0000"`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeStyle {
+    /// The shape of the node
+    pub shape: Shape,
+    /// The color of the border of the node
+    pub color: Option<String>,
+    /// The color of the inside of the node
+    pub fill: Option<String>,
+}
+
+impl NodeStyle {
+    /// A style with the given shape, and the default colors.
+    pub fn new(shape: Shape) -> Self {
+        NodeStyle {
+            shape,
+            color: None,
+            fill: None,
+        }
+    }
+}
+
+/// The appearance of an edge.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EdgeStyle {
+    /// The color of the edge
+    pub color: Option<String>,
+    /// Whether the edge is drawn as a dashed line
+    pub dashed: bool,
+}
+
+/// Configuration of [`to_dot`](fn.to_dot.html) and [`to_mermaid`](fn.to_mermaid.html).
+#[derive(Clone, Debug)]
+pub struct VizConfig {
+    left_to_right: bool,
+    labels: bool,
+    max_label_length: Option<usize>,
+    registry: Option<VocabRegistry>,
+    node_styles: [NodeStyle; 4],
+    edge_style: EdgeStyle,
+    predicate_styles: HashMap<BoxTerm, EdgeStyle>,
+}
+
+impl Default for VizConfig {
+    fn default() -> Self {
+        let mut literal = NodeStyle::new(Shape::Box);
+        literal.fill = Some("#eeeeee".into());
+        VizConfig {
+            left_to_right: true,
+            labels: true,
+            max_label_length: None,
+            registry: None,
+            node_styles: [
+                NodeStyle::new(Shape::Ellipse),
+                literal,
+                NodeStyle::new(Shape::Circle),
+                NodeStyle::new(Shape::Rounded),
+            ],
+            edge_style: EdgeStyle::default(),
+            predicate_styles: HashMap::new(),
+        }
+    }
+}
+
+impl VizConfig {
+    /// Set whether the diagram is laid out from left to right (the default),
+    /// or from top to bottom.
+    pub fn set_left_to_right(&mut self, left_to_right: bool) -> &mut Self {
+        self.left_to_right = left_to_right;
+        self
+    }
+
+    /// Set whether IRIs are labelled with human-readable labels (see [module documentation](index.html)),
+    /// rather than with their prefixed name.
+    pub fn set_labels(&mut self, labels: bool) -> &mut Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Set the maximum number of characters of labels (longer ones are truncated with `…`).
+    ///
+    /// By default, labels are never truncated.
+    pub fn set_max_label_length(&mut self, max_label_length: Option<usize>) -> &mut Self {
+        self.max_label_length = max_label_length;
+        self
+    }
+
+    /// Set the registry providing prefixes and labels.
+    ///
+    /// By default, the [global registry](../vocab/fn.global.html) is used.
+    pub fn set_registry(&mut self, registry: Option<VocabRegistry>) -> &mut Self {
+        self.registry = registry;
+        self
+    }
+
+    /// The style of nodes of the given kind.
+    pub fn node_style(&self, kind: TermKind) -> &NodeStyle {
+        &self.node_styles[kind_index(kind)]
+    }
+
+    /// Set the style of nodes of the given kind.
+    ///
+    /// By default, IRIs are drawn as ellipses, literals as grey boxes,
+    /// blank nodes as circles and variables as rounded boxes.
+    pub fn set_node_style(&mut self, kind: TermKind, style: NodeStyle) -> &mut Self {
+        self.node_styles[kind_index(kind)] = style;
+        self
+    }
+
+    /// Set the style of the edges whose predicate has no specific style.
+    pub fn set_edge_style(&mut self, style: EdgeStyle) -> &mut Self {
+        self.edge_style = style;
+        self
+    }
+
+    /// Set the style of the edges with the given predicate.
+    pub fn set_predicate_style<T>(&mut self, predicate: &T, style: EdgeStyle) -> &mut Self
+    where
+        T: TTerm + ?Sized,
+    {
+        self.predicate_styles
+            .insert(BoxTerm::copy(predicate), style);
+        self
+    }
+
+    /// The style of the edges with the given predicate.
+    pub fn predicate_style<T>(&self, predicate: &T) -> &EdgeStyle
+    where
+        T: TTerm + ?Sized,
+    {
+        self.predicate_styles
+            .get(&BoxTerm::copy(predicate))
+            .unwrap_or(&self.edge_style)
+    }
+}
+
+/// Render `graph` in the [Graphviz DOT](https://graphviz.org/doc/info/lang.html) language.
+pub fn to_dot<G>(graph: &G, config: &VizConfig) -> GResult<G, String>
+where
+    G: Graph + ?Sized,
+{
+    let diagram = Diagram::new(graph, config)?;
+    let mut dot = String::from("digraph {\n");
+    let rankdir = if config.left_to_right { "LR" } else { "TB" };
+    writeln!(dot, "  rankdir={};", rankdir).unwrap();
+    for (i, (term, label)) in diagram.nodes.iter().enumerate() {
+        let style = config.node_style(term.kind());
+        let shape = match style.shape {
+            Shape::Box | Shape::Rounded => "box",
+            Shape::Ellipse => "ellipse",
+            Shape::Circle => "circle",
+        };
+        write!(
+            dot,
+            "  n{} [label=\"{}\", shape={}",
+            i,
+            dot_escape(label),
+            shape
+        )
+        .unwrap();
+        let mut styles = vec![];
+        if style.shape == Shape::Rounded {
+            styles.push("rounded");
+        }
+        if let Some(fill) = &style.fill {
+            styles.push("filled");
+            write!(dot, ", fillcolor=\"{}\"", dot_escape(fill)).unwrap();
+        }
+        if !styles.is_empty() {
+            write!(dot, ", style=\"{}\"", styles.join(",")).unwrap();
+        }
+        if let Some(color) = &style.color {
+            write!(dot, ", color=\"{}\"", dot_escape(color)).unwrap();
+        }
+        dot.push_str("];\n");
+    }
+    for (s, o, label, style) in &diagram.edges {
+        write!(dot, "  n{} -> n{} [label=\"{}\"", s, o, dot_escape(label)).unwrap();
+        if let Some(color) = &style.color {
+            write!(dot, ", color=\"{}\"", dot_escape(color)).unwrap();
+        }
+        if style.dashed {
+            dot.push_str(", style=dashed");
+        }
+        dot.push_str("];\n");
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// Render `graph` as a [Mermaid flowchart](https://mermaid.js.org/syntax/flowchart.html).
+pub fn to_mermaid<G>(graph: &G, config: &VizConfig) -> GResult<G, String>
+where
+    G: Graph + ?Sized,
+{
+    let diagram = Diagram::new(graph, config)?;
+    let direction = if config.left_to_right { "LR" } else { "TB" };
+    let mut mmd = format!("flowchart {}\n", direction);
+    for (i, (term, label)) in diagram.nodes.iter().enumerate() {
+        let (open, close) = match config.node_style(term.kind()).shape {
+            Shape::Box => ("[", "]"),
+            Shape::Rounded => ("(", ")"),
+            Shape::Ellipse => ("([", "])"),
+            Shape::Circle => ("((", "))"),
+        };
+        let label = mermaid_escape(label);
+        writeln!(mmd, "  n{}{}\"{}\"{}", i, open, label, close).unwrap();
+    }
+    for (s, o, label, style) in &diagram.edges {
+        let arrow = if style.dashed { "-.->" } else { "-->" };
+        let label = mermaid_escape(label);
+        writeln!(mmd, "  n{} {}|\"{}\"| n{}", s, arrow, label, o).unwrap();
+    }
+    for (kind, class) in [
+        (TermKind::Iri, "iri"),
+        (TermKind::Literal, "literal"),
+        (TermKind::BlankNode, "bnode"),
+        (TermKind::Variable, "variable"),
+    ] {
+        let style = config.node_style(kind);
+        let mut props = vec![];
+        if let Some(fill) = &style.fill {
+            props.push(format!("fill:{}", fill));
+        }
+        if let Some(color) = &style.color {
+            props.push(format!("stroke:{}", color));
+        }
+        let nodes: Vec<_> = diagram
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, (t, _))| t.kind() == kind)
+            .map(|(i, _)| format!("n{}", i))
+            .collect();
+        if !props.is_empty() && !nodes.is_empty() {
+            writeln!(mmd, "  classDef {} {}", class, props.join(",")).unwrap();
+            writeln!(mmd, "  class {} {}", nodes.join(","), class).unwrap();
+        }
+    }
+    for (i, (_, _, _, style)) in diagram.edges.iter().enumerate() {
+        if let Some(color) = &style.color {
+            writeln!(mmd, "  linkStyle {} stroke:{}", i, color).unwrap();
+        }
+    }
+    Ok(mmd)
+}
+
+/// The nodes and edges of a graph, with their labels.
+struct Diagram<'c> {
+    nodes: Vec<(BoxTerm, String)>,
+    edges: Vec<(usize, usize, String, &'c EdgeStyle)>,
+}
+
+impl<'c> Diagram<'c> {
+    fn new<G>(graph: &G, config: &'c VizConfig) -> GResult<G, Self>
+    where
+        G: Graph + ?Sized,
+    {
+        let guard;
+        let registry = match &config.registry {
+            Some(registry) => registry,
+            None => {
+                guard = vocab::global()
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner);
+                &*guard
+            }
+        };
+        let mut index: HashMap<BoxTerm, usize> = HashMap::new();
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        let mut node = |t: BoxTerm, nodes: &mut Vec<BoxTerm>| {
+            *index.entry(t).or_insert_with_key(|t| {
+                nodes.push(t.clone());
+                nodes.len() - 1
+            })
+        };
+        for t in graph.triples() {
+            let t = t?;
+            let s = node(BoxTerm::copy(t.s()), &mut nodes);
+            let o = node(BoxTerm::copy(t.o()), &mut nodes);
+            let p = BoxTerm::copy(t.p());
+            let label = label(graph, &p, config, registry)?;
+            edges.push((s, o, label, config.predicate_style(&p)));
+        }
+        let nodes = nodes
+            .into_iter()
+            .map(|t| {
+                let label = label(graph, &t, config, registry)?;
+                Ok((t, label))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Diagram { nodes, edges })
+    }
+}
+
+/// The label of `term` in a diagram (see [module documentation](index.html)).
+fn label<G>(
+    graph: &G,
+    term: &BoxTerm,
+    config: &VizConfig,
+    registry: &VocabRegistry,
+) -> GResult<G, String>
+where
+    G: Graph + ?Sized,
+{
+    let mut label = None;
+    if config.labels {
+        match term.kind() {
+            TermKind::Iri => {
+                if let Some(t) = graph.triples_with_sp(term, &rdfs::label).next() {
+                    label = Some(t?.o().value().to_string());
+                } else if registry.lookup(&term.value()).is_some() {
+                    label = Some(registry.label(&term.value()));
+                }
+            }
+            TermKind::Literal => label = Some(term.value().to_string()),
+            _ => (),
+        }
+    }
+    let label = label.unwrap_or_else(|| registry.format_term(term));
+    Ok(match config.max_label_length {
+        Some(max) if label.chars().count() > max => {
+            let mut truncated: String = label.chars().take(max.saturating_sub(1)).collect();
+            truncated.push('…');
+            truncated
+        }
+        _ => label,
+    })
+}
+
+fn kind_index(kind: TermKind) -> usize {
+    match kind {
+        TermKind::Iri => 0,
+        TermKind::Literal => 1,
+        TermKind::BlankNode => 2,
+        TermKind::Variable => 3,
+    }
+}
+
+fn dot_escape(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn mermaid_escape(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '\n' => escaped.push_str("<br>"),
+            '\r' => (),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::inmem::FastGraph;
+    use crate::parser::turtle;
+    use crate::triple::stream::TripleSource;
+
+    fn graph(ttl: &str) -> FastGraph {
+        turtle::parse_str(ttl).collect_triples().unwrap()
+    }
+
+    #[test]
+    fn dot() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph(r#"<tag:a> <tag:says> "say \"hi\"", _:b."#);
+        let mut config = VizConfig::default();
+        config
+            .set_left_to_right(false)
+            .set_predicate_style(
+                &BoxTerm::new_iri("tag:says")?,
+                EdgeStyle {
+                    color: Some("red".into()),
+                    dashed: true,
+                },
+            )
+            .set_registry(Some(VocabRegistry::new()));
+        let dot = to_dot(&g, &config)?;
+        assert!(dot.contains("rankdir=TB;"));
+        assert!(dot.contains(r#"[label="<tag:a>", shape=ellipse];"#));
+        assert!(dot.contains(
+            r##"[label="say \"hi\"", shape=box, fillcolor="#eeeeee", style="filled"];"##
+        ));
+        assert!(dot.contains("shape=circle];"));
+        assert_eq!(
+            dot.matches(r#"[label="<tag:says>", color="red", style=dashed];"#)
+                .count(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mermaid() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph(
+            r#"@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#>.
+            <tag:a> rdfs:subClassOf <tag:b>.
+            <tag:b> rdfs:label "The \"B\" class"."#,
+        );
+        let mut config = VizConfig::default();
+        config.set_max_label_length(Some(8));
+        let mmd = to_mermaid(&g, &config)?;
+        assert!(mmd.contains(r#"(["The #quot;B#quot;…"])"#));
+        assert!(mmd.contains(r#"-->|"sub cla…"|"#));
+        assert!(mmd.contains(r#"(["<tag:a>"])"#));
+        assert!(mmd.contains("classDef literal fill:#eeeeee"));
+
+        config.set_labels(false).set_max_label_length(None);
+        let mmd = to_mermaid(&g, &config)?;
+        assert!(mmd.contains(r#"-->|"rdfs:subClassOf"|"#));
+        Ok(())
+    }
+}
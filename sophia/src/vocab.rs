@@ -0,0 +1,412 @@
+//! A registry of vocabularies, for reverse lookup of IRIs.
+//!
+//! A [`VocabRegistry`] records vocabularies (a prefix, a namespace, and optionally their terms),
+//! typically from namespace modules generated by the [`namespace!`] macro.
+//! Given an arbitrary IRI, it finds the vocabulary it belongs to,
+//! and provides its prefix, its local name, and a human-readable label
+//! (either registered explicitly, or derived from the local name,
+//! e.g. `subClassOf` is labelled "sub class of").
+//! This allows serializers and debugging tools to display `rdfs:label`
+//! rather than `<http://www.w3.org/2000/01/rdf-schema#label>`.
+//!
+//! [`VocabRegistry::with_builtins`] contains the vocabularies shipped with Sophia;
+//! [`global`] gives access to a process-wide registry,
+//! initialized with those vocabularies,
+//! where applications can register their own.
+//!
+//! # Example
+//! ```
+//! use sophia::ns::rdfs;
+//! use sophia::vocab::VocabRegistry;
+//!
+//! mod foaf {
+//!     sophia_api::namespace!("http://xmlns.com/foaf/0.1/", Person, name, knows);
+//! }
+//!
+//! let mut registry = VocabRegistry::with_builtins();
+//! registry.register("foaf", foaf::PREFIX, foaf::ALL_TERMS);
+//! registry.set_label("http://xmlns.com/foaf/0.1/knows", "is acquainted with");
+//!
+//! let info = registry.lookup("http://www.w3.org/2000/01/rdf-schema#subClassOf").unwrap();
+//! assert_eq!(info.prefix, "rdfs");
+//! assert_eq!(info.local, "subClassOf");
+//! assert_eq!(info.label, "sub class of");
+//! assert!(info.known);
+//! assert_eq!(registry.lookup("http://xmlns.com/foaf/0.1/knows").unwrap().label, "is acquainted with");
+//! assert!(!registry.lookup("http://xmlns.com/foaf/0.1/mbox").unwrap().known);
+//! assert_eq!(registry.format_term(&foaf::Person), "foaf:Person");
+//! assert_eq!(registry.format_term(&rdfs::label), "rdfs:label");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`VocabRegistry`]: struct.VocabRegistry.html
+//! [`VocabRegistry::with_builtins`]: struct.VocabRegistry.html#method.with_builtins
+//! [`global`]: fn.global.html
+//! [`namespace!`]: https://docs.rs/sophia_api/latest/sophia_api/macro.namespace.html
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{OnceLock, RwLock};
+
+use sophia_api::ns::{geo, owl, rdf, rdfs, xml, xsd};
+use sophia_api::prefix::{term_to_turtle_with, NamespaceIndex};
+use sophia_api::term::{SimpleIri, TTerm};
+
+/// A registered vocabulary.
+#[derive(Clone, Debug, Default)]
+struct Vocabulary {
+    prefix: String,
+    namespace: String,
+    terms: HashSet<String>,
+}
+
+/// What a [`VocabRegistry`](struct.VocabRegistry.html) knows about an IRI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermInfo<'a> {
+    /// The prefix of the vocabulary containing the IRI.
+    pub prefix: &'a str,
+    /// The namespace of the vocabulary containing the IRI.
+    pub namespace: &'a str,
+    /// The local name of the IRI in its vocabulary.
+    pub local: &'a str,
+    /// A human-readable label for the IRI.
+    pub label: String,
+    /// Whether the IRI is a registered term of its vocabulary
+    /// (otherwise, it only starts with its namespace).
+    pub known: bool,
+}
+
+impl TermInfo<'_> {
+    /// The IRI as a prefixed name (e.g. `rdfs:label`).
+    ///
+    /// Note that the local name is not escaped,
+    /// so the result is not always valid Turtle.
+    pub fn prefixed_name(&self) -> String {
+        format!("{}:{}", self.prefix, self.local)
+    }
+}
+
+/// A registry of vocabularies.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct VocabRegistry {
+    vocabs: Vec<Vocabulary>,
+    labels: HashMap<String, String>,
+}
+
+impl VocabRegistry {
+    /// Build an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a registry containing the vocabularies shipped with Sophia:
+    /// `rdf`, `rdfs`, `xsd`, `owl`, `xml` and `geo`
+    /// (from [`sophia::ns`](../ns/index.html)),
+    /// as well as `skos`, `dcat`, `dcterms` and `void`
+    /// (from the corresponding modules of this crate),
+    /// and, depending on the enabled features,
+    /// `ldp` (`http`), `sec` (`signing`), `rr`, `rml` and `ql` (`rml`).
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register("rdf", rdf::PREFIX, rdf::ALL_TERMS)
+            .register("rdfs", rdfs::PREFIX, rdfs::ALL_TERMS)
+            .register("xsd", xsd::PREFIX, xsd::ALL_TERMS)
+            .register("owl", owl::PREFIX, owl::ALL_TERMS)
+            .register("xml", xml::PREFIX, xml::ALL_TERMS)
+            .register("geo", geo::PREFIX, geo::ALL_TERMS)
+            .register(
+                "skos",
+                crate::skos::skos::PREFIX,
+                crate::skos::skos::ALL_TERMS,
+            )
+            .register(
+                "dcat",
+                crate::catalog::dcat::PREFIX,
+                crate::catalog::dcat::ALL_TERMS,
+            )
+            .register(
+                "dcterms",
+                crate::catalog::dcterms::PREFIX,
+                crate::catalog::dcterms::ALL_TERMS,
+            )
+            .register(
+                "void",
+                crate::catalog::void::PREFIX,
+                crate::catalog::void::ALL_TERMS,
+            );
+        #[cfg(feature = "http")]
+        registry.register("ldp", crate::ldp::ldp::PREFIX, crate::ldp::ldp::ALL_TERMS);
+        #[cfg(feature = "signing")]
+        registry.register(
+            "sec",
+            crate::signing::sec::PREFIX,
+            crate::signing::sec::ALL_TERMS,
+        );
+        #[cfg(feature = "rml")]
+        registry
+            .register("rr", crate::rml::rr::PREFIX, crate::rml::rr::ALL_TERMS)
+            .register("rml", crate::rml::rml::PREFIX, crate::rml::rml::ALL_TERMS)
+            .register("ql", crate::rml::ql::PREFIX, crate::rml::ql::ALL_TERMS);
+        registry
+    }
+
+    /// Register a vocabulary, given its prefix, namespace and terms
+    /// (typically the `PREFIX` and `ALL_TERMS` of a namespace module).
+    ///
+    /// Terms not starting with `namespace` are ignored.
+    /// If a vocabulary is already registered with the same prefix,
+    /// it is replaced.
+    pub fn register(&mut self, prefix: &str, namespace: &str, terms: &[SimpleIri]) -> &mut Self {
+        let terms = terms
+            .iter()
+            .filter_map(|t| t.value().strip_prefix(namespace).map(str::to_string))
+            .collect();
+        let vocab = Vocabulary {
+            prefix: prefix.to_string(),
+            namespace: namespace.to_string(),
+            terms,
+        };
+        match self.vocabs.iter_mut().find(|v| v.prefix == prefix) {
+            Some(old) => *old = vocab,
+            None => self.vocabs.push(vocab),
+        }
+        self
+    }
+
+    /// Set the human-readable label of the given IRI,
+    /// overriding the label derived from its local name.
+    pub fn set_label(&mut self, iri: &str, label: &str) -> &mut Self {
+        self.labels.insert(iri.to_string(), label.to_string());
+        self
+    }
+
+    /// The number of registered vocabularies.
+    pub fn len(&self) -> usize {
+        self.vocabs.len()
+    }
+
+    /// Whether no vocabulary is registered.
+    pub fn is_empty(&self) -> bool {
+        self.vocabs.is_empty()
+    }
+
+    /// Iterate over the prefixes and namespaces of the registered vocabularies.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.vocabs
+            .iter()
+            .map(|v| (v.prefix.as_str(), v.namespace.as_str()))
+    }
+
+    /// The namespace registered with the given prefix, if any.
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.vocabs
+            .iter()
+            .find(|v| v.prefix == prefix)
+            .map(|v| v.namespace.as_str())
+    }
+
+    /// Find the vocabulary containing `iri` (the one with the longest matching namespace),
+    /// and return what is known about `iri`.
+    pub fn lookup<'s>(&'s self, iri: &'s str) -> Option<TermInfo<'s>> {
+        let vocab = self
+            .vocabs
+            .iter()
+            .filter(|v| iri.starts_with(&v.namespace))
+            .max_by_key(|v| v.namespace.len())?;
+        let local = &iri[vocab.namespace.len()..];
+        let label = match self.labels.get(iri) {
+            Some(label) => label.clone(),
+            None => label_from_local_name(local),
+        };
+        Some(TermInfo {
+            prefix: &vocab.prefix,
+            namespace: &vocab.namespace,
+            local,
+            label,
+            known: vocab.terms.contains(local),
+        })
+    }
+
+    /// A human-readable label for `iri`:
+    /// its registered label, or a label derived from its local name,
+    /// or `iri` itself if it belongs to no registered vocabulary.
+    pub fn label(&self, iri: &str) -> String {
+        match self.lookup(iri) {
+            Some(info) => info.label,
+            None => self
+                .labels
+                .get(iri)
+                .cloned()
+                .unwrap_or_else(|| iri.to_string()),
+        }
+    }
+
+    /// A [`NamespaceIndex`](../prefix/struct.NamespaceIndex.html)
+    /// of the registered vocabularies, usable by serializers.
+    pub fn namespace_index(&self) -> NamespaceIndex<'_> {
+        self.vocabs
+            .iter()
+            .map(|v| {
+                (
+                    v.prefix.as_str(),
+                    SimpleIri::new_unchecked(v.namespace.as_str(), None),
+                )
+            })
+            .collect()
+    }
+
+    /// Format `term` in Turtle,
+    /// using prefixed names for IRIs in registered vocabularies
+    /// (see [`term_format_turtle`](../prefix/fn.term_format_turtle.html)).
+    pub fn format_term<T>(&self, term: &T) -> String
+    where
+        T: TTerm + ?Sized,
+    {
+        term_to_turtle_with(term, &self.namespace_index())
+    }
+}
+
+/// The process-wide registry,
+/// initialized with [`VocabRegistry::with_builtins`](struct.VocabRegistry.html#method.with_builtins).
+///
+/// Applications and libraries can register their own vocabularies in it,
+/// in order to make them available to every component using it.
+///
+/// # Example
+/// ```
+/// mod ex {
+///     sophia_api::namespace!("http://example.org/vocab#", Thing);
+/// }
+///
+/// sophia::vocab::global()
+///     .write()
+///     .unwrap()
+///     .register("ex", ex::PREFIX, ex::ALL_TERMS);
+/// let registry = sophia::vocab::global().read().unwrap();
+/// assert_eq!(registry.format_term(&ex::Thing), "ex:Thing");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn global() -> &'static RwLock<VocabRegistry> {
+    static GLOBAL: OnceLock<RwLock<VocabRegistry>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(VocabRegistry::with_builtins()))
+}
+
+/// Derive a label from a camel-cased local name,
+/// e.g. `"subClassOf"` gives `"sub class of"` and `"XMLLiteral"` gives `"XML literal"`.
+fn label_from_local_name(local: &str) -> String {
+    let chars: Vec<char> = local.chars().collect();
+    let mut words: Vec<String> = vec![];
+    let mut word = String::new();
+    for (i, c) in chars.iter().copied().enumerate() {
+        if c == '_' || c == '-' || c == '.' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if !prev.is_uppercase() || next_is_lower {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+        .into_iter()
+        .map(|w| {
+            if w.chars()
+                .filter(|c| c.is_alphabetic())
+                .all(char::is_uppercase)
+                && w.len() > 1
+            {
+                w
+            } else {
+                w.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::term::test::TestTerm;
+    use test_case::test_case;
+
+    #[test_case("subClassOf", "sub class of"; "camel case")]
+    #[test_case("XMLLiteral", "XML literal"; "leading acronym")]
+    #[test_case("type", "type"; "single word")]
+    #[test_case("Class", "class"; "capitalized")]
+    #[test_case("hasURL", "has URL"; "trailing acronym")]
+    #[test_case("dc_title-long", "dc title long"; "separators")]
+    #[test_case("", ""; "empty")]
+    fn labels(local: &str, expected: &str) {
+        assert_eq!(label_from_local_name(local), expected);
+    }
+
+    #[test]
+    fn lookup() {
+        let registry = VocabRegistry::with_builtins();
+        let type_ = rdf::type_.value();
+        let info = registry.lookup(&type_).unwrap();
+        assert_eq!(info.prefix, "rdf");
+        assert_eq!(info.namespace, rdf::PREFIX);
+        assert_eq!(info.local, "type");
+        assert_eq!(info.prefixed_name(), "rdf:type");
+        assert!(info.known);
+        assert_eq!(
+            registry
+                .lookup("http://www.w3.org/2004/02/skos/core#prefLabel")
+                .unwrap()
+                .prefixed_name(),
+            "skos:prefLabel"
+        );
+        assert!(registry.lookup("http://example.org/").is_none());
+        assert_eq!(registry.label("http://example.org/"), "http://example.org/");
+    }
+
+    #[test]
+    fn longest_namespace_wins() {
+        let mut registry = VocabRegistry::new();
+        registry
+            .register("ex", "http://example.org/", &[])
+            .register("sub", "http://example.org/sub/", &[]);
+        let info = registry.lookup("http://example.org/sub/thing").unwrap();
+        assert_eq!(info.prefix, "sub");
+        assert_eq!(info.local, "thing");
+        assert!(!info.known);
+        // re-registering a prefix replaces the vocabulary
+        registry.register("sub", "http://example.org/other/", &[]);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry
+                .lookup("http://example.org/sub/thing")
+                .unwrap()
+                .prefix,
+            "ex"
+        );
+        assert_eq!(registry.namespace("sub"), Some("http://example.org/other/"));
+    }
+
+    #[test]
+    fn format_term() {
+        let registry = VocabRegistry::with_builtins();
+        let lit = TestTerm::<&str>::lit_dt("42", xsd::integer);
+        assert_eq!(registry.format_term(&lit), r#""42"^^xsd:integer"#);
+        assert_eq!(registry.format_term(&owl::Thing), "owl:Thing");
+        let other = TestTerm::<&str>::iri("http://example.org/a");
+        assert_eq!(registry.format_term(&other), "<http://example.org/a>");
+    }
+}
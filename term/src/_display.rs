@@ -5,6 +5,8 @@
 use std::fmt;
 use std::io;
 
+use sophia_api::prefix::{term_to_turtle_with, PrefixMap};
+
 use crate::*;
 
 impl<T> fmt::Display for Term<T>
@@ -49,6 +51,17 @@ where
             Variable(var) => var.write_io(w),
         }
     }
+
+    /// Formats the term in the Turtle syntax,
+    /// writing IRIs (including datatypes) as prefixed names when `prefixes` allows it.
+    ///
+    /// See [`term_format_turtle`](https://docs.rs/sophia_api/latest/sophia_api/prefix/fn.term_format_turtle.html).
+    pub fn to_turtle_with<'a, P>(&self, prefixes: &P) -> String
+    where
+        P: PrefixMap<'a>,
+    {
+        term_to_turtle_with(self, prefixes)
+    }
 }
 
 #[cfg(test)]
@@ -98,7 +111,12 @@ pub(crate) mod test {
                 "é \u{10000}".as_literal().into(),
                 // in canonical form, non-ascii characters are NOT escaped in literals
                 "\"é \u{10000}\"",
-            )
+            ),
+            (
+                // control characters other than CR and LF are not escaped
+                StaticTerm::new_literal_dt("a\tb\u{7}", xsd::string).unwrap(),
+                "\"a\tb\u{7}\"",
+            ),
         ];
     }
 
@@ -117,4 +135,34 @@ pub(crate) mod test {
             assert_eq!(&got3, expected);
         }
     }
+
+    #[test]
+    fn turtle() {
+        let mut prefixes = std::collections::HashMap::new();
+        prefixes.insert("xsd", SimpleIri::new_unchecked(xsd::PREFIX, None));
+        prefixes.insert("ex", SimpleIri::new_unchecked("http://example.org/", None));
+        let cases = vec![
+            (
+                StaticTerm::new_iri("http://example.org/foo").unwrap(),
+                "ex:foo",
+            ),
+            (
+                StaticTerm::new_iri("http://example.org/foo/bar").unwrap(),
+                "<http://example.org/foo/bar>",
+            ),
+            (StaticTerm::new_iri("http://example.org/").unwrap(), "ex:"),
+            (
+                StaticTerm::new_literal_dt("42", xsd::integer).unwrap(),
+                r#""42"^^xsd:integer"#,
+            ),
+            (
+                StaticTerm::new_literal_lang("chat", "fr").unwrap(),
+                r#""chat"@fr"#,
+            ),
+            (StaticTerm::new_bnode("b1").unwrap(), "_:b1"),
+        ];
+        for (term, expected) in cases {
+            assert_eq!(term.to_turtle_with(&prefixes), expected);
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use sophia_iri::error::InvalidIri;
 use thiserror::Error;
 
 /// Type alias for `Result` with default error `TermError`.
@@ -12,8 +13,8 @@ pub enum TermError {
     #[error("The supplied datatype '{0}' was not an IRI")]
     InvalidDatatype(String),
     /// The IRI of a term must apply to [RFC 3987](https://tools.ietf.org/html/rfc3987).
-    #[error("The given IRI '{0}' is not valid according to RFC3987")]
-    InvalidIri(String),
+    #[error("{0}")]
+    InvalidIri(#[from] InvalidIri),
     /// An IRI must be represented by one `TermData` to be able to convert it
     /// to a `Namespace`.
     #[error("IRI components could not be turned into a `Namespace` as it has a suffix")]
@@ -50,6 +51,9 @@ pub enum TermError {
     /// Raised when failing to convert a literal into a native type
     #[error("The term '{0}' has an unsupported datatype")]
     UnsupportedDatatype(String),
+    /// Raised when parsing a term from a string with an invalid syntax
+    #[error("The text '{0}' is not a valid term in N-Triples syntax")]
+    InvalidSyntax(String),
 }
 
 impl From<std::convert::Infallible> for TermError {
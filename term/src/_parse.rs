@@ -0,0 +1,189 @@
+// this module is transparently re-exported by its parent `term`
+//
+// Implement the FromStr trait for Term, using the N-Triples syntax.
+
+use std::str::FromStr;
+
+use sophia_api::ns::xsd;
+
+use crate::iri::is_absolute_iri_ref;
+use crate::*;
+
+impl<T> FromStr for Term<T>
+where
+    T: TermData + From<String>,
+{
+    type Err = TermError;
+
+    /// Parse a term in the N-Triples syntax
+    /// (`<http://example.org/>`, `_:b1`, `"chat"@fr`, `"42"^^<http://www.w3.org/2001/XMLSchema#integer>`),
+    /// or a variable in the SPARQL syntax (`?x` or `$x`).
+    ///
+    /// Leading and trailing whitespace is ignored.
+    /// Escape sequences (`\n`, `\u00E9`...) are supported in IRIs and literals.
+    fn from_str(txt: &str) -> Result<Self> {
+        let trimmed = txt.trim();
+        let syntax_err = || TermError::InvalidSyntax(txt.to_string());
+        match trimmed.chars().next() {
+            Some('<') => {
+                let (iri, rest) = parse_iri(trimmed).ok_or_else(syntax_err)?;
+                if !rest.is_empty() || !is_absolute_iri_ref(&iri) {
+                    return Err(syntax_err());
+                }
+                Term::new_iri(iri)
+            }
+            Some('"') => {
+                let (lex, rest) = parse_string(trimmed).ok_or_else(syntax_err)?;
+                if rest.is_empty() {
+                    let dt = Iri::<T>::new_unchecked(xsd::string.value().to_string());
+                    Term::new_literal_dt(lex, dt)
+                } else if let Some(dt) = rest.strip_prefix("^^") {
+                    match parse_iri(dt) {
+                        Some((dt, "")) if is_absolute_iri_ref(&dt) => {
+                            Term::new_literal_dt(lex, Iri::<T>::new(dt)?)
+                        }
+                        _ => Err(syntax_err()),
+                    }
+                } else if let Some(tag) = rest.strip_prefix('@') {
+                    match tag.split_once("--") {
+                        None => Term::new_literal_lang(lex, String::from(tag)),
+                        Some((tag, dir)) => {
+                            let dir = BaseDirection::parse(dir).ok_or_else(syntax_err)?;
+                            Term::new_literal_dir_lang(lex, String::from(tag), dir)
+                        }
+                    }
+                } else {
+                    Err(syntax_err())
+                }
+            }
+            Some('_') => match trimmed.strip_prefix("_:") {
+                Some(id) => Term::new_bnode(String::from(id)),
+                None => Err(syntax_err()),
+            },
+            Some('?') | Some('$') => Term::new_variable(String::from(&trimmed[1..])),
+            _ => Err(syntax_err()),
+        }
+    }
+}
+
+/// Parse an IRI in angle brackets at the start of `txt`,
+/// and return it (unescaped) with the rest of `txt`.
+fn parse_iri(txt: &str) -> Option<(String, &str)> {
+    let txt = txt.strip_prefix('<')?;
+    let mut iri = String::new();
+    let mut chars = txt.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '>' => return Some((iri, &txt[pos + 1..])),
+            '\\' => iri.push(parse_uchar(&mut chars)?),
+            _ => iri.push(c),
+        }
+    }
+    None
+}
+
+/// Parse a double-quoted string at the start of `txt`,
+/// and return it (unescaped) with the rest of `txt`.
+fn parse_string(txt: &str) -> Option<(String, &str)> {
+    let txt = txt.strip_prefix('"')?;
+    let mut lex = String::new();
+    let mut chars = txt.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '"' => return Some((lex, &txt[pos + 1..])),
+            '\\' => {
+                let unescaped = match chars.clone().next()?.1 {
+                    't' => '\t',
+                    'b' => '\u{8}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    'f' => '\u{C}',
+                    '"' => '"',
+                    '\'' => '\'',
+                    '\\' => '\\',
+                    _ => {
+                        lex.push(parse_uchar(&mut chars)?);
+                        continue;
+                    }
+                };
+                chars.next();
+                lex.push(unescaped);
+            }
+            _ => lex.push(c),
+        }
+    }
+    None
+}
+
+/// Parse the rest of a `\uXXXX` or `\UXXXXXXXX` escape sequence (after the backslash).
+fn parse_uchar(chars: &mut std::str::CharIndices) -> Option<char> {
+    let len = match chars.next()?.1 {
+        'u' => 4,
+        'U' => 8,
+        _ => return None,
+    };
+    let hex: String = chars.take(len).map(|(_, c)| c).collect();
+    if hex.len() != len {
+        return None;
+    }
+    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::_display::test::NT_TERMS;
+    use crate::*;
+    use sophia_api::ns::xsd;
+
+    #[test]
+    fn round_trip() {
+        for (term, txt) in NT_TERMS.iter() {
+            let parsed: BoxTerm = txt.parse().unwrap();
+            assert_eq!(&parsed, term, "{}", txt);
+        }
+    }
+
+    #[test]
+    fn escapes_and_variables() -> Result<()> {
+        let t: BoxTerm = r#" "a\tbé\U0001F600\"" "#.parse()?;
+        assert_eq!(t, BoxTerm::new_literal_dt("a\tbé😀\"", xsd::string)?);
+        let t: BoxTerm = r"<http://example.org/é>".parse()?;
+        assert_eq!(t, BoxTerm::new_iri("http://example.org/é")?);
+        let t: RcTerm = r#""hello"@en--ltr"#.parse()?;
+        assert_eq!(
+            t,
+            RcTerm::new_literal_dir_lang("hello", "en", BaseDirection::Ltr)?
+        );
+        let t: BoxTerm = "?x".parse()?;
+        assert_eq!(t, BoxTerm::new_variable("x")?);
+        let t: BoxTerm = "$x".parse()?;
+        assert_eq!(t, BoxTerm::new_variable("x")?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for txt in &[
+            "",
+            "foo",
+            "<http://example.org/",
+            "<http://example.org/> x",
+            "<relative>",
+            r#""unterminated"#,
+            r#""lit"^^xsd:integer"#,
+            r#""lit"@"#,
+            r#""lit"@en--up"#,
+            r#""\x""#,
+            r#""\u12""#,
+            "_x",
+            "_:",
+            "?",
+        ] {
+            assert!(txt.parse::<BoxTerm>().is_err(), "{}", txt);
+        }
+        assert!(matches!(
+            "foo".parse::<BoxTerm>(),
+            Err(TermError::InvalidSyntax(_))
+        ));
+    }
+}
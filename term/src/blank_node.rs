@@ -10,6 +10,8 @@ use std::hash::Hasher;
 use std::io;
 use std::ops::Deref;
 
+pub mod generator;
+
 lazy_static! {
     /// A modified production of Turtle's BLANK_NODE_LABEL according to the
     /// [Turtle spec](https://www.w3.org/TR/turtle/#grammar-production-BlankNode).
@@ -48,6 +50,19 @@ lazy_static! {
 #[derive(Clone, Copy, Debug, Eq, Ord)]
 pub struct BlankNode<TD: TermData>(TD);
 
+impl<'a> BlankNode<&'a str> {
+    /// Return a new blank node in a `const` context,
+    /// e.g. in the code generated by the `static_graph!` macro.
+    ///
+    /// # Pre-condition
+    ///
+    /// Same as [`new_unchecked`](#method.new_unchecked),
+    /// but no check is performed, even in `debug` mode.
+    pub const fn new_const_unchecked(id: &'a str) -> Self {
+        BlankNode(id)
+    }
+}
+
 impl<TD> BlankNode<TD>
 where
     TD: TermData,
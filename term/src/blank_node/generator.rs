@@ -0,0 +1,451 @@
+//! Pluggable strategies for labelling blank nodes.
+//!
+//! Blank node identifiers are only meaningful locally,
+//! so parsers and other producers of terms are free to choose them.
+//! A [`BnodeIdGenerator`] makes that choice explicit and configurable:
+//! * [`Sequential`] produces `b0`, `b1`, ... (with a configurable prefix),
+//!   which yields small and reproducible output;
+//! * [`Uuid`] produces random UUIDs,
+//!   which are unique across documents and processes;
+//! * [`HashBased`] derives identifiers from a salt (e.g. the IRI or the hash of a document)
+//!   and the original labels, which yields identifiers that are both reproducible
+//!   and distinct across documents;
+//! * any closure `FnMut(Option<&str>) -> String` can be used for other strategies.
+//!
+//! Generators are used
+//! through [`TermFactory::bnode_with`](../../factory/trait.TermFactory.html#method.bnode_with),
+//! and by the parsers and serializers of the `sophia` crate
+//! (see `ParserConfig::set_bnode_ids` and `NtConfig::set_bnode_ids`),
+//! which share them through a [`SharedBnodeIds`].
+//!
+//! # Scopes
+//!
+//! Blank node labels are only meaningful within a document (or a graph),
+//! so the same label `_:b1` found in two different files
+//! must not be turned into the same blank node when the files are merged.
+//! Generators therefore remember labels per *scope*:
+//! [`enter_scope`] starts a new scope, in which previously seen labels are forgotten
+//! (they yield new identifiers), and [`leave_scope`] goes back to the enclosing scope.
+//! When ingesting several sources in a stream,
+//! a scope is typically entered before each document (or named graph) and left afterwards.
+//!
+//! ```
+//! use sophia_term::blank_node::generator::{BnodeIdGenerator, Sequential};
+//!
+//! let mut gen = Sequential::new("n");
+//! assert_eq!(gen.generate(Some("alice")), "n0");
+//! assert_eq!(gen.generate(None), "n1");
+//! assert_eq!(gen.generate(Some("alice")), "n0");
+//!
+//! gen.enter_scope();
+//! assert_eq!(gen.generate(Some("alice")), "n2");
+//! gen.leave_scope();
+//! assert_eq!(gen.generate(Some("alice")), "n0");
+//! ```
+//!
+//! [`BnodeIdGenerator`]: trait.BnodeIdGenerator.html
+//! [`Sequential`]: struct.Sequential.html
+//! [`Uuid`]: struct.Uuid.html
+//! [`HashBased`]: struct.HashBased.html
+//! [`SharedBnodeIds`]: struct.SharedBnodeIds.html
+//! [`enter_scope`]: trait.BnodeIdGenerator.html#method.enter_scope
+//! [`leave_scope`]: trait.BnodeIdGenerator.html#method.leave_scope
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// A strategy for labelling blank nodes.
+///
+/// See [module documentation](index.html).
+pub trait BnodeIdGenerator {
+    /// Return the identifier to use for the blank node labelled `label` in the source,
+    /// or for a fresh blank node if `label` is `None`.
+    ///
+    /// Implementations must return the same identifier each time they are given the same label,
+    /// distinct identifiers for distinct labels and for each fresh blank node,
+    /// and only valid blank node identifiers.
+    ///
+    /// Labels are only compared within the current [scope](index.html#scopes).
+    fn generate(&mut self, label: Option<&str>) -> String;
+
+    /// Start a new scope, nested in the current one.
+    ///
+    /// Until the matching call to [`leave_scope`](#method.leave_scope),
+    /// labels yield identifiers distinct from those given in any other scope.
+    ///
+    /// The default implementation does nothing,
+    /// which is appropriate for generators that ignore labels.
+    fn enter_scope(&mut self) {}
+
+    /// Leave the current scope, and go back to the enclosing one.
+    ///
+    /// The labels seen in the left scope are forgotten.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Panics
+    /// The generators of this module panic if no scope has been entered.
+    fn leave_scope(&mut self) {}
+}
+
+impl<F> BnodeIdGenerator for F
+where
+    F: FnMut(Option<&str>) -> String,
+{
+    fn generate(&mut self, label: Option<&str>) -> String {
+        self(label)
+    }
+}
+
+/// Keep track of the identifiers already given to labels, in each open scope.
+#[derive(Clone, Debug)]
+struct Memo {
+    /// The open scopes, each with its unique number and its labels
+    scopes: Vec<(u64, HashMap<String, String>)>,
+    /// The number of scopes entered so far
+    entered: u64,
+}
+
+impl Default for Memo {
+    fn default() -> Self {
+        Memo {
+            scopes: vec![(0, HashMap::new())],
+            entered: 0,
+        }
+    }
+}
+
+impl Memo {
+    fn get_or<F: FnOnce() -> String>(&mut self, label: Option<&str>, f: F) -> String {
+        match label {
+            None => f(),
+            Some(label) => {
+                let labels = &mut self.scopes.last_mut().unwrap().1;
+                labels.entry(label.to_string()).or_insert_with(f).clone()
+            }
+        }
+    }
+
+    /// The unique number of the current scope (0 for the outermost one).
+    fn scope(&self) -> u64 {
+        self.scopes.last().unwrap().0
+    }
+
+    fn enter(&mut self) {
+        self.entered += 1;
+        self.scopes.push((self.entered, HashMap::new()));
+    }
+
+    fn leave(&mut self) {
+        assert!(self.scopes.len() > 1, "no scope to leave");
+        self.scopes.pop();
+    }
+}
+
+/// Generate identifiers made of a prefix followed by a counter (`b0`, `b1`, ...).
+#[derive(Clone, Debug)]
+pub struct Sequential {
+    prefix: String,
+    next: usize,
+    memo: Memo,
+}
+
+impl Sequential {
+    /// Generate identifiers starting with `prefix`.
+    ///
+    /// # Panics
+    /// if `prefix` is empty or is not a valid blank node identifier.
+    pub fn new<T: Into<String>>(prefix: T) -> Self {
+        let prefix = prefix.into();
+        assert!(
+            super::BlankNode::<&str>::new(prefix.as_str()).is_ok(),
+            "invalid prefix {:?}",
+            prefix
+        );
+        Sequential {
+            prefix,
+            next: 0,
+            memo: Memo::default(),
+        }
+    }
+}
+
+impl Default for Sequential {
+    fn default() -> Self {
+        Sequential::new("b")
+    }
+}
+
+impl BnodeIdGenerator for Sequential {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        let Sequential { prefix, next, memo } = self;
+        memo.get_or(label, || {
+            *next += 1;
+            format!("{}{}", prefix, *next - 1)
+        })
+    }
+
+    fn enter_scope(&mut self) {
+        self.memo.enter();
+    }
+
+    fn leave_scope(&mut self) {
+        self.memo.leave();
+    }
+}
+
+/// Generate random (version 4) UUIDs.
+///
+/// The randomness comes from the standard library's hash seeds,
+/// which is enough for uniqueness, but not for cryptographic purposes.
+#[derive(Clone, Debug, Default)]
+pub struct Uuid {
+    counter: u64,
+    memo: Memo,
+}
+
+impl Uuid {
+    /// Build a new UUID generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BnodeIdGenerator for Uuid {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        let Uuid { counter, memo } = self;
+        memo.get_or(label, || {
+            *counter += 1;
+            let mut bits = [0; 2];
+            for (i, b) in bits.iter_mut().enumerate() {
+                let mut hasher = RandomState::new().build_hasher();
+                hasher.write_u64(*counter);
+                hasher.write_usize(i);
+                *b = hasher.finish();
+            }
+            // version 4, variant 1
+            bits[0] = (bits[0] & !0xf000) | 0x4000;
+            bits[1] = (bits[1] & !(0xc << 60)) | (0x8 << 60);
+            format_uuid(bits)
+        })
+    }
+
+    fn enter_scope(&mut self) {
+        self.memo.enter();
+    }
+
+    fn leave_scope(&mut self) {
+        self.memo.leave();
+    }
+}
+
+/// Generate identifiers by hashing a salt with the original labels.
+///
+/// The same salt and labels always produce the same identifiers,
+/// while different salts (e.g. the IRIs of different documents)
+/// produce different identifiers with overwhelming probability.
+/// Fresh blank nodes are hashed with their rank,
+/// and labels in nested [scopes](index.html#scopes) with the rank of their scope.
+///
+/// Identifiers are of the form `h` followed by 32 hexadecimal digits.
+/// The hash function is stable across platforms and versions,
+/// but is not cryptographic.
+#[derive(Clone, Debug)]
+pub struct HashBased {
+    salt: String,
+    fresh: u64,
+    memo: Memo,
+}
+
+impl HashBased {
+    /// Generate identifiers derived from `salt`.
+    pub fn new<T: Into<String>>(salt: T) -> Self {
+        HashBased {
+            salt: salt.into(),
+            fresh: 0,
+            memo: Memo::default(),
+        }
+    }
+}
+
+impl BnodeIdGenerator for HashBased {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        let HashBased { salt, fresh, memo } = self;
+        let scope = memo.scope();
+        memo.get_or(label, || {
+            let input = match label {
+                Some(label) if scope == 0 => format!("{}\u{0}l{}", salt, label),
+                Some(label) => format!("{}\u{0}s{}\u{0}l{}", salt, scope, label),
+                None => {
+                    *fresh += 1;
+                    format!("{}\u{0}f{}", salt, *fresh)
+                }
+            };
+            format!("h{:016x}{:016x}", fnv1a(&input, 0), fnv1a(&input, 1))
+        })
+    }
+
+    fn enter_scope(&mut self) {
+        self.memo.enter();
+    }
+
+    fn leave_scope(&mut self) {
+        self.memo.leave();
+    }
+}
+
+/// A [`BnodeIdGenerator`](trait.BnodeIdGenerator.html) that can be cloned and shared across threads,
+/// all clones using the same underlying generator.
+#[derive(Clone)]
+pub struct SharedBnodeIds(Arc<Mutex<dyn BnodeIdGenerator + Send>>);
+
+impl SharedBnodeIds {
+    /// Share `generator`.
+    pub fn new<G: BnodeIdGenerator + Send + 'static>(generator: G) -> Self {
+        SharedBnodeIds(Arc::new(Mutex::new(generator)))
+    }
+}
+
+impl BnodeIdGenerator for SharedBnodeIds {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        let mut generator = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        generator.generate(label)
+    }
+
+    fn enter_scope(&mut self) {
+        let mut generator = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        generator.enter_scope()
+    }
+
+    fn leave_scope(&mut self) {
+        let mut generator = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        generator.leave_scope()
+    }
+}
+
+impl fmt::Debug for SharedBnodeIds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedBnodeIds")
+    }
+}
+
+fn format_uuid(bits: [u64; 2]) -> String {
+    let hex = format!("{:016x}{:016x}", bits[0], bits[1]);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// The 64-bit FNV-1a hash of `txt`, with a distinct offset basis for each `round`.
+fn fnv1a(txt: &str, round: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ round.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    for b in txt.bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blank_node::BlankNode;
+
+    fn check_valid(id: &str) {
+        assert!(BlankNode::<&str>::new(id).is_ok(), "{:?}", id);
+    }
+
+    #[test]
+    fn sequential() {
+        let mut gen = Sequential::default();
+        let ids: Vec<_> = vec![Some("x"), None, Some("y"), Some("x"), None]
+            .into_iter()
+            .map(|l| gen.generate(l))
+            .collect();
+        assert_eq!(ids, vec!["b0", "b1", "b2", "b0", "b3"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sequential_invalid_prefix() {
+        Sequential::new("a b");
+    }
+
+    #[test]
+    fn uuid() {
+        let mut gen = Uuid::new();
+        let a = gen.generate(Some("a"));
+        assert_eq!(a.len(), 36);
+        assert_eq!(&a[14..15], "4");
+        check_valid(&a);
+        assert_eq!(gen.generate(Some("a")), a);
+        assert_ne!(gen.generate(None), gen.generate(None));
+        assert_ne!(Uuid::new().generate(Some("a")), a);
+    }
+
+    #[test]
+    fn hash_based() {
+        let a1 = HashBased::new("doc1").generate(Some("a"));
+        check_valid(&a1);
+        assert_eq!(a1.len(), 33);
+        assert_eq!(HashBased::new("doc1").generate(Some("a")), a1);
+        assert_ne!(HashBased::new("doc2").generate(Some("a")), a1);
+        let mut gen = HashBased::new("doc1");
+        assert_ne!(gen.generate(None), gen.generate(None));
+    }
+
+    #[test]
+    fn scopes() {
+        let mut seq = Sequential::default();
+        let mut hash = HashBased::new("doc");
+        for gen in [&mut seq as &mut dyn BnodeIdGenerator, &mut hash] {
+            let outer = gen.generate(Some("x"));
+            gen.enter_scope();
+            let inner1 = gen.generate(Some("x"));
+            assert_ne!(inner1, outer);
+            assert_eq!(gen.generate(Some("x")), inner1);
+            gen.leave_scope();
+            gen.enter_scope();
+            let inner2 = gen.generate(Some("x"));
+            assert_ne!(inner2, outer);
+            assert_ne!(inner2, inner1);
+            gen.leave_scope();
+            assert_eq!(gen.generate(Some("x")), outer);
+        }
+        // hash-based identifiers are still reproducible
+        let replay = || {
+            let mut gen = HashBased::new("doc");
+            gen.enter_scope();
+            gen.leave_scope();
+            gen.enter_scope();
+            gen.generate(Some("x"))
+        };
+        assert_eq!(replay(), replay());
+    }
+
+    #[test]
+    #[should_panic]
+    fn leave_outermost_scope() {
+        Sequential::default().leave_scope();
+    }
+
+    #[test]
+    fn closure_and_shared() {
+        let mut n = 0;
+        let mut gen = SharedBnodeIds::new(move |_: Option<&str>| {
+            n += 1;
+            format!("c{}", n)
+        });
+        let mut clone = gen.clone();
+        assert_eq!(gen.generate(None), "c1");
+        assert_eq!(clone.generate(None), "c2");
+    }
+}
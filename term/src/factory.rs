@@ -2,13 +2,24 @@
 //!
 //! This is especially useful for  [`RcTerm`s](../index.html) and [`ArcTerm`s](../index.html),
 //! for which two implementations of `TermFactory` are provided.
+//!
+//! A third implementation, [`SmallTermFactory`](struct.SmallTermFactory.html),
+//! produces [`SmallTerm`s](../type.SmallTerm.html).
+//!
+//! Finally, [`HashedTermFactory`](struct.HashedTermFactory.html) works like `ArcTermFactory`,
+//! but can also produce [`HashedTerm`s](../hashed/struct.HashedTerm.html) caching their hash,
+//! and optionally normalize the text of the terms it creates.
 
+use std::borrow::Cow;
+use std::fmt;
 use std::rc;
 use std::sync;
 
 use weak_table::WeakHashSet;
 
 use super::*;
+use crate::blank_node::generator::BnodeIdGenerator;
+use crate::hashed::HashedTerm;
 
 /// Type alias for the terms produced by a term factory.
 pub type FTerm<F> = Term<<F as TermFactory>::TermData>;
@@ -52,6 +63,17 @@ pub trait TermFactory {
         Term::new_bnode(self.get_term_data(id))
     }
 
+    /// Get a blank node labelled by `generator`,
+    /// for the blank node labelled `label` in the source (or a fresh one if `label` is `None`).
+    ///
+    /// See [`generator`](../blank_node/generator/index.html).
+    fn bnode_with<G>(&mut self, generator: &mut G, label: Option<&str>) -> FTerm<Self>
+    where
+        G: BnodeIdGenerator + ?Sized,
+    {
+        Term::new_bnode_unchecked(self.get_term_data(&*generator.generate(label)))
+    }
+
     /// Get a new language-tagged literal.
     fn literal_lang<T, U>(&mut self, txt: T, lang: U) -> Result<FTerm<Self>>
     where
@@ -146,7 +168,134 @@ impl TermFactory for ArcTermFactory {
     }
 }
 
+/// A `TermFactory` producing [`SmallTerm`s](../type.SmallTerm.html).
+///
+/// Unlike the other factories, it does not share data between the terms it creates:
+/// short strings are stored inline, which is cheaper than sharing them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmallTermFactory;
+
+impl TermFactory for SmallTermFactory {
+    type TermData = SmallStr;
+
+    fn get_term_data<T>(&mut self, txt: T) -> SmallStr
+    where
+        T: TermData + Into<SmallStr>,
+    {
+        txt.into()
+    }
+
+    fn shrink_to_fit(&mut self) {}
+}
+
+/// A `TermFactory` producing [`ArcTerm`s](../type.ArcTerm.html)
+/// (sharing their data as [`ArcTermFactory`](type.ArcTermFactory.html) does)
+/// and [`HashedTerm`s](../hashed/struct.HashedTerm.html).
+///
+/// It can optionally be given a *normalizer*,
+/// applied to every piece of text before it is shared
+/// (IRIs, blank node identifiers, lexical forms, language tags...),
+/// e.g. `sophia::nfc::to_nfc` to normalize all terms to Unicode Normalization Form C.
+/// Terms that only differ before normalization then share their data (and hash).
+#[derive(Default)]
+pub struct HashedTermFactory {
+    data: ArcTermFactory,
+    normalizer: Option<fn(&str) -> Cow<'_, str>>,
+}
+
+impl HashedTermFactory {
+    /// Construct a factory without normalizer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a factory applying `normalizer` to the text of every term it creates.
+    pub fn with_normalizer(normalizer: fn(&str) -> Cow<'_, str>) -> Self {
+        HashedTermFactory {
+            data: ArcTermFactory::default(),
+            normalizer: Some(normalizer),
+        }
+    }
+
+    /// Copy `term` into a [`HashedTerm`](../hashed/struct.HashedTerm.html),
+    /// using data from this factory.
+    pub fn hashed<T>(&mut self, term: &T) -> HashedTerm
+    where
+        T: TTerm + ?Sized,
+    {
+        HashedTerm::new(self.clone_term(term))
+    }
+}
+
+impl TermFactory for HashedTermFactory {
+    type TermData = sync::Arc<str>;
+
+    fn get_term_data<T>(&mut self, txt: T) -> sync::Arc<str>
+    where
+        T: TermData + Into<sync::Arc<str>>,
+    {
+        match self.normalizer.map(|n| n(txt.as_ref())) {
+            Some(Cow::Owned(normalized)) => self.data.get_term_data(&*normalized),
+            _ => self.data.get_term_data(txt),
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+    }
+}
+
+impl fmt::Debug for HashedTermFactory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashedTermFactory")
+            .field("len", &self.data.len())
+            .field("normalized", &self.normalizer.is_some())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    // Nothing really worth testing here
+    use super::*;
+
+    #[test]
+    fn shared_data() -> Result<()> {
+        // terms from the same factory share their data,
+        // which allows term_eq to skip comparing their content
+        let mut f = RcTermFactory::default();
+        let t1 = f.iri("http://example.org/a")?;
+        let t2 = f.iri(String::from("http://example.org/a"))?;
+        assert!(std::ptr::eq(t1.value_raw().0, t2.value_raw().0));
+        assert_eq!(t1, t2);
+        Ok(())
+    }
+
+    #[test]
+    fn hashed() -> Result<()> {
+        let mut f = HashedTermFactory::new();
+        let iri = f.iri("http://example.org/a")?;
+        let t1 = f.hashed(&iri);
+        let t2 = f.hashed(&StaticTerm::new_iri("http://example.org/a")?);
+        assert!(std::ptr::eq(t1.value_raw().0, t2.value_raw().0));
+        assert_eq!(t1, t2);
+        assert_eq!(t1.hash_value(), t2.hash_value());
+        Ok(())
+    }
+
+    #[test]
+    fn normalizer() -> Result<()> {
+        fn lowercase(txt: &str) -> Cow<'_, str> {
+            if txt.bytes().any(|b| b.is_ascii_uppercase()) {
+                Cow::Owned(txt.to_ascii_lowercase())
+            } else {
+                Cow::Borrowed(txt)
+            }
+        }
+        let mut f = HashedTermFactory::with_normalizer(lowercase);
+        let t1 = f.literal_lang("Chat", "fr")?;
+        let t2 = f.literal_lang("chat", "fr")?;
+        assert!(std::ptr::eq(t1.value_raw().0, t2.value_raw().0));
+        assert_eq!(f.hashed(&t1), f.hashed(&t2));
+        Ok(())
+    }
 }
@@ -0,0 +1,186 @@
+//! Terms caching their hash, for hash-map heavy workloads.
+//!
+//! Hashing a [`Term`](../enum.Term.html) hashes all its text
+//! (e.g. the whole IRI, or the lexical form, datatype and language tag of a literal).
+//! When the same terms are looked up many times in hash maps or hash sets,
+//! this cost adds up.
+//! A [`HashedTerm`] computes the hash of its [`ArcTerm`](../type.ArcTerm.html) once,
+//! when it is created, and then only feeds this precomputed value to hashers.
+//! Like `ArcTerm`, it can be cheaply cloned and shared across threads.
+//!
+//! [`HashedTermFactory`](../factory/struct.HashedTermFactory.html)
+//! produces `HashedTerm`s sharing their text,
+//! and can optionally normalize this text.
+//!
+//! # Example
+//! ```
+//! # use sophia_term::hashed::HashedTerm;
+//! # use sophia_term::ArcTerm;
+//! use std::collections::HashSet;
+//!
+//! let alice = HashedTerm::new(ArcTerm::new_iri("http://example.org/alice")?);
+//! let mut seen = HashSet::new();
+//! seen.insert(alice.clone());
+//! assert!(seen.contains(&alice));
+//! assert_eq!(alice, HashedTerm::new(ArcTerm::new_iri("http://example.org/alice")?));
+//! # Ok::<(), sophia_term::TermError>(())
+//! ```
+//!
+//! [`HashedTerm`]: struct.HashedTerm.html
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use sophia_api::term::{
+    term_cmp, term_eq, term_hash, BaseDirection, CopyTerm, RawValue, SimpleIri, TTerm, TermKind,
+};
+
+use crate::ArcTerm;
+
+/// An [`ArcTerm`](../type.ArcTerm.html) with a precomputed hash.
+///
+/// See [module documentation](index.html).
+#[derive(Clone)]
+pub struct HashedTerm {
+    term: ArcTerm,
+    hash: u64,
+}
+
+impl HashedTerm {
+    /// Wrap `term`, computing its hash.
+    pub fn new(term: ArcTerm) -> Self {
+        // DefaultHasher::new always uses the same keys,
+        // so equal terms always get the same hash
+        let mut hasher = DefaultHasher::new();
+        term_hash(&term, &mut hasher);
+        let hash = hasher.finish();
+        HashedTerm { term, hash }
+    }
+
+    /// The wrapped term.
+    pub fn term(&self) -> &ArcTerm {
+        &self.term
+    }
+
+    /// Unwrap the term.
+    pub fn into_term(self) -> ArcTerm {
+        self.term
+    }
+
+    /// The precomputed hash of this term.
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl TTerm for HashedTerm {
+    fn kind(&self) -> TermKind {
+        self.term.kind()
+    }
+    fn value_raw(&self) -> RawValue {
+        self.term.value_raw()
+    }
+    fn datatype(&self) -> Option<SimpleIri> {
+        self.term.datatype()
+    }
+    fn language(&self) -> Option<&str> {
+        self.term.language()
+    }
+    fn base_direction(&self) -> Option<BaseDirection> {
+        self.term.base_direction()
+    }
+    fn as_dyn(&self) -> &dyn TTerm {
+        self
+    }
+}
+
+impl CopyTerm for HashedTerm {
+    fn copy<T>(term: &T) -> Self
+    where
+        T: TTerm + ?Sized,
+    {
+        HashedTerm::new(ArcTerm::copy(term))
+    }
+}
+
+impl From<ArcTerm> for HashedTerm {
+    fn from(term: ArcTerm) -> Self {
+        HashedTerm::new(term)
+    }
+}
+
+impl From<HashedTerm> for ArcTerm {
+    fn from(term: HashedTerm) -> Self {
+        term.term
+    }
+}
+
+impl PartialEq for HashedTerm {
+    fn eq(&self, other: &HashedTerm) -> bool {
+        self.hash == other.hash && term_eq(&self.term, &other.term)
+    }
+}
+
+impl Eq for HashedTerm {}
+
+impl PartialOrd for HashedTerm {
+    fn partial_cmp(&self, other: &HashedTerm) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HashedTerm {
+    fn cmp(&self, other: &HashedTerm) -> std::cmp::Ordering {
+        term_cmp(&self.term, &other.term)
+    }
+}
+
+impl Hash for HashedTerm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+impl fmt::Debug for HashedTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.term, f)
+    }
+}
+
+impl fmt::Display for HashedTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.term, f)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use sophia_api::ns::xsd;
+
+    #[test]
+    fn send_sync() {
+        fn check<T: Send + Sync>() {}
+        check::<HashedTerm>();
+    }
+
+    #[test]
+    fn equal_terms_equal_hashes() -> crate::Result<()> {
+        let t1 = HashedTerm::new(ArcTerm::new_literal_lang("chat", "fr")?);
+        let t2 = HashedTerm::copy(&StaticTerm::new_literal_lang("chat", "FR")?);
+        assert_eq!(t1, t2);
+        assert_eq!(t1.hash_value(), t2.hash_value());
+        assert!(term_eq(&t1, t2.term()));
+
+        let t3 = HashedTerm::copy(&StaticTerm::new_literal_dt("chat", xsd::string)?);
+        assert_ne!(t1, t3);
+        assert_eq!(t3.datatype(), Some(xsd::string));
+        Ok(())
+    }
+}
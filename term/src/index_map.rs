@@ -35,6 +35,19 @@ pub trait TermIndexMap: Default {
     fn dec_ref(&mut self, i: Self::Index);
     /// Shrinks the capacity of the TermIndexMap as much as possible.
     fn shrink_to_fit(&mut self);
+    /// Return the number of terms currently mapped to an index.
+    fn len(&self) -> usize;
+    /// Return an estimate of the heap memory (in bytes) used by this TermIndexMap,
+    /// including the textual data of its terms.
+    ///
+    /// NB: the textual data of each term is counted in full,
+    /// even if the factory shares it with other terms or structures.
+    fn heap_size(&self) -> usize;
+
+    /// Whether this TermIndexMap contains no term.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
     // The following methods have a default impl, and would generally not be overridden
 
@@ -76,6 +89,7 @@ pub trait TermIndexMap: Default {
 pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
     let t = RefTerm::new_iri("http://example.org/").unwrap();
     assert!(ti.get_index(&t).is_none());
+    assert!(ti.is_empty());
 
     // insert term, then remove it
 
@@ -137,6 +151,7 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
     let it1 = ti.make_index(&t1);
     let it2 = ti.make_index(&t2);
     assert!(it1 != it2);
+    assert_eq!(ti.len(), 2);
 
     ti.dec_ref(it2);
     assert!(ti.get_index(&t1).is_some());
@@ -145,6 +160,7 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
     ti.dec_ref(it1);
     assert!(ti.get_index(&t1).is_none());
     assert!(ti.get_index(&t2).is_none());
+    assert!(ti.is_empty());
 }
 
 #[cfg(test)]
@@ -21,8 +21,10 @@ pub use self::_join::*;
 use super::*;
 use mownstr::MownStr;
 use sophia_api::{ns::Namespace, term::RawValue};
+use sophia_iri::error::InvalidIri;
 pub use sophia_iri::resolve::*; // prefixed with "pub" to ease transition from older versions of Sophia
 pub use sophia_iri::*; // prefixed with "pub" to ease transition from older versions of Sophia
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -71,6 +73,20 @@ pub struct Iri<TD: TermData> {
     pub(crate) suffix: Option<TD>,
 }
 
+impl<'a> Iri<&'a str> {
+    /// Create a new IRI-term in a `const` context,
+    /// e.g. in the code generated by the `static_graph!` macro.
+    ///
+    /// # Pre-conditions
+    ///
+    /// Same as [`new_unchecked`](#method.new_unchecked)
+    /// and [`new_suffixed_unchecked`](#method.new_suffixed_unchecked),
+    /// but no check is performed, even in `debug` mode.
+    pub const fn new_const_unchecked(ns: &'a str, suffix: Option<&'a str>) -> Self {
+        Iri { ns, suffix }
+    }
+}
+
 impl<TD> Iri<TD>
 where
     TD: TermData,
@@ -90,7 +106,7 @@ where
                 suffix: None,
             })
         } else {
-            Err(TermError::InvalidIri(iri.as_ref().to_string()))
+            Err(TermError::InvalidIri(InvalidIri::new(iri.as_ref())))
         }
     }
 
@@ -122,10 +138,32 @@ where
                 suffix,
             })
         } else {
-            Err(TermError::InvalidIri(full))
+            Err(TermError::InvalidIri(InvalidIri::new(full)))
         }
     }
 
+    /// Return a new IRI-term from a given, possibly invalid, IRI,
+    /// percent-encoding the characters that make it invalid (see [`fix_iri_ref`]).
+    ///
+    /// An error is returned if the IRI can not be fixed that way
+    /// (e.g. because its scheme is invalid).
+    ///
+    /// [`fix_iri_ref`]: ./fn.fix_iri_ref.html
+    pub fn fix<U>(iri: U) -> Result<Self>
+    where
+        U: AsRef<str>,
+        TD: From<U> + From<String>,
+    {
+        let fixed = match fix_iri_ref(iri.as_ref())? {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(fixed) => Some(fixed),
+        };
+        Ok(Iri {
+            ns: fixed.map_or_else(|| iri.into(), Into::into),
+            suffix: None,
+        })
+    }
+
     /// Create a new IRI-term from a given IRI without checking its validity.
     ///
     /// # Pre-condition
@@ -662,6 +700,20 @@ mod test {
         }
     }
 
+    #[test]
+    fn fix() {
+        let iri = Iri::<Box<str>>::fix("http://example.org/a b|c").unwrap();
+        assert_eq!(iri.value(), "http://example.org/a%20b%7Cc");
+        assert!(Iri::<Box<str>>::fix("http://example.org/").is_ok());
+        match Iri::<Box<str>>::fix("1http://example.org/") {
+            Err(TermError::InvalidIri(err)) => {
+                assert_eq!(err.position, Some(0));
+                assert_eq!(err.production, Some("scheme"));
+            }
+            _ => panic!("an error was expected"),
+        }
+    }
+
     #[test]
     fn map() {
         let input = Iri::new_suffixed("some/iri/", "example").unwrap();
@@ -39,6 +39,16 @@
 //!   should be used in situations where some terms can borrow their data,
 //!   while others need to own it.
 //!
+//! * [`SmallTerm`](type.SmallTerm.html) (alias of `Term<SmallStr>`)
+//!   should be used when a large number of terms are kept in memory
+//!   (e.g. in a `HashSet<[SmallTerm; 3]>` graph):
+//!   short strings (the majority of them, in typical RDF data)
+//!   are stored inline instead of being allocated on the heap
+//!   (see [`small_str`](small_str/index.html)).
+//!
+//! Finally, [`HashedTerm`](hashed/struct.HashedTerm.html) wraps an `ArcTerm` with its precomputed hash,
+//! for terms that are looked up many times in hash maps or hash sets.
+//!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
 //! [Linked Data]: http://linkeddata.org/
@@ -47,6 +57,7 @@
 #![deny(missing_docs)]
 
 use mownstr::MownStr;
+pub use sophia_api::term::BaseDirection;
 use sophia_api::term::{
     term_cmp, term_eq, term_format, term_hash, term_to_string, CopyTerm, RawValue, SimpleIri,
     TTerm, TermKind, TryCopyTerm,
@@ -58,6 +69,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 pub mod factory;
+pub mod hashed;
 pub mod index_map;
 
 pub mod variable;
@@ -69,10 +81,13 @@ use self::iri::{Iri, Normalization};
 pub mod literal;
 use literal::convert::{AsLiteral, DataType, NativeLiteral};
 use literal::Literal;
+pub mod small_str;
+use small_str::SmallStr;
 
 mod _display;
 mod _error;
 pub use self::_error::*;
+mod _parse;
 
 /// Generic type for RDF terms.
 ///
@@ -99,6 +114,24 @@ where
 pub trait TermData: AsRef<str> + Clone + Eq + Hash {}
 impl<T> TermData for T where T: AsRef<str> + Clone + Eq + Hash {}
 
+/// Marker trait for [`TermData`] whose text stays at the same address
+/// when the `TermData` itself is moved,
+/// i.e. whose text is stored behind a pointer rather than inline.
+///
+/// # Safety
+///
+/// Implementors must guarantee that the `&str` returned by `as_ref`
+/// remains valid as long as the `TermData` (or one of its clones) is not dropped,
+/// wherever it is moved.
+///
+/// [`TermData`]: trait.TermData.html
+pub unsafe trait StableTermData: TermData {}
+unsafe impl StableTermData for Box<str> {}
+unsafe impl StableTermData for Rc<str> {}
+unsafe impl StableTermData for Arc<str> {}
+unsafe impl StableTermData for &str {}
+unsafe impl StableTermData for MownStr<'_> {}
+
 /// Convenient alias for a specialization of `Term<T>`.
 ///
 /// See [module documentation](index.html)
@@ -129,6 +162,11 @@ pub type StaticTerm = RefTerm<'static>;
 /// See [module documentation](index.html)
 /// for more detail on when to use it.
 pub type MownTerm<'a> = Term<MownStr<'a>>;
+/// Convenient alias for a specialization of `Term<T>`.
+///
+/// See [module documentation](index.html)
+/// for more detail on when to use it.
+pub type SmallTerm = Term<SmallStr>;
 
 impl<T> Term<T>
 where
@@ -182,6 +220,17 @@ where
         Literal::<T>::new_lang(txt, lang).map(Into::into)
     }
 
+    /// Return a new literal term with the given value, language tag and base direction.
+    ///
+    /// May fail if the language tag is not a valid BCP47 language tag.
+    pub fn new_literal_dir_lang<U, V>(txt: U, lang: V, dir: BaseDirection) -> Result<Self>
+    where
+        V: AsRef<str>,
+        T: From<U> + From<V>,
+    {
+        Literal::<T>::new_dir_lang(txt, lang, dir).map(Into::into)
+    }
+
     /// Return a new literal term with the given value and datatype.
     ///
     /// May fail if `dt` is not an IRI.
@@ -359,6 +408,20 @@ where
         Literal::<T>::new_lang_unchecked(txt, lang).into()
     }
 
+    /// Return a directional language-tagged literal term.
+    ///
+    /// # Pre-condition
+    ///
+    /// This function requires that `lang` is a valid language tag.
+    /// In debug mode this constraint is asserted.
+    pub fn new_literal_dir_lang_unchecked<U, V>(txt: U, lang: V, dir: BaseDirection) -> Self
+    where
+        V: AsRef<str>,
+        T: From<U> + From<V>,
+    {
+        Literal::<T>::new_dir_lang_unchecked(txt, lang, dir).into()
+    }
+
     /// Return a typed literal term.
     ///
     /// # Panics
@@ -420,6 +483,13 @@ impl<T: TermData> TTerm for Term<T> {
             None
         }
     }
+    fn base_direction(&self) -> Option<BaseDirection> {
+        if let Term::Literal(lit) = self {
+            lit.dir()
+        } else {
+            None
+        }
+    }
     fn as_dyn(&self) -> &dyn TTerm {
         self
     }
@@ -556,7 +626,10 @@ where
                     let dt: Iri<&'a str> = t.datatype().unwrap().into();
                     Literal::new_dt(v.0, dt)
                 }
-                Some(tag) => Literal::new_lang_unchecked(v.0, tag),
+                Some(tag) => match t.base_direction() {
+                    None => Literal::new_lang_unchecked(v.0, tag),
+                    Some(dir) => Literal::new_dir_lang_unchecked(v.0, tag, dir),
+                },
             }),
             TermKind::BlankNode => Term::BNode(BlankNode::new_unchecked(v.0)),
             TermKind::Variable => Term::Variable(Variable::new_unchecked(v.0)),
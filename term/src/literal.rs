@@ -13,17 +13,25 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::io;
 
+pub mod canonical;
 pub mod convert;
+pub mod datetime;
+#[cfg(feature = "geo")]
+pub mod geo;
+pub mod lang;
+pub mod matcher;
+pub mod valued;
 
 /// Internal distinction of literals.
 ///
 /// Opaque to users.
 #[derive(Clone, Copy, Debug)]
 enum Kind<TD: TermData> {
-    /// Something representing a language tag.
+    /// Something representing a language tag,
+    /// and an optional base direction.
     ///
     /// The tags conform to [BCP47](https://tools.ietf.org/html/bcp47).
-    Lang(TD),
+    Lang(TD, Option<BaseDirection>),
     /// The IRI referencing the datatype.
     Dt(Iri<TD>),
 }
@@ -41,6 +49,10 @@ use self::Kind::*;
 ///
 /// The tags conform to [BCP47](https://tools.ietf.org/html/bcp47).
 ///
+/// RDF 1.2 introduces directional language-tagged literals,
+/// of type `rdf:dirLangString`,
+/// that additionally have a [base direction](https://www.w3.org/TR/rdf12-concepts/#section-text-direction).
+///
 /// # Datatypes
 ///
 /// Datatypes in RDF have a lexical scope and a value scope. Transformation
@@ -56,6 +68,32 @@ pub struct Literal<TD: TermData> {
     kind: Kind<TD>,
 }
 
+impl<'a> Literal<&'a str> {
+    /// Return a new literal with an arbitrary datatype in a `const` context,
+    /// e.g. in the code generated by the `static_graph!` macro.
+    pub const fn new_dt_const(txt: &'a str, dt: Iri<&'a str>) -> Self {
+        Literal { txt, kind: Dt(dt) }
+    }
+
+    /// Return a new (possibly directional) language-tagged literal in a `const` context,
+    /// e.g. in the code generated by the `static_graph!` macro.
+    ///
+    /// # Pre-condition
+    ///
+    /// Same as [`new_lang_unchecked`](#method.new_lang_unchecked),
+    /// but no check is performed, even in `debug` mode.
+    pub const fn new_lang_const_unchecked(
+        txt: &'a str,
+        tag: &'a str,
+        dir: Option<BaseDirection>,
+    ) -> Self {
+        Literal {
+            txt,
+            kind: Lang(tag, dir),
+        }
+    }
+}
+
 impl<TD> Literal<TD>
 where
     TD: TermData,
@@ -80,10 +118,29 @@ where
 
         Ok(Self {
             txt: txt.into(),
-            kind: Lang(tag.into()),
+            kind: Lang(tag.into(), None),
         })
     }
 
+    /// Return a new directional language-tagged literal.
+    ///
+    /// # Error
+    ///
+    /// If `tag` is not a valid language-tag according to
+    /// [BCP47](https://tools.ietf.org/html/bcp47) an error is raised.
+    pub fn new_dir_lang<U, V>(txt: U, tag: V, dir: BaseDirection) -> Result<Self>
+    where
+        V: AsRef<str>,
+        TD: From<U> + From<V>,
+    {
+        let mut lit = Self::new_lang(txt, tag)?;
+        lit.kind = match lit.kind {
+            Lang(tag, _) => Lang(tag, Some(dir)),
+            _ => unreachable!(),
+        };
+        Ok(lit)
+    }
+
     /// Return a new literal with an arbitrary datatype.
     ///
     /// Neither is checked if `dt` refers to a known datatype nor if `txt` is
@@ -121,15 +178,35 @@ where
 
         Self {
             txt: txt.into(),
-            kind: Lang(tag.into()),
+            kind: Lang(tag.into(), None),
         }
     }
 
+    /// Return a new directional language-tagged literal.
+    ///
+    /// # Pre-condition
+    ///
+    /// `tag` must be a valid language-tag according to
+    /// [BCP47](https://tools.ietf.org/html/bcp47).
+    /// In debug mode this is asserted.
+    pub fn new_dir_lang_unchecked<U, V>(txt: U, tag: V, dir: BaseDirection) -> Self
+    where
+        V: AsRef<str>,
+        TD: From<U> + From<V>,
+    {
+        let Literal { txt, kind } = Self::new_lang_unchecked(txt, tag);
+        let kind = match kind {
+            Lang(tag, _) => Lang(tag, Some(dir)),
+            _ => unreachable!(),
+        };
+        Literal { txt, kind }
+    }
+
     /// Borrow the inner contents of the literal.
     pub fn as_ref(&self) -> Literal<&TD> {
         let txt = &self.txt;
         let kind = match &self.kind {
-            Lang(tag) => Lang(tag),
+            Lang(tag, dir) => Lang(tag, *dir),
             Dt(dt) => Dt(dt.as_ref()),
         };
         Literal { txt, kind }
@@ -139,7 +216,7 @@ where
     pub fn as_ref_str(&self) -> Literal<&str> {
         let txt = self.txt.as_ref();
         let kind = match &self.kind {
-            Lang(tag) => Lang(tag.as_ref()),
+            Lang(tag, dir) => Lang(tag.as_ref(), *dir),
             Dt(dt) => Dt(dt.as_ref_str()),
         };
         Literal { txt, kind }
@@ -154,7 +231,7 @@ where
         let mut f = f;
         let txt = f(self.txt);
         let kind = match self.kind {
-            Lang(tag) => Lang(f(tag)),
+            Lang(tag, dir) => Lang(f(tag), dir),
             Dt(dt) => Dt(dt.map(f)),
         };
         Literal { txt, kind }
@@ -181,7 +258,7 @@ where
         let mut factory = factory;
         let txt = factory(self.txt.as_ref());
         let kind = match &self.kind {
-            Lang(tag) => Lang(factory(tag.as_ref())),
+            Lang(tag, dir) => Lang(factory(tag.as_ref()), *dir),
             Dt(iri) => Dt(iri.clone_map(factory)),
         };
 
@@ -206,7 +283,7 @@ where
     pub fn normalized(&self, policy: Normalization) -> Literal<MownStr> {
         let txt = MownStr::from(self.txt.as_ref());
         let kind = match &self.kind {
-            Lang(tag) => Lang(MownStr::from(tag.as_ref())),
+            Lang(tag, dir) => Lang(MownStr::from(tag.as_ref()), *dir),
             Dt(iri) => Dt(iri.normalized(policy)),
         };
         Literal { txt, kind }
@@ -221,9 +298,14 @@ where
         fmt_quoted_string(w, self.txt.as_ref())?;
 
         match &self.kind {
-            Lang(tag) => {
+            Lang(tag, dir) => {
                 w.write_str("\"@")?;
-                w.write_str(tag.as_ref())
+                w.write_str(tag.as_ref())?;
+                if let Some(dir) = dir {
+                    w.write_str("--")?;
+                    w.write_str(dir.as_str())?;
+                }
+                Ok(())
             }
             Dt(dt) => {
                 if &xsd::string != dt {
@@ -245,9 +327,14 @@ where
         io_quoted_string(w, self.txt.as_ref().as_bytes())?;
 
         match &self.kind {
-            Lang(tag) => {
+            Lang(tag, dir) => {
                 w.write_all(b"\"@")?;
-                w.write_all(tag.as_ref().as_bytes())
+                w.write_all(tag.as_ref().as_bytes())?;
+                if let Some(dir) = dir {
+                    w.write_all(b"--")?;
+                    w.write_all(dir.as_str().as_bytes())?;
+                }
+                Ok(())
             }
             Dt(dt) => {
                 if &xsd::string != dt {
@@ -267,23 +354,34 @@ where
 
     /// Return an IRI borrowing the literals datatype.
     ///
-    /// _Note:_ A language-tagged literal has always the type `rdf:langString`.
+    /// _Note:_ A language-tagged literal has always the type `rdf:langString`
+    /// (or `rdf:dirLangString` if it has a base direction).
     pub fn dt(&self) -> Iri<&str> {
         match &self.kind {
-            Lang(_) => rdf::langString.into(),
+            Lang(_, None) => rdf::langString.into(),
+            Lang(_, Some(_)) => rdf::dirLangString.into(),
             Dt(dt) => dt.as_ref_str(),
         }
     }
 
     /// Return the language-tag of the literal if it has one.
     pub fn lang(&self) -> Option<&TD> {
-        if let Lang(tag) = &self.kind {
+        if let Lang(tag, _) = &self.kind {
             Some(tag)
         } else {
             None
         }
     }
 
+    /// Return the base direction of the literal if it has one.
+    pub fn dir(&self) -> Option<BaseDirection> {
+        if let Lang(_, dir) = &self.kind {
+            *dir
+        } else {
+            None
+        }
+    }
+
     /// Check if both literals have the same lexical value.
     pub fn eq_txt<U>(&self, other: Literal<U>) -> bool
     where
@@ -306,6 +404,9 @@ impl<TD: TermData> TTerm for Literal<TD> {
     fn language(&self) -> Option<&str> {
         self.lang().map(|td| td.as_ref())
     }
+    fn base_direction(&self) -> Option<BaseDirection> {
+        self.dir()
+    }
     fn as_dyn(&self) -> &dyn TTerm {
         self
     }
@@ -380,9 +481,10 @@ where
     {
         if term.kind() == TermKind::Literal {
             let txt = term.value_raw().0;
-            Ok(match term.language() {
-                None => Self::new_dt(txt, term.datatype().unwrap().into()),
-                Some(tag) => Self::new_lang_unchecked(txt, tag),
+            Ok(match (term.language(), term.base_direction()) {
+                (None, _) => Self::new_dt(txt, term.datatype().unwrap().into()),
+                (Some(tag), None) => Self::new_lang_unchecked(txt, tag),
+                (Some(tag), Some(dir)) => Self::new_dir_lang_unchecked(txt, tag, dir),
             })
         } else {
             Err(TermError::UnsupportedKind(term_to_string(term)))
@@ -504,6 +606,25 @@ mod test {
     // (especially the ::term::test module).
 
     use super::*;
+    use sophia_api::term::{term_eq, term_to_string};
+
+    #[test]
+    fn const_constructors() {
+        const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+        const TYPED: Literal<&str> =
+            Literal::new_dt_const("42", Iri::new_const_unchecked(XSD, Some("integer")));
+        const TAGGED: Literal<&str> = Literal::new_lang_const_unchecked("chat", "fr", None);
+        const BNODE: BlankNode<&str> = BlankNode::new_const_unchecked("b1");
+        assert_eq!(
+            StaticTerm::Literal(TYPED),
+            BoxTerm::new_literal_dt("42", xsd::integer).unwrap()
+        );
+        assert_eq!(
+            StaticTerm::Literal(TAGGED),
+            BoxTerm::new_literal_lang("chat", "fr").unwrap()
+        );
+        assert_eq!(StaticTerm::BNode(BNODE), BoxTerm::new_bnode("b1").unwrap());
+    }
 
     #[test]
     fn convert_to_mown_does_not_allocate() {
@@ -561,4 +682,20 @@ mod test {
             mapped.clone_into::<std::sync::Arc<str>>()
         );
     }
+
+    #[test]
+    fn dir_lang() {
+        let ltr = Literal::<&str>::new_dir_lang("hello", "en", BaseDirection::Ltr).unwrap();
+        let rtl = Literal::<&str>::new_dir_lang("hello", "en", BaseDirection::Rtl).unwrap();
+        let plain = Literal::<&str>::new_lang("hello", "en").unwrap();
+        assert_eq!(ltr.dir(), Some(BaseDirection::Ltr));
+        assert_eq!(plain.dir(), None);
+        assert_eq!(&ltr.dt(), &rdf::dirLangString);
+        assert_eq!(&plain.dt(), &rdf::langString);
+        assert!(!term_eq(&ltr, &rtl));
+        assert!(!term_eq(&ltr, &plain));
+        assert_eq!(term_to_string(&ltr), "\"hello\"@en--ltr");
+        let copy: Literal<Box<str>> = Literal::try_copy(&rtl).unwrap();
+        assert_eq!(copy.dir(), Some(BaseDirection::Rtl));
+    }
 }
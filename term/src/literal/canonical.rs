@@ -0,0 +1,293 @@
+//! Canonical lexical forms of numeric literals.
+//!
+//! The same number can be written in many ways (e.g. `"01"`, `"+1"` and `"1"` for `xsd:integer`).
+//! [`canonical_numeric`] rewrites the lexical form of a numeric literal
+//! into the canonical form defined by [XML Schema 1.1],
+//! so that serialized output is stable regardless of how the data was initially written.
+//!
+//! It supports `xsd:integer` (and all the types derived from it),
+//! `xsd:decimal`, `xsd:double` and `xsd:float`.
+//!
+//! [`canonicalize_numeric_term`] applies it to a term, and can be used to transform whole
+//! triple or quad sources:
+//! ```
+//! # use sophia_api::triple::stream::TripleSource;
+//! # use sophia_api::term::TTerm;
+//! # use sophia_api::triple::Triple;
+//! # use sophia_term::literal::canonical::canonicalize_numeric_term;
+//! # use sophia_term::{BoxTerm, StaticTerm, ns::xsd};
+//! let triples = vec![[
+//!     StaticTerm::new_iri("http://example.org/x")?,
+//!     StaticTerm::new_iri("http://example.org/p")?,
+//!     StaticTerm::new_literal_dt("+0042", xsd::integer)?,
+//! ]];
+//! let mut canonical: Vec<[BoxTerm; 3]> = vec![];
+//! triples
+//!     .iter()
+//!     .map(Ok::<_, std::convert::Infallible>)
+//!     .map_triple_terms(|t| canonicalize_numeric_term(t))
+//!     .for_each_triple(|t| canonical.push([t.s().clone(), t.p().clone(), t.o().clone()]))?;
+//! assert_eq!(canonical[0][2].value(), "42");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [XML Schema 1.1]: https://www.w3.org/TR/xmlschema11-2/
+//! [`canonical_numeric`]: fn.canonical_numeric.html
+//! [`canonicalize_numeric_term`]: fn.canonicalize_numeric_term.html
+
+use crate::iri::Iri;
+use crate::literal::Literal;
+use crate::{BoxTerm, Term};
+use sophia_api::ns::xsd;
+use sophia_api::term::{CopyTerm, SimpleIri, TTerm, TermKind};
+
+/// The canonical lexical form of `lex` for the numeric `datatype`.
+///
+/// Returns `None` if `datatype` is not a supported numeric datatype,
+/// or if `lex` is not in its lexical space.
+///
+/// NB: following XML Schema 1.1, decimals with no fractional part
+/// are written without a decimal point (e.g. `"1"` rather than `"1.0"`).
+pub fn canonical_numeric(lex: &str, datatype: &SimpleIri) -> Option<String> {
+    let lex = lex.trim_matches(|c| c == ' ' || c == '\t' || c == '\n' || c == '\r');
+    if *datatype == xsd::decimal {
+        canonical_decimal(lex)
+    } else if *datatype == xsd::double {
+        canonical_double(lex)
+    } else if *datatype == xsd::float {
+        canonical_float(lex)
+    } else {
+        let (min, max) = integer_range(datatype)?;
+        let canonical = canonical_integer(lex)?;
+        let in_range = match canonical.parse::<i128>() {
+            Ok(value) => min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max),
+            // outside of the i128 range: only valid for unbounded types
+            Err(_) => match canonical.starts_with('-') {
+                true => min.is_none(),
+                false => max.is_none(),
+            },
+        };
+        if in_range {
+            Some(canonical)
+        } else {
+            None
+        }
+    }
+}
+
+/// A copy of `term`, where numeric literals are rewritten in their
+/// [canonical form](fn.canonical_numeric.html).
+///
+/// Other terms, and numeric literals with an invalid lexical form, are copied unchanged.
+pub fn canonicalize_numeric_term<T>(term: &T) -> BoxTerm
+where
+    T: TTerm + ?Sized,
+{
+    if term.kind() == TermKind::Literal {
+        if let Some(dt) = term.datatype() {
+            let lex = term.value_raw().0;
+            if let Some(canonical) = canonical_numeric(lex, &dt) {
+                if canonical != lex {
+                    return Term::Literal(Literal::new_dt(canonical, Iri::<&str>::from(dt)));
+                }
+            }
+        }
+    }
+    BoxTerm::copy(term)
+}
+
+/// The bounds of `datatype`, if it is `xsd:integer` or one of its derived types.
+fn integer_range(datatype: &SimpleIri) -> Option<(Option<i128>, Option<i128>)> {
+    let range = |min: i128, max: i128| Some((Some(min), Some(max)));
+    if *datatype == xsd::integer {
+        Some((None, None))
+    } else if *datatype == xsd::nonNegativeInteger {
+        Some((Some(0), None))
+    } else if *datatype == xsd::positiveInteger {
+        Some((Some(1), None))
+    } else if *datatype == xsd::nonPositiveInteger {
+        Some((None, Some(0)))
+    } else if *datatype == xsd::negativeInteger {
+        Some((None, Some(-1)))
+    } else if *datatype == xsd::long {
+        range(i64::MIN as i128, i64::MAX as i128)
+    } else if *datatype == xsd::int {
+        range(i32::MIN as i128, i32::MAX as i128)
+    } else if *datatype == xsd::short {
+        range(i16::MIN as i128, i16::MAX as i128)
+    } else if *datatype == xsd::byte {
+        range(i8::MIN as i128, i8::MAX as i128)
+    } else if *datatype == xsd::unsignedLong {
+        range(0, u64::MAX as i128)
+    } else if *datatype == xsd::unsignedInt {
+        range(0, u32::MAX as i128)
+    } else if *datatype == xsd::unsignedShort {
+        range(0, u16::MAX as i128)
+    } else if *datatype == xsd::unsignedByte {
+        range(0, u8::MAX as i128)
+    } else {
+        None
+    }
+}
+
+/// Split the optional sign of `lex`, returning whether it is negative.
+fn split_sign(lex: &str) -> (bool, &str) {
+    if let Some(rest) = lex.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, lex.strip_prefix('+').unwrap_or(lex))
+    }
+}
+
+fn all_digits(txt: &str) -> bool {
+    txt.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn canonical_integer(lex: &str) -> Option<String> {
+    let (negative, digits) = split_sign(lex);
+    if digits.is_empty() || !all_digits(digits) {
+        return None;
+    }
+    let digits = digits.trim_start_matches('0');
+    Some(match (digits.is_empty(), negative) {
+        (true, _) => "0".to_string(),
+        (false, true) => format!("-{}", digits),
+        (false, false) => digits.to_string(),
+    })
+}
+
+fn canonical_decimal(lex: &str) -> Option<String> {
+    let (negative, unsigned) = split_sign(lex);
+    let (int, frac) = match unsigned.find('.') {
+        Some(pos) => (&unsigned[..pos], &unsigned[pos + 1..]),
+        None => (unsigned, ""),
+    };
+    if (int.is_empty() && frac.is_empty()) || !all_digits(int) || !all_digits(frac) {
+        return None;
+    }
+    let int = int.trim_start_matches('0');
+    let frac = frac.trim_end_matches('0');
+    if int.is_empty() && frac.is_empty() {
+        return Some("0".to_string());
+    }
+    let mut canonical = String::with_capacity(int.len() + frac.len() + 3);
+    if negative {
+        canonical.push('-');
+    }
+    canonical.push_str(if int.is_empty() { "0" } else { int });
+    if !frac.is_empty() {
+        canonical.push('.');
+        canonical.push_str(frac);
+    }
+    Some(canonical)
+}
+
+/// Check that `lex` is in the lexical space of `xsd:double`,
+/// returning its special value (`INF`, `-INF` or `NaN`) if it is one.
+fn check_floating_point(lex: &str) -> Option<Option<&'static str>> {
+    match lex {
+        "INF" | "+INF" => return Some(Some("INF")),
+        "-INF" => return Some(Some("-INF")),
+        "NaN" => return Some(Some("NaN")),
+        _ => {}
+    }
+    let (mantissa, exponent) = match lex.find(['e', 'E']) {
+        Some(pos) => (&lex[..pos], Some(&lex[pos + 1..])),
+        None => (lex, None),
+    };
+    canonical_decimal(mantissa)?;
+    if let Some(exponent) = exponent {
+        canonical_integer(exponent)?;
+    }
+    Some(None)
+}
+
+/// Format the shortest scientific representation of a number
+/// (as produced by `{:e}`) in the canonical form of XML Schema.
+fn scientific(formatted: String) -> String {
+    let pos = formatted.find('e').unwrap();
+    let (mantissa, exponent) = (&formatted[..pos], &formatted[pos + 1..]);
+    if mantissa.contains('.') {
+        format!("{}E{}", mantissa, exponent)
+    } else {
+        format!("{}.0E{}", mantissa, exponent)
+    }
+}
+
+fn canonical_double(lex: &str) -> Option<String> {
+    if let Some(special) = check_floating_point(lex)? {
+        return Some(special.to_string());
+    }
+    let value: f64 = lex.parse().ok()?;
+    if value.is_infinite() {
+        return Some(if value > 0.0 { "INF" } else { "-INF" }.to_string());
+    }
+    Some(scientific(format!("{:e}", value)))
+}
+
+fn canonical_float(lex: &str) -> Option<String> {
+    if let Some(special) = check_floating_point(lex)? {
+        return Some(special.to_string());
+    }
+    let value: f32 = lex.parse().ok()?;
+    if value.is_infinite() {
+        return Some(if value > 0.0 { "INF" } else { "-INF" }.to_string());
+    }
+    Some(scientific(format!("{:e}", value)))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StaticTerm;
+    use test_case::test_case;
+
+    #[test_case("0", xsd::integer => Some("0".to_string()); "integer zero")]
+    #[test_case("-000", xsd::integer => Some("0".to_string()); "integer minus zero")]
+    #[test_case("+0042", xsd::integer => Some("42".to_string()); "integer leading zeros")]
+    #[test_case("-12", xsd::integer => Some("-12".to_string()); "integer negative")]
+    #[test_case(" 12\n", xsd::integer => Some("12".to_string()); "integer whitespace")]
+    #[test_case("123456789012345678901234567890123456789012", xsd::integer => Some("123456789012345678901234567890123456789012".to_string()); "integer big")]
+    #[test_case("1.0", xsd::integer => None; "integer with decimal point")]
+    #[test_case("", xsd::integer => None; "integer empty")]
+    #[test_case("300", xsd::byte => None; "byte out of range")]
+    #[test_case("-0128", xsd::byte => Some("-128".to_string()); "byte min")]
+    #[test_case("-1", xsd::nonNegativeInteger => None; "negative nonNegativeInteger")]
+    #[test_case("123456789012345678901234567890123456789012", xsd::unsignedLong => None; "big unsignedLong")]
+    #[test_case("0100.500", xsd::decimal => Some("100.5".to_string()); "decimal")]
+    #[test_case("-.5", xsd::decimal => Some("-0.5".to_string()); "decimal no integer part")]
+    #[test_case("3.", xsd::decimal => Some("3".to_string()); "decimal no fractional part")]
+    #[test_case("-0.0", xsd::decimal => Some("0".to_string()); "decimal minus zero")]
+    #[test_case(".", xsd::decimal => None; "decimal dot")]
+    #[test_case("1e3", xsd::decimal => None; "decimal exponent")]
+    #[test_case("100", xsd::double => Some("1.0E2".to_string()); "double integer")]
+    #[test_case("0.0015e+3", xsd::double => Some("1.5E0".to_string()); "double exponent")]
+    #[test_case("-0", xsd::double => Some("-0.0E0".to_string()); "double minus zero")]
+    #[test_case("+INF", xsd::double => Some("INF".to_string()); "double infinity")]
+    #[test_case("1e400", xsd::double => Some("INF".to_string()); "double overflow")]
+    #[test_case("inf", xsd::double => None; "double rust infinity")]
+    #[test_case("--1", xsd::double => None; "double double sign")]
+    #[test_case("1e", xsd::double => None; "double empty exponent")]
+    #[test_case("0.1", xsd::float => Some("1.0E-1".to_string()); "float")]
+    #[test_case("1", xsd::string => None; "not numeric")]
+    fn canonical(lex: &str, dt: SimpleIri) -> Option<String> {
+        canonical_numeric(lex, &dt)
+    }
+
+    #[test]
+    fn term() {
+        let lit = StaticTerm::new_literal_dt_unchecked("01.10", xsd::decimal);
+        assert_eq!(
+            canonicalize_numeric_term(&lit),
+            StaticTerm::new_literal_dt_unchecked("1.1", xsd::decimal)
+        );
+        let invalid = StaticTerm::new_literal_dt_unchecked("x", xsd::decimal);
+        assert_eq!(canonicalize_numeric_term(&invalid), invalid);
+        let iri = StaticTerm::new_iri_unchecked("tag:01");
+        assert_eq!(canonicalize_numeric_term(&iri), iri);
+    }
+}
@@ -111,7 +111,7 @@ where
     /// This constructor does not check that `lexval`
     /// is a valid lexical value for this literal's datatype.
     /// It is the user's responsibility to ensure that.
-    fn new(lexval: U) -> Self {
+    pub(crate) fn new(lexval: U) -> Self {
         Self {
             lexval,
             _phantom: std::marker::PhantomData,
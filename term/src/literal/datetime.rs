@@ -0,0 +1,1201 @@
+//! Native types for the temporal datatypes of [XML Schema]:
+//! `xsd:dateTime`, `xsd:date` and `xsd:duration`.
+//!
+//! [`DateTime`], [`Date`] and [`Duration`] can be parsed from (and formatted back to)
+//! the canonical lexical form of their datatype,
+//! and converted from and to literals
+//! (see [`TryConvertTerm`] and [`AsLiteral`]).
+//!
+//! Durations can be added to (or subtracted from) dateTimes and dates,
+//! either as native values, or directly as literals with [`add_duration`] and [`subtract_duration`],
+//! which also accept the `xsd:dayTimeDuration` and `xsd:yearMonthDuration` subtypes of `xsd:duration`.
+//!
+//! Comparison takes timezones into account,
+//! following the rules of [XML Schema 1.1]:
+//! a value without timezone is only comparable to a value with a timezone
+//! if they are more than 14 hours apart.
+//! Similarly, some durations (e.g. `P1M` and `P30D`) are not comparable.
+//! This is why those types only implement `PartialOrd`.
+//!
+//! ```
+//! # use sophia_term::literal::datetime::*;
+//! let start: DateTime = "2021-01-31T12:00:00+02:00".parse().unwrap();
+//! let end = start + "P1M".parse::<Duration>().unwrap();
+//! assert_eq!(end.to_string(), "2021-02-28T12:00:00+02:00");
+//! assert_eq!(end.duration_since(&start).unwrap().to_string(), "P28D");
+//! assert!(start < "2021-01-31T11:00:00Z".parse().unwrap());
+//! ```
+//!
+//! [XML Schema]: https://www.w3.org/TR/xmlschema11-2/
+//! [XML Schema 1.1]: https://www.w3.org/TR/xmlschema11-2/#dateTime
+//! [`DateTime`]: struct.DateTime.html
+//! [`Date`]: struct.Date.html
+//! [`Duration`]: struct.Duration.html
+//! [`TryConvertTerm`]: ../convert/trait.TryConvertTerm.html
+//! [`AsLiteral`]: ../convert/trait.AsLiteral.html
+//! [`add_duration`]: fn.add_duration.html
+//! [`subtract_duration`]: fn.subtract_duration.html
+
+use super::convert::{AsLiteral, DataType, NativeLiteral, TryConvertTerm};
+use crate::BoxTerm;
+use sophia_api::ns::xsd;
+use sophia_api::term::{SimpleIri, TTerm};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+use thiserror::Error;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MINUTE: i128 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_DAY: i128 = 86_400 * NANOS_PER_SECOND;
+/// The maximum difference between a local time and UTC, in minutes.
+const MAX_TIMEZONE: i16 = 14 * 60;
+/// The maximum absolute year produced by checked arithmetic.
+const MAX_YEAR: i64 = 1 << 40;
+
+/// This error is raised when parsing a temporal value fails.
+#[derive(Clone, Debug, Error)]
+#[error("Invalid lexical value for xsd:{datatype}: {lexical:?}")]
+pub struct TemporalParseError {
+    /// The local name of the expected datatype.
+    pub datatype: &'static str,
+    /// The faulty lexical value.
+    pub lexical: String,
+}
+
+/// An `xsd:dateTime` value, with an optional timezone.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug)]
+pub struct DateTime {
+    /// Nanoseconds since 1970-01-01T00:00:00, in local time.
+    local: i128,
+    /// Offset from UTC, in minutes.
+    timezone: Option<i16>,
+}
+
+impl DateTime {
+    /// Build a dateTime from its components,
+    /// returning `None` if they do not denote a valid dateTime.
+    ///
+    /// `timezone` is the offset from UTC in minutes (between -840 and 840).
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        year: i64,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        nanosecond: u32,
+        timezone: Option<i16>,
+    ) -> Option<Self> {
+        if !valid_date(year, month, day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+            || nanosecond as i128 >= NANOS_PER_SECOND
+            || !valid_timezone(timezone)
+        {
+            return None;
+        }
+        let local = days_from_civil(year, month, day) as i128 * NANOS_PER_DAY
+            + (hour as i128 * 3600 + minute as i128 * 60 + second as i128) * NANOS_PER_SECOND
+            + nanosecond as i128;
+        Some(DateTime { local, timezone })
+    }
+
+    /// The year of this dateTime (in local time).
+    pub fn year(&self) -> i64 {
+        civil_from_days(self.days()).0
+    }
+    /// The month of this dateTime (in local time), from 1 to 12.
+    pub fn month(&self) -> u8 {
+        civil_from_days(self.days()).1
+    }
+    /// The day of this dateTime (in local time), from 1 to 31.
+    pub fn day(&self) -> u8 {
+        civil_from_days(self.days()).2
+    }
+    /// The hour of this dateTime (in local time), from 0 to 23.
+    pub fn hour(&self) -> u8 {
+        (self.nanos_of_day() / (60 * NANOS_PER_MINUTE)) as u8
+    }
+    /// The minute of this dateTime (in local time), from 0 to 59.
+    pub fn minute(&self) -> u8 {
+        (self.nanos_of_day() / NANOS_PER_MINUTE % 60) as u8
+    }
+    /// The second of this dateTime, from 0 to 59.
+    pub fn second(&self) -> u8 {
+        (self.nanos_of_day() / NANOS_PER_SECOND % 60) as u8
+    }
+    /// The fractional part of the seconds of this dateTime, in nanoseconds.
+    pub fn nanosecond(&self) -> u32 {
+        (self.nanos_of_day() % NANOS_PER_SECOND) as u32
+    }
+    /// The offset from UTC of this dateTime, in minutes, if any.
+    pub fn timezone(&self) -> Option<i16> {
+        self.timezone
+    }
+
+    /// The date part of this dateTime.
+    pub fn date(&self) -> Date {
+        Date {
+            days: self.days(),
+            timezone: self.timezone,
+        }
+    }
+
+    /// Adjust this dateTime to `timezone`,
+    /// in the manner of the XPath function [`fn:adjust-dateTime-to-timezone`]:
+    /// * if both this dateTime and `timezone` have a value,
+    ///   the result represents the same instant in the new timezone;
+    /// * if this dateTime has no timezone, `timezone` is simply attached to it;
+    /// * if `timezone` is `None`, the timezone is removed, keeping the local time.
+    ///
+    /// Returns `None` if `timezone` is out of range.
+    ///
+    /// [`fn:adjust-dateTime-to-timezone`]: https://www.w3.org/TR/xpath-functions/#func-adjust-dateTime-to-timezone
+    pub fn adjust_to_timezone(&self, timezone: Option<i16>) -> Option<Self> {
+        if !valid_timezone(timezone) {
+            return None;
+        }
+        let local = match (self.timezone, timezone) {
+            (Some(old), Some(new)) => self.local + (new - old) as i128 * NANOS_PER_MINUTE,
+            _ => self.local,
+        };
+        Some(DateTime { local, timezone })
+    }
+
+    /// The duration elapsed from `earlier` to this dateTime,
+    /// or `None` if only one of them has a timezone.
+    pub fn duration_since(&self, earlier: &DateTime) -> Option<Duration> {
+        if self.timezone.is_some() != earlier.timezone.is_some() {
+            return None;
+        }
+        Duration::new(0, self.instant() - earlier.instant())
+    }
+
+    /// Add `duration` to this dateTime (see [`add`](#method.add)),
+    /// returning `None` if the result is out of the supported range.
+    pub fn checked_add(&self, duration: Duration) -> Option<DateTime> {
+        let (year, month, _) = civil_from_days(self.days());
+        let months = (year * 12 + (month as i64 - 1)).checked_add(duration.months)?;
+        if months.div_euclid(12).abs() > MAX_YEAR {
+            return None;
+        }
+        let max = (MAX_YEAR as i128 * 366 + 1) * NANOS_PER_DAY;
+        let shifted = *self + Duration::from_months(duration.months);
+        let local = shifted.local.checked_add(duration.nanoseconds)?;
+        if local.abs() > max {
+            return None;
+        }
+        Some(DateTime {
+            local,
+            timezone: self.timezone,
+        })
+    }
+
+    /// Subtract `duration` from this dateTime,
+    /// returning `None` if the result is out of the supported range.
+    pub fn checked_sub(&self, duration: Duration) -> Option<DateTime> {
+        self.checked_add(duration.checked_neg()?)
+    }
+
+    fn days(&self) -> i64 {
+        self.local.div_euclid(NANOS_PER_DAY) as i64
+    }
+
+    fn nanos_of_day(&self) -> i128 {
+        self.local.rem_euclid(NANOS_PER_DAY)
+    }
+
+    /// Nanoseconds since 1970-01-01T00:00:00Z, assuming UTC if there is no timezone.
+    fn instant(&self) -> i128 {
+        self.local - self.timezone.unwrap_or(0) as i128 * NANOS_PER_MINUTE
+    }
+}
+
+impl PartialEq for DateTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        compare_instants(
+            self.instant(),
+            self.timezone.is_some(),
+            other.instant(),
+            other.timezone.is_some(),
+        )
+    }
+}
+
+impl Add<Duration> for DateTime {
+    type Output = DateTime;
+
+    /// Add a duration to a dateTime,
+    /// following [XML Schema 1.1](https://www.w3.org/TR/xmlschema11-2/#sec-dt-arith):
+    /// months are added first (clamping the day to the end of the month if necessary),
+    /// then the rest of the duration.
+    fn add(self, duration: Duration) -> DateTime {
+        let (year, month, day) = civil_from_days(self.days());
+        let months = year * 12 + (month as i64 - 1) + duration.months;
+        let year = months.div_euclid(12);
+        let month = months.rem_euclid(12) as u8 + 1;
+        let day = day.min(days_in_month(year, month));
+        let local = days_from_civil(year, month, day) as i128 * NANOS_PER_DAY
+            + self.nanos_of_day()
+            + duration.nanoseconds;
+        DateTime {
+            local,
+            timezone: self.timezone,
+        }
+    }
+}
+
+impl Sub<Duration> for DateTime {
+    type Output = DateTime;
+
+    fn sub(self, duration: Duration) -> DateTime {
+        self + -duration
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = TemporalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TemporalParseError {
+            datatype: "dateTime",
+            lexical: s.to_string(),
+        };
+        let mut lexer = Lexer::new(s);
+        let (year, month, day) = lexer.date().ok_or_else(err)?;
+        if !lexer.eat(b'T') {
+            return Err(err());
+        }
+        let nanos_of_day = lexer.time().ok_or_else(err)?;
+        let timezone = lexer.timezone().ok_or_else(err)?;
+        if !lexer.is_done() || !valid_date(year, month, day) {
+            return Err(err());
+        }
+        Ok(DateTime {
+            local: days_from_civil(year, month, day) as i128 * NANOS_PER_DAY + nanos_of_day,
+            timezone,
+        })
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_date(f, self.days())?;
+        write!(
+            f,
+            "T{:02}:{:02}:{:02}",
+            self.hour(),
+            self.minute(),
+            self.second()
+        )?;
+        write_fraction(f, self.nanosecond())?;
+        write_timezone(f, self.timezone)
+    }
+}
+
+/// An `xsd:date` value, with an optional timezone.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug)]
+pub struct Date {
+    /// Days since 1970-01-01.
+    days: i64,
+    /// Offset from UTC, in minutes.
+    timezone: Option<i16>,
+}
+
+impl Date {
+    /// Build a date from its components,
+    /// returning `None` if they do not denote a valid date.
+    ///
+    /// `timezone` is the offset from UTC in minutes (between -840 and 840).
+    pub fn new(year: i64, month: u8, day: u8, timezone: Option<i16>) -> Option<Self> {
+        if !valid_date(year, month, day) || !valid_timezone(timezone) {
+            return None;
+        }
+        Some(Date {
+            days: days_from_civil(year, month, day),
+            timezone,
+        })
+    }
+
+    /// The year of this date.
+    pub fn year(&self) -> i64 {
+        civil_from_days(self.days).0
+    }
+    /// The month of this date, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        civil_from_days(self.days).1
+    }
+    /// The day of this date, from 1 to 31.
+    pub fn day(&self) -> u8 {
+        civil_from_days(self.days).2
+    }
+    /// The offset from UTC of this date, in minutes, if any.
+    pub fn timezone(&self) -> Option<i16> {
+        self.timezone
+    }
+
+    /// The dateTime at the start of this date.
+    pub fn start(&self) -> DateTime {
+        DateTime {
+            local: self.days as i128 * NANOS_PER_DAY,
+            timezone: self.timezone,
+        }
+    }
+
+    /// The duration elapsed from `earlier` to this date,
+    /// or `None` if only one of them has a timezone.
+    pub fn duration_since(&self, earlier: &Date) -> Option<Duration> {
+        self.start().duration_since(&earlier.start())
+    }
+
+    /// Add `duration` to this date (see [`add`](#method.add)),
+    /// returning `None` if the result is out of the supported range.
+    pub fn checked_add(&self, duration: Duration) -> Option<Date> {
+        self.start().checked_add(duration).map(|dt| dt.date())
+    }
+
+    /// Subtract `duration` from this date,
+    /// returning `None` if the result is out of the supported range.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Date> {
+        self.start().checked_sub(duration).map(|dt| dt.date())
+    }
+}
+
+impl PartialEq for Date {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.start().partial_cmp(&other.start())
+    }
+}
+
+impl Add<Duration> for Date {
+    type Output = Date;
+
+    /// Add a duration to a date,
+    /// as if it was added to the start of the date
+    /// (see [`DateTime::add`](struct.DateTime.html#method.add)).
+    fn add(self, duration: Duration) -> Date {
+        (self.start() + duration).date()
+    }
+}
+
+impl Sub<Duration> for Date {
+    type Output = Date;
+
+    fn sub(self, duration: Duration) -> Date {
+        self + -duration
+    }
+}
+
+impl FromStr for Date {
+    type Err = TemporalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TemporalParseError {
+            datatype: "date",
+            lexical: s.to_string(),
+        };
+        let mut lexer = Lexer::new(s);
+        let (year, month, day) = lexer.date().ok_or_else(err)?;
+        let timezone = lexer.timezone().ok_or_else(err)?;
+        if !lexer.is_done() {
+            return Err(err());
+        }
+        Date::new(year, month, day, timezone).ok_or_else(err)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_date(f, self.days)?;
+        write_timezone(f, self.timezone)
+    }
+}
+
+/// An `xsd:duration` value,
+/// made of a number of months and a number of (nano)seconds, of the same sign.
+///
+/// See [module documentation](index.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Duration {
+    months: i64,
+    nanoseconds: i128,
+}
+
+impl Duration {
+    /// Build a duration of `months` months and `nanoseconds` nanoseconds,
+    /// returning `None` if they have opposite signs.
+    pub fn new(months: i64, nanoseconds: i128) -> Option<Self> {
+        if (months < 0 && nanoseconds > 0) || (months > 0 && nanoseconds < 0) {
+            return None;
+        }
+        Some(Duration {
+            months,
+            nanoseconds,
+        })
+    }
+
+    /// Build a duration of `months` months.
+    pub fn from_months(months: i64) -> Self {
+        Duration {
+            months,
+            nanoseconds: 0,
+        }
+    }
+
+    /// Build a duration of `seconds` seconds.
+    pub fn from_seconds(seconds: i64) -> Self {
+        Duration {
+            months: 0,
+            nanoseconds: seconds as i128 * NANOS_PER_SECOND,
+        }
+    }
+
+    /// The number of months of this duration.
+    pub fn months(&self) -> i64 {
+        self.months
+    }
+
+    /// The number of nanoseconds of this duration (in addition to its months).
+    pub fn nanoseconds(&self) -> i128 {
+        self.nanoseconds
+    }
+
+    /// Whether this duration is a valid `xsd:dayTimeDuration`, i.e. it has no months.
+    pub fn is_day_time(&self) -> bool {
+        self.months == 0
+    }
+
+    /// Whether this duration is a valid `xsd:yearMonthDuration`, i.e. it has no (nano)seconds.
+    pub fn is_year_month(&self) -> bool {
+        self.nanoseconds == 0
+    }
+
+    /// Negate this duration, returning `None` on overflow.
+    pub fn checked_neg(&self) -> Option<Duration> {
+        Some(Duration {
+            months: self.months.checked_neg()?,
+            nanoseconds: self.nanoseconds.checked_neg()?,
+        })
+    }
+
+    /// Add two durations,
+    /// returning `None` if the result would have months and seconds of opposite signs.
+    pub fn checked_add(&self, other: &Duration) -> Option<Duration> {
+        Duration::new(
+            self.months.checked_add(other.months)?,
+            self.nanoseconds.checked_add(other.nanoseconds)?,
+        )
+    }
+}
+
+impl Neg for Duration {
+    type Output = Duration;
+
+    fn neg(self) -> Duration {
+        Duration {
+            months: -self.months,
+            nanoseconds: -self.nanoseconds,
+        }
+    }
+}
+
+impl PartialOrd for Duration {
+    /// Durations are compared by adding them to the four reference dateTimes
+    /// specified by [XML Schema](https://www.w3.org/TR/xmlschema11-2/#duration);
+    /// they are only comparable if all four comparisons agree.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        let references = [(1696, 9), (1697, 2), (1903, 3), (1903, 7)];
+        let mut result = None;
+        for (year, month) in references.iter() {
+            let reference = DateTime::new(*year, *month, 1, 0, 0, 0, 0, Some(0)).unwrap();
+            let ord = (reference + *self).partial_cmp(&(reference + *other))?;
+            match result {
+                None => result = Some(ord),
+                Some(previous) if previous != ord => return None,
+                _ => {}
+            }
+        }
+        result
+    }
+}
+
+impl FromStr for Duration {
+    type Err = TemporalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TemporalParseError {
+            datatype: "duration",
+            lexical: s.to_string(),
+        };
+        let mut lexer = Lexer::new(s);
+        let negative = lexer.eat(b'-');
+        if !lexer.eat(b'P') {
+            return Err(err());
+        }
+        let mut months: i64 = 0;
+        let mut nanoseconds: i128 = 0;
+        let mut components = 0;
+        let mut in_time = false;
+        let mut time_components = 0;
+        let mut last = 0;
+        while !lexer.is_done() {
+            if !in_time && lexer.eat(b'T') {
+                in_time = true;
+                continue;
+            }
+            let (value, fraction) = lexer.decimal().ok_or_else(err)?;
+            let designator = lexer.next().ok_or_else(err)?;
+            let (rank, unit) = match (in_time, designator) {
+                (false, b'Y') => (1, 12),
+                (false, b'M') => (2, 1),
+                (false, b'D') => (3, NANOS_PER_DAY),
+                (true, b'H') => (4, 60 * NANOS_PER_MINUTE),
+                (true, b'M') => (5, NANOS_PER_MINUTE),
+                (true, b'S') => (6, NANOS_PER_SECOND),
+                _ => return Err(err()),
+            };
+            if rank <= last || (fraction.is_some() && rank != 6) {
+                return Err(err());
+            }
+            last = rank;
+            components += 1;
+            if in_time {
+                time_components += 1;
+            }
+            if rank <= 2 {
+                let value = i64::try_from(value).ok().ok_or_else(err)?;
+                months = value
+                    .checked_mul(unit as i64)
+                    .and_then(|v| months.checked_add(v))
+                    .ok_or_else(err)?;
+            } else {
+                nanoseconds = value
+                    .checked_mul(unit)
+                    .and_then(|v| v.checked_add(fraction.unwrap_or(0) as i128))
+                    .and_then(|v| nanoseconds.checked_add(v))
+                    .ok_or_else(err)?;
+            }
+        }
+        if components == 0 || (in_time && time_components == 0) {
+            return Err(err());
+        }
+        if negative {
+            months = -months;
+            nanoseconds = -nanoseconds;
+        }
+        Ok(Duration {
+            months,
+            nanoseconds,
+        })
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.months == 0 && self.nanoseconds == 0 {
+            return f.write_str("PT0S");
+        }
+        if self.months < 0 || self.nanoseconds < 0 {
+            f.write_str("-")?;
+        }
+        f.write_str("P")?;
+        let months = self.months.abs();
+        if months >= 12 {
+            write!(f, "{}Y", months / 12)?;
+        }
+        if months % 12 != 0 {
+            write!(f, "{}M", months % 12)?;
+        }
+        let nanos = self.nanoseconds.abs();
+        if nanos >= NANOS_PER_DAY {
+            write!(f, "{}D", nanos / NANOS_PER_DAY)?;
+        }
+        let nanos = nanos % NANOS_PER_DAY;
+        if nanos != 0 {
+            f.write_str("T")?;
+            let hours = nanos / (60 * NANOS_PER_MINUTE);
+            let minutes = nanos / NANOS_PER_MINUTE % 60;
+            let seconds = nanos % NANOS_PER_MINUTE;
+            if hours != 0 {
+                write!(f, "{}H", hours)?;
+            }
+            if minutes != 0 {
+                write!(f, "{}M", minutes)?;
+            }
+            if seconds != 0 {
+                write!(f, "{}", seconds / NANOS_PER_SECOND)?;
+                write_fraction(f, (seconds % NANOS_PER_SECOND) as u32)?;
+                f.write_str("S")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_temporal_literal {
+    ($ty:ty, $iri:expr) => {
+        impl DataType for $ty {
+            fn iri() -> SimpleIri<'static> {
+                $iri
+            }
+        }
+
+        impl AsLiteral for $ty {
+            type Term = NativeLiteral<Self>;
+            fn as_literal(&self) -> Self::Term {
+                NativeLiteral::new(self.to_string().into_boxed_str())
+            }
+        }
+
+        impl TryConvertTerm for $ty {}
+    };
+}
+
+impl_temporal_literal!(DateTime, xsd::dateTime);
+impl_temporal_literal!(Date, xsd::date);
+impl_temporal_literal!(Duration, xsd::duration);
+
+/// Parse a literal of type `xsd:duration`, `xsd:dayTimeDuration` or `xsd:yearMonthDuration`.
+///
+/// Returns `None` if `term` is not such a literal, or if its lexical form is invalid
+/// (e.g. a `xsd:dayTimeDuration` with a number of years).
+pub fn duration_from_term<T>(term: &T) -> Option<Duration>
+where
+    T: TTerm + ?Sized,
+{
+    let dt = term.datatype()?;
+    let lex = term.value_raw().0;
+    if xsd::dayTimeDuration == dt {
+        let date_part = lex.split('T').next().unwrap_or("");
+        if date_part.contains(['Y', 'M']) {
+            return None;
+        }
+    } else if xsd::yearMonthDuration == dt {
+        if lex.contains(['D', 'T']) {
+            return None;
+        }
+    } else if xsd::duration != dt {
+        return None;
+    }
+    lex.parse().ok()
+}
+
+/// Add the duration literal `duration` to the `xsd:dateTime` or `xsd:date` literal `temporal`,
+/// following the arithmetic of [XML Schema 1.1](https://www.w3.org/TR/xmlschema11-2/#sec-dt-arith)
+/// (see [`DateTime::add`](struct.DateTime.html#method.add)).
+///
+/// `duration` may be an `xsd:duration`, `xsd:dayTimeDuration` or `xsd:yearMonthDuration`
+/// (see [`duration_from_term`](fn.duration_from_term.html)).
+/// The result has the same datatype and timezone as `temporal`;
+/// a date is considered as its first instant, then truncated after the addition.
+///
+/// Returns `None` if any argument is not a valid literal of the expected types,
+/// or if the result is out of the supported range.
+///
+/// ```
+/// # use sophia_term::{BoxTerm, literal::datetime::add_duration};
+/// # use sophia_api::{ns::xsd, term::TTerm};
+/// let date = BoxTerm::new_literal_dt("2020-01-31-05:00", xsd::date).unwrap();
+/// let month = BoxTerm::new_literal_dt("P1M", xsd::yearMonthDuration).unwrap();
+/// let end = add_duration(&date, &month).unwrap();
+/// assert_eq!(end.value(), "2020-02-29-05:00");
+/// assert_eq!(end.datatype(), Some(xsd::date));
+/// ```
+pub fn add_duration<T, U>(temporal: &T, duration: &U) -> Option<BoxTerm>
+where
+    T: TTerm + ?Sized,
+    U: TTerm + ?Sized,
+{
+    shift_temporal(temporal, duration_from_term(duration)?)
+}
+
+/// Subtract the duration literal `duration` from the `xsd:dateTime` or `xsd:date` literal `temporal`.
+///
+/// See [`add_duration`](fn.add_duration.html).
+pub fn subtract_duration<T, U>(temporal: &T, duration: &U) -> Option<BoxTerm>
+where
+    T: TTerm + ?Sized,
+    U: TTerm + ?Sized,
+{
+    shift_temporal(temporal, duration_from_term(duration)?.checked_neg()?)
+}
+
+fn shift_temporal<T>(temporal: &T, duration: Duration) -> Option<BoxTerm>
+where
+    T: TTerm + ?Sized,
+{
+    let dt = temporal.datatype()?;
+    let lex = temporal.value_raw().0;
+    if xsd::dateTime == dt {
+        let result = lex.parse::<DateTime>().ok()?.checked_add(duration)?;
+        Some(BoxTerm::new_literal_dt_unchecked(
+            result.to_string(),
+            xsd::dateTime,
+        ))
+    } else if xsd::date == dt {
+        let result = lex.parse::<Date>().ok()?.checked_add(duration)?;
+        Some(BoxTerm::new_literal_dt_unchecked(
+            result.to_string(),
+            xsd::date,
+        ))
+    } else {
+        None
+    }
+}
+
+fn valid_date(year: i64, month: u8, day: u8) -> bool {
+    (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)
+}
+
+fn valid_timezone(timezone: Option<i16>) -> bool {
+    timezone.is_none_or(|tz| (-MAX_TIMEZONE..=MAX_TIMEZONE).contains(&tz))
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 of the given date
+/// in the proleptic Gregorian calendar (with a year 0, as in XML Schema 1.1).
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u8;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u8;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Compare two instants, only one of which may have a timezone
+/// (in which case they are comparable only if they are more than 14 hours apart).
+fn compare_instants(a: i128, a_tz: bool, b: i128, b_tz: bool) -> Option<Ordering> {
+    if a_tz == b_tz {
+        return Some(a.cmp(&b));
+    }
+    let margin = MAX_TIMEZONE as i128 * NANOS_PER_MINUTE;
+    if a + margin < b {
+        Some(Ordering::Less)
+    } else if a - margin > b {
+        Some(Ordering::Greater)
+    } else {
+        None
+    }
+}
+
+fn write_date(f: &mut fmt::Formatter, days: i64) -> fmt::Result {
+    let (year, month, day) = civil_from_days(days);
+    if year < 0 {
+        f.write_str("-")?;
+    }
+    write!(f, "{:04}-{:02}-{:02}", year.abs(), month, day)
+}
+
+/// Write the fractional part of a number of seconds, without trailing zeros.
+fn write_fraction(f: &mut fmt::Formatter, nanos: u32) -> fmt::Result {
+    if nanos == 0 {
+        return Ok(());
+    }
+    let digits = format!("{:09}", nanos);
+    write!(f, ".{}", digits.trim_end_matches('0'))
+}
+
+fn write_timezone(f: &mut fmt::Formatter, timezone: Option<i16>) -> fmt::Result {
+    match timezone {
+        None => Ok(()),
+        Some(0) => f.write_str("Z"),
+        Some(tz) => write!(
+            f,
+            "{}{:02}:{:02}",
+            if tz < 0 { '-' } else { '+' },
+            tz.abs() / 60,
+            tz.abs() % 60
+        ),
+    }
+}
+
+/// A minimal lexer for the lexical forms of temporal datatypes.
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(txt: &'a str) -> Self {
+        Lexer {
+            bytes: txt.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume a sequence of digits, returning it.
+    fn digits(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos]).unwrap()
+    }
+
+    /// Consume exactly two digits.
+    fn two_digits(&mut self) -> Option<u8> {
+        let digits = self.digits();
+        if digits.len() == 2 {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Consume the fractional part of a number (after the dot), as nanoseconds
+    /// (digits beyond the nanosecond are ignored).
+    fn fraction(&mut self) -> Option<u32> {
+        let digits = self.digits();
+        if digits.is_empty() {
+            return None;
+        }
+        let mut padded = digits.chars().take(9).collect::<String>();
+        while padded.len() < 9 {
+            padded.push('0');
+        }
+        padded.parse().ok()
+    }
+
+    /// Consume an unsigned decimal number, returning its integer part
+    /// and its optional fractional part (as nanoseconds).
+    fn decimal(&mut self) -> Option<(i128, Option<u32>)> {
+        let digits = self.digits();
+        if digits.is_empty() {
+            return None;
+        }
+        let value = digits.parse().ok()?;
+        let fraction = if self.eat(b'.') {
+            Some(self.fraction()?)
+        } else {
+            None
+        };
+        Some((value, fraction))
+    }
+
+    /// Consume a date (`-?YYYY-MM-DD`), without checking the number of days in the month.
+    fn date(&mut self) -> Option<(i64, u8, u8)> {
+        let negative = self.eat(b'-');
+        let digits = self.digits();
+        if digits.len() < 4 || (digits.len() > 4 && digits.starts_with('0')) {
+            return None;
+        }
+        let year: i64 = digits.parse().ok()?;
+        if !self.eat(b'-') {
+            return None;
+        }
+        let month = self.two_digits()?;
+        if !self.eat(b'-') {
+            return None;
+        }
+        let day = self.two_digits()?;
+        Some((if negative { -year } else { year }, month, day))
+    }
+
+    /// Consume a time (`hh:mm:ss(.s+)?`), returning the nanoseconds since midnight
+    /// (`24:00:00` is accepted, and denotes the midnight of the next day).
+    fn time(&mut self) -> Option<i128> {
+        let hour = self.two_digits()?;
+        if !self.eat(b':') {
+            return None;
+        }
+        let minute = self.two_digits()?;
+        if !self.eat(b':') {
+            return None;
+        }
+        let second = self.two_digits()?;
+        let nanos = if self.eat(b'.') { self.fraction()? } else { 0 };
+        if hour > 24
+            || minute > 59
+            || second > 59
+            || (hour == 24 && (minute, second, nanos) != (0, 0, 0))
+        {
+            return None;
+        }
+        Some(
+            (hour as i128 * 3600 + minute as i128 * 60 + second as i128) * NANOS_PER_SECOND
+                + nanos as i128,
+        )
+    }
+
+    /// Consume an optional timezone (`Z` or `[+-]hh:mm`), returning it in minutes.
+    ///
+    /// The outer option is `None` if the timezone is invalid.
+    fn timezone(&mut self) -> Option<Option<i16>> {
+        let sign = match self.peek() {
+            None => return Some(None),
+            Some(b'Z') => {
+                self.pos += 1;
+                return Some(Some(0));
+            }
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            Some(_) => return None,
+        };
+        self.pos += 1;
+        let hours = self.two_digits()? as i16;
+        if !self.eat(b':') {
+            return None;
+        }
+        let minutes = self.two_digits()? as i16;
+        let timezone = hours * 60 + minutes;
+        if minutes > 59 || timezone > MAX_TIMEZONE {
+            return None;
+        }
+        Some(Some(sign * timezone))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::convert::ConvertibleTerm;
+    use crate::StaticTerm;
+    use sophia_api::term::{term_eq, TTerm};
+    use test_case::test_case;
+
+    #[test_case("2021-03-04T05:06:07" => Some("2021-03-04T05:06:07".to_string()); "no timezone")]
+    #[test_case("2021-03-04T05:06:07.120Z" => Some("2021-03-04T05:06:07.12Z".to_string()); "fraction")]
+    #[test_case("2021-03-04T05:06:07+00:00" => Some("2021-03-04T05:06:07Z".to_string()); "utc offset")]
+    #[test_case("2021-03-04T05:06:07-05:30" => Some("2021-03-04T05:06:07-05:30".to_string()); "negative offset")]
+    #[test_case("2021-12-31T24:00:00" => Some("2022-01-01T00:00:00".to_string()); "midnight")]
+    #[test_case("-0044-03-15T12:00:00" => Some("-0044-03-15T12:00:00".to_string()); "negative year")]
+    #[test_case("12021-03-04T05:06:07" => Some("12021-03-04T05:06:07".to_string()); "long year")]
+    #[test_case("2021-02-29T05:06:07" => None; "not a leap year")]
+    #[test_case("2021-03-04T24:00:01" => None; "after midnight")]
+    #[test_case("2021-03-04T05:06:07+15:00" => None; "timezone out of range")]
+    #[test_case("2021-03-04" => None; "date")]
+    #[test_case("021-03-04T05:06:07" => None; "short year")]
+    #[test_case("02021-03-04T05:06:07" => None; "leading zero")]
+    fn parse_date_time(lex: &str) -> Option<String> {
+        lex.parse::<DateTime>().ok().map(|dt| dt.to_string())
+    }
+
+    #[test_case("P1Y2M3DT4H5M6.7S" => Some("P1Y2M3DT4H5M6.7S".to_string()); "full")]
+    #[test_case("-P14M" => Some("-P1Y2M".to_string()); "months")]
+    #[test_case("PT36H" => Some("P1DT12H".to_string()); "hours")]
+    #[test_case("P0D" => Some("PT0S".to_string()); "zero")]
+    #[test_case("PT0.5S" => Some("PT0.5S".to_string()); "fraction")]
+    #[test_case("P" => None; "empty")]
+    #[test_case("P1DT" => None; "empty time")]
+    #[test_case("PT1D" => None; "days in time")]
+    #[test_case("P1M1Y" => None; "wrong order")]
+    #[test_case("P1.5Y" => None; "fractional years")]
+    fn parse_duration(lex: &str) -> Option<String> {
+        lex.parse::<Duration>().ok().map(|d| d.to_string())
+    }
+
+    #[test]
+    fn parse_date() {
+        let d: Date = "2020-02-29+01:00".parse().unwrap();
+        assert_eq!((d.year(), d.month(), d.day()), (2020, 2, 29));
+        assert_eq!(d.timezone(), Some(60));
+        assert_eq!(d.to_string(), "2020-02-29+01:00");
+        assert!("2020-02-30".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let dt: DateTime = "2020-01-31T23:30:00Z".parse().unwrap();
+        let d = |s: &str| s.parse::<Duration>().unwrap();
+        assert_eq!((dt + d("P1M")).to_string(), "2020-02-29T23:30:00Z");
+        assert_eq!((dt + d("PT1H")).to_string(), "2020-02-01T00:30:00Z");
+        assert_eq!((dt - d("P1Y1DT0.5S")).to_string(), "2019-01-30T23:29:59.5Z");
+        let date: Date = "2020-03-31".parse().unwrap();
+        assert_eq!((date - d("P1M")).to_string(), "2020-02-29");
+        assert_eq!((date + d("PT25H")).to_string(), "2020-04-01");
+        let later: DateTime = "2020-02-01T01:00:00+02:00".parse().unwrap();
+        assert_eq!(later.duration_since(&dt).unwrap(), d("-PT30M"));
+        let local: DateTime = "2020-02-01T01:00:00".parse().unwrap();
+        assert_eq!(local.duration_since(&dt), None);
+        assert_eq!(d("P1M").checked_add(&d("-P1D")), None);
+        assert_eq!(d("P1M").checked_add(&d("P1D")), Some(d("P1MT24H")));
+    }
+
+    #[test]
+    fn comparison() {
+        let dt = |s: &str| s.parse::<DateTime>().unwrap();
+        assert_eq!(dt("2020-01-01T01:00:00+01:00"), dt("2020-01-01T00:00:00Z"));
+        assert!(dt("2020-01-01T00:00:00Z") < dt("2020-01-01T00:00:01Z"));
+        assert_eq!(
+            dt("2020-01-01T00:00:00").partial_cmp(&dt("2020-01-01T10:00:00Z")),
+            None
+        );
+        assert!(dt("2020-01-01T00:00:00") < dt("2020-01-02T00:00:00Z"));
+        let d = |s: &str| s.parse::<Duration>().unwrap();
+        assert!(d("P1Y") > d("P364D"));
+        assert!(d("P1M") < d("P32D"));
+        assert_eq!(d("P1M").partial_cmp(&d("P30D")), None);
+        assert_eq!(d("PT24H").partial_cmp(&d("P1D")), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn timezones() {
+        let dt: DateTime = "2020-01-01T00:30:00+01:00".parse().unwrap();
+        let utc = dt.adjust_to_timezone(Some(0)).unwrap();
+        assert_eq!(utc.to_string(), "2019-12-31T23:30:00Z");
+        assert_eq!(utc.date().to_string(), "2019-12-31Z");
+        assert_eq!(
+            dt.adjust_to_timezone(None).unwrap().to_string(),
+            "2020-01-01T00:30:00"
+        );
+        assert!(dt.adjust_to_timezone(Some(900)).is_none());
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let dt: DateTime = "2020-01-31T23:30:00Z".parse().unwrap();
+        let d = |s: &str| s.parse::<Duration>().unwrap();
+        assert_eq!(dt.checked_add(d("P1M")), Some(dt + d("P1M")));
+        assert_eq!(dt.checked_sub(d("PT1H")), Some(dt - d("PT1H")));
+        assert_eq!(dt.checked_add(Duration::from_months(i64::MAX)), None);
+        assert_eq!(dt.checked_sub(Duration::new(0, i128::MAX).unwrap()), None);
+        let date: Date = "2020-02-29".parse().unwrap();
+        assert_eq!(
+            date.checked_add(d("P1Y")).unwrap().to_string(),
+            "2021-02-28"
+        );
+        assert_eq!(
+            date.checked_sub(d("-PT23H")).unwrap().to_string(),
+            "2020-02-29"
+        );
+    }
+
+    #[test_case("P1DT2H", xsd::dayTimeDuration => Some("P1DT2H".to_string()); "day time")]
+    #[test_case("-PT90M", xsd::dayTimeDuration => Some("-PT1H30M".to_string()); "negative day time")]
+    #[test_case("P1Y", xsd::dayTimeDuration => None; "day time with years")]
+    #[test_case("P2M", xsd::dayTimeDuration => None; "day time with months")]
+    #[test_case("P1Y2M", xsd::yearMonthDuration => Some("P1Y2M".to_string()); "year month")]
+    #[test_case("P1D", xsd::yearMonthDuration => None; "year month with days")]
+    #[test_case("PT1M", xsd::yearMonthDuration => None; "year month with minutes")]
+    #[test_case("P1Y2DT3S", xsd::duration => Some("P1Y2DT3S".to_string()); "duration")]
+    #[test_case("P1D", xsd::string => None; "not a duration")]
+    fn duration_term(lex: &str, dt: SimpleIri) -> Option<String> {
+        let t = BoxTerm::new_literal_dt_unchecked(lex, dt);
+        duration_from_term(&t).map(|d| d.to_string())
+    }
+
+    #[test_case("2020-01-31T10:00:00+02:00", xsd::dateTime, "P1M", xsd::yearMonthDuration => Some("2020-02-29T10:00:00+02:00".to_string()); "month clamped")]
+    #[test_case("2020-12-31T23:00:00", xsd::dateTime, "PT2H", xsd::dayTimeDuration => Some("2021-01-01T01:00:00".to_string()); "year change")]
+    #[test_case("2020-03-01Z", xsd::date, "P1D", xsd::dayTimeDuration => Some("2020-03-02Z".to_string()); "date plus day")]
+    #[test_case("2020-03-01", xsd::date, "PT23H", xsd::dayTimeDuration => Some("2020-03-01".to_string()); "date truncated")]
+    #[test_case("2020-03-01", xsd::date, "-PT1H", xsd::dayTimeDuration => Some("2020-02-29".to_string()); "date minus hour")]
+    #[test_case("2020-03-01", xsd::date, "P1Y", xsd::dayTimeDuration => None; "invalid duration")]
+    #[test_case("2020-03-01", xsd::string, "P1D", xsd::dayTimeDuration => None; "not a date")]
+    #[test_case("2020-02-30", xsd::date, "P1D", xsd::dayTimeDuration => None; "invalid date")]
+    fn add_duration_term(lex: &str, dt: SimpleIri, dur: &str, dur_dt: SimpleIri) -> Option<String> {
+        let t = BoxTerm::new_literal_dt_unchecked(lex, dt);
+        let d = BoxTerm::new_literal_dt_unchecked(dur, dur_dt);
+        let result = add_duration(&t, &d)?;
+        assert!(term_eq(&result.datatype()?, &dt));
+        let value = result.value().to_string();
+        Some(value)
+    }
+
+    #[test]
+    fn subtract_duration_term() {
+        let t = BoxTerm::new_literal_dt_unchecked("2021-03-31T12:00:00Z", xsd::dateTime);
+        let d = BoxTerm::new_literal_dt_unchecked("P1M", xsd::yearMonthDuration);
+        assert_eq!(
+            subtract_duration(&t, &d).unwrap().value(),
+            "2021-02-28T12:00:00Z"
+        );
+        let d = BoxTerm::new_literal_dt_unchecked("-P1DT1H", xsd::dayTimeDuration);
+        assert_eq!(
+            subtract_duration(&t, &d).unwrap().value(),
+            "2021-04-01T13:00:00Z"
+        );
+    }
+
+    #[test]
+    fn literals() {
+        let lit = StaticTerm::new_literal_dt_unchecked("2020-01-01T00:00:00.0Z", xsd::dateTime);
+        let dt: DateTime = lit.try_converted().unwrap();
+        assert_eq!(dt.hour(), 0);
+        let lit2 = dt.as_literal();
+        assert_eq!(lit2.value(), "2020-01-01T00:00:00Z");
+        assert_eq!(lit2.datatype(), Some(xsd::dateTime));
+        let lit = StaticTerm::new_literal_dt_unchecked("P1D", xsd::date);
+        assert!(lit.try_converted::<Date>().is_err());
+        assert!(lit.try_converted::<Duration>().is_err());
+    }
+}
@@ -0,0 +1,1006 @@
+//! Native geometry types for the literals of [GeoSPARQL]:
+//! `geo:wktLiteral` and `geo:gmlLiteral`.
+//!
+//! A [`Geometry`] can be parsed from (and formatted back to) [Well-Known Text],
+//! parsed from [GML] with [`parse_gml`],
+//! and obtained from a literal of either datatype with [`as_geometry`].
+//! It supports the basic spatial functions
+//! [`intersects`], [`contains`], [`within`] and [`distance`],
+//! which are also available as a term matcher
+//! (see [`GeometryMatcher`]).
+//!
+//! Coordinates are compared as they are, in a Cartesian plane:
+//! the coordinate reference system (CRS) of literals is parsed but otherwise ignored,
+//! so only literals using the same CRS should be compared,
+//! and distances are expressed in the units of that CRS
+//! (i.e. degrees for the default CRS of WKT literals).
+//! Only the first two coordinates of each point are kept.
+//!
+//! This module does not rely on a geometry library (such as the `geo` crate):
+//! it only supports the geometries and spatial functions listed above,
+//! with simple planar algorithms; polygons are not checked to be valid (e.g. closed rings).
+//!
+//! This module requires the `geo` feature.
+//!
+//! ```
+//! # use sophia_term::literal::geo::*;
+//! let square: Geometry = "POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0))".parse()?;
+//! let point: Geometry = "POINT (5 5)".parse()?;
+//! assert!(square.contains(&point));
+//! assert!(point.within(&square));
+//! assert_eq!(point.distance(&"POINT (8 9)".parse()?), Some(5.0));
+//!
+//! let line = parse_gml(
+//!     r#"<gml:LineString><gml:posList>-5 5 5 5</gml:posList></gml:LineString>"#
+//! )?;
+//! assert_eq!(line.to_string(), "LINESTRING (-5 5, 5 5)");
+//! assert!(line.intersects(&square));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [GeoSPARQL]: https://www.ogc.org/standard/geosparql/
+//! [Well-Known Text]: https://www.ogc.org/standard/sfa/
+//! [GML]: https://www.ogc.org/standard/gml/
+//! [`Geometry`]: enum.Geometry.html
+//! [`parse_gml`]: fn.parse_gml.html
+//! [`as_geometry`]: fn.as_geometry.html
+//! [`intersects`]: enum.Geometry.html#method.intersects
+//! [`contains`]: enum.Geometry.html#method.contains
+//! [`within`]: enum.Geometry.html#method.within
+//! [`distance`]: enum.Geometry.html#method.distance
+//! [`GeometryMatcher`]: ../matcher/struct.GeometryMatcher.html
+
+use super::convert::{AsLiteral, DataType, NativeLiteral, TryConvertTerm};
+use sophia_api::ns::geo;
+use sophia_api::term::{SimpleIri, TTerm};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// This error is raised when parsing a geometry fails.
+#[derive(Clone, Debug, Error)]
+#[error("Invalid lexical value for geo:{datatype}: {lexical:?}")]
+pub struct GeoParseError {
+    /// The local name of the expected datatype.
+    pub datatype: &'static str,
+    /// The faulty lexical value.
+    pub lexical: String,
+}
+
+/// A point in a plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    /// The first coordinate (longitude, in geographic CRSs).
+    pub x: f64,
+    /// The second coordinate (latitude, in geographic CRSs).
+    pub y: f64,
+}
+
+/// A polygon, made of an exterior ring and optional holes.
+///
+/// Each ring is expected to be closed (its last point equal to its first point).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    /// The exterior ring.
+    pub exterior: Vec<Point>,
+    /// The interior rings (holes).
+    pub interiors: Vec<Vec<Point>>,
+}
+
+/// A geometry, as defined by [OGC Simple Features].
+///
+/// Empty geometries (e.g. `POINT EMPTY`) are represented by an empty collection.
+///
+/// See [module documentation](index.html).
+///
+/// [OGC Simple Features]: https://www.ogc.org/standard/sfa/
+#[derive(Clone, Debug, PartialEq)]
+pub enum Geometry {
+    /// A single point.
+    Point(Point),
+    /// A sequence of points, linked by straight segments.
+    LineString(Vec<Point>),
+    /// A polygon.
+    Polygon(Polygon),
+    /// A set of points.
+    MultiPoint(Vec<Point>),
+    /// A set of line strings.
+    MultiLineString(Vec<Vec<Point>>),
+    /// A set of polygons.
+    MultiPolygon(Vec<Polygon>),
+    /// A set of arbitrary geometries.
+    GeometryCollection(Vec<Geometry>),
+}
+
+impl Geometry {
+    /// Whether this geometry has no point.
+    pub fn is_empty(&self) -> bool {
+        self.parts().is_empty()
+    }
+
+    /// Whether this geometry and `other` have at least one point in common.
+    pub fn intersects(&self, other: &Geometry) -> bool {
+        let theirs = other.parts();
+        self.parts()
+            .iter()
+            .any(|a| theirs.iter().any(|b| a.intersects(b)))
+    }
+
+    /// Whether every point of `other` is a point of this geometry.
+    ///
+    /// For multi-geometries and collections,
+    /// this is approximated by checking that each part of `other`
+    /// is contained in some part of this geometry.
+    /// An empty geometry is not contained in any geometry.
+    pub fn contains(&self, other: &Geometry) -> bool {
+        let mine = self.parts();
+        let theirs = other.parts();
+        !theirs.is_empty() && theirs.iter().all(|b| mine.iter().any(|a| a.contains(b)))
+    }
+
+    /// Whether every point of this geometry is a point of `other`.
+    pub fn within(&self, other: &Geometry) -> bool {
+        other.contains(self)
+    }
+
+    /// The minimal Cartesian distance between this geometry and `other`,
+    /// or `None` if either of them is empty.
+    pub fn distance(&self, other: &Geometry) -> Option<f64> {
+        let mine = self.parts();
+        let theirs = other.parts();
+        if mine.is_empty() || theirs.is_empty() {
+            return None;
+        }
+        if self.intersects(other) {
+            return Some(0.0);
+        }
+        let mut min = f64::INFINITY;
+        for a in &mine {
+            for b in &theirs {
+                for (a1, a2) in a.segments() {
+                    for (b1, b2) in b.segments() {
+                        min = min.min(segment_distance(a1, a2, b1, b2));
+                    }
+                }
+            }
+        }
+        Some(min)
+    }
+
+    /// The points, lines and polygons composing this geometry.
+    fn parts(&self) -> Vec<Part<'_>> {
+        let mut parts = vec![];
+        self.push_parts(&mut parts);
+        parts
+    }
+
+    fn push_parts<'a>(&'a self, parts: &mut Vec<Part<'a>>) {
+        match self {
+            Geometry::Point(p) => parts.push(Part::Point(*p)),
+            Geometry::LineString(l) if !l.is_empty() => parts.push(Part::Line(l)),
+            Geometry::Polygon(p) if !p.exterior.is_empty() => parts.push(Part::Polygon(p)),
+            Geometry::MultiPoint(ps) => parts.extend(ps.iter().map(|p| Part::Point(*p))),
+            Geometry::MultiLineString(ls) => {
+                parts.extend(ls.iter().filter(|l| !l.is_empty()).map(|l| Part::Line(l)))
+            }
+            Geometry::MultiPolygon(ps) => parts.extend(
+                ps.iter()
+                    .filter(|p| !p.exterior.is_empty())
+                    .map(Part::Polygon),
+            ),
+            Geometry::GeometryCollection(gs) => {
+                for g in gs {
+                    g.push_parts(parts);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Parse a WKT literal into its (optional) CRS IRI and its geometry.
+pub fn parse_wkt(txt: &str) -> Result<(Option<&str>, Geometry), GeoParseError> {
+    let err = || GeoParseError {
+        datatype: "wktLiteral",
+        lexical: txt.to_string(),
+    };
+    let mut wkt = txt.trim();
+    let mut crs = None;
+    if let Some(rest) = wkt.strip_prefix('<') {
+        let end = rest.find('>').ok_or_else(err)?;
+        crs = Some(&rest[..end]);
+        wkt = &rest[end + 1..];
+    }
+    let mut tokens = WktTokens::new(wkt);
+    let geometry = tokens.geometry().ok_or_else(err)?;
+    match tokens.next() {
+        None => Ok((crs, geometry)),
+        Some(_) => Err(err()),
+    }
+}
+
+/// Parse a GML literal into a geometry.
+///
+/// The supported elements are `Point`, `LineString`, `Polygon`
+/// (with `pos`, `posList` or `coordinates`),
+/// and their `Multi` counterparts
+/// (`MultiPoint`, `MultiCurve`, `MultiLineString`, `MultiSurface` and `MultiPolygon`).
+/// The `srsName` attribute is ignored.
+pub fn parse_gml(txt: &str) -> Result<Geometry, GeoParseError> {
+    let err = || GeoParseError {
+        datatype: "gmlLiteral",
+        lexical: txt.to_string(),
+    };
+    let root = GmlElement::parse(txt).ok_or_else(err)?;
+    root.geometry().ok_or_else(err)
+}
+
+/// The geometry of a `geo:wktLiteral` or `geo:gmlLiteral`,
+/// or `None` if `term` is not a valid literal of either datatype.
+pub fn as_geometry<T: TTerm + ?Sized>(term: &T) -> Option<Geometry> {
+    let dt = term.datatype()?;
+    if geo::wktLiteral == dt {
+        parse_wkt(&term.value()).ok().map(|(_, g)| g)
+    } else if geo::gmlLiteral == dt {
+        parse_gml(&term.value()).ok()
+    } else {
+        None
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = GeoParseError;
+
+    /// Parse WKT, ignoring the CRS IRI if any (see [`parse_wkt`](fn.parse_wkt.html)).
+    fn from_str(txt: &str) -> Result<Self, Self::Err> {
+        parse_wkt(txt).map(|(_, g)| g)
+    }
+}
+
+impl fmt::Display for Geometry {
+    /// Format as WKT.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let empty = match self {
+            Geometry::LineString(l) => l.is_empty(),
+            Geometry::Polygon(p) => p.exterior.is_empty(),
+            Geometry::MultiPoint(v) => v.is_empty(),
+            Geometry::MultiLineString(v) => v.is_empty(),
+            Geometry::MultiPolygon(v) => v.is_empty(),
+            Geometry::GeometryCollection(v) => v.is_empty(),
+            Geometry::Point(_) => false,
+        };
+        let keyword = match self {
+            Geometry::Point(_) => "POINT",
+            Geometry::LineString(_) => "LINESTRING",
+            Geometry::Polygon(_) => "POLYGON",
+            Geometry::MultiPoint(_) => "MULTIPOINT",
+            Geometry::MultiLineString(_) => "MULTILINESTRING",
+            Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+            Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+        };
+        if empty {
+            return write!(f, "{} EMPTY", keyword);
+        }
+        write!(f, "{} ", keyword)?;
+        match self {
+            Geometry::Point(p) => write!(f, "({})", WktPoint(p)),
+            Geometry::LineString(l) => write!(f, "{}", WktPoints(l)),
+            Geometry::Polygon(p) => write!(f, "{}", WktPolygon(p)),
+            Geometry::MultiPoint(ps) => write!(f, "{}", WktPoints(ps)),
+            Geometry::MultiLineString(ls) => {
+                write_list(f, ls, |f, l| write!(f, "{}", WktPoints(l)))
+            }
+            Geometry::MultiPolygon(ps) => write_list(f, ps, |f, p| write!(f, "{}", WktPolygon(p))),
+            Geometry::GeometryCollection(gs) => write_list(f, gs, |f, g| write!(f, "{}", g)),
+        }
+    }
+}
+
+impl DataType for Geometry {
+    fn iri() -> SimpleIri<'static> {
+        geo::wktLiteral
+    }
+}
+
+impl AsLiteral for Geometry {
+    type Term = NativeLiteral<Self>;
+    fn as_literal(&self) -> Self::Term {
+        NativeLiteral::new(self.to_string().into_boxed_str())
+    }
+}
+
+impl TryConvertTerm for Geometry {}
+
+// ---------------------------------------------------------------------------------
+//                                  WKT formatting
+// ---------------------------------------------------------------------------------
+
+struct WktPoint<'a>(&'a Point);
+
+impl fmt::Display for WktPoint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0.x, self.0.y)
+    }
+}
+
+struct WktPoints<'a>(&'a [Point]);
+
+impl fmt::Display for WktPoints<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_list(f, self.0, |f, p| write!(f, "{}", WktPoint(p)))
+    }
+}
+
+struct WktPolygon<'a>(&'a Polygon);
+
+impl fmt::Display for WktPolygon<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rings: Vec<&Vec<Point>> = std::iter::once(&self.0.exterior)
+            .chain(&self.0.interiors)
+            .collect();
+        write_list(f, &rings, |f, r| write!(f, "{}", WktPoints(r)))
+    }
+}
+
+fn write_list<T, F>(f: &mut fmt::Formatter, items: &[T], mut write_item: F) -> fmt::Result
+where
+    F: FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+{
+    write!(f, "(")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_item(f, item)?;
+    }
+    write!(f, ")")
+}
+
+// ---------------------------------------------------------------------------------
+//                                  WKT parsing
+// ---------------------------------------------------------------------------------
+
+struct WktTokens<'a> {
+    txt: &'a str,
+}
+
+impl<'a> WktTokens<'a> {
+    fn new(txt: &'a str) -> Self {
+        WktTokens { txt }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        let txt = self.txt.trim_start();
+        let first = txt.chars().next()?;
+        if "(),".contains(first) {
+            return Some(&txt[..1]);
+        }
+        let end = txt
+            .find(|c: char| c.is_whitespace() || "(),".contains(c))
+            .unwrap_or(txt.len());
+        Some(&txt[..end])
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek()?;
+        let txt = self.txt.trim_start();
+        self.txt = &txt[token.len()..];
+        Some(token)
+    }
+
+    fn expect(&mut self, token: &str) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn geometry(&mut self) -> Option<Geometry> {
+        let keyword = self.next()?.to_ascii_uppercase();
+        // dimension qualifiers
+        if let Some(dims) = self.peek() {
+            if ["Z", "M", "ZM"].contains(&dims.to_ascii_uppercase().as_str()) {
+                self.next();
+            }
+        }
+        if self.peek()?.eq_ignore_ascii_case("EMPTY") {
+            self.next();
+            return match keyword.as_str() {
+                "POINT" | "LINESTRING" | "POLYGON" | "MULTIPOINT" | "MULTILINESTRING"
+                | "MULTIPOLYGON" | "GEOMETRYCOLLECTION" => {
+                    Some(Geometry::GeometryCollection(vec![]))
+                }
+                _ => None,
+            };
+        }
+        match keyword.as_str() {
+            "POINT" => {
+                self.expect("(")?;
+                let p = self.point()?;
+                self.expect(")")?;
+                Some(Geometry::Point(p))
+            }
+            "LINESTRING" => Some(Geometry::LineString(self.points()?)),
+            "POLYGON" => Some(Geometry::Polygon(self.polygon()?)),
+            "MULTIPOINT" => Some(Geometry::MultiPoint(self.list(|t| {
+                // points may or may not be enclosed in parentheses
+                if t.peek()? == "(" {
+                    t.next();
+                    let p = t.point()?;
+                    t.expect(")")?;
+                    Some(p)
+                } else {
+                    t.point()
+                }
+            })?)),
+            "MULTILINESTRING" => Some(Geometry::MultiLineString(self.list(Self::points)?)),
+            "MULTIPOLYGON" => Some(Geometry::MultiPolygon(self.list(Self::polygon)?)),
+            "GEOMETRYCOLLECTION" => Some(Geometry::GeometryCollection(self.list(Self::geometry)?)),
+            _ => None,
+        }
+    }
+
+    fn list<T, F>(&mut self, mut item: F) -> Option<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> Option<T>,
+    {
+        self.expect("(")?;
+        let mut items = vec![item(self)?];
+        loop {
+            match self.next()? {
+                "," => items.push(item(self)?),
+                ")" => return Some(items),
+                _ => return None,
+            }
+        }
+    }
+
+    fn points(&mut self) -> Option<Vec<Point>> {
+        self.list(Self::point)
+    }
+
+    fn polygon(&mut self) -> Option<Polygon> {
+        let mut rings = self.list(Self::points)?.into_iter();
+        Some(Polygon {
+            exterior: rings.next()?,
+            interiors: rings.collect(),
+        })
+    }
+
+    fn point(&mut self) -> Option<Point> {
+        let x = self.number()?;
+        let y = self.number()?;
+        // ignore Z and M
+        while self.peek().is_some_and(|t| !"(),".contains(t)) {
+            self.number()?;
+        }
+        Some(Point { x, y })
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.next()?.parse().ok().filter(|n: &f64| n.is_finite())
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                  GML parsing
+// ---------------------------------------------------------------------------------
+
+/// A minimal representation of a GML element:
+/// namespace prefixes are dropped, and only the `srsDimension` attribute is kept.
+struct GmlElement<'a> {
+    name: &'a str,
+    dimension: usize,
+    text: String,
+    children: Vec<GmlElement<'a>>,
+}
+
+impl<'a> GmlElement<'a> {
+    fn parse(txt: &'a str) -> Option<Self> {
+        let mut stack: Vec<GmlElement> = vec![];
+        let mut root = None;
+        let mut rest = txt.trim();
+        while !rest.is_empty() {
+            if let Some(tag) = rest.strip_prefix('<') {
+                let end = tag.find('>')?;
+                let (tag, after) = (&tag[..end], &tag[end + 1..]);
+                rest = after;
+                if tag.starts_with('?') || tag.starts_with('!') {
+                    continue;
+                }
+                if tag.starts_with('/') {
+                    let element = stack.pop()?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => root = Some(element),
+                    }
+                    continue;
+                }
+                let self_closing = tag.ends_with('/');
+                let tag = tag.trim_end_matches('/');
+                let qname = tag.split_whitespace().next()?;
+                let name = qname.rsplit(':').next()?;
+                let dimension = attribute(tag, "srsDimension")
+                    .and_then(|d| d.parse().ok())
+                    .unwrap_or(2);
+                let element = GmlElement {
+                    name,
+                    dimension,
+                    text: String::new(),
+                    children: vec![],
+                };
+                if self_closing {
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => root = Some(element),
+                    }
+                } else {
+                    stack.push(element);
+                }
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                stack.last_mut()?.text.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            if root.is_some() && !rest.trim().is_empty() {
+                return None;
+            }
+        }
+        if !stack.is_empty() {
+            return None;
+        }
+        root
+    }
+
+    fn child(&self, name: &str) -> Option<&GmlElement<'a>> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// The descendants named `name`, not nested in each other.
+    fn descendants<'b>(&'b self, name: &str, found: &mut Vec<&'b GmlElement<'a>>) {
+        for c in &self.children {
+            if c.name == name {
+                found.push(c);
+            } else {
+                c.descendants(name, found);
+            }
+        }
+    }
+
+    fn geometry(&self) -> Option<Geometry> {
+        let members = |name: &str| {
+            let mut found = vec![];
+            self.descendants(name, &mut found);
+            found
+        };
+        match self.name {
+            "Point" => Some(Geometry::Point(*self.points()?.first()?)),
+            "LineString" => Some(Geometry::LineString(self.points()?)),
+            "Polygon" => Some(Geometry::Polygon(self.polygon()?)),
+            "MultiPoint" => members("Point")
+                .iter()
+                .map(|p| p.points()?.first().copied())
+                .collect::<Option<_>>()
+                .map(Geometry::MultiPoint),
+            "MultiCurve" | "MultiLineString" => members("LineString")
+                .iter()
+                .map(|l| l.points())
+                .collect::<Option<_>>()
+                .map(Geometry::MultiLineString),
+            "MultiSurface" | "MultiPolygon" => members("Polygon")
+                .iter()
+                .map(|p| p.polygon())
+                .collect::<Option<_>>()
+                .map(Geometry::MultiPolygon),
+            _ => None,
+        }
+    }
+
+    fn polygon(&self) -> Option<Polygon> {
+        let ring = |e: &GmlElement| e.child("LinearRing")?.points();
+        let exterior = self
+            .child("exterior")
+            .or_else(|| self.child("outerBoundaryIs"))?;
+        let interiors = self
+            .children
+            .iter()
+            .filter(|c| c.name == "interior" || c.name == "innerBoundaryIs")
+            .map(ring)
+            .collect::<Option<_>>()?;
+        Some(Polygon {
+            exterior: ring(exterior)?,
+            interiors,
+        })
+    }
+
+    fn points(&self) -> Option<Vec<Point>> {
+        if let Some(list) = self.child("posList") {
+            return coordinates(list.text.split_whitespace(), list.dimension);
+        }
+        if let Some(coords) = self.child("coordinates") {
+            // tuples separated by spaces, coordinates separated by commas
+            return coords
+                .text
+                .split_whitespace()
+                .map(|tuple| coordinates(tuple.split(','), 0)?.pop())
+                .collect();
+        }
+        let positions: Vec<_> = self.children.iter().filter(|c| c.name == "pos").collect();
+        if positions.is_empty() {
+            return None;
+        }
+        positions
+            .iter()
+            .map(|p| coordinates(p.text.split_whitespace(), 0)?.pop())
+            .collect()
+    }
+}
+
+/// Group `numbers` into points of `dimension` coordinates
+/// (or a single point, if `dimension` is 0).
+fn coordinates<'a, I: Iterator<Item = &'a str>>(
+    numbers: I,
+    dimension: usize,
+) -> Option<Vec<Point>> {
+    let numbers: Vec<f64> = numbers
+        .map(|n| n.parse().ok().filter(|n: &f64| n.is_finite()))
+        .collect::<Option<_>>()?;
+    let dimension = if dimension == 0 {
+        numbers.len()
+    } else {
+        dimension
+    };
+    if dimension < 2 || numbers.is_empty() || !numbers.len().is_multiple_of(dimension) {
+        return None;
+    }
+    Some(
+        numbers
+            .chunks(dimension)
+            .map(|c| Point { x: c[0], y: c[1] })
+            .collect(),
+    )
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+    let quote = tag[start..].chars().next()?;
+    let value = &tag[start + 1..];
+    Some(&value[..value.find(quote)?])
+}
+
+// ---------------------------------------------------------------------------------
+//                                  spatial functions
+// ---------------------------------------------------------------------------------
+
+/// An atomic component of a geometry.
+enum Part<'a> {
+    Point(Point),
+    Line(&'a [Point]),
+    Polygon(&'a Polygon),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+impl Part<'_> {
+    /// The segments of this part (a point is a degenerate segment),
+    /// including the rings of polygons.
+    fn segments(&self) -> Vec<(Point, Point)> {
+        match self {
+            Part::Point(p) => vec![(*p, *p)],
+            Part::Line(l) => line_segments(l),
+            Part::Polygon(p) => rings(p).flat_map(|r| line_segments(r)).collect(),
+        }
+    }
+
+    fn vertices(&self) -> Vec<Point> {
+        match self {
+            Part::Point(p) => vec![*p],
+            Part::Line(l) => l.to_vec(),
+            Part::Polygon(p) => rings(p).flatten().copied().collect(),
+        }
+    }
+
+    fn intersects(&self, other: &Part) -> bool {
+        let crossing = self.segments().iter().any(|(a1, a2)| {
+            other
+                .segments()
+                .iter()
+                .any(|(b1, b2)| segments_intersect(*a1, *a2, *b1, *b2))
+        });
+        // without crossing, one of them may still be inside the other
+        let inside = |a: &Part, b: &Part| match b {
+            Part::Polygon(p) => locate(a.vertices()[0], p) != Location::Outside,
+            _ => false,
+        };
+        crossing || inside(self, other) || inside(other, self)
+    }
+
+    fn contains(&self, other: &Part) -> bool {
+        let vertices = other.vertices();
+        let midpoints: Vec<Point> = other
+            .segments()
+            .iter()
+            .map(|(a, b)| Point {
+                x: (a.x + b.x) / 2.0,
+                y: (a.y + b.y) / 2.0,
+            })
+            .collect();
+        match self {
+            Part::Point(p) => vertices.iter().all(|v| v == p),
+            Part::Line(l) => {
+                let on_line =
+                    |v: &Point| line_segments(l).iter().any(|(a, b)| on_segment(*v, *a, *b));
+                !matches!(other, Part::Polygon(_))
+                    && vertices.iter().all(on_line)
+                    && midpoints.iter().all(on_line)
+            }
+            Part::Polygon(p) => {
+                let inside = |v: &Point| locate(*v, p) != Location::Outside;
+                let crossing = other.segments().iter().any(|(a1, a2)| {
+                    self.segments()
+                        .iter()
+                        .any(|(b1, b2)| segments_cross(*a1, *a2, *b1, *b2))
+                });
+                // a hole of self inside other
+                let hole_inside = match other {
+                    Part::Polygon(q) => p
+                        .interiors
+                        .iter()
+                        .any(|h| h.iter().any(|v| locate(*v, q) == Location::Inside)),
+                    _ => false,
+                };
+                vertices.iter().all(inside)
+                    && midpoints.iter().all(inside)
+                    && !crossing
+                    && !hole_inside
+            }
+        }
+    }
+}
+
+fn rings(p: &Polygon) -> impl Iterator<Item = &Vec<Point>> {
+    std::iter::once(&p.exterior).chain(&p.interiors)
+}
+
+fn line_segments(l: &[Point]) -> Vec<(Point, Point)> {
+    match l {
+        [p] => vec![(*p, *p)],
+        _ => l.windows(2).map(|w| (w[0], w[1])).collect(),
+    }
+}
+
+/// The location of `p` relative to polygon `poly`.
+fn locate(p: Point, poly: &Polygon) -> Location {
+    match locate_in_ring(p, &poly.exterior) {
+        Location::Inside => {
+            for hole in &poly.interiors {
+                match locate_in_ring(p, hole) {
+                    Location::Inside => return Location::Outside,
+                    Location::Boundary => return Location::Boundary,
+                    Location::Outside => (),
+                }
+            }
+            Location::Inside
+        }
+        other => other,
+    }
+}
+
+fn locate_in_ring(p: Point, ring: &[Point]) -> Location {
+    let segments = line_segments(ring);
+    if segments.iter().any(|(a, b)| on_segment(p, *a, *b)) {
+        return Location::Boundary;
+    }
+    // ray casting
+    let mut inside = false;
+    for (a, b) in segments {
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    if inside {
+        Location::Inside
+    } else {
+        Location::Outside
+    }
+}
+
+fn orient(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn on_segment(p: Point, a: Point, b: Point) -> bool {
+    orient(a, b, p) == 0.0
+        && p.x >= a.x.min(b.x)
+        && p.x <= a.x.max(b.x)
+        && p.y >= a.y.min(b.y)
+        && p.y <= a.y.max(b.y)
+}
+
+fn segments_intersect(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    segments_cross(a1, a2, b1, b2)
+        || on_segment(a1, b1, b2)
+        || on_segment(a2, b1, b2)
+        || on_segment(b1, a1, a2)
+        || on_segment(b2, a1, a2)
+}
+
+/// Whether the segments cross at a point interior to both of them.
+fn segments_cross(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let (o1, o2) = (orient(a1, a2, b1), orient(a1, a2, b2));
+    let (o3, o4) = (orient(b1, b2, a1), orient(b1, b2, a2));
+    o1 * o2 < 0.0 && o3 * o4 < 0.0
+}
+
+fn point_segment_distance(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+    };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+fn segment_distance(a1: Point, a2: Point, b1: Point, b2: Point) -> f64 {
+    if segments_intersect(a1, a2, b1, b2) {
+        return 0.0;
+    }
+    point_segment_distance(a1, b1, b2)
+        .min(point_segment_distance(a2, b1, b2))
+        .min(point_segment_distance(b1, a1, a2))
+        .min(point_segment_distance(b2, a1, a2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::convert::ConvertibleTerm;
+    use crate::BoxTerm;
+
+    fn g(wkt: &str) -> Geometry {
+        wkt.parse().unwrap()
+    }
+
+    #[test]
+    fn wkt() {
+        for (txt, expected) in [
+            ("POINT(1 2)", "POINT (1 2)"),
+            ("point z (1 2 3)", "POINT (1 2)"),
+            ("LINESTRING (0 0, 1.5 -2)", "LINESTRING (0 0, 1.5 -2)"),
+            (
+                "POLYGON ((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))",
+                "POLYGON ((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))",
+            ),
+            ("MULTIPOINT ((1 2), 3 4)", "MULTIPOINT (1 2, 3 4)"),
+            (
+                "MULTIPOLYGON (((0 0, 1 0, 0 1, 0 0)))",
+                "MULTIPOLYGON (((0 0, 1 0, 0 1, 0 0)))",
+            ),
+            (
+                "GEOMETRYCOLLECTION (POINT (1 1), LINESTRING (0 0, 1 1))",
+                "GEOMETRYCOLLECTION (POINT (1 1), LINESTRING (0 0, 1 1))",
+            ),
+            ("POINT EMPTY", "GEOMETRYCOLLECTION EMPTY"),
+        ] {
+            assert_eq!(g(txt).to_string(), expected, "{}", txt);
+        }
+        let (crs, _) =
+            parse_wkt("<http://www.opengis.net/def/crs/EPSG/0/4326> POINT(45 2)").unwrap();
+        assert_eq!(crs, Some("http://www.opengis.net/def/crs/EPSG/0/4326"));
+        for invalid in [
+            "",
+            "POINT (1)",
+            "POINT (1 2",
+            "CIRCLE (1 2)",
+            "POINT (1 2) x",
+            "POINT (a b)",
+        ] {
+            assert!(invalid.parse::<Geometry>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn gml() {
+        let point = parse_gml(
+            r#"<gml:Point xmlns:gml="http://www.opengis.net/gml/3.2" srsName="urn:ogc:def:crs:EPSG::4326"><gml:pos>1 2</gml:pos></gml:Point>"#,
+        )
+        .unwrap();
+        assert_eq!(point, g("POINT (1 2)"));
+        let polygon = parse_gml(
+            "<gml:Polygon>
+                <gml:exterior><gml:LinearRing>
+                    <gml:posList srsDimension='3'>0 0 9 4 0 9 4 4 9 0 0 9</gml:posList>
+                </gml:LinearRing></gml:exterior>
+                <gml:interior><gml:LinearRing>
+                    <gml:pos>1 1</gml:pos><gml:pos>2 1</gml:pos><gml:pos>2 2</gml:pos><gml:pos>1 1</gml:pos>
+                </gml:LinearRing></gml:interior>
+            </gml:Polygon>",
+        )
+        .unwrap();
+        assert_eq!(
+            polygon,
+            g("POLYGON ((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))")
+        );
+        let multi = parse_gml(
+            "<MultiCurve><curveMember><LineString><coordinates>0,0 1,1</coordinates></LineString></curveMember>\
+             <curveMember><LineString><coordinates>2,2 3,3</coordinates></LineString></curveMember></MultiCurve>",
+        )
+        .unwrap();
+        assert_eq!(multi, g("MULTILINESTRING ((0 0, 1 1), (2 2, 3 3))"));
+        assert!(parse_gml("<gml:Point><gml:pos>1</gml:pos></gml:Point>").is_err());
+        assert!(parse_gml("<gml:Point><gml:pos>1 2</gml:pos>").is_err());
+    }
+
+    #[test]
+    fn literals() -> Result<(), Box<dyn std::error::Error>> {
+        let wkt = BoxTerm::new_literal_dt("POINT(1 2)", geo::wktLiteral)?;
+        assert_eq!(as_geometry(&wkt), Some(g("POINT (1 2)")));
+        assert_eq!(wkt.try_converted::<Geometry>()?, g("POINT (1 2)"));
+        let gml = BoxTerm::new_literal_dt("<Point><pos>1 2</pos></Point>", geo::gmlLiteral)?;
+        assert_eq!(as_geometry(&gml), Some(g("POINT (1 2)")));
+        assert_eq!(
+            as_geometry(&BoxTerm::new_literal_dt("POINT(1 2)", geo::gmlLiteral)?),
+            None
+        );
+        let lit = g("LINESTRING (0 0, 1 1)").as_literal();
+        assert_eq!(as_geometry(&lit), Some(g("LINESTRING (0 0, 1 1)")));
+        Ok(())
+    }
+
+    #[test]
+    fn relations() {
+        let square = g("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))");
+        // points
+        assert!(square.contains(&g("POINT (1 1)")));
+        assert!(square.contains(&g("POINT (0 5)")));
+        assert!(!square.contains(&g("POINT (5 5)")));
+        assert!(square.intersects(&g("POINT (4 5)")));
+        assert!(!square.intersects(&g("POINT (11 5)")));
+        // lines
+        assert!(square.contains(&g("LINESTRING (1 1, 3 1)")));
+        assert!(!square.contains(&g("LINESTRING (1 1, 9 9)")));
+        assert!(square.intersects(&g("LINESTRING (-1 5, 1 5)")));
+        assert!(!square.intersects(&g("LINESTRING (4.5 4.5, 5.5 5.5)")));
+        assert!(g("LINESTRING (0 0, 2 2)").contains(&g("POINT (1 1)")));
+        assert!(g("LINESTRING (0 0, 2 2, 4 0)").contains(&g("LINESTRING (1 1, 2 2, 3 1)")));
+        assert!(!g("LINESTRING (0 0, 2 2)").contains(&g("LINESTRING (0 0, 2 0)")));
+        // polygons
+        assert!(square.contains(&g("POLYGON ((1 1, 3 1, 3 3, 1 1))")));
+        assert!(!square.contains(&g("POLYGON ((1 1, 9 1, 9 9, 1 9, 1 1))")));
+        assert!(square.intersects(&g("POLYGON ((8 8, 12 8, 12 12, 8 8))")));
+        assert!(g("POLYGON ((-1 -1, 20 -1, 20 20, -1 -1))").intersects(&g("POINT (10 5)")));
+        assert!(g("POINT (5 5)").within(&g("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0))")));
+        // collections
+        let multi = g("MULTIPOINT (1 1, 5 5)");
+        assert!(!square.contains(&multi));
+        assert!(square.intersects(&multi));
+        assert!(
+            g("GEOMETRYCOLLECTION (POINT (5 5), POLYGON ((0 0, 2 0, 2 2, 0 0)))").contains(&multi)
+        );
+        assert!(!square.contains(&g("POINT EMPTY")));
+    }
+
+    #[test]
+    fn distance() {
+        let d = |a: &str, b: &str| g(a).distance(&g(b));
+        assert_eq!(d("POINT (0 0)", "POINT (3 4)"), Some(5.0));
+        assert_eq!(d("POINT (0 2)", "LINESTRING (-1 0, 1 0)"), Some(2.0));
+        assert_eq!(
+            d("LINESTRING (0 0, 1 1)", "LINESTRING (0 1, 1 0)"),
+            Some(0.0)
+        );
+        assert_eq!(
+            d("POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0))", "POINT (1 1)"),
+            Some(0.0)
+        );
+        assert_eq!(
+            d("POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0))", "POINT (5 1)"),
+            Some(3.0)
+        );
+        assert_eq!(d("POINT (0 0)", "POINT EMPTY"), None);
+    }
+}
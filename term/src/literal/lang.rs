@@ -0,0 +1,212 @@
+//! Selection of literals according to language preferences.
+//!
+//! [`LanguagePreference`] selects, among a set of literals
+//! (typically the values of `rdfs:label` or `skos:prefLabel` for a given resource),
+//! the one that best matches a user's preference list,
+//! such as the one provided by an HTTP [`Accept-Language`] header.
+//!
+//! ```
+//! # use sophia_term::BoxTerm;
+//! use sophia_term::literal::lang::LanguagePreference;
+//!
+//! let labels = vec![
+//!     BoxTerm::new_literal_lang("colour", "en-GB")?,
+//!     BoxTerm::new_literal_lang("couleur", "fr")?,
+//!     BoxTerm::new_literal_dt("color", sophia_api::ns::xsd::string)?,
+//! ];
+//! let pref = LanguagePreference::parse("fr-CH, en;q=0.8");
+//! assert_eq!(pref.select(&labels), Some(&labels[1]));
+//! let pref = LanguagePreference::parse("de");
+//! assert_eq!(pref.select(&labels), Some(&labels[2]));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`LanguagePreference`]: struct.LanguagePreference.html
+//! [`Accept-Language`]: https://tools.ietf.org/html/rfc7231#section-5.3.5
+
+use sophia_api::ns::xsd;
+use sophia_api::term::{TTerm, TermKind};
+
+/// An ordered list of language ranges, from the most to the least preferred.
+#[derive(Clone, Debug, Default)]
+pub struct LanguagePreference {
+    ranges: Vec<String>,
+}
+
+impl LanguagePreference {
+    /// Build a preference list from the given language ranges,
+    /// from the most to the least preferred.
+    ///
+    /// The range `*` matches any language tag.
+    pub fn new<I>(ranges: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        LanguagePreference {
+            ranges: ranges
+                .into_iter()
+                .map(|r| r.as_ref().trim().to_ascii_lowercase())
+                .filter(|r| !r.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Parse a preference list in the syntax of the HTTP [`Accept-Language`] header
+    /// (e.g. `fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5`).
+    ///
+    /// Ranges are sorted by decreasing quality value
+    /// (ranges with the same quality keep their relative order);
+    /// ranges with a quality of 0 are discarded, as well as invalid quality values.
+    ///
+    /// [`Accept-Language`]: https://tools.ietf.org/html/rfc7231#section-5.3.5
+    pub fn parse(header: &str) -> Self {
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let range = parts.next()?.trim();
+                let mut q = 1.0;
+                for param in parts {
+                    if let Some(val) = param.trim().strip_prefix("q=") {
+                        q = val.trim().parse().ok()?;
+                    }
+                }
+                (q > 0.0).then_some((range, q))
+            })
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Self::new(ranges.into_iter().map(|(range, _)| range))
+    }
+
+    /// The language ranges of this list, lowercased, from the most to the least preferred.
+    pub fn ranges(&self) -> &[String] {
+        &self.ranges
+    }
+
+    /// Select the literal of `literals` that best matches this preference list.
+    ///
+    /// For each range in order, a literal matches
+    /// if its language tag matches the range (using the basic filtering scheme of [RFC 4647]),
+    /// or else one of the truncations of the range
+    /// (e.g. `de-CH-1996`, then `de-CH`, then `de`, as in the lookup scheme of [RFC 4647]).
+    /// If no language-tagged literal matches,
+    /// simple literals (with datatype `xsd:string`) are used as a fallback.
+    /// Among equally good candidates, the first one is returned.
+    ///
+    /// Return `None` if no literal is acceptable.
+    ///
+    /// [RFC 4647]: https://tools.ietf.org/html/rfc4647
+    pub fn select<'a, I, T>(&self, literals: I) -> Option<&'a T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: TTerm + ?Sized + 'a,
+    {
+        let mut best: Option<(&T, (usize, usize))> = None;
+        for lit in literals {
+            if let Some(rank) = self.rank(lit) {
+                if best.is_none_or(|(_, r)| rank < r) {
+                    best = Some((lit, rank));
+                }
+            }
+        }
+        best.map(|(lit, _)| lit)
+    }
+
+    /// Rank `t` with respect to this preference list (lower is better),
+    /// or return `None` if `t` is not acceptable.
+    fn rank<T: TTerm + ?Sized>(&self, t: &T) -> Option<(usize, usize)> {
+        if t.kind() != TermKind::Literal {
+            return None;
+        }
+        match t.language() {
+            None if t.datatype().is_some_and(|dt| xsd::string == dt) => {
+                Some((self.ranges.len(), 0))
+            }
+            None => None,
+            Some(tag) => {
+                let tag = tag.to_ascii_lowercase();
+                self.ranges.iter().enumerate().find_map(|(i, range)| {
+                    truncations(range)
+                        .position(|r| lang_matches(&tag, r))
+                        .map(|level| (i, level))
+                })
+            }
+        }
+    }
+}
+
+/// Whether `tag` matches `range`, using the basic filtering scheme of RFC 4647
+/// (both assumed to be lowercase).
+fn lang_matches(tag: &str, range: &str) -> bool {
+    range == "*"
+        || tag
+            .strip_prefix(range)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+}
+
+/// Iter over `range` and its successive truncations, as in the lookup scheme of RFC 4647
+/// (single-letter subtags are removed along with the following subtag).
+fn truncations(range: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(range);
+    std::iter::from_fn(move || {
+        let current = next?;
+        let mut cut = current.rfind('-');
+        while let Some(i) = cut {
+            if current[..i].rfind('-').is_some_and(|j| i - j == 2) {
+                cut = Some(i - 2);
+            } else {
+                break;
+            }
+        }
+        next = cut.map(|i| &current[..i]);
+        Some(current)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BoxTerm;
+
+    fn lang(value: &str, tag: &str) -> BoxTerm {
+        BoxTerm::new_literal_lang(value, tag).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let pref = LanguagePreference::parse("en;q=0.8, fr-CH, fr;q=0.9, de;q=0, *;q=0.5");
+        assert_eq!(pref.ranges(), &["fr-ch", "fr", "en", "*"]);
+        assert!(LanguagePreference::parse("").ranges().is_empty());
+        assert_eq!(LanguagePreference::parse("en;q=x, fr").ranges(), &["fr"]);
+    }
+
+    #[test]
+    fn truncate() {
+        let t: Vec<_> = truncations("zh-hant-cn-x-private1").collect();
+        assert_eq!(
+            t,
+            vec!["zh-hant-cn-x-private1", "zh-hant-cn", "zh-hant", "zh"]
+        );
+    }
+
+    #[test]
+    fn select() {
+        let labels = vec![
+            lang("chat", "fr"),
+            lang("cat", "en-GB"),
+            lang("cat", "en-US"),
+            BoxTerm::new_literal_dt("cat", xsd::string).unwrap(),
+            BoxTerm::new_iri("tag:cat").unwrap(),
+        ];
+        let select = |header| LanguagePreference::parse(header).select(&labels);
+        assert_eq!(select("en-US, fr"), Some(&labels[2]));
+        assert_eq!(select("en"), Some(&labels[1]));
+        assert_eq!(select("fr-CH, en"), Some(&labels[0]));
+        assert_eq!(select("en-AU, fr-CH"), Some(&labels[1]));
+        assert_eq!(select("de"), Some(&labels[3]));
+        assert_eq!(select("de, *"), Some(&labels[0]));
+        assert_eq!(LanguagePreference::parse("de").select(&labels[..1]), None);
+        assert_eq!(LanguagePreference::default().select(&labels[4..]), None);
+    }
+}
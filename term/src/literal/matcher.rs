@@ -0,0 +1,428 @@
+//! Ready-made [term matchers] for literals.
+//!
+//! These matchers can be combined with each other, and with any other matcher,
+//! using the [`And`], [`Or`] and [`Not`] combinators.
+//!
+//! ```
+//! # use sophia_api::term::matcher::{And, TermMatcher};
+//! # use sophia_api::ns::xsd;
+//! # use sophia_term::BoxTerm;
+//! use sophia_term::literal::matcher::*;
+//!
+//! let m = And(LanguageMatcher::new("en"), RegexMatcher::new("^colou?r$")?);
+//! assert!(m.matches(&BoxTerm::new_literal_lang("colour", "en-GB")?));
+//! assert!(!m.matches(&BoxTerm::new_literal_lang("couleur", "fr")?));
+//!
+//! let m = NumericRangeMatcher::new(0.0..=100.0);
+//! assert!(m.matches(&BoxTerm::new_literal_dt("42", xsd::integer)?));
+//! assert!(!m.matches(&BoxTerm::new_literal_dt("42", xsd::string)?));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [term matchers]: ../../matcher/trait.TermMatcher.html
+//! [`And`]: ../../matcher/struct.And.html
+//! [`Or`]: ../../matcher/struct.Or.html
+//! [`Not`]: ../../matcher/struct.Not.html
+
+use std::ops::{Bound, RangeBounds};
+
+use regex::Regex;
+use sophia_api::ns::xsd;
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{term_eq, SimpleIri, TTerm, TermKind};
+
+use super::datetime::{Date, DateTime};
+#[cfg(feature = "geo")]
+use super::geo::{as_geometry, Geometry};
+
+/// Matches literals whose language tag matches a [BCP47] language range,
+/// using the basic filtering scheme of [RFC 4647].
+///
+/// For example, the range `en` matches the tags `en`, `en-GB` and `EN-us`,
+/// but not `eng`;
+/// the range `*` matches any language-tagged literal.
+///
+/// [BCP47]: https://tools.ietf.org/html/bcp47
+/// [RFC 4647]: https://tools.ietf.org/html/rfc4647#section-3.3.1
+#[derive(Clone, Debug)]
+pub struct LanguageMatcher {
+    range: String,
+}
+
+impl LanguageMatcher {
+    /// Match the language tags in the given language range.
+    pub fn new<T: Into<String>>(range: T) -> Self {
+        LanguageMatcher {
+            range: range.into().to_ascii_lowercase(),
+        }
+    }
+}
+
+impl TermMatcher for LanguageMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        match t.language() {
+            None => false,
+            Some(_) if self.range == "*" => true,
+            Some(tag) => {
+                let tag = tag.to_ascii_lowercase();
+                tag.strip_prefix(self.range.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+            }
+        }
+    }
+}
+
+/// Matches literals with a given datatype.
+#[derive(Clone, Debug)]
+pub struct DatatypeMatcher<T>(pub T);
+
+impl<U> TermMatcher for DatatypeMatcher<U>
+where
+    U: TTerm,
+{
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        t.datatype().is_some_and(|dt| term_eq(&dt, &self.0))
+    }
+}
+
+/// Matches literals whose lexical form matches a regular expression.
+///
+/// As in SPARQL's `REGEX` function, the regular expression needs only match part of the value
+/// (use `^` and `$` to match the whole value).
+#[derive(Clone, Debug)]
+pub struct RegexMatcher(Regex);
+
+impl RegexMatcher {
+    /// Match the literals whose lexical form matches `pattern`.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(RegexMatcher)
+    }
+}
+
+impl From<Regex> for RegexMatcher {
+    fn from(regex: Regex) -> Self {
+        RegexMatcher(regex)
+    }
+}
+
+impl TermMatcher for RegexMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        t.kind() == TermKind::Literal && self.0.is_match(&t.value())
+    }
+}
+
+/// Matches literals with a numeric XSD datatype, whose value is in a given range.
+///
+/// Literals with a numeric datatype but an invalid lexical form are not matched.
+#[derive(Clone, Debug)]
+pub struct NumericRangeMatcher {
+    start: Bound<f64>,
+    end: Bound<f64>,
+}
+
+impl NumericRangeMatcher {
+    /// Match the numeric literals whose value is in `range`.
+    pub fn new<R: RangeBounds<f64>>(range: R) -> Self {
+        NumericRangeMatcher {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        }
+    }
+}
+
+impl TermMatcher for NumericRangeMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        match t.datatype() {
+            Some(dt) if NUMERIC_TYPES.iter().any(|n| **n == dt) => t
+                .value()
+                .trim()
+                .parse::<f64>()
+                .is_ok_and(|n| (self.start, self.end).contains(&n)),
+            _ => false,
+        }
+    }
+}
+
+/// Matches `xsd:dateTime` and `xsd:date` literals whose value is in a given range.
+///
+/// Dates are considered to start at midnight.
+/// Values whose comparison with the bounds is indeterminate
+/// (because only one of them has a timezone, and they are less than 14 hours apart)
+/// are not matched,
+/// nor are literals with an invalid lexical form.
+#[derive(Clone, Debug)]
+pub struct DateTimeRangeMatcher {
+    start: Bound<DateTime>,
+    end: Bound<DateTime>,
+}
+
+impl DateTimeRangeMatcher {
+    /// Match the date and dateTime literals whose value is in `range`.
+    pub fn new<R: RangeBounds<DateTime>>(range: R) -> Self {
+        DateTimeRangeMatcher {
+            start: range.start_bound().cloned(),
+            end: range.end_bound().cloned(),
+        }
+    }
+}
+
+impl TermMatcher for DateTimeRangeMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        let value = match t.datatype() {
+            Some(dt) if xsd::dateTime == dt => t.value().trim().parse::<DateTime>().ok(),
+            Some(dt) if xsd::date == dt => t.value().trim().parse::<Date>().ok().map(|d| d.start()),
+            _ => None,
+        };
+        // RangeBounds::contains would treat indeterminate comparisons as satisfied
+        value.is_some_and(|v| {
+            (match &self.start {
+                Bound::Included(start) => v >= *start,
+                Bound::Excluded(start) => v > *start,
+                Bound::Unbounded => true,
+            }) && (match &self.end {
+                Bound::Included(end) => v <= *end,
+                Bound::Excluded(end) => v < *end,
+                Bound::Unbounded => true,
+            })
+        })
+    }
+}
+
+/// Matches `geo:wktLiteral` and `geo:gmlLiteral` literals
+/// whose geometry is in a given spatial relation with a reference geometry.
+///
+/// Literals with an invalid lexical form are not matched.
+/// See the [`geo`](../geo/index.html) module for the limitations of spatial functions.
+///
+/// Requires the `geo` feature.
+#[cfg(feature = "geo")]
+#[derive(Clone, Debug)]
+pub struct GeometryMatcher {
+    relation: SpatialRelation,
+    geometry: Geometry,
+}
+
+#[cfg(feature = "geo")]
+#[derive(Clone, Copy, Debug)]
+enum SpatialRelation {
+    Intersects,
+    Within,
+    Contains,
+    WithinDistance(f64),
+}
+
+#[cfg(feature = "geo")]
+impl GeometryMatcher {
+    /// Match the geometries intersecting `geometry`.
+    pub fn intersects(geometry: Geometry) -> Self {
+        Self::new(SpatialRelation::Intersects, geometry)
+    }
+
+    /// Match the geometries within `geometry`.
+    pub fn within(geometry: Geometry) -> Self {
+        Self::new(SpatialRelation::Within, geometry)
+    }
+
+    /// Match the geometries containing `geometry`.
+    pub fn contains(geometry: Geometry) -> Self {
+        Self::new(SpatialRelation::Contains, geometry)
+    }
+
+    /// Match the geometries at most at `distance` from `geometry`.
+    pub fn within_distance(geometry: Geometry, distance: f64) -> Self {
+        Self::new(SpatialRelation::WithinDistance(distance), geometry)
+    }
+
+    fn new(relation: SpatialRelation, geometry: Geometry) -> Self {
+        GeometryMatcher { relation, geometry }
+    }
+}
+
+#[cfg(feature = "geo")]
+impl TermMatcher for GeometryMatcher {
+    type Term = SimpleIri<'static>;
+    // NB: the type above does not really matter,
+    // since `constant` below always returns None
+    fn constant(&self) -> Option<&SimpleIri<'static>> {
+        None
+    }
+    fn matches<T>(&self, t: &T) -> bool
+    where
+        T: TTerm + ?Sized,
+    {
+        as_geometry(t).is_some_and(|g| match self.relation {
+            SpatialRelation::Intersects => g.intersects(&self.geometry),
+            SpatialRelation::Within => g.within(&self.geometry),
+            SpatialRelation::Contains => g.contains(&self.geometry),
+            SpatialRelation::WithinDistance(d) => {
+                g.distance(&self.geometry).is_some_and(|x| x <= d)
+            }
+        })
+    }
+}
+
+static NUMERIC_TYPES: [&SimpleIri; 16] = [
+    &xsd::integer,
+    &xsd::decimal,
+    &xsd::double,
+    &xsd::float,
+    &xsd::long,
+    &xsd::int,
+    &xsd::short,
+    &xsd::byte,
+    &xsd::nonNegativeInteger,
+    &xsd::positiveInteger,
+    &xsd::nonPositiveInteger,
+    &xsd::negativeInteger,
+    &xsd::unsignedLong,
+    &xsd::unsignedInt,
+    &xsd::unsignedShort,
+    &xsd::unsignedByte,
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BoxTerm;
+    use sophia_api::term::matcher::{Not, Or};
+
+    fn lit(value: &str, dt: SimpleIri) -> BoxTerm {
+        BoxTerm::new_literal_dt(value, dt).unwrap()
+    }
+
+    fn lang(value: &str, tag: &str) -> BoxTerm {
+        BoxTerm::new_literal_lang(value, tag).unwrap()
+    }
+
+    #[test]
+    fn language() {
+        let m = LanguageMatcher::new("EN");
+        assert!(m.matches(&lang("a", "en")));
+        assert!(m.matches(&lang("a", "en-GB")));
+        assert!(!m.matches(&lang("a", "eng")));
+        assert!(!m.matches(&lang("a", "fr")));
+        assert!(!m.matches(&lit("a", xsd::string)));
+        let m = LanguageMatcher::new("en-gb");
+        assert!(!m.matches(&lang("a", "en")));
+        assert!(m.matches(&lang("a", "en-GB")));
+        let m = LanguageMatcher::new("*");
+        assert!(m.matches(&lang("a", "fr")));
+        assert!(!m.matches(&lit("a", xsd::string)));
+        let m = Or(LanguageMatcher::new("fr"), Not(LanguageMatcher::new("*")));
+        assert!(m.matches(&lang("a", "fr")));
+        assert!(m.matches(&lit("a", xsd::string)));
+        assert!(!m.matches(&lang("a", "en")));
+    }
+
+    #[test]
+    fn datatype() {
+        let m = DatatypeMatcher(xsd::integer);
+        assert!(m.matches(&lit("1", xsd::integer)));
+        assert!(!m.matches(&lit("1", xsd::int)));
+        assert!(!m.matches(&BoxTerm::new_iri("tag:x").unwrap()));
+    }
+
+    #[test]
+    fn regex() {
+        let m = RegexMatcher::new("[Ss]mith").unwrap();
+        assert!(m.matches(&lit("John Smith", xsd::string)));
+        assert!(m.matches(&lang("Smith", "en")));
+        assert!(!m.matches(&lit("Jones", xsd::string)));
+        assert!(!m.matches(&BoxTerm::new_iri("tag:smith").unwrap()));
+        assert!(RegexMatcher::new("(").is_err());
+    }
+
+    #[test]
+    fn numeric_range() {
+        let m = NumericRangeMatcher::new(-1.0..10.0);
+        assert!(m.matches(&lit("-1", xsd::int)));
+        assert!(m.matches(&lit("9.99", xsd::decimal)));
+        assert!(m.matches(&lit(" 1e0 ", xsd::double)));
+        assert!(!m.matches(&lit("10", xsd::integer)));
+        assert!(!m.matches(&lit("5", xsd::string)));
+        assert!(!m.matches(&lit("five", xsd::integer)));
+        assert!(NumericRangeMatcher::new(..).matches(&lit("5", xsd::byte)));
+    }
+
+    #[test]
+    fn datetime_range() {
+        let start: DateTime = "2020-01-01T00:00:00Z".parse().unwrap();
+        let end: DateTime = "2021-01-01T00:00:00Z".parse().unwrap();
+        let m = DateTimeRangeMatcher::new(start..end);
+        assert!(m.matches(&lit("2020-01-01T00:00:00Z", xsd::dateTime)));
+        assert!(m.matches(&lit("2020-06-15T13:00:00+02:00", xsd::dateTime)));
+        assert!(m.matches(&lit("2020-06-15Z", xsd::date)));
+        assert!(!m.matches(&lit("2021-01-01T00:00:00Z", xsd::dateTime)));
+        assert!(m.matches(&lit("2020-06-15T13:00:00", xsd::dateTime)));
+        assert!(!m.matches(&lit("2020-12-31T20:00:00", xsd::dateTime)));
+        assert!(!m.matches(&lit("2020-06-15", xsd::string)));
+        assert!(!m.matches(&lit("not a date", xsd::date)));
+        let m = DateTimeRangeMatcher::new(..=start);
+        assert!(m.matches(&lit("2019-12-31T23:59:59-00:00", xsd::dateTime)));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn geometry() {
+        use sophia_api::ns::geo;
+        let area = "POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0))".parse().unwrap();
+        let m = GeometryMatcher::within(area);
+        assert!(m.matches(&lit("POINT (1 1)", geo::wktLiteral)));
+        assert!(m.matches(&lit("<Point><pos>2 2</pos></Point>", geo::gmlLiteral)));
+        assert!(!m.matches(&lit("POINT (11 1)", geo::wktLiteral)));
+        assert!(!m.matches(&lit("POINT (1 1)", xsd::string)));
+        assert!(!m.matches(&lit("POINT (1)", geo::wktLiteral)));
+        let m = GeometryMatcher::within_distance("POINT (0 0)".parse().unwrap(), 5.0);
+        assert!(m.matches(&lit("LINESTRING (3 4, 3 9)", geo::wktLiteral)));
+        assert!(!m.matches(&lit("LINESTRING (3 5, 3 9)", geo::wktLiteral)));
+        let m = GeometryMatcher::contains("POINT (1 1)".parse().unwrap());
+        assert!(m.matches(&lit("LINESTRING (0 0, 2 2)", geo::wktLiteral)));
+        let m = GeometryMatcher::intersects("LINESTRING (0 2, 2 0)".parse().unwrap());
+        assert!(m.matches(&lit("LINESTRING (0 0, 2 2)", geo::wktLiteral)));
+    }
+}
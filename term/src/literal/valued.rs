@@ -0,0 +1,360 @@
+//! Literals keeping both their lexical form and their value.
+//!
+//! A [`ValuedLiteral`] wraps a [`Literal`] and lazily caches its value
+//! (as an [`XsdValue`]), the first time it is needed.
+//! It behaves as a term exactly like the wrapped literal,
+//! so serializing it reproduces its original lexical form byte-for-byte
+//! (e.g. `"01.50"^^xsd:decimal` is not rewritten into `"1.5"^^xsd:decimal`),
+//! while value-based operations ([`value_eq`], [`value_cmp`])
+//! only parse the lexical form once.
+//!
+//! ```
+//! # use sophia_term::literal::valued::{ValuedLiteral, XsdValue};
+//! # use sophia_term::BoxTerm;
+//! # use sophia_api::{ns::xsd, term::TryCopyTerm};
+//! let a = ValuedLiteral::<Box<str>>::try_copy(&BoxTerm::new_literal_dt("01.50", xsd::decimal)?)?;
+//! let b = ValuedLiteral::<Box<str>>::try_copy(&BoxTerm::new_literal_dt("1.5E0", xsd::double)?)?;
+//! assert!(!a.is_parsed());
+//! assert!(a.value_eq(&b));
+//! assert!(a.is_parsed());
+//! assert_eq!(a.value(), Some(&XsdValue::Decimal(1.5)));
+//! assert_eq!(a.to_string(), r#""01.50"^^<http://www.w3.org/2001/XMLSchema#decimal>"#);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`ValuedLiteral`]: struct.ValuedLiteral.html
+//! [`Literal`]: ../struct.Literal.html
+//! [`XsdValue`]: enum.XsdValue.html
+//! [`value_eq`]: struct.ValuedLiteral.html#method.value_eq
+//! [`value_cmp`]: struct.ValuedLiteral.html#method.value_cmp
+
+use super::canonical::canonical_numeric;
+use super::datetime::{Date, DateTime, Duration};
+use super::Literal;
+use crate::{TermData, TermError};
+use sophia_api::ns::xsd;
+use sophia_api::term::{term_eq, term_format, term_hash, BaseDirection, RawValue, SimpleIri};
+use sophia_api::term::{TTerm, TermKind, TryCopyTerm};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+/// The value of a literal of one of the common XSD datatypes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XsdValue {
+    /// An `xsd:boolean`.
+    Boolean(bool),
+    /// An `xsd:integer`, or any of its derived types.
+    Integer(i128),
+    /// An `xsd:decimal` (or an integer outside the range of `i128`).
+    Decimal(f64),
+    /// An `xsd:float`.
+    Float(f32),
+    /// An `xsd:double`.
+    Double(f64),
+    /// An `xsd:dateTime`.
+    DateTime(DateTime),
+    /// An `xsd:date`.
+    Date(Date),
+    /// An `xsd:duration`, `xsd:dayTimeDuration` or `xsd:yearMonthDuration`.
+    Duration(Duration),
+}
+
+impl XsdValue {
+    /// The value of `term`,
+    /// or `None` if it is not a literal of a supported datatype with a valid lexical form.
+    pub fn from_term<T>(term: &T) -> Option<XsdValue>
+    where
+        T: TTerm + ?Sized,
+    {
+        let dt = term.datatype()?;
+        let lex = term.value_raw().0;
+        if xsd::boolean == dt {
+            match lex.trim() {
+                "true" | "1" => Some(XsdValue::Boolean(true)),
+                "false" | "0" => Some(XsdValue::Boolean(false)),
+                _ => None,
+            }
+        } else if xsd::dateTime == dt {
+            lex.parse().ok().map(XsdValue::DateTime)
+        } else if xsd::date == dt {
+            lex.parse().ok().map(XsdValue::Date)
+        } else if xsd::duration == dt || xsd::dayTimeDuration == dt || xsd::yearMonthDuration == dt
+        {
+            super::datetime::duration_from_term(term).map(XsdValue::Duration)
+        } else {
+            let canonical = canonical_numeric(lex, &dt)?;
+            if xsd::decimal == dt {
+                canonical.parse().ok().map(XsdValue::Decimal)
+            } else if xsd::float == dt {
+                canonical.parse().ok().map(XsdValue::Float)
+            } else if xsd::double == dt {
+                canonical.parse().ok().map(XsdValue::Double)
+            } else {
+                match canonical.parse() {
+                    Ok(i) => Some(XsdValue::Integer(i)),
+                    Err(_) => canonical.parse().ok().map(XsdValue::Decimal),
+                }
+            }
+        }
+    }
+
+    /// Compare two values,
+    /// promoting numbers to a common type as in [XPath](https://www.w3.org/TR/xpath-functions/#op.numeric).
+    ///
+    /// Returns `None` if the values are not comparable
+    /// (values of different kinds, NaN, indeterminate dateTimes or durations...).
+    pub fn value_cmp(&self, other: &XsdValue) -> Option<Ordering> {
+        use XsdValue::*;
+        match (self, other) {
+            (Boolean(a), Boolean(b)) => Some(a.cmp(b)),
+            (Integer(a), Integer(b)) => Some(a.cmp(b)),
+            (DateTime(a), DateTime(b)) => a.partial_cmp(b),
+            (Date(a), Date(b)) => a.partial_cmp(b),
+            (Duration(a), Duration(b)) => a.partial_cmp(b),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            XsdValue::Integer(i) => Some(i as f64),
+            XsdValue::Decimal(d) | XsdValue::Double(d) => Some(d),
+            XsdValue::Float(f) => Some(f as f64),
+            _ => None,
+        }
+    }
+}
+
+/// A literal keeping its lexical form, and caching its value.
+///
+/// See [module documentation](index.html).
+///
+/// Equality and hashing (as for any [`TTerm`](../../trait.TTerm.html))
+/// are based on the lexical form;
+/// use [`value_eq`](#method.value_eq) for value-based equality.
+#[derive(Clone, Debug)]
+pub struct ValuedLiteral<TD: TermData = Box<str>> {
+    literal: Literal<TD>,
+    value: OnceLock<Option<XsdValue>>,
+}
+
+impl<TD> ValuedLiteral<TD>
+where
+    TD: TermData,
+{
+    /// Wrap `literal`; its value will only be computed when first needed.
+    pub fn new(literal: Literal<TD>) -> Self {
+        ValuedLiteral {
+            literal,
+            value: OnceLock::new(),
+        }
+    }
+
+    /// The wrapped literal.
+    pub fn literal(&self) -> &Literal<TD> {
+        &self.literal
+    }
+
+    /// Unwrap the wrapped literal, dropping the cached value.
+    pub fn into_literal(self) -> Literal<TD> {
+        self.literal
+    }
+
+    /// The original lexical form of this literal.
+    pub fn lexical_form(&self) -> &str {
+        self.literal.txt().as_ref()
+    }
+
+    /// The value of this literal, parsed on the first call,
+    /// or `None` if its datatype is not supported by [`XsdValue`](enum.XsdValue.html)
+    /// or its lexical form is invalid.
+    pub fn value(&self) -> Option<&XsdValue> {
+        self.value
+            .get_or_init(|| XsdValue::from_term(&self.literal))
+            .as_ref()
+    }
+
+    /// Whether the value of this literal has already been parsed.
+    pub fn is_parsed(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Whether this literal and `other` have the same value,
+    /// or (if either has no value) are the same term.
+    pub fn value_eq<TE: TermData>(&self, other: &ValuedLiteral<TE>) -> bool {
+        match (self.value(), other.value()) {
+            (Some(a), Some(b)) => a.value_cmp(b) == Some(Ordering::Equal),
+            _ => term_eq(&self.literal, &other.literal),
+        }
+    }
+
+    /// Compare the values of this literal and `other`,
+    /// or `None` if either has no value or if they are not comparable
+    /// (see [`XsdValue::value_cmp`](enum.XsdValue.html#method.value_cmp)).
+    pub fn value_cmp<TE: TermData>(&self, other: &ValuedLiteral<TE>) -> Option<Ordering> {
+        self.value()?.value_cmp(other.value()?)
+    }
+}
+
+impl<TD> From<Literal<TD>> for ValuedLiteral<TD>
+where
+    TD: TermData,
+{
+    fn from(literal: Literal<TD>) -> Self {
+        ValuedLiteral::new(literal)
+    }
+}
+
+impl<TD> TryCopyTerm for ValuedLiteral<TD>
+where
+    TD: TermData + for<'x> From<&'x str>,
+{
+    type Error = TermError;
+
+    fn try_copy<T>(term: &T) -> Result<Self, Self::Error>
+    where
+        T: TTerm + ?Sized,
+    {
+        Literal::try_copy(term).map(ValuedLiteral::new)
+    }
+}
+
+impl<TD: TermData> TTerm for ValuedLiteral<TD> {
+    fn kind(&self) -> TermKind {
+        TermKind::Literal
+    }
+    fn value_raw(&self) -> RawValue {
+        self.literal.value_raw()
+    }
+    fn datatype(&self) -> Option<SimpleIri> {
+        self.literal.datatype()
+    }
+    fn language(&self) -> Option<&str> {
+        self.literal.language()
+    }
+    fn base_direction(&self) -> Option<BaseDirection> {
+        self.literal.base_direction()
+    }
+    fn as_dyn(&self) -> &dyn TTerm {
+        self
+    }
+}
+
+impl<TD> fmt::Display for ValuedLiteral<TD>
+where
+    TD: TermData,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        term_format(self, f)
+    }
+}
+
+impl<TD, TE> PartialEq<TE> for ValuedLiteral<TD>
+where
+    TD: TermData,
+    TE: TTerm + ?Sized,
+{
+    fn eq(&self, other: &TE) -> bool {
+        term_eq(self, other)
+    }
+}
+
+impl<TD: TermData> Eq for ValuedLiteral<TD> {}
+
+impl<TD> Hash for ValuedLiteral<TD>
+where
+    TD: TermData,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        term_hash(self, state)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::iri::Iri;
+    use crate::BoxTerm;
+    use sophia_api::term::CopyTerm;
+    use std::convert::TryFrom;
+    use test_case::test_case;
+
+    fn lit(lex: &str, dt: SimpleIri) -> ValuedLiteral {
+        Literal::new_dt(lex, Iri::<&str>::from(dt)).into()
+    }
+
+    #[test_case(" true ", xsd::boolean => Some(XsdValue::Boolean(true)); "boolean")]
+    #[test_case("0", xsd::boolean => Some(XsdValue::Boolean(false)); "boolean zero")]
+    #[test_case("+042", xsd::integer => Some(XsdValue::Integer(42)); "integer")]
+    #[test_case("300", xsd::byte => None; "byte out of range")]
+    #[test_case("1e400", xsd::double => Some(XsdValue::Double(f64::INFINITY)); "double overflow")]
+    #[test_case("-INF", xsd::float => Some(XsdValue::Float(f32::NEG_INFINITY)); "float infinity")]
+    #[test_case("99999999999999999999999999999999999999999", xsd::integer => Some(XsdValue::Decimal(1e41)); "big integer")]
+    #[test_case("P1D", xsd::dayTimeDuration => Some(XsdValue::Duration(Duration::from_seconds(86_400))); "duration")]
+    #[test_case("abc", xsd::string => None; "string")]
+    #[test_case("abc", xsd::decimal => None; "invalid decimal")]
+    fn values(lex: &str, dt: SimpleIri) -> Option<XsdValue> {
+        lit(lex, dt).value().copied()
+    }
+
+    #[test]
+    fn lexical_form_is_preserved() {
+        let l = lit(" +01.50 ", xsd::decimal);
+        assert_eq!(l.value(), Some(&XsdValue::Decimal(1.5)));
+        assert_eq!(l.lexical_form(), " +01.50 ");
+        assert_eq!(l.value_raw().0, " +01.50 ");
+        assert_eq!(
+            l.to_string(),
+            r#"" +01.50 "^^<http://www.w3.org/2001/XMLSchema#decimal>"#
+        );
+        let t = BoxTerm::copy(&l);
+        assert_eq!(t.value(), " +01.50 ");
+        assert_eq!(Literal::<Box<str>>::try_from(t).unwrap(), l);
+    }
+
+    #[test]
+    fn lazy() {
+        let l = lit("42", xsd::integer);
+        assert!(!l.is_parsed());
+        let _ = l.to_string();
+        assert!(!l.is_parsed());
+        assert_eq!(l.value(), Some(&XsdValue::Integer(42)));
+        assert!(l.is_parsed());
+        assert!(l.clone().is_parsed());
+        let s = lit("abc", xsd::string);
+        assert_eq!(s.value(), None);
+        assert!(s.is_parsed());
+    }
+
+    #[test]
+    fn value_comparison() {
+        assert!(lit("1", xsd::integer).value_eq(&lit("1.0", xsd::decimal)));
+        assert!(lit("1", xsd::integer).value_eq(&lit("01", xsd::int)));
+        assert!(lit("1", xsd::integer) != lit("01", xsd::int));
+        assert!(!lit("1", xsd::integer).value_eq(&lit("1", xsd::string)));
+        assert!(lit("a", xsd::string).value_eq(&lit("a", xsd::string)));
+        assert_eq!(
+            lit("2", xsd::integer).value_cmp(&lit("10", xsd::integer)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            lit("2020-01-01T01:00:00+01:00", xsd::dateTime)
+                .value_cmp(&lit("2020-01-01T00:00:00Z", xsd::dateTime)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            lit("NaN", xsd::double).value_cmp(&lit("NaN", xsd::double)),
+            None
+        );
+        assert_eq!(
+            lit("true", xsd::boolean).value_cmp(&lit("1", xsd::integer)),
+            None
+        );
+    }
+}
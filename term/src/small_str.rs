@@ -0,0 +1,255 @@
+//! A string type storing short strings inline, used by [`SmallTerm`](../type.SmallTerm.html).
+//!
+//! Most IRIs, blank node identifiers and literals found in RDF data are short.
+//! Storing them inline, rather than in a separate heap allocation,
+//! saves allocations and improves cache locality
+//! when a large number of terms are kept in memory.
+
+use mownstr::MownStr;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// The maximum length (in bytes) of a string stored inline in a [`SmallStr`].
+///
+/// [`SmallStr`]: struct.SmallStr.html
+pub const INLINE_CAPACITY: usize = 22;
+
+/// An immutable string,
+/// stored inline if it is not longer than [`INLINE_CAPACITY`] bytes,
+/// and on the heap otherwise.
+///
+/// A `SmallStr` has the same size as a `String`.
+///
+/// [`INLINE_CAPACITY`]: constant.INLINE_CAPACITY.html
+#[derive(Clone)]
+pub struct SmallStr(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, buf: [u8; INLINE_CAPACITY] },
+    Heap(Box<str>),
+}
+
+impl SmallStr {
+    /// Copy `txt` into a new `SmallStr`.
+    pub fn new(txt: &str) -> Self {
+        if txt.len() <= INLINE_CAPACITY {
+            let mut buf = [0; INLINE_CAPACITY];
+            buf[..txt.len()].copy_from_slice(txt.as_bytes());
+            SmallStr(Repr::Inline {
+                len: txt.len() as u8,
+                buf,
+            })
+        } else {
+            SmallStr(Repr::Heap(txt.into()))
+        }
+    }
+
+    /// Extracts a string slice containing the entire `SmallStr`.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Inline { len, buf } => {
+                // SAFETY: buf[..len] is always copied from a valid str
+                unsafe { std::str::from_utf8_unchecked(&buf[..*len as usize]) }
+            }
+            Repr::Heap(txt) => txt,
+        }
+    }
+
+    /// Whether this string is stored inline (as opposed to on the heap).
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+}
+
+impl Deref for SmallStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SmallStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SmallStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallStr {}
+
+impl PartialEq<str> for SmallStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for SmallStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for SmallStr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallStr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a> From<&'a str> for SmallStr {
+    fn from(other: &'a str) -> Self {
+        SmallStr::new(other)
+    }
+}
+
+impl From<Box<str>> for SmallStr {
+    fn from(other: Box<str>) -> Self {
+        if other.len() <= INLINE_CAPACITY {
+            SmallStr::new(&other)
+        } else {
+            SmallStr(Repr::Heap(other))
+        }
+    }
+}
+
+impl From<String> for SmallStr {
+    fn from(other: String) -> Self {
+        other.into_boxed_str().into()
+    }
+}
+
+impl<'a> From<MownStr<'a>> for SmallStr {
+    fn from(other: MownStr<'a>) -> Self {
+        if other.is_borrowed() {
+            SmallStr::new(&other)
+        } else {
+            Box::<str>::from(other).into()
+        }
+    }
+}
+
+impl From<Rc<str>> for SmallStr {
+    fn from(other: Rc<str>) -> Self {
+        SmallStr::new(&other)
+    }
+}
+
+impl From<Arc<str>> for SmallStr {
+    fn from(other: Arc<str>) -> Self {
+        SmallStr::new(&other)
+    }
+}
+
+impl From<SmallStr> for Box<str> {
+    fn from(other: SmallStr) -> Self {
+        match other.0 {
+            Repr::Inline { .. } => other.as_str().into(),
+            Repr::Heap(txt) => txt,
+        }
+    }
+}
+
+impl From<SmallStr> for String {
+    fn from(other: SmallStr) -> Self {
+        Box::<str>::from(other).into()
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn size() {
+        assert_eq!(
+            std::mem::size_of::<SmallStr>(),
+            std::mem::size_of::<String>()
+        );
+    }
+
+    #[test_case("", true)]
+    #[test_case("http://ex.org/é", true)]
+    #[test_case("1234567890123456789012", true)]
+    #[test_case("12345678901234567890123", false)]
+    #[test_case("http://www.w3.org/2001/XMLSchema#string", false)]
+    fn storage(txt: &str, inline: bool) {
+        let s1 = SmallStr::new(txt);
+        assert_eq!(s1.is_inline(), inline);
+        assert_eq!(s1.as_str(), txt);
+        let s2 = SmallStr::from(txt.to_string());
+        assert_eq!(s2.is_inline(), inline);
+        assert_eq!(s1, s2);
+        assert_eq!(String::from(s2), txt);
+    }
+
+    #[test]
+    fn order() {
+        assert!(SmallStr::new("abc") < SmallStr::new("abd"));
+        assert!(SmallStr::new("abc") < SmallStr::new("abcdefghijklmnopqrstuvwxyz"));
+    }
+
+    #[test]
+    fn factory() -> crate::Result<()> {
+        use crate::factory::{SmallTermFactory, TermFactory};
+        use crate::{SmallTerm, Term};
+        use sophia_api::ns::xsd;
+        use sophia_api::term::{term_eq, CopyTerm};
+
+        let mut f = SmallTermFactory;
+        let t = f.literal_dt("42", SmallTerm::copy(&xsd::integer))?;
+        assert!(term_eq(
+            &t,
+            &crate::StaticTerm::new_literal_dt("42", xsd::integer)?
+        ));
+        match &t {
+            Term::Literal(lit) => assert!(lit.txt().is_inline()),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}